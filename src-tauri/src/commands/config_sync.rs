@@ -0,0 +1,16 @@
+use crate::modules::config_sync::{self, SyncAction, SyncReport, SyncState};
+
+/// 上次同步的时间、远端设备与错误
+#[tauri::command]
+pub async fn get_config_sync_status() -> Result<SyncState, String> {
+    Ok(config_sync::load_state())
+}
+
+/// 立即同步; `direction` 为 push / pull 时强制上传或下载 (用于解决冲突)
+#[tauri::command]
+pub async fn sync_config_now(app: tauri::AppHandle, direction: Option<SyncAction>) -> Result<SyncReport, String> {
+    if matches!(direction, Some(SyncAction::UpToDate | SyncAction::Conflict)) {
+        return Err("direction must be push or pull".to_string());
+    }
+    config_sync::sync_and_apply(&app, direction).await
+}
@@ -0,0 +1,14 @@
+use crate::modules::key_check::{self, KeyCheckResult};
+
+/// 上次巡检的结果 (尚未巡检时为空)
+#[tauri::command]
+pub async fn get_key_check_results() -> Result<Vec<KeyCheckResult>, String> {
+    Ok(key_check::results())
+}
+
+/// 立即检查全部上游 Key 的有效性与余额
+#[tauri::command]
+pub async fn check_upstream_keys() -> Result<Vec<KeyCheckResult>, String> {
+    let config = crate::modules::config::load_app_config()?;
+    Ok(key_check::check_all(&crate::modules::profiles::effective(&config.proxy)).await)
+}
@@ -9,6 +9,22 @@ pub mod proxy;
 pub mod autostart;
 // 导出 cloudflared 命令
 pub mod cloudflared;
+// 导出密钥存储命令
+pub mod secrets;
+// 导出模型下载命令
+pub mod model_download;
+// 导出资源监控命令
+pub mod resources;
+// 导出外部服务管理命令
+pub mod services;
+// 导出启动方案命令
+pub mod profiles;
+// 导出配置同步命令
+pub mod config_sync;
+// 导出首次运行向导命令
+pub mod setup;
+// 导出上游 Key 巡检命令
+pub mod key_check;
 
 /// 列出所有账号
 #[tauri::command]
@@ -302,12 +318,51 @@ pub async fn open_device_folder(app: tauri::AppHandle) -> Result<(), String> {
 }
 
 
+/// 获取生成图片的保存目录
+#[tauri::command]
+pub async fn get_image_save_dir() -> Result<String, String> {
+    let config = modules::load_app_config()?;
+    let dir = modules::image_store::resolve_dir(&config.proxy.images)?;
+    Ok(dir.to_string_lossy().to_string())
+}
+
+/// 打开生成图片的保存目录
+#[tauri::command]
+pub async fn open_image_save_dir(app: tauri::AppHandle) -> Result<(), String> {
+    let config = modules::load_app_config()?;
+    let dir = modules::image_store::resolve_dir(&config.proxy.images)?;
+    app.opener()
+        .open_path(dir.to_string_lossy().to_string(), None::<&str>)
+        .map_err(|e| format!("打开目录失败: {}", e))
+}
+
 /// 加载配置
 #[tauri::command]
 pub async fn load_config() -> Result<AppConfig, String> {
     modules::load_app_config()
 }
 
+/// 校验配置, 返回带字段路径的问题列表 (error 会阻止保存)
+#[tauri::command]
+pub async fn validate_config(
+    config: AppConfig,
+) -> Result<Vec<crate::proxy::common::config_validation::ConfigIssue>, String> {
+    Ok(crate::proxy::common::config_validation::check(&config.proxy))
+}
+
+/// 查询管理操作审计日志 (参数与管理接口 GET /api/audit 相同)
+#[tauri::command]
+pub async fn get_audit_log(
+    since: Option<i64>,
+    until: Option<i64>,
+    actor: Option<String>,
+    action: Option<String>,
+    limit: Option<usize>,
+    offset: Option<usize>,
+) -> Result<Vec<modules::audit::AuditEntry>, String> {
+    modules::audit::query(&modules::audit::AuditQuery { since, until, actor, action, limit, offset })
+}
+
 /// 保存配置
 #[tauri::command]
 pub async fn save_config(
@@ -315,29 +370,48 @@ pub async fn save_config(
     proxy_state: tauri::State<'_, crate::commands::proxy::ProxyServiceState>,
     config: AppConfig,
 ) -> Result<(), String> {
+    crate::proxy::common::config_validation::ensure_valid(&config.proxy)?;
+    modules::service_manager::validate(&config.external_services)?;
+    modules::profiles::validate(&config)?;
+    let previous = modules::load_app_config().ok();
+    let before = previous.as_ref().and_then(|p| serde_json::to_value(p).ok());
     modules::save_app_config(&config)?;
+    modules::notifications::configure(&config.desktop_notifications);
+    if let (Some(before), Ok(after)) = (before, serde_json::to_value(&config)) {
+        let changes = modules::audit::diff(&before, &after);
+        if !changes.is_empty() {
+            modules::audit::record(modules::audit::AuditEvent {
+                actor: "desktop".to_string(),
+                source: "desktop",
+                action: "config.update".to_string(),
+                target: "config".to_string(),
+                changes,
+                ..Default::default()
+            });
+        }
+    }
 
     // 通知托盘配置已更新
     let _ = app.emit("config://updated", ());
 
+    // 方案变化时可能需要重建监听服务器
+    match previous.filter(|p| modules::profiles::changed(p, &config)) {
+        Some(previous) => {
+            let cf_state = std::sync::Arc::new(app.state::<crate::commands::cloudflared::CloudflaredState>().inner().clone());
+            let integration = crate::modules::integration::SystemManager::Desktop(app.clone());
+            if let Err(e) = modules::profiles::apply(&previous, &config, &proxy_state, integration, cf_state).await {
+                // 其他修改保留, 只恢复原方案
+                let _ = modules::save_app_config(&AppConfig { active_profile: previous.active_profile.clone(), ..config });
+                return Err(e);
+            }
+        }
+        None => modules::profiles::configure(&config),
+    }
+
     // 热更新正在运行的服务
     let instance_lock = proxy_state.instance.read().await;
     if let Some(instance) = instance_lock.as_ref() {
-        // 更新模型映射
-        instance.axum_server.update_mapping(&config.proxy).await;
-        // 更新上游代理
-        instance
-            .axum_server
-            .update_proxy(config.proxy.upstream_proxy.clone())
-            .await;
-        // 更新安全策略 (auth)
-        instance.axum_server.update_security(&config.proxy).await;
-        // 更新 z.ai 配置
-        instance.axum_server.update_zai(&config.proxy).await;
-        // 更新实验性配置
-        instance.axum_server.update_experimental(&config.proxy).await;
-        // 更新调试日志配置
-        instance.axum_server.update_debug_logging(&config.proxy).await;
+        instance.axum_server.apply_config(&config.proxy).await?;
         // 更新熔断配置
         instance.token_manager.update_circuit_breaker_config(config.circuit_breaker.clone()).await;
         tracing::debug!("已同步热更新反代服务配置");
@@ -346,6 +420,35 @@ pub async fn save_config(
     Ok(())
 }
 
+/// 导出完整配置到文件; `include_secrets` 为 false 时清空密钥, 返回清空的数量
+#[tauri::command]
+pub async fn export_config(file_path: String, include_secrets: bool) -> Result<usize, String> {
+    let stripped = modules::config_transfer::export_to(&file_path, include_secrets)?;
+    modules::audit::record(modules::audit::AuditEvent {
+        actor: "desktop".to_string(),
+        source: "desktop",
+        action: "config.export".to_string(),
+        target: "config".to_string(),
+        detail: Some(serde_json::json!({ "include_secrets": include_secrets })),
+        ..Default::default()
+    });
+    Ok(stripped)
+}
+
+/// 从导出文件导入配置 (旧格式先迁移), 按保存配置的流程校验并热更新
+#[tauri::command]
+pub async fn import_config(
+    app: tauri::AppHandle,
+    proxy_state: tauri::State<'_, crate::commands::proxy::ProxyServiceState>,
+    file_path: String,
+) -> Result<modules::config_transfer::ImportReport, String> {
+    let content = std::fs::read_to_string(&file_path).map_err(|e| format!("Failed to read {}: {}", file_path, e))?;
+    let (bundle, from_version) = modules::config_transfer::parse_bundle(&content)?;
+    let (config, report) = modules::config_transfer::restore(bundle, from_version, &modules::load_app_config()?)?;
+    save_config(app, proxy_state, config).await?;
+    Ok(report)
+}
+
 // --- OAuth 命令 ---
 
 #[tauri::command]
@@ -794,3 +897,51 @@ pub async fn get_token_stats_account_trend_hourly(hours: i64) -> Result<Vec<crat
 pub async fn get_token_stats_account_trend_daily(days: i64) -> Result<Vec<crate::modules::token_stats::AccountTrendPoint>, String> {
     crate::modules::token_stats::get_account_trend_daily(days)
 }
+
+// ============================================================================
+// Usage Accounting Commands
+// ============================================================================
+
+#[tauri::command]
+pub async fn get_usage_by_day(days: i64) -> Result<Vec<crate::modules::usage::UsageAggregate>, String> {
+    crate::modules::usage::get_usage_by_day(days)
+}
+
+#[tauri::command]
+pub async fn get_usage_by_model(days: i64) -> Result<Vec<crate::modules::usage::UsageAggregate>, String> {
+    crate::modules::usage::get_usage_by_model(days)
+}
+
+#[tauri::command]
+pub async fn get_usage_by_api_key(days: i64) -> Result<Vec<crate::modules::usage::UsageAggregate>, String> {
+    crate::modules::usage::get_usage_by_api_key(days)
+}
+
+#[tauri::command]
+pub async fn get_usage_by_client(days: i64) -> Result<Vec<crate::modules::usage::UsageAggregate>, String> {
+    crate::modules::usage::get_usage_by_client(days)
+}
+
+#[tauri::command]
+pub async fn get_usage_by_upstream(days: i64) -> Result<Vec<crate::modules::usage::UsageAggregate>, String> {
+    crate::modules::usage::get_usage_by_upstream(days)
+}
+
+/// Export usage for a date range (inclusive, UTC "YYYY-MM-DD") as CSV or JSON;
+/// `group_by` empty exports individual requests. Returns the number of rows written.
+#[tauri::command]
+pub async fn export_usage(
+    file_path: String,
+    format: crate::modules::usage::UsageExportFormat,
+    start_date: Option<String>,
+    end_date: Option<String>,
+    group_by: Option<Vec<crate::modules::usage::UsageExportGroup>>,
+) -> Result<usize, String> {
+    crate::modules::usage::export_usage(
+        &file_path,
+        format,
+        start_date.as_deref(),
+        end_date.as_deref(),
+        &group_by.unwrap_or_default(),
+    )
+}
@@ -0,0 +1,25 @@
+use crate::modules::model_download::{self, LocalModel, ModelDownload, ModelDownloadRequest};
+
+/// 开始 (或续传) 下载 Hugging Face 上的 GGUF 模型, 进度通过 `model-download://progress` 事件推送
+#[tauri::command]
+pub async fn start_model_download(app: tauri::AppHandle, request: ModelDownloadRequest) -> Result<ModelDownload, String> {
+    model_download::start(app, request)
+}
+
+/// 当前会话中的下载任务
+#[tauri::command]
+pub async fn list_model_downloads() -> Result<Vec<ModelDownload>, String> {
+    Ok(model_download::list())
+}
+
+/// 取消下载 (已下载部分保留, 再次开始时续传)
+#[tauri::command]
+pub async fn cancel_model_download(id: String) -> Result<(), String> {
+    model_download::cancel(&id)
+}
+
+/// 模型目录下已下载的 GGUF 文件
+#[tauri::command]
+pub async fn list_local_models() -> Result<Vec<LocalModel>, String> {
+    model_download::list_local()
+}
@@ -0,0 +1,45 @@
+use std::sync::Arc;
+
+use tauri::{Emitter, Manager, State};
+
+use crate::commands::proxy::ProxyServiceState;
+use crate::modules::profiles::{self, ProfileSummary};
+
+/// 已配置的启动方案
+#[tauri::command]
+pub async fn list_profiles() -> Result<Vec<ProfileSummary>, String> {
+    Ok(profiles::summaries(&crate::modules::config::load_app_config()?))
+}
+
+/// 切换启动方案 (`None` 恢复基础配置); 新方案无法生效时恢复原方案
+#[tauri::command]
+pub async fn switch_profile(
+    app: tauri::AppHandle,
+    state: State<'_, ProxyServiceState>,
+    name: Option<String>,
+) -> Result<Vec<ProfileSummary>, String> {
+    let previous = crate::modules::config::load_app_config()?;
+    let next = profiles::select(&previous, name.as_deref())?;
+    crate::modules::config::save_app_config(&next)?;
+
+    let cf_state = Arc::new(app.state::<crate::commands::cloudflared::CloudflaredState>().inner().clone());
+    let integration = crate::modules::integration::SystemManager::Desktop(app.clone());
+    let switched = match profiles::apply(&previous, &next, &state, integration, cf_state).await {
+        Ok(switched) => switched,
+        Err(e) => {
+            let _ = crate::modules::config::save_app_config(&previous);
+            return Err(e);
+        }
+    };
+
+    crate::modules::audit::record(crate::modules::audit::AuditEvent {
+        actor: "desktop".to_string(),
+        source: "desktop",
+        action: "profile.switch".to_string(),
+        target: next.active_profile.clone().unwrap_or_else(|| profiles::DEFAULT_PROFILE.to_string()),
+        detail: Some(serde_json::json!({ "from": previous.active_profile, "restarted": switched.restarted })),
+        ..Default::default()
+    });
+    let _ = app.emit("config://updated", ());
+    Ok(profiles::summaries(&next))
+}
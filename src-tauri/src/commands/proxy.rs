@@ -28,6 +28,7 @@ pub struct ProxyServiceState {
 
 pub struct AdminServerInstance {
     pub axum_server: crate::proxy::AxumServer,
+    /// 监督任务的 handle (服务器任务由它等待)
     pub server_handle: tokio::task::JoinHandle<()>,
 }
 
@@ -58,12 +59,26 @@ pub async fn start_proxy_service(
     cf_state: State<'_, crate::commands::cloudflared::CloudflaredState>,
     app_handle: tauri::AppHandle,
 ) -> Result<ProxyStatus, String> {
-    internal_start_proxy_service(
+    let status = internal_start_proxy_service(
         config,
         &state,
         crate::modules::integration::SystemManager::Desktop(app_handle),
         Arc::new(cf_state.inner().clone()),
-    ).await
+    ).await?;
+    desktop_audit("service.start", "proxy", None);
+    Ok(status)
+}
+
+/// 记录桌面端的管理操作
+fn desktop_audit(action: &str, target: &str, detail: Option<serde_json::Value>) {
+    crate::modules::audit::record(crate::modules::audit::AuditEvent {
+        actor: "desktop".to_string(),
+        source: "desktop",
+        action: action.to_string(),
+        target: target.to_string(),
+        detail,
+        ..Default::default()
+    });
 }
 
 struct StartingGuard(Arc<AtomicBool>);
@@ -95,6 +110,8 @@ pub async fn internal_start_proxy_service(
 
     // 使用自定义 Drop guard 确保无论成功失败都会重置 starting 状态
     let _starting_guard = StartingGuard(state.starting.clone());
+    // 叠加当前启动方案
+    let config = crate::modules::profiles::effective(&config);
 
     // Ensure monitor exists
     {
@@ -127,6 +144,7 @@ pub async fn internal_start_proxy_service(
     // [NEW] 加载熔断配置 (从主配置加载)
     let app_config = crate::modules::config::load_app_config().unwrap_or_else(|_| crate::models::AppConfig::new());
     token_manager.update_circuit_breaker_config(app_config.circuit_breaker).await;
+    crate::modules::notifications::configure(&app_config.desktop_notifications);
 
     // 🆕 [FIX #820] 恢复固定账号模式设置
     if let Some(ref account_id) = config.preferred_account_id {
@@ -146,10 +164,11 @@ pub async fn internal_start_proxy_service(
             && !matches!(config.zai.dispatch_mode, crate::proxy::ZaiDispatchMode::Off);
         if !zai_enabled {
             tracing::warn!("沒有可用賬號，反代邏輯將暫停，請通過管理界面添加。");
+            let port = state.admin_server.read().await.as_ref().map_or(config.port, |a| a.axum_server.bound_port());
             return Ok(ProxyStatus {
                 running: false,
-                port: config.port,
-                base_url: format!("http://127.0.0.1:{}", config.port),
+                port,
+                base_url: format!("http://127.0.0.1:{}", port),
                 active_accounts: 0,
             });
         }
@@ -174,10 +193,11 @@ pub async fn internal_start_proxy_service(
     
     // 成功启动后，guard 在这里结束并重置 starting 是 OK 的
     // 但其实我们可以直接手动掉，或者相信 guard
+    let port = axum_server.bound_port();
     Ok(ProxyStatus {
         running: true,
-        port: config.port,
-        base_url: format!("http://127.0.0.1:{}", config.port),
+        port,
+        base_url: format!("http://127.0.0.1:{}", port),
         active_accounts,
     })
 }
@@ -193,6 +213,7 @@ pub async fn ensure_admin_server(
     if admin_lock.is_some() {
         return Ok(());
     }
+    let config = crate::modules::profiles::effective(&config);
 
     // Ensure monitor exists
     let monitor = {
@@ -216,19 +237,11 @@ pub async fn ensure_admin_server(
 
     let (axum_server, server_handle) =
         match crate::proxy::AxumServer::start(
-            config.get_bind_address().to_string(),
-            config.port,
+            &config,
             token_manager,
-            config.custom_mapping.clone(),
-            config.request_timeout,
-            config.upstream_proxy.clone(),
-            crate::proxy::ProxySecurityConfig::from_proxy_config(&config),
-            config.zai.clone(),
             monitor,
-            config.experimental.clone(),
-            config.debug_logging.clone(),
             integration.clone(),
-            cloudflared_state,
+            cloudflared_state.clone(),
         ).await {
             Ok((server, handle)) => (server, handle),
             Err(e) => return Err(format!("启动管理服务器失败: {}", e)),
@@ -236,17 +249,151 @@ pub async fn ensure_admin_server(
 
     *admin_lock = Some(AdminServerInstance {
         axum_server,
-        server_handle,
+        server_handle: supervise_admin_server(server_handle, state.clone(), integration, cloudflared_state),
     });
 
     Ok(())
 }
 
+/// 以新配置重建管理服务器 (监听地址、端口等无法热更新时使用), 保留反代的运行状态
+pub async fn restart_admin_server(
+    config: ProxyConfig,
+    state: &ProxyServiceState,
+    integration: crate::modules::integration::SystemManager,
+    cloudflared_state: Arc<crate::commands::cloudflared::CloudflaredState>,
+) -> Result<(), String> {
+    let stale = state.admin_server.write().await.take();
+    let was_running = match stale {
+        Some(stale) => {
+            let running = stale.axum_server.is_running().await;
+            stale.axum_server.stop().await;
+            // 等待旧监听器释放端口
+            let _ = tokio::time::timeout(std::time::Duration::from_secs(10), stale.server_handle).await;
+            running
+        }
+        None => false,
+    };
+
+    let mut result = Ok(());
+    for attempt in 0..5 {
+        if attempt > 0 {
+            tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+        }
+        result = ensure_admin_server(config.clone(), state, integration.clone(), cloudflared_state.clone()).await;
+        if result.is_ok() {
+            break;
+        }
+    }
+    result?;
+
+    let server = state.admin_server.read().await.as_ref().map(|a| a.axum_server.clone());
+    if let Some(server) = server {
+        server.set_running(was_running).await;
+        if let Some(instance) = state.instance.write().await.as_mut() {
+            instance.axum_server = server;
+            instance.config = crate::modules::profiles::effective(&config);
+        }
+    }
+    Ok(())
+}
+
+/// 监督管理服务器任务: panic 或监听失败退出时按指数退避重启, 正常停止时结束
+fn supervise_admin_server(
+    handle: tokio::task::JoinHandle<Result<(), String>>,
+    state: ProxyServiceState,
+    integration: crate::modules::integration::SystemManager,
+    cloudflared_state: Arc<crate::commands::cloudflared::CloudflaredState>,
+) -> tokio::task::JoinHandle<()> {
+    use crate::proxy::supervisor::{exit_reason, Supervisor};
+
+    tokio::spawn(async move {
+        let started = std::time::Instant::now();
+        let Some(mut reason) = exit_reason(handle.await) else {
+            return;
+        };
+        // 应用退出时 admin_server 已被取走, 不再重启
+        let Some(stale) = state.admin_server.write().await.take() else {
+            return;
+        };
+        let was_running = stale.axum_server.is_running().await;
+        // 旧实例在后台排空仍在处理的连接
+        tokio::spawn(async move { stale.axum_server.stop().await });
+
+        let supervisor = Supervisor::global();
+        let mut uptime = Some(started.elapsed());
+        let failure = reason.clone();
+        loop {
+            let delay = supervisor.record_failure(&reason, uptime.take());
+            tracing::error!("反代服务异常退出: {}, {}s 后重启", reason, delay.as_secs());
+            tokio::time::sleep(delay).await;
+            let result = match crate::modules::config::load_app_config() {
+                Ok(config) => {
+                    ensure_admin_server(config.proxy, &state, integration.clone(), cloudflared_state.clone()).await
+                }
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(()) => break,
+                Err(e) => reason = e,
+            }
+        }
+
+        let server = state.admin_server.read().await.as_ref().map(|a| a.axum_server.clone());
+        if let Some(server) = server {
+            server.set_running(was_running).await;
+            // 逻辑服务实例仍引用旧服务器
+            if let Some(instance) = state.instance.write().await.as_mut() {
+                instance.axum_server = server;
+            }
+        }
+        supervisor.record_restart();
+        tracing::info!("反代服务已自动重启 (原因: {})", failure);
+        crate::modules::audit::record(crate::modules::audit::AuditEvent {
+            actor: "supervisor".to_string(),
+            source: "system",
+            action: "service.restart".to_string(),
+            target: "proxy".to_string(),
+            detail: Some(serde_json::json!({ "reason": failure, "restarts": supervisor.status().restarts })),
+            ..Default::default()
+        });
+        crate::modules::notifications::notify(
+            crate::modules::notifications::NotificationKind::ServiceRestarted,
+            "proxy",
+            "Proxy restarted",
+            &format!("The proxy service was restarted after: {}", failure),
+        );
+        let restarts = supervisor.status().restarts;
+        crate::proxy::webhooks::notify(crate::proxy::webhooks::WebhookEvent::new(
+            crate::proxy::config::WebhookEventKind::ServiceRestarted,
+            "proxy",
+            format!("Proxy service was restarted after: {}", failure),
+            serde_json::json!({ "reason": failure, "restarts": restarts }),
+        ));
+        if let crate::modules::integration::SystemManager::Desktop(ref handle) = integration {
+            use tauri::Emitter;
+            let _ = handle.emit("proxy://restarted", supervisor.status());
+        }
+    })
+}
+
+/// 获取服务监督状态 (重启次数与最近一次原因)
+#[tauri::command]
+pub async fn get_proxy_supervisor_status() -> Result<crate::proxy::supervisor::SupervisorStatus, String> {
+    Ok(crate::proxy::supervisor::Supervisor::global().status())
+}
+
 /// 停止反代服务
 #[tauri::command]
 pub async fn stop_proxy_service(
     state: State<'_, ProxyServiceState>,
 ) -> Result<(), String> {
+    internal_stop_proxy_service(&state).await?;
+    desktop_audit("service.stop", "proxy", None);
+    Ok(())
+}
+
+/// 内部停止反代服务逻辑 (供托盘等非命令入口复用)
+pub async fn internal_stop_proxy_service(state: &ProxyServiceState) -> Result<(), String> {
     let mut instance_lock = state.instance.write().await;
     
     if instance_lock.is_none() {
@@ -285,8 +432,8 @@ pub async fn get_proxy_status(
             match instance_lock.as_ref() {
                 Some(instance) => Ok(ProxyStatus {
                     running: true,
-                    port: instance.config.port,
-                    base_url: format!("http://127.0.0.1:{}", instance.config.port),
+                    port: instance.axum_server.bound_port(),
+                    base_url: format!("http://127.0.0.1:{}", instance.axum_server.bound_port()),
                     active_accounts: instance.token_manager.len(),
                 }),
                 None => Ok(ProxyStatus {
@@ -322,6 +469,51 @@ pub async fn get_proxy_stats(
     }
 }
 
+/// 获取最近 `window_mins` 分钟 (默认 15, 最长 60) 内按模型 / 上游的延迟分位统计
+#[tauri::command]
+pub async fn get_latency_stats(
+    state: State<'_, ProxyServiceState>,
+    window_mins: Option<u64>,
+) -> Result<Vec<crate::proxy::latency::LatencyStats>, String> {
+    let window = std::time::Duration::from_secs(window_mins.unwrap_or(15) * 60);
+    let monitor_lock = state.monitor.read().await;
+    Ok(monitor_lock.as_ref().map(|m| m.latency.snapshot(window)).unwrap_or_default())
+}
+
+/// 把同一个 Chat Completions 请求并行发往多个模型, 返回各自的回复、耗时与费用
+#[tauri::command]
+pub async fn compare_models(
+    state: State<'_, ProxyServiceState>,
+    models: Vec<String>,
+    request: serde_json::Value,
+) -> Result<Vec<crate::proxy::compare::CompareResult>, String> {
+    let (target, prices, timeout) = {
+        let instance_lock = state.instance.read().await;
+        let instance = instance_lock.as_ref().ok_or("Proxy service is not running")?;
+        let target = crate::proxy::compare::CompareTarget {
+            base_url: format!("http://127.0.0.1:{}", instance.axum_server.bound_port()),
+            api_key: instance.config.api_key.clone(),
+            client_name: crate::proxy::compare::CLIENT_NAME,
+        };
+        (target, instance.config.pricing.models.clone(), instance.config.request_timeout)
+    };
+    let request = crate::proxy::compare::CompareRequest { models, request };
+    crate::proxy::compare::compare(request, target, &prices, timeout).await
+}
+
+/// 影子流量的最近结果 (新的在前)
+#[tauri::command]
+pub async fn get_shadow_results() -> Result<Vec<crate::proxy::shadow::ShadowResult>, String> {
+    Ok(crate::proxy::shadow::results())
+}
+
+/// 清空影子流量结果
+#[tauri::command]
+pub async fn clear_shadow_results() -> Result<(), String> {
+    crate::proxy::shadow::clear();
+    Ok(())
+}
+
 /// 获取反代请求日志
 #[tauri::command]
 pub async fn get_proxy_logs(
@@ -381,6 +573,75 @@ pub async fn get_proxy_log_detail(
     crate::modules::proxy_db::get_log_detail(&log_id)
 }
 
+/// 重放日志中的请求 (可替换模型或上游), 返回与原响应的逐行差异
+#[tauri::command]
+pub async fn replay_proxy_request(
+    state: State<'_, ProxyServiceState>,
+    log_id: String,
+    options: Option<crate::proxy::replay::ReplayOptions>,
+) -> Result<crate::proxy::replay::ReplayResult, String> {
+    let options = options.unwrap_or_default();
+    let log = crate::modules::proxy_db::get_log_detail(&log_id)?;
+    let target = match options.base_url.as_deref().map(str::trim).filter(|u| !u.is_empty()) {
+        Some(base_url) => crate::proxy::replay::ReplayTarget {
+            base_url: base_url.to_string(),
+            api_key: options.api_key.clone().unwrap_or_default(),
+            upstream_proxy: crate::modules::load_app_config()?.proxy.upstream_proxy,
+        },
+        None => {
+            let instance_lock = state.instance.read().await;
+            let instance = instance_lock.as_ref().ok_or("Proxy service is not running")?;
+            crate::proxy::replay::ReplayTarget {
+                base_url: format!("http://127.0.0.1:{}", instance.axum_server.bound_port()),
+                api_key: instance.config.api_key.clone(),
+                upstream_proxy: Default::default(),
+            }
+        }
+    };
+    crate::proxy::replay::replay(&log, target, &options).await
+}
+
+/// 进行中的请求 (实时检查器)
+#[tauri::command]
+pub async fn get_proxy_inflight_requests(
+    state: State<'_, ProxyServiceState>,
+) -> Result<Vec<crate::proxy::monitor::InflightRequest>, String> {
+    let monitor_lock = state.monitor.read().await;
+    Ok(monitor_lock
+        .as_ref()
+        .map(|monitor| monitor.inflight_requests())
+        .unwrap_or_default())
+}
+
+/// 单个请求的完整检查信息: 客户端请求/响应以及协议转换后的上游请求
+#[tauri::command]
+pub async fn get_proxy_request_inspection(
+    state: State<'_, ProxyServiceState>,
+    log_id: String,
+) -> Result<crate::proxy::monitor::RequestInspection, String> {
+    let log = crate::modules::proxy_db::get_log_detail(&log_id)?;
+    let monitor_lock = state.monitor.read().await;
+    let upstream_request = monitor_lock.as_ref().and_then(|monitor| monitor.upstream_capture(&log_id));
+    Ok(crate::proxy::monitor::RequestInspection { log, upstream_request })
+}
+
+/// 打开独立的实时请求检查器窗口 (已打开时聚焦)
+#[tauri::command]
+pub async fn open_request_inspector(app: tauri::AppHandle) -> Result<(), String> {
+    use tauri::Manager;
+    if let Some(window) = app.get_webview_window("inspector") {
+        window.show().map_err(|e| e.to_string())?;
+        return window.set_focus().map_err(|e| e.to_string());
+    }
+    tauri::WebviewWindowBuilder::new(&app, "inspector", tauri::WebviewUrl::App("inspector".into()))
+        .title("Request Inspector")
+        .inner_size(1100.0, 720.0)
+        .min_inner_size(720.0, 480.0)
+        .build()
+        .map_err(|e| format!("Failed to open inspector window: {}", e))?;
+    Ok(())
+}
+
 /// 获取日志总数
 #[tauri::command]
 pub async fn get_proxy_logs_count() -> Result<u64, String> {
@@ -564,16 +825,15 @@ pub async fn fetch_zai_models(
     if zai.base_url.trim().is_empty() {
         return Err("z.ai base_url is empty".to_string());
     }
-    if zai.api_key.trim().is_empty() {
-        return Err("z.ai api_key is not set".to_string());
-    }
+    let api_key = match zai.key_pool().into_iter().next() {
+        Some(k) => k.key,
+        None => return Err("z.ai api_key is not set".to_string()),
+    };
 
     let url = join_base_url(&zai.base_url, "/v1/models");
 
     let mut builder = reqwest::Client::builder().timeout(Duration::from_secs(request_timeout.max(5)));
-    if upstream_proxy.enabled && !upstream_proxy.url.is_empty() {
-        let proxy = reqwest::Proxy::all(&upstream_proxy.url)
-            .map_err(|e| format!("Invalid upstream proxy url: {}", e))?;
+    if let Some(proxy) = crate::proxy::upstream::client_pool::build_proxy(&upstream_proxy)? {
         builder = builder.proxy(proxy);
     }
     let client = builder
@@ -582,8 +842,8 @@ pub async fn fetch_zai_models(
 
     let resp = client
         .get(&url)
-        .header("Authorization", format!("Bearer {}", zai.api_key))
-        .header("x-api-key", zai.api_key)
+        .header("Authorization", format!("Bearer {}", api_key))
+        .header("x-api-key", api_key)
         .header("anthropic-version", "2023-06-01")
         .header("accept", "application/json")
         .send()
@@ -607,6 +867,48 @@ pub async fn fetch_zai_models(
     Ok(models)
 }
 
+/// 出站代理连通性测试结果
+#[derive(serde::Serialize)]
+pub struct UpstreamProxyTestResult {
+    pub url: String,
+    pub status: u16,
+    pub latency_ms: u64,
+}
+
+const PROXY_TEST_URL: &str = "https://www.gstatic.com/generate_204";
+
+/// 通过指定的出站代理请求目标地址 (默认 generate_204), 检查代理与认证是否可用
+#[tauri::command]
+pub async fn test_upstream_proxy(
+    proxy: crate::proxy::config::UpstreamProxyConfig,
+    target_url: Option<String>,
+    timeout_secs: Option<u64>,
+) -> Result<UpstreamProxyTestResult, String> {
+    let url = target_url
+        .map(|u| u.trim().to_string())
+        .filter(|u| !u.is_empty())
+        .unwrap_or_else(|| PROXY_TEST_URL.to_string());
+    let proxy = crate::proxy::upstream::client_pool::build_proxy(&proxy)?
+        .ok_or("Upstream proxy is disabled or its url is empty")?;
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(timeout_secs.unwrap_or(15).max(1)))
+        .proxy(proxy)
+        .build()
+        .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+
+    let started = std::time::Instant::now();
+    let resp = client
+        .get(&url)
+        .send()
+        .await
+        .map_err(|e| format!("Proxy connection failed: {}", e))?;
+    Ok(UpstreamProxyTestResult {
+        url,
+        status: resp.status().as_u16(),
+        latency_ms: started.elapsed().as_millis() as u64,
+    })
+}
+
 /// 获取当前调度配置
 #[tauri::command]
 pub async fn get_proxy_scheduling_config(
@@ -635,6 +937,204 @@ pub async fn update_proxy_scheduling_config(
     }
 }
 
+/// 获取客户端限流配置
+#[tauri::command]
+pub async fn get_proxy_rate_limit_config(
+    state: State<'_, ProxyServiceState>,
+) -> Result<crate::proxy::config::ClientRateLimitConfig, String> {
+    let instance_lock = state.instance.read().await;
+    if let Some(instance) = instance_lock.as_ref() {
+        Ok(instance.axum_server.client_rate_limit_config())
+    } else {
+        let app_config = crate::modules::config::load_app_config()?;
+        Ok(app_config.proxy.client_rate_limit)
+    }
+}
+
+/// 更新客户端限流配置 (持久化, 服务运行时立即生效)
+#[tauri::command]
+pub async fn update_proxy_rate_limit_config(
+    state: State<'_, ProxyServiceState>,
+    config: crate::proxy::config::ClientRateLimitConfig,
+) -> Result<(), String> {
+    let mut app_config = crate::modules::config::load_app_config()?;
+    let before = std::mem::replace(&mut app_config.proxy.client_rate_limit, config);
+    crate::modules::config::save_app_config(&app_config)?;
+
+    let instance_lock = state.instance.read().await;
+    if let Some(instance) = instance_lock.as_ref() {
+        // 运行中的服务按生效方案覆盖后的配置更新
+        instance.axum_server.update_client_rate_limit(&crate::modules::profiles::effective(&app_config.proxy));
+    }
+    desktop_audit(
+        "rate_limit.update",
+        "client_rate_limit",
+        Some(serde_json::json!({ "before": before, "after": app_config.proxy.client_rate_limit })),
+    );
+    Ok(())
+}
+
+/// 某个额外监听的访问控制规则, None 表示沿用主监听的规则
+#[derive(Debug, Clone, Serialize)]
+pub struct ListenerAccessControl {
+    pub listener: String,
+    pub access_control: Option<crate::proxy::config::AccessControlConfig>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AccessControlOverview {
+    pub main: crate::proxy::config::AccessControlConfig,
+    pub listeners: Vec<ListenerAccessControl>,
+}
+
+/// 获取 IP 访问控制规则
+#[tauri::command]
+pub async fn get_access_control() -> Result<AccessControlOverview, String> {
+    let proxy = crate::modules::config::load_app_config()?.proxy;
+    Ok(AccessControlOverview {
+        listeners: proxy
+            .listeners
+            .iter()
+            .map(|l| ListenerAccessControl { listener: l.label(), access_control: l.access_control.clone() })
+            .collect(),
+        main: proxy.access_control,
+    })
+}
+
+/// 更新 IP 访问控制规则 (持久化, 服务运行时立即生效)
+/// `listener` 为空时修改主监听; 修改额外监听时 `rules` 为 None 表示沿用主监听的规则
+#[tauri::command]
+pub async fn update_access_control(
+    state: State<'_, ProxyServiceState>,
+    listener: Option<String>,
+    rules: Option<crate::proxy::config::AccessControlConfig>,
+) -> Result<(), String> {
+    use crate::proxy::middleware::access_control::validate_rules;
+    if let Some(rules) = &rules {
+        validate_rules(&rules.admin).map_err(|e| format!("admin: {}", e))?;
+        validate_rules(&rules.inference).map_err(|e| format!("inference: {}", e))?;
+    }
+
+    let mut app_config = crate::modules::config::load_app_config()?;
+    let before = match listener.as_deref().filter(|l| !l.is_empty()) {
+        None => std::mem::replace(&mut app_config.proxy.access_control, rules.clone().unwrap_or_default()),
+        Some(name) => {
+            let target = app_config
+                .proxy
+                .listeners
+                .iter_mut()
+                .find(|l| l.label() == name)
+                .ok_or_else(|| format!("Listener not found: {}", name))?;
+            std::mem::replace(&mut target.access_control, rules.clone()).unwrap_or_default()
+        }
+    };
+    crate::modules::config::save_app_config(&app_config)?;
+
+    let instance_lock = state.instance.read().await;
+    if let Some(instance) = instance_lock.as_ref() {
+        instance.axum_server.update_access_control(&crate::modules::profiles::effective(&app_config.proxy));
+    }
+    desktop_audit(
+        "access_control.update",
+        listener.as_deref().unwrap_or("main"),
+        Some(serde_json::json!({ "before": before, "after": rules })),
+    );
+    Ok(())
+}
+
+/// 向 Webhook 目标发送一条测试通知 (不重试)
+#[tauri::command]
+pub async fn test_webhook(target: crate::proxy::config::WebhookTarget) -> Result<(), String> {
+    crate::proxy::webhooks::send_test(&target).await
+}
+
+/// 获取模型别名规则
+#[tauri::command]
+pub async fn get_model_aliases(
+    state: State<'_, ProxyServiceState>,
+) -> Result<Vec<crate::proxy::config::ModelAliasRule>, String> {
+    let instance_lock = state.instance.read().await;
+    if let Some(instance) = instance_lock.as_ref() {
+        Ok(instance.axum_server.model_alias_rules())
+    } else {
+        let app_config = crate::modules::config::load_app_config()?;
+        Ok(app_config.proxy.model_aliases)
+    }
+}
+
+/// 更新模型别名规则 (校验后持久化, 服务运行时立即生效)
+#[tauri::command]
+pub async fn update_model_aliases(
+    state: State<'_, ProxyServiceState>,
+    rules: Vec<crate::proxy::config::ModelAliasRule>,
+) -> Result<(), String> {
+    crate::proxy::common::model_alias::validate_rules(&rules)?;
+
+    let mut app_config = crate::modules::config::load_app_config()?;
+    let before = std::mem::replace(&mut app_config.proxy.model_aliases, rules);
+    crate::modules::config::save_app_config(&app_config)?;
+
+    let instance_lock = state.instance.read().await;
+    if let Some(instance) = instance_lock.as_ref() {
+        instance.axum_server.update_model_aliases(&crate::modules::profiles::effective(&app_config.proxy));
+    }
+    desktop_audit(
+        "model_aliases.update",
+        "model_aliases",
+        Some(serde_json::json!({ "before": before, "after": app_config.proxy.model_aliases })),
+    );
+    Ok(())
+}
+
+/// 清空响应缓存, 返回清除的条目数
+#[tauri::command]
+pub async fn clear_response_cache(
+    state: State<'_, ProxyServiceState>,
+) -> Result<usize, String> {
+    let instance_lock = state.instance.read().await;
+    if let Some(instance) = instance_lock.as_ref() {
+        Ok(instance.axum_server.clear_response_cache())
+    } else {
+        Ok(0)
+    }
+}
+
+/// 服务端会话记忆中的会话列表
+#[tauri::command]
+pub async fn list_conversation_sessions(
+    state: State<'_, ProxyServiceState>,
+) -> Result<Vec<crate::proxy::conversation_store::ConversationSessionInfo>, String> {
+    let instance_lock = state.instance.read().await;
+    Ok(instance_lock
+        .as_ref()
+        .map(|instance| instance.axum_server.list_conversation_sessions())
+        .unwrap_or_default())
+}
+
+/// 会话的完整历史
+#[tauri::command]
+pub async fn get_conversation_session(
+    state: State<'_, ProxyServiceState>,
+    id: String,
+) -> Result<Option<crate::proxy::conversation_store::ConversationSessionDetail>, String> {
+    let instance_lock = state.instance.read().await;
+    Ok(instance_lock.as_ref().and_then(|instance| instance.axum_server.get_conversation_session(&id)))
+}
+
+/// 删除会话 (不传 id 时删除全部), 返回删除的会话数
+#[tauri::command]
+pub async fn clear_conversation_sessions(
+    state: State<'_, ProxyServiceState>,
+    id: Option<String>,
+) -> Result<usize, String> {
+    let instance_lock = state.instance.read().await;
+    if let Some(instance) = instance_lock.as_ref() {
+        Ok(instance.axum_server.clear_conversation_sessions(id.as_deref()))
+    } else {
+        Ok(0)
+    }
+}
+
 /// 清除所有会话粘性绑定
 #[tauri::command]
 pub async fn clear_proxy_session_bindings(
@@ -725,3 +1225,163 @@ pub async fn clear_all_proxy_rate_limits(
         Err("服务未运行".to_string())
     }
 }
+
+/// 各上游的并发与排队状态
+#[tauri::command]
+pub async fn get_proxy_queue_status(
+    state: State<'_, ProxyServiceState>,
+) -> Result<Vec<crate::proxy::upstream::concurrency::QueueStatus>, String> {
+    let instance_lock = state.instance.read().await;
+    Ok(instance_lock
+        .as_ref()
+        .map(|instance| instance.axum_server.queue_status())
+        .unwrap_or_default())
+}
+
+/// 各上游的健康检查结果 (延迟与可用性历史)
+#[tauri::command]
+pub async fn get_upstream_health(
+    state: State<'_, ProxyServiceState>,
+) -> Result<Vec<crate::proxy::upstream::health_check::UpstreamHealth>, String> {
+    let admin_lock = state.admin_server.read().await;
+    Ok(admin_lock
+        .as_ref()
+        .map(|admin| admin.axum_server.upstream_health())
+        .unwrap_or_default())
+}
+
+/// 因连续鉴权 / 额度错误停用的上游 Key
+#[tauri::command]
+pub async fn get_disabled_upstream_keys(
+    state: State<'_, ProxyServiceState>,
+) -> Result<Vec<crate::proxy::providers::key_pool::DisabledKey>, String> {
+    let admin_lock = state.admin_server.read().await;
+    Ok(admin_lock
+        .as_ref()
+        .map(|admin| admin.axum_server.disabled_keys())
+        .unwrap_or_default())
+}
+
+/// 恢复停用的上游 Key (`key_id` 取自停用列表)
+#[tauri::command]
+pub async fn enable_upstream_key(state: State<'_, ProxyServiceState>, key_id: String) -> Result<(), String> {
+    let admin_lock = state.admin_server.read().await;
+    let enabled = admin_lock.as_ref().is_some_and(|admin| admin.axum_server.enable_key(&key_id));
+    if !enabled {
+        return Err(format!("No disabled key {}", key_id));
+    }
+    desktop_audit("key.enable", &key_id, None);
+    Ok(())
+}
+
+/// 本地 token 计数: 传入 `text` 时只计算该文本, 否则按请求体 (OpenAI / Anthropic / Gemini) 计算
+#[tauri::command]
+pub async fn count_tokens(
+    model: String,
+    body: Option<serde_json::Value>,
+    text: Option<String>,
+) -> Result<crate::proxy::common::token_counter::TokenCount, String> {
+    use crate::proxy::common::token_counter::{count_request, count_text, TokenCount, TokenEncoding};
+    match (text, body) {
+        (Some(text), _) => {
+            let encoding = TokenEncoding::for_model(&model);
+            Ok(TokenCount { input_tokens: count_text(encoding, &text), model, encoding })
+        }
+        (None, Some(body)) => Ok(count_request(&model, &body)),
+        (None, None) => Err("Either text or body is required".to_string()),
+    }
+}
+
+/// 内置的 OpenAI 兼容上游预设
+#[tauri::command]
+pub async fn get_provider_presets() -> Result<Vec<crate::proxy::providers::presets::ProviderPreset>, String> {
+    Ok(crate::proxy::providers::presets::PRESETS.to_vec())
+}
+
+// ===== 虚拟 API Key =====
+
+/// 签发虚拟 API Key (明文只在此处返回一次)
+#[tauri::command]
+pub async fn create_virtual_key(
+    request: crate::proxy::virtual_keys::CreateVirtualKeyRequest,
+) -> Result<crate::proxy::virtual_keys::CreatedVirtualKey, String> {
+    let created = crate::proxy::virtual_keys::VirtualKeyStore::global().create(request)?;
+    desktop_audit("virtual_key.create", &created.info.id, Some(created.info.audit_detail()));
+    Ok(created)
+}
+
+/// 吊销虚拟 API Key
+#[tauri::command]
+pub async fn revoke_virtual_key(id: String) -> Result<(), String> {
+    crate::proxy::virtual_keys::VirtualKeyStore::global().revoke(&id)?;
+    desktop_audit("virtual_key.revoke", &id, None);
+    Ok(())
+}
+
+/// 修改虚拟 API Key 的月度预算 (美元), 传入 null 表示不限
+#[tauri::command]
+pub async fn set_virtual_key_spend_cap(id: String, cap: Option<f64>) -> Result<(), String> {
+    let store = crate::proxy::virtual_keys::VirtualKeyStore::global();
+    let before = store.list().into_iter().find(|k| k.info.id == id).and_then(|k| k.info.monthly_spend_cap);
+    store.set_spend_cap(&id, cap)?;
+    crate::modules::audit::record(crate::modules::audit::AuditEvent {
+        actor: "desktop".to_string(),
+        source: "desktop",
+        action: "virtual_key.update".to_string(),
+        target: id,
+        changes: vec![crate::modules::audit::FieldChange {
+            path: "monthly_spend_cap".to_string(),
+            before: serde_json::json!(before),
+            after: serde_json::json!(cap),
+        }],
+        ..Default::default()
+    });
+    Ok(())
+}
+
+/// 列出虚拟 API Key 及其当前周期的用量
+#[tauri::command]
+pub async fn list_virtual_keys() -> Result<Vec<crate::proxy::virtual_keys::VirtualKeySummary>, String> {
+    Ok(crate::proxy::virtual_keys::VirtualKeyStore::global().list())
+}
+
+/// 托管的本地 llama-server 状态
+#[tauri::command]
+pub async fn get_llama_server_status() -> Result<crate::proxy::llama_server::LlamaServerStatus, String> {
+    Ok(crate::proxy::llama_server::LlamaServer::global().status())
+}
+
+/// 提示词模板列表
+#[tauri::command]
+pub async fn list_prompt_templates() -> Result<Vec<crate::proxy::prompt_templates::PromptTemplate>, String> {
+    Ok(crate::proxy::prompt_templates::PromptTemplateStore::global().list())
+}
+
+/// 新建 (id 为空) 或更新提示词模板
+#[tauri::command]
+pub async fn save_prompt_template(
+    template: crate::proxy::prompt_templates::PromptTemplate,
+) -> Result<crate::proxy::prompt_templates::PromptTemplate, String> {
+    let saved = crate::proxy::prompt_templates::PromptTemplateStore::global().save(template)?;
+    desktop_audit("prompt_template.save", &saved.name, None);
+    Ok(saved)
+}
+
+#[tauri::command]
+pub async fn delete_prompt_template(id: String) -> Result<(), String> {
+    crate::proxy::prompt_templates::PromptTemplateStore::global().delete(&id)?;
+    desktop_audit("prompt_template.delete", &id, None);
+    Ok(())
+}
+
+/// 按给定变量预览模板展开结果 (OpenAI Chat 格式, `input` 为示例用户消息)
+#[tauri::command]
+pub async fn preview_prompt_template(
+    template: crate::proxy::prompt_templates::PromptTemplate,
+    variables: std::collections::HashMap<String, String>,
+    input: String,
+) -> Result<serde_json::Value, String> {
+    let mut body = serde_json::json!({ "messages": [{ "role": "user", "content": input }] });
+    crate::proxy::prompt_templates::apply("/v1/chat/completions", &mut body, &template, &variables)?;
+    Ok(body)
+}
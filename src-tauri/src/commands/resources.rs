@@ -0,0 +1,24 @@
+use crate::modules::resource_monitor::{self, ModelFit, ResourceSnapshot};
+
+/// 当前 CPU / 内存 / 显存占用
+#[tauri::command]
+pub async fn get_system_resources() -> Result<ResourceSnapshot, String> {
+    Ok(resource_monitor::latest().await)
+}
+
+/// 开启后每 2 秒推送 `system://resources` 事件
+#[tauri::command]
+pub async fn set_resource_monitor_enabled(app: tauri::AppHandle, enabled: bool) -> Result<(), String> {
+    resource_monitor::set_enabled(app, enabled);
+    Ok(())
+}
+
+/// 估算模型能否按 llama-server 配置 (上下文长度、GPU 层数) 装入; 未指定路径时检查当前配置的模型
+#[tauri::command]
+pub async fn check_model_fit(model_path: Option<String>) -> Result<ModelFit, String> {
+    let config = crate::modules::config::load_app_config()?.proxy.llama_server;
+    let path = model_path.filter(|p| !p.trim().is_empty()).unwrap_or(config.model_path);
+    let metadata = std::fs::metadata(path.trim()).map_err(|e| format!("Failed to read model file '{}': {}", path, e))?;
+    let snapshot = resource_monitor::sample().await;
+    Ok(resource_monitor::estimate_fit(metadata.len(), config.ctx_size, config.gpu_layers, &snapshot))
+}
@@ -0,0 +1,36 @@
+use crate::modules::{self, secrets};
+
+/// 加密存储状态 (主密钥位置与已保存的密钥, 只返回掩码)
+#[tauri::command]
+pub async fn get_secret_storage_status() -> Result<secrets::SecretStorageStatus, String> {
+    let config = modules::load_app_config()?;
+    secrets::status(config.proxy.secret_storage)
+}
+
+/// 设置或轮换单个上游密钥, name 为配置字段路径 (例如 `proxy.zai.api_key`)
+#[tauri::command]
+pub async fn set_secret(
+    app: tauri::AppHandle,
+    proxy_state: tauri::State<'_, crate::commands::proxy::ProxyServiceState>,
+    name: String,
+    value: String,
+) -> Result<(), String> {
+    if !secrets::is_secret_path(&name) {
+        return Err(format!("Not a secret field: {}", name));
+    }
+    let config = modules::load_app_config()?;
+    let mut root = serde_json::to_value(&config).map_err(|e| e.to_string())?;
+    secrets::set_path(&mut root, &name, value)?;
+    let config = serde_json::from_value(root).map_err(|e| e.to_string())?;
+    crate::commands::save_config(app, proxy_state, config).await?;
+    modules::logger::log_info(&format!("[Secrets] Updated {}", name));
+    Ok(())
+}
+
+/// 生成新的主密钥并重新加密所有密钥, 返回重新加密的数量
+#[tauri::command]
+pub async fn rotate_secret_key() -> Result<usize, String> {
+    let count = secrets::rotate_master_key()?;
+    modules::logger::log_info(&format!("[Secrets] Master key rotated, {} secrets re-encrypted", count));
+    Ok(count)
+}
@@ -0,0 +1,43 @@
+use crate::models::ExternalServiceConfig;
+use crate::modules::service_manager::{self, ServiceLogLine, ServiceStatus};
+
+fn configured_services() -> Result<Vec<ExternalServiceConfig>, String> {
+    Ok(crate::modules::config::load_app_config()?.external_services)
+}
+
+/// 已配置的外部服务及其运行状态
+#[tauri::command]
+pub async fn list_external_services() -> Result<Vec<ServiceStatus>, String> {
+    Ok(service_manager::statuses(&configured_services()?))
+}
+
+/// 启动外部服务 (先启动依赖), 健康检查通过后返回
+#[tauri::command]
+pub async fn start_external_service(app: tauri::AppHandle, id: String) -> Result<ServiceStatus, String> {
+    service_manager::start(&app, &configured_services()?, &id).await
+}
+
+/// 停止外部服务 (依赖它的服务一并停止)
+#[tauri::command]
+pub async fn stop_external_service(app: tauri::AppHandle, id: String) -> Result<ServiceStatus, String> {
+    service_manager::stop(&app, &configured_services()?, &id).await
+}
+
+#[tauri::command]
+pub async fn restart_external_service(app: tauri::AppHandle, id: String) -> Result<ServiceStatus, String> {
+    let services = configured_services()?;
+    service_manager::stop(&app, &services, &id).await?;
+    service_manager::start(&app, &services, &id).await
+}
+
+/// 最近的输出 (实时输出通过 `service://log` 事件推送)
+#[tauri::command]
+pub async fn get_external_service_logs(id: String) -> Result<Vec<ServiceLogLine>, String> {
+    Ok(service_manager::logs(&id))
+}
+
+/// Ollama / ComfyUI / vLLM 配置模板
+#[tauri::command]
+pub async fn list_external_service_presets() -> Result<Vec<ExternalServiceConfig>, String> {
+    Ok(service_manager::presets())
+}
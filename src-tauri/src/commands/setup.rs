@@ -0,0 +1,78 @@
+use std::sync::Arc;
+
+use tauri::{Manager, State};
+
+use crate::commands::proxy::ProxyServiceState;
+use crate::models::AppConfig;
+use crate::modules::setup_wizard::{self, KeyTestRequest, KeyTestResult, LocalBackend, ProviderOption, SetupChoices, SetupStep, StepStatus};
+
+/// 向导可选的服务商
+#[tauri::command]
+pub async fn setup_list_providers() -> Result<Vec<ProviderOption>, String> {
+    Ok(setup_wizard::providers())
+}
+
+/// 测试粘贴的 API Key
+#[tauri::command]
+pub async fn setup_test_api_key(request: KeyTestRequest) -> Result<KeyTestResult, String> {
+    Ok(setup_wizard::test_api_key(&request).await)
+}
+
+/// 探测本机运行的 Ollama / LM Studio
+#[tauri::command]
+pub async fn setup_detect_local_backends() -> Result<Vec<LocalBackend>, String> {
+    Ok(setup_wizard::detect_local_backends().await)
+}
+
+/// 根据向导的选择生成建议配置 (不写入)
+#[tauri::command]
+pub async fn setup_propose_config(choices: SetupChoices) -> Result<AppConfig, String> {
+    let current = crate::modules::config::load_app_config()?;
+    Ok(setup_wizard::propose(&current, &choices))
+}
+
+/// 校验并写入向导确认的配置, 可选立即启动反代; 每一步通过 `setup://progress` 推送
+#[tauri::command]
+pub async fn setup_apply_config(
+    app: tauri::AppHandle,
+    proxy_state: State<'_, ProxyServiceState>,
+    config: AppConfig,
+    start_proxy: bool,
+) -> Result<Vec<SetupStep>, String> {
+    let mut steps = Vec::new();
+
+    setup_wizard::report(&app, &mut steps, "validate", StepStatus::Running, None);
+    if let Err(e) = crate::proxy::common::config_validation::ensure_valid(&config.proxy) {
+        setup_wizard::report(&app, &mut steps, "validate", StepStatus::Failed, Some(e));
+        return Ok(steps);
+    }
+    setup_wizard::report(&app, &mut steps, "validate", StepStatus::Ok, None);
+
+    setup_wizard::report(&app, &mut steps, "save", StepStatus::Running, None);
+    if let Err(e) = crate::commands::save_config(app.clone(), proxy_state.clone(), config.clone()).await {
+        setup_wizard::report(&app, &mut steps, "save", StepStatus::Failed, Some(e));
+        return Ok(steps);
+    }
+    setup_wizard::report(&app, &mut steps, "save", StepStatus::Ok, None);
+
+    if !start_proxy {
+        setup_wizard::report(&app, &mut steps, "start_proxy", StepStatus::Skipped, None);
+        return Ok(steps);
+    }
+    if proxy_state.instance.read().await.is_some() {
+        // 运行中的服务已由 save_config 热更新
+        setup_wizard::report(&app, &mut steps, "start_proxy", StepStatus::Skipped, Some("The proxy is already running".to_string()));
+        return Ok(steps);
+    }
+    setup_wizard::report(&app, &mut steps, "start_proxy", StepStatus::Running, None);
+    let cf_state = Arc::new(app.state::<crate::commands::cloudflared::CloudflaredState>().inner().clone());
+    let integration = crate::modules::integration::SystemManager::Desktop(app.clone());
+    match crate::commands::proxy::internal_start_proxy_service(config.proxy, &proxy_state, integration, cf_state).await {
+        Ok(status) => {
+            let message = format!("Listening on {}", status.base_url);
+            setup_wizard::report(&app, &mut steps, "start_proxy", StepStatus::Ok, Some(message));
+        }
+        Err(e) => setup_wizard::report(&app, &mut steps, "start_proxy", StepStatus::Failed, Some(e)),
+    }
+    Ok(steps)
+}
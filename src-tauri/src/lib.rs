@@ -37,6 +37,50 @@ fn increase_nofile_limit() {
     }
 }
 
+/// 读取 `--name value` 或 `--name=value` 形式的参数
+fn arg_value(args: &[String], name: &str) -> Option<String> {
+    let prefix = format!("{}=", name);
+    args.iter().enumerate().find_map(|(i, arg)| {
+        if arg == name {
+            args.get(i + 1).cloned()
+        } else {
+            arg.strip_prefix(&prefix).map(str::to_string)
+        }
+    })
+}
+
+/// 等待停止信号: Ctrl-C, 以及 systemd / launchd 停止服务时发送的 SIGTERM、Windows 关机通知
+async fn wait_for_shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut term) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = term.recv() => info!("Received SIGTERM"),
+                }
+                return;
+            }
+            Err(e) => warn!("Failed to listen for SIGTERM: {}", e),
+        }
+    }
+    #[cfg(windows)]
+    {
+        match tokio::signal::windows::ctrl_shutdown() {
+            Ok(mut shutdown) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = shutdown.recv() => info!("Received system shutdown notification"),
+                }
+                return;
+            }
+            Err(e) => warn!("Failed to listen for shutdown notification: {}", e),
+        }
+    }
+    tokio::signal::ctrl_c().await.ok();
+}
+
 // Test command
 #[tauri::command]
 fn greet(name: &str) -> String {
@@ -47,7 +91,7 @@ fn greet(name: &str) -> String {
 pub fn run() {
     // Check for headless mode and custom data directory
     let args: Vec<String> = std::env::args().collect();
-    let is_headless = args.iter().any(|arg| arg == "--headless");
+    let mut is_headless = args.iter().any(|arg| arg == "--headless");
     
     // Parse --data-dir argument for multi-instance support
     // Usage: AIOLauncher-Server-Trans.exe --data-dir "C:\path\to\data"
@@ -64,6 +108,43 @@ pub fn run() {
         }
     }
 
+    // 命令行子命令 (serve / status / keys / usage / config validate), 见 modules::cli::USAGE
+    // Usage: AIOLauncher-Server-Trans keys create --limit 1000000
+    match modules::cli::parse(&args) {
+        Some(Ok(modules::cli::Command::Serve)) => is_headless = true,
+        Some(Ok(command)) => {
+            modules::cli::attach_console();
+            std::process::exit(modules::cli::run(command));
+        }
+        Some(Err(e)) => {
+            modules::cli::attach_console();
+            eprintln!("{}\n\n{}", e, modules::cli::USAGE);
+            std::process::exit(2);
+        }
+        None => {}
+    }
+
+    // 输出系统服务定义后退出 (systemd / launchd / windows), 服务以 --headless 运行
+    // Usage: AIOLauncher-Server-Trans --service-unit systemd [--data-dir <dir>] > aiolauncher-proxy.service
+    if let Some(kind) = arg_value(&args, "--service-unit") {
+        modules::cli::attach_console();
+        let unit = modules::service_unit::ServiceKind::parse(&kind).and_then(|kind| {
+            let exe = std::env::current_exe().map_err(|e| format!("Failed to resolve executable path: {}", e))?;
+            let data_dir = modules::account::get_data_dir()?;
+            Ok(modules::service_unit::render(kind, &exe, &data_dir))
+        });
+        match unit {
+            Ok(unit) => {
+                print!("{}", unit);
+                std::process::exit(0);
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(2);
+            }
+        }
+    }
+
     // Increase file descriptor limit (macOS only)
     #[cfg(target_os = "macos")]
     increase_nofile_limit();
@@ -75,6 +156,24 @@ pub fn run() {
     if let Err(e) = modules::token_stats::init_db() {
         error!("Failed to initialize token stats database: {}", e);
     }
+
+    // Initialize usage accounting database
+    if let Err(e) = modules::usage::init_db() {
+        error!("Failed to initialize usage database: {}", e);
+    }
+
+    // Initialize audit log database
+    if let Err(e) = modules::audit::init_db() {
+        error!("Failed to initialize audit database: {}", e);
+    }
+
+    // 启动时报告配置问题 (不阻止启动, 保存时才会拒绝)
+    if let Ok(config) = modules::load_app_config() {
+        for issue in proxy::common::config_validation::check(&config.proxy) {
+            warn!("[Config] {:?} {}", issue.severity, issue);
+        }
+        modules::profiles::configure(&config);
+    }
     
     if is_headless {
         info!("Starting in HEADLESS mode...");
@@ -143,6 +242,7 @@ pub fn run() {
                     info!("--------------------------------------------------");
                     info!("🚀 Headless mode proxy service starting...");
                     info!("📍 Port: {}", config.proxy.port);
+                    info!("🛠  Admin API: http://<host>:{}/api (remote control, Web UI password required)", config.proxy.port);
                     info!("🔑 Current API Key: {}", config.proxy.api_key);
                     if let Some(ref pwd) = config.proxy.admin_password {
                         info!("🔐 Web UI Password: {}", pwd);
@@ -169,6 +269,10 @@ pub fn run() {
                     // Start smart scheduler
                     modules::scheduler::start_scheduler(None, proxy_state.clone());
                     info!("Smart scheduler started in headless mode.");
+
+                    // Watch config file for external edits
+                    modules::config_watcher::start_config_watcher(None, proxy_state.clone());
+                    modules::key_check::start_key_check();
                 }
                 Err(e) => {
                     error!("Failed to load config for headless mode: {}", e);
@@ -176,9 +280,13 @@ pub fn run() {
                 }
             }
             
-            // Wait for Ctrl-C
-            tokio::signal::ctrl_c().await.ok();
+            // Wait for Ctrl-C / SIGTERM
+            wait_for_shutdown_signal().await;
             info!("Headless mode shutting down");
+            let admin = proxy_state.admin_server.write().await.take();
+            if let Some(admin) = admin {
+                admin.axum_server.stop().await;
+            }
         });
         return;
     }
@@ -245,7 +353,14 @@ pub fn run() {
                         info!("Admin server (port {}) started successfully", config.proxy.port);
                     }
 
-                    // 2. 自动启动转发逻辑
+                    // 2. 自动启动外部服务 (当前方案指定时按方案)
+                    let services_config = config.clone();
+                    let services_handle = handle.clone();
+                    tauri::async_runtime::spawn(async move {
+                        modules::profiles::start_services(&services_handle, &services_config).await;
+                    });
+
+                    // 3. 自动启动转发逻辑
                     if config.proxy.auto_start {
                         if let Err(e) = commands::proxy::internal_start_proxy_service(
                             config.proxy,
@@ -264,6 +379,11 @@ pub fn run() {
             // Start smart scheduler
             let scheduler_state = app.handle().state::<commands::proxy::ProxyServiceState>();
             modules::scheduler::start_scheduler(Some(app.handle().clone()), scheduler_state.inner().clone());
+
+            // Watch config file for external edits
+            modules::config_watcher::start_config_watcher(Some(app.handle().clone()), scheduler_state.inner().clone());
+            modules::config_sync::start_auto_sync(app.handle().clone());
+            modules::key_check::start_key_check();
             
             // [PHASE 1] 已整合至 Axum 端口 (8045)，不再单独启动 19527 端口
             info!("Management API integrated into main proxy server (port 8045)");
@@ -272,6 +392,10 @@ pub fn run() {
         })
         .on_window_event(|window, event| {
             if let tauri::WindowEvent::CloseRequested { api, .. } = event {
+                // 只有主窗口关闭时隐藏到托盘, 检查器等辅助窗口正常关闭
+                if window.label() != "main" {
+                    return;
+                }
                 let _ = window.hide();
                 #[cfg(target_os = "macos")]
                 {
@@ -308,6 +432,13 @@ pub fn run() {
             // Config commands
             commands::load_config,
             commands::save_config,
+            commands::export_config,
+            commands::import_config,
+            commands::validate_config,
+            commands::get_audit_log,
+            commands::secrets::get_secret_storage_status,
+            commands::secrets::set_secret,
+            commands::secrets::rotate_secret_key,
             // Additional commands
             commands::prepare_oauth_url,
             commands::start_oauth_login,
@@ -323,6 +454,8 @@ pub fn run() {
             commands::read_text_file,
             commands::clear_log_cache,
             commands::open_data_folder,
+            commands::get_image_save_dir,
+            commands::open_image_save_dir,
             commands::get_data_dir_path,
             commands::show_main_window,
             commands::set_window_theme,
@@ -338,10 +471,19 @@ pub fn run() {
             commands::proxy::start_proxy_service,
             commands::proxy::stop_proxy_service,
             commands::proxy::get_proxy_status,
+            commands::proxy::get_proxy_supervisor_status,
             commands::proxy::get_proxy_stats,
+            commands::proxy::get_latency_stats,
+            commands::proxy::get_shadow_results,
+            commands::proxy::clear_shadow_results,
+            commands::proxy::compare_models,
             commands::proxy::get_proxy_logs,
             commands::proxy::get_proxy_logs_paginated,
             commands::proxy::get_proxy_log_detail,
+            commands::proxy::get_proxy_inflight_requests,
+            commands::proxy::get_proxy_request_inspection,
+            commands::proxy::replay_proxy_request,
+            commands::proxy::open_request_inspector,
             commands::proxy::get_proxy_logs_count,
             commands::proxy::export_proxy_logs,
             commands::proxy::export_proxy_logs_json,
@@ -349,17 +491,44 @@ pub fn run() {
             commands::proxy::get_proxy_logs_filtered,
             commands::proxy::set_proxy_monitor_enabled,
             commands::proxy::clear_proxy_logs,
+            commands::proxy::clear_response_cache,
+            commands::proxy::list_conversation_sessions,
+            commands::proxy::get_conversation_session,
+            commands::proxy::clear_conversation_sessions,
             commands::proxy::generate_api_key,
             commands::proxy::reload_proxy_accounts,
             commands::proxy::update_model_mapping,
             commands::proxy::fetch_zai_models,
+            commands::proxy::test_upstream_proxy,
             commands::proxy::get_proxy_scheduling_config,
             commands::proxy::update_proxy_scheduling_config,
+            commands::proxy::get_proxy_rate_limit_config,
+            commands::proxy::update_proxy_rate_limit_config,
+            commands::proxy::get_access_control,
+            commands::proxy::update_access_control,
+            commands::proxy::test_webhook,
+            commands::proxy::get_model_aliases,
+            commands::proxy::update_model_aliases,
             commands::proxy::clear_proxy_session_bindings,
             commands::proxy::set_preferred_account,
             commands::proxy::get_preferred_account,
             commands::proxy::clear_proxy_rate_limit,
             commands::proxy::clear_all_proxy_rate_limits,
+            commands::proxy::get_proxy_queue_status,
+            commands::proxy::get_upstream_health,
+            commands::proxy::get_disabled_upstream_keys,
+            commands::proxy::enable_upstream_key,
+            commands::proxy::get_provider_presets,
+            commands::proxy::count_tokens,
+            commands::proxy::create_virtual_key,
+            commands::proxy::revoke_virtual_key,
+            commands::proxy::set_virtual_key_spend_cap,
+            commands::proxy::list_virtual_keys,
+            commands::proxy::list_prompt_templates,
+            commands::proxy::get_llama_server_status,
+            commands::proxy::save_prompt_template,
+            commands::proxy::delete_prompt_template,
+            commands::proxy::preview_prompt_template,
             // Autostart commands
             commands::autostart::toggle_auto_launch,
             commands::autostart::is_auto_launch_enabled,
@@ -380,6 +549,12 @@ pub fn run() {
             commands::get_token_stats_model_trend_daily,
             commands::get_token_stats_account_trend_hourly,
             commands::get_token_stats_account_trend_daily,
+            commands::get_usage_by_day,
+            commands::get_usage_by_model,
+            commands::get_usage_by_api_key,
+            commands::get_usage_by_client,
+            commands::get_usage_by_upstream,
+            commands::export_usage,
             proxy::cli_sync::get_cli_sync_status,
             proxy::cli_sync::execute_cli_sync,
             proxy::cli_sync::execute_cli_restore,
@@ -390,10 +565,37 @@ pub fn run() {
             commands::cloudflared::cloudflared_start,
             commands::cloudflared::cloudflared_stop,
             commands::cloudflared::cloudflared_get_status,
+            commands::model_download::start_model_download,
+            commands::model_download::list_model_downloads,
+            commands::model_download::cancel_model_download,
+            commands::model_download::list_local_models,
+            commands::resources::get_system_resources,
+            commands::resources::set_resource_monitor_enabled,
+            commands::resources::check_model_fit,
+            commands::services::list_external_services,
+            commands::services::start_external_service,
+            commands::services::stop_external_service,
+            commands::services::restart_external_service,
+            commands::services::get_external_service_logs,
+            commands::services::list_external_service_presets,
+            commands::profiles::list_profiles,
+            commands::profiles::switch_profile,
+            commands::config_sync::get_config_sync_status,
+            commands::config_sync::sync_config_now,
+            commands::setup::setup_list_providers,
+            commands::setup::setup_test_api_key,
+            commands::setup::setup_detect_local_backends,
+            commands::setup::setup_propose_config,
+            commands::setup::setup_apply_config,
+            commands::key_check::get_key_check_results,
+            commands::key_check::check_upstream_keys,
         ])
         .build(tauri::generate_context!())
         .expect("error while building tauri application")
         .run(|app_handle, event| {
+            if let tauri::RunEvent::Exit = event {
+                modules::service_manager::stop_all();
+            }
             // Handle macOS dock icon click to reopen window
             #[cfg(target_os = "macos")]
             if let tauri::RunEvent::Reopen { .. } = event {
@@ -25,6 +25,17 @@ pub struct AppConfig {
     pub pinned_quota_models: PinnedQuotaModelsConfig, // [NEW] Pinned quota models list
     #[serde(default)]
     pub circuit_breaker: CircuitBreakerConfig, // [NEW] Circuit breaker configuration
+    #[serde(default)]
+    pub desktop_notifications: DesktopNotificationConfig,
+    /// External tools (Ollama, ComfyUI, vLLM...) managed by the launcher
+    #[serde(default)]
+    pub external_services: Vec<ExternalServiceConfig>,
+    /// Named startup profiles layered over `proxy`
+    #[serde(default)]
+    pub profiles: Vec<StartupProfile>,
+    /// Profile currently in effect; `None` runs the plain `proxy` settings
+    #[serde(default)]
+    pub active_profile: Option<String>,
 }
 
 /// Scheduled warmup configuration
@@ -163,6 +174,95 @@ impl Default for CircuitBreakerConfig {
     }
 }
 
+/// Desktop notifications for critical proxy events (each type can be turned off)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DesktopNotificationConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Upstream became unavailable (requires health checks)
+    #[serde(default = "default_true")]
+    pub upstream_outage: bool,
+    /// Virtual key request / token quota or spend cap exhausted
+    #[serde(default = "default_true")]
+    pub quota_exhausted: bool,
+    /// TLS listener certificate expires soon or has expired
+    #[serde(default = "default_true")]
+    pub cert_expiry: bool,
+    /// Proxy service was restarted by the supervisor
+    #[serde(default = "default_true")]
+    pub service_restarted: bool,
+    /// Upstream key was rejected, is running out of credits or expires soon
+    #[serde(default = "default_true")]
+    pub upstream_keys: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// An external service started and supervised by the launcher
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ExternalServiceConfig {
+    /// Unique id, referenced by `depends_on`
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    /// Program to run (absolute path or a name on PATH)
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub cwd: Option<String>,
+    #[serde(default)]
+    pub env: std::collections::BTreeMap<String, String>,
+    /// Port the service listens on; `{port}` in args, env and health_url is replaced with the
+    /// port actually used (the next free one when this port is taken)
+    #[serde(default)]
+    pub port: Option<u16>,
+    /// Polled after start; the service counts as ready once it returns 2xx
+    #[serde(default)]
+    pub health_url: Option<String>,
+    /// Services that must be ready before this one starts
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    /// Start together with the launcher
+    #[serde(default)]
+    pub autostart: bool,
+    #[serde(default = "default_startup_timeout_secs")]
+    pub startup_timeout_secs: u64,
+}
+
+fn default_startup_timeout_secs() -> u64 {
+    60
+}
+
+/// A named set of overrides (upstreams, aliases, limits...) plus the external services to run
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StartupProfile {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// JSON Merge Patch (RFC 7386) applied to the saved `proxy` settings
+    #[serde(default)]
+    pub proxy: serde_json::Value,
+    /// External service ids to keep running; `None` leaves services untouched
+    #[serde(default)]
+    pub services: Option<Vec<String>>,
+}
+
+impl Default for DesktopNotificationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            upstream_outage: true,
+            quota_exhausted: true,
+            cert_expiry: true,
+            service_restarted: true,
+            upstream_keys: true,
+        }
+    }
+}
+
 impl AppConfig {
     pub fn new() -> Self {
         Self {
@@ -181,6 +281,10 @@ impl AppConfig {
             quota_protection: QuotaProtectionConfig::default(),
             pinned_quota_models: PinnedQuotaModelsConfig::default(),
             circuit_breaker: CircuitBreakerConfig::default(),
+            desktop_notifications: DesktopNotificationConfig::default(),
+            external_services: Vec::new(),
+            profiles: Vec::new(),
+            active_profile: None,
         }
     }
 }
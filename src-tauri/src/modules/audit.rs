@@ -0,0 +1,270 @@
+// 管理操作审计日志
+// 管理接口、桌面端、命令行与服务监督的变更操作追加写入 audit.db (触发器禁止修改和删除记录);
+// 配置变更按字段记录前后差异, 密钥字段只记录掩码。
+
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::PathBuf;
+
+/// 单个字段的变更 (`before` / `after` 为 null 表示新增 / 删除)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldChange {
+    pub path: String,
+    pub before: Value,
+    pub after: Value,
+}
+
+/// 待写入的审计事件
+#[derive(Debug, Clone, Default)]
+pub struct AuditEvent {
+    /// 操作者, 例如 `admin@192.168.1.5`、`desktop`、`cli`
+    pub actor: String,
+    /// 来源: admin_api / desktop / cli / system
+    pub source: &'static str,
+    /// 操作, 例如 `config.update`、`virtual_key.create`、`service.restart`
+    pub action: String,
+    /// 操作对象 (请求路径、Key id 等)
+    pub target: String,
+    /// 管理接口的响应状态码
+    pub status: Option<u16>,
+    pub changes: Vec<FieldChange>,
+    pub detail: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub timestamp: i64,
+    pub actor: String,
+    pub source: String,
+    pub action: String,
+    pub target: String,
+    pub status: Option<u16>,
+    pub changes: Vec<FieldChange>,
+    pub detail: Option<Value>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct AuditQuery {
+    pub since: Option<i64>,
+    pub until: Option<i64>,
+    pub actor: Option<String>,
+    /// 按前缀匹配, 例如 `config` 或 `virtual_key.`
+    pub action: Option<String>,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+}
+
+const DEFAULT_LIMIT: usize = 100;
+const MAX_LIMIT: usize = 1000;
+/// 配置中需要掩码的字段 (除 secrets 识别的上游密钥外)
+const MASKED_PATHS: &[&str] = &["proxy.api_key", "proxy.admin_password"];
+
+fn get_db_path() -> Result<PathBuf, String> {
+    let data_dir = crate::modules::account::get_data_dir()?;
+    Ok(data_dir.join("audit.db"))
+}
+
+fn connect_db() -> Result<Connection, String> {
+    let conn = Connection::open(get_db_path()?).map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "journal_mode", "WAL")
+        .map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "busy_timeout", 5000)
+        .map_err(|e| e.to_string())?;
+    Ok(conn)
+}
+
+fn init_schema(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS audit_log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            timestamp INTEGER NOT NULL,
+            actor TEXT NOT NULL,
+            source TEXT NOT NULL,
+            action TEXT NOT NULL,
+            target TEXT NOT NULL,
+            status INTEGER,
+            changes TEXT NOT NULL DEFAULT '[]',
+            detail TEXT
+        );
+        CREATE INDEX IF NOT EXISTS idx_audit_timestamp ON audit_log (timestamp DESC);
+        CREATE TRIGGER IF NOT EXISTS audit_log_no_update BEFORE UPDATE ON audit_log
+        BEGIN SELECT RAISE(ABORT, 'audit log is append-only'); END;
+        CREATE TRIGGER IF NOT EXISTS audit_log_no_delete BEFORE DELETE ON audit_log
+        BEGIN SELECT RAISE(ABORT, 'audit log is append-only'); END;",
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Initialize the audit database
+pub fn init_db() -> Result<(), String> {
+    init_schema(&connect_db()?)
+}
+
+fn insert(conn: &Connection, event: &AuditEvent, timestamp: i64) -> Result<(), String> {
+    let changes = serde_json::to_string(&event.changes).map_err(|e| e.to_string())?;
+    let detail = event.detail.as_ref().map(|d| d.to_string());
+    conn.execute(
+        "INSERT INTO audit_log (timestamp, actor, source, action, target, status, changes, detail)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![timestamp, event.actor, event.source, event.action, event.target, event.status, changes, detail],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 追加一条审计记录; 写入失败只记录警告, 不影响操作本身
+pub fn record(event: AuditEvent) {
+    let result = connect_db().and_then(|conn| insert(&conn, &event, chrono::Utc::now().timestamp()));
+    if let Err(e) = result {
+        tracing::warn!("[Audit] Failed to record {} by {}: {}", event.action, event.actor, e);
+    }
+}
+
+fn query_entries(conn: &Connection, query: &AuditQuery) -> Result<Vec<AuditEntry>, String> {
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, timestamp, actor, source, action, target, status, changes, detail FROM audit_log
+             WHERE (?1 IS NULL OR timestamp >= ?1) AND (?2 IS NULL OR timestamp <= ?2)
+               AND (?3 IS NULL OR actor = ?3) AND (?4 IS NULL OR action LIKE ?4 || '%')
+             ORDER BY id DESC LIMIT ?5 OFFSET ?6",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(
+            params![query.since, query.until, query.actor, query.action, limit as i64, query.offset.unwrap_or(0) as i64],
+            |row| {
+                let changes: String = row.get(7)?;
+                let detail: Option<String> = row.get(8)?;
+                Ok(AuditEntry {
+                    id: row.get(0)?,
+                    timestamp: row.get(1)?,
+                    actor: row.get(2)?,
+                    source: row.get(3)?,
+                    action: row.get(4)?,
+                    target: row.get(5)?,
+                    status: row.get(6)?,
+                    changes: serde_json::from_str(&changes).unwrap_or_default(),
+                    detail: detail.and_then(|d| serde_json::from_str(&d).ok()),
+                })
+            },
+        )
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+/// 按时间倒序查询审计记录
+pub fn query(query: &AuditQuery) -> Result<Vec<AuditEntry>, String> {
+    query_entries(&connect_db()?, query)
+}
+
+/// 掩码密钥字段 (整体替换的对象 / 数组按内部字段路径逐个处理)
+fn redact(path: &str, value: &Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(k, v)| (k.clone(), redact(&format!("{}.{}", path, k), v)))
+                .collect(),
+        ),
+        Value::Array(items) => {
+            Value::Array(items.iter().enumerate().map(|(i, v)| redact(&format!("{}[{}]", path, i), v)).collect())
+        }
+        Value::String(s) if !s.is_empty() && (MASKED_PATHS.contains(&path) || crate::modules::secrets::is_secret_path(path)) => {
+            Value::String("****".to_string())
+        }
+        other => other.clone(),
+    }
+}
+
+fn diff_into(path: &str, before: &Value, after: &Value, out: &mut Vec<FieldChange>) {
+    if before == after {
+        return;
+    }
+    let child = |key: &str| if path.is_empty() { key.to_string() } else { format!("{}.{}", path, key) };
+    match (before, after) {
+        (Value::Object(a), Value::Object(b)) => {
+            for (key, value) in a {
+                diff_into(&child(key), value, b.get(key).unwrap_or(&Value::Null), out);
+            }
+            for (key, value) in b.iter().filter(|(k, _)| !a.contains_key(*k)) {
+                diff_into(&child(key), &Value::Null, value, out);
+            }
+        }
+        (Value::Array(a), Value::Array(b)) if a.len() == b.len() => {
+            for (i, (x, y)) in a.iter().zip(b).enumerate() {
+                diff_into(&format!("{}[{}]", path, i), x, y, out);
+            }
+        }
+        _ => out.push(FieldChange { path: path.to_string(), before: redact(path, before), after: redact(path, after) }),
+    }
+}
+
+/// 按字段比较两个 JSON 值 (通常是配置快照)
+pub fn diff(before: &Value, after: &Value) -> Vec<FieldChange> {
+    let mut out = Vec::new();
+    diff_into("", before, after, &mut out);
+    out
+}
+
+/// 当前配置的 JSON 快照, 用于计算变更前后差异
+pub fn config_snapshot() -> Option<Value> {
+    crate::modules::config::load_app_config()
+        .ok()
+        .and_then(|config| serde_json::to_value(config).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_diff_masks_secrets() {
+        let before = json!({"proxy": {"port": 8045, "api_key": "sk-old", "zai": {"api_key": "z1", "enabled": false},
+            "listeners": [], "model_aliases": [{"pattern": "a", "target": "b"}]}});
+        let after = json!({"proxy": {"port": 9000, "api_key": "sk-new", "zai": {"api_key": "z2", "enabled": true},
+            "listeners": [{"host": "0.0.0.0", "port": 9001}], "model_aliases": [{"pattern": "a", "target": "c"}]}});
+        let changes = diff(&before, &after);
+        let by_path = |p: &str| changes.iter().find(|c| c.path == p).cloned().unwrap();
+        assert_eq!(changes.len(), 6);
+        assert_eq!(by_path("proxy.port").after, json!(9000));
+        assert_eq!(by_path("proxy.api_key").after, json!("****"));
+        assert_eq!(by_path("proxy.zai.api_key").before, json!("****"));
+        assert_eq!(by_path("proxy.zai.enabled").after, json!(true));
+        assert_eq!(by_path("proxy.listeners").after, json!([{"host": "0.0.0.0", "port": 9001}]));
+        assert_eq!(by_path("proxy.model_aliases[0].target").after, json!("c"));
+        assert!(diff(&before, &before).is_empty());
+    }
+
+    #[test]
+    fn test_append_only_log() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn).unwrap();
+        let event = |action: &str, actor: &str| AuditEvent {
+            actor: actor.to_string(),
+            source: "admin_api",
+            action: action.to_string(),
+            target: "/api/config".to_string(),
+            status: Some(200),
+            changes: vec![FieldChange { path: "proxy.port".to_string(), before: json!(1), after: json!(2) }],
+            detail: None,
+        };
+        insert(&conn, &event("config.update", "admin@127.0.0.1"), 100).unwrap();
+        insert(&conn, &event("virtual_key.create", "cli"), 200).unwrap();
+        insert(&conn, &event("virtual_key.revoke", "cli"), 300).unwrap();
+
+        let all = query_entries(&conn, &AuditQuery::default()).unwrap();
+        assert_eq!(all.iter().map(|e| e.timestamp).collect::<Vec<_>>(), vec![300, 200, 100]);
+        assert_eq!(all[2].changes[0].after, json!(2));
+        let keys = AuditQuery { action: Some("virtual_key.".to_string()), since: Some(250), ..Default::default() };
+        assert_eq!(query_entries(&conn, &keys).unwrap().len(), 1);
+        let by_actor = AuditQuery { actor: Some("cli".to_string()), ..Default::default() };
+        assert_eq!(query_entries(&conn, &by_actor).unwrap().len(), 2);
+
+        assert!(conn.execute("DELETE FROM audit_log", []).is_err());
+        assert!(conn.execute("UPDATE audit_log SET actor = 'x'", []).is_err());
+    }
+}
@@ -0,0 +1,463 @@
+// 命令行管理接口
+// `aiolauncher <serve|status|keys|usage|config|profile> ...` 供脚本调用, 与桌面版共用同一个数据目录;
+// 运行中的服务会自动重新加载命令行修改的虚拟 Key 与配置文件。
+
+use std::collections::HashMap;
+
+use serde_json::json;
+
+use crate::modules::audit::AuditEvent;
+use crate::modules::usage::UsageExportGroup;
+use crate::proxy::common::config_validation::{self, IssueSeverity};
+use crate::proxy::virtual_keys::{CreateVirtualKeyRequest, QuotaPeriod, VirtualKeyStore};
+
+pub const USAGE: &str = "\
+Usage: aiolauncher <command> [options] [--data-dir <dir>]
+
+Commands:
+  serve                          Run the proxy without a window (same as --headless)
+  status [--json]                Show whether the local proxy is running
+  keys list [--json]             List virtual API keys
+  keys create [options]          Create a virtual API key
+      --name <name>              Display name (default: cli)
+      --limit <tokens>           Token quota per period
+      --requests <count>         Request quota per period
+      --period <daily|monthly|total>
+      --models <a,b,...>         Allowed model patterns (wildcards supported)
+      --spend-cap <usd>          Monthly spend cap
+      --expires <7d|YYYY-MM-DD>  Expiry
+      --account <email>          Upstream account bound to the key
+      --json
+  keys revoke <id>               Revoke a virtual API key
+  usage [--since 7d] [--by day|model|key|client|upstream] [--json]
+  config validate [--file <path>] [--json]
+  profile list [--json]          List startup profiles
+  profile use <name|default>     Switch profile (a running launcher applies it)
+";
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Serve,
+    Help,
+    Status { json: bool },
+    KeysList { json: bool },
+    KeysCreate { args: HashMap<String, String>, json: bool },
+    KeysRevoke { id: String },
+    Usage { since: String, group: UsageExportGroup, json: bool },
+    ConfigValidate { file: Option<String>, json: bool },
+    ProfileList { json: bool },
+    ProfileUse { name: String },
+}
+
+/// 解析后的参数: 带值的选项、开关与位置参数; `--data-dir` 由启动流程处理, 这里跳过
+struct Flags {
+    values: HashMap<String, String>,
+    switches: Vec<String>,
+    positional: Vec<String>,
+}
+
+fn parse_flags(args: &[String], with_value: &[&str]) -> Result<Flags, String> {
+    let mut flags = Flags { values: HashMap::new(), switches: Vec::new(), positional: Vec::new() };
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let Some(name) = arg.strip_prefix("--") else {
+            flags.positional.push(arg.clone());
+            continue;
+        };
+        let (name, inline) = match name.split_once('=') {
+            Some((name, value)) => (name, Some(value.to_string())),
+            None => (name, None),
+        };
+        if name == "data-dir" {
+            if inline.is_none() {
+                iter.next();
+            }
+        } else if with_value.contains(&name) {
+            let value = inline
+                .or_else(|| iter.next().cloned())
+                .ok_or_else(|| format!("Option --{} requires a value", name))?;
+            flags.values.insert(name.to_string(), value);
+        } else if name == "json" && inline.is_none() {
+            flags.switches.push(name.to_string());
+        } else {
+            return Err(format!("Unknown option --{}", name));
+        }
+    }
+    Ok(flags)
+}
+
+/// 识别子命令; 第一个参数不是子命令时返回 None (按原方式启动)
+pub fn parse(args: &[String]) -> Option<Result<Command, String>> {
+    let command = args.get(1)?.as_str();
+    let rest = &args[2..];
+    let sub = rest.first().map(String::as_str);
+    let parsed = match (command, sub) {
+        ("serve", _) => parse_flags(rest, &[]).map(|_| Command::Serve),
+        ("help" | "--help" | "-h", _) => Ok(Command::Help),
+        ("status", _) => parse_flags(rest, &[]).map(|f| Command::Status { json: f.switches.contains(&"json".to_string()) }),
+        ("keys", Some("list")) => {
+            parse_flags(&rest[1..], &[]).map(|f| Command::KeysList { json: f.switches.contains(&"json".to_string()) })
+        }
+        ("keys", Some("create")) => parse_flags(
+            &rest[1..],
+            &["name", "limit", "requests", "period", "models", "spend-cap", "expires", "account"],
+        )
+        .map(|f| Command::KeysCreate { json: f.switches.contains(&"json".to_string()), args: f.values }),
+        ("keys", Some("revoke")) => parse_flags(&rest[1..], &[]).and_then(|f| match f.positional.as_slice() {
+            [id] => Ok(Command::KeysRevoke { id: id.clone() }),
+            _ => Err("Usage: aiolauncher keys revoke <id>".to_string()),
+        }),
+        ("keys", _) => Err("Usage: aiolauncher keys <list|create|revoke>".to_string()),
+        ("usage", _) => parse_flags(rest, &["since", "by"]).and_then(|f| {
+            let group = match f.values.get("by").map(String::as_str).unwrap_or("day") {
+                "day" => UsageExportGroup::Day,
+                "model" => UsageExportGroup::Model,
+                "key" | "api_key" => UsageExportGroup::ApiKey,
+                "client" => UsageExportGroup::Client,
+                "upstream" => UsageExportGroup::Upstream,
+                other => return Err(format!("Unknown grouping '{}', expected day, model, key, client or upstream", other)),
+            };
+            Ok(Command::Usage {
+                since: f.values.get("since").cloned().unwrap_or_else(|| "7d".to_string()),
+                group,
+                json: f.switches.contains(&"json".to_string()),
+            })
+        }),
+        ("config", Some("validate")) => parse_flags(&rest[1..], &["file"]).map(|f| Command::ConfigValidate {
+            file: f.values.get("file").cloned(),
+            json: f.switches.contains(&"json".to_string()),
+        }),
+        ("config", _) => Err("Usage: aiolauncher config validate [--file <path>]".to_string()),
+        ("profile", Some("list")) => {
+            parse_flags(&rest[1..], &[]).map(|f| Command::ProfileList { json: f.switches.contains(&"json".to_string()) })
+        }
+        ("profile", Some("use")) => parse_flags(&rest[1..], &[]).and_then(|f| match f.positional.as_slice() {
+            [name] => Ok(Command::ProfileUse { name: name.clone() }),
+            _ => Err("Usage: aiolauncher profile use <name|default>".to_string()),
+        }),
+        ("profile", _) => Err("Usage: aiolauncher profile <list|use>".to_string()),
+        _ => return None,
+    };
+    Some(parsed)
+}
+
+/// 解析相对时间 (30m / 24h / 7d / 2w) 或 UTC 日期 (YYYY-MM-DD), 返回 Unix 秒
+fn parse_time(value: &str, now: i64, future: bool) -> Result<i64, String> {
+    let value = value.trim();
+    if let Ok(date) = chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc().timestamp());
+    }
+    let split = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+    let (amount, unit) = value.split_at(split);
+    let amount: i64 = amount.parse().map_err(|_| format!("Invalid time '{}', use e.g. 7d or 2026-01-31", value))?;
+    let unit_secs = match unit {
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86_400,
+        "w" => 7 * 86_400,
+        _ => return Err(format!("Invalid time unit in '{}', expected m, h, d or w", value)),
+    };
+    let offset = amount.saturating_mul(unit_secs);
+    Ok(if future { now + offset } else { now - offset })
+}
+
+fn parse_number<T: std::str::FromStr>(args: &HashMap<String, String>, name: &str) -> Result<Option<T>, String> {
+    args.get(name)
+        .map(|v| v.trim().replace('_', "").parse::<T>().map_err(|_| format!("Invalid value for --{}: {}", name, v)))
+        .transpose()
+}
+
+fn create_request(args: &HashMap<String, String>, now: i64) -> Result<CreateVirtualKeyRequest, String> {
+    let quota_period = match args.get("period").map(String::as_str) {
+        None | Some("monthly") => QuotaPeriod::Monthly,
+        Some("daily") => QuotaPeriod::Daily,
+        Some("total") => QuotaPeriod::Total,
+        Some(other) => return Err(format!("Unknown period '{}', expected daily, monthly or total", other)),
+    };
+    Ok(CreateVirtualKeyRequest {
+        name: args.get("name").cloned().unwrap_or_else(|| "cli".to_string()),
+        allowed_models: args
+            .get("models")
+            .map(|m| m.split(',').map(|s| s.trim().to_string()).collect())
+            .unwrap_or_default(),
+        request_quota: parse_number(args, "requests")?,
+        token_quota: parse_number(args, "limit")?,
+        quota_period,
+        monthly_spend_cap: parse_number(args, "spend-cap")?,
+        expires_at: args.get("expires").map(|v| parse_time(v, now, true)).transpose()?,
+        upstream_account: args.get("account").cloned(),
+    })
+}
+
+fn print_json(value: &impl serde::Serialize) {
+    println!("{}", serde_json::to_string_pretty(value).unwrap_or_default());
+}
+
+fn status(json: bool) -> Result<i32, String> {
+    let config = crate::modules::config::load_app_config()?.proxy;
+    let scheme = if config.listener_tls.enabled { "https" } else { "http" };
+    let url = format!("{}://127.0.0.1:{}/api/proxy/status", scheme, config.port);
+    let token = config.admin_password.clone().filter(|p| !p.is_empty()).unwrap_or(config.api_key.clone());
+    // 本机自签证书
+    let client = reqwest::blocking::Client::builder()
+        .timeout(std::time::Duration::from_secs(3))
+        .danger_accept_invalid_certs(true)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let status = client
+        .get(&url)
+        .bearer_auth(token)
+        .send()
+        .map_err(|e| e.to_string())
+        .and_then(|r| r.error_for_status().map_err(|e| e.to_string()))
+        .and_then(|r| r.json::<serde_json::Value>().map_err(|e| e.to_string()));
+    match status {
+        Ok(status) => {
+            if json {
+                print_json(&json!({ "reachable": true, "status": status }));
+            } else {
+                let running = status.get("running").and_then(|v| v.as_bool()).unwrap_or(false);
+                println!(
+                    "Proxy {} on port {} ({} active accounts)",
+                    if running { "running" } else { "stopped (admin server up)" },
+                    config.port,
+                    status.get("active_accounts").and_then(|v| v.as_u64()).unwrap_or(0)
+                );
+            }
+            Ok(0)
+        }
+        Err(e) => {
+            if json {
+                print_json(&json!({ "reachable": false, "port": config.port, "error": e }));
+            } else {
+                println!("Proxy not reachable on port {}: {}", config.port, e);
+            }
+            Ok(1)
+        }
+    }
+}
+
+fn keys_list(json: bool) -> Result<i32, String> {
+    let _ = crate::modules::usage::init_db();
+    let keys = VirtualKeyStore::global().list();
+    if json {
+        print_json(&keys);
+        return Ok(0);
+    }
+    println!("{:<36}  {:<20}  {:<14}  {:>12}  {:>12}  STATUS", "ID", "NAME", "KEY", "TOKENS", "QUOTA");
+    for key in keys {
+        let status = if key.info.revoked {
+            "revoked"
+        } else if key.info.expires_at.is_some_and(|t| t <= chrono::Utc::now().timestamp()) {
+            "expired"
+        } else {
+            "active"
+        };
+        println!(
+            "{:<36}  {:<20}  {:<14}  {:>12}  {:>12}  {}",
+            key.info.id,
+            key.info.name,
+            key.info.key_hint,
+            key.used_tokens,
+            key.info.token_quota.map_or("-".to_string(), |q| q.to_string()),
+            status
+        );
+    }
+    Ok(0)
+}
+
+fn keys_create(args: &HashMap<String, String>, json: bool) -> Result<i32, String> {
+    let request = create_request(args, chrono::Utc::now().timestamp())?;
+    let created = VirtualKeyStore::global().create(request)?;
+    audit(AuditEvent {
+        action: "virtual_key.create".to_string(),
+        target: created.info.id.clone(),
+        detail: Some(created.info.audit_detail()),
+        ..Default::default()
+    });
+    if json {
+        print_json(&created);
+    } else {
+        println!("Created key '{}' ({})", created.info.name, created.info.id);
+        println!("{}", created.key);
+        eprintln!("The key is shown only once; store it now.");
+    }
+    Ok(0)
+}
+
+fn audit(event: AuditEvent) {
+    let _ = crate::modules::audit::init_db();
+    crate::modules::audit::record(AuditEvent { actor: "cli".to_string(), source: "cli", ..event });
+}
+
+fn usage(since: &str, group: UsageExportGroup, json: bool) -> Result<i32, String> {
+    let since = parse_time(since, chrono::Utc::now().timestamp(), false)?;
+    crate::modules::usage::init_db()?;
+    let rows = crate::modules::usage::get_usage_since(group, since)?;
+    if json {
+        print_json(&rows);
+        return Ok(0);
+    }
+    println!("{:<32}  {:>9}  {:>7}  {:>14}  {:>8}  {:>10}", "GROUP", "REQUESTS", "ERRORS", "TOKENS", "TOK/S", "COST");
+    for row in rows {
+        let rate = row.avg_tokens_per_second.map(|t| format!("{:.1}", t)).unwrap_or_else(|| "-".to_string());
+        println!(
+            "{:<32}  {:>9}  {:>7}  {:>14}  {:>8}  {:>10.4}",
+            row.label, row.request_count, row.error_count, row.total_tokens, rate, row.cost_usd
+        );
+    }
+    Ok(0)
+}
+
+fn config_validate(file: Option<&str>, json: bool) -> Result<i32, String> {
+    let (path, parsed) = match file {
+        Some(path) => (
+            path.to_string(),
+            std::fs::read_to_string(path)
+                .map_err(|e| format!("failed_to_read_config_file: {}", e))
+                .and_then(|c| serde_json::from_str::<crate::models::AppConfig>(&c).map_err(|e| e.to_string())),
+        ),
+        None => (
+            crate::modules::config::config_path()?.display().to_string(),
+            crate::modules::config::load_app_config(),
+        ),
+    };
+    let issues = parsed.as_ref().map(|config| config_validation::check(&config.proxy)).unwrap_or_default();
+    let valid = parsed.is_ok() && !issues.iter().any(|i| i.severity == IssueSeverity::Error);
+    if json {
+        print_json(&json!({ "file": path, "valid": valid, "error": parsed.as_ref().err(), "issues": issues }));
+    } else {
+        if let Err(e) = &parsed {
+            println!("{}: {}", path, e);
+        }
+        for issue in &issues {
+            let level = if issue.severity == IssueSeverity::Error { "error" } else { "warning" };
+            println!("{}: {}: {}", path, level, issue);
+        }
+        if valid {
+            println!("{}: OK", path);
+        }
+    }
+    Ok(if valid { 0 } else { 1 })
+}
+
+fn profile_list(json: bool) -> Result<i32, String> {
+    let profiles = crate::modules::profiles::summaries(&crate::modules::config::load_app_config()?);
+    if json {
+        print_json(&profiles);
+        return Ok(0);
+    }
+    if profiles.is_empty() {
+        println!("No profiles configured");
+    }
+    for profile in profiles {
+        let services = profile.services.map_or("-".to_string(), |s| s.join(","));
+        println!(
+            "{} {:<20}  {:<40}  services: {}",
+            if profile.active { "*" } else { " " },
+            profile.name,
+            profile.sections.join(","),
+            services
+        );
+    }
+    Ok(0)
+}
+
+fn profile_use(name: &str) -> Result<i32, String> {
+    let config = crate::modules::config::load_app_config()?;
+    let next = crate::modules::profiles::select(&config, Some(name))?;
+    crate::modules::config::save_app_config(&next)?;
+    audit(AuditEvent { action: "profile.switch".to_string(), target: name.to_string(), ..Default::default() });
+    match &next.active_profile {
+        Some(name) => println!("Switched to profile {}", name),
+        None => println!("Switched to the default configuration"),
+    }
+    Ok(0)
+}
+
+/// Windows 发布版没有控制台, 命令行调用时附加到父进程的控制台以显示输出
+pub fn attach_console() {
+    #[cfg(windows)]
+    unsafe {
+        use windows_sys::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+/// 执行子命令, 返回进程退出码
+pub fn run(command: Command) -> i32 {
+    let result = match command {
+        Command::Serve => Ok(0),
+        Command::Help => {
+            print!("{}", USAGE);
+            Ok(0)
+        }
+        Command::Status { json } => status(json),
+        Command::KeysList { json } => keys_list(json),
+        Command::KeysCreate { args, json } => keys_create(&args, json),
+        Command::KeysRevoke { id } => VirtualKeyStore::global().revoke(&id).map(|()| {
+            audit(AuditEvent { action: "virtual_key.revoke".to_string(), target: id.clone(), ..Default::default() });
+            println!("Revoked key {}", id);
+            0
+        }),
+        Command::Usage { since, group, json } => usage(&since, group, json),
+        Command::ConfigValidate { file, json } => config_validate(file.as_deref(), json),
+        Command::ProfileList { json } => profile_list(json),
+        Command::ProfileUse { name } => profile_use(&name),
+    };
+    result.unwrap_or_else(|e| {
+        eprintln!("Error: {}", e);
+        1
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        std::iter::once("aiolauncher").chain(line.split_whitespace()).map(str::to_string).collect()
+    }
+
+    #[test]
+    fn test_parse_commands() {
+        assert!(parse(&args("")).is_none());
+        assert!(parse(&args("--headless")).is_none());
+        assert_eq!(parse(&args("serve --data-dir /srv/aio")).unwrap(), Ok(Command::Serve));
+        assert_eq!(
+            parse(&args("usage --since 30d --by model --json")).unwrap(),
+            Ok(Command::Usage { since: "30d".to_string(), group: UsageExportGroup::Model, json: true })
+        );
+        let Ok(Command::KeysCreate { args: values, json }) = parse(&args("keys create --limit=1000000 --name ci")).unwrap()
+        else {
+            panic!("expected keys create");
+        };
+        assert!(!json);
+        assert_eq!(values.get("limit").map(String::as_str), Some("1000000"));
+        assert!(parse(&args("keys create --limit")).unwrap().is_err());
+        assert!(parse(&args("keys rotate")).unwrap().is_err());
+        assert!(parse(&args("status --verbose")).unwrap().is_err());
+        assert_eq!(parse(&args("profile use demo")).unwrap(), Ok(Command::ProfileUse { name: "demo".to_string() }));
+        assert!(parse(&args("profile use")).unwrap().is_err());
+    }
+
+    #[test]
+    fn test_create_request_and_times() {
+        let now = 1_700_000_000;
+        assert_eq!(parse_time("7d", now, false).unwrap(), now - 7 * 86_400);
+        assert_eq!(parse_time("2h", now, true).unwrap(), now + 7200);
+        assert_eq!(parse_time("2024-01-01", now, false).unwrap(), 1_704_067_200);
+        assert!(parse_time("soon", now, true).is_err());
+
+        let values: HashMap<String, String> = [("limit", "1_000_000"), ("period", "daily"), ("models", "gemini-*, claude-*"), ("expires", "1d")]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        let request = create_request(&values, now).unwrap();
+        assert_eq!(request.token_quota, Some(1_000_000));
+        assert_eq!(request.quota_period, QuotaPeriod::Daily);
+        assert_eq!(request.allowed_models, vec!["gemini-*", "claude-*"]);
+        assert_eq!(request.expires_at, Some(now + 86_400));
+        assert_eq!(request.name, "cli");
+    }
+}
@@ -1,43 +1,50 @@
 use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
 use serde_json;
 
 use crate::models::AppConfig;
+use crate::proxy::config::SecretStorageMode;
 use super::account::get_data_dir;
 
 const CONFIG_FILE: &str = "gui_config.json";
 
-/// Load application configuration
-pub fn load_app_config() -> Result<AppConfig, String> {
-    let data_dir = get_data_dir()?;
-    let config_path = data_dir.join(CONFIG_FILE);
-    
-    if !config_path.exists() {
-        return Ok(AppConfig::new());
-    }
-    
-    let content = fs::read_to_string(&config_path)
-        .map_err(|e| format!("failed_to_read_config_file: {}", e))?;
-    
-    let mut v: serde_json::Value = serde_json::from_str(&content)
-        .map_err(|e| format!("failed_to_parse_config_file: {}", e))?;
-    
-    let mut modified = false;
+/// 最近一次由本进程写入的配置内容哈希, 供配置文件监听跳过自身写入
+static LAST_SAVED_HASH: AtomicU64 = AtomicU64::new(0);
+
+/// Path of the configuration file
+pub fn config_path() -> Result<PathBuf, String> {
+    Ok(get_data_dir()?.join(CONFIG_FILE))
+}
 
-    // Migration logic
+/// Hash of the configuration file content
+pub fn content_hash(content: &str) -> u64 {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Hash of the content last written by `save_app_config`
+pub fn last_saved_hash() -> u64 {
+    LAST_SAVED_HASH.load(Ordering::Relaxed)
+}
+
+/// Migrate fields from older config formats; returns whether anything changed
+pub fn migrate(v: &mut serde_json::Value) -> bool {
+    let mut modified = false;
     if let Some(proxy) = v.get_mut("proxy") {
         let mut custom_mapping = proxy.get("custom_mapping")
             .and_then(|m| m.as_object())
-            .map(|m| m.clone())
+            .cloned()
             .unwrap_or_default();
 
         // Migrate Anthropic mapping
         if let Some(anthropic) = proxy.get_mut("anthropic_mapping").and_then(|m| m.as_object_mut()) {
             for (k, v) in anthropic.iter() {
                 // Only move non-series fields, as series fields are now handled by Preset logic or builtin tables
-                if !k.ends_with("-series") {
-                    if !custom_mapping.contains_key(k) {
-                        custom_mapping.insert(k.clone(), v.clone());
-                    }
+                if !k.ends_with("-series") && !custom_mapping.contains_key(k) {
+                    custom_mapping.insert(k.clone(), v.clone());
                 }
             }
             // Remove old field
@@ -48,10 +55,8 @@ pub fn load_app_config() -> Result<AppConfig, String> {
         // Migrate OpenAI mapping
         if let Some(openai) = proxy.get_mut("openai_mapping").and_then(|m| m.as_object_mut()) {
             for (k, v) in openai.iter() {
-                if !k.ends_with("-series") {
-                    if !custom_mapping.contains_key(k) {
-                        custom_mapping.insert(k.clone(), v.clone());
-                    }
+                if !k.ends_with("-series") && !custom_mapping.contains_key(k) {
+                    custom_mapping.insert(k.clone(), v.clone());
                 }
             }
             // Remove old field
@@ -63,9 +68,42 @@ pub fn load_app_config() -> Result<AppConfig, String> {
             proxy.as_object_mut().unwrap().insert("custom_mapping".to_string(), serde_json::Value::Object(custom_mapping));
         }
     }
+    modified
+}
+
+/// Load application configuration
+pub fn load_app_config() -> Result<AppConfig, String> {
+    let data_dir = get_data_dir()?;
+    let config_path = data_dir.join(CONFIG_FILE);
+    
+    if !config_path.exists() {
+        return Ok(AppConfig::new());
+    }
+    
+    let content = fs::read_to_string(&config_path)
+        .map_err(|e| format!("failed_to_read_config_file: {}", e))?;
+    
+    let mut v: serde_json::Value = serde_json::from_str(&content)
+        .map_err(|e| format!("failed_to_parse_config_file: {}", e))?;
+    
+    let mut modified = migrate(&mut v);
+
+    // 解密上游密钥; 仍为明文的密钥在下面保存时迁移到加密存储。
+    // 无法解密的字段保留 `secret://` 引用 (对应上游按无效 Key 处理), 保存配置时不会丢失密文
+    let has_plaintext_secrets = match v.get_mut("proxy").map(super::secrets::resolve) {
+        Some(Ok(has_plaintext)) => has_plaintext,
+        Some(Err(e)) => {
+            tracing::error!("[Config] {}", e);
+            false
+        }
+        None => false,
+    };
 
     let config: AppConfig = serde_json::from_value(v)
         .map_err(|e| format!("failed_to_convert_config_after_migration: {}", e))?;
+    if has_plaintext_secrets && config.proxy.secret_storage != SecretStorageMode::Plaintext {
+        modified = true;
+    }
     
     // If migration occurred, auto-save once to clean up the file
     if modified {
@@ -80,9 +118,21 @@ pub fn save_app_config(config: &AppConfig) -> Result<(), String> {
     let data_dir = get_data_dir()?;
     let config_path = data_dir.join(CONFIG_FILE);
     
-    let content = serde_json::to_string_pretty(config)
+    let mut value = serde_json::to_value(config)
+        .map_err(|e| format!("failed_to_serialize_config: {}", e))?;
+    if config.proxy.secret_storage != SecretStorageMode::Plaintext {
+        if let Some(proxy) = value.get_mut("proxy") {
+            super::secrets::externalize(proxy, config.proxy.secret_storage)?;
+        }
+    }
+    let content = serde_json::to_string_pretty(&value)
         .map_err(|e| format!("failed_to_serialize_config: {}", e))?;
     
-    fs::write(&config_path, content)
+    LAST_SAVED_HASH.store(content_hash(&content), Ordering::Relaxed);
+    // 先写临时文件再替换, 避免中断时留下半个配置文件
+    let tmp_path = config_path.with_extension("json.tmp");
+    fs::write(&tmp_path, content)
+        .map_err(|e| format!("failed_to_save_config: {}", e))?;
+    fs::rename(&tmp_path, &config_path)
         .map_err(|e| format!("failed_to_save_config: {}", e))
 }
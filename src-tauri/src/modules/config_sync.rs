@@ -0,0 +1,574 @@
+// 配置云同步 (可选)
+// 配置 (含上游密钥, 不含同步设置本身) 在本地用口令派生的密钥加密 (PBKDF2-SHA256 + AES-256-GCM) 后,
+// 上传到用户自己的 WebDAV、S3 兼容存储或私有 Gist; 远端只保存密文。同步时比较远端更新时间与本机配置的哈希,
+// 只有一方变化时自动上传或下载, 两边都变化时视为冲突, 由用户选择保留哪一边。
+// 上传使用条件请求 (ETag / If-Match), 期间远端被其他设备更新时同样报告冲突, 不会覆盖对方。
+// 端口、监听地址、证书与本地程序路径等与本机环境相关的配置不参与同步, 下载时保留本机的值。
+
+use std::num::NonZeroU32;
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use rand::RngCore;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::warn;
+
+use crate::models::AppConfig;
+use crate::modules::config_transfer::{self, ImportReport};
+use crate::proxy::config::{ConfigSyncBackend, ConfigSyncConfig};
+
+/// 前端监听该事件刷新同步状态 (包括冲突)
+pub const SYNC_EVENT: &str = "config-sync://synced";
+const FORMAT: &str = "aiolauncher-sync";
+const STATE_FILE: &str = "config_sync_state.json";
+const GIST_FILE: &str = "aiolauncher-config.sync";
+const PBKDF2_ITERATIONS: u32 = 210_000;
+const SALT_LEN: usize = 16;
+/// 不参与同步的配置 (JSON Pointer)
+const LOCAL_FIELDS: &[&str] = &[
+    "/default_export_path",
+    "/proxy/config_sync",
+    "/proxy/port",
+    "/proxy/port_fallback",
+    "/proxy/listeners",
+    "/proxy/listener_tls",
+    "/proxy/provider_tls",
+    "/proxy/llama_server",
+];
+
+/// 远端保存的密文; 更新时间与设备名作为附加数据参与认证
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncEnvelope {
+    pub format: String,
+    pub version: u32,
+    pub updated_at: i64,
+    pub device: String,
+    pub iterations: u32,
+    pub salt: String,
+    pub nonce: String,
+    pub ciphertext: String,
+}
+
+/// 本机的同步记录
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SyncState {
+    /// 上次同步时远端的更新时间
+    pub remote_updated_at: Option<i64>,
+    /// 上次同步时本机配置的哈希
+    pub local_hash: Option<String>,
+    pub last_sync_at: Option<i64>,
+    pub last_device: Option<String>,
+    pub last_error: Option<String>,
+    /// 首次上传时创建的 Gist
+    pub gist_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncAction {
+    UpToDate,
+    Push,
+    Pull,
+    Conflict,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncReport {
+    pub action: SyncAction,
+    pub remote_updated_at: Option<i64>,
+    pub remote_device: Option<String>,
+    /// 下载的配置中缺少、需要在本机填写的密钥
+    pub missing_secrets: Vec<String>,
+}
+
+/// 同步结果; 下载时附带需要保存的配置
+pub struct SyncOutcome {
+    pub report: SyncReport,
+    pub pulled: Option<AppConfig>,
+}
+
+fn derive_key(passphrase: &str, salt: &[u8], iterations: u32) -> Result<[u8; 32], String> {
+    let iterations = NonZeroU32::new(iterations).ok_or("Invalid iteration count")?;
+    let mut key = [0u8; 32];
+    ring::pbkdf2::derive(ring::pbkdf2::PBKDF2_HMAC_SHA256, iterations, salt, passphrase.as_bytes(), &mut key);
+    Ok(key)
+}
+
+fn cipher(key: &[u8; 32]) -> Result<LessSafeKey, String> {
+    UnboundKey::new(&AES_256_GCM, key).map(LessSafeKey::new).map_err(|_| "Invalid sync key".to_string())
+}
+
+fn aad(updated_at: i64, device: &str) -> Vec<u8> {
+    format!("{}:{}:{}", FORMAT, updated_at, device).into_bytes()
+}
+
+pub fn encrypt(passphrase: &str, plaintext: &[u8], updated_at: i64, device: &str) -> Result<SyncEnvelope, String> {
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::rngs::OsRng.fill_bytes(&mut salt);
+    rand::rngs::OsRng.fill_bytes(&mut nonce);
+    let key = derive_key(passphrase, &salt, PBKDF2_ITERATIONS)?;
+    let mut data = plaintext.to_vec();
+    cipher(&key)?
+        .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::from(aad(updated_at, device)), &mut data)
+        .map_err(|_| "Failed to encrypt config".to_string())?;
+    Ok(SyncEnvelope {
+        format: FORMAT.to_string(),
+        version: 1,
+        updated_at,
+        device: device.to_string(),
+        iterations: PBKDF2_ITERATIONS,
+        salt: STANDARD.encode(salt),
+        nonce: STANDARD.encode(nonce),
+        ciphertext: STANDARD.encode(data),
+    })
+}
+
+pub fn decrypt(passphrase: &str, envelope: &SyncEnvelope) -> Result<Vec<u8>, String> {
+    if envelope.format != FORMAT || envelope.version != 1 {
+        return Err("Unsupported sync file format".to_string());
+    }
+    let decode = |v: &str| STANDARD.decode(v).map_err(|_| "Sync file is malformed".to_string());
+    let salt = decode(&envelope.salt)?;
+    let nonce: [u8; NONCE_LEN] = decode(&envelope.nonce)?.try_into().map_err(|_| "Sync file is malformed")?;
+    let mut data = decode(&envelope.ciphertext)?;
+    let key = derive_key(passphrase, &salt, envelope.iterations)?;
+    let plain = cipher(&key)?
+        .open_in_place(Nonce::assume_unique_for_key(nonce), Aad::from(aad(envelope.updated_at, &envelope.device)), &mut data)
+        .map_err(|_| "Failed to decrypt the synced config (wrong passphrase?)".to_string())?;
+    Ok(plain.to_vec())
+}
+
+/// 上一次同步以来哪一边发生了变化
+pub fn decide(state: &SyncState, remote_updated_at: Option<i64>, local_hash: &str) -> SyncAction {
+    let local_changed = state.local_hash.as_deref() != Some(local_hash);
+    let remote_changed = remote_updated_at.is_some() && remote_updated_at != state.remote_updated_at;
+    match (remote_changed, local_changed) {
+        (false, false) => SyncAction::UpToDate,
+        (false, true) => SyncAction::Push,
+        (true, false) => SyncAction::Pull,
+        (true, true) => SyncAction::Conflict,
+    }
+}
+
+fn split_pointer(pointer: &str) -> (&str, &str) {
+    pointer.rsplit_once('/').unwrap_or(("", pointer))
+}
+
+fn remove_local_fields(config: &mut serde_json::Value) {
+    for pointer in LOCAL_FIELDS {
+        let (parent, field) = split_pointer(pointer);
+        if let Some(parent) = config.pointer_mut(parent).and_then(|p| p.as_object_mut()) {
+            parent.remove(field);
+        }
+    }
+}
+
+/// 下载的配置使用本机的端口、监听与路径
+fn keep_local_fields(local: &serde_json::Value, config: &mut serde_json::Value) {
+    for pointer in LOCAL_FIELDS {
+        let (parent, field) = split_pointer(pointer);
+        let (Some(value), Some(parent)) = (local.pointer(pointer), config.pointer_mut(parent).and_then(|p| p.as_object_mut()))
+        else {
+            continue;
+        };
+        parent.insert(field.to_string(), value.clone());
+    }
+}
+
+/// 参与同步的内容: 完整配置 (含密钥), 去掉同步设置本身与本机相关的配置
+fn payload(config: &AppConfig) -> Result<(Vec<u8>, String), String> {
+    let mut bundle = config_transfer::build_bundle(config, true)?;
+    remove_local_fields(&mut bundle.config);
+    let hash = hex(&Sha256::digest(serde_json::to_string(&bundle.config).map_err(|e| e.to_string())?.as_bytes()));
+    let content = serde_json::to_vec(&bundle).map_err(|e| e.to_string())?;
+    Ok((content, hash))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn state_path() -> Result<std::path::PathBuf, String> {
+    Ok(crate::modules::account::get_data_dir()?.join(STATE_FILE))
+}
+
+pub fn load_state() -> SyncState {
+    state_path()
+        .ok()
+        .and_then(|p| std::fs::read_to_string(p).ok())
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+fn save_state(state: &SyncState) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(state).map_err(|e| e.to_string())?;
+    std::fs::write(state_path()?, content).map_err(|e| format!("Failed to save sync state: {}", e))
+}
+
+fn device_name() -> String {
+    sysinfo::System::host_name().unwrap_or_else(|| "unknown".to_string())
+}
+
+// ===== 存储后端 =====
+
+fn client() -> Result<reqwest::Client, String> {
+    reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(30))
+        .user_agent(concat!("aiolauncher/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .map_err(|e| e.to_string())
+}
+
+fn gist_id(config: &ConfigSyncConfig, state: &SyncState) -> Option<String> {
+    Some(config.gist_id.trim().to_string()).filter(|id| !id.is_empty()).or_else(|| state.gist_id.clone())
+}
+
+fn s3_url(config: &ConfigSyncConfig) -> Result<url::Url, String> {
+    let key = config.object_key.trim_start_matches('/');
+    url::Url::parse(&format!("{}/{}/{}", config.url.trim_end_matches('/'), config.bucket, key))
+        .map_err(|e| format!("Invalid S3 endpoint: {}", e))
+}
+
+fn s3_headers(config: &ConfigSyncConfig, method: &str, url: &url::Url, body: &[u8]) -> Vec<(String, String)> {
+    use crate::proxy::common::sigv4::{AwsCredentials, AwsSigner};
+    let credentials = AwsCredentials {
+        access_key_id: config.access_key_id.clone(),
+        secret_access_key: config.secret_access_key.clone(),
+        session_token: None,
+    };
+    let region = if config.region.is_empty() { "us-east-1" } else { config.region.as_str() };
+    let content_hash = hex(&Sha256::digest(body));
+    let signer = AwsSigner { credentials: &credentials, region, service: "s3" };
+    let mut headers = signer.sign(method, url, &[("x-amz-content-sha256", &content_hash)], body, chrono::Utc::now());
+    headers.push(("x-amz-content-sha256".to_string(), content_hash));
+    headers
+}
+
+/// 远端当前的内容; WebDAV / S3 同时返回 ETag, 用于条件上传
+struct Remote {
+    content: String,
+    etag: Option<String>,
+}
+
+enum Uploaded {
+    /// 新建 Gist 时返回其 id
+    Done(Option<String>),
+    /// 下载之后远端已被其他设备更新
+    RemoteChanged,
+}
+
+/// 条件上传的请求头: 远端为空时要求仍不存在, 否则要求 ETag 未变
+fn precondition(remote: Option<&Remote>) -> Option<(&'static str, String)> {
+    match remote {
+        None => Some(("If-None-Match", "*".to_string())),
+        Some(Remote { etag: Some(etag), .. }) => Some(("If-Match", etag.clone())),
+        Some(_) => None,
+    }
+}
+
+fn check(response: reqwest::Response, what: &str) -> Result<reqwest::Response, String> {
+    let status = response.status();
+    if status.is_success() {
+        Ok(response)
+    } else {
+        Err(format!("{} failed: HTTP {}", what, status))
+    }
+}
+
+/// 下载远端内容; 不存在时返回 None
+async fn fetch(config: &ConfigSyncConfig, state: &SyncState) -> Result<Option<Remote>, String> {
+    let client = client()?;
+    let request = match config.backend {
+        ConfigSyncBackend::WebDav => client.get(&config.url).basic_auth(&config.username, Some(&config.password)),
+        ConfigSyncBackend::S3 => {
+            let url = s3_url(config)?;
+            let headers = s3_headers(config, "GET", &url, b"");
+            headers.into_iter().fold(client.get(url), |r, (k, v)| r.header(k, v))
+        }
+        ConfigSyncBackend::Gist => {
+            let Some(id) = gist_id(config, state) else {
+                return Ok(None);
+            };
+            let gist: serde_json::Value = check(
+                client
+                    .get(format!("https://api.github.com/gists/{}", id))
+                    .bearer_auth(&config.token)
+                    .header("Accept", "application/vnd.github+json")
+                    .send()
+                    .await
+                    .map_err(|e| e.to_string())?,
+                "Gist download",
+            )?
+            .json()
+            .await
+            .map_err(|e| e.to_string())?;
+            let Some(file) = gist.get("files").and_then(|f| f.get(GIST_FILE)) else {
+                return Ok(None);
+            };
+            // 超过 1 MB 的内容需要从 raw_url 读取
+            if file.get("truncated").and_then(|t| t.as_bool()) != Some(true) {
+                let content = file.get("content").and_then(|c| c.as_str()).map(str::to_string);
+                return Ok(content.map(|content| Remote { content, etag: None }));
+            }
+            let raw = file.get("raw_url").and_then(|u| u.as_str()).ok_or("Gist file has no raw_url")?;
+            client.get(raw).bearer_auth(&config.token)
+        }
+    };
+    let response = request.send().await.map_err(|e| e.to_string())?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    let etag = (config.backend != ConfigSyncBackend::Gist)
+        .then(|| response.headers().get(reqwest::header::ETAG).and_then(|v| v.to_str().ok()).map(str::to_string))
+        .flatten();
+    let content = check(response, "Download")?.text().await.map_err(|e| e.to_string())?;
+    Ok(Some(Remote { content, etag }))
+}
+
+/// 上传内容; `remote` 为本次同步下载到的远端内容
+async fn upload(
+    config: &ConfigSyncConfig,
+    state: &SyncState,
+    remote: Option<&Remote>,
+    content: String,
+) -> Result<Uploaded, String> {
+    // 没有 ETag 时 (Gist 或不返回 ETag 的服务) 上传前重新读取比对, 缩小覆盖其他设备更新的窗口
+    if let Some(remote) = remote.filter(|r| r.etag.is_none()) {
+        if fetch(config, state).await?.map(|r| r.content).as_deref() != Some(remote.content.as_str()) {
+            return Ok(Uploaded::RemoteChanged);
+        }
+    }
+    let client = client()?;
+    let request = match config.backend {
+        ConfigSyncBackend::WebDav => client.put(&config.url).basic_auth(&config.username, Some(&config.password)),
+        ConfigSyncBackend::S3 => {
+            let url = s3_url(config)?;
+            let headers = s3_headers(config, "PUT", &url, content.as_bytes());
+            headers.into_iter().fold(client.put(url), |r, (k, v)| r.header(k, v))
+        }
+        ConfigSyncBackend::Gist => {
+            let existing = gist_id(config, state);
+            let body = serde_json::json!({
+                "description": "AIOLauncher config (encrypted)",
+                "public": false,
+                "files": { GIST_FILE: { "content": content } },
+            });
+            let request = match &existing {
+                Some(id) => client.patch(format!("https://api.github.com/gists/{}", id)),
+                None => client.post("https://api.github.com/gists"),
+            };
+            let response = request
+                .bearer_auth(&config.token)
+                .header("Accept", "application/vnd.github+json")
+                .json(&body)
+                .send()
+                .await
+                .map_err(|e| e.to_string())?;
+            let created: serde_json::Value = check(response, "Gist upload")?.json().await.map_err(|e| e.to_string())?;
+            let id = existing.is_none().then(|| created.get("id").and_then(|v| v.as_str()).map(str::to_string));
+            return Ok(Uploaded::Done(id.flatten()));
+        }
+    };
+    let request = match precondition(remote) {
+        Some((name, value)) => request.header(name, value),
+        None => request,
+    };
+    let response = request.body(content).send().await.map_err(|e| e.to_string())?;
+    if response.status() == reqwest::StatusCode::PRECONDITION_FAILED {
+        return Ok(Uploaded::RemoteChanged);
+    }
+    check(response, "Upload")?;
+    Ok(Uploaded::Done(None))
+}
+
+pub fn validate(config: &ConfigSyncConfig) -> Result<(), String> {
+    if !config.enabled {
+        return Err("Config sync is disabled".to_string());
+    }
+    if config.passphrase.chars().count() < 8 {
+        return Err("The sync passphrase must be at least 8 characters".to_string());
+    }
+    let missing = match config.backend {
+        ConfigSyncBackend::WebDav if config.url.trim().is_empty() => "url",
+        ConfigSyncBackend::S3 if config.url.trim().is_empty() || config.bucket.trim().is_empty() => "url / bucket",
+        ConfigSyncBackend::S3 if config.access_key_id.is_empty() || config.secret_access_key.is_empty() => "access keys",
+        ConfigSyncBackend::Gist if config.token.is_empty() => "token",
+        _ => return Ok(()),
+    };
+    Err(format!("Config sync is missing {}", missing))
+}
+
+/// 同步一次; `force` 指定方向时忽略冲突检测
+pub async fn sync(config: &AppConfig, force: Option<SyncAction>) -> Result<SyncOutcome, String> {
+    let settings = &config.proxy.config_sync;
+    validate(settings)?;
+    let mut state = load_state();
+    let result = sync_with_state(config, settings, &mut state, force).await;
+    state.last_error = result.as_ref().err().cloned();
+    save_state(&state)?;
+    result
+}
+
+async fn sync_with_state(
+    config: &AppConfig,
+    settings: &ConfigSyncConfig,
+    state: &mut SyncState,
+    force: Option<SyncAction>,
+) -> Result<SyncOutcome, String> {
+    let fetched = fetch(settings, state).await?;
+    let remote = match &fetched {
+        Some(r) => Some(serde_json::from_str::<SyncEnvelope>(&r.content).map_err(|_| "Remote sync file is malformed")?),
+        None => None,
+    };
+    let (content, local_hash) = payload(config)?;
+    let remote_updated_at = remote.as_ref().map(|r| r.updated_at);
+    let action = match force {
+        Some(action) => action,
+        None => decide(state, remote_updated_at, &local_hash),
+    };
+    let mut report = SyncReport {
+        action,
+        remote_updated_at,
+        remote_device: remote.as_ref().map(|r| r.device.clone()),
+        missing_secrets: Vec::new(),
+    };
+    let mut pulled = None;
+    match action {
+        SyncAction::UpToDate | SyncAction::Conflict => {}
+        SyncAction::Push => {
+            let now = chrono::Utc::now().timestamp_millis();
+            let device = device_name();
+            let envelope = encrypt(&settings.passphrase, &content, now, &device)?;
+            let body = serde_json::to_string(&envelope).map_err(|e| e.to_string())?;
+            match upload(settings, state, fetched.as_ref(), body).await? {
+                Uploaded::Done(Some(id)) => state.gist_id = Some(id),
+                Uploaded::Done(None) => {}
+                Uploaded::RemoteChanged => {
+                    warn!("[ConfigSync] Remote config changed during upload; not overwriting it");
+                    report.action = SyncAction::Conflict;
+                    return Ok(SyncOutcome { report, pulled: None });
+                }
+            }
+            state.remote_updated_at = Some(now);
+            state.local_hash = Some(local_hash);
+            state.last_device = Some(device.clone());
+            report.remote_updated_at = Some(now);
+            report.remote_device = Some(device);
+        }
+        SyncAction::Pull => {
+            let remote = remote.ok_or("Nothing has been synced yet")?;
+            let plain = decrypt(&settings.passphrase, &remote)?;
+            let (mut bundle, from_version) = config_transfer::parse_bundle(&String::from_utf8_lossy(&plain))?;
+            keep_local_fields(&serde_json::to_value(config).map_err(|e| e.to_string())?, &mut bundle.config);
+            let (mut next, ImportReport { missing_secrets, .. }) = config_transfer::restore(bundle, from_version, config)?;
+            next.proxy.config_sync = settings.clone();
+            state.remote_updated_at = Some(remote.updated_at);
+            state.local_hash = Some(payload(&next)?.1);
+            state.last_device = Some(remote.device);
+            report.missing_secrets = missing_secrets;
+            pulled = Some(next);
+        }
+    }
+    if action != SyncAction::Conflict {
+        state.last_sync_at = Some(chrono::Utc::now().timestamp());
+    }
+    Ok(SyncOutcome { report, pulled })
+}
+
+/// 同步并保存下载的配置 (与保存配置相同的校验与热更新), 结果通过事件推送给前端
+pub async fn sync_and_apply(app: &tauri::AppHandle, force: Option<SyncAction>) -> Result<SyncReport, String> {
+    use tauri::{Emitter, Manager};
+    let outcome = sync(&crate::modules::config::load_app_config()?, force).await?;
+    if let Some(next) = outcome.pulled {
+        crate::commands::save_config(app.clone(), app.state(), next).await?;
+    }
+    let _ = app.emit(SYNC_EVENT, &outcome.report);
+    Ok(outcome.report)
+}
+
+/// 按 `interval_minutes` 定期同步; 冲突时只通知, 不覆盖任何一边
+pub fn start_auto_sync(app: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut last_run: Option<std::time::Instant> = None;
+        loop {
+            tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+            let Ok(config) = crate::modules::config::load_app_config() else {
+                continue;
+            };
+            let settings = &config.proxy.config_sync;
+            let interval = std::time::Duration::from_secs(settings.interval_minutes * 60);
+            if !settings.enabled || settings.interval_minutes == 0 || last_run.is_some_and(|t| t.elapsed() < interval) {
+                continue;
+            }
+            last_run = Some(std::time::Instant::now());
+            match sync_and_apply(&app, None).await {
+                Ok(report) if report.action == SyncAction::Conflict => {
+                    warn!("[ConfigSync] Local and remote config both changed; choose which one to keep");
+                }
+                Ok(_) => {}
+                Err(e) => warn!("[ConfigSync] Sync failed: {}", e),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_roundtrip() {
+        let envelope = encrypt("correct horse", b"{\"proxy\":{}}", 1_700_000_000_000, "laptop").unwrap();
+        assert_eq!(decrypt("correct horse", &envelope).unwrap(), b"{\"proxy\":{}}");
+        assert!(decrypt("wrong passphrase", &envelope).is_err());
+        // 元数据被篡改时解密失败
+        let tampered = SyncEnvelope { device: "desktop".to_string(), ..envelope };
+        assert!(decrypt("correct horse", &tampered).is_err());
+    }
+
+    #[test]
+    fn test_decide() {
+        let state = SyncState { remote_updated_at: Some(10), local_hash: Some("a".to_string()), ..Default::default() };
+        assert_eq!(decide(&state, Some(10), "a"), SyncAction::UpToDate);
+        assert_eq!(decide(&state, Some(10), "b"), SyncAction::Push);
+        assert_eq!(decide(&state, Some(20), "a"), SyncAction::Pull);
+        assert_eq!(decide(&state, Some(20), "b"), SyncAction::Conflict);
+        // 远端为空时上传
+        assert_eq!(decide(&SyncState::default(), None, "a"), SyncAction::Push);
+        assert_eq!(decide(&SyncState::default(), Some(5), "a"), SyncAction::Conflict);
+    }
+
+    #[test]
+    fn test_precondition() {
+        assert_eq!(precondition(None), Some(("If-None-Match", "*".to_string())));
+        let remote = Remote { content: String::new(), etag: Some("\"abc\"".to_string()) };
+        assert_eq!(precondition(Some(&remote)), Some(("If-Match", "\"abc\"".to_string())));
+        assert_eq!(precondition(Some(&Remote { etag: None, ..remote })), None);
+    }
+
+    #[test]
+    fn test_local_fields_are_not_synced() {
+        let mut config = AppConfig::new();
+        config.proxy.port = 9100;
+        config.proxy.listener_tls.cert_path = "/home/a/cert.pem".to_string();
+        let (content, hash) = payload(&config).unwrap();
+        let bundle: serde_json::Value = serde_json::from_slice(&content).unwrap();
+        assert!(bundle.pointer("/config/proxy/port").is_none());
+        assert!(bundle.pointer("/config/proxy/listener_tls").is_none());
+        assert!(bundle.pointer("/config/proxy/api_key").is_some());
+
+        // 另一台机器的端口与证书不同, 但同步内容相同
+        let mut other = config.clone();
+        other.proxy.port = 8045;
+        other.proxy.listener_tls.cert_path = String::new();
+        assert_eq!(payload(&other).unwrap().1, hash);
+
+        let mut remote = bundle["config"].clone();
+        keep_local_fields(&serde_json::to_value(&other).unwrap(), &mut remote);
+        let restored: AppConfig = serde_json::from_value(remote).unwrap();
+        assert_eq!(restored.proxy.port, 8045);
+        assert_eq!(restored.proxy.listener_tls.cert_path, "");
+    }
+}
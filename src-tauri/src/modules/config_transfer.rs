@@ -0,0 +1,242 @@
+// 配置导入 / 导出
+// 完整配置导出为单个带格式版本号的 JSON 文件, 可选择不包含密钥 (清空的字段记录在文件中); 导入时按版本迁移、
+// 用本机同一条目的密钥补齐被清空的字段, 再与保存配置走同样的校验。直接复制的 gui_config.json 视为版本 1。
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::models::AppConfig;
+use crate::modules::secrets;
+
+pub const FORMAT: &str = "aiolauncher-config";
+/// 1: 直接复制的 gui_config.json (可能含旧的映射字段与本机的 `secret://` 引用); 2: 带版本信息的导出文件
+pub const FORMAT_VERSION: u32 = 2;
+/// 反代自身的访问凭证, 不在上游密钥字段中
+const LOCAL_SECRETS: &[&str] = &["proxy.api_key", "proxy.admin_password"];
+/// 外部服务环境变量名包含这些词时视为密钥
+const ENV_SECRET_HINTS: &[&str] = &["TOKEN", "KEY", "SECRET", "PASSWORD"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigBundle {
+    pub format: String,
+    pub version: u32,
+    #[serde(default)]
+    pub app_version: Option<String>,
+    #[serde(default)]
+    pub exported_at: Option<i64>,
+    /// 导出时清空的密钥字段路径
+    #[serde(default)]
+    pub stripped_secrets: Vec<String>,
+    pub config: Value,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportReport {
+    pub from_version: u32,
+    pub app_version: Option<String>,
+    pub exported_at: Option<i64>,
+    /// 是否迁移过旧格式
+    pub migrated: bool,
+    /// 文件中没有且本机也没有的密钥, 需要重新填写
+    pub missing_secrets: Vec<String>,
+}
+
+/// 清空全部密钥字段, 返回清空的路径 (导出与非 admin 角色读取配置共用)
+pub fn strip_secrets(config: &mut Value) -> Vec<String> {
+    let mut stripped = Vec::new();
+    for path in LOCAL_SECRETS {
+        if secrets::get_path(config, path).and_then(Value::as_str).is_some_and(|v| !v.is_empty())
+            && secrets::set_path(config, path, String::new()).is_ok()
+        {
+            stripped.push(path.to_string());
+        }
+    }
+    if let Some(proxy) = config.get_mut("proxy") {
+        stripped.extend(secrets::strip(proxy, "proxy", false));
+    }
+    if let Some(Value::Array(profiles)) = config.get_mut("profiles") {
+        for (i, profile) in profiles.iter_mut().enumerate() {
+            if let Some(proxy) = profile.get_mut("proxy") {
+                stripped.extend(secrets::strip(proxy, &format!("profiles[{}].proxy", i), false));
+            }
+        }
+    }
+    if let Some(Value::Array(services)) = config.get_mut("external_services") {
+        for (i, service) in services.iter_mut().enumerate() {
+            let Some(Value::Object(env)) = service.get_mut("env") else {
+                continue;
+            };
+            for (name, value) in env.iter_mut() {
+                let upper = name.to_uppercase();
+                if ENV_SECRET_HINTS.iter().any(|h| upper.contains(h)) && value.as_str().is_some_and(|v| !v.is_empty()) {
+                    *value = Value::String(String::new());
+                    stripped.push(format!("external_services[{}].env.{}", i, name));
+                }
+            }
+        }
+    }
+    stripped
+}
+
+pub fn build_bundle(config: &AppConfig, include_secrets: bool) -> Result<ConfigBundle, String> {
+    let mut value = serde_json::to_value(config).map_err(|e| e.to_string())?;
+    let stripped_secrets = if include_secrets { Vec::new() } else { strip_secrets(&mut value) };
+    Ok(ConfigBundle {
+        format: FORMAT.to_string(),
+        version: FORMAT_VERSION,
+        app_version: Some(env!("CARGO_PKG_VERSION").to_string()),
+        exported_at: Some(chrono::Utc::now().timestamp()),
+        stripped_secrets,
+        config: value,
+    })
+}
+
+/// 导出当前配置, 返回清空的密钥数量
+pub fn export_to(path: &str, include_secrets: bool) -> Result<usize, String> {
+    let bundle = build_bundle(&crate::modules::config::load_app_config()?, include_secrets)?;
+    let content = serde_json::to_string_pretty(&bundle).map_err(|e| e.to_string())?;
+    std::fs::write(path, content).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+    Ok(bundle.stripped_secrets.len())
+}
+
+/// 解析导出文件并迁移到当前版本, 返回原始版本号
+pub fn parse_bundle(content: &str) -> Result<(ConfigBundle, u32), String> {
+    let value: Value = serde_json::from_str(content).map_err(|e| format!("Invalid config file: {}", e))?;
+    let mut bundle = if value.get("format").and_then(Value::as_str) == Some(FORMAT) {
+        serde_json::from_value::<ConfigBundle>(value).map_err(|e| format!("Invalid config file: {}", e))?
+    } else if value.get("proxy").is_some() {
+        ConfigBundle {
+            format: FORMAT.to_string(),
+            version: 1,
+            app_version: None,
+            exported_at: None,
+            stripped_secrets: Vec::new(),
+            config: value,
+        }
+    } else {
+        return Err("Not an AIOLauncher config file".to_string());
+    };
+
+    let from_version = bundle.version;
+    if from_version > FORMAT_VERSION {
+        return Err(format!(
+            "The file was exported by a newer version (format {}); update the launcher before importing",
+            from_version
+        ));
+    }
+    if from_version < 2 {
+        // 其他机器的 `secret://` 引用在本机无法解密, 按未导出的密钥处理
+        if let Some(proxy) = bundle.config.get_mut("proxy") {
+            bundle.stripped_secrets.extend(secrets::strip(proxy, "proxy", true));
+        }
+    }
+    crate::modules::config::migrate(&mut bundle.config);
+    bundle.version = FORMAT_VERSION;
+    Ok((bundle, from_version))
+}
+
+/// 数组元素按 id / name 对应, 避免顺序不同时把密钥填到别的条目
+fn same_entry(local: &Value, imported: &Value, path: &str) -> bool {
+    let mut prefix = String::new();
+    for segment in path.split('.') {
+        if !prefix.is_empty() {
+            prefix.push('.');
+        }
+        prefix.push_str(segment);
+        if segment.ends_with(']') {
+            let (Some(a), Some(b)) = (secrets::get_path(local, &prefix), secrets::get_path(imported, &prefix)) else {
+                return false;
+            };
+            if ["id", "name"].iter().any(|k| a.get(k) != b.get(k)) {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+/// 生成导入后的配置: 被清空的密钥用本机配置中同一条目的值补齐
+pub fn restore(bundle: ConfigBundle, from_version: u32, local: &AppConfig) -> Result<(AppConfig, ImportReport), String> {
+    let local = serde_json::to_value(local).map_err(|e| e.to_string())?;
+    let mut value = bundle.config;
+    let mut missing_secrets = Vec::new();
+    for path in &bundle.stripped_secrets {
+        let known = secrets::get_path(&local, path)
+            .and_then(Value::as_str)
+            .filter(|v| !v.is_empty() && same_entry(&local, &value, path));
+        match known {
+            Some(secret) if secrets::set_path(&mut value, path, secret.to_string()).is_ok() => {}
+            _ => missing_secrets.push(path.clone()),
+        }
+    }
+    let config: AppConfig = serde_json::from_value(value).map_err(|e| format!("Invalid config file: {}", e))?;
+    let report = ImportReport {
+        from_version,
+        app_version: bundle.app_version,
+        exported_at: bundle.exported_at,
+        migrated: from_version < FORMAT_VERSION,
+        missing_secrets,
+    };
+    Ok((config, report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_export_without_secrets_and_restore() {
+        let mut config = AppConfig::new();
+        config.proxy.api_key = "sk-local".to_string();
+        config.proxy.zai.api_key = "zai-secret".to_string();
+        let bundle = build_bundle(&config, false).unwrap();
+        assert!(bundle.stripped_secrets.contains(&"proxy.api_key".to_string()));
+        assert!(bundle.stripped_secrets.contains(&"proxy.zai.api_key".to_string()));
+        assert_eq!(bundle.config["proxy"]["zai"]["api_key"], "");
+
+        let content = serde_json::to_string(&bundle).unwrap();
+        let (parsed, from) = parse_bundle(&content).unwrap();
+        assert_eq!(from, FORMAT_VERSION);
+
+        // 本机已有的密钥被保留, 没有的列为缺失
+        let mut local = AppConfig::new();
+        local.proxy.zai.api_key = "zai-here".to_string();
+        local.proxy.api_key = String::new();
+        let (restored, report) = restore(parsed, from, &local).unwrap();
+        assert_eq!(restored.proxy.zai.api_key, "zai-here");
+        assert_eq!(report.missing_secrets, vec!["proxy.api_key".to_string()]);
+        assert!(!report.migrated);
+    }
+
+    #[test]
+    fn test_strip_admin_credentials() {
+        let mut config = AppConfig::new();
+        config.proxy.admin_password = Some("admin-pass".to_string());
+        config.proxy.admin_tokens = vec![crate::proxy::config::AdminToken {
+            name: "ops".to_string(),
+            token: "ops-token".to_string(),
+            role: crate::proxy::config::AdminRole::Operator,
+            enabled: true,
+        }];
+        let mut value = serde_json::to_value(&config).unwrap();
+        strip_secrets(&mut value);
+        assert_eq!(value["proxy"]["admin_password"], "");
+        assert_eq!(value["proxy"]["admin_tokens"][0]["token"], "");
+        assert_eq!(value["proxy"]["admin_tokens"][0]["name"], "ops");
+    }
+
+    #[test]
+    fn test_parse_legacy_and_newer() {
+        let mut legacy = serde_json::to_value(AppConfig::new()).unwrap();
+        legacy["proxy"]["openai_mapping"] = serde_json::json!({ "gpt-4o": "gemini-2.5-pro" });
+        legacy["proxy"]["zai"]["api_key"] = Value::String("secret://proxy.zai.api_key".to_string());
+        let (bundle, from) = parse_bundle(&legacy.to_string()).unwrap();
+        assert_eq!(from, 1);
+        assert_eq!(bundle.config["proxy"]["custom_mapping"]["gpt-4o"], "gemini-2.5-pro");
+        assert_eq!(bundle.stripped_secrets, vec!["proxy.zai.api_key".to_string()]);
+
+        let newer = serde_json::json!({ "format": FORMAT, "version": FORMAT_VERSION + 1, "config": {} });
+        assert!(parse_bundle(&newer.to_string()).unwrap_err().contains("newer version"));
+        assert!(parse_bundle("{\"accounts\": []}").is_err());
+    }
+}
@@ -0,0 +1,183 @@
+// 配置文件热加载
+// 轮询 gui_config.json, 外部修改后重新加载并热更新运行中的反代服务 (配置原地替换, 进行中的请求不受影响);
+// 启动方案变化时 (例如命令行切换) 按新方案重建监听并启停外部服务
+
+use std::sync::Arc;
+use std::time::SystemTime;
+use tauri::{Emitter, Manager};
+use tokio::time::{self, Duration};
+
+use crate::commands::cloudflared::CloudflaredState;
+use crate::models::AppConfig;
+use crate::modules::integration::SystemManager;
+use crate::modules::{config, logger};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// 记录已处理的文件内容, 区分外部修改与本进程写入
+#[derive(Debug, Default)]
+struct ChangeDetector {
+    seen: Option<u64>,
+}
+
+impl ChangeDetector {
+    fn new(initial: Option<&str>) -> Self {
+        Self {
+            seen: initial.map(config::content_hash),
+        }
+    }
+
+    /// 返回 true 表示内容被外部修改, 需要重新加载
+    fn observe(&mut self, content: &str, self_written: u64) -> bool {
+        let hash = config::content_hash(content);
+        if self.seen == Some(hash) {
+            return false;
+        }
+        self.seen = Some(hash);
+        hash != self_written
+    }
+}
+
+fn modified_at(path: &std::path::Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+async fn apply(proxy_state: &crate::commands::proxy::ProxyServiceState, config: &AppConfig) -> Result<(), String> {
+    crate::modules::notifications::configure(&config.desktop_notifications);
+    crate::modules::profiles::configure(config);
+    let instance_lock = proxy_state.instance.read().await;
+    if let Some(instance) = instance_lock.as_ref() {
+        instance.axum_server.apply_config(&config.proxy).await?;
+        instance
+            .token_manager
+            .update_circuit_breaker_config(config.circuit_breaker.clone())
+            .await;
+    }
+    Ok(())
+}
+
+fn integration(app_handle: Option<&tauri::AppHandle>) -> (SystemManager, Arc<CloudflaredState>) {
+    match app_handle {
+        Some(app) => {
+            let cf_state = app.state::<CloudflaredState>().inner().clone();
+            (SystemManager::Desktop(app.clone()), Arc::new(cf_state))
+        }
+        None => (SystemManager::Headless, Arc::new(CloudflaredState::new())),
+    }
+}
+
+pub fn start_config_watcher(
+    app_handle: Option<tauri::AppHandle>,
+    proxy_state: crate::commands::proxy::ProxyServiceState,
+) {
+    tauri::async_runtime::spawn(async move {
+        let path = match config::config_path() {
+            Ok(path) => path,
+            Err(e) => {
+                logger::log_warn(&format!("[ConfigWatcher] Disabled: {}", e));
+                return;
+            }
+        };
+        logger::log_info(&format!("[ConfigWatcher] Watching {}", path.display()));
+
+        let mut detector = ChangeDetector::new(std::fs::read_to_string(&path).ok().as_deref());
+        let mut current = config::load_app_config().ok();
+        let mut last_modified = modified_at(&path);
+        let mut interval = time::interval(POLL_INTERVAL);
+
+        loop {
+            interval.tick().await;
+            // 命令行等外部进程修改的虚拟 Key 同样生效
+            crate::proxy::virtual_keys::VirtualKeyStore::global().reload_if_changed();
+
+            let modified = modified_at(&path);
+            if modified.is_none() || modified == last_modified {
+                continue;
+            }
+            last_modified = modified;
+
+            let Ok(content) = std::fs::read_to_string(&path) else {
+                continue;
+            };
+            if !detector.observe(&content, config::last_saved_hash()) {
+                continue;
+            }
+
+            // 解析或校验失败时保留当前生效的配置
+            let loaded = config::load_app_config().and_then(|cfg| {
+                crate::proxy::common::config_validation::ensure_valid(&cfg.proxy)?;
+                crate::modules::profiles::validate(&cfg).map(|()| cfg)
+            });
+            let new_config = match loaded {
+                Ok(cfg) => cfg,
+                Err(e) => {
+                    logger::log_warn(&format!("[ConfigWatcher] Reload failed, keeping previous config: {}", e));
+                    if let Some(app) = &app_handle {
+                        let _ = app.emit("config://reload-failed", e);
+                    }
+                    continue;
+                }
+            };
+
+            // 命令行切换了启动方案
+            match current.replace(new_config.clone()).filter(|p| crate::modules::profiles::changed(p, &new_config)) {
+                Some(previous) => {
+                    let (integration, cf_state) = integration(app_handle.as_ref());
+                    if let Err(e) = crate::modules::profiles::apply(&previous, &new_config, &proxy_state, integration, cf_state).await {
+                        logger::log_warn(&format!("[ConfigWatcher] Profile switch failed: {}", e));
+                        current = Some(previous);
+                        if let Some(app) = &app_handle {
+                            let _ = app.emit("config://reload-failed", e);
+                        }
+                        continue;
+                    }
+                    if let Err(e) = apply(&proxy_state, &new_config).await {
+                        logger::log_warn(&format!("[ConfigWatcher] Reloaded config not applied: {}", e));
+                    }
+                    logger::log_info(&format!(
+                        "[ConfigWatcher] Switched to profile {}",
+                        new_config.active_profile.as_deref().unwrap_or(crate::modules::profiles::DEFAULT_PROFILE)
+                    ));
+                }
+                None => {
+                    if let Err(e) = apply(&proxy_state, &new_config).await {
+                        logger::log_warn(&format!("[ConfigWatcher] Reload failed, keeping previous config: {}", e));
+                        if let Some(app) = &app_handle {
+                            let _ = app.emit("config://reload-failed", e);
+                        }
+                        continue;
+                    }
+                    logger::log_info("[ConfigWatcher] Config file changed, proxy settings reloaded");
+                }
+            }
+
+            if let Some(app) = &app_handle {
+                let _ = app.emit("config://updated", ());
+                let _ = app.emit("config://reloaded", &new_config);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_change_detector() {
+        let mut detector = ChangeDetector::new(Some("{\"a\":1}"));
+        assert!(!detector.observe("{\"a\":1}", 0));
+
+        // 外部修改
+        assert!(detector.observe("{\"a\":2}", 0));
+        assert!(!detector.observe("{\"a\":2}", 0));
+
+        // 本进程写入的内容不触发重新加载
+        let saved = "{\"a\":3}";
+        assert!(!detector.observe(saved, config::content_hash(saved)));
+        assert!(detector.observe("{\"a\":1}", config::content_hash(saved)));
+
+        let mut empty = ChangeDetector::new(None);
+        assert!(empty.observe("{}", 0));
+    }
+}
@@ -13,6 +13,13 @@ pub struct TrayTexts {
     pub no_account: String,
     pub unknown_quota: String,
     pub forbidden: String,
+    pub proxy_running: String,
+    pub proxy_stopped: String,
+    pub active_requests: String,
+    pub start_proxy: String,
+    pub stop_proxy: String,
+    pub auto_launch: String,
+    pub open_dashboard: String,
 }
 
 /// Load translations from JSON
@@ -52,5 +59,12 @@ pub fn get_tray_texts(lang: &str) -> TrayTexts {
         no_account: t.get("no_account").cloned().unwrap_or_else(|| "No Account".to_string()),
         unknown_quota: t.get("unknown_quota").cloned().unwrap_or_else(|| "Unknown".to_string()),
         forbidden: t.get("forbidden").cloned().unwrap_or_else(|| "Account Forbidden".to_string()),
+        proxy_running: t.get("proxy_running").cloned().unwrap_or_else(|| "Proxy: Running on port {port}".to_string()),
+        proxy_stopped: t.get("proxy_stopped").cloned().unwrap_or_else(|| "Proxy: Stopped".to_string()),
+        active_requests: t.get("active_requests").cloned().unwrap_or_else(|| "Active Requests: {count}".to_string()),
+        start_proxy: t.get("start_proxy").cloned().unwrap_or_else(|| "Start Proxy".to_string()),
+        stop_proxy: t.get("stop_proxy").cloned().unwrap_or_else(|| "Stop Proxy".to_string()),
+        auto_launch: t.get("auto_launch").cloned().unwrap_or_else(|| "Launch at Login".to_string()),
+        open_dashboard: t.get("open_dashboard").cloned().unwrap_or_else(|| "Open Dashboard".to_string()),
     }
 }
@@ -0,0 +1,74 @@
+use base64::Engine as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::proxy::config::ImagesConfig;
+
+/// Directory for generated images: the configured one, or `<data_dir>/generated_images`
+pub fn resolve_dir(config: &ImagesConfig) -> Result<PathBuf, String> {
+    let dir = if config.save_dir.trim().is_empty() {
+        crate::modules::account::get_data_dir()?.join("generated_images")
+    } else {
+        PathBuf::from(config.save_dir.trim())
+    };
+    if !dir.exists() {
+        fs::create_dir_all(&dir).map_err(|e| format!("failed_to_create_image_dir: {}", e))?;
+    }
+    Ok(dir)
+}
+
+fn extension_for(mime_type: &str) -> &'static str {
+    match mime_type {
+        "image/jpeg" | "image/jpg" => "jpg",
+        "image/webp" => "webp",
+        "image/gif" => "gif",
+        _ => "png",
+    }
+}
+
+/// Split a `data:<mime>;base64,<data>` URL into (mime, data)
+pub fn split_data_url(url: &str) -> Option<(&str, &str)> {
+    let rest = url.strip_prefix("data:")?;
+    let (meta, data) = rest.split_once(',')?;
+    let mime = meta.strip_suffix(";base64")?;
+    Some((mime, data))
+}
+
+/// Decode and write one image, returning its path
+pub fn save_image(dir: &Path, b64: &str, mime_type: &str) -> Result<PathBuf, String> {
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(b64.trim())
+        .map_err(|e| format!("invalid_image_data: {}", e))?;
+    let name = format!(
+        "img_{}_{}.{}",
+        chrono::Local::now().format("%Y%m%d_%H%M%S"),
+        &uuid::Uuid::new_v4().simple().to_string()[..8],
+        extension_for(mime_type)
+    );
+    let path = dir.join(name);
+    fs::write(&path, bytes).map_err(|e| format!("failed_to_save_image: {}", e))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_data_url() {
+        assert_eq!(split_data_url("data:image/webp;base64,AAAA"), Some(("image/webp", "AAAA")));
+        assert_eq!(split_data_url("https://example.com/a.png"), None);
+        assert_eq!(split_data_url("data:text/plain,hello"), None);
+    }
+
+    #[test]
+    fn test_save_image() {
+        let dir = std::env::temp_dir().join(format!("image_store_test_{}", uuid::Uuid::new_v4().simple()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = save_image(&dir, "aGVsbG8=", "image/jpeg").unwrap();
+        assert_eq!(path.extension().and_then(|e| e.to_str()), Some("jpg"));
+        assert_eq!(fs::read(&path).unwrap(), b"hello");
+        assert!(save_image(&dir, "not base64!", "image/png").is_err());
+        fs::remove_dir_all(&dir).ok();
+    }
+}
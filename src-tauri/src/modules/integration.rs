@@ -64,8 +64,13 @@ impl SystemIntegration for DesktopIntegration {
     }
 
     fn show_notification(&self, title: &str, body: &str) {
-        // 使用 tauri-plugin-dialog 或原生通知（此处简化）
+        // 由前端通过系统通知展示 (未授权时退回应用内提示)
+        use tauri::Emitter;
         crate::modules::logger::log_info(&format!("[Notification] {}: {}", title, body));
+        let _ = self.app_handle.emit(
+            crate::modules::notifications::NOTIFICATION_EVENT,
+            serde_json::json!({ "title": title, "body": body }),
+        );
     }
 }
 
@@ -116,6 +121,14 @@ impl SystemManager {
         }
     }
 
+    /// 向前端推送事件 (headless 时忽略)
+    pub fn emit<S: serde::Serialize + Clone>(&self, event: &str, payload: &S) {
+        use tauri::Emitter;
+        if let SystemManager::Desktop(handle) = self {
+            let _ = handle.emit(event, payload.clone());
+        }
+    }
+
     pub fn show_notification(&self, title: &str, body: &str) {
         match self {
             SystemManager::Desktop(handle) => {
@@ -0,0 +1,380 @@
+// 上游 Key 巡检
+// 定期用模型列表接口验证每个已保存的上游 Key (不产生费用), 对支持余额查询的上游 (OpenRouter、DeepSeek) 读取剩余额度
+// 与过期时间; 失效、余额不足或即将过期的 Key 在仪表盘中标出并发送桌面通知。结果只保存在内存中。
+
+use std::sync::RwLock;
+use std::time::Duration;
+
+use serde::Serialize;
+use serde_json::Value;
+use tracing::warn;
+
+use crate::modules::notifications::{self, NotificationKind};
+use crate::modules::usage::mask_api_key;
+use crate::proxy::config::{KeyCheckConfig, ProxyConfig};
+use crate::proxy::upstream::health_check::{openai_models_target, ProbeTarget};
+
+/// 前端监听该事件刷新仪表盘
+pub const KEY_CHECK_EVENT: &str = "key-check://updated";
+const PROBE_TIMEOUT: Duration = Duration::from_secs(15);
+
+static RESULTS: RwLock<Vec<KeyCheckResult>> = RwLock::new(Vec::new());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyStatus {
+    Valid,
+    /// 被上游拒绝或已过期
+    Invalid,
+    LowBalance,
+    Expiring,
+    /// 网络错误或上游异常, 无法判断
+    Unreachable,
+}
+
+/// 提供余额查询接口的上游
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BalanceApi {
+    OpenRouter,
+    DeepSeek,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct KeyBalance {
+    /// 剩余额度, None 表示 Key 没有额度上限
+    pub remaining: Option<f64>,
+    pub currency: String,
+    /// Unix 秒
+    pub expires_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyCheckResult {
+    pub upstream: String,
+    pub key_hint: String,
+    pub status: KeyStatus,
+    pub http_status: Option<u16>,
+    pub balance: Option<KeyBalance>,
+    pub error: Option<String>,
+    pub checked_at: i64,
+}
+
+struct KeyTarget {
+    probe: ProbeTarget,
+    key: String,
+    balance: Option<BalanceApi>,
+}
+
+fn balance_api(base_url: &str, preset: Option<&str>) -> Option<BalanceApi> {
+    let host = url::Url::parse(base_url).ok()?.host_str()?.to_ascii_lowercase();
+    if preset == Some("openrouter") || host == "openrouter.ai" || host.ends_with(".openrouter.ai") {
+        Some(BalanceApi::OpenRouter)
+    } else if host == "api.deepseek.com" {
+        Some(BalanceApi::DeepSeek)
+    } else {
+        None
+    }
+}
+
+/// 已启用上游中保存的全部 Key (同一上游的相同 Key 只检查一次)
+fn targets(config: &ProxyConfig) -> Vec<KeyTarget> {
+    let mut targets = Vec::new();
+    let zai = &config.zai;
+    if zai.enabled {
+        for entry in zai.key_pool() {
+            targets.push(KeyTarget {
+                probe: ProbeTarget {
+                    name: "zai".to_string(),
+                    kind: "zai",
+                    url: format!("{}/v1/models", zai.base_url.trim_end_matches('/')),
+                    auth: Some(("x-api-key", entry.key.clone())),
+                },
+                key: entry.key,
+                balance: None,
+            });
+        }
+    }
+    for upstream in config.compatible_upstreams.upstreams.iter().filter(|u| u.enabled && !u.api_key.trim().is_empty()) {
+        let Some(route) = crate::proxy::providers::openai_compat::route_for(upstream) else {
+            continue;
+        };
+        let key = route.api_key.trim().to_string();
+        let mut probe = openai_models_target(route.name.clone(), "openai_compatible", &route.base_url, &key);
+        if let Some(preset) = route.preset {
+            probe.url = format!("{}/models", route.base_url);
+            probe.auth = Some((preset.auth_header, preset.auth_value(&key)));
+        }
+        let balance = balance_api(&route.base_url, route.preset.map(|p| p.id));
+        targets.push(KeyTarget { probe, key, balance });
+    }
+    let mut openai = |name: String, kind: &'static str, base_url: &str, api_key: &str| {
+        if !base_url.is_empty() && !api_key.trim().is_empty() {
+            targets.push(KeyTarget {
+                probe: openai_models_target(name, kind, base_url, api_key.trim()),
+                key: api_key.trim().to_string(),
+                balance: balance_api(base_url, None),
+            });
+        }
+    };
+    if config.audio.enabled {
+        openai("audio".to_string(), "openai", &config.audio.base_url, &config.audio.api_key);
+    }
+    if config.embeddings.enabled {
+        for provider in config.embeddings.providers.iter().filter(|p| p.enabled) {
+            openai(format!("embeddings:{}", provider.pattern), "embeddings", &provider.base_url, &provider.api_key);
+        }
+    }
+    for backend in config.images.backends.iter().filter(|b| b.enabled) {
+        openai(format!("images:{}", backend.pattern), "images", &backend.base_url, &backend.api_key);
+    }
+
+    let mut seen = std::collections::HashSet::new();
+    targets.retain(|t| seen.insert((t.probe.name.clone(), t.key.clone())));
+    targets
+}
+
+fn parse_timestamp(value: Option<&Value>) -> Option<i64> {
+    match value? {
+        Value::Number(n) => n.as_i64(),
+        Value::String(s) => chrono::DateTime::parse_from_rfc3339(s).ok().map(|t| t.timestamp()),
+        _ => None,
+    }
+}
+
+fn as_f64(value: Option<&Value>) -> Option<f64> {
+    match value? {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
+
+/// OpenRouter `/api/v1/key`: Key 设置了额度上限时返回 `limit_remaining`
+fn parse_openrouter_key(body: &Value) -> KeyBalance {
+    let data = &body["data"];
+    KeyBalance {
+        remaining: as_f64(data.get("limit_remaining")),
+        currency: "USD".to_string(),
+        expires_at: parse_timestamp(data.get("expires_at")),
+    }
+}
+
+/// DeepSeek `/user/balance`
+fn parse_deepseek_balance(body: &Value) -> Option<KeyBalance> {
+    let info = body.get("balance_infos")?.as_array()?.first()?;
+    Some(KeyBalance {
+        remaining: as_f64(info.get("total_balance")),
+        currency: info.get("currency").and_then(Value::as_str).unwrap_or("CNY").to_string(),
+        expires_at: None,
+    })
+}
+
+async fn get_json(client: &reqwest::Client, url: &str, key: &str) -> Result<Value, String> {
+    let response = client.get(url).bearer_auth(key).timeout(PROBE_TIMEOUT).send().await.map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("HTTP {}", response.status()));
+    }
+    response.json().await.map_err(|e| e.to_string())
+}
+
+async fn fetch_balance(client: &reqwest::Client, api: BalanceApi, key: &str) -> Result<KeyBalance, String> {
+    match api {
+        BalanceApi::OpenRouter => {
+            let mut balance = parse_openrouter_key(&get_json(client, "https://openrouter.ai/api/v1/key", key).await?);
+            if balance.remaining.is_none() {
+                // Key 没有单独的额度上限时按账户余额计算
+                if let Ok(credits) = get_json(client, "https://openrouter.ai/api/v1/credits", key).await {
+                    let data = &credits["data"];
+                    if let (Some(total), Some(used)) = (as_f64(data.get("total_credits")), as_f64(data.get("total_usage"))) {
+                        balance.remaining = Some(total - used);
+                    }
+                }
+            }
+            Ok(balance)
+        }
+        BalanceApi::DeepSeek => parse_deepseek_balance(&get_json(client, "https://api.deepseek.com/user/balance", key).await?)
+            .ok_or_else(|| "Unexpected balance response".to_string()),
+    }
+}
+
+/// 根据探测结果判断 Key 状态
+fn classify(http_status: Option<u16>, balance: Option<&KeyBalance>, settings: &KeyCheckConfig, now: i64) -> KeyStatus {
+    match http_status {
+        Some(401 | 403) => return KeyStatus::Invalid,
+        // 余额不足
+        Some(402) => return KeyStatus::LowBalance,
+        Some(200..=299) => {}
+        _ => return KeyStatus::Unreachable,
+    }
+    let Some(balance) = balance else {
+        return KeyStatus::Valid;
+    };
+    if let Some(expires_at) = balance.expires_at {
+        if expires_at <= now {
+            return KeyStatus::Invalid;
+        }
+        if expires_at - now <= settings.expiry_warning_days as i64 * 86_400 {
+            return KeyStatus::Expiring;
+        }
+    }
+    match balance.remaining {
+        Some(remaining) if remaining < settings.low_balance_threshold => KeyStatus::LowBalance,
+        _ => KeyStatus::Valid,
+    }
+}
+
+async fn check_target(client: &reqwest::Client, target: KeyTarget, settings: &KeyCheckConfig) -> KeyCheckResult {
+    let mut request = client.get(&target.probe.url).timeout(PROBE_TIMEOUT);
+    if let Some((name, value)) = &target.probe.auth {
+        request = request.header(*name, value);
+    }
+    let (http_status, mut error) = match request.send().await {
+        Ok(response) if response.status().is_success() => (Some(response.status().as_u16()), None),
+        Ok(response) => (Some(response.status().as_u16()), Some(format!("HTTP {}", response.status()))),
+        Err(e) => (None, Some(e.to_string())),
+    };
+    let mut balance = None;
+    if let (Some(api), None) = (target.balance, &error) {
+        match fetch_balance(client, api, &target.key).await {
+            Ok(b) => balance = Some(b),
+            Err(e) => error = Some(format!("Balance query failed: {}", e)),
+        }
+    }
+    let now = chrono::Utc::now().timestamp();
+    KeyCheckResult {
+        upstream: target.probe.name,
+        key_hint: mask_api_key(&target.key),
+        status: classify(http_status, balance.as_ref(), settings, now),
+        http_status,
+        balance,
+        error,
+        checked_at: now,
+    }
+}
+
+/// 上次巡检的结果
+pub fn results() -> Vec<KeyCheckResult> {
+    RESULTS.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// 立即检查全部 Key, 更新结果并对异常的 Key 发送通知
+pub async fn check_all(config: &ProxyConfig) -> Vec<KeyCheckResult> {
+    let client = crate::utils::http::get_client();
+    let checks = targets(config).into_iter().map(|t| check_target(&client, t, &config.key_check));
+    let results = futures::future::join_all(checks).await;
+
+    for result in &results {
+        let reason = match result.status {
+            KeyStatus::Invalid => "was rejected or has expired",
+            KeyStatus::LowBalance => "is running out of credits",
+            KeyStatus::Expiring => "expires soon",
+            KeyStatus::Valid | KeyStatus::Unreachable => continue,
+        };
+        let subject = format!("{}:{}", result.upstream, result.key_hint);
+        notifications::notify(
+            NotificationKind::UpstreamKey,
+            &subject,
+            "Upstream key needs attention",
+            &format!("The {} key {} {}", result.upstream, result.key_hint, reason),
+        );
+    }
+    *RESULTS.write().unwrap_or_else(|e| e.into_inner()) = results.clone();
+    if let Some(integration) = notifications::integration() {
+        integration.emit(KEY_CHECK_EVENT, &results);
+    }
+    results
+}
+
+/// 按 `key_check.interval_minutes` 定期巡检 (生效配置, 包含当前启动方案)
+pub fn start_key_check() {
+    tauri::async_runtime::spawn(async move {
+        let mut last_run: Option<std::time::Instant> = None;
+        loop {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            let config = match crate::modules::config::load_app_config() {
+                Ok(config) => crate::modules::profiles::effective(&config.proxy),
+                Err(e) => {
+                    warn!("[KeyCheck] Failed to load config: {}", e);
+                    continue;
+                }
+            };
+            let settings = &config.key_check;
+            let interval = Duration::from_secs(settings.interval_minutes.max(1) * 60);
+            if !settings.enabled || last_run.is_some_and(|t| t.elapsed() < interval) {
+                continue;
+            }
+            last_run = Some(std::time::Instant::now());
+            check_all(&config).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy::config::CompatibleUpstream;
+    use serde_json::json;
+
+    #[test]
+    fn test_targets() {
+        let mut config = ProxyConfig::default();
+        config.zai.enabled = true;
+        config.zai.api_key = "zai-key-1".to_string();
+        let upstream = |name: &str, preset: Option<&str>, base_url: &str| CompatibleUpstream {
+            name: name.to_string(),
+            preset: preset.map(str::to_string),
+            base_url: base_url.to_string(),
+            api_key: "sk-abcdefgh1234".to_string(),
+            models: Vec::new(),
+            chat_only: false,
+            enabled: true,
+        };
+        config.compatible_upstreams.upstreams = vec![
+            upstream("openrouter", Some("openrouter"), ""),
+            upstream("deepseek", None, "https://api.deepseek.com/v1"),
+            upstream("lmstudio", None, "http://127.0.0.1:1234/v1"),
+            CompatibleUpstream { enabled: false, ..upstream("off", Some("groq"), "") },
+        ];
+        let targets = targets(&config);
+        let summary: Vec<(&str, &str, Option<BalanceApi>)> =
+            targets.iter().map(|t| (t.probe.name.as_str(), t.probe.url.as_str(), t.balance)).collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("zai", "https://api.z.ai/api/anthropic/v1/models", None),
+                ("openrouter", "https://openrouter.ai/api/v1/models", Some(BalanceApi::OpenRouter)),
+                ("deepseek", "https://api.deepseek.com/v1/models", Some(BalanceApi::DeepSeek)),
+                ("lmstudio", "http://127.0.0.1:1234/v1/models", None),
+            ]
+        );
+        assert_eq!(targets[1].probe.auth, Some(("Authorization", "Bearer sk-abcdefgh1234".to_string())));
+    }
+
+    #[test]
+    fn test_balance_and_classify() {
+        let settings = KeyCheckConfig::default();
+        let now = 1_800_000_000;
+        let openrouter = parse_openrouter_key(&json!({
+            "data": { "limit": 10, "usage": 9.5, "limit_remaining": 0.5, "expires_at": "2027-01-30T00:00:00Z" }
+        }));
+        assert_eq!(openrouter.remaining, Some(0.5));
+        assert_eq!(classify(Some(200), Some(&openrouter), &settings, now), KeyStatus::LowBalance);
+
+        let deepseek = parse_deepseek_balance(&json!({
+            "is_available": true,
+            "balance_infos": [{ "currency": "CNY", "total_balance": "110.00", "granted_balance": "10.00" }]
+        }))
+        .unwrap();
+        assert_eq!((deepseek.remaining, deepseek.currency.as_str()), (Some(110.0), "CNY"));
+        assert_eq!(classify(Some(200), Some(&deepseek), &settings, now), KeyStatus::Valid);
+
+        let expiring = KeyBalance { remaining: None, currency: "USD".to_string(), expires_at: Some(now + 86_400) };
+        assert_eq!(classify(Some(200), Some(&expiring), &settings, now), KeyStatus::Expiring);
+        let expired = KeyBalance { expires_at: Some(now - 1), ..expiring };
+        assert_eq!(classify(Some(200), Some(&expired), &settings, now), KeyStatus::Invalid);
+        assert_eq!(classify(Some(401), None, &settings, now), KeyStatus::Invalid);
+        assert_eq!(classify(Some(402), None, &settings, now), KeyStatus::LowBalance);
+        assert_eq!(classify(Some(503), None, &settings, now), KeyStatus::Unreachable);
+        assert_eq!(classify(None, None, &settings, now), KeyStatus::Unreachable);
+    }
+}
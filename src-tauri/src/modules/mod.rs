@@ -13,11 +13,27 @@ pub mod proxy_db;
 pub mod device;
 pub mod update_checker;
 pub mod scheduler;
+pub mod config_watcher;
 pub mod token_stats;
+pub mod usage;
 pub mod cloudflared;
 pub mod integration;
 pub mod account_service;
 pub mod http_api;
+pub mod image_store;
+pub mod service_unit;
+pub mod cli;
+pub mod secrets;
+pub mod audit;
+pub mod notifications;
+pub mod model_download;
+pub mod resource_monitor;
+pub mod service_manager;
+pub mod profiles;
+pub mod config_transfer;
+pub mod config_sync;
+pub mod setup_wizard;
+pub mod key_check;
 
 use crate::models;
 
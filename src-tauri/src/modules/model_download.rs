@@ -0,0 +1,511 @@
+// GGUF 模型下载管理
+// 从 Hugging Face 下载模型文件到 `<data_dir>/models/<owner>--<name>/`, 供本地 llama-server 使用。
+// 下载先写入 `.part` 文件, 取消或中断后再次下载以 Range 请求续传; 完成后按 Hugging Face 提供 (或调用方指定)
+// 的 SHA-256 校验, 通过后才改名为正式文件。开始前检查磁盘剩余空间, 进度以事件推送给前端。
+
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use tauri::Emitter;
+use tokio::io::AsyncWriteExt;
+use tracing::{info, warn};
+
+/// 前端监听该事件更新下载进度
+pub const PROGRESS_EVENT: &str = "model-download://progress";
+const HF_BASE: &str = "https://huggingface.co";
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(500);
+/// 磁盘上至少保留的空间
+const DISK_MARGIN: u64 = 512 * 1024 * 1024;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelDownloadRequest {
+    /// 仓库, 例如 `Qwen/Qwen2.5-7B-Instruct-GGUF`
+    pub repo: String,
+    /// 仓库内的文件路径
+    pub file: String,
+    #[serde(default)]
+    pub revision: Option<String>,
+    /// 期望的 SHA-256; 为空时使用 Hugging Face 记录的 LFS 哈希
+    #[serde(default)]
+    pub sha256: Option<String>,
+    /// 访问受限 (gated) 仓库所需的 Token; 为空时读取 `HF_TOKEN` 环境变量
+    #[serde(default)]
+    pub token: Option<String>,
+    /// 完成后设为 llama-server 的模型
+    #[serde(default)]
+    pub activate: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DownloadState {
+    Downloading,
+    Verifying,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelDownload {
+    pub id: String,
+    pub repo: String,
+    pub file: String,
+    pub revision: String,
+    pub path: String,
+    pub total_bytes: Option<u64>,
+    pub downloaded_bytes: u64,
+    pub bytes_per_sec: u64,
+    pub state: DownloadState,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LocalModel {
+    pub path: String,
+    pub name: String,
+    pub size: u64,
+}
+
+struct Entry {
+    task: ModelDownload,
+    cancel: Arc<AtomicBool>,
+}
+
+fn downloads() -> &'static Mutex<HashMap<String, Entry>> {
+    static DOWNLOADS: OnceLock<Mutex<HashMap<String, Entry>>> = OnceLock::new();
+    DOWNLOADS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+pub fn models_dir() -> Result<PathBuf, String> {
+    let dir = crate::modules::account::get_data_dir()?.join("models");
+    if !dir.exists() {
+        std::fs::create_dir_all(&dir).map_err(|e| format!("failed_to_create_models_dir: {}", e))?;
+    }
+    Ok(dir)
+}
+
+/// 校验仓库名与文件路径 (只允许 `.gguf`, 不允许跳出模型目录)
+pub fn validate_request(request: &ModelDownloadRequest) -> Result<(), String> {
+    let repo_ok = request.repo.split('/').count() == 2
+        && request.repo.split('/').all(|p| !p.is_empty() && p != "." && p != "..");
+    if !repo_ok {
+        return Err(format!("Invalid repository '{}', expected 'owner/name'", request.repo));
+    }
+    let file = request.file.trim_start_matches('/');
+    if file.is_empty() || file.split(['/', '\\']).any(|p| p.is_empty() || p == "." || p == "..") {
+        return Err(format!("Invalid file path '{}'", request.file));
+    }
+    if !file.to_ascii_lowercase().ends_with(".gguf") {
+        return Err(format!("Only .gguf files can be downloaded, got '{}'", request.file));
+    }
+    Ok(())
+}
+
+pub fn dest_path(models_dir: &Path, repo: &str, file: &str) -> PathBuf {
+    models_dir.join(repo.replace('/', "--")).join(file.trim_start_matches('/'))
+}
+
+pub fn resolve_url(repo: &str, revision: &str, file: &str) -> String {
+    format!("{}/{}/resolve/{}/{}", HF_BASE, repo, revision, file.trim_start_matches('/'))
+}
+
+/// 在仓库目录列表 (`/api/models/{repo}/tree/{rev}/{dir}`) 中查找文件, 返回 (大小, SHA-256)
+pub fn find_tree_entry(tree: &Value, file: &str) -> Option<(u64, Option<String>)> {
+    let entry = tree.as_array()?.iter().find(|e| e["path"].as_str() == Some(file))?;
+    let size = entry.pointer("/lfs/size").or(entry.get("size")).and_then(|v| v.as_u64())?;
+    let sha = entry.pointer("/lfs/oid").and_then(|v| v.as_str()).map(String::from);
+    Some((size, sha))
+}
+
+/// `Content-Range: bytes 100-999/1000` 中的总大小
+pub fn parse_content_range_total(value: &str) -> Option<u64> {
+    value.rsplit('/').next()?.trim().parse().ok()
+}
+
+/// 目标目录所在磁盘的剩余空间 (取挂载点最长的匹配)
+fn available_space(dir: &Path) -> Option<u64> {
+    let dir = dir.canonicalize().ok()?;
+    let disks = sysinfo::Disks::new_with_refreshed_list();
+    disks
+        .list()
+        .iter()
+        .filter(|d| dir.starts_with(d.mount_point()))
+        .max_by_key(|d| d.mount_point().as_os_str().len())
+        .map(|d| d.available_space())
+}
+
+fn ensure_disk_space(dir: &Path, needed: u64) -> Result<(), String> {
+    match available_space(dir) {
+        Some(available) if available < needed.saturating_add(DISK_MARGIN) => Err(format!(
+            "Not enough disk space: {:.1} GB needed, {:.1} GB available",
+            (needed + DISK_MARGIN) as f64 / 1e9,
+            available as f64 / 1e9
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn sha256_file(path: &Path) -> Result<String, String> {
+    let mut file = std::fs::File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 1024 * 1024];
+    loop {
+        let n = file.read(&mut buf).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+async fn verify(path: &Path, expected: Option<&str>) -> Result<(), String> {
+    let Some(expected) = expected else {
+        return Ok(());
+    };
+    let owned = path.to_path_buf();
+    let actual = tokio::task::spawn_blocking(move || sha256_file(&owned))
+        .await
+        .map_err(|e| format!("Checksum task failed: {}", e))??;
+    if !actual.eq_ignore_ascii_case(expected.trim()) {
+        return Err(format!("Checksum mismatch: expected {}, got {}", expected.trim(), actual));
+    }
+    Ok(())
+}
+
+fn part_path(dest: &Path) -> PathBuf {
+    let mut name = dest.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    dest.with_file_name(name)
+}
+
+fn update(id: &str, f: impl FnOnce(&mut ModelDownload)) -> Option<ModelDownload> {
+    let mut downloads = downloads().lock().unwrap_or_else(|e| e.into_inner());
+    let entry = downloads.get_mut(id)?;
+    f(&mut entry.task);
+    Some(entry.task.clone())
+}
+
+fn publish(app: &tauri::AppHandle, id: &str, f: impl FnOnce(&mut ModelDownload)) {
+    if let Some(task) = update(id, f) {
+        let _ = app.emit(PROGRESS_EVENT, &task);
+    }
+}
+
+/// 开始下载; 同一文件已在下载时返回现有任务
+pub fn start(app: tauri::AppHandle, request: ModelDownloadRequest) -> Result<ModelDownload, String> {
+    validate_request(&request)?;
+    let file = request.file.trim_start_matches('/').to_string();
+    let revision = request.revision.clone().filter(|r| !r.is_empty()).unwrap_or_else(|| "main".to_string());
+    let dest = dest_path(&models_dir()?, &request.repo, &file);
+
+    let mut map = downloads().lock().unwrap_or_else(|e| e.into_inner());
+    let path = dest.to_string_lossy().to_string();
+    if let Some(existing) = map
+        .values()
+        .find(|e| e.task.path == path && matches!(e.task.state, DownloadState::Downloading | DownloadState::Verifying))
+    {
+        return Ok(existing.task.clone());
+    }
+    let task = ModelDownload {
+        id: uuid::Uuid::new_v4().to_string(),
+        repo: request.repo.clone(),
+        file,
+        revision,
+        path,
+        total_bytes: None,
+        downloaded_bytes: 0,
+        bytes_per_sec: 0,
+        state: DownloadState::Downloading,
+        error: None,
+    };
+    let cancel = Arc::new(AtomicBool::new(false));
+    // 已结束的同名任务由新任务替代
+    map.retain(|_, e| e.task.path != task.path);
+    map.insert(task.id.clone(), Entry { task: task.clone(), cancel: cancel.clone() });
+    drop(map);
+
+    let id = task.id.clone();
+    tauri::async_runtime::spawn(async move {
+        let result = run(&app, &id, &request, dest, cancel.clone()).await;
+        match result {
+            Ok(()) => publish(&app, &id, |t| {
+                t.state = DownloadState::Completed;
+                t.bytes_per_sec = 0;
+            }),
+            Err(_) if cancel.load(Ordering::SeqCst) => {
+                info!("[ModelDownload] Cancelled {}", request.file);
+                publish(&app, &id, |t| {
+                    t.state = DownloadState::Cancelled;
+                    t.bytes_per_sec = 0;
+                });
+            }
+            Err(e) => {
+                warn!("[ModelDownload] {} failed: {}", request.file, e);
+                publish(&app, &id, |t| {
+                    t.state = DownloadState::Failed;
+                    t.bytes_per_sec = 0;
+                    t.error = Some(e);
+                });
+            }
+        }
+    });
+    Ok(task)
+}
+
+async fn run(
+    app: &tauri::AppHandle,
+    id: &str,
+    request: &ModelDownloadRequest,
+    dest: PathBuf,
+    cancel: Arc<AtomicBool>,
+) -> Result<(), String> {
+    let task = update(id, |_| {}).ok_or("Download task disappeared")?;
+    let token = request
+        .token
+        .clone()
+        .filter(|t| !t.is_empty())
+        .or_else(|| std::env::var("HF_TOKEN").ok().filter(|t| !t.is_empty()));
+    let client = reqwest::Client::builder()
+        .connect_timeout(Duration::from_secs(30))
+        .user_agent(crate::constants::USER_AGENT.as_str())
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    let authorized = |builder: reqwest::RequestBuilder| match &token {
+        Some(token) => builder.bearer_auth(token),
+        None => builder,
+    };
+
+    // 文件大小与 LFS 哈希 (获取失败时只按 Content-Length 下载, 不做校验)
+    let parent = Path::new(&task.file).parent().map(|p| p.to_string_lossy().replace('\\', "/")).unwrap_or_default();
+    let tree_url = format!("{}/api/models/{}/tree/{}/{}", HF_BASE, task.repo, task.revision, parent);
+    let metadata = match authorized(client.get(tree_url.trim_end_matches('/'))).send().await {
+        Ok(resp) if resp.status().is_success() => resp.json::<Value>().await.ok().and_then(|t| find_tree_entry(&t, &task.file)),
+        Ok(resp) => {
+            warn!("[ModelDownload] Metadata request returned {}", resp.status());
+            None
+        }
+        Err(e) => {
+            warn!("[ModelDownload] Metadata request failed: {}", e);
+            None
+        }
+    };
+    let mut total = metadata.as_ref().map(|(size, _)| *size);
+    let expected_sha = request.sha256.clone().filter(|s| !s.trim().is_empty()).or(metadata.and_then(|(_, sha)| sha));
+
+    let dir = dest.parent().ok_or("Invalid destination")?.to_path_buf();
+    tokio::fs::create_dir_all(&dir).await.map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+
+    // 已下载过的文件校验通过即完成
+    if let Ok(meta) = tokio::fs::metadata(&dest).await {
+        if total.is_none_or(|t| t == meta.len()) {
+            publish(app, id, |t| {
+                t.state = DownloadState::Verifying;
+                t.total_bytes = Some(meta.len());
+                t.downloaded_bytes = meta.len();
+            });
+            if verify(&dest, expected_sha.as_deref()).await.is_ok() {
+                return finish(app, request, &dest);
+            }
+        }
+    }
+
+    let part = part_path(&dest);
+    let mut offset = tokio::fs::metadata(&part).await.map(|m| m.len()).unwrap_or(0);
+    if total.is_some_and(|t| offset > t) {
+        offset = 0;
+    }
+    if let Some(total) = total {
+        ensure_disk_space(&dir, total - offset)?;
+    }
+
+    let url = resolve_url(&task.repo, &task.revision, &task.file);
+    info!("[ModelDownload] {} -> {} (resume from {} bytes)", url, dest.display(), offset);
+    let mut builder = authorized(client.get(&url));
+    if offset > 0 {
+        builder = builder.header(reqwest::header::RANGE, format!("bytes={}-", offset));
+    }
+    let resp = builder.send().await.map_err(|e| format!("Download request failed: {}", e))?;
+    let status = resp.status();
+    let resumed = status == reqwest::StatusCode::PARTIAL_CONTENT;
+    if status == reqwest::StatusCode::RANGE_NOT_SATISFIABLE && total == Some(offset) {
+        // `.part` 已经完整
+    } else if status == reqwest::StatusCode::UNAUTHORIZED || status == reqwest::StatusCode::FORBIDDEN {
+        return Err(format!("Access denied ({}): the repository may be gated and require a Hugging Face token", status));
+    } else if !status.is_success() {
+        return Err(format!("Download failed with status: {}", status));
+    } else {
+        if !resumed {
+            offset = 0;
+        }
+        let header_total = if resumed {
+            resp.headers().get(reqwest::header::CONTENT_RANGE).and_then(|v| v.to_str().ok()).and_then(parse_content_range_total)
+        } else {
+            resp.content_length()
+        };
+        if total.is_none() {
+            total = header_total;
+            if let Some(total) = total {
+                ensure_disk_space(&dir, total.saturating_sub(offset))?;
+            }
+        }
+        publish(app, id, |t| {
+            t.total_bytes = total;
+            t.downloaded_bytes = offset;
+        });
+
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(resumed)
+            .truncate(!resumed)
+            .open(&part)
+            .await
+            .map_err(|e| format!("Failed to open {}: {}", part.display(), e))?;
+        let mut stream = resp.bytes_stream();
+        let mut downloaded = offset;
+        let (mut last_emit, mut last_bytes) = (Instant::now(), downloaded);
+        while let Some(chunk) = stream.next().await {
+            if cancel.load(Ordering::SeqCst) {
+                let _ = file.flush().await;
+                return Err("Cancelled".to_string());
+            }
+            let chunk = chunk.map_err(|e| format!("Download interrupted: {}", e))?;
+            file.write_all(&chunk).await.map_err(|e| format!("Failed to write {}: {}", part.display(), e))?;
+            downloaded += chunk.len() as u64;
+            let elapsed = last_emit.elapsed();
+            if elapsed >= PROGRESS_INTERVAL {
+                let speed = ((downloaded - last_bytes) as f64 / elapsed.as_secs_f64()) as u64;
+                publish(app, id, |t| {
+                    t.downloaded_bytes = downloaded;
+                    t.bytes_per_sec = speed;
+                });
+                (last_emit, last_bytes) = (Instant::now(), downloaded);
+            }
+        }
+        file.flush().await.map_err(|e| format!("Failed to write {}: {}", part.display(), e))?;
+        if total.is_some_and(|t| downloaded != t) {
+            return Err(format!("Download incomplete: {} of {} bytes", downloaded, total.unwrap_or_default()));
+        }
+        publish(app, id, |t| t.downloaded_bytes = downloaded);
+    }
+
+    publish(app, id, |t| {
+        t.state = DownloadState::Verifying;
+        t.bytes_per_sec = 0;
+    });
+    if let Err(e) = verify(&part, expected_sha.as_deref()).await {
+        // 内容损坏, 续传没有意义
+        let _ = tokio::fs::remove_file(&part).await;
+        return Err(e);
+    }
+    tokio::fs::rename(&part, &dest).await.map_err(|e| format!("Failed to move {}: {}", part.display(), e))?;
+    info!("[ModelDownload] Saved {}", dest.display());
+    finish(app, request, &dest)
+}
+
+/// `activate` 时把模型设为 llama-server 的 model_path 并立即生效
+fn finish(app: &tauri::AppHandle, request: &ModelDownloadRequest, dest: &Path) -> Result<(), String> {
+    if !request.activate {
+        return Ok(());
+    }
+    let mut config = crate::modules::config::load_app_config()?;
+    config.proxy.llama_server.model_path = dest.to_string_lossy().to_string();
+    crate::proxy::common::config_validation::ensure_valid(&config.proxy)?;
+    crate::modules::config::save_app_config(&config)?;
+    crate::proxy::llama_server::configure(&config.proxy.llama_server);
+    let _ = app.emit("config://updated", ());
+    Ok(())
+}
+
+pub fn list() -> Vec<ModelDownload> {
+    let downloads = downloads().lock().unwrap_or_else(|e| e.into_inner());
+    let mut tasks: Vec<ModelDownload> = downloads.values().map(|e| e.task.clone()).collect();
+    tasks.sort_by(|a, b| a.path.cmp(&b.path));
+    tasks
+}
+
+/// 取消下载, 保留 `.part` 文件以便续传
+pub fn cancel(id: &str) -> Result<(), String> {
+    let downloads = downloads().lock().unwrap_or_else(|e| e.into_inner());
+    let entry = downloads.get(id).ok_or_else(|| format!("Download '{}' not found", id))?;
+    entry.cancel.store(true, Ordering::SeqCst);
+    Ok(())
+}
+
+/// 模型目录下已下载完成的 GGUF 文件
+pub fn list_local() -> Result<Vec<LocalModel>, String> {
+    fn walk(dir: &Path, out: &mut Vec<LocalModel>) {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                walk(&path, out);
+            } else if path.extension().is_some_and(|e| e.eq_ignore_ascii_case("gguf")) {
+                out.push(LocalModel {
+                    name: path.file_stem().unwrap_or_default().to_string_lossy().to_string(),
+                    size: entry.metadata().map(|m| m.len()).unwrap_or(0),
+                    path: path.to_string_lossy().to_string(),
+                });
+            }
+        }
+    }
+    let mut models = Vec::new();
+    walk(&models_dir()?, &mut models);
+    models.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(models)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn request(repo: &str, file: &str) -> ModelDownloadRequest {
+        ModelDownloadRequest {
+            repo: repo.to_string(),
+            file: file.to_string(),
+            revision: None,
+            sha256: None,
+            token: None,
+            activate: false,
+        }
+    }
+
+    #[test]
+    fn test_validate_and_paths() {
+        assert!(validate_request(&request("Qwen/Qwen2.5-7B-Instruct-GGUF", "qwen2.5-7b-instruct-q4_k_m.gguf")).is_ok());
+        assert!(validate_request(&request("Qwen", "a.gguf")).is_err());
+        assert!(validate_request(&request("a/b", "../../etc/passwd.gguf")).is_err());
+        assert!(validate_request(&request("a/b", "model.safetensors")).is_err());
+
+        let dest = dest_path(Path::new("/data/models"), "Qwen/Qwen2.5-GGUF", "Q4/model.gguf");
+        assert_eq!(dest, Path::new("/data/models/Qwen--Qwen2.5-GGUF/Q4/model.gguf"));
+        assert_eq!(part_path(&dest), Path::new("/data/models/Qwen--Qwen2.5-GGUF/Q4/model.gguf.part"));
+        assert_eq!(resolve_url("a/b", "main", "/x.gguf"), "https://huggingface.co/a/b/resolve/main/x.gguf");
+    }
+
+    #[test]
+    fn test_metadata_parsing() {
+        let tree = json!([
+            {"type": "file", "path": "README.md", "size": 10},
+            {"type": "file", "path": "Q4/model.gguf", "size": 134, "lfs": {"oid": "abc123", "size": 4_000_000_000u64}}
+        ]);
+        assert_eq!(find_tree_entry(&tree, "Q4/model.gguf"), Some((4_000_000_000, Some("abc123".to_string()))));
+        assert_eq!(find_tree_entry(&tree, "README.md"), Some((10, None)));
+        assert_eq!(find_tree_entry(&tree, "missing.gguf"), None);
+        assert_eq!(parse_content_range_total("bytes 100-999/1000"), Some(1000));
+        assert_eq!(parse_content_range_total("bytes 0-9/*"), None);
+    }
+}
@@ -0,0 +1,116 @@
+// 桌面通知
+// 反代的关键事件 (上游故障、虚拟 Key 额度耗尽、监听证书即将过期、服务自动重启、上游 Key 失效) 以系统通知提示,
+// 可在设置中按类型关闭; 同一事件 (类型 + 对象) 在冷却时间内只提示一次。无界面 (headless) 时只写日志。
+
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock, RwLock};
+use std::time::{Duration, Instant};
+
+use crate::models::DesktopNotificationConfig;
+use crate::modules::integration::SystemManager;
+
+/// 前端监听该事件并调用系统通知
+pub const NOTIFICATION_EVENT: &str = "app://notification";
+const COOLDOWN: Duration = Duration::from_secs(30 * 60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NotificationKind {
+    UpstreamOutage,
+    QuotaExhausted,
+    CertExpiry,
+    ServiceRestarted,
+    UpstreamKey,
+}
+
+impl NotificationKind {
+    fn enabled_in(self, config: &DesktopNotificationConfig) -> bool {
+        config.enabled
+            && match self {
+                NotificationKind::UpstreamOutage => config.upstream_outage,
+                NotificationKind::QuotaExhausted => config.quota_exhausted,
+                NotificationKind::CertExpiry => config.cert_expiry,
+                NotificationKind::ServiceRestarted => config.service_restarted,
+                NotificationKind::UpstreamKey => config.upstream_keys,
+            }
+    }
+}
+
+struct NotifierState {
+    config: RwLock<DesktopNotificationConfig>,
+    integration: RwLock<Option<SystemManager>>,
+    last_shown: Mutex<HashMap<(NotificationKind, String), Instant>>,
+}
+
+impl NotifierState {
+    fn new() -> Self {
+        Self {
+            config: RwLock::new(DesktopNotificationConfig::default()),
+            integration: RwLock::new(None),
+            last_shown: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 已关闭的类型或冷却时间内的重复事件返回 false
+    fn should_show(&self, kind: NotificationKind, subject: &str, now: Instant) -> bool {
+        if !kind.enabled_in(&self.config.read().unwrap_or_else(|e| e.into_inner())) {
+            return false;
+        }
+        let mut last_shown = self.last_shown.lock().unwrap_or_else(|e| e.into_inner());
+        last_shown.retain(|_, at| now.duration_since(*at) < COOLDOWN);
+        last_shown.insert((kind, subject.to_string()), now).is_none()
+    }
+}
+
+fn state() -> &'static NotifierState {
+    static STATE: OnceLock<NotifierState> = OnceLock::new();
+    STATE.get_or_init(NotifierState::new)
+}
+
+/// 应用通知设置 (启动、保存配置与配置文件热加载时调用)
+pub fn configure(config: &DesktopNotificationConfig) {
+    *state().config.write().unwrap_or_else(|e| e.into_inner()) = config.clone();
+}
+
+/// 反代服务启动时登记系统集成层 (桌面 / headless)
+pub fn set_integration(integration: SystemManager) {
+    *state().integration.write().unwrap_or_else(|e| e.into_inner()) = Some(integration);
+}
+
+/// 已登记的系统集成层 (用于向前端推送事件)
+pub fn integration() -> Option<SystemManager> {
+    state().integration.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// 提示一条关键事件; `subject` 为事件对象 (上游名称、Key 名称等), 用于去重
+pub fn notify(kind: NotificationKind, subject: &str, title: &str, body: &str) {
+    let state = state();
+    if !state.should_show(kind, subject, Instant::now()) {
+        return;
+    }
+    let integration = state.integration.read().unwrap_or_else(|e| e.into_inner()).clone();
+    match integration {
+        Some(integration) => integration.show_notification(title, body),
+        None => crate::modules::logger::log_info(&format!("[Notification] {}: {}", title, body)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toggles_and_cooldown() {
+        let state = NotifierState::new();
+        let now = Instant::now();
+        assert!(state.should_show(NotificationKind::UpstreamOutage, "zai", now));
+        assert!(!state.should_show(NotificationKind::UpstreamOutage, "zai", now));
+        assert!(state.should_show(NotificationKind::UpstreamOutage, "audio", now));
+        assert!(state.should_show(NotificationKind::UpstreamOutage, "zai", now + COOLDOWN));
+
+        *state.config.write().unwrap() = DesktopNotificationConfig { cert_expiry: false, ..Default::default() };
+        assert!(!state.should_show(NotificationKind::CertExpiry, "cert.pem", now));
+        assert!(state.should_show(NotificationKind::QuotaExhausted, "ci", now));
+        *state.config.write().unwrap() = DesktopNotificationConfig { enabled: false, ..Default::default() };
+        assert!(!state.should_show(NotificationKind::ServiceRestarted, "proxy", now));
+    }
+}
@@ -0,0 +1,254 @@
+// 启动方案 (profile)
+// 每个方案是叠加在保存的反代配置上的 JSON Merge Patch (上游、别名、限流等) 加上需要运行的外部服务。
+// 配置文件里的 `proxy` 始终是基础配置, 生效配置 = 基础配置 + 当前方案; 切换时先完整校验, 监听地址变化才重建
+// 服务器, 重建失败回滚到原方案, 最后按方案启停外部服务。
+
+use std::sync::{Arc, RwLock};
+
+use serde::Serialize;
+use tracing::{error, warn};
+
+use crate::commands::cloudflared::CloudflaredState;
+use crate::commands::proxy::ProxyServiceState;
+use crate::models::{AppConfig, StartupProfile};
+use crate::modules::integration::SystemManager;
+use crate::proxy::config::ProxyConfig;
+
+/// 前端监听该事件刷新当前方案
+pub const PROFILE_EVENT: &str = "profile://switched";
+/// 命令行中表示不使用方案
+pub const DEFAULT_PROFILE: &str = "default";
+
+static ACTIVE: RwLock<Option<StartupProfile>> = RwLock::new(None);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfileSummary {
+    pub name: String,
+    pub description: String,
+    pub active: bool,
+    /// 方案覆盖的配置字段
+    pub sections: Vec<String>,
+    pub services: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfileSwitched {
+    pub profile: Option<String>,
+    /// 是否重建了监听服务器
+    pub restarted: bool,
+    /// 外部服务启动失败 (不回滚方案)
+    pub service_error: Option<String>,
+}
+
+pub fn find<'a>(config: &'a AppConfig, name: &str) -> Option<&'a StartupProfile> {
+    config.profiles.iter().find(|p| p.name == name)
+}
+
+/// 当前方案 (名称不存在时视为未启用)
+pub fn active(config: &AppConfig) -> Option<&StartupProfile> {
+    config.active_profile.as_deref().and_then(|name| find(config, name))
+}
+
+/// 登记当前方案 (启动、保存配置、热加载与切换时调用)
+pub fn configure(config: &AppConfig) {
+    *ACTIVE.write().unwrap_or_else(|e| e.into_inner()) = active(config).cloned();
+}
+
+/// 基础配置叠加方案 (Merge Patch 可重复应用, 已叠加过的配置结果不变)
+pub fn overlay(base: &ProxyConfig, profile: Option<&StartupProfile>) -> Result<ProxyConfig, String> {
+    match profile {
+        Some(profile) if !profile.proxy.is_null() => crate::proxy::common::config_patch::apply_patch(base, &profile.proxy)
+            .map_err(|e| format!("Profile '{}': {}", profile.name, e)),
+        _ => Ok(base.clone()),
+    }
+}
+
+/// 叠加已登记的方案; 方案失效时退回基础配置
+pub fn effective(base: &ProxyConfig) -> ProxyConfig {
+    let profile = ACTIVE.read().unwrap_or_else(|e| e.into_inner()).clone();
+    overlay(base, profile.as_ref()).unwrap_or_else(|e| {
+        warn!("[Profiles] {}, using the saved proxy settings", e);
+        base.clone()
+    })
+}
+
+/// 保存前校验全部方案
+pub fn validate(config: &AppConfig) -> Result<(), String> {
+    let mut names = std::collections::HashSet::new();
+    for profile in &config.profiles {
+        let name = profile.name.trim();
+        if name.is_empty() || name == DEFAULT_PROFILE {
+            return Err(format!("Invalid profile name '{}'", profile.name));
+        }
+        if !names.insert(name) {
+            return Err(format!("Duplicate profile name '{}'", name));
+        }
+        for id in profile.services.iter().flatten() {
+            if !config.external_services.iter().any(|s| &s.id == id) {
+                return Err(format!("Profile '{}' references unknown service '{}'", name, id));
+            }
+        }
+        overlay(&config.proxy, Some(profile))?;
+    }
+    match &config.active_profile {
+        Some(name) if find(config, name).is_none() => Err(format!("Unknown profile '{}'", name)),
+        _ => Ok(()),
+    }
+}
+
+/// 选择方案后的配置; `None` 或 "default" 表示恢复基础配置
+pub fn select(config: &AppConfig, name: Option<&str>) -> Result<AppConfig, String> {
+    let mut next = config.clone();
+    next.active_profile = name.map(str::trim).filter(|n| !n.is_empty() && *n != DEFAULT_PROFILE).map(str::to_string);
+    if let Some(name) = &next.active_profile {
+        let profile = find(config, name).ok_or_else(|| format!("Unknown profile '{}'", name))?;
+        overlay(&config.proxy, Some(profile))?;
+    }
+    Ok(next)
+}
+
+pub fn summaries(config: &AppConfig) -> Vec<ProfileSummary> {
+    config
+        .profiles
+        .iter()
+        .map(|p| ProfileSummary {
+            name: p.name.clone(),
+            description: p.description.clone(),
+            active: config.active_profile.as_deref() == Some(p.name.as_str()),
+            sections: p.proxy.as_object().map(|o| o.keys().cloned().collect()).unwrap_or_default(),
+            services: p.services.clone(),
+        })
+        .collect()
+}
+
+/// 生效的方案是否变化 (切换或修改了当前方案)
+pub fn changed(previous: &AppConfig, next: &AppConfig) -> bool {
+    active(previous) != active(next)
+}
+
+/// 无法热更新、需要重建服务器的设置是否变化
+pub fn needs_restart(old: &ProxyConfig, new: &ProxyConfig) -> bool {
+    let listen = |c: &ProxyConfig| {
+        serde_json::json!([c.get_bind_address(), c.port, c.port_fallback, c.listeners, c.listener_tls, c.mdns])
+    };
+    listen(old) != listen(new)
+}
+
+/// 在运行中的应用里生效方案变化: 热更新配置, 需要时重建服务器 (失败时回滚并返回错误), 最后启停外部服务
+pub async fn apply(
+    previous: &AppConfig,
+    next: &AppConfig,
+    state: &ProxyServiceState,
+    integration: SystemManager,
+    cloudflared_state: Arc<CloudflaredState>,
+) -> Result<ProfileSwitched, String> {
+    let old = overlay(&previous.proxy, active(previous))?;
+    let new = overlay(&next.proxy, active(next))?;
+    configure(next);
+
+    let server = state.admin_server.read().await.as_ref().map(|a| a.axum_server.clone());
+    let restarted = server.is_some() && needs_restart(&old, &new);
+    if restarted {
+        let restart = crate::commands::proxy::restart_admin_server(new, state, integration.clone(), cloudflared_state.clone());
+        if let Err(e) = restart.await {
+            configure(previous);
+            let rollback = crate::commands::proxy::restart_admin_server(old, state, integration.clone(), cloudflared_state);
+            if let Err(rollback) = rollback.await {
+                error!("[Profiles] Failed to restore the previous listener: {}", rollback);
+            }
+            return Err(format!("Failed to restart the proxy with the new profile: {}", e));
+        }
+    } else if let Some(server) = server {
+        if let Err(e) = server.apply_config(&new).await {
+            configure(previous);
+            return Err(format!("Failed to apply the new profile: {}", e));
+        }
+        if let Some(instance) = state.instance.write().await.as_mut() {
+            instance.config = new;
+        }
+    }
+
+    let mut service_error = None;
+    if let (SystemManager::Desktop(app), Some(wanted)) = (&integration, active(next).and_then(|p| p.services.as_ref())) {
+        if let Err(e) = crate::modules::service_manager::reconcile(app, &next.external_services, wanted).await {
+            warn!("[Profiles] {}", e);
+            service_error = Some(e);
+        }
+    }
+    let switched = ProfileSwitched { profile: active(next).map(|p| p.name.clone()), restarted, service_error };
+    integration.emit(PROFILE_EVENT, &switched);
+    Ok(switched)
+}
+
+/// 启动时拉起外部服务: 当前方案指定了服务时按方案, 否则按 `autostart`
+pub async fn start_services(app: &tauri::AppHandle, config: &AppConfig) {
+    match active(config).and_then(|p| p.services.as_ref()) {
+        Some(wanted) => {
+            if let Err(e) = crate::modules::service_manager::reconcile(app, &config.external_services, wanted).await {
+                warn!("[Profiles] {}", e);
+            }
+        }
+        None => crate::modules::service_manager::start_autostart(app, &config.external_services).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn config_with_profiles() -> AppConfig {
+        let mut config = AppConfig::new();
+        config.profiles = vec![
+            StartupProfile {
+                name: "demo".to_string(),
+                description: String::new(),
+                proxy: json!({ "port": 9045, "model_aliases": [{ "pattern": "fast", "target": "gemini-2.5-flash" }] }),
+                services: None,
+            },
+            StartupProfile {
+                name: "local-only".to_string(),
+                description: String::new(),
+                proxy: json!({ "request_timeout": 600 }),
+                services: Some(Vec::new()),
+            },
+        ];
+        config
+    }
+
+    #[test]
+    fn test_overlay_and_select() {
+        let config = config_with_profiles();
+        assert!(validate(&config).is_ok());
+
+        let demo = select(&config, Some("demo")).unwrap();
+        let proxy = overlay(&demo.proxy, active(&demo)).unwrap();
+        assert_eq!(proxy.port, 9045);
+        assert_eq!(proxy.model_aliases[0].target, "gemini-2.5-flash");
+        // 重复叠加结果不变, 保存的基础配置未被修改
+        assert_eq!(overlay(&proxy, active(&demo)).unwrap().port, 9045);
+        assert_eq!(demo.proxy.port, config.proxy.port);
+        assert!(needs_restart(&config.proxy, &proxy));
+
+        let local = select(&config, Some("local-only")).unwrap();
+        assert!(!needs_restart(&config.proxy, &overlay(&local.proxy, active(&local)).unwrap()));
+        assert!(changed(&demo, &local));
+        assert_eq!(select(&demo, Some(DEFAULT_PROFILE)).unwrap().active_profile, None);
+        assert!(select(&config, Some("work")).is_err());
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut config = config_with_profiles();
+        config.profiles[1].services = Some(vec!["ollama".to_string()]);
+        assert!(validate(&config).unwrap_err().contains("unknown service"));
+
+        let mut config = config_with_profiles();
+        config.profiles[1].name = "demo".to_string();
+        assert!(validate(&config).unwrap_err().contains("Duplicate"));
+
+        let mut config = config_with_profiles();
+        config.profiles[0].proxy = json!({ "port": "not a port" });
+        assert!(validate(&config).is_err());
+    }
+}
@@ -49,6 +49,13 @@ pub fn init_db() -> Result<(), String> {
     let _ = conn.execute("ALTER TABLE request_logs ADD COLUMN account_email TEXT", []);
     let _ = conn.execute("ALTER TABLE request_logs ADD COLUMN mapped_model TEXT", []);
     let _ = conn.execute("ALTER TABLE request_logs ADD COLUMN protocol TEXT", []);
+    let _ = conn.execute("ALTER TABLE request_logs ADD COLUMN param_adjustments TEXT", []);
+    let _ = conn.execute("ALTER TABLE request_logs ADD COLUMN client_name TEXT", []);
+    let _ = conn.execute("ALTER TABLE request_logs ADD COLUMN user_agent TEXT", []);
+    let _ = conn.execute("ALTER TABLE request_logs ADD COLUMN client_port INTEGER", []);
+    let _ = conn.execute("ALTER TABLE request_logs ADD COLUMN tokens_per_second REAL", []);
+    let _ = conn.execute("ALTER TABLE request_logs ADD COLUMN cache_read_tokens INTEGER", []);
+    let _ = conn.execute("ALTER TABLE request_logs ADD COLUMN cache_write_tokens INTEGER", []);
 
     conn.execute(
         "CREATE INDEX IF NOT EXISTS idx_timestamp ON request_logs (timestamp DESC)",
@@ -68,8 +75,8 @@ pub fn save_log(log: &ProxyRequestLog) -> Result<(), String> {
     let conn = connect_db()?;
 
     conn.execute(
-        "INSERT INTO request_logs (id, timestamp, method, url, status, duration, model, error, request_body, response_body, input_tokens, output_tokens, account_email, mapped_model, protocol)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
+        "INSERT INTO request_logs (id, timestamp, method, url, status, duration, model, error, request_body, response_body, input_tokens, output_tokens, account_email, mapped_model, protocol, param_adjustments, client_name, user_agent, client_port, tokens_per_second, cache_read_tokens, cache_write_tokens)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22)",
         params![
             log.id,
             log.timestamp,
@@ -86,6 +93,13 @@ pub fn save_log(log: &ProxyRequestLog) -> Result<(), String> {
             log.account_email,
             log.mapped_model,
             log.protocol,
+            log.param_adjustments,
+            log.client_name,
+            log.user_agent,
+            log.client_port,
+            log.tokens_per_second,
+            log.cache_read_tokens,
+            log.cache_write_tokens,
         ],
     ).map_err(|e| e.to_string())?;
 
@@ -99,7 +113,7 @@ pub fn get_logs_summary(limit: usize, offset: usize) -> Result<Vec<ProxyRequestL
     let mut stmt = conn.prepare(
         "SELECT id, timestamp, method, url, status, duration, model, error, 
                 NULL as request_body, NULL as response_body,
-                input_tokens, output_tokens, account_email, mapped_model, protocol
+                input_tokens, output_tokens, account_email, mapped_model, protocol, param_adjustments, client_name, user_agent, client_port, tokens_per_second, cache_read_tokens, cache_write_tokens
          FROM request_logs 
          ORDER BY timestamp DESC 
          LIMIT ?1 OFFSET ?2"
@@ -122,6 +136,13 @@ pub fn get_logs_summary(limit: usize, offset: usize) -> Result<Vec<ProxyRequestL
             input_tokens: row.get(10).unwrap_or(None),
             output_tokens: row.get(11).unwrap_or(None),
             protocol: row.get(14).unwrap_or(None),
+            param_adjustments: row.get(15).unwrap_or(None),
+            client_name: row.get(16).unwrap_or(None),
+            user_agent: row.get(17).unwrap_or(None),
+            client_port: row.get(18).unwrap_or(None),
+            tokens_per_second: row.get(19).unwrap_or(None),
+            cache_read_tokens: row.get(20).unwrap_or(None),
+            cache_write_tokens: row.get(21).unwrap_or(None),
         })
     }).map_err(|e| e.to_string())?;
 
@@ -155,6 +176,7 @@ pub fn get_stats() -> Result<crate::proxy::monitor::ProxyStats, String> {
         total_requests,
         success_count,
         error_count,
+        ..Default::default()
     })
 }
 
@@ -165,7 +187,7 @@ pub fn get_log_detail(log_id: &str) -> Result<ProxyRequestLog, String> {
     let mut stmt = conn.prepare(
         "SELECT id, timestamp, method, url, status, duration, model, error, 
                 request_body, response_body, input_tokens, output_tokens, 
-                account_email, mapped_model, protocol
+                account_email, mapped_model, protocol, param_adjustments, client_name, user_agent, client_port, tokens_per_second, cache_read_tokens, cache_write_tokens
          FROM request_logs 
          WHERE id = ?1"
     ).map_err(|e| e.to_string())?;
@@ -187,6 +209,13 @@ pub fn get_log_detail(log_id: &str) -> Result<ProxyRequestLog, String> {
             input_tokens: row.get(10).unwrap_or(None),
             output_tokens: row.get(11).unwrap_or(None),
             protocol: row.get(14).unwrap_or(None),
+            param_adjustments: row.get(15).unwrap_or(None),
+            client_name: row.get(16).unwrap_or(None),
+            user_agent: row.get(17).unwrap_or(None),
+            client_port: row.get(18).unwrap_or(None),
+            tokens_per_second: row.get(19).unwrap_or(None),
+            cache_read_tokens: row.get(20).unwrap_or(None),
+            cache_write_tokens: row.get(21).unwrap_or(None),
         })
     }).map_err(|e| e.to_string())
 }
@@ -245,7 +274,7 @@ pub fn get_logs_count() -> Result<u64, String> {
 }
 
 /// Get count of logs matching search filter
-/// filter: search text to match in url, method, model, or status
+/// filter: search text to match in url, method, model, status, account or client name
 /// errors_only: if true, only count logs with status < 200 or >= 400
 pub fn get_logs_count_filtered(filter: &str, errors_only: bool) -> Result<u64, String> {
     let conn = connect_db()?;
@@ -258,7 +287,7 @@ pub fn get_logs_count_filtered(filter: &str, errors_only: bool) -> Result<u64, S
         "SELECT COUNT(*) FROM request_logs"
     } else {
         "SELECT COUNT(*) FROM request_logs WHERE
-            (url LIKE ?1 OR method LIKE ?1 OR model LIKE ?1 OR CAST(status AS TEXT) LIKE ?1 OR account_email LIKE ?1)"
+            (url LIKE ?1 OR method LIKE ?1 OR model LIKE ?1 OR CAST(status AS TEXT) LIKE ?1 OR account_email LIKE ?1 OR client_name LIKE ?1)"
     };
     
     let count: u64 = if filter.is_empty() && !errors_only {
@@ -273,7 +302,7 @@ pub fn get_logs_count_filtered(filter: &str, errors_only: bool) -> Result<u64, S
 }
 
 /// Get logs with search filter and pagination
-/// filter: search text to match in url, method, model, or status
+/// filter: search text to match in url, method, model, status, account or client name
 /// errors_only: if true, only return logs with status < 200 or >= 400
 pub fn get_logs_filtered(filter: &str, errors_only: bool, limit: usize, offset: usize) -> Result<Vec<ProxyRequestLog>, String> {
     let conn = connect_db()?;
@@ -283,7 +312,7 @@ pub fn get_logs_filtered(filter: &str, errors_only: bool, limit: usize, offset:
     let sql = if errors_only {
         "SELECT id, timestamp, method, url, status, duration, model, error, 
                 NULL as request_body, NULL as response_body,
-                input_tokens, output_tokens, account_email, mapped_model, protocol
+                input_tokens, output_tokens, account_email, mapped_model, protocol, param_adjustments, client_name, user_agent, client_port, tokens_per_second, cache_read_tokens, cache_write_tokens
          FROM request_logs 
          WHERE (status < 200 OR status >= 400)
          ORDER BY timestamp DESC 
@@ -291,16 +320,16 @@ pub fn get_logs_filtered(filter: &str, errors_only: bool, limit: usize, offset:
     } else if filter.is_empty() {
         "SELECT id, timestamp, method, url, status, duration, model, error, 
                 NULL as request_body, NULL as response_body,
-                input_tokens, output_tokens, account_email, mapped_model, protocol
+                input_tokens, output_tokens, account_email, mapped_model, protocol, param_adjustments, client_name, user_agent, client_port, tokens_per_second, cache_read_tokens, cache_write_tokens
          FROM request_logs 
          ORDER BY timestamp DESC 
          LIMIT ?1 OFFSET ?2"
     } else {
         "SELECT id, timestamp, method, url, status, duration, model, error, 
                 NULL as request_body, NULL as response_body,
-                input_tokens, output_tokens, account_email, mapped_model, protocol
+                input_tokens, output_tokens, account_email, mapped_model, protocol, param_adjustments, client_name, user_agent, client_port, tokens_per_second, cache_read_tokens, cache_write_tokens
          FROM request_logs 
-         WHERE (url LIKE ?3 OR method LIKE ?3 OR model LIKE ?3 OR CAST(status AS TEXT) LIKE ?3 OR account_email LIKE ?3)
+         WHERE (url LIKE ?3 OR method LIKE ?3 OR model LIKE ?3 OR CAST(status AS TEXT) LIKE ?3 OR account_email LIKE ?3 OR client_name LIKE ?3)
          ORDER BY timestamp DESC 
          LIMIT ?1 OFFSET ?2"
     };
@@ -324,6 +353,13 @@ pub fn get_logs_filtered(filter: &str, errors_only: bool, limit: usize, offset:
                 input_tokens: row.get(10).unwrap_or(None),
                 output_tokens: row.get(11).unwrap_or(None),
                 protocol: row.get(14).unwrap_or(None),
+                param_adjustments: row.get(15).unwrap_or(None),
+                client_name: row.get(16).unwrap_or(None),
+                user_agent: row.get(17).unwrap_or(None),
+                client_port: row.get(18).unwrap_or(None),
+                tokens_per_second: row.get(19).unwrap_or(None),
+            cache_read_tokens: row.get(20).unwrap_or(None),
+            cache_write_tokens: row.get(21).unwrap_or(None),
             })
         }).map_err(|e| e.to_string())?;
         logs_iter.filter_map(|r| r.ok()).collect()
@@ -346,6 +382,13 @@ pub fn get_logs_filtered(filter: &str, errors_only: bool, limit: usize, offset:
                 input_tokens: row.get(10).unwrap_or(None),
                 output_tokens: row.get(11).unwrap_or(None),
                 protocol: row.get(14).unwrap_or(None),
+                param_adjustments: row.get(15).unwrap_or(None),
+                client_name: row.get(16).unwrap_or(None),
+                user_agent: row.get(17).unwrap_or(None),
+                client_port: row.get(18).unwrap_or(None),
+                tokens_per_second: row.get(19).unwrap_or(None),
+            cache_read_tokens: row.get(20).unwrap_or(None),
+            cache_write_tokens: row.get(21).unwrap_or(None),
             })
         }).map_err(|e| e.to_string())?;
         logs_iter.filter_map(|r| r.ok()).collect()
@@ -368,6 +411,13 @@ pub fn get_logs_filtered(filter: &str, errors_only: bool, limit: usize, offset:
                 input_tokens: row.get(10).unwrap_or(None),
                 output_tokens: row.get(11).unwrap_or(None),
                 protocol: row.get(14).unwrap_or(None),
+                param_adjustments: row.get(15).unwrap_or(None),
+                client_name: row.get(16).unwrap_or(None),
+                user_agent: row.get(17).unwrap_or(None),
+                client_port: row.get(18).unwrap_or(None),
+                tokens_per_second: row.get(19).unwrap_or(None),
+            cache_read_tokens: row.get(20).unwrap_or(None),
+            cache_write_tokens: row.get(21).unwrap_or(None),
             })
         }).map_err(|e| e.to_string())?;
         logs_iter.filter_map(|r| r.ok()).collect()
@@ -383,7 +433,7 @@ pub fn get_all_logs_for_export() -> Result<Vec<ProxyRequestLog>, String> {
     let mut stmt = conn.prepare(
         "SELECT id, timestamp, method, url, status, duration, model, error, 
                 request_body, response_body, input_tokens, output_tokens, 
-                account_email, mapped_model, protocol
+                account_email, mapped_model, protocol, param_adjustments, client_name, user_agent, client_port, tokens_per_second, cache_read_tokens, cache_write_tokens
          FROM request_logs 
          ORDER BY timestamp DESC"
     ).map_err(|e| e.to_string())?;
@@ -405,6 +455,13 @@ pub fn get_all_logs_for_export() -> Result<Vec<ProxyRequestLog>, String> {
             input_tokens: row.get(10).unwrap_or(None),
             output_tokens: row.get(11).unwrap_or(None),
             protocol: row.get(14).unwrap_or(None),
+            param_adjustments: row.get(15).unwrap_or(None),
+            client_name: row.get(16).unwrap_or(None),
+            user_agent: row.get(17).unwrap_or(None),
+            client_port: row.get(18).unwrap_or(None),
+            tokens_per_second: row.get(19).unwrap_or(None),
+            cache_read_tokens: row.get(20).unwrap_or(None),
+            cache_write_tokens: row.get(21).unwrap_or(None),
         })
     }).map_err(|e| e.to_string())?;
 
@@ -429,7 +486,7 @@ pub fn get_logs_by_ids(ids: &[String]) -> Result<Vec<ProxyRequestLog>, String> {
     let sql = format!(
         "SELECT id, timestamp, method, url, status, duration, model, error, 
                 request_body, response_body, input_tokens, output_tokens, 
-                account_email, mapped_model, protocol
+                account_email, mapped_model, protocol, param_adjustments, client_name, user_agent, client_port, tokens_per_second, cache_read_tokens, cache_write_tokens
          FROM request_logs 
          WHERE id IN ({})
          ORDER BY timestamp DESC",
@@ -458,6 +515,13 @@ pub fn get_logs_by_ids(ids: &[String]) -> Result<Vec<ProxyRequestLog>, String> {
             input_tokens: row.get(10).unwrap_or(None),
             output_tokens: row.get(11).unwrap_or(None),
             protocol: row.get(14).unwrap_or(None),
+            param_adjustments: row.get(15).unwrap_or(None),
+            client_name: row.get(16).unwrap_or(None),
+            user_agent: row.get(17).unwrap_or(None),
+            client_port: row.get(18).unwrap_or(None),
+            tokens_per_second: row.get(19).unwrap_or(None),
+            cache_read_tokens: row.get(20).unwrap_or(None),
+            cache_write_tokens: row.get(21).unwrap_or(None),
         })
     }).map_err(|e| e.to_string())?;
 
@@ -0,0 +1,294 @@
+// 本地后端资源监控
+// 定期采样 CPU / 内存 / 显卡显存 (NVIDIA 经 nvidia-smi, AMD 经 Linux sysfs) 以及 llama-server 进程占用,
+// 通过事件推送给前端; 只在前端开启监控时采样。加载本地模型前可按模型文件大小估算能否装入显存 / 内存。
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock, RwLock};
+use std::time::Duration;
+
+use serde::Serialize;
+use sysinfo::{Pid, ProcessesToUpdate, System};
+use tauri::Emitter;
+use tokio::sync::Notify;
+
+/// 前端监听该事件显示实时占用
+pub const RESOURCES_EVENT: &str = "system://resources";
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(2);
+/// 每个上下文 token 的 KV 缓存粗略估算 (7B 级 GQA 模型, f16)
+const KV_BYTES_PER_TOKEN: u64 = 128 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GpuInfo {
+    pub index: u32,
+    pub name: String,
+    pub vendor: String,
+    pub memory_total: u64,
+    pub memory_used: u64,
+    /// 百分比
+    pub utilization: Option<f32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProcessUsage {
+    pub pid: u32,
+    pub cpu_percent: f32,
+    pub memory: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ResourceSnapshot {
+    pub timestamp: i64,
+    pub cpu_percent: f32,
+    pub cpu_cores: usize,
+    pub memory_total: u64,
+    pub memory_used: u64,
+    pub memory_available: u64,
+    pub gpus: Vec<GpuInfo>,
+    /// 托管的 llama-server 进程
+    pub llama_server: Option<ProcessUsage>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelFit {
+    pub model_bytes: u64,
+    /// 模型 + KV 缓存 + 运行时开销的估算
+    pub required_bytes: u64,
+    pub gpu_free: Option<u64>,
+    pub memory_available: u64,
+    pub fits_gpu: bool,
+    pub fits_memory: bool,
+    pub warning: Option<String>,
+}
+
+/// 解析 `nvidia-smi --query-gpu=index,name,memory.total,memory.used,utilization.gpu --format=csv,noheader,nounits`
+pub fn parse_nvidia_smi(output: &str) -> Vec<GpuInfo> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            if fields.len() < 5 {
+                return None;
+            }
+            let mib = |v: &str| v.parse::<u64>().ok().map(|m| m * 1024 * 1024);
+            Some(GpuInfo {
+                index: fields[0].parse().ok()?,
+                name: fields[1].to_string(),
+                vendor: "nvidia".to_string(),
+                memory_total: mib(fields[2])?,
+                memory_used: mib(fields[3])?,
+                utilization: fields[4].parse().ok(),
+            })
+        })
+        .collect()
+}
+
+async fn nvidia_gpus() -> Vec<GpuInfo> {
+    let mut cmd = tokio::process::Command::new("nvidia-smi");
+    cmd.args(["--query-gpu=index,name,memory.total,memory.used,utilization.gpu", "--format=csv,noheader,nounits"])
+        .kill_on_drop(true);
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        cmd.creation_flags(0x08000000);
+    }
+    match tokio::time::timeout(Duration::from_secs(5), cmd.output()).await {
+        Ok(Ok(output)) if output.status.success() => parse_nvidia_smi(&String::from_utf8_lossy(&output.stdout)),
+        _ => Vec::new(),
+    }
+}
+
+/// amdgpu 驱动在 sysfs 中提供显存用量
+#[cfg(target_os = "linux")]
+fn amd_gpus(first_index: u32) -> Vec<GpuInfo> {
+    let read = |path: std::path::PathBuf| std::fs::read_to_string(path).ok().map(|s| s.trim().to_string());
+    let Ok(entries) = std::fs::read_dir("/sys/class/drm") else {
+        return Vec::new();
+    };
+    let mut cards: Vec<_> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.file_name().and_then(|n| n.to_str()).is_some_and(|n| n.starts_with("card") && !n.contains('-')))
+        .collect();
+    cards.sort();
+    cards
+        .into_iter()
+        .filter_map(|card| {
+            let device = card.join("device");
+            let total = read(device.join("mem_info_vram_total"))?.parse().ok()?;
+            let used = read(device.join("mem_info_vram_used"))?.parse().ok()?;
+            let name = read(device.join("product_name")).unwrap_or_else(|| "AMD GPU".to_string());
+            Some((name, total, used, read(device.join("gpu_busy_percent")).and_then(|v| v.parse().ok())))
+        })
+        .enumerate()
+        .map(|(i, (name, memory_total, memory_used, utilization))| GpuInfo {
+            index: first_index + i as u32,
+            name,
+            vendor: "amd".to_string(),
+            memory_total,
+            memory_used,
+            utilization,
+        })
+        .collect()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn amd_gpus(_first_index: u32) -> Vec<GpuInfo> {
+    Vec::new()
+}
+
+/// 估算模型能否装入: `gpu_layers` > 0 时按整个模型放入单张显卡计算 (无法得知层数, 偏保守)
+pub fn estimate_fit(model_bytes: u64, ctx_size: u32, gpu_layers: Option<i32>, snapshot: &ResourceSnapshot) -> ModelFit {
+    let required = model_bytes + model_bytes / 10 + ctx_size as u64 * KV_BYTES_PER_TOKEN;
+    let gpu_free = snapshot.gpus.iter().map(|g| g.memory_total.saturating_sub(g.memory_used)).max();
+    let fits_gpu = gpu_free.is_some_and(|free| free >= required);
+    let fits_memory = snapshot.memory_available >= required;
+    let gb = |b: u64| b as f64 / 1e9;
+    let offload = gpu_layers.is_some_and(|l| l != 0);
+    let warning = if offload && gpu_free.is_none() {
+        Some("GPU offload is configured but no GPU was detected; the model will run on CPU".to_string())
+    } else if offload && !fits_gpu && fits_memory {
+        Some(format!(
+            "About {:.1} GB is needed but only {:.1} GB of VRAM is free; reduce gpu_layers to offload part of the model",
+            gb(required),
+            gb(gpu_free.unwrap_or_default())
+        ))
+    } else if !(fits_memory || (offload && fits_gpu)) {
+        Some(format!("About {:.1} GB is needed but only {:.1} GB of memory is available", gb(required), gb(snapshot.memory_available)))
+    } else {
+        None
+    };
+    ModelFit {
+        model_bytes,
+        required_bytes: required,
+        gpu_free,
+        memory_available: snapshot.memory_available,
+        fits_gpu,
+        fits_memory,
+        warning,
+    }
+}
+
+struct Monitor {
+    system: Mutex<System>,
+    latest: RwLock<Option<ResourceSnapshot>>,
+    enabled: AtomicBool,
+    started: AtomicBool,
+    changed: Notify,
+}
+
+fn monitor() -> &'static Monitor {
+    static INSTANCE: OnceLock<Monitor> = OnceLock::new();
+    INSTANCE.get_or_init(|| Monitor {
+        system: Mutex::new(System::new()),
+        latest: RwLock::new(None),
+        enabled: AtomicBool::new(false),
+        started: AtomicBool::new(false),
+        changed: Notify::new(),
+    })
+}
+
+/// 采样一次 (CPU 占用需要两次刷新之间的差值, 首次结果为 0)
+pub async fn sample() -> ResourceSnapshot {
+    let monitor = monitor();
+    let pid = crate::proxy::llama_server::LlamaServer::global().status().pid;
+    let mut snapshot = {
+        let mut system = monitor.system.lock().unwrap_or_else(|e| e.into_inner());
+        system.refresh_cpu_usage();
+        system.refresh_memory();
+        let llama_server = pid.and_then(|pid| {
+            let pid = Pid::from_u32(pid);
+            system.refresh_processes(ProcessesToUpdate::Some(&[pid]));
+            system.process(pid).map(|p| ProcessUsage { pid: pid.as_u32(), cpu_percent: p.cpu_usage(), memory: p.memory() })
+        });
+        ResourceSnapshot {
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            cpu_percent: system.global_cpu_usage(),
+            cpu_cores: system.cpus().len(),
+            memory_total: system.total_memory(),
+            memory_used: system.used_memory(),
+            memory_available: system.available_memory(),
+            gpus: Vec::new(),
+            llama_server,
+        }
+    };
+    let mut gpus = nvidia_gpus().await;
+    gpus.extend(amd_gpus(gpus.len() as u32));
+    snapshot.gpus = gpus;
+    *monitor.latest.write().unwrap_or_else(|e| e.into_inner()) = Some(snapshot.clone());
+    snapshot
+}
+
+/// 最近一次采样; 尚未采样时立即采样
+pub async fn latest() -> ResourceSnapshot {
+    let cached = monitor().latest.read().unwrap_or_else(|e| e.into_inner()).clone();
+    match cached {
+        Some(snapshot) => snapshot,
+        None => sample().await,
+    }
+}
+
+/// 开启 / 关闭定期采样 (采样任务只创建一次, 关闭时挂起)
+pub fn set_enabled(app: tauri::AppHandle, enabled: bool) {
+    let monitor = monitor();
+    monitor.enabled.store(enabled, Ordering::SeqCst);
+    monitor.changed.notify_one();
+    if !enabled || monitor.started.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    tauri::async_runtime::spawn(async move {
+        loop {
+            if !monitor.enabled.load(Ordering::SeqCst) {
+                monitor.changed.notified().await;
+                continue;
+            }
+            let snapshot = sample().await;
+            let _ = app.emit(RESOURCES_EVENT, &snapshot);
+            tokio::select! {
+                _ = tokio::time::sleep(SAMPLE_INTERVAL) => {}
+                _ = monitor.changed.notified() => {}
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_nvidia_smi() {
+        let output = "0, NVIDIA GeForce RTX 4090, 24564, 1024, 7\n1, Tesla T4, 15360, 0, [N/A]\ngarbage\n";
+        let gpus = parse_nvidia_smi(output);
+        assert_eq!(gpus.len(), 2);
+        assert_eq!(gpus[0].name, "NVIDIA GeForce RTX 4090");
+        assert_eq!(gpus[0].memory_total, 24564 * 1024 * 1024);
+        assert_eq!(gpus[0].utilization, Some(7.0));
+        assert_eq!(gpus[1].utilization, None);
+    }
+
+    #[test]
+    fn test_estimate_fit() {
+        let gb = 1_000_000_000u64;
+        let snapshot = ResourceSnapshot {
+            memory_available: 16 * gb,
+            gpus: vec![GpuInfo {
+                index: 0,
+                name: "GPU".to_string(),
+                vendor: "nvidia".to_string(),
+                memory_total: 8 * gb,
+                memory_used: gb,
+                utilization: None,
+            }],
+            ..Default::default()
+        };
+        let small = estimate_fit(4 * gb, 4096, Some(99), &snapshot);
+        assert!(small.fits_gpu && small.fits_memory && small.warning.is_none());
+        let large = estimate_fit(9 * gb, 4096, Some(99), &snapshot);
+        assert!(!large.fits_gpu && large.fits_memory);
+        assert!(large.warning.unwrap().contains("gpu_layers"));
+        let huge = estimate_fit(20 * gb, 4096, None, &snapshot);
+        assert!(!huge.fits_memory && huge.warning.is_some());
+        let no_gpu = estimate_fit(gb, 4096, Some(10), &ResourceSnapshot { memory_available: 16 * gb, ..Default::default() });
+        assert!(no_gpu.warning.unwrap().contains("no GPU"));
+    }
+}
@@ -0,0 +1,675 @@
+// 上游密钥加密存储
+// 保存配置时 gui_config.json 中的上游 API Key / 密码替换为 `secret://<字段路径>` 引用, 密文保存在 secrets.json
+// (AES-256-GCM, 字段路径作为附加数据); 主密钥优先放在系统钥匙串 (Windows 凭据管理器 / macOS Keychain /
+// Secret Service), 不可用时放在数据目录的 secrets.key。加载配置时自动解密, 已有的明文密钥会在下次加载时迁移。
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use rand::RngCore;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::proxy::config::SecretStorageMode;
+
+const SECRETS_FILE: &str = "secrets.json";
+const KEY_FILE: &str = "secrets.key";
+pub const REF_PREFIX: &str = "secret://";
+/// 按字段名识别的密钥字段 (反代自身的 `proxy.api_key` 除外, 客户端需要直接查看)
+const SECRET_FIELDS: &[&str] = &["api_key", "secret_access_key", "session_token", "password", "hmac_secret", "secret", "passphrase"];
+const EXCLUDED_PATHS: &[&str] = &["proxy.api_key"];
+
+/// 主密钥的保存位置
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyBackend {
+    Keychain,
+    File,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SecretEntry {
+    /// base64(nonce || 密文 || tag)
+    value: String,
+    hint: String,
+    updated_at: i64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SecretsFile {
+    #[serde(default)]
+    backend: Option<KeyBackend>,
+    #[serde(default)]
+    entries: BTreeMap<String, SecretEntry>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SecretInfo {
+    /// 配置字段路径, 例如 `proxy.zai.api_key`
+    pub name: String,
+    pub hint: String,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SecretStorageStatus {
+    pub mode: SecretStorageMode,
+    /// 当前主密钥所在位置, 尚未保存过密钥时为 None
+    pub backend: Option<KeyBackend>,
+    pub keychain_available: bool,
+    pub secrets: Vec<SecretInfo>,
+}
+
+/// 进程内缓存的主密钥, 避免每次加载配置都访问钥匙串
+static MASTER_KEY: Mutex<Option<(KeyBackend, [u8; 32])>> = Mutex::new(None);
+
+fn secrets_path(dir: &Path) -> PathBuf {
+    dir.join(SECRETS_FILE)
+}
+
+fn mask(value: &str) -> String {
+    let chars: Vec<char> = value.chars().collect();
+    if chars.len() <= 8 {
+        return "****".to_string();
+    }
+    let head: String = chars[..4].iter().collect();
+    let tail: String = chars[chars.len() - 4..].iter().collect();
+    format!("{}****{}", head, tail)
+}
+
+/// 写入仅当前用户可读的文件
+fn write_private(path: &Path, content: &[u8]) -> Result<(), String> {
+    std::fs::write(path, content).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let _ = std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600));
+    }
+    Ok(())
+}
+
+fn read_file(dir: &Path) -> SecretsFile {
+    std::fs::read_to_string(secrets_path(dir))
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+/// 先写临时文件再替换, 避免中断时留下半个密文文件
+fn write_atomic(dir: &Path, content: &[u8]) -> Result<(), String> {
+    let path = secrets_path(dir);
+    let tmp_path = path.with_extension("json.tmp");
+    write_private(&tmp_path, content)?;
+    std::fs::rename(&tmp_path, &path).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn write_file(dir: &Path, file: &SecretsFile) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(file).map_err(|e| e.to_string())?;
+    write_atomic(dir, content.as_bytes())
+}
+
+fn new_key() -> [u8; 32] {
+    let mut key = [0u8; 32];
+    rand::rngs::OsRng.fill_bytes(&mut key);
+    key
+}
+
+fn decode_key(encoded: &str) -> Result<[u8; 32], String> {
+    STANDARD
+        .decode(encoded.trim())
+        .ok()
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+        .ok_or_else(|| "Stored master key is malformed".to_string())
+}
+
+/// 读取主密钥; `create` 为 true 时不存在则生成并保存
+fn load_key(dir: &Path, backend: KeyBackend, create: bool) -> Result<Option<[u8; 32]>, String> {
+    let stored = match backend {
+        KeyBackend::Keychain => keychain::get()?,
+        KeyBackend::File => std::fs::read_to_string(dir.join(KEY_FILE)).ok(),
+    };
+    if let Some(encoded) = stored {
+        return decode_key(&encoded).map(Some);
+    }
+    if !create {
+        return Ok(None);
+    }
+    let key = new_key();
+    store_key(dir, backend, &key)?;
+    Ok(Some(key))
+}
+
+fn store_key(dir: &Path, backend: KeyBackend, key: &[u8; 32]) -> Result<(), String> {
+    let encoded = STANDARD.encode(key);
+    match backend {
+        KeyBackend::Keychain => keychain::set(&encoded),
+        KeyBackend::File => write_private(&dir.join(KEY_FILE), encoded.as_bytes()),
+    }
+}
+
+/// 按存储模式选择主密钥; Auto 在钥匙串不可用时退回文件
+fn master_key(dir: &Path, mode: SecretStorageMode, current: Option<KeyBackend>) -> Result<(KeyBackend, [u8; 32]), String> {
+    let mut cache = MASTER_KEY.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some((backend, key)) = *cache {
+        if current.is_none_or(|c| c == backend) {
+            return Ok((backend, key));
+        }
+    }
+    // 已有密文时只读取原来的主密钥, 读取失败不能生成新密钥覆盖
+    let candidates = match (current, mode) {
+        (Some(backend), _) => vec![backend],
+        (None, SecretStorageMode::File) => vec![KeyBackend::File],
+        (None, SecretStorageMode::Keychain) => vec![KeyBackend::Keychain],
+        (None, _) => vec![KeyBackend::Keychain, KeyBackend::File],
+    };
+    let mut last_error = String::new();
+    for backend in candidates {
+        match load_key(dir, backend, current.is_none()) {
+            Ok(Some(key)) => {
+                *cache = Some((backend, key));
+                return Ok((backend, key));
+            }
+            Ok(None) => last_error = format!("master key not found in {:?} storage", backend),
+            Err(e) => {
+                tracing::warn!("[Secrets] {:?} key storage unavailable: {}", backend, e);
+                last_error = e;
+            }
+        }
+    }
+    Err(format!("No usable secret key storage: {}", last_error))
+}
+
+fn cipher(key: &[u8; 32]) -> Result<LessSafeKey, String> {
+    UnboundKey::new(&AES_256_GCM, key)
+        .map(LessSafeKey::new)
+        .map_err(|_| "Invalid secret key".to_string())
+}
+
+fn encrypt(key: &[u8; 32], name: &str, plaintext: &str) -> Result<String, String> {
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::rngs::OsRng.fill_bytes(&mut nonce);
+    let mut data = plaintext.as_bytes().to_vec();
+    cipher(key)?
+        .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::from(name.as_bytes()), &mut data)
+        .map_err(|_| "Failed to encrypt secret".to_string())?;
+    let mut out = nonce.to_vec();
+    out.extend_from_slice(&data);
+    Ok(STANDARD.encode(out))
+}
+
+fn decrypt(key: &[u8; 32], name: &str, encoded: &str) -> Result<String, String> {
+    let mut data = STANDARD.decode(encoded).map_err(|_| format!("Secret '{}' is malformed", name))?;
+    if data.len() < NONCE_LEN {
+        return Err(format!("Secret '{}' is malformed", name));
+    }
+    let (nonce, sealed) = data.split_at_mut(NONCE_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| format!("Secret '{}' is malformed", name))?;
+    let plain = cipher(key)?
+        .open_in_place(nonce, Aad::from(name.as_bytes()), sealed)
+        .map_err(|_| format!("Failed to decrypt secret '{}' (wrong key?)", name))?;
+    String::from_utf8(plain.to_vec()).map_err(|e| e.to_string())
+}
+
+/// 字段路径是否为密钥字段 (包括 `api_keys[i].key`)
+pub fn is_secret_path(path: &str) -> bool {
+    if EXCLUDED_PATHS.contains(&path) {
+        return false;
+    }
+    let field = path.rsplit('.').next().unwrap_or(path);
+    SECRET_FIELDS.contains(&field)
+        || (field == "key" && path.contains(".api_keys["))
+        || (field == "token" && (path.contains(".admin_tokens[") || path.ends_with(".config_sync.token")))
+        // Slack / Discord 的 Webhook 地址本身就是凭证
+        || (field == "url" && path.contains(".webhooks.targets["))
+}
+
+/// 遍历配置中的密钥字段 (字符串叶子节点), 路径格式与配置校验一致
+fn visit_secrets(value: &mut Value, path: &mut String, f: &mut dyn FnMut(&str, &mut String)) {
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map.iter_mut() {
+                let len = path.len();
+                path.push('.');
+                path.push_str(key);
+                if let Value::String(s) = child {
+                    if is_secret_path(path) {
+                        f(path, s);
+                    }
+                } else {
+                    visit_secrets(child, path, f);
+                }
+                path.truncate(len);
+            }
+        }
+        Value::Array(items) => {
+            for (i, child) in items.iter_mut().enumerate() {
+                let len = path.len();
+                path.push_str(&format!("[{}]", i));
+                visit_secrets(child, path, f);
+                path.truncate(len);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// 清空密钥字段 (导出配置时使用; `unresolved_only` 时只清空无法在本机解密的引用), 返回清空的字段路径。
+/// `root` 为 `value` 在配置中的路径, 例如 `proxy`
+pub fn strip(value: &mut Value, root: &str, unresolved_only: bool) -> Vec<String> {
+    let mut stripped = Vec::new();
+    visit_secrets(value, &mut root.to_string(), &mut |path, secret| {
+        if !secret.is_empty() && (!unresolved_only || secret.starts_with(REF_PREFIX)) {
+            secret.clear();
+            stripped.push(path.to_string());
+        }
+    });
+    stripped
+}
+
+/// 把 `proxy` 中的明文密钥加密保存并替换为引用 (保存配置前调用)
+pub fn externalize(proxy: &mut Value, mode: SecretStorageMode) -> Result<(), String> {
+    externalize_in(&crate::modules::account::get_data_dir()?, proxy, mode)
+}
+
+fn externalize_in(dir: &Path, proxy: &mut Value, mode: SecretStorageMode) -> Result<(), String> {
+    let mut file = read_file(dir);
+    // 保留的引用按其中的名称对应密文: 删除数组元素后, 未能解密的引用可能位于新的下标, 但名称仍是原路径
+    let mut referenced = Vec::new();
+    visit_secrets(proxy, &mut "proxy".to_string(), &mut |_, value| {
+        if let Some(name) = value.strip_prefix(REF_PREFIX) {
+            referenced.push(name.to_string());
+        }
+    });
+    let mut plaintext = Vec::new();
+    visit_secrets(proxy, &mut "proxy".to_string(), &mut |path, value| {
+        if !value.is_empty() && !value.starts_with(REF_PREFIX) {
+            // 路径已被其他引用占用时换一个名称, 不覆盖其密文
+            let name = std::iter::once(path.to_string())
+                .chain((1..).map(|n| format!("{}#{}", path, n)))
+                .find(|name| !referenced.contains(name))
+                .unwrap_or_default();
+            referenced.push(name.clone());
+            plaintext.push((name.clone(), std::mem::replace(value, format!("{}{}", REF_PREFIX, name))));
+        }
+    });
+    let before = file.entries.len();
+    file.entries.retain(|name, _| referenced.contains(name));
+    if plaintext.is_empty() && file.entries.len() == before {
+        return Ok(());
+    }
+
+    if !plaintext.is_empty() {
+        let (backend, key) = master_key(dir, mode, file.backend)?;
+        file.backend = Some(backend);
+        let now = chrono::Utc::now().timestamp();
+        for (name, value) in plaintext {
+            // 内容未变时保留原密文与更新时间
+            let unchanged = file
+                .entries
+                .get(&name)
+                .is_some_and(|e| decrypt(&key, &name, &e.value).is_ok_and(|v| v == value));
+            if !unchanged {
+                let entry = SecretEntry { value: encrypt(&key, &name, &value)?, hint: mask(&value), updated_at: now };
+                file.entries.insert(name, entry);
+            }
+        }
+    }
+    write_file(dir, &file)
+}
+
+/// 解密 `proxy` 中的引用; 返回是否存在需要迁移的明文密钥。
+/// 无法解密的引用保持原样 (保存配置时保留对应密文), 并返回列出这些字段的错误
+pub fn resolve(proxy: &mut Value) -> Result<bool, String> {
+    match crate::modules::account::get_data_dir() {
+        Ok(dir) => resolve_in(&dir, proxy),
+        Err(_) => Ok(false),
+    }
+}
+
+fn resolve_in(dir: &Path, proxy: &mut Value) -> Result<bool, String> {
+    let mut has_refs = false;
+    let mut has_plaintext = false;
+    visit_secrets(proxy, &mut "proxy".to_string(), &mut |_, value| {
+        if value.starts_with(REF_PREFIX) {
+            has_refs = true;
+        } else if !value.is_empty() {
+            has_plaintext = true;
+        }
+    });
+    if !has_refs {
+        return Ok(has_plaintext);
+    }
+
+    let file = read_file(dir);
+    let key = match file.backend {
+        Some(backend) => master_key(dir, SecretStorageMode::Auto, Some(backend)).map(|(_, key)| key),
+        None => Err("secrets.json has no key backend".to_string()),
+    };
+    let mut unavailable = Vec::new();
+    visit_secrets(proxy, &mut "proxy".to_string(), &mut |path, value| {
+        let Some(name) = value.strip_prefix(REF_PREFIX).map(str::to_string) else {
+            return;
+        };
+        let decrypted = key.as_ref().map_err(Clone::clone).and_then(|key| {
+            let entry = file.entries.get(&name).ok_or_else(|| format!("Secret '{}' not found", name))?;
+            decrypt(key, &name, &entry.value)
+        });
+        match decrypted {
+            Ok(plain) => *value = plain,
+            Err(e) => unavailable.push(format!("{} ({})", path, e)),
+        }
+    });
+    if unavailable.is_empty() {
+        Ok(has_plaintext)
+    } else {
+        Err(format!("Secrets are unavailable: {}", unavailable.join(", ")))
+    }
+}
+
+/// 使用新的主密钥重新加密所有密钥, 返回重新加密的数量
+pub fn rotate_master_key() -> Result<usize, String> {
+    let dir = crate::modules::account::get_data_dir()?;
+    let mut file = read_file(&dir);
+    let Some(backend) = file.backend else {
+        return Ok(0);
+    };
+    let (_, old_key) = master_key(&dir, SecretStorageMode::Auto, Some(backend))?;
+    let previous = std::fs::read(secrets_path(&dir)).map_err(|e| format!("Failed to read {}: {}", SECRETS_FILE, e))?;
+    let new_key = new_key();
+    for (name, entry) in file.entries.iter_mut() {
+        let value = decrypt(&old_key, name, &entry.value)?;
+        entry.value = encrypt(&new_key, name, &value)?;
+    }
+    // 先写入新密文, 再保存新主密钥; 主密钥保存失败时恢复原密文, 两者始终匹配
+    write_file(&dir, &file)?;
+    if let Err(e) = store_key(&dir, backend, &new_key) {
+        write_atomic(&dir, &previous).map_err(|restore| format!("{}; failed to restore {}: {}", e, SECRETS_FILE, restore))?;
+        return Err(e);
+    }
+    *MASTER_KEY.lock().unwrap_or_else(|e| e.into_inner()) = Some((backend, new_key));
+    Ok(file.entries.len())
+}
+
+pub fn status(mode: SecretStorageMode) -> Result<SecretStorageStatus, String> {
+    let file = read_file(&crate::modules::account::get_data_dir()?);
+    Ok(SecretStorageStatus {
+        mode,
+        backend: file.backend,
+        keychain_available: keychain::available(),
+        secrets: file
+            .entries
+            .into_iter()
+            .map(|(name, e)| SecretInfo { name, hint: e.hint, updated_at: e.updated_at })
+            .collect(),
+    })
+}
+
+/// 按字段路径读取配置中的值, 路径格式同 `set_path`
+pub fn get_path<'a>(root: &'a Value, path: &str) -> Option<&'a Value> {
+    let mut node = root;
+    for segment in path.split('.') {
+        let (field, indexes) = segment.split_once('[').map_or((segment, ""), |(f, rest)| (f, rest));
+        node = node.get(field)?;
+        for index in indexes.split('[').filter(|s| !s.is_empty()) {
+            node = node.get(index.trim_end_matches(']').parse::<usize>().ok()?)?;
+        }
+    }
+    Some(node)
+}
+
+/// 按字段路径修改配置中的值 (例如 `proxy.compatible_upstreams.upstreams[0].api_key`)
+pub fn set_path(root: &mut Value, path: &str, new_value: String) -> Result<(), String> {
+    let mut node = root;
+    for segment in path.split('.') {
+        let (field, indexes) = segment.split_once('[').map_or((segment, ""), |(f, rest)| (f, rest));
+        node = node.get_mut(field).ok_or_else(|| format!("Unknown config field: {}", path))?;
+        for index in indexes.split('[').filter(|s| !s.is_empty()) {
+            let index: usize = index
+                .trim_end_matches(']')
+                .parse()
+                .map_err(|_| format!("Invalid index in config path: {}", path))?;
+            node = node.get_mut(index).ok_or_else(|| format!("Index out of range in config path: {}", path))?;
+        }
+    }
+    if !node.is_string() {
+        return Err(format!("Config field is not a string: {}", path));
+    }
+    *node = Value::String(new_value);
+    Ok(())
+}
+
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+mod keychain {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    const SERVICE: &str = "aiolauncher";
+    const ACCOUNT: &str = "secrets-master-key";
+
+    pub fn available() -> bool {
+        let tool = if cfg!(target_os = "macos") { "security" } else { "secret-tool" };
+        Command::new(tool).arg("--help").stdout(Stdio::null()).stderr(Stdio::null()).status().is_ok()
+    }
+
+    /// 条目不存在时返回 Ok(None); 工具不可用时返回 Err
+    pub fn get() -> Result<Option<String>, String> {
+        let output = if cfg!(target_os = "macos") {
+            Command::new("security").args(["find-generic-password", "-s", SERVICE, "-a", ACCOUNT, "-w"]).output()
+        } else {
+            Command::new("secret-tool").args(["lookup", "service", SERVICE, "account", ACCOUNT]).output()
+        }
+        .map_err(|e| format!("keychain tool not available: {}", e))?;
+        let value = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        if output.status.success() && !value.is_empty() {
+            return Ok(Some(value));
+        }
+        // 确认钥匙串本身可用, 否则 Auto 模式应退回文件
+        if !available() {
+            return Err("keychain tool not available".to_string());
+        }
+        Ok(None)
+    }
+
+    /// 主密钥通过标准输入传给工具, 不出现在命令行参数中 (其他进程可见)
+    pub fn set(value: &str) -> Result<(), String> {
+        let (tool, args, input): (&str, &[&str], String) = if cfg!(target_os = "macos") {
+            // `security -i` 从标准输入读取命令; 主密钥为 base64, 不含引号与空白
+            let command = format!("add-generic-password -U -s {} -a {} -w \"{}\"\n", SERVICE, ACCOUNT, value);
+            ("security", &["-i"], command)
+        } else {
+            ("secret-tool", &["store", "--label=AIO Launcher secrets", "service", SERVICE, "account", ACCOUNT], value.to_string())
+        };
+        let status = Command::new(tool)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .and_then(|mut child| {
+                if let Some(mut stdin) = child.stdin.take() {
+                    stdin.write_all(input.as_bytes())?;
+                }
+                child.wait_with_output()
+            })
+            .map(|o| (o.status.success(), String::from_utf8_lossy(&o.stderr).to_string()))
+            .map_err(|e| format!("keychain tool not available: {}", e))?;
+        match status {
+            (true, _) => Ok(()),
+            (false, stderr) => Err(format!("Failed to store key in keychain: {}", stderr.trim())),
+        }
+    }
+}
+
+#[cfg(windows)]
+mod keychain {
+    use windows_sys::Win32::Security::Credentials::{
+        CredFree, CredReadW, CredWriteW, CREDENTIALW, CRED_PERSIST_LOCAL_MACHINE, CRED_TYPE_GENERIC,
+    };
+
+    const TARGET: &str = "aiolauncher/secrets-master-key";
+
+    fn wide(value: &str) -> Vec<u16> {
+        value.encode_utf16().chain(std::iter::once(0)).collect()
+    }
+
+    pub fn available() -> bool {
+        true
+    }
+
+    pub fn get() -> Result<Option<String>, String> {
+        let target = wide(TARGET);
+        let mut credential: *mut CREDENTIALW = std::ptr::null_mut();
+        unsafe {
+            if CredReadW(target.as_ptr(), CRED_TYPE_GENERIC, 0, &mut credential) == 0 {
+                return Ok(None);
+            }
+            let blob = std::slice::from_raw_parts((*credential).CredentialBlob, (*credential).CredentialBlobSize as usize);
+            let value = String::from_utf8_lossy(blob).to_string();
+            CredFree(credential as *const _);
+            Ok(Some(value))
+        }
+    }
+
+    pub fn set(value: &str) -> Result<(), String> {
+        let mut target = wide(TARGET);
+        let mut blob = value.as_bytes().to_vec();
+        let credential = CREDENTIALW {
+            Type: CRED_TYPE_GENERIC,
+            TargetName: target.as_mut_ptr(),
+            CredentialBlobSize: blob.len() as u32,
+            CredentialBlob: blob.as_mut_ptr(),
+            Persist: CRED_PERSIST_LOCAL_MACHINE,
+            ..Default::default()
+        };
+        if unsafe { CredWriteW(&credential, 0) } == 0 {
+            return Err(format!("Failed to store key in Credential Manager: {}", std::io::Error::last_os_error()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux", windows)))]
+mod keychain {
+    pub fn available() -> bool {
+        false
+    }
+
+    pub fn get() -> Result<Option<String>, String> {
+        Err("keychain not supported on this platform".to_string())
+    }
+
+    pub fn set(_value: &str) -> Result<(), String> {
+        Err("keychain not supported on this platform".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_secret_paths() {
+        assert!(is_secret_path("proxy.zai.api_key"));
+        assert!(is_secret_path("proxy.zai.api_keys[1].key"));
+        assert!(is_secret_path("proxy.admin_tokens[0].token"));
+        assert!(is_secret_path("proxy.config_sync.token"));
+        assert!(is_secret_path("proxy.webhooks.targets[0].url"));
+        assert!(!is_secret_path("proxy.jwt_auth.jwks_url"));
+        assert!(is_secret_path("proxy.upstream_proxy.password"));
+        assert!(!is_secret_path("proxy.api_key"));
+        assert!(!is_secret_path("proxy.pricing.key"));
+
+        let mut value = json!({"proxy": {"compatible_upstreams": {"upstreams": [{"api_key": "a"}]}}});
+        set_path(&mut value, "proxy.compatible_upstreams.upstreams[0].api_key", "b".to_string()).unwrap();
+        assert_eq!(value["proxy"]["compatible_upstreams"]["upstreams"][0]["api_key"], "b");
+        assert!(set_path(&mut value, "proxy.compatible_upstreams.upstreams[3].api_key", String::new()).is_err());
+    }
+
+    #[test]
+    fn test_externalize_and_resolve() {
+        let dir = std::env::temp_dir().join(format!("aio-secrets-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let original = json!({
+            "api_key": "sk-proxy",
+            "zai": {"api_key": "zai-secret-123456", "api_keys": [{"key": "k1-abcdefgh", "weight": 2}]},
+            "audio": {"api_key": ""},
+        });
+
+        let mut stored = original.clone();
+        externalize_in(&dir, &mut stored, SecretStorageMode::File).unwrap();
+        assert_eq!(stored["api_key"], "sk-proxy");
+        assert_eq!(stored["zai"]["api_key"], "secret://proxy.zai.api_key");
+        assert_eq!(stored["zai"]["api_keys"][0]["key"], "secret://proxy.zai.api_keys[0].key");
+        assert_eq!(stored["audio"]["api_key"], "");
+        let on_disk = std::fs::read_to_string(secrets_path(&dir)).unwrap();
+        assert!(!on_disk.contains("zai-secret-123456"));
+        assert!(on_disk.contains("zai-****3456"));
+
+        let mut loaded = stored.clone();
+        assert!(!resolve_in(&dir, &mut loaded).unwrap());
+        assert_eq!(loaded, original);
+
+        // 无法解密的引用保持原样并报错, 之后保存配置不会删除其密文
+        let mut broken = stored.clone();
+        broken["audio"]["api_key"] = json!("secret://proxy.audio.api_key");
+        let err = resolve_in(&dir, &mut broken).unwrap_err();
+        assert!(err.contains("proxy.audio.api_key"));
+        assert_eq!(broken["audio"]["api_key"], "secret://proxy.audio.api_key");
+        assert_eq!(broken["zai"]["api_key"], "zai-secret-123456");
+        let intact = std::fs::read(secrets_path(&dir)).unwrap();
+        let mut file = read_file(&dir);
+        file.entries.get_mut("proxy.zai.api_key").unwrap().value = STANDARD.encode([0u8; 40]);
+        write_file(&dir, &file).unwrap();
+        let mut tampered = stored.clone();
+        assert!(resolve_in(&dir, &mut tampered).is_err());
+        assert_eq!(tampered["zai"]["api_key"], "secret://proxy.zai.api_key");
+        externalize_in(&dir, &mut tampered, SecretStorageMode::File).unwrap();
+        assert!(read_file(&dir).entries.contains_key("proxy.zai.api_key"));
+        write_atomic(&dir, &intact).unwrap();
+
+        // 已删除的字段对应的密文会被清理
+        let mut updated = loaded.clone();
+        updated["zai"]["api_keys"] = json!([]);
+        externalize_in(&dir, &mut updated, SecretStorageMode::File).unwrap();
+        assert_eq!(read_file(&dir).entries.len(), 1);
+
+        // 删除前面的上游后, 未能解密的引用移到新的下标: 按引用中的名称保留密文, 新密钥不占用该名称
+        let mut upstreams = json!({
+            "compatible_upstreams": {"upstreams": [{"api_key": "first-key-1234"}, {"api_key": "second-key-5678"}]}
+        });
+        externalize_in(&dir, &mut upstreams, SecretStorageMode::File).unwrap();
+        let moved = upstreams["compatible_upstreams"]["upstreams"][1].clone();
+        let mut shifted = json!({
+            "compatible_upstreams": {"upstreams": [moved, {"api_key": "third-key-9012"}]}
+        });
+        externalize_in(&dir, &mut shifted, SecretStorageMode::File).unwrap();
+        let entries = read_file(&dir).entries;
+        assert!(!entries.contains_key("proxy.compatible_upstreams.upstreams[0].api_key"));
+        assert!(entries.contains_key("proxy.compatible_upstreams.upstreams[1].api_key"));
+        assert_eq!(
+            shifted["compatible_upstreams"]["upstreams"][1]["api_key"],
+            "secret://proxy.compatible_upstreams.upstreams[1].api_key#1"
+        );
+        let mut reloaded = shifted.clone();
+        assert!(!resolve_in(&dir, &mut reloaded).unwrap());
+        assert_eq!(reloaded["compatible_upstreams"]["upstreams"][0]["api_key"], "second-key-5678");
+        assert_eq!(reloaded["compatible_upstreams"]["upstreams"][1]["api_key"], "third-key-9012");
+        // 全部解密后再次保存, 名称恢复为字段路径
+        externalize_in(&dir, &mut reloaded, SecretStorageMode::File).unwrap();
+        assert_eq!(
+            reloaded["compatible_upstreams"]["upstreams"][1]["api_key"],
+            "secret://proxy.compatible_upstreams.upstreams[1].api_key"
+        );
+        assert!(!resolve_in(&dir, &mut reloaded).unwrap());
+        assert_eq!(reloaded["compatible_upstreams"]["upstreams"][0]["api_key"], "second-key-5678");
+
+        // 明文需要迁移
+        let mut plain = json!({"zai": {"api_key": "new-plaintext"}});
+        assert!(resolve_in(&dir, &mut plain).unwrap());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,547 @@
+// 外部服务进程管理
+// 按配置 (`external_services`) 启动 Ollama、ComfyUI、vLLM 等外部工具: 先按 `depends_on` 依次启动依赖并等待
+// 健康检查通过, 停止时先停止依赖它的服务。进程的 stdout / stderr 逐行推送给前端并保留最近的日志。
+
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::process::Stdio;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::Emitter;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::Command;
+use tokio::sync::oneshot;
+use tracing::{info, warn};
+
+use crate::models::ExternalServiceConfig;
+
+pub const LOG_EVENT: &str = "service://log";
+pub const STATUS_EVENT: &str = "service://status";
+const LOG_LINES: usize = 500;
+/// 停止时先发送 SIGTERM, 超时后强制结束
+const STOP_GRACE: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ServiceState {
+    Stopped,
+    Starting,
+    Running,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ServiceStatus {
+    pub id: String,
+    pub name: String,
+    pub state: ServiceState,
+    pub pid: Option<u32>,
+    /// 实际使用的端口 (`port` 被占用时自动改用后续的空闲端口)
+    pub port: Option<u16>,
+    pub started_at: Option<i64>,
+    pub exit_code: Option<i32>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ServiceLogLine {
+    pub id: String,
+    pub stream: &'static str,
+    pub line: String,
+    pub timestamp: i64,
+}
+
+#[derive(Default)]
+struct ManagerState {
+    statuses: HashMap<String, ServiceStatus>,
+    stops: HashMap<String, oneshot::Sender<()>>,
+    logs: HashMap<String, VecDeque<ServiceLogLine>>,
+}
+
+fn manager() -> &'static Mutex<ManagerState> {
+    static INSTANCE: OnceLock<Mutex<ManagerState>> = OnceLock::new();
+    INSTANCE.get_or_init(|| Mutex::new(ManagerState::default()))
+}
+
+fn lock() -> std::sync::MutexGuard<'static, ManagerState> {
+    manager().lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// 常用工具的配置模板
+pub fn presets() -> Vec<ExternalServiceConfig> {
+    let preset = |id: &str, name: &str, command: &str, args: &[&str], port: u16, health_url: &str| ExternalServiceConfig {
+        id: id.to_string(),
+        name: name.to_string(),
+        command: command.to_string(),
+        args: args.iter().map(|a| a.to_string()).collect(),
+        cwd: None,
+        env: BTreeMap::new(),
+        port: Some(port),
+        health_url: Some(health_url.to_string()),
+        depends_on: Vec::new(),
+        autostart: false,
+        startup_timeout_secs: 60,
+    };
+    let mut ollama = preset("ollama", "Ollama", "ollama", &["serve"], 11434, "http://127.0.0.1:{port}/api/version");
+    ollama.env.insert("OLLAMA_HOST".to_string(), "127.0.0.1:{port}".to_string());
+    let mut vllm = preset(
+        "vllm",
+        "vLLM",
+        "vllm",
+        &["serve", "Qwen/Qwen2.5-7B-Instruct", "--host", "127.0.0.1", "--port", "{port}"],
+        8000,
+        "http://127.0.0.1:{port}/health",
+    );
+    // 首次启动需要下载并加载模型
+    vllm.startup_timeout_secs = 600;
+    vec![
+        ollama,
+        preset(
+            "comfyui",
+            "ComfyUI",
+            "python",
+            &["main.py", "--listen", "127.0.0.1", "--port", "{port}"],
+            8188,
+            "http://127.0.0.1:{port}/system_stats",
+        ),
+        vllm,
+    ]
+}
+
+/// 启动顺序: 依赖在前, 目标服务在最后
+pub fn start_order(services: &[ExternalServiceConfig], id: &str) -> Result<Vec<String>, String> {
+    fn visit(
+        services: &[ExternalServiceConfig],
+        id: &str,
+        visiting: &mut Vec<String>,
+        order: &mut Vec<String>,
+    ) -> Result<(), String> {
+        if order.iter().any(|o| o == id) {
+            return Ok(());
+        }
+        if visiting.iter().any(|v| v == id) {
+            visiting.push(id.to_string());
+            return Err(format!("Circular service dependency: {}", visiting.join(" -> ")));
+        }
+        let service = services.iter().find(|s| s.id == id).ok_or_else(|| format!("Unknown service '{}'", id))?;
+        visiting.push(id.to_string());
+        for dep in &service.depends_on {
+            visit(services, dep, visiting, order)?;
+        }
+        visiting.pop();
+        order.push(id.to_string());
+        Ok(())
+    }
+    let mut order = Vec::new();
+    visit(services, id, &mut Vec::new(), &mut order)?;
+    Ok(order)
+}
+
+/// 直接或间接依赖 `id` 的服务, 按停止顺序 (最外层在前)
+pub fn dependents(services: &[ExternalServiceConfig], id: &str) -> Vec<String> {
+    fn visit(services: &[ExternalServiceConfig], id: &str, seen: &mut HashSet<String>, out: &mut Vec<String>) {
+        for service in services.iter().filter(|s| s.depends_on.iter().any(|d| d == id)) {
+            // 防止配置文件被外部改出环时无限递归
+            if seen.insert(service.id.clone()) {
+                visit(services, &service.id, seen, out);
+                out.push(service.id.clone());
+            }
+        }
+    }
+    let mut out = Vec::new();
+    visit(services, id, &mut HashSet::from([id.to_string()]), &mut out);
+    out
+}
+
+/// 保存配置前校验: id 唯一、命令非空、依赖存在且无环
+pub fn validate(services: &[ExternalServiceConfig]) -> Result<(), String> {
+    let mut ids = HashSet::new();
+    for service in services {
+        if service.id.trim().is_empty() {
+            return Err("External service id must not be empty".to_string());
+        }
+        if !ids.insert(service.id.as_str()) {
+            return Err(format!("Duplicate external service id '{}'", service.id));
+        }
+        if service.command.trim().is_empty() {
+            return Err(format!("External service '{}' has no command", service.id));
+        }
+    }
+    for service in services {
+        start_order(services, &service.id)?;
+    }
+    Ok(())
+}
+
+fn status_of(service: &ExternalServiceConfig) -> ServiceStatus {
+    lock().statuses.get(&service.id).cloned().unwrap_or_else(|| ServiceStatus {
+        id: service.id.clone(),
+        name: display_name(service),
+        state: ServiceState::Stopped,
+        pid: None,
+        port: None,
+        started_at: None,
+        exit_code: None,
+        error: None,
+    })
+}
+
+fn display_name(service: &ExternalServiceConfig) -> String {
+    if service.name.is_empty() { service.id.clone() } else { service.name.clone() }
+}
+
+fn set_status(app: &tauri::AppHandle, service: &ExternalServiceConfig, f: impl FnOnce(&mut ServiceStatus)) {
+    let mut status = status_of(service);
+    f(&mut status);
+    lock().statuses.insert(service.id.clone(), status.clone());
+    let _ = app.emit(STATUS_EVENT, &status);
+}
+
+/// 已配置服务的状态
+pub fn statuses(services: &[ExternalServiceConfig]) -> Vec<ServiceStatus> {
+    services.iter().map(status_of).collect()
+}
+
+pub fn logs(id: &str) -> Vec<ServiceLogLine> {
+    lock().logs.get(id).map(|l| l.iter().cloned().collect()).unwrap_or_default()
+}
+
+fn is_active(id: &str) -> bool {
+    lock().statuses.get(id).is_some_and(|s| matches!(s.state, ServiceState::Starting | ServiceState::Running))
+}
+
+/// 启动服务及其依赖 (已在运行的跳过), 每个服务就绪后才启动下一个
+pub async fn start(app: &tauri::AppHandle, services: &[ExternalServiceConfig], id: &str) -> Result<ServiceStatus, String> {
+    for sid in start_order(services, id)? {
+        let service = services.iter().find(|s| s.id == sid).ok_or_else(|| format!("Unknown service '{}'", sid))?;
+        if !is_active(&sid) {
+            spawn(app, service)?;
+        }
+        if let Err(e) = wait_ready(service).await {
+            stop_one(service).await;
+            set_status(app, service, |s| {
+                s.state = ServiceState::Failed;
+                s.error = Some(e.clone());
+            });
+            return Err(if sid == id { e } else { format!("Dependency '{}' failed to start: {}", sid, e) });
+        }
+        set_status(app, service, |s| {
+            if s.state == ServiceState::Starting {
+                s.state = ServiceState::Running;
+            }
+        });
+    }
+    Ok(status_of(services.iter().find(|s| s.id == id).ok_or("Unknown service")?))
+}
+
+/// 参数、环境变量与健康检查地址中的 `{port}` 替换为实际端口
+pub fn with_port(text: &str, port: Option<u16>) -> String {
+    match port {
+        Some(port) => text.replace("{port}", &port.to_string()),
+        None => text.to_string(),
+    }
+}
+
+/// 配置的端口被占用时改用后续的空闲端口
+fn resolve_port(service: &ExternalServiceConfig) -> Result<Option<u16>, String> {
+    use crate::utils::ports;
+    let Some(port) = service.port else {
+        return Ok(None);
+    };
+    if ports::is_available("127.0.0.1", port) {
+        return Ok(Some(port));
+    }
+    let conflict = ports::describe_conflict(port);
+    let fallback = ports::find_available("127.0.0.1", port).ok_or_else(|| format!("{}; no free port nearby", conflict))?;
+    ports::report_fallback(&service.id, port, fallback, conflict);
+    Ok(Some(fallback))
+}
+
+fn spawn(app: &tauri::AppHandle, service: &ExternalServiceConfig) -> Result<(), String> {
+    let port = match resolve_port(service) {
+        Ok(port) => port,
+        Err(e) => {
+            set_status(app, service, |s| {
+                s.state = ServiceState::Failed;
+                s.error = Some(e.clone());
+            });
+            return Err(e);
+        }
+    };
+    let mut cmd = Command::new(service.command.trim());
+    cmd.args(service.args.iter().map(|a| with_port(a, port)))
+        .envs(service.env.iter().map(|(k, v)| (k, with_port(v, port))))
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    if let Some(cwd) = service.cwd.as_deref().filter(|c| !c.trim().is_empty()) {
+        cmd.current_dir(cwd.trim());
+    }
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        cmd.creation_flags(0x08000000);
+    }
+    let mut child = match cmd.spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            let message = format!("Failed to start '{}': {}", service.command, e);
+            set_status(app, service, |s| {
+                s.state = ServiceState::Failed;
+                s.pid = None;
+                s.error = Some(message.clone());
+            });
+            return Err(message);
+        }
+    };
+    let pid = child.id();
+    info!("[Services] Started {} (pid {:?})", service.id, pid);
+    lock().logs.remove(&service.id);
+    set_status(app, service, |s| {
+        s.name = display_name(service);
+        s.state = ServiceState::Starting;
+        s.pid = pid;
+        s.port = port;
+        s.started_at = Some(chrono::Utc::now().timestamp());
+        s.exit_code = None;
+        s.error = None;
+    });
+    if let Some(stdout) = child.stdout.take() {
+        spawn_log_reader(app.clone(), service.id.clone(), "stdout", stdout);
+    }
+    if let Some(stderr) = child.stderr.take() {
+        spawn_log_reader(app.clone(), service.id.clone(), "stderr", stderr);
+    }
+
+    let (stop_tx, mut stop_rx) = oneshot::channel();
+    lock().stops.insert(service.id.clone(), stop_tx);
+    let app = app.clone();
+    let service = service.clone();
+    tokio::spawn(async move {
+        let mut stopped = false;
+        let exit = tokio::select! {
+            exit = child.wait() => exit,
+            _ = &mut stop_rx => {
+                stopped = true;
+                terminate(pid);
+                match tokio::time::timeout(STOP_GRACE, child.wait()).await {
+                    Ok(exit) => exit,
+                    Err(_) => {
+                        let _ = child.kill().await;
+                        child.wait().await
+                    }
+                }
+            }
+        };
+        lock().stops.remove(&service.id);
+        let code = exit.as_ref().ok().and_then(|s| s.code());
+        if stopped {
+            info!("[Services] Stopped {}", service.id);
+        } else {
+            warn!("[Services] {} exited ({:?})", service.id, exit);
+        }
+        set_status(&app, &service, |s| {
+            s.state = if stopped || code == Some(0) { ServiceState::Stopped } else { ServiceState::Failed };
+            s.pid = None;
+            s.port = None;
+            s.exit_code = code;
+            if !stopped && code != Some(0) {
+                s.error = Some(match &exit {
+                    Ok(status) => format!("Process exited ({})", status),
+                    Err(e) => format!("Failed to wait for process: {}", e),
+                });
+            }
+        });
+    });
+    Ok(())
+}
+
+/// 请求进程自行退出 (Windows 没有 SIGTERM, 超时后强制结束)
+fn terminate(pid: Option<u32>) {
+    #[cfg(unix)]
+    if let Some(pid) = pid {
+        unsafe {
+            libc::kill(pid as libc::pid_t, libc::SIGTERM);
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = pid;
+}
+
+/// 等待健康检查通过; 没有配置健康检查时进程存活 1 秒即视为就绪
+async fn wait_ready(service: &ExternalServiceConfig) -> Result<(), String> {
+    let deadline = Instant::now() + Duration::from_secs(service.startup_timeout_secs.max(1));
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(3))
+        .no_proxy()
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    let port = status_of(service).port;
+    let health_url = service.health_url.as_deref().map(str::trim).filter(|u| !u.is_empty()).map(|u| with_port(u, port));
+    let health_url = health_url.as_deref();
+    loop {
+        if !is_active(&service.id) {
+            let error = lock().statuses.get(&service.id).and_then(|s| s.error.clone());
+            return Err(error.unwrap_or_else(|| "Process exited during startup".to_string()));
+        }
+        match health_url {
+            Some(url) => {
+                if client.get(url).send().await.is_ok_and(|r| r.status().is_success()) {
+                    return Ok(());
+                }
+            }
+            None => {
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                if is_active(&service.id) {
+                    return Ok(());
+                }
+                continue;
+            }
+        }
+        if Instant::now() >= deadline {
+            return Err(format!("Health check did not pass within {}s", service.startup_timeout_secs));
+        }
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+}
+
+async fn stop_one(service: &ExternalServiceConfig) {
+    let Some(stop) = lock().stops.remove(&service.id) else {
+        return;
+    };
+    let _ = stop.send(());
+    let deadline = Instant::now() + STOP_GRACE + Duration::from_secs(5);
+    while is_active(&service.id) && Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+}
+
+/// 停止服务, 依赖它的服务先停止
+pub async fn stop(app: &tauri::AppHandle, services: &[ExternalServiceConfig], id: &str) -> Result<ServiceStatus, String> {
+    let service = services.iter().find(|s| s.id == id).ok_or_else(|| format!("Unknown service '{}'", id))?;
+    for dependent in dependents(services, id) {
+        if let Some(dependent) = services.iter().find(|s| s.id == dependent) {
+            stop_one(dependent).await;
+        }
+    }
+    stop_one(service).await;
+    // 没有运行中的进程时清除失败状态
+    if !is_active(id) {
+        set_status(app, service, |s| {
+            if s.state == ServiceState::Failed {
+                s.state = ServiceState::Stopped;
+            }
+        });
+    }
+    Ok(status_of(service))
+}
+
+/// 启动时拉起 `autostart` 的服务
+pub async fn start_autostart(app: &tauri::AppHandle, services: &[ExternalServiceConfig]) {
+    for service in services.iter().filter(|s| s.autostart) {
+        if let Err(e) = start(app, services, &service.id).await {
+            warn!("[Services] Autostart of {} failed: {}", service.id, e);
+        }
+    }
+}
+
+/// 只保留指定服务 (及其依赖) 运行: 其余运行中的服务停止, 未运行的启动
+pub async fn reconcile(app: &tauri::AppHandle, services: &[ExternalServiceConfig], wanted: &[String]) -> Result<(), String> {
+    let mut keep = std::collections::HashSet::new();
+    for id in wanted {
+        keep.extend(start_order(services, id)?);
+    }
+    for service in services.iter().filter(|s| !keep.contains(&s.id) && is_active(&s.id)) {
+        stop(app, services, &service.id).await?;
+    }
+    let mut errors = Vec::new();
+    for id in wanted {
+        if let Err(e) = start(app, services, id).await {
+            errors.push(format!("{}: {}", id, e));
+        }
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("Some services failed to start: {}", errors.join("; ")))
+    }
+}
+
+/// 应用退出时结束所有服务进程
+pub fn stop_all() {
+    let pids: Vec<u32> = lock().statuses.values().filter_map(|s| s.pid).collect();
+    if pids.is_empty() {
+        return;
+    }
+    let mut system = sysinfo::System::new();
+    let pids: Vec<sysinfo::Pid> = pids.into_iter().map(sysinfo::Pid::from_u32).collect();
+    system.refresh_processes(sysinfo::ProcessesToUpdate::Some(&pids));
+    for pid in pids {
+        if let Some(process) = system.process(pid) {
+            process.kill();
+        }
+    }
+}
+
+fn spawn_log_reader<R>(app: tauri::AppHandle, id: String, stream: &'static str, reader: R)
+where
+    R: AsyncRead + Unpin + Send + 'static,
+{
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(reader).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            let entry = ServiceLogLine { id: id.clone(), stream, line, timestamp: chrono::Utc::now().timestamp_millis() };
+            {
+                let mut state = lock();
+                let buffer = state.logs.entry(id.clone()).or_default();
+                if buffer.len() >= LOG_LINES {
+                    buffer.pop_front();
+                }
+                buffer.push_back(entry.clone());
+            }
+            let _ = app.emit(LOG_EVENT, &entry);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn service(id: &str, deps: &[&str]) -> ExternalServiceConfig {
+        ExternalServiceConfig {
+            id: id.to_string(),
+            name: String::new(),
+            command: "true".to_string(),
+            args: Vec::new(),
+            cwd: None,
+            env: BTreeMap::new(),
+            port: None,
+            health_url: None,
+            depends_on: deps.iter().map(|d| d.to_string()).collect(),
+            autostart: false,
+            startup_timeout_secs: 60,
+        }
+    }
+
+    #[test]
+    fn test_dependency_order() {
+        let services = vec![service("ui", &["api", "ollama"]), service("api", &["ollama"]), service("ollama", &[])];
+        assert_eq!(start_order(&services, "ui").unwrap(), vec!["ollama", "api", "ui"]);
+        assert_eq!(dependents(&services, "ollama"), vec!["ui", "api"]);
+        assert!(dependents(&services, "ui").is_empty());
+        assert!(validate(&services).is_ok());
+        assert!(validate(&presets()).is_ok());
+        assert_eq!(with_port("127.0.0.1:{port}", Some(11435)), "127.0.0.1:11435");
+    }
+
+    #[test]
+    fn test_validate_rejects_bad_graphs() {
+        let cycle = vec![service("a", &["b"]), service("b", &["a"])];
+        assert!(validate(&cycle).unwrap_err().contains("a -> b -> a"));
+        assert!(validate(&[service("a", &["missing"])]).unwrap_err().contains("missing"));
+        assert!(validate(&[service("a", &[]), service("a", &[])]).unwrap_err().contains("Duplicate"));
+    }
+}
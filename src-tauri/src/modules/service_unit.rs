@@ -0,0 +1,152 @@
+// 系统服务定义生成
+// `--service-unit <systemd|launchd|windows>` 输出以 --headless 运行反代的服务定义后退出,
+// 服务与桌面版共用同一个数据目录 (配置文件、账号), 管理接口 /api 可用于远程控制。
+
+use std::path::Path;
+
+const DESCRIPTION: &str = "AIO Launcher proxy (headless)";
+const SERVICE_NAME: &str = "aiolauncher-proxy";
+const LAUNCHD_LABEL: &str = "com.aiolauncher.proxy";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServiceKind {
+    Systemd,
+    Launchd,
+    Windows,
+}
+
+impl ServiceKind {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "systemd" => Ok(ServiceKind::Systemd),
+            "launchd" => Ok(ServiceKind::Launchd),
+            "windows" => Ok(ServiceKind::Windows),
+            other => Err(format!("Unknown service kind '{}', expected systemd, launchd or windows", other)),
+        }
+    }
+}
+
+fn quote(path: &Path) -> String {
+    format!("\"{}\"", path.display().to_string().replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn systemd(exe: &Path, data_dir: &Path) -> String {
+    format!(
+        "# 保存为 ~/.config/systemd/user/{name}.service 后执行:\n\
+         #   systemctl --user daemon-reload && systemctl --user enable --now {name}\n\
+         [Unit]\n\
+         Description={desc}\n\
+         After=network-online.target\n\
+         Wants=network-online.target\n\
+         \n\
+         [Service]\n\
+         Type=simple\n\
+         ExecStart={exe} --headless --data-dir {dir}\n\
+         Restart=on-failure\n\
+         RestartSec=5\n\
+         # 停止时先排空进行中的请求 (drain_timeout_secs)\n\
+         TimeoutStopSec=60\n\
+         \n\
+         [Install]\n\
+         WantedBy=default.target\n",
+        name = SERVICE_NAME,
+        desc = DESCRIPTION,
+        exe = quote(exe),
+        dir = quote(data_dir),
+    )
+}
+
+fn launchd(exe: &Path, data_dir: &Path) -> String {
+    let log = data_dir.join("logs").join("launchd.log");
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <!-- 保存为 ~/Library/LaunchAgents/{label}.plist 后执行: launchctl load -w <文件路径> -->\n\
+         <!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n\
+         <plist version=\"1.0\">\n\
+         <dict>\n\
+         \x20   <key>Label</key>\n\
+         \x20   <string>{label}</string>\n\
+         \x20   <key>ProgramArguments</key>\n\
+         \x20   <array>\n\
+         \x20       <string>{exe}</string>\n\
+         \x20       <string>--headless</string>\n\
+         \x20       <string>--data-dir</string>\n\
+         \x20       <string>{dir}</string>\n\
+         \x20   </array>\n\
+         \x20   <key>RunAtLoad</key>\n\
+         \x20   <true/>\n\
+         \x20   <key>KeepAlive</key>\n\
+         \x20   <dict>\n\
+         \x20       <key>SuccessfulExit</key>\n\
+         \x20       <false/>\n\
+         \x20   </dict>\n\
+         \x20   <key>ExitTimeOut</key>\n\
+         \x20   <integer>60</integer>\n\
+         \x20   <key>StandardOutPath</key>\n\
+         \x20   <string>{log}</string>\n\
+         \x20   <key>StandardErrorPath</key>\n\
+         \x20   <string>{log}</string>\n\
+         </dict>\n\
+         </plist>\n",
+        label = LAUNCHD_LABEL,
+        exe = xml_escape(&exe.display().to_string()),
+        dir = xml_escape(&data_dir.display().to_string()),
+        log = xml_escape(&log.display().to_string()),
+    )
+}
+
+/// Windows 服务需要实现 SCM 控制协议, 这里使用登录时启动的计划任务运行 headless 模式;
+/// 与 systemd 用户服务 / LaunchAgent 一样以当前用户的普通权限运行, 数据目录由 --data-dir 显式指定
+fn windows(exe: &Path, data_dir: &Path) -> String {
+    let command = format!("\\\"{}\\\" --headless --data-dir \\\"{}\\\"", exe.display(), data_dir.display());
+    format!(
+        "REM 在普通命令提示符中以当前用户执行; 删除: schtasks /Delete /TN \"{name}\" /F\r\n\
+         schtasks /Create /TN \"{name}\" /SC ONLOGON /RU \"%USERDOMAIN%\\%USERNAME%\" /IT /RL LIMITED /F /TR \"{command}\"\r\n\
+         schtasks /Run /TN \"{name}\"\r\n",
+        name = SERVICE_NAME,
+        command = command,
+    )
+}
+
+/// 生成服务定义; data_dir 写入启动参数, 保证服务与桌面版读取同一份配置
+pub fn render(kind: ServiceKind, exe: &Path, data_dir: &Path) -> String {
+    match kind {
+        ServiceKind::Systemd => systemd(exe, data_dir),
+        ServiceKind::Launchd => launchd(exe, data_dir),
+        ServiceKind::Windows => windows(exe, data_dir),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_service_units() {
+        assert_eq!(ServiceKind::parse(" SystemD ").unwrap(), ServiceKind::Systemd);
+        assert!(ServiceKind::parse("upstart").is_err());
+
+        let exe = Path::new("/opt/AIO Launcher/aiolauncher");
+        let data_dir = Path::new("/home/dev/.aio & data");
+        let unit = render(ServiceKind::Systemd, exe, data_dir);
+        assert!(unit.contains("ExecStart=\"/opt/AIO Launcher/aiolauncher\" --headless --data-dir \"/home/dev/.aio & data\"\n"));
+        assert!(unit.contains("WantedBy=default.target"));
+
+        let plist = render(ServiceKind::Launchd, exe, data_dir);
+        assert!(plist.contains("<string>/home/dev/.aio &amp; data</string>"));
+        assert!(plist.contains("    <string>--headless</string>\n"));
+
+        let task = render(ServiceKind::Windows, Path::new(r"C:\AIO\aio.exe"), Path::new(r"C:\data"));
+        assert!(task.contains(r#"/TR "\"C:\AIO\aio.exe\" --headless --data-dir \"C:\data\"""#));
+        assert!(task.contains(r#"/SC ONLOGON /RU "%USERDOMAIN%\%USERNAME%" /IT /RL LIMITED"#));
+        assert!(!task.contains("SYSTEM") && !task.contains("HIGHEST"));
+    }
+}
@@ -0,0 +1,361 @@
+// 首次运行向导 (后端)
+// 测试粘贴的 API Key、探测本机运行的 Ollama / LM Studio、根据结果生成建议配置, 确认后校验并原子写入,
+// 每一步的结果通过 `setup://progress` 事件推送给向导界面。
+
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::AppConfig;
+use crate::proxy::config::CompatibleUpstream;
+
+/// 向导监听该事件显示每一步的进度
+pub const PROGRESS_EVENT: &str = "setup://progress";
+const LOCAL_PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+const KEY_TEST_TIMEOUT: Duration = Duration::from_secs(15);
+/// 返回给界面的模型数量上限
+const MAX_MODELS: usize = 100;
+
+/// 向导可选的云端服务商 (OpenAI 兼容预设之外的补充)
+struct WizardProvider {
+    id: &'static str,
+    name: &'static str,
+    base_url: &'static str,
+    /// 路由到该服务商的模型
+    models: &'static [&'static str],
+}
+
+const EXTRA_PROVIDERS: &[WizardProvider] = &[
+    WizardProvider { id: "openai", name: "OpenAI", base_url: "https://api.openai.com/v1", models: &["gpt-*", "o1*", "o3*", "o4*"] },
+    WizardProvider { id: "deepseek", name: "DeepSeek", base_url: "https://api.deepseek.com/v1", models: &["deepseek-*"] },
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderOption {
+    pub id: String,
+    pub name: String,
+    pub base_url: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyTestRequest {
+    /// 预设 id (`openrouter`、`groq` 等)、`openai`、`deepseek`、`zai` 或 `custom`
+    pub provider: String,
+    pub api_key: String,
+    /// `custom` 必填, 其余可覆盖默认地址
+    #[serde(default)]
+    pub base_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyTestResult {
+    pub provider: String,
+    pub ok: bool,
+    pub status: Option<u16>,
+    pub latency_ms: Option<u64>,
+    pub models: Vec<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LocalBackendKind {
+    Ollama,
+    LmStudio,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocalBackend {
+    pub kind: LocalBackendKind,
+    pub base_url: String,
+    #[serde(default)]
+    pub models: Vec<String>,
+}
+
+/// 生成建议配置的输入
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SetupChoices {
+    /// 测试通过的 Key
+    #[serde(default)]
+    pub keys: Vec<KeyTestRequest>,
+    #[serde(default)]
+    pub local_backends: Vec<LocalBackend>,
+    #[serde(default)]
+    pub port: Option<u16>,
+    #[serde(default)]
+    pub allow_lan_access: bool,
+    /// 随应用启动反代
+    #[serde(default = "default_true")]
+    pub auto_start: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    Running,
+    Ok,
+    Failed,
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SetupStep {
+    pub step: String,
+    pub status: StepStatus,
+    pub message: Option<String>,
+}
+
+pub fn providers() -> Vec<ProviderOption> {
+    let presets = crate::proxy::providers::presets::PRESETS
+        .iter()
+        .map(|p| ProviderOption { id: p.id.to_string(), name: p.name.to_string(), base_url: p.base_url.to_string() });
+    let extras = EXTRA_PROVIDERS
+        .iter()
+        .map(|p| ProviderOption { id: p.id.to_string(), name: p.name.to_string(), base_url: p.base_url.to_string() });
+    let zai = ProviderOption {
+        id: "zai".to_string(),
+        name: "z.ai".to_string(),
+        base_url: crate::proxy::ZaiConfig::default().base_url,
+    };
+    presets.chain(extras).chain(std::iter::once(zai)).collect()
+}
+
+/// 模型列表地址与鉴权 Header
+fn models_request(request: &KeyTestRequest) -> Result<(String, &'static str, String), String> {
+    let key = request.api_key.trim();
+    if key.is_empty() {
+        return Err("API key is empty".to_string());
+    }
+    let custom_base = request.base_url.as_deref().map(str::trim).filter(|u| !u.is_empty());
+    let base = match request.provider.as_str() {
+        "zai" => {
+            let base = custom_base.map(str::to_string).unwrap_or_else(|| crate::proxy::ZaiConfig::default().base_url);
+            return Ok((format!("{}/v1/models", base.trim_end_matches('/')), "x-api-key", key.to_string()));
+        }
+        "custom" => custom_base.ok_or("A base URL is required for a custom provider")?.to_string(),
+        id => match (crate::proxy::providers::presets::find(id), EXTRA_PROVIDERS.iter().find(|p| p.id == id)) {
+            (Some(preset), _) => custom_base.unwrap_or(preset.base_url).to_string(),
+            (None, Some(extra)) => custom_base.unwrap_or(extra.base_url).to_string(),
+            (None, None) => return Err(format!("Unknown provider '{}'", id)),
+        },
+    };
+    let base = base.trim_end_matches('/');
+    let url = if base.ends_with("/v1") || request.provider != "custom" {
+        format!("{}/models", base)
+    } else {
+        format!("{}/v1/models", base)
+    };
+    Ok((url, "Authorization", format!("Bearer {}", key)))
+}
+
+/// OpenAI 格式 (`data[].id`) 或 Ollama 格式 (`models[].name`) 的模型列表
+pub fn parse_models(body: &serde_json::Value) -> Vec<String> {
+    let (list, field) = match (body.get("data"), body.get("models")) {
+        (Some(data), _) => (data, "id"),
+        (None, Some(models)) => (models, "name"),
+        _ => return Vec::new(),
+    };
+    let mut models: Vec<String> = list
+        .as_array()
+        .map(|items| items.iter().filter_map(|m| m.get(field)?.as_str().map(str::to_string)).collect())
+        .unwrap_or_default();
+    models.sort();
+    models.truncate(MAX_MODELS);
+    models
+}
+
+/// 用模型列表接口验证 Key (不产生费用)
+pub async fn test_api_key(request: &KeyTestRequest) -> KeyTestResult {
+    let mut result = KeyTestResult {
+        provider: request.provider.clone(),
+        ok: false,
+        status: None,
+        latency_ms: None,
+        models: Vec::new(),
+        error: None,
+    };
+    let (url, header, value) = match models_request(request) {
+        Ok(target) => target,
+        Err(e) => {
+            result.error = Some(e);
+            return result;
+        }
+    };
+    let start = Instant::now();
+    let response = crate::utils::http::get_client().get(&url).header(header, value).timeout(KEY_TEST_TIMEOUT).send().await;
+    result.latency_ms = Some(start.elapsed().as_millis() as u64);
+    match response {
+        Ok(response) => {
+            let status = response.status();
+            result.status = Some(status.as_u16());
+            result.ok = status.is_success();
+            result.error = match status.as_u16() {
+                200..=299 => None,
+                401 | 403 => Some("The API key was rejected".to_string()),
+                429 => Some("The key is valid but rate limited or out of credits".to_string()),
+                _ => Some(format!("HTTP {}", status)),
+            };
+            if result.ok {
+                result.models = response.json::<serde_json::Value>().await.map(|b| parse_models(&b)).unwrap_or_default();
+            }
+        }
+        Err(e) => result.error = Some(e.to_string()),
+    }
+    result
+}
+
+async fn probe_local(kind: LocalBackendKind, base_url: &str, path: &str) -> Option<LocalBackend> {
+    let response = crate::utils::http::get_client()
+        .get(format!("{}{}", base_url, path))
+        .timeout(LOCAL_PROBE_TIMEOUT)
+        .send()
+        .await
+        .ok()
+        .filter(|r| r.status().is_success())?;
+    let body = response.json::<serde_json::Value>().await.ok()?;
+    Some(LocalBackend { kind, base_url: base_url.to_string(), models: parse_models(&body) })
+}
+
+/// 探测本机默认端口上的 Ollama 与 LM Studio
+pub async fn detect_local_backends() -> Vec<LocalBackend> {
+    let ollama_url = crate::proxy::config::OllamaConfig::default().base_url;
+    let (ollama, lm_studio) = tokio::join!(
+        probe_local(LocalBackendKind::Ollama, &ollama_url, "/api/tags"),
+        probe_local(LocalBackendKind::LmStudio, "http://127.0.0.1:1234/v1", "/models"),
+    );
+    ollama.into_iter().chain(lm_studio).collect()
+}
+
+fn upstream_for(key: &KeyTestRequest) -> CompatibleUpstream {
+    let base_url = key.base_url.clone().unwrap_or_default();
+    let (name, preset, models) = match (crate::proxy::providers::presets::find(&key.provider), EXTRA_PROVIDERS.iter().find(|p| p.id == key.provider)) {
+        // 预设带有默认地址与模型
+        (Some(preset), _) => (preset.id.to_string(), Some(preset.id.to_string()), Vec::new()),
+        (None, Some(extra)) => (
+            extra.id.to_string(),
+            None,
+            extra.models.iter().map(|m| m.to_string()).collect(),
+        ),
+        (None, None) => ("custom".to_string(), None, Vec::new()),
+    };
+    let base_url = match EXTRA_PROVIDERS.iter().find(|p| p.id == key.provider) {
+        Some(extra) if base_url.trim().is_empty() => extra.base_url.to_string(),
+        _ => base_url,
+    };
+    CompatibleUpstream { name, preset, base_url, api_key: key.api_key.trim().to_string(), models, chat_only: false, enabled: true }
+}
+
+/// 在当前配置的基础上生成建议配置 (不写入); 同名上游会被替换
+pub fn propose(current: &AppConfig, choices: &SetupChoices) -> AppConfig {
+    let mut config = current.clone();
+    let proxy = &mut config.proxy;
+    if let Some(port) = choices.port {
+        proxy.port = port;
+    }
+    proxy.allow_lan_access = choices.allow_lan_access;
+    proxy.auto_start = choices.auto_start;
+
+    let mut upstreams: Vec<CompatibleUpstream> = Vec::new();
+    for key in &choices.keys {
+        if key.provider == "zai" {
+            proxy.zai.enabled = true;
+            proxy.zai.api_key = key.api_key.trim().to_string();
+            if let Some(base_url) = key.base_url.as_ref().filter(|u| !u.trim().is_empty()) {
+                proxy.zai.base_url = base_url.trim().to_string();
+            }
+            if proxy.zai.dispatch_mode == crate::proxy::ZaiDispatchMode::Off {
+                proxy.zai.dispatch_mode = crate::proxy::ZaiDispatchMode::Fallback;
+            }
+        } else {
+            upstreams.push(upstream_for(key));
+        }
+    }
+    for backend in &choices.local_backends {
+        match backend.kind {
+            LocalBackendKind::Ollama => {
+                proxy.ollama.enabled = true;
+                proxy.ollama.base_url = backend.base_url.clone();
+            }
+            LocalBackendKind::LmStudio => upstreams.push(CompatibleUpstream {
+                name: "lmstudio".to_string(),
+                preset: None,
+                base_url: backend.base_url.clone(),
+                api_key: String::new(),
+                models: backend.models.clone(),
+                chat_only: false,
+                enabled: true,
+            }),
+        }
+    }
+    let configured = &mut proxy.compatible_upstreams.upstreams;
+    configured.retain(|u| !upstreams.iter().any(|n| n.name == u.name));
+    configured.extend(upstreams);
+    config
+}
+
+/// 记录并推送一步的结果
+pub fn report(app: &tauri::AppHandle, steps: &mut Vec<SetupStep>, step: &str, status: StepStatus, message: Option<String>) {
+    use tauri::Emitter;
+    let entry = SetupStep { step: step.to_string(), status, message };
+    let _ = app.emit(PROGRESS_EVENT, &entry);
+    if status != StepStatus::Running {
+        steps.push(entry);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_models_and_requests() {
+        assert_eq!(parse_models(&json!({"data": [{"id": "b"}, {"id": "a"}]})), vec!["a", "b"]);
+        assert_eq!(parse_models(&json!({"models": [{"name": "llama3.1:8b"}]})), vec!["llama3.1:8b"]);
+        assert!(parse_models(&json!({"error": "x"})).is_empty());
+
+        let key = |provider: &str, base_url: Option<&str>| KeyTestRequest {
+            provider: provider.to_string(),
+            api_key: " sk-test ".to_string(),
+            base_url: base_url.map(str::to_string),
+        };
+        let (url, header, value) = models_request(&key("openrouter", None)).unwrap();
+        assert_eq!((url.as_str(), header, value.as_str()), ("https://openrouter.ai/api/v1/models", "Authorization", "Bearer sk-test"));
+        assert_eq!(models_request(&key("zai", None)).unwrap().1, "x-api-key");
+        assert_eq!(models_request(&key("custom", Some("http://10.0.0.2:8000"))).unwrap().0, "http://10.0.0.2:8000/v1/models");
+        assert!(models_request(&key("custom", None)).is_err());
+        assert!(models_request(&key("nope", None)).is_err());
+    }
+
+    #[test]
+    fn test_propose() {
+        let choices = SetupChoices {
+            keys: vec![
+                KeyTestRequest { provider: "groq".to_string(), api_key: "gsk".to_string(), base_url: None },
+                KeyTestRequest { provider: "deepseek".to_string(), api_key: "ds".to_string(), base_url: None },
+            ],
+            local_backends: vec![LocalBackend {
+                kind: LocalBackendKind::Ollama,
+                base_url: "http://127.0.0.1:11434".to_string(),
+                models: vec!["llama3.1".to_string()],
+            }],
+            port: Some(9000),
+            allow_lan_access: false,
+            auto_start: true,
+        };
+        let config = propose(&AppConfig::new(), &choices);
+        assert_eq!(config.proxy.port, 9000);
+        assert!(config.proxy.ollama.enabled && config.proxy.auto_start);
+        let upstreams = &config.proxy.compatible_upstreams.upstreams;
+        assert_eq!(upstreams.len(), 2);
+        assert_eq!(upstreams[0].preset.as_deref(), Some("groq"));
+        assert_eq!(upstreams[1].base_url, "https://api.deepseek.com/v1");
+        // 再次运行向导替换同名上游
+        assert_eq!(propose(&config, &choices).proxy.compatible_upstreams.upstreams.len(), 2);
+        assert!(crate::proxy::common::config_validation::ensure_valid(&config.proxy).is_ok());
+    }
+}
@@ -1,11 +1,122 @@
 use tauri::{
     image::Image,
-    menu::{Menu, MenuItem, PredefinedMenuItem},
+    menu::{CheckMenuItem, Menu, MenuItem, PredefinedMenuItem},
     tray::{MouseButton, TrayIconBuilder, TrayIconEvent},
-    Manager, Emitter, Listener,
+    Manager, Emitter, Listener, Wry,
 };
+use tauri_plugin_autostart::ManagerExt;
+use std::sync::Mutex;
 use crate::modules;
 
+/// 托盘中展示的反代服务状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+struct ProxySnapshot {
+    running: bool,
+    port: u16,
+    active_requests: usize,
+}
+
+/// 需要定时刷新文字的菜单项 (菜单重建时一并替换)
+struct ProxyMenuItems {
+    status: MenuItem<Wry>,
+    active: MenuItem<Wry>,
+    toggle: MenuItem<Wry>,
+}
+
+static PROXY_MENU_ITEMS: Mutex<Option<ProxyMenuItems>> = Mutex::new(None);
+
+/// 托盘状态刷新间隔
+const STATUS_REFRESH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(3);
+
+async fn proxy_snapshot(app: &tauri::AppHandle) -> ProxySnapshot {
+    let state = app.state::<crate::commands::proxy::ProxyServiceState>();
+    let instance = state.instance.read().await;
+    match instance.as_ref() {
+        Some(instance) => ProxySnapshot {
+            running: true,
+            port: instance.axum_server.bound_port(),
+            active_requests: instance.axum_server.active_requests(),
+        },
+        None => ProxySnapshot::default(),
+    }
+}
+
+fn proxy_texts(texts: &modules::i18n::TrayTexts, snapshot: ProxySnapshot) -> (String, String, String) {
+    let status = if snapshot.running {
+        texts.proxy_running.replace("{port}", &snapshot.port.to_string())
+    } else {
+        texts.proxy_stopped.clone()
+    };
+    let active = texts.active_requests.replace("{count}", &snapshot.active_requests.to_string());
+    let toggle = if snapshot.running { texts.stop_proxy.clone() } else { texts.start_proxy.clone() };
+    (status, active, toggle)
+}
+
+fn build_proxy_items(
+    app: &tauri::AppHandle,
+    texts: &modules::i18n::TrayTexts,
+    snapshot: ProxySnapshot,
+) -> tauri::Result<ProxyMenuItems> {
+    let (status, active, toggle) = proxy_texts(texts, snapshot);
+    Ok(ProxyMenuItems {
+        status: MenuItem::with_id(app, "proxy_status", &status, false, None::<&str>)?,
+        active: MenuItem::with_id(app, "proxy_active", &active, false, None::<&str>)?,
+        toggle: MenuItem::with_id(app, "toggle_proxy", &toggle, true, None::<&str>)?,
+    })
+}
+
+fn show_main_window(app: &tauri::AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.set_focus();
+        #[cfg(target_os = "macos")]
+        app.set_activation_policy(tauri::ActivationPolicy::Regular).unwrap_or(());
+    }
+}
+
+/// 托盘启动/停止反代服务 (与前端调用同一套生命周期逻辑)
+async fn toggle_proxy_service(app: &tauri::AppHandle) -> Result<(), String> {
+    let state = app.state::<crate::commands::proxy::ProxyServiceState>().inner().clone();
+    if state.instance.read().await.is_some() {
+        return crate::commands::proxy::internal_stop_proxy_service(&state).await;
+    }
+    let config = modules::load_app_config()?;
+    let cf_state = app.state::<crate::commands::cloudflared::CloudflaredState>().inner().clone();
+    let status = crate::commands::proxy::internal_start_proxy_service(
+        config.proxy,
+        &state,
+        crate::modules::integration::SystemManager::Desktop(app.clone()),
+        std::sync::Arc::new(cf_state),
+    )
+    .await?;
+    if !status.running {
+        return Err("No available accounts, proxy not started".to_string());
+    }
+    Ok(())
+}
+
+/// 定时刷新托盘中的端口与活跃请求数
+fn start_status_refresher(app: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut last: Option<ProxySnapshot> = None;
+        loop {
+            let snapshot = proxy_snapshot(&app).await;
+            if last != Some(snapshot) {
+                let config = modules::load_app_config().unwrap_or_default();
+                let texts = modules::i18n::get_tray_texts(&config.language);
+                let (status, active, toggle) = proxy_texts(&texts, snapshot);
+                if let Some(items) = PROXY_MENU_ITEMS.lock().unwrap_or_else(|e| e.into_inner()).as_ref() {
+                    let _ = items.status.set_text(&status);
+                    let _ = items.active.set_text(&active);
+                    let _ = items.toggle.set_text(&toggle);
+                }
+                last = Some(snapshot);
+            }
+            tokio::time::sleep(STATUS_REFRESH_INTERVAL).await;
+        }
+    });
+}
+
 pub fn create_tray(app: &tauri::AppHandle) -> tauri::Result<()> {
     // 1. Load config to get language settings
     let config = modules::load_app_config().unwrap_or_default();
@@ -30,26 +141,40 @@ pub fn create_tray(app: &tauri::AppHandle) -> tauri::Result<()> {
     let switch_next = MenuItem::with_id(app, "switch_next", &texts.switch_next, true, None::<&str>)?;
     let refresh_curr = MenuItem::with_id(app, "refresh_curr", &texts.refresh_current, true, None::<&str>)?;
     
+    // Proxy service area (状态由定时任务刷新)
+    let proxy_items = build_proxy_items(app, &texts, ProxySnapshot::default())?;
+
     // System functions
+    let auto_launch_enabled = app.autolaunch().is_enabled().unwrap_or(false);
+    let auto_launch = CheckMenuItem::with_id(app, "auto_launch", &texts.auto_launch, true, auto_launch_enabled, None::<&str>)?;
+    let dashboard_i = MenuItem::with_id(app, "open_dashboard", &texts.open_dashboard, true, None::<&str>)?;
     let show_i = MenuItem::with_id(app, "show", &texts.show_window, true, None::<&str>)?;
     let quit_i = MenuItem::with_id(app, "quit", &texts.quit, true, None::<&str>)?;
     
     let sep1 = PredefinedMenuItem::separator(app)?;
     let sep2 = PredefinedMenuItem::separator(app)?;
     let sep3 = PredefinedMenuItem::separator(app)?;
+    let sep4 = PredefinedMenuItem::separator(app)?;
 
     // 4. Build menu
     let menu = Menu::with_items(app, &[
         &info_user,
         &info_quota,
         &sep1,
+        &proxy_items.status,
+        &proxy_items.active,
+        &proxy_items.toggle,
+        &sep2,
         &switch_next,
         &refresh_curr,
-        &sep2,
-        &show_i,
         &sep3,
+        &auto_launch,
+        &dashboard_i,
+        &show_i,
+        &sep4,
         &quit_i,
     ])?;
+    *PROXY_MENU_ITEMS.lock().unwrap_or_else(|e| e.into_inner()) = Some(proxy_items);
 
     // 5. Build tray icon
     let _ = TrayIconBuilder::with_id("main")
@@ -59,28 +184,40 @@ pub fn create_tray(app: &tauri::AppHandle) -> tauri::Result<()> {
         .on_menu_event(move |app, event| {
             let app_handle = app.clone();
             match event.id().as_ref() {
-                "show" => {
-                    if let Some(window) = app.get_webview_window("main") {
-                        let _ = window.show();
-                        let _ = window.set_focus();
-                        #[cfg(target_os = "macos")]
-                        app.set_activation_policy(tauri::ActivationPolicy::Regular).unwrap_or(());
-                    }
+                "show" => show_main_window(app),
+                "open_dashboard" => {
+                    show_main_window(app);
+                    let _ = app.emit("tray://open-dashboard", ());
+                }
+                "toggle_proxy" => {
+                    tauri::async_runtime::spawn(async move {
+                        if let Err(e) = toggle_proxy_service(&app_handle).await {
+                            modules::logger::log_error(&format!("Tray proxy toggle failed: {}", e));
+                        }
+                        update_tray_menus(&app_handle);
+                    });
+                }
+                "auto_launch" => {
+                    tauri::async_runtime::spawn(async move {
+                        let enable = !app_handle.autolaunch().is_enabled().unwrap_or(false);
+                        if let Err(e) = crate::commands::autostart::toggle_auto_launch(app_handle.clone(), enable).await {
+                            modules::logger::log_error(&format!("Tray auto-launch toggle failed: {}", e));
+                        }
+                        // 无论成功与否都按实际状态重建, 纠正勾选状态
+                        update_tray_menus(&app_handle);
+                    });
                 }
                 "quit" => {
-                    // 先停止 Admin Server，避免僵尸 socket
+                    // 先停止 Admin Server，避免僵尸 socket; 等待进行中的流式响应结束后再退出
                     let state = app.state::<crate::commands::proxy::ProxyServiceState>();
                     let admin_server = state.admin_server.clone();
                     tauri::async_runtime::spawn(async move {
-                        let mut lock = admin_server.write().await;
-                        if let Some(admin) = lock.take() {
-                            admin.axum_server.stop();
-                            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+                        let admin = admin_server.write().await.take();
+                        if let Some(admin) = admin {
+                            admin.axum_server.stop().await;
                         }
+                        app_handle.exit(0);
                     });
-                    // 給一點時間讓 socket 關閉
-                    std::thread::sleep(std::time::Duration::from_millis(200));
-                    app.exit(0);
                 }
                 "refresh_curr" => {
                     // Execute refresh asynchronously
@@ -145,13 +282,7 @@ pub fn create_tray(app: &tauri::AppHandle) -> tauri::Result<()> {
                 ..
             } = event
             {
-               let app = tray.app_handle();
-               if let Some(window) = app.get_webview_window("main") {
-                   let _ = window.show();
-                   let _ = window.set_focus();
-                   #[cfg(target_os = "macos")]
-                   app.set_activation_policy(tauri::ActivationPolicy::Regular).unwrap_or(());
-               }
+               show_main_window(tray.app_handle());
             }
         })
         .build(app)?;
@@ -161,6 +292,7 @@ pub fn create_tray(app: &tauri::AppHandle) -> tauri::Result<()> {
     tauri::async_runtime::spawn(async move {
         update_tray_menus(&handle);
     });
+    start_status_refresher(app.clone());
 
     // Listen for config update events
     let handle = app.clone();
@@ -180,6 +312,9 @@ pub fn update_tray_menus(app: &tauri::AppHandle) {
          let config = modules::load_app_config().unwrap_or_default();
          let texts = modules::i18n::get_tray_texts(&config.language);
          
+         let snapshot = proxy_snapshot(&app_clone).await;
+         let auto_launch_enabled = app_clone.autolaunch().is_enabled().unwrap_or(false);
+
          // Get current account info
          let current = modules::get_current_account_id().unwrap_or(None);
          
@@ -237,13 +372,19 @@ pub fn update_tray_menus(app: &tauri::AppHandle) {
          let switch_next = MenuItem::with_id(&app_clone, "switch_next", &texts.switch_next, true, None::<&str>);
          let refresh_curr = MenuItem::with_id(&app_clone, "refresh_curr", &texts.refresh_current, true, None::<&str>);
          
+         let proxy_items = build_proxy_items(&app_clone, &texts, snapshot);
+         let auto_launch = CheckMenuItem::with_id(&app_clone, "auto_launch", &texts.auto_launch, true, auto_launch_enabled, None::<&str>);
+         let dashboard_i = MenuItem::with_id(&app_clone, "open_dashboard", &texts.open_dashboard, true, None::<&str>);
          let show_i = MenuItem::with_id(&app_clone, "show", &texts.show_window, true, None::<&str>);
          let quit_i = MenuItem::with_id(&app_clone, "quit", &texts.quit, true, None::<&str>);
          
-         if let (Ok(i_u), Ok(s_n), Ok(r_c), Ok(s), Ok(q)) = (info_user, switch_next, refresh_curr, show_i, quit_i) {
+         if let (Ok(i_u), Ok(p), Ok(s_n), Ok(r_c), Ok(a_l), Ok(d), Ok(s), Ok(q)) =
+             (info_user, proxy_items, switch_next, refresh_curr, auto_launch, dashboard_i, show_i, quit_i)
+         {
              let sep1 = PredefinedMenuItem::separator(&app_clone).ok();
              let sep2 = PredefinedMenuItem::separator(&app_clone).ok();
              let sep3 = PredefinedMenuItem::separator(&app_clone).ok();
+             let sep4 = PredefinedMenuItem::separator(&app_clone).ok();
              
              let mut items: Vec<&dyn tauri::menu::IsMenuItem<tauri::Wry>> = vec![&i_u];
              // Add dynamic quota items
@@ -252,11 +393,17 @@ pub fn update_tray_menus(app: &tauri::AppHandle) {
              }
              
              if let Some(ref s) = sep1 { items.push(s); }
+             items.push(&p.status);
+             items.push(&p.active);
+             items.push(&p.toggle);
+             if let Some(ref s) = sep2 { items.push(s); }
              items.push(&s_n);
              items.push(&r_c);
-             if let Some(ref s) = sep2 { items.push(s); }
-             items.push(&s);
              if let Some(ref s) = sep3 { items.push(s); }
+             items.push(&a_l);
+             items.push(&d);
+             items.push(&s);
+             if let Some(ref s) = sep4 { items.push(s); }
              items.push(&q);
              
              if let Ok(menu) = Menu::with_items(&app_clone, &items) {
@@ -264,6 +411,8 @@ pub fn update_tray_menus(app: &tauri::AppHandle) {
                      let _ = tray.set_menu(Some(menu));
                  }
              }
+             drop(items);
+             *PROXY_MENU_ITEMS.lock().unwrap_or_else(|e| e.into_inner()) = Some(p);
          }
     });
 }
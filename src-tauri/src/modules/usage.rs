@@ -0,0 +1,795 @@
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// One proxied request, as recorded for usage accounting
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct UsageRecord {
+    pub timestamp: i64, // seconds
+    pub model: String,
+    pub mapped_model: Option<String>,
+    /// Upstream that served the request: "google", "zai" or "unknown"
+    pub upstream: String,
+    pub account_email: Option<String>,
+    /// Fingerprint of the client API key (never the key itself)
+    pub api_key_hash: Option<String>,
+    /// Masked key for display, e.g. "sk-a…wxyz"
+    pub api_key_hint: Option<String>,
+    /// Client tool from X-Client-Name or the User-Agent, e.g. "Cursor"
+    #[serde(default)]
+    pub client_name: Option<String>,
+    pub protocol: Option<String>,
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+    /// Prompt tokens served from the provider's prompt cache (included in `prompt_tokens`)
+    #[serde(default)]
+    pub cache_read_tokens: u32,
+    /// Prompt tokens written to the provider's prompt cache (included in `prompt_tokens`)
+    #[serde(default)]
+    pub cache_write_tokens: u32,
+    pub latency_ms: u64,
+    /// Output rate of streaming responses (locally counted tokens per second)
+    #[serde(default)]
+    pub tokens_per_second: Option<f64>,
+    pub status: u16,
+    /// Cost in USD according to the pricing table
+    pub cost_usd: f64,
+    /// Same ID as the `X-Request-Id` response header and the request log
+    pub request_id: Option<String>,
+}
+
+/// Aggregated usage for one group (day / model / API key)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct UsageAggregate {
+    pub key: String,
+    /// Human readable label (masked API key for key groups, otherwise same as key)
+    pub label: String,
+    pub request_count: u64,
+    pub error_count: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub total_tokens: u64,
+    pub avg_latency_ms: f64,
+    /// Average streaming output rate; `None` when the group has no measured streams
+    pub avg_tokens_per_second: Option<f64>,
+    pub cost_usd: f64,
+}
+
+/// Request outcome per upstream and served model, used to score smart routing candidates
+#[derive(Debug, Clone, PartialEq)]
+pub struct UpstreamModelStats {
+    pub upstream: String,
+    /// Mapped model when known, otherwise the requested model
+    pub model: String,
+    pub requests: u64,
+    pub errors: u64,
+    pub avg_latency_ms: f64,
+}
+
+/// Totals for one client API key over a time window
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct KeyTotals {
+    pub requests: u64,
+    pub tokens: u64,
+    pub cost_usd: f64,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum UsageGroup {
+    Day,
+    Model,
+    ApiKey,
+    Client,
+    Upstream,
+}
+
+impl UsageGroup {
+    /// (group key, display label) SQL expressions
+    fn columns(self) -> (&'static str, &'static str) {
+        match self {
+            UsageGroup::Day => (
+                "strftime('%Y-%m-%d', timestamp, 'unixepoch')",
+                "strftime('%Y-%m-%d', timestamp, 'unixepoch')",
+            ),
+            UsageGroup::Model => ("model", "model"),
+            UsageGroup::ApiKey => (
+                "COALESCE(api_key_hash, 'anonymous')",
+                "COALESCE(MAX(api_key_hint), 'anonymous')",
+            ),
+            UsageGroup::Client => ("COALESCE(client_name, 'unknown')", "COALESCE(client_name, 'unknown')"),
+            UsageGroup::Upstream => ("upstream", "upstream"),
+        }
+    }
+}
+
+pub(crate) fn get_db_path() -> Result<PathBuf, String> {
+    let data_dir = crate::modules::account::get_data_dir()?;
+    Ok(data_dir.join("usage.db"))
+}
+
+fn connect_db() -> Result<Connection, String> {
+    let db_path = get_db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+
+    // Enable WAL mode for better concurrency
+    conn.pragma_update(None, "journal_mode", "WAL")
+        .map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "busy_timeout", 5000)
+        .map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "synchronous", "NORMAL")
+        .map_err(|e| e.to_string())?;
+
+    Ok(conn)
+}
+
+fn init_schema(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS usage_records (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            timestamp INTEGER NOT NULL,
+            model TEXT NOT NULL,
+            mapped_model TEXT,
+            upstream TEXT NOT NULL,
+            account_email TEXT,
+            api_key_hash TEXT,
+            api_key_hint TEXT,
+            protocol TEXT,
+            prompt_tokens INTEGER NOT NULL DEFAULT 0,
+            completion_tokens INTEGER NOT NULL DEFAULT 0,
+            latency_ms INTEGER NOT NULL DEFAULT 0,
+            status INTEGER NOT NULL,
+            cost_usd REAL NOT NULL DEFAULT 0,
+            request_id TEXT,
+            client_name TEXT,
+            tokens_per_second REAL,
+            cache_read_tokens INTEGER NOT NULL DEFAULT 0,
+            cache_write_tokens INTEGER NOT NULL DEFAULT 0
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
+    // Try to add new columns (ignore errors if they exist)
+    let _ = conn.execute("ALTER TABLE usage_records ADD COLUMN cost_usd REAL NOT NULL DEFAULT 0", []);
+    let _ = conn.execute("ALTER TABLE usage_records ADD COLUMN request_id TEXT", []);
+    let _ = conn.execute("ALTER TABLE usage_records ADD COLUMN client_name TEXT", []);
+    let _ = conn.execute("ALTER TABLE usage_records ADD COLUMN tokens_per_second REAL", []);
+    let _ = conn.execute("ALTER TABLE usage_records ADD COLUMN cache_read_tokens INTEGER NOT NULL DEFAULT 0", []);
+    let _ = conn.execute("ALTER TABLE usage_records ADD COLUMN cache_write_tokens INTEGER NOT NULL DEFAULT 0", []);
+
+    for index in [
+        "CREATE INDEX IF NOT EXISTS idx_usage_timestamp ON usage_records (timestamp DESC)",
+        "CREATE INDEX IF NOT EXISTS idx_usage_model ON usage_records (model)",
+        "CREATE INDEX IF NOT EXISTS idx_usage_api_key ON usage_records (api_key_hash)",
+        "CREATE INDEX IF NOT EXISTS idx_usage_client ON usage_records (client_name)",
+        "CREATE INDEX IF NOT EXISTS idx_usage_upstream ON usage_records (upstream)",
+    ] {
+        conn.execute(index, []).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Initialize the usage database
+pub fn init_db() -> Result<(), String> {
+    let conn = connect_db()?;
+    init_schema(&conn)
+}
+
+fn insert_record(conn: &Connection, record: &UsageRecord) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO usage_records (timestamp, model, mapped_model, upstream, account_email, api_key_hash, api_key_hint,
+            protocol, prompt_tokens, completion_tokens, latency_ms, status, cost_usd, request_id, client_name, tokens_per_second,
+            cache_read_tokens, cache_write_tokens)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)",
+        params![
+            record.timestamp,
+            record.model,
+            record.mapped_model,
+            record.upstream,
+            record.account_email,
+            record.api_key_hash,
+            record.api_key_hint,
+            record.protocol,
+            record.prompt_tokens,
+            record.completion_tokens,
+            record.latency_ms as i64,
+            record.status,
+            record.cost_usd,
+            record.request_id,
+            record.client_name,
+            record.tokens_per_second,
+            record.cache_read_tokens,
+            record.cache_write_tokens,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Record a proxied request
+pub fn record(record: &UsageRecord) -> Result<(), String> {
+    let conn = connect_db()?;
+    insert_record(&conn, record)
+}
+
+fn query_aggregates(conn: &Connection, group: UsageGroup, since: i64) -> Result<Vec<UsageAggregate>, String> {
+    let (key_expr, label_expr) = group.columns();
+    let order = match group {
+        UsageGroup::Day => "group_key ASC",
+        _ => "total DESC",
+    };
+    let sql = format!(
+        "SELECT {key} AS group_key, {label},
+            COUNT(*),
+            SUM(CASE WHEN status >= 400 THEN 1 ELSE 0 END),
+            SUM(prompt_tokens),
+            SUM(completion_tokens),
+            SUM(prompt_tokens + completion_tokens) AS total,
+            AVG(latency_ms),
+            SUM(cost_usd),
+            AVG(tokens_per_second)
+         FROM usage_records
+         WHERE timestamp >= ?1
+         GROUP BY group_key
+         ORDER BY {order}",
+        key = key_expr,
+        label = label_expr,
+        order = order,
+    );
+
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([since], |row| {
+            Ok(UsageAggregate {
+                key: row.get(0)?,
+                label: row.get(1)?,
+                request_count: row.get(2)?,
+                error_count: row.get(3)?,
+                prompt_tokens: row.get(4)?,
+                completion_tokens: row.get(5)?,
+                total_tokens: row.get(6)?,
+                avg_latency_ms: row.get(7)?,
+                avg_tokens_per_second: row.get(9)?,
+                cost_usd: row.get(8)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut result = Vec::new();
+    for row in rows {
+        result.push(row.map_err(|e| e.to_string())?);
+    }
+    Ok(result)
+}
+
+fn aggregate_since_days(group: UsageGroup, days: i64) -> Result<Vec<UsageAggregate>, String> {
+    let conn = connect_db()?;
+    let since = (chrono::Utc::now() - chrono::Duration::days(days)).timestamp();
+    query_aggregates(&conn, group, since)
+}
+
+/// Usage per day for the last `days` days
+pub fn get_usage_by_day(days: i64) -> Result<Vec<UsageAggregate>, String> {
+    aggregate_since_days(UsageGroup::Day, days)
+}
+
+/// Usage per requested model for the last `days` days
+pub fn get_usage_by_model(days: i64) -> Result<Vec<UsageAggregate>, String> {
+    aggregate_since_days(UsageGroup::Model, days)
+}
+
+/// Usage per client API key for the last `days` days
+pub fn get_usage_by_api_key(days: i64) -> Result<Vec<UsageAggregate>, String> {
+    aggregate_since_days(UsageGroup::ApiKey, days)
+}
+
+/// Usage per client tool for the last `days` days
+pub fn get_usage_by_client(days: i64) -> Result<Vec<UsageAggregate>, String> {
+    aggregate_since_days(UsageGroup::Client, days)
+}
+
+/// Usage per upstream provider for the last `days` days, e.g. to compare streaming throughput
+pub fn get_usage_by_upstream(days: i64) -> Result<Vec<UsageAggregate>, String> {
+    aggregate_since_days(UsageGroup::Upstream, days)
+}
+
+/// Usage grouped by day / model / API key / client / upstream since `since` (unix seconds)
+pub fn get_usage_since(group: UsageExportGroup, since: i64) -> Result<Vec<UsageAggregate>, String> {
+    let conn = connect_db()?;
+    query_aggregates(&conn, group.group(), since)
+}
+
+fn query_key_totals(conn: &Connection, api_key_hash: &str, since: i64) -> Result<KeyTotals, String> {
+    conn.query_row(
+        "SELECT COUNT(*), COALESCE(SUM(prompt_tokens + completion_tokens), 0), COALESCE(SUM(cost_usd), 0)
+         FROM usage_records
+         WHERE api_key_hash = ?1 AND timestamp >= ?2",
+        params![api_key_hash, since],
+        |row| {
+            Ok(KeyTotals {
+                requests: row.get(0)?,
+                tokens: row.get(1)?,
+                cost_usd: row.get(2)?,
+            })
+        },
+    )
+    .map_err(|e| e.to_string())
+}
+
+fn query_upstream_model_stats(conn: &Connection, since: i64) -> Result<Vec<UpstreamModelStats>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT upstream, COALESCE(mapped_model, model) AS served_model, COUNT(*),
+                SUM(CASE WHEN status >= 400 THEN 1 ELSE 0 END), AVG(latency_ms)
+             FROM usage_records
+             WHERE timestamp >= ?1
+             GROUP BY upstream, served_model",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([since], |row| {
+            Ok(UpstreamModelStats {
+                upstream: row.get(0)?,
+                model: row.get(1)?,
+                requests: row.get(2)?,
+                errors: row.get(3)?,
+                avg_latency_ms: row.get(4)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+/// Requests, errors and average latency per (upstream, served model) since `since`
+pub fn get_upstream_model_stats(since: i64) -> Result<Vec<UpstreamModelStats>, String> {
+    let conn = connect_db()?;
+    query_upstream_model_stats(&conn, since)
+}
+
+/// Request count, tokens and cost for one client API key since `since`
+pub fn get_key_totals_since(api_key_hash: &str, since: i64) -> Result<KeyTotals, String> {
+    let conn = connect_db()?;
+    query_key_totals(&conn, api_key_hash, since)
+}
+
+/// Output format of a usage export
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UsageExportFormat {
+    Csv,
+    Json,
+}
+
+/// Grouping dimension of a usage export; no grouping exports individual requests
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UsageExportGroup {
+    Day,
+    Model,
+    ApiKey,
+    Client,
+    Upstream,
+}
+
+impl UsageExportGroup {
+    fn group(self) -> UsageGroup {
+        match self {
+            UsageExportGroup::Day => UsageGroup::Day,
+            UsageExportGroup::Model => UsageGroup::Model,
+            UsageExportGroup::ApiKey => UsageGroup::ApiKey,
+            UsageExportGroup::Client => UsageGroup::Client,
+            UsageExportGroup::Upstream => UsageGroup::Upstream,
+        }
+    }
+}
+
+/// One row of a grouped usage export; columns that are not grouped on are `None`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct UsageExportRow {
+    pub day: Option<String>,
+    pub model: Option<String>,
+    /// Masked API key
+    pub api_key: Option<String>,
+    pub client: Option<String>,
+    pub upstream: Option<String>,
+    pub request_count: u64,
+    pub error_count: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub total_tokens: u64,
+    pub avg_latency_ms: f64,
+    pub avg_tokens_per_second: Option<f64>,
+    pub cost_usd: f64,
+}
+
+/// Parse an inclusive UTC date range ("YYYY-MM-DD") into [since, until) timestamps
+pub fn parse_date_range(start: Option<&str>, end: Option<&str>) -> Result<(i64, i64), String> {
+    let parse = |date: &str| {
+        chrono::NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+            .map(|d| d.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc().timestamp())
+            .map_err(|e| format!("Invalid date '{}': {}", date, e))
+    };
+    let since = start.filter(|s| !s.trim().is_empty()).map(parse).transpose()?.unwrap_or(0);
+    let until = end
+        .filter(|s| !s.trim().is_empty())
+        .map(parse)
+        .transpose()?
+        .map_or(i64::MAX, |t| t + 86_400);
+    if since >= until {
+        return Err("Start date must not be after end date".to_string());
+    }
+    Ok((since, until))
+}
+
+fn query_records(conn: &Connection, since: i64, until: i64) -> Result<Vec<UsageRecord>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT timestamp, model, mapped_model, upstream, account_email, api_key_hash, api_key_hint, protocol,
+                prompt_tokens, completion_tokens, latency_ms, status, cost_usd, request_id, client_name, tokens_per_second,
+                cache_read_tokens, cache_write_tokens
+             FROM usage_records
+             WHERE timestamp >= ?1 AND timestamp < ?2
+             ORDER BY timestamp ASC, id ASC",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![since, until], |row| {
+            Ok(UsageRecord {
+                timestamp: row.get(0)?,
+                model: row.get(1)?,
+                mapped_model: row.get(2)?,
+                upstream: row.get(3)?,
+                account_email: row.get(4)?,
+                api_key_hash: row.get(5)?,
+                api_key_hint: row.get(6)?,
+                protocol: row.get(7)?,
+                prompt_tokens: row.get(8)?,
+                completion_tokens: row.get(9)?,
+                latency_ms: row.get::<_, i64>(10)? as u64,
+                status: row.get(11)?,
+                cost_usd: row.get(12)?,
+                request_id: row.get(13)?,
+                client_name: row.get(14)?,
+                tokens_per_second: row.get(15)?,
+                cache_read_tokens: row.get(16)?,
+                cache_write_tokens: row.get(17)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+fn query_grouped(
+    conn: &Connection,
+    groups: &[UsageExportGroup],
+    since: i64,
+    until: i64,
+) -> Result<Vec<UsageExportRow>, String> {
+    // Fixed column order regardless of the order the caller listed the groups in
+    let dims: Vec<UsageExportGroup> = [
+        UsageExportGroup::Day,
+        UsageExportGroup::Model,
+        UsageExportGroup::ApiKey,
+        UsageExportGroup::Client,
+        UsageExportGroup::Upstream,
+    ]
+    .into_iter()
+        .filter(|g| groups.contains(g))
+        .collect();
+    let labels: Vec<&str> = dims.iter().map(|g| g.group().columns().1).collect();
+    let keys: Vec<&str> = dims.iter().map(|g| g.group().columns().0).collect();
+    let sql = format!(
+        "SELECT {labels},
+            COUNT(*),
+            SUM(CASE WHEN status >= 400 THEN 1 ELSE 0 END),
+            SUM(prompt_tokens),
+            SUM(completion_tokens),
+            SUM(prompt_tokens + completion_tokens),
+            AVG(latency_ms),
+            SUM(cost_usd),
+            AVG(tokens_per_second)
+         FROM usage_records
+         WHERE timestamp >= ?1 AND timestamp < ?2
+         GROUP BY {keys}
+         ORDER BY {keys}",
+        labels = labels.join(", "),
+        keys = keys.join(", "),
+    );
+
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let n = dims.len();
+    let rows = stmt
+        .query_map(params![since, until], |row| {
+            let mut out = UsageExportRow {
+                day: None,
+                model: None,
+                api_key: None,
+                client: None,
+                upstream: None,
+                request_count: row.get(n)?,
+                error_count: row.get(n + 1)?,
+                prompt_tokens: row.get(n + 2)?,
+                completion_tokens: row.get(n + 3)?,
+                total_tokens: row.get(n + 4)?,
+                avg_latency_ms: row.get(n + 5)?,
+                avg_tokens_per_second: row.get(n + 7)?,
+                cost_usd: row.get(n + 6)?,
+            };
+            for (i, dim) in dims.iter().enumerate() {
+                let value: Option<String> = row.get(i)?;
+                match dim {
+                    UsageExportGroup::Day => out.day = value,
+                    UsageExportGroup::Model => out.model = value,
+                    UsageExportGroup::ApiKey => out.api_key = value,
+                    UsageExportGroup::Client => out.client = value,
+                    UsageExportGroup::Upstream => out.upstream = value,
+                }
+            }
+            Ok(out)
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn csv_line(fields: &[String]) -> String {
+    let mut line = fields.iter().map(|f| csv_field(f)).collect::<Vec<_>>().join(",");
+    line.push_str("\r\n");
+    line
+}
+
+fn records_to_csv(records: &[UsageRecord]) -> String {
+    let mut out = csv_line(
+        &[
+            "timestamp", "model", "mapped_model", "upstream", "account_email", "api_key", "client", "protocol",
+            "prompt_tokens", "completion_tokens", "cache_read_tokens", "cache_write_tokens", "latency_ms", "tokens_per_second",
+            "status", "cost_usd", "request_id",
+        ]
+        .map(String::from),
+    );
+    for r in records {
+        let time = chrono::DateTime::from_timestamp(r.timestamp, 0).map(|t| t.to_rfc3339()).unwrap_or_default();
+        out.push_str(&csv_line(&[
+            time,
+            r.model.clone(),
+            r.mapped_model.clone().unwrap_or_default(),
+            r.upstream.clone(),
+            r.account_email.clone().unwrap_or_default(),
+            r.api_key_hint.clone().unwrap_or_default(),
+            r.client_name.clone().unwrap_or_default(),
+            r.protocol.clone().unwrap_or_default(),
+            r.prompt_tokens.to_string(),
+            r.completion_tokens.to_string(),
+            r.cache_read_tokens.to_string(),
+            r.cache_write_tokens.to_string(),
+            r.latency_ms.to_string(),
+            r.tokens_per_second.map(|t| format!("{:.1}", t)).unwrap_or_default(),
+            r.status.to_string(),
+            r.cost_usd.to_string(),
+            r.request_id.clone().unwrap_or_default(),
+        ]));
+    }
+    out
+}
+
+fn rows_to_csv(rows: &[UsageExportRow], groups: &[UsageExportGroup]) -> String {
+    let dims = [
+        (UsageExportGroup::Day, "day"),
+        (UsageExportGroup::Model, "model"),
+        (UsageExportGroup::ApiKey, "api_key"),
+        (UsageExportGroup::Client, "client"),
+        (UsageExportGroup::Upstream, "upstream"),
+    ];
+    let dims: Vec<_> = dims.into_iter().filter(|(g, _)| groups.contains(g)).collect();
+    let mut header: Vec<String> = dims.iter().map(|(_, name)| name.to_string()).collect();
+    header.extend(
+        [
+            "request_count", "error_count", "prompt_tokens", "completion_tokens", "total_tokens", "avg_latency_ms",
+            "avg_tokens_per_second", "cost_usd",
+        ]
+            .map(String::from),
+    );
+    let mut out = csv_line(&header);
+    for r in rows {
+        let mut fields: Vec<String> = dims
+            .iter()
+            .map(|(g, _)| match g {
+                UsageExportGroup::Day => r.day.clone(),
+                UsageExportGroup::Model => r.model.clone(),
+                UsageExportGroup::ApiKey => r.api_key.clone(),
+                UsageExportGroup::Client => r.client.clone(),
+                UsageExportGroup::Upstream => r.upstream.clone(),
+            })
+            .map(Option::unwrap_or_default)
+            .collect();
+        fields.extend([
+            r.request_count.to_string(),
+            r.error_count.to_string(),
+            r.prompt_tokens.to_string(),
+            r.completion_tokens.to_string(),
+            r.total_tokens.to_string(),
+            format!("{:.1}", r.avg_latency_ms),
+            r.avg_tokens_per_second.map(|t| format!("{:.1}", t)).unwrap_or_default(),
+            r.cost_usd.to_string(),
+        ]);
+        out.push_str(&csv_line(&fields));
+    }
+    out
+}
+
+fn render_export(
+    conn: &Connection,
+    format: UsageExportFormat,
+    groups: &[UsageExportGroup],
+    since: i64,
+    until: i64,
+) -> Result<(String, usize), String> {
+    let to_json = |value: Result<String, serde_json::Error>| value.map_err(|e| format!("Failed to serialize usage: {}", e));
+    if groups.is_empty() {
+        let records = query_records(conn, since, until)?;
+        let content = match format {
+            UsageExportFormat::Csv => records_to_csv(&records),
+            UsageExportFormat::Json => to_json(serde_json::to_string_pretty(&records))?,
+        };
+        Ok((content, records.len()))
+    } else {
+        let rows = query_grouped(conn, groups, since, until)?;
+        let content = match format {
+            UsageExportFormat::Csv => rows_to_csv(&rows, groups),
+            UsageExportFormat::Json => to_json(serde_json::to_string_pretty(&rows))?,
+        };
+        Ok((content, rows.len()))
+    }
+}
+
+/// Export usage in a date range to `file_path`; returns the number of rows written
+pub fn export_usage(
+    file_path: &str,
+    format: UsageExportFormat,
+    start_date: Option<&str>,
+    end_date: Option<&str>,
+    groups: &[UsageExportGroup],
+) -> Result<usize, String> {
+    let (since, until) = parse_date_range(start_date, end_date)?;
+    let conn = connect_db()?;
+    let (content, count) = render_export(&conn, format, groups, since, until)?;
+    std::fs::write(file_path, content).map_err(|e| format!("Failed to write file: {}", e))?;
+    Ok(count)
+}
+
+/// Mask an API key for display: keep the first and last 4 characters
+pub fn mask_api_key(key: &str) -> String {
+    let chars: Vec<char> = key.chars().collect();
+    if chars.len() <= 8 {
+        return "****".to_string();
+    }
+    let head: String = chars[..4].iter().collect();
+    let tail: String = chars[chars.len() - 4..].iter().collect();
+    format!("{}…{}", head, tail)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(timestamp: i64, model: &str, key: Option<&str>, status: u16) -> UsageRecord {
+        UsageRecord {
+            timestamp,
+            model: model.to_string(),
+            upstream: "google".to_string(),
+            api_key_hash: key.map(|k| format!("hash-{}", k)),
+            api_key_hint: key.map(mask_api_key),
+            prompt_tokens: 10,
+            completion_tokens: 5,
+            latency_ms: 100,
+            status,
+            cost_usd: 0.25,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_aggregates_by_model_day_and_key() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn).unwrap();
+        let day1 = 1_700_000_000; // 2023-11-14
+        let day2 = day1 + 86_400;
+        insert_record(&conn, &sample(day1, "gemini-2.5-pro", Some("sk-aaaabbbbcccc"), 200)).unwrap();
+        insert_record(&conn, &sample(day2, "gemini-2.5-pro", Some("sk-aaaabbbbcccc"), 500)).unwrap();
+        insert_record(&conn, &sample(day2, "claude-sonnet-4-5", None, 200)).unwrap();
+
+        let by_model = query_aggregates(&conn, UsageGroup::Model, 0).unwrap();
+        assert_eq!(by_model[0].key, "gemini-2.5-pro");
+        assert_eq!(by_model[0].request_count, 2);
+        assert_eq!(by_model[0].error_count, 1);
+        assert_eq!(by_model[0].total_tokens, 30);
+
+        let by_day = query_aggregates(&conn, UsageGroup::Day, 0).unwrap();
+        assert_eq!(by_day.iter().map(|d| d.key.as_str()).collect::<Vec<_>>(), vec!["2023-11-14", "2023-11-15"]);
+
+        let by_key = query_aggregates(&conn, UsageGroup::ApiKey, 0).unwrap();
+        assert_eq!(by_key.len(), 2);
+        assert_eq!(by_key[0].label, "sk-a…cccc");
+        assert_eq!(by_key[1].key, "anonymous");
+
+        // since 过滤
+        assert_eq!(query_aggregates(&conn, UsageGroup::Model, day2).unwrap().len(), 2);
+        assert_eq!(query_aggregates(&conn, UsageGroup::Day, day2).unwrap().len(), 1);
+
+        assert_eq!(by_model[0].cost_usd, 0.5);
+
+        let totals = query_key_totals(&conn, "hash-sk-aaaabbbbcccc", 0).unwrap();
+        assert_eq!(totals, KeyTotals { requests: 2, tokens: 30, cost_usd: 0.5 });
+        assert_eq!(query_key_totals(&conn, "hash-sk-aaaabbbbcccc", day2).unwrap().requests, 1);
+        assert_eq!(query_key_totals(&conn, "missing", 0).unwrap(), KeyTotals::default());
+
+        let tagged = UsageRecord { client_name: Some("Cursor".to_string()), ..sample(day2, "claude-sonnet-4-5", None, 200) };
+        insert_record(&conn, &tagged).unwrap();
+        let by_client = query_aggregates(&conn, UsageGroup::Client, 0).unwrap();
+        let mut clients: Vec<(&str, u64)> = by_client.iter().map(|c| (c.key.as_str(), c.request_count)).collect();
+        clients.sort();
+        assert_eq!(clients, vec![("Cursor", 1), ("unknown", 3)]);
+
+        // 只统计测到速率的流式请求
+        for (upstream, tps) in [("zai", Some(40.0)), ("zai", Some(60.0)), ("zai", None)] {
+            let streamed = UsageRecord { upstream: upstream.to_string(), tokens_per_second: tps, ..sample(day2, "glm-4.6", None, 200) };
+            insert_record(&conn, &streamed).unwrap();
+        }
+        let by_upstream = query_aggregates(&conn, UsageGroup::Upstream, 0).unwrap();
+        let rates: Vec<(&str, Option<f64>)> = by_upstream.iter().map(|u| (u.key.as_str(), u.avg_tokens_per_second)).collect();
+        assert_eq!(rates, vec![("google", None), ("zai", Some(50.0))]);
+
+        let mut served = query_upstream_model_stats(&conn, day2).unwrap();
+        served.sort_by(|a, b| a.model.cmp(&b.model));
+        let served: Vec<(&str, &str, u64, u64)> =
+            served.iter().map(|s| (s.upstream.as_str(), s.model.as_str(), s.requests, s.errors)).collect();
+        assert_eq!(
+            served,
+            vec![("google", "claude-sonnet-4-5", 2, 0), ("google", "gemini-2.5-pro", 1, 1), ("zai", "glm-4.6", 3, 0)]
+        );
+    }
+
+    #[test]
+    fn test_export_grouped_and_raw() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn).unwrap();
+        let day1 = 1_700_000_000; // 2023-11-14
+        let day2 = day1 + 86_400;
+        insert_record(&conn, &sample(day1, "gemini-2.5-pro", Some("sk-aaaabbbbcccc"), 200)).unwrap();
+        insert_record(&conn, &sample(day2, "gemini-2.5-pro", Some("sk-aaaabbbbcccc"), 500)).unwrap();
+        insert_record(&conn, &sample(day2, "model,with \"comma\"", None, 200)).unwrap();
+
+        let (since, until) = parse_date_range(Some("2023-11-15"), Some("2023-11-15")).unwrap();
+        assert_eq!((since, until), (1_700_006_400, 1_700_092_800));
+        assert!(parse_date_range(Some("2023-11-16"), Some("2023-11-15")).is_err());
+
+        let groups = [UsageExportGroup::ApiKey, UsageExportGroup::Model];
+        let (csv, count) = render_export(&conn, UsageExportFormat::Csv, &groups, 0, i64::MAX).unwrap();
+        assert_eq!(count, 2);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            lines[0],
+            "model,api_key,request_count,error_count,prompt_tokens,completion_tokens,total_tokens,avg_latency_ms,avg_tokens_per_second,cost_usd"
+        );
+        assert_eq!(lines[1], "gemini-2.5-pro,sk-a…cccc,2,1,20,10,30,100.0,,0.5");
+        assert_eq!(lines[2], "\"model,with \"\"comma\"\"\",anonymous,1,0,10,5,15,100.0,,0.25");
+
+        let (json, count) = render_export(&conn, UsageExportFormat::Json, &[], since, until).unwrap();
+        assert_eq!(count, 2);
+        let records: Vec<UsageRecord> = serde_json::from_str(&json).unwrap();
+        assert!(records.iter().all(|r| r.timestamp == day2));
+    }
+
+    #[test]
+    fn test_mask_api_key() {
+        assert_eq!(mask_api_key("sk-1234567890abcd"), "sk-1…abcd");
+        assert_eq!(mask_api_key("short"), "****");
+    }
+}
@@ -0,0 +1,946 @@
+// Assistants API 兼容层
+// 本地实现 OpenAI Assistants API 的 assistants / threads / messages / runs。Run 在后台把助手指令、
+// 线程消息与函数工具组装为 Chat Completions 请求, 经本地反代执行 (沿用映射、路由、请求日志与用量统计),
+// 因此任意已配置的上游都可以服务 Assistants 客户端。模型调用函数时 Run 进入 `requires_action`,
+// 客户端提交工具输出后继续。只支持 `function` 工具与非流式 Run; 数据保存在 `<data_dir>/assistants.json`。
+
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock, RwLock};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::proxy::config::AssistantsConfig;
+
+const STORE_FILE: &str = "assistants.json";
+/// Run 请求在日志与用量中显示的客户端名称
+pub const CLIENT_NAME: &str = "assistants-api";
+
+#[derive(Debug)]
+pub enum AssistantsError {
+    NotFound(String),
+    Invalid(String),
+}
+
+type Result<T> = std::result::Result<T, AssistantsError>;
+
+fn not_found(kind: &str, id: &str) -> AssistantsError {
+    AssistantsError::NotFound(format!("No {} found with id '{}'", kind, id))
+}
+
+fn invalid(message: impl Into<String>) -> AssistantsError {
+    AssistantsError::Invalid(message.into())
+}
+
+fn now() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+fn new_id(prefix: &str) -> String {
+    format!("{}_{}", prefix, uuid::Uuid::new_v4().simple())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Assistant {
+    pub id: String,
+    pub object: String,
+    pub created_at: i64,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub model: String,
+    pub instructions: Option<String>,
+    #[serde(default)]
+    pub tools: Vec<Value>,
+    #[serde(default)]
+    pub metadata: Value,
+    pub temperature: Option<f64>,
+    pub top_p: Option<f64>,
+    pub response_format: Option<Value>,
+}
+
+/// 创建与修改助手; 修改时只更新提供的字段
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AssistantRequest {
+    pub model: Option<String>,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub instructions: Option<String>,
+    pub tools: Option<Vec<Value>>,
+    pub metadata: Option<Value>,
+    pub temperature: Option<f64>,
+    pub top_p: Option<f64>,
+    pub response_format: Option<Value>,
+}
+
+impl AssistantRequest {
+    fn apply(self, assistant: &mut Assistant) {
+        macro_rules! set {
+            ($($field:ident),*) => { $(if self.$field.is_some() { assistant.$field = self.$field; })* };
+        }
+        set!(name, description, instructions, temperature, top_p, response_format);
+        if let Some(model) = self.model {
+            assistant.model = model;
+        }
+        if let Some(tools) = self.tools {
+            assistant.tools = tools;
+        }
+        if let Some(metadata) = self.metadata {
+            assistant.metadata = metadata;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Thread {
+    pub id: String,
+    pub object: String,
+    pub created_at: i64,
+    #[serde(default)]
+    pub metadata: Value,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ThreadRequest {
+    #[serde(default)]
+    pub messages: Vec<MessageRequest>,
+    pub metadata: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Message {
+    pub id: String,
+    pub object: String,
+    pub created_at: i64,
+    pub thread_id: String,
+    pub status: String,
+    pub role: String,
+    pub content: Vec<Value>,
+    pub assistant_id: Option<String>,
+    pub run_id: Option<String>,
+    #[serde(default)]
+    pub attachments: Vec<Value>,
+    #[serde(default)]
+    pub metadata: Value,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MessageRequest {
+    pub role: String,
+    /// 文本或内容块数组 (`text` / `image_url` / `image_file`)
+    pub content: Value,
+    #[serde(default)]
+    pub attachments: Vec<Value>,
+    pub metadata: Option<Value>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RunStatus {
+    Queued,
+    InProgress,
+    RequiresAction,
+    Cancelling,
+    Cancelled,
+    Failed,
+    Completed,
+}
+
+impl RunStatus {
+    fn is_active(self) -> bool {
+        matches!(self, RunStatus::Queued | RunStatus::InProgress | RunStatus::RequiresAction | RunStatus::Cancelling)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Run {
+    pub id: String,
+    pub object: String,
+    pub created_at: i64,
+    pub thread_id: String,
+    pub assistant_id: String,
+    pub status: RunStatus,
+    pub required_action: Option<Value>,
+    pub last_error: Option<Value>,
+    pub expires_at: Option<i64>,
+    pub started_at: Option<i64>,
+    pub cancelled_at: Option<i64>,
+    pub failed_at: Option<i64>,
+    pub completed_at: Option<i64>,
+    pub model: String,
+    pub instructions: Option<String>,
+    #[serde(default)]
+    pub tools: Vec<Value>,
+    #[serde(default)]
+    pub metadata: Value,
+    pub usage: Option<Value>,
+    pub temperature: Option<f64>,
+    pub top_p: Option<f64>,
+    pub max_completion_tokens: Option<u32>,
+    pub response_format: Option<Value>,
+    pub tool_choice: Option<Value>,
+    pub parallel_tool_calls: Option<bool>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RunRequest {
+    pub assistant_id: String,
+    pub model: Option<String>,
+    pub instructions: Option<String>,
+    pub additional_instructions: Option<String>,
+    #[serde(default)]
+    pub additional_messages: Vec<MessageRequest>,
+    pub tools: Option<Vec<Value>>,
+    pub metadata: Option<Value>,
+    pub temperature: Option<f64>,
+    pub top_p: Option<f64>,
+    pub max_completion_tokens: Option<u32>,
+    pub response_format: Option<Value>,
+    pub tool_choice: Option<Value>,
+    pub parallel_tool_calls: Option<bool>,
+    #[serde(default)]
+    pub stream: bool,
+}
+
+/// `POST /v1/threads/runs`: 创建线程并立即运行
+#[derive(Debug, Clone, Deserialize)]
+pub struct ThreadAndRunRequest {
+    #[serde(default)]
+    pub thread: ThreadRequest,
+    #[serde(flatten)]
+    pub run: RunRequest,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ToolOutput {
+    pub tool_call_id: String,
+    #[serde(default)]
+    pub output: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ListQuery {
+    pub limit: Option<usize>,
+    pub order: Option<String>,
+    pub after: Option<String>,
+    /// 只列出该 Run 产生的消息
+    pub run_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Owned<T> {
+    owner: String,
+    #[serde(flatten)]
+    item: T,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredRun {
+    owner: String,
+    /// Run 内部的对话 (带 tool_calls 的助手消息与工具输出), 续跑时附在线程消息之后
+    #[serde(default)]
+    conversation: Vec<Value>,
+    #[serde(flatten)]
+    run: Run,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Data {
+    #[serde(default)]
+    assistants: Vec<Owned<Assistant>>,
+    #[serde(default)]
+    threads: Vec<Owned<Thread>>,
+    #[serde(default)]
+    messages: Vec<Message>,
+    #[serde(default)]
+    runs: Vec<StoredRun>,
+}
+
+impl Data {
+    fn thread(&self, owner: &str, id: &str) -> Result<&Thread> {
+        self.threads
+            .iter()
+            .find(|t| t.item.id == id && t.owner == owner)
+            .map(|t| &t.item)
+            .ok_or_else(|| not_found("thread", id))
+    }
+
+    fn run_mut(&mut self, owner: &str, thread_id: &str, id: &str) -> Result<&mut StoredRun> {
+        self.runs
+            .iter_mut()
+            .find(|r| r.run.id == id && r.run.thread_id == thread_id && r.owner == owner)
+            .ok_or_else(|| not_found("run", id))
+    }
+
+    fn has_active_run(&self, thread_id: &str) -> bool {
+        self.runs.iter().any(|r| r.run.thread_id == thread_id && r.run.status.is_active())
+    }
+}
+
+/// 按创建顺序排列的列表分页 (默认最新的在前)
+fn page<T: Clone>(mut items: Vec<T>, id: impl Fn(&T) -> &str, query: &ListQuery) -> (Vec<T>, bool) {
+    if query.order.as_deref() != Some("asc") {
+        items.reverse();
+    }
+    let start = query
+        .after
+        .as_deref()
+        .and_then(|after| items.iter().position(|item| id(item) == after))
+        .map_or(0, |p| p + 1);
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+    let has_more = items.len() > start + limit;
+    (items.into_iter().skip(start).take(limit).collect(), has_more)
+}
+
+fn text_part(text: &str) -> Value {
+    json!({ "type": "text", "text": { "value": text, "annotations": [] } })
+}
+
+/// 请求中的消息内容转为 Assistants 格式的内容块
+fn message_content(content: &Value) -> Result<Vec<Value>> {
+    match content {
+        Value::String(text) => Ok(vec![text_part(text)]),
+        Value::Array(parts) => parts
+            .iter()
+            .map(|part| match part.get("type").and_then(Value::as_str) {
+                Some("text") => {
+                    let text = part.get("text").and_then(|t| t.as_str().or_else(|| t.get("value")?.as_str()));
+                    Ok(text_part(text.unwrap_or_default()))
+                }
+                Some("image_url" | "image_file") => Ok(part.clone()),
+                other => Err(invalid(format!("Unsupported content type '{}'", other.unwrap_or_default()))),
+            })
+            .collect(),
+        _ => Err(invalid("'content' must be a string or an array of content parts")),
+    }
+}
+
+/// Assistants 内容块转为 Chat Completions 消息内容; 图片文件交给文件引用中间件展开
+fn chat_content(content: &[Value]) -> Value {
+    let parts: Vec<Value> = content
+        .iter()
+        .filter_map(|part| match part.get("type").and_then(Value::as_str)? {
+            "text" => Some(json!({ "type": "text", "text": part.pointer("/text/value")? })),
+            "image_url" => Some(json!({ "type": "image_url", "image_url": part.get("image_url")? })),
+            "image_file" => Some(json!({ "type": "file", "file": { "file_id": part.pointer("/image_file/file_id")? } })),
+            _ => None,
+        })
+        .collect();
+    if parts.iter().all(|p| p["type"] == "text") {
+        let texts: Vec<&str> = parts.iter().filter_map(|p| p["text"].as_str()).collect();
+        Value::String(texts.join("\n"))
+    } else {
+        Value::Array(parts)
+    }
+}
+
+fn new_message(thread_id: &str, request: MessageRequest) -> Result<Message> {
+    if !matches!(request.role.as_str(), "user" | "assistant") {
+        return Err(invalid(format!("Invalid role '{}', expected 'user' or 'assistant'", request.role)));
+    }
+    Ok(Message {
+        id: new_id("msg"),
+        object: "thread.message".to_string(),
+        created_at: now(),
+        thread_id: thread_id.to_string(),
+        status: "completed".to_string(),
+        role: request.role,
+        content: message_content(&request.content)?,
+        assistant_id: None,
+        run_id: None,
+        attachments: request.attachments,
+        metadata: request.metadata.unwrap_or_else(|| json!({})),
+    })
+}
+
+/// Run 当前一步的 Chat Completions 请求
+fn chat_request(stored: &StoredRun, messages: &[Message]) -> Value {
+    let run = &stored.run;
+    let mut chat = Vec::new();
+    if let Some(instructions) = run.instructions.as_deref().filter(|i| !i.trim().is_empty()) {
+        chat.push(json!({ "role": "system", "content": instructions }));
+    }
+    for message in messages.iter().filter(|m| m.thread_id == run.thread_id && m.run_id.as_deref() != Some(&run.id)) {
+        chat.push(json!({ "role": message.role, "content": chat_content(&message.content) }));
+    }
+    chat.extend(stored.conversation.iter().cloned());
+
+    let mut body = json!({ "model": run.model, "messages": chat, "stream": false });
+    let tools: Vec<Value> = run.tools.iter().filter(|t| t["type"] == "function").cloned().collect();
+    if !tools.is_empty() {
+        body["tools"] = Value::Array(tools);
+        if let Some(choice) = &run.tool_choice {
+            body["tool_choice"] = choice.clone();
+        }
+        if let Some(parallel) = run.parallel_tool_calls {
+            body["parallel_tool_calls"] = json!(parallel);
+        }
+    }
+    if let Some(format) = run.response_format.as_ref().filter(|f| f.as_str() != Some("auto")) {
+        body["response_format"] = format.clone();
+    }
+    for (key, value) in [("temperature", run.temperature), ("top_p", run.top_p)] {
+        if let Some(value) = value {
+            body[key] = json!(value);
+        }
+    }
+    if let Some(max) = run.max_completion_tokens {
+        body["max_completion_tokens"] = json!(max);
+    }
+    body
+}
+
+fn add_usage(run: &mut Run, usage: Option<&Value>) {
+    let total = run.usage.get_or_insert_with(|| json!({}));
+    for key in ["prompt_tokens", "completion_tokens", "total_tokens"] {
+        let current = total.get(key).and_then(Value::as_u64).unwrap_or(0);
+        let added = usage.and_then(|u| u.get(key)).and_then(Value::as_u64).unwrap_or(0);
+        total[key] = json!(current + added);
+    }
+}
+
+pub struct AssistantStore {
+    path: Option<PathBuf>,
+    data: Mutex<Data>,
+    config: RwLock<AssistantsConfig>,
+}
+
+impl AssistantStore {
+    fn new(path: Option<PathBuf>) -> Self {
+        let mut data: Data = path
+            .as_ref()
+            .and_then(|p| std::fs::read_to_string(p).ok())
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default();
+        // 停止前未完成的步骤无法继续; 等待工具输出的 Run 不受影响
+        for stored in &mut data.runs {
+            let run = &mut stored.run;
+            match run.status {
+                RunStatus::Queued | RunStatus::InProgress => {
+                    run.status = RunStatus::Failed;
+                    run.failed_at = Some(now());
+                    run.last_error = Some(json!({ "code": "server_error", "message": "The run was interrupted by a service restart" }));
+                }
+                RunStatus::Cancelling => {
+                    run.status = RunStatus::Cancelled;
+                    run.cancelled_at = Some(now());
+                }
+                _ => {}
+            }
+        }
+        Self { path, data: Mutex::new(data), config: RwLock::new(AssistantsConfig::default()) }
+    }
+
+    /// Global singleton instance, persisted to `<data_dir>/assistants.json`
+    pub fn global() -> &'static AssistantStore {
+        static INSTANCE: OnceLock<AssistantStore> = OnceLock::new();
+        INSTANCE.get_or_init(|| {
+            AssistantStore::new(crate::modules::account::get_data_dir().ok().map(|d| d.join(STORE_FILE)))
+        })
+    }
+
+    pub fn config(&self) -> AssistantsConfig {
+        self.config.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    fn read<R>(&self, f: impl FnOnce(&Data) -> R) -> R {
+        f(&self.data.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    /// 修改数据, 成功时保存
+    fn write<R>(&self, f: impl FnOnce(&mut Data) -> Result<R>) -> Result<R> {
+        let mut data = self.data.lock().unwrap_or_else(|e| e.into_inner());
+        let result = f(&mut data)?;
+        if let Some(path) = &self.path {
+            let saved = serde_json::to_string_pretty(&*data)
+                .map_err(|e| e.to_string())
+                .and_then(|content| std::fs::write(path, content).map_err(|e| e.to_string()));
+            if let Err(e) = saved {
+                tracing::warn!("[Assistants] failed_to_save_assistants: {}", e);
+            }
+        }
+        Ok(result)
+    }
+
+    // ===== Assistants =====
+
+    pub fn create_assistant(&self, owner: &str, request: AssistantRequest) -> Result<Assistant> {
+        let model = request.model.clone().filter(|m| !m.trim().is_empty()).ok_or_else(|| invalid("'model' is required"))?;
+        let mut assistant = Assistant {
+            id: new_id("asst"),
+            object: "assistant".to_string(),
+            created_at: now(),
+            name: None,
+            description: None,
+            model,
+            instructions: None,
+            tools: Vec::new(),
+            metadata: json!({}),
+            temperature: None,
+            top_p: None,
+            response_format: None,
+        };
+        request.apply(&mut assistant);
+        self.write(|data| {
+            data.assistants.push(Owned { owner: owner.to_string(), item: assistant.clone() });
+            Ok(assistant)
+        })
+    }
+
+    pub fn list_assistants(&self, owner: &str, query: &ListQuery) -> (Vec<Assistant>, bool) {
+        let owned = self.read(|data| {
+            data.assistants.iter().filter(|a| a.owner == owner).map(|a| a.item.clone()).collect()
+        });
+        page(owned, |a: &Assistant| &a.id, query)
+    }
+
+    pub fn get_assistant(&self, owner: &str, id: &str) -> Result<Assistant> {
+        self.read(|data| data.assistants.iter().find(|a| a.item.id == id && a.owner == owner).map(|a| a.item.clone()))
+            .ok_or_else(|| not_found("assistant", id))
+    }
+
+    pub fn update_assistant(&self, owner: &str, id: &str, request: AssistantRequest) -> Result<Assistant> {
+        self.write(|data| {
+            let assistant = data
+                .assistants
+                .iter_mut()
+                .find(|a| a.item.id == id && a.owner == owner)
+                .ok_or_else(|| not_found("assistant", id))?;
+            request.apply(&mut assistant.item);
+            Ok(assistant.item.clone())
+        })
+    }
+
+    pub fn delete_assistant(&self, owner: &str, id: &str) -> Result<()> {
+        self.write(|data| {
+            let index = data
+                .assistants
+                .iter()
+                .position(|a| a.item.id == id && a.owner == owner)
+                .ok_or_else(|| not_found("assistant", id))?;
+            data.assistants.remove(index);
+            Ok(())
+        })
+    }
+
+    // ===== Threads & Messages =====
+
+    pub fn create_thread(&self, owner: &str, request: ThreadRequest) -> Result<Thread> {
+        let thread = Thread {
+            id: new_id("thread"),
+            object: "thread".to_string(),
+            created_at: now(),
+            metadata: request.metadata.unwrap_or_else(|| json!({})),
+        };
+        let messages = request
+            .messages
+            .into_iter()
+            .map(|m| new_message(&thread.id, m))
+            .collect::<Result<Vec<_>>>()?;
+        self.write(|data| {
+            data.threads.push(Owned { owner: owner.to_string(), item: thread.clone() });
+            data.messages.extend(messages);
+            Ok(thread)
+        })
+    }
+
+    pub fn get_thread(&self, owner: &str, id: &str) -> Result<Thread> {
+        self.read(|data| data.thread(owner, id).cloned())
+    }
+
+    pub fn update_thread(&self, owner: &str, id: &str, metadata: Option<Value>) -> Result<Thread> {
+        self.write(|data| {
+            let thread = data
+                .threads
+                .iter_mut()
+                .find(|t| t.item.id == id && t.owner == owner)
+                .ok_or_else(|| not_found("thread", id))?;
+            if let Some(metadata) = metadata {
+                thread.item.metadata = metadata;
+            }
+            Ok(thread.item.clone())
+        })
+    }
+
+    /// 删除线程及其消息与 Run
+    pub fn delete_thread(&self, owner: &str, id: &str) -> Result<()> {
+        self.write(|data| {
+            data.thread(owner, id)?;
+            data.threads.retain(|t| t.item.id != id);
+            data.messages.retain(|m| m.thread_id != id);
+            data.runs.retain(|r| r.run.thread_id != id);
+            Ok(())
+        })
+    }
+
+    pub fn create_message(&self, owner: &str, thread_id: &str, request: MessageRequest) -> Result<Message> {
+        let message = new_message(thread_id, request)?;
+        self.write(|data| {
+            data.thread(owner, thread_id)?;
+            if data.has_active_run(thread_id) {
+                return Err(invalid(format!("Can't add messages to {} while a run is active", thread_id)));
+            }
+            data.messages.push(message.clone());
+            Ok(message)
+        })
+    }
+
+    pub fn list_messages(&self, owner: &str, thread_id: &str, query: &ListQuery) -> Result<(Vec<Message>, bool)> {
+        let messages = self.read(|data| {
+            data.thread(owner, thread_id)?;
+            Ok(data
+                .messages
+                .iter()
+                .filter(|m| m.thread_id == thread_id)
+                .filter(|m| query.run_id.is_none() || m.run_id == query.run_id)
+                .cloned()
+                .collect::<Vec<_>>())
+        })?;
+        Ok(page(messages, |m: &Message| &m.id, query))
+    }
+
+    pub fn get_message(&self, owner: &str, thread_id: &str, id: &str) -> Result<Message> {
+        self.read(|data| {
+            data.thread(owner, thread_id)?;
+            data.messages
+                .iter()
+                .find(|m| m.id == id && m.thread_id == thread_id)
+                .cloned()
+                .ok_or_else(|| not_found("message", id))
+        })
+    }
+
+    // ===== Runs =====
+
+    pub fn create_run(&self, owner: &str, thread_id: &str, request: RunRequest) -> Result<Run> {
+        if request.stream {
+            return Err(invalid("Streaming runs are not supported; poll the run instead"));
+        }
+        let assistant = self.get_assistant(owner, &request.assistant_id)?;
+        let additional = request
+            .additional_messages
+            .into_iter()
+            .map(|m| new_message(thread_id, m))
+            .collect::<Result<Vec<_>>>()?;
+        let mut instructions = request.instructions.or(assistant.instructions);
+        if let Some(extra) = request.additional_instructions.filter(|i| !i.trim().is_empty()) {
+            instructions = Some(match instructions.filter(|i| !i.trim().is_empty()) {
+                Some(base) => format!("{}\n\n{}", base, extra),
+                None => extra,
+            });
+        }
+        let run = Run {
+            id: new_id("run"),
+            object: "thread.run".to_string(),
+            created_at: now(),
+            thread_id: thread_id.to_string(),
+            assistant_id: assistant.id,
+            status: RunStatus::Queued,
+            required_action: None,
+            last_error: None,
+            expires_at: None,
+            started_at: None,
+            cancelled_at: None,
+            failed_at: None,
+            completed_at: None,
+            model: request.model.unwrap_or(assistant.model),
+            instructions,
+            tools: request.tools.unwrap_or(assistant.tools),
+            metadata: request.metadata.unwrap_or_else(|| json!({})),
+            usage: None,
+            temperature: request.temperature.or(assistant.temperature),
+            top_p: request.top_p.or(assistant.top_p),
+            max_completion_tokens: request.max_completion_tokens,
+            response_format: request.response_format.or(assistant.response_format),
+            tool_choice: request.tool_choice,
+            parallel_tool_calls: request.parallel_tool_calls,
+        };
+        self.write(|data| {
+            data.thread(owner, thread_id)?;
+            if data.has_active_run(thread_id) {
+                return Err(invalid(format!("Thread {} already has an active run", thread_id)));
+            }
+            data.messages.extend(additional);
+            data.runs.push(StoredRun { owner: owner.to_string(), conversation: Vec::new(), run: run.clone() });
+            Ok(run)
+        })
+    }
+
+    pub fn create_thread_and_run(&self, owner: &str, request: ThreadAndRunRequest) -> Result<Run> {
+        // 先确认助手存在, 避免留下空线程
+        self.get_assistant(owner, &request.run.assistant_id)?;
+        let thread = self.create_thread(owner, request.thread)?;
+        self.create_run(owner, &thread.id, request.run)
+    }
+
+    pub fn list_runs(&self, owner: &str, thread_id: &str, query: &ListQuery) -> Result<(Vec<Run>, bool)> {
+        let runs = self.read(|data| {
+            data.thread(owner, thread_id)?;
+            Ok(data.runs.iter().filter(|r| r.run.thread_id == thread_id).map(|r| r.run.clone()).collect::<Vec<_>>())
+        })?;
+        Ok(page(runs, |r: &Run| &r.id, query))
+    }
+
+    pub fn get_run(&self, owner: &str, thread_id: &str, id: &str) -> Result<Run> {
+        self.read(|data| {
+            data.runs
+                .iter()
+                .find(|r| r.run.id == id && r.run.thread_id == thread_id && r.owner == owner)
+                .map(|r| r.run.clone())
+                .ok_or_else(|| not_found("run", id))
+        })
+    }
+
+    pub fn cancel_run(&self, owner: &str, thread_id: &str, id: &str) -> Result<Run> {
+        self.write(|data| {
+            let stored = data.run_mut(owner, thread_id, id)?;
+            let run = &mut stored.run;
+            match run.status {
+                // 正在执行的步骤结束时再标记为已取消
+                RunStatus::Queued | RunStatus::InProgress => run.status = RunStatus::Cancelling,
+                RunStatus::RequiresAction => {
+                    run.status = RunStatus::Cancelled;
+                    run.cancelled_at = Some(now());
+                    run.required_action = None;
+                }
+                status => return Err(invalid(format!("Cannot cancel run with status '{:?}'", status).to_lowercase())),
+            }
+            Ok(run.clone())
+        })
+    }
+
+    pub fn submit_tool_outputs(&self, owner: &str, thread_id: &str, id: &str, outputs: Vec<ToolOutput>) -> Result<Run> {
+        self.write(|data| {
+            let stored = data.run_mut(owner, thread_id, id)?;
+            if stored.run.status != RunStatus::RequiresAction {
+                return Err(invalid(format!("Run {} is not waiting for tool outputs", id)));
+            }
+            let expected: Vec<&str> = stored
+                .run
+                .required_action
+                .as_ref()
+                .and_then(|a| a.pointer("/submit_tool_outputs/tool_calls"))
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(|c| c["id"].as_str())
+                .collect();
+            if let Some(missing) = expected.iter().find(|id| !outputs.iter().any(|o| o.tool_call_id == **id)) {
+                return Err(invalid(format!("Missing output for tool call '{}'", missing)));
+            }
+            for output in outputs.into_iter().filter(|o| expected.contains(&o.tool_call_id.as_str())) {
+                stored.conversation.push(json!({ "role": "tool", "tool_call_id": output.tool_call_id, "content": output.output }));
+            }
+            stored.run.status = RunStatus::Queued;
+            stored.run.required_action = None;
+            Ok(stored.run.clone())
+        })
+    }
+
+    /// 开始执行一步: 标记为 in_progress 并返回 Chat Completions 请求; 已取消时返回 None
+    fn begin_step(&self, id: &str) -> Option<Value> {
+        self.write(|data| {
+            let messages = std::mem::take(&mut data.messages);
+            let step = data.runs.iter_mut().find(|r| r.run.id == id).and_then(|stored| {
+                let run = &mut stored.run;
+                match run.status {
+                    RunStatus::Cancelling => {
+                        run.status = RunStatus::Cancelled;
+                        run.cancelled_at = Some(now());
+                        None
+                    }
+                    RunStatus::Queued => {
+                        run.status = RunStatus::InProgress;
+                        run.started_at.get_or_insert_with(now);
+                        Some(chat_request(stored, &messages))
+                    }
+                    _ => None,
+                }
+            });
+            data.messages = messages;
+            Ok(step)
+        })
+        .ok()
+        .flatten()
+    }
+
+    /// 记录一步的结果: `Ok((状态码, 响应))` 或网络错误
+    fn finish_step(&self, id: &str, result: std::result::Result<(u16, Value), String>) {
+        let _ = self.write(|data| {
+            let Some(stored) = data.runs.iter_mut().find(|r| r.run.id == id) else {
+                return Ok(());
+            };
+            let run = &mut stored.run;
+            if run.status == RunStatus::Cancelling {
+                run.status = RunStatus::Cancelled;
+                run.cancelled_at = Some(now());
+                return Ok(());
+            }
+            let completion = match result {
+                Ok((status, body)) if (200..300).contains(&status) => body,
+                other => {
+                    let (code, message) = match other {
+                        Ok((429, body)) => ("rate_limit_exceeded", body),
+                        Ok((_, body)) => ("server_error", body),
+                        Err(e) => ("server_error", Value::String(e)),
+                    };
+                    let message = message.pointer("/error/message").cloned().unwrap_or(message);
+                    run.status = RunStatus::Failed;
+                    run.failed_at = Some(now());
+                    run.last_error = Some(json!({ "code": code, "message": message }));
+                    tracing::warn!("[Assistants] run {} failed: {}", run.id, message);
+                    return Ok(());
+                }
+            };
+            add_usage(run, completion.get("usage"));
+            let message = completion.pointer("/choices/0/message").cloned().unwrap_or_else(|| json!({}));
+            let tool_calls = message.get("tool_calls").and_then(Value::as_array).filter(|c| !c.is_empty()).cloned();
+            if let Some(tool_calls) = tool_calls {
+                run.status = RunStatus::RequiresAction;
+                run.required_action = Some(json!({
+                    "type": "submit_tool_outputs",
+                    "submit_tool_outputs": { "tool_calls": tool_calls },
+                }));
+                stored.conversation.push(json!({ "role": "assistant", "content": message.get("content"), "tool_calls": tool_calls }));
+                return Ok(());
+            }
+            run.status = RunStatus::Completed;
+            run.completed_at = Some(now());
+            let reply = Message {
+                id: new_id("msg"),
+                object: "thread.message".to_string(),
+                created_at: now(),
+                thread_id: run.thread_id.clone(),
+                status: "completed".to_string(),
+                role: "assistant".to_string(),
+                content: vec![text_part(message.get("content").and_then(Value::as_str).unwrap_or_default())],
+                assistant_id: Some(run.assistant_id.clone()),
+                run_id: Some(run.id.clone()),
+                attachments: Vec::new(),
+                metadata: json!({}),
+            };
+            data.messages.push(reply);
+            Ok(())
+        });
+    }
+
+    /// 在后台执行 Run 的下一步, 请求经本地端口发出
+    pub fn spawn_step(&'static self, id: String, api_key: Option<String>, port: u16, timeout_secs: u64) {
+        tokio::spawn(async move {
+            let Some(body) = self.begin_step(&id) else {
+                return;
+            };
+            let client = match crate::proxy::handlers::common::build_upstream_client(Default::default(), timeout_secs) {
+                Ok(client) => client,
+                Err(e) => return self.finish_step(&id, Err(e.to_string())),
+            };
+            let mut req = client
+                .post(format!("http://127.0.0.1:{}/v1/chat/completions", port))
+                .header("Content-Type", "application/json")
+                .header(crate::proxy::common::client_info::CLIENT_NAME_HEADER, CLIENT_NAME)
+                .body(body.to_string());
+            if let Some(key) = api_key {
+                req = req.bearer_auth(key);
+            }
+            let result = async {
+                let resp = req.send().await.map_err(|e| format!("Request failed: {}", e))?;
+                let status = resp.status().as_u16();
+                let text = resp.text().await.map_err(|e| format!("Request failed: {}", e))?;
+                Ok((status, serde_json::from_str::<Value>(&text).unwrap_or(Value::String(text))))
+            }
+            .await;
+            self.finish_step(&id, result);
+        });
+    }
+}
+
+/// 应用 Assistants API 配置 (服务启动与配置热更新时调用)
+pub fn configure(config: &AssistantsConfig) {
+    *AssistantStore::global().config.write().unwrap_or_else(|e| e.into_inner()) = config.clone();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(content: Value) -> MessageRequest {
+        MessageRequest { role: "user".to_string(), content, attachments: Vec::new(), metadata: None }
+    }
+
+    fn run_request(assistant_id: &str) -> RunRequest {
+        serde_json::from_value(json!({ "assistant_id": assistant_id, "additional_instructions": "Be brief." })).unwrap()
+    }
+
+    fn setup(store: &AssistantStore) -> Run {
+        let assistant = store
+            .create_assistant("owner", AssistantRequest {
+                model: Some("gpt-4o".to_string()),
+                instructions: Some("You are a weather bot.".to_string()),
+                tools: Some(vec![
+                    json!({"type": "function", "function": {"name": "get_weather", "parameters": {"type": "object"}}}),
+                    json!({"type": "code_interpreter"}),
+                ]),
+                ..Default::default()
+            })
+            .unwrap();
+        let thread = store
+            .create_thread("owner", ThreadRequest {
+                messages: vec![message(json!([
+                    {"type": "text", "text": "What is on this map?"},
+                    {"type": "image_file", "image_file": {"file_id": "file-1"}}
+                ]))],
+                metadata: None,
+            })
+            .unwrap();
+        store.create_run("owner", &thread.id, run_request(&assistant.id)).unwrap()
+    }
+
+    #[test]
+    fn test_chat_request_from_thread() {
+        let store = AssistantStore::new(None);
+        let run = setup(&store);
+        let body = store.begin_step(&run.id).unwrap();
+        assert_eq!(body["model"], "gpt-4o");
+        assert_eq!(body["messages"][0], json!({"role": "system", "content": "You are a weather bot.\n\nBe brief."}));
+        assert_eq!(body["messages"][1]["content"][1], json!({"type": "file", "file": {"file_id": "file-1"}}));
+        // 只转发函数工具
+        assert_eq!(body["tools"].as_array().unwrap().len(), 1);
+        assert_eq!(store.get_run("owner", &run.thread_id, &run.id).unwrap().status, RunStatus::InProgress);
+        assert!(store.get_run("other", &run.thread_id, &run.id).is_err());
+    }
+
+    #[test]
+    fn test_run_with_tool_calls() {
+        let path = std::env::temp_dir().join(format!("assistants_test_{}.json", uuid::Uuid::new_v4().simple()));
+        let store = AssistantStore::new(Some(path.clone()));
+        let run = setup(&store);
+        let thread_id = run.thread_id.clone();
+        store.begin_step(&run.id).unwrap();
+        let call = json!({"id": "call_1", "type": "function", "function": {"name": "get_weather", "arguments": "{}"}});
+        let usage = json!({"prompt_tokens": 10, "completion_tokens": 2, "total_tokens": 12});
+        let reply = json!({"choices": [{"message": {"role": "assistant", "content": null, "tool_calls": [call]}}], "usage": usage});
+        store.finish_step(&run.id, Ok((200, reply)));
+        let waiting = store.get_run("owner", &thread_id, &run.id).unwrap();
+        assert_eq!(waiting.status, RunStatus::RequiresAction);
+        assert!(store.create_message("owner", &thread_id, message(json!("hi"))).is_err());
+
+        let outputs = vec![ToolOutput { tool_call_id: "call_1".to_string(), output: "sunny".to_string() }];
+        assert!(store.submit_tool_outputs("owner", &thread_id, &run.id, Vec::new()).is_err());
+        store.submit_tool_outputs("owner", &thread_id, &run.id, outputs).unwrap();
+        let body = store.begin_step(&run.id).unwrap();
+        let messages = body["messages"].as_array().unwrap();
+        assert_eq!(messages[messages.len() - 1], json!({"role": "tool", "tool_call_id": "call_1", "content": "sunny"}));
+
+        let reply = json!({"choices": [{"message": {"role": "assistant", "content": "It is sunny."}}], "usage": usage});
+        store.finish_step(&run.id, Ok((200, reply)));
+        let done = store.get_run("owner", &thread_id, &run.id).unwrap();
+        assert_eq!((done.status, done.usage.unwrap()["total_tokens"].as_u64()), (RunStatus::Completed, Some(24)));
+        let (latest, _) = store.list_messages("owner", &thread_id, &ListQuery { limit: Some(1), ..Default::default() }).unwrap();
+        assert_eq!(latest[0].content[0]["text"]["value"], "It is sunny.");
+        assert_eq!(latest[0].run_id.as_deref(), Some(run.id.as_str()));
+
+        // 重新加载后数据仍在
+        let reloaded = AssistantStore::new(Some(path.clone()));
+        assert_eq!(reloaded.list_messages("owner", &thread_id, &ListQuery::default()).unwrap().0.len(), 2);
+        std::fs::remove_file(&path).ok();
+    }
+}
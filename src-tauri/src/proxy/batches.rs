@@ -0,0 +1,762 @@
+// Batch API
+// 本地实现 OpenAI `/v1/batches`: JSONL 中的请求逐条经本地反代执行 (沿用正常的映射、路由、请求日志与用量统计)。
+// 批次按创建顺序依次执行, 批次内按配置的并发数与每分钟请求数发出。状态与结果保存在 `<data_dir>/batches`,
+// 服务重启后未完成的批次从尚未执行的请求继续。子请求使用创建者的 API Key, Key 只保存在内存中:
+// 重启后需要创建者再次访问 Batch API, 其批次才会继续执行。
+// 输入可以引用 Files API 上传的文件, 结束时结果文件同样写入文件存储 (用途 `batch_output`)。
+
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock, RwLock};
+use std::time::Duration;
+
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::proxy::config::BatchConfig;
+
+const STORE_DIR: &str = "batches";
+/// 子请求在日志与用量中显示的客户端名称
+pub const CLIENT_NAME: &str = "batch-api";
+/// 支持批量执行的接口
+pub const ENDPOINTS: &[&str] = &["/v1/chat/completions", "/v1/completions", "/v1/embeddings", "/v1/responses"];
+/// 创建时最多报告的输入错误数
+const MAX_LINE_ERRORS: usize = 100;
+/// 唯一支持的 completion_window
+const COMPLETION_WINDOW: &str = "24h";
+const COMPLETION_WINDOW_SECS: i64 = 86_400;
+/// 没有可执行的批次时检查过期的间隔
+const IDLE_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchStatus {
+    Validating,
+    Failed,
+    InProgress,
+    Finalizing,
+    Completed,
+    Expired,
+    Cancelling,
+    Cancelled,
+}
+
+impl BatchStatus {
+    fn is_pending(self) -> bool {
+        matches!(self, BatchStatus::Validating | BatchStatus::InProgress | BatchStatus::Finalizing)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct RequestCounts {
+    pub total: usize,
+    pub completed: usize,
+    pub failed: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BatchLineError {
+    pub code: String,
+    pub message: String,
+    /// 输入中的行号 (从 1 开始)
+    pub line: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BatchErrors {
+    pub object: String,
+    pub data: Vec<BatchLineError>,
+}
+
+/// OpenAI 格式的批次对象
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Batch {
+    pub id: String,
+    pub object: String,
+    pub endpoint: String,
+    pub errors: Option<BatchErrors>,
+    pub input_file_id: Option<String>,
+    pub completion_window: String,
+    pub status: BatchStatus,
+    pub output_file_id: Option<String>,
+    pub error_file_id: Option<String>,
+    pub created_at: i64,
+    pub in_progress_at: Option<i64>,
+    pub expires_at: Option<i64>,
+    pub finalizing_at: Option<i64>,
+    pub completed_at: Option<i64>,
+    pub failed_at: Option<i64>,
+    pub expired_at: Option<i64>,
+    pub cancelling_at: Option<i64>,
+    pub cancelled_at: Option<i64>,
+    pub request_counts: RequestCounts,
+    pub metadata: Option<Value>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreateBatchRequest {
+    /// Files API 上传的 JSONL 文件
+    #[serde(default)]
+    pub input_file_id: Option<String>,
+    /// 直接提交的 JSONL 文本或请求对象数组
+    #[serde(default)]
+    pub input: Option<Value>,
+    pub endpoint: String,
+    #[serde(default = "default_completion_window")]
+    pub completion_window: String,
+    #[serde(default)]
+    pub metadata: Option<Value>,
+}
+
+fn default_completion_window() -> String {
+    COMPLETION_WINDOW.to_string()
+}
+
+/// 输入文件中的一行
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BatchRequestLine {
+    pub custom_id: String,
+    pub method: String,
+    pub url: String,
+    pub body: Value,
+}
+
+/// 结果文件 (`output` 或 `errors`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchFile {
+    Output,
+    Errors,
+}
+
+impl BatchFile {
+    fn suffix(self) -> &'static str {
+        match self {
+            BatchFile::Output => "output",
+            BatchFile::Errors => "errors",
+        }
+    }
+}
+
+/// 存储中的批次, 附带创建者的 Key 指纹
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredBatch {
+    owner: String,
+    #[serde(flatten)]
+    batch: Batch,
+}
+
+/// 一个请求的执行结果
+struct LineResult {
+    ok: bool,
+    line: Value,
+}
+
+fn now() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+fn line_error(code: &str, message: String, line: Option<usize>) -> BatchLineError {
+    BatchLineError { code: code.to_string(), message, line }
+}
+
+/// 解析并校验 JSONL 输入
+pub fn parse_input(text: &str, endpoint: &str, max_requests: usize) -> Result<Vec<BatchRequestLine>, Vec<BatchLineError>> {
+    let mut lines = Vec::new();
+    let mut errors = Vec::new();
+    let mut seen = HashSet::new();
+    for (index, raw) in text.lines().enumerate().filter(|(_, l)| !l.trim().is_empty()) {
+        let number = Some(index + 1);
+        let line = match serde_json::from_str::<BatchRequestLine>(raw) {
+            Ok(line) => line,
+            Err(e) => {
+                errors.push(line_error("invalid_json_line", format!("Invalid request line: {}", e), number));
+                continue;
+            }
+        };
+        if line.custom_id.is_empty() || !seen.insert(line.custom_id.clone()) {
+            let message = format!("custom_id '{}' is empty or duplicated", line.custom_id);
+            errors.push(line_error("duplicate_custom_id", message, number));
+        } else if !line.method.eq_ignore_ascii_case("POST") {
+            errors.push(line_error("invalid_method", format!("Unsupported method '{}'", line.method), number));
+        } else if line.url != endpoint {
+            let message = format!("URL '{}' does not match the batch endpoint '{}'", line.url, endpoint);
+            errors.push(line_error("mismatched_endpoint", message, number));
+        } else if !line.body.is_object() {
+            errors.push(line_error("invalid_body", "Request body must be a JSON object".to_string(), number));
+        }
+        lines.push(line);
+    }
+    if lines.is_empty() && errors.is_empty() {
+        errors.push(line_error("empty_file", "The batch contains no requests".to_string(), None));
+    }
+    if lines.len() > max_requests {
+        let message = format!("The batch contains {} requests, at most {} are allowed", lines.len(), max_requests);
+        errors.push(line_error("too_many_requests", message, None));
+    }
+    if errors.is_empty() {
+        Ok(lines)
+    } else {
+        errors.truncate(MAX_LINE_ERRORS);
+        Err(errors)
+    }
+}
+
+/// 请求中的 `input` 转为 JSONL 文本
+fn input_text(input: &Value) -> Result<String, String> {
+    match input {
+        Value::String(s) => Ok(s.clone()),
+        Value::Array(items) => Ok(items.iter().map(Value::to_string).collect::<Vec<_>>().join("\n")),
+        _ => Err("'input' must be a JSONL string or an array of request objects".to_string()),
+    }
+}
+
+fn result_line(custom_id: &str, response: Option<Value>, error: Option<Value>) -> Value {
+    json!({
+        "id": format!("batch_req_{}", uuid::Uuid::new_v4().simple()),
+        "custom_id": custom_id,
+        "response": response,
+        "error": error,
+    })
+}
+
+/// 批次中的子请求一律非流式
+fn prepare_body(mut body: Value) -> Value {
+    if let Some(obj) = body.as_object_mut() {
+        obj.remove("stream_options");
+        if obj.contains_key("stream") {
+            obj.insert("stream".to_string(), Value::Bool(false));
+        }
+    }
+    body
+}
+
+async fn execute(client: &reqwest::Client, base_url: &str, api_key: Option<&str>, line: BatchRequestLine) -> LineResult {
+    let mut req = client
+        .post(format!("{}{}", base_url, line.url))
+        .header("Content-Type", "application/json")
+        .header(crate::proxy::common::client_info::CLIENT_NAME_HEADER, CLIENT_NAME);
+    if let Some(key) = api_key {
+        req = req.bearer_auth(key);
+    }
+    let result = async {
+        let resp = req.body(prepare_body(line.body).to_string()).send().await?;
+        let status = resp.status().as_u16();
+        let request_id = resp
+            .headers()
+            .get(crate::proxy::middleware::request_id::REQUEST_ID_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .map(String::from);
+        Ok::<_, reqwest::Error>((status, request_id, resp.text().await?))
+    }
+    .await;
+    match result {
+        Ok((status, request_id, text)) => {
+            let body = serde_json::from_str::<Value>(&text).unwrap_or(Value::String(text));
+            let ok = (200..300).contains(&status);
+            let response = json!({ "status_code": status, "request_id": request_id, "body": body });
+            LineResult { ok, line: result_line(&line.custom_id, Some(response), None) }
+        }
+        Err(e) => {
+            let error = json!({ "code": "request_failed", "message": format!("Request failed: {}", e) });
+            LineResult { ok: false, line: result_line(&line.custom_id, None, Some(error)) }
+        }
+    }
+}
+
+pub struct BatchStore {
+    dir: PathBuf,
+    /// 按创建顺序
+    batches: Mutex<Vec<StoredBatch>>,
+    /// Key 指纹 → Key, 用于执行子请求 (不落盘)
+    keys: Mutex<HashMap<String, String>>,
+    config: RwLock<BatchConfig>,
+    wake: tokio::sync::Notify,
+    /// 每次启动 / 停止服务时递增, 旧的执行任务随之退出
+    epoch: AtomicU64,
+    /// 正在执行的批次
+    running: Mutex<Option<String>>,
+    /// 下一个请求最早的发出时间 (每分钟请求数限制)
+    next_slot: tokio::sync::Mutex<tokio::time::Instant>,
+}
+
+impl BatchStore {
+    fn new(dir: PathBuf) -> Self {
+        let mut batches: Vec<StoredBatch> = std::fs::read_dir(&dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|e| e == "json"))
+            .filter_map(|p| serde_json::from_str::<StoredBatch>(&std::fs::read_to_string(p).ok()?).ok())
+            .collect();
+        batches.sort_by_key(|b| b.batch.created_at);
+        let store = Self {
+            dir,
+            batches: Mutex::new(Vec::new()),
+            keys: Mutex::new(HashMap::new()),
+            config: RwLock::new(BatchConfig::default()),
+            wake: tokio::sync::Notify::new(),
+            epoch: AtomicU64::new(0),
+            running: Mutex::new(None),
+            next_slot: tokio::sync::Mutex::new(tokio::time::Instant::now()),
+        };
+        // 停止前正在取消的批次不再继续
+        for stored in batches.iter_mut().filter(|b| b.batch.status == BatchStatus::Cancelling) {
+            stored.batch.status = BatchStatus::Cancelled;
+            stored.batch.cancelled_at = Some(now());
+            store.save(stored);
+        }
+        *store.batches.lock().unwrap_or_else(|e| e.into_inner()) = batches;
+        store
+    }
+
+    /// Global singleton instance, persisted to `<data_dir>/batches`
+    pub fn global() -> &'static BatchStore {
+        static INSTANCE: OnceLock<BatchStore> = OnceLock::new();
+        INSTANCE.get_or_init(|| {
+            let dir = crate::modules::account::get_data_dir()
+                .unwrap_or_else(|_| std::env::temp_dir())
+                .join(STORE_DIR);
+            BatchStore::new(dir)
+        })
+    }
+
+    pub fn config(&self) -> BatchConfig {
+        self.config.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    fn path(&self, id: &str, kind: &str) -> PathBuf {
+        self.dir.join(format!("{}.{}", id, kind))
+    }
+
+    fn save(&self, stored: &StoredBatch) {
+        let result = std::fs::create_dir_all(&self.dir)
+            .and_then(|_| std::fs::write(self.path(&stored.batch.id, "json"), serde_json::to_vec_pretty(stored).unwrap_or_default()));
+        if let Err(e) = result {
+            tracing::warn!("[Batch] Failed to save batch {}: {}", stored.batch.id, e);
+        }
+    }
+
+    /// 修改批次并保存, 返回修改后的批次
+    fn update<F: FnOnce(&mut Batch)>(&self, id: &str, f: F) -> Option<Batch> {
+        let mut batches = self.batches.lock().unwrap_or_else(|e| e.into_inner());
+        let stored = batches.iter_mut().find(|b| b.batch.id == id)?;
+        f(&mut stored.batch);
+        self.save(stored);
+        Some(stored.batch.clone())
+    }
+
+    /// 记下创建者的 Key, 重启后凭此继续执行其批次
+    pub fn remember_key(&self, owner: &str, key: Option<&str>) {
+        if let Some(key) = key {
+            let mut keys = self.keys.lock().unwrap_or_else(|e| e.into_inner());
+            if keys.insert(owner.to_string(), key.to_string()).is_none() {
+                self.wake.notify_one();
+            }
+        }
+    }
+
+    pub fn create(&self, owner: &str, request: CreateBatchRequest) -> Result<Batch, String> {
+        if !ENDPOINTS.contains(&request.endpoint.as_str()) {
+            return Err(format!("Unsupported endpoint '{}', expected one of: {}", request.endpoint, ENDPOINTS.join(", ")));
+        }
+        if request.completion_window != COMPLETION_WINDOW {
+            return Err(format!("Unsupported completion_window '{}', only '24h' is supported", request.completion_window));
+        }
+        let text = match (&request.input, &request.input_file_id) {
+            (Some(input), _) => input_text(input)?,
+            (None, Some(file_id)) => {
+                let (_, data) = crate::proxy::files::FileStore::global().content(owner, file_id)?;
+                String::from_utf8(data).map_err(|_| format!("File '{}' is not valid UTF-8 JSONL", file_id))?
+            }
+            (None, None) => return Err("Either 'input' or 'input_file_id' is required".to_string()),
+        };
+
+        let created_at = now();
+        let mut batch = Batch {
+            id: format!("batch_{}", uuid::Uuid::new_v4().simple()),
+            object: "batch".to_string(),
+            endpoint: request.endpoint.clone(),
+            errors: None,
+            input_file_id: request.input_file_id.clone(),
+            completion_window: request.completion_window.clone(),
+            status: BatchStatus::Validating,
+            output_file_id: None,
+            error_file_id: None,
+            created_at,
+            in_progress_at: None,
+            expires_at: Some(created_at + COMPLETION_WINDOW_SECS),
+            finalizing_at: None,
+            completed_at: None,
+            failed_at: None,
+            expired_at: None,
+            cancelling_at: None,
+            cancelled_at: None,
+            request_counts: RequestCounts::default(),
+            metadata: request.metadata.clone(),
+        };
+        match parse_input(&text, &request.endpoint, self.config().max_requests) {
+            Ok(lines) => {
+                batch.request_counts.total = lines.len();
+                let jsonl = lines.iter().filter_map(|l| serde_json::to_string(l).ok()).collect::<Vec<_>>().join("\n");
+                std::fs::create_dir_all(&self.dir)
+                    .and_then(|_| std::fs::write(self.path(&batch.id, "input.jsonl"), jsonl))
+                    .map_err(|e| format!("Failed to store batch input: {}", e))?;
+            }
+            Err(errors) => {
+                batch.status = BatchStatus::Failed;
+                batch.failed_at = Some(created_at);
+                batch.errors = Some(BatchErrors { object: "list".to_string(), data: errors });
+            }
+        }
+
+        let stored = StoredBatch { owner: owner.to_string(), batch: batch.clone() };
+        self.save(&stored);
+        self.batches.lock().unwrap_or_else(|e| e.into_inner()).push(stored);
+        tracing::info!("[Batch] Created {} ({} requests, {:?})", batch.id, batch.request_counts.total, batch.status);
+        self.wake.notify_one();
+        Ok(batch)
+    }
+
+    pub fn get(&self, owner: &str, id: &str) -> Option<Batch> {
+        let batches = self.batches.lock().unwrap_or_else(|e| e.into_inner());
+        batches.iter().find(|b| b.batch.id == id && b.owner == owner).map(|b| b.batch.clone())
+    }
+
+    /// 最新的批次在前; `after` 为上一页最后一个批次的 id, 返回 (批次, 是否还有更多)
+    pub fn list(&self, owner: &str, limit: usize, after: Option<&str>) -> (Vec<Batch>, bool) {
+        let batches = self.batches.lock().unwrap_or_else(|e| e.into_inner());
+        let mut owned = batches.iter().rev().filter(|b| b.owner == owner).map(|b| &b.batch);
+        if let Some(after) = after {
+            owned.by_ref().find(|b| b.id == after);
+        }
+        let mut page: Vec<Batch> = owned.take(limit.clamp(1, 100) + 1).cloned().collect();
+        let has_more = page.len() > limit.clamp(1, 100);
+        page.truncate(limit.clamp(1, 100));
+        (page, has_more)
+    }
+
+    pub fn cancel(&self, owner: &str, id: &str) -> Result<Batch, String> {
+        let batch = self.get(owner, id).ok_or_else(|| format!("No batch found with id '{}'", id))?;
+        if !batch.status.is_pending() {
+            return Err(format!("Cannot cancel a batch with status '{:?}'", batch.status).to_lowercase());
+        }
+        let running = self.running.lock().unwrap_or_else(|e| e.into_inner()).as_deref() == Some(id);
+        let cancelled = self.update(id, |b| {
+            b.cancelling_at = Some(now());
+            // 尚未开始执行的批次直接取消, 正在执行的由执行任务结束
+            if running {
+                b.status = BatchStatus::Cancelling;
+            } else {
+                b.status = BatchStatus::Cancelled;
+                b.cancelled_at = b.cancelling_at;
+            }
+        });
+        tracing::info!("[Batch] Cancel requested for {}", id);
+        cancelled.ok_or_else(|| format!("No batch found with id '{}'", id))
+    }
+
+    /// 结果文件内容 (JSONL), 执行中也可以读取已完成的部分
+    pub fn file(&self, owner: &str, id: &str, kind: BatchFile) -> Result<String, String> {
+        self.get(owner, id).ok_or_else(|| format!("No batch found with id '{}'", id))?;
+        Ok(std::fs::read_to_string(self.path(id, &format!("{}.jsonl", kind.suffix()))).unwrap_or_default())
+    }
+
+    fn read_lines(&self, id: &str, kind: &str) -> Vec<Value> {
+        std::fs::read_to_string(self.path(id, kind))
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|l| serde_json::from_str(l).ok())
+            .collect()
+    }
+
+    /// 尚未执行的请求; 同时按结果文件校正计数 (停止前最后的计数可能未保存)
+    fn pending_lines(&self, id: &str) -> Vec<BatchRequestLine> {
+        let output = self.read_lines(id, "output.jsonl");
+        let errors = self.read_lines(id, "errors.jsonl");
+        let done: HashSet<&str> = output.iter().chain(&errors).filter_map(|l| l["custom_id"].as_str()).collect();
+        let pending: Vec<BatchRequestLine> = self
+            .read_lines(id, "input.jsonl")
+            .into_iter()
+            .filter_map(|l| serde_json::from_value::<BatchRequestLine>(l).ok())
+            .filter(|l| !done.contains(l.custom_id.as_str()))
+            .collect();
+        self.update(id, |b| {
+            b.request_counts.completed = output.len();
+            b.request_counts.failed = errors.len();
+        });
+        pending
+    }
+
+    fn append(&self, id: &str, kind: BatchFile, line: &Value) {
+        let path = self.path(id, &format!("{}.jsonl", kind.suffix()));
+        let result = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .and_then(|mut f| writeln!(f, "{}", line));
+        if let Err(e) = result {
+            tracing::warn!("[Batch] Failed to write {}: {}", path.display(), e);
+        }
+    }
+
+    fn record(&self, id: &str, result: &LineResult) {
+        let kind = if result.ok { BatchFile::Output } else { BatchFile::Errors };
+        self.append(id, kind, &result.line);
+        self.update(id, |b| {
+            if result.ok {
+                b.request_counts.completed += 1;
+            } else {
+                b.request_counts.failed += 1;
+            }
+        });
+    }
+
+    /// 按创建顺序取下一个可执行的批次及其 Key; 顺带把等待中已过期的批次标记为过期
+    fn next_runnable(&self) -> Option<(String, Option<String>)> {
+        let keys = self.keys.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        let pending: Vec<(String, String, Option<i64>)> = {
+            let batches = self.batches.lock().unwrap_or_else(|e| e.into_inner());
+            batches
+                .iter()
+                .filter(|b| b.batch.status.is_pending())
+                .map(|b| (b.batch.id.clone(), b.owner.clone(), b.batch.expires_at))
+                .collect()
+        };
+        for (id, owner, expires_at) in pending {
+            if expires_at.is_some_and(|t| t <= now()) {
+                self.expire(&id);
+                continue;
+            }
+            match keys.get(&owner) {
+                Some(key) => return Some((id, Some(key.clone()))),
+                None if owner == crate::proxy::middleware::auth::ANONYMOUS_OWNER => return Some((id, None)),
+                None => continue,
+            }
+        }
+        None
+    }
+
+    /// 过期: 未执行的请求写入错误文件
+    fn expire(&self, id: &str) {
+        for line in self.pending_lines(id) {
+            let error = json!({ "code": "batch_expired", "message": "This request could not be executed before the completion window expired." });
+            self.record(id, &LineResult { ok: false, line: result_line(&line.custom_id, None, Some(error)) });
+        }
+        self.finish(id, BatchStatus::Expired);
+        tracing::info!("[Batch] {} expired", id);
+    }
+
+    /// 结束批次, 设置结果文件 id
+    fn finish(&self, id: &str, status: BatchStatus) {
+        let owner = {
+            let batches = self.batches.lock().unwrap_or_else(|e| e.into_inner());
+            batches.iter().find(|b| b.batch.id == id).map(|b| b.owner.clone()).unwrap_or_default()
+        };
+        let output_file_id = self.publish(id, &owner, BatchFile::Output);
+        let error_file_id = self.publish(id, &owner, BatchFile::Errors);
+        self.update(id, |b| {
+            let t = Some(now());
+            b.status = status;
+            match status {
+                BatchStatus::Completed => b.completed_at = t,
+                BatchStatus::Expired => b.expired_at = t,
+                BatchStatus::Cancelled => b.cancelled_at = t,
+                _ => {}
+            }
+            b.output_file_id = output_file_id;
+            b.error_file_id = error_file_id;
+        });
+    }
+
+    /// 结果文件写入 Files API 存储, 返回文件 id
+    fn publish(&self, id: &str, owner: &str, kind: BatchFile) -> Option<String> {
+        let data = std::fs::read(self.path(id, &format!("{}.jsonl", kind.suffix()))).ok()?;
+        let filename = format!("{}_{}.jsonl", id, kind.suffix());
+        match crate::proxy::files::FileStore::global().put(owner, &filename, crate::proxy::files::BATCH_OUTPUT_PURPOSE, &data) {
+            Ok(file) => Some(file.id),
+            Err(e) => {
+                tracing::warn!("[Batch] Failed to store {} for {}: {}", kind.suffix(), id, e);
+                None
+            }
+        }
+    }
+
+    /// 每分钟请求数限制: 请求按固定间隔发出
+    async fn pace(&self, requests_per_minute: u32) {
+        if requests_per_minute == 0 {
+            return;
+        }
+        let interval = Duration::from_secs(60) / requests_per_minute;
+        let slot = {
+            let mut next = self.next_slot.lock().await;
+            let slot = (*next).max(tokio::time::Instant::now());
+            *next = slot + interval;
+            slot
+        };
+        tokio::time::sleep_until(slot).await;
+    }
+
+    async fn run(&self, id: &str, api_key: Option<String>, base_url: &str, timeout_secs: u64, epoch: u64) {
+        let pending = self.pending_lines(id);
+        self.update(id, |b| {
+            if b.status == BatchStatus::Validating {
+                b.status = BatchStatus::InProgress;
+                b.in_progress_at = Some(now());
+            }
+        });
+        let client = match crate::proxy::handlers::common::build_upstream_client(Default::default(), timeout_secs) {
+            Ok(client) => client,
+            Err(e) => {
+                tracing::warn!("[Batch] Failed to build client for {}: {}", id, e);
+                return;
+            }
+        };
+        let config = self.config();
+        tracing::info!("[Batch] Running {} ({} pending requests)", id, pending.len());
+        let mut results = futures::stream::iter(pending)
+            .map(|line| {
+                let (client, api_key) = (&client, api_key.as_deref());
+                async move {
+                    self.pace(config.requests_per_minute).await;
+                    execute(client, base_url, api_key, line).await
+                }
+            })
+            .buffer_unordered(config.concurrency.max(1));
+        while let Some(result) = results.next().await {
+            self.record(id, &result);
+            if self.epoch.load(Ordering::SeqCst) != epoch {
+                // 服务停止: 进行中的请求丢弃, 重启后重新执行
+                return;
+            }
+            let Some(batch) = self.batches.lock().unwrap_or_else(|e| e.into_inner()).iter().find(|b| b.batch.id == id).map(|b| b.batch.clone()) else {
+                return;
+            };
+            if batch.status == BatchStatus::Cancelling {
+                drop(results);
+                self.finish(id, BatchStatus::Cancelled);
+                tracing::info!("[Batch] {} cancelled", id);
+                return;
+            }
+            if batch.expires_at.is_some_and(|t| t <= now()) {
+                drop(results);
+                self.expire(id);
+                return;
+            }
+        }
+        drop(results);
+        if self.get_status(id) == Some(BatchStatus::Cancelling) {
+            self.finish(id, BatchStatus::Cancelled);
+            return;
+        }
+        self.update(id, |b| {
+            b.status = BatchStatus::Finalizing;
+            b.finalizing_at = Some(now());
+        });
+        self.finish(id, BatchStatus::Completed);
+        tracing::info!("[Batch] {} completed", id);
+    }
+
+    fn get_status(&self, id: &str) -> Option<BatchStatus> {
+        let batches = self.batches.lock().unwrap_or_else(|e| e.into_inner());
+        batches.iter().find(|b| b.batch.id == id).map(|b| b.batch.status)
+    }
+
+    async fn worker(&'static self, port: u16, timeout_secs: u64, epoch: u64) {
+        let base_url = format!("http://127.0.0.1:{}", port);
+        while self.epoch.load(Ordering::SeqCst) == epoch {
+            match self.next_runnable() {
+                Some((id, api_key)) => {
+                    *self.running.lock().unwrap_or_else(|e| e.into_inner()) = Some(id.clone());
+                    self.run(&id, api_key, &base_url, timeout_secs, epoch).await;
+                    *self.running.lock().unwrap_or_else(|e| e.into_inner()) = None;
+                }
+                None => {
+                    let _ = tokio::time::timeout(IDLE_INTERVAL, self.wake.notified()).await;
+                }
+            }
+        }
+    }
+}
+
+/// 应用 Batch API 配置 (服务启动与配置热更新时调用)
+pub fn configure(config: &BatchConfig) {
+    *BatchStore::global().config.write().unwrap_or_else(|e| e.into_inner()) = config.clone();
+    BatchStore::global().wake.notify_one();
+}
+
+/// 服务启动时开始执行 (及恢复) 批次, 子请求发往本地端口
+pub fn start(port: u16, timeout_secs: u64) {
+    let store = BatchStore::global();
+    let epoch = store.epoch.fetch_add(1, Ordering::SeqCst) + 1;
+    tokio::spawn(store.worker(port, timeout_secs, epoch));
+}
+
+/// 服务停止时暂停执行, 进行中的请求在下次启动后重新执行
+pub fn pause() {
+    let store = BatchStore::global();
+    store.epoch.fetch_add(1, Ordering::SeqCst);
+    store.wake.notify_waiters();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(input: &str) -> CreateBatchRequest {
+        CreateBatchRequest {
+            input_file_id: None,
+            input: Some(Value::String(input.to_string())),
+            endpoint: "/v1/chat/completions".to_string(),
+            completion_window: COMPLETION_WINDOW.to_string(),
+            metadata: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_input() {
+        let ok = "{\"custom_id\":\"a\",\"method\":\"POST\",\"url\":\"/v1/chat/completions\",\"body\":{\"model\":\"m\"}}\n\n\
+{\"custom_id\":\"b\",\"method\":\"POST\",\"url\":\"/v1/chat/completions\",\"body\":{\"model\":\"m\"}}";
+        assert_eq!(parse_input(ok, "/v1/chat/completions", 10).unwrap().len(), 2);
+
+        let bad = "not json\n\
+{\"custom_id\":\"a\",\"method\":\"POST\",\"url\":\"/v1/embeddings\",\"body\":{}}\n\
+{\"custom_id\":\"b\",\"method\":\"POST\",\"url\":\"/v1/chat/completions\",\"body\":{}}\n\
+{\"custom_id\":\"b\",\"method\":\"POST\",\"url\":\"/v1/chat/completions\",\"body\":{}}";
+        let errors = parse_input(bad, "/v1/chat/completions", 10).unwrap_err();
+        let codes: Vec<(&str, Option<usize>)> = errors.iter().map(|e| (e.code.as_str(), e.line)).collect();
+        assert_eq!(codes, vec![("invalid_json_line", Some(1)), ("mismatched_endpoint", Some(2)), ("duplicate_custom_id", Some(4))]);
+        assert_eq!(parse_input(ok, "/v1/chat/completions", 1).unwrap_err()[0].code, "too_many_requests");
+        assert_eq!(parse_input("", "/v1/chat/completions", 1).unwrap_err()[0].code, "empty_file");
+    }
+
+    #[test]
+    fn test_store_persists_and_resumes() {
+        let dir = std::env::temp_dir().join(format!("batch_store_test_{}", uuid::Uuid::new_v4().simple()));
+        let store = BatchStore::new(dir.clone());
+        let input = "{\"custom_id\":\"a\",\"method\":\"POST\",\"url\":\"/v1/chat/completions\",\"body\":{}}\n\
+{\"custom_id\":\"b\",\"method\":\"POST\",\"url\":\"/v1/chat/completions\",\"body\":{}}";
+        let batch = store.create("owner", request(input)).unwrap();
+        assert_eq!((batch.status, batch.request_counts.total), (BatchStatus::Validating, 2));
+        assert_eq!(store.create("owner", request("oops")).unwrap().status, BatchStatus::Failed);
+        // 其他 Key 看不到该批次
+        assert!(store.get("other", &batch.id).is_none());
+        assert_eq!(store.list("owner", 1, None).1, true);
+
+        // 模拟执行了一个请求后重启
+        store.record(&batch.id, &LineResult { ok: true, line: result_line("a", Some(json!({"status_code": 200})), None) });
+        let reloaded = BatchStore::new(dir.clone());
+        let pending = reloaded.pending_lines(&batch.id);
+        assert_eq!(pending.iter().map(|l| l.custom_id.as_str()).collect::<Vec<_>>(), vec!["b"]);
+        assert_eq!(reloaded.get("owner", &batch.id).unwrap().request_counts.completed, 1);
+        assert!(reloaded.file("owner", &batch.id, BatchFile::Output).unwrap().contains("\"custom_id\":\"a\""));
+
+        let cancelled = reloaded.cancel("owner", &batch.id).unwrap();
+        assert_eq!(cancelled.status, BatchStatus::Cancelled);
+        assert!(reloaded.cancel("owner", &batch.id).is_err());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,336 @@
+// 请求/响应 Body 日志
+// 以 JSON Lines 写入滚动文件 (bodies.jsonl -> bodies.1.jsonl -> ...), 写入前按配置脱敏
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Serialize;
+use std::path::PathBuf;
+use tokio::io::AsyncWriteExt;
+
+use crate::proxy::config::BodyLoggingConfig;
+
+const LOG_FILE_STEM: &str = "bodies";
+const REDACTED: &str = "[REDACTED]";
+
+/// 常见凭证格式
+static SECRET_PATTERNS: Lazy<Vec<Regex>> = Lazy::new(|| {
+    [
+        // JSON 中的敏感字段值
+        r#"(?i)("(?:api_?key|access_?token|refresh_?token|id_?token|client_secret|password|secret)"\s*:\s*")[^"]*(")"#,
+        r"(?i)(bearer\s+)[A-Za-z0-9._~+/=-]{8,}()",
+        r"()\bsk-[A-Za-z0-9_-]{16,}()",
+        r"()\bAIza[0-9A-Za-z_-]{35}()",
+        r"()\bya29\.[0-9A-Za-z_-]{20,}()",
+        r"()\b1//[0-9A-Za-z_-]{20,}()",
+    ]
+    .iter()
+    .map(|p| Regex::new(p).expect("invalid secret pattern"))
+    .collect()
+});
+
+static EMAIL_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}").unwrap());
+static PHONE_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\+\d{1,3}[\s.-]?\(?\d{1,4}\)?(?:[\s.-]?\d{2,4}){2,4}").unwrap());
+static IPV4_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\b(?:(?:25[0-5]|2[0-4]\d|1?\d?\d)\.){3}(?:25[0-5]|2[0-4]\d|1?\d?\d)\b").unwrap());
+
+/// 按配置编译好的脱敏规则
+pub struct Redactor {
+    redact_secrets: bool,
+    redact_pii: bool,
+    headers: Vec<String>,
+    custom: Vec<Regex>,
+}
+
+impl Redactor {
+    pub fn from_config(cfg: &BodyLoggingConfig) -> Self {
+        let custom = cfg
+            .redact_patterns
+            .iter()
+            .filter_map(|p| match Regex::new(p) {
+                Ok(re) => Some(re),
+                Err(e) => {
+                    tracing::warn!("[Body-Log] Ignoring invalid redact pattern {:?}: {}", p, e);
+                    None
+                }
+            })
+            .collect();
+        Self {
+            redact_secrets: cfg.redact_secrets,
+            redact_pii: cfg.redact_pii,
+            headers: cfg.redact_headers.iter().map(|h| h.to_ascii_lowercase()).collect(),
+            custom,
+        }
+    }
+
+    pub fn redact_headers(&self, headers: &axum::http::HeaderMap) -> serde_json::Map<String, serde_json::Value> {
+        headers
+            .iter()
+            .map(|(name, value)| {
+                let name = name.as_str().to_ascii_lowercase();
+                let value = if self.headers.contains(&name) {
+                    REDACTED.to_string()
+                } else {
+                    self.redact_text(&String::from_utf8_lossy(value.as_bytes()))
+                };
+                (name, serde_json::Value::String(value))
+            })
+            .collect()
+    }
+
+    pub fn redact_text(&self, text: &str) -> String {
+        let mut out = text.to_string();
+        if self.redact_secrets {
+            for re in SECRET_PATTERNS.iter() {
+                out = re.replace_all(&out, format!("${{1}}{}${{2}}", REDACTED)).into_owned();
+            }
+        }
+        if self.redact_pii {
+            out = EMAIL_RE.replace_all(&out, "[EMAIL]").into_owned();
+            out = PHONE_RE.replace_all(&out, "[PHONE]").into_owned();
+            out = IPV4_RE.replace_all(&out, "[IP]").into_owned();
+        }
+        for re in &self.custom {
+            out = re.replace_all(&out, REDACTED).into_owned();
+        }
+        out
+    }
+}
+
+/// 一条 Body 日志
+#[derive(Debug, Clone, Serialize)]
+pub struct BodyLogEntry {
+    pub id: String,
+    pub timestamp: i64,
+    pub method: String,
+    pub url: String,
+    pub status: u16,
+    pub duration_ms: u64,
+    pub request_headers: serde_json::Map<String, serde_json::Value>,
+    pub request_body: Option<String>,
+    pub response_headers: serde_json::Map<String, serde_json::Value>,
+    pub response_body: Option<String>,
+    pub stream: bool,
+    pub truncated: bool,
+}
+
+/// 将 Body 截断到上限后解码为文本
+pub fn body_to_text(bytes: &[u8], limit: usize) -> (Option<String>, bool) {
+    if bytes.is_empty() {
+        return (None, false);
+    }
+    let truncated = bytes.len() > limit;
+    let slice = &bytes[..bytes.len().min(limit)];
+    match std::str::from_utf8(slice) {
+        Ok(s) => (Some(s.to_string()), truncated),
+        // 截断点可能落在多字节字符中间
+        Err(e) if truncated && e.error_len().is_none() => {
+            (Some(String::from_utf8_lossy(&slice[..e.valid_up_to()]).into_owned()), truncated)
+        }
+        Err(_) => (Some(format!("[Binary Data: {} bytes]", bytes.len())), truncated),
+    }
+}
+
+/// 滚动文件存储
+pub struct BodyLogStore {
+    config: std::sync::RwLock<(BodyLoggingConfig, std::sync::Arc<Redactor>)>,
+    write_lock: tokio::sync::Mutex<()>,
+}
+
+impl BodyLogStore {
+    pub fn new(config: BodyLoggingConfig) -> Self {
+        let redactor = std::sync::Arc::new(Redactor::from_config(&config));
+        Self {
+            config: std::sync::RwLock::new((config, redactor)),
+            write_lock: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    pub fn update_config(&self, config: BodyLoggingConfig) {
+        let redactor = std::sync::Arc::new(Redactor::from_config(&config));
+        *self.config.write().unwrap_or_else(|e| e.into_inner()) = (config, redactor);
+    }
+
+    pub fn config(&self) -> BodyLoggingConfig {
+        self.config.read().unwrap_or_else(|e| e.into_inner()).0.clone()
+    }
+
+    pub fn redactor(&self) -> std::sync::Arc<Redactor> {
+        self.config.read().unwrap_or_else(|e| e.into_inner()).1.clone()
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.read().unwrap_or_else(|e| e.into_inner()).0.enabled
+    }
+
+    fn output_dir(cfg: &BodyLoggingConfig) -> Option<PathBuf> {
+        if let Some(dir) = cfg.output_dir.as_ref().filter(|d| !d.is_empty()) {
+            return Some(PathBuf::from(dir));
+        }
+        crate::modules::account::get_data_dir()
+            .ok()
+            .map(|d| d.join("body_logs"))
+    }
+
+    fn file_path(dir: &std::path::Path, index: u32) -> PathBuf {
+        if index == 0 {
+            dir.join(format!("{}.jsonl", LOG_FILE_STEM))
+        } else {
+            dir.join(format!("{}.{}.jsonl", LOG_FILE_STEM, index))
+        }
+    }
+
+    /// bodies.jsonl -> bodies.1.jsonl, 超出 max_files 的最旧文件被删除
+    async fn rotate(dir: &std::path::Path, max_files: u32) {
+        let _ = tokio::fs::remove_file(Self::file_path(dir, max_files)).await;
+        for index in (0..max_files).rev() {
+            let from = Self::file_path(dir, index);
+            if tokio::fs::try_exists(&from).await.unwrap_or(false) {
+                let _ = tokio::fs::rename(&from, Self::file_path(dir, index + 1)).await;
+            }
+        }
+        if max_files == 0 {
+            let _ = tokio::fs::remove_file(Self::file_path(dir, 0)).await;
+        }
+    }
+
+    /// 追加一条日志 (调用方负责脱敏)
+    pub async fn append(&self, entry: &BodyLogEntry) {
+        let cfg = self.config();
+        if !cfg.enabled {
+            return;
+        }
+        let Some(dir) = Self::output_dir(&cfg) else {
+            tracing::warn!("[Body-Log] Enabled but output_dir is not available.");
+            return;
+        };
+
+        let mut line = match serde_json::to_vec(entry) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                tracing::warn!("[Body-Log] Failed to serialize entry: {}", e);
+                return;
+            }
+        };
+        line.push(b'\n');
+
+        let _guard = self.write_lock.lock().await;
+        if let Err(e) = tokio::fs::create_dir_all(&dir).await {
+            tracing::warn!("[Body-Log] Failed to create output dir: {}", e);
+            return;
+        }
+
+        let current = Self::file_path(&dir, 0);
+        let max_bytes = cfg.max_file_size_mb.max(1) * 1024 * 1024;
+        let size = tokio::fs::metadata(&current).await.map(|m| m.len()).unwrap_or(0);
+        if size > 0 && size + line.len() as u64 > max_bytes {
+            Self::rotate(&dir, cfg.max_files).await;
+        }
+
+        let result = async {
+            let mut file = tokio::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&current)
+                .await?;
+            file.write_all(&line).await
+        }
+        .await;
+        if let Err(e) = result {
+            tracing::warn!("[Body-Log] Failed to write file: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn redactor() -> Redactor {
+        Redactor::from_config(&BodyLoggingConfig {
+            redact_patterns: vec![r"order-\d+".to_string()],
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_redact_secrets_and_pii() {
+        let r = redactor();
+        let text = r#"{"api_key":"abc123","messages":[{"content":"mail me at john.doe@example.com or +1 415-555-0100, key sk-abcdefghijklmnopqrstuv, order-42"}]}"#;
+        let out = r.redact_text(text);
+
+        assert!(out.contains(r#""api_key":"[REDACTED]""#));
+        assert!(!out.contains("john.doe@example.com") && out.contains("[EMAIL]"));
+        assert!(out.contains("[PHONE]"));
+        assert!(!out.contains("sk-abcdefghijklmnopqrstuv"));
+        assert!(!out.contains("order-42"));
+    }
+
+    #[test]
+    fn test_redact_headers() {
+        let r = redactor();
+        let mut headers = axum::http::HeaderMap::new();
+        headers.insert("Authorization", "Bearer sk-secret".parse().unwrap());
+        headers.insert("content-type", "application/json".parse().unwrap());
+
+        let out = r.redact_headers(&headers);
+        assert_eq!(out["authorization"], "[REDACTED]");
+        assert_eq!(out["content-type"], "application/json");
+    }
+
+    #[test]
+    fn test_redaction_disabled() {
+        let r = Redactor::from_config(&BodyLoggingConfig {
+            redact_secrets: false,
+            redact_pii: false,
+            ..Default::default()
+        });
+        let text = "user@example.com sk-abcdefghijklmnopqrstuv";
+        assert_eq!(r.redact_text(text), text);
+    }
+
+    #[test]
+    fn test_body_to_text_truncates_on_char_boundary() {
+        let (text, truncated) = body_to_text("你好".as_bytes(), 4);
+        assert_eq!(text.as_deref(), Some("你"));
+        assert!(truncated);
+        assert_eq!(body_to_text(b"", 4), (None, false));
+    }
+
+    #[tokio::test]
+    async fn test_append_rotates_files() {
+        let dir = std::env::temp_dir().join(format!("body_logs_test_{}", uuid::Uuid::new_v4()));
+        let store = BodyLogStore::new(BodyLoggingConfig {
+            enabled: true,
+            output_dir: Some(dir.to_string_lossy().to_string()),
+            max_file_size_mb: 1,
+            max_files: 2,
+            ..Default::default()
+        });
+        let entry = BodyLogEntry {
+            id: "1".to_string(),
+            timestamp: 0,
+            method: "POST".to_string(),
+            url: "/v1/chat/completions".to_string(),
+            status: 200,
+            duration_ms: 1,
+            request_headers: Default::default(),
+            request_body: Some("x".repeat(600 * 1024)),
+            response_headers: Default::default(),
+            response_body: None,
+            stream: false,
+            truncated: false,
+        };
+
+        for _ in 0..4 {
+            store.append(&entry).await;
+        }
+        assert!(BodyLogStore::file_path(&dir, 0).exists());
+        assert!(BodyLogStore::file_path(&dir, 1).exists());
+        assert!(BodyLogStore::file_path(&dir, 2).exists());
+        assert!(!BodyLogStore::file_path(&dir, 3).exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,126 @@
+// 客户端识别
+// 从 X-Client-Name / User-Agent 推断发起请求的工具 (Cursor、Continue、脚本等), 用于日志与用量按工具归类。
+
+use std::net::SocketAddr;
+
+use axum::http::{header, HeaderMap};
+
+/// 客户端自报名称的请求头
+pub const CLIENT_NAME_HEADER: &str = "x-client-name";
+
+const MAX_NAME_LEN: usize = 64;
+const MAX_USER_AGENT_LEN: usize = 256;
+
+/// User-Agent 中的特征片段 (小写) 与显示名称, 按顺序匹配 (工具优先于其使用的 SDK)
+const KNOWN_CLIENTS: &[(&str, &str)] = &[
+    ("cursor", "Cursor"),
+    ("continue", "Continue"),
+    ("cline", "Cline"),
+    ("roo-code", "Roo Code"),
+    ("roocode", "Roo Code"),
+    ("kilo-code", "Kilo Code"),
+    ("aider", "Aider"),
+    ("claude-cli", "Claude CLI"),
+    ("codex", "Codex CLI"),
+    ("geminicli", "Gemini CLI"),
+    ("gemini-cli", "Gemini CLI"),
+    ("opencode", "OpenCode"),
+    ("windsurf", "Windsurf"),
+    ("zed/", "Zed"),
+    ("cherrystudio", "Cherry Studio"),
+    ("cherry-studio", "Cherry Studio"),
+    ("lobehub", "LobeChat"),
+    ("open-webui", "Open WebUI"),
+    ("chatbox", "Chatbox"),
+    ("openai/python", "OpenAI Python SDK"),
+    ("openai/js", "OpenAI Node SDK"),
+    ("anthropic/python", "Anthropic Python SDK"),
+    ("anthropic/js", "Anthropic Node SDK"),
+    ("google-genai-sdk", "Google GenAI SDK"),
+    ("python-requests", "python-requests"),
+    ("python-httpx", "httpx"),
+    ("aiohttp", "aiohttp"),
+    ("curl/", "curl"),
+    ("wget/", "wget"),
+    ("postmanruntime", "Postman"),
+    ("node-fetch", "node-fetch"),
+    ("axios", "axios"),
+    ("go-http-client", "Go"),
+    ("okhttp", "OkHttp"),
+];
+
+/// 请求来源信息
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ClientInfo {
+    /// X-Client-Name, 或从 User-Agent 推断的工具名
+    pub name: Option<String>,
+    pub user_agent: Option<String>,
+    pub source_port: Option<u16>,
+}
+
+fn truncate(value: &str, max: usize) -> String {
+    value.chars().take(max).collect()
+}
+
+/// 从 User-Agent 推断工具名; 未知时取第一个产品标识 (例如 `MyScript/1.0` -> `MyScript`)
+fn client_from_user_agent(user_agent: &str) -> Option<String> {
+    let lower = user_agent.to_ascii_lowercase();
+    if let Some((_, name)) = KNOWN_CLIENTS.iter().find(|(pattern, _)| lower.contains(pattern)) {
+        return Some(name.to_string());
+    }
+    let product = user_agent.split_whitespace().next()?.split('/').next()?.trim();
+    // 浏览器的 User-Agent 都以 Mozilla 开头, 没有区分意义
+    (!product.is_empty() && !product.eq_ignore_ascii_case("mozilla")).then(|| truncate(product, MAX_NAME_LEN))
+}
+
+pub fn identify(headers: &HeaderMap, peer: Option<SocketAddr>) -> ClientInfo {
+    let header_value = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+    };
+    let user_agent = header_value(header::USER_AGENT.as_str());
+    let name = header_value(CLIENT_NAME_HEADER)
+        .map(|n| truncate(n, MAX_NAME_LEN))
+        .or_else(|| user_agent.and_then(client_from_user_agent));
+    ClientInfo {
+        name,
+        user_agent: user_agent.map(|ua| truncate(ua, MAX_USER_AGENT_LEN)),
+        source_port: peer.map(|addr| addr.port()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(&str, &str)]) -> HeaderMap {
+        let mut map = HeaderMap::new();
+        for (name, value) in pairs {
+            map.insert(header::HeaderName::from_bytes(name.as_bytes()).unwrap(), value.parse().unwrap());
+        }
+        map
+    }
+
+    #[test]
+    fn test_identify_client() {
+        let peer: SocketAddr = "192.168.1.5:51234".parse().unwrap();
+        let info = identify(&headers(&[("user-agent", "Cursor/0.45.11 (darwin arm64)")]), Some(peer));
+        assert_eq!(info.name.as_deref(), Some("Cursor"));
+        assert_eq!(info.source_port, Some(51234));
+
+        // 自报名称优先于 User-Agent
+        let info = identify(&headers(&[("user-agent", "OpenAI/Python 1.54.0"), ("x-client-name", " nightly-eval ")]), None);
+        assert_eq!(info.name.as_deref(), Some("nightly-eval"));
+        assert_eq!(info.user_agent.as_deref(), Some("OpenAI/Python 1.54.0"));
+
+        let name = |ua: &str| identify(&headers(&[("user-agent", ua)]), None).name;
+        assert_eq!(name("OpenAI/Python 1.54.0").as_deref(), Some("OpenAI Python SDK"));
+        assert_eq!(name("curl/8.5.0").as_deref(), Some("curl"));
+        assert_eq!(name("MyBatchJob/2.1 (+ops)").as_deref(), Some("MyBatchJob"));
+        assert_eq!(name("Mozilla/5.0 (Windows NT 10.0)"), None);
+        assert_eq!(identify(&HeaderMap::new(), None), ClientInfo::default());
+    }
+}
@@ -0,0 +1,211 @@
+// 响应压缩与解压
+// 客户端方向: 按 Accept-Encoding 协商 gzip / br, 只用于完整的非流式响应。
+// 上游方向: 带 Content-Encoding 的上游响应边读边解压, SSE 事件逐块还原, 无需等待整个响应。
+
+use std::io::{self, Write};
+
+use axum::body::Body;
+use axum::http::{header, HeaderMap};
+use bytes::Bytes;
+use futures::StreamExt;
+
+use crate::proxy::config::CompressionConfig;
+
+/// 转发给上游的 Accept-Encoding, 只包含能够解压的编码
+pub const SUPPORTED_ENCODINGS: &str = "gzip, deflate, br";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Gzip,
+    Deflate,
+    Brotli,
+}
+
+impl Encoding {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Encoding::Gzip => "gzip",
+            Encoding::Deflate => "deflate",
+            Encoding::Brotli => "br",
+        }
+    }
+
+    /// 解析 Content-Encoding; 缺失或 identity 时返回 None, 无法解压的编码返回 Err
+    pub fn from_headers(headers: &HeaderMap) -> Result<Option<Self>, String> {
+        let Some(value) = headers.get(header::CONTENT_ENCODING) else {
+            return Ok(None);
+        };
+        let value = value.to_str().map_err(|e| e.to_string())?.trim().to_ascii_lowercase();
+        match value.as_str() {
+            "" | "identity" => Ok(None),
+            "gzip" | "x-gzip" => Ok(Some(Encoding::Gzip)),
+            "deflate" => Ok(Some(Encoding::Deflate)),
+            "br" => Ok(Some(Encoding::Brotli)),
+            other => Err(format!("unsupported content-encoding: {}", other)),
+        }
+    }
+}
+
+/// 按 Accept-Encoding 的 q 值选择压缩编码; 权重相同时优先 br
+pub fn negotiate(accept: &str, config: &CompressionConfig) -> Option<Encoding> {
+    let entries: Vec<(String, f32)> = accept
+        .split(',')
+        .filter_map(|item| {
+            let mut parts = item.split(';');
+            let name = parts.next()?.trim().to_ascii_lowercase();
+            let q = parts
+                .find_map(|p| p.trim().strip_prefix("q=").and_then(|v| v.trim().parse::<f32>().ok()))
+                .unwrap_or(1.0);
+            (!name.is_empty()).then_some((name, q))
+        })
+        .collect();
+    let weight = |names: &[&str]| {
+        entries
+            .iter()
+            .find(|(n, _)| names.contains(&n.as_str()))
+            .or_else(|| entries.iter().find(|(n, _)| n == "*"))
+            .map_or(0.0, |(_, q)| *q)
+    };
+
+    let mut best: Option<(Encoding, f32)> = None;
+    for (encoding, enabled, names) in [
+        (Encoding::Brotli, config.brotli, &["br"][..]),
+        (Encoding::Gzip, config.gzip, &["gzip", "x-gzip"][..]),
+    ] {
+        let q = weight(names);
+        if enabled && q > 0.0 && best.map_or(true, |(_, b)| q > b) {
+            best = Some((encoding, q));
+        }
+    }
+    best.map(|(e, _)| e)
+}
+
+pub fn compress(data: &[u8], encoding: Encoding) -> io::Result<Vec<u8>> {
+    match encoding {
+        Encoding::Gzip => {
+            let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(data)?;
+            encoder.finish()
+        }
+        Encoding::Deflate => {
+            let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(data)?;
+            encoder.finish()
+        }
+        Encoding::Brotli => {
+            let mut encoder = brotli::CompressorWriter::new(Vec::new(), 4096, 5, 22);
+            encoder.write_all(data)?;
+            Ok(encoder.into_inner())
+        }
+    }
+}
+
+/// 增量解压器: 每写入一块压缩数据, 返回目前已经可以得到的明文
+enum Decoder {
+    Gzip(flate2::write::GzDecoder<Vec<u8>>),
+    Deflate(flate2::write::ZlibDecoder<Vec<u8>>),
+    Brotli(Box<brotli::DecompressorWriter<Vec<u8>>>),
+}
+
+impl Decoder {
+    fn new(encoding: Encoding) -> Self {
+        match encoding {
+            Encoding::Gzip => Decoder::Gzip(flate2::write::GzDecoder::new(Vec::new())),
+            Encoding::Deflate => Decoder::Deflate(flate2::write::ZlibDecoder::new(Vec::new())),
+            Encoding::Brotli => Decoder::Brotli(Box::new(brotli::DecompressorWriter::new(Vec::new(), 4096))),
+        }
+    }
+
+    fn push(&mut self, chunk: &[u8]) -> io::Result<Vec<u8>> {
+        match self {
+            Decoder::Gzip(d) => {
+                d.write_all(chunk)?;
+                d.flush()?;
+                Ok(std::mem::take(d.get_mut()))
+            }
+            Decoder::Deflate(d) => {
+                d.write_all(chunk)?;
+                d.flush()?;
+                Ok(std::mem::take(d.get_mut()))
+            }
+            Decoder::Brotli(d) => {
+                d.write_all(chunk)?;
+                d.flush()?;
+                Ok(std::mem::take(d.get_mut()))
+            }
+        }
+    }
+
+    fn finish(self) -> io::Result<Vec<u8>> {
+        match self {
+            Decoder::Gzip(d) => d.finish(),
+            Decoder::Deflate(d) => d.finish(),
+            Decoder::Brotli(d) => d
+                .into_inner()
+                .map_err(|_| io::Error::new(io::ErrorKind::UnexpectedEof, "truncated brotli stream")),
+        }
+    }
+}
+
+/// 以流的方式解压响应体
+pub fn decode_body(body: Body, encoding: Encoding) -> Body {
+    let mut stream = body.into_data_stream();
+    let decoded = async_stream::stream! {
+        let mut decoder = Decoder::new(encoding);
+        while let Some(chunk) = stream.next().await {
+            let chunk = match chunk {
+                Ok(chunk) => chunk,
+                Err(e) => {
+                    yield Err(io::Error::other(e.to_string()));
+                    return;
+                }
+            };
+            match decoder.push(&chunk) {
+                Ok(out) if !out.is_empty() => yield Ok(Bytes::from(out)),
+                Ok(_) => {}
+                Err(e) => {
+                    yield Err(e);
+                    return;
+                }
+            }
+        }
+        match decoder.finish() {
+            Ok(out) if !out.is_empty() => yield Ok(Bytes::from(out)),
+            Ok(_) => {}
+            Err(e) => yield Err(e),
+        }
+    };
+    Body::from_stream(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negotiate() {
+        let config = CompressionConfig { enabled: true, ..Default::default() };
+        assert_eq!(negotiate("gzip, deflate, br", &config), Some(Encoding::Brotli));
+        assert_eq!(negotiate("gzip;q=1.0, br;q=0.5", &config), Some(Encoding::Gzip));
+        assert_eq!(negotiate("*;q=0.8, br;q=0", &config), Some(Encoding::Gzip));
+        assert_eq!(negotiate("identity", &config), None);
+        assert_eq!(negotiate("", &config), None);
+        let gzip_only = CompressionConfig { brotli: false, ..config };
+        assert_eq!(negotiate("br, gzip", &gzip_only), Some(Encoding::Gzip));
+    }
+
+    #[tokio::test]
+    async fn test_round_trip_in_chunks() {
+        let plain = "data: {\"choices\":[{\"delta\":{\"content\":\"hi\"}}]}\n\n".repeat(200);
+        for encoding in [Encoding::Gzip, Encoding::Deflate, Encoding::Brotli] {
+            let compressed = compress(plain.as_bytes(), encoding).unwrap();
+            assert!(compressed.len() < plain.len());
+            // 模拟上游分块到达
+            let chunks: Vec<Result<Bytes, io::Error>> =
+                compressed.chunks(7).map(|c| Ok(Bytes::copy_from_slice(c))).collect();
+            let body = decode_body(Body::from_stream(futures::stream::iter(chunks)), encoding);
+            let decoded = axum::body::to_bytes(body, usize::MAX).await.unwrap();
+            assert_eq!(decoded, plain.as_bytes());
+        }
+    }
+}
@@ -0,0 +1,102 @@
+// 代理配置的局部更新
+// 管理 API 通过 JSON Merge Patch (RFC 7386) 或按字段整体替换来修改 ProxyConfig。
+
+use serde_json::Value;
+
+use crate::proxy::config::ProxyConfig;
+
+/// RFC 7386: 对象递归合并, `null` 表示删除字段, 其他值直接替换
+pub fn merge_patch(target: &mut Value, patch: &Value) {
+    let Value::Object(patch_map) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(Default::default());
+    }
+    if let Value::Object(target_map) = target {
+        for (key, value) in patch_map {
+            if value.is_null() {
+                target_map.remove(key);
+            } else {
+                merge_patch(target_map.entry(key.clone()).or_insert(Value::Null), value);
+            }
+        }
+    }
+}
+
+/// 保存前的校验 (规则、地址、端口冲突等), 见 config_validation
+pub fn validate(config: &ProxyConfig) -> Result<(), String> {
+    crate::proxy::common::config_validation::ensure_valid(config)
+}
+
+fn from_value(value: Value) -> Result<ProxyConfig, String> {
+    let config: ProxyConfig = serde_json::from_value(value).map_err(|e| format!("Invalid proxy config: {}", e))?;
+    validate(&config)?;
+    Ok(config)
+}
+
+/// 将 Merge Patch 应用到当前配置
+pub fn apply_patch(current: &ProxyConfig, patch: &Value) -> Result<ProxyConfig, String> {
+    if !patch.is_object() {
+        return Err("Patch must be a JSON object".to_string());
+    }
+    let mut value = serde_json::to_value(current).map_err(|e| e.to_string())?;
+    merge_patch(&mut value, patch);
+    from_value(value)
+}
+
+/// 读取单个配置字段 (例如 `zai`, `model_aliases`, `client_rate_limit`)
+pub fn get_section(config: &ProxyConfig, section: &str) -> Option<Value> {
+    serde_json::to_value(config).ok()?.get(section).cloned()
+}
+
+/// 整体替换单个配置字段
+pub fn replace_section(current: &ProxyConfig, section: &str, value: Value) -> Result<ProxyConfig, String> {
+    let mut config = serde_json::to_value(current).map_err(|e| e.to_string())?;
+    let Some(slot) = config.get_mut(section) else {
+        return Err(format!("Unknown config section: {}", section));
+    };
+    *slot = value;
+    from_value(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_merge_patch() {
+        let mut target = json!({"a": 1, "b": {"c": 2, "d": 3}, "e": [1, 2]});
+        merge_patch(&mut target, &json!({"b": {"c": null, "x": 9}, "e": [3], "f": "new"}));
+        assert_eq!(target, json!({"a": 1, "b": {"d": 3, "x": 9}, "e": [3], "f": "new"}));
+    }
+
+    #[test]
+    fn test_apply_patch_and_sections() {
+        let current = ProxyConfig::default();
+        let updated = apply_patch(
+            &current,
+            &json!({"client_rate_limit": {"enabled": true}, "zai": {"enabled": true, "api_key": "k"}}),
+        )
+        .unwrap();
+        assert!(updated.client_rate_limit.enabled);
+        assert!(updated.zai.enabled);
+        assert_eq!(updated.port, current.port);
+
+        assert!(apply_patch(&current, &json!({"port": "not a number"})).is_err());
+        assert!(apply_patch(&current, &json!([1])).is_err());
+        assert!(apply_patch(
+            &current,
+            &json!({"model_aliases": [{"pattern": "(", "target": "x", "match_type": "regex"}]})
+        )
+        .is_err());
+
+        let replaced = replace_section(&current, "model_aliases", json!([{"pattern": "gpt-*", "target": "gemini-2.5-pro"}])).unwrap();
+        assert_eq!(replaced.model_aliases.len(), 1);
+        assert_eq!(get_section(&replaced, "model_aliases").unwrap()[0]["target"], "gemini-2.5-pro");
+        assert!(replace_section(&current, "nope", json!({})).is_err());
+        assert!(get_section(&current, "nope").is_none());
+    }
+}
@@ -0,0 +1,538 @@
+// 反代配置校验
+// 保存、热加载与 `config validate` 命令共用; 每个问题带字段路径 (例如 `proxy.listeners[1].port`),
+// error 会阻止保存, warning 只提示 (配置仍可生效)。
+
+use std::fmt;
+use std::net::IpAddr;
+
+use serde::Serialize;
+
+use crate::proxy::config::{ProxyAuthMode, ProxyConfig};
+use crate::proxy::security::ProxySecurityConfig;
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueSeverity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct ConfigIssue {
+    /// 字段路径, 例如 `proxy.model_aliases[2].pattern`
+    pub path: String,
+    pub message: String,
+    pub severity: IssueSeverity,
+}
+
+impl fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+#[derive(Default)]
+struct Checker {
+    issues: Vec<ConfigIssue>,
+}
+
+impl Checker {
+    fn push(&mut self, severity: IssueSeverity, path: String, message: impl Into<String>) {
+        self.issues.push(ConfigIssue { path, message: message.into(), severity });
+    }
+
+    fn error(&mut self, path: String, message: impl Into<String>) {
+        self.push(IssueSeverity::Error, path, message);
+    }
+
+    fn warning(&mut self, path: String, message: impl Into<String>) {
+        self.push(IssueSeverity::Warning, path, message);
+    }
+
+    /// 必填地址; 只允许给定的协议
+    fn url(&mut self, path: String, value: &str, schemes: &[&str]) {
+        let value = value.trim();
+        if value.is_empty() {
+            self.error(path, "URL is required");
+            return;
+        }
+        match url::Url::parse(value) {
+            Ok(url) if !schemes.contains(&url.scheme()) => {
+                self.error(path, format!("Unsupported scheme '{}', expected {}", url.scheme(), schemes.join(" or ")))
+            }
+            Ok(url) if url.host_str().is_none_or(str::is_empty) => self.error(path, "URL has no host"),
+            Ok(_) => {}
+            Err(e) => self.error(path, format!("Invalid URL '{}': {}", value, e)),
+        }
+    }
+
+    /// 可选地址, 为空时使用默认值
+    fn optional_url(&mut self, path: String, value: &str) {
+        if !value.trim().is_empty() {
+            self.url(path, value, HTTP);
+        }
+    }
+
+    fn required(&mut self, path: String, value: &str, what: &str) {
+        if value.trim().is_empty() {
+            self.error(path, format!("{} is required", what));
+        }
+    }
+}
+
+const HTTP: &[&str] = &["http", "https"];
+const PROXY_SCHEMES: &[&str] = &["http", "https", "socks5", "socks5h"];
+
+/// 两个绑定地址是否会争用同一端口 (0.0.0.0 / :: 覆盖所有地址)
+fn hosts_overlap(a: &str, b: &str) -> bool {
+    let unspecified = |h: &str| h.parse::<IpAddr>().is_ok_and(|ip| ip.is_unspecified());
+    a == b || unspecified(a) || unspecified(b)
+}
+
+fn check_rules(c: &mut Checker, config: &ProxyConfig) {
+    for (i, rule) in config.model_aliases.iter().enumerate().filter(|(_, r)| r.enabled) {
+        if let Err(e) = crate::proxy::common::model_alias::validate_rules(std::slice::from_ref(rule)) {
+            c.error(format!("proxy.model_aliases[{}]", i), e);
+        }
+        if !rule.fallbacks.is_empty() && rule.fallback_on.is_empty() {
+            c.warning(
+                format!("proxy.model_aliases[{}].fallback_on", i),
+                "Fallback models are configured but no error type triggers them",
+            );
+        }
+        let duplicate = config.model_aliases[..i]
+            .iter()
+            .position(|r| r.enabled && r.pattern == rule.pattern && r.match_type == rule.match_type);
+        if let Some(first) = duplicate {
+            c.error(
+                format!("proxy.model_aliases[{}].pattern", i),
+                format!("Duplicate alias '{}', already defined by model_aliases[{}]", rule.pattern, first),
+            );
+        }
+    }
+    for (i, rule) in config.content_filter.rules.iter().enumerate().filter(|(_, r)| r.enabled) {
+        if let Err(e) = crate::proxy::common::content_filter::validate_rules(std::slice::from_ref(rule)) {
+            c.error(format!("proxy.content_filter.rules[{}]", i), e);
+        }
+    }
+    for (i, rule) in config.stream_transform.rules.iter().enumerate() {
+        if let Err(e) = crate::proxy::common::output_rules::validate(&rule.output) {
+            c.error(format!("proxy.stream_transform.rules[{}].output", i), e);
+        }
+    }
+}
+
+fn check_upstreams(c: &mut Checker, config: &ProxyConfig) {
+    if config.upstream_proxy.enabled {
+        c.url("proxy.upstream_proxy.url".to_string(), &config.upstream_proxy.url, PROXY_SCHEMES);
+    }
+    for (i, rule) in config.provider_proxies.iter().enumerate().filter(|(_, r)| r.proxy.enabled) {
+        c.url(format!("proxy.provider_proxies[{}].proxy.url", i), &rule.proxy.url, PROXY_SCHEMES);
+    }
+    for (i, rule) in config.provider_tls.iter().enumerate() {
+        if let Err(e) = crate::proxy::upstream::client_pool::apply_tls(reqwest::Client::builder(), &rule.tls) {
+            c.error(format!("proxy.provider_tls[{}].tls", i), e);
+        }
+    }
+
+    if config.zai.enabled {
+        c.url("proxy.zai.base_url".to_string(), &config.zai.base_url, HTTP);
+        if config.zai.api_key.trim().is_empty() && config.zai.api_keys.is_empty() {
+            c.error("proxy.zai.api_key".to_string(), "z.ai is enabled but no API key is configured");
+        }
+    }
+    if config.ollama.enabled {
+        c.url("proxy.ollama.base_url".to_string(), &config.ollama.base_url, HTTP);
+    }
+    if config.audio.enabled {
+        c.url("proxy.audio.base_url".to_string(), &config.audio.base_url, HTTP);
+        c.required("proxy.audio.api_key".to_string(), &config.audio.api_key, "API key");
+    }
+    if config.azure_openai.enabled {
+        c.url("proxy.azure_openai.endpoint".to_string(), &config.azure_openai.endpoint, HTTP);
+        c.required("proxy.azure_openai.api_key".to_string(), &config.azure_openai.api_key, "API key");
+    }
+    if config.bedrock.enabled {
+        // 凭证可以来自环境变量, 这里只检查区域
+        c.required("proxy.bedrock.region".to_string(), &config.bedrock.region, "Region");
+    }
+
+    for (i, upstream) in config.compatible_upstreams.upstreams.iter().enumerate().filter(|(_, u)| u.enabled) {
+        if upstream.preset.is_none() {
+            c.url(format!("proxy.compatible_upstreams.upstreams[{}].base_url", i), &upstream.base_url, HTTP);
+        } else {
+            c.optional_url(format!("proxy.compatible_upstreams.upstreams[{}].base_url", i), &upstream.base_url);
+        }
+        if upstream.api_key.trim().is_empty() {
+            c.warning(
+                format!("proxy.compatible_upstreams.upstreams[{}].api_key", i),
+                "API key is empty; requests are sent without credentials",
+            );
+        }
+    }
+    for (i, provider) in config.embeddings.providers.iter().enumerate().filter(|(_, p)| p.enabled) {
+        c.optional_url(format!("proxy.embeddings.providers[{}].base_url", i), &provider.base_url);
+    }
+    for (i, provider) in config.rerank.providers.iter().enumerate().filter(|(_, p)| p.enabled) {
+        c.optional_url(format!("proxy.rerank.providers[{}].base_url", i), &provider.base_url);
+    }
+    for (i, backend) in config.images.backends.iter().enumerate().filter(|(_, b)| b.enabled) {
+        c.optional_url(format!("proxy.images.backends[{}].base_url", i), &backend.base_url);
+    }
+}
+
+fn check_auth(c: &mut Checker, config: &ProxyConfig) {
+    for (i, token) in config.admin_tokens.iter().enumerate().filter(|(_, t)| t.enabled) {
+        c.required(format!("proxy.admin_tokens[{}].name", i), &token.name, "Name");
+        let path = format!("proxy.admin_tokens[{}].token", i);
+        if token.token.trim().is_empty() {
+            c.error(path, "Token is required");
+        } else if token.token == config.api_key || config.admin_password.as_deref() == Some(token.token.as_str()) {
+            c.error(path, "Token must differ from api_key and admin_password");
+        } else if let Some(first) = config.admin_tokens[..i].iter().position(|t| t.enabled && t.token == token.token) {
+            c.error(path, format!("Duplicate token, already used by admin_tokens[{}]", first));
+        }
+    }
+    if config.jwt_auth.enabled {
+        let jwt = &config.jwt_auth;
+        if jwt.hmac_secret.is_empty() && jwt.jwks_url.trim().is_empty() {
+            c.error("proxy.jwt_auth.jwks_url".to_string(), "JWT auth needs a JWKS URL or an HMAC secret");
+        }
+        if !jwt.jwks_url.trim().is_empty() {
+            c.url("proxy.jwt_auth.jwks_url".to_string(), &jwt.jwks_url, HTTP);
+        }
+        c.required("proxy.jwt_auth.subject_claim".to_string(), &jwt.subject_claim, "Subject claim");
+    }
+}
+
+fn check_webhooks(c: &mut Checker, config: &ProxyConfig) {
+    let webhooks = &config.webhooks;
+    if !webhooks.enabled {
+        return;
+    }
+    for (i, target) in webhooks.targets.iter().enumerate().filter(|(_, t)| t.enabled) {
+        c.required(format!("proxy.webhooks.targets[{}].name", i), &target.name, "Name");
+        c.url(format!("proxy.webhooks.targets[{}].url", i), &target.url, HTTP);
+    }
+    if !(0.0..1.0).contains(&webhooks.spend_warning_ratio) {
+        c.error(
+            "proxy.webhooks.spend_warning_ratio".to_string(),
+            "Spend warning ratio must be at least 0 and below 1",
+        );
+    }
+}
+
+fn check_smart_routing(c: &mut Checker, config: &ProxyConfig) {
+    let smart = &config.smart_routing;
+    if !smart.enabled {
+        return;
+    }
+    for (i, route) in smart.routes.iter().enumerate().filter(|(_, r)| r.enabled) {
+        c.required(format!("proxy.smart_routing.routes[{}].alias", i), &route.alias, "Alias");
+        if route.candidates.is_empty() {
+            c.warning(format!("proxy.smart_routing.routes[{}].candidates", i), "Route has no candidates and is ignored");
+        }
+        for (j, candidate) in route.candidates.iter().enumerate() {
+            let path = format!("proxy.smart_routing.routes[{}].candidates[{}]", i, j);
+            c.required(format!("{}.upstream", path), &candidate.upstream, "Upstream");
+            c.required(format!("{}.model", path), &candidate.model, "Model");
+        }
+    }
+    let w = &smart.weights;
+    if [w.latency, w.error_rate, w.cost].iter().any(|v| !v.is_finite() || *v < 0.0) {
+        c.error("proxy.smart_routing.weights".to_string(), "Weights must be zero or positive");
+    }
+}
+
+fn check_shadow(c: &mut Checker, config: &ProxyConfig) {
+    if !config.shadow.enabled {
+        return;
+    }
+    for (i, rule) in config.shadow.rules.iter().enumerate().filter(|(_, r)| r.enabled) {
+        let path = format!("proxy.shadow.rules[{}]", i);
+        c.required(format!("{}.name", path), &rule.name, "Name");
+        if !(0.0..=100.0).contains(&rule.percent) {
+            c.error(format!("{}.percent", path), "Percent must be between 0 and 100");
+        }
+        if let Some(base_url) = rule.base_url.as_deref().filter(|u| !u.trim().is_empty()) {
+            c.url(format!("{}.base_url", path), base_url, HTTP);
+        } else if rule.target_model.as_deref().is_none_or(|m| m.trim().is_empty()) {
+            c.warning(path, "Rule mirrors to the local proxy with the same model; set a target model or base URL");
+        }
+    }
+}
+
+fn check_hedging(c: &mut Checker, config: &ProxyConfig) {
+    if !config.hedging.enabled {
+        return;
+    }
+    for (i, rule) in config.hedging.rules.iter().enumerate().filter(|(_, r)| r.enabled) {
+        let path = format!("proxy.hedging.rules[{}]", i);
+        c.required(format!("{}.name", path), &rule.name, "Name");
+        c.required(format!("{}.backup_model", path), &rule.backup_model, "Backup model");
+        if rule.threshold_ms == 0 {
+            c.warning(format!("{}.threshold_ms", path), "Threshold 0 sends every request to both models");
+        }
+    }
+}
+
+fn check_council(c: &mut Checker, config: &ProxyConfig) {
+    let council = &config.council;
+    if !council.enabled {
+        return;
+    }
+    let names: Vec<&str> = council.councils.iter().map(|c| c.name.as_str()).collect();
+    for (i, entry) in council.councils.iter().enumerate().filter(|(_, c)| c.enabled) {
+        let path = format!("proxy.council.councils[{}]", i);
+        c.required(format!("{}.name", path), &entry.name, "Name");
+        c.required(format!("{}.judge", path), &entry.judge, "Judge model");
+        if entry.members.is_empty() || entry.members.len() > crate::proxy::compare::MAX_MODELS {
+            c.error(
+                format!("{}.members", path),
+                format!("Council needs 1 to {} member models", crate::proxy::compare::MAX_MODELS),
+            );
+        }
+        if entry.members.iter().chain(std::iter::once(&entry.judge)).any(|m| names.contains(&m.as_str())) {
+            c.error(format!("{}.members", path), "Members and judge cannot be councils themselves");
+        }
+    }
+}
+
+fn check_structured_output(c: &mut Checker, config: &ProxyConfig) {
+    let max = crate::proxy::structured_output::MAX_RETRIES;
+    if config.structured_output.enabled && config.structured_output.max_retries > max {
+        c.error("proxy.structured_output.max_retries".to_string(), format!("Retries must be between 0 and {}", max));
+    }
+}
+
+fn check_tool_emulation(c: &mut Checker, config: &ProxyConfig) {
+    if config.tool_emulation.enabled && config.tool_emulation.models.is_empty() {
+        c.warning("proxy.tool_emulation.models".to_string(), "No models configured; tool emulation has no effect");
+    }
+}
+
+fn check_batches(c: &mut Checker, config: &ProxyConfig) {
+    let batches = &config.batches;
+    if !(1..=64).contains(&batches.concurrency) {
+        c.error("proxy.batches.concurrency".to_string(), "Concurrency must be between 1 and 64");
+    }
+    if batches.max_requests == 0 {
+        c.error("proxy.batches.max_requests".to_string(), "Max requests per batch must be at least 1");
+    }
+}
+
+fn check_files(c: &mut Checker, config: &ProxyConfig) {
+    if config.files.max_file_mb == 0 {
+        c.error("proxy.files.max_file_mb".to_string(), "Max file size must be at least 1 MB");
+    }
+}
+
+fn check_moderation(c: &mut Checker, config: &ProxyConfig) {
+    let moderation = &config.moderation;
+    if !(moderation.threshold > 0.0 && moderation.threshold <= 1.0) {
+        c.error("proxy.moderation.threshold".to_string(), "Threshold must be greater than 0 and at most 1");
+    }
+    for (i, rule) in moderation.rules.iter().enumerate().filter(|(_, r)| r.enabled) {
+        if let Err(e) = crate::proxy::moderation::validate_rules(std::slice::from_ref(rule)) {
+            c.error(format!("proxy.moderation.rules[{}]", i), e);
+        }
+    }
+}
+
+fn check_llama_server(c: &mut Checker, config: &ProxyConfig) {
+    let llama = &config.llama_server;
+    if !llama.enabled {
+        return;
+    }
+    for (field, path) in [("binary_path", &llama.binary_path), ("model_path", &llama.model_path)] {
+        if path.trim().is_empty() {
+            c.error(format!("proxy.llama_server.{}", field), "Path is required when llama-server is enabled");
+        } else if !std::path::Path::new(path.trim()).exists() {
+            c.warning(format!("proxy.llama_server.{}", field), format!("File not found: {}", path.trim()));
+        }
+    }
+    if llama.port == 0 || llama.port == config.port {
+        c.error("proxy.llama_server.port".to_string(), "Port must be set and differ from the proxy port");
+    }
+}
+
+fn check_listeners(c: &mut Checker, config: &ProxyConfig) {
+    if config.port == 0 {
+        c.error("proxy.port".to_string(), "Port must be between 1 and 65535");
+    }
+    let auth_enabled = !matches!(
+        ProxySecurityConfig::from_proxy_config(config).effective_auth_mode(),
+        ProxyAuthMode::Off
+    );
+    let auth_listener = config.listeners.iter().any(|l| l.enabled && l.require_auth);
+    if (auth_enabled || auth_listener) && config.api_key.trim().is_empty() {
+        c.warning(
+            "proxy.api_key".to_string(),
+            "API key is empty; routes that require auth only accept virtual keys",
+        );
+    }
+    let tls_used = config.listener_tls.enabled || config.listeners.iter().any(|l| l.enabled && l.tls);
+    if tls_used {
+        let tls = &config.listener_tls;
+        c.required("proxy.listener_tls.cert_path".to_string(), &tls.cert_path, "Certificate path");
+        c.required("proxy.listener_tls.key_path".to_string(), &tls.key_path, "Private key path");
+        // 证书在保存时加载, 无法读取时阻止保存 (运行中的服务会热替换证书)
+        if !tls.cert_path.trim().is_empty() && !tls.key_path.trim().is_empty() {
+            if let Err(e) = crate::proxy::common::tls_cert::load_acceptor(tls) {
+                c.error("proxy.listener_tls".to_string(), e);
+            }
+        }
+    }
+
+    let access_rules = |c: &mut Checker, path: &str, rules: &crate::proxy::config::AccessControlConfig| {
+        for (scope, list) in [("admin", &rules.admin), ("inference", &rules.inference)] {
+            if let Err(e) = crate::proxy::middleware::access_control::validate_rules(list) {
+                c.error(format!("{}.{}", path, scope), e);
+            }
+        }
+    };
+    access_rules(c, "proxy.access_control", &config.access_control);
+    for (i, listener) in config.listeners.iter().enumerate() {
+        if let Some(rules) = &listener.access_control {
+            access_rules(c, &format!("proxy.listeners[{}].access_control", i), rules);
+        }
+    }
+
+    let main_host = config.get_bind_address();
+    for (i, listener) in config.listeners.iter().enumerate().filter(|(_, l)| l.enabled) {
+        if listener.host.trim().parse::<IpAddr>().is_err() {
+            c.error(format!("proxy.listeners[{}].host", i), format!("Invalid bind address '{}'", listener.host));
+        }
+        let path = format!("proxy.listeners[{}].port", i);
+        if listener.port == 0 {
+            c.error(path, "Port must be between 1 and 65535");
+            continue;
+        }
+        if listener.port == config.port && hosts_overlap(&listener.host, main_host) {
+            c.error(path, format!("Port {} conflicts with proxy.port on {}", listener.port, main_host));
+            continue;
+        }
+        let conflict = config.listeners[..i]
+            .iter()
+            .position(|l| l.enabled && l.port == listener.port && hosts_overlap(&l.host, &listener.host));
+        if let Some(first) = conflict {
+            c.error(path, format!("Port {} conflicts with listeners[{}]", listener.port, first));
+        }
+    }
+}
+
+/// 检查整份反代配置, 按字段顺序返回所有问题
+pub fn check(config: &ProxyConfig) -> Vec<ConfigIssue> {
+    let mut checker = Checker::default();
+    check_listeners(&mut checker, config);
+    check_auth(&mut checker, config);
+    check_webhooks(&mut checker, config);
+    check_smart_routing(&mut checker, config);
+    check_shadow(&mut checker, config);
+    check_hedging(&mut checker, config);
+    check_council(&mut checker, config);
+    check_structured_output(&mut checker, config);
+    check_tool_emulation(&mut checker, config);
+    check_batches(&mut checker, config);
+    check_files(&mut checker, config);
+    check_moderation(&mut checker, config);
+    check_llama_server(&mut checker, config);
+    check_upstreams(&mut checker, config);
+    check_rules(&mut checker, config);
+    checker.issues
+}
+
+/// 存在 error 时返回合并后的错误信息 (每行一个问题)
+pub fn ensure_valid(config: &ProxyConfig) -> Result<(), String> {
+    let errors: Vec<String> = check(config)
+        .iter()
+        .filter(|i| i.severity == IssueSeverity::Error)
+        .map(ToString::to_string)
+        .collect();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("Invalid proxy config:\n{}", errors.join("\n")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy::config::{ListenerConfig, ListenerTlsConfig, ModelAliasRule, ProviderTlsRule, UpstreamTlsConfig};
+
+    fn paths(issues: &[ConfigIssue], severity: IssueSeverity) -> Vec<&str> {
+        issues.iter().filter(|i| i.severity == severity).map(|i| i.path.as_str()).collect()
+    }
+
+    #[test]
+    fn test_default_config_is_valid() {
+        assert_eq!(paths(&check(&ProxyConfig::default()), IssueSeverity::Error), Vec::<&str>::new());
+        assert!(ensure_valid(&ProxyConfig::default()).is_ok());
+    }
+
+    #[test]
+    fn test_reports_field_paths() {
+        let mut config = ProxyConfig::default();
+        let alias = ModelAliasRule {
+            pattern: "gpt-4o".to_string(),
+            target: "gemini-2.5-pro".to_string(),
+            match_type: Default::default(),
+            upstream: None,
+            enabled: true,
+            fallbacks: Vec::new(),
+            fallback_on: Vec::new(),
+        };
+        config.model_aliases = vec![alias.clone(), alias];
+        config.api_key = String::new();
+        config.upstream_proxy.enabled = true;
+        config.upstream_proxy.url = "ftp://proxy.local".to_string();
+        config.zai.enabled = true;
+        config.zai.base_url = "not a url".to_string();
+        config.listener_tls = ListenerTlsConfig {
+            enabled: true,
+            cert_path: "/nonexistent/cert.pem".to_string(),
+            key_path: "/nonexistent/key.pem".to_string(),
+        };
+        config.provider_tls = vec![ProviderTlsRule {
+            provider: "compat:lab".to_string(),
+            tls: UpstreamTlsConfig { client_cert_path: Some("/nonexistent/client.pem".to_string()), ..Default::default() },
+        }];
+        let listener = ListenerConfig {
+            enabled: true,
+            name: "lan".to_string(),
+            host: "0.0.0.0".to_string(),
+            port: config.port,
+            require_auth: true,
+            allow_admin: false,
+            tls: false,
+            access_control: None,
+        };
+        config.listeners = vec![
+            listener.clone(),
+            ListenerConfig { port: 9000, ..listener.clone() },
+            ListenerConfig { port: 9000, host: "192.168.1.2".to_string(), ..listener },
+        ];
+
+        let issues = check(&config);
+        assert_eq!(
+            paths(&issues, IssueSeverity::Error),
+            vec![
+                "proxy.listener_tls",
+                "proxy.listeners[0].port",
+                "proxy.listeners[2].port",
+                "proxy.upstream_proxy.url",
+                "proxy.provider_tls[0].tls",
+                "proxy.zai.base_url",
+                "proxy.zai.api_key",
+                "proxy.model_aliases[1].pattern",
+            ]
+        );
+        assert_eq!(paths(&issues, IssueSeverity::Warning), vec!["proxy.api_key"]);
+
+        let message = ensure_valid(&config).unwrap_err();
+        assert!(message.contains("proxy.listeners[2].port: Port 9000 conflicts with listeners[1]"));
+        assert!(message.contains("Unsupported scheme 'ftp'"));
+        assert!(message.contains("proxy.listener_tls: 读取 TLS 证书 /nonexistent/cert.pem 失败"));
+    }
+}
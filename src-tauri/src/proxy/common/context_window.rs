@@ -0,0 +1,223 @@
+// 上下文窗口溢出保护
+// 按模型的上下文大小检查请求 (prompt + 预留输出), 超出时按配置拒绝, 或从最早的消息开始删除;
+// system 消息与最后一条消息始终保留。
+
+use serde_json::{json, Value};
+
+use crate::proxy::common::model_mapping::wildcard_match;
+use crate::proxy::common::token_counter::{count_request, count_value_text, TokenEncoding, TOKENS_PER_MESSAGE};
+use crate::proxy::config::{ContextOverflowStrategy, ContextWindowConfig};
+
+/// 内置的上下文大小 (按顺序匹配, 模型名先转为小写)
+static BUILTIN_CONTEXT_SIZES: &[(&str, u32)] = &[
+    ("gpt-4.1*", 1_047_576),
+    ("gpt-5*", 400_000),
+    ("gpt-4o*", 128_000),
+    ("chatgpt-4o*", 128_000),
+    ("gpt-4-turbo*", 128_000),
+    ("gpt-4-32k*", 32_768),
+    ("gpt-4", 8_192),
+    ("gpt-3.5-turbo*", 16_385),
+    ("o1-mini*", 128_000),
+    ("o1*", 200_000),
+    ("o3*", 200_000),
+    ("o4-mini*", 200_000),
+    ("claude-*", 200_000),
+    ("gemini-1.5-pro*", 2_097_152),
+    ("gemini-*", 1_048_576),
+    ("*llama-3.1-*", 128_000),
+    ("*llama-3.3-*", 128_000),
+    ("mistral-large*", 128_000),
+    ("codestral*", 256_000),
+];
+
+const PLACEHOLDER: &str = "[Earlier conversation omitted to fit the context window: {n} messages removed]";
+
+/// 检查结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FitOutcome {
+    Fits,
+    /// 已删除 `dropped` 条消息, 截断后约 `tokens` 个 token
+    Truncated { dropped: usize, tokens: u32 },
+    /// 无法放入 (或策略为 error); `budget` 为扣除输出预留后可用的 prompt token 数
+    Overflow { tokens: u32, budget: u32 },
+}
+
+/// 用户规则优先, 其次内置表; 未知模型返回 None (不检查)
+pub fn context_limit(config: &ContextWindowConfig, model: &str) -> Option<u32> {
+    if let Some(rule) = config.models.iter().find(|r| wildcard_match(r.model.trim(), model)) {
+        return Some(rule.context_tokens);
+    }
+    let model = model.rsplit('/').next().unwrap_or(model).to_lowercase();
+    BUILTIN_CONTEXT_SIZES
+        .iter()
+        .find(|(pattern, _)| wildcard_match(pattern, &model))
+        .map(|(_, size)| *size)
+}
+
+/// 请求声明的最大输出 token 数
+fn requested_output(body: &Value) -> Option<u32> {
+    ["max_tokens", "max_completion_tokens", "max_output_tokens"]
+        .iter()
+        .find_map(|key| body.get(*key))
+        .or_else(|| body.pointer("/generationConfig/maxOutputTokens"))
+        .and_then(|v| v.as_u64())
+        .map(|v| v.min(u32::MAX as u64) as u32)
+}
+
+fn role(message: &Value) -> &str {
+    message.get("role").and_then(|r| r.as_str()).unwrap_or("")
+}
+
+/// 截断后的第一条消息必须是普通的用户消息 (不能是工具结果或助手回复)
+pub(crate) fn is_clean_start(message: &Value) -> bool {
+    if role(message) != "user" {
+        return false;
+    }
+    let blocks = message.get("content").or_else(|| message.get("parts")).and_then(|c| c.as_array());
+    !blocks.is_some_and(|blocks| {
+        blocks.iter().any(|b| {
+            b.get("type").and_then(|t| t.as_str()) == Some("tool_result") || b.get("functionResponse").is_some()
+        })
+    })
+}
+
+fn placeholder_message(key: &str, dropped: usize) -> Value {
+    let text = PLACEHOLDER.replace("{n}", &dropped.to_string());
+    if key == "contents" {
+        json!({ "role": "user", "parts": [{ "text": text }] })
+    } else {
+        json!({ "role": "user", "content": text })
+    }
+}
+
+/// 检查请求是否放得进 `limit`, 需要时就地删除最早的消息
+pub fn fit(config: &ContextWindowConfig, model: &str, limit: u32, body: &mut Value) -> FitOutcome {
+    let reserve = requested_output(body).unwrap_or(config.reserve_output_tokens);
+    let budget = limit.saturating_sub(reserve);
+    let tokens = count_request(model, body).input_tokens;
+    if tokens <= budget {
+        return FitOutcome::Fits;
+    }
+    let overflow = FitOutcome::Overflow { tokens, budget };
+    if config.strategy == ContextOverflowStrategy::Error {
+        return overflow;
+    }
+    let Some(key) = ["messages", "contents"].into_iter().find(|k| body.get(*k).is_some_and(|v| v.is_array())) else {
+        return overflow;
+    };
+    let encoding = TokenEncoding::for_model(model);
+    let messages = body[key].as_array().cloned().unwrap_or_default();
+
+    // 可删除的消息: 除最后一条以外的非 system 消息
+    let candidates: Vec<usize> = messages
+        .iter()
+        .enumerate()
+        .filter(|(_, m)| !matches!(role(m), "system" | "developer"))
+        .map(|(i, _)| i)
+        .collect();
+    let Some((_, droppable)) = candidates.split_last() else {
+        return overflow;
+    };
+    let summarize = config.strategy == ContextOverflowStrategy::Summarize;
+    let placeholder_cost = if summarize {
+        count_value_text(encoding, &placeholder_message(key, droppable.len())) + TOKENS_PER_MESSAGE
+    } else {
+        0
+    };
+
+    let fits = |total: u32| total.saturating_add(placeholder_cost) <= budget;
+    let cost = |index: usize| count_value_text(encoding, &messages[index]) + TOKENS_PER_MESSAGE;
+
+    // 删除到放得下为止; 剩余的第一条消息必须是普通用户消息, 工具调用与结果因此成对删除
+    let mut total = tokens;
+    let mut dropped = 0;
+    while dropped < droppable.len() {
+        if fits(total) && is_clean_start(&messages[candidates[dropped]]) {
+            break;
+        }
+        total = total.saturating_sub(cost(droppable[dropped]));
+        dropped += 1;
+    }
+    if !fits(total) || !is_clean_start(&messages[candidates[dropped]]) {
+        return overflow;
+    }
+
+    let removed: Vec<usize> = droppable[..dropped].to_vec();
+    let mut kept: Vec<Value> = messages
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| !removed.contains(i))
+        .map(|(_, m)| m)
+        .collect();
+    if summarize {
+        kept.insert(removed[0], placeholder_message(key, dropped));
+        total += placeholder_cost;
+    }
+    body[key] = Value::Array(kept);
+    FitOutcome::Truncated { dropped, tokens: total }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy::config::ContextWindowRule;
+
+    fn config(strategy: ContextOverflowStrategy) -> ContextWindowConfig {
+        ContextWindowConfig {
+            enabled: true,
+            strategy,
+            reserve_output_tokens: 0,
+            models: vec![ContextWindowRule { model: "tiny-*".to_string(), context_tokens: 60 }],
+        }
+    }
+
+    fn conversation() -> Value {
+        let long = "lorem ipsum dolor sit amet ".repeat(4);
+        json!({
+            "model": "tiny-model",
+            "messages": [
+                {"role": "system", "content": "Be brief."},
+                {"role": "user", "content": long},
+                {"role": "assistant", "content": long},
+                {"role": "user", "content": "What is the weather?"},
+                {"role": "assistant", "tool_calls": [{"id": "c1", "type": "function", "function": {"name": "weather", "arguments": "{}"}}]},
+                {"role": "tool", "tool_call_id": "c1", "content": "Sunny"},
+                {"role": "user", "content": "Thanks, and tomorrow?"}
+            ]
+        })
+    }
+
+    #[test]
+    fn test_context_limits() {
+        let cfg = config(ContextOverflowStrategy::Error);
+        assert_eq!(context_limit(&cfg, "tiny-model"), Some(60));
+        assert_eq!(context_limit(&cfg, "gpt-4o-mini"), Some(128_000));
+        assert_eq!(context_limit(&cfg, "gpt-4"), Some(8_192));
+        assert_eq!(context_limit(&cfg, "meta-llama/Llama-3.3-70B-Instruct-Turbo"), Some(128_000));
+        assert_eq!(context_limit(&cfg, "my-local-model"), None);
+
+        let mut body = conversation();
+        assert!(matches!(fit(&cfg, "tiny-model", 60, &mut body), FitOutcome::Overflow { .. }));
+        assert_eq!(body, conversation());
+        assert_eq!(fit(&cfg, "tiny-model", 100_000, &mut body), FitOutcome::Fits);
+    }
+
+    #[test]
+    fn test_drop_oldest_and_summarize() {
+        let mut body = conversation();
+        let outcome = fit(&config(ContextOverflowStrategy::DropOldest), "tiny-model", 60, &mut body);
+        assert!(matches!(outcome, FitOutcome::Truncated { dropped: 2, tokens } if tokens <= 60), "{:?}", outcome);
+        let roles: Vec<&str> = body["messages"].as_array().unwrap().iter().map(role).collect();
+        assert_eq!(roles, vec!["system", "user", "assistant", "tool", "user"]);
+
+        // 预算更小时工具调用与结果一起删除
+        let mut body = conversation();
+        let outcome = fit(&config(ContextOverflowStrategy::Summarize), "tiny-model", 80, &mut body);
+        assert!(matches!(outcome, FitOutcome::Truncated { .. }), "{:?}", outcome);
+        let messages = body["messages"].as_array().unwrap();
+        assert_eq!(role(&messages[0]), "system");
+        assert!(messages[1]["content"].as_str().unwrap().starts_with("[Earlier conversation omitted"));
+        assert_eq!(messages.last().unwrap()["content"], "Thanks, and tomorrow?");
+    }
+}
@@ -0,0 +1,149 @@
+// max_tokens 自动调整
+// 输出上限取模型支持的最大输出与上下文剩余空间 (上下文大小减去 prompt) 中较小者;
+// 请求的值超过上限时下调, 必填该字段的协议缺少时补上默认值。
+
+use serde_json::{json, Value};
+
+use crate::proxy::common::model_mapping::wildcard_match;
+use crate::proxy::common::token_counter::count_request;
+use crate::proxy::config::MaxTokensConfig;
+
+/// 内置的最大输出 token 数 (按顺序匹配, 模型名先转为小写)
+static BUILTIN_OUTPUT_LIMITS: &[(&str, u32)] = &[
+    ("gpt-4.1*", 32_768),
+    ("gpt-5*", 128_000),
+    ("gpt-4o*", 16_384),
+    ("chatgpt-4o*", 16_384),
+    ("gpt-4-turbo*", 4_096),
+    ("gpt-3.5-turbo*", 4_096),
+    ("o1-mini*", 65_536),
+    ("o1*", 100_000),
+    ("o3*", 100_000),
+    ("o4-mini*", 100_000),
+    ("claude-opus-4*", 32_000),
+    ("claude-sonnet-4*", 64_000),
+    ("claude-haiku-4*", 64_000),
+    ("claude-3-7-sonnet*", 64_000),
+    ("claude-3-5-*", 8_192),
+    ("claude-3-*", 4_096),
+    ("gemini-2.5-*", 65_536),
+    ("gemini-*", 8_192),
+];
+
+/// 输出上限字段, 按协议: OpenAI Chat / Responses / Gemini
+const OUTPUT_FIELDS: [&str; 4] = ["/max_tokens", "/max_completion_tokens", "/max_output_tokens", "/generationConfig/maxOutputTokens"];
+
+/// 调整记录, 写入响应头 `X-Max-Tokens-Adjusted`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MaxTokensAdjustment {
+    Clamped { from: u32, to: u32 },
+    Injected(u32),
+}
+
+impl MaxTokensAdjustment {
+    pub fn header_value(&self) -> String {
+        match self {
+            MaxTokensAdjustment::Clamped { from, to } => format!("clamped {} -> {}", from, to),
+            MaxTokensAdjustment::Injected(value) => format!("injected {}", value),
+        }
+    }
+}
+
+/// 用户规则优先, 其次内置表; 未知模型返回 None
+pub fn output_limit(config: &MaxTokensConfig, model: &str) -> Option<u32> {
+    if let Some(rule) = config.models.iter().find(|r| wildcard_match(r.model.trim(), model)) {
+        return Some(rule.max_output_tokens);
+    }
+    let model = model.rsplit('/').next().unwrap_or(model).to_lowercase();
+    BUILTIN_OUTPUT_LIMITS
+        .iter()
+        .find(|(pattern, _)| wildcard_match(pattern, &model))
+        .map(|(_, limit)| *limit)
+}
+
+/// 该路径的协议要求请求必须携带 max_tokens
+pub fn requires_max_tokens(path: &str) -> bool {
+    path == "/v1/messages"
+}
+
+/// 按模型上限和上下文剩余空间调整请求; `context` 为模型的上下文大小 (未知时为 None)
+pub fn adjust(
+    config: &MaxTokensConfig,
+    model: &str,
+    context: Option<u32>,
+    required: bool,
+    body: &mut Value,
+) -> Option<MaxTokensAdjustment> {
+    // prompt 已占满上下文时不处理, 交给上下文窗口保护
+    let available = context
+        .map(|limit| limit.saturating_sub(count_request(model, body).input_tokens))
+        .filter(|available| *available > 0);
+    let ceiling = [output_limit(config, model), available].into_iter().flatten().min();
+
+    let field = OUTPUT_FIELDS.iter().find(|p| body.pointer(p).is_some_and(|v| v.is_u64()));
+    match field {
+        Some(pointer) => {
+            let ceiling = ceiling?;
+            let slot = body.pointer_mut(pointer)?;
+            let from = slot.as_u64()?.min(u32::MAX as u64) as u32;
+            if from <= ceiling {
+                return None;
+            }
+            *slot = json!(ceiling);
+            Some(MaxTokensAdjustment::Clamped { from, to: ceiling })
+        }
+        None if required && config.inject_missing && body.is_object() => {
+            let value = ceiling.map_or(config.default_max_tokens, |c| c.min(config.default_max_tokens));
+            body["max_tokens"] = json!(value);
+            Some(MaxTokensAdjustment::Injected(value))
+        }
+        None => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy::config::MaxTokensRule;
+
+    #[test]
+    fn test_clamp_to_model_and_context() {
+        let config = MaxTokensConfig::default();
+        assert_eq!(output_limit(&config, "gpt-4o-mini"), Some(16_384));
+        assert_eq!(output_limit(&config, "anthropic/claude-sonnet-4-5"), Some(64_000));
+        assert_eq!(output_limit(&config, "my-local-model"), None);
+
+        let mut body = json!({"model": "gpt-4o", "max_tokens": 100_000, "messages": [{"role": "user", "content": "hi"}]});
+        assert_eq!(
+            adjust(&config, "gpt-4o", Some(128_000), false, &mut body),
+            Some(MaxTokensAdjustment::Clamped { from: 100_000, to: 16_384 })
+        );
+        assert_eq!(body["max_tokens"], 16_384);
+        // 已在上限内不修改
+        assert_eq!(adjust(&config, "gpt-4o", Some(128_000), false, &mut body), None);
+
+        // 上下文剩余空间小于模型上限时按剩余空间
+        let config = MaxTokensConfig {
+            models: vec![MaxTokensRule { model: "tiny-*".to_string(), max_output_tokens: 4096 }],
+            ..MaxTokensConfig::default()
+        };
+        let mut body = json!({"generationConfig": {"maxOutputTokens": 4096}, "contents": [{"role": "user", "parts": [{"text": "hi"}]}]});
+        let adjusted = adjust(&config, "tiny-model", Some(1000), false, &mut body);
+        assert!(matches!(adjusted, Some(MaxTokensAdjustment::Clamped { from: 4096, to }) if to < 1000 && to > 980), "{:?}", adjusted);
+    }
+
+    #[test]
+    fn test_inject_when_required() {
+        let config = MaxTokensConfig::default();
+        let mut body = json!({"model": "claude-3-haiku-20240307", "messages": [{"role": "user", "content": "hi"}]});
+        assert_eq!(adjust(&config, "claude-3-haiku-20240307", None, false, &mut body), None);
+        assert_eq!(
+            adjust(&config, "claude-3-haiku-20240307", None, true, &mut body),
+            Some(MaxTokensAdjustment::Injected(4096))
+        );
+        assert_eq!(body["max_tokens"], 4096);
+        assert_eq!(MaxTokensAdjustment::Injected(4096).header_value(), "injected 4096");
+        assert!(requires_max_tokens("/v1/messages"));
+        assert!(!requires_max_tokens("/v1/messages/count_tokens"));
+    }
+}
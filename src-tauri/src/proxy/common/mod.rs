@@ -2,9 +2,30 @@
 
 // pub mod error;
 // pub mod rate_limiter;
+pub mod compression;
+pub mod config_patch;
+pub mod client_info;
+pub mod config_validation;
+pub mod content_filter;
+pub mod context_window;
+pub mod max_tokens;
+pub mod model_alias;
 pub mod model_mapping;
+pub mod output_rules;
+pub mod param_policy;
+pub mod pii;
+pub mod pricing;
+pub mod prompt_cache;
+pub mod reasoning;
+pub mod system_prompt;
 pub mod utils;
 pub mod json_schema;
 pub mod tool_adapter;
 pub mod tool_adapters;
 pub mod schema_cache;
+pub mod sse;
+pub mod stream_relay;
+pub mod sigv4;
+pub mod token_counter;
+pub mod token_rate;
+pub mod tls_cert;
@@ -0,0 +1,249 @@
+// 模型别名与改写规则
+// 在 custom_mapping / 系统默认映射之前应用: 先把客户端请求的模型名改写为别名目标,
+// 规则可同时指定转发的上游 (例如 "gpt-4o" -> "glm-4.7" on z.ai)。
+
+use regex::Regex;
+
+use crate::proxy::config::{AliasUpstream, FallbackTrigger, ModelAliasMatch, ModelAliasRule};
+
+tokio::task_local! {
+    static BYPASS: ();
+}
+
+/// 别名解析结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelAlias {
+    pub target: String,
+    pub upstream: Option<AliasUpstream>,
+    pub fallbacks: Vec<String>,
+    pub fallback_on: Vec<FallbackTrigger>,
+}
+
+/// 在作用域内跳过别名改写 (备用模型按原样转发, 避免再次命中同一条规则)
+pub async fn without_aliases<F: std::future::Future>(fut: F) -> F::Output {
+    BYPASS.scope((), fut).await
+}
+
+#[derive(Debug)]
+enum Matcher {
+    Exact(String),
+    Glob(String),
+    Regex(Regex),
+}
+
+#[derive(Debug)]
+struct CompiledRule {
+    matcher: Matcher,
+    target: String,
+    upstream: Option<AliasUpstream>,
+    fallbacks: Vec<String>,
+    fallback_on: Vec<FallbackTrigger>,
+}
+
+impl CompiledRule {
+    fn compile(rule: &ModelAliasRule) -> Result<Self, String> {
+        let pattern = rule.pattern.trim();
+        if pattern.is_empty() {
+            return Err("Alias pattern must not be empty".to_string());
+        }
+        if rule.target.trim().is_empty() {
+            return Err(format!("Alias target for '{}' must not be empty", pattern));
+        }
+        let matcher = match rule.match_type {
+            ModelAliasMatch::Exact => Matcher::Exact(pattern.to_string()),
+            ModelAliasMatch::Glob => Matcher::Glob(pattern.to_string()),
+            // 整串匹配, 避免 "gpt-4" 意外命中 "gpt-4o-mini"
+            ModelAliasMatch::Regex => Matcher::Regex(
+                Regex::new(&format!("^(?:{})$", pattern))
+                    .map_err(|e| format!("Invalid alias regex '{}': {}", pattern, e))?,
+            ),
+        };
+        if rule.fallbacks.iter().any(|m| m.trim().is_empty()) {
+            return Err(format!("Fallback models for '{}' must not be empty", pattern));
+        }
+        Ok(Self {
+            matcher,
+            target: rule.target.trim().to_string(),
+            upstream: rule.upstream,
+            fallbacks: rule.fallbacks.iter().map(|m| m.trim().to_string()).collect(),
+            fallback_on: rule.fallback_on.clone(),
+        })
+    }
+
+    fn apply(&self, model: &str) -> Option<String> {
+        match &self.matcher {
+            Matcher::Exact(p) => (p == model).then(|| self.target.clone()),
+            Matcher::Glob(p) => super::model_mapping::wildcard_match(p, model).then(|| self.target.clone()),
+            Matcher::Regex(re) => re.captures(model).map(|caps| {
+                let mut out = String::new();
+                caps.expand(&self.target, &mut out);
+                out
+            }),
+        }
+    }
+}
+
+/// 校验规则是否可用 (正则可编译、字段非空)
+pub fn validate_rules(rules: &[ModelAliasRule]) -> Result<(), String> {
+    rules
+        .iter()
+        .filter(|r| r.enabled)
+        .try_for_each(|r| CompiledRule::compile(r).map(|_| ()))
+}
+
+/// 编译后的别名表, 支持热更新
+pub struct ModelAliasTable {
+    rules: std::sync::RwLock<Vec<ModelAliasRule>>,
+    compiled: std::sync::RwLock<Vec<CompiledRule>>,
+}
+
+impl ModelAliasTable {
+    /// 无效规则会被跳过并记录警告 (保存时已经通过 `validate_rules` 校验)
+    pub fn new(rules: Vec<ModelAliasRule>) -> Self {
+        let table = Self {
+            rules: std::sync::RwLock::new(Vec::new()),
+            compiled: std::sync::RwLock::new(Vec::new()),
+        };
+        table.update_rules(rules);
+        table
+    }
+
+    pub fn rules(&self) -> Vec<ModelAliasRule> {
+        self.rules.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    pub fn update_rules(&self, rules: Vec<ModelAliasRule>) {
+        let compiled = rules
+            .iter()
+            .filter(|r| r.enabled)
+            .filter_map(|r| match CompiledRule::compile(r) {
+                Ok(c) => Some(c),
+                Err(e) => {
+                    tracing::warn!("[Model-Alias] Skipping rule: {}", e);
+                    None
+                }
+            })
+            .collect();
+        *self.compiled.write().unwrap_or_else(|e| e.into_inner()) = compiled;
+        *self.rules.write().unwrap_or_else(|e| e.into_inner()) = rules;
+    }
+
+    /// 按顺序匹配, 返回第一条命中规则的结果
+    pub fn resolve(&self, model: &str) -> Option<ModelAlias> {
+        if BYPASS.try_with(|_| ()).is_ok() {
+            return None;
+        }
+        let compiled = self.compiled.read().unwrap_or_else(|e| e.into_inner());
+        compiled.iter().find_map(|rule| {
+            rule.apply(model).map(|target| ModelAlias {
+                target,
+                upstream: rule.upstream,
+                fallbacks: rule.fallbacks.clone(),
+                fallback_on: rule.fallback_on.clone(),
+            })
+        })
+    }
+
+    /// 是否有配置了备用模型链的规则 (备用模型链中间件据此决定是否读取请求体)
+    pub fn has_fallbacks(&self) -> bool {
+        if BYPASS.try_with(|_| ()).is_ok() {
+            return false;
+        }
+        let compiled = self.compiled.read().unwrap_or_else(|e| e.into_inner());
+        compiled.iter().any(|rule| !rule.fallbacks.is_empty())
+    }
+
+    /// 改写模型名, 未命中时原样返回
+    pub fn rewrite(&self, model: &str) -> String {
+        match self.resolve(model) {
+            Some(alias) => {
+                if alias.target != model {
+                    crate::modules::logger::log_info(&format!("[Router] 模型别名: {} -> {}", model, alias.target));
+                }
+                alias.target
+            }
+            None => model.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(pattern: &str, target: &str, match_type: ModelAliasMatch) -> ModelAliasRule {
+        ModelAliasRule {
+            pattern: pattern.to_string(),
+            target: target.to_string(),
+            match_type,
+            upstream: None,
+            enabled: true,
+            fallbacks: Vec::new(),
+            fallback_on: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_first_matching_rule_wins() {
+        let mut zai = rule("gpt-4o", "glm-4.7", ModelAliasMatch::Exact);
+        zai.upstream = Some(AliasUpstream::Zai);
+        let mut disabled = rule("gpt-*", "never", ModelAliasMatch::Glob);
+        disabled.enabled = false;
+        let table = ModelAliasTable::new(vec![
+            disabled,
+            zai,
+            rule("gpt-*", "gemini-2.5-flash", ModelAliasMatch::Glob),
+        ]);
+
+        assert_eq!(
+            table.resolve("gpt-4o"),
+            Some(ModelAlias {
+                target: "glm-4.7".to_string(),
+                upstream: Some(AliasUpstream::Zai),
+                fallbacks: Vec::new(),
+                fallback_on: Vec::new(),
+            })
+        );
+        assert_eq!(table.rewrite("gpt-4o-mini"), "gemini-2.5-flash");
+        assert_eq!(table.rewrite("claude-sonnet-4-5"), "claude-sonnet-4-5");
+    }
+
+    #[test]
+    fn test_regex_rule_with_captures() {
+        let table = ModelAliasTable::new(vec![rule(
+            r"claude-(?P<tier>opus|sonnet)-4-5-\d{8}",
+            "claude-${tier}-4-5",
+            ModelAliasMatch::Regex,
+        )]);
+        assert_eq!(table.rewrite("claude-opus-4-5-20251101"), "claude-opus-4-5");
+        // 整串匹配
+        assert_eq!(table.rewrite("x-claude-opus-4-5-20251101"), "x-claude-opus-4-5-20251101");
+    }
+
+    #[test]
+    fn test_validate_rules() {
+        assert!(validate_rules(&[rule("gpt-(", "x", ModelAliasMatch::Regex)]).is_err());
+        assert!(validate_rules(&[rule("gpt-4", " ", ModelAliasMatch::Exact)]).is_err());
+        assert!(validate_rules(&[rule("gpt-4*", "x", ModelAliasMatch::Glob)]).is_ok());
+
+        // 无效规则在运行时被跳过
+        let table = ModelAliasTable::new(vec![rule("gpt-(", "x", ModelAliasMatch::Regex)]);
+        assert_eq!(table.resolve("gpt-("), None);
+        assert_eq!(table.rules().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_fallbacks_bypass_aliases() {
+        let mut primary = rule("gpt-*", "gemini-2.5-pro", ModelAliasMatch::Glob);
+        primary.fallbacks = vec![" gpt-4o-mini ".to_string()];
+        primary.fallback_on = vec![FallbackTrigger::ContextLength];
+        let table = ModelAliasTable::new(vec![primary]);
+
+        let alias = table.resolve("gpt-4o").unwrap();
+        assert_eq!(alias.fallbacks, vec!["gpt-4o-mini"]);
+        assert_eq!(alias.fallback_on, vec![FallbackTrigger::ContextLength]);
+        // 备用模型同样匹配 "gpt-*", 在作用域内按原样转发
+        assert_eq!(without_aliases(async { table.rewrite("gpt-4o-mini") }).await, "gpt-4o-mini");
+        assert_eq!(table.rewrite("gpt-4o-mini"), "gemini-2.5-pro");
+    }
+}
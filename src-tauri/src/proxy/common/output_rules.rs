@@ -0,0 +1,356 @@
+// 输出后处理
+// 额外的停止序列、去掉包裹整个回复的 ``` 代码块、去掉固定的前后缀、逐行正则替换。
+// 流式文本按 chunk 推入, 可能属于停止序列、开头或结尾的部分先暂存; 完整响应整体推入一次,
+// 两种方式经过同一套状态机, 结果一致。
+
+use regex::Regex;
+use serde_json::{json, Value};
+
+use crate::proxy::config::OutputRules;
+
+const FENCE: &str = "```";
+
+/// 编译后的规则
+#[derive(Debug, Clone)]
+pub struct CompiledOutputRules {
+    stop: Vec<String>,
+    strip_code_fences: bool,
+    prefix: Option<String>,
+    suffix: Option<String>,
+    replacements: Vec<(Regex, String)>,
+}
+
+impl CompiledOutputRules {
+    pub fn new(rules: &OutputRules) -> Result<Self, String> {
+        let non_empty = |s: &Option<String>| s.clone().filter(|s| !s.is_empty());
+        let replacements = rules
+            .replacements
+            .iter()
+            .map(|r| {
+                Regex::new(&r.pattern)
+                    .map(|re| (re, r.replacement.clone()))
+                    .map_err(|e| format!("Invalid output replacement regex '{}': {}", r.pattern, e))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            stop: rules.stop.iter().filter(|s| !s.is_empty()).cloned().collect(),
+            strip_code_fences: rules.strip_code_fences,
+            prefix: non_empty(&rules.trim_prefix),
+            suffix: non_empty(&rules.trim_suffix),
+            replacements,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stop.is_empty()
+            && !self.strip_code_fences
+            && self.prefix.is_none()
+            && self.suffix.is_none()
+            && self.replacements.is_empty()
+    }
+}
+
+/// 只检查规则能否编译 (配置校验)
+pub fn validate(rules: &OutputRules) -> Result<(), String> {
+    CompiledOutputRules::new(rules).map(|_| ())
+}
+
+/// 不大于 `index` 的最近字符边界
+fn floor_boundary(text: &str, mut index: usize) -> usize {
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+/// 单段输出 (一个 choice 或一个 Anthropic 文本块) 的处理状态
+/// 依次经过: 停止序列 -> 开头 (代码块开始标记与前缀) -> 逐行替换 -> 结尾 (代码块结束标记与后缀)
+#[derive(Debug)]
+pub struct OutputProcessor {
+    rules: CompiledOutputRules,
+    /// 可能是停止序列开头的未决后缀
+    raw: String,
+    /// 开头尚未确定时的缓冲, None 表示已确定
+    head: Option<String>,
+    /// 不完整的行 (有替换规则时)
+    line: String,
+    /// 可能被结尾规则去掉的部分
+    tail: String,
+    fenced: bool,
+    stop_sequence: Option<String>,
+    finished: bool,
+}
+
+impl OutputProcessor {
+    pub fn new(rules: CompiledOutputRules) -> Self {
+        Self {
+            rules,
+            raw: String::new(),
+            head: Some(String::new()),
+            line: String::new(),
+            tail: String::new(),
+            fenced: false,
+            stop_sequence: None,
+            finished: false,
+        }
+    }
+
+    /// 命中的停止序列; 命中后处理器结束, 后续文本全部丢弃
+    pub fn stop_sequence(&self) -> Option<&str> {
+        self.stop_sequence.as_deref()
+    }
+
+    /// 推入一段文本, 返回可以输出的部分
+    pub fn push(&mut self, text: &str) -> String {
+        if self.finished {
+            return String::new();
+        }
+        self.raw.push_str(text);
+        let hit = self
+            .rules
+            .stop
+            .iter()
+            .filter_map(|s| self.raw.find(s.as_str()).map(|pos| (pos, s.clone())))
+            .min_by_key(|(pos, _)| *pos);
+        if let Some((pos, stop)) = hit {
+            self.raw.truncate(pos);
+            self.stop_sequence = Some(stop);
+            return self.finish();
+        }
+        let keep = self
+            .rules
+            .stop
+            .iter()
+            .flat_map(|s| s.char_indices().skip(1).map(move |(i, _)| &s[..i]))
+            .filter(|p| self.raw.ends_with(p))
+            .map(str::len)
+            .max()
+            .unwrap_or(0);
+        let ready: String = self.raw.drain(..self.raw.len() - keep).collect();
+        self.head_stage(ready, false)
+    }
+
+    /// 输出结束, 返回剩余部分
+    pub fn finish(&mut self) -> String {
+        if self.finished {
+            return String::new();
+        }
+        self.finished = true;
+        let raw = std::mem::take(&mut self.raw);
+        self.head_stage(raw, true)
+    }
+
+    fn head_stage(&mut self, text: String, last: bool) -> String {
+        let Some(mut head) = self.head.take() else {
+            return self.line_stage(text, last);
+        };
+        head.push_str(&text);
+        match self.resolve_head(&head, last) {
+            Some(rest) => self.line_stage(rest, last),
+            None => {
+                self.head = Some(head);
+                String::new()
+            }
+        }
+    }
+
+    /// 去掉开头的代码块标记行与前缀, 还无法判断时返回 None
+    fn resolve_head(&mut self, head: &str, last: bool) -> Option<String> {
+        let mut rest = head;
+        self.fenced = false;
+        if self.rules.strip_code_fences {
+            let start = rest.trim_start();
+            if start.starts_with(FENCE) {
+                match start.find('\n') {
+                    Some(newline) => {
+                        rest = &start[newline + 1..];
+                        self.fenced = true;
+                    }
+                    None if !last => return None,
+                    None => {}
+                }
+            } else if FENCE.starts_with(start) && !last {
+                return None;
+            }
+        }
+        if let Some(prefix) = &self.rules.prefix {
+            if rest.len() < prefix.len() && prefix.starts_with(rest) && !last {
+                return None;
+            }
+            rest = rest.strip_prefix(prefix.as_str()).unwrap_or(rest);
+        }
+        Some(rest.to_string())
+    }
+
+    fn line_stage(&mut self, text: String, last: bool) -> String {
+        if self.rules.replacements.is_empty() {
+            return self.tail_stage(text, last);
+        }
+        self.line.push_str(&text);
+        let split = if last { self.line.len() } else { self.line.rfind('\n').map_or(0, |i| i + 1) };
+        let ready: String = self.line.drain(..split).collect();
+        let mut replaced = String::new();
+        for line in ready.split_inclusive('\n') {
+            let (body, newline) = line.strip_suffix('\n').map_or((line, ""), |body| (body, "\n"));
+            let mut body = body.to_string();
+            for (regex, replacement) in &self.rules.replacements {
+                body = regex.replace_all(&body, replacement.as_str()).into_owned();
+            }
+            replaced.push_str(&body);
+            replaced.push_str(newline);
+        }
+        self.tail_stage(replaced, last)
+    }
+
+    fn tail_stage(&mut self, text: String, last: bool) -> String {
+        self.tail.push_str(&text);
+        if last {
+            let tail = std::mem::take(&mut self.tail);
+            return self.resolve_tail(tail);
+        }
+        if !self.fenced && self.rules.suffix.is_none() {
+            return std::mem::take(&mut self.tail);
+        }
+        // 保留结尾规则可能涉及的部分: 末尾空白、代码块结束标记及其前的换行与空白、后缀
+        let tail = self.tail.as_str();
+        let mut hold = tail.trim_end().len();
+        if self.fenced {
+            hold = floor_boundary(tail, hold.saturating_sub(FENCE.len() + 1));
+            hold = tail[..hold].trim_end().len();
+        }
+        if let Some(suffix) = &self.rules.suffix {
+            hold = floor_boundary(tail, hold.saturating_sub(suffix.len()));
+        }
+        self.tail.drain(..hold).collect()
+    }
+
+    fn resolve_tail(&self, mut tail: String) -> String {
+        if self.fenced {
+            if let Some(stripped) = tail.trim_end().strip_suffix(FENCE) {
+                tail = stripped.strip_suffix('\n').unwrap_or(stripped).to_string();
+            }
+        }
+        if let Some(suffix) = &self.rules.suffix {
+            if let Some(stripped) = tail.trim_end().strip_suffix(suffix.as_str()) {
+                tail = stripped.to_string();
+            }
+        }
+        tail
+    }
+}
+
+/// 处理完整的文本, 返回 (结果, 命中的停止序列)
+pub fn apply(rules: &CompiledOutputRules, text: &str) -> (String, Option<String>) {
+    let mut processor = OutputProcessor::new(rules.clone());
+    let mut out = processor.push(text);
+    out.push_str(&processor.finish());
+    (out, processor.stop_sequence)
+}
+
+/// 处理完整的 JSON 响应 (OpenAI Chat / Completions 与 Anthropic Messages), 返回是否有改动
+pub fn apply_response(rules: &CompiledOutputRules, json: &mut Value) -> bool {
+    let mut changed = false;
+    if let Some(choices) = json.get_mut("choices").and_then(Value::as_array_mut) {
+        for choice in choices {
+            let pointer = if choice.get("message").is_some() { "/message/content" } else { "/text" };
+            let Some(text) = choice.pointer(pointer).and_then(Value::as_str) else {
+                continue;
+            };
+            let (out, stop) = apply(rules, text);
+            if out != text {
+                *choice.pointer_mut(pointer).expect("checked above") = Value::String(out);
+                changed = true;
+            }
+            if stop.is_some() {
+                choice["finish_reason"] = json!("stop");
+                changed = true;
+            }
+        }
+        return changed;
+    }
+
+    let Some(blocks) = json.get_mut("content").and_then(Value::as_array_mut) else {
+        return false;
+    };
+    let mut stopped_at = None;
+    for (i, block) in blocks.iter_mut().enumerate() {
+        let Some(text) = block.get("text").and_then(Value::as_str).filter(|_| block["type"] == "text") else {
+            continue;
+        };
+        let (out, stop) = apply(rules, text);
+        if out != text {
+            block["text"] = Value::String(out);
+            changed = true;
+        }
+        if stop.is_some() {
+            stopped_at = Some((i, stop));
+            break;
+        }
+    }
+    if let Some((i, stop)) = stopped_at {
+        // 停止序列之后的内容块一并丢弃
+        blocks.truncate(i + 1);
+        json["stop_reason"] = json!("stop_sequence");
+        json["stop_sequence"] = json!(stop);
+        changed = true;
+    }
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy::config::OutputReplacement;
+
+    fn rules() -> CompiledOutputRules {
+        CompiledOutputRules::new(&OutputRules {
+            stop: vec!["<END>".to_string()],
+            strip_code_fences: true,
+            trim_prefix: Some("Answer:".to_string()),
+            trim_suffix: Some("Thanks!".to_string()),
+            replacements: vec![OutputReplacement { pattern: r"\bfoo\b".to_string(), replacement: "bar".to_string() }],
+        })
+        .unwrap()
+    }
+
+    /// 按每个字符切分推入, 结果应与整体处理一致
+    fn streamed(rules: &CompiledOutputRules, text: &str) -> (String, Option<String>) {
+        let mut processor = OutputProcessor::new(rules.clone());
+        let mut out = String::new();
+        for c in text.chars() {
+            out.push_str(&processor.push(&c.to_string()));
+        }
+        out.push_str(&processor.finish());
+        (out, processor.stop_sequence().map(String::from))
+    }
+
+    #[test]
+    fn test_rules_match_between_stream_and_collected() {
+        let rules = rules();
+        let cases = [
+            ("```json\n{\"a\": \"foo\"}\n```", "{\"a\": \"bar\"}", None),
+            ("Answer: foo food\nfoo Thanks! \n", " bar food\nbar ", None),
+            ("Answer:x\nfoo<END> ignored", "x\nbar", Some("<END>")),
+            ("plain ```text``` <EN", "plain ```text``` <EN", None),
+        ];
+        for (input, expected, stop) in cases {
+            let collected = apply(&rules, input);
+            assert_eq!(collected, (expected.to_string(), stop.map(String::from)), "{}", input);
+            assert_eq!(streamed(&rules, input), collected, "{}", input);
+        }
+        assert!(CompiledOutputRules::new(&OutputRules::default()).unwrap().is_empty());
+
+        let mut openai = json!({"choices": [{"message": {"content": "Answer: foo<END>more"}, "finish_reason": "length"}]});
+        assert!(apply_response(&rules, &mut openai));
+        assert_eq!(openai["choices"][0], json!({"message": {"content": " bar"}, "finish_reason": "stop"}));
+        let mut anthropic = json!({"content": [{"type": "text", "text": "a<END>b"}, {"type": "text", "text": "c"}], "stop_reason": "end_turn"});
+        assert!(apply_response(&rules, &mut anthropic));
+        assert_eq!(anthropic["content"], json!([{"type": "text", "text": "a"}]));
+        assert_eq!(anthropic["stop_sequence"], "<END>");
+        let invalid = OutputRules {
+            replacements: vec![OutputReplacement { pattern: "(".to_string(), replacement: String::new() }],
+            ..Default::default()
+        };
+        assert!(validate(&invalid).is_err());
+    }
+}
@@ -0,0 +1,170 @@
+// 按上游清理 OpenAI 格式的请求参数
+// 删除上游不支持的字段、把数值限制在上游接受的范围内, 避免上游直接返回 400;
+// 每项调整都会记录到请求日志 (`param_adjustments`)。
+
+use serde_json::{json, Value};
+
+use crate::proxy::common::model_mapping::wildcard_match;
+use crate::proxy::config::{ParamClamp, ParamPolicyConfig};
+use crate::proxy::providers::presets::ProviderPreset;
+
+/// 单个上游的参数策略 (内置规则 + 用户规则)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ParamPolicy {
+    pub drop: Vec<String>,
+    pub clamp: Vec<ParamClamp>,
+}
+
+fn clamp(param: &str, min: f64, max: f64) -> ParamClamp {
+    ParamClamp { param: param.to_string(), min: Some(min), max: Some(max) }
+}
+
+fn names(list: &[&str]) -> Vec<String> {
+    list.iter().map(|s| s.to_string()).collect()
+}
+
+impl ParamPolicy {
+    /// 内置上游的已知限制
+    pub fn builtin(upstream: &str) -> Self {
+        match upstream {
+            // Gemini (默认账号池): 以下字段在协议转换时被忽略, candidateCount 最大 8
+            "google" => Self {
+                drop: names(&["logit_bias", "logprobs", "top_logprobs", "frequency_penalty", "presence_penalty", "seed"]),
+                clamp: vec![clamp("temperature", 0.0, 2.0), clamp("top_p", 0.0, 1.0), clamp("n", 1.0, 8.0)],
+            },
+            // Bedrock 上的 Anthropic / Titan 模型: temperature 仅接受 0-1
+            "bedrock" => Self {
+                drop: names(&["logit_bias", "logprobs", "top_logprobs", "frequency_penalty", "presence_penalty", "seed", "n"]),
+                clamp: vec![clamp("temperature", 0.0, 1.0), clamp("top_p", 0.0, 1.0)],
+            },
+            "ollama" => Self {
+                drop: names(&["logit_bias", "logprobs", "top_logprobs", "n"]),
+                clamp: vec![clamp("temperature", 0.0, 2.0), clamp("top_p", 0.0, 1.0)],
+            },
+            "azure" => Self {
+                drop: Vec::new(),
+                clamp: vec![
+                    clamp("temperature", 0.0, 2.0),
+                    clamp("top_p", 0.0, 1.0),
+                    clamp("frequency_penalty", -2.0, 2.0),
+                    clamp("presence_penalty", -2.0, 2.0),
+                ],
+            },
+            _ => Self::default(),
+        }
+    }
+
+    pub fn for_preset(preset: &ProviderPreset) -> Self {
+        Self {
+            drop: names(preset.unsupported_params),
+            clamp: preset.clamped_params.iter().map(|(p, min, max)| clamp(p, *min, *max)).collect(),
+        }
+    }
+
+    /// 追加用户配置中匹配任一名称 (上游名 / 预设 id) 的规则
+    pub fn with_rules(mut self, config: &ParamPolicyConfig, upstreams: &[&str]) -> Self {
+        for rule in config.rules.iter().filter(|r| upstreams.iter().any(|u| wildcard_match(&r.upstream, u))) {
+            self.drop.extend(rule.drop.iter().cloned());
+            self.clamp.extend(rule.clamp.iter().cloned());
+        }
+        self
+    }
+
+    /// 调整请求体, 返回调整说明 (例如 `dropped logit_bias`, `clamped temperature 1.5 -> 1`)
+    pub fn apply(&self, body: &mut Value) -> Vec<String> {
+        let mut adjustments = Vec::new();
+        let Some(obj) = body.as_object_mut() else {
+            return adjustments;
+        };
+        for param in &self.drop {
+            if obj.remove(param).is_some() {
+                adjustments.push(format!("dropped {}", param));
+            }
+        }
+        for rule in &self.clamp {
+            let Some(value) = obj.get_mut(&rule.param) else { continue };
+            let Some(current) = value.as_f64() else { continue };
+            let bounded = rule.max.map_or(current, |max| current.min(max));
+            let bounded = rule.min.map_or(bounded, |min| bounded.max(min));
+            if bounded == current {
+                continue;
+            }
+            // 整数参数 (例如 n) 保持整数类型
+            *value = if (value.is_i64() || value.is_u64()) && bounded.fract() == 0.0 {
+                json!(bounded as i64)
+            } else {
+                json!(bounded)
+            };
+            adjustments.push(format!("clamped {} {} -> {}", rule.param, current, bounded));
+        }
+        adjustments
+    }
+}
+
+/// 按策略清理请求并记录调整; 策略关闭时不做任何修改
+pub fn sanitize(
+    config: &ParamPolicyConfig,
+    upstream: &str,
+    preset: Option<&ProviderPreset>,
+    body: &mut Value,
+) -> Vec<String> {
+    if !config.enabled {
+        return Vec::new();
+    }
+    let (policy, names) = match preset {
+        Some(preset) => (ParamPolicy::for_preset(preset), vec![upstream, preset.id]),
+        None => (ParamPolicy::builtin(upstream), vec![upstream]),
+    };
+    let adjustments = policy.with_rules(config, &names).apply(body);
+    if !adjustments.is_empty() {
+        tracing::info!("[ParamPolicy] {}: {}", upstream, adjustments.join(", "));
+        crate::proxy::monitor::note_param_adjustments(&adjustments);
+    }
+    adjustments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy::config::ParamPolicyRule;
+
+    #[test]
+    fn test_builtin_policy_drops_and_clamps() {
+        let mut body = json!({
+            "model": "claude-3-5-sonnet",
+            "temperature": 1.5,
+            "top_p": 0.9,
+            "logit_bias": {"42": 1},
+            "frequency_penalty": 0.5
+        });
+        let adjustments = sanitize(&ParamPolicyConfig::default(), "bedrock", None, &mut body);
+        assert_eq!(body, json!({ "model": "claude-3-5-sonnet", "temperature": 1.0, "top_p": 0.9 }));
+        assert_eq!(
+            adjustments,
+            vec!["dropped logit_bias", "dropped frequency_penalty", "clamped temperature 1.5 -> 1"]
+        );
+    }
+
+    #[test]
+    fn test_user_rules_and_disabled_policy() {
+        let mut config = ParamPolicyConfig {
+            enabled: true,
+            rules: vec![ParamPolicyRule {
+                upstream: "groq".to_string(),
+                drop: vec!["user".to_string()],
+                clamp: vec![ParamClamp { param: "max_tokens".to_string(), min: None, max: Some(8192.0) }],
+            }],
+        };
+        let preset = crate::proxy::providers::presets::find("groq").unwrap();
+        let original = json!({ "model": "llama-3.3-70b-versatile", "n": 3, "user": "u", "max_tokens": 32768 });
+
+        let mut body = original.clone();
+        sanitize(&config, "my-groq", Some(preset), &mut body);
+        assert_eq!(body, json!({ "model": "llama-3.3-70b-versatile", "n": 1, "max_tokens": 8192 }));
+
+        config.enabled = false;
+        let mut body = original.clone();
+        assert!(sanitize(&config, "my-groq", Some(preset), &mut body).is_empty());
+        assert_eq!(body, original);
+    }
+}
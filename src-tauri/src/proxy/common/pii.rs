@@ -0,0 +1,281 @@
+// 个人信息脱敏 (邮箱、电话、API Key、卡号)
+// 请求文本中的敏感值替换为编号占位符 (例如 `[EMAIL_1]`), 同一请求内相同的值使用同一个占位符;
+// 响应中出现的占位符还原为原值。检测基于正则, 电话号码为启发式匹配, 可能漏检或误检。
+
+use std::collections::HashMap;
+
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use serde_json::Value;
+
+use crate::proxy::common::content_filter::visit_text_fields;
+use crate::proxy::config::PiiRedactionConfig;
+
+static API_KEY_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(concat!(
+        r"\b(?:sk-[A-Za-z0-9_-]{20,}",
+        r"|AKIA[0-9A-Z]{16}",
+        r"|AIza[0-9A-Za-z_-]{35}",
+        r"|gh[pousr]_[A-Za-z0-9]{36,}",
+        r"|github_pat_[A-Za-z0-9_]{22,}",
+        r"|xox[abprs]-[A-Za-z0-9-]{10,}",
+        r"|glpat-[A-Za-z0-9_-]{20,})"
+    ))
+    .unwrap()
+});
+static EMAIL_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\b[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}\b").unwrap());
+static CARD_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\b\d(?:[ -]?\d){12,18}\b").unwrap());
+// 需要分隔符 (或 + 国家码), 后续分组 3-4 位: 不匹配日期、版本号和 IP
+static PHONE_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?:\+\d{1,3}[ -]?)?(?:\(\d{1,4}\)[ -]?)?\b\d{2,4}(?:[ -]\d{3,4}){1,3}\b|\+\d{9,15}\b").unwrap()
+});
+static PLACEHOLDER_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\[(?:API_KEY|EMAIL|CARD|PHONE)_\d+\]").unwrap());
+
+/// 占位符的最大长度, 用于判断流式文本末尾是否为被截断的占位符
+const MAX_PLACEHOLDER_LEN: usize = 24;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PiiKind {
+    ApiKey,
+    Email,
+    CreditCard,
+    Phone,
+}
+
+impl PiiKind {
+    /// 检测顺序: 卡号先于电话, 避免卡号被识别为电话
+    const ALL: [PiiKind; 4] = [PiiKind::ApiKey, PiiKind::Email, PiiKind::CreditCard, PiiKind::Phone];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PiiKind::ApiKey => "API_KEY",
+            PiiKind::Email => "EMAIL",
+            PiiKind::CreditCard => "CARD",
+            PiiKind::Phone => "PHONE",
+        }
+    }
+
+    fn regex(self) -> &'static Regex {
+        match self {
+            PiiKind::ApiKey => &API_KEY_RE,
+            PiiKind::Email => &EMAIL_RE,
+            PiiKind::CreditCard => &CARD_RE,
+            PiiKind::Phone => &PHONE_RE,
+        }
+    }
+
+    fn accepts(self, matched: &str) -> bool {
+        match self {
+            PiiKind::CreditCard => luhn_valid(matched),
+            PiiKind::Phone => (9..=15).contains(&matched.chars().filter(|c| c.is_ascii_digit()).count()),
+            _ => true,
+        }
+    }
+}
+
+fn luhn_valid(number: &str) -> bool {
+    let digits: Vec<u32> = number.chars().filter_map(|c| c.to_digit(10)).collect();
+    if !(13..=19).contains(&digits.len()) {
+        return false;
+    }
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| match (i % 2, d * 2) {
+            (1, doubled) if doubled > 9 => doubled - 9,
+            (1, doubled) => doubled,
+            _ => d,
+        })
+        .sum();
+    sum % 10 == 0
+}
+
+/// 单个请求的脱敏状态
+#[derive(Debug, Default)]
+pub struct PiiMasker {
+    kinds: Vec<PiiKind>,
+    placeholders: HashMap<String, String>, // 原值 -> 占位符
+    originals: HashMap<String, String>,    // 占位符 -> 原值
+    counts: Vec<(PiiKind, usize)>,
+}
+
+impl PiiMasker {
+    pub fn new(config: &PiiRedactionConfig) -> Self {
+        let kinds = PiiKind::ALL
+            .into_iter()
+            .filter(|kind| match kind {
+                PiiKind::ApiKey => config.api_keys,
+                PiiKind::Email => config.emails,
+                PiiKind::CreditCard => config.credit_cards,
+                PiiKind::Phone => config.phone_numbers,
+            })
+            .collect();
+        Self { kinds, ..Self::default() }
+    }
+
+    /// 脱敏 JSON 中的文本字段, 返回是否有修改
+    pub fn mask_json(&mut self, value: &mut Value) -> bool {
+        let mut modified = false;
+        visit_text_fields(value, &mut |_, text| {
+            if let Some(masked) = self.mask_text(text) {
+                *text = masked;
+                modified = true;
+            }
+        });
+        modified
+    }
+
+    /// 未检测到敏感值时返回 None
+    pub fn mask_text(&mut self, text: &str) -> Option<String> {
+        let mut current = text.to_string();
+        for kind in self.kinds.clone() {
+            if !kind.regex().is_match(&current) {
+                continue;
+            }
+            current = kind
+                .regex()
+                .replace_all(&current, |caps: &Captures| {
+                    let matched = &caps[0];
+                    if kind.accepts(matched) {
+                        self.placeholder(kind, matched)
+                    } else {
+                        matched.to_string()
+                    }
+                })
+                .into_owned();
+        }
+        (current != text).then_some(current)
+    }
+
+    fn placeholder(&mut self, kind: PiiKind, value: &str) -> String {
+        if let Some(existing) = self.placeholders.get(value) {
+            return existing.clone();
+        }
+        let index = match self.counts.iter_mut().find(|(k, _)| *k == kind) {
+            Some((_, count)) => {
+                *count += 1;
+                *count
+            }
+            None => {
+                self.counts.push((kind, 1));
+                1
+            }
+        };
+        let placeholder = format!("[{}_{}]", kind.label(), index);
+        self.placeholders.insert(value.to_string(), placeholder.clone());
+        self.originals.insert(placeholder.clone(), value.to_string());
+        placeholder
+    }
+
+    /// 各类型的脱敏数量 (例如 `EMAIL x2, API_KEY x1`), 只用于日志, 不包含原值
+    pub fn summary(&self) -> String {
+        self.counts
+            .iter()
+            .map(|(kind, count)| format!("{} x{}", kind.label(), count))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    pub fn into_restorer(self) -> PiiRestorer {
+        PiiRestorer { originals: self.originals }
+    }
+}
+
+/// 把响应中的占位符还原为原值; 上游改写过的占位符 (例如改了大小写) 无法还原
+#[derive(Debug, Clone, Default)]
+pub struct PiiRestorer {
+    originals: HashMap<String, String>,
+}
+
+impl PiiRestorer {
+    /// 没有需要还原的内容时返回 None
+    pub fn restore_text(&self, text: &str) -> Option<String> {
+        if !text.contains('[') || !PLACEHOLDER_RE.is_match(text) {
+            return None;
+        }
+        let restored = PLACEHOLDER_RE.replace_all(text, |caps: &Captures| {
+            self.originals.get(&caps[0]).cloned().unwrap_or_else(|| caps[0].to_string())
+        });
+        (restored != text).then(|| restored.into_owned())
+    }
+
+    pub fn restore_json(&self, value: &mut Value) -> bool {
+        let mut modified = false;
+        visit_text_fields(value, &mut |_, text| {
+            if let Some(restored) = self.restore_text(text) {
+                *text = restored;
+                modified = true;
+            }
+        });
+        modified
+    }
+}
+
+/// 文本末尾可能是被截断的占位符 (例如 `[EMA`) 时返回其起始位置
+pub fn partial_placeholder_start(text: &str) -> Option<usize> {
+    let start = text.rfind('[')?;
+    let rest = &text[start + 1..];
+    if rest.len() >= MAX_PLACEHOLDER_LEN {
+        return None;
+    }
+    PiiKind::ALL
+        .iter()
+        .any(|kind| {
+            let prefix = format!("{}_", kind.label());
+            if rest.len() <= prefix.len() {
+                prefix.starts_with(rest)
+            } else {
+                rest.starts_with(&prefix) && rest[prefix.len()..].bytes().all(|b| b.is_ascii_digit())
+            }
+        })
+        .then_some(start)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_mask_and_restore_round_trip() {
+        let mut masker = PiiMasker::new(&PiiRedactionConfig::default());
+        let mut body = json!({
+            "model": "gpt-4o",
+            "user": "alice@example.com",
+            "messages": [{
+                "role": "user",
+                "content": "Mail alice@example.com or bob@corp.io, call +1 415-555-0132, \
+                            card 4111 1111 1111 1111 (not 4111 1111 1111 1112), key sk-proj-abcdefghijklmnopqrstuvwx. \
+                            Again alice@example.com on 2024-05-01 from 192.168.100.200"
+            }]
+        });
+        assert!(masker.mask_json(&mut body));
+        let masked = body["messages"][0]["content"].as_str().unwrap().to_string();
+        assert_eq!(
+            masked,
+            "Mail [EMAIL_1] or [EMAIL_2], call [PHONE_1], card [CARD_1] (not 4111 1111 1111 1112), key [API_KEY_1]. \
+             Again [EMAIL_1] on 2024-05-01 from 192.168.100.200"
+        );
+        // 非文本字段不处理
+        assert_eq!(body["user"], "alice@example.com");
+        assert_eq!(masker.summary(), "API_KEY x1, EMAIL x2, CARD x1, PHONE x1");
+
+        let restorer = masker.into_restorer();
+        let mut resp = json!({"choices": [{"message": {"content": "Sent to [EMAIL_2], unknown [EMAIL_9]"}}]});
+        assert!(restorer.restore_json(&mut resp));
+        assert_eq!(resp["choices"][0]["message"]["content"], "Sent to bob@corp.io, unknown [EMAIL_9]");
+    }
+
+    #[test]
+    fn test_partial_placeholder_and_disabled_kinds() {
+        assert_eq!(partial_placeholder_start("write to [EMA"), Some(9));
+        assert_eq!(partial_placeholder_start("key [API_KEY_1"), Some(4));
+        assert_eq!(partial_placeholder_start("a [link"), None);
+        assert_eq!(partial_placeholder_start("done [EMAIL_1]"), None);
+
+        let mut masker = PiiMasker::new(&PiiRedactionConfig { emails: false, ..PiiRedactionConfig::default() });
+        assert_eq!(masker.mask_text("alice@example.com"), None);
+    }
+}
@@ -0,0 +1,74 @@
+// 请求费用计算
+// 按价格表 (美元 / 百万 token) 计算单个请求的费用, 优先匹配实际上游模型
+
+use crate::proxy::common::model_mapping::wildcard_match;
+use crate::proxy::common::prompt_cache::TokenUsage;
+use crate::proxy::config::ModelPrice;
+
+/// 按顺序查找第一个匹配的价格
+pub fn find_price<'a>(prices: &'a [ModelPrice], model: &str) -> Option<&'a ModelPrice> {
+    prices.iter().find(|p| wildcard_match(&p.pattern, model))
+}
+
+/// 请求费用 (美元); 没有匹配的价格时为 0
+pub fn request_cost(
+    prices: &[ModelPrice],
+    model: &str,
+    mapped_model: Option<&str>,
+    prompt_tokens: u32,
+    completion_tokens: u32,
+) -> f64 {
+    let usage = TokenUsage { input: Some(prompt_tokens), output: Some(completion_tokens), ..Default::default() };
+    usage_cost(prices, model, mapped_model, &usage)
+}
+
+/// 计入提示词缓存的请求费用; 输入 token 包含缓存命中与写入部分, 未配置缓存价格时按输入价格计算
+pub fn usage_cost(prices: &[ModelPrice], model: &str, mapped_model: Option<&str>, usage: &TokenUsage) -> f64 {
+    let price = mapped_model
+        .and_then(|m| find_price(prices, m))
+        .or_else(|| find_price(prices, model));
+    let Some(p) = price else {
+        return 0.0;
+    };
+    let cache_read = usage.cache_read.unwrap_or(0) as f64;
+    let cache_write = usage.cache_write.unwrap_or(0) as f64;
+    let uncached = (usage.input.unwrap_or(0) as f64 - cache_read - cache_write).max(0.0);
+    (uncached * p.input_per_million
+        + cache_read * p.cache_read_per_million.unwrap_or(p.input_per_million)
+        + cache_write * p.cache_write_per_million.unwrap_or(p.input_per_million)
+        + usage.output.unwrap_or(0) as f64 * p.output_per_million)
+        / 1_000_000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy::config::PricingConfig;
+
+    #[test]
+    fn test_request_cost() {
+        let prices = PricingConfig::default().models;
+        // 具体模式排在通配模式之前
+        assert_eq!(find_price(&prices, "gemini-2.5-flash-lite").unwrap().input_per_million, 0.10);
+        assert_eq!(find_price(&prices, "gemini-2.5-flash").unwrap().input_per_million, 0.30);
+
+        let cost = request_cost(&prices, "claude-sonnet-4-5", None, 1_000_000, 100_000);
+        assert!((cost - 4.5).abs() < 1e-9);
+        // 映射后的模型优先
+        let cost = request_cost(&prices, "gpt-4o", Some("gemini-2.5-pro"), 1_000_000, 0);
+        assert!((cost - 1.25).abs() < 1e-9);
+        assert_eq!(request_cost(&prices, "unknown-model", None, 1000, 1000), 0.0);
+    }
+
+    #[test]
+    fn test_usage_cost_with_cache() {
+        let prices = PricingConfig::default().models;
+        // 900k 命中 (0.30) + 50k 写入 (3.75) + 50k 未缓存 (3.0)
+        let usage = TokenUsage { input: Some(1_000_000), output: Some(0), cache_read: Some(900_000), cache_write: Some(50_000) };
+        let cost = usage_cost(&prices, "claude-sonnet-4-5", None, &usage);
+        assert!((cost - (0.27 + 0.1875 + 0.15)).abs() < 1e-9);
+        // 未配置写入价格时按输入价格计算
+        let usage = TokenUsage { input: Some(1_000_000), output: Some(0), cache_read: None, cache_write: Some(1_000_000) };
+        assert!((usage_cost(&prices, "gpt-4o", None, &usage) - 2.5).abs() < 1e-9);
+    }
+}
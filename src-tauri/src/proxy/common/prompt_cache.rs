@@ -0,0 +1,130 @@
+// 提示词缓存
+// 统一各协议 usage 中的缓存 token: OpenAI `prompt_tokens_details.cached_tokens`、
+// Anthropic `cache_read_input_tokens` / `cache_creation_input_tokens`、Gemini `cachedContentTokenCount`。
+// 输入 token 统一为包含缓存命中与写入的完整提示词长度 (OpenAI 语义), Anthropic 的 input_tokens 不含缓存部分, 需要加回。
+
+use serde_json::Value;
+
+/// 一个响应的 token 用量
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TokenUsage {
+    pub input: Option<u32>,
+    pub output: Option<u32>,
+    /// 命中缓存的输入 token
+    pub cache_read: Option<u32>,
+    /// 写入缓存的输入 token
+    pub cache_write: Option<u32>,
+}
+
+impl TokenUsage {
+    /// 流式响应的用量分散在多个事件中 (例如 Anthropic 的 message_start 与 message_delta), 后出现的值优先
+    pub fn merge(self, later: TokenUsage) -> TokenUsage {
+        TokenUsage {
+            input: later.input.or(self.input),
+            output: later.output.or(self.output),
+            cache_read: later.cache_read.or(self.cache_read),
+            cache_write: later.cache_write.or(self.cache_write),
+        }
+    }
+}
+
+fn number(usage: &Value, pointers: &[&str]) -> Option<u32> {
+    pointers
+        .iter()
+        .find_map(|p| usage.pointer(p).and_then(Value::as_u64))
+        .map(|v| v.min(u32::MAX as u64) as u32)
+}
+
+/// 读取任一协议的 usage / usageMetadata 对象
+pub fn read_usage(usage: &Value) -> TokenUsage {
+    let anthropic_read = number(usage, &["/cache_read_input_tokens"]);
+    let anthropic_write = number(usage, &["/cache_creation_input_tokens"]);
+    let cache_read = anthropic_read.or_else(|| {
+        number(usage, &["/prompt_tokens_details/cached_tokens", "/input_tokens_details/cached_tokens", "/cachedContentTokenCount"])
+    });
+    let cache_write = anthropic_write.or_else(|| number(usage, &["/prompt_tokens_details/cache_write_tokens"]));
+
+    let input = match number(usage, &["/prompt_tokens", "/promptTokenCount"]) {
+        Some(prompt) => Some(prompt),
+        // Anthropic 的 input_tokens 只是最后一个缓存断点之后的部分
+        None => number(usage, &["/input_tokens"]).map(|input| {
+            input.saturating_add(anthropic_read.unwrap_or(0)).saturating_add(anthropic_write.unwrap_or(0))
+        }),
+    };
+    let mut output = number(usage, &["/completion_tokens", "/output_tokens", "/candidatesTokenCount"]);
+    if input.is_none() && output.is_none() {
+        output = number(usage, &["/total_tokens", "/totalTokenCount"]);
+    }
+    TokenUsage { input, output, cache_read, cache_write }
+}
+
+/// 内容块、消息或工具定义上是否带有 Anthropic 风格的缓存断点
+pub fn has_cache_hint(value: &Value) -> bool {
+    value.get("cache_control").is_some_and(|c| !c.is_null())
+}
+
+/// 删除 Chat Completions 请求中消息、内容块与工具上的 cache_control, 返回删除的数量
+pub fn strip_cache_hints(body: &mut Value) -> usize {
+    let mut removed = 0;
+    let mut strip = |value: &mut Value| {
+        if let Some(obj) = value.as_object_mut() {
+            removed += obj.remove("cache_control").is_some() as usize;
+        }
+    };
+    for message in body.get_mut("messages").and_then(Value::as_array_mut).into_iter().flatten() {
+        for part in message.get_mut("content").and_then(Value::as_array_mut).into_iter().flatten() {
+            strip(part);
+        }
+        strip(message);
+    }
+    for tool in body.get_mut("tools").and_then(Value::as_array_mut).into_iter().flatten() {
+        if let Some(function) = tool.get_mut("function") {
+            strip(function);
+        }
+        strip(tool);
+    }
+    removed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_read_usage_normalizes_cache_tokens() {
+        let openai = json!({"prompt_tokens": 1200, "completion_tokens": 30, "prompt_tokens_details": {"cached_tokens": 1024}});
+        assert_eq!(
+            read_usage(&openai),
+            TokenUsage { input: Some(1200), output: Some(30), cache_read: Some(1024), cache_write: None }
+        );
+        // Anthropic: 缓存部分加回输入 token
+        let anthropic = json!({"input_tokens": 20, "output_tokens": 5, "cache_read_input_tokens": 900, "cache_creation_input_tokens": 100});
+        assert_eq!(
+            read_usage(&anthropic),
+            TokenUsage { input: Some(1020), output: Some(5), cache_read: Some(900), cache_write: Some(100) }
+        );
+        let gemini = json!({"promptTokenCount": 500, "candidatesTokenCount": 8, "cachedContentTokenCount": 256});
+        assert_eq!(read_usage(&gemini).cache_read, Some(256));
+        assert_eq!(read_usage(&json!({"total_tokens": 9})).output, Some(9));
+
+        // message_delta 只带输出 token, 保留 message_start 中的输入与缓存用量
+        let start = read_usage(&json!({"input_tokens": 3, "output_tokens": 1, "cache_read_input_tokens": 10}));
+        let merged = start.merge(read_usage(&json!({"output_tokens": 42})));
+        assert_eq!(merged, TokenUsage { input: Some(13), output: Some(42), cache_read: Some(10), cache_write: None });
+    }
+
+    #[test]
+    fn test_strip_cache_hints() {
+        let mut body = json!({
+            "messages": [
+                {"role": "system", "content": [{"type": "text", "text": "rules", "cache_control": {"type": "ephemeral"}}]},
+                {"role": "user", "content": "hi", "cache_control": {"type": "ephemeral"}}
+            ],
+            "tools": [{"type": "function", "function": {"name": "f"}, "cache_control": {"type": "ephemeral"}}]
+        });
+        assert!(has_cache_hint(&body["messages"][0]["content"][0]));
+        assert_eq!(strip_cache_hints(&mut body), 3);
+        assert!(!body.to_string().contains("cache_control"));
+    }
+}
@@ -0,0 +1,384 @@
+// 推理内容归一化
+// 不同上游返回推理内容的方式不同: OpenAI 兼容上游的 `reasoning_content` / `reasoning` 字段、
+// 正文中的 `<think>...</think>` 标签、Anthropic 的 thinking 块、Gemini 的 `thought` part。
+// normalize 模式把 OpenAI 格式的推理统一到 `reasoning_content`, strip 模式全部删除。
+// 流式事件和完整 JSON 响应 (collector 输出) 使用同一套规则。
+
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::proxy::config::ReasoningMode;
+
+const THINK_OPEN: &str = "<think>";
+const THINK_CLOSE: &str = "</think>";
+
+/// OpenAI 兼容上游使用的其他推理字段名
+const ALT_REASONING_FIELDS: [&str; 2] = ["reasoning", "thinking"];
+
+/// 按 `<think>` 标签拆分流式文本, 标签被拆到多个 chunk 时也能识别
+#[derive(Debug, Default)]
+pub struct ThinkTagSplitter {
+    in_think: bool,
+    /// 可能是标签开头的未决后缀
+    pending: String,
+    /// `</think>` 之后的空白行不属于正文
+    trim_next: bool,
+}
+
+impl ThinkTagSplitter {
+    /// 返回 (正文, 推理)
+    pub fn push(&mut self, text: &str) -> (String, String) {
+        let mut buf = std::mem::take(&mut self.pending);
+        buf.push_str(text);
+        let (mut content, mut reasoning) = (String::new(), String::new());
+
+        loop {
+            let tag = if self.in_think { THINK_CLOSE } else { THINK_OPEN };
+            if let Some(pos) = buf.find(tag) {
+                self.emit(&buf[..pos], &mut content, &mut reasoning);
+                buf.drain(..pos + tag.len());
+                self.in_think = !self.in_think;
+                self.trim_next = !self.in_think;
+                continue;
+            }
+            // 保留可能是标签前缀的结尾 (标签均为 ASCII, 切分点一定是字符边界)
+            let keep = (1..tag.len().min(buf.len() + 1))
+                .rev()
+                .find(|&k| buf.ends_with(&tag[..k]))
+                .unwrap_or(0);
+            let split = buf.len() - keep;
+            self.emit(&buf[..split], &mut content, &mut reasoning);
+            self.pending = buf[split..].to_string();
+            break;
+        }
+        (content, reasoning)
+    }
+
+    /// 流结束时输出未决内容
+    pub fn finish(&mut self) -> (String, String) {
+        let pending = std::mem::take(&mut self.pending);
+        let (mut content, mut reasoning) = (String::new(), String::new());
+        self.emit(&pending, &mut content, &mut reasoning);
+        (content, reasoning)
+    }
+
+    fn emit(&mut self, text: &str, content: &mut String, reasoning: &mut String) {
+        if self.in_think {
+            reasoning.push_str(text);
+            return;
+        }
+        let text = if self.trim_next { text.trim_start() } else { text };
+        if !text.is_empty() {
+            self.trim_next = false;
+            content.push_str(text);
+        }
+    }
+}
+
+/// 一次性拆分完整文本
+fn split_think_tags(text: &str) -> (String, String) {
+    let mut splitter = ThinkTagSplitter::default();
+    let (mut content, mut reasoning) = splitter.push(text);
+    let (c, r) = splitter.finish();
+    content.push_str(&c);
+    reasoning.push_str(&r);
+    (content, reasoning)
+}
+
+/// 归一化 OpenAI message / delta 对象, 返回是否有改动
+fn normalize_openai_message(
+    mode: ReasoningMode,
+    message: &mut serde_json::Map<String, Value>,
+    split: impl FnOnce(&str) -> (String, String),
+) -> bool {
+    let mut changed = false;
+    let mut reasoning = match message.remove("reasoning_content") {
+        Some(Value::String(s)) => s,
+        Some(_) => {
+            changed = true;
+            String::new()
+        }
+        None => String::new(),
+    };
+    for field in ALT_REASONING_FIELDS {
+        if let Some(value) = message.remove(field) {
+            changed = true;
+            if let Value::String(s) = value {
+                if reasoning.is_empty() {
+                    reasoning = s;
+                }
+            }
+        }
+    }
+
+    if let Some(Value::String(text)) = message.get("content") {
+        let (content, tagged) = split(text);
+        if content != *text {
+            changed = true;
+            reasoning.push_str(&tagged);
+            if content.is_empty() && message.contains_key("tool_calls") {
+                message.insert("content".to_string(), Value::Null);
+            } else {
+                message.insert("content".to_string(), Value::String(content));
+            }
+        }
+    }
+
+    match mode {
+        ReasoningMode::Strip => changed |= !reasoning.is_empty(),
+        _ if !reasoning.is_empty() => {
+            message.insert("reasoning_content".to_string(), Value::String(reasoning));
+        }
+        _ => {}
+    }
+    changed
+}
+
+/// Gemini: 删除 `thought: true` 的 part
+fn strip_gemini_thoughts(json: &mut Value) -> bool {
+    let mut changed = false;
+    for candidate in json
+        .get_mut("candidates")
+        .and_then(|c| c.as_array_mut())
+        .into_iter()
+        .flatten()
+    {
+        if let Some(parts) = candidate.pointer_mut("/content/parts").and_then(|p| p.as_array_mut()) {
+            let before = parts.len();
+            parts.retain(|p| p.get("thought").and_then(|t| t.as_bool()) != Some(true));
+            changed |= parts.len() != before;
+        }
+    }
+    changed
+}
+
+fn is_thinking_block(block: &Value) -> bool {
+    matches!(
+        block.get("type").and_then(|t| t.as_str()),
+        Some("thinking") | Some("redacted_thinking")
+    )
+}
+
+/// 归一化完整的 JSON 响应 (OpenAI / Anthropic / Gemini), 返回是否有改动
+pub fn normalize_response(mode: ReasoningMode, json: &mut Value) -> bool {
+    if mode == ReasoningMode::Passthrough {
+        return false;
+    }
+    let mut changed = false;
+
+    if let Some(choices) = json.get_mut("choices").and_then(|c| c.as_array_mut()) {
+        for choice in choices {
+            if let Some(message) = choice.get_mut("message").and_then(|m| m.as_object_mut()) {
+                changed |= normalize_openai_message(mode, message, split_think_tags);
+            }
+        }
+    }
+
+    // Anthropic thinking 块本身就是统一格式, 只在 strip 模式下处理
+    if mode == ReasoningMode::Strip {
+        if let Some(blocks) = json.get_mut("content").and_then(|c| c.as_array_mut()) {
+            let before = blocks.len();
+            blocks.retain(|b| !is_thinking_block(b));
+            changed |= blocks.len() != before;
+            for block in blocks.iter_mut().filter(|b| b.get("type").and_then(|t| t.as_str()) == Some("text")) {
+                if let Some(Value::String(text)) = block.get("text") {
+                    let (content, _) = split_think_tags(text);
+                    if content != *text {
+                        block["text"] = Value::String(content);
+                        changed = true;
+                    }
+                }
+            }
+        }
+        changed |= strip_gemini_thoughts(json);
+    }
+    changed
+}
+
+/// 单个流的归一化状态
+#[derive(Debug)]
+pub struct StreamReasoningNormalizer {
+    mode: ReasoningMode,
+    /// 按 OpenAI choice index / Anthropic 块 index 区分的标签拆分状态
+    splitters: HashMap<u64, ThinkTagSplitter>,
+    /// strip 模式下被删除的 Anthropic 块 (按原 index 排序)
+    dropped_blocks: Vec<u64>,
+}
+
+impl StreamReasoningNormalizer {
+    pub fn new(mode: ReasoningMode) -> Self {
+        Self {
+            mode,
+            splitters: HashMap::new(),
+            dropped_blocks: Vec::new(),
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.mode != ReasoningMode::Passthrough
+    }
+
+    /// 改写一个流式事件, 返回 (是否有改动, 是否丢弃该事件)
+    pub fn apply(&mut self, json: &mut Value) -> (bool, bool) {
+        if !self.is_active() {
+            return (false, false);
+        }
+        let mut changed = false;
+
+        if let Some(choices) = json.get_mut("choices").and_then(|c| c.as_array_mut()) {
+            for choice in choices {
+                let index = choice.get("index").and_then(|i| i.as_u64()).unwrap_or(0);
+                let finished = choice.get("finish_reason").map_or(false, |f| !f.is_null());
+                let Some(delta) = choice.get_mut("delta").and_then(|d| d.as_object_mut()) else { continue };
+                let splitter = self.splitters.entry(index).or_default();
+                changed |= normalize_openai_message(self.mode, delta, |text| splitter.push(text));
+
+                // 最后一个 chunk 补上被暂存的标签前缀
+                if finished {
+                    let (content, reasoning) = splitter.finish();
+                    if !content.is_empty() {
+                        let existing = delta.get("content").and_then(|c| c.as_str()).unwrap_or_default();
+                        delta.insert("content".to_string(), Value::String(format!("{}{}", existing, content)));
+                        changed = true;
+                    }
+                    if !reasoning.is_empty() && self.mode != ReasoningMode::Strip {
+                        let existing = delta.get("reasoning_content").and_then(|c| c.as_str()).unwrap_or_default();
+                        delta.insert(
+                            "reasoning_content".to_string(),
+                            Value::String(format!("{}{}", existing, reasoning)),
+                        );
+                        changed = true;
+                    }
+                }
+            }
+            return (changed, false);
+        }
+
+        if self.mode == ReasoningMode::Strip {
+            if json.get("candidates").is_some() {
+                return (strip_gemini_thoughts(json), false);
+            }
+            return self.strip_anthropic_event(json);
+        }
+        (false, false)
+    }
+
+    /// 删除 Anthropic thinking 块, 并把后续块的 index 前移
+    fn strip_anthropic_event(&mut self, json: &mut Value) -> (bool, bool) {
+        let Some(index) = json.get("index").and_then(|i| i.as_u64()) else {
+            return (false, false);
+        };
+        let event_type = json.get("type").and_then(|t| t.as_str()).unwrap_or_default();
+
+        if event_type == "content_block_start" && json.get("content_block").map_or(false, is_thinking_block) {
+            self.dropped_blocks.push(index);
+            return (true, true);
+        }
+        if self.dropped_blocks.contains(&index) {
+            return (true, true);
+        }
+
+        let mut changed = false;
+        if event_type == "content_block_delta"
+            && json.pointer("/delta/type").and_then(|t| t.as_str()) == Some("text_delta")
+        {
+            let text = json.pointer("/delta/text").and_then(|t| t.as_str()).unwrap_or_default().to_string();
+            let (content, _) = self.splitters.entry(index).or_default().push(&text);
+            if content != text {
+                if content.is_empty() {
+                    return (true, true);
+                }
+                json["delta"]["text"] = Value::String(content);
+                changed = true;
+            }
+        }
+
+        let shift = self.dropped_blocks.iter().filter(|&&i| i < index).count() as u64;
+        if shift > 0 {
+            json["index"] = Value::from(index - shift);
+            changed = true;
+        }
+        (changed, false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_think_tag_splitter_across_chunks() {
+        let mut splitter = ThinkTagSplitter::default();
+        let mut content = String::new();
+        let mut reasoning = String::new();
+        for piece in ["<thi", "nk>plan", " it</th", "ink>\n\nAns", "wer <b"] {
+            let (c, r) = splitter.push(piece);
+            content.push_str(&c);
+            reasoning.push_str(&r);
+        }
+        let (c, _) = splitter.finish();
+        content.push_str(&c);
+        assert_eq!(reasoning, "plan it");
+        assert_eq!(content, "Answer <b");
+    }
+
+    #[test]
+    fn test_normalize_openai_response() {
+        let mut resp = json!({"choices": [{"index": 0, "message": {
+            "role": "assistant", "content": "<think>why</think>Because.", "reasoning": "Step 1. "
+        }}]});
+        assert!(normalize_response(ReasoningMode::Normalize, &mut resp));
+        let message = &resp["choices"][0]["message"];
+        assert_eq!(message["content"], "Because.");
+        assert_eq!(message["reasoning_content"], "Step 1. why");
+        assert!(message.get("reasoning").is_none());
+
+        let mut resp = json!({"choices": [{"message": {"content": "Hi", "reasoning_content": "r"}}]});
+        assert!(normalize_response(ReasoningMode::Strip, &mut resp));
+        assert_eq!(resp["choices"][0]["message"], json!({"content": "Hi"}));
+    }
+
+    #[test]
+    fn test_strip_anthropic_response_and_gemini() {
+        let mut resp = json!({"content": [
+            {"type": "thinking", "thinking": "x", "signature": "s"},
+            {"type": "text", "text": "<think>y</think>Done"}
+        ]});
+        assert!(normalize_response(ReasoningMode::Strip, &mut resp));
+        assert_eq!(resp["content"], json!([{"type": "text", "text": "Done"}]));
+
+        let mut gemini = json!({"candidates": [{"content": {"parts": [{"text": "t", "thought": true}, {"text": "a"}]}}]});
+        assert!(normalize_response(ReasoningMode::Strip, &mut gemini));
+        assert_eq!(gemini["candidates"][0]["content"]["parts"], json!([{"text": "a"}]));
+    }
+
+    #[test]
+    fn test_stream_openai_think_tags() {
+        let mut normalizer = StreamReasoningNormalizer::new(ReasoningMode::Normalize);
+        let mut first = json!({"choices": [{"index": 0, "delta": {"content": "<think>hm"}}]});
+        assert_eq!(normalizer.apply(&mut first), (true, false));
+        assert_eq!(first["choices"][0]["delta"]["reasoning_content"], "hm");
+        assert_eq!(first["choices"][0]["delta"]["content"], "");
+
+        let mut second = json!({"choices": [{"index": 0, "delta": {"content": "</think>ok"}, "finish_reason": "stop"}]});
+        normalizer.apply(&mut second);
+        assert_eq!(second["choices"][0]["delta"]["content"], "ok");
+    }
+
+    #[test]
+    fn test_stream_strip_anthropic_thinking_reindexes() {
+        let mut normalizer = StreamReasoningNormalizer::new(ReasoningMode::Strip);
+        let mut start = json!({"type": "content_block_start", "index": 0, "content_block": {"type": "thinking", "thinking": ""}});
+        assert_eq!(normalizer.apply(&mut start), (true, true));
+        let mut delta = json!({"type": "content_block_delta", "index": 0, "delta": {"type": "thinking_delta", "thinking": "x"}});
+        assert_eq!(normalizer.apply(&mut delta), (true, true));
+
+        let mut text = json!({"type": "content_block_delta", "index": 1, "delta": {"type": "text_delta", "text": "Hi"}});
+        assert_eq!(normalizer.apply(&mut text), (true, false));
+        assert_eq!(text["index"], 0);
+
+        let mut stop = json!({"type": "message_stop"});
+        assert_eq!(normalizer.apply(&mut stop), (false, false));
+    }
+}
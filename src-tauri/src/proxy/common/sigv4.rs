@@ -0,0 +1,230 @@
+// AWS Signature Version 4 请求签名
+// 用于 Bedrock 等 AWS 服务, 只实现 Authorization Header 方式 (不支持预签名 URL)。
+
+use sha2::{Digest, Sha256};
+
+/// AWS 访问凭证
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AwsCredentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    /// 临时凭证 (STS) 的会话令牌
+    pub session_token: Option<String>,
+}
+
+const ALGORITHM: &str = "AWS4-HMAC-SHA256";
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> [u8; 32] {
+    const BLOCK_SIZE: usize = 64;
+    let mut block = [0u8; BLOCK_SIZE];
+    if key.len() > BLOCK_SIZE {
+        block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+    let mut ipad = [0x36u8; BLOCK_SIZE];
+    let mut opad = [0x5cu8; BLOCK_SIZE];
+    for i in 0..BLOCK_SIZE {
+        ipad[i] ^= block[i];
+        opad[i] ^= block[i];
+    }
+    let inner = Sha256::new().chain_update(ipad).chain_update(data).finalize();
+    Sha256::new().chain_update(opad).chain_update(inner).finalize().into()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// RFC 3986 编码, 仅保留非保留字符 (`A-Z a-z 0-9 - _ . ~`)
+pub fn uri_encode(input: &str, encode_slash: bool) -> String {
+    let mut out = String::with_capacity(input.len());
+    for b in input.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => out.push(b as char),
+            b'/' if !encode_slash => out.push('/'),
+            _ => out.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    out
+}
+
+/// 还原 URL 路径中的百分号编码
+fn percent_decode(input: &str) -> Vec<u8> {
+    let bytes = input.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|h| std::str::from_utf8(h).ok()).and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(b)) => {
+                out.push(b);
+                i += 3;
+            }
+            (b, _) => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+/// 规范 URI: S3 按解码后的对象路径只编码一次, 其他服务对实际发送的路径再编码一次
+fn canonical_uri(url: &url::Url, service: &str) -> String {
+    let path = url.path();
+    if path.is_empty() {
+        return "/".to_string();
+    }
+    if service == "s3" {
+        return uri_encode(&String::from_utf8_lossy(&percent_decode(path)), false);
+    }
+    uri_encode(path, false)
+}
+
+fn canonical_query(url: &url::Url) -> String {
+    let mut pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .map(|(k, v)| (uri_encode(&k, true), uri_encode(&v, true)))
+        .collect();
+    pairs.sort();
+    pairs
+        .into_iter()
+        .map(|(k, v)| format!("{}={}", k, v))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+fn host_header(url: &url::Url) -> String {
+    let host = url.host_str().unwrap_or_default();
+    match url.port() {
+        Some(port) => format!("{}:{}", host, port),
+        None => host.to_string(),
+    }
+}
+
+/// 某个区域与服务的签名器
+pub struct AwsSigner<'a> {
+    pub credentials: &'a AwsCredentials,
+    pub region: &'a str,
+    pub service: &'a str,
+}
+
+impl AwsSigner<'_> {
+    /// 计算签名, 返回需要附加到请求上的 Header (`x-amz-date`, `x-amz-security-token`, `authorization`)。
+    /// `headers` 中的 Header 全部参与签名, `host` 由 URL 自动补齐。
+    pub fn sign(
+        &self,
+        method: &str,
+        url: &url::Url,
+        headers: &[(&str, &str)],
+        body: &[u8],
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Vec<(String, String)> {
+        let (credentials, region, service) = (self.credentials, self.region, self.service);
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+
+        let mut signed: Vec<(String, String)> = headers
+            .iter()
+            .map(|(k, v)| (k.to_ascii_lowercase(), v.trim().to_string()))
+            .collect();
+        if !signed.iter().any(|(k, _)| k == "host") {
+            signed.push(("host".to_string(), host_header(url)));
+        }
+        let mut extra = vec![("x-amz-date".to_string(), amz_date.clone())];
+        if let Some(token) = credentials.session_token.as_ref().filter(|t| !t.is_empty()) {
+            extra.push(("x-amz-security-token".to_string(), token.clone()));
+        }
+        signed.extend(extra.iter().cloned());
+        signed.sort();
+
+        let canonical_headers: String = signed.iter().map(|(k, v)| format!("{}:{}\n", k, v)).collect();
+        let signed_headers = signed.iter().map(|(k, _)| k.as_str()).collect::<Vec<_>>().join(";");
+        let canonical_request = format!(
+            "{}\n{}\n{}\n{}\n{}\n{}",
+            method,
+            canonical_uri(url, service),
+            canonical_query(url),
+            canonical_headers,
+            signed_headers,
+            hex(&Sha256::digest(body)),
+        );
+
+        let scope = format!("{}/{}/{}/aws4_request", date, region, service);
+        let string_to_sign = format!(
+            "{}\n{}\n{}\n{}",
+            ALGORITHM,
+            amz_date,
+            scope,
+            hex(&Sha256::digest(canonical_request.as_bytes()))
+        );
+
+        let k_date = hmac_sha256(format!("AWS4{}", credentials.secret_access_key).as_bytes(), date.as_bytes());
+        let k_region = hmac_sha256(&k_date, region.as_bytes());
+        let k_service = hmac_sha256(&k_region, service.as_bytes());
+        let k_signing = hmac_sha256(&k_service, b"aws4_request");
+        let signature = hex(&hmac_sha256(&k_signing, string_to_sign.as_bytes()));
+
+        extra.push((
+            "authorization".to_string(),
+            format!(
+                "{} Credential={}/{}, SignedHeaders={}, Signature={}",
+                ALGORITHM, credentials.access_key_id, scope, signed_headers, signature
+            ),
+        ));
+        extra
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_hmac_sha256_rfc4231() {
+        let mac = hmac_sha256(b"Jefe", b"what do ya want for nothing?");
+        assert_eq!(hex(&mac), "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843");
+    }
+
+    #[test]
+    fn test_sign_matches_aws_example() {
+        // AWS 文档中的 IAM ListUsers 签名示例
+        let url = url::Url::parse("https://iam.amazonaws.com/?Action=ListUsers&Version=2010-05-08").unwrap();
+        let credentials = AwsCredentials {
+            access_key_id: "AKIDEXAMPLE".to_string(),
+            secret_access_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".to_string(),
+            session_token: None,
+        };
+        let now = chrono::Utc.with_ymd_and_hms(2015, 8, 30, 12, 36, 0).unwrap();
+        let signer = AwsSigner { credentials: &credentials, region: "us-east-1", service: "iam" };
+        let headers = signer.sign(
+            "GET",
+            &url,
+            &[("Content-Type", "application/x-www-form-urlencoded; charset=utf-8")],
+            b"",
+            now,
+        );
+        let auth = headers.iter().find(|(k, _)| k == "authorization").unwrap();
+        assert_eq!(
+            auth.1,
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/iam/aws4_request, \
+             SignedHeaders=content-type;host;x-amz-date, \
+             Signature=5d672d79c15b13162d9279b0855cfba6789a8edb4c82c400e06b5924a6f2b5d7"
+        );
+        assert!(headers.iter().any(|(k, v)| k == "x-amz-date" && v == "20150830T123600Z"));
+    }
+
+    #[test]
+    fn test_canonical_uri_double_encodes() {
+        let url = url::Url::parse("https://bedrock-runtime.us-east-1.amazonaws.com/model/a.b-v1%3A0/converse").unwrap();
+        assert_eq!(canonical_uri(&url, "bedrock"), "/model/a.b-v1%253A0/converse");
+    }
+
+    #[test]
+    fn test_canonical_uri_s3_encodes_once() {
+        let url = url::Url::parse("https://s3.example.com/bucket/sync/my%20config:1.json").unwrap();
+        assert_eq!(canonical_uri(&url, "s3"), "/bucket/sync/my%20config%3A1.json");
+    }
+}
@@ -0,0 +1,181 @@
+// 增量 SSE 解析器
+// 按字节缓冲, 只在完整行上解码 UTF-8, 只在空行处分发事件,
+// 因此事件或多字节字符被拆分到多个网络 chunk 时也能正确解析。
+
+/// 一个完整的 SSE 事件
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SseEvent {
+    /// `event:` 字段, 未指定时为空 (即默认的 "message")
+    pub event: String,
+    /// 所有 `data:` 行按 `\n` 拼接后的内容
+    pub data: String,
+    /// `id:` 字段
+    pub id: Option<String>,
+}
+
+impl SseEvent {
+    /// 是否为 OpenAI 风格的流结束标记
+    pub fn is_done(&self) -> bool {
+        self.data.trim() == "[DONE]"
+    }
+}
+
+/// Buffered, event-boundary aware SSE parser
+#[derive(Debug, Default)]
+pub struct SseParser {
+    buffer: Vec<u8>,
+    event: String,
+    data: Vec<String>,
+    id: Option<String>,
+    /// 上一个 chunk 以 `\r` 结尾, 下一个 chunk 开头的 `\n` 属于同一个换行
+    pending_cr: bool,
+}
+
+impl SseParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 追加一个网络 chunk, 返回其中已完整的事件
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        let mut chunk = chunk;
+        if self.pending_cr {
+            self.pending_cr = false;
+            if chunk.first() == Some(&b'\n') {
+                chunk = &chunk[1..];
+            }
+        }
+        self.buffer.extend_from_slice(chunk);
+
+        let mut events = Vec::new();
+        let mut start = 0;
+        let mut i = 0;
+        while i < self.buffer.len() {
+            match self.buffer[i] {
+                b'\n' => {
+                    let line = self.buffer[start..i].to_vec();
+                    self.process_line(&line, &mut events);
+                    start = i + 1;
+                }
+                b'\r' => {
+                    let line = self.buffer[start..i].to_vec();
+                    self.process_line(&line, &mut events);
+                    if i + 1 < self.buffer.len() {
+                        if self.buffer[i + 1] == b'\n' {
+                            i += 1;
+                        }
+                    } else {
+                        self.pending_cr = true;
+                    }
+                    start = i + 1;
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+        self.buffer.drain(..start);
+        events
+    }
+
+    /// 流结束: 分发尚未以空行结束的最后一个事件
+    pub fn finish(&mut self) -> Vec<SseEvent> {
+        let mut events = Vec::new();
+        if !self.buffer.is_empty() {
+            let line = std::mem::take(&mut self.buffer);
+            self.process_line(&line, &mut events);
+        }
+        self.dispatch(&mut events);
+        events
+    }
+
+    fn process_line(&mut self, line: &[u8], events: &mut Vec<SseEvent>) {
+        if line.is_empty() {
+            self.dispatch(events);
+            return;
+        }
+
+        let line = String::from_utf8_lossy(line);
+        // `:` 开头为注释 (心跳)
+        if line.starts_with(':') {
+            return;
+        }
+
+        let (field, value) = match line.find(':') {
+            Some(pos) => {
+                let value = &line[pos + 1..];
+                (&line[..pos], value.strip_prefix(' ').unwrap_or(value))
+            }
+            None => (line.as_ref(), ""),
+        };
+
+        match field {
+            "event" => self.event = value.to_string(),
+            "data" => self.data.push(value.to_string()),
+            "id" => self.id = Some(value.to_string()),
+            _ => {}
+        }
+    }
+
+    fn dispatch(&mut self, events: &mut Vec<SseEvent>) {
+        if self.data.is_empty() {
+            // 没有 data 的事件按规范丢弃
+            self.event.clear();
+            return;
+        }
+        events.push(SseEvent {
+            event: std::mem::take(&mut self.event),
+            data: std::mem::take(&mut self.data).join("\n"),
+            id: self.id.clone(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_split_across_chunks() {
+        let mut parser = SseParser::new();
+        assert!(parser.push(b"event: message_start\nda").is_empty());
+        assert!(parser.push(b"ta: {\"a\":").is_empty());
+        let events = parser.push(b"1}\n\ndata: [DONE]\n\n");
+
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].event, "message_start");
+        assert_eq!(events[0].data, "{\"a\":1}");
+        assert!(events[1].is_done());
+    }
+
+    #[test]
+    fn test_multibyte_utf8_split_at_boundary() {
+        let payload = "data: {\"text\":\"你好\"}\n\n".as_bytes();
+        // 在 "你" 的 3 字节编码中间拆分
+        let split = payload.iter().position(|&b| b >= 0x80).unwrap() + 1;
+
+        let mut parser = SseParser::new();
+        assert!(parser.push(&payload[..split]).is_empty());
+        let events = parser.push(&payload[split..]);
+        assert_eq!(events[0].data, "{\"text\":\"你好\"}");
+    }
+
+    #[test]
+    fn test_crlf_comments_and_multiline_data() {
+        let mut parser = SseParser::new();
+        let mut events = parser.push(b": ping\r\ndata: line1\r");
+        events.extend(parser.push(b"\ndata: line2\r\n\r\n"));
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].data, "line1\nline2");
+    }
+
+    #[test]
+    fn test_finish_flushes_unterminated_event() {
+        let mut parser = SseParser::new();
+        assert!(parser.push(b"data: tail").is_empty());
+        let events = parser.finish();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].data, "tail");
+        assert!(parser.finish().is_empty());
+    }
+}
@@ -0,0 +1,102 @@
+// 流式响应转发
+// 中间件在后台任务中边读取内层响应边转发给客户端。客户端断开后接收端关闭, 等待中的读取立即结束,
+// 任务丢弃内层流, 逐层关闭直到上游的 reqwest 连接, 上游不再继续生成 (和计费)。
+// 透传上游响应体时, SSE 中途出错以一个 OpenAI 格式的错误事件结束, 不会把错误文本混入事件流。
+
+use bytes::Bytes;
+use futures::{Stream, StreamExt};
+use serde_json::json;
+use tokio::sync::mpsc::Sender;
+
+/// 客户端已断开
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientGone;
+
+/// 读取内层流的下一个数据块; 等待期间客户端断开时返回 `Err(ClientGone)`
+pub async fn next_chunk<S, T>(stream: &mut S, tx: &Sender<T>) -> Result<Option<S::Item>, ClientGone>
+where
+    S: Stream + Unpin,
+{
+    tokio::select! {
+        biased;
+        _ = tx.closed() => Err(ClientGone),
+        item = stream.next() => Ok(item),
+    }
+}
+
+pub fn is_event_stream(headers: &axum::http::HeaderMap) -> bool {
+    headers
+        .get(axum::http::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|ct| ct.contains("text/event-stream"))
+}
+
+/// 上游流中途出错时发给客户端的 SSE 错误事件
+pub fn error_event(message: &str) -> Bytes {
+    let body = json!({ "error": { "message": message, "type": "upstream_error", "code": "stream_error" } });
+    Bytes::from(format!("data: {}\n\n", body))
+}
+
+/// 原样透传上游响应体。SSE 读取出错时追加错误事件并结束流; 其他响应返回错误, 中断响应体
+pub fn relay_upstream<S, E>(upstream: S, sse: bool, label: &'static str) -> impl Stream<Item = Result<Bytes, std::io::Error>>
+where
+    S: Stream<Item = Result<Bytes, E>>,
+    E: std::fmt::Display,
+{
+    async_stream::stream! {
+        futures::pin_mut!(upstream);
+        while let Some(chunk) = upstream.next().await {
+            match chunk {
+                Ok(bytes) => yield Ok(bytes),
+                Err(e) => {
+                    tracing::warn!("[{}] Upstream stream error: {}", label, e);
+                    if sse {
+                        yield Ok(error_event(&format!("Upstream stream error: {}", e)));
+                    } else {
+                        yield Err(std::io::Error::other(e.to_string()));
+                    }
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_next_chunk_stops_when_client_gone() {
+        let (tx, rx) = tokio::sync::mpsc::channel::<u8>(1);
+        let mut ready = futures::stream::iter(vec![1u8]);
+        assert_eq!(next_chunk(&mut ready, &tx).await, Ok(Some(1)));
+        assert_eq!(next_chunk(&mut ready, &tx).await, Ok(None));
+
+        // 上游迟迟没有数据时, 客户端断开立即结束等待
+        let mut pending = futures::stream::pending::<u8>();
+        drop(rx);
+        assert_eq!(next_chunk(&mut pending, &tx).await, Err(ClientGone));
+    }
+
+    #[tokio::test]
+    async fn test_relay_upstream_ends_with_error_event() {
+        let upstream = || {
+            futures::stream::iter(vec![
+                Ok(Bytes::from("data: {\"a\":1}\n\n")),
+                Err("connection reset"),
+                Ok(Bytes::from("data: never\n\n")),
+            ])
+        };
+        let chunks: Vec<_> = relay_upstream(upstream(), true, "test").collect().await;
+        assert_eq!(chunks.len(), 2);
+        let event = String::from_utf8(chunks[1].as_ref().unwrap().to_vec()).unwrap();
+        let data: serde_json::Value = serde_json::from_str(event.strip_prefix("data: ").unwrap().trim()).unwrap();
+        assert_eq!(data["error"]["message"], "Upstream stream error: connection reset");
+
+        // 非 SSE 响应直接中断
+        let chunks: Vec<_> = relay_upstream(upstream(), false, "test").collect().await;
+        assert_eq!(chunks.len(), 2);
+        assert!(chunks[1].is_err());
+    }
+}
@@ -0,0 +1,145 @@
+// 系统提示词注入
+// 按请求路径和虚拟 Key 匹配规则, 把配置的提示词写入各协议的系统提示词位置:
+// Anthropic `system`、Gemini `systemInstruction`、Responses `instructions`、OpenAI Chat 的 system 消息。
+
+use serde_json::{json, Value};
+
+use crate::proxy::common::model_mapping::wildcard_match;
+use crate::proxy::config::{SystemPromptMode, SystemPromptRule};
+
+/// 规则是否作用于该请求; `virtual_key` 为 (id, 名称)
+pub fn rule_matches(rule: &SystemPromptRule, path: &str, virtual_key: Option<(&str, &str)>) -> bool {
+    if !rule.enabled || rule.prompt.trim().is_empty() {
+        return false;
+    }
+    let path_ok = rule.paths.is_empty() || rule.paths.iter().any(|p| wildcard_match(p.trim(), path));
+    let key_ok = rule.virtual_keys.is_empty()
+        || virtual_key.is_some_and(|(id, name)| rule.virtual_keys.iter().any(|k| k.trim() == id || k.trim() == name));
+    path_ok && key_ok
+}
+
+fn combine(existing: &str, prompt: &str, mode: SystemPromptMode) -> String {
+    match mode {
+        _ if existing.trim().is_empty() => prompt.to_string(),
+        SystemPromptMode::Prefix => format!("{}\n\n{}", prompt, existing),
+        SystemPromptMode::Suffix => format!("{}\n\n{}", existing, prompt),
+        SystemPromptMode::Replace => prompt.to_string(),
+    }
+}
+
+/// 文本或内容块数组形式的系统提示词; 缺失时写入纯文本
+fn apply_to_slot(slot: &mut Value, prompt: &str, mode: SystemPromptMode, block: Value) {
+    match slot {
+        Value::Array(blocks) if mode != SystemPromptMode::Replace => match mode {
+            SystemPromptMode::Prefix => blocks.insert(0, block),
+            _ => blocks.push(block),
+        },
+        Value::String(existing) => *existing = combine(existing, prompt, mode),
+        _ => *slot = Value::String(prompt.to_string()),
+    }
+}
+
+/// 把提示词写入请求体, 返回是否识别出了请求格式
+pub fn inject(path: &str, body: &mut Value, prompt: &str, mode: SystemPromptMode) -> bool {
+    let Some(obj) = body.as_object_mut() else {
+        return false;
+    };
+    if path.starts_with("/v1/messages") {
+        let slot = obj.entry("system").or_insert(Value::Null);
+        apply_to_slot(slot, prompt, mode, json!({ "type": "text", "text": prompt }));
+        return true;
+    }
+    if obj.contains_key("contents") {
+        let key = if obj.contains_key("system_instruction") { "system_instruction" } else { "systemInstruction" };
+        let instruction = obj.entry(key).or_insert_with(|| json!({ "parts": [] }));
+        match instruction.get_mut("parts").and_then(|p| p.as_array_mut()) {
+            Some(parts) if mode != SystemPromptMode::Replace => {
+                let part = json!({ "text": prompt });
+                if mode == SystemPromptMode::Prefix {
+                    parts.insert(0, part);
+                } else {
+                    parts.push(part);
+                }
+            }
+            _ => *instruction = json!({ "parts": [{ "text": prompt }] }),
+        }
+        return true;
+    }
+    if let Some(messages) = obj.get_mut("messages").and_then(|m| m.as_array_mut()) {
+        let leading = messages
+            .iter()
+            .take_while(|m| matches!(m.get("role").and_then(|r| r.as_str()), Some("system" | "developer")))
+            .count();
+        if leading == 0 {
+            messages.insert(0, json!({ "role": "system", "content": prompt }));
+            return true;
+        }
+        if mode == SystemPromptMode::Replace {
+            messages.splice(..leading, [json!({ "role": "system", "content": prompt })]);
+            return true;
+        }
+        let index = if mode == SystemPromptMode::Prefix { 0 } else { leading - 1 };
+        let slot = messages[index].as_object_mut().map(|m| m.entry("content").or_insert(Value::Null));
+        if let Some(slot) = slot {
+            apply_to_slot(slot, prompt, mode, json!({ "type": "text", "text": prompt }));
+        }
+        return true;
+    }
+    if path.ends_with("/responses") {
+        let slot = obj.entry("instructions").or_insert(Value::Null);
+        apply_to_slot(slot, prompt, mode, Value::Null);
+        return true;
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(mode: SystemPromptMode) -> SystemPromptRule {
+        SystemPromptRule {
+            name: "org".to_string(),
+            prompt: "Answer in English.".to_string(),
+            mode,
+            paths: vec!["/v1/chat/*".to_string()],
+            virtual_keys: vec!["team-a".to_string()],
+            enabled: true,
+        }
+    }
+
+    #[test]
+    fn test_rule_matching() {
+        let r = rule(SystemPromptMode::Prefix);
+        assert!(rule_matches(&r, "/v1/chat/completions", Some(("vk_1", "team-a"))));
+        assert!(rule_matches(&r, "/v1/chat/completions", Some(("team-a", "other"))));
+        assert!(!rule_matches(&r, "/v1/chat/completions", None));
+        assert!(!rule_matches(&r, "/v1/messages", Some(("vk_1", "team-a"))));
+        assert!(rule_matches(&SystemPromptRule { paths: vec![], virtual_keys: vec![], ..r }, "/v1/messages", None));
+    }
+
+    #[test]
+    fn test_inject_per_protocol() {
+        let prompt = "Answer in English.";
+        let mut openai = json!({"messages": [{"role": "system", "content": "Be brief."}, {"role": "user", "content": "hi"}]});
+        assert!(inject("/v1/chat/completions", &mut openai, prompt, SystemPromptMode::Prefix));
+        assert_eq!(openai["messages"][0]["content"], "Answer in English.\n\nBe brief.");
+
+        let mut openai = json!({"messages": [{"role": "user", "content": "hi"}]});
+        inject("/v1/chat/completions", &mut openai, prompt, SystemPromptMode::Suffix);
+        assert_eq!(openai["messages"][0], json!({"role": "system", "content": prompt}));
+
+        let mut claude = json!({"system": [{"type": "text", "text": "Be brief."}], "messages": []});
+        inject("/v1/messages", &mut claude, prompt, SystemPromptMode::Suffix);
+        assert_eq!(claude["system"][1]["text"], prompt);
+
+        let mut gemini = json!({"contents": [], "systemInstruction": {"parts": [{"text": "Be brief."}]}});
+        inject("/v1beta/models/gemini-2.5-pro:generateContent", &mut gemini, prompt, SystemPromptMode::Replace);
+        assert_eq!(gemini["systemInstruction"], json!({"parts": [{"text": prompt}]}));
+
+        let mut responses = json!({"input": "hi", "instructions": "Be brief."});
+        inject("/v1/responses", &mut responses, prompt, SystemPromptMode::Suffix);
+        assert_eq!(responses["instructions"], "Be brief.\n\nAnswer in English.");
+        assert!(!inject("/v1/embeddings", &mut json!({"model": "x"}), prompt, SystemPromptMode::Prefix));
+    }
+}
@@ -0,0 +1,150 @@
+// 监听证书加载与有效期
+// 只解析 PEM 中第一张证书的 notAfter, 用于到期提醒; 证书本身的校验由 native-tls 完成。
+// 启用 HTTPS 的监听共用同一份证书, 保存配置时重新加载, 新连接使用新证书 (HTTPS 的开关仍需重启生效)。
+
+use std::sync::RwLock;
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use tokio_native_tls::TlsAcceptor;
+
+use crate::proxy::config::ListenerTlsConfig;
+
+const PEM_BEGIN: &str = "-----BEGIN CERTIFICATE-----";
+const PEM_END: &str = "-----END CERTIFICATE-----";
+
+/// 读取一个 DER TLV, 返回 (tag, 内容, 剩余部分)
+fn read_tlv(input: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, rest) = input.split_first()?;
+    let (&first, rest) = rest.split_first()?;
+    let (len, rest) = if first < 0x80 {
+        (first as usize, rest)
+    } else {
+        let n = (first & 0x7f) as usize;
+        if n == 0 || n > 4 || rest.len() < n {
+            return None;
+        }
+        let len = rest[..n].iter().fold(0usize, |acc, b| (acc << 8) | *b as usize);
+        (len, &rest[n..])
+    };
+    (rest.len() >= len).then(|| (tag, &rest[..len], &rest[len..]))
+}
+
+/// UTCTime (0x17, YYMMDDHHMMSSZ) 或 GeneralizedTime (0x18, YYYYMMDDHHMMSSZ) 转 Unix 秒
+fn parse_time(tag: u8, value: &str) -> Option<i64> {
+    let value = value.strip_suffix('Z')?;
+    let full = match tag {
+        0x17 => {
+            let year: u32 = value.get(..2)?.parse().ok()?;
+            format!("{}{}", if year >= 50 { 19 } else { 20 }, value)
+        }
+        0x18 => value.to_string(),
+        _ => return None,
+    };
+    chrono::NaiveDateTime::parse_from_str(&full, "%Y%m%d%H%M%S").ok().map(|t| t.and_utc().timestamp())
+}
+
+/// Certificate -> TBSCertificate -> [version] serialNumber signature issuer validity
+fn der_not_after(der: &[u8]) -> Option<i64> {
+    let (_, cert, _) = read_tlv(der)?;
+    let (_, tbs, _) = read_tlv(cert)?;
+    let rest = match read_tlv(tbs)? {
+        (0xa0, _, rest) => rest,
+        _ => tbs,
+    };
+    let (_, _, rest) = read_tlv(rest)?;
+    let (_, _, rest) = read_tlv(rest)?;
+    let (_, _, rest) = read_tlv(rest)?;
+    let (_, validity, _) = read_tlv(rest)?;
+    let (_, _, validity) = read_tlv(validity)?;
+    let (tag, time, _) = read_tlv(validity)?;
+    parse_time(tag, std::str::from_utf8(time).ok()?)
+}
+
+/// 读取证书与私钥 (忽略 enabled, 由调用方决定是否启用)
+pub fn load_acceptor(config: &ListenerTlsConfig) -> Result<TlsAcceptor, String> {
+    let cert = std::fs::read(config.cert_path.trim())
+        .map_err(|e| format!("读取 TLS 证书 {} 失败: {}", config.cert_path, e))?;
+    let key = std::fs::read(config.key_path.trim())
+        .map_err(|e| format!("读取 TLS 私钥 {} 失败: {}", config.key_path, e))?;
+    let identity = native_tls::Identity::from_pkcs8(&cert, &key).map_err(|e| format!("TLS 证书无效: {}", e))?;
+    let acceptor = native_tls::TlsAcceptor::new(identity).map_err(|e| format!("TLS 初始化失败: {}", e))?;
+    Ok(TlsAcceptor::from(acceptor))
+}
+
+/// 监听共用的证书; 未加载时表示服务没有启用 HTTPS 的监听
+#[derive(Default)]
+pub struct ListenerCerts {
+    acceptor: RwLock<Option<TlsAcceptor>>,
+}
+
+impl ListenerCerts {
+    /// 启动时加载证书
+    pub fn load(&self, config: &ListenerTlsConfig) -> Result<(), String> {
+        let acceptor = load_acceptor(config)?;
+        *self.acceptor.write().unwrap_or_else(|e| e.into_inner()) = Some(acceptor);
+        Ok(())
+    }
+
+    /// 重新读取证书; 失败时保留原证书并返回错误, 没有 HTTPS 监听时不做处理
+    pub fn reload(&self, config: &ListenerTlsConfig) -> Result<(), String> {
+        if self.current().is_none() {
+            return Ok(());
+        }
+        self.load(config)
+    }
+
+    pub fn current(&self) -> Option<TlsAcceptor> {
+        self.acceptor.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+/// 证书链 (PEM) 中第一张证书的到期时间 (Unix 秒)
+pub fn not_after(pem: &str) -> Result<i64, String> {
+    let start = pem.find(PEM_BEGIN).ok_or("No PEM certificate found")? + PEM_BEGIN.len();
+    let end = pem[start..].find(PEM_END).ok_or("Unterminated PEM certificate")? + start;
+    let body: String = pem[start..end].chars().filter(|c| !c.is_whitespace()).collect();
+    let der = STANDARD.decode(body).map_err(|e| format!("Invalid PEM certificate: {}", e))?;
+    der_not_after(&der).ok_or_else(|| "Invalid certificate validity".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CERT: &str = "-----BEGIN CERTIFICATE-----
+MIIBfTCCASOgAwIBAgIUWLhHGMbK81yu/zAEGx2HeogVAoowCgYIKoZIzj0EAwIw
+FDESMBAGA1UEAwwJbG9jYWxob3N0MB4XDTI2MTAxNDEwMTkxM1oXDTI2MTExMzEw
+MTkxM1owFDESMBAGA1UEAwwJbG9jYWxob3N0MFkwEwYHKoZIzj0CAQYIKoZIzj0D
+AQcDQgAECrRG7YiODEFZqoWLhAv3RDiH7AL3StmLFeBbLaIluiLMNwIVti6/ZBk8
+eIkytSvylC7IQm27Vm9B7I8VmLMW2aNTMFEwHQYDVR0OBBYEFKAM8TRcAJhpdXRn
+zSwxRmbbSE4TMB8GA1UdIwQYMBaAFKAM8TRcAJhpdXRnzSwxRmbbSE4TMA8GA1Ud
+EwEB/wQFMAMBAf8wCgYIKoZIzj0EAwIDSAAwRQIhAOw5toFzrcwF717pxAMMdmYQ
+D/cZrcv0v5KvL9nxzV2yAiAS0JVvpB5nv0oCMBCq+LMDU/qDBGraHe+GnlUz3fRN
+SA==
+-----END CERTIFICATE-----
+";
+
+    #[test]
+    fn test_listener_certs_reload() {
+        let missing = ListenerTlsConfig {
+            enabled: true,
+            cert_path: "/nonexistent/cert.pem".to_string(),
+            key_path: "/nonexistent/key.pem".to_string(),
+        };
+        let certs = ListenerCerts::default();
+        // 没有 HTTPS 监听时保存配置不加载证书
+        assert!(certs.reload(&missing).is_ok());
+        assert!(certs.load(&missing).unwrap_err().contains("/nonexistent/cert.pem"));
+        assert!(certs.current().is_none());
+    }
+
+    #[test]
+    fn test_certificate_not_after() {
+        // notAfter=Nov 13 10:19:13 2026 GMT
+        assert_eq!(not_after(CERT), Ok(1_794_565_153));
+        assert_eq!(parse_time(0x18, "20500101000000Z"), Some(2_524_608_000));
+        assert_eq!(parse_time(0x17, "991231235959Z"), Some(946_684_799));
+        assert!(not_after("not a certificate").is_err());
+        assert!(not_after(&CERT.replace("MIIBfTCC", "MIIBfTDD")).is_err());
+    }
+}
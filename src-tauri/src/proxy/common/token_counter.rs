@@ -0,0 +1,143 @@
+// 本地 token 计数 (tiktoken)
+// OpenAI 模型使用其实际的编码; Claude / Gemini 等没有公开词表的模型使用 cl100k_base 近似。
+// 请求计数覆盖所有协议: 累加文本字段, 加上每条消息的固定开销和工具定义。
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use serde_json::Value;
+use tiktoken_rs::tokenizer::{get_tokenizer, Tokenizer};
+use tiktoken_rs::CoreBPE;
+
+use crate::proxy::common::content_filter::visit_text_fields;
+
+static CL100K: Lazy<Option<CoreBPE>> = Lazy::new(|| tiktoken_rs::cl100k_base().ok());
+static O200K: Lazy<Option<CoreBPE>> = Lazy::new(|| tiktoken_rs::o200k_base().ok());
+
+/// 每条消息的格式开销 (角色与分隔符) 以及回复前缀, 与 OpenAI cookbook 一致
+pub(crate) const TOKENS_PER_MESSAGE: u32 = 3;
+const REPLY_PRIMING_TOKENS: u32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum TokenEncoding {
+    #[serde(rename = "cl100k_base")]
+    Cl100k,
+    #[serde(rename = "o200k_base")]
+    O200k,
+}
+
+impl TokenEncoding {
+    pub fn for_model(model: &str) -> Self {
+        let model = model.rsplit('/').next().unwrap_or(model); // openai/gpt-4o
+        match get_tokenizer(model) {
+            Some(Tokenizer::O200kBase) => TokenEncoding::O200k,
+            _ => TokenEncoding::Cl100k,
+        }
+    }
+
+    fn bpe(self) -> Option<&'static CoreBPE> {
+        match self {
+            TokenEncoding::Cl100k => CL100K.as_ref(),
+            TokenEncoding::O200k => O200K.as_ref(),
+        }
+    }
+}
+
+/// 一次计数的结果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TokenCount {
+    pub model: String,
+    pub encoding: TokenEncoding,
+    pub input_tokens: u32,
+}
+
+pub fn count_text(encoding: TokenEncoding, text: &str) -> u32 {
+    if text.is_empty() {
+        return 0;
+    }
+    match encoding.bpe() {
+        Some(bpe) => bpe.encode_ordinary(text).len() as u32,
+        // 词表加载失败时退回字符估算
+        None => crate::proxy::mappers::context_manager::estimate_tokens_from_str(text),
+    }
+}
+
+/// JSON 中所有文本字段的 token 数
+pub fn count_value_text(encoding: TokenEncoding, value: &Value) -> u32 {
+    let mut value = value.clone();
+    let mut total = 0u32;
+    visit_text_fields(&mut value, &mut |_, text| total = total.saturating_add(count_text(encoding, text)));
+    total
+}
+
+/// 估算请求的 prompt token 数 (OpenAI / Anthropic / Gemini 请求体)
+pub fn count_request(model: &str, body: &Value) -> TokenCount {
+    let encoding = TokenEncoding::for_model(model);
+    let mut total = count_value_text(encoding, body);
+
+    let messages = ["messages", "contents", "input"]
+        .iter()
+        .filter_map(|key| body.get(*key).and_then(|v| v.as_array()))
+        .map(|items| items.len() as u32)
+        .sum::<u32>();
+    if messages > 0 {
+        total = total.saturating_add(messages * TOKENS_PER_MESSAGE + REPLY_PRIMING_TOKENS);
+    }
+    // 工具定义按 JSON 原文计数
+    for key in ["tools", "functions"] {
+        if let Some(tools) = body.get(key).filter(|t| !t.is_null()) {
+            total = total.saturating_add(count_text(encoding, &tools.to_string()));
+        }
+    }
+    TokenCount {
+        model: model.to_string(),
+        encoding,
+        input_tokens: total,
+    }
+}
+
+/// 上游没有返回 usage 时估算 (prompt, completion); 请求或响应不是 JSON 时对应项为 None
+pub fn estimate_usage(model: &str, request_body: Option<&str>, response_body: Option<&str>) -> (Option<u32>, Option<u32>) {
+    let parse = |body: Option<&str>| body.and_then(|b| serde_json::from_str::<Value>(b).ok());
+    let prompt = parse(request_body).map(|body| count_request(model, &body).input_tokens);
+    let completion = parse(response_body).map(|body| count_value_text(TokenEncoding::for_model(model), &body));
+    (prompt, completion)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_encoding_and_text_count() {
+        assert_eq!(TokenEncoding::for_model("gpt-4o-mini"), TokenEncoding::O200k);
+        assert_eq!(TokenEncoding::for_model("openai/gpt-4o"), TokenEncoding::O200k);
+        assert_eq!(TokenEncoding::for_model("gpt-4-turbo"), TokenEncoding::Cl100k);
+        assert_eq!(TokenEncoding::for_model("claude-sonnet-4-5"), TokenEncoding::Cl100k);
+        assert_eq!(count_text(TokenEncoding::Cl100k, "hello world"), 2);
+        assert_eq!(count_text(TokenEncoding::O200k, ""), 0);
+    }
+
+    #[test]
+    fn test_count_request_and_estimate_usage() {
+        let body = json!({
+            "model": "gpt-4",
+            "messages": [
+                {"role": "system", "content": "You are a helpful assistant."},
+                {"role": "user", "content": "hello world"}
+            ]
+        });
+        // 6 + 2 文本 token, 2 条消息 * 3 + 3
+        let count = count_request("gpt-4", &body);
+        assert_eq!(count.input_tokens, 17);
+        assert_eq!(count.encoding, TokenEncoding::Cl100k);
+
+        let tools = json!({"messages": [], "tools": [{"type": "function", "function": {"name": "get_weather"}}]});
+        assert!(count_request("gpt-4", &tools).input_tokens > 0);
+
+        let response = json!({"choices": [{"message": {"role": "assistant", "content": "hello world"}}]}).to_string();
+        let (prompt, completion) = estimate_usage("gpt-4", Some(&body.to_string()), Some(&response));
+        assert_eq!((prompt, completion), (Some(17), Some(2)));
+        assert_eq!(estimate_usage("gpt-4", Some("[Binary Request Data]"), None), (None, None));
+    }
+}
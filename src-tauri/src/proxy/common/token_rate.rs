@@ -0,0 +1,141 @@
+// 流式响应的输出速率 (tokens/s)
+// 逐个 SSE 事件提取增量文本 (OpenAI / Anthropic / Gemini 格式) 并用本地词表计数,
+// 速率 = 首个增量之后生成的 token 数 / 首个增量到最后一个增量的时间, 不含排队与首 Token 等待。
+
+use std::time::Instant;
+
+use serde_json::Value;
+
+use crate::proxy::common::sse::SseParser;
+use crate::proxy::common::token_counter::{count_text, TokenEncoding};
+
+/// 生成时间过短时速率没有意义 (例如上游一次性返回全部内容)
+const MIN_GENERATION_MS: u128 = 50;
+
+/// 一个 SSE 事件中的增量文本 (正文与思考内容)
+fn delta_text(data: &Value) -> String {
+    let mut text = String::new();
+    let mut push = |value: Option<&Value>| {
+        if let Some(s) = value.and_then(Value::as_str) {
+            text.push_str(s);
+        }
+    };
+    // OpenAI Chat Completions
+    for choice in data.get("choices").and_then(Value::as_array).into_iter().flatten() {
+        let delta = choice.get("delta");
+        push(delta.and_then(|d| d.get("content")));
+        push(delta.and_then(|d| d.get("reasoning_content")));
+    }
+    // Anthropic content_block_delta / OpenAI Responses 的 *.delta 事件
+    match data.get("delta") {
+        Some(Value::String(_)) => push(data.get("delta")),
+        Some(delta) => {
+            push(delta.get("text"));
+            push(delta.get("thinking"));
+        }
+        None => {}
+    }
+    // Gemini (v1internal 包在 response 中)
+    let gemini = data.get("response").unwrap_or(data);
+    for candidate in gemini.get("candidates").and_then(Value::as_array).into_iter().flatten() {
+        let parts = candidate.get("content").and_then(|c| c.get("parts")).and_then(Value::as_array);
+        for part in parts.into_iter().flatten() {
+            push(part.get("text"));
+        }
+    }
+    text
+}
+
+/// 边转发边计数的流式输出速率计
+pub struct StreamTokenMeter {
+    encoding: TokenEncoding,
+    parser: SseParser,
+    tokens: u32,
+    first_tokens: u32,
+    first_at: Option<Instant>,
+    last_at: Option<Instant>,
+}
+
+impl StreamTokenMeter {
+    pub fn new(model: &str) -> Self {
+        Self {
+            encoding: TokenEncoding::for_model(model),
+            parser: SseParser::new(),
+            tokens: 0,
+            first_tokens: 0,
+            first_at: None,
+            last_at: None,
+        }
+    }
+
+    /// 追加一个网络 chunk, `now` 为收到该 chunk 的时间
+    pub fn feed(&mut self, chunk: &[u8], now: Instant) {
+        let mut added = 0u32;
+        for event in self.parser.push(chunk) {
+            if event.is_done() {
+                continue;
+            }
+            if let Ok(data) = serde_json::from_str::<Value>(&event.data) {
+                added = added.saturating_add(count_text(self.encoding, &delta_text(&data)));
+            }
+        }
+        if added == 0 {
+            return;
+        }
+        if self.first_at.is_none() {
+            self.first_at = Some(now);
+            self.first_tokens = added;
+        }
+        self.last_at = Some(now);
+        self.tokens = self.tokens.saturating_add(added);
+    }
+
+    /// 输出速率; 只有一个增量或生成时间过短时为 None
+    pub fn tokens_per_second(&self) -> Option<f64> {
+        let elapsed = self.last_at?.duration_since(self.first_at?);
+        let generated = self.tokens - self.first_tokens;
+        if generated == 0 || elapsed.as_millis() < MIN_GENERATION_MS {
+            return None;
+        }
+        Some(generated as f64 / elapsed.as_secs_f64())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_stream_rate_across_formats() {
+        let start = Instant::now();
+        let mut meter = StreamTokenMeter::new("gpt-4o");
+        meter.feed(b"data: {\"choices\":[{\"delta\":{\"content\":\"Hello\"}}]}\n\n", start);
+        // 事件被拆分到两个 chunk, 时间按收到完整事件的 chunk 计
+        meter.feed(b"data: {\"choices\":[{\"delta\":{\"content\":\" world, how", start + Duration::from_millis(100));
+        meter.feed(b" are you\"}}]}\n\n", start + Duration::from_millis(500));
+        meter.feed(b"data: [DONE]\n\n", start + Duration::from_secs(2));
+        let generated = meter.tokens - count_text(TokenEncoding::O200k, "Hello");
+        assert_eq!(generated, count_text(TokenEncoding::O200k, " world, how are you"));
+        assert_eq!(meter.tokens_per_second(), Some(generated as f64 / 0.5));
+
+        let anthropic = json_event(r#"{"type":"content_block_delta","delta":{"type":"text_delta","text":"Hi"}}"#);
+        let gemini = json_event(r#"{"response":{"candidates":[{"content":{"parts":[{"text":"Hi"}]}}]}}"#);
+        let responses = json_event(r#"{"type":"response.output_text.delta","delta":"Hi"}"#);
+        for event in [anthropic, gemini, responses] {
+            let mut meter = StreamTokenMeter::new("claude-sonnet-4-5");
+            meter.feed(event.as_bytes(), start);
+            assert_eq!(meter.tokens, 1);
+            // 只有一个增量时不计算速率
+            assert_eq!(meter.tokens_per_second(), None);
+            meter.feed(event.as_bytes(), start + Duration::from_millis(10));
+            assert_eq!(meter.tokens_per_second(), None);
+            meter.feed(event.as_bytes(), start + Duration::from_millis(250));
+            assert_eq!(meter.tokens_per_second(), Some(8.0));
+        }
+    }
+
+    fn json_event(data: &str) -> String {
+        format!("data: {}\n\n", data)
+    }
+}
@@ -0,0 +1,199 @@
+// 模型对比
+// 把同一个 OpenAI Chat Completions 请求并行发往多个模型 (经本地反代, 沿用正常的映射与路由),
+// 返回各自的回复、耗时、用量与费用, 供前端并排对比 (聚合路由也基于此)。每个子请求都会正常记入请求日志与用量统计。
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::proxy::common::pricing::request_cost;
+use crate::proxy::config::ModelPrice;
+
+/// 一次对比的模型数上限
+pub const MAX_MODELS: usize = 6;
+/// 对比请求在日志与用量中显示的客户端名称
+pub const CLIENT_NAME: &str = "model-compare";
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CompareRequest {
+    pub models: Vec<String>,
+    /// OpenAI Chat Completions 请求体, 其中的 `model` 与 `stream` 会被覆盖
+    pub request: Value,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct CompareResult {
+    pub model: String,
+    /// 响应中的模型名 (映射后的实际模型)
+    pub mapped_model: Option<String>,
+    /// 请求失败 (网络错误、超时) 时为 null
+    pub status: Option<u16>,
+    pub duration: u64, // ms
+    pub content: Option<String>,
+    pub input_tokens: Option<u32>,
+    pub output_tokens: Option<u32>,
+    /// 美元; 没有用量时为 null
+    pub cost: Option<f64>,
+    pub error: Option<String>,
+}
+
+/// 本地反代地址与 API Key
+#[derive(Clone)]
+pub struct CompareTarget {
+    pub base_url: String,
+    pub api_key: String,
+    /// 子请求的 `X-Client-Name`
+    pub client_name: &'static str,
+}
+
+/// 去重后的模型列表
+fn validate(request: &CompareRequest) -> Result<Vec<String>, String> {
+    let mut models: Vec<String> = Vec::new();
+    for model in request.models.iter().map(|m| m.trim()).filter(|m| !m.is_empty()) {
+        if !models.iter().any(|m| m == model) {
+            models.push(model.to_string());
+        }
+    }
+    if models.is_empty() {
+        return Err("At least one model is required".to_string());
+    }
+    if models.len() > MAX_MODELS {
+        return Err(format!("At most {} models can be compared at once", MAX_MODELS));
+    }
+    if !request.request.get("messages").is_some_and(Value::is_array) {
+        return Err("Request must be a Chat Completions body with a messages array".to_string());
+    }
+    Ok(models)
+}
+
+/// 解析单个模型的响应
+fn parse_response(model: &str, status: u16, duration: u64, text: &str, prices: &[ModelPrice]) -> CompareResult {
+    let json: Option<Value> = serde_json::from_str(text).ok();
+    let mut result = CompareResult {
+        model: model.to_string(),
+        mapped_model: None,
+        status: Some(status),
+        duration,
+        content: None,
+        input_tokens: None,
+        output_tokens: None,
+        cost: None,
+        error: None,
+    };
+    let json = match json {
+        Some(json) if (200..300).contains(&status) => json,
+        other => {
+            let message = other.as_ref().and_then(|j| j.pointer("/error/message")).and_then(Value::as_str);
+            result.error = Some(message.map(String::from).unwrap_or_else(|| text.chars().take(500).collect()));
+            return result;
+        }
+    };
+    result.mapped_model = json.get("model").and_then(Value::as_str).map(String::from);
+    result.content = json.pointer("/choices/0/message/content").and_then(Value::as_str).map(String::from);
+    let usage = json.get("usage");
+    let tokens = |key: &str| usage.and_then(|u| u.get(key)).and_then(Value::as_u64).map(|v| v as u32);
+    result.input_tokens = tokens("prompt_tokens");
+    result.output_tokens = tokens("completion_tokens");
+    if result.input_tokens.is_some() || result.output_tokens.is_some() {
+        result.cost = Some(request_cost(
+            prices,
+            model,
+            result.mapped_model.as_deref(),
+            result.input_tokens.unwrap_or(0),
+            result.output_tokens.unwrap_or(0),
+        ));
+    }
+    result
+}
+
+async fn run_one(
+    client: &reqwest::Client,
+    target: &CompareTarget,
+    model: String,
+    mut body: Value,
+    prices: &[ModelPrice],
+) -> CompareResult {
+    body["model"] = Value::String(model.clone());
+    body["stream"] = Value::Bool(false);
+    let mut req = client
+        .post(format!("{}/v1/chat/completions", target.base_url.trim_end_matches('/')))
+        .header("Content-Type", "application/json")
+        .header(crate::proxy::common::client_info::CLIENT_NAME_HEADER, target.client_name);
+    if !target.api_key.is_empty() {
+        req = req.header("Authorization", format!("Bearer {}", target.api_key));
+    }
+    let start = std::time::Instant::now();
+    let result = async {
+        let resp = req.body(body.to_string()).send().await?;
+        let status = resp.status().as_u16();
+        Ok::<_, reqwest::Error>((status, resp.text().await?))
+    }
+    .await;
+    let duration = start.elapsed().as_millis() as u64;
+    match result {
+        Ok((status, text)) => parse_response(&model, status, duration, &text, prices),
+        Err(e) => CompareResult {
+            model,
+            mapped_model: None,
+            status: None,
+            duration,
+            content: None,
+            input_tokens: None,
+            output_tokens: None,
+            cost: None,
+            error: Some(format!("Request failed: {}", e)),
+        },
+    }
+}
+
+/// 并行发往各个模型, 结果顺序与请求中的模型顺序一致
+pub async fn compare(
+    request: CompareRequest,
+    target: CompareTarget,
+    prices: &[ModelPrice],
+    timeout_secs: u64,
+) -> Result<Vec<CompareResult>, String> {
+    let models = validate(&request)?;
+    let client = crate::proxy::handlers::common::build_upstream_client(Default::default(), timeout_secs)?;
+    tracing::info!("[Compare] Fanning out to {} models: {:?}", models.len(), models);
+    let runs = models.into_iter().map(|model| run_one(&client, &target, model, request.request.clone(), prices));
+    Ok(futures::future::join_all(runs).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_validate_models() {
+        let request = |models: &[&str], body: Value| CompareRequest {
+            models: models.iter().map(|m| m.to_string()).collect(),
+            request: body,
+        };
+        let messages = json!({"messages": [{"role": "user", "content": "hi"}]});
+        assert_eq!(
+            validate(&request(&["gpt-4o", " gpt-4o ", "", "glm-4.6"], messages.clone())).unwrap(),
+            vec!["gpt-4o", "glm-4.6"]
+        );
+        assert!(validate(&request(&[], messages.clone())).is_err());
+        assert!(validate(&request(&["a", "b", "c", "d", "e", "f", "g"], messages)).is_err());
+        assert!(validate(&request(&["gpt-4o"], json!({"prompt": "hi"}))).is_err());
+    }
+
+    #[test]
+    fn test_parse_response() {
+        let prices = vec![ModelPrice { pattern: "gemini-2.5-pro".to_string(), input_per_million: 1.25, output_per_million: 10.0, ..Default::default() }];
+        let ok = r#"{"model":"gemini-2.5-pro","choices":[{"message":{"role":"assistant","content":"Hello"}}],
+            "usage":{"prompt_tokens":1000,"completion_tokens":100}}"#;
+        let result = parse_response("pro", 200, 850, ok, &prices);
+        assert_eq!(result.mapped_model.as_deref(), Some("gemini-2.5-pro"));
+        assert_eq!(result.content.as_deref(), Some("Hello"));
+        assert_eq!((result.input_tokens, result.output_tokens), (Some(1000), Some(100)));
+        assert!((result.cost.unwrap() - 0.00225).abs() < 1e-12);
+        assert!(result.error.is_none());
+
+        let failed = parse_response("glm-4.6", 429, 20, r#"{"error":{"message":"Rate limited"}}"#, &prices);
+        assert_eq!((failed.status, failed.error.as_deref()), (Some(429), Some("Rate limited")));
+        assert_eq!(parse_response("x", 502, 5, "Bad Gateway", &prices).error.as_deref(), Some("Bad Gateway"));
+    }
+}
@@ -36,6 +36,29 @@ impl Default for ZaiDispatchMode {
     }
 }
 
+/// 多 Key 负载均衡策略
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyBalanceStrategy {
+    /// 平滑加权轮询
+    #[default]
+    WeightedRoundRobin,
+    /// 选择当前并发请求数 (按权重归一化) 最少的 Key
+    LeastInFlight,
+}
+
+/// 带权重的上游 API Key
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct WeightedApiKey {
+    pub key: String,
+    #[serde(default = "default_key_weight")]
+    pub weight: u32,
+}
+
+fn default_key_weight() -> u32 {
+    1
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ZaiModelDefaults {
     /// Default model for "opus" family (when the incoming model is a Claude id).
@@ -90,6 +113,11 @@ pub struct ZaiConfig {
     pub base_url: String,
     #[serde(default)]
     pub api_key: String,
+    /// Additional API keys; together with `api_key` they form the load-balanced key pool.
+    #[serde(default)]
+    pub api_keys: Vec<WeightedApiKey>,
+    #[serde(default)]
+    pub key_strategy: KeyBalanceStrategy,
     #[serde(default)]
     pub dispatch_mode: ZaiDispatchMode,
     /// Optional per-model mapping overrides for Anthropic/Claude model ids.
@@ -108,6 +136,8 @@ impl Default for ZaiConfig {
             enabled: false,
             base_url: default_zai_base_url(),
             api_key: String::new(),
+            api_keys: Vec::new(),
+            key_strategy: KeyBalanceStrategy::default(),
             dispatch_mode: ZaiDispatchMode::Off,
             model_mapping: HashMap::new(),
             models: ZaiModelDefaults::default(),
@@ -116,6 +146,27 @@ impl Default for ZaiConfig {
     }
 }
 
+impl ZaiConfig {
+    /// All usable keys: `api_key` (weight 1) followed by `api_keys`, empty and duplicate keys removed.
+    pub fn key_pool(&self) -> Vec<WeightedApiKey> {
+        let mut pool: Vec<WeightedApiKey> = Vec::new();
+        let primary = WeightedApiKey {
+            key: self.api_key.trim().to_string(),
+            weight: 1,
+        };
+        for entry in std::iter::once(primary).chain(self.api_keys.iter().map(|k| WeightedApiKey {
+            key: k.key.trim().to_string(),
+            weight: k.weight,
+        })) {
+            if entry.key.is_empty() || entry.weight == 0 || pool.iter().any(|p| p.key == entry.key) {
+                continue;
+            }
+            pool.push(entry);
+        }
+        pool
+    }
+}
+
 /// 实验性功能配置 (Feature Flags)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExperimentalConfig {
@@ -193,111 +244,2609 @@ impl Default for DebugLoggingConfig {
     }
 }
 
-/// 反代服务配置
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ProxyConfig {
-    /// 是否启用反代服务
-    pub enabled: bool,
+/// 模型别名的匹配方式
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ModelAliasMatch {
+    Exact,
+    /// `*` 通配 (区分大小写)
+    #[default]
+    Glob,
+    /// 正则表达式 (整串匹配), 目标可引用捕获组 `$1` / `${name}`
+    Regex,
+}
 
-    /// 是否允许局域网访问
-    /// - false: 仅本机访问 127.0.0.1（默认，隐私优先）
-    /// - true: 允许局域网访问 0.0.0.0
-    #[serde(default)]
-    pub allow_lan_access: bool,
+/// 别名指定的上游
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AliasUpstream {
+    Google,
+    Zai,
+}
 
-    /// Authorization policy for the proxy.
-    /// - off: no auth required
-    /// - strict: auth required for all routes
-    /// - all_except_health: auth required for all routes except `/healthz`
-    /// - auto: recommended defaults (currently: allow_lan_access => all_except_health, else off)
+/// 模型别名规则 (按列表顺序匹配, 第一条命中的规则生效)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ModelAliasRule {
+    pub pattern: String,
+    pub target: String,
     #[serde(default)]
-    pub auth_mode: ProxyAuthMode,
-
-    /// 监听端口
-    pub port: u16,
+    pub match_type: ModelAliasMatch,
+    /// 强制使用的上游 (z.ai 支持 Anthropic 与 OpenAI Chat Completions 协议), 为空时按默认调度
+    #[serde(default)]
+    pub upstream: Option<AliasUpstream>,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// 备用模型链: 目标模型返回 `fallback_on` 中的错误时依次改用 (按原样转发, 不再经过别名改写)
+    #[serde(default)]
+    pub fallbacks: Vec<String>,
+    /// 触发备用模型的错误类型
+    #[serde(default = "default_fallback_triggers")]
+    pub fallback_on: Vec<FallbackTrigger>,
+}
 
-    /// API 密钥
-    pub api_key: String,
-    
-    /// Web UI 管理后台密码 (可选，如未设置则使用 api_key)
-    pub admin_password: Option<String>,
+/// 触发备用模型的错误类型
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FallbackTrigger {
+    /// 上游内容安全策略拒绝
+    ContentFilter,
+    /// 超出上下文长度
+    ContextLength,
+    /// 429 限流 / 配额耗尽
+    RateLimit,
+}
 
-    /// 是否自动启动
-    pub auto_start: bool,
+fn default_fallback_triggers() -> Vec<FallbackTrigger> {
+    vec![FallbackTrigger::ContentFilter, FallbackTrigger::ContextLength, FallbackTrigger::RateLimit]
+}
 
-    /// 自定义精确模型映射表 (key: 原始模型名, value: 目标模型名)
+/// 流式响应改写规则 (按路由匹配)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct StreamTransformRule {
+    /// 请求路径, 支持 `*` 通配 (例如 `/v1/chat/completions`, `/v1/*`)
+    pub path: String,
+    /// 从每个事件的 JSON 中删除的字段 (任意层级, 例如 `reasoning_content`)
     #[serde(default)]
-    pub custom_mapping: std::collections::HashMap<String, String>,
-
-    /// API 请求超时时间(秒)
-    #[serde(default = "default_request_timeout")]
-    pub request_timeout: u64,
+    pub strip_fields: Vec<String>,
+    /// 将 `model` 字段改写为该值, `{request}` 表示客户端请求的模型名
+    #[serde(default)]
+    pub model: Option<String>,
+    /// 上游未返回 usage 时, 在 `[DONE]` 之前补发一个估算的 usage chunk (OpenAI 格式)
+    #[serde(default)]
+    pub inject_usage: bool,
+    /// 推理内容处理方式, 同时作用于流式和非流式 (JSON) 响应
+    #[serde(default)]
+    pub reasoning: ReasoningMode,
+    /// 输出后处理, 同时作用于流式和非流式响应
+    #[serde(default)]
+    pub output: OutputRules,
+}
 
-    /// 是否开启请求日志记录 (监控)
+/// 输出文本后处理 (OpenAI Chat / Completions 与 Anthropic Messages 格式)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct OutputRules {
+    /// 额外的停止序列: 输出在第一个匹配处截断, 流式响应随即结束
     #[serde(default)]
-    pub enable_logging: bool,
+    pub stop: Vec<String>,
+    /// 整个回复以 ``` 代码块开头时去掉开始与结束标记
+    #[serde(default)]
+    pub strip_code_fences: bool,
+    /// 回复以该文本开头时去掉
+    #[serde(default)]
+    pub trim_prefix: Option<String>,
+    /// 回复以该文本结尾时去掉 (忽略末尾空白)
+    #[serde(default)]
+    pub trim_suffix: Option<String>,
+    /// 正则替换, 按顺序逐行应用
+    #[serde(default)]
+    pub replacements: Vec<OutputReplacement>,
+}
 
-    /// 调试日志配置 (保存完整链路)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OutputReplacement {
+    pub pattern: String,
+    /// 支持 `$1` 等捕获组引用
     #[serde(default)]
-    pub debug_logging: DebugLoggingConfig,
+    pub replacement: String,
+}
 
-    /// 上游代理配置
+/// 推理内容归一化模式
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ReasoningMode {
+    /// 原样转发
+    #[default]
+    Passthrough,
+    /// `reasoning` / `<think>` 标签统一为 `reasoning_content` (Anthropic thinking 块保持不变)
+    Normalize,
+    /// 删除所有推理内容 (reasoning 字段、`<think>` 标签、thinking 块、Gemini thought part)
+    Strip,
+}
+
+/// 流式响应改写配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct StreamTransformConfig {
     #[serde(default)]
-    pub upstream_proxy: UpstreamProxyConfig,
+    pub enabled: bool,
+    /// 第一条匹配的规则生效
+    #[serde(default)]
+    pub rules: Vec<StreamTransformRule>,
+}
 
-    /// z.ai provider configuration (Anthropic-compatible).
+/// 上游路由与故障转移配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpstreamRoutingConfig {
+    /// 默认 v1internal 端点 (按优先级排序), 为空时使用内置列表
+    #[serde(default = "UpstreamRoutingConfig::default_endpoints")]
+    pub endpoints: Vec<String>,
+    /// 按模型指定端点, 支持 `gemini-*` 前缀通配
     #[serde(default)]
-    pub zai: ZaiConfig,
+    pub model_endpoints: HashMap<String, Vec<String>>,
+    /// 最大尝试次数 (0 = 每个端点各尝试一次)
+    #[serde(default)]
+    pub max_attempts: u32,
+    /// 重试同一端点前的退避基数 (毫秒, 指数增长)
+    #[serde(default = "default_backoff_base_ms")]
+    pub backoff_base_ms: u64,
+    /// 退避上限 (毫秒)
+    #[serde(default = "default_backoff_max_ms")]
+    pub backoff_max_ms: u64,
+    /// 单次尝试等待响应头的超时 (秒, 0 = 不单独限制)
+    #[serde(default)]
+    pub attempt_timeout_secs: u64,
+    /// 建立 TCP/TLS 连接的超时 (秒)
+    #[serde(default = "default_connect_timeout_secs")]
+    pub connect_timeout_secs: u64,
+    /// 流式响应等待首个有效数据块的超时 (秒), 超时后换账号重试
+    #[serde(default = "default_first_byte_timeout_secs")]
+    pub first_byte_timeout_secs: u64,
+    /// 流式响应两个数据块之间的最长间隔 (秒, 0 = 不限制, 心跳不计入);
+    /// 超时后中断流、向客户端发送 SSE 错误事件并记入端点失败
+    #[serde(default = "default_stream_idle_timeout_secs")]
+    pub stream_idle_timeout_secs: u64,
+    /// 连续失败多少次后标记端点不健康
+    #[serde(default = "default_failure_threshold")]
+    pub failure_threshold: u32,
+    /// 不健康端点的冷却时间 (秒), 期间排在候选列表末尾
+    #[serde(default = "default_unhealthy_cooldown_secs")]
+    pub unhealthy_cooldown_secs: u64,
+    /// 熔断: 不健康端点在冷却期内直接跳过 (不再兜底), 全部熔断时快速失败, 冷却后放行探测请求
+    #[serde(default)]
+    pub circuit_breaker_enabled: bool,
+}
 
-    /// 账号调度配置 (粘性会话/限流重试)
+impl UpstreamRoutingConfig {
+    pub fn default_endpoints() -> Vec<String> {
+        crate::proxy::upstream::client::V1_INTERNAL_BASE_URL_FALLBACKS
+            .iter()
+            .map(|s| s.to_string())
+            .collect()
+    }
+}
+
+impl Default for UpstreamRoutingConfig {
+    fn default() -> Self {
+        Self {
+            endpoints: Self::default_endpoints(),
+            model_endpoints: HashMap::new(),
+            max_attempts: 0,
+            backoff_base_ms: default_backoff_base_ms(),
+            backoff_max_ms: default_backoff_max_ms(),
+            attempt_timeout_secs: 0,
+            connect_timeout_secs: default_connect_timeout_secs(),
+            first_byte_timeout_secs: default_first_byte_timeout_secs(),
+            stream_idle_timeout_secs: default_stream_idle_timeout_secs(),
+            failure_threshold: default_failure_threshold(),
+            unhealthy_cooldown_secs: default_unhealthy_cooldown_secs(),
+            circuit_breaker_enabled: false,
+        }
+    }
+}
+
+fn default_connect_timeout_secs() -> u64 {
+    20
+}
+
+/// 上游 HTTP 连接池配置, 作用于 v1internal 客户端和所有直连第三方上游的共享客户端
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ConnectionPoolConfig {
+    /// 每主机最多保留的空闲连接 (0 = 不复用连接)
+    #[serde(default = "default_pool_max_idle_per_host")]
+    pub max_idle_per_host: usize,
+    /// 空闲连接保持时间 (秒)
+    #[serde(default = "default_pool_idle_timeout_secs")]
+    pub idle_timeout_secs: u64,
+    /// TCP 保活探测间隔 (秒, 0 = 关闭)
+    #[serde(default = "default_tcp_keepalive_secs")]
+    pub tcp_keepalive_secs: u64,
+    /// 允许通过 ALPN 协商 HTTP/2; 关闭时只使用 HTTP/1.1
+    #[serde(default = "default_true")]
+    pub http2: bool,
+    #[serde(default = "default_true")]
+    pub tcp_nodelay: bool,
+}
+
+impl Default for ConnectionPoolConfig {
+    fn default() -> Self {
+        Self {
+            max_idle_per_host: default_pool_max_idle_per_host(),
+            idle_timeout_secs: default_pool_idle_timeout_secs(),
+            tcp_keepalive_secs: default_tcp_keepalive_secs(),
+            http2: true,
+            tcp_nodelay: true,
+        }
+    }
+}
+
+fn default_pool_max_idle_per_host() -> usize {
+    16
+}
+
+fn default_pool_idle_timeout_secs() -> u64 {
+    90
+}
+
+fn default_tcp_keepalive_secs() -> u64 {
+    60
+}
+
+fn default_first_byte_timeout_secs() -> u64 {
+    60
+}
+
+fn default_stream_idle_timeout_secs() -> u64 {
+    180
+}
+
+fn default_backoff_base_ms() -> u64 {
+    200
+}
+
+fn default_backoff_max_ms() -> u64 {
+    2000
+}
+
+fn default_failure_threshold() -> u32 {
+    3
+}
+
+fn default_unhealthy_cooldown_secs() -> u64 {
+    60
+}
+
+/// 上游健康检查配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthCheckConfig {
     #[serde(default)]
-    pub scheduling: crate::proxy::sticky_config::StickySessionConfig,
+    pub enabled: bool,
+    /// 探测间隔 (秒)
+    #[serde(default = "default_health_check_interval_secs")]
+    pub interval_secs: u64,
+    /// 单次探测超时 (秒)
+    #[serde(default = "default_health_check_timeout_secs")]
+    pub timeout_secs: u64,
+    /// 每个上游保留的探测历史条数
+    #[serde(default = "default_health_check_history_size")]
+    pub history_size: usize,
+    /// 所有上游均不可用时直接返回 503, 不再等待上游超时
+    #[serde(default)]
+    pub fail_fast: bool,
+}
 
-    /// 实验性功能配置
+impl Default for HealthCheckConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_secs: default_health_check_interval_secs(),
+            timeout_secs: default_health_check_timeout_secs(),
+            history_size: default_health_check_history_size(),
+            fail_fast: false,
+        }
+    }
+}
+
+fn default_health_check_interval_secs() -> u64 {
+    60
+}
+
+fn default_health_check_timeout_secs() -> u64 {
+    10
+}
+
+fn default_health_check_history_size() -> usize {
+    60
+}
+
+/// `/v1/models` 聚合配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelCatalogConfig {
+    /// 聚合结果缓存时间 (秒), 0 表示每次请求都重新聚合
+    #[serde(default = "default_model_catalog_ttl_secs")]
+    pub cache_ttl_secs: u64,
+    /// 向 z.ai 及 OpenAI 兼容上游请求模型列表, 关闭时只列出配置中的具体模型名
+    #[serde(default = "default_true")]
+    pub include_upstream_models: bool,
+}
+
+impl Default for ModelCatalogConfig {
+    fn default() -> Self {
+        Self {
+            cache_ttl_secs: default_model_catalog_ttl_secs(),
+            include_upstream_models: true,
+        }
+    }
+}
+
+fn default_model_catalog_ttl_secs() -> u64 {
+    300
+}
+
+/// OpenAI Realtime API (WebSocket) 透传配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RealtimeConfig {
     #[serde(default)]
-    pub experimental: ExperimentalConfig,
+    pub enabled: bool,
+    /// 上游 WebSocket 地址, 客户端的查询参数 (如 `model`) 会原样附加
+    #[serde(default = "default_realtime_upstream_url")]
+    pub upstream_url: String,
+    /// 注入到上游握手请求的 API Key (客户端的凭证不会被转发)
+    #[serde(default)]
+    pub api_key: String,
+    /// 额外注入的握手请求头
+    #[serde(default = "default_realtime_headers")]
+    pub headers: HashMap<String, String>,
+}
 
-    /// 固定账号模式的账号ID (Fixed Account Mode)
-    /// - None: 使用轮询模式
-    /// - Some(account_id): 固定使用指定账号
+impl Default for RealtimeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            upstream_url: default_realtime_upstream_url(),
+            api_key: String::new(),
+            headers: default_realtime_headers(),
+        }
+    }
+}
+
+fn default_realtime_upstream_url() -> String {
+    "wss://api.openai.com/v1/realtime".to_string()
+}
+
+fn default_realtime_headers() -> HashMap<String, String> {
+    HashMap::from([("OpenAI-Beta".to_string(), "realtime=v1".to_string())])
+}
+
+/// Embeddings 上游类型
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum EmbeddingProviderKind {
+    /// OpenAI 兼容接口, 请求原样转发
+    #[default]
+    Openai,
+    /// Gemini API `batchEmbedContents`
+    Gemini,
+    Voyage,
+    /// Cohere v2 `embed`
+    Cohere,
+}
+
+/// 按模型名匹配的 Embeddings 上游
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EmbeddingProviderConfig {
+    /// 客户端请求的模型名, 支持 `*` 通配 (例如 `text-embedding-*`)
+    pub pattern: String,
     #[serde(default)]
-    pub preferred_account_id: Option<String>,
+    pub provider: EmbeddingProviderKind,
+    /// 为空时使用该类型的官方地址
+    #[serde(default)]
+    pub base_url: String,
+    #[serde(default)]
+    pub api_key: String,
+    /// 改写为上游模型名, 为空时沿用请求的模型名
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
 }
 
-/// 上游代理配置
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
-pub struct UpstreamProxyConfig {
-    /// 是否启用
+/// `/v1/embeddings` 配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EmbeddingsConfig {
+    #[serde(default)]
     pub enabled: bool,
-    /// 代理地址 (http://, https://, socks5://)
-    pub url: String,
+    /// 单次上游请求的最大输入条数, 输入数组超出时分批请求
+    #[serde(default = "default_embedding_batch_size")]
+    pub max_batch_size: usize,
+    /// 第一条匹配的上游生效
+    #[serde(default)]
+    pub providers: Vec<EmbeddingProviderConfig>,
 }
 
-impl Default for ProxyConfig {
+impl Default for EmbeddingsConfig {
     fn default() -> Self {
         Self {
             enabled: false,
-            allow_lan_access: false, // 默认仅本机访问，隐私优先
-            auth_mode: ProxyAuthMode::default(),
-            port: 8045,
-            api_key: format!("sk-{}", uuid::Uuid::new_v4().simple()),
-            admin_password: None,
-            auto_start: false,
-            custom_mapping: std::collections::HashMap::new(),
-            request_timeout: default_request_timeout(),
-            enable_logging: true, // 默认开启，支持 token 统计功能
-            debug_logging: DebugLoggingConfig::default(),
-            upstream_proxy: UpstreamProxyConfig::default(),
-            zai: ZaiConfig::default(),
-            scheduling: crate::proxy::sticky_config::StickySessionConfig::default(),
-            experimental: ExperimentalConfig::default(),
-            preferred_account_id: None, // 默认使用轮询模式
+            max_batch_size: default_embedding_batch_size(),
+            providers: Vec::new(),
         }
     }
 }
 
-fn default_request_timeout() -> u64 {
-    120 // 默认 120 秒,原来 60 秒太短
+fn default_embedding_batch_size() -> usize {
+    96
+}
+
+/// Rerank 上游类型
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RerankProviderKind {
+    /// Cohere v2 `rerank`
+    #[default]
+    Cohere,
+    Jina,
+    Voyage,
+}
+
+/// 按模型名匹配的 Rerank 上游
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RerankProviderConfig {
+    /// 客户端请求的模型名, 支持 `*` 通配 (例如 `rerank-*`)
+    pub pattern: String,
+    #[serde(default)]
+    pub provider: RerankProviderKind,
+    /// 为空时使用该类型的官方地址
+    #[serde(default)]
+    pub base_url: String,
+    #[serde(default)]
+    pub api_key: String,
+    /// 改写为上游模型名, 为空时沿用请求的模型名
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+/// `/v1/rerank` 配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct RerankConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 第一条匹配的上游生效
+    #[serde(default)]
+    pub providers: Vec<RerankProviderConfig>,
+    /// 没有匹配的上游时经 `/v1/embeddings` 取该模型的向量按余弦相似度排序; 为空时使用请求的模型名
+    #[serde(default)]
+    pub embedding_model: String,
+}
+
+/// 图像生成上游类型
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ImageBackendKind {
+    /// OpenAI DALL-E 兼容接口
+    #[default]
+    Openai,
+    /// Stability AI v1 `text-to-image`
+    Stability,
+}
+
+/// 按模型名匹配的图像生成上游, 未匹配的模型走内置 Gemini 图像生成
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ImageBackendConfig {
+    /// 客户端请求的模型名, 支持 `*` 通配 (例如 `dall-e-*`)
+    pub pattern: String,
+    #[serde(default)]
+    pub provider: ImageBackendKind,
+    /// 为空时使用该类型的官方地址
+    #[serde(default)]
+    pub base_url: String,
+    #[serde(default)]
+    pub api_key: String,
+    /// 改写为上游模型名 (Stability 为 engine id), 为空时沿用请求的模型名
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+/// 图像生成配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct ImagesConfig {
+    /// 第一条匹配的上游生效
+    #[serde(default)]
+    pub backends: Vec<ImageBackendConfig>,
+    /// 将生成的图片保存到本地目录
+    #[serde(default)]
+    pub save_enabled: bool,
+    /// 保存目录, 为空时使用数据目录下的 `generated_images`
+    #[serde(default)]
+    pub save_dir: String,
+}
+
+/// 音频透传配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AudioConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// OpenAI 兼容上游地址
+    #[serde(default = "default_audio_base_url")]
+    pub base_url: String,
+    #[serde(default)]
+    pub api_key: String,
+    /// 透传到上游的转录模型 (支持 `*` 通配), 其余模型仍使用 Gemini 转录; `/v1/audio/speech` 始终透传
+    #[serde(default = "default_audio_transcription_models")]
+    pub transcription_models: Vec<String>,
+}
+
+impl Default for AudioConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            base_url: default_audio_base_url(),
+            api_key: String::new(),
+            transcription_models: default_audio_transcription_models(),
+        }
+    }
+}
+
+fn default_audio_base_url() -> String {
+    "https://api.openai.com".to_string()
+}
+
+fn default_audio_transcription_models() -> Vec<String> {
+    vec!["whisper-*".to_string(), "gpt-4o*-transcribe*".to_string()]
+}
+
+/// 本地 Ollama 上游 (通过 OpenAI 兼容接口暴露)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OllamaConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_ollama_base_url")]
+    pub base_url: String,
+    /// 以该前缀开头的模型转发到 Ollama, 转发时去掉前缀 (例如 `ollama/llama3.1`)
+    #[serde(default = "default_ollama_model_prefix")]
+    pub model_prefix: String,
+    /// 额外转发到 Ollama 的模型名 (支持 `*` 通配), 原样转发
+    #[serde(default)]
+    pub models: Vec<String>,
+    /// 默认上下文长度 (`options.num_ctx`), 请求中的 `num_ctx` 优先; 0 表示使用模型默认值
+    #[serde(default)]
+    pub num_ctx: u32,
+    /// 模型常驻内存时间 (例如 `5m`, `-1`), 为空时使用 Ollama 默认值
+    #[serde(default)]
+    pub keep_alive: String,
+}
+
+impl Default for OllamaConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            base_url: default_ollama_base_url(),
+            model_prefix: default_ollama_model_prefix(),
+            models: Vec::new(),
+            num_ctx: 0,
+            keep_alive: String::new(),
+        }
+    }
+}
+
+fn default_ollama_base_url() -> String {
+    "http://127.0.0.1:11434".to_string()
+}
+
+fn default_ollama_model_prefix() -> String {
+    "ollama/".to_string()
+}
+
+/// Azure OpenAI 部署 (模型名 → 部署名)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AzureDeployment {
+    /// 客户端请求的模型名, 支持 `*` 通配
+    pub model: String,
+    /// Azure 部署名, 为空时与请求的模型名相同
+    #[serde(default)]
+    pub deployment: String,
+    /// 覆盖全局 `api_version`
+    #[serde(default)]
+    pub api_version: Option<String>,
+    /// 部署只提供 Chat Completions (新模型均如此), 传统 Completions 请求转换后转发
+    #[serde(default)]
+    pub chat_only: bool,
+}
+
+/// Azure OpenAI 上游 (模型通过 URL 中的部署名指定, `api-key` Header 鉴权)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AzureOpenAIConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 资源地址, 例如 `https://my-resource.openai.azure.com`
+    #[serde(default)]
+    pub endpoint: String,
+    #[serde(default)]
+    pub api_key: String,
+    /// 地址中已带 `api-version` 查询参数时以地址为准
+    #[serde(default = "default_azure_api_version")]
+    pub api_version: String,
+    /// 按顺序匹配, 第一条命中的部署生效
+    #[serde(default)]
+    pub deployments: Vec<AzureDeployment>,
+}
+
+impl Default for AzureOpenAIConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: String::new(),
+            api_key: String::new(),
+            api_version: default_azure_api_version(),
+            deployments: Vec::new(),
+        }
+    }
+}
+
+fn default_azure_api_version() -> String {
+    "2024-10-21".to_string()
+}
+
+/// Bedrock 模型路由 (模型名 → Bedrock 模型 ID / 推理配置文件)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BedrockModel {
+    /// 客户端请求的模型名, 支持 `*` 通配
+    pub model: String,
+    /// 例如 `anthropic.claude-3-5-sonnet-20240620-v1:0`, 为空时与请求的模型名相同
+    #[serde(default)]
+    pub model_id: String,
+}
+
+/// AWS Bedrock 上游 (Converse API, SigV4 签名)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BedrockConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_bedrock_region")]
+    pub region: String,
+    /// 凭证为空时读取环境变量 `AWS_ACCESS_KEY_ID` / `AWS_SECRET_ACCESS_KEY` / `AWS_SESSION_TOKEN`
+    #[serde(default)]
+    pub access_key_id: String,
+    #[serde(default)]
+    pub secret_access_key: String,
+    #[serde(default)]
+    pub session_token: String,
+    /// 按顺序匹配, 第一条命中的规则生效
+    #[serde(default)]
+    pub models: Vec<BedrockModel>,
+}
+
+impl Default for BedrockConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            region: default_bedrock_region(),
+            access_key_id: String::new(),
+            secret_access_key: String::new(),
+            session_token: String::new(),
+            models: Vec::new(),
+        }
+    }
+}
+
+fn default_bedrock_region() -> String {
+    "us-east-1".to_string()
+}
+
+/// OpenAI 兼容的对话上游 (Mistral / Groq / Together / OpenRouter 等)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CompatibleUpstream {
+    /// 显示名称, 用于日志与 `X-Upstream`; 为空时使用预设 id
+    #[serde(default)]
+    pub name: String,
+    /// 内置预设 id (`mistral` / `groq` / `together` / `openrouter`), 提供默认地址、鉴权方式、模型列表与参数兼容处理
+    #[serde(default)]
+    pub preset: Option<String>,
+    /// 包含版本前缀的地址 (例如 `https://api.groq.com/openai/v1`), 为空时使用预设地址
+    #[serde(default)]
+    pub base_url: String,
+    #[serde(default)]
+    pub api_key: String,
+    /// 路由到该上游的模型名, 支持 `*` 通配; 为空时使用预设的已知模型
+    #[serde(default)]
+    pub models: Vec<String>,
+    /// 上游只提供 Chat Completions, 传统 Completions 请求转换后转发; 预设上游不提供该接口时自动开启
+    #[serde(default)]
+    pub chat_only: bool,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+/// OpenAI 兼容上游列表
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct CompatibleUpstreamsConfig {
+    /// 第一条匹配的上游生效
+    #[serde(default)]
+    pub upstreams: Vec<CompatibleUpstream>,
+}
+
+/// 参数数值范围, 超出时截断到边界
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ParamClamp {
+    pub param: String,
+    #[serde(default)]
+    pub min: Option<f64>,
+    #[serde(default)]
+    pub max: Option<f64>,
+}
+
+/// 按上游追加的参数策略, 与内置规则叠加
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ParamPolicyRule {
+    /// 上游名称, 支持 `*` 通配 (`google` / `bedrock` / `ollama` / `azure`, 兼容上游的名称或预设 id)
+    pub upstream: String,
+    /// 转发前删除的顶层参数
+    #[serde(default)]
+    pub drop: Vec<String>,
+    #[serde(default)]
+    pub clamp: Vec<ParamClamp>,
+}
+
+/// 请求参数清理 (删除上游不支持的参数、限制数值范围)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ParamPolicyConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub rules: Vec<ParamPolicyRule>,
+}
+
+impl Default for ParamPolicyConfig {
+    fn default() -> Self {
+        Self { enabled: true, rules: Vec::new() }
+    }
+}
+
+/// 内容过滤规则的匹配方式
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ContentFilterMatch {
+    /// 关键字 (不区分大小写)
+    #[default]
+    Keyword,
+    Regex,
+}
+
+/// 命中后的处理方式
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ContentFilterAction {
+    /// 拒绝请求 / 中断响应
+    Block,
+    /// 替换为占位文本后继续
+    Redact,
+    /// 只记录日志
+    #[default]
+    Log,
+}
+
+/// 规则检查的方向
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ContentFilterScope {
+    Request,
+    Response,
+    #[default]
+    Both,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ContentFilterRule {
+    /// 规则名称, 用于日志与错误信息 (不会输出命中的内容)
+    pub name: String,
+    pub pattern: String,
+    #[serde(default)]
+    pub match_type: ContentFilterMatch,
+    #[serde(default)]
+    pub action: ContentFilterAction,
+    #[serde(default)]
+    pub scope: ContentFilterScope,
+    /// 生效的请求路径, 支持 `*` 通配 (例如 `/v1/chat/*`); 为空时作用于所有路由
+    #[serde(default)]
+    pub paths: Vec<String>,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+/// 请求 / 响应内容过滤
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ContentFilterConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 脱敏时的替换文本
+    #[serde(default = "default_redaction_text")]
+    pub redaction_text: String,
+    /// 按顺序检查, 先命中的 block 规则生效
+    #[serde(default)]
+    pub rules: Vec<ContentFilterRule>,
+}
+
+impl Default for ContentFilterConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            redaction_text: default_redaction_text(),
+            rules: Vec::new(),
+        }
+    }
+}
+
+fn default_redaction_text() -> String {
+    "[REDACTED]".to_string()
+}
+
+/// 系统提示词的注入方式
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SystemPromptMode {
+    /// 放在客户端的系统提示词之前
+    #[default]
+    Prefix,
+    /// 放在客户端的系统提示词之后
+    Suffix,
+    /// 替换客户端的系统提示词
+    Replace,
+}
+
+/// 单条系统提示词注入规则
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SystemPromptRule {
+    /// 规则名称, 用于日志
+    pub name: String,
+    pub prompt: String,
+    #[serde(default)]
+    pub mode: SystemPromptMode,
+    /// 生效的请求路径, 支持 `*` 通配; 为空时作用于所有路由
+    #[serde(default)]
+    pub paths: Vec<String>,
+    /// 生效的虚拟 Key (名称或 id); 为空时不限制
+    #[serde(default)]
+    pub virtual_keys: Vec<String>,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+/// 按路由 / 虚拟 Key 注入系统提示词
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct SystemPromptConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 按顺序应用所有命中的规则
+    #[serde(default)]
+    pub rules: Vec<SystemPromptRule>,
+}
+
+/// 发往第三方上游前的个人信息脱敏
+/// 检测到的值替换为占位符 (例如 `[EMAIL_1]`), 响应中出现的占位符尽可能还原为原值
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PiiRedactionConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_true")]
+    pub emails: bool,
+    #[serde(default = "default_true")]
+    pub phone_numbers: bool,
+    /// 常见服务的 API Key / 访问令牌 (OpenAI、Anthropic、AWS、Google、GitHub、Slack 等)
+    #[serde(default = "default_true")]
+    pub api_keys: bool,
+    /// 通过 Luhn 校验的 13-19 位卡号
+    #[serde(default = "default_true")]
+    pub credit_cards: bool,
+    /// 在响应中还原占位符
+    #[serde(default = "default_true")]
+    pub restore_response: bool,
+    /// 不做脱敏的模型 (支持 `*` 通配); 路由到本地 Ollama 的模型始终跳过
+    #[serde(default)]
+    pub skip_models: Vec<String>,
+}
+
+impl Default for PiiRedactionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            emails: true,
+            phone_numbers: true,
+            api_keys: true,
+            credit_cards: true,
+            restore_response: true,
+            skip_models: Vec::new(),
+        }
+    }
+}
+
+/// 请求超出上下文窗口时的处理方式
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ContextOverflowStrategy {
+    /// 直接返回 400, 不转发
+    #[default]
+    Error,
+    /// 从最早的消息开始删除 (保留 system 与最后一条消息)
+    DropOldest,
+    /// 删除最早的消息, 并插入一条说明已省略内容的占位消息
+    Summarize,
+}
+
+/// 单个模型的上下文窗口大小
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ContextWindowRule {
+    /// 模型名, 支持 `*` 通配; 优先于内置表
+    pub model: String,
+    pub context_tokens: u32,
+}
+
+/// 上下文窗口溢出保护
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ContextWindowConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub strategy: ContextOverflowStrategy,
+    /// 请求未指定 max_tokens 时为输出预留的 token 数
+    #[serde(default = "default_reserve_output_tokens")]
+    pub reserve_output_tokens: u32,
+    #[serde(default)]
+    pub models: Vec<ContextWindowRule>,
+}
+
+impl Default for ContextWindowConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            strategy: ContextOverflowStrategy::default(),
+            reserve_output_tokens: default_reserve_output_tokens(),
+            models: Vec::new(),
+        }
+    }
+}
+
+fn default_reserve_output_tokens() -> u32 {
+    1024
+}
+
+/// 单个模型支持的最大输出 token 数
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MaxTokensRule {
+    /// 模型名, 支持 `*` 通配; 优先于内置表
+    pub model: String,
+    pub max_output_tokens: u32,
+}
+
+/// max_tokens 自动调整
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MaxTokensConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 必填 max_tokens 的协议 (Anthropic Messages) 缺少该字段时补上
+    #[serde(default = "default_true")]
+    pub inject_missing: bool,
+    /// 补上的默认值 (仍受模型上限和上下文剩余空间限制)
+    #[serde(default = "default_inject_max_tokens")]
+    pub default_max_tokens: u32,
+    #[serde(default)]
+    pub models: Vec<MaxTokensRule>,
+}
+
+impl Default for MaxTokensConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            inject_missing: true,
+            default_max_tokens: default_inject_max_tokens(),
+            models: Vec::new(),
+        }
+    }
+}
+
+fn default_inject_max_tokens() -> u32 {
+    4096
+}
+
+/// 单个模型的价格 (美元 / 百万 token)
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ModelPrice {
+    /// 模型名, 支持 `*` 通配; 按顺序匹配, 先匹配者生效
+    pub pattern: String,
+    #[serde(default)]
+    pub input_per_million: f64,
+    #[serde(default)]
+    pub output_per_million: f64,
+    /// 命中提示词缓存的输入价格, 未设置时按输入价格
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_read_per_million: Option<f64>,
+    /// 写入提示词缓存的输入价格 (Anthropic 按 1.25 倍收费), 未设置时按输入价格
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_write_per_million: Option<f64>,
+}
+
+impl ModelPrice {
+    fn new(pattern: &str, input_per_million: f64, output_per_million: f64) -> Self {
+        Self {
+            pattern: pattern.to_string(),
+            input_per_million,
+            output_per_million,
+            ..Default::default()
+        }
+    }
+
+    fn with_cache(mut self, read_per_million: f64, write_per_million: Option<f64>) -> Self {
+        self.cache_read_per_million = Some(read_per_million);
+        self.cache_write_per_million = write_per_million;
+        self
+    }
+}
+
+/// 价格表, 用于计算每个请求的费用
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PricingConfig {
+    #[serde(default = "default_model_prices")]
+    pub models: Vec<ModelPrice>,
+}
+
+impl Default for PricingConfig {
+    fn default() -> Self {
+        Self {
+            models: default_model_prices(),
+        }
+    }
+}
+
+fn default_model_prices() -> Vec<ModelPrice> {
+    vec![
+        ModelPrice::new("gemini-2.5-pro*", 1.25, 10.0).with_cache(0.31, None),
+        ModelPrice::new("gemini-2.5-flash-lite*", 0.10, 0.40).with_cache(0.025, None),
+        ModelPrice::new("gemini-2.5-flash*", 0.30, 2.50).with_cache(0.075, None),
+        ModelPrice::new("gemini-3-pro*", 2.0, 12.0).with_cache(0.20, None),
+        ModelPrice::new("claude-opus-4*", 15.0, 75.0).with_cache(1.50, Some(18.75)),
+        ModelPrice::new("claude-sonnet-4*", 3.0, 15.0).with_cache(0.30, Some(3.75)),
+        ModelPrice::new("claude-haiku-4*", 1.0, 5.0).with_cache(0.10, Some(1.25)),
+        ModelPrice::new("gpt-4o-mini*", 0.15, 0.60).with_cache(0.075, None),
+        ModelPrice::new("gpt-4o*", 2.50, 10.0).with_cache(1.25, None),
+    ]
+}
+
+/// 相同请求的响应缓存配置 (仅非流式请求)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResponseCacheConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 缓存有效期 (秒)
+    #[serde(default = "default_response_cache_ttl_secs")]
+    pub ttl_secs: u64,
+    /// 最多缓存的响应数量, 超出后淘汰最早写入的条目
+    #[serde(default = "default_response_cache_max_entries")]
+    pub max_entries: usize,
+    /// 单个响应体上限 (KB), 超过则不缓存
+    #[serde(default = "default_response_cache_max_body_kb")]
+    pub max_body_kb: u64,
+}
+
+impl Default for ResponseCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ttl_secs: default_response_cache_ttl_secs(),
+            max_entries: default_response_cache_max_entries(),
+            max_body_kb: default_response_cache_max_body_kb(),
+        }
+    }
+}
+
+fn default_response_cache_ttl_secs() -> u64 {
+    300
+}
+
+fn default_response_cache_max_entries() -> usize {
+    500
+}
+
+fn default_response_cache_max_body_kb() -> u64 {
+    2048
+}
+
+/// 服务端会话记忆: 按会话请求头保存对话历史, 无状态客户端只需发送最新一条消息
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ConversationMemoryConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 携带会话 ID 的请求头
+    #[serde(default = "default_conversation_header")]
+    pub header: String,
+    /// 会话最后一次更新后的保留时间 (秒)
+    #[serde(default = "default_conversation_ttl_secs")]
+    pub ttl_secs: u64,
+    /// 最多保存的会话数量, 超出后淘汰最久未更新的会话
+    #[serde(default = "default_conversation_max_sessions")]
+    pub max_sessions: usize,
+    /// 每个会话最多保存的消息数 (超出时从最早的消息开始删除)
+    #[serde(default = "default_conversation_max_messages")]
+    pub max_messages: usize,
+}
+
+impl Default for ConversationMemoryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            header: default_conversation_header(),
+            ttl_secs: default_conversation_ttl_secs(),
+            max_sessions: default_conversation_max_sessions(),
+            max_messages: default_conversation_max_messages(),
+        }
+    }
+}
+
+fn default_conversation_header() -> String {
+    "X-Session-Id".to_string()
+}
+
+fn default_conversation_ttl_secs() -> u64 {
+    3600
+}
+
+fn default_conversation_max_sessions() -> usize {
+    1000
+}
+
+fn default_conversation_max_messages() -> usize {
+    200
+}
+
+/// 单个端点的请求体大小限制
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BodyLimitRule {
+    /// 请求路径, 支持 `*` 通配 (例如 `/v1/audio/*`)
+    pub path: String,
+    pub max_mb: u64,
+}
+
+/// 按端点限制请求体大小, 超出时返回 413
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BodyLimitConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 没有匹配规则时的上限
+    #[serde(default = "default_body_limit_mb")]
+    pub default_mb: u64,
+    /// 按顺序匹配, 先命中的生效
+    #[serde(default)]
+    pub rules: Vec<BodyLimitRule>,
+}
+
+impl Default for BodyLimitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            default_mb: default_body_limit_mb(),
+            rules: Vec::new(),
+        }
+    }
+}
+
+fn default_body_limit_mb() -> u64 {
+    100
+}
+
+/// 非流式 JSON 响应的压缩 (按客户端 Accept-Encoding 协商); SSE 流始终不压缩
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CompressionConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 小于该大小的响应不压缩
+    #[serde(default = "default_compression_min_bytes")]
+    pub min_bytes: usize,
+    #[serde(default = "default_true")]
+    pub gzip: bool,
+    #[serde(default = "default_true")]
+    pub brotli: bool,
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_bytes: default_compression_min_bytes(),
+            gzip: true,
+            brotli: true,
+        }
+    }
+}
+
+fn default_compression_min_bytes() -> usize {
+    1024
+}
+
+/// 请求/响应 Body 日志配置 (滚动文件存储, 写入前脱敏)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BodyLoggingConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 输出目录, 默认 <data_dir>/body_logs
+    #[serde(default)]
+    pub output_dir: Option<String>,
+    /// 单个日志文件上限 (MB), 超过后滚动
+    #[serde(default = "default_body_log_file_size_mb")]
+    pub max_file_size_mb: u64,
+    /// 保留的历史文件数量
+    #[serde(default = "default_body_log_max_files")]
+    pub max_files: u32,
+    /// 每个 Body 最多记录的字节数 (KB), 超出部分截断
+    #[serde(default = "default_body_log_max_body_kb")]
+    pub max_body_kb: u64,
+    /// 脱敏 API Key / Token 等凭证
+    #[serde(default = "default_true")]
+    pub redact_secrets: bool,
+    /// 脱敏用户 PII (邮箱、电话、IP)
+    #[serde(default = "default_true")]
+    pub redact_pii: bool,
+    /// 需要脱敏的请求/响应头 (不区分大小写)
+    #[serde(default = "default_body_log_redact_headers")]
+    pub redact_headers: Vec<String>,
+    /// 额外的自定义脱敏正则
+    #[serde(default)]
+    pub redact_patterns: Vec<String>,
+}
+
+impl Default for BodyLoggingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            output_dir: None,
+            max_file_size_mb: default_body_log_file_size_mb(),
+            max_files: default_body_log_max_files(),
+            max_body_kb: default_body_log_max_body_kb(),
+            redact_secrets: true,
+            redact_pii: true,
+            redact_headers: default_body_log_redact_headers(),
+            redact_patterns: Vec::new(),
+        }
+    }
+}
+
+fn default_body_log_file_size_mb() -> u64 {
+    20
+}
+
+fn default_body_log_max_files() -> u32 {
+    5
+}
+
+fn default_body_log_max_body_kb() -> u64 {
+    1024
+}
+
+fn default_body_log_redact_headers() -> Vec<String> {
+    [
+        "authorization",
+        "proxy-authorization",
+        "x-api-key",
+        "x-goog-api-key",
+        "cookie",
+        "set-cookie",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect()
+}
+
+/// 客户端限流配置 (按 API Key / IP 的令牌桶)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClientRateLimitConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 每分钟请求数上限 (0 = 不限制)
+    #[serde(default = "default_requests_per_minute")]
+    pub requests_per_minute: u32,
+    /// 每分钟 Token 数上限 (0 = 不限制)
+    /// 请求时按请求体大小预估扣除, 响应完成后按实际用量结算
+    #[serde(default)]
+    pub tokens_per_minute: u32,
+    /// 突发请求容量, 0 表示等于 requests_per_minute
+    #[serde(default)]
+    pub burst: u32,
+    /// 按 API Key 分别限流
+    #[serde(default = "default_true")]
+    pub per_api_key: bool,
+    /// 按客户端 IP 分别限流
+    #[serde(default = "default_true")]
+    pub per_ip: bool,
+}
+
+impl Default for ClientRateLimitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            requests_per_minute: default_requests_per_minute(),
+            tokens_per_minute: 0,
+            burst: 0,
+            per_api_key: true,
+            per_ip: true,
+        }
+    }
+}
+
+fn default_requests_per_minute() -> u32 {
+    60
+}
+
+/// 单个上游的并发覆盖设置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct UpstreamConcurrencyLimit {
+    pub max_concurrent: usize,
+    pub max_queue: usize,
+}
+
+/// 上游并发限制 (超出部分按 FIFO 排队, 队列满时返回 503)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ConcurrencyConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 每个上游的默认最大并发请求数
+    #[serde(default = "default_max_concurrent")]
+    pub max_concurrent: usize,
+    /// 每个上游的默认最大排队数
+    #[serde(default = "default_max_queue")]
+    pub max_queue: usize,
+    /// 排队超时 (秒), 超时返回 503
+    #[serde(default = "default_queue_timeout_secs")]
+    pub queue_timeout_secs: u64,
+    /// 按上游覆盖: `google` / `zai` / `embeddings` / `images` / `audio`
+    #[serde(default)]
+    pub overrides: HashMap<String, UpstreamConcurrencyLimit>,
+}
+
+impl Default for ConcurrencyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_concurrent: default_max_concurrent(),
+            max_queue: default_max_queue(),
+            queue_timeout_secs: default_queue_timeout_secs(),
+            overrides: HashMap::new(),
+        }
+    }
+}
+
+impl ConcurrencyConfig {
+    /// 指定上游生效的 (最大并发, 最大排队)
+    pub fn limits_for(&self, upstream: &str) -> (usize, usize) {
+        match self.overrides.get(upstream) {
+            Some(o) => (o.max_concurrent.max(1), o.max_queue),
+            None => (self.max_concurrent.max(1), self.max_queue),
+        }
+    }
+}
+
+fn default_max_concurrent() -> usize {
+    16
+}
+
+fn default_max_queue() -> usize {
+    64
+}
+
+fn default_queue_timeout_secs() -> u64 {
+    60
+}
+
+/// 反代服务配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProxyConfig {
+    /// 是否启用反代服务
+    pub enabled: bool,
+
+    /// 是否允许局域网访问
+    /// - false: 仅本机访问 127.0.0.1（默认，隐私优先）
+    /// - true: 允许局域网访问 0.0.0.0
+    #[serde(default)]
+    pub allow_lan_access: bool,
+
+    /// Authorization policy for the proxy.
+    /// - off: no auth required
+    /// - strict: auth required for all routes
+    /// - all_except_health: auth required for all routes except `/healthz`
+    /// - auto: recommended defaults (currently: allow_lan_access => all_except_health, else off)
+    #[serde(default)]
+    pub auth_mode: ProxyAuthMode,
+
+    /// 监听端口
+    pub port: u16,
+
+    /// 端口被占用时改用后续的空闲端口 (实际端口通过 `app://port-fallback` 事件通知前端)
+    #[serde(default)]
+    pub port_fallback: bool,
+
+    /// API 密钥
+    pub api_key: String,
+    
+    /// Web UI 管理后台密码 (可选，如未设置则使用 api_key)
+    pub admin_password: Option<String>,
+
+    /// 额外的管理接口 Token, 按角色限制权限 (admin_password / api_key 始终为 admin)
+    #[serde(default)]
+    pub admin_tokens: Vec<AdminToken>,
+
+    /// 是否自动启动
+    pub auto_start: bool,
+
+    /// 自定义精确模型映射表 (key: 原始模型名, value: 目标模型名)
+    #[serde(default)]
+    pub custom_mapping: std::collections::HashMap<String, String>,
+
+    /// API 请求超时时间(秒)
+    #[serde(default = "default_request_timeout")]
+    pub request_timeout: u64,
+
+    /// 停止服务时等待进行中请求 (含流式响应) 结束的最长时间(秒), 超时后补发终止事件
+    #[serde(default = "default_drain_timeout_secs")]
+    pub drain_timeout_secs: u64,
+
+    /// 是否开启请求日志记录 (监控)
+    #[serde(default)]
+    pub enable_logging: bool,
+
+    /// 调试日志配置 (保存完整链路)
+    #[serde(default)]
+    pub debug_logging: DebugLoggingConfig,
+
+    /// 上游路由与故障转移
+    #[serde(default)]
+    pub upstream_routing: UpstreamRoutingConfig,
+
+    /// 上游连接池
+    #[serde(default)]
+    pub connection_pool: ConnectionPoolConfig,
+
+    /// 上游健康检查
+    #[serde(default)]
+    pub health_check: HealthCheckConfig,
+
+    /// `/v1/models` 聚合
+    #[serde(default)]
+    pub model_catalog: ModelCatalogConfig,
+
+    /// 请求/响应 Body 日志
+    #[serde(default)]
+    pub body_logging: BodyLoggingConfig,
+
+    /// 按端点的请求体大小限制
+    #[serde(default)]
+    pub body_limits: BodyLimitConfig,
+
+    /// 响应压缩
+    #[serde(default)]
+    pub compression: CompressionConfig,
+
+    /// 上游代理配置
+    #[serde(default)]
+    pub upstream_proxy: UpstreamProxyConfig,
+
+    /// 按上游覆盖出站代理, 按顺序匹配
+    #[serde(default)]
+    pub provider_proxies: Vec<ProviderProxyRule>,
+
+    /// 按上游设置 TLS (自定义根证书 / mTLS / 跳过校验)
+    #[serde(default)]
+    pub provider_tls: Vec<ProviderTlsRule>,
+
+    /// 本地监听 HTTPS
+    #[serde(default)]
+    pub listener_tls: ListenerTlsConfig,
+
+    /// 额外监听 (例如局域网地址 + 强制鉴权)
+    #[serde(default)]
+    pub listeners: Vec<ListenerConfig>,
+
+    /// 局域网 mDNS 服务发布
+    #[serde(default)]
+    pub mdns: MdnsConfig,
+
+    /// 上游 API Key / 密码的保存方式 (加密保存在 secrets.json, 配置文件中只保留引用)
+    #[serde(default)]
+    pub secret_storage: SecretStorageMode,
+
+    /// 使用 JWT (SSO / OIDC 签发) 访问代理接口, 按 subject 统计额度
+    #[serde(default)]
+    pub jwt_auth: JwtAuthConfig,
+
+    /// 主监听的 IP 访问控制 (额外监听未单独设置时也使用这组规则)
+    #[serde(default)]
+    pub access_control: AccessControlConfig,
+
+    /// Webhook 事件通知 (上游故障、花费预警、限流、服务重启)
+    #[serde(default)]
+    pub webhooks: WebhookConfig,
+
+    /// 智能路由: 模型别名按延迟 / 错误率 / 费用评分选择上游
+    #[serde(default)]
+    pub smart_routing: SmartRoutingConfig,
+
+    /// 影子流量: 复制部分请求到次要上游并记录对比结果
+    #[serde(default)]
+    pub shadow: ShadowConfig,
+
+    /// 请求对冲: 主请求超时未出首字节时向备用模型发送同一请求, 返回先响应的一方
+    #[serde(default)]
+    pub hedging: HedgingConfig,
+
+    /// 聚合路由: 多个模型回答后由评审模型合并或择优
+    #[serde(default)]
+    pub council: CouncilConfig,
+
+    /// 结构化输出兼容层
+    #[serde(default)]
+    pub structured_output: StructuredOutputConfig,
+
+    /// 函数调用模拟
+    #[serde(default)]
+    pub tool_emulation: ToolEmulationConfig,
+
+    /// Batch API (/v1/batches)
+    #[serde(default)]
+    pub batches: BatchConfig,
+
+    /// Files API (/v1/files)
+    #[serde(default)]
+    pub files: FilesConfig,
+
+    /// Assistants API 兼容层
+    #[serde(default)]
+    pub assistants: AssistantsConfig,
+
+    /// 审核接口与本地分类器
+    #[serde(default)]
+    pub moderation: ModerationConfig,
+
+    /// Rerank 接口
+    #[serde(default)]
+    pub rerank: RerankConfig,
+
+    /// 本地 llama-server 托管
+    #[serde(default)]
+    pub llama_server: LlamaServerConfig,
+
+    /// 配置云同步 (可选, 本地加密后上传)
+    #[serde(default)]
+    pub config_sync: ConfigSyncConfig,
+
+    /// 上游 Key 有效性与余额巡检
+    #[serde(default)]
+    pub key_check: KeyCheckConfig,
+
+    /// z.ai provider configuration (Anthropic-compatible).
+    #[serde(default)]
+    pub zai: ZaiConfig,
+
+    /// 账号调度配置 (粘性会话/限流重试)
+    #[serde(default)]
+    pub scheduling: crate::proxy::sticky_config::StickySessionConfig,
+
+    /// 实验性功能配置
+    #[serde(default)]
+    pub experimental: ExperimentalConfig,
+
+    /// 固定账号模式的账号ID (Fixed Account Mode)
+    /// - None: 使用轮询模式
+    /// - Some(account_id): 固定使用指定账号
+    #[serde(default)]
+    pub preferred_account_id: Option<String>,
+
+    /// 客户端限流配置
+    #[serde(default)]
+    pub client_rate_limit: ClientRateLimitConfig,
+
+    /// 模型别名与改写规则 (在 custom_mapping 之前应用)
+    #[serde(default)]
+    pub model_aliases: Vec<ModelAliasRule>,
+
+    /// 流式响应改写 (兼容不支持非标准字段的客户端)
+    #[serde(default)]
+    pub stream_transform: StreamTransformConfig,
+
+    /// 相同请求的响应缓存
+    #[serde(default)]
+    pub response_cache: ResponseCacheConfig,
+
+    /// 按会话请求头保存对话历史 (服务端会话记忆)
+    #[serde(default)]
+    pub conversation_memory: ConversationMemoryConfig,
+
+    /// Realtime API WebSocket 透传
+    #[serde(default)]
+    pub realtime: RealtimeConfig,
+
+    /// `/v1/embeddings` 的上游映射
+    #[serde(default)]
+    pub embeddings: EmbeddingsConfig,
+
+    /// `/v1/images/generations` 的上游映射与本地保存
+    #[serde(default)]
+    pub images: ImagesConfig,
+
+    /// `/v1/audio/*` 透传到 OpenAI 兼容上游 (Whisper / TTS)
+    #[serde(default)]
+    pub audio: AudioConfig,
+
+    /// 本地 Ollama 模型
+    #[serde(default)]
+    pub ollama: OllamaConfig,
+
+    /// Azure OpenAI 部署
+    #[serde(default)]
+    pub azure_openai: AzureOpenAIConfig,
+
+    /// AWS Bedrock 模型
+    #[serde(default)]
+    pub bedrock: BedrockConfig,
+
+    /// OpenAI 兼容的对话上游 (可基于内置预设)
+    #[serde(default)]
+    pub compatible_upstreams: CompatibleUpstreamsConfig,
+
+    /// 按上游清理请求参数
+    #[serde(default)]
+    pub param_policy: ParamPolicyConfig,
+
+    /// 请求 / 响应内容过滤 (阻止、脱敏或记录敏感内容)
+    #[serde(default)]
+    pub content_filter: ContentFilterConfig,
+
+    /// 发往第三方上游前脱敏邮箱、电话、API Key、卡号
+    #[serde(default)]
+    pub pii_redaction: PiiRedactionConfig,
+
+    /// 按路由或虚拟 Key 注入组织统一的系统提示词
+    #[serde(default)]
+    pub system_prompt: SystemPromptConfig,
+
+    /// 超出模型上下文窗口的请求: 拒绝或截断历史消息
+    #[serde(default)]
+    pub context_window: ContextWindowConfig,
+
+    /// max_tokens 超出模型上限时下调, 必填时自动补上
+    #[serde(default)]
+    pub max_tokens: MaxTokensConfig,
+
+    /// 模型价格表 (用量费用统计与虚拟 Key 月度预算)
+    #[serde(default)]
+    pub pricing: PricingConfig,
+
+    /// 上游并发限制与排队
+    #[serde(default)]
+    pub concurrency: ConcurrencyConfig,
+}
+
+/// 上游代理配置
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct UpstreamProxyConfig {
+    /// 是否启用
+    pub enabled: bool,
+    /// 代理地址 (http://, https://, socks5://)
+    pub url: String,
+    /// 代理认证 (也可以直接写在 URL 中)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+}
+
+/// 上游 TLS 选项 (证书文件在客户端创建时读取, 修改文件后需重新保存配置)
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq, Hash)]
+pub struct UpstreamTlsConfig {
+    /// 额外信任的根证书 (PEM, 可以包含多个证书)
+    #[serde(default)]
+    pub ca_cert_path: Option<String>,
+    /// mTLS 客户端证书 (PEM)
+    #[serde(default)]
+    pub client_cert_path: Option<String>,
+    /// mTLS 客户端私钥 (PKCS#8 PEM)
+    #[serde(default)]
+    pub client_key_path: Option<String>,
+    /// 跳过证书校验, 仅用于实验环境
+    #[serde(default)]
+    pub accept_invalid_certs: bool,
+}
+
+/// 指定上游使用的 TLS 选项
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ProviderTlsRule {
+    /// 上游标识, 与 ProviderProxyRule 相同, 支持 * 通配
+    pub provider: String,
+    #[serde(default)]
+    pub tls: UpstreamTlsConfig,
+}
+
+/// 管理接口角色
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum AdminRole {
+    /// 只读 (不包括含密钥的配置)
+    #[default]
+    Viewer,
+    /// 只读 + 启停服务、清除限流、切换账号等运维操作
+    Operator,
+    /// 全部权限
+    Admin,
+}
+
+/// 管理接口 Token
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AdminToken {
+    /// 名称, 记录在审计日志中
+    pub name: String,
+    pub token: String,
+    #[serde(default)]
+    pub role: AdminRole,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+/// 额外的监听地址 (主监听由 port / allow_lan_access 决定, 重启服务后生效)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListenerConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub name: String,
+    /// 绑定地址, 例如 127.0.0.1、0.0.0.0 或某个局域网 IP
+    pub host: String,
+    pub port: u16,
+    /// 强制校验 API Key (或虚拟 Key), 不受 auth_mode 影响
+    #[serde(default = "default_true")]
+    pub require_auth: bool,
+    /// 是否开放管理接口 (/api) 与 OAuth 回调
+    #[serde(default)]
+    pub allow_admin: bool,
+    /// 使用 listener_tls 中的证书提供 HTTPS
+    #[serde(default)]
+    pub tls: bool,
+    /// 该监听的 IP 访问控制, 未设置时使用 proxy.access_control
+    #[serde(default)]
+    pub access_control: Option<AccessControlConfig>,
+}
+
+impl ListenerConfig {
+    /// 日志与访问控制中使用的监听名称
+    pub fn label(&self) -> String {
+        if self.name.trim().is_empty() {
+            format!("{}:{}", self.host, self.port)
+        } else {
+            self.name.clone()
+        }
+    }
+}
+
+/// 一组 IP 访问规则 (CIDR 或单个地址): deny 优先; allow 非空时只放行其中的地址
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(default)]
+pub struct AccessRules {
+    pub allow: Vec<String>,
+    pub deny: Vec<String>,
+}
+
+/// IP 访问控制, 管理接口 (/api、OAuth 回调) 与推理接口分别配置
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(default)]
+pub struct AccessControlConfig {
+    pub admin: AccessRules,
+    pub inference: AccessRules,
+}
+
+/// Webhook 通知的事件类型
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEventKind {
+    /// 健康检查发现上游不可用
+    UpstreamDown,
+    UpstreamRecovered,
+    /// 虚拟 Key 的月度花费达到预警比例或上限
+    SpendThreshold,
+    /// 客户端触发限流
+    RateLimited,
+    /// 监督任务自动重启了反代服务
+    ServiceRestarted,
+}
+
+impl WebhookEventKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WebhookEventKind::UpstreamDown => "upstream_down",
+            WebhookEventKind::UpstreamRecovered => "upstream_recovered",
+            WebhookEventKind::SpendThreshold => "spend_threshold",
+            WebhookEventKind::RateLimited => "rate_limited",
+            WebhookEventKind::ServiceRestarted => "service_restarted",
+        }
+    }
+}
+
+/// 通知目标的消息格式
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookFormat {
+    /// 原始 JSON 事件
+    #[default]
+    Json,
+    Slack,
+    Discord,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct WebhookTarget {
+    pub name: String,
+    pub url: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub format: WebhookFormat,
+    /// 订阅的事件, 为空表示全部
+    #[serde(default)]
+    pub events: Vec<WebhookEventKind>,
+    /// JSON 格式时用于 HMAC-SHA256 签名 (X-Webhook-Signature), 留空不签名
+    #[serde(default)]
+    pub secret: String,
+}
+
+impl WebhookTarget {
+    pub fn subscribes(&self, kind: WebhookEventKind) -> bool {
+        self.events.is_empty() || self.events.contains(&kind)
+    }
+}
+
+/// Webhook 事件通知
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WebhookConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub targets: Vec<WebhookTarget>,
+    /// 发送失败 (网络错误、429、5xx) 后的重试次数
+    #[serde(default = "default_webhook_max_retries")]
+    pub max_retries: u32,
+    /// 同一事件 (类型 + 对象) 的最小通知间隔 (秒), 避免限流等高频事件刷屏
+    #[serde(default = "default_webhook_cooldown_secs")]
+    pub cooldown_secs: u64,
+    /// 月度花费达到上限的该比例时预警 (0 表示只在达到上限时通知)
+    #[serde(default = "default_webhook_spend_warning_ratio")]
+    pub spend_warning_ratio: f64,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            targets: Vec::new(),
+            max_retries: default_webhook_max_retries(),
+            cooldown_secs: default_webhook_cooldown_secs(),
+            spend_warning_ratio: default_webhook_spend_warning_ratio(),
+        }
+    }
+}
+
+fn default_webhook_max_retries() -> u32 {
+    3
+}
+
+fn default_webhook_cooldown_secs() -> u64 {
+    300
+}
+
+fn default_webhook_spend_warning_ratio() -> f64 {
+    0.8
+}
+
+/// 智能路由的候选: 上游名称 (与健康检查、用量统计中的名称一致) 与发往该上游的模型名
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct SmartRouteCandidate {
+    pub upstream: String,
+    pub model: String,
+}
+
+/// 一个模型别名及其候选上游 (列表顺序用于同分时的优先级)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct SmartRoute {
+    pub alias: String,
+    #[serde(default)]
+    pub candidates: Vec<SmartRouteCandidate>,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+/// 评分权重; 各项指标先在候选之间归一化到 0-1, 加权和最低的候选胜出
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct SmartRoutingWeights {
+    #[serde(default = "default_smart_weight")]
+    pub latency: f64,
+    #[serde(default = "default_smart_weight")]
+    pub error_rate: f64,
+    #[serde(default = "default_smart_cost_weight")]
+    pub cost: f64,
+}
+
+impl Default for SmartRoutingWeights {
+    fn default() -> Self {
+        Self {
+            latency: default_smart_weight(),
+            error_rate: default_smart_weight(),
+            cost: default_smart_cost_weight(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SmartRoutingConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub routes: Vec<SmartRoute>,
+    #[serde(default)]
+    pub weights: SmartRoutingWeights,
+    /// 统计用量数据的时间窗口 (分钟)
+    #[serde(default = "default_smart_window_mins")]
+    pub window_mins: u64,
+    /// 窗口内请求数少于该值时不使用用量数据 (改用健康检查结果)
+    #[serde(default = "default_smart_min_samples")]
+    pub min_samples: u64,
+}
+
+impl Default for SmartRoutingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            routes: Vec::new(),
+            weights: SmartRoutingWeights::default(),
+            window_mins: default_smart_window_mins(),
+            min_samples: default_smart_min_samples(),
+        }
+    }
+}
+
+fn default_smart_weight() -> f64 {
+    1.0
+}
+
+fn default_smart_cost_weight() -> f64 {
+    0.5
+}
+
+fn default_smart_window_mins() -> u64 {
+    60
+}
+
+fn default_smart_min_samples() -> u64 {
+    5
+}
+
+/// 影子流量规则: 按比例把匹配的请求复制一份发往次要目标, 其响应只记录、不返回给客户端
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct ShadowRule {
+    pub name: String,
+    /// 客户端请求的模型 (支持通配符), 为空时匹配全部
+    #[serde(default)]
+    pub models: Vec<String>,
+    /// 复制的请求比例 (0-100)
+    #[serde(default)]
+    pub percent: f64,
+    /// 替换影子请求的模型名, 为空时保持不变
+    #[serde(default)]
+    pub target_model: Option<String>,
+    /// 其他上游 (例如 `https://api.groq.com/openai/v1`), 为空时发往本地反代
+    #[serde(default)]
+    pub base_url: Option<String>,
+    #[serde(default)]
+    pub api_key: Option<String>,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+/// 请求对冲规则: 主请求在 `threshold_ms` 内没有首字节时, 把同一请求发往备用模型
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HedgingRule {
+    pub name: String,
+    /// 客户端请求的模型 (支持通配符), 为空时匹配全部
+    #[serde(default)]
+    pub models: Vec<String>,
+    /// 备用请求使用的模型名, 按本地反代的正常路由分发到对应上游
+    pub backup_model: String,
+    #[serde(default = "default_hedging_threshold_ms")]
+    pub threshold_ms: u64,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+fn default_hedging_threshold_ms() -> u64 {
+    2000
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct HedgingConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub rules: Vec<HedgingRule>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ShadowConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub rules: Vec<ShadowRule>,
+    /// 保留的对比结果条数
+    #[serde(default = "default_shadow_max_results")]
+    pub max_results: usize,
+    /// 影子请求超时 (秒)
+    #[serde(default = "default_shadow_timeout")]
+    pub timeout_secs: u64,
+}
+
+impl Default for ShadowConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            rules: Vec::new(),
+            max_results: default_shadow_max_results(),
+            timeout_secs: default_shadow_timeout(),
+        }
+    }
+}
+
+fn default_shadow_max_results() -> usize {
+    200
+}
+
+fn default_shadow_timeout() -> u64 {
+    120
+}
+
+/// 评审模型合并各成员的回答, 或从中选出最好的一个 (原样返回)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum CouncilStrategy {
+    #[default]
+    Merge,
+    Select,
+}
+
+/// 聚合路由: 客户端以 `name` 作为模型名请求 Chat Completions 时, 由成员模型分别回答、评审模型汇总
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct Council {
+    pub name: String,
+    #[serde(default)]
+    pub members: Vec<String>,
+    pub judge: String,
+    #[serde(default)]
+    pub strategy: CouncilStrategy,
+    /// 替换默认的评审提示词
+    #[serde(default)]
+    pub judge_prompt: Option<String>,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct CouncilConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub councils: Vec<Council>,
+}
+
+/// JSON Schema 结构化输出兼容层: 对不支持原生 `response_format: json_schema` 的上游,
+/// 把 schema 写入提示词, 校验回复并在不符合时带着错误信息重试
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StructuredOutputConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 需要兼容处理的模型 (客户端请求的模型名), 支持 `*` 通配; 为空时处理所有 json_schema 请求
+    #[serde(default)]
+    pub models: Vec<String>,
+    /// 校验失败后的最大重试次数 (0-5)
+    #[serde(default = "default_structured_output_retries")]
+    pub max_retries: u32,
+    /// 修复 `json_object` 请求中被截断或格式错误的回复, 无法修复时返回错误
+    #[serde(default)]
+    pub repair_json_object: bool,
+}
+
+/// 函数调用模拟: 对不支持原生 function calling 的模型, 工具定义转为提示词, 回复中的调用还原为 `tool_calls`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct ToolEmulationConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 需要模拟的模型 (客户端请求的模型名), 支持 `*` 通配
+    #[serde(default)]
+    pub models: Vec<String>,
+}
+
+/// Batch API: JSONL 中的请求经本地反代异步执行, 状态与结果保存在数据目录
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BatchConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 同时执行的请求数 (1-64)
+    #[serde(default = "default_batch_concurrency")]
+    pub concurrency: usize,
+    /// 每分钟最多发出的请求数, 0 表示不限制
+    #[serde(default)]
+    pub requests_per_minute: u32,
+    /// 单个批次的最大请求数
+    #[serde(default = "default_batch_max_requests")]
+    pub max_requests: usize,
+}
+
+fn default_batch_concurrency() -> usize {
+    4
+}
+
+fn default_batch_max_requests() -> usize {
+    50_000
+}
+
+impl Default for BatchConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            concurrency: default_batch_concurrency(),
+            requests_per_minute: 0,
+            max_requests: default_batch_max_requests(),
+        }
+    }
+}
+
+/// Files API: 上传的文件按内容哈希保存在数据目录, 可供批次与多模态请求引用
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FilesConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 单个文件的大小上限 (MB); 上传同样受 ABV_MAX_BODY_SIZE 限制
+    #[serde(default = "default_files_max_file_mb")]
+    pub max_file_mb: u64,
+}
+
+fn default_files_max_file_mb() -> u64 {
+    100
+}
+
+impl Default for FilesConfig {
+    fn default() -> Self {
+        Self { enabled: false, max_file_mb: default_files_max_file_mb() }
+    }
+}
+
+/// Assistants API 兼容层: 助手、线程与 Run 保存在本地, Run 经 Chat Completions 执行
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct AssistantsConfig {
+    #[serde(default)]
+    pub enabled: bool,
+}
+
+/// 本地审核规则
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ModerationRule {
+    /// OpenAI 审核类别 (例如 `violence`、`self-harm/intent`)
+    pub category: String,
+    pub pattern: String,
+    #[serde(default)]
+    pub match_type: ContentFilterMatch,
+    /// 命中时该类别的分数 (0-1)
+    #[serde(default = "default_moderation_score")]
+    pub score: f64,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+fn default_moderation_score() -> f64 {
+    1.0
+}
+
+/// `/v1/moderations`: 模型命中 OpenAI 兼容上游时转发, 否则使用本地关键字 / 正则分类器
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ModerationConfig {
+    /// 启用本地分类器
+    #[serde(default)]
+    pub enabled: bool,
+    /// 使用内置规则, 自定义规则在其后追加
+    #[serde(default = "default_true")]
+    pub builtin_rules: bool,
+    /// 分数达到该值时类别记为命中
+    #[serde(default = "default_moderation_threshold")]
+    pub threshold: f64,
+    #[serde(default)]
+    pub rules: Vec<ModerationRule>,
+}
+
+fn default_moderation_threshold() -> f64 {
+    0.5
+}
+
+impl Default for ModerationConfig {
+    fn default() -> Self {
+        Self { enabled: false, builtin_rules: true, threshold: default_moderation_threshold(), rules: Vec::new() }
+    }
+}
+
+/// 配置同步的存储位置
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfigSyncBackend {
+    #[default]
+    WebDav,
+    S3,
+    Gist,
+}
+
+/// 配置云同步: 配置在本地用口令加密后经 WebDAV / S3 兼容存储 / GitHub Gist 同步, 本节设置不参与同步
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ConfigSyncConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub backend: ConfigSyncBackend,
+    /// WebDAV: 同步文件的完整地址; S3: 服务地址 (例如 `https://s3.us-east-1.amazonaws.com`)
+    #[serde(default)]
+    pub url: String,
+    #[serde(default)]
+    pub username: String,
+    #[serde(default)]
+    pub password: String,
+    #[serde(default)]
+    pub bucket: String,
+    #[serde(default = "default_config_sync_object_key")]
+    pub object_key: String,
+    #[serde(default)]
+    pub region: String,
+    #[serde(default)]
+    pub access_key_id: String,
+    #[serde(default)]
+    pub secret_access_key: String,
+    /// GitHub Token (需要 gist 权限)
+    #[serde(default)]
+    pub token: String,
+    /// 为空时首次上传创建私有 Gist
+    #[serde(default)]
+    pub gist_id: String,
+    /// 加密口令, 只保存在本机; 各台机器需要一致
+    #[serde(default)]
+    pub passphrase: String,
+    /// 自动同步间隔 (分钟), 0 表示只手动同步
+    #[serde(default)]
+    pub interval_minutes: u64,
+}
+
+impl Default for ConfigSyncConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            backend: ConfigSyncBackend::default(),
+            url: String::new(),
+            username: String::new(),
+            password: String::new(),
+            bucket: String::new(),
+            object_key: default_config_sync_object_key(),
+            region: String::new(),
+            access_key_id: String::new(),
+            secret_access_key: String::new(),
+            token: String::new(),
+            gist_id: String::new(),
+            passphrase: String::new(),
+            interval_minutes: 0,
+        }
+    }
+}
+
+fn default_config_sync_object_key() -> String {
+    "aiolauncher/config.sync".to_string()
+}
+
+/// 定期验证已保存的上游 Key 并查询剩余额度 (OpenRouter / DeepSeek)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct KeyCheckConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 巡检间隔 (分钟)
+    #[serde(default = "default_key_check_interval_minutes")]
+    pub interval_minutes: u64,
+    /// 剩余额度低于该值 (美元 / 人民币等上游货币) 时提示
+    #[serde(default = "default_key_check_low_balance")]
+    pub low_balance_threshold: f64,
+    /// Key 在该天数内过期时提示
+    #[serde(default = "default_key_check_expiry_days")]
+    pub expiry_warning_days: u32,
+}
+
+impl Default for KeyCheckConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_minutes: default_key_check_interval_minutes(),
+            low_balance_threshold: default_key_check_low_balance(),
+            expiry_warning_days: default_key_check_expiry_days(),
+        }
+    }
+}
+
+fn default_key_check_interval_minutes() -> u64 {
+    360
+}
+
+fn default_key_check_low_balance() -> f64 {
+    1.0
+}
+
+fn default_key_check_expiry_days() -> u32 {
+    7
+}
+
+/// 本地 llama-server (llama.cpp) 托管: 反代启动时拉起并监控, 崩溃后自动重启
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LlamaServerConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// llama-server 可执行文件路径
+    #[serde(default)]
+    pub binary_path: String,
+    /// GGUF 模型文件路径
+    #[serde(default)]
+    pub model_path: String,
+    /// 客户端请求的模型名, 为空时使用模型文件名 (不含扩展名)
+    #[serde(default)]
+    pub alias: String,
+    /// 只监听 127.0.0.1; 被占用时自动改用后续的空闲端口
+    #[serde(default = "default_llama_server_port")]
+    pub port: u16,
+    #[serde(default = "default_llama_server_ctx_size")]
+    pub ctx_size: u32,
+    /// 卸载到 GPU 的层数 (`-ngl`), 为空时使用 llama-server 默认值
+    #[serde(default)]
+    pub gpu_layers: Option<i32>,
+    #[serde(default)]
+    pub threads: Option<u32>,
+    /// 追加的命令行参数
+    #[serde(default)]
+    pub extra_args: Vec<String>,
+    /// 连续崩溃超过该次数后停止重启
+    #[serde(default = "default_llama_server_max_restarts")]
+    pub max_restarts: u32,
+    /// 首次重启的等待时间, 之后每次加倍 (最多 5 分钟)
+    #[serde(default = "default_llama_server_restart_delay")]
+    pub restart_delay_secs: u64,
+}
+
+fn default_llama_server_port() -> u16 {
+    8091
+}
+
+fn default_llama_server_ctx_size() -> u32 {
+    4096
+}
+
+fn default_llama_server_max_restarts() -> u32 {
+    5
+}
+
+fn default_llama_server_restart_delay() -> u64 {
+    3
+}
+
+impl Default for LlamaServerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            binary_path: String::new(),
+            model_path: String::new(),
+            alias: String::new(),
+            port: default_llama_server_port(),
+            ctx_size: default_llama_server_ctx_size(),
+            gpu_layers: None,
+            threads: None,
+            extra_args: Vec::new(),
+            max_restarts: default_llama_server_max_restarts(),
+            restart_delay_secs: default_llama_server_restart_delay(),
+        }
+    }
+}
+
+fn default_structured_output_retries() -> u32 {
+    2
+}
+
+impl Default for StructuredOutputConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            models: Vec::new(),
+            max_retries: default_structured_output_retries(),
+            repair_json_object: false,
+        }
+    }
+}
+
+/// 通过 mDNS 发布 _aiolauncher._tcp 服务 (仅在允许局域网访问时生效, 重启服务后生效)
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct MdnsConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 服务实例名, 留空时使用 "AIO Launcher (<主机名>)"
+    #[serde(default)]
+    pub service_name: String,
+}
+
+/// JWT 鉴权 (HS256 共享密钥或 RS256 + JWKS)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct JwtAuthConfig {
+    pub enabled: bool,
+    /// 要求的 `iss`, 留空不校验
+    pub issuer: String,
+    /// 要求包含的 `aud`, 留空不校验
+    pub audience: String,
+    /// HS256 共享密钥, 留空时不接受 HS256
+    pub hmac_secret: String,
+    /// RS256 公钥集合地址 (OIDC 的 jwks_uri)
+    pub jwks_url: String,
+    /// JWKS 缓存时间 (秒), 遇到未知 kid 时提前刷新
+    pub jwks_cache_secs: u64,
+    /// 允许的时钟偏差 (秒)
+    pub leeway_secs: u64,
+    /// 作为身份标识的 claim
+    pub subject_claim: String,
+    /// 可用模型列表的 claim (数组或空格分隔的字符串), 缺失时允许所有模型
+    pub models_claim: String,
+    /// 绑定上游账号的 claim, 留空不绑定
+    pub account_claim: String,
+    /// 每个 subject 的额度 (与虚拟 Key 相同的统计方式)
+    pub request_quota: Option<u64>,
+    pub token_quota: Option<u64>,
+    pub quota_period: crate::proxy::virtual_keys::QuotaPeriod,
+    pub monthly_spend_cap: Option<f64>,
+}
+
+impl Default for JwtAuthConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            issuer: String::new(),
+            audience: String::new(),
+            hmac_secret: String::new(),
+            jwks_url: String::new(),
+            jwks_cache_secs: 3600,
+            leeway_secs: 60,
+            subject_claim: "sub".to_string(),
+            models_claim: "allowed_models".to_string(),
+            account_claim: String::new(),
+            request_quota: None,
+            token_quota: None,
+            quota_period: Default::default(),
+            monthly_spend_cap: None,
+        }
+    }
+}
+
+/// 加密主密钥的保存位置
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SecretStorageMode {
+    /// 系统钥匙串, 不可用时使用数据目录中的 secrets.key
+    #[default]
+    Auto,
+    Keychain,
+    File,
+    /// 不加密, 密钥明文写入配置文件
+    Plaintext,
+}
+
+/// 本地监听的 HTTPS 配置 (开关重启服务后生效, 更换证书保存后即对新连接生效)
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ListenerTlsConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 服务端证书链 (PEM)
+    #[serde(default)]
+    pub cert_path: String,
+    /// 服务端私钥 (PKCS#8 PEM)
+    #[serde(default)]
+    pub key_path: String,
+}
+
+/// 指定上游使用的出站代理, 覆盖全局上游代理
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ProviderProxyRule {
+    /// 上游标识: google / zai / azure_openai / bedrock / compat:<名称> / embeddings / images / audio, 支持 * 通配
+    pub provider: String,
+    /// enabled = false 时该上游直连, 不使用全局代理
+    #[serde(default)]
+    pub proxy: UpstreamProxyConfig,
+}
+
+impl Default for ProxyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            allow_lan_access: false, // 默认仅本机访问，隐私优先
+            auth_mode: ProxyAuthMode::default(),
+            port: 8045,
+            port_fallback: false,
+            api_key: format!("sk-{}", uuid::Uuid::new_v4().simple()),
+            admin_password: None,
+            admin_tokens: Vec::new(),
+            auto_start: false,
+            custom_mapping: std::collections::HashMap::new(),
+            request_timeout: default_request_timeout(),
+            drain_timeout_secs: default_drain_timeout_secs(),
+            enable_logging: true, // 默认开启，支持 token 统计功能
+            debug_logging: DebugLoggingConfig::default(),
+            upstream_routing: UpstreamRoutingConfig::default(),
+            connection_pool: ConnectionPoolConfig::default(),
+            health_check: HealthCheckConfig::default(),
+            model_catalog: ModelCatalogConfig::default(),
+            body_logging: BodyLoggingConfig::default(),
+            body_limits: BodyLimitConfig::default(),
+            compression: CompressionConfig::default(),
+            upstream_proxy: UpstreamProxyConfig::default(),
+            provider_proxies: Vec::new(),
+            provider_tls: Vec::new(),
+            listener_tls: ListenerTlsConfig::default(),
+            listeners: Vec::new(),
+            mdns: MdnsConfig::default(),
+            secret_storage: SecretStorageMode::default(),
+            jwt_auth: JwtAuthConfig::default(),
+            access_control: AccessControlConfig::default(),
+            webhooks: WebhookConfig::default(),
+            smart_routing: SmartRoutingConfig::default(),
+            shadow: ShadowConfig::default(),
+            hedging: HedgingConfig::default(),
+            council: CouncilConfig::default(),
+            structured_output: StructuredOutputConfig::default(),
+            tool_emulation: ToolEmulationConfig::default(),
+            batches: BatchConfig::default(),
+            files: FilesConfig::default(),
+            assistants: AssistantsConfig::default(),
+            moderation: ModerationConfig::default(),
+            rerank: RerankConfig::default(),
+            llama_server: LlamaServerConfig::default(),
+            config_sync: ConfigSyncConfig::default(),
+            key_check: KeyCheckConfig::default(),
+            zai: ZaiConfig::default(),
+            scheduling: crate::proxy::sticky_config::StickySessionConfig::default(),
+            experimental: ExperimentalConfig::default(),
+            preferred_account_id: None, // 默认使用轮询模式
+            client_rate_limit: ClientRateLimitConfig::default(),
+            model_aliases: Vec::new(),
+            stream_transform: StreamTransformConfig::default(),
+            response_cache: ResponseCacheConfig::default(),
+            conversation_memory: ConversationMemoryConfig::default(),
+            realtime: RealtimeConfig::default(),
+            embeddings: EmbeddingsConfig::default(),
+            images: ImagesConfig::default(),
+            audio: AudioConfig::default(),
+            ollama: OllamaConfig::default(),
+            azure_openai: AzureOpenAIConfig::default(),
+            bedrock: BedrockConfig::default(),
+            compatible_upstreams: CompatibleUpstreamsConfig::default(),
+            param_policy: ParamPolicyConfig::default(),
+            content_filter: ContentFilterConfig::default(),
+            pii_redaction: PiiRedactionConfig::default(),
+            system_prompt: SystemPromptConfig::default(),
+            context_window: ContextWindowConfig::default(),
+            max_tokens: MaxTokensConfig::default(),
+            pricing: PricingConfig::default(),
+            concurrency: ConcurrencyConfig::default(),
+        }
+    }
+}
+
+fn default_request_timeout() -> u64 {
+    120 // 默认 120 秒,原来 60 秒太短
+}
+
+fn default_drain_timeout_secs() -> u64 {
+    30
 }
 
 fn default_zai_base_url() -> String {
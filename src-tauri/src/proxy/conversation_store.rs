@@ -0,0 +1,317 @@
+// 服务端会话记忆
+// 以 (客户端 API Key 指纹, 会话 ID) 为键保存对话历史; 请求只携带最新消息时由代理在前面补上历史,
+// 成功的响应把助手回复追加进历史。会话在 TTL 内没有更新时过期。
+// 流式响应只保存回复文本, 流式的工具调用不会写入历史。
+
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::proxy::common::context_window::is_clean_start;
+use crate::proxy::common::sse::SseParser;
+use crate::proxy::config::ConversationMemoryConfig;
+
+struct Session {
+    session_id: String,
+    api_key_hint: Option<String>,
+    messages: Vec<Value>,
+    created_at: i64,
+    updated_at: i64,
+    touched: Instant,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConversationSessionInfo {
+    pub id: String,
+    pub session_id: String,
+    pub api_key_hint: Option<String>,
+    pub message_count: usize,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub expires_in_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConversationSessionDetail {
+    #[serde(flatten)]
+    pub info: ConversationSessionInfo,
+    pub messages: Vec<Value>,
+}
+
+/// 会话的存储键; 不同客户端即使使用相同的会话 ID 也互不可见
+pub fn session_key(api_key: Option<&str>, session_id: &str) -> String {
+    let owner = api_key
+        .filter(|k| !k.is_empty())
+        .map(crate::proxy::middleware::auth::api_key_fingerprint)
+        .unwrap_or_else(|| "anonymous".to_string());
+    format!("{}:{}", owner, session_id)
+}
+
+fn is_system(message: &Value) -> bool {
+    matches!(message.get("role").and_then(|r| r.as_str()), Some("system" | "developer"))
+}
+
+/// 把历史插入到请求消息中 (位于开头的 system 消息之后), 返回 (完整消息, 本轮新增的消息)。
+/// 客户端已经发送了完整历史时不重复插入。
+pub fn merge_history(history: &[Value], messages: Vec<Value>) -> (Vec<Value>, Vec<Value>) {
+    let split = messages.iter().take_while(|m| is_system(m)).count();
+    let mut system = messages;
+    let mut turn = system.split_off(split);
+    if turn.len() > history.len() && turn[..history.len()] == *history {
+        turn.drain(..history.len());
+    }
+    let mut full = system;
+    full.extend(history.iter().cloned());
+    full.extend(turn.iter().cloned());
+    (full, turn)
+}
+
+/// 超出上限时从最早的消息开始删除, 保证历史从普通用户消息开始
+fn trim(messages: &mut Vec<Value>, max_messages: usize) {
+    let mut start = messages.len().saturating_sub(max_messages.max(1));
+    while start < messages.len() && !is_clean_start(&messages[start]) {
+        start += 1;
+    }
+    messages.drain(..start);
+}
+
+/// 从非流式响应中取出助手消息 (OpenAI / Anthropic / Gemini)
+pub fn assistant_message(response: &Value) -> Option<Value> {
+    if let Some(message) = response.pointer("/choices/0/message") {
+        return Some(message.clone());
+    }
+    if let Some(content) = response.pointer("/candidates/0/content") {
+        return Some(json!({ "role": "model", "parts": content.get("parts").cloned().unwrap_or(json!([])) }));
+    }
+    if response.get("type").and_then(|t| t.as_str()) == Some("message") {
+        return Some(json!({ "role": "assistant", "content": response.get("content").cloned()? }));
+    }
+    None
+}
+
+/// 从 SSE 响应中拼出助手回复文本; `gemini` 决定生成的消息格式
+pub fn stream_assistant_message(sse: &[u8], gemini: bool) -> Option<Value> {
+    let mut parser = SseParser::new();
+    let mut events = parser.push(sse);
+    events.extend(parser.finish());
+    let mut text = String::new();
+    for event in events {
+        let Ok(json) = serde_json::from_str::<Value>(&event.data) else {
+            continue;
+        };
+        if let Some(delta) = json.pointer("/choices/0/delta/content").and_then(|v| v.as_str()) {
+            text.push_str(delta);
+        } else if let Some(delta) = json.pointer("/delta/text").and_then(|v| v.as_str()) {
+            text.push_str(delta);
+        } else if let Some(parts) = json.pointer("/candidates/0/content/parts").and_then(|p| p.as_array()) {
+            // Gemini 的 thought 部分不写入历史
+            parts
+                .iter()
+                .filter(|p| !p.get("thought").and_then(|t| t.as_bool()).unwrap_or(false))
+                .filter_map(|p| p.get("text").and_then(|t| t.as_str()))
+                .for_each(|t| text.push_str(t));
+        }
+    }
+    if text.is_empty() {
+        return None;
+    }
+    Some(if gemini {
+        json!({ "role": "model", "parts": [{ "text": text }] })
+    } else {
+        json!({ "role": "assistant", "content": text })
+    })
+}
+
+pub struct ConversationStore {
+    config: std::sync::RwLock<ConversationMemoryConfig>,
+    sessions: Mutex<HashMap<String, Session>>,
+}
+
+impl ConversationStore {
+    pub fn new(config: ConversationMemoryConfig) -> Self {
+        Self {
+            config: std::sync::RwLock::new(config),
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn config(&self) -> ConversationMemoryConfig {
+        self.config.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.read().unwrap_or_else(|e| e.into_inner()).enabled
+    }
+
+    pub fn update_config(&self, config: ConversationMemoryConfig) {
+        let enabled = config.enabled;
+        *self.config.write().unwrap_or_else(|e| e.into_inner()) = config;
+        if !enabled {
+            self.clear(None);
+        }
+    }
+
+    fn ttl(&self) -> Duration {
+        Duration::from_secs(self.config().ttl_secs)
+    }
+
+    /// 会话的历史消息, 不存在或已过期时为空
+    pub fn history(&self, key: &str) -> Vec<Value> {
+        self.history_at(key, Instant::now())
+    }
+
+    fn history_at(&self, key: &str, now: Instant) -> Vec<Value> {
+        let ttl = self.ttl();
+        let mut sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+        match sessions.get(key) {
+            Some(session) if now.duration_since(session.touched) < ttl => session.messages.clone(),
+            Some(_) => {
+                sessions.remove(key);
+                Vec::new()
+            }
+            None => Vec::new(),
+        }
+    }
+
+    /// 保存会话的完整历史 (不含 system 消息)
+    pub fn save(&self, key: &str, session_id: &str, api_key_hint: Option<String>, messages: Vec<Value>) {
+        self.save_at(key, session_id, api_key_hint, messages, Instant::now());
+    }
+
+    fn save_at(&self, key: &str, session_id: &str, api_key_hint: Option<String>, mut messages: Vec<Value>, now: Instant) {
+        let config = self.config();
+        if config.max_sessions == 0 {
+            return;
+        }
+        trim(&mut messages, config.max_messages);
+        let ttl = Duration::from_secs(config.ttl_secs);
+        let timestamp = chrono::Utc::now().timestamp_millis();
+        let mut sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+        if sessions.len() >= config.max_sessions && !sessions.contains_key(key) {
+            sessions.retain(|_, s| now.duration_since(s.touched) < ttl);
+            while sessions.len() >= config.max_sessions {
+                let oldest = sessions.iter().min_by_key(|(_, s)| s.touched).map(|(k, _)| k.clone());
+                match oldest {
+                    Some(k) => sessions.remove(&k),
+                    None => break,
+                };
+            }
+        }
+        let session = sessions.entry(key.to_string()).or_insert_with(|| Session {
+            session_id: session_id.to_string(),
+            api_key_hint,
+            messages: Vec::new(),
+            created_at: timestamp,
+            updated_at: timestamp,
+            touched: now,
+        });
+        session.messages = messages;
+        session.updated_at = timestamp;
+        session.touched = now;
+    }
+
+    fn info(key: &str, session: &Session, ttl: Duration, now: Instant) -> ConversationSessionInfo {
+        ConversationSessionInfo {
+            id: key.to_string(),
+            session_id: session.session_id.clone(),
+            api_key_hint: session.api_key_hint.clone(),
+            message_count: session.messages.len(),
+            created_at: session.created_at,
+            updated_at: session.updated_at,
+            expires_in_secs: ttl.saturating_sub(now.duration_since(session.touched)).as_secs(),
+        }
+    }
+
+    /// 未过期的会话, 最近更新的在前
+    pub fn list(&self) -> Vec<ConversationSessionInfo> {
+        let (ttl, now) = (self.ttl(), Instant::now());
+        let mut sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+        sessions.retain(|_, s| now.duration_since(s.touched) < ttl);
+        let mut list: Vec<ConversationSessionInfo> =
+            sessions.iter().map(|(key, session)| Self::info(key, session, ttl, now)).collect();
+        list.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+        list
+    }
+
+    pub fn get(&self, id: &str) -> Option<ConversationSessionDetail> {
+        let (ttl, now) = (self.ttl(), Instant::now());
+        let sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+        let session = sessions.get(id).filter(|s| now.duration_since(s.touched) < ttl)?;
+        Some(ConversationSessionDetail { info: Self::info(id, session, ttl, now), messages: session.messages.clone() })
+    }
+
+    /// 删除指定会话 (None 时删除全部), 返回删除的会话数
+    pub fn clear(&self, id: Option<&str>) -> usize {
+        let mut sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+        match id {
+            Some(id) => sessions.remove(id).map_or(0, |_| 1),
+            None => {
+                let count = sessions.len();
+                sessions.clear();
+                count
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user(text: &str) -> Value {
+        json!({"role": "user", "content": text})
+    }
+
+    fn assistant(text: &str) -> Value {
+        json!({"role": "assistant", "content": text})
+    }
+
+    #[test]
+    fn test_merge_history() {
+        let history = vec![user("hi"), assistant("hello")];
+        let (full, turn) = merge_history(&history, vec![json!({"role": "system", "content": "Be brief."}), user("how are you?")]);
+        assert_eq!(full, vec![json!({"role": "system", "content": "Be brief."}), user("hi"), assistant("hello"), user("how are you?")]);
+        assert_eq!(turn, vec![user("how are you?")]);
+
+        // 客户端发送了完整历史
+        let (full, turn) = merge_history(&history, vec![user("hi"), assistant("hello"), user("again")]);
+        assert_eq!(full.len(), 3);
+        assert_eq!(turn, vec![user("again")]);
+
+        let mut messages = vec![user("1"), assistant("2"), json!({"role": "tool", "content": "x"}), user("3"), assistant("4")];
+        trim(&mut messages, 4);
+        assert_eq!(messages, vec![user("3"), assistant("4")]);
+    }
+
+    #[test]
+    fn test_store_ttl_and_clear() {
+        let store = ConversationStore::new(ConversationMemoryConfig { enabled: true, ttl_secs: 60, ..Default::default() });
+        let now = Instant::now();
+        let key = session_key(Some("sk-test"), "s1");
+        assert_ne!(key, session_key(Some("sk-other"), "s1"));
+        store.save_at(&key, "s1", None, vec![user("hi"), assistant("hello")], now);
+        assert_eq!(store.history_at(&key, now + Duration::from_secs(59)).len(), 2);
+        assert_eq!(store.get(&key).unwrap().info.message_count, 2);
+        assert!(store.history_at(&key, now + Duration::from_secs(60)).is_empty());
+
+        store.save_at(&key, "s1", None, vec![user("hi")], now);
+        assert_eq!(store.list().len(), 1);
+        assert_eq!(store.clear(Some(&key)), 1);
+        assert_eq!(store.clear(None), 0);
+    }
+
+    #[test]
+    fn test_assistant_from_responses() {
+        let openai = json!({"choices": [{"message": {"role": "assistant", "content": "hi"}}]});
+        assert_eq!(assistant_message(&openai), Some(assistant("hi")));
+        let claude = json!({"type": "message", "content": [{"type": "text", "text": "hi"}]});
+        assert_eq!(assistant_message(&claude).unwrap()["content"][0]["text"], "hi");
+
+        let sse = b"data: {\"choices\":[{\"delta\":{\"content\":\"Hel\"}}]}\n\ndata: {\"choices\":[{\"delta\":{\"content\":\"lo\"}}]}\n\ndata: [DONE]\n\n";
+        assert_eq!(stream_assistant_message(sse, false), Some(assistant("Hello")));
+        let gemini = b"data: {\"candidates\":[{\"content\":{\"parts\":[{\"text\":\"Hi\"}]}}]}\n\n";
+        assert_eq!(stream_assistant_message(gemini, true).unwrap()["parts"][0]["text"], "Hi");
+    }
+}
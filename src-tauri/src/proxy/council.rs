@@ -0,0 +1,239 @@
+// 聚合路由 (council)
+// 客户端以聚合名称作为模型请求 Chat Completions 时, 先把请求并行发往各成员模型, 再由评审模型
+// 合并各回答或从中选出最好的一个, 以单个 OpenAI 兼容响应返回, `council` 字段附带各成员的状态、耗时与费用。
+
+use std::sync::{OnceLock, RwLock};
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::proxy::compare::{CompareRequest, CompareResult, CompareTarget};
+use crate::proxy::config::{Council, CouncilConfig, CouncilStrategy, ModelPrice};
+
+/// 成员与评审请求在日志与用量中显示的客户端名称; 带该名称的请求不会再次进入聚合路由
+pub const CLIENT_NAME: &str = "council";
+
+const MERGE_PROMPT: &str = "You are the judge of a council of AI models. Several models answered the same conversation. \
+Combine their answers into the single best response to the user's last message: keep what is correct, fix mistakes and drop repetition. \
+Reply with the final answer only, without mentioning the council or the individual answers.";
+
+const SELECT_PROMPT: &str = "You are the judge of a council of AI models. Several models answered the same conversation. \
+Pick the answer that best responds to the user's last message. Reply with the number of the chosen answer only.";
+
+/// 响应中的成员信息
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct CouncilMember {
+    pub model: String,
+    pub mapped_model: Option<String>,
+    pub status: Option<u16>,
+    pub duration: u64, // ms
+    pub input_tokens: Option<u32>,
+    pub output_tokens: Option<u32>,
+    pub cost: Option<f64>,
+    pub error: Option<String>,
+    /// select 模式下被选中的回答
+    pub selected: bool,
+}
+
+impl From<&CompareResult> for CouncilMember {
+    fn from(r: &CompareResult) -> Self {
+        Self {
+            model: r.model.clone(),
+            mapped_model: r.mapped_model.clone(),
+            status: r.status,
+            duration: r.duration,
+            input_tokens: r.input_tokens,
+            output_tokens: r.output_tokens,
+            cost: r.cost,
+            error: r.error.clone(),
+            selected: false,
+        }
+    }
+}
+
+fn config() -> &'static RwLock<CouncilConfig> {
+    static CONFIG: OnceLock<RwLock<CouncilConfig>> = OnceLock::new();
+    CONFIG.get_or_init(|| RwLock::new(CouncilConfig::default()))
+}
+
+/// 应用聚合路由配置 (服务启动与配置热更新时调用)
+pub fn configure(council: &CouncilConfig) {
+    *config().write().unwrap_or_else(|e| e.into_inner()) = council.clone();
+}
+
+pub fn is_enabled() -> bool {
+    let config = config().read().unwrap_or_else(|e| e.into_inner());
+    config.enabled && config.councils.iter().any(|c| c.enabled)
+}
+
+/// 名称与请求模型一致的聚合路由
+pub fn find(model: &str) -> Option<Council> {
+    let config = config().read().unwrap_or_else(|e| e.into_inner());
+    if !config.enabled {
+        return None;
+    }
+    config.councils.iter().find(|c| c.enabled && c.name == model && !c.members.is_empty()).cloned()
+}
+
+/// 消息内容中的文本 (字符串或 content parts 中的 text)
+fn message_text(content: &Value) -> String {
+    match content {
+        Value::String(s) => s.clone(),
+        Value::Array(parts) => parts
+            .iter()
+            .filter_map(|p| p.get("text").and_then(Value::as_str))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+/// 发给评审模型的消息: 原始对话与编号后的各成员回答
+fn judge_messages(council: &Council, messages: &[Value], answers: &[&str]) -> Vec<Value> {
+    let system = council.judge_prompt.as_deref().filter(|p| !p.trim().is_empty()).unwrap_or(match council.strategy {
+        CouncilStrategy::Merge => MERGE_PROMPT,
+        CouncilStrategy::Select => SELECT_PROMPT,
+    });
+    let transcript: Vec<String> = messages
+        .iter()
+        .map(|m| {
+            let role = m.get("role").and_then(Value::as_str).unwrap_or("user");
+            format!("{}: {}", role, message_text(m.get("content").unwrap_or(&Value::Null)))
+        })
+        .collect();
+    let answers: Vec<String> =
+        answers.iter().enumerate().map(|(i, a)| format!("### Answer {}\n{}", i + 1, a)).collect();
+    vec![
+        json!({"role": "system", "content": system}),
+        json!({"role": "user", "content": format!("Conversation:\n{}\n\nAnswers:\n\n{}", transcript.join("\n"), answers.join("\n\n"))}),
+    ]
+}
+
+/// select 模式: 评审回复中的第一个有效编号 (从 1 开始), 转为下标
+fn parse_selection(reply: &str, count: usize) -> Option<usize> {
+    reply
+        .split(|c: char| !c.is_ascii_digit())
+        .filter_map(|s| s.parse::<usize>().ok())
+        .find(|n| (1..=count).contains(n))
+        .map(|n| n - 1)
+}
+
+fn sum_tokens(results: &[&CompareResult]) -> (u32, u32) {
+    results.iter().fold((0, 0), |(i, o), r| (i + r.input_tokens.unwrap_or(0), o + r.output_tokens.unwrap_or(0)))
+}
+
+/// 执行聚合: 成员回答 -> 评审 -> OpenAI Chat Completions 响应
+pub async fn run(
+    council: &Council,
+    mut body: Value,
+    target: CompareTarget,
+    prices: &[ModelPrice],
+    timeout_secs: u64,
+) -> Result<Value, String> {
+    if let Some(obj) = body.as_object_mut() {
+        obj.remove("stream");
+        obj.remove("stream_options");
+    }
+    let messages = body.get("messages").and_then(Value::as_array).cloned().unwrap_or_default();
+    let request = CompareRequest { models: council.members.clone(), request: body };
+    let results = crate::proxy::compare::compare(request, target.clone(), prices, timeout_secs).await?;
+    let answered: Vec<(usize, &str)> = results
+        .iter()
+        .enumerate()
+        .filter_map(|(i, r)| r.error.is_none().then_some(r.content.as_deref()).flatten().map(|c| (i, c)))
+        .collect();
+    if answered.is_empty() {
+        let errors: Vec<String> =
+            results.iter().map(|r| format!("{}: {}", r.model, r.error.as_deref().unwrap_or("empty reply"))).collect();
+        return Err(format!("All council members failed ({})", errors.join("; ")));
+    }
+
+    let answers: Vec<&str> = answered.iter().map(|(_, c)| *c).collect();
+    let judge_request = CompareRequest {
+        models: vec![council.judge.clone()],
+        request: json!({ "messages": judge_messages(council, &messages, &answers) }),
+    };
+    let judge = crate::proxy::compare::compare(judge_request, target, prices, timeout_secs)
+        .await?
+        .pop()
+        .ok_or("Judge returned no result")?;
+    let judge_reply = judge.content.as_deref().filter(|_| judge.error.is_none());
+
+    let mut members: Vec<CouncilMember> = results.iter().map(CouncilMember::from).collect();
+    // 评审失败或选择无法解析时退回第一个成功的回答
+    let content = match (council.strategy, judge_reply) {
+        (CouncilStrategy::Merge, Some(reply)) => reply.to_string(),
+        (CouncilStrategy::Select, reply) => {
+            let choice = reply.and_then(|r| parse_selection(r, answered.len())).unwrap_or(0);
+            members[answered[choice].0].selected = true;
+            answered[choice].1.to_string()
+        }
+        (CouncilStrategy::Merge, None) => answered[0].1.to_string(),
+    };
+
+    let all: Vec<&CompareResult> = results.iter().chain(std::iter::once(&judge)).collect();
+    let (input_tokens, output_tokens) = sum_tokens(&all);
+    let total_cost: f64 = all.iter().filter_map(|r| r.cost).sum();
+    tracing::info!(
+        "[Council] {}: {}/{} members answered, judge {} {}",
+        council.name,
+        answered.len(),
+        results.len(),
+        council.judge,
+        if judge_reply.is_some() { "ok" } else { "failed" }
+    );
+    Ok(json!({
+        "id": format!("chatcmpl-council-{}", uuid::Uuid::new_v4().simple()),
+        "object": "chat.completion",
+        "created": chrono::Utc::now().timestamp(),
+        "model": council.name,
+        "choices": [{
+            "index": 0,
+            "message": { "role": "assistant", "content": content },
+            "finish_reason": "stop"
+        }],
+        "usage": {
+            "prompt_tokens": input_tokens,
+            "completion_tokens": output_tokens,
+            "total_tokens": input_tokens + output_tokens
+        },
+        "council": {
+            "strategy": council.strategy,
+            "members": members,
+            "judge": CouncilMember::from(&judge),
+            "total_cost": total_cost
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_judge_messages_and_selection() {
+        let council = Council {
+            name: "council".to_string(),
+            members: vec!["a".to_string(), "b".to_string()],
+            judge: "judge".to_string(),
+            strategy: CouncilStrategy::Select,
+            ..Default::default()
+        };
+        let messages = vec![
+            json!({"role": "system", "content": "Be brief"}),
+            json!({"role": "user", "content": [{"type": "text", "text": "2+2?"}, {"type": "image_url", "image_url": {}}]}),
+        ];
+        let judge = judge_messages(&council, &messages, &["4", "5"]);
+        assert_eq!(judge[0]["content"], SELECT_PROMPT);
+        assert_eq!(
+            judge[1]["content"],
+            "Conversation:\nsystem: Be brief\nuser: 2+2?\n\nAnswers:\n\n### Answer 1\n4\n\n### Answer 2\n5"
+        );
+        let custom = Council { judge_prompt: Some("Pick one".to_string()), ..council };
+        assert_eq!(judge_messages(&custom, &messages, &["4"])[0]["content"], "Pick one");
+
+        assert_eq!(parse_selection("Answer 2", 3), Some(1));
+        assert_eq!(parse_selection("7, then 1", 3), Some(0));
+        assert_eq!(parse_selection("none", 3), None);
+    }
+}
@@ -0,0 +1,345 @@
+// Files API
+// 本地实现 OpenAI `/v1/files`: 内容按 SHA-256 存放在 `<data_dir>/files/blobs`, 相同内容只保存一份,
+// 文件记录 (所有者、文件名、用途) 保存在 `files.json`。文件只对上传它的 API Key 可见。
+// 批次可以通过 `input_file_id` 引用上传的 JSONL, 结果同样写入文件存储;
+// Chat Completions / Responses 请求中引用 `file_id` 的内容块在转发前展开为 data URL。
+
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock, RwLock};
+
+use base64::Engine as _;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+use crate::proxy::config::FilesConfig;
+
+const STORE_DIR: &str = "files";
+const INDEX_FILE: &str = "files.json";
+const BLOB_DIR: &str = "blobs";
+/// 允许上传的用途
+pub const PURPOSES: &[&str] = &["assistants", "batch", "fine-tune", "vision", "user_data", "evals"];
+/// 批次结果文件的用途 (只能由批次生成)
+pub const BATCH_OUTPUT_PURPOSE: &str = "batch_output";
+
+/// OpenAI 格式的文件对象
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FileObject {
+    pub id: String,
+    pub object: String,
+    pub bytes: u64,
+    pub created_at: i64,
+    pub filename: String,
+    pub purpose: String,
+    pub status: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredFile {
+    owner: String,
+    sha256: String,
+    #[serde(flatten)]
+    file: FileObject,
+}
+
+/// 写入中的上传: 边写临时文件边计算哈希
+pub struct Upload {
+    path: PathBuf,
+    file: std::fs::File,
+    hasher: Sha256,
+    bytes: u64,
+    limit: u64,
+}
+
+impl Upload {
+    pub fn write(&mut self, chunk: &[u8]) -> Result<(), String> {
+        self.bytes += chunk.len() as u64;
+        if self.bytes > self.limit {
+            return Err(format!("File exceeds the {} MB limit", self.limit / 1024 / 1024));
+        }
+        self.hasher.update(chunk);
+        self.file.write_all(chunk).map_err(|e| format!("Failed to write upload: {}", e))
+    }
+}
+
+impl Drop for Upload {
+    fn drop(&mut self) {
+        // 已提交的上传已被移走, 这里只清理中断的临时文件
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// 按扩展名判断 MIME 类型
+pub fn mime_type(filename: &str) -> &'static str {
+    let ext = filename.rsplit('.').next().unwrap_or_default().to_ascii_lowercase();
+    match ext.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "pdf" => "application/pdf",
+        "json" => "application/json",
+        "jsonl" => "application/x-ndjson",
+        "txt" | "md" => "text/plain",
+        "csv" => "text/csv",
+        _ => "application/octet-stream",
+    }
+}
+
+pub struct FileStore {
+    dir: PathBuf,
+    files: Mutex<Vec<StoredFile>>,
+    config: RwLock<FilesConfig>,
+}
+
+impl FileStore {
+    fn new(dir: PathBuf) -> Self {
+        let files = std::fs::read_to_string(dir.join(INDEX_FILE))
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default();
+        Self { dir, files: Mutex::new(files), config: RwLock::new(FilesConfig::default()) }
+    }
+
+    /// Global singleton instance, persisted to `<data_dir>/files`
+    pub fn global() -> &'static FileStore {
+        static INSTANCE: OnceLock<FileStore> = OnceLock::new();
+        INSTANCE.get_or_init(|| {
+            let dir = crate::modules::account::get_data_dir()
+                .unwrap_or_else(|_| std::env::temp_dir())
+                .join(STORE_DIR);
+            FileStore::new(dir)
+        })
+    }
+
+    pub fn config(&self) -> FilesConfig {
+        self.config.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    fn blob_path(&self, sha256: &str) -> PathBuf {
+        self.dir.join(BLOB_DIR).join(sha256)
+    }
+
+    fn save(&self, files: &[StoredFile]) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(files).map_err(|e| format!("failed_to_serialize_files: {}", e))?;
+        std::fs::write(self.dir.join(INDEX_FILE), content).map_err(|e| format!("failed_to_save_files: {}", e))
+    }
+
+    /// 开始一个上传, 大小超过配置的上限时写入失败
+    pub fn begin_upload(&self) -> Result<Upload, String> {
+        let blobs = self.dir.join(BLOB_DIR);
+        std::fs::create_dir_all(&blobs).map_err(|e| format!("Failed to create file store: {}", e))?;
+        let path = blobs.join(format!(".upload-{}", uuid::Uuid::new_v4().simple()));
+        let file = std::fs::File::create(&path).map_err(|e| format!("Failed to create upload: {}", e))?;
+        let limit = self.config().max_file_mb.max(1).saturating_mul(1024 * 1024);
+        Ok(Upload { path, file, hasher: Sha256::new(), bytes: 0, limit })
+    }
+
+    /// 完成上传并登记文件; 相同内容已存在时复用
+    pub fn commit(&self, owner: &str, filename: &str, purpose: &str, upload: Upload) -> Result<FileObject, String> {
+        let sha256 = format!("{:x}", upload.hasher.clone().finalize());
+        let blob = self.blob_path(&sha256);
+        // 与 delete 共用同一把锁: 检查内容是否已存在到登记完成之间, 其他请求不能删除该内容
+        let mut files = self.files.lock().unwrap_or_else(|e| e.into_inner());
+        if !blob.exists() {
+            std::fs::rename(&upload.path, &blob).map_err(|e| format!("Failed to store file: {}", e))?;
+        }
+        let file = FileObject {
+            id: format!("file-{}", uuid::Uuid::new_v4().simple()),
+            object: "file".to_string(),
+            bytes: upload.bytes,
+            created_at: chrono::Utc::now().timestamp(),
+            filename: filename.to_string(),
+            purpose: purpose.to_string(),
+            status: "processed".to_string(),
+        };
+        files.push(StoredFile { owner: owner.to_string(), sha256, file: file.clone() });
+        self.save(&files)?;
+        tracing::info!("[Files] Stored {} ({}, {} bytes)", file.id, file.filename, file.bytes);
+        Ok(file)
+    }
+
+    /// 登记内存中的内容 (批次结果等)
+    pub fn put(&self, owner: &str, filename: &str, purpose: &str, data: &[u8]) -> Result<FileObject, String> {
+        let mut upload = self.begin_upload()?;
+        upload.limit = u64::MAX;
+        upload.write(data)?;
+        self.commit(owner, filename, purpose, upload)
+    }
+
+    pub fn get(&self, owner: &str, id: &str) -> Option<FileObject> {
+        let files = self.files.lock().unwrap_or_else(|e| e.into_inner());
+        files.iter().find(|f| f.file.id == id && f.owner == owner).map(|f| f.file.clone())
+    }
+
+    /// 按创建时间排序 (`desc` 时最新的在前); `after` 为上一页最后一个文件的 id, 返回 (文件, 是否还有更多)
+    pub fn list(&self, owner: &str, purpose: Option<&str>, limit: usize, after: Option<&str>, desc: bool) -> (Vec<FileObject>, bool) {
+        let limit = limit.clamp(1, 10_000);
+        let files = self.files.lock().unwrap_or_else(|e| e.into_inner());
+        let mut owned: Vec<&FileObject> = files
+            .iter()
+            .filter(|f| f.owner == owner && purpose.is_none_or(|p| f.file.purpose == p))
+            .map(|f| &f.file)
+            .collect();
+        if desc {
+            owned.reverse();
+        }
+        let mut ordered = owned.into_iter();
+        if let Some(after) = after {
+            ordered.by_ref().find(|f| f.id == after);
+        }
+        let mut page: Vec<FileObject> = ordered.take(limit + 1).cloned().collect();
+        let has_more = page.len() > limit;
+        page.truncate(limit);
+        (page, has_more)
+    }
+
+    /// 文件内容
+    pub fn content(&self, owner: &str, id: &str) -> Result<(FileObject, Vec<u8>), String> {
+        let (file, sha256) = {
+            let files = self.files.lock().unwrap_or_else(|e| e.into_inner());
+            let stored = files
+                .iter()
+                .find(|f| f.file.id == id && f.owner == owner)
+                .ok_or_else(|| format!("No file found with id '{}'", id))?;
+            (stored.file.clone(), stored.sha256.clone())
+        };
+        let data = std::fs::read(self.blob_path(&sha256)).map_err(|e| format!("Failed to read file {}: {}", id, e))?;
+        Ok((file, data))
+    }
+
+    /// 删除文件记录; 没有其他记录引用时一并删除内容
+    pub fn delete(&self, owner: &str, id: &str) -> Result<bool, String> {
+        let mut files = self.files.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(index) = files.iter().position(|f| f.file.id == id && f.owner == owner) else {
+            return Ok(false);
+        };
+        let removed = files.remove(index);
+        self.save(&files)?;
+        if !files.iter().any(|f| f.sha256 == removed.sha256) {
+            let _ = std::fs::remove_file(self.blob_path(&removed.sha256));
+        }
+        tracing::info!("[Files] Deleted {}", id);
+        Ok(true)
+    }
+
+    fn data_url(&self, owner: &str, id: &str) -> Result<(FileObject, String), String> {
+        let (file, data) = self.content(owner, id)?;
+        let encoded = base64::engine::general_purpose::STANDARD.encode(data);
+        let url = format!("data:{};base64,{}", mime_type(&file.filename), encoded);
+        Ok((file, url))
+    }
+
+    /// 把内容块中的 `file_id` 引用展开为 data URL, 返回展开的数量:
+    /// Chat 的 `{"type":"file","file":{"file_id"}}` (图片转为 `image_url`),
+    /// Responses 的 `{"type":"input_image","file_id"}` 与 `{"type":"input_file","file_id"}`
+    pub fn inline_references(&self, owner: &str, body: &mut Value) -> Result<usize, String> {
+        let mut inlined = 0;
+        let mut messages: Vec<&mut Value> = Vec::new();
+        if let Some(obj) = body.as_object_mut() {
+            for (key, value) in obj.iter_mut() {
+                if key == "messages" || key == "input" {
+                    messages.extend(value.as_array_mut().into_iter().flatten());
+                }
+            }
+        }
+        for message in messages {
+            let Some(parts) = message.get_mut("content").and_then(Value::as_array_mut) else {
+                continue;
+            };
+            for part in parts {
+                let kind = part.get("type").and_then(Value::as_str).unwrap_or_default();
+                let file_id = match kind {
+                    "file" => part.pointer("/file/file_id"),
+                    "input_image" | "input_file" => part.get("file_id"),
+                    _ => None,
+                };
+                let Some(file_id) = file_id.and_then(Value::as_str).map(String::from) else {
+                    continue;
+                };
+                let (file, url) = self.data_url(owner, &file_id)?;
+                let is_image = mime_type(&file.filename).starts_with("image/");
+                *part = match kind {
+                    "file" if is_image => json!({ "type": "image_url", "image_url": { "url": url } }),
+                    "file" => json!({ "type": "file", "file": { "filename": file.filename, "file_data": url } }),
+                    "input_image" => {
+                        let detail = part.get("detail").cloned().unwrap_or(json!("auto"));
+                        json!({ "type": "input_image", "image_url": url, "detail": detail })
+                    }
+                    _ => json!({ "type": "input_file", "filename": file.filename, "file_data": url }),
+                };
+                inlined += 1;
+            }
+        }
+        Ok(inlined)
+    }
+}
+
+/// 应用 Files API 配置 (服务启动与配置热更新时调用)
+pub fn configure(config: &FilesConfig) {
+    *FileStore::global().config.write().unwrap_or_else(|e| e.into_inner()) = config.clone();
+}
+
+/// 请求体中是否可能有文件引用 (避免解析所有请求)
+pub fn may_reference_files(body: &[u8]) -> bool {
+    body.windows(9).any(|w| w == b"\"file_id\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_store() -> (FileStore, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("file_store_test_{}", uuid::Uuid::new_v4().simple()));
+        (FileStore::new(dir.clone()), dir)
+    }
+
+    #[test]
+    fn test_store_dedups_and_deletes() {
+        let (store, dir) = temp_store();
+        let a = store.put("owner", "a.jsonl", "batch", b"same").unwrap();
+        let b = store.put("owner", "b.jsonl", "batch", b"same").unwrap();
+        let other = store.put("other", "c.txt", "user_data", b"different").unwrap();
+        assert_eq!(std::fs::read_dir(dir.join(BLOB_DIR)).unwrap().count(), 2);
+        assert!(store.get("other", &a.id).is_none());
+
+        let (page, has_more) = store.list("owner", Some("batch"), 1, None, true);
+        assert_eq!((page[0].id.as_str(), has_more), (b.id.as_str(), true));
+        assert_eq!(store.list("owner", None, 10, Some(&b.id), true).0[0].id, a.id);
+
+        // 重新加载后内容仍在; 最后一个引用删除后内容一并删除
+        let store = FileStore::new(dir.clone());
+        assert!(store.delete("owner", &a.id).unwrap());
+        assert_eq!(store.content("owner", &b.id).unwrap().1, b"same");
+        assert!(store.delete("owner", &b.id).unwrap());
+        assert!(!store.delete("owner", &b.id).unwrap());
+        assert_eq!(std::fs::read_dir(dir.join(BLOB_DIR)).unwrap().count(), 1);
+        assert_eq!(store.content("other", &other.id).unwrap().1, b"different");
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_inline_references() {
+        let (store, dir) = temp_store();
+        let image = store.put("owner", "cat.png", "vision", b"png").unwrap();
+        let doc = store.put("owner", "doc.pdf", "user_data", b"pdf").unwrap();
+        let mut body = json!({"messages": [{"role": "user", "content": [
+            {"type": "text", "text": "describe"},
+            {"type": "file", "file": {"file_id": image.id}},
+            {"type": "file", "file": {"file_id": doc.id}}
+        ]}]});
+        assert!(may_reference_files(body.to_string().as_bytes()));
+        assert_eq!(store.inline_references("owner", &mut body).unwrap(), 2);
+        assert_eq!(body["messages"][0]["content"][1]["image_url"]["url"], "data:image/png;base64,cG5n");
+        assert_eq!(body["messages"][0]["content"][2]["file"]["file_data"], "data:application/pdf;base64,cGRm");
+
+        let mut responses = json!({"input": [{"role": "user", "content": [{"type": "input_image", "file_id": image.id}]}]});
+        assert_eq!(store.inline_references("owner", &mut responses).unwrap(), 1);
+        assert_eq!(responses["input"][0]["content"][0]["image_url"], "data:image/png;base64,cG5n");
+        // 其他 Key 不能引用
+        let mut foreign = json!({"input": [{"role": "user", "content": [{"type": "input_file", "file_id": doc.id}]}]});
+        assert!(store.inline_references("other", &mut foreign).is_err());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,234 @@
+// Assistants API 处理器 (OpenAI `/v1/assistants`、`/v1/threads` 兼容)
+// 数据只对创建它的 API Key 可见; Run 使用本次请求的 Key 在后台执行
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::proxy::assistants::{
+    AssistantRequest, AssistantStore, AssistantsError, ListQuery, MessageRequest, Run, RunRequest, ThreadAndRunRequest,
+    ThreadRequest, ToolOutput,
+};
+use crate::proxy::server::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct ThreadUpdate {
+    pub metadata: Option<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ToolOutputsRequest {
+    #[serde(default)]
+    pub tool_outputs: Vec<ToolOutput>,
+}
+
+fn error(status: StatusCode, code: &str, message: String) -> Response {
+    let body = json!({ "error": { "message": message, "type": "invalid_request_error", "code": code } });
+    (status, Json(body)).into_response()
+}
+
+fn respond<T: Serialize>(result: Result<T, AssistantsError>) -> Response {
+    match result {
+        Ok(value) => Json(value).into_response(),
+        Err(AssistantsError::NotFound(message)) => error(StatusCode::NOT_FOUND, "not_found", message),
+        Err(AssistantsError::Invalid(message)) => error(StatusCode::BAD_REQUEST, "invalid_request", message),
+    }
+}
+
+fn list<T: Serialize>(result: Result<(Vec<T>, bool), AssistantsError>, id: impl Fn(&T) -> &str) -> Response {
+    respond(result.map(|(items, has_more)| {
+        json!({
+            "object": "list",
+            "first_id": items.first().map(&id),
+            "last_id": items.last().map(&id),
+            "has_more": has_more,
+            "data": items,
+        })
+    }))
+}
+
+fn deleted(result: Result<(), AssistantsError>, id: String, object: &str) -> Response {
+    respond(result.map(|_| json!({ "id": id, "object": format!("{}.deleted", object), "deleted": true })))
+}
+
+fn parse<T: DeserializeOwned>(body: Value) -> Result<T, String> {
+    serde_json::from_value(body).map_err(|e| format!("Invalid request body: {}", e))
+}
+
+/// 调用方的所有者与 Key; 未启用时按不存在的接口处理
+struct Caller {
+    owner: String,
+    api_key: Option<String>,
+}
+
+fn caller(headers: &HeaderMap) -> Option<Caller> {
+    if !AssistantStore::global().config().enabled {
+        return None;
+    }
+    let api_key = crate::proxy::middleware::auth::extract_api_key(headers).filter(|k| !k.is_empty());
+    Some(Caller { owner: crate::proxy::middleware::auth::key_owner(api_key), api_key: api_key.map(String::from) })
+}
+
+/// 创建成功的 Run 开始在后台执行
+fn start(state: &AppState, caller: Caller, result: Result<Run, AssistantsError>) -> Response {
+    if let Ok(run) = &result {
+        AssistantStore::global().spawn_step(run.id.clone(), caller.api_key, state.port, state.request_timeout);
+    }
+    respond(result)
+}
+
+macro_rules! caller {
+    ($headers:expr) => {
+        match caller(&$headers) {
+            Some(caller) => caller,
+            None => return error(StatusCode::NOT_FOUND, "not_found", "Assistants API is not enabled".to_string()),
+        }
+    };
+}
+
+macro_rules! parse {
+    ($body:expr) => {
+        match parse($body) {
+            Ok(request) => request,
+            Err(e) => return error(StatusCode::BAD_REQUEST, "invalid_request", e),
+        }
+    };
+}
+
+// ===== Assistants =====
+
+pub async fn handle_create_assistant(headers: HeaderMap, Json(body): Json<Value>) -> Response {
+    let caller = caller!(headers);
+    let request: AssistantRequest = parse!(body);
+    respond(AssistantStore::global().create_assistant(&caller.owner, request))
+}
+
+pub async fn handle_list_assistants(headers: HeaderMap, Query(query): Query<ListQuery>) -> Response {
+    let caller = caller!(headers);
+    list(Ok(AssistantStore::global().list_assistants(&caller.owner, &query)), |a| &a.id)
+}
+
+pub async fn handle_get_assistant(headers: HeaderMap, Path(assistant_id): Path<String>) -> Response {
+    let caller = caller!(headers);
+    respond(AssistantStore::global().get_assistant(&caller.owner, &assistant_id))
+}
+
+pub async fn handle_update_assistant(
+    headers: HeaderMap,
+    Path(assistant_id): Path<String>,
+    Json(body): Json<Value>,
+) -> Response {
+    let caller = caller!(headers);
+    let request: AssistantRequest = parse!(body);
+    respond(AssistantStore::global().update_assistant(&caller.owner, &assistant_id, request))
+}
+
+pub async fn handle_delete_assistant(headers: HeaderMap, Path(assistant_id): Path<String>) -> Response {
+    let caller = caller!(headers);
+    let result = AssistantStore::global().delete_assistant(&caller.owner, &assistant_id);
+    deleted(result, assistant_id, "assistant")
+}
+
+// ===== Threads =====
+
+pub async fn handle_create_thread(headers: HeaderMap, body: Option<Json<Value>>) -> Response {
+    let caller = caller!(headers);
+    let request: ThreadRequest = parse!(body.map(|b| b.0).unwrap_or_else(|| json!({})));
+    respond(AssistantStore::global().create_thread(&caller.owner, request))
+}
+
+pub async fn handle_get_thread(headers: HeaderMap, Path(thread_id): Path<String>) -> Response {
+    let caller = caller!(headers);
+    respond(AssistantStore::global().get_thread(&caller.owner, &thread_id))
+}
+
+pub async fn handle_update_thread(headers: HeaderMap, Path(thread_id): Path<String>, Json(body): Json<Value>) -> Response {
+    let caller = caller!(headers);
+    let update: ThreadUpdate = parse!(body);
+    respond(AssistantStore::global().update_thread(&caller.owner, &thread_id, update.metadata))
+}
+
+pub async fn handle_delete_thread(headers: HeaderMap, Path(thread_id): Path<String>) -> Response {
+    let caller = caller!(headers);
+    let result = AssistantStore::global().delete_thread(&caller.owner, &thread_id);
+    deleted(result, thread_id, "thread")
+}
+
+// ===== Messages =====
+
+pub async fn handle_create_message(headers: HeaderMap, Path(thread_id): Path<String>, Json(body): Json<Value>) -> Response {
+    let caller = caller!(headers);
+    let request: MessageRequest = parse!(body);
+    respond(AssistantStore::global().create_message(&caller.owner, &thread_id, request))
+}
+
+pub async fn handle_list_messages(
+    headers: HeaderMap,
+    Path(thread_id): Path<String>,
+    Query(query): Query<ListQuery>,
+) -> Response {
+    let caller = caller!(headers);
+    list(AssistantStore::global().list_messages(&caller.owner, &thread_id, &query), |m| &m.id)
+}
+
+pub async fn handle_get_message(headers: HeaderMap, Path((thread_id, message_id)): Path<(String, String)>) -> Response {
+    let caller = caller!(headers);
+    respond(AssistantStore::global().get_message(&caller.owner, &thread_id, &message_id))
+}
+
+// ===== Runs =====
+
+pub async fn handle_create_run(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(thread_id): Path<String>,
+    Json(body): Json<Value>,
+) -> Response {
+    let caller = caller!(headers);
+    let request: RunRequest = parse!(body);
+    let result = AssistantStore::global().create_run(&caller.owner, &thread_id, request);
+    start(&state, caller, result)
+}
+
+pub async fn handle_create_thread_and_run(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(body): Json<Value>,
+) -> Response {
+    let caller = caller!(headers);
+    let request: ThreadAndRunRequest = parse!(body);
+    let result = AssistantStore::global().create_thread_and_run(&caller.owner, request);
+    start(&state, caller, result)
+}
+
+pub async fn handle_list_runs(headers: HeaderMap, Path(thread_id): Path<String>, Query(query): Query<ListQuery>) -> Response {
+    let caller = caller!(headers);
+    list(AssistantStore::global().list_runs(&caller.owner, &thread_id, &query), |r| &r.id)
+}
+
+pub async fn handle_get_run(headers: HeaderMap, Path((thread_id, run_id)): Path<(String, String)>) -> Response {
+    let caller = caller!(headers);
+    respond(AssistantStore::global().get_run(&caller.owner, &thread_id, &run_id))
+}
+
+pub async fn handle_cancel_run(headers: HeaderMap, Path((thread_id, run_id)): Path<(String, String)>) -> Response {
+    let caller = caller!(headers);
+    respond(AssistantStore::global().cancel_run(&caller.owner, &thread_id, &run_id))
+}
+
+pub async fn handle_submit_tool_outputs(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((thread_id, run_id)): Path<(String, String)>,
+    Json(body): Json<Value>,
+) -> Response {
+    let caller = caller!(headers);
+    let request: ToolOutputsRequest = parse!(body);
+    let result = AssistantStore::global().submit_tool_outputs(&caller.owner, &thread_id, &run_id, request.tool_outputs);
+    start(&state, caller, result)
+}
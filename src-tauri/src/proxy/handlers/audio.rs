@@ -1,23 +1,220 @@
 use axum::{
-    extract::{Multipart, State},
-    http::StatusCode,
-    response::IntoResponse,
+    body::{Body, BodyDataStream, Bytes},
+    extract::{FromRequest, Multipart, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
+use futures::StreamExt;
 use serde_json::{json, Value};
 use tracing::{debug, info};
 use uuid::Uuid;
 
 use crate::proxy::{
     audio::AudioProcessor,
+    config::AudioConfig,
+    middleware::request_id::WithRequestId,
     server::AppState,
 };
 
+/// 查找 model 字段时最多读取的请求体前缀
+const FORM_PEEK_LIMIT: usize = 1024 * 1024;
+/// Gemini 转录需要完整音频时的缓冲上限
+const MAX_BUFFERED_FORM_SIZE: usize = 100 * 1024 * 1024; // 与 monitor 保持一致
+
+/// 用原始请求体重建 multipart 解析器
+async fn multipart_from(headers: &HeaderMap, body: Bytes) -> Result<Multipart, (StatusCode, String)> {
+    let mut request = axum::extract::Request::new(Body::from(body));
+    *request.headers_mut() = headers.clone();
+    Multipart::from_request(request, &())
+        .await
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("解析表单失败: {}", e)))
+}
+
+/// 读取表单中的 model 字段 (跳过文件内容)
+async fn form_model(headers: &HeaderMap, body: Bytes) -> Result<Option<String>, (StatusCode, String)> {
+    let mut multipart = multipart_from(headers, body).await?;
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("解析表单失败: {}", e)))?
+    {
+        if field.name() == Some("model") {
+            return Ok(field.text().await.ok());
+        }
+    }
+    Ok(None)
+}
+
+fn find_bytes(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+/// 在 multipart 前缀中查找文本字段的值; 字段内容尚未完整读取时返回 None
+fn find_form_field(prefix: &[u8], name: &str) -> Option<String> {
+    let marker = format!("name=\"{}\"", name);
+    let mut offset = 0;
+    while let Some(pos) = find_bytes(&prefix[offset..], marker.as_bytes()) {
+        let start = offset + pos;
+        offset = start + marker.len();
+        // 跳过 filename="model" 之类的匹配
+        if start > 0 && !matches!(prefix[start - 1], b' ' | b';' | b'\t') {
+            continue;
+        }
+        let value_start = find_bytes(&prefix[offset..], b"\r\n\r\n")? + offset + 4;
+        let value_end = find_bytes(&prefix[value_start..], b"\r\n--")? + value_start;
+        return std::str::from_utf8(&prefix[value_start..value_end]).ok().map(|v| v.trim().to_string());
+    }
+    None
+}
+
+/// 读取请求体开头直到找到 model 字段, 返回 (model, 已读取的前缀, 剩余的请求体)
+async fn peek_form_model(body: Body) -> Result<(Option<String>, Vec<u8>, BodyDataStream), (StatusCode, String)> {
+    let mut stream = body.into_data_stream();
+    let mut prefix = Vec::new();
+    while prefix.len() < FORM_PEEK_LIMIT {
+        match stream.next().await {
+            Some(Ok(chunk)) => {
+                prefix.extend_from_slice(&chunk);
+                if let Some(model) = find_form_field(&prefix, "model") {
+                    return Ok((Some(model), prefix, stream));
+                }
+            }
+            Some(Err(e)) => return Err((StatusCode::BAD_REQUEST, format!("读取请求体失败: {}", e))),
+            None => break,
+        }
+    }
+    Ok((None, prefix, stream))
+}
+
+async fn collect_form(prefix: Vec<u8>, rest: BodyDataStream) -> Result<Bytes, (StatusCode, String)> {
+    let limit = MAX_BUFFERED_FORM_SIZE.saturating_sub(prefix.len());
+    let rest = axum::body::to_bytes(Body::from_stream(rest), limit)
+        .await
+        .map_err(|e| (StatusCode::PAYLOAD_TOO_LARGE, format!("读取请求体失败: {}", e)))?;
+    let mut body = prefix;
+    body.extend_from_slice(&rest);
+    Ok(Bytes::from(body))
+}
+
+fn should_passthrough(config: &AudioConfig, model: &str) -> bool {
+    config.enabled
+        && config
+            .transcription_models
+            .iter()
+            .any(|p| crate::proxy::common::model_mapping::wildcard_match(p, model))
+}
+
+/// 原样转发到 OpenAI 兼容上游, 响应 (JSON 或二进制音频) 以流式返回
+async fn forward_audio(
+    state: &AppState,
+    config: &AudioConfig,
+    path: &str,
+    content_type: &str,
+    body: reqwest::Body,
+    content_length: Option<u64>,
+    model: Option<&str>,
+) -> Result<Response, (StatusCode, String)> {
+    if config.api_key.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "Audio upstream has no API key".to_string()));
+    }
+    let url = format!("{}{}", config.base_url.trim_end_matches('/'), path);
+    info!("[Audio] Passthrough {} ({:?} bytes) -> {}", path, content_length, url);
+
+    let client = crate::proxy::handlers::common::provider_client(state, "audio", state.request_timeout)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    let mut req = client
+        .post(&url)
+        .with_request_id()
+        .bearer_auth(&config.api_key)
+        .header(reqwest::header::CONTENT_TYPE, content_type);
+    if let Some(length) = content_length {
+        req = req.header(reqwest::header::CONTENT_LENGTH, length);
+    }
+    let resp = req
+        .body(body)
+        .send()
+        .await
+        .map_err(|e| (StatusCode::BAD_GATEWAY, format!("上游请求失败: {}", e)))?;
+
+    let status = StatusCode::from_u16(resp.status().as_u16()).unwrap_or(StatusCode::BAD_GATEWAY);
+    let mut builder = Response::builder()
+        .status(status)
+        .header("X-Upstream", "openai");
+    for name in [header::CONTENT_TYPE, header::CONTENT_LENGTH, header::CONTENT_DISPOSITION] {
+        if let Some(v) = resp.headers().get(name.as_str()).and_then(|v| HeaderValue::from_bytes(v.as_bytes()).ok()) {
+            builder = builder.header(name, v);
+        }
+    }
+    if let Some(v) = model.and_then(|m| HeaderValue::from_str(m).ok()) {
+        builder = builder.header("X-Mapped-Model", v);
+    }
+    builder
+        .body(Body::from_stream(resp.bytes_stream()))
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
 /// 处理音频转录请求 (OpenAI Whisper API 兼容)
+/// 配置了音频上游且模型匹配时透传, 否则使用 Gemini 转录。
+/// 透传时只读取到 model 字段为止, 其余部分 (音频文件) 直接流式发往上游, 不在内存中缓冲。
 pub async fn handle_audio_transcription(
     State(state): State<AppState>,
+    headers: HeaderMap,
+    body: Body,
+) -> Result<Response, (StatusCode, String)> {
+    let config = state.audio.read().await.clone();
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("multipart/form-data")
+        .to_string();
+    let path = "/v1/audio/transcriptions";
+    let (peeked, prefix, rest) = peek_form_model(body).await?;
+    if let Some(model) = peeked.as_deref().filter(|m| should_passthrough(&config, m)) {
+        let content_length = headers
+            .get(header::CONTENT_LENGTH)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<u64>().ok());
+        let stream = futures::stream::once(async move { Ok::<_, axum::Error>(Bytes::from(prefix)) }).chain(rest);
+        let body = reqwest::Body::wrap_stream(stream);
+        return forward_audio(&state, &config, path, &content_type, body, content_length, Some(model)).await;
+    }
+
+    // Gemini 转录需要完整音频; model 字段位于文件之后时也需要读完整个表单再判断
+    let body = collect_form(prefix, rest).await?;
+    if peeked.is_none() && config.enabled {
+        if let Some(model) = form_model(&headers, body.clone()).await?.filter(|m| should_passthrough(&config, m)) {
+            let length = Some(body.len() as u64);
+            return forward_audio(&state, &config, path, &content_type, body.into(), length, Some(&model)).await;
+        }
+    }
+    let multipart = multipart_from(&headers, body).await?;
+    transcribe_with_gemini(state, multipart).await
+}
+
+/// 处理语音合成请求 (OpenAI TTS API 兼容, 仅透传)
+pub async fn handle_audio_speech(
+    State(state): State<AppState>,
+    Json(body): Json<Value>,
+) -> Result<Response, (StatusCode, String)> {
+    let config = state.audio.read().await.clone();
+    if !config.enabled {
+        return Err((
+            StatusCode::NOT_FOUND,
+            "Audio passthrough is disabled. Enable it in proxy settings.".to_string(),
+        ));
+    }
+    let model = body.get("model").and_then(|v| v.as_str()).map(|s| s.to_string());
+    let payload = serde_json::to_vec(&body).map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    let length = Some(payload.len() as u64);
+    forward_audio(&state, &config, "/v1/audio/speech", "application/json", payload.into(), length, model.as_deref()).await
+}
+
+async fn transcribe_with_gemini(
+    state: AppState,
     mut multipart: Multipart,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
+) -> Result<Response, (StatusCode, String)> {
     let mut audio_data: Option<Vec<u8>> = None;
     let mut filename: Option<String> = None;
     let mut model = "gemini-2.0-flash-exp".to_string();
@@ -160,3 +357,43 @@ pub async fn handle_audio_transcription(
         }))
     ).into_response())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_form_model_and_passthrough() {
+        let body = "--XBOUNDARY\r\n\
+Content-Disposition: form-data; name=\"file\"; filename=\"a.mp3\"\r\n\
+Content-Type: audio/mpeg\r\n\r\n\
+ID3data\r\n\
+--XBOUNDARY\r\n\
+Content-Disposition: form-data; name=\"model\"\r\n\r\n\
+whisper-1\r\n\
+--XBOUNDARY--\r\n";
+        let mut headers = HeaderMap::new();
+        headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("multipart/form-data; boundary=XBOUNDARY"));
+        let model = form_model(&headers, Bytes::from(body)).await.unwrap();
+        assert_eq!(model.as_deref(), Some("whisper-1"));
+
+        let mut config = AudioConfig { enabled: true, ..Default::default() };
+        assert!(should_passthrough(&config, "whisper-1"));
+        assert!(should_passthrough(&config, "gpt-4o-mini-transcribe"));
+        assert!(!should_passthrough(&config, "gemini-2.0-flash-exp"));
+        config.enabled = false;
+        assert!(!should_passthrough(&config, "whisper-1"));
+    }
+
+    #[test]
+    fn test_find_form_field_in_prefix() {
+        let head = "--B\r\nContent-Disposition: form-data; name=\"model\"\r\n\r\nwhisper-1\r\n--B\r\n\
+Content-Disposition: form-data; name=\"file\"; filename=\"model\"\r\n\r\nRIFF";
+        assert_eq!(find_form_field(head.as_bytes(), "model").as_deref(), Some("whisper-1"));
+        // 字段值尚未读完
+        assert_eq!(find_form_field(b"--B\r\nContent-Disposition: form-data; name=\"model\"\r\n\r\nwhis", "model"), None);
+        // filename="model" 不是字段名
+        let file_only = "--B\r\nContent-Disposition: form-data; name=\"file\"; filename=\"model\"\r\n\r\nRIFF\r\n--B--";
+        assert_eq!(find_form_field(file_only.as_bytes(), "model"), None);
+    }
+}
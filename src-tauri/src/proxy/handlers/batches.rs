@@ -0,0 +1,120 @@
+// Batch API 处理器 (OpenAI `/v1/batches` 兼容)
+// 批次只对创建它的 API Key 可见; 每次访问都会记下 Key, 以便重启后继续执行该 Key 的批次
+
+use axum::{
+    extract::{Path, Query},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::proxy::batches::{BatchFile, BatchStore, CreateBatchRequest};
+
+#[derive(Debug, Deserialize)]
+pub struct ListBatchesQuery {
+    #[serde(default)]
+    pub after: Option<String>,
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+fn error(status: StatusCode, code: &str, message: String) -> Response {
+    let body = json!({ "error": { "message": message, "type": "invalid_request_error", "code": code } });
+    (status, Json(body)).into_response()
+}
+
+fn not_found(message: String) -> Response {
+    error(StatusCode::NOT_FOUND, "not_found", message)
+}
+
+/// 未启用时按不存在的接口处理
+fn disabled() -> Response {
+    not_found("Batch API is not enabled".to_string())
+}
+
+/// 启用时返回所有者并记下 Key
+fn owner(headers: &HeaderMap) -> Option<String> {
+    let store = BatchStore::global();
+    if !store.config().enabled {
+        return None;
+    }
+    let api_key = crate::proxy::middleware::auth::extract_api_key(headers).filter(|k| !k.is_empty());
+    let owner = crate::proxy::middleware::auth::key_owner(api_key);
+    store.remember_key(&owner, api_key);
+    Some(owner)
+}
+
+pub async fn handle_create_batch(headers: HeaderMap, Json(body): Json<Value>) -> Response {
+    let Some(owner) = owner(&headers) else {
+        return disabled();
+    };
+    let request = match serde_json::from_value::<CreateBatchRequest>(body) {
+        Ok(request) => request,
+        Err(e) => return error(StatusCode::BAD_REQUEST, "invalid_request", format!("Invalid batch request: {}", e)),
+    };
+    match BatchStore::global().create(&owner, request) {
+        Ok(batch) => Json(batch).into_response(),
+        Err(e) => error(StatusCode::BAD_REQUEST, "invalid_request", e),
+    }
+}
+
+pub async fn handle_list_batches(headers: HeaderMap, Query(query): Query<ListBatchesQuery>) -> Response {
+    let Some(owner) = owner(&headers) else {
+        return disabled();
+    };
+    let (batches, has_more) = BatchStore::global().list(&owner, query.limit.unwrap_or(20), query.after.as_deref());
+    Json(json!({
+        "object": "list",
+        "first_id": batches.first().map(|b| b.id.clone()),
+        "last_id": batches.last().map(|b| b.id.clone()),
+        "has_more": has_more,
+        "data": batches,
+    }))
+    .into_response()
+}
+
+pub async fn handle_get_batch(headers: HeaderMap, Path(batch_id): Path<String>) -> Response {
+    let Some(owner) = owner(&headers) else {
+        return disabled();
+    };
+    match BatchStore::global().get(&owner, &batch_id) {
+        Some(batch) => Json(batch).into_response(),
+        None => not_found(format!("No batch found with id '{}'", batch_id)),
+    }
+}
+
+pub async fn handle_cancel_batch(headers: HeaderMap, Path(batch_id): Path<String>) -> Response {
+    let Some(owner) = owner(&headers) else {
+        return disabled();
+    };
+    let store = BatchStore::global();
+    if store.get(&owner, &batch_id).is_none() {
+        return not_found(format!("No batch found with id '{}'", batch_id));
+    }
+    match store.cancel(&owner, &batch_id) {
+        Ok(batch) => Json(batch).into_response(),
+        Err(e) => error(StatusCode::CONFLICT, "invalid_state", e),
+    }
+}
+
+async fn batch_file(headers: HeaderMap, batch_id: String, kind: BatchFile) -> Response {
+    let Some(owner) = owner(&headers) else {
+        return disabled();
+    };
+    match BatchStore::global().file(&owner, &batch_id, kind) {
+        Ok(content) => ([(header::CONTENT_TYPE, "application/x-ndjson")], content).into_response(),
+        Err(e) => not_found(e),
+    }
+}
+
+/// 成功请求的结果 (JSONL)
+pub async fn handle_batch_output(headers: HeaderMap, Path(batch_id): Path<String>) -> Response {
+    batch_file(headers, batch_id, BatchFile::Output).await
+}
+
+/// 失败请求的结果 (JSONL)
+pub async fn handle_batch_errors(headers: HeaderMap, Path(batch_id): Path<String>) -> Response {
+    batch_file(headers, batch_id, BatchFile::Errors).await
+}
@@ -18,6 +18,9 @@ use crate::proxy::mappers::claude::{
     clean_cache_control_from_messages, merge_consecutive_messages,
     models::{Message, MessageContent},
 };
+use crate::proxy::mappers::anthropic::{collect_stream_to_json, create_anthropic_sse_stream, transform_anthropic_request, transform_anthropic_response};
+use crate::proxy::mappers::ollama::OllamaEndpoint;
+use crate::proxy::mappers::openai::OpenAIResponse;
 use crate::proxy::server::AppState;
 use crate::proxy::mappers::context_manager::ContextManager;
 use crate::proxy::mappers::estimation_calibrator::get_calibrator;
@@ -27,6 +30,64 @@ use std::sync::{atomic::Ordering, Arc};
 
 const MAX_RETRY_ATTEMPTS: usize = 3;
 
+/// 模型 (别名改写后) 命中 OpenAI 协议的上游时, 转换请求转发, 并把响应转换回 Anthropic 格式
+async fn dispatch_openai_protocol(state: &AppState, request: &ClaudeRequest) -> Option<Response> {
+    use crate::proxy::handlers::openai::{dispatch_azure, dispatch_bedrock, dispatch_compatible, dispatch_ollama};
+    let body = serde_json::to_value(transform_anthropic_request(request)).ok()?;
+    let response = if let Some(r) = dispatch_ollama(state, OllamaEndpoint::Chat, &body).await {
+        r
+    } else if let Some(r) = dispatch_azure(state, "chat/completions", &body).await {
+        r
+    } else if let Some(r) = dispatch_bedrock(state, &body).await {
+        r
+    } else {
+        dispatch_compatible(state, "chat/completions", &body).await?
+    };
+    Some(openai_to_anthropic_response(response, request.stream).await)
+}
+
+/// OpenAI 格式的成功响应转换为 Anthropic 格式; 错误响应原样返回
+/// 客户端要求流式而上游返回 JSON 时返回完整消息, 由外层的流式桥接合成 SSE
+async fn openai_to_anthropic_response(response: Response, stream: bool) -> Response {
+    if !response.status().is_success() {
+        return response;
+    }
+    let is_sse = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|ct| ct.contains("text/event-stream"));
+    let (mut parts, body) = response.into_parts();
+    parts.headers.remove(header::CONTENT_LENGTH);
+    let openai_stream = body.into_data_stream().map(|chunk| chunk.map_err(|e| e.to_string()));
+    if is_sse && stream {
+        let body = Body::from_stream(create_anthropic_sse_stream(Box::pin(openai_stream)));
+        return Response::from_parts(parts, body);
+    }
+
+    let message = if is_sse {
+        collect_stream_to_json(Box::pin(openai_stream)).await
+    } else {
+        match crate::proxy::middleware::buffer::buffer_response(Body::from_stream(openai_stream)).await {
+            Ok(bytes) => serde_json::from_slice::<OpenAIResponse>(&bytes)
+                .map(|r| transform_anthropic_response(&r))
+                .map_err(|e| format!("Invalid upstream response: {}", e)),
+            Err(response) => return response,
+        }
+    };
+    match message {
+        Ok(message) => {
+            parts.headers.insert(header::CONTENT_TYPE, header::HeaderValue::from_static("application/json"));
+            Response::from_parts(parts, Body::from(serde_json::to_vec(&message).unwrap_or_default()))
+        }
+        Err(e) => (
+            StatusCode::BAD_GATEWAY,
+            Json(json!({ "type": "error", "error": { "type": "api_error", "message": e } })),
+        )
+            .into_response(),
+    }
+}
+
 // ===== Model Constants for Background Tasks =====
 // These can be adjusted for performance/cost optimization or overridden by custom_mapping
 const INTERNAL_BACKGROUND_TASK: &str = "internal-background-task";  // Unified virtual ID for all background tasks
@@ -166,7 +227,20 @@ pub async fn handle_messages(
     let normalized_model = crate::proxy::common::model_mapping::normalize_to_standard_id(&request.model)
         .unwrap_or_else(|| request.model.clone());
 
-    let use_zai = if !zai_enabled {
+    // 模型别名: 可改写模型名并强制指定上游
+    let model_alias = state.model_aliases.resolve(&request.model);
+    let alias_upstream = model_alias.as_ref().and_then(|a| a.upstream);
+    let routed_model = match &model_alias {
+        Some(alias) => {
+            tracing::info!("[{}] Model alias: {} -> {} (upstream: {:?})", trace_id, request.model, alias.target, alias_upstream);
+            alias.target.clone()
+        }
+        None => request.model.clone(),
+    };
+
+    let use_zai = if let Some(upstream) = alias_upstream {
+        matches!(upstream, crate::proxy::config::AliasUpstream::Zai) && zai.enabled
+    } else if !zai_enabled {
         false
     } else {
         match zai.dispatch_mode {
@@ -212,7 +286,7 @@ pub async fn handle_messages(
     let target_family = if use_zai {
         Some("claude")
     } else {
-        let mapped_model = crate::proxy::common::model_mapping::map_claude_model_to_gemini(&request.model);
+        let mapped_model = crate::proxy::common::model_mapping::map_claude_model_to_gemini(&routed_model);
         if mapped_model.contains("gemini") {
             Some("gemini")
         } else {
@@ -240,7 +314,15 @@ pub async fn handle_messages(
         return create_warmup_response(&request, request.stream);
     }
 
+    // 本地 Ollama 模型、Azure 部署、Bedrock 模型与 OpenAI 兼容上游按 OpenAI 协议转发 (别名指定了上游时不参与)
+    if alias_upstream.is_none() {
+        if let Some(response) = dispatch_openai_protocol(&state, &request).await {
+            return response;
+        }
+    }
+
     if use_zai {
+        request.model = routed_model;
         // 重新序列化修复后的请求体
         let new_body = match serde_json::to_value(&request) {
             Ok(v) => v,
@@ -263,6 +345,9 @@ pub async fn handle_messages(
     
     // Google Flow 继续使用 request 对象
     // (后续代码不需要再次 filter_invalid_thinking_blocks)
+
+    // Gemini 不接受 URL 图片, 先下载为 base64
+    crate::proxy::handlers::common::inline_remote_images_claude(&state, &mut request).await;
     
     // [NEW] 获取上下文控制配置
     let experimental = state.experimental.read().await;
@@ -387,7 +472,7 @@ pub async fn handle_messages(
     for attempt in 0..max_attempts {
         // 2. 模型路由解析
         let mut mapped_model = crate::proxy::common::model_mapping::resolve_model_route(
-            &request_for_body.model,
+            &routed_model,
             &*state.custom_mapping.read().await,
         );
         last_mapped_model = Some(mapped_model.clone());
@@ -768,7 +853,7 @@ pub async fn handle_messages(
 
                 // Loop to skip heartbeats during peek
                 loop {
-                    match tokio::time::timeout(std::time::Duration::from_secs(60), claude_stream.next()).await {
+                    match tokio::time::timeout(state.upstream.first_byte_timeout(), claude_stream.next()).await {
                         Ok(Some(Ok(bytes))) => {
                             if bytes.is_empty() {
                                 continue;
@@ -798,7 +883,7 @@ pub async fn handle_messages(
                             break;
                         }
                         Err(_) => {
-                            tracing::warn!("[{}] Timeout waiting for first data (60s), retrying...", trace_id);
+                            tracing::warn!("[{}] Timeout waiting for first data, retrying...", trace_id);
                             last_error = "Timeout waiting for first data".to_string();
                             retry_this_account = true;
                             break;
@@ -1164,28 +1249,15 @@ pub async fn handle_messages(
 
 /// 列出可用模型
 pub async fn handle_list_models(State(state): State<AppState>) -> impl IntoResponse {
-    use crate::proxy::common::model_mapping::get_all_dynamic_models;
-
-    let model_ids = get_all_dynamic_models(
-        &state.custom_mapping,
-    ).await;
-
-    let data: Vec<_> = model_ids.into_iter().map(|id| {
-        json!({
-            "id": id,
-            "object": "model",
-            "created": 1706745600,
-            "owned_by": "antigravity"
-        })
-    }).collect();
+    let models = state.model_catalog.list(&state).await;
 
     Json(json!({
         "object": "list",
-        "data": data
+        "data": *models
     }))
 }
 
-/// 计算 tokens (占位符)
+/// 计算 tokens: z.ai 启用时转发, 否则本地计数
 pub async fn handle_count_tokens(
     State(state): State<AppState>,
     headers: HeaderMap,
@@ -1206,8 +1278,10 @@ pub async fn handle_count_tokens(
         .await;
     }
 
+    let model = body.get("model").and_then(|m| m.as_str()).unwrap_or_default();
+    let count = crate::proxy::common::token_counter::count_request(model, &body);
     Json(json!({
-        "input_tokens": 0,
+        "input_tokens": count.input_tokens,
         "output_tokens": 0
     }))
     .into_response()
@@ -1690,3 +1764,43 @@ async fn try_compress_with_summary(
         quality: original_request.quality.clone(),
     })
 }
+
+#[cfg(test)]
+mod openai_protocol_tests {
+    use super::*;
+
+    fn upstream(content_type: &'static str, body: &'static str) -> Response {
+        let mut response = Response::new(Body::from(body));
+        response.headers_mut().insert(header::CONTENT_TYPE, header::HeaderValue::from_static(content_type));
+        response
+    }
+
+    async fn body_json(response: Response) -> Value {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_openai_to_anthropic_response() {
+        let completion = r#"{"id":"chatcmpl-1","object":"chat.completion","created":0,"model":"gpt-4o","choices":[{"index":0,"message":{"role":"assistant","content":"hi"},"finish_reason":"stop"}]}"#;
+        let message = body_json(openai_to_anthropic_response(upstream("application/json", completion), false).await).await;
+        assert_eq!(message["type"], "message");
+        assert_eq!(message["content"][0]["text"], "hi");
+
+        // 非流式客户端收到 SSE 时收集为完整消息
+        let sse = "data: {\"id\":\"chatcmpl-2\",\"model\":\"gpt-4o\",\"choices\":[{\"index\":0,\"delta\":{\"content\":\"yo\"},\"finish_reason\":\"stop\"}]}\n\ndata: [DONE]\n\n";
+        let message = body_json(openai_to_anthropic_response(upstream("text/event-stream", sse), false).await).await;
+        assert_eq!(message["content"][0]["text"], "yo");
+
+        let response = openai_to_anthropic_response(upstream("text/event-stream", sse), true).await;
+        let events = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert!(String::from_utf8_lossy(&events).contains("event: message_start"));
+
+        // 错误响应原样返回
+        let mut error = upstream("application/json", r#"{"error":{"message":"bad"}}"#);
+        *error.status_mut() = StatusCode::BAD_REQUEST;
+        let response = openai_to_anthropic_response(error, false).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(body_json(response).await["error"]["message"], "bad");
+    }
+}
@@ -4,6 +4,55 @@ use axum::{http::StatusCode, response::{IntoResponse, Response}, Json, extract::
 use serde_json::{json, Value};
 use crate::proxy::server::AppState;
 
+/// 直连第三方上游 (Embeddings / 图像 / 音频) 的 HTTP 客户端, 遵循上游代理与连接池配置
+/// 客户端在请求间共享, 请求 ID 需要通过 `WithRequestId` 逐个请求附加
+pub(crate) fn build_upstream_client(
+    upstream_proxy: crate::proxy::config::UpstreamProxyConfig,
+    timeout_secs: u64,
+) -> Result<reqwest::Client, String> {
+    crate::proxy::upstream::client_pool::ClientPool::global().client(&upstream_proxy, &Default::default(), timeout_secs)
+}
+
+/// 指定上游的共享客户端, 应用该上游的出站代理与 TLS 选项
+pub(crate) async fn provider_client(state: &AppState, provider: &str, timeout_secs: u64) -> Result<reqwest::Client, String> {
+    let upstream_proxy = upstream_proxy_for(state, provider).await;
+    let tls = crate::proxy::upstream::client_pool::resolve_tls(&state.provider_tls.read().await, provider);
+    crate::proxy::upstream::client_pool::ClientPool::global().client(&upstream_proxy, &tls, timeout_secs)
+}
+
+/// 指定上游使用的出站代理 (按 `provider_proxies` 覆盖全局上游代理)
+pub(crate) async fn upstream_proxy_for(state: &AppState, provider: &str) -> crate::proxy::config::UpstreamProxyConfig {
+    let rules = state.provider_proxies.read().await;
+    let global = state.upstream_proxy.read().await;
+    crate::proxy::upstream::client_pool::resolve_proxy(&rules, provider, &global)
+}
+
+/// Gemini 上游只接受内联图片: 转换前把 OpenAI 请求中的远程 image_url 下载为 data URL
+pub(crate) async fn inline_remote_images_openai(
+    state: &AppState,
+    req: &mut crate::proxy::mappers::openai::OpenAIRequest,
+) {
+    if crate::proxy::mappers::vision::remote_openai_image_urls(req).is_empty() {
+        return;
+    }
+    let upstream_proxy = state.upstream_proxy.read().await.clone();
+    let count = crate::proxy::mappers::vision::inline_openai_images(req, &upstream_proxy).await;
+    debug!("[Vision] Inlined {} remote image(s)", count);
+}
+
+/// 同上, 处理 Anthropic 请求中 `source.type = "url"` 的图片
+pub(crate) async fn inline_remote_images_claude(
+    state: &AppState,
+    req: &mut crate::proxy::mappers::claude::models::ClaudeRequest,
+) {
+    if crate::proxy::mappers::vision::remote_claude_image_urls(req).is_empty() {
+        return;
+    }
+    let upstream_proxy = state.upstream_proxy.read().await.clone();
+    let count = crate::proxy::mappers::vision::inline_claude_images(req, &upstream_proxy).await;
+    debug!("[Vision] Inlined {} remote image(s)", count);
+}
+
 // ===== 统一重试与退避策略 =====
 
 /// 重试策略枚举
@@ -154,7 +203,7 @@ pub async fn handle_detect_model(
 
     // 1. Resolve mapping
     let mapped_model = crate::proxy::common::model_mapping::resolve_model_route(
-        model_name,
+        &state.model_aliases.rewrite(model_name),
         &*state.custom_mapping.read().await,
     );
 
@@ -186,3 +235,17 @@ pub async fn handle_detect_model(
 
     Json(response).into_response()
 }
+
+/// Counts prompt tokens locally with tiktoken (OpenAI / Anthropic / Gemini request bodies)
+/// POST /v1/token_count
+pub async fn handle_token_count(
+    State(state): State<AppState>,
+    Json(body): Json<Value>,
+) -> Response {
+    let model = body.get("model").and_then(|v| v.as_str()).unwrap_or("");
+    if model.is_empty() {
+        return (StatusCode::BAD_REQUEST, "Missing 'model' field").into_response();
+    }
+    let model = state.model_aliases.rewrite(model);
+    Json(crate::proxy::common::token_counter::count_request(&model, &body)).into_response()
+}
@@ -0,0 +1,136 @@
+// Embeddings 处理器
+// 按模型名选择上游, 输入数组超出批量上限时分批请求, 统一返回 OpenAI 格式 (用量由 monitor 中间件记录)
+
+use axum::{
+    extract::State,
+    http::{HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::Value;
+use tracing::{debug, info};
+
+use crate::proxy::config::EmbeddingProviderKind;
+use crate::proxy::mappers::embeddings::{
+    auth_header, batch_limit, build_response, build_upstream_request, default_base_url, estimate_tokens,
+    find_provider, parse_request, parse_upstream_response,
+};
+use crate::proxy::middleware::request_id::WithRequestId;
+use crate::proxy::server::AppState;
+
+fn upstream_name(kind: EmbeddingProviderKind) -> &'static str {
+    match kind {
+        EmbeddingProviderKind::Openai => "openai",
+        EmbeddingProviderKind::Gemini => "gemini",
+        EmbeddingProviderKind::Voyage => "voyage",
+        EmbeddingProviderKind::Cohere => "cohere",
+    }
+}
+
+/// 处理 Embeddings 请求 (OpenAI `/v1/embeddings` 兼容)
+pub async fn handle_embeddings(
+    State(state): State<AppState>,
+    Json(body): Json<Value>,
+) -> Result<Response, (StatusCode, String)> {
+    // Azure 部署独立于 Embeddings 上游配置
+    if let Some(response) = crate::proxy::handlers::openai::dispatch_azure(&state, "embeddings", &body).await {
+        return Ok(response);
+    }
+
+    let config = state.embeddings.read().await.clone();
+    if !config.enabled {
+        return Err((
+            StatusCode::NOT_FOUND,
+            "Embeddings endpoint is disabled. Enable it in proxy settings.".to_string(),
+        ));
+    }
+
+    let request = parse_request(&body).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    let model = state.model_aliases.rewrite(&request.model);
+    let provider = find_provider(&config.providers, &model).ok_or_else(|| {
+        (
+            StatusCode::BAD_REQUEST,
+            format!("No embeddings provider configured for model '{}'", model),
+        )
+    })?;
+    if provider.api_key.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Embeddings provider for '{}' has no API key", provider.pattern),
+        ));
+    }
+
+    let kind = provider.provider;
+    let upstream_model = provider
+        .model
+        .clone()
+        .filter(|m| !m.is_empty())
+        .unwrap_or_else(|| model.clone());
+    let base_url = if provider.base_url.is_empty() {
+        default_base_url(kind)
+    } else {
+        provider.base_url.as_str()
+    };
+    let batch_size = config.max_batch_size.clamp(1, batch_limit(kind));
+
+    info!(
+        "[Embeddings] model={} -> {} ({}), inputs={}, batch_size={}",
+        request.model,
+        upstream_model,
+        upstream_name(kind),
+        request.inputs.len(),
+        batch_size
+    );
+
+    let client = crate::proxy::handlers::common::provider_client(&state, "embeddings", state.request_timeout)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    let (auth_name, auth_value) = auth_header(kind, &provider.api_key);
+
+    let mut vectors = Vec::with_capacity(request.inputs.len());
+    let mut prompt_tokens = 0u32;
+    for (batch_index, batch) in request.inputs.chunks(batch_size).enumerate() {
+        let (url, payload) = build_upstream_request(kind, base_url, &upstream_model, batch, &request)
+            .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+        debug!("[Embeddings] batch #{} ({} inputs) -> {}", batch_index, batch.len(), url);
+
+        let resp = client
+            .post(&url)
+            .with_request_id()
+            .header(auth_name, &auth_value)
+            .json(&payload)
+            .send()
+            .await
+            .map_err(|e| (StatusCode::BAD_GATEWAY, format!("Embeddings upstream request failed: {}", e)))?;
+        let status = resp.status();
+        let text = resp
+            .text()
+            .await
+            .map_err(|e| (StatusCode::BAD_GATEWAY, format!("Failed to read embeddings response: {}", e)))?;
+        if !status.is_success() {
+            let status = StatusCode::from_u16(status.as_u16()).unwrap_or(StatusCode::BAD_GATEWAY);
+            return Err((status, text));
+        }
+
+        let json: Value = serde_json::from_str(&text)
+            .map_err(|e| (StatusCode::BAD_GATEWAY, format!("Invalid embeddings response: {}", e)))?;
+        let (batch_vectors, tokens) =
+            parse_upstream_response(kind, &json).map_err(|e| (StatusCode::BAD_GATEWAY, e))?;
+        if batch_vectors.len() != batch.len() {
+            return Err((
+                StatusCode::BAD_GATEWAY,
+                format!("Embeddings upstream returned {} vectors for {} inputs", batch_vectors.len(), batch.len()),
+            ));
+        }
+        vectors.extend(batch_vectors);
+        prompt_tokens = prompt_tokens.saturating_add(tokens.unwrap_or_else(|| estimate_tokens(batch)));
+    }
+
+    let mut response = Json(build_response(&request.model, vectors, prompt_tokens, request.base64)).into_response();
+    let headers = response.headers_mut();
+    headers.insert("X-Upstream", HeaderValue::from_static(upstream_name(kind)));
+    if let Ok(v) = HeaderValue::from_str(&upstream_model) {
+        headers.insert("X-Mapped-Model", v);
+    }
+    Ok(response)
+}
@@ -0,0 +1,160 @@
+// Files API 处理器 (OpenAI `/v1/files` 兼容)
+// 上传的文件边接收边写入存储, 不在内存中缓冲; 文件只对上传它的 API Key 可见
+
+use axum::{
+    extract::{multipart::MultipartRejection, Multipart, Path, Query},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::proxy::files::{mime_type, FileStore, PURPOSES};
+
+#[derive(Debug, Deserialize)]
+pub struct ListFilesQuery {
+    #[serde(default)]
+    pub purpose: Option<String>,
+    #[serde(default)]
+    pub after: Option<String>,
+    #[serde(default)]
+    pub limit: Option<usize>,
+    #[serde(default)]
+    pub order: Option<String>,
+}
+
+fn error(status: StatusCode, code: &str, message: String) -> Response {
+    let body = json!({ "error": { "message": message, "type": "invalid_request_error", "code": code } });
+    (status, Json(body)).into_response()
+}
+
+fn not_found(id: &str) -> Response {
+    error(StatusCode::NOT_FOUND, "not_found", format!("No file found with id '{}'", id))
+}
+
+/// 未启用时按不存在的接口处理
+fn disabled() -> Response {
+    error(StatusCode::NOT_FOUND, "not_found", "Files API is not enabled".to_string())
+}
+
+fn owner(headers: &HeaderMap) -> Option<String> {
+    if !FileStore::global().config().enabled {
+        return None;
+    }
+    let api_key = crate::proxy::middleware::auth::extract_api_key(headers);
+    Some(crate::proxy::middleware::auth::key_owner(api_key))
+}
+
+pub async fn handle_upload_file(headers: HeaderMap, multipart: Result<Multipart, MultipartRejection>) -> Response {
+    let Some(owner) = owner(&headers) else {
+        return disabled();
+    };
+    let mut multipart = match multipart {
+        Ok(multipart) => multipart,
+        Err(e) => return error(StatusCode::BAD_REQUEST, "invalid_request", format!("Expected multipart/form-data: {}", e)),
+    };
+    let store = FileStore::global();
+    let mut purpose = None;
+    let mut upload = None;
+    loop {
+        let mut field = match multipart.next_field().await {
+            Ok(Some(field)) => field,
+            Ok(None) => break,
+            Err(e) => return error(StatusCode::BAD_REQUEST, "invalid_request", format!("Invalid form data: {}", e)),
+        };
+        match field.name() {
+            Some("purpose") => purpose = field.text().await.ok().map(|p| p.trim().to_string()),
+            Some("file") => {
+                let filename = field.file_name().unwrap_or("upload").to_string();
+                let mut staged = match store.begin_upload() {
+                    Ok(staged) => staged,
+                    Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, "storage_error", e),
+                };
+                loop {
+                    match field.chunk().await {
+                        Ok(Some(chunk)) => {
+                            if let Err(e) = staged.write(&chunk) {
+                                return error(StatusCode::PAYLOAD_TOO_LARGE, "file_too_large", e);
+                            }
+                        }
+                        Ok(None) => break,
+                        Err(e) => return error(StatusCode::BAD_REQUEST, "invalid_request", format!("Upload interrupted: {}", e)),
+                    }
+                }
+                upload = Some((filename, staged));
+            }
+            _ => {}
+        }
+    }
+    let Some((filename, staged)) = upload else {
+        return error(StatusCode::BAD_REQUEST, "invalid_request", "Missing 'file' field".to_string());
+    };
+    let purpose = match purpose {
+        Some(purpose) if PURPOSES.contains(&purpose.as_str()) => purpose,
+        other => {
+            let message = format!("Invalid purpose '{}', expected one of: {}", other.unwrap_or_default(), PURPOSES.join(", "));
+            return error(StatusCode::BAD_REQUEST, "invalid_request", message);
+        }
+    };
+    match store.commit(&owner, &filename, &purpose, staged) {
+        Ok(file) => Json(file).into_response(),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, "storage_error", e),
+    }
+}
+
+pub async fn handle_list_files(headers: HeaderMap, Query(query): Query<ListFilesQuery>) -> Response {
+    let Some(owner) = owner(&headers) else {
+        return disabled();
+    };
+    let desc = query.order.as_deref() != Some("asc");
+    let limit = query.limit.unwrap_or(10_000);
+    let (files, has_more) =
+        FileStore::global().list(&owner, query.purpose.as_deref(), limit, query.after.as_deref(), desc);
+    Json(json!({
+        "object": "list",
+        "first_id": files.first().map(|f| f.id.clone()),
+        "last_id": files.last().map(|f| f.id.clone()),
+        "has_more": has_more,
+        "data": files,
+    }))
+    .into_response()
+}
+
+pub async fn handle_get_file(headers: HeaderMap, Path(file_id): Path<String>) -> Response {
+    let Some(owner) = owner(&headers) else {
+        return disabled();
+    };
+    match FileStore::global().get(&owner, &file_id) {
+        Some(file) => Json(file).into_response(),
+        None => not_found(&file_id),
+    }
+}
+
+pub async fn handle_file_content(headers: HeaderMap, Path(file_id): Path<String>) -> Response {
+    let Some(owner) = owner(&headers) else {
+        return disabled();
+    };
+    let Ok((file, data)) = FileStore::global().content(&owner, &file_id) else {
+        return not_found(&file_id);
+    };
+    let mut response = data.into_response();
+    let headers = response.headers_mut();
+    headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(mime_type(&file.filename)));
+    let disposition = format!("attachment; filename=\"{}\"", file.filename.replace(['"', '\r', '\n'], "_"));
+    if let Ok(value) = HeaderValue::from_str(&disposition) {
+        headers.insert(header::CONTENT_DISPOSITION, value);
+    }
+    response
+}
+
+pub async fn handle_delete_file(headers: HeaderMap, Path(file_id): Path<String>) -> Response {
+    let Some(owner) = owner(&headers) else {
+        return disabled();
+    };
+    match FileStore::global().delete(&owner, &file_id) {
+        Ok(true) => Json(json!({ "id": file_id, "object": "file", "deleted": true })).into_response(),
+        Ok(false) => not_found(&file_id),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, "storage_error", e),
+    }
+}
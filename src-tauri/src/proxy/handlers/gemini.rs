@@ -62,11 +62,12 @@ pub async fn handle_generate(
     
     let mut last_error = String::new();
     let mut last_email: Option<String> = None;
+    let routed_model = state.model_aliases.rewrite(&model_name);
 
     for attempt in 0..max_attempts {
         // 3. 模型路由解析
         let mapped_model = crate::proxy::common::model_mapping::resolve_model_route(
-            &model_name,
+            &routed_model,
             &*state.custom_mapping.read().await,
         );
         // 提取 tools 列表以进行联网探测 (Gemini 风格可能是嵌套的)
@@ -170,7 +171,7 @@ pub async fn handle_generate(
                 let mut first_chunk = None;
                 let mut retry_gemini = false;
 
-                match tokio::time::timeout(std::time::Duration::from_secs(30), response_stream.next()).await {
+                match tokio::time::timeout(state.upstream.first_byte_timeout(), response_stream.next()).await {
                     Ok(Some(Ok(bytes))) => {
                         if bytes.is_empty() {
                             tracing::warn!("[Gemini] Empty first chunk received, retrying...");
@@ -453,10 +454,11 @@ pub async fn handle_get_model(Path(model_name): Path<String>) -> impl IntoRespon
     }))
 }
 
-pub async fn handle_count_tokens(State(state): State<AppState>, Path(_model_name): Path<String>, Json(_body): Json<Value>) -> Result<impl IntoResponse, (StatusCode, String)> {
+pub async fn handle_count_tokens(State(state): State<AppState>, Path(model_name): Path<String>, Json(body): Json<Value>) -> Result<impl IntoResponse, (StatusCode, String)> {
     let model_group = "gemini";
     let (_access_token, _project_id, _, _wait_ms) = state.token_manager.get_token(model_group, false, None, "gemini").await
         .map_err(|e| (StatusCode::SERVICE_UNAVAILABLE, format!("Token error: {}", e)))?;
     
-    Ok(Json(json!({"totalTokens": 0})))
+    let count = crate::proxy::common::token_counter::count_request(&model_name, &body);
+    Ok(Json(json!({"totalTokens": count.input_tokens})))
 }
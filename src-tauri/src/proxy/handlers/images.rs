@@ -0,0 +1,176 @@
+// 图像生成上游转发与本地保存
+// 匹配到 `images.backends` 的模型转发到 DALL-E / Stability 上游, 其余模型仍走 Gemini 图像生成
+
+use axum::{
+    http::{HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use base64::Engine as _;
+use serde_json::{json, Value};
+use tracing::{info, warn};
+
+use crate::proxy::config::{ImageBackendConfig, ImageBackendKind, ImagesConfig};
+use crate::proxy::mappers::images::{
+    build_backend_request, default_base_url, parse_backend_response, to_openai_item, GeneratedImage,
+};
+use crate::proxy::middleware::request_id::WithRequestId;
+use crate::proxy::server::AppState;
+
+fn upstream_name(kind: ImageBackendKind) -> &'static str {
+    match kind {
+        ImageBackendKind::Openai => "openai",
+        ImageBackendKind::Stability => "stability",
+    }
+}
+
+/// 下载 url 形式的图片, 返回 (mime, b64)
+async fn download_image(client: &reqwest::Client, url: &str) -> Result<(String, String), String> {
+    let resp = client
+        .get(url)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to download image: {}", e))?;
+    if !resp.status().is_success() {
+        return Err(format!("Failed to download image: HTTP {}", resp.status()));
+    }
+    let mime = resp
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("image/png")
+        .to_string();
+    let bytes = resp
+        .bytes()
+        .await
+        .map_err(|e| format!("Failed to download image: {}", e))?;
+    Ok((mime, base64::engine::general_purpose::STANDARD.encode(bytes)))
+}
+
+/// 从 OpenAI 响应的 data 项中提取内联图片 (mime, b64)
+pub(crate) fn inline_image(item: &Value) -> Option<(String, String)> {
+    if let Some(b64) = item.get("b64_json").and_then(|v| v.as_str()) {
+        return Some(("image/png".to_string(), b64.to_string()));
+    }
+    let url = item.get("url").and_then(|v| v.as_str())?;
+    crate::modules::image_store::split_data_url(url).map(|(mime, data)| (mime.to_string(), data.to_string()))
+}
+
+/// 后台保存图片到本地目录, 失败只记录日志
+pub(crate) fn save_images(config: &ImagesConfig, images: Vec<(String, String)>) {
+    if !config.save_enabled || images.is_empty() {
+        return;
+    }
+    let config = config.clone();
+    tokio::task::spawn_blocking(move || {
+        let dir = match crate::modules::image_store::resolve_dir(&config) {
+            Ok(dir) => dir,
+            Err(e) => {
+                warn!("[Images] Cannot resolve save directory: {}", e);
+                return;
+            }
+        };
+        let mut saved = 0;
+        for (mime, b64) in &images {
+            match crate::modules::image_store::save_image(&dir, b64, mime) {
+                Ok(_) => saved += 1,
+                Err(e) => warn!("[Images] Failed to save image: {}", e),
+            }
+        }
+        info!("[Images] Saved {} image(s) to {}", saved, dir.display());
+    });
+}
+
+/// 转发到配置的图像生成上游
+pub(crate) async fn generate_with_backend(
+    state: &AppState,
+    backend: &ImageBackendConfig,
+    images_config: &ImagesConfig,
+    body: &Value,
+    model: &str,
+    response_format: &str,
+) -> Result<Response, (StatusCode, String)> {
+    if backend.api_key.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Image backend for '{}' has no API key", backend.pattern),
+        ));
+    }
+    let kind = backend.provider;
+    let upstream_model = backend
+        .model
+        .clone()
+        .filter(|m| !m.is_empty())
+        .unwrap_or_else(|| model.to_string());
+    let base_url = if backend.base_url.is_empty() {
+        default_base_url(kind)
+    } else {
+        backend.base_url.as_str()
+    };
+    let (url, payload) =
+        build_backend_request(kind, base_url, &upstream_model, body).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    info!("[Images] model={} -> {} ({})", model, upstream_model, upstream_name(kind));
+
+    let client = crate::proxy::handlers::common::provider_client(&state, "images", state.request_timeout)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    let resp = client
+        .post(&url)
+        .with_request_id()
+        .bearer_auth(&backend.api_key)
+        .header(reqwest::header::ACCEPT, "application/json")
+        .json(&payload)
+        .send()
+        .await
+        .map_err(|e| (StatusCode::BAD_GATEWAY, format!("Image upstream request failed: {}", e)))?;
+    let status = resp.status();
+    let text = resp
+        .text()
+        .await
+        .map_err(|e| (StatusCode::BAD_GATEWAY, format!("Failed to read image response: {}", e)))?;
+    if !status.is_success() {
+        let status = StatusCode::from_u16(status.as_u16()).unwrap_or(StatusCode::BAD_GATEWAY);
+        return Err((status, text));
+    }
+    let json: Value = serde_json::from_str(&text)
+        .map_err(|e| (StatusCode::BAD_GATEWAY, format!("Invalid image response: {}", e)))?;
+    let mut images = parse_backend_response(kind, &json).map_err(|e| (StatusCode::BAD_GATEWAY, e))?;
+
+    // 客户端要求 b64_json 或需要保存时, 下载只有 url 的图片
+    let need_inline = response_format != "url" || images_config.save_enabled;
+    for image in images.iter_mut().filter(|img| img.b64_json.is_none()) {
+        let Some(url) = image.url.clone().filter(|_| need_inline) else {
+            continue;
+        };
+        match download_image(&client, &url).await {
+            Ok((mime, b64)) => {
+                image.mime_type = mime;
+                image.b64_json = Some(b64);
+            }
+            Err(e) if response_format != "url" => return Err((StatusCode::BAD_GATEWAY, e)),
+            Err(e) => warn!("[Images] {}", e),
+        }
+    }
+
+    save_images(
+        images_config,
+        images
+            .iter()
+            .filter_map(|img: &GeneratedImage| img.b64_json.clone().map(|b64| (img.mime_type.clone(), b64)))
+            .collect(),
+    );
+
+    let data: Vec<Value> = images.iter().map(|img| to_openai_item(img, response_format)).collect();
+    let mut response = Json(json!({
+        "created": chrono::Utc::now().timestamp(),
+        "data": data,
+    }))
+    .into_response();
+    let headers = response.headers_mut();
+    headers.insert("X-Upstream", HeaderValue::from_static(upstream_name(kind)));
+    if let Ok(v) = HeaderValue::from_str(&upstream_model) {
+        headers.insert("X-Mapped-Model", v);
+    }
+    Ok(response)
+}
@@ -1,33 +1,19 @@
 use axum::{
-    body::{to_bytes, Body},
+    body::Body,
     extract::State,
     http::{header, HeaderMap, HeaderValue, Method, StatusCode},
     response::{IntoResponse, Response},
 };
-use bytes::Bytes;
 use futures::StreamExt;
 use serde_json::{json, Value};
 use tokio::time::Duration;
 use tokio_stream::wrappers::IntervalStream;
 
+use crate::proxy::common::stream_relay::{is_event_stream, relay_upstream};
+use crate::proxy::middleware::buffer::buffer_request;
+use crate::proxy::providers::zai_anthropic::report_key_rejection;
 use crate::proxy::server::AppState;
 
-fn build_client(
-    upstream_proxy: crate::proxy::config::UpstreamProxyConfig,
-    timeout_secs: u64,
-) -> Result<reqwest::Client, String> {
-    let mut builder = reqwest::Client::builder()
-        .timeout(Duration::from_secs(timeout_secs.max(5)));
-
-    if upstream_proxy.enabled && !upstream_proxy.url.is_empty() {
-        let proxy = reqwest::Proxy::all(&upstream_proxy.url)
-            .map_err(|e| format!("Invalid upstream proxy url: {}", e))?;
-        builder = builder.proxy(proxy);
-    }
-
-    builder.build().map_err(|e| format!("Failed to build HTTP client: {}", e))
-}
-
 fn copy_passthrough_headers(incoming: &HeaderMap) -> HeaderMap {
     let mut out = HeaderMap::new();
     for (k, v) in incoming.iter() {
@@ -50,61 +36,85 @@ async fn forward_mcp(
     body: Body,
 ) -> Response {
     let zai = state.zai.read().await.clone();
-    if !zai.enabled || zai.api_key.trim().is_empty() {
-        return (StatusCode::BAD_REQUEST, "z.ai is not configured").into_response();
-    }
+    let key_pool = zai.key_pool();
+    let mut key_lease = match state.zai_keys.acquire(&key_pool, zai.key_strategy) {
+        Some(lease) if zai.enabled => lease,
+        _ => return (StatusCode::BAD_REQUEST, "z.ai is not configured").into_response(),
+    };
 
     if !zai.mcp.enabled {
         return StatusCode::NOT_FOUND.into_response();
     }
 
-    let upstream_proxy = state.upstream_proxy.read().await.clone();
-    let client = match build_client(upstream_proxy, state.request_timeout) {
+    let client = match crate::proxy::handlers::common::provider_client(&state, "zai", state.request_timeout).await {
         Ok(c) => c,
         Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
     };
 
-    let collected = match to_bytes(body, 100 * 1024 * 1024).await {
+    let collected = match buffer_request(body).await {
         Ok(b) => b,
-        Err(e) => {
-            return (
-                StatusCode::BAD_REQUEST,
-                format!("Failed to read request body: {}", e),
-            )
-                .into_response();
-        }
+        Err(response) => return response,
     };
 
-    let mut headers = copy_passthrough_headers(&incoming_headers);
-    if let Ok(v) = HeaderValue::from_str(&format!("Bearer {}", zai.api_key)) {
-        headers.insert(header::AUTHORIZATION, v);
-    }
+    // Key 被拒绝或额度耗尽时记录失败并换用下一个 Key, 与 z.ai Anthropic 转发一致
+    let mut tried: Vec<String> = Vec::new();
+    let (resp, status) = loop {
+        let mut headers = copy_passthrough_headers(&incoming_headers);
+        if let Ok(v) = HeaderValue::from_str(&format!("Bearer {}", key_lease.key)) {
+            headers.insert(header::AUTHORIZATION, v);
+        }
+
+        let req = client
+            .request(method.clone(), upstream_url)
+            .headers(headers)
+            .body(collected.clone());
+
+        let resp = match req.send().await {
+            Ok(r) => r,
+            Err(e) => {
+                return (
+                    StatusCode::BAD_GATEWAY,
+                    format!("Upstream request failed: {}", e),
+                )
+                    .into_response();
+            }
+        };
 
-    let req = client
-        .request(method, upstream_url)
-        .headers(headers)
-        .body(collected);
+        let status = StatusCode::from_u16(resp.status().as_u16()).unwrap_or(StatusCode::BAD_GATEWAY);
+        state.zai_keys.observe(&key_lease.key, status, resp.headers());
+        if !matches!(status.as_u16(), 401 | 402 | 403 | 429) {
+            break (resp, status);
+        }
 
-    let resp = match req.send().await {
-        Ok(r) => r,
-        Err(e) => {
-            return (
-                StatusCode::BAD_GATEWAY,
-                format!("Upstream request failed: {}", e),
-            )
-                .into_response();
+        let content_type = resp.headers().get(header::CONTENT_TYPE).cloned();
+        let error_body = resp.bytes().await.unwrap_or_default();
+        report_key_rejection(state, &key_lease.key, status, &error_body);
+        tried.push(key_lease.key.clone());
+        match state.zai_keys.rotate(&key_pool, zai.key_strategy, &tried) {
+            Some(next) => {
+                tracing::info!("[MCP] Upstream returned {}, retrying with another key", status);
+                key_lease = next;
+            }
+            None => {
+                let mut out = Response::builder().status(status);
+                if let Some(ct) = content_type {
+                    out = out.header(header::CONTENT_TYPE, ct);
+                }
+                return out.body(Body::from(error_body)).unwrap_or_else(|_| status.into_response());
+            }
         }
     };
 
-    let status = StatusCode::from_u16(resp.status().as_u16()).unwrap_or(StatusCode::BAD_GATEWAY);
     let mut out = Response::builder().status(status);
     if let Some(ct) = resp.headers().get(header::CONTENT_TYPE) {
         out = out.header(header::CONTENT_TYPE, ct.clone());
     }
 
-    let stream = resp.bytes_stream().map(|chunk| match chunk {
-        Ok(b) => Ok::<Bytes, std::io::Error>(b),
-        Err(e) => Ok(Bytes::from(format!("Upstream stream error: {}", e))),
+    // Key 租用保持到响应体结束
+    let sse = is_event_stream(resp.headers());
+    let stream = relay_upstream(resp.bytes_stream(), sse, "MCP").map(move |chunk| {
+        let _lease = &key_lease;
+        chunk
     });
 
     out.body(Body::from_stream(stream)).unwrap_or_else(|_| {
@@ -227,15 +237,9 @@ async fn handle_vision_delete(state: AppState, headers: HeaderMap) -> Response {
 }
 
 async fn handle_vision_post(state: AppState, headers: HeaderMap, body: Body) -> Response {
-    let collected = match to_bytes(body, 100 * 1024 * 1024).await {
+    let collected = match buffer_request(body).await {
         Ok(b) => b,
-        Err(e) => {
-            return (
-                StatusCode::BAD_REQUEST,
-                format!("Failed to read request body: {}", e),
-            )
-                .into_response();
-        }
+        Err(response) => return response,
     };
 
     let request_json: Value = match serde_json::from_slice(&collected) {
@@ -333,7 +337,7 @@ async fn handle_vision_post(state: AppState, headers: HeaderMap, body: Body) ->
             let arguments = params.get("arguments").cloned().unwrap_or(Value::Object(Default::default()));
 
             let zai = state.zai.read().await.clone();
-            let upstream_proxy = state.upstream_proxy.read().await.clone();
+            let upstream_proxy = crate::proxy::handlers::common::upstream_proxy_for(&state, "zai").await;
             let timeout = state.request_timeout;
 
             match crate::proxy::zai_vision_tools::call_tool(
@@ -380,7 +384,7 @@ pub async fn handle_zai_mcp_server(
     body: Body,
 ) -> Response {
     let zai = state.zai.read().await.clone();
-    if !zai.enabled || zai.api_key.trim().is_empty() {
+    if !zai.enabled || zai.key_pool().is_empty() {
         return (StatusCode::BAD_REQUEST, "z.ai is not configured").into_response();
     }
     if !zai.mcp.enabled || !zai.mcp.vision_enabled {
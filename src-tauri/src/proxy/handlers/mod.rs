@@ -8,4 +8,12 @@ pub mod mcp;
 pub mod common;
 pub mod audio;  // 音频转录处理器
 pub mod warmup; // 预热处理器
+pub mod realtime; // Realtime API WebSocket 透传
+pub mod embeddings; // Embeddings 上游映射
+pub mod images; // 图像生成上游与本地保存
+pub mod batches; // Batch API
+pub mod files; // Files API
+pub mod assistants; // Assistants API 兼容层
+pub mod moderations; // Moderations API
+pub mod rerank; // Rerank API
 
@@ -0,0 +1,36 @@
+// Moderations 处理器 (OpenAI `/v1/moderations` 兼容)
+// 模型 (默认 `omni-moderation-latest`) 命中 OpenAI 兼容上游时转发, 否则由本地分类器给出结果
+
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::{json, Value};
+
+use crate::proxy::moderation::{Moderator, DEFAULT_MODEL};
+use crate::proxy::server::AppState;
+
+fn error(status: StatusCode, message: String) -> Response {
+    let body = json!({ "error": { "message": message, "type": "invalid_request_error", "code": null } });
+    (status, Json(body)).into_response()
+}
+
+pub async fn handle_moderations(State(state): State<AppState>, Json(mut body): Json<Value>) -> Response {
+    if body.get("model").and_then(Value::as_str).is_none_or(str::is_empty) {
+        body["model"] = json!(DEFAULT_MODEL);
+    }
+    if let Some(response) = crate::proxy::handlers::openai::dispatch_compatible(&state, "moderations", &body).await {
+        return response;
+    }
+    let moderator = Moderator::global();
+    if !moderator.config().enabled {
+        let model = body["model"].as_str().unwrap_or_default();
+        return error(StatusCode::NOT_FOUND, format!("No moderation upstream configured for model '{}'", model));
+    }
+    match moderator.moderate(&body) {
+        Ok(response) => Json(response).into_response(),
+        Err(e) => error(StatusCode::BAD_REQUEST, e),
+    }
+}
@@ -7,6 +7,10 @@ use bytes::Bytes;
 use serde_json::{json, Value};
 use tracing::{debug, error, info}; // Import Engine trait for encode method
 
+use crate::proxy::mappers::anthropic::{
+    create_openai_sse_stream_from_anthropic, transform_anthropic_response_to_openai, transform_openai_request_to_anthropic,
+};
+use crate::proxy::mappers::ollama::OllamaEndpoint;
 use crate::proxy::mappers::openai::{
     transform_openai_request, transform_openai_response, OpenAIRequest,
 };
@@ -21,6 +25,90 @@ use super::common::{
 use crate::proxy::session_manager::SessionManager;
 use tokio::time::Duration;
 
+/// 模型 (别名改写后) 命中 Ollama 规则时转发并返回响应
+pub(crate) async fn dispatch_ollama(state: &AppState, endpoint: OllamaEndpoint, body: &Value) -> Option<Response> {
+    let model = state.model_aliases.rewrite(body.get("model")?.as_str()?);
+    let upstream_model = crate::proxy::mappers::ollama::resolve_model(&*state.ollama.read().await, &model)?;
+    Some(crate::proxy::providers::ollama::forward(state, endpoint, body.clone(), &model, &upstream_model).await)
+}
+
+/// 模型 (别名改写后) 命中 Azure 部署时转发并返回响应
+pub(crate) async fn dispatch_azure(state: &AppState, operation: &str, body: &Value) -> Option<Response> {
+    let model = state.model_aliases.rewrite(body.get("model")?.as_str()?);
+    crate::proxy::providers::azure_openai::forward(state, operation, &model, body.clone()).await
+}
+
+/// 模型 (别名改写后) 命中 Bedrock 规则时转发并返回响应
+pub(crate) async fn dispatch_bedrock(state: &AppState, body: &Value) -> Option<Response> {
+    let model = state.model_aliases.rewrite(body.get("model")?.as_str()?);
+    crate::proxy::providers::bedrock::forward(state, &model, body).await
+}
+
+/// 模型 (别名改写后) 命中 OpenAI 兼容上游时转发并返回响应
+pub(crate) async fn dispatch_compatible(state: &AppState, operation: &str, body: &Value) -> Option<Response> {
+    let model = state.model_aliases.rewrite(body.get("model")?.as_str()?);
+    crate::proxy::providers::openai_compat::forward(state, operation, &model, body.clone()).await
+}
+
+/// 别名强制指定 z.ai 上游时, 转换为 Anthropic 协议转发, 并把响应转换回 OpenAI 格式
+async fn dispatch_zai(state: &AppState, body: &Value) -> Option<Response> {
+    let alias = state.model_aliases.resolve(body.get("model")?.as_str()?)?;
+    if alias.upstream != Some(crate::proxy::config::AliasUpstream::Zai) || !state.zai.read().await.enabled {
+        return None;
+    }
+    let mut request: OpenAIRequest = serde_json::from_value(body.clone()).ok()?;
+    request.model = alias.target;
+    let request = transform_openai_request_to_anthropic(&request);
+    let mut headers = axum::http::HeaderMap::new();
+    headers.insert("anthropic-version", axum::http::HeaderValue::from_static("2023-06-01"));
+    let response = crate::proxy::providers::zai_anthropic::forward_anthropic_json(
+        state,
+        axum::http::Method::POST,
+        "/v1/messages",
+        &headers,
+        serde_json::to_value(&request).ok()?,
+        request.messages.len(),
+    )
+    .await;
+    Some(anthropic_to_openai_response(response).await)
+}
+
+/// Anthropic 格式的成功响应转换为 OpenAI 格式; 错误响应原样返回
+async fn anthropic_to_openai_response(response: Response) -> Response {
+    use axum::http::header;
+    use futures::StreamExt;
+    if !response.status().is_success() {
+        return response;
+    }
+    let is_sse = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|ct| ct.contains("text/event-stream"));
+    let (mut parts, body) = response.into_parts();
+    parts.headers.remove(header::CONTENT_LENGTH);
+    if is_sse {
+        let anthropic_stream = body.into_data_stream().map(|chunk| chunk.map_err(|e| e.to_string()));
+        let body = axum::body::Body::from_stream(create_openai_sse_stream_from_anthropic(Box::pin(anthropic_stream)));
+        return Response::from_parts(parts, body);
+    }
+    let bytes = match crate::proxy::middleware::buffer::buffer_response(body).await {
+        Ok(bytes) => bytes,
+        Err(response) => return response,
+    };
+    match serde_json::from_slice::<crate::proxy::mappers::claude::models::ClaudeResponse>(&bytes) {
+        Ok(message) => {
+            let completion = transform_anthropic_response_to_openai(&message);
+            Response::from_parts(parts, axum::body::Body::from(serde_json::to_vec(&completion).unwrap_or_default()))
+        }
+        Err(e) => (
+            StatusCode::BAD_GATEWAY,
+            Json(json!({ "error": { "message": format!("Invalid upstream response: {}", e), "type": "upstream_error" } })),
+        )
+            .into_response(),
+    }
+}
+
 pub async fn handle_chat_completions(
     State(state): State<AppState>,
     Json(mut body): Json<Value>,
@@ -78,6 +166,24 @@ pub async fn handle_chat_completions(
         }
     }
 
+    // 本地 Ollama 模型、Azure 部署、Bedrock 模型与 OpenAI 兼容上游直接转发, 不经过账号池
+    if let Some(response) = dispatch_ollama(&state, OllamaEndpoint::Chat, &body).await {
+        return Ok(response);
+    }
+    if let Some(response) = dispatch_azure(&state, "chat/completions", &body).await {
+        return Ok(response);
+    }
+    if let Some(response) = dispatch_bedrock(&state, &body).await {
+        return Ok(response);
+    }
+    if let Some(response) = dispatch_compatible(&state, "chat/completions", &body).await {
+        return Ok(response);
+    }
+    if let Some(response) = dispatch_zai(&state, &body).await {
+        return Ok(response);
+    }
+    crate::proxy::common::param_policy::sanitize(&*state.param_policy.read().await, "google", None, &mut body);
+
     let mut openai_req: OpenAIRequest = serde_json::from_value(body)
         .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid request: {}", e)))?;
 
@@ -98,6 +204,8 @@ pub async fn handle_chat_completions(
             });
     }
 
+    crate::proxy::handlers::common::inline_remote_images_openai(&state, &mut openai_req).await;
+
     let trace_id = format!("req_{}", chrono::Utc::now().timestamp_subsec_millis());
     info!(
         "[{}] OpenAI Chat Request: {} | {} messages | stream: {}",
@@ -128,7 +236,7 @@ pub async fn handle_chat_completions(
 
     // 2. 模型路由解析 (移到循环外以支持在所有路径返回 X-Mapped-Model)
     let mapped_model = crate::proxy::common::model_mapping::resolve_model_route(
-        &openai_req.model,
+        &state.model_aliases.rewrite(&openai_req.model),
         &*state.custom_mapping.read().await,
     );
 
@@ -271,7 +379,7 @@ pub async fn handle_chat_completions(
                 // Loop to skip heartbeats during peek
                 loop {
                     match tokio::time::timeout(
-                        std::time::Duration::from_secs(60),
+                        state.upstream.first_byte_timeout(),
                         openai_stream.next(),
                     )
                     .await
@@ -316,7 +424,7 @@ pub async fn handle_chat_completions(
                         }
                         Err(_) => {
                             tracing::warn!(
-                                "[OpenAI] Timeout waiting for first data (60s), retrying..."
+                                "[OpenAI] Timeout waiting for first data, retrying..."
                             );
                             last_error = "Timeout waiting for first data".to_string();
                             retry_this_account = true;
@@ -568,6 +676,106 @@ pub async fn handle_chat_completions(
     }
 }
 
+/// 处理 Responses API (/v1/responses)
+/// 只支持 Chat Completions 的上游 (Ollama、Azure、Bedrock、OpenAI 兼容) 由本层转换请求与响应,
+/// 其余模型沿用 handle_completions 的 Gemini 转换
+pub async fn handle_responses(State(state): State<AppState>, Json(body): Json<Value>) -> Response {
+    use crate::proxy::mappers::responses::{from_chat_response, to_chat_request, translate_stream};
+
+    let chat = match to_chat_request(&body) {
+        Ok(chat) => chat,
+        Err(e) => return invalid_request(e),
+    };
+    let dispatched = match dispatch_ollama(&state, OllamaEndpoint::Chat, &chat).await {
+        Some(response) => Some(response),
+        None => dispatch_chat_only(&state, &chat).await,
+    };
+    match dispatched {
+        Some(response) => translate_chat_response(response, &body, from_chat_response, translate_stream).await,
+        None => handle_completions(State(state), Json(body)).await,
+    }
+}
+
+fn invalid_request(message: String) -> Response {
+    let error = json!({ "error": { "message": message, "type": "invalid_request_error" } });
+    (StatusCode::BAD_REQUEST, Json(error)).into_response()
+}
+
+/// 按 Azure、Bedrock、OpenAI 兼容上游的顺序转发 Chat Completions 请求
+async fn dispatch_chat_only(state: &AppState, chat: &Value) -> Option<Response> {
+    if let Some(response) = dispatch_azure(state, "chat/completions", chat).await {
+        return Some(response);
+    }
+    if let Some(response) = dispatch_bedrock(state, chat).await {
+        return Some(response);
+    }
+    dispatch_compatible(state, "chat/completions", chat).await
+}
+
+/// 模型命中的上游只提供 Chat Completions (Bedrock, 或标记为 `chat_only` 的 Azure 部署 / 兼容上游)
+async fn routes_to_chat_only(state: &AppState, body: &Value) -> bool {
+    let Some(model) = body.get("model").and_then(|m| m.as_str()) else {
+        return false;
+    };
+    let model = state.model_aliases.rewrite(model);
+    if let Some(route) = crate::proxy::providers::azure_openai::resolve_deployment(&*state.azure_openai.read().await, &model) {
+        return route.chat_only;
+    }
+    if crate::proxy::providers::bedrock::resolve_model_id(&*state.bedrock.read().await, &model).is_some() {
+        return true;
+    }
+    crate::proxy::providers::openai_compat::resolve(&*state.compatible_upstreams.read().await, &model)
+        .is_some_and(|route| route.chat_only)
+}
+
+type ByteStream = futures::stream::BoxStream<'static, Result<Bytes, String>>;
+
+/// Chat Completions 响应 (JSON 或 SSE) 转为客户端请求的格式; 错误响应原样返回
+async fn translate_chat_response(
+    response: Response,
+    request: &Value,
+    convert: fn(&Value, &Value) -> Value,
+    convert_stream: fn(ByteStream, &Value) -> ByteStream,
+) -> Response {
+    use axum::http::{header, HeaderValue};
+    use futures::StreamExt;
+
+    if !response.status().is_success() {
+        return response;
+    }
+    let is_sse = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|ct| ct.contains("text/event-stream"));
+    let (mut parts, body) = response.into_parts();
+    parts.headers.remove(header::CONTENT_LENGTH);
+    let wants_stream = request.get("stream").and_then(Value::as_bool) == Some(true);
+    let body = if is_sse {
+        let upstream = body.into_data_stream().map(|chunk| chunk.map_err(|e| e.to_string())).boxed();
+        axum::body::Body::from_stream(convert_stream(upstream, request))
+    } else {
+        let Ok(bytes) = axum::body::to_bytes(body, usize::MAX).await else {
+            return (StatusCode::BAD_GATEWAY, "Failed to read upstream response").into_response();
+        };
+        let Ok(chat) = serde_json::from_slice::<Value>(&bytes) else {
+            return Response::from_parts(parts, axum::body::Body::from(bytes));
+        };
+        if wants_stream {
+            // 上游忽略了 stream 参数: 先合成 Chat SSE 再转换
+            let events = crate::proxy::mappers::stream_bridge::json_to_sse(&chat).unwrap_or_default();
+            let upstream = futures::stream::iter(events.into_iter().map(Ok)).boxed();
+            parts.headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("text/event-stream"));
+            parts.headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+            axum::body::Body::from_stream(convert_stream(upstream, request))
+        } else {
+            parts.headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("application/json"));
+            axum::body::Body::from(convert(&chat, request).to_string())
+        }
+    };
+    Response::from_parts(parts, body)
+}
+
 /// 处理 Legacy Completions API (/v1/completions)
 /// 将 Prompt 转换为 Chat Message 格式，复用 handle_chat_completions
 pub async fn handle_completions(
@@ -581,6 +789,30 @@ pub async fn handle_completions(
 
     let is_codex_style = body.get("input").is_some() || body.get("instructions").is_some();
 
+    // 传统 Completions (prompt) 可转发到 Ollama `/api/generate`、Azure 部署或 OpenAI 兼容上游;
+    // 只提供 Chat Completions 的上游由本层转换请求与响应
+    if !is_codex_style {
+        if let Some(response) = dispatch_ollama(&state, OllamaEndpoint::Generate, &body).await {
+            return response;
+        }
+        if routes_to_chat_only(&state, &body).await {
+            use crate::proxy::mappers::completions::{from_chat_response, to_chat_request, translate_stream};
+            let chat = match to_chat_request(&body) {
+                Ok(chat) => chat,
+                Err(e) => return invalid_request(e),
+            };
+            if let Some(response) = dispatch_chat_only(&state, &chat).await {
+                return translate_chat_response(response, &body, from_chat_response, translate_stream).await;
+            }
+        }
+        if let Some(response) = dispatch_azure(&state, "completions", &body).await {
+            return response;
+        }
+        if let Some(response) = dispatch_compatible(&state, "completions", &body).await {
+            return response;
+        }
+    }
+
     // 1. Convert Payload to Messages (Shared Chat Format)
     if is_codex_style {
         let instructions = body
@@ -946,6 +1178,8 @@ pub async fn handle_completions(
             });
     }
 
+    crate::proxy::handlers::common::inline_remote_images_openai(&state, &mut openai_req).await;
+
     let upstream = state.upstream.clone();
     let token_manager = state.token_manager;
     let pool_size = token_manager.len();
@@ -957,7 +1191,7 @@ pub async fn handle_completions(
 
     // 2. 模型路由解析 (移到循环外以支持在所有路径返回 X-Mapped-Model)
     let mapped_model = crate::proxy::common::model_mapping::resolve_model_route(
-        &openai_req.model,
+        &state.model_aliases.rewrite(&openai_req.model),
         &*state.custom_mapping.read().await,
     );
     let trace_id = format!("req_{}", chrono::Utc::now().timestamp_subsec_millis());
@@ -1081,7 +1315,7 @@ pub async fn handle_completions(
 
                     loop {
                         match tokio::time::timeout(
-                            std::time::Duration::from_secs(60),
+                            state.upstream.first_byte_timeout(),
                             openai_stream.next(),
                         )
                         .await
@@ -1154,7 +1388,7 @@ pub async fn handle_completions(
                     let mut retry_this_account = false;
                     loop {
                         match tokio::time::timeout(
-                            std::time::Duration::from_secs(60),
+                            state.upstream.first_byte_timeout(),
                             openai_stream.next(),
                         )
                         .await
@@ -1368,25 +1602,11 @@ pub async fn handle_completions(
 }
 
 pub async fn handle_list_models(State(state): State<AppState>) -> impl IntoResponse {
-    use crate::proxy::common::model_mapping::get_all_dynamic_models;
-
-    let model_ids = get_all_dynamic_models(&state.custom_mapping).await;
-
-    let data: Vec<_> = model_ids
-        .into_iter()
-        .map(|id| {
-            json!({
-                "id": id,
-                "object": "model",
-                "created": 1706745600,
-                "owned_by": "antigravity"
-            })
-        })
-        .collect();
+    let models = state.model_catalog.list(&state).await;
 
     Json(json!({
         "object": "list",
-        "data": data
+        "data": *models
     }))
 }
 
@@ -1438,6 +1658,20 @@ pub async fn handle_images_generations(
         style
     );
 
+    // 配置了外部上游的模型 (DALL-E / Stability) 直接转发
+    let images_config = state.images.read().await.clone();
+    if let Some(backend) = crate::proxy::mappers::images::find_backend(&images_config.backends, model) {
+        return crate::proxy::handlers::images::generate_with_backend(
+            &state,
+            backend,
+            &images_config,
+            &body,
+            model,
+            response_format,
+        )
+        .await;
+    }
+
     // 2. 使用 common_utils 解析图片配置（统一逻辑，支持动态计算宽高比和 quality 映射）
     let (image_config, _) = crate::proxy::mappers::common_utils::parse_image_config_with_params(
         model,
@@ -1612,6 +1846,11 @@ pub async fn handle_images_generations(
         n
     );
 
+    crate::proxy::handlers::images::save_images(
+        &images_config,
+        images.iter().filter_map(crate::proxy::handlers::images::inline_image).collect(),
+    );
+
     // 6. 构建 OpenAI 格式响应
     let openai_response = json!({
         "created": chrono::Utc::now().timestamp(),
@@ -0,0 +1,401 @@
+// OpenAI Realtime API WebSocket 透传
+// 先与上游完成握手 (注入配置的 API Key), 成功后再升级客户端连接并双向转发消息;
+// 会话结束时记录时长、消息数与 response.done 中的 token 用量。
+
+use axum::{
+    extract::{
+        ws::{CloseFrame, Message, WebSocket, WebSocketUpgrade},
+        RawQuery, State,
+    },
+    http::{HeaderMap, HeaderName, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use futures::{SinkExt, StreamExt};
+use serde_json::Value;
+use std::time::{Duration, Instant};
+use tokio_tungstenite::tungstenite::{
+    self,
+    client::IntoClientRequest,
+    protocol::{frame::coding::CloseCode, CloseFrame as UpstreamCloseFrame},
+};
+
+use crate::proxy::config::RealtimeConfig;
+use crate::proxy::monitor::ProxyRequestLog;
+use crate::proxy::server::AppState;
+
+const UPSTREAM_CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+/// 1001 Going Away: 服务停止
+const CLOSE_GOING_AWAY: u16 = 1001;
+
+type UpstreamSocket =
+    tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>;
+
+/// 拼接上游地址, 保留客户端的查询参数
+fn upstream_url(base: &str, query: Option<&str>) -> String {
+    match query.filter(|q| !q.is_empty()) {
+        Some(q) if base.contains('?') => format!("{}&{}", base, q),
+        Some(q) => format!("{}?{}", base, q),
+        None => base.to_string(),
+    }
+}
+
+pub(crate) fn query_param(query: Option<&str>, name: &str) -> Option<String> {
+    let url = reqwest::Url::parse(&format!("http://localhost/?{}", query?)).ok()?;
+    let value = url.query_pairs().find(|(k, _)| k == name).map(|(_, v)| v.into_owned());
+    value
+}
+
+fn build_upstream_request(
+    config: &RealtimeConfig,
+    query: Option<&str>,
+) -> Result<tungstenite::handshake::client::Request, String> {
+    let mut request = upstream_url(&config.upstream_url, query)
+        .into_client_request()
+        .map_err(|e| format!("Invalid realtime upstream url: {}", e))?;
+    let headers = request.headers_mut();
+    let auth = HeaderValue::from_str(&format!("Bearer {}", config.api_key.trim()))
+        .map_err(|e| format!("Invalid realtime api_key: {}", e))?;
+    headers.insert("Authorization", auth);
+    for (name, value) in &config.headers {
+        match (HeaderName::from_bytes(name.as_bytes()), HeaderValue::from_str(value)) {
+            (Ok(name), Ok(value)) => {
+                headers.insert(name, value);
+            }
+            _ => tracing::warn!("[Realtime] Ignoring invalid header {:?}", name),
+        }
+    }
+    Ok(request)
+}
+
+fn to_upstream(message: Message) -> tungstenite::Message {
+    match message {
+        Message::Text(text) => tungstenite::Message::Text(text),
+        Message::Binary(data) => tungstenite::Message::Binary(data),
+        Message::Ping(data) => tungstenite::Message::Ping(data),
+        Message::Pong(data) => tungstenite::Message::Pong(data),
+        Message::Close(frame) => tungstenite::Message::Close(frame.map(|f| UpstreamCloseFrame {
+            code: CloseCode::from(f.code),
+            reason: f.reason,
+        })),
+    }
+}
+
+fn to_client(message: tungstenite::Message) -> Option<Message> {
+    Some(match message {
+        tungstenite::Message::Text(text) => Message::Text(text),
+        tungstenite::Message::Binary(data) => Message::Binary(data),
+        tungstenite::Message::Ping(data) => Message::Ping(data),
+        tungstenite::Message::Pong(data) => Message::Pong(data),
+        tungstenite::Message::Close(frame) => Message::Close(frame.map(|f| CloseFrame {
+            code: f.code.into(),
+            reason: f.reason,
+        })),
+        // 原始帧只在读取时出现, 不会由 Stream 返回
+        tungstenite::Message::Frame(_) => return None,
+    })
+}
+
+/// 单个会话的统计
+#[derive(Debug, Default)]
+struct SessionStats {
+    client_messages: u64,
+    upstream_messages: u64,
+    client_bytes: u64,
+    upstream_bytes: u64,
+    input_tokens: u32,
+    output_tokens: u32,
+}
+
+impl SessionStats {
+    /// 累加 `response.done` 事件中的用量
+    fn observe_upstream_text(&mut self, text: &str) {
+        if !text.contains("\"response.done\"") {
+            return;
+        }
+        let Ok(event) = serde_json::from_str::<Value>(text) else {
+            return;
+        };
+        if event.get("type").and_then(|t| t.as_str()) != Some("response.done") {
+            return;
+        }
+        if let Some(usage) = event.pointer("/response/usage") {
+            let get = |key: &str| usage.get(key).and_then(|v| v.as_u64()).unwrap_or(0) as u32;
+            self.input_tokens = self.input_tokens.saturating_add(get("input_tokens"));
+            self.output_tokens = self.output_tokens.saturating_add(get("output_tokens"));
+        }
+    }
+}
+
+fn message_len(message: &Message) -> usize {
+    match message {
+        Message::Text(t) => t.len(),
+        Message::Binary(b) | Message::Ping(b) | Message::Pong(b) => b.len(),
+        Message::Close(_) => 0,
+    }
+}
+
+/// GET /v1/realtime
+pub async fn handle_realtime(
+    State(state): State<AppState>,
+    RawQuery(query): RawQuery,
+    connect_info: Option<axum::extract::ConnectInfo<std::net::SocketAddr>>,
+    headers: HeaderMap,
+    ws: WebSocketUpgrade,
+) -> Response {
+    let config = state.realtime.config();
+    if !config.enabled || config.api_key.trim().is_empty() {
+        return (StatusCode::SERVICE_UNAVAILABLE, "Realtime proxy is not configured").into_response();
+    }
+
+    let request = match build_upstream_request(&config, query.as_deref()) {
+        Ok(r) => r,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+
+    // 先连接上游: 失败时返回明确的 HTTP 错误, 而不是升级后立即断开
+    let upstream = match tokio::time::timeout(UPSTREAM_CONNECT_TIMEOUT, tokio_tungstenite::connect_async(request)).await {
+        Ok(Ok((socket, _))) => socket,
+        Ok(Err(tungstenite::Error::Http(resp))) => {
+            let status = StatusCode::from_u16(resp.status().as_u16()).unwrap_or(StatusCode::BAD_GATEWAY);
+            let body = resp
+                .body()
+                .as_ref()
+                .map(|b| String::from_utf8_lossy(b).to_string())
+                .unwrap_or_default();
+            tracing::warn!("[Realtime] Upstream rejected handshake: {} {}", status, body);
+            return (status, body).into_response();
+        }
+        Ok(Err(e)) => {
+            return (StatusCode::BAD_GATEWAY, format!("Realtime upstream connection failed: {}", e)).into_response();
+        }
+        Err(_) => {
+            return (StatusCode::GATEWAY_TIMEOUT, "Realtime upstream connection timed out").into_response();
+        }
+    };
+
+    let model = query_param(query.as_deref(), "model");
+    let api_key = crate::proxy::middleware::auth::extract_api_key(&headers)
+        .filter(|k| !k.is_empty())
+        .map(str::to_string);
+    let client_info = crate::proxy::common::client_info::identify(&headers, connect_info.map(|c| c.0));
+    let uri = match &query {
+        Some(q) => format!("/v1/realtime?{}", q),
+        None => "/v1/realtime".to_string(),
+    };
+
+    // 浏览器客户端会请求 "realtime" 子协议
+    ws.protocols(["realtime"])
+        .on_upgrade(move |socket| run_session(state, socket, upstream, uri, model, api_key, client_info))
+}
+
+async fn run_session(
+    state: AppState,
+    client: WebSocket,
+    upstream: UpstreamSocket,
+    uri: String,
+    model: Option<String>,
+    api_key: Option<String>,
+    client_info: crate::proxy::common::client_info::ClientInfo,
+) {
+    let session_id = uuid::Uuid::new_v4().to_string();
+    let start = Instant::now();
+    let mut guard = state.realtime.register();
+    tracing::info!(
+        "[Realtime] Session {} opened (model: {}, active: {})",
+        session_id,
+        model.as_deref().unwrap_or("-"),
+        state.realtime.active_sessions()
+    );
+
+    let (mut client_tx, mut client_rx) = client.split();
+    let (mut upstream_tx, mut upstream_rx) = upstream.split();
+    let mut stats = SessionStats::default();
+    let mut error: Option<String> = None;
+
+    let close_reason = loop {
+        tokio::select! {
+            msg = client_rx.next() => match msg {
+                Some(Ok(msg)) => {
+                    stats.client_messages += 1;
+                    stats.client_bytes += message_len(&msg) as u64;
+                    let is_close = matches!(msg, Message::Close(_));
+                    if let Err(e) = upstream_tx.send(to_upstream(msg)).await {
+                        error = Some(format!("upstream send failed: {}", e));
+                        break "upstream error";
+                    }
+                    if is_close {
+                        break "client closed";
+                    }
+                }
+                Some(Err(e)) => {
+                    error = Some(format!("client error: {}", e));
+                    let _ = upstream_tx.send(tungstenite::Message::Close(None)).await;
+                    break "client error";
+                }
+                None => {
+                    let _ = upstream_tx.send(tungstenite::Message::Close(None)).await;
+                    break "client disconnected";
+                }
+            },
+            msg = upstream_rx.next() => match msg {
+                Some(Ok(msg)) => {
+                    let Some(msg) = to_client(msg) else { continue };
+                    stats.upstream_messages += 1;
+                    stats.upstream_bytes += message_len(&msg) as u64;
+                    if let Message::Text(text) = &msg {
+                        stats.observe_upstream_text(text);
+                    }
+                    let is_close = matches!(msg, Message::Close(_));
+                    if client_tx.send(msg).await.is_err() {
+                        let _ = upstream_tx.send(tungstenite::Message::Close(None)).await;
+                        break "client disconnected";
+                    }
+                    if is_close {
+                        break "upstream closed";
+                    }
+                }
+                Some(Err(e)) => {
+                    error = Some(format!("upstream error: {}", e));
+                    let _ = client_tx.send(Message::Close(Some(CloseFrame {
+                        code: 1011,
+                        reason: "upstream error".into(),
+                    }))).await;
+                    break "upstream error";
+                }
+                None => {
+                    let _ = client_tx.send(Message::Close(None)).await;
+                    break "upstream disconnected";
+                }
+            },
+            _ = guard.shutdown.changed() => {
+                let _ = client_tx.send(Message::Close(Some(CloseFrame {
+                    code: CLOSE_GOING_AWAY,
+                    reason: "proxy service stopped".into(),
+                }))).await;
+                let _ = upstream_tx.send(tungstenite::Message::Close(None)).await;
+                break "service stopped";
+            }
+        }
+    };
+    drop(guard);
+
+    let duration = start.elapsed();
+    tracing::info!(
+        "[Realtime] Session {} closed ({}) after {:.1}s: {} client msgs / {} bytes, {} upstream msgs / {} bytes, tokens in {} out {}",
+        session_id,
+        close_reason,
+        duration.as_secs_f64(),
+        stats.client_messages,
+        stats.client_bytes,
+        stats.upstream_messages,
+        stats.upstream_bytes,
+        stats.input_tokens,
+        stats.output_tokens
+    );
+
+    let log = ProxyRequestLog {
+        id: session_id,
+        timestamp: chrono::Utc::now().timestamp_millis(),
+        method: "GET".to_string(),
+        url: uri,
+        status: if error.is_some() { 502 } else { 101 },
+        duration: duration.as_millis() as u64,
+        model: model.clone(),
+        mapped_model: model.clone(),
+        account_email: None,
+        error,
+        request_body: None,
+        response_body: None,
+        input_tokens: Some(stats.input_tokens),
+        output_tokens: Some(stats.output_tokens),
+        protocol: Some("openai-realtime".to_string()),
+        param_adjustments: None,
+        client_name: client_info.name,
+        user_agent: client_info.user_agent,
+        client_port: client_info.source_port,
+        tokens_per_second: None,
+        cache_read_tokens: None,
+        cache_write_tokens: None,
+    };
+
+    let model = model.unwrap_or_else(|| "unknown".to_string());
+    let cost_usd = crate::proxy::common::pricing::request_cost(
+        &state.pricing.read().await.models,
+        &model,
+        None,
+        stats.input_tokens,
+        stats.output_tokens,
+    );
+    let api_key_hash = api_key.as_deref().map(crate::proxy::middleware::auth::api_key_fingerprint);
+    // 会话结束后计入虚拟 Key 的请求数、Token 与花费
+    if let Some(hash) = &api_key_hash {
+        let used = stats.input_tokens as u64 + stats.output_tokens as u64;
+        crate::proxy::virtual_keys::VirtualKeyStore::global().record_usage(hash, used, cost_usd);
+    }
+    let record = crate::modules::usage::UsageRecord {
+        timestamp: chrono::Utc::now().timestamp(),
+        model,
+        upstream: "openai-realtime".to_string(),
+        api_key_hash,
+        api_key_hint: api_key.as_deref().map(crate::proxy::middleware::auth::api_key_hint),
+        client_name: log.client_name.clone(),
+        protocol: log.protocol.clone(),
+        prompt_tokens: stats.input_tokens,
+        completion_tokens: stats.output_tokens,
+        latency_ms: log.duration,
+        status: log.status,
+        cost_usd,
+        ..Default::default()
+    };
+    tokio::task::spawn_blocking(move || {
+        if let Err(e) = crate::modules::usage::record(&record) {
+            tracing::debug!("Failed to record realtime usage: {}", e);
+        }
+    });
+
+    if state.monitor.is_enabled() {
+        state.monitor.log_request(log).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_upstream_url_keeps_query() {
+        assert_eq!(
+            upstream_url("wss://api.openai.com/v1/realtime", Some("model=gpt-4o-realtime-preview")),
+            "wss://api.openai.com/v1/realtime?model=gpt-4o-realtime-preview"
+        );
+        assert_eq!(upstream_url("wss://host/rt?v=1", Some("model=x")), "wss://host/rt?v=1&model=x");
+        assert_eq!(upstream_url("wss://host/rt", None), "wss://host/rt");
+        assert_eq!(query_param(Some("a=1&model=gpt%2Drt"), "model").as_deref(), Some("gpt-rt"));
+    }
+
+    #[test]
+    fn test_upstream_request_injects_auth() {
+        let config = RealtimeConfig {
+            enabled: true,
+            api_key: "sk-upstream".to_string(),
+            ..Default::default()
+        };
+        let request = build_upstream_request(&config, Some("model=m")).unwrap();
+        assert_eq!(request.headers()["Authorization"], "Bearer sk-upstream");
+        assert_eq!(request.headers()["OpenAI-Beta"], "realtime=v1");
+        assert_eq!(request.uri().query(), Some("model=m"));
+    }
+
+    #[test]
+    fn test_usage_from_response_done() {
+        let mut stats = SessionStats::default();
+        stats.observe_upstream_text(r#"{"type":"response.created"}"#);
+        stats.observe_upstream_text(
+            r#"{"type":"response.done","response":{"usage":{"input_tokens":12,"output_tokens":30}}}"#,
+        );
+        stats.observe_upstream_text(
+            r#"{"type":"response.done","response":{"usage":{"input_tokens":3,"output_tokens":4}}}"#,
+        );
+        assert_eq!((stats.input_tokens, stats.output_tokens), (15, 34));
+    }
+}
@@ -0,0 +1,148 @@
+// Rerank 处理器 (Cohere / Jina 风格 `/v1/rerank`)
+// 模型命中 Rerank 上游时转发; 否则经 Embeddings 接口取 query 与文档的向量, 按余弦相似度排序
+
+use std::sync::RwLock;
+
+use axum::{
+    extract::State,
+    http::{HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::{json, Value};
+use tracing::info;
+
+use crate::proxy::config::{EmbeddingProviderKind, RerankConfig, RerankProviderConfig, RerankProviderKind};
+use crate::proxy::mappers::rerank::{
+    build_response, build_upstream_request, default_base_url, find_provider, parse_request, parse_upstream_response,
+    score_by_similarity, RerankRequest,
+};
+use crate::proxy::middleware::request_id::WithRequestId;
+use crate::proxy::server::AppState;
+
+static CONFIG: RwLock<Option<RerankConfig>> = RwLock::new(None);
+
+pub fn configure(config: &RerankConfig) {
+    *CONFIG.write().unwrap_or_else(|e| e.into_inner()) = Some(config.clone());
+}
+
+fn config() -> RerankConfig {
+    CONFIG.read().unwrap_or_else(|e| e.into_inner()).clone().unwrap_or_default()
+}
+
+fn upstream_name(kind: RerankProviderKind) -> &'static str {
+    match kind {
+        RerankProviderKind::Cohere => "cohere",
+        RerankProviderKind::Jina => "jina",
+        RerankProviderKind::Voyage => "voyage",
+    }
+}
+
+/// 处理 Rerank 请求
+pub async fn handle_rerank(State(state): State<AppState>, Json(body): Json<Value>) -> Result<Response, (StatusCode, String)> {
+    let config = config();
+    if !config.enabled {
+        return Err((StatusCode::NOT_FOUND, "Rerank endpoint is disabled. Enable it in proxy settings.".to_string()));
+    }
+    let request = parse_request(&body).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    let model = state.model_aliases.rewrite(&request.model);
+    match find_provider(&config.providers, &model) {
+        Some(provider) => rerank_upstream(&state, provider, &model, &request).await,
+        None => {
+            let embedding_model = Some(config.embedding_model.clone()).filter(|m| !m.is_empty()).unwrap_or(model);
+            rerank_by_embeddings(&state, &embedding_model, &request).await
+        }
+    }
+}
+
+async fn rerank_upstream(
+    state: &AppState,
+    provider: &RerankProviderConfig,
+    model: &str,
+    request: &RerankRequest,
+) -> Result<Response, (StatusCode, String)> {
+    if provider.api_key.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, format!("Rerank provider for '{}' has no API key", provider.pattern)));
+    }
+    let kind = provider.provider;
+    let upstream_model = provider.model.clone().filter(|m| !m.is_empty()).unwrap_or_else(|| model.to_string());
+    let base_url = if provider.base_url.is_empty() { default_base_url(kind) } else { provider.base_url.as_str() };
+    let (url, payload) = build_upstream_request(kind, base_url, &upstream_model, request);
+    info!(
+        "[Rerank] model={} -> {} ({}), documents={}",
+        request.model,
+        upstream_model,
+        upstream_name(kind),
+        request.documents.len()
+    );
+
+    let client = crate::proxy::handlers::common::provider_client(state, "rerank", state.request_timeout)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    let resp = client
+        .post(&url)
+        .with_request_id()
+        .bearer_auth(&provider.api_key)
+        .json(&payload)
+        .send()
+        .await
+        .map_err(|e| (StatusCode::BAD_GATEWAY, format!("Rerank upstream request failed: {}", e)))?;
+    let status = resp.status();
+    let text = resp
+        .text()
+        .await
+        .map_err(|e| (StatusCode::BAD_GATEWAY, format!("Failed to read rerank response: {}", e)))?;
+    if !status.is_success() {
+        let status = StatusCode::from_u16(status.as_u16()).unwrap_or(StatusCode::BAD_GATEWAY);
+        return Err((status, text));
+    }
+    let json: Value =
+        serde_json::from_str(&text).map_err(|e| (StatusCode::BAD_GATEWAY, format!("Invalid rerank response: {}", e)))?;
+    let (scores, tokens) = parse_upstream_response(kind, &json).map_err(|e| (StatusCode::BAD_GATEWAY, e))?;
+    let tokens = tokens.unwrap_or_else(|| estimate_tokens(request));
+
+    let mut response = Json(build_response(&request.model, request, scores, tokens)).into_response();
+    let headers = response.headers_mut();
+    headers.insert("X-Upstream", HeaderValue::from_static(upstream_name(kind)));
+    if let Ok(v) = HeaderValue::from_str(&upstream_model) {
+        headers.insert("X-Mapped-Model", v);
+    }
+    Ok(response)
+}
+
+fn estimate_tokens(request: &RerankRequest) -> u32 {
+    let texts: Vec<Value> = std::iter::once(&request.query).chain(&request.documents).map(|t| json!(t)).collect();
+    crate::proxy::mappers::embeddings::estimate_tokens(&texts)
+}
+
+/// 没有原生上游: 一次请求取 query 与全部文档的向量
+async fn rerank_by_embeddings(
+    state: &AppState,
+    embedding_model: &str,
+    request: &RerankRequest,
+) -> Result<Response, (StatusCode, String)> {
+    let input: Vec<&str> = std::iter::once(request.query.as_str()).chain(request.documents.iter().map(String::as_str)).collect();
+    info!("[Rerank] model={} -> embeddings {} ({} documents)", request.model, embedding_model, request.documents.len());
+    let body = json!({ "model": embedding_model, "input": input });
+    let response = crate::proxy::handlers::embeddings::handle_embeddings(State(state.clone()), Json(body)).await?;
+    let status = response.status();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .map_err(|e| (StatusCode::BAD_GATEWAY, format!("Failed to read embeddings response: {}", e)))?;
+    if !status.is_success() {
+        return Err((status, String::from_utf8_lossy(&bytes).to_string()));
+    }
+    let json: Value = serde_json::from_slice(&bytes)
+        .map_err(|e| (StatusCode::BAD_GATEWAY, format!("Invalid embeddings response: {}", e)))?;
+    let (vectors, tokens) = crate::proxy::mappers::embeddings::parse_upstream_response(EmbeddingProviderKind::Openai, &json)
+        .map_err(|e| (StatusCode::BAD_GATEWAY, e))?;
+    let Some((query, documents)) = vectors.split_first().filter(|(_, docs)| docs.len() == request.documents.len()) else {
+        return Err((StatusCode::BAD_GATEWAY, format!("Embeddings returned {} vectors for {} inputs", vectors.len(), input.len())));
+    };
+    let scores = score_by_similarity(query, documents);
+    let tokens = tokens.unwrap_or_else(|| estimate_tokens(request));
+
+    let mut response = Json(build_response(&request.model, request, scores, tokens)).into_response();
+    response.headers_mut().insert("X-Upstream", HeaderValue::from_static("embeddings"));
+    Ok(response)
+}
@@ -0,0 +1,91 @@
+// 请求对冲
+// 主请求在阈值内没有产生首字节时, 把同一请求改用备用模型再发一次, 返回先响应的一方并取消另一方。
+// 两个请求都会计入上游用量, 适合对尾延迟敏感的交互场景。
+
+use std::sync::{OnceLock, RwLock};
+use std::time::Duration;
+
+use crate::proxy::common::model_mapping::wildcard_match;
+use crate::proxy::config::HedgingConfig;
+
+/// 匹配到的对冲规则
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hedge {
+    pub rule: String,
+    pub backup_model: String,
+    pub threshold: Duration,
+}
+
+fn config() -> &'static RwLock<HedgingConfig> {
+    static CONFIG: OnceLock<RwLock<HedgingConfig>> = OnceLock::new();
+    CONFIG.get_or_init(|| RwLock::new(HedgingConfig::default()))
+}
+
+/// 应用请求对冲配置 (服务启动与配置热更新时调用)
+pub fn configure(hedging: &HedgingConfig) {
+    *config().write().unwrap_or_else(|e| e.into_inner()) = hedging.clone();
+}
+
+/// 是否启用 (中间件据此决定是否读取请求体)
+pub fn is_enabled() -> bool {
+    let config = config().read().unwrap_or_else(|e| e.into_inner());
+    config.enabled && config.rules.iter().any(|r| r.enabled)
+}
+
+/// 第一条匹配模型的规则; 备用模型与请求的模型相同时不对冲
+fn find_rule(config: &HedgingConfig, model: &str) -> Option<Hedge> {
+    if !config.enabled {
+        return None;
+    }
+    config
+        .rules
+        .iter()
+        .find(|r| r.enabled && (r.models.is_empty() || r.models.iter().any(|p| wildcard_match(p, model))))
+        .filter(|r| !r.backup_model.trim().is_empty() && r.backup_model.trim() != model)
+        .map(|r| Hedge {
+            rule: r.name.clone(),
+            backup_model: r.backup_model.trim().to_string(),
+            threshold: Duration::from_millis(r.threshold_ms),
+        })
+}
+
+pub fn rule_for(model: &str) -> Option<Hedge> {
+    find_rule(&config().read().unwrap_or_else(|e| e.into_inner()), model)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy::config::HedgingRule;
+
+    fn rule(name: &str, models: &[&str], backup: &str) -> HedgingRule {
+        HedgingRule {
+            name: name.to_string(),
+            models: models.iter().map(|m| m.to_string()).collect(),
+            backup_model: backup.to_string(),
+            threshold_ms: 1500,
+            enabled: true,
+        }
+    }
+
+    #[test]
+    fn test_find_rule() {
+        let mut disabled = rule("off", &[], "never");
+        disabled.enabled = false;
+        let mut config = HedgingConfig {
+            enabled: true,
+            rules: vec![disabled, rule("gpt", &["gpt-*"], "deepseek-chat"), rule("self", &["deepseek-*"], "deepseek-chat")],
+        };
+
+        let hedge = find_rule(&config, "gpt-4o").unwrap();
+        assert_eq!(hedge.rule, "gpt");
+        assert_eq!(hedge.backup_model, "deepseek-chat");
+        assert_eq!(hedge.threshold, Duration::from_millis(1500));
+        assert_eq!(find_rule(&config, "claude-sonnet-4-5"), None);
+        // 备用模型与主请求相同
+        assert_eq!(find_rule(&config, "deepseek-chat"), None);
+
+        config.enabled = false;
+        assert_eq!(find_rule(&config, "gpt-4o"), None);
+    }
+}
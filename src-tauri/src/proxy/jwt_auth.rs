@@ -0,0 +1,412 @@
+// JWT 鉴权
+// 代理放在 SSO 之后时, 客户端携带 IdP 签发的 JWT (HS256 共享密钥或 RS256 + JWKS) 访问代理接口;
+// 校验通过后按 subject 映射为类似虚拟 Key 的身份, 额度与模型限制复用虚拟 Key 的统计。
+// JWKS 缓存在内存中, 过期或遇到未知 kid 时在后台刷新, 请求路径上不访问 IdP。
+
+use std::collections::HashMap;
+use std::sync::{OnceLock, RwLock};
+use std::time::{Duration, Instant};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use serde_json::Value;
+
+use crate::proxy::config::JwtAuthConfig;
+use crate::proxy::virtual_keys::{RequestModel, VirtualKey, VirtualKeyError, VirtualKeyGrant, VirtualKeyStore};
+
+/// 未知 kid 触发刷新 JWKS 的最小间隔
+const JWKS_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+/// 记住的已校验 token 数量上限 (超出时清理过期项)
+const MAX_VERIFIED_TOKENS: usize = 10_000;
+
+/// 校验通过的 JWT 身份
+#[derive(Debug, Clone, PartialEq)]
+pub struct JwtIdentity {
+    pub subject: String,
+    /// 为空表示允许所有模型
+    pub allowed_models: Vec<String>,
+    pub upstream_account: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+struct RsaKey {
+    n: Vec<u8>,
+    e: Vec<u8>,
+}
+
+#[derive(Debug, Default)]
+struct Jwks {
+    url: String,
+    keys: HashMap<String, RsaKey>,
+    fetched_at: Option<Instant>,
+    /// 后台刷新进行中
+    refreshing: bool,
+}
+
+struct JwtState {
+    config: RwLock<JwtAuthConfig>,
+    jwks: RwLock<Jwks>,
+    /// 签名校验通过的 token (SHA-256) -> (subject, exp), 用于按 subject 聚合用量
+    verified: RwLock<HashMap<String, (String, i64)>>,
+}
+
+fn state() -> &'static JwtState {
+    static STATE: OnceLock<JwtState> = OnceLock::new();
+    STATE.get_or_init(|| JwtState {
+        config: RwLock::new(JwtAuthConfig::default()),
+        jwks: RwLock::new(Jwks::default()),
+        verified: RwLock::new(HashMap::new()),
+    })
+}
+
+fn current_config() -> JwtAuthConfig {
+    state().config.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// 应用 JWT 配置 (服务启动与配置热更新时调用); JWKS 地址变化时清空缓存并在后台重新加载
+pub fn configure(config: &JwtAuthConfig) {
+    let state = state();
+    *state.config.write().unwrap_or_else(|e| e.into_inner()) = config.clone();
+    state.verified.write().unwrap_or_else(|e| e.into_inner()).clear();
+    let changed = {
+        let mut jwks = state.jwks.write().unwrap_or_else(|e| e.into_inner());
+        let changed = jwks.url != config.jwks_url;
+        if changed {
+            *jwks = Jwks { url: config.jwks_url.clone(), ..Default::default() };
+        }
+        changed
+    };
+    if changed && config.enabled && !config.jwks_url.is_empty() {
+        spawn_refresh(&config.jwks_url);
+    }
+}
+
+fn decode_segment(segment: &str) -> Option<Vec<u8>> {
+    URL_SAFE_NO_PAD.decode(segment.trim_end_matches('=')).ok()
+}
+
+fn decode_json(segment: &str) -> Option<Value> {
+    serde_json::from_slice(&decode_segment(segment)?).ok()
+}
+
+/// 拆分为 (header, payload, signature) 三段
+fn split_token(token: &str) -> Option<(&str, &str, &str)> {
+    let mut parts = token.split('.');
+    let parts = (parts.next()?, parts.next()?, parts.next()?, parts.next());
+    match parts {
+        (header, payload, signature, None) if !header.is_empty() && !payload.is_empty() => Some((header, payload, signature)),
+        _ => None,
+    }
+}
+
+fn claim_string(claims: &Value, name: &str) -> Option<String> {
+    match claims.get(name)? {
+        Value::String(s) if !s.is_empty() => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// 启用 JWT 鉴权时, 判断客户端 Key 是否为 JWT
+pub fn looks_like_jwt(key: &str) -> bool {
+    state().config.read().unwrap_or_else(|e| e.into_inner()).enabled
+        && split_token(key).and_then(|(header, _, _)| decode_json(header)).is_some_and(|h| h.get("alg").is_some())
+}
+
+fn token_hash(token: &str) -> String {
+    use sha2::{Digest, Sha256};
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+/// 记住签名校验通过的 token, 到期前可按 subject 识别
+fn remember(token: &str, subject: &str, exp: i64, now: i64) {
+    let mut verified = state().verified.write().unwrap_or_else(|e| e.into_inner());
+    if verified.len() >= MAX_VERIFIED_TOKENS {
+        verified.retain(|_, (_, exp)| *exp > now);
+        if verified.len() >= MAX_VERIFIED_TOKENS {
+            verified.clear();
+        }
+    }
+    verified.insert(token_hash(token), (subject.to_string(), exp));
+}
+
+fn verified_subject_at(key: &str, now: i64) -> Option<String> {
+    let verified = state().verified.read().unwrap_or_else(|e| e.into_inner());
+    verified.get(&token_hash(key)).filter(|(_, exp)| *exp > now).map(|(subject, _)| subject.clone())
+}
+
+/// 已通过签名校验的 JWT 的 subject, 用于用量统计聚合 (同一 subject 每次签发的 token 不同);
+/// 未校验或校验失败的 token 返回 None, 按普通 Key 统计
+pub fn verified_subject(key: &str) -> Option<String> {
+    verified_subject_at(key, chrono::Utc::now().timestamp())
+}
+
+/// 按 `kid` 选择公钥; 未带 kid 时依次尝试所有公钥
+fn verify_rs256(keys: &HashMap<String, RsaKey>, kid: Option<&str>, message: &[u8], signature: &[u8]) -> Result<(), String> {
+    use ring::signature::{RsaPublicKeyComponents, RSA_PKCS1_2048_8192_SHA256};
+    let candidates: Vec<&RsaKey> = match kid {
+        Some(kid) => keys.get(kid).into_iter().collect(),
+        None => keys.values().collect(),
+    };
+    if candidates.is_empty() {
+        return Err(format!("No JWKS key matches kid '{}'", kid.unwrap_or("")));
+    }
+    let valid = candidates.iter().any(|key| {
+        RsaPublicKeyComponents { n: &key.n, e: &key.e }
+            .verify(&RSA_PKCS1_2048_8192_SHA256, message, signature)
+            .is_ok()
+    });
+    if valid {
+        Ok(())
+    } else {
+        Err("Invalid token signature".to_string())
+    }
+}
+
+fn check_claims(config: &JwtAuthConfig, claims: &Value, now: i64) -> Result<JwtIdentity, String> {
+    let leeway = config.leeway_secs as i64;
+    let exp = claims.get("exp").and_then(Value::as_i64).ok_or("Token has no exp claim")?;
+    if now > exp + leeway {
+        return Err("Token has expired".to_string());
+    }
+    if claims.get("nbf").and_then(Value::as_i64).is_some_and(|nbf| now + leeway < nbf) {
+        return Err("Token is not valid yet".to_string());
+    }
+    if !config.issuer.is_empty() && claims.get("iss").and_then(Value::as_str) != Some(config.issuer.as_str()) {
+        return Err("Token issuer does not match".to_string());
+    }
+    if !config.audience.is_empty() {
+        let matches = match claims.get("aud") {
+            Some(Value::String(aud)) => *aud == config.audience,
+            Some(Value::Array(items)) => items.iter().any(|a| a.as_str() == Some(config.audience.as_str())),
+            _ => false,
+        };
+        if !matches {
+            return Err("Token audience does not match".to_string());
+        }
+    }
+    let subject = claim_string(claims, &config.subject_claim)
+        .ok_or_else(|| format!("Token has no '{}' claim", config.subject_claim))?;
+    let allowed_models = match claims.get(&config.models_claim) {
+        Some(Value::Array(items)) => items.iter().filter_map(Value::as_str).map(str::to_string).collect(),
+        Some(Value::String(s)) => s.split_whitespace().map(str::to_string).collect(),
+        _ => Vec::new(),
+    };
+    let upstream_account = Some(config.account_claim.as_str())
+        .filter(|c| !c.is_empty())
+        .and_then(|c| claim_string(claims, c));
+    Ok(JwtIdentity { subject, allowed_models, upstream_account })
+}
+
+/// 校验签名与声明 (不访问网络, RS256 使用给定的公钥集合)
+fn verify_with(config: &JwtAuthConfig, keys: &HashMap<String, RsaKey>, token: &str, now: i64) -> Result<JwtIdentity, String> {
+    let (header_b64, payload_b64, signature_b64) = split_token(token).ok_or("Malformed token")?;
+    let header = decode_json(header_b64).ok_or("Malformed token header")?;
+    let claims = decode_json(payload_b64).ok_or("Malformed token payload")?;
+    let signature = decode_segment(signature_b64).ok_or("Malformed token signature")?;
+    let message = format!("{}.{}", header_b64, payload_b64);
+
+    match header.get("alg").and_then(Value::as_str) {
+        Some("HS256") if !config.hmac_secret.is_empty() => {
+            let key = ring::hmac::Key::new(ring::hmac::HMAC_SHA256, config.hmac_secret.as_bytes());
+            ring::hmac::verify(&key, message.as_bytes(), &signature).map_err(|_| "Invalid token signature")?;
+        }
+        Some("RS256") if !config.jwks_url.is_empty() => {
+            verify_rs256(keys, header.get("kid").and_then(Value::as_str), message.as_bytes(), &signature)?;
+        }
+        alg => return Err(format!("Unsupported token algorithm '{}'", alg.unwrap_or("none"))),
+    }
+    check_claims(config, &claims, now)
+}
+
+fn parse_jwks(body: &Value) -> HashMap<String, RsaKey> {
+    let keys = body.get("keys").and_then(Value::as_array).cloned().unwrap_or_default();
+    keys.iter()
+        .filter(|k| k.get("kty").and_then(Value::as_str) == Some("RSA"))
+        .filter(|k| k.get("use").and_then(Value::as_str).is_none_or(|u| u == "sig"))
+        .enumerate()
+        .filter_map(|(i, k)| {
+            let n = decode_segment(k.get("n")?.as_str()?)?;
+            let e = decode_segment(k.get("e")?.as_str()?)?;
+            let kid = k.get("kid").and_then(Value::as_str).map(str::to_string).unwrap_or_else(|| format!("#{}", i));
+            Some((kid, RsaKey { n, e }))
+        })
+        .collect()
+}
+
+async fn fetch_jwks(url: &str) -> Result<HashMap<String, RsaKey>, String> {
+    let response = crate::utils::http::get_client()
+        .get(url)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to fetch JWKS: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("Failed to fetch JWKS: HTTP {}", response.status()));
+    }
+    let body: Value = response.json().await.map_err(|e| format!("Invalid JWKS: {}", e))?;
+    let keys = parse_jwks(&body);
+    tracing::info!("[JwtAuth] Loaded {} signing keys from {}", keys.len(), url);
+    Ok(keys)
+}
+
+/// 在后台刷新 JWKS (同一地址同时只有一个刷新任务)
+fn spawn_refresh(url: &str) {
+    let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+        return;
+    };
+    {
+        let mut jwks = state().jwks.write().unwrap_or_else(|e| e.into_inner());
+        if jwks.url != url || jwks.refreshing {
+            return;
+        }
+        jwks.refreshing = true;
+    }
+    let url = url.to_string();
+    runtime.spawn(async move {
+        let result = fetch_jwks(&url).await;
+        let mut jwks = state().jwks.write().unwrap_or_else(|e| e.into_inner());
+        if jwks.url != url {
+            return;
+        }
+        jwks.refreshing = false;
+        // 刷新失败时继续使用旧的公钥, 并推迟下一次刷新
+        jwks.fetched_at = Some(Instant::now());
+        match result {
+            Ok(keys) => jwks.keys = keys,
+            Err(e) => tracing::warn!("[JwtAuth] {}", e),
+        }
+    });
+}
+
+/// 当前缓存的公钥集合; 缓存过期或遇到未知 kid 时触发后台刷新, 本次校验仍使用缓存
+fn signing_keys(config: &JwtAuthConfig, kid: Option<&str>) -> HashMap<String, RsaKey> {
+    let (keys, stale) = {
+        let jwks = state().jwks.read().unwrap_or_else(|e| e.into_inner());
+        let age = jwks.fetched_at.map(|t| t.elapsed());
+        let expired = age.is_none_or(|age| age >= Duration::from_secs(config.jwks_cache_secs));
+        let unknown_kid = kid.is_some_and(|kid| !jwks.keys.contains_key(kid))
+            && age.is_none_or(|age| age >= JWKS_REFRESH_INTERVAL);
+        (jwks.keys.clone(), expired || unknown_kid)
+    };
+    if stale {
+        spawn_refresh(&config.jwks_url);
+    }
+    keys
+}
+
+/// 校验客户端携带的 JWT; 校验通过的 token 在到期前可由 `verified_subject` 识别
+pub async fn verify(token: &str) -> Result<JwtIdentity, String> {
+    let config = current_config();
+    let (header, payload, _) = split_token(token).ok_or("Malformed token")?;
+    let header = decode_json(header).ok_or("Malformed token")?;
+    let keys = if header.get("alg").and_then(Value::as_str) == Some("RS256") && !config.jwks_url.is_empty() {
+        signing_keys(&config, header.get("kid").and_then(Value::as_str))
+    } else {
+        HashMap::new()
+    };
+    let now = chrono::Utc::now().timestamp();
+    let identity = verify_with(&config, &keys, token, now)?;
+    let exp = decode_json(payload).and_then(|claims| claims.get("exp").and_then(Value::as_i64)).unwrap_or(now);
+    remember(token, &identity.subject, exp + config.leeway_secs as i64, now);
+    Ok(identity)
+}
+
+/// subject 对应的虚拟 Key (不持久化), 指纹与用量统计中的 `jwt:<subject>` 一致
+fn subject_key(config: &JwtAuthConfig, identity: &JwtIdentity) -> VirtualKey {
+    use sha2::{Digest, Sha256};
+    VirtualKey {
+        id: String::new(),
+        name: format!("jwt:{}", identity.subject),
+        key_hash: format!("{:x}", Sha256::digest(format!("jwt:{}", identity.subject).as_bytes())),
+        key_hint: format!("jwt:{}", identity.subject),
+        allowed_models: identity.allowed_models.clone(),
+        request_quota: config.request_quota,
+        token_quota: config.token_quota,
+        quota_period: config.quota_period,
+        monthly_spend_cap: config.monthly_spend_cap,
+        expires_at: None,
+        upstream_account: identity.upstream_account.clone(),
+        created_at: 0,
+        revoked: false,
+    }
+}
+
+/// 按 subject 校验可用模型与额度
+pub fn authorize(identity: &JwtIdentity, model: RequestModel) -> Result<VirtualKeyGrant, VirtualKeyError> {
+    VirtualKeyStore::global().authorize_subject(subject_key(&current_config(), identity), model)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sign_hs256(secret: &str, header: Value, claims: Value) -> String {
+        let header = URL_SAFE_NO_PAD.encode(header.to_string());
+        let payload = URL_SAFE_NO_PAD.encode(claims.to_string());
+        let key = ring::hmac::Key::new(ring::hmac::HMAC_SHA256, secret.as_bytes());
+        let signature = ring::hmac::sign(&key, format!("{}.{}", header, payload).as_bytes());
+        format!("{}.{}.{}", header, payload, URL_SAFE_NO_PAD.encode(signature.as_ref()))
+    }
+
+    #[test]
+    fn test_verify_hs256_claims() {
+        let config = JwtAuthConfig {
+            enabled: true,
+            issuer: "https://sso.example.com".to_string(),
+            audience: "aio-proxy".to_string(),
+            hmac_secret: "shared-secret".to_string(),
+            account_claim: "upstream".to_string(),
+            ..Default::default()
+        };
+        let keys = HashMap::new();
+        let now = 1_700_000_000;
+        let claims = json!({
+            "sub": "alice", "iss": "https://sso.example.com", "aud": ["other", "aio-proxy"],
+            "exp": now + 300, "allowed_models": "gemini-* claude-*", "upstream": "a@example.com"
+        });
+        let token = sign_hs256("shared-secret", json!({"alg": "HS256", "typ": "JWT"}), claims.clone());
+        let identity = verify_with(&config, &keys, &token, now).unwrap();
+        assert_eq!(identity.subject, "alice");
+        assert_eq!(identity.allowed_models, vec!["gemini-*", "claude-*"]);
+        assert_eq!(identity.upstream_account.as_deref(), Some("a@example.com"));
+
+        // 过期 (超过容差)、签名错误、受众不符、alg=none 均拒绝
+        assert_eq!(verify_with(&config, &keys, &token, now + 300 + 61).unwrap_err(), "Token has expired");
+        assert!(verify_with(&config, &keys, &token, now + 300 + 60).is_ok());
+        let forged = sign_hs256("wrong-secret", json!({"alg": "HS256"}), claims.clone());
+        assert_eq!(verify_with(&config, &keys, &forged, now).unwrap_err(), "Invalid token signature");
+        let mut other_aud = claims.clone();
+        other_aud["aud"] = json!("someone-else");
+        let token = sign_hs256("shared-secret", json!({"alg": "HS256"}), other_aud);
+        assert_eq!(verify_with(&config, &keys, &token, now).unwrap_err(), "Token audience does not match");
+        let unsigned = format!("{}.{}.", URL_SAFE_NO_PAD.encode(r#"{"alg":"none"}"#), URL_SAFE_NO_PAD.encode(claims.to_string()));
+        assert!(verify_with(&config, &keys, &unsigned, now).unwrap_err().contains("Unsupported token algorithm"));
+        // 只配置了 HS256 时不接受 RS256
+        let rs = sign_hs256("shared-secret", json!({"alg": "RS256"}), claims);
+        assert!(verify_with(&config, &keys, &rs, now).is_err());
+    }
+
+    #[test]
+    fn test_verified_subject_cache() {
+        let now = 1_700_000_000;
+        remember("token-verified", "alice", now + 60, now);
+        assert_eq!(verified_subject_at("token-verified", now).as_deref(), Some("alice"));
+        assert_eq!(verified_subject_at("token-verified", now + 61), None);
+        // 未经校验的 token 不会按 claims 中的 subject 归属
+        assert_eq!(verified_subject_at("token-forged", now), None);
+    }
+
+    #[test]
+    fn test_parse_jwks() {
+        let body = json!({"keys": [
+            {"kty": "RSA", "kid": "k1", "use": "sig", "n": "AQAB", "e": "AQAB"},
+            {"kty": "RSA", "kid": "enc", "use": "enc", "n": "AQAB", "e": "AQAB"},
+            {"kty": "EC", "kid": "ec", "crv": "P-256"},
+            {"kty": "RSA", "n": "AQAB", "e": "AQAB"}
+        ]});
+        let keys = parse_jwks(&body);
+        assert_eq!(keys.len(), 2);
+        assert_eq!(keys["k1"].e, vec![1, 0, 1]);
+        assert!(verify_rs256(&keys, Some("missing"), b"m", b"s").unwrap_err().contains("missing"));
+    }
+}
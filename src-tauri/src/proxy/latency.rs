@@ -0,0 +1,186 @@
+// 延迟统计
+// 按 模型 + 上游 保留最近一段时间内每个请求的总耗时与首 Token 时间 (TTFT, 仅流式响应),
+// 查询时计算 p50 / p95 / p99 与分桶直方图, 供仪表盘的延迟图表使用。
+// 与 Prometheus 的累计直方图不同, 这里只反映滑动窗口内的情况。
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+/// 样本保留时长 (查询窗口的上限)
+pub const MAX_WINDOW: Duration = Duration::from_secs(60 * 60);
+/// 每个 模型 + 上游 最多保留的样本数
+const MAX_SAMPLES: usize = 5000;
+/// 直方图分桶上界 (毫秒), 超出最后一个上界的样本计入溢出桶
+const BUCKETS_MS: [u64; 10] = [250, 500, 1_000, 2_000, 5_000, 10_000, 20_000, 30_000, 60_000, 120_000];
+
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    at: Instant,
+    total_ms: u64,
+    ttft_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct LatencySummary {
+    pub count: usize,
+    pub avg_ms: f64,
+    pub p50_ms: u64,
+    pub p95_ms: u64,
+    pub p99_ms: u64,
+    pub max_ms: u64,
+    /// 与分桶上界对应的计数; 最后一项为溢出桶 (`le_ms` 为 null)
+    pub histogram: Vec<HistogramBucket>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct HistogramBucket {
+    pub le_ms: Option<u64>,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LatencyStats {
+    pub model: String,
+    pub upstream: String,
+    pub total: LatencySummary,
+    /// 窗口内没有流式请求时为 null
+    pub ttft: Option<LatencySummary>,
+}
+
+/// 最近邻秩百分位 (输入已排序且非空)
+fn percentile(sorted: &[u64], p: f64) -> u64 {
+    let rank = (p * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+fn summarize(mut values: Vec<u64>) -> Option<LatencySummary> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_unstable();
+    let mut histogram: Vec<HistogramBucket> = BUCKETS_MS
+        .iter()
+        .map(|&le| HistogramBucket { le_ms: Some(le), count: 0 })
+        .chain(std::iter::once(HistogramBucket { le_ms: None, count: 0 }))
+        .collect();
+    for &value in &values {
+        let index = BUCKETS_MS.iter().position(|&le| value <= le).unwrap_or(BUCKETS_MS.len());
+        histogram[index].count += 1;
+    }
+    Some(LatencySummary {
+        count: values.len(),
+        avg_ms: values.iter().sum::<u64>() as f64 / values.len() as f64,
+        p50_ms: percentile(&values, 0.50),
+        p95_ms: percentile(&values, 0.95),
+        p99_ms: percentile(&values, 0.99),
+        max_ms: values[values.len() - 1],
+        histogram,
+    })
+}
+
+/// 滑动窗口延迟统计 (进程内, 服务重启后清空)
+#[derive(Default)]
+pub struct LatencyTracker {
+    samples: Mutex<HashMap<(String, String), VecDeque<Sample>>>,
+}
+
+impl LatencyTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 记录一个已完成的请求; `ttft` 仅流式响应有值
+    pub fn record(&self, model: &str, upstream: &str, total: Duration, ttft: Option<Duration>) {
+        self.record_at(model, upstream, total, ttft, Instant::now());
+    }
+
+    fn record_at(&self, model: &str, upstream: &str, total: Duration, ttft: Option<Duration>, now: Instant) {
+        let sample = Sample {
+            at: now,
+            total_ms: total.as_millis() as u64,
+            ttft_ms: ttft.map(|t| t.as_millis() as u64),
+        };
+        let mut samples = self.samples.lock().unwrap_or_else(|e| e.into_inner());
+        let queue = samples.entry((model.to_string(), upstream.to_string())).or_default();
+        if queue.len() >= MAX_SAMPLES {
+            queue.pop_front();
+        }
+        queue.push_back(sample);
+    }
+
+    /// 最近 `window` 内的统计, 按请求数从多到少排序; 窗口最长为 [`MAX_WINDOW`]
+    pub fn snapshot(&self, window: Duration) -> Vec<LatencyStats> {
+        self.snapshot_at(window, Instant::now())
+    }
+
+    fn snapshot_at(&self, window: Duration, now: Instant) -> Vec<LatencyStats> {
+        let window = window.min(MAX_WINDOW);
+        let mut samples = self.samples.lock().unwrap_or_else(|e| e.into_inner());
+        // 顺便清理过期样本
+        samples.retain(|_, queue| {
+            while queue.front().is_some_and(|s| now.duration_since(s.at) > MAX_WINDOW) {
+                queue.pop_front();
+            }
+            !queue.is_empty()
+        });
+        let mut stats: Vec<LatencyStats> = samples
+            .iter()
+            .filter_map(|((model, upstream), queue)| {
+                let recent: Vec<&Sample> = queue.iter().filter(|s| now.duration_since(s.at) <= window).collect();
+                let total = summarize(recent.iter().map(|s| s.total_ms).collect())?;
+                Some(LatencyStats {
+                    model: model.clone(),
+                    upstream: upstream.clone(),
+                    total,
+                    ttft: summarize(recent.iter().filter_map(|s| s.ttft_ms).collect()),
+                })
+            })
+            .collect();
+        stats.sort_by(|a, b| b.total.count.cmp(&a.total.count).then_with(|| a.model.cmp(&b.model)));
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentiles_and_window() {
+        let tracker = LatencyTracker::new();
+        let start = Instant::now();
+        let ms = Duration::from_millis;
+        for i in 1..=100 {
+            let ttft = (i % 2 == 0).then(|| ms(i * 10));
+            tracker.record_at("gemini-2.5-pro", "google", ms(i * 100), ttft, start);
+        }
+        tracker.record_at("glm-4.6", "zai", ms(800), None, start);
+
+        let now = start + Duration::from_secs(60);
+        let stats = tracker.snapshot_at(Duration::from_secs(300), now);
+        assert_eq!(stats.len(), 2);
+        let gemini = &stats[0];
+        assert_eq!((gemini.model.as_str(), gemini.upstream.as_str()), ("gemini-2.5-pro", "google"));
+        assert_eq!(gemini.total.count, 100);
+        assert_eq!((gemini.total.p50_ms, gemini.total.p95_ms, gemini.total.p99_ms), (5_000, 9_500, 9_900));
+        assert_eq!(gemini.total.max_ms, 10_000);
+        assert_eq!(gemini.total.avg_ms, 5_050.0);
+        // <=250: 100, 200; <=500: 300..500
+        assert_eq!(gemini.total.histogram[0].count, 2);
+        assert_eq!(gemini.total.histogram[1].count, 3);
+        assert_eq!(gemini.total.histogram.iter().map(|b| b.count).sum::<usize>(), 100);
+        let ttft = gemini.ttft.as_ref().unwrap();
+        assert_eq!((ttft.count, ttft.p50_ms, ttft.max_ms), (50, 500, 1_000));
+        assert!(stats[1].ttft.is_none());
+
+        // 窗口外的样本不计入
+        tracker.record_at("glm-4.6", "zai", ms(1_200), None, now);
+        let stats = tracker.snapshot_at(Duration::from_secs(30), now);
+        assert_eq!(stats.len(), 1);
+        assert_eq!((stats[0].total.count, stats[0].total.p50_ms), (1, 1_200));
+        assert!(tracker.snapshot_at(Duration::from_secs(60), now + MAX_WINDOW * 2).is_empty());
+    }
+}
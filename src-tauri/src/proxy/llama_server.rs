@@ -0,0 +1,350 @@
+// 本地 llama-server (llama.cpp) 托管
+// 启用后反代启动时拉起 llama-server 并监控: 进程退出时按 restart_delay_secs 指数退避重启, 连续崩溃超过
+// max_restarts 次后停止重试 (配置变更后重新开始)。进程就绪后以 OpenAI 兼容上游的形式参与路由,
+// 请求的模型名 (别名改写后) 等于 `alias` 时转发。llama-server 只监听本机并使用每次启动随机生成的 API Key。
+
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::{Child, Command};
+use tokio::sync::Notify;
+
+use crate::proxy::config::LlamaServerConfig;
+use crate::proxy::providers::openai_compat::CompatRoute;
+
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+#[cfg(target_os = "windows")]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+/// 稳定运行超过该时长后重置连续崩溃计数
+const STABLE_AFTER: Duration = Duration::from_secs(60);
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LlamaServerStatus {
+    pub running: bool,
+    /// `/health` 已返回 200 (模型加载完成)
+    pub ready: bool,
+    pub pid: Option<u32>,
+    pub base_url: Option<String>,
+    pub model: Option<String>,
+    /// 本次配置下连续崩溃的次数
+    pub restarts: u32,
+    pub last_error: Option<String>,
+}
+
+pub struct LlamaServer {
+    config: RwLock<LlamaServerConfig>,
+    status: RwLock<LlamaServerStatus>,
+    api_key: String,
+    /// 反代运行中
+    active: AtomicBool,
+    spawned: AtomicBool,
+    /// 配置变更或启停
+    changed: Notify,
+}
+
+/// 模型名: `alias` 为空时使用模型文件名 (不含扩展名)
+pub fn model_name(config: &LlamaServerConfig) -> String {
+    if !config.alias.trim().is_empty() {
+        return config.alias.trim().to_string();
+    }
+    std::path::Path::new(&config.model_path)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "local".to_string())
+}
+
+/// llama-server 命令行参数 (不含程序路径)
+pub fn build_args(config: &LlamaServerConfig, api_key: &str) -> Vec<String> {
+    let mut args: Vec<String> = vec![
+        "-m".into(),
+        config.model_path.clone(),
+        "--host".into(),
+        "127.0.0.1".into(),
+        "--port".into(),
+        config.port.to_string(),
+        "--alias".into(),
+        model_name(config),
+        "--api-key".into(),
+        api_key.to_string(),
+        "-c".into(),
+        config.ctx_size.to_string(),
+    ];
+    if let Some(layers) = config.gpu_layers {
+        args.extend(["-ngl".into(), layers.to_string()]);
+    }
+    if let Some(threads) = config.threads {
+        args.extend(["-t".into(), threads.to_string()]);
+    }
+    args.extend(config.extra_args.iter().cloned());
+    args
+}
+
+fn backoff(delay_secs: u64, restarts: u32) -> Duration {
+    let delay = Duration::from_secs(delay_secs.max(1)).saturating_mul(1 << restarts.saturating_sub(1).min(8));
+    delay.min(MAX_BACKOFF)
+}
+
+impl LlamaServer {
+    pub fn global() -> &'static LlamaServer {
+        static INSTANCE: OnceLock<LlamaServer> = OnceLock::new();
+        INSTANCE.get_or_init(|| LlamaServer {
+            config: RwLock::new(LlamaServerConfig::default()),
+            status: RwLock::new(LlamaServerStatus::default()),
+            api_key: format!("sk-local-{}", uuid::Uuid::new_v4().simple()),
+            active: AtomicBool::new(false),
+            spawned: AtomicBool::new(false),
+            changed: Notify::new(),
+        })
+    }
+
+    pub fn config(&self) -> LlamaServerConfig {
+        self.config.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    pub fn status(&self) -> LlamaServerStatus {
+        self.status.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    fn set_status(&self, f: impl FnOnce(&mut LlamaServerStatus)) {
+        f(&mut self.status.write().unwrap_or_else(|e| e.into_inner()));
+    }
+
+    /// 进程运行中且模型名匹配时返回上游
+    pub fn route(&self, model: &str) -> Option<CompatRoute> {
+        let config = self.config();
+        let status = self.status();
+        if !config.enabled || !status.running || model_name(&config) != model {
+            return None;
+        }
+        Some(CompatRoute {
+            name: "llama-server".to_string(),
+            base_url: status.base_url?,
+            api_key: self.api_key.clone(),
+            preset: None,
+            chat_only: false,
+        })
+    }
+
+    /// 返回进程与最后一行输出 (崩溃时作为错误信息)
+    fn spawn(&self, config: &LlamaServerConfig) -> Result<(Child, Arc<Mutex<String>>), String> {
+        if config.binary_path.trim().is_empty() || config.model_path.trim().is_empty() {
+            return Err("binary_path and model_path must be set".to_string());
+        }
+        let mut cmd = Command::new(config.binary_path.trim());
+        cmd.args(build_args(config, &self.api_key))
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        #[cfg(target_os = "windows")]
+        cmd.creation_flags(CREATE_NO_WINDOW);
+        let mut child = cmd.spawn().map_err(|e| format!("Failed to start llama-server: {}", e))?;
+        let last_line = Arc::new(Mutex::new(String::new()));
+        if let Some(stdout) = child.stdout.take() {
+            spawn_log_reader(stdout, last_line.clone());
+        }
+        if let Some(stderr) = child.stderr.take() {
+            spawn_log_reader(stderr, last_line.clone());
+        }
+        Ok((child, last_line))
+    }
+
+    /// 监控循环: 每次配置变更或进程退出后重新决定是否 (重新) 启动
+    async fn supervise(&'static self) {
+        let mut restarts = 0u32;
+        let mut applied: Option<LlamaServerConfig> = None;
+        loop {
+            let config = self.config();
+            if applied.as_ref() != Some(&config) {
+                restarts = 0;
+                applied = Some(config.clone());
+            }
+            if !self.active.load(Ordering::SeqCst) || !config.enabled {
+                self.set_status(|s| *s = LlamaServerStatus { last_error: s.last_error.take(), ..Default::default() });
+                self.changed.notified().await;
+                continue;
+            }
+            if restarts > config.max_restarts {
+                self.set_status(|s| {
+                    s.running = false;
+                    s.ready = false;
+                    s.pid = None;
+                    s.last_error = Some(format!("Gave up after {} consecutive crashes", restarts));
+                });
+                self.changed.notified().await;
+                continue;
+            }
+
+            let mut config = config;
+            if let Err(e) = resolve_port(&mut config) {
+                tracing::warn!("[LlamaServer] {}", e);
+                self.set_status(|s| s.last_error = Some(e));
+                restarts += 1;
+                self.wait_or_change(backoff(config.restart_delay_secs, restarts)).await;
+                continue;
+            }
+            let (mut child, last_line) = match self.spawn(&config) {
+                Ok(spawned) => spawned,
+                Err(e) => {
+                    tracing::warn!("[LlamaServer] {}", e);
+                    self.set_status(|s| s.last_error = Some(e));
+                    restarts += 1;
+                    self.wait_or_change(backoff(config.restart_delay_secs, restarts)).await;
+                    continue;
+                }
+            };
+            let base_url = format!("http://127.0.0.1:{}/v1", config.port);
+            tracing::info!("[LlamaServer] Started pid {:?} on {} ({})", child.id(), base_url, model_name(&config));
+            self.set_status(|s| {
+                s.running = true;
+                s.ready = false;
+                s.pid = child.id();
+                s.base_url = Some(base_url);
+                s.model = Some(model_name(&config));
+                s.restarts = restarts;
+            });
+            let started = Instant::now();
+            let probe = async {
+                wait_until_ready(config.port).await;
+                tracing::info!("[LlamaServer] Model loaded in {:.1}s", started.elapsed().as_secs_f64());
+                self.set_status(|s| s.ready = true);
+                std::future::pending::<()>().await
+            };
+
+            tokio::select! {
+                exit = child.wait() => {
+                    let mut message = match exit {
+                        Ok(status) => format!("llama-server exited ({})", status),
+                        Err(e) => format!("llama-server wait failed: {}", e),
+                    };
+                    let last_line = last_line.lock().unwrap_or_else(|e| e.into_inner()).clone();
+                    if !last_line.is_empty() {
+                        message = format!("{}: {}", message, last_line);
+                    }
+                    tracing::warn!("[LlamaServer] {}", message);
+                    restarts = if started.elapsed() >= STABLE_AFTER { 1 } else { restarts + 1 };
+                    self.set_status(|s| {
+                        s.running = false;
+                        s.ready = false;
+                        s.pid = None;
+                        s.restarts = restarts;
+                        s.last_error = Some(message);
+                    });
+                    self.wait_or_change(backoff(config.restart_delay_secs, restarts)).await;
+                }
+                _ = self.changed.notified() => {
+                    let _ = child.kill().await;
+                    tracing::info!("[LlamaServer] Stopped for configuration change or shutdown");
+                }
+                _ = probe => {}
+            }
+        }
+    }
+
+    async fn wait_or_change(&self, delay: Duration) {
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => {}
+            _ = self.changed.notified() => {}
+        }
+    }
+}
+
+/// 端口只供反代访问, 被占用时直接改用后续的空闲端口
+fn resolve_port(config: &mut LlamaServerConfig) -> Result<(), String> {
+    use crate::utils::ports;
+    if ports::is_available("127.0.0.1", config.port) {
+        return Ok(());
+    }
+    let conflict = ports::describe_conflict(config.port);
+    let port = ports::find_available("127.0.0.1", config.port).ok_or_else(|| format!("{}; no free port nearby", conflict))?;
+    ports::report_fallback("llama-server", config.port, port, conflict);
+    config.port = port;
+    Ok(())
+}
+
+async fn wait_until_ready(port: u16) {
+    let Ok(client) = reqwest::Client::builder().timeout(Duration::from_secs(2)).no_proxy().build() else {
+        return;
+    };
+    let url = format!("http://127.0.0.1:{}/health", port);
+    loop {
+        if client.get(&url).send().await.is_ok_and(|r| r.status().is_success()) {
+            return;
+        }
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+}
+
+fn spawn_log_reader<R>(stream: R, last_line: Arc<Mutex<String>>)
+where
+    R: AsyncRead + Unpin + Send + 'static,
+{
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(stream).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            tracing::debug!("[llama-server] {}", line);
+            *last_line.lock().unwrap_or_else(|e| e.into_inner()) = line;
+        }
+    });
+}
+
+pub fn configure(config: &LlamaServerConfig) {
+    let server = LlamaServer::global();
+    let mut current = server.config.write().unwrap_or_else(|e| e.into_inner());
+    if *current != *config {
+        *current = config.clone();
+        drop(current);
+        server.changed.notify_one();
+    }
+}
+
+/// 反代启动时调用 (监控任务只创建一次)
+pub fn start() {
+    let server = LlamaServer::global();
+    server.active.store(true, Ordering::SeqCst);
+    if !server.spawned.swap(true, Ordering::SeqCst) {
+        tokio::spawn(server.supervise());
+    }
+    server.changed.notify_one();
+}
+
+/// 反代停止时结束 llama-server 进程
+pub fn stop() {
+    let server = LlamaServer::global();
+    server.active.store(false, Ordering::SeqCst);
+    server.changed.notify_one();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_args_and_backoff() {
+        let config = LlamaServerConfig {
+            enabled: true,
+            binary_path: "/opt/llama.cpp/llama-server".to_string(),
+            model_path: "/models/Qwen2.5-7B-Instruct-Q4_K_M.gguf".to_string(),
+            gpu_layers: Some(99),
+            extra_args: vec!["--flash-attn".to_string()],
+            ..LlamaServerConfig::default()
+        };
+        assert_eq!(model_name(&config), "Qwen2.5-7B-Instruct-Q4_K_M");
+        let args = build_args(&config, "k");
+        assert_eq!(&args[..4], &["-m", "/models/Qwen2.5-7B-Instruct-Q4_K_M.gguf", "--host", "127.0.0.1"]);
+        assert!(args.windows(2).any(|w| w == ["-ngl", "99"]));
+        assert!(args.windows(2).any(|w| w == ["--api-key", "k"]));
+        assert_eq!(args.last().unwrap(), "--flash-attn");
+
+        assert_eq!(backoff(3, 1), Duration::from_secs(3));
+        assert_eq!(backoff(3, 3), Duration::from_secs(12));
+        assert_eq!(backoff(3, 30), MAX_BACKOFF);
+    }
+}
@@ -0,0 +1,39 @@
+// Anthropic Stream Collector
+// 将 OpenAI SSE 流收集为完整的 Anthropic Messages 响应 (供非 Stream 的 Claude 客户端使用)
+
+use super::response::transform_anthropic_response;
+use crate::proxy::mappers::claude::models::ClaudeResponse;
+use bytes::Bytes;
+
+/// Collects an OpenAI SSE stream into a complete ClaudeResponse
+pub async fn collect_stream_to_json<S, E>(stream: S) -> Result<ClaudeResponse, String>
+where
+    S: futures::Stream<Item = Result<Bytes, E>> + Unpin,
+    E: std::fmt::Display,
+{
+    let openai_response = crate::proxy::mappers::openai::collector::collect_stream_to_json(stream).await?;
+    Ok(transform_anthropic_response(&openai_response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy::mappers::claude::models::ContentBlock;
+    use futures::stream;
+
+    #[tokio::test]
+    async fn test_collect_openai_stream_as_anthropic() {
+        let chunks = vec![
+            "data: {\"id\":\"chatcmpl-9\",\"model\":\"gpt-4o\",\"choices\":[{\"index\":0,\"delta\":{\"content\":\"Hello\"}}]}\n\n",
+            "data: {\"choices\":[{\"index\":0,\"delta\":{\"content\":\" there\"},\"finish_reason\":\"length\"}],\"usage\":{\"prompt_tokens\":5,\"completion_tokens\":2,\"total_tokens\":7}}\n\n",
+            "data: [DONE]\n\n",
+        ];
+        let byte_stream = stream::iter(chunks.into_iter().map(|s| Ok::<Bytes, String>(Bytes::from(s))));
+
+        let response = collect_stream_to_json(byte_stream).await.unwrap();
+        assert_eq!(response.id, "msg_9");
+        assert_eq!(response.stop_reason, "max_tokens");
+        assert_eq!(response.usage.output_tokens, 2);
+        assert!(matches!(&response.content[0], ContentBlock::Text { text } if text == "Hello there"));
+    }
+}
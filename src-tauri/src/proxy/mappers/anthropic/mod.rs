@@ -0,0 +1,30 @@
+// Anthropic mapper 模块
+// 负责 Anthropic Messages API ↔ OpenAI Chat Completions 协议转换
+
+pub mod request;
+pub mod response;
+pub mod streaming;
+pub mod collector;
+
+pub use request::{transform_anthropic_request, transform_openai_request_to_anthropic};
+pub use response::{transform_anthropic_response, transform_anthropic_response_to_openai};
+pub use streaming::{create_anthropic_sse_stream, create_openai_sse_stream_from_anthropic};
+pub use collector::collect_stream_to_json;
+
+/// OpenAI finish_reason -> Anthropic stop_reason
+pub fn to_anthropic_stop_reason(finish_reason: Option<&str>) -> &'static str {
+    match finish_reason {
+        Some("length") => "max_tokens",
+        Some("tool_calls") | Some("function_call") => "tool_use",
+        _ => "end_turn",
+    }
+}
+
+/// Anthropic stop_reason -> OpenAI finish_reason
+pub fn to_openai_finish_reason(stop_reason: &str) -> &'static str {
+    match stop_reason {
+        "max_tokens" => "length",
+        "tool_use" => "tool_calls",
+        _ => "stop",
+    }
+}
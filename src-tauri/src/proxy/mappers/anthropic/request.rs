@@ -0,0 +1,489 @@
+// Anthropic 请求转换 (Anthropic Messages ↔ OpenAI Chat Completions)
+
+use crate::proxy::mappers::claude::models::{
+    ClaudeRequest, ContentBlock, ImageSource, Message, MessageContent, SystemPrompt, Tool,
+};
+use crate::proxy::mappers::claude::models::ThinkingConfig as ClaudeThinkingConfig;
+use crate::proxy::mappers::openai::models::{
+    OpenAIContent, OpenAIContentBlock, OpenAIImageUrl, OpenAIMessage, OpenAIRequest,
+    ThinkingConfig as OpenAIThinkingConfig, ToolCall, ToolFunction,
+};
+use crate::proxy::mappers::vision::{parse_image_url, ImageRef};
+use serde_json::{json, Value};
+
+/// 将 Anthropic /v1/messages 请求转换为 OpenAI Chat Completions 请求
+pub fn transform_anthropic_request(req: &ClaudeRequest) -> OpenAIRequest {
+    let mut messages: Vec<OpenAIMessage> = Vec::new();
+
+    // 1. System Prompt -> system message
+    if let Some(system) = &req.system {
+        let text = match system {
+            SystemPrompt::String(s) => s.clone(),
+            SystemPrompt::Array(blocks) => blocks
+                .iter()
+                .map(|b| b.text.as_str())
+                .collect::<Vec<_>>()
+                .join("\n\n"),
+        };
+        if !text.is_empty() {
+            messages.push(text_message("system", text));
+        }
+    }
+
+    // 2. Messages
+    for msg in &req.messages {
+        match &msg.content {
+            MessageContent::String(s) => messages.push(text_message(&msg.role, s.clone())),
+            MessageContent::Array(blocks) => convert_blocks_to_openai(&msg.role, blocks, &mut messages),
+        }
+    }
+
+    // 3. Tools (server tools such as web_search keep their name for networking detection)
+    let tools = req.tools.as_ref().map(|list| {
+        list.iter()
+            .map(|tool| {
+                json!({
+                    "type": "function",
+                    "function": {
+                        "name": tool.get_name(),
+                        "description": tool.description.clone().unwrap_or_default(),
+                        "parameters": tool.input_schema.clone().unwrap_or_else(|| json!({"type": "object", "properties": {}})),
+                    }
+                })
+            })
+            .collect::<Vec<Value>>()
+    });
+
+    OpenAIRequest {
+        model: req.model.clone(),
+        messages,
+        prompt: None,
+        stream: req.stream,
+        n: None,
+        max_tokens: req.max_tokens,
+        temperature: req.temperature,
+        top_p: req.top_p,
+        stop: None,
+        response_format: None,
+        tools,
+        tool_choice: None,
+        parallel_tool_calls: None,
+        instructions: None,
+        input: None,
+        size: req.size.clone(),
+        quality: req.quality.clone(),
+        person_generation: None,
+        thinking: req.thinking.as_ref().map(|t| OpenAIThinkingConfig {
+            thinking_type: Some(t.type_.clone()),
+            budget_tokens: t.budget_tokens,
+        }),
+    }
+}
+
+/// 将 OpenAI Chat Completions 请求转换为 Anthropic /v1/messages 请求
+pub fn transform_openai_request_to_anthropic(req: &OpenAIRequest) -> ClaudeRequest {
+    let mut system_parts: Vec<String> = Vec::new();
+    let mut messages: Vec<Message> = Vec::new();
+
+    for msg in &req.messages {
+        match msg.role.as_str() {
+            "system" | "developer" => {
+                let text = content_to_text(msg.content.as_ref());
+                if !text.is_empty() {
+                    system_parts.push(text);
+                }
+            }
+            "tool" | "function" => {
+                // 工具结果在 Anthropic 中属于 user 消息
+                let block = ContentBlock::ToolResult {
+                    tool_use_id: msg.tool_call_id.clone().unwrap_or_default(),
+                    content: Value::String(content_to_text(msg.content.as_ref())),
+                    is_error: None,
+                };
+                push_block(&mut messages, "user", block);
+            }
+            role => {
+                let role = if role == "assistant" { "assistant" } else { "user" };
+                let mut blocks: Vec<ContentBlock> = Vec::new();
+
+                if let Some(reasoning) = msg.reasoning_content.as_ref().filter(|r| !r.is_empty()) {
+                    blocks.push(ContentBlock::Thinking {
+                        thinking: reasoning.clone(),
+                        signature: None,
+                        cache_control: None,
+                    });
+                }
+
+                match &msg.content {
+                    Some(OpenAIContent::String(s)) if !s.is_empty() => {
+                        blocks.push(ContentBlock::Text { text: s.clone() });
+                    }
+                    Some(OpenAIContent::Array(parts)) => {
+                        for part in parts {
+                            if let Some(block) = convert_openai_part(part) {
+                                blocks.push(block);
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+
+                for tc in msg.tool_calls.iter().flatten() {
+                    blocks.push(ContentBlock::ToolUse {
+                        id: tc.id.clone(),
+                        name: tc.function.name.clone(),
+                        input: serde_json::from_str(&tc.function.arguments).unwrap_or(json!({})),
+                        signature: None,
+                        cache_control: None,
+                    });
+                }
+
+                for block in blocks {
+                    push_block(&mut messages, role, block);
+                }
+            }
+        }
+    }
+
+    let tools = req.tools.as_ref().map(|list| {
+        list.iter()
+            .filter_map(|tool| {
+                let func = tool.get("function")?;
+                Some(Tool {
+                    type_: None,
+                    name: func.get("name").and_then(|v| v.as_str()).map(String::from),
+                    description: func.get("description").and_then(|v| v.as_str()).map(String::from),
+                    input_schema: Some(func.get("parameters").cloned().unwrap_or_else(|| json!({"type": "object", "properties": {}}))),
+                })
+            })
+            .collect::<Vec<Tool>>()
+    });
+
+    ClaudeRequest {
+        model: req.model.clone(),
+        messages,
+        system: if system_parts.is_empty() {
+            None
+        } else {
+            Some(SystemPrompt::String(system_parts.join("\n\n")))
+        },
+        tools,
+        stream: req.stream,
+        max_tokens: req.max_tokens,
+        temperature: req.temperature,
+        top_p: req.top_p,
+        top_k: None,
+        thinking: req.thinking.as_ref().map(|t| ClaudeThinkingConfig {
+            type_: t.thinking_type.clone().unwrap_or_else(|| "enabled".to_string()),
+            budget_tokens: t.budget_tokens,
+        }),
+        metadata: None,
+        output_config: None,
+        size: req.size.clone(),
+        quality: req.quality.clone(),
+    }
+}
+
+fn text_message(role: &str, text: String) -> OpenAIMessage {
+    OpenAIMessage {
+        role: role.to_string(),
+        content: Some(OpenAIContent::String(text)),
+        reasoning_content: None,
+        tool_calls: None,
+        tool_call_id: None,
+        name: None,
+    }
+}
+
+/// 将一条 Anthropic 消息的内容块拆分为 OpenAI 消息
+/// (tool_result 需要独立的 role=tool 消息)
+fn convert_blocks_to_openai(role: &str, blocks: &[ContentBlock], out: &mut Vec<OpenAIMessage>) {
+    let mut parts: Vec<OpenAIContentBlock> = Vec::new();
+    let mut reasoning = String::new();
+    let mut tool_calls: Vec<ToolCall> = Vec::new();
+
+    for block in blocks {
+        match block {
+            ContentBlock::Text { text } => parts.push(OpenAIContentBlock::Text { text: text.clone() }),
+            ContentBlock::Thinking { thinking, .. } => reasoning.push_str(thinking),
+            ContentBlock::Image { source, .. } => parts.push(OpenAIContentBlock::ImageUrl {
+                image_url: OpenAIImageUrl {
+                    url: match source.url.as_ref().filter(|_| source.source_type == "url") {
+                        Some(url) => url.clone(),
+                        None => format!("data:{};base64,{}", source.media_type, source.data),
+                    },
+                    detail: None,
+                },
+            }),
+            ContentBlock::ToolUse { id, name, input, .. } => tool_calls.push(ToolCall {
+                id: id.clone(),
+                r#type: "function".to_string(),
+                function: ToolFunction {
+                    name: name.clone(),
+                    arguments: input.to_string(),
+                },
+            }),
+            ContentBlock::ToolResult { tool_use_id, content, is_error } => {
+                // OpenAI 没有 is_error 字段, 以前缀告知模型
+                let mut text = tool_result_to_text(content);
+                if *is_error == Some(true) {
+                    text = format!("Error: {}", text);
+                }
+                out.push(OpenAIMessage {
+                    role: "tool".to_string(),
+                    content: Some(OpenAIContent::String(text)),
+                    reasoning_content: None,
+                    tool_calls: None,
+                    tool_call_id: Some(tool_use_id.clone()),
+                    name: None,
+                });
+            }
+            // Document / RedactedThinking / server tool blocks have no OpenAI equivalent
+            _ => {}
+        }
+    }
+
+    if parts.is_empty() && reasoning.is_empty() && tool_calls.is_empty() {
+        return;
+    }
+
+    // 纯文本内容折叠为字符串, 兼容更多上游
+    let content = match parts.as_slice() {
+        [] => None,
+        [OpenAIContentBlock::Text { text }] => Some(OpenAIContent::String(text.clone())),
+        _ => Some(OpenAIContent::Array(parts)),
+    };
+
+    out.push(OpenAIMessage {
+        role: role.to_string(),
+        content,
+        reasoning_content: if reasoning.is_empty() { None } else { Some(reasoning) },
+        tool_calls: if tool_calls.is_empty() { None } else { Some(tool_calls) },
+        tool_call_id: None,
+        name: None,
+    });
+}
+
+fn tool_result_to_text(content: &Value) -> String {
+    match content {
+        Value::String(s) => s.clone(),
+        Value::Array(items) => items
+            .iter()
+            .filter_map(|item| item.get("text").and_then(|v| v.as_str()))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+fn content_to_text(content: Option<&OpenAIContent>) -> String {
+    match content {
+        Some(OpenAIContent::String(s)) => s.clone(),
+        Some(OpenAIContent::Array(parts)) => parts
+            .iter()
+            .filter_map(|p| match p {
+                OpenAIContentBlock::Text { text } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+        None => String::new(),
+    }
+}
+
+fn convert_openai_part(part: &OpenAIContentBlock) -> Option<ContentBlock> {
+    match part {
+        OpenAIContentBlock::Text { text } => Some(ContentBlock::Text { text: text.clone() }),
+        OpenAIContentBlock::ImageUrl { image_url } => {
+            let source = match parse_image_url(&image_url.url) {
+                ImageRef::Inline { mime_type, data } => base64_source(mime_type, data),
+                // Anthropic 原生支持 URL 图片
+                ImageRef::Remote(url) => ImageSource {
+                    source_type: "url".to_string(),
+                    media_type: String::new(),
+                    data: String::new(),
+                    url: Some(url.to_string()),
+                },
+                // 不读取客户端指定的本地路径
+                ImageRef::Local(path) => {
+                    tracing::debug!("[Anthropic-Request] Ignoring local image path {}", path);
+                    return Some(ContentBlock::Text {
+                        text: format!("[image: {}]", image_url.url),
+                    });
+                }
+            };
+            Some(ContentBlock::Image {
+                source,
+                cache_control: None,
+            })
+        }
+        OpenAIContentBlock::AudioUrl { .. } => None,
+    }
+}
+
+fn base64_source(media_type: String, data: String) -> ImageSource {
+    ImageSource {
+        source_type: "base64".to_string(),
+        media_type,
+        data,
+        url: None,
+    }
+}
+
+/// 追加内容块, 相同 role 的连续块合并到同一条消息 (Anthropic 要求 user/assistant 交替)
+fn push_block(messages: &mut Vec<Message>, role: &str, block: ContentBlock) {
+    if let Some(last) = messages.last_mut() {
+        if last.role == role {
+            if let MessageContent::String(s) = &last.content {
+                last.content = MessageContent::Array(vec![ContentBlock::Text { text: s.clone() }]);
+            }
+            if let MessageContent::Array(blocks) = &mut last.content {
+                blocks.push(block);
+                return;
+            }
+        }
+    }
+    messages.push(Message {
+        role: role.to_string(),
+        content: MessageContent::Array(vec![block]),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn claude_request(body: Value) -> ClaudeRequest {
+        serde_json::from_value(body).unwrap()
+    }
+
+    #[test]
+    fn test_anthropic_to_openai_system_and_tools() {
+        let req = claude_request(json!({
+            "model": "claude-sonnet-4-5",
+            "max_tokens": 1024,
+            "system": [{"type": "text", "text": "Be brief."}, {"type": "text", "text": "Be kind."}],
+            "messages": [
+                {"role": "user", "content": "What's the weather?"},
+                {"role": "assistant", "content": [
+                    {"type": "thinking", "thinking": "Need a tool", "signature": "sig"},
+                    {"type": "tool_use", "id": "toolu_1", "name": "get_weather", "input": {"city": "Paris"}}
+                ]},
+                {"role": "user", "content": [
+                    {"type": "tool_result", "tool_use_id": "toolu_1", "content": [{"type": "text", "text": "Sunny"}]},
+                    {"type": "tool_result", "tool_use_id": "toolu_2", "content": "timeout", "is_error": true}
+                ]}
+            ],
+            "tools": [{"name": "get_weather", "description": "Weather", "input_schema": {"type": "object"}}]
+        }));
+
+        let openai = transform_anthropic_request(&req);
+        assert_eq!(openai.max_tokens, Some(1024));
+        assert_eq!(openai.messages.len(), 5);
+
+        assert_eq!(openai.messages[0].role, "system");
+        assert_eq!(openai.messages[0].content, Some(OpenAIContent::String("Be brief.\n\nBe kind.".to_string())));
+
+        let assistant = &openai.messages[2];
+        assert_eq!(assistant.reasoning_content.as_deref(), Some("Need a tool"));
+        let calls = assistant.tool_calls.as_ref().unwrap();
+        assert_eq!(calls[0].id, "toolu_1");
+        assert_eq!(calls[0].function.arguments, "{\"city\":\"Paris\"}");
+
+        let tool = &openai.messages[3];
+        assert_eq!(tool.role, "tool");
+        assert_eq!(tool.tool_call_id.as_deref(), Some("toolu_1"));
+        assert_eq!(tool.content, Some(OpenAIContent::String("Sunny".to_string())));
+        assert_eq!(openai.messages[4].content, Some(OpenAIContent::String("Error: timeout".to_string())));
+
+        let tools = openai.tools.unwrap();
+        assert_eq!(tools[0]["function"]["name"], "get_weather");
+    }
+
+    #[test]
+    fn test_anthropic_image_becomes_data_url() {
+        let req = claude_request(json!({
+            "model": "claude-sonnet-4-5",
+            "messages": [{"role": "user", "content": [
+                {"type": "text", "text": "Describe"},
+                {"type": "image", "source": {"type": "base64", "media_type": "image/png", "data": "AAAA"}}
+            ]}]
+        }));
+
+        let openai = transform_anthropic_request(&req);
+        match openai.messages[0].content.as_ref().unwrap() {
+            OpenAIContent::Array(parts) => match &parts[1] {
+                OpenAIContentBlock::ImageUrl { image_url } => {
+                    assert_eq!(image_url.url, "data:image/png;base64,AAAA");
+                }
+                other => panic!("Expected image part, got {:?}", other),
+            },
+            other => panic!("Expected array content, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_openai_image_parts_to_anthropic() {
+        let req: OpenAIRequest = serde_json::from_value(json!({
+            "model": "gpt-4o",
+            "messages": [{"role": "user", "content": [
+                {"type": "image_url", "image_url": {"url": "data:image/webp;base64,UklGR"}},
+                {"type": "image_url", "image_url": {"url": "https://example.com/cat.png"}}
+            ]}]
+        }))
+        .unwrap();
+
+        let claude = transform_openai_request_to_anthropic(&req);
+        let MessageContent::Array(blocks) = &claude.messages[0].content else { panic!("Expected blocks") };
+        match (&blocks[0], &blocks[1]) {
+            (ContentBlock::Image { source: inline, .. }, ContentBlock::Image { source: remote, .. }) => {
+                assert_eq!((inline.source_type.as_str(), inline.media_type.as_str()), ("base64", "image/webp"));
+                assert_eq!(remote.source_type, "url");
+                assert_eq!(remote.url.as_deref(), Some("https://example.com/cat.png"));
+            }
+            other => panic!("Expected image blocks, got {:?}", other),
+        }
+
+        // URL 图片序列化为 Anthropic 的 {"type": "url", "url": ...}
+        let value = serde_json::to_value(&blocks[1]).unwrap();
+        assert_eq!(value["source"], json!({"type": "url", "url": "https://example.com/cat.png"}));
+    }
+
+    #[test]
+    fn test_openai_to_anthropic_merges_tool_results() {
+        let req: OpenAIRequest = serde_json::from_value(json!({
+            "model": "gpt-4o",
+            "messages": [
+                {"role": "system", "content": "sys"},
+                {"role": "user", "content": "hi"},
+                {"role": "assistant", "content": null, "tool_calls": [
+                    {"id": "call_1", "type": "function", "function": {"name": "a", "arguments": "{\"x\":1}"}},
+                    {"id": "call_2", "type": "function", "function": {"name": "b", "arguments": "{}"}}
+                ]},
+                {"role": "tool", "tool_call_id": "call_1", "content": "r1"},
+                {"role": "tool", "tool_call_id": "call_2", "content": "r2"}
+            ]
+        }))
+        .unwrap();
+
+        let claude = transform_openai_request_to_anthropic(&req);
+        assert!(matches!(claude.system, Some(SystemPrompt::String(ref s)) if s == "sys"));
+        assert_eq!(claude.messages.len(), 3);
+
+        match &claude.messages[1].content {
+            MessageContent::Array(blocks) => {
+                assert_eq!(blocks.len(), 2);
+                assert!(matches!(&blocks[0], ContentBlock::ToolUse { input, .. } if input["x"] == 1));
+            }
+            _ => panic!("Expected tool_use blocks"),
+        }
+
+        // 两个工具结果合并为一条 user 消息
+        assert_eq!(claude.messages[2].role, "user");
+        match &claude.messages[2].content {
+            MessageContent::Array(blocks) => assert_eq!(blocks.len(), 2),
+            _ => panic!("Expected tool_result blocks"),
+        }
+    }
+}
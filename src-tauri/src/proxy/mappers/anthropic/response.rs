@@ -0,0 +1,231 @@
+// Anthropic 响应转换 (OpenAI Chat Completions ↔ Anthropic Messages)
+
+use super::{to_anthropic_stop_reason, to_openai_finish_reason};
+use crate::proxy::mappers::claude::models::{ClaudeResponse, ContentBlock, Usage};
+use crate::proxy::mappers::openai::models::{
+    Choice, OpenAIContent, OpenAIContentBlock, OpenAIMessage, OpenAIResponse, OpenAIUsage,
+    PromptTokensDetails, ToolCall, ToolFunction,
+};
+use serde_json::json;
+
+/// 将 OpenAI Chat Completions 响应转换为 Anthropic /v1/messages 响应
+pub fn transform_anthropic_response(resp: &OpenAIResponse) -> ClaudeResponse {
+    let mut content: Vec<ContentBlock> = Vec::new();
+    let choice = resp.choices.first();
+
+    if let Some(message) = choice.map(|c| &c.message) {
+        if let Some(reasoning) = message.reasoning_content.as_ref().filter(|r| !r.is_empty()) {
+            content.push(ContentBlock::Thinking {
+                thinking: reasoning.clone(),
+                signature: None,
+                cache_control: None,
+            });
+        }
+
+        let text = match &message.content {
+            Some(OpenAIContent::String(s)) => s.clone(),
+            Some(OpenAIContent::Array(parts)) => parts
+                .iter()
+                .filter_map(|p| match p {
+                    OpenAIContentBlock::Text { text } => Some(text.as_str()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+                .join(""),
+            None => String::new(),
+        };
+        if !text.is_empty() {
+            content.push(ContentBlock::Text { text });
+        }
+
+        for tc in message.tool_calls.iter().flatten() {
+            content.push(ContentBlock::ToolUse {
+                id: tc.id.clone(),
+                name: tc.function.name.clone(),
+                input: serde_json::from_str(&tc.function.arguments).unwrap_or(json!({})),
+                signature: None,
+                cache_control: None,
+            });
+        }
+    }
+
+    let usage = resp.usage.as_ref();
+    ClaudeResponse {
+        id: to_anthropic_message_id(&resp.id),
+        type_: "message".to_string(),
+        role: "assistant".to_string(),
+        model: resp.model.clone(),
+        content,
+        stop_reason: to_anthropic_stop_reason(choice.and_then(|c| c.finish_reason.as_deref())).to_string(),
+        stop_sequence: None,
+        usage: Usage {
+            input_tokens: usage.map(|u| u.prompt_tokens).unwrap_or(0),
+            output_tokens: usage.map(|u| u.completion_tokens).unwrap_or(0),
+            cache_read_input_tokens: usage
+                .and_then(|u| u.prompt_tokens_details.as_ref())
+                .and_then(|d| d.cached_tokens),
+            cache_creation_input_tokens: None,
+            server_tool_use: None,
+        },
+    }
+}
+
+/// 将 Anthropic /v1/messages 响应转换为 OpenAI Chat Completions 响应
+pub fn transform_anthropic_response_to_openai(resp: &ClaudeResponse) -> OpenAIResponse {
+    let mut text = String::new();
+    let mut reasoning = String::new();
+    let mut tool_calls: Vec<ToolCall> = Vec::new();
+
+    for block in &resp.content {
+        match block {
+            ContentBlock::Text { text: t } => text.push_str(t),
+            ContentBlock::Thinking { thinking, .. } => reasoning.push_str(thinking),
+            ContentBlock::ToolUse { id, name, input, .. } => tool_calls.push(ToolCall {
+                id: id.clone(),
+                r#type: "function".to_string(),
+                function: ToolFunction {
+                    name: name.clone(),
+                    arguments: input.to_string(),
+                },
+            }),
+            _ => {}
+        }
+    }
+
+    let prompt_tokens = resp.usage.input_tokens;
+    let completion_tokens = resp.usage.output_tokens;
+
+    OpenAIResponse {
+        id: to_openai_completion_id(&resp.id),
+        object: "chat.completion".to_string(),
+        created: chrono::Utc::now().timestamp() as u64,
+        model: resp.model.clone(),
+        choices: vec![Choice {
+            index: 0,
+            message: OpenAIMessage {
+                role: "assistant".to_string(),
+                // 只有工具调用时 content 为 null (与 OpenAI 一致)
+                content: if text.is_empty() && !tool_calls.is_empty() {
+                    None
+                } else {
+                    Some(OpenAIContent::String(text))
+                },
+                reasoning_content: if reasoning.is_empty() { None } else { Some(reasoning) },
+                tool_calls: if tool_calls.is_empty() { None } else { Some(tool_calls) },
+                tool_call_id: None,
+                name: None,
+            },
+            finish_reason: Some(to_openai_finish_reason(&resp.stop_reason).to_string()),
+        }],
+        usage: Some(OpenAIUsage {
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: prompt_tokens + completion_tokens,
+            prompt_tokens_details: resp.usage.cache_read_input_tokens.map(|cached| PromptTokensDetails {
+                cached_tokens: Some(cached),
+            }),
+            completion_tokens_details: None,
+        }),
+    }
+}
+
+/// OpenAI 客户端期望 `chatcmpl-` 前缀的 ID
+pub(crate) fn to_openai_completion_id(id: &str) -> String {
+    if id.starts_with("chatcmpl-") {
+        id.to_string()
+    } else {
+        format!("chatcmpl-{}", id.trim_start_matches("msg_"))
+    }
+}
+
+/// Anthropic 客户端期望 `msg_` 前缀的消息 ID
+pub(crate) fn to_anthropic_message_id(id: &str) -> String {
+    if id.starts_with("msg_") {
+        id.to_string()
+    } else {
+        format!("msg_{}", id.trim_start_matches("chatcmpl-"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_openai_response_to_anthropic() {
+        let resp: OpenAIResponse = serde_json::from_value(json!({
+            "id": "chatcmpl-abc",
+            "object": "chat.completion",
+            "created": 1,
+            "model": "gpt-4o",
+            "choices": [{
+                "index": 0,
+                "message": {
+                    "role": "assistant",
+                    "content": "Let me check.",
+                    "reasoning_content": "thinking...",
+                    "tool_calls": [{"id": "call_1", "type": "function", "function": {"name": "lookup", "arguments": "{\"q\":\"rust\"}"}}]
+                },
+                "finish_reason": "tool_calls"
+            }],
+            "usage": {"prompt_tokens": 12, "completion_tokens": 7, "total_tokens": 19}
+        }))
+        .unwrap();
+
+        let claude = transform_anthropic_response(&resp);
+        assert_eq!(claude.id, "msg_abc");
+        assert_eq!(claude.stop_reason, "tool_use");
+        assert_eq!(claude.usage.input_tokens, 12);
+        assert_eq!(claude.usage.output_tokens, 7);
+        assert_eq!(claude.content.len(), 3);
+        assert!(matches!(&claude.content[0], ContentBlock::Thinking { thinking, .. } if thinking == "thinking..."));
+        assert!(matches!(&claude.content[1], ContentBlock::Text { text } if text == "Let me check."));
+        assert!(matches!(&claude.content[2], ContentBlock::ToolUse { input, .. } if input["q"] == "rust"));
+    }
+
+    #[test]
+    fn test_anthropic_response_to_openai() {
+        let resp: ClaudeResponse = serde_json::from_value(json!({
+            "id": "msg_1",
+            "type": "message",
+            "role": "assistant",
+            "model": "claude-sonnet-4-5",
+            "content": [{"type": "text", "text": "Hi"}],
+            "stop_reason": "max_tokens",
+            "usage": {"input_tokens": 3, "output_tokens": 4, "cache_read_input_tokens": 2}
+        }))
+        .unwrap();
+
+        let openai = transform_anthropic_response_to_openai(&resp);
+        let choice = &openai.choices[0];
+        assert_eq!(choice.message.content, Some(OpenAIContent::String("Hi".to_string())));
+        assert_eq!(choice.finish_reason.as_deref(), Some("length"));
+
+        let usage = openai.usage.unwrap();
+        assert_eq!(usage.total_tokens, 7);
+        assert_eq!(usage.prompt_tokens_details.unwrap().cached_tokens, Some(2));
+    }
+
+    #[test]
+    fn test_anthropic_tool_use_response_to_openai() {
+        let resp: ClaudeResponse = serde_json::from_value(json!({
+            "id": "msg_7",
+            "type": "message",
+            "role": "assistant",
+            "model": "claude-sonnet-4-5",
+            "content": [{"type": "tool_use", "id": "toolu_1", "name": "lookup", "input": {"q": "rust"}}],
+            "stop_reason": "tool_use",
+            "usage": {"input_tokens": 1, "output_tokens": 1}
+        }))
+        .unwrap();
+
+        let openai = transform_anthropic_response_to_openai(&resp);
+        assert_eq!(openai.id, "chatcmpl-7");
+        let choice = &openai.choices[0];
+        assert_eq!(choice.message.content, None);
+        assert_eq!(choice.finish_reason.as_deref(), Some("tool_calls"));
+        let call = &choice.message.tool_calls.as_ref().unwrap()[0];
+        assert_eq!(call.id, "toolu_1");
+        assert_eq!(call.function.arguments, "{\"q\":\"rust\"}");
+    }
+}
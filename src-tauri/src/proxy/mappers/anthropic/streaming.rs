@@ -0,0 +1,668 @@
+// Anthropic 流式转换
+// OpenAI Chat Completions SSE 流 ↔ Anthropic Messages SSE 事件
+// (tool_calls 参数增量与 input_json_delta 的 partial_json 互相对应)
+
+use super::response::{to_anthropic_message_id, to_openai_completion_id};
+use super::{to_anthropic_stop_reason, to_openai_finish_reason};
+use crate::proxy::common::sse::SseParser;
+use bytes::Bytes;
+use futures::{Stream, StreamExt};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::pin::Pin;
+
+/// 当前打开的内容块类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BlockKind {
+    Text,
+    Thinking,
+    ToolUse,
+}
+
+/// OpenAI chunk -> Anthropic 事件的状态机
+#[derive(Debug, Default)]
+pub struct AnthropicStreamState {
+    message_started: bool,
+    message_stopped: bool,
+    message_id: String,
+    model: String,
+    block_index: usize,
+    current_block: Option<BlockKind>,
+    current_tool_id: Option<String>,
+    /// 当前 tool_use 块对应的 OpenAI `tool_calls[].index`
+    current_tool_index: Option<u64>,
+    finish_reason: Option<String>,
+    input_tokens: u32,
+    output_tokens: u32,
+}
+
+impl AnthropicStreamState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn emit(event: &str, data: Value) -> Bytes {
+        Bytes::from(format!("event: {}\ndata: {}\n\n", event, data))
+    }
+
+    fn ensure_message_start(&mut self, out: &mut Vec<Bytes>) {
+        if self.message_started {
+            return;
+        }
+        self.message_started = true;
+        if self.message_id.is_empty() {
+            self.message_id = format!("msg_{}", uuid::Uuid::new_v4().simple());
+        }
+        out.push(Self::emit(
+            "message_start",
+            json!({
+                "type": "message_start",
+                "message": {
+                    "id": self.message_id,
+                    "type": "message",
+                    "role": "assistant",
+                    "model": self.model,
+                    "content": [],
+                    "stop_reason": null,
+                    "stop_sequence": null,
+                    "usage": {"input_tokens": self.input_tokens, "output_tokens": 0}
+                }
+            }),
+        ));
+    }
+
+    fn close_block(&mut self, out: &mut Vec<Bytes>) {
+        if self.current_block.take().is_some() {
+            out.push(Self::emit(
+                "content_block_stop",
+                json!({"type": "content_block_stop", "index": self.block_index}),
+            ));
+            self.block_index += 1;
+            self.current_tool_id = None;
+            self.current_tool_index = None;
+        }
+    }
+
+    fn open_block(&mut self, kind: BlockKind, content_block: Value, out: &mut Vec<Bytes>) {
+        self.close_block(out);
+        self.current_block = Some(kind);
+        out.push(Self::emit(
+            "content_block_start",
+            json!({"type": "content_block_start", "index": self.block_index, "content_block": content_block}),
+        ));
+    }
+
+    fn emit_delta(&self, delta: Value, out: &mut Vec<Bytes>) {
+        out.push(Self::emit(
+            "content_block_delta",
+            json!({"type": "content_block_delta", "index": self.block_index, "delta": delta}),
+        ));
+    }
+
+    /// 处理一个 OpenAI chunk (已解析的 JSON), 返回需要发送的 Anthropic 事件
+    pub fn process_chunk(&mut self, chunk: &Value) -> Vec<Bytes> {
+        let mut out = Vec::new();
+
+        if self.message_id.is_empty() {
+            if let Some(id) = chunk.get("id").and_then(|v| v.as_str()) {
+                self.message_id = to_anthropic_message_id(id);
+            }
+        }
+        if self.model.is_empty() {
+            if let Some(model) = chunk.get("model").and_then(|v| v.as_str()) {
+                self.model = model.to_string();
+            }
+        }
+        if let Some(usage) = chunk.get("usage").filter(|u| !u.is_null()) {
+            if let Some(p) = usage.get("prompt_tokens").and_then(|v| v.as_u64()) {
+                self.input_tokens = p as u32;
+            }
+            if let Some(c) = usage.get("completion_tokens").and_then(|v| v.as_u64()) {
+                self.output_tokens = c as u32;
+            }
+        }
+
+        // Anthropic 协议仅有单一候选, 只翻译 index 0
+        let choice = chunk
+            .get("choices")
+            .and_then(|v| v.as_array())
+            .and_then(|arr| {
+                arr.iter()
+                    .find(|c| c.get("index").and_then(|i| i.as_u64()).unwrap_or(0) == 0)
+            });
+
+        let Some(choice) = choice else {
+            return out;
+        };
+        self.ensure_message_start(&mut out);
+
+        if let Some(delta) = choice.get("delta") {
+            if let Some(reasoning) = delta.get("reasoning_content").and_then(|v| v.as_str()).filter(|s| !s.is_empty()) {
+                if self.current_block != Some(BlockKind::Thinking) {
+                    self.open_block(BlockKind::Thinking, json!({"type": "thinking", "thinking": ""}), &mut out);
+                }
+                self.emit_delta(json!({"type": "thinking_delta", "thinking": reasoning}), &mut out);
+            }
+
+            if let Some(text) = delta.get("content").and_then(|v| v.as_str()).filter(|s| !s.is_empty()) {
+                if self.current_block != Some(BlockKind::Text) {
+                    self.open_block(BlockKind::Text, json!({"type": "text", "text": ""}), &mut out);
+                }
+                self.emit_delta(json!({"type": "text_delta", "text": text}), &mut out);
+            }
+
+            for tc in delta.get("tool_calls").and_then(|v| v.as_array()).into_iter().flatten() {
+                let id = tc.get("id").and_then(|v| v.as_str()).filter(|s| !s.is_empty());
+                let index = tc.get("index").and_then(|v| v.as_u64());
+                let function = tc.get("function");
+                let name = function
+                    .and_then(|f| f.get("name"))
+                    .and_then(|v| v.as_str())
+                    .filter(|s| !s.is_empty());
+
+                // 并行调用按 index 区分, 部分上游只在首个 chunk 带 id
+                let switches_index = index.is_some() && index != self.current_tool_index;
+                let starts_new_call = match id {
+                    Some(id) => self.current_tool_id.as_deref() != Some(id),
+                    None => self.current_block != Some(BlockKind::ToolUse) || (switches_index && name.is_some()),
+                };
+
+                if !starts_new_call && switches_index && self.current_tool_index.is_some() {
+                    // Anthropic 的内容块不能交错, 已关闭的调用无法再追加参数
+                    tracing::warn!("[Anthropic-Stream] Dropping interleaved arguments for tool call index {:?}", index);
+                    continue;
+                }
+
+                if starts_new_call {
+                    let id = id
+                        .map(String::from)
+                        .unwrap_or_else(|| format!("toolu_{}", uuid::Uuid::new_v4().simple()));
+                    self.open_block(
+                        BlockKind::ToolUse,
+                        json!({"type": "tool_use", "id": id, "name": name.unwrap_or_default(), "input": {}}),
+                        &mut out,
+                    );
+                    self.current_tool_id = Some(id);
+                    self.current_tool_index = index;
+                }
+
+                if let Some(args) = function
+                    .and_then(|f| f.get("arguments"))
+                    .and_then(|v| v.as_str())
+                    .filter(|s| !s.is_empty())
+                {
+                    self.emit_delta(json!({"type": "input_json_delta", "partial_json": args}), &mut out);
+                }
+            }
+        }
+
+        if let Some(fr) = choice.get("finish_reason").and_then(|v| v.as_str()) {
+            self.finish_reason = Some(fr.to_string());
+        }
+
+        out
+    }
+
+    /// 结束消息: 关闭打开的块并发送 message_delta / message_stop
+    pub fn finish(&mut self) -> Vec<Bytes> {
+        let mut out = Vec::new();
+        if self.message_stopped {
+            return out;
+        }
+        self.ensure_message_start(&mut out);
+        self.close_block(&mut out);
+        out.push(Self::emit(
+            "message_delta",
+            json!({
+                "type": "message_delta",
+                "delta": {
+                    "stop_reason": to_anthropic_stop_reason(self.finish_reason.as_deref()),
+                    "stop_sequence": null
+                },
+                "usage": {"input_tokens": self.input_tokens, "output_tokens": self.output_tokens}
+            }),
+        ));
+        out.push(Self::emit("message_stop", json!({"type": "message_stop"})));
+        self.message_stopped = true;
+        out
+    }
+}
+
+/// 创建从 OpenAI SSE 流到 Anthropic SSE 流的转换
+pub fn create_anthropic_sse_stream(
+    mut openai_stream: Pin<Box<dyn Stream<Item = Result<Bytes, String>> + Send>>,
+) -> Pin<Box<dyn Stream<Item = Result<Bytes, String>> + Send>> {
+    use async_stream::stream;
+
+    Box::pin(stream! {
+        let mut state = AnthropicStreamState::new();
+        let mut parser = SseParser::new();
+
+        loop {
+            let (events, ended) = match openai_stream.next().await {
+                Some(Ok(chunk)) => (parser.push(&chunk), false),
+                Some(Err(e)) => {
+                    yield Err(e);
+                    return;
+                }
+                None => (parser.finish(), true),
+            };
+
+            for event in events {
+                if event.is_done() {
+                    for bytes in state.finish() {
+                        yield Ok(bytes);
+                    }
+                    continue;
+                }
+
+                let Ok(json) = serde_json::from_str::<Value>(&event.data) else { continue };
+                if let Some(err) = json.get("error") {
+                    let error_event = json!({
+                        "type": "error",
+                        "error": {
+                            "type": "api_error",
+                            "message": err.get("message").and_then(|m| m.as_str()).unwrap_or("Upstream error")
+                        }
+                    });
+                    yield Ok(Bytes::from(format!("event: error\ndata: {}\n\n", error_event)));
+                    continue;
+                }
+                for bytes in state.process_chunk(&json) {
+                    yield Ok(bytes);
+                }
+            }
+
+            if ended {
+                break;
+            }
+        }
+
+        // 上游未发送 [DONE] 时补齐结束事件
+        for bytes in state.finish() {
+            yield Ok(bytes);
+        }
+    })
+}
+
+/// Anthropic 事件 -> OpenAI chunk 的状态机
+#[derive(Debug, Default)]
+pub struct OpenAIStreamState {
+    id: String,
+    model: String,
+    created: u64,
+    /// Anthropic 内容块 index -> OpenAI `tool_calls[].index`
+    tool_indices: HashMap<u64, usize>,
+    finish_reason: Option<String>,
+    prompt_tokens: u32,
+    completion_tokens: u32,
+    cached_tokens: Option<u32>,
+    finished: bool,
+}
+
+impl OpenAIStreamState {
+    pub fn new() -> Self {
+        Self {
+            created: chrono::Utc::now().timestamp() as u64,
+            ..Self::default()
+        }
+    }
+
+    fn chunk(&self, delta: Value, finish_reason: Option<&str>) -> Bytes {
+        let chunk = json!({
+            "id": self.id,
+            "object": "chat.completion.chunk",
+            "created": self.created,
+            "model": self.model,
+            "choices": [{"index": 0, "delta": delta, "finish_reason": finish_reason}]
+        });
+        Bytes::from(format!("data: {}\n\n", chunk))
+    }
+
+    fn tool_call_delta(tool_index: usize, call: Value) -> Value {
+        let mut call = call;
+        call["index"] = json!(tool_index);
+        json!({"tool_calls": [call]})
+    }
+
+    /// 处理一个 Anthropic 事件, 返回需要发送的 OpenAI chunk
+    pub fn process_event(&mut self, event_type: &str, data: &Value) -> Vec<Bytes> {
+        let mut out = Vec::new();
+        let block_index = data.get("index").and_then(|v| v.as_u64()).unwrap_or(0);
+
+        match event_type {
+            "message_start" => {
+                let Some(message) = data.get("message") else { return out };
+                if let Some(id) = message.get("id").and_then(|v| v.as_str()) {
+                    self.id = to_openai_completion_id(id);
+                }
+                if let Some(model) = message.get("model").and_then(|v| v.as_str()) {
+                    self.model = model.to_string();
+                }
+                if let Some(usage) = message.get("usage") {
+                    self.read_usage(usage);
+                }
+                if self.id.is_empty() {
+                    self.id = format!("chatcmpl-{}", uuid::Uuid::new_v4().simple());
+                }
+                out.push(self.chunk(json!({"role": "assistant", "content": ""}), None));
+            }
+            "content_block_start" => {
+                let Some(block) = data.get("content_block") else { return out };
+                match block.get("type").and_then(|v| v.as_str()) {
+                    Some("tool_use") => {
+                        let tool_index = self.tool_indices.len();
+                        self.tool_indices.insert(block_index, tool_index);
+                        // 个别实现在 start 中直接给出完整 input
+                        let arguments = block
+                            .get("input")
+                            .filter(|input| input.as_object().map_or(false, |o| !o.is_empty()))
+                            .map(|input| input.to_string())
+                            .unwrap_or_default();
+                        out.push(self.chunk(
+                            Self::tool_call_delta(
+                                tool_index,
+                                json!({
+                                    "id": block.get("id").and_then(|v| v.as_str()).unwrap_or_default(),
+                                    "type": "function",
+                                    "function": {
+                                        "name": block.get("name").and_then(|v| v.as_str()).unwrap_or_default(),
+                                        "arguments": arguments
+                                    }
+                                }),
+                            ),
+                            None,
+                        ));
+                    }
+                    Some("text") => {
+                        if let Some(text) = block.get("text").and_then(|v| v.as_str()).filter(|s| !s.is_empty()) {
+                            out.push(self.chunk(json!({"content": text}), None));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            "content_block_delta" => {
+                let Some(delta) = data.get("delta") else { return out };
+                match delta.get("type").and_then(|v| v.as_str()) {
+                    Some("text_delta") => {
+                        if let Some(text) = delta.get("text").and_then(|v| v.as_str()) {
+                            out.push(self.chunk(json!({"content": text}), None));
+                        }
+                    }
+                    Some("thinking_delta") => {
+                        if let Some(thinking) = delta.get("thinking").and_then(|v| v.as_str()) {
+                            out.push(self.chunk(json!({"reasoning_content": thinking}), None));
+                        }
+                    }
+                    Some("input_json_delta") => {
+                        let partial = delta.get("partial_json").and_then(|v| v.as_str()).unwrap_or_default();
+                        if let (Some(&tool_index), false) = (self.tool_indices.get(&block_index), partial.is_empty()) {
+                            out.push(self.chunk(
+                                Self::tool_call_delta(tool_index, json!({"function": {"arguments": partial}})),
+                                None,
+                            ));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            "message_delta" => {
+                if let Some(reason) = data.pointer("/delta/stop_reason").and_then(|v| v.as_str()) {
+                    self.finish_reason = Some(to_openai_finish_reason(reason).to_string());
+                }
+                if let Some(usage) = data.get("usage") {
+                    self.read_usage(usage);
+                }
+            }
+            "message_stop" => out.extend(self.finish()),
+            _ => {}
+        }
+
+        out
+    }
+
+    fn read_usage(&mut self, usage: &Value) {
+        if let Some(input) = usage.get("input_tokens").and_then(|v| v.as_u64()).filter(|v| *v > 0) {
+            self.prompt_tokens = input as u32;
+        }
+        if let Some(output) = usage.get("output_tokens").and_then(|v| v.as_u64()) {
+            self.completion_tokens = output as u32;
+        }
+        if let Some(cached) = usage.get("cache_read_input_tokens").and_then(|v| v.as_u64()) {
+            self.cached_tokens = Some(cached as u32);
+        }
+    }
+
+    /// 结束: 发送带 finish_reason 和 usage 的最后一个 chunk 以及 `[DONE]`
+    pub fn finish(&mut self) -> Vec<Bytes> {
+        if self.finished {
+            return Vec::new();
+        }
+        self.finished = true;
+
+        let finish_reason = self.finish_reason.clone().unwrap_or_else(|| {
+            if self.tool_indices.is_empty() { "stop" } else { "tool_calls" }.to_string()
+        });
+        let mut chunk = json!({
+            "id": self.id,
+            "object": "chat.completion.chunk",
+            "created": self.created,
+            "model": self.model,
+            "choices": [{"index": 0, "delta": {}, "finish_reason": finish_reason}],
+            "usage": {
+                "prompt_tokens": self.prompt_tokens,
+                "completion_tokens": self.completion_tokens,
+                "total_tokens": self.prompt_tokens + self.completion_tokens
+            }
+        });
+        if let Some(cached) = self.cached_tokens {
+            chunk["usage"]["prompt_tokens_details"] = json!({"cached_tokens": cached});
+        }
+        vec![
+            Bytes::from(format!("data: {}\n\n", chunk)),
+            Bytes::from_static(b"data: [DONE]\n\n"),
+        ]
+    }
+}
+
+/// 创建从 Anthropic SSE 流到 OpenAI SSE 流的转换
+pub fn create_openai_sse_stream_from_anthropic(
+    mut anthropic_stream: Pin<Box<dyn Stream<Item = Result<Bytes, String>> + Send>>,
+) -> Pin<Box<dyn Stream<Item = Result<Bytes, String>> + Send>> {
+    use async_stream::stream;
+
+    Box::pin(stream! {
+        let mut state = OpenAIStreamState::new();
+        let mut parser = SseParser::new();
+
+        loop {
+            let (events, ended) = match anthropic_stream.next().await {
+                Some(Ok(chunk)) => (parser.push(&chunk), false),
+                Some(Err(e)) => {
+                    yield Err(e);
+                    return;
+                }
+                None => (parser.finish(), true),
+            };
+
+            for event in events {
+                let Ok(json) = serde_json::from_str::<Value>(&event.data) else { continue };
+                // 缺少 `event:` 行时回退到 data.type
+                let event_type = if event.event.is_empty() {
+                    json.get("type").and_then(|v| v.as_str()).unwrap_or_default().to_string()
+                } else {
+                    event.event
+                };
+                if event_type == "error" {
+                    let error_chunk = json!({
+                        "error": {
+                            "message": json.pointer("/error/message").and_then(|m| m.as_str()).unwrap_or("Upstream error"),
+                            "type": json.pointer("/error/type").and_then(|m| m.as_str()).unwrap_or("api_error")
+                        }
+                    });
+                    yield Ok(Bytes::from(format!("data: {}\n\n", error_chunk)));
+                    continue;
+                }
+                for bytes in state.process_event(&event_type, &json) {
+                    yield Ok(bytes);
+                }
+            }
+
+            if ended {
+                break;
+            }
+        }
+
+        // 上游未发送 message_stop 时补齐结束 chunk
+        for bytes in state.finish() {
+            yield Ok(bytes);
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn events(out: &[Bytes]) -> Vec<String> {
+        out.iter()
+            .map(|b| {
+                let s = String::from_utf8_lossy(b);
+                s.lines().next().unwrap_or_default().trim_start_matches("event: ").to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_text_stream_event_sequence() {
+        let mut state = AnthropicStreamState::new();
+        let mut out = state.process_chunk(&json!({
+            "id": "chatcmpl-1", "model": "gpt-4o",
+            "choices": [{"index": 0, "delta": {"role": "assistant", "content": "Hel"}}]
+        }));
+        out.extend(state.process_chunk(&json!({
+            "choices": [{"index": 0, "delta": {"content": "lo"}, "finish_reason": "stop"}]
+        })));
+        out.extend(state.finish());
+
+        assert_eq!(
+            events(&out),
+            vec![
+                "message_start",
+                "content_block_start",
+                "content_block_delta",
+                "content_block_delta",
+                "content_block_stop",
+                "message_delta",
+                "message_stop",
+            ]
+        );
+        assert!(String::from_utf8_lossy(&out[0]).contains("\"id\":\"msg_1\""));
+        assert!(String::from_utf8_lossy(&out[5]).contains("\"stop_reason\":\"end_turn\""));
+        // finish 幂等
+        assert!(state.finish().is_empty());
+    }
+
+    #[test]
+    fn test_reasoning_then_tool_call_blocks() {
+        let mut state = AnthropicStreamState::new();
+        let mut out = state.process_chunk(&json!({
+            "choices": [{"index": 0, "delta": {"reasoning_content": "hmm"}}]
+        }));
+        out.extend(state.process_chunk(&json!({
+            "choices": [{"index": 0, "delta": {"tool_calls": [{"index": 0, "id": "call_1", "function": {"name": "f", "arguments": "{\"a\":"}}]}}]
+        })));
+        out.extend(state.process_chunk(&json!({
+            "choices": [{"index": 0, "delta": {"tool_calls": [{"index": 0, "function": {"arguments": "1}"}}]}, "finish_reason": "tool_calls"}]
+        })));
+        out.extend(state.finish());
+
+        let text: Vec<String> = out.iter().map(|b| String::from_utf8_lossy(b).to_string()).collect();
+        assert!(text[1].contains("\"type\":\"thinking\""));
+        assert!(text[2].contains("thinking_delta"));
+        assert!(text[3].contains("content_block_stop"));
+        assert!(text[4].contains("\"type\":\"tool_use\"") && text[4].contains("\"index\":1"));
+        assert!(text[5].contains("input_json_delta"));
+        assert!(text[6].contains("input_json_delta"));
+        assert!(text.iter().any(|t| t.contains("\"stop_reason\":\"tool_use\"")));
+    }
+
+    #[test]
+    fn test_parallel_tool_calls_keyed_by_index() {
+        let mut state = AnthropicStreamState::new();
+        let mut out = state.process_chunk(&json!({
+            "choices": [{"index": 0, "delta": {"tool_calls": [
+                {"index": 0, "id": "call_a", "function": {"name": "a", "arguments": ""}},
+                {"index": 0, "function": {"arguments": "{}"}},
+                {"index": 1, "function": {"name": "b", "arguments": "{\"x\""}},
+                {"index": 1, "function": {"arguments": ":2}"}}
+            ]}}]
+        }));
+        out.extend(state.finish());
+
+        let text: Vec<String> = out.iter().map(|b| String::from_utf8_lossy(b).to_string()).collect();
+        let starts: Vec<&String> = text.iter().filter(|t| t.contains("content_block_start")).collect();
+        assert_eq!(starts.len(), 2);
+        assert!(starts[0].contains("\"id\":\"call_a\"") && starts[0].contains("\"index\":0"));
+        assert!(starts[1].contains("\"name\":\"b\"") && starts[1].contains("\"index\":1"));
+        let second_args: Vec<&String> = text
+            .iter()
+            .filter(|t| t.contains("input_json_delta") && t.contains("\"index\":1"))
+            .collect();
+        assert_eq!(second_args.len(), 2);
+    }
+
+    fn openai_chunks(out: &[Bytes]) -> Vec<Value> {
+        out.iter()
+            .filter_map(|b| {
+                let s = String::from_utf8_lossy(b);
+                serde_json::from_str(s.trim().trim_start_matches("data: ")).ok()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_anthropic_tool_use_stream_to_openai() {
+        let mut state = OpenAIStreamState::new();
+        let events = [
+            ("message_start", json!({"type": "message_start", "message": {"id": "msg_42", "model": "claude-sonnet-4-5", "usage": {"input_tokens": 10, "output_tokens": 0}}})),
+            ("content_block_start", json!({"type": "content_block_start", "index": 0, "content_block": {"type": "text", "text": ""}})),
+            ("content_block_delta", json!({"type": "content_block_delta", "index": 0, "delta": {"type": "text_delta", "text": "Checking"}})),
+            ("content_block_stop", json!({"type": "content_block_stop", "index": 0})),
+            ("content_block_start", json!({"type": "content_block_start", "index": 1, "content_block": {"type": "tool_use", "id": "toolu_1", "name": "get_weather", "input": {}}})),
+            ("content_block_delta", json!({"type": "content_block_delta", "index": 1, "delta": {"type": "input_json_delta", "partial_json": "{\"city\":"}})),
+            ("content_block_delta", json!({"type": "content_block_delta", "index": 1, "delta": {"type": "input_json_delta", "partial_json": "\"Paris\"}"}})),
+            ("content_block_stop", json!({"type": "content_block_stop", "index": 1})),
+            ("message_delta", json!({"type": "message_delta", "delta": {"stop_reason": "tool_use"}, "usage": {"output_tokens": 15}})),
+            ("message_stop", json!({"type": "message_stop"})),
+        ];
+        let mut out = Vec::new();
+        for (event_type, data) in &events {
+            out.extend(state.process_event(event_type, data));
+        }
+        assert!(state.finish().is_empty());
+        assert_eq!(out.last().unwrap(), &Bytes::from_static(b"data: [DONE]\n\n"));
+
+        let chunks = openai_chunks(&out);
+        assert_eq!(chunks[0]["id"], "chatcmpl-42");
+        assert_eq!(chunks[0]["choices"][0]["delta"]["role"], "assistant");
+        assert_eq!(chunks[1]["choices"][0]["delta"]["content"], "Checking");
+
+        let start = &chunks[2]["choices"][0]["delta"]["tool_calls"][0];
+        assert_eq!(start["index"], 0);
+        assert_eq!(start["id"], "toolu_1");
+        assert_eq!(start["function"]["name"], "get_weather");
+
+        let arguments: String = chunks
+            .iter()
+            .filter_map(|c| c.pointer("/choices/0/delta/tool_calls/0/function/arguments")?.as_str())
+            .collect();
+        assert_eq!(arguments, "{\"city\":\"Paris\"}");
+
+        let last = chunks.last().unwrap();
+        assert_eq!(last["choices"][0]["finish_reason"], "tool_calls");
+        assert_eq!(last["usage"]["prompt_tokens"], 10);
+        assert_eq!(last["usage"]["completion_tokens"], 15);
+    }
+}
@@ -0,0 +1,154 @@
+// AWS event-stream 二进制帧解析 (`application/vnd.amazon.eventstream`)
+// 帧结构: 总长度(4) | Header 长度(4) | 前导 CRC(4) | Headers | Payload | 消息 CRC(4), 整数均为大端序。
+
+use std::collections::HashMap;
+
+const PRELUDE_LEN: usize = 12;
+const MIN_FRAME_LEN: usize = PRELUDE_LEN + 4;
+/// 单帧上限, 防止损坏的长度字段导致无限缓冲
+const MAX_FRAME_LEN: usize = 16 * 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EventMessage {
+    /// 仅保留字符串类型的 Header (`:event-type`, `:message-type` 等)
+    pub headers: HashMap<String, String>,
+    pub payload: Vec<u8>,
+}
+
+impl EventMessage {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).map(String::as_str)
+    }
+}
+
+fn read_u16(buf: &[u8], pos: usize) -> Option<usize> {
+    buf.get(pos..pos + 2).map(|b| u16::from_be_bytes([b[0], b[1]]) as usize)
+}
+
+fn read_u32(buf: &[u8], pos: usize) -> Option<u32> {
+    buf.get(pos..pos + 4).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+fn parse_headers(mut buf: &[u8]) -> Result<HashMap<String, String>, String> {
+    let mut headers = HashMap::new();
+    while !buf.is_empty() {
+        let name_len = buf[0] as usize;
+        let name = buf.get(1..1 + name_len).ok_or("Truncated header name")?;
+        let name = String::from_utf8_lossy(name).into_owned();
+        let mut pos = 1 + name_len;
+        let kind = *buf.get(pos).ok_or("Truncated header type")?;
+        pos += 1;
+        let value_len = match kind {
+            0 | 1 => 0,       // bool true / false
+            2 => 1,           // byte
+            3 => 2,           // short
+            4 => 4,           // int
+            5 | 8 => 8,       // long / timestamp
+            9 => 16,          // uuid
+            6 | 7 => {
+                let len = read_u16(buf, pos).ok_or("Truncated header value length")?;
+                pos += 2;
+                len
+            }
+            other => return Err(format!("Unknown header value type {}", other)),
+        };
+        let value = buf.get(pos..pos + value_len).ok_or("Truncated header value")?;
+        if kind == 7 {
+            headers.insert(name, String::from_utf8_lossy(value).into_owned());
+        }
+        buf = &buf[pos + value_len..];
+    }
+    Ok(headers)
+}
+
+/// 增量解码器: 数据块可能在任意位置被截断
+#[derive(Default)]
+pub struct EventStreamDecoder {
+    buffer: Vec<u8>,
+}
+
+impl EventStreamDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 输入一段数据, 返回其中完整的消息; 校验失败时返回错误 (之后的数据无法再对齐)
+    pub fn push(&mut self, chunk: &[u8]) -> Result<Vec<EventMessage>, String> {
+        self.buffer.extend_from_slice(chunk);
+        let mut messages = Vec::new();
+        while self.buffer.len() >= PRELUDE_LEN {
+            let total_len = read_u32(&self.buffer, 0).unwrap_or(0) as usize;
+            let headers_len = read_u32(&self.buffer, 4).unwrap_or(0) as usize;
+            let prelude_crc = read_u32(&self.buffer, 8).unwrap_or(0);
+            if crc32fast::hash(&self.buffer[..8]) != prelude_crc {
+                return Err("Event stream prelude checksum mismatch".to_string());
+            }
+            if !(MIN_FRAME_LEN..=MAX_FRAME_LEN).contains(&total_len) || headers_len > total_len - MIN_FRAME_LEN {
+                return Err(format!("Invalid event stream frame length {}", total_len));
+            }
+            if self.buffer.len() < total_len {
+                break;
+            }
+
+            let frame: Vec<u8> = self.buffer.drain(..total_len).collect();
+            let message_crc = read_u32(&frame, total_len - 4).unwrap_or(0);
+            if crc32fast::hash(&frame[..total_len - 4]) != message_crc {
+                return Err("Event stream message checksum mismatch".to_string());
+            }
+            let headers = parse_headers(&frame[PRELUDE_LEN..PRELUDE_LEN + headers_len])?;
+            let payload = frame[PRELUDE_LEN + headers_len..total_len - 4].to_vec();
+            messages.push(EventMessage { headers, payload });
+        }
+        Ok(messages)
+    }
+}
+
+#[cfg(test)]
+pub(crate) fn encode_frame(headers: &[(&str, &str)], payload: &[u8]) -> Vec<u8> {
+    let mut header_bytes = Vec::new();
+    for (name, value) in headers {
+        header_bytes.push(name.len() as u8);
+        header_bytes.extend_from_slice(name.as_bytes());
+        header_bytes.push(7);
+        header_bytes.extend_from_slice(&(value.len() as u16).to_be_bytes());
+        header_bytes.extend_from_slice(value.as_bytes());
+    }
+    let total = (MIN_FRAME_LEN + header_bytes.len() + payload.len()) as u32;
+    let mut frame = Vec::new();
+    frame.extend_from_slice(&total.to_be_bytes());
+    frame.extend_from_slice(&(header_bytes.len() as u32).to_be_bytes());
+    let prelude_crc = crc32fast::hash(&frame);
+    frame.extend_from_slice(&prelude_crc.to_be_bytes());
+    frame.extend_from_slice(&header_bytes);
+    frame.extend_from_slice(payload);
+    let crc = crc32fast::hash(&frame);
+    frame.extend_from_slice(&crc.to_be_bytes());
+    frame
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_split_frames() {
+        let mut data = encode_frame(&[(":event-type", "messageStart"), (":message-type", "event")], br#"{"role":"assistant"}"#);
+        data.extend(encode_frame(&[(":event-type", "contentBlockDelta")], br#"{"delta":{"text":"hi"}}"#));
+
+        let mut decoder = EventStreamDecoder::new();
+        let (a, b) = data.split_at(20);
+        assert!(decoder.push(a).unwrap().is_empty());
+        let messages = decoder.push(b).unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].header(":event-type"), Some("messageStart"));
+        assert_eq!(messages[1].payload, br#"{"delta":{"text":"hi"}}"#.to_vec());
+    }
+
+    #[test]
+    fn test_decode_rejects_corrupted_frame() {
+        let mut frame = encode_frame(&[(":event-type", "messageStop")], b"{}");
+        let last = frame.len() - 5;
+        frame[last] ^= 0xff;
+        assert!(EventStreamDecoder::new().push(&frame).is_err());
+    }
+}
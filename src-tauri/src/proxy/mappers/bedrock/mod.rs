@@ -0,0 +1,21 @@
+// Bedrock mapper 模块
+// 负责 OpenAI Chat Completions ↔ Bedrock Converse API 协议转换, 以及 ConverseStream 的 event-stream 二进制帧解析
+
+pub mod event_stream;
+pub mod request;
+pub mod response;
+pub mod streaming;
+
+pub use request::build_converse_request;
+pub use response::converse_response_to_openai;
+pub use streaming::converse_stream_to_sse;
+
+/// Bedrock stopReason -> OpenAI finish_reason
+pub fn to_openai_finish_reason(stop_reason: &str) -> &'static str {
+    match stop_reason {
+        "max_tokens" => "length",
+        "tool_use" => "tool_calls",
+        "content_filtered" | "guardrail_intervened" => "content_filter",
+        _ => "stop",
+    }
+}
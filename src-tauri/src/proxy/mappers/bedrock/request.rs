@@ -0,0 +1,316 @@
+// OpenAI Chat Completions → Bedrock Converse 请求
+
+use serde_json::{json, Map, Value};
+
+use crate::proxy::common::prompt_cache::has_cache_hint;
+
+/// Anthropic 风格的 cache_control 断点对应 Converse 的 cachePoint 块
+fn cache_point() -> Value {
+    json!({ "cachePoint": { "type": "default" } })
+}
+
+/// data URI 的 MIME 类型 → Converse 图片格式
+fn image_format(mime: &str) -> Option<&'static str> {
+    match mime {
+        "image/png" => Some("png"),
+        "image/jpeg" | "image/jpg" => Some("jpeg"),
+        "image/gif" => Some("gif"),
+        "image/webp" => Some("webp"),
+        _ => None,
+    }
+}
+
+fn part_block(part: &Value) -> Option<Value> {
+    match part.get("type").and_then(|t| t.as_str()) {
+        Some("text") => part
+            .get("text")
+            .and_then(|t| t.as_str())
+            .filter(|t| !t.is_empty())
+            .map(|t| json!({ "text": t })),
+        Some("image_url") => {
+            let url = part
+                .pointer("/image_url/url")
+                .or_else(|| part.get("image_url"))
+                .and_then(|u| u.as_str())?;
+            // 仅支持内联 base64 图片: data:image/png;base64,....
+            let (meta, data) = url.strip_prefix("data:")?.split_once(',')?;
+            let format = image_format(meta.split(';').next().unwrap_or_default())?;
+            Some(json!({ "image": { "format": format, "source": { "bytes": data } } }))
+        }
+        _ => None,
+    }
+}
+
+fn content_blocks(content: Option<&Value>) -> Vec<Value> {
+    match content {
+        Some(Value::String(s)) if !s.is_empty() => vec![json!({ "text": s })],
+        Some(Value::Array(parts)) => {
+            let mut blocks = Vec::new();
+            for part in parts {
+                let block = part_block(part);
+                let cached = block.is_some() && has_cache_hint(part);
+                blocks.extend(block);
+                if cached {
+                    blocks.push(cache_point());
+                }
+            }
+            blocks
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// 消息的内容块; 整条消息带 cache_control 时在末尾加断点
+fn message_blocks(msg: &Value) -> Vec<Value> {
+    let mut blocks = content_blocks(msg.get("content"));
+    if has_cache_hint(msg) && !blocks.is_empty() {
+        blocks.push(cache_point());
+    }
+    blocks
+}
+
+/// 请求中是否有显式的缓存断点
+fn has_explicit_cache_hints(body: &Value) -> bool {
+    let messages = body.get("messages").and_then(|m| m.as_array()).into_iter().flatten();
+    let parts = messages.clone().filter_map(|m| m.get("content")).filter_map(|c| c.as_array()).flatten();
+    let tools = body.get("tools").and_then(|t| t.as_array()).into_iter().flatten();
+    messages.clone().any(has_cache_hint)
+        || parts.into_iter().any(has_cache_hint)
+        || tools.flat_map(|t| [Some(t), t.get("function")]).flatten().any(has_cache_hint)
+}
+
+fn tool_result_text(content: Option<&Value>) -> String {
+    match content {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Array(parts)) => parts
+            .iter()
+            .filter_map(|p| p.get("text").and_then(|t| t.as_str()))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        Some(v) if !v.is_null() => v.to_string(),
+        _ => String::new(),
+    }
+}
+
+/// Converse 要求 user / assistant 交替出现, 相邻同角色的消息合并为一条
+fn push_message(messages: &mut Vec<Value>, role: &str, blocks: Vec<Value>) {
+    if blocks.is_empty() {
+        return;
+    }
+    if let Some(last) = messages.last_mut() {
+        if last["role"] == role {
+            if let Some(content) = last["content"].as_array_mut() {
+                content.extend(blocks);
+                return;
+            }
+        }
+    }
+    messages.push(json!({ "role": role, "content": blocks }));
+}
+
+fn tool_spec(tool: &Value) -> Option<Value> {
+    let function = tool.get("function")?;
+    let mut spec = json!({
+        "name": function.get("name")?.as_str()?,
+        "inputSchema": { "json": function.get("parameters").cloned().unwrap_or_else(|| json!({ "type": "object" })) },
+    });
+    if let Some(desc) = function.get("description").and_then(|d| d.as_str()).filter(|d| !d.is_empty()) {
+        spec["description"] = json!(desc);
+    }
+    Some(json!({ "toolSpec": spec }))
+}
+
+fn convert_tool_choice(choice: Option<&Value>) -> Option<Value> {
+    match choice? {
+        Value::String(s) if s == "required" => Some(json!({ "any": {} })),
+        Value::String(s) if s == "auto" => Some(json!({ "auto": {} })),
+        Value::Object(obj) => obj
+            .get("function")
+            .and_then(|f| f.get("name"))
+            .and_then(|n| n.as_str())
+            .map(|name| json!({ "tool": { "name": name } })),
+        _ => None,
+    }
+}
+
+pub fn build_converse_request(body: &Value) -> Result<Value, String> {
+    let source = body
+        .get("messages")
+        .and_then(|m| m.as_array())
+        .ok_or("Missing 'messages' field")?;
+
+    let mut system = Vec::new();
+    let mut messages = Vec::new();
+    for msg in source {
+        match msg.get("role").and_then(|r| r.as_str()).unwrap_or("user") {
+            "system" | "developer" => system.extend(message_blocks(msg)),
+            "assistant" => {
+                let mut blocks = message_blocks(msg);
+                for call in msg.get("tool_calls").and_then(|c| c.as_array()).into_iter().flatten() {
+                    let Some(function) = call.get("function") else { continue };
+                    let input = match function.get("arguments") {
+                        Some(Value::String(s)) => serde_json::from_str(s).unwrap_or_else(|_| json!({})),
+                        Some(v) if v.is_object() => v.clone(),
+                        _ => json!({}),
+                    };
+                    blocks.push(json!({
+                        "toolUse": {
+                            "toolUseId": call.get("id").and_then(|i| i.as_str()).unwrap_or_default(),
+                            "name": function.get("name").and_then(|n| n.as_str()).unwrap_or_default(),
+                            "input": input,
+                        }
+                    }));
+                }
+                push_message(&mut messages, "assistant", blocks);
+            }
+            "tool" => {
+                let block = json!({
+                    "toolResult": {
+                        "toolUseId": msg.get("tool_call_id").and_then(|i| i.as_str()).unwrap_or_default(),
+                        "content": [{ "text": tool_result_text(msg.get("content")) }],
+                    }
+                });
+                let mut blocks = vec![block];
+                if has_cache_hint(msg) {
+                    blocks.push(cache_point());
+                }
+                push_message(&mut messages, "user", blocks);
+            }
+            _ => push_message(&mut messages, "user", message_blocks(msg)),
+        }
+    }
+    if messages.is_empty() {
+        return Err("Request contains no user or assistant messages".to_string());
+    }
+
+    // OpenAI 的 prompt_cache_key 表示希望缓存稳定前缀: 没有显式断点时在系统提示词与工具定义之后各加一个
+    let cache_prefix = body.get("prompt_cache_key").is_some_and(|k| !k.is_null()) && !has_explicit_cache_hints(body);
+    if cache_prefix && !system.is_empty() {
+        system.push(cache_point());
+    }
+
+    let mut request = json!({ "messages": messages });
+    if !system.is_empty() {
+        request["system"] = json!(system);
+    }
+
+    let mut inference = Map::new();
+    if let Some(v) = body.get("max_completion_tokens").or_else(|| body.get("max_tokens")).filter(|v| v.is_u64()) {
+        inference.insert("maxTokens".to_string(), v.clone());
+    }
+    if let Some(v) = body.get("temperature").filter(|v| v.is_number()) {
+        inference.insert("temperature".to_string(), v.clone());
+    }
+    if let Some(v) = body.get("top_p").filter(|v| v.is_number()) {
+        inference.insert("topP".to_string(), v.clone());
+    }
+    match body.get("stop") {
+        Some(Value::String(s)) => {
+            inference.insert("stopSequences".to_string(), json!([s]));
+        }
+        Some(Value::Array(items)) if !items.is_empty() => {
+            inference.insert("stopSequences".to_string(), Value::Array(items.clone()));
+        }
+        _ => {}
+    }
+    if !inference.is_empty() {
+        request["inferenceConfig"] = Value::Object(inference);
+    }
+
+    let tool_choice = body.get("tool_choice");
+    let tools_disabled = tool_choice.and_then(|c| c.as_str()) == Some("none");
+    let mut tools: Vec<Value> = Vec::new();
+    for tool in body.get("tools").and_then(|t| t.as_array()).into_iter().flatten() {
+        let spec = tool_spec(tool);
+        let cached = spec.is_some() && (has_cache_hint(tool) || tool.get("function").is_some_and(has_cache_hint));
+        tools.extend(spec);
+        if cached {
+            tools.push(cache_point());
+        }
+    }
+    if cache_prefix && !tools.is_empty() {
+        tools.push(cache_point());
+    }
+    if !tools.is_empty() && !tools_disabled {
+        let mut tool_config = json!({ "tools": tools });
+        if let Some(choice) = convert_tool_choice(tool_choice) {
+            tool_config["toolChoice"] = choice;
+        }
+        request["toolConfig"] = tool_config;
+    }
+
+    // 模型特有参数 (例如 Claude 的 thinking / top_k) 原样透传
+    if let Some(extra) = body.get("additional_model_request_fields").filter(|v| v.is_object()) {
+        request["additionalModelRequestFields"] = extra.clone();
+    }
+    Ok(request)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_converse_request() {
+        let body = json!({
+            "model": "claude-3-5-sonnet",
+            "max_tokens": 512,
+            "temperature": 0.3,
+            "stop": ["END"],
+            "tool_choice": "required",
+            "tools": [{"type": "function", "function": {"name": "lookup", "description": "Find", "parameters": {"type": "object"}}}],
+            "messages": [
+                {"role": "system", "content": "be brief"},
+                {"role": "user", "content": [
+                    {"type": "text", "text": "what is this"},
+                    {"type": "image_url", "image_url": {"url": "data:image/png;base64,AAAA"}}
+                ]},
+                {"role": "assistant", "content": null, "tool_calls": [
+                    {"id": "t1", "type": "function", "function": {"name": "lookup", "arguments": "{\"q\":1}"}},
+                    {"id": "t2", "type": "function", "function": {"name": "lookup", "arguments": "{\"q\":2}"}}
+                ]},
+                {"role": "tool", "tool_call_id": "t1", "content": "one"},
+                {"role": "tool", "tool_call_id": "t2", "content": "two"}
+            ]
+        });
+        let req = build_converse_request(&body).unwrap();
+
+        assert_eq!(req["system"], json!([{ "text": "be brief" }]));
+        assert_eq!(req["inferenceConfig"], json!({ "maxTokens": 512, "temperature": 0.3, "stopSequences": ["END"] }));
+        assert_eq!(req["toolConfig"]["toolChoice"], json!({ "any": {} }));
+        assert_eq!(req["toolConfig"]["tools"][0]["toolSpec"]["inputSchema"]["json"]["type"], "object");
+
+        let messages = req["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0]["content"][1]["image"]["format"], "png");
+        assert_eq!(messages[1]["content"][1]["toolUse"]["input"]["q"], 2);
+        // 连续的工具结果合并为一条 user 消息
+        assert_eq!(messages[2]["role"], "user");
+        assert_eq!(messages[2]["content"].as_array().unwrap().len(), 2);
+        assert_eq!(messages[2]["content"][1]["toolResult"]["toolUseId"], "t2");
+    }
+
+    #[test]
+    fn test_cache_hints_become_cache_points() {
+        let body = json!({
+            "messages": [
+                {"role": "system", "content": [{"type": "text", "text": "long rules", "cache_control": {"type": "ephemeral"}}]},
+                {"role": "user", "content": "question", "cache_control": {"type": "ephemeral"}}
+            ]
+        });
+        let req = build_converse_request(&body).unwrap();
+        assert_eq!(req["system"], json!([{ "text": "long rules" }, { "cachePoint": { "type": "default" } }]));
+        assert_eq!(req["messages"][0]["content"][1], json!({ "cachePoint": { "type": "default" } }));
+
+        // prompt_cache_key 且没有显式断点: 缓存系统提示词与工具定义
+        let body = json!({
+            "prompt_cache_key": "agent-1",
+            "tools": [{"type": "function", "function": {"name": "lookup"}}],
+            "messages": [{"role": "system", "content": "rules"}, {"role": "user", "content": "hi"}]
+        });
+        let req = build_converse_request(&body).unwrap();
+        assert_eq!(req["system"][1], json!({ "cachePoint": { "type": "default" } }));
+        assert_eq!(req["toolConfig"]["tools"][1], json!({ "cachePoint": { "type": "default" } }));
+        assert_eq!(req["messages"][0]["content"].as_array().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,98 @@
+// Bedrock Converse 响应 → OpenAI Chat Completions
+
+use serde_json::{json, Value};
+
+use super::to_openai_finish_reason;
+
+/// Converse 的 inputTokens 不含缓存命中与写入部分, 转为 OpenAI 语义 (prompt_tokens 包含缓存部分)
+pub fn usage(usage: &Value) -> Value {
+    let cache_read = usage.get("cacheReadInputTokens").and_then(|v| v.as_u64()).unwrap_or(0);
+    let cache_write = usage.get("cacheWriteInputTokens").and_then(|v| v.as_u64()).unwrap_or(0);
+    let prompt = usage.get("inputTokens").and_then(|v| v.as_u64()).unwrap_or(0) + cache_read + cache_write;
+    let completion = usage.get("outputTokens").and_then(|v| v.as_u64()).unwrap_or(0);
+    let mut out = json!({
+        "prompt_tokens": prompt,
+        "completion_tokens": completion,
+        "total_tokens": usage.get("totalTokens").and_then(|v| v.as_u64()).unwrap_or(prompt + completion),
+    });
+    if cache_read > 0 || cache_write > 0 {
+        out["prompt_tokens_details"] = json!({ "cached_tokens": cache_read, "cache_write_tokens": cache_write });
+    }
+    out
+}
+
+pub fn converse_response_to_openai(resp: &Value, model: &str) -> Value {
+    let blocks = resp
+        .pointer("/output/message/content")
+        .and_then(|c| c.as_array())
+        .cloned()
+        .unwrap_or_default();
+
+    let mut text = String::new();
+    let mut reasoning = String::new();
+    let mut tool_calls = Vec::new();
+    for block in &blocks {
+        if let Some(t) = block.get("text").and_then(|t| t.as_str()) {
+            text.push_str(t);
+        } else if let Some(t) = block.pointer("/reasoningContent/reasoningText/text").and_then(|t| t.as_str()) {
+            reasoning.push_str(t);
+        } else if let Some(tool) = block.get("toolUse") {
+            tool_calls.push(json!({
+                "id": tool.get("toolUseId").and_then(|i| i.as_str()).unwrap_or_default(),
+                "type": "function",
+                "function": {
+                    "name": tool.get("name").and_then(|n| n.as_str()).unwrap_or_default(),
+                    "arguments": tool.get("input").map(|i| i.to_string()).unwrap_or_else(|| "{}".to_string()),
+                }
+            }));
+        }
+    }
+
+    let mut message = json!({ "role": "assistant", "content": text });
+    if !reasoning.is_empty() {
+        message["reasoning_content"] = json!(reasoning);
+    }
+    if !tool_calls.is_empty() {
+        message["tool_calls"] = json!(tool_calls);
+    }
+    let stop_reason = resp.get("stopReason").and_then(|s| s.as_str()).unwrap_or("end_turn");
+
+    json!({
+        "id": format!("chatcmpl-{}", uuid::Uuid::new_v4().simple()),
+        "object": "chat.completion",
+        "created": chrono::Utc::now().timestamp(),
+        "model": model,
+        "choices": [{
+            "index": 0,
+            "message": message,
+            "finish_reason": to_openai_finish_reason(stop_reason),
+        }],
+        "usage": usage(resp.get("usage").unwrap_or(&Value::Null)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_converse_response_to_openai() {
+        let resp = json!({
+            "output": {"message": {"role": "assistant", "content": [
+                {"reasoningContent": {"reasoningText": {"text": "hmm"}}},
+                {"text": "Calling tool"},
+                {"toolUse": {"toolUseId": "tooluse_1", "name": "lookup", "input": {"q": "x"}}}
+            ]}},
+            "stopReason": "tool_use",
+            "usage": {"inputTokens": 10, "outputTokens": 4, "totalTokens": 14}
+        });
+        let out = converse_response_to_openai(&resp, "claude-3-5-sonnet");
+        let choice = &out["choices"][0];
+        assert_eq!(choice["finish_reason"], "tool_calls");
+        assert_eq!(choice["message"]["content"], "Calling tool");
+        assert_eq!(choice["message"]["reasoning_content"], "hmm");
+        assert_eq!(choice["message"]["tool_calls"][0]["id"], "tooluse_1");
+        assert_eq!(choice["message"]["tool_calls"][0]["function"]["arguments"], "{\"q\":\"x\"}");
+        assert_eq!(out["usage"]["total_tokens"], 14);
+    }
+}
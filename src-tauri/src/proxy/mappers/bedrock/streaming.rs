@@ -0,0 +1,219 @@
+// Bedrock ConverseStream (event-stream) → OpenAI SSE
+
+use bytes::Bytes;
+use futures::{Stream, StreamExt};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+
+use super::event_stream::{EventMessage, EventStreamDecoder};
+use super::response::usage;
+use super::to_openai_finish_reason;
+
+pub struct ConverseStreamState {
+    id: String,
+    model: String,
+    created: i64,
+    /// contentBlockIndex → OpenAI tool_calls 下标
+    tool_indexes: HashMap<u64, usize>,
+    stop_reason: Option<String>,
+    finished: bool,
+}
+
+impl ConverseStreamState {
+    pub fn new(model: &str) -> Self {
+        Self {
+            id: format!("chatcmpl-{}", uuid::Uuid::new_v4().simple()),
+            model: model.to_string(),
+            created: chrono::Utc::now().timestamp(),
+            tool_indexes: HashMap::new(),
+            stop_reason: None,
+            finished: false,
+        }
+    }
+
+    fn chunk(&self, delta: Value, finish_reason: Option<&str>, usage: Option<Value>) -> String {
+        let mut chunk = json!({
+            "id": self.id,
+            "object": "chat.completion.chunk",
+            "created": self.created,
+            "model": self.model,
+            "choices": [{ "index": 0, "delta": delta, "finish_reason": finish_reason }],
+        });
+        if let Some(usage) = usage {
+            chunk["usage"] = usage;
+        }
+        format!("data: {}\n\n", chunk)
+    }
+
+    fn finish_chunk(&mut self, usage: Option<Value>) -> String {
+        self.finished = true;
+        let reason = to_openai_finish_reason(self.stop_reason.as_deref().unwrap_or("end_turn"));
+        format!("{}data: [DONE]\n\n", self.chunk(json!({}), Some(reason), usage))
+    }
+
+    fn error(&mut self, message: &str) -> String {
+        self.finished = true;
+        format!(
+            "data: {}\n\ndata: [DONE]\n\n",
+            json!({ "error": { "message": message, "type": "upstream_error", "code": "bedrock_error" } })
+        )
+    }
+
+    pub fn convert(&mut self, message: &EventMessage) -> String {
+        if self.finished {
+            return String::new();
+        }
+        let payload: Value = serde_json::from_slice(&message.payload).unwrap_or(Value::Null);
+        if message.header(":message-type") == Some("exception") {
+            let text = payload
+                .get("message")
+                .and_then(|m| m.as_str())
+                .or_else(|| message.header(":exception-type"))
+                .unwrap_or("Bedrock stream exception");
+            return self.error(text);
+        }
+
+        match message.header(":event-type").unwrap_or_default() {
+            "messageStart" => self.chunk(json!({ "role": "assistant", "content": "" }), None, None),
+            "contentBlockStart" => {
+                let Some(tool) = payload.pointer("/start/toolUse") else {
+                    return String::new();
+                };
+                let block = payload.get("contentBlockIndex").and_then(|i| i.as_u64()).unwrap_or(0);
+                let index = self.tool_indexes.len();
+                self.tool_indexes.insert(block, index);
+                self.chunk(
+                    json!({ "tool_calls": [{
+                        "index": index,
+                        "id": tool.get("toolUseId").and_then(|i| i.as_str()).unwrap_or_default(),
+                        "type": "function",
+                        "function": { "name": tool.get("name").and_then(|n| n.as_str()).unwrap_or_default(), "arguments": "" },
+                    }] }),
+                    None,
+                    None,
+                )
+            }
+            "contentBlockDelta" => {
+                let Some(delta) = payload.get("delta") else {
+                    return String::new();
+                };
+                let mut out = Map::new();
+                if let Some(text) = delta.get("text").and_then(|t| t.as_str()) {
+                    out.insert("content".to_string(), json!(text));
+                } else if let Some(text) = delta.pointer("/reasoningContent/text").and_then(|t| t.as_str()) {
+                    out.insert("reasoning_content".to_string(), json!(text));
+                } else if let Some(input) = delta.pointer("/toolUse/input").and_then(|i| i.as_str()) {
+                    let block = payload.get("contentBlockIndex").and_then(|i| i.as_u64()).unwrap_or(0);
+                    let index = self.tool_indexes.get(&block).copied().unwrap_or(0);
+                    out.insert(
+                        "tool_calls".to_string(),
+                        json!([{ "index": index, "function": { "arguments": input } }]),
+                    );
+                }
+                if out.is_empty() {
+                    String::new()
+                } else {
+                    self.chunk(Value::Object(out), None, None)
+                }
+            }
+            "messageStop" => {
+                self.stop_reason = payload.get("stopReason").and_then(|s| s.as_str()).map(String::from);
+                String::new()
+            }
+            // metadata 在 messageStop 之后到达, 携带用量
+            "metadata" => {
+                let usage = payload.get("usage").map(usage);
+                self.finish_chunk(usage)
+            }
+            _ => String::new(),
+        }
+    }
+
+    /// 上游结束但未收到 metadata 时补发结束标记
+    pub fn finish(&mut self) -> String {
+        if self.finished {
+            String::new()
+        } else {
+            self.finish_chunk(None)
+        }
+    }
+}
+
+/// 把 ConverseStream 的二进制响应流转换为 OpenAI SSE 流
+pub fn converse_stream_to_sse<S, E>(upstream: S, model: &str) -> impl Stream<Item = Result<Bytes, std::io::Error>> + Send
+where
+    S: Stream<Item = Result<Bytes, E>> + Send + Unpin + 'static,
+    E: std::fmt::Display + Send + 'static,
+{
+    let mut state = ConverseStreamState::new(model);
+    async_stream::stream! {
+        let mut upstream = upstream;
+        let mut decoder = EventStreamDecoder::new();
+        while let Some(chunk) = upstream.next().await {
+            let bytes = match chunk {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    tracing::warn!("[Bedrock] Upstream stream error: {}", e);
+                    break;
+                }
+            };
+            let out = match decoder.push(&bytes) {
+                Ok(messages) => messages.iter().map(|m| state.convert(m)).collect::<String>(),
+                Err(e) => {
+                    tracing::warn!("[Bedrock] {}", e);
+                    yield Ok(Bytes::from(state.error(&e)));
+                    break;
+                }
+            };
+            if !out.is_empty() {
+                yield Ok(Bytes::from(out));
+            }
+        }
+        let out = state.finish();
+        if !out.is_empty() {
+            yield Ok(Bytes::from(out));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy::mappers::bedrock::event_stream::encode_frame;
+
+    fn event(kind: &str, payload: Value) -> Bytes {
+        Bytes::from(encode_frame(
+            &[(":event-type", kind), (":message-type", "event")],
+            payload.to_string().as_bytes(),
+        ))
+    }
+
+    #[tokio::test]
+    async fn test_converse_stream_to_sse() {
+        let frames = vec![
+            Ok::<_, String>(event("messageStart", json!({"role": "assistant"}))),
+            Ok(event("contentBlockDelta", json!({"contentBlockIndex": 0, "delta": {"text": "Hi"}}))),
+            Ok(event("contentBlockStart", json!({"contentBlockIndex": 1, "start": {"toolUse": {"toolUseId": "t1", "name": "lookup"}}}))),
+            Ok(event("contentBlockDelta", json!({"contentBlockIndex": 1, "delta": {"toolUse": {"input": "{\"q\":1}"}}}))),
+            Ok(event("messageStop", json!({"stopReason": "tool_use"}))),
+            Ok(event("metadata", json!({"usage": {"inputTokens": 3, "outputTokens": 5, "totalTokens": 8}}))),
+        ];
+        let sse = converse_stream_to_sse(futures::stream::iter(frames), "claude-3-5-sonnet");
+        let out: String = sse
+            .map(|c| String::from_utf8(c.unwrap().to_vec()).unwrap())
+            .collect::<Vec<_>>()
+            .await
+            .concat();
+
+        let events: Vec<&str> = out.split("\n\n").filter(|e| !e.is_empty()).collect();
+        assert_eq!(events.len(), 6);
+        let parse = |e: &str| serde_json::from_str::<Value>(e.trim_start_matches("data: ")).unwrap();
+        assert_eq!(parse(events[1])["choices"][0]["delta"]["content"], "Hi");
+        assert_eq!(parse(events[2])["choices"][0]["delta"]["tool_calls"][0]["id"], "t1");
+        assert_eq!(parse(events[3])["choices"][0]["delta"]["tool_calls"][0]["function"]["arguments"], "{\"q\":1}");
+        let last = parse(events[4]);
+        assert_eq!(last["choices"][0]["finish_reason"], "tool_calls");
+        assert_eq!(last["usage"]["total_tokens"], 8);
+        assert_eq!(events[5], "data: [DONE]");
+    }
+}
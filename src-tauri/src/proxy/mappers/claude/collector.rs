@@ -2,29 +2,12 @@
 // 用于非 Stream 请求的自动转换
 
 use super::models::*;
+use crate::proxy::common::sse::SseParser;
 use bytes::Bytes;
 use futures::StreamExt;
 use serde_json::{json, Value};
 use std::io;
 
-/// SSE 事件类型
-#[derive(Debug, Clone)]
-struct SseEvent {
-    event_type: String,
-    data: Value,
-}
-
-/// 解析 SSE 行
-fn parse_sse_line(line: &str) -> Option<(String, String)> {
-    if let Some(colon_pos) = line.find(':') {
-        let key = &line[..colon_pos];
-        let value = line[colon_pos + 1..].trim_start();
-        Some((key.to_string(), value.to_string()))
-    } else {
-        None
-    }
-}
-
 /// 将 SSE Stream 收集为完整的 Claude Response
 ///
 /// 此函数接收一个 SSE 字节流，解析所有事件，并重建完整的 ClaudeResponse 对象。
@@ -35,37 +18,25 @@ pub async fn collect_stream_to_json<S>(
 where
     S: futures::Stream<Item = Result<Bytes, io::Error>> + Unpin,
 {
-    let mut events = Vec::new();
-    let mut current_event_type = String::new();
-    let mut current_data = String::new();
-
-    // 1. 收集所有 SSE 事件
+    // 1. 收集所有 SSE 事件 (增量解析, 兼容跨 chunk 拆分的事件)
+    let mut parser = SseParser::new();
+    let mut raw_events = Vec::new();
     while let Some(chunk_result) = stream.next().await {
         let chunk = chunk_result.map_err(|e| format!("Stream error: {}", e))?;
-        let text = String::from_utf8_lossy(&chunk);
-
-        for line in text.lines() {
-            if line.is_empty() {
-                // 空行表示事件结束
-                if !current_data.is_empty() {
-                    if let Ok(data) = serde_json::from_str::<Value>(&current_data) {
-                        events.push(SseEvent {
-                            event_type: current_event_type.clone(),
-                            data,
-                        });
-                    }
-                    current_event_type.clear();
-                    current_data.clear();
-                }
-            } else if let Some((key, value)) = parse_sse_line(line) {
-                match key.as_str() {
-                    "event" => current_event_type = value,
-                    "data" => current_data = value,
-                    _ => {}
-                }
-            }
-        }
+        raw_events.extend(parser.push(&chunk));
     }
+    raw_events.extend(parser.finish());
+
+    // 缺少 `event:` 行时回退到 data.type
+    let events = raw_events.into_iter().filter_map(|event| {
+        let data = serde_json::from_str::<Value>(&event.data).ok()?;
+        let event_type = if event.event.is_empty() {
+            data.get("type").and_then(|v| v.as_str()).unwrap_or_default().to_string()
+        } else {
+            event.event
+        };
+        Some((event_type, data))
+    });
 
     // 2. 重建 ClaudeResponse
     let mut response = ClaudeResponse {
@@ -92,11 +63,11 @@ where
     let mut current_tool_use: Option<Value> = None;
     let mut current_tool_input = String::new();
 
-    for event in events {
-        match event.event_type.as_str() {
+    for (event_type, data) in events {
+        match event_type.as_str() {
             "message_start" => {
                 // 提取基本信息
-                if let Some(message) = event.data.get("message") {
+                if let Some(message) = data.get("message") {
                     if let Some(id) = message.get("id").and_then(|v| v.as_str()) {
                         response.id = id.to_string();
                     }
@@ -112,7 +83,7 @@ where
             }
 
             "content_block_start" => {
-                if let Some(content_block) = event.data.get("content_block") {
+                if let Some(content_block) = data.get("content_block") {
                     if let Some(block_type) = content_block.get("type").and_then(|v| v.as_str()) {
                         match block_type {
                             "text" => current_text.clear(),
@@ -134,7 +105,7 @@ where
             }
 
             "content_block_delta" => {
-                if let Some(delta) = event.data.get("delta") {
+                if let Some(delta) = data.get("delta") {
                     if let Some(delta_type) = delta.get("type").and_then(|v| v.as_str()) {
                         match delta_type {
                             "text_delta" => {
@@ -151,6 +122,11 @@ where
                                     current_signature = Some(sig.to_string());
                                 }
                             }
+                            "signature_delta" => {
+                                if let Some(sig) = delta.get("signature").and_then(|v| v.as_str()) {
+                                    current_signature = Some(sig.to_string());
+                                }
+                            }
                             "input_json_delta" => {
                                 if let Some(partial_json) = delta.get("partial_json").and_then(|v| v.as_str()) {
                                     current_tool_input.push_str(partial_json);
@@ -198,12 +174,12 @@ where
             }
 
             "message_delta" => {
-                if let Some(delta) = event.data.get("delta") {
+                if let Some(delta) = data.get("delta") {
                     if let Some(stop_reason) = delta.get("stop_reason").and_then(|v| v.as_str()) {
                         response.stop_reason = stop_reason.to_string();
                     }
                 }
-                if let Some(usage) = event.data.get("usage") {
+                if let Some(usage) = data.get("usage") {
                     if let Ok(u) = serde_json::from_value::<Usage>(usage.clone()) {
                         response.usage = u;
                     }
@@ -217,7 +193,7 @@ where
 
             "error" => {
                 // 错误事件
-                return Err(format!("Stream error: {:?}", event.data));
+                return Err(format!("Stream error: {:?}", data));
             }
 
             _ => {
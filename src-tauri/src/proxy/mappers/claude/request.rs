@@ -1009,6 +1009,15 @@ fn build_contents(
                                 }
                             }));
                             saw_non_thinking = true;
+                        } else if let Some(url) = source.url.as_deref() {
+                            // 未能预先下载的远程图片
+                            parts.push(json!({
+                                "fileData": {
+                                    "fileUri": url,
+                                    "mimeType": crate::proxy::mappers::vision::mime_from_path(url)
+                                }
+                            }));
+                            saw_non_thinking = true;
                         }
                     }
                     ContentBlock::Document { source, .. } => {
@@ -1967,6 +1976,7 @@ mod tests {
                                 source_type: "base64".to_string(),
                                 media_type: "image/png".to_string(),
                                 data: "iVBORw0KGgo=".to_string(),
+                                url: None,
                             },
                             cache_control: Some(json!({"type": "ephemeral"})), // 这个也应该被清理
                         },
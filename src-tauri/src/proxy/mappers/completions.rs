@@ -0,0 +1,260 @@
+// Legacy Completions ↔ Chat Completions
+// 只提供 Chat Completions 接口的上游处理 `/v1/completions` 请求: prompt 转为一条用户消息,
+// 回复 (JSON 或 SSE) 转回 `text_completion` 格式。`echo` 把 prompt 拼在文本前; `logprobs` 转为 Chat 的
+// `logprobs` / `top_logprobs`, 回复中的逐 token 概率转为传统格式 (prompt 部分没有概率, 按一个 token 记为 null)。
+
+use std::collections::HashMap;
+use std::pin::Pin;
+
+use bytes::{Bytes, BytesMut};
+use futures::{Stream, StreamExt};
+use serde_json::{json, Map, Value};
+
+/// 请求中的单个 prompt; 多个 prompt 或 token 数组无法转换为一次 Chat 请求
+pub fn prompt_of(body: &Value) -> Result<String, String> {
+    match body.get("prompt") {
+        None | Some(Value::Null) => Ok(String::new()),
+        Some(Value::String(prompt)) => Ok(prompt.clone()),
+        Some(Value::Array(items)) => match items.as_slice() {
+            [] => Ok(String::new()),
+            [Value::String(prompt)] => Ok(prompt.clone()),
+            [Value::String(_), ..] => Err("Only a single prompt per request is supported for this model".to_string()),
+            _ => Err("Token-array prompts are not supported for this model; send the prompt as text".to_string()),
+        },
+        Some(_) => Err("'prompt' must be a string".to_string()),
+    }
+}
+
+/// Legacy Completions 请求转为 Chat Completions 请求
+pub fn to_chat_request(body: &Value) -> Result<Value, String> {
+    let prompt = prompt_of(body)?;
+    if body.get("suffix").and_then(Value::as_str).is_some_and(|s| !s.is_empty()) {
+        return Err("'suffix' (insertion) is not supported for this model".to_string());
+    }
+    let mut chat = Map::new();
+    chat.insert("model".to_string(), body.get("model").cloned().unwrap_or(Value::Null));
+    chat.insert("messages".to_string(), json!([{ "role": "user", "content": prompt }]));
+    for key in [
+        "max_tokens", "temperature", "top_p", "n", "stop", "presence_penalty", "frequency_penalty",
+        "logit_bias", "seed", "user", "stream",
+    ] {
+        if let Some(value) = body.get(key).filter(|v| !v.is_null()) {
+            chat.insert(key.to_string(), value.clone());
+        }
+    }
+    if body.get("stream").and_then(Value::as_bool) == Some(true) {
+        chat.insert("stream_options".to_string(), json!({ "include_usage": true }));
+    }
+    // 传统接口的 logprobs 是候选数 (0-5)
+    if let Some(top) = body.get("logprobs").and_then(Value::as_u64) {
+        chat.insert("logprobs".to_string(), json!(true));
+        chat.insert("top_logprobs".to_string(), json!(top));
+    }
+    Ok(Value::Object(chat))
+}
+
+fn wants_echo(request: &Value) -> bool {
+    request.get("echo").and_then(Value::as_bool) == Some(true)
+}
+
+/// Chat 逐 token 概率转为传统格式; `offset` 为第一个 token 在文本中的位置
+fn legacy_logprobs(content: &[Value], offset: usize, prompt: Option<&str>) -> Value {
+    let (mut tokens, mut token_logprobs, mut top_logprobs, mut text_offset) = (vec![], vec![], vec![], vec![]);
+    let mut position = offset;
+    if let Some(prompt) = prompt.filter(|p| !p.is_empty()) {
+        tokens.push(json!(prompt));
+        token_logprobs.push(Value::Null);
+        top_logprobs.push(Value::Null);
+        text_offset.push(json!(position));
+        position += prompt.len();
+    }
+    for entry in content {
+        let token = entry.get("token").and_then(Value::as_str).unwrap_or_default();
+        let top: Map<String, Value> = entry
+            .get("top_logprobs")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|t| Some((t.get("token")?.as_str()?.to_string(), t.get("logprob")?.clone())))
+            .collect();
+        tokens.push(json!(token));
+        token_logprobs.push(entry.get("logprob").cloned().unwrap_or(Value::Null));
+        top_logprobs.push(Value::Object(top));
+        text_offset.push(json!(position));
+        position += token.len();
+    }
+    json!({ "tokens": tokens, "token_logprobs": token_logprobs, "top_logprobs": top_logprobs, "text_offset": text_offset })
+}
+
+fn logprob_content(choice: &Value) -> Option<&Vec<Value>> {
+    choice.pointer("/logprobs/content").and_then(Value::as_array)
+}
+
+/// Chat Completions 响应转为 Legacy Completions 响应
+pub fn from_chat_response(chat: &Value, request: &Value) -> Value {
+    let prompt = if wants_echo(request) { prompt_of(request).unwrap_or_default() } else { String::new() };
+    let choices: Vec<Value> = chat
+        .get("choices")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .map(|choice| {
+            let text = choice.pointer("/message/content").and_then(Value::as_str).unwrap_or_default();
+            let logprobs = logprob_content(choice).map(|c| legacy_logprobs(c, 0, Some(&prompt))).unwrap_or(Value::Null);
+            json!({
+                "text": format!("{}{}", prompt, text),
+                "index": choice.get("index").cloned().unwrap_or(json!(0)),
+                "logprobs": logprobs,
+                "finish_reason": choice.get("finish_reason").cloned().unwrap_or(Value::Null),
+            })
+        })
+        .collect();
+    json!({
+        "id": chat.get("id").and_then(Value::as_str).map(|id| id.replacen("chatcmpl", "cmpl", 1)),
+        "object": "text_completion",
+        "created": chat.get("created").cloned().unwrap_or_else(|| json!(chrono::Utc::now().timestamp())),
+        "model": chat.get("model").cloned().unwrap_or_else(|| request.get("model").cloned().unwrap_or(Value::Null)),
+        "choices": choices,
+        "usage": chat.get("usage").cloned().unwrap_or(Value::Null),
+    })
+}
+
+/// Chat SSE chunk → `text_completion` chunk
+pub struct CompletionsStream {
+    echo: Option<String>,
+    /// 每个 choice 已输出的文本长度 (用于 text_offset); 不存在表示尚未输出
+    offsets: HashMap<u64, usize>,
+}
+
+impl CompletionsStream {
+    pub fn new(request: &Value) -> Self {
+        let echo = wants_echo(request).then(|| prompt_of(request).unwrap_or_default());
+        Self { echo, offsets: HashMap::new() }
+    }
+
+    pub fn push(&mut self, chunk: &Value) -> Option<Value> {
+        if chunk.get("error").is_some() {
+            return Some(chunk.clone());
+        }
+        let mut choices = Vec::new();
+        for choice in chunk.get("choices").and_then(Value::as_array).into_iter().flatten() {
+            let index = choice.get("index").and_then(Value::as_u64).unwrap_or(0);
+            let finish_reason = choice.get("finish_reason").cloned().unwrap_or(Value::Null);
+            let mut text = choice.pointer("/delta/content").and_then(Value::as_str).unwrap_or_default().to_string();
+            let tokens = logprob_content(choice);
+            if text.is_empty() && finish_reason.is_null() && tokens.is_none_or(|t| t.is_empty()) {
+                continue;
+            }
+            let first = !self.offsets.contains_key(&index);
+            let offset = self.offsets.entry(index).or_insert(0);
+            let prompt = self.echo.as_deref().filter(|_| first);
+            let logprobs = tokens.map(|t| legacy_logprobs(t, *offset, prompt)).unwrap_or(Value::Null);
+            if let Some(prompt) = prompt {
+                text.insert_str(0, prompt);
+            }
+            *offset += text.len();
+            choices.push(json!({ "text": text, "index": index, "logprobs": logprobs, "finish_reason": finish_reason }));
+        }
+        let usage = chunk.get("usage").filter(|u| !u.is_null());
+        if choices.is_empty() && usage.is_none() {
+            return None;
+        }
+        let mut legacy = json!({
+            "id": chunk.get("id").and_then(Value::as_str).map(|id| id.replacen("chatcmpl", "cmpl", 1)),
+            "object": "text_completion",
+            "created": chunk.get("created").cloned().unwrap_or(Value::Null),
+            "model": chunk.get("model").cloned().unwrap_or(Value::Null),
+            "choices": choices,
+        });
+        if let Some(usage) = usage {
+            legacy["usage"] = usage.clone();
+        }
+        Some(legacy)
+    }
+}
+
+/// Chat SSE 流转为 Legacy Completions SSE 流
+pub fn translate_stream<S, E>(upstream: S, request: &Value) -> Pin<Box<dyn Stream<Item = Result<Bytes, E>> + Send>>
+where
+    S: Stream<Item = Result<Bytes, E>> + Send + 'static,
+    E: Send + 'static,
+{
+    let mut state = CompletionsStream::new(request);
+    let stream = async_stream::stream! {
+        let mut upstream = Box::pin(upstream);
+        let mut buffer = BytesMut::new();
+        while let Some(chunk) = upstream.next().await {
+            let chunk = match chunk {
+                Ok(chunk) => chunk,
+                Err(e) => {
+                    yield Err(e);
+                    return;
+                }
+            };
+            buffer.extend_from_slice(&chunk);
+            while let Some(pos) = buffer.iter().position(|&b| b == b'\n') {
+                let line = buffer.split_to(pos + 1);
+                let line = String::from_utf8_lossy(&line);
+                let Some(data) = line.trim().strip_prefix("data:").map(str::trim) else {
+                    continue;
+                };
+                if data == "[DONE]" {
+                    continue;
+                }
+                if let Some(legacy) = serde_json::from_str::<Value>(data).ok().and_then(|json| state.push(&json)) {
+                    yield Ok(Bytes::from(format!("data: {}\n\n", legacy)));
+                }
+            }
+        }
+        yield Ok(Bytes::from_static(b"data: [DONE]\n\n"));
+    };
+    Box::pin(stream)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_and_response_with_echo_logprobs() {
+        let body = json!({"model": "mistral-small", "prompt": ["Once upon"], "max_tokens": 5, "echo": true, "logprobs": 2, "best_of": 3});
+        let chat = to_chat_request(&body).unwrap();
+        assert_eq!(chat["messages"], json!([{"role": "user", "content": "Once upon"}]));
+        assert_eq!((chat["logprobs"].clone(), chat["top_logprobs"].clone()), (json!(true), json!(2)));
+        assert!(chat.get("best_of").is_none() && chat.get("echo").is_none());
+        assert!(to_chat_request(&json!({"prompt": ["a", "b"]})).is_err());
+        assert!(to_chat_request(&json!({"prompt": [1, 2, 3]})).is_err());
+
+        let reply = json!({
+            "id": "chatcmpl-1", "created": 1, "model": "mistral-small",
+            "choices": [{"index": 0, "message": {"role": "assistant", "content": " a time"}, "finish_reason": "length",
+                "logprobs": {"content": [
+                    {"token": " a", "logprob": -0.1, "top_logprobs": [{"token": " a", "logprob": -0.1}, {"token": " the", "logprob": -2.5}]},
+                    {"token": " time", "logprob": -0.01, "top_logprobs": []}
+                ]}}],
+            "usage": {"prompt_tokens": 2, "completion_tokens": 2, "total_tokens": 4}
+        });
+        let legacy = from_chat_response(&reply, &body);
+        assert_eq!(legacy["id"], "cmpl-1");
+        assert_eq!(legacy["object"], "text_completion");
+        let choice = &legacy["choices"][0];
+        assert_eq!(choice["text"], "Once upon a time");
+        assert_eq!(choice["logprobs"]["tokens"], json!(["Once upon", " a", " time"]));
+        assert_eq!(choice["logprobs"]["token_logprobs"], json!([null, -0.1, -0.01]));
+        assert_eq!(choice["logprobs"]["text_offset"], json!([0, 9, 11]));
+        assert_eq!(choice["logprobs"]["top_logprobs"][1], json!({" a": -0.1, " the": -2.5}));
+    }
+
+    #[test]
+    fn test_stream_chunks() {
+        let mut stream = CompletionsStream::new(&json!({"prompt": "Hi", "echo": true}));
+        assert!(stream.push(&json!({"id": "chatcmpl-2", "choices": [{"index": 0, "delta": {"role": "assistant"}}]})).is_none());
+        let first = stream.push(&json!({"id": "chatcmpl-2", "choices": [{"index": 0, "delta": {"content": " there"}}]})).unwrap();
+        assert_eq!(first["choices"][0]["text"], "Hi there");
+        assert_eq!(first["id"], "cmpl-2");
+        let second = stream.push(&json!({"choices": [{"index": 0, "delta": {"content": "!"}, "finish_reason": "stop"}]})).unwrap();
+        assert_eq!((second["choices"][0]["text"].clone(), second["choices"][0]["finish_reason"].clone()), (json!("!"), json!("stop")));
+        let usage = stream.push(&json!({"choices": [], "usage": {"total_tokens": 3}})).unwrap();
+        assert_eq!(usage["usage"]["total_tokens"], 3);
+    }
+}
@@ -0,0 +1,362 @@
+// Embeddings 协议转换
+// OpenAI `/v1/embeddings` ↔ Gemini batchEmbedContents / Voyage / Cohere v2 embed
+
+use base64::Engine as _;
+use serde_json::{json, Value};
+
+use crate::proxy::config::{EmbeddingProviderConfig, EmbeddingProviderKind};
+
+/// 解析后的 OpenAI Embeddings 请求
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmbeddingRequest {
+    pub model: String,
+    /// 每个元素为字符串或 token 数组 (仅 OpenAI 兼容上游支持 token 数组)
+    pub inputs: Vec<Value>,
+    pub dimensions: Option<u64>,
+    pub base64: bool,
+    /// 非标准字段, 透传给支持检索类型的上游 (`query` / `document`)
+    pub input_type: Option<String>,
+}
+
+pub fn parse_request(body: &Value) -> Result<EmbeddingRequest, String> {
+    let model = body
+        .get("model")
+        .and_then(|v| v.as_str())
+        .filter(|m| !m.is_empty())
+        .ok_or("Missing 'model' field")?
+        .to_string();
+
+    let inputs = match body.get("input") {
+        Some(Value::String(s)) => vec![Value::String(s.clone())],
+        // 单个 token 数组: [1, 2, 3]
+        Some(Value::Array(items)) if !items.is_empty() && items.iter().all(Value::is_number) => {
+            vec![Value::Array(items.clone())]
+        }
+        Some(Value::Array(items)) => {
+            if let Some(bad) = items.iter().find(|v| !v.is_string() && !v.is_array()) {
+                return Err(format!("Invalid item in 'input': {}", bad));
+            }
+            items.clone()
+        }
+        Some(other) => return Err(format!("Invalid 'input' type: {}", other)),
+        None => return Err("Missing 'input' field".to_string()),
+    };
+    if inputs.is_empty() {
+        return Err("'input' must not be empty".to_string());
+    }
+
+    let base64 = match body.get("encoding_format").and_then(|v| v.as_str()) {
+        None | Some("float") => false,
+        Some("base64") => true,
+        Some(other) => return Err(format!("Unsupported encoding_format: {}", other)),
+    };
+
+    Ok(EmbeddingRequest {
+        model,
+        inputs,
+        dimensions: body.get("dimensions").and_then(|v| v.as_u64()),
+        base64,
+        input_type: body.get("input_type").and_then(|v| v.as_str()).map(|s| s.to_string()),
+    })
+}
+
+/// 第一条启用且匹配模型名的上游
+pub fn find_provider<'a>(
+    providers: &'a [EmbeddingProviderConfig],
+    model: &str,
+) -> Option<&'a EmbeddingProviderConfig> {
+    providers
+        .iter()
+        .filter(|p| p.enabled)
+        .find(|p| crate::proxy::common::model_mapping::wildcard_match(&p.pattern, model))
+}
+
+pub fn default_base_url(kind: EmbeddingProviderKind) -> &'static str {
+    match kind {
+        EmbeddingProviderKind::Openai => "https://api.openai.com",
+        EmbeddingProviderKind::Gemini => "https://generativelanguage.googleapis.com",
+        EmbeddingProviderKind::Voyage => "https://api.voyageai.com",
+        EmbeddingProviderKind::Cohere => "https://api.cohere.com",
+    }
+}
+
+/// 上游单次请求允许的最大输入条数
+pub fn batch_limit(kind: EmbeddingProviderKind) -> usize {
+    match kind {
+        EmbeddingProviderKind::Openai => 2048,
+        EmbeddingProviderKind::Gemini => 100,
+        EmbeddingProviderKind::Voyage => 1000,
+        EmbeddingProviderKind::Cohere => 96,
+    }
+}
+
+/// 上游认证头 (name, value)
+pub fn auth_header(kind: EmbeddingProviderKind, api_key: &str) -> (&'static str, String) {
+    match kind {
+        EmbeddingProviderKind::Gemini => ("x-goog-api-key", api_key.to_string()),
+        _ => ("Authorization", format!("Bearer {}", api_key)),
+    }
+}
+
+fn texts(inputs: &[Value]) -> Result<Vec<&str>, String> {
+    inputs
+        .iter()
+        .map(|v| v.as_str().ok_or_else(|| "Token array inputs are only supported by OpenAI-compatible providers".to_string()))
+        .collect()
+}
+
+/// 构造一批输入的上游请求, 返回 (url, body)
+pub fn build_upstream_request(
+    kind: EmbeddingProviderKind,
+    base_url: &str,
+    model: &str,
+    inputs: &[Value],
+    request: &EmbeddingRequest,
+) -> Result<(String, Value), String> {
+    let base = base_url.trim_end_matches('/');
+    match kind {
+        EmbeddingProviderKind::Openai => {
+            // 始终向上游请求 float, base64 由本地编码
+            let mut body = json!({"model": model, "input": inputs, "encoding_format": "float"});
+            if let Some(dimensions) = request.dimensions {
+                body["dimensions"] = json!(dimensions);
+            }
+            Ok((format!("{}/v1/embeddings", base), body))
+        }
+        EmbeddingProviderKind::Gemini => {
+            let model = model.trim_start_matches("models/");
+            let task_type = request.input_type.as_deref().map(|t| match t {
+                "query" | "search_query" => "RETRIEVAL_QUERY".to_string(),
+                "document" | "search_document" => "RETRIEVAL_DOCUMENT".to_string(),
+                other => other.to_ascii_uppercase(),
+            });
+            let requests: Vec<Value> = texts(inputs)?
+                .into_iter()
+                .map(|text| {
+                    let mut item = json!({
+                        "model": format!("models/{}", model),
+                        "content": {"parts": [{"text": text}]},
+                    });
+                    if let Some(dimensions) = request.dimensions {
+                        item["outputDimensionality"] = json!(dimensions);
+                    }
+                    if let Some(task_type) = &task_type {
+                        item["taskType"] = json!(task_type);
+                    }
+                    item
+                })
+                .collect();
+            Ok((
+                format!("{}/v1beta/models/{}:batchEmbedContents", base, model),
+                json!({"requests": requests}),
+            ))
+        }
+        EmbeddingProviderKind::Voyage => {
+            let mut body = json!({"model": model, "input": texts(inputs)?});
+            if let Some(dimensions) = request.dimensions {
+                body["output_dimension"] = json!(dimensions);
+            }
+            if let Some(input_type) = &request.input_type {
+                body["input_type"] = json!(input_type.trim_start_matches("search_"));
+            }
+            Ok((format!("{}/v1/embeddings", base), body))
+        }
+        EmbeddingProviderKind::Cohere => {
+            // v3 及以上模型要求 input_type, 默认按文档处理
+            let input_type = match request.input_type.as_deref() {
+                None | Some("document") => "search_document",
+                Some("query") => "search_query",
+                Some(other) => other,
+            };
+            let mut body = json!({
+                "model": model,
+                "texts": texts(inputs)?,
+                "input_type": input_type,
+                "embedding_types": ["float"],
+            });
+            if let Some(dimensions) = request.dimensions {
+                body["output_dimension"] = json!(dimensions);
+            }
+            Ok((format!("{}/v2/embed", base), body))
+        }
+    }
+}
+
+fn to_vector(value: &Value) -> Option<Vec<f32>> {
+    value
+        .as_array()?
+        .iter()
+        .map(|v| v.as_f64().map(|f| f as f32))
+        .collect()
+}
+
+/// 解析上游响应, 返回 (按输入顺序的向量, 上游报告的 token 数)
+pub fn parse_upstream_response(
+    kind: EmbeddingProviderKind,
+    body: &Value,
+) -> Result<(Vec<Vec<f32>>, Option<u32>), String> {
+    let invalid = || format!("Unexpected embeddings response: {:.200}", body.to_string());
+    match kind {
+        EmbeddingProviderKind::Openai | EmbeddingProviderKind::Voyage => {
+            let mut items: Vec<(u64, Vec<f32>)> = body
+                .get("data")
+                .and_then(|d| d.as_array())
+                .ok_or_else(invalid)?
+                .iter()
+                .enumerate()
+                .map(|(i, item)| {
+                    let index = item.get("index").and_then(|v| v.as_u64()).unwrap_or(i as u64);
+                    item.get("embedding").and_then(to_vector).map(|v| (index, v))
+                })
+                .collect::<Option<_>>()
+                .ok_or_else(invalid)?;
+            items.sort_by_key(|(index, _)| *index);
+            let tokens = body
+                .pointer("/usage/prompt_tokens")
+                .or_else(|| body.pointer("/usage/total_tokens"))
+                .and_then(|v| v.as_u64())
+                .map(|v| v as u32);
+            Ok((items.into_iter().map(|(_, v)| v).collect(), tokens))
+        }
+        EmbeddingProviderKind::Gemini => {
+            let vectors = body
+                .get("embeddings")
+                .and_then(|e| e.as_array())
+                .ok_or_else(invalid)?
+                .iter()
+                .map(|e| e.get("values").and_then(to_vector))
+                .collect::<Option<_>>()
+                .ok_or_else(invalid)?;
+            Ok((vectors, None))
+        }
+        EmbeddingProviderKind::Cohere => {
+            let vectors = body
+                .pointer("/embeddings/float")
+                .and_then(|e| e.as_array())
+                .ok_or_else(invalid)?
+                .iter()
+                .map(to_vector)
+                .collect::<Option<_>>()
+                .ok_or_else(invalid)?;
+            let tokens = body
+                .pointer("/meta/billed_units/input_tokens")
+                .and_then(|v| v.as_u64())
+                .map(|v| v as u32);
+            Ok((vectors, tokens))
+        }
+    }
+}
+
+/// 上游未报告用量时按文本估算
+pub fn estimate_tokens(inputs: &[Value]) -> u32 {
+    inputs
+        .iter()
+        .map(|v| match v {
+            Value::String(s) => crate::proxy::mappers::context_manager::estimate_tokens_from_str(s),
+            Value::Array(tokens) => tokens.len() as u32,
+            _ => 0,
+        })
+        .sum()
+}
+
+/// 组装 OpenAI 格式的响应
+pub fn build_response(model: &str, vectors: Vec<Vec<f32>>, prompt_tokens: u32, base64: bool) -> Value {
+    let data: Vec<Value> = vectors
+        .into_iter()
+        .enumerate()
+        .map(|(index, vector)| {
+            let embedding = if base64 {
+                let bytes: Vec<u8> = vector.iter().flat_map(|f| f.to_le_bytes()).collect();
+                json!(base64::engine::general_purpose::STANDARD.encode(bytes))
+            } else {
+                json!(vector)
+            };
+            json!({"object": "embedding", "index": index, "embedding": embedding})
+        })
+        .collect();
+    json!({
+        "object": "list",
+        "data": data,
+        "model": model,
+        "usage": {"prompt_tokens": prompt_tokens, "total_tokens": prompt_tokens},
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_request_inputs() {
+        let req = parse_request(&json!({"model": "m", "input": "hello"})).unwrap();
+        assert_eq!(req.inputs, vec![json!("hello")]);
+        assert!(!req.base64);
+
+        let req = parse_request(&json!({"model": "m", "input": [1, 2, 3], "encoding_format": "base64"})).unwrap();
+        assert_eq!(req.inputs, vec![json!([1, 2, 3])]);
+        assert!(req.base64);
+
+        let req = parse_request(&json!({"model": "m", "input": ["a", "b"], "dimensions": 256})).unwrap();
+        assert_eq!(req.inputs.len(), 2);
+        assert_eq!(req.dimensions, Some(256));
+
+        assert!(parse_request(&json!({"model": "m", "input": []})).is_err());
+        assert!(parse_request(&json!({"input": "x"})).is_err());
+    }
+
+    #[test]
+    fn test_build_gemini_and_cohere_requests() {
+        let req = parse_request(&json!({"model": "m", "input": ["a", "b"], "dimensions": 768, "input_type": "query"})).unwrap();
+        let (url, body) = build_upstream_request(
+            EmbeddingProviderKind::Gemini,
+            "https://generativelanguage.googleapis.com/",
+            "text-embedding-004",
+            &req.inputs,
+            &req,
+        )
+        .unwrap();
+        assert_eq!(url, "https://generativelanguage.googleapis.com/v1beta/models/text-embedding-004:batchEmbedContents");
+        assert_eq!(body["requests"][1]["content"]["parts"][0]["text"], "b");
+        assert_eq!(body["requests"][0]["outputDimensionality"], 768);
+        assert_eq!(body["requests"][0]["taskType"], "RETRIEVAL_QUERY");
+
+        let (url, body) =
+            build_upstream_request(EmbeddingProviderKind::Cohere, "https://api.cohere.com", "embed-v4.0", &req.inputs, &req).unwrap();
+        assert_eq!(url, "https://api.cohere.com/v2/embed");
+        assert_eq!(body["texts"], json!(["a", "b"]));
+        assert_eq!(body["input_type"], "search_query");
+
+        // 非 OpenAI 上游不支持 token 数组
+        let tokens = parse_request(&json!({"model": "m", "input": [[1, 2]]})).unwrap();
+        assert!(build_upstream_request(EmbeddingProviderKind::Voyage, "", "voyage-3", &tokens.inputs, &tokens).is_err());
+    }
+
+    #[test]
+    fn test_parse_responses_and_build() {
+        let (vectors, tokens) = parse_upstream_response(
+            EmbeddingProviderKind::Voyage,
+            &json!({"data": [{"index": 1, "embedding": [0.5]}, {"index": 0, "embedding": [0.25]}], "usage": {"total_tokens": 7}}),
+        )
+        .unwrap();
+        assert_eq!(vectors, vec![vec![0.25], vec![0.5]]);
+        assert_eq!(tokens, Some(7));
+
+        let (vectors, tokens) = parse_upstream_response(
+            EmbeddingProviderKind::Cohere,
+            &json!({"embeddings": {"float": [[1.0, 2.0]]}, "meta": {"billed_units": {"input_tokens": 3}}}),
+        )
+        .unwrap();
+        assert_eq!(vectors, vec![vec![1.0, 2.0]]);
+        assert_eq!(tokens, Some(3));
+
+        let (vectors, tokens) =
+            parse_upstream_response(EmbeddingProviderKind::Gemini, &json!({"embeddings": [{"values": [0.5]}]})).unwrap();
+        assert_eq!(vectors, vec![vec![0.5]]);
+        assert_eq!(tokens, None);
+        assert!(parse_upstream_response(EmbeddingProviderKind::Gemini, &json!({"error": {}})).is_err());
+
+        let out = build_response("m", vec![vec![1.0]], 3, true);
+        assert_eq!(out["data"][0]["embedding"], "AACAPw==");
+        assert_eq!(out["usage"]["prompt_tokens"], 3);
+    }
+}
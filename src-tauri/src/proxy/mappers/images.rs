@@ -0,0 +1,200 @@
+// 图像生成协议转换
+// OpenAI `/v1/images/generations` ↔ DALL-E 兼容接口 / Stability AI v1 text-to-image
+
+use serde_json::{json, Value};
+
+use crate::proxy::config::{ImageBackendConfig, ImageBackendKind};
+
+/// 上游返回的单张图片, `b64_json` 与 `url` 至少有一个
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct GeneratedImage {
+    pub b64_json: Option<String>,
+    pub url: Option<String>,
+    pub mime_type: String,
+    pub revised_prompt: Option<String>,
+}
+
+/// 第一条启用且匹配模型名的上游
+pub fn find_backend<'a>(backends: &'a [ImageBackendConfig], model: &str) -> Option<&'a ImageBackendConfig> {
+    backends
+        .iter()
+        .filter(|b| b.enabled)
+        .find(|b| crate::proxy::common::model_mapping::wildcard_match(&b.pattern, model))
+}
+
+pub fn default_base_url(kind: ImageBackendKind) -> &'static str {
+    match kind {
+        ImageBackendKind::Openai => "https://api.openai.com",
+        ImageBackendKind::Stability => "https://api.stability.ai",
+    }
+}
+
+/// 解析 `1024x1024` 格式的尺寸
+fn parse_size(size: &str) -> Option<(u64, u64)> {
+    let (w, h) = size.split_once(['x', 'X'])?;
+    Some((w.trim().parse().ok()?, h.trim().parse().ok()?))
+}
+
+/// 构造上游请求, 返回 (url, body)
+pub fn build_backend_request(
+    kind: ImageBackendKind,
+    base_url: &str,
+    model: &str,
+    body: &Value,
+) -> Result<(String, Value), String> {
+    let base = base_url.trim_end_matches('/');
+    let prompt = body
+        .get("prompt")
+        .and_then(|v| v.as_str())
+        .ok_or("Missing 'prompt' field")?;
+    let n = body.get("n").and_then(|v| v.as_u64()).unwrap_or(1).max(1);
+
+    match kind {
+        ImageBackendKind::Openai => {
+            // 只转发客户端显式给出的可选参数, 避免 dall-e-2 拒绝 quality/style
+            let mut upstream = json!({"model": model, "prompt": prompt, "n": n});
+            for key in ["size", "quality", "style", "response_format", "user", "background", "output_format"] {
+                if let Some(v) = body.get(key) {
+                    upstream[key] = v.clone();
+                }
+            }
+            Ok((format!("{}/v1/images/generations", base), upstream))
+        }
+        ImageBackendKind::Stability => {
+            let size = body.get("size").and_then(|v| v.as_str()).unwrap_or("1024x1024");
+            let (width, height) = parse_size(size).ok_or_else(|| format!("Invalid size: {}", size))?;
+            let mut text_prompts = vec![json!({"text": prompt, "weight": 1.0})];
+            if let Some(negative) = body.get("negative_prompt").and_then(|v| v.as_str()) {
+                text_prompts.push(json!({"text": negative, "weight": -1.0}));
+            }
+            let mut upstream = json!({
+                "text_prompts": text_prompts,
+                "width": width,
+                "height": height,
+                "samples": n,
+            });
+            for key in ["cfg_scale", "steps", "seed", "style_preset"] {
+                if let Some(v) = body.get(key) {
+                    upstream[key] = v.clone();
+                }
+            }
+            Ok((format!("{}/v1/generation/{}/text-to-image", base, model), upstream))
+        }
+    }
+}
+
+pub fn parse_backend_response(kind: ImageBackendKind, body: &Value) -> Result<Vec<GeneratedImage>, String> {
+    let invalid = || format!("Unexpected image response: {:.200}", body.to_string());
+    let images: Vec<GeneratedImage> = match kind {
+        ImageBackendKind::Openai => {
+            let mime_type = match body.get("output_format").and_then(|v| v.as_str()) {
+                Some("jpeg") => "image/jpeg",
+                Some("webp") => "image/webp",
+                _ => "image/png",
+            };
+            body.get("data")
+                .and_then(|d| d.as_array())
+                .ok_or_else(invalid)?
+                .iter()
+                .map(|item| GeneratedImage {
+                    b64_json: item.get("b64_json").and_then(|v| v.as_str()).map(|s| s.to_string()),
+                    url: item.get("url").and_then(|v| v.as_str()).map(|s| s.to_string()),
+                    mime_type: mime_type.to_string(),
+                    revised_prompt: item.get("revised_prompt").and_then(|v| v.as_str()).map(|s| s.to_string()),
+                })
+                .collect()
+        }
+        ImageBackendKind::Stability => body
+            .get("artifacts")
+            .and_then(|a| a.as_array())
+            .ok_or_else(invalid)?
+            .iter()
+            // 被内容过滤的图片 finishReason 为 CONTENT_FILTERED
+            .filter(|a| a.get("finishReason").and_then(|v| v.as_str()).unwrap_or("SUCCESS") == "SUCCESS")
+            .map(|a| GeneratedImage {
+                b64_json: a.get("base64").and_then(|v| v.as_str()).map(|s| s.to_string()),
+                mime_type: "image/png".to_string(),
+                ..Default::default()
+            })
+            .collect(),
+    };
+    let images: Vec<GeneratedImage> = images
+        .into_iter()
+        .filter(|img| img.b64_json.is_some() || img.url.is_some())
+        .collect();
+    if images.is_empty() {
+        return Err("No images generated".to_string());
+    }
+    Ok(images)
+}
+
+/// 转换为 OpenAI 响应中的 data 项; 需要 b64 但只有 url 时由调用方预先下载
+pub fn to_openai_item(image: &GeneratedImage, response_format: &str) -> Value {
+    let mut item = match (response_format, &image.b64_json, &image.url) {
+        ("url", _, Some(url)) => json!({"url": url}),
+        ("url", Some(b64), None) => json!({"url": format!("data:{};base64,{}", image.mime_type, b64)}),
+        (_, Some(b64), _) => json!({"b64_json": b64}),
+        (_, None, url) => json!({"url": url}),
+    };
+    if let Some(revised) = &image.revised_prompt {
+        item["revised_prompt"] = json!(revised);
+    }
+    item
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_stability_request() {
+        let body = json!({"prompt": "a cat", "n": 2, "size": "1152x896", "negative_prompt": "blurry", "seed": 7});
+        let (url, upstream) =
+            build_backend_request(ImageBackendKind::Stability, "https://api.stability.ai/", "stable-diffusion-xl-1024-v1-0", &body)
+                .unwrap();
+        assert_eq!(url, "https://api.stability.ai/v1/generation/stable-diffusion-xl-1024-v1-0/text-to-image");
+        assert_eq!(upstream["width"], 1152);
+        assert_eq!(upstream["height"], 896);
+        assert_eq!(upstream["samples"], 2);
+        assert_eq!(upstream["text_prompts"][1]["weight"], -1.0);
+        assert_eq!(upstream["seed"], 7);
+
+        let bad = json!({"prompt": "a cat", "size": "large"});
+        assert!(build_backend_request(ImageBackendKind::Stability, "", "sdxl", &bad).is_err());
+    }
+
+    #[test]
+    fn test_build_openai_request_keeps_explicit_options() {
+        let body = json!({"prompt": "a cat", "response_format": "b64_json"});
+        let (url, upstream) = build_backend_request(ImageBackendKind::Openai, "https://api.openai.com", "dall-e-2", &body).unwrap();
+        assert_eq!(url, "https://api.openai.com/v1/images/generations");
+        assert_eq!(upstream["response_format"], "b64_json");
+        assert!(upstream.get("quality").is_none());
+    }
+
+    #[test]
+    fn test_parse_and_convert() {
+        let images = parse_backend_response(
+            ImageBackendKind::Stability,
+            &json!({"artifacts": [
+                {"base64": "AAAA", "finishReason": "SUCCESS"},
+                {"base64": "BBBB", "finishReason": "CONTENT_FILTERED"}
+            ]}),
+        )
+        .unwrap();
+        assert_eq!(images.len(), 1);
+        assert_eq!(to_openai_item(&images[0], "b64_json"), json!({"b64_json": "AAAA"}));
+        assert_eq!(to_openai_item(&images[0], "url"), json!({"url": "data:image/png;base64,AAAA"}));
+
+        let images = parse_backend_response(
+            ImageBackendKind::Openai,
+            &json!({"data": [{"url": "https://example.com/a.png", "revised_prompt": "a fluffy cat"}]}),
+        )
+        .unwrap();
+        assert_eq!(
+            to_openai_item(&images[0], "url"),
+            json!({"url": "https://example.com/a.png", "revised_prompt": "a fluffy cat"})
+        );
+        assert!(parse_backend_response(ImageBackendKind::Openai, &json!({"data": []})).is_err());
+    }
+}
@@ -1,12 +1,22 @@
 // Mappers 模块 - 协议转换器
 // 协议转换器模块
 
+pub mod anthropic;
+pub mod bedrock;
 pub mod claude;
 pub mod common_utils;
+pub mod completions;
 pub mod context_manager;
+pub mod embeddings;
 pub mod error_classifier;
 pub mod estimation_calibrator;
 pub mod gemini;
+pub mod images;
+pub mod ollama;
 pub mod openai;
+pub mod rerank;
+pub mod responses;
 pub mod signature_store;
+pub mod stream_bridge;
 pub mod tool_result_compressor;
+pub mod vision;
@@ -0,0 +1,632 @@
+// Ollama 协议转换
+// OpenAI `/v1/chat/completions` ↔ Ollama `/api/chat`, `/v1/completions` ↔ `/api/generate`;
+// 流式响应为 NDJSON (每行一个 JSON 对象), 在此转换为 OpenAI SSE。
+
+use bytes::Bytes;
+use futures::{Stream, StreamExt};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+
+use crate::proxy::common::model_mapping::wildcard_match;
+use crate::proxy::config::OllamaConfig;
+
+/// Ollama 接口类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OllamaEndpoint {
+    /// `/api/chat`
+    Chat,
+    /// `/api/generate`
+    Generate,
+}
+
+impl OllamaEndpoint {
+    pub fn path(self) -> &'static str {
+        match self {
+            OllamaEndpoint::Chat => "/api/chat",
+            OllamaEndpoint::Generate => "/api/generate",
+        }
+    }
+}
+
+/// 判断模型是否转发到 Ollama, 返回 Ollama 侧的模型名
+pub fn resolve_model(config: &OllamaConfig, model: &str) -> Option<String> {
+    if !config.enabled {
+        return None;
+    }
+    if !config.model_prefix.is_empty() {
+        if let Some(name) = model.strip_prefix(config.model_prefix.as_str()).filter(|m| !m.is_empty()) {
+            return Some(name.to_string());
+        }
+    }
+    config
+        .models
+        .iter()
+        .any(|p| wildcard_match(p, model))
+        .then(|| model.to_string())
+}
+
+/// OpenAI 采样参数 → Ollama `options`
+pub fn build_options(body: &Value, default_num_ctx: u32) -> Map<String, Value> {
+    let mut options = Map::new();
+    if default_num_ctx > 0 {
+        options.insert("num_ctx".to_string(), json!(default_num_ctx));
+    }
+
+    const DIRECT: [&str; 9] = [
+        "temperature",
+        "top_p",
+        "top_k",
+        "min_p",
+        "seed",
+        "frequency_penalty",
+        "presence_penalty",
+        "repeat_penalty",
+        "num_ctx",
+    ];
+    for key in DIRECT {
+        if let Some(v) = body.get(key).filter(|v| !v.is_null()) {
+            options.insert(key.to_string(), v.clone());
+        }
+    }
+    if let Some(v) = body
+        .get("max_completion_tokens")
+        .or_else(|| body.get("max_tokens"))
+        .filter(|v| !v.is_null())
+    {
+        options.insert("num_predict".to_string(), v.clone());
+    }
+    match body.get("stop") {
+        Some(Value::String(s)) => {
+            options.insert("stop".to_string(), json!([s]));
+        }
+        Some(Value::Array(items)) if !items.is_empty() => {
+            options.insert("stop".to_string(), Value::Array(items.clone()));
+        }
+        _ => {}
+    }
+
+    // 客户端直接传入的原生 options 优先级最高
+    if let Some(native) = body.get("options").and_then(|v| v.as_object()) {
+        for (k, v) in native {
+            options.insert(k.clone(), v.clone());
+        }
+    }
+    options
+}
+
+/// OpenAI 的 content (字符串或分段数组) 拆分为文本与 base64 图片
+fn split_content(content: Option<&Value>) -> (String, Vec<String>) {
+    match content {
+        Some(Value::String(s)) => (s.clone(), Vec::new()),
+        Some(Value::Array(parts)) => {
+            let mut text = Vec::new();
+            let mut images = Vec::new();
+            for part in parts {
+                match part.get("type").and_then(|t| t.as_str()) {
+                    Some("text") => {
+                        if let Some(t) = part.get("text").and_then(|t| t.as_str()) {
+                            text.push(t.to_string());
+                        }
+                    }
+                    Some("image_url") => {
+                        let url = part
+                            .pointer("/image_url/url")
+                            .or_else(|| part.get("image_url"))
+                            .and_then(|u| u.as_str())
+                            .unwrap_or_default();
+                        // Ollama 只接受 base64, 远程地址已由处理器内联或无法使用
+                        match url.strip_prefix("data:").and_then(|rest| rest.split_once(",")) {
+                            Some((_, data)) => images.push(data.to_string()),
+                            None => tracing::warn!("[Ollama] Skipping non-inline image: {}", url),
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            (text.join("\n"), images)
+        }
+        _ => (String::new(), Vec::new()),
+    }
+}
+
+fn convert_messages(messages: &[Value]) -> Vec<Value> {
+    // tool 消息只带 tool_call_id, Ollama 需要工具名
+    let mut call_names: HashMap<String, String> = HashMap::new();
+    let mut out = Vec::with_capacity(messages.len());
+
+    for msg in messages {
+        let role = msg.get("role").and_then(|r| r.as_str()).unwrap_or("user");
+        let role = if role == "developer" { "system" } else { role };
+        let (content, images) = split_content(msg.get("content"));
+        let mut converted = json!({ "role": role, "content": content });
+        if !images.is_empty() {
+            converted["images"] = json!(images);
+        }
+
+        if let Some(calls) = msg.get("tool_calls").and_then(|c| c.as_array()) {
+            let tool_calls: Vec<Value> = calls
+                .iter()
+                .filter_map(|call| {
+                    let function = call.get("function")?;
+                    let name = function.get("name")?.as_str()?.to_string();
+                    if let Some(id) = call.get("id").and_then(|i| i.as_str()) {
+                        call_names.insert(id.to_string(), name.clone());
+                    }
+                    let arguments = match function.get("arguments") {
+                        Some(Value::String(s)) => serde_json::from_str(s).unwrap_or_else(|_| json!({})),
+                        Some(v) => v.clone(),
+                        None => json!({}),
+                    };
+                    Some(json!({ "function": { "name": name, "arguments": arguments } }))
+                })
+                .collect();
+            if !tool_calls.is_empty() {
+                converted["tool_calls"] = json!(tool_calls);
+            }
+        }
+        if role == "tool" {
+            if let Some(name) = msg
+                .get("tool_call_id")
+                .and_then(|i| i.as_str())
+                .and_then(|id| call_names.get(id))
+            {
+                converted["tool_name"] = json!(name);
+            }
+        }
+        out.push(converted);
+    }
+    out
+}
+
+/// `response_format` → Ollama `format` (`json` 或 JSON Schema)
+fn convert_format(body: &Value) -> Option<Value> {
+    let format = body.get("response_format")?;
+    match format.get("type").and_then(|t| t.as_str()) {
+        Some("json_object") => Some(json!("json")),
+        Some("json_schema") => format.pointer("/json_schema/schema").cloned(),
+        _ => None,
+    }
+}
+
+fn apply_common(request: &mut Value, body: &Value, config: &OllamaConfig) {
+    let options = build_options(body, config.num_ctx);
+    if !options.is_empty() {
+        request["options"] = Value::Object(options);
+    }
+    if let Some(format) = convert_format(body) {
+        request["format"] = format;
+    }
+    if !config.keep_alive.is_empty() {
+        request["keep_alive"] = json!(config.keep_alive);
+    }
+    if let Some(think) = body.get("think").filter(|v| v.is_boolean()) {
+        request["think"] = think.clone();
+    }
+}
+
+pub fn build_chat_request(body: &Value, model: &str, config: &OllamaConfig) -> Value {
+    let messages = body.get("messages").and_then(|m| m.as_array()).cloned().unwrap_or_default();
+    let mut request = json!({
+        "model": model,
+        "messages": convert_messages(&messages),
+        "stream": is_stream(body),
+    });
+    // Ollama 使用与 OpenAI 相同的函数工具结构
+    if let Some(tools) = body.get("tools").and_then(|t| t.as_array()).filter(|t| !t.is_empty()) {
+        request["tools"] = Value::Array(tools.clone());
+    }
+    apply_common(&mut request, body, config);
+    request
+}
+
+pub fn build_generate_request(body: &Value, model: &str, config: &OllamaConfig) -> Value {
+    let prompt = match body.get("prompt") {
+        Some(Value::String(s)) => s.clone(),
+        // 批量 prompt 只取第一条
+        Some(Value::Array(items)) => items.first().and_then(|p| p.as_str()).unwrap_or_default().to_string(),
+        _ => String::new(),
+    };
+    let mut request = json!({
+        "model": model,
+        "prompt": prompt,
+        "stream": is_stream(body),
+    });
+    if let Some(suffix) = body.get("suffix").and_then(|s| s.as_str()) {
+        request["suffix"] = json!(suffix);
+    }
+    apply_common(&mut request, body, config);
+    request
+}
+
+pub fn is_stream(body: &Value) -> bool {
+    body.get("stream").and_then(|v| v.as_bool()).unwrap_or(false)
+}
+
+fn new_id(prefix: &str) -> String {
+    format!("{}{}", prefix, uuid::Uuid::new_v4().simple())
+}
+
+fn usage(resp: &Value) -> Value {
+    let prompt = resp.get("prompt_eval_count").and_then(|v| v.as_u64()).unwrap_or(0);
+    let completion = resp.get("eval_count").and_then(|v| v.as_u64()).unwrap_or(0);
+    json!({
+        "prompt_tokens": prompt,
+        "completion_tokens": completion,
+        "total_tokens": prompt + completion,
+    })
+}
+
+fn finish_reason(resp: &Value, has_tool_calls: bool) -> &'static str {
+    if has_tool_calls {
+        return "tool_calls";
+    }
+    match resp.get("done_reason").and_then(|r| r.as_str()) {
+        Some("length") => "length",
+        _ => "stop",
+    }
+}
+
+/// Ollama 工具调用 (arguments 为对象) → OpenAI 工具调用 (arguments 为 JSON 字符串)
+fn convert_tool_calls(message: &Value, with_index: bool) -> Vec<Value> {
+    message
+        .get("tool_calls")
+        .and_then(|c| c.as_array())
+        .map(|calls| {
+            calls
+                .iter()
+                .enumerate()
+                .filter_map(|(index, call)| {
+                    let function = call.get("function")?;
+                    let arguments = function.get("arguments").cloned().unwrap_or_else(|| json!({}));
+                    let mut converted = json!({
+                        "id": new_id("call_"),
+                        "type": "function",
+                        "function": {
+                            "name": function.get("name").and_then(|n| n.as_str()).unwrap_or_default(),
+                            "arguments": arguments.to_string(),
+                        }
+                    });
+                    if with_index {
+                        converted["index"] = json!(index);
+                    }
+                    Some(converted)
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+pub fn chat_response_to_openai(resp: &Value, model: &str) -> Value {
+    let message = resp.get("message").cloned().unwrap_or_else(|| json!({}));
+    let tool_calls = convert_tool_calls(&message, false);
+    let mut out_message = json!({
+        "role": "assistant",
+        "content": message.get("content").and_then(|c| c.as_str()).unwrap_or_default(),
+    });
+    if let Some(thinking) = message.get("thinking").and_then(|t| t.as_str()).filter(|t| !t.is_empty()) {
+        out_message["reasoning_content"] = json!(thinking);
+    }
+    if !tool_calls.is_empty() {
+        out_message["tool_calls"] = json!(tool_calls);
+    }
+    json!({
+        "id": new_id("chatcmpl-"),
+        "object": "chat.completion",
+        "created": chrono::Utc::now().timestamp(),
+        "model": model,
+        "choices": [{
+            "index": 0,
+            "message": out_message,
+            "finish_reason": finish_reason(resp, !tool_calls.is_empty()),
+        }],
+        "usage": usage(resp),
+    })
+}
+
+pub fn generate_response_to_openai(resp: &Value, model: &str) -> Value {
+    json!({
+        "id": new_id("cmpl-"),
+        "object": "text_completion",
+        "created": chrono::Utc::now().timestamp(),
+        "model": model,
+        "choices": [{
+            "index": 0,
+            "text": resp.get("response").and_then(|r| r.as_str()).unwrap_or_default(),
+            "logprobs": null,
+            "finish_reason": finish_reason(resp, false),
+        }],
+        "usage": usage(resp),
+    })
+}
+
+/// 上游错误 (`{"error": "..."}`) → OpenAI 错误格式
+pub fn error_to_openai(body: &str) -> Value {
+    let message = serde_json::from_str::<Value>(body)
+        .ok()
+        .and_then(|v| v.get("error").and_then(|e| e.as_str()).map(String::from))
+        .unwrap_or_else(|| body.to_string());
+    json!({ "error": { "message": message, "type": "upstream_error", "code": "ollama_error" } })
+}
+
+/// NDJSON → OpenAI SSE 的增量转换状态
+pub struct NdjsonToSse {
+    endpoint: OllamaEndpoint,
+    model: String,
+    id: String,
+    created: i64,
+    buffer: Vec<u8>,
+    sent_role: bool,
+    tool_calls: usize,
+    finished: bool,
+}
+
+impl NdjsonToSse {
+    pub fn new(endpoint: OllamaEndpoint, model: &str) -> Self {
+        let prefix = match endpoint {
+            OllamaEndpoint::Chat => "chatcmpl-",
+            OllamaEndpoint::Generate => "cmpl-",
+        };
+        Self {
+            endpoint,
+            model: model.to_string(),
+            id: new_id(prefix),
+            created: chrono::Utc::now().timestamp(),
+            buffer: Vec::new(),
+            sent_role: false,
+            tool_calls: 0,
+            finished: false,
+        }
+    }
+
+    fn event(&self, choice: Value, usage: Option<Value>) -> String {
+        let object = match self.endpoint {
+            OllamaEndpoint::Chat => "chat.completion.chunk",
+            OllamaEndpoint::Generate => "text_completion",
+        };
+        let mut chunk = json!({
+            "id": self.id,
+            "object": object,
+            "created": self.created,
+            "model": self.model,
+            "choices": [choice],
+        });
+        if let Some(usage) = usage {
+            chunk["usage"] = usage;
+        }
+        format!("data: {}\n\n", chunk)
+    }
+
+    fn convert_line(&mut self, line: &Value) -> String {
+        if let Some(error) = line.get("error") {
+            self.finished = true;
+            let message = error.as_str().map(String::from).unwrap_or_else(|| error.to_string());
+            return format!(
+                "data: {}\n\ndata: [DONE]\n\n",
+                json!({ "error": { "message": message, "type": "upstream_error", "code": "ollama_error" } })
+            );
+        }
+
+        let mut out = String::new();
+        match self.endpoint {
+            OllamaEndpoint::Chat => {
+                let message = line.get("message").cloned().unwrap_or_else(|| json!({}));
+                let mut delta = Map::new();
+                if let Some(content) = message.get("content").and_then(|c| c.as_str()).filter(|c| !c.is_empty()) {
+                    delta.insert("content".to_string(), json!(content));
+                }
+                if let Some(thinking) = message.get("thinking").and_then(|t| t.as_str()).filter(|t| !t.is_empty()) {
+                    delta.insert("reasoning_content".to_string(), json!(thinking));
+                }
+                let mut calls = convert_tool_calls(&message, true);
+                for call in calls.iter_mut() {
+                    let index = call["index"].as_u64().unwrap_or(0) as usize + self.tool_calls;
+                    call["index"] = json!(index);
+                }
+                if !calls.is_empty() {
+                    self.tool_calls += calls.len();
+                    delta.insert("tool_calls".to_string(), json!(calls));
+                }
+                if !delta.is_empty() {
+                    // 第一个增量携带 role
+                    if !self.sent_role {
+                        self.sent_role = true;
+                        delta.insert("role".to_string(), json!("assistant"));
+                    }
+                    out.push_str(&self.event(json!({ "index": 0, "delta": delta, "finish_reason": null }), None));
+                }
+            }
+            OllamaEndpoint::Generate => {
+                if let Some(text) = line.get("response").and_then(|r| r.as_str()).filter(|t| !t.is_empty()) {
+                    out.push_str(&self.event(
+                        json!({ "index": 0, "text": text, "logprobs": null, "finish_reason": null }),
+                        None,
+                    ));
+                }
+            }
+        }
+
+        if line_done(line) {
+            self.finished = true;
+            let reason = finish_reason(line, self.tool_calls > 0);
+            let choice = match self.endpoint {
+                OllamaEndpoint::Chat => json!({ "index": 0, "delta": {}, "finish_reason": reason }),
+                OllamaEndpoint::Generate => json!({ "index": 0, "text": "", "logprobs": null, "finish_reason": reason }),
+            };
+            out.push_str(&self.event(choice, Some(usage(line))));
+            out.push_str("data: [DONE]\n\n");
+        }
+        out
+    }
+
+    /// 输入一段上游数据, 返回可发送的 SSE 文本 (不完整的行留到下次)
+    pub fn push(&mut self, chunk: &[u8]) -> String {
+        self.buffer.extend_from_slice(chunk);
+        let mut out = String::new();
+        while let Some(pos) = self.buffer.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=pos).collect();
+            out.push_str(&self.convert_bytes(&line));
+        }
+        out
+    }
+
+    /// 上游结束: 处理残留数据, 未收到 `done` 时补发结束标记
+    pub fn finish(&mut self) -> String {
+        let rest = std::mem::take(&mut self.buffer);
+        let mut out = self.convert_bytes(&rest);
+        if !self.finished {
+            self.finished = true;
+            out.push_str("data: [DONE]\n\n");
+        }
+        out
+    }
+
+    fn convert_bytes(&mut self, line: &[u8]) -> String {
+        if self.finished {
+            return String::new();
+        }
+        let text = String::from_utf8_lossy(line);
+        let text = text.trim();
+        if text.is_empty() {
+            return String::new();
+        }
+        match serde_json::from_str::<Value>(text) {
+            Ok(value) => self.convert_line(&value),
+            Err(e) => {
+                tracing::warn!("[Ollama] Invalid NDJSON line ({}): {}", e, text);
+                String::new()
+            }
+        }
+    }
+}
+
+fn line_done(line: &Value) -> bool {
+    line.get("done").and_then(|d| d.as_bool()).unwrap_or(false)
+}
+
+/// 把 Ollama NDJSON 响应流转换为 OpenAI SSE 流
+pub fn ndjson_to_sse<S, E>(
+    upstream: S,
+    endpoint: OllamaEndpoint,
+    model: &str,
+) -> impl Stream<Item = Result<Bytes, std::io::Error>> + Send
+where
+    S: Stream<Item = Result<Bytes, E>> + Send + Unpin + 'static,
+    E: std::fmt::Display + Send + 'static,
+{
+    let mut converter = NdjsonToSse::new(endpoint, model);
+    async_stream::stream! {
+        let mut upstream = upstream;
+        while let Some(chunk) = upstream.next().await {
+            match chunk {
+                Ok(bytes) => {
+                    let out = converter.push(&bytes);
+                    if !out.is_empty() {
+                        yield Ok(Bytes::from(out));
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!("[Ollama] Upstream stream error: {}", e);
+                    break;
+                }
+            }
+        }
+        let out = converter.finish();
+        if !out.is_empty() {
+            yield Ok(Bytes::from(out));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> OllamaConfig {
+        OllamaConfig {
+            enabled: true,
+            models: vec!["qwen*".to_string()],
+            num_ctx: 8192,
+            ..OllamaConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_resolve_model() {
+        let cfg = config();
+        assert_eq!(resolve_model(&cfg, "ollama/llama3.1:8b").as_deref(), Some("llama3.1:8b"));
+        assert_eq!(resolve_model(&cfg, "qwen2.5-coder").as_deref(), Some("qwen2.5-coder"));
+        assert_eq!(resolve_model(&cfg, "gpt-4o"), None);
+        assert_eq!(resolve_model(&OllamaConfig::default(), "ollama/llama3.1"), None);
+    }
+
+    #[test]
+    fn test_build_chat_request_maps_options_and_messages() {
+        let body = json!({
+            "model": "ollama/llama3.1",
+            "stream": true,
+            "temperature": 0.2,
+            "max_tokens": 256,
+            "stop": "###",
+            "num_ctx": 32768,
+            "response_format": {"type": "json_object"},
+            "messages": [
+                {"role": "user", "content": [
+                    {"type": "text", "text": "describe"},
+                    {"type": "image_url", "image_url": {"url": "data:image/png;base64,AAAA"}}
+                ]},
+                {"role": "assistant", "content": null, "tool_calls": [
+                    {"id": "call_1", "type": "function", "function": {"name": "lookup", "arguments": "{\"q\":\"x\"}"}}
+                ]},
+                {"role": "tool", "tool_call_id": "call_1", "content": "result"}
+            ]
+        });
+        let req = build_chat_request(&body, "llama3.1", &config());
+
+        assert_eq!(req["model"], "llama3.1");
+        assert_eq!(req["stream"], true);
+        assert_eq!(req["format"], "json");
+        assert_eq!(req["options"]["temperature"], 0.2);
+        assert_eq!(req["options"]["num_predict"], 256);
+        assert_eq!(req["options"]["stop"], json!(["###"]));
+        // 请求中的 num_ctx 覆盖配置默认值
+        assert_eq!(req["options"]["num_ctx"], 32768);
+        assert_eq!(req["messages"][0]["images"], json!(["AAAA"]));
+        assert_eq!(req["messages"][1]["tool_calls"][0]["function"]["arguments"]["q"], "x");
+        assert_eq!(req["messages"][2]["tool_name"], "lookup");
+    }
+
+    #[test]
+    fn test_chat_ndjson_to_sse() {
+        let mut conv = NdjsonToSse::new(OllamaEndpoint::Chat, "ollama/llama3.1");
+        // 行被拆分在两个数据块中
+        let mut out = conv.push(b"{\"message\":{\"role\":\"assistant\",\"content\":\"Hel");
+        assert!(out.is_empty());
+        out.push_str(&conv.push(b"lo\"},\"done\":false}\n{\"message\":{\"role\":\"assistant\",\"content\":\"!\"},\"done\":false}\n"));
+        out.push_str(&conv.push(
+            b"{\"message\":{\"role\":\"assistant\",\"content\":\"\"},\"done\":true,\"done_reason\":\"stop\",\"prompt_eval_count\":5,\"eval_count\":2}\n",
+        ));
+        out.push_str(&conv.finish());
+
+        let events: Vec<&str> = out.split("\n\n").filter(|e| !e.is_empty()).collect();
+        assert_eq!(events.len(), 4);
+        let first: Value = serde_json::from_str(events[0].trim_start_matches("data: ")).unwrap();
+        assert_eq!(first["choices"][0]["delta"]["role"], "assistant");
+        assert_eq!(first["choices"][0]["delta"]["content"], "Hello");
+        let second: Value = serde_json::from_str(events[1].trim_start_matches("data: ")).unwrap();
+        assert!(second["choices"][0]["delta"].get("role").is_none());
+        let last: Value = serde_json::from_str(events[2].trim_start_matches("data: ")).unwrap();
+        assert_eq!(last["choices"][0]["finish_reason"], "stop");
+        assert_eq!(last["usage"]["total_tokens"], 7);
+        assert_eq!(events[3], "data: [DONE]");
+    }
+
+    #[test]
+    fn test_generate_response_to_openai() {
+        let resp = json!({"response": "4", "done": true, "done_reason": "length", "prompt_eval_count": 3, "eval_count": 1});
+        let out = generate_response_to_openai(&resp, "ollama/llama3.1");
+        assert_eq!(out["object"], "text_completion");
+        assert_eq!(out["choices"][0]["text"], "4");
+        assert_eq!(out["choices"][0]["finish_reason"], "length");
+        assert_eq!(out["usage"]["prompt_tokens"], 3);
+    }
+}
@@ -2,10 +2,11 @@
 // Used for auto-converting streaming responses to JSON for non-streaming requests
 
 use super::models::*;
+use crate::proxy::common::sse::{SseEvent, SseParser};
 use bytes::Bytes;
 use futures::StreamExt;
-use serde_json::{json, Value};
-use std::io;
+use serde_json::Value;
+use std::collections::BTreeMap;
 
 /// Collects an OpenAI SSE stream into a complete OpenAIResponse
 pub async fn collect_stream_to_json<S, E>(
@@ -24,98 +25,301 @@ where
         usage: None,
     };
 
-    let mut role: Option<String> = None;
-    let mut content_parts: Vec<String> = Vec::new();
-    let mut reasoning_parts: Vec<String> = Vec::new();
-    let mut finish_reason: Option<String> = None;
-    let mut tool_calls: Vec<Value> = Vec::new(); // Store as Value to be flexible with partials
+    // Per-choice accumulators keyed by choice index (n > 1 yields several)
+    let mut choices: BTreeMap<u32, ChoiceAccumulator> = BTreeMap::new();
 
+    let mut parser = SseParser::new();
     while let Some(chunk_result) = stream.next().await {
         let chunk = chunk_result.map_err(|e| format!("Stream error: {}", e))?;
-        let text = String::from_utf8_lossy(&chunk);
-
-        for line in text.lines() {
-            let line = line.trim();
-            if line.starts_with("data: ") {
-                let data_str = line.trim_start_matches("data: ").trim();
-                if data_str == "[DONE]" {
-                    continue;
-                }
+        for event in parser.push(&chunk) {
+            apply_event(&event, &mut response, &mut choices);
+        }
+    }
+    for event in parser.finish() {
+        apply_event(&event, &mut response, &mut choices);
+    }
+
+    // Keep the previous contract: always return at least one (possibly empty) choice
+    if choices.is_empty() {
+        choices.insert(0, ChoiceAccumulator::default());
+    }
+
+    response.choices = choices
+        .into_iter()
+        .map(|(index, acc)| acc.finish(index))
+        .collect();
+
+    Ok(response)
+}
+
+/// Applies one SSE event (an OpenAI chunk) to the response being collected
+fn apply_event(event: &SseEvent, response: &mut OpenAIResponse, choices: &mut BTreeMap<u32, ChoiceAccumulator>) {
+    if event.is_done() {
+        return;
+    }
+    let Ok(json) = serde_json::from_str::<Value>(&event.data) else {
+        return;
+    };
+
+    // Update meta fields
+    if let Some(id) = json.get("id").and_then(|v| v.as_str()) {
+        response.id = id.to_string();
+    }
+    if let Some(model) = json.get("model").and_then(|v| v.as_str()) {
+        response.model = model.to_string();
+    }
+    if let Some(created) = json.get("created").and_then(|v| v.as_u64()) {
+        response.created = created;
+    }
+
+    // Collect Usage
+    if let Some(usage) = json.get("usage") {
+        if let Ok(u) = serde_json::from_value::<OpenAIUsage>(usage.clone()) {
+            response.usage = Some(u);
+        }
+    }
+
+    // Collect Choices Delta
+    if let Some(chunk_choices) = json.get("choices").and_then(|v| v.as_array()) {
+        for choice in chunk_choices {
+            let index = choice.get("index").and_then(|v| v.as_u64()).unwrap_or(0) as u32;
+            choices.entry(index).or_default().push_chunk(choice);
+        }
+    }
+}
 
-                if let Ok(json) = serde_json::from_str::<Value>(data_str) {
-                    // Update meta fields
-                    if let Some(id) = json.get("id").and_then(|v| v.as_str()) {
-                        response.id = id.to_string();
-                    }
-                    if let Some(model) = json.get("model").and_then(|v| v.as_str()) {
-                        response.model = model.to_string();
-                    }
-                    if let Some(created) = json.get("created").and_then(|v| v.as_u64()) {
-                        response.created = created;
-                    }
-
-                    // Collect Usage
-                    if let Some(usage) = json.get("usage") {
-                        if let Ok(u) = serde_json::from_value::<OpenAIUsage>(usage.clone()) {
-                            response.usage = Some(u);
-                        }
-                    }
-
-                    // Collect Choices Delta
-                    if let Some(choices) = json.get("choices").and_then(|v| v.as_array()) {
-                        if let Some(choice) = choices.first() {
-                            if let Some(delta) = choice.get("delta") {
-                                // Role
-                                if let Some(r) = delta.get("role").and_then(|v| v.as_str()) {
-                                    role = Some(r.to_string());
-                                }
-                                
-                                // Content
-                                if let Some(c) = delta.get("content").and_then(|v| v.as_str()) {
-                                    content_parts.push(c.to_string());
-                                }
-
-                                // Reasoning Content
-                                if let Some(rc) = delta.get("reasoning_content").and_then(|v| v.as_str()) {
-                                    reasoning_parts.push(rc.to_string());
-                                }
-
-                                // Tool Calls Logic would go here (simplified for now as usually not mixed with non-stream heavy)
-                                // But proper implementation needs to aggregate tool calls by index.
-                            }
-
-                            if let Some(fr) = choice.get("finish_reason").and_then(|v| v.as_str()) {
-                                finish_reason = Some(fr.to_string());
-                            }
-                        }
-                    }
+/// Accumulated state of a single streamed choice
+#[derive(Debug, Default)]
+struct ChoiceAccumulator {
+    role: Option<String>,
+    content: String,
+    reasoning: String,
+    finish_reason: Option<String>,
+    tool_calls: ToolCallAccumulator,
+}
+
+impl ChoiceAccumulator {
+    fn push_chunk(&mut self, choice: &Value) {
+        if let Some(delta) = choice.get("delta") {
+            // Role
+            if let Some(r) = delta.get("role").and_then(|v| v.as_str()) {
+                self.role = Some(r.to_string());
+            }
+
+            // Content
+            if let Some(c) = delta.get("content").and_then(|v| v.as_str()) {
+                self.content.push_str(c);
+            }
+
+            // Reasoning Content
+            if let Some(rc) = delta.get("reasoning_content").and_then(|v| v.as_str()) {
+                self.reasoning.push_str(rc);
+            }
+
+            // Tool Calls (aggregated by index)
+            if let Some(deltas) = delta.get("tool_calls").and_then(|v| v.as_array()) {
+                for tc in deltas {
+                    self.tool_calls.push_delta(tc);
                 }
             }
         }
+
+        if let Some(fr) = choice.get("finish_reason").and_then(|v| v.as_str()) {
+            self.finish_reason = Some(fr.to_string());
+        }
     }
 
-    // Construct final message
-    let full_content = content_parts.join("");
-    let full_reasoning = if reasoning_parts.is_empty() {
-        None
-    } else {
-        Some(reasoning_parts.join(""))
-    };
+    fn finish(self, index: u32) -> Choice {
+        let message = OpenAIMessage {
+            role: self.role.unwrap_or("assistant".to_string()),
+            content: Some(OpenAIContent::String(self.content)),
+            reasoning_content: if self.reasoning.is_empty() {
+                None
+            } else {
+                Some(self.reasoning)
+            },
+            tool_calls: self.tool_calls.finish(),
+            tool_call_id: None,
+            name: None,
+        };
 
-    let message = OpenAIMessage {
-        role: role.unwrap_or("assistant".to_string()),
-        content: Some(OpenAIContent::String(full_content)),
-        reasoning_content: full_reasoning,
-        tool_calls: None, // TODO: Implement tool call aggregation if needed
-        tool_call_id: None,
-        name: None,
-    };
+        Choice {
+            index,
+            message,
+            finish_reason: self.finish_reason.or(Some("stop".to_string())),
+        }
+    }
+}
 
-    response.choices.push(Choice {
-        index: 0,
-        message,
-        finish_reason: finish_reason.or(Some("stop".to_string())),
-    });
+/// Partially received tool call, keyed by its delta `index`
+#[derive(Debug, Default)]
+struct PartialToolCall {
+    index: u64,
+    id: Option<String>,
+    name: String,
+    arguments: String,
+}
 
-    Ok(response)
+/// Merges streamed `tool_calls` deltas into complete tool calls.
+///
+/// OpenAI sends `id` and `function.name` on the first delta of a call and only
+/// `index` plus argument fragments afterwards. Our own Gemini→OpenAI streamer
+/// emits every call as a single delta at index 0, so a new `id` arriving at an
+/// occupied index starts a new call instead of overwriting the previous one.
+#[derive(Debug, Default)]
+struct ToolCallAccumulator {
+    calls: Vec<PartialToolCall>,
+}
+
+impl ToolCallAccumulator {
+    fn push_delta(&mut self, delta: &Value) {
+        let index = delta.get("index").and_then(|v| v.as_u64()).unwrap_or(0);
+        let id = delta.get("id").and_then(|v| v.as_str()).filter(|s| !s.is_empty());
+        let function = delta.get("function");
+        let name = function.and_then(|f| f.get("name")).and_then(|v| v.as_str());
+        let arguments = function.and_then(|f| f.get("arguments")).and_then(|v| v.as_str());
+
+        // Latest call at this index, unless the delta clearly starts a different call
+        let pos = self.calls.iter().rposition(|c| c.index == index).filter(|&pos| {
+            match (id, self.calls[pos].id.as_deref()) {
+                (Some(new_id), Some(cur_id)) => new_id == cur_id,
+                _ => true,
+            }
+        });
+        let call = match pos {
+            Some(pos) => &mut self.calls[pos],
+            None => {
+                self.calls.push(PartialToolCall { index, ..Default::default() });
+                self.calls.last_mut().unwrap()
+            }
+        };
+
+        if let Some(id) = id {
+            call.id = Some(id.to_string());
+        }
+        if let Some(name) = name {
+            call.name.push_str(name);
+        }
+        if let Some(arguments) = arguments {
+            call.arguments.push_str(arguments);
+        }
+    }
+
+    fn finish(self) -> Option<Vec<ToolCall>> {
+        if self.calls.is_empty() {
+            return None;
+        }
+        Some(
+            self.calls
+                .into_iter()
+                .map(|c| ToolCall {
+                    id: c.id.unwrap_or_else(|| format!("call_{}", c.index)),
+                    r#type: "function".to_string(),
+                    function: ToolFunction {
+                        name: c.name,
+                        arguments: c.arguments,
+                    },
+                })
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::stream;
+
+    fn sse_stream(chunks: Vec<&'static str>) -> impl futures::Stream<Item = Result<Bytes, String>> + Unpin {
+        stream::iter(chunks.into_iter().map(|s| Ok::<Bytes, String>(Bytes::from(s))))
+    }
+
+    #[tokio::test]
+    async fn test_collect_merges_tool_call_fragments() {
+        let chunks = vec![
+            "data: {\"id\":\"chatcmpl-1\",\"model\":\"gpt-4o\",\"choices\":[{\"index\":0,\"delta\":{\"role\":\"assistant\",\"tool_calls\":[{\"index\":0,\"id\":\"call_abc\",\"type\":\"function\",\"function\":{\"name\":\"get_weather\",\"arguments\":\"\"}}]}}]}\n\n",
+            "data: {\"choices\":[{\"index\":0,\"delta\":{\"tool_calls\":[{\"index\":0,\"function\":{\"arguments\":\"{\\\"city\\\":\"}}]}}]}\n\n",
+            "data: {\"choices\":[{\"index\":0,\"delta\":{\"tool_calls\":[{\"index\":0,\"function\":{\"arguments\":\"\\\"Paris\\\"}\"}}]}}]}\n\n",
+            "data: {\"choices\":[{\"index\":0,\"delta\":{\"tool_calls\":[{\"index\":1,\"id\":\"call_def\",\"function\":{\"name\":\"get_time\",\"arguments\":\"{}\"}}]}}]}\n\n",
+            "data: {\"choices\":[{\"index\":0,\"delta\":{},\"finish_reason\":\"tool_calls\"}]}\n\n",
+            "data: [DONE]\n\n",
+        ];
+
+        let response = collect_stream_to_json(sse_stream(chunks)).await.unwrap();
+        let choice = &response.choices[0];
+        assert_eq!(choice.finish_reason.as_deref(), Some("tool_calls"));
+
+        let calls = choice.message.tool_calls.as_ref().expect("tool_calls should be collected");
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].id, "call_abc");
+        assert_eq!(calls[0].function.name, "get_weather");
+        assert_eq!(calls[0].function.arguments, "{\"city\":\"Paris\"}");
+        assert_eq!(calls[1].id, "call_def");
+        assert_eq!(calls[1].function.name, "get_time");
+    }
+
+    #[tokio::test]
+    async fn test_collect_distinct_ids_at_same_index() {
+        // Gemini→OpenAI streaming emits each complete call at index 0
+        let chunks = vec![
+            "data: {\"choices\":[{\"index\":0,\"delta\":{\"tool_calls\":[{\"index\":0,\"id\":\"call_1\",\"type\":\"function\",\"function\":{\"name\":\"a\",\"arguments\":\"{}\"}}]}}]}\n\n",
+            "data: {\"choices\":[{\"index\":0,\"delta\":{\"tool_calls\":[{\"index\":0,\"id\":\"call_2\",\"type\":\"function\",\"function\":{\"name\":\"b\",\"arguments\":\"{\\\"x\\\":1}\"}}]}}]}\n\n",
+        ];
+
+        let response = collect_stream_to_json(sse_stream(chunks)).await.unwrap();
+        let calls = response.choices[0].message.tool_calls.as_ref().unwrap();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].function.name, "a");
+        assert_eq!(calls[1].function.name, "b");
+        assert_eq!(calls[1].function.arguments, "{\"x\":1}");
+    }
+
+    #[tokio::test]
+    async fn test_collect_without_tool_calls() {
+        let chunks = vec![
+            "data: {\"choices\":[{\"index\":0,\"delta\":{\"content\":\"Hello\"}}]}\n\n",
+            "data: {\"choices\":[{\"index\":0,\"delta\":{\"content\":\" World\"},\"finish_reason\":\"stop\"}]}\n\n",
+        ];
+
+        let response = collect_stream_to_json(sse_stream(chunks)).await.unwrap();
+        let message = &response.choices[0].message;
+        assert!(message.tool_calls.is_none());
+        assert_eq!(message.content, Some(OpenAIContent::String("Hello World".to_string())));
+    }
+
+    #[tokio::test]
+    async fn test_collect_multiple_choices() {
+        let chunks = vec![
+            "data: {\"choices\":[{\"index\":0,\"delta\":{\"role\":\"assistant\",\"content\":\"A\"}},{\"index\":1,\"delta\":{\"role\":\"assistant\",\"content\":\"B\"}}]}\n\n",
+            "data: {\"choices\":[{\"index\":1,\"delta\":{\"reasoning_content\":\"hmm\",\"content\":\"b\"},\"finish_reason\":\"length\"}]}\n\n",
+            "data: {\"choices\":[{\"index\":0,\"delta\":{\"content\":\"a\"},\"finish_reason\":\"stop\"}]}\n\n",
+        ];
+
+        let response = collect_stream_to_json(sse_stream(chunks)).await.unwrap();
+        assert_eq!(response.choices.len(), 2);
+
+        let first = &response.choices[0];
+        assert_eq!(first.index, 0);
+        assert_eq!(first.message.content, Some(OpenAIContent::String("Aa".to_string())));
+        assert!(first.message.reasoning_content.is_none());
+        assert_eq!(first.finish_reason.as_deref(), Some("stop"));
+
+        let second = &response.choices[1];
+        assert_eq!(second.index, 1);
+        assert_eq!(second.message.content, Some(OpenAIContent::String("Bb".to_string())));
+        assert_eq!(second.message.reasoning_content.as_deref(), Some("hmm"));
+        assert_eq!(second.finish_reason.as_deref(), Some("length"));
+    }
+
+    #[tokio::test]
+    async fn test_collect_event_split_across_chunks() {
+        let chunks = vec![
+            "data: {\"choices\":[{\"index\":0,\"delta\":{\"content\":\"Hel",
+            "lo\"}}]}\n",
+            "\ndata: {\"choices\":[{\"index\":0,\"delta\":{\"content\":\"!\"}}]}",
+        ];
+
+        let response = collect_stream_to_json(sse_stream(chunks)).await.unwrap();
+        assert_eq!(response.choices[0].message.content, Some(OpenAIContent::String("Hello!".to_string())));
+    }
 }
@@ -0,0 +1,193 @@
+// Rerank 协议转换
+// `/v1/rerank` (Cohere / Jina 风格) ↔ Cohere v2 rerank / Jina / Voyage; 没有原生上游时按向量余弦相似度排序
+
+use serde_json::{json, Value};
+
+use crate::proxy::config::{RerankProviderConfig, RerankProviderKind};
+
+/// 解析后的 Rerank 请求
+#[derive(Debug, Clone, PartialEq)]
+pub struct RerankRequest {
+    pub model: String,
+    pub query: String,
+    pub documents: Vec<String>,
+    pub top_n: Option<usize>,
+    pub return_documents: bool,
+}
+
+pub fn parse_request(body: &Value) -> Result<RerankRequest, String> {
+    let model = body
+        .get("model")
+        .and_then(|v| v.as_str())
+        .filter(|m| !m.is_empty())
+        .ok_or("Missing 'model' field")?
+        .to_string();
+    let query = body.get("query").and_then(|v| v.as_str()).ok_or("Missing 'query' field")?.to_string();
+    // 文档为字符串或 `{"text": ...}` 对象
+    let documents = body
+        .get("documents")
+        .and_then(|v| v.as_array())
+        .ok_or("Missing 'documents' field")?
+        .iter()
+        .map(|d| {
+            d.as_str()
+                .or_else(|| d.get("text").and_then(|t| t.as_str()))
+                .map(String::from)
+                .ok_or_else(|| format!("Invalid item in 'documents': {:.100}", d.to_string()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if documents.is_empty() {
+        return Err("'documents' must not be empty".to_string());
+    }
+    Ok(RerankRequest {
+        model,
+        query,
+        documents,
+        top_n: body.get("top_n").or(body.get("top_k")).and_then(|v| v.as_u64()).map(|n| n as usize),
+        return_documents: body.get("return_documents").and_then(|v| v.as_bool()).unwrap_or(false),
+    })
+}
+
+/// 第一条启用且匹配模型名的上游
+pub fn find_provider<'a>(providers: &'a [RerankProviderConfig], model: &str) -> Option<&'a RerankProviderConfig> {
+    providers
+        .iter()
+        .filter(|p| p.enabled)
+        .find(|p| crate::proxy::common::model_mapping::wildcard_match(&p.pattern, model))
+}
+
+pub fn default_base_url(kind: RerankProviderKind) -> &'static str {
+    match kind {
+        RerankProviderKind::Cohere => "https://api.cohere.com",
+        RerankProviderKind::Jina => "https://api.jina.ai",
+        RerankProviderKind::Voyage => "https://api.voyageai.com",
+    }
+}
+
+/// 构造上游请求, 返回 (url, body)
+pub fn build_upstream_request(kind: RerankProviderKind, base_url: &str, model: &str, request: &RerankRequest) -> (String, Value) {
+    let base = base_url.trim_end_matches('/');
+    let mut body = json!({ "model": model, "query": request.query, "documents": request.documents });
+    // 文档内容由本地按索引回填, 不需要上游返回
+    match kind {
+        RerankProviderKind::Cohere => {
+            if let Some(top_n) = request.top_n {
+                body["top_n"] = json!(top_n);
+            }
+            (format!("{}/v2/rerank", base), body)
+        }
+        RerankProviderKind::Jina => {
+            if let Some(top_n) = request.top_n {
+                body["top_n"] = json!(top_n);
+            }
+            body["return_documents"] = json!(false);
+            (format!("{}/v1/rerank", base), body)
+        }
+        RerankProviderKind::Voyage => {
+            if let Some(top_n) = request.top_n {
+                body["top_k"] = json!(top_n);
+            }
+            (format!("{}/v1/rerank", base), body)
+        }
+    }
+}
+
+/// (文档索引, 分数)
+pub type Scores = Vec<(usize, f64)>;
+
+/// 解析上游响应, 返回 (分数, 上游报告的 token 数)
+pub fn parse_upstream_response(kind: RerankProviderKind, body: &Value) -> Result<(Scores, Option<u32>), String> {
+    let invalid = || format!("Unexpected rerank response: {:.200}", body.to_string());
+    let field = match kind {
+        RerankProviderKind::Cohere | RerankProviderKind::Jina => "results",
+        RerankProviderKind::Voyage => "data",
+    };
+    let scores = body
+        .get(field)
+        .and_then(|r| r.as_array())
+        .ok_or_else(invalid)?
+        .iter()
+        .map(|r| Some((r.get("index")?.as_u64()? as usize, r.get("relevance_score")?.as_f64()?)))
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(invalid)?;
+    let tokens = body.pointer("/usage/total_tokens").and_then(|v| v.as_u64()).map(|v| v as u32);
+    Ok((scores, tokens))
+}
+
+fn cosine(a: &[f32], b: &[f32]) -> f64 {
+    let (mut dot, mut norm_a, mut norm_b) = (0f64, 0f64, 0f64);
+    for (x, y) in a.iter().zip(b) {
+        let (x, y) = (*x as f64, *y as f64);
+        dot += x * y;
+        norm_a += x * x;
+        norm_b += y * y;
+    }
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a.sqrt() * norm_b.sqrt())
+}
+
+/// 按与 query 向量的余弦相似度为文档打分
+pub fn score_by_similarity(query: &[f32], documents: &[Vec<f32>]) -> Scores {
+    documents.iter().enumerate().map(|(index, doc)| (index, cosine(query, doc))).collect()
+}
+
+/// 组装响应: 按分数降序, 截取 `top_n`
+pub fn build_response(model: &str, request: &RerankRequest, mut scores: Scores, tokens: u32) -> Value {
+    scores.retain(|(index, _)| *index < request.documents.len());
+    scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+    scores.truncate(request.top_n.unwrap_or(usize::MAX));
+    let results: Vec<Value> = scores
+        .into_iter()
+        .map(|(index, score)| {
+            let mut result = json!({ "index": index, "relevance_score": score });
+            if request.return_documents {
+                result["document"] = json!({ "text": request.documents[index] });
+            }
+            result
+        })
+        .collect();
+    json!({
+        "id": uuid::Uuid::new_v4().to_string(),
+        "model": model,
+        "results": results,
+        "usage": { "prompt_tokens": tokens, "total_tokens": tokens },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_upstream_round_trip() {
+        let body = json!({"model": "rerank-v3.5", "query": "capital of France", "documents": ["Berlin", {"text": "Paris"}, "Rome"], "top_n": 2, "return_documents": true});
+        let request = parse_request(&body).unwrap();
+        assert_eq!(request.documents, vec!["Berlin", "Paris", "Rome"]);
+
+        let (url, payload) = build_upstream_request(RerankProviderKind::Voyage, "https://api.voyageai.com/", "rerank-2", &request);
+        assert_eq!(url, "https://api.voyageai.com/v1/rerank");
+        assert_eq!(payload["top_k"], 2);
+        let (url, _) = build_upstream_request(RerankProviderKind::Cohere, default_base_url(RerankProviderKind::Cohere), "rerank-v3.5", &request);
+        assert_eq!(url, "https://api.cohere.com/v2/rerank");
+
+        let upstream = json!({"data": [{"index": 1, "relevance_score": 0.9}, {"index": 0, "relevance_score": 0.1}], "usage": {"total_tokens": 12}});
+        let (scores, tokens) = parse_upstream_response(RerankProviderKind::Voyage, &upstream).unwrap();
+        assert_eq!(tokens, Some(12));
+        let response = build_response("rerank-v3.5", &request, scores, 12);
+        assert_eq!(response["results"][0], json!({"index": 1, "relevance_score": 0.9, "document": {"text": "Paris"}}));
+        assert_eq!(response["results"].as_array().unwrap().len(), 2);
+        assert!(parse_request(&json!({"model": "m", "query": "q", "documents": []})).is_err());
+    }
+
+    #[test]
+    fn test_similarity_ranking() {
+        let request = parse_request(&json!({"model": "m", "query": "q", "documents": ["a", "b", "c"]})).unwrap();
+        let scores = score_by_similarity(&[1.0, 0.0], &[vec![0.0, 1.0], vec![1.0, 0.1], vec![0.0, 0.0]]);
+        let response = build_response("m", &request, scores, 3);
+        let order: Vec<u64> = response["results"].as_array().unwrap().iter().map(|r| r["index"].as_u64().unwrap()).collect();
+        assert_eq!(order, vec![1, 0, 2]);
+        assert!(response["results"][0].get("document").is_none());
+    }
+}
@@ -0,0 +1,619 @@
+// Responses API ↔ Chat Completions
+// 只支持 Chat Completions 的上游 (Ollama、Azure、Bedrock、OpenAI 兼容上游) 处理 `/v1/responses` 请求:
+// 请求转换为 Chat 格式, 响应 (JSON 或 SSE) 转换回 Responses 格式。流式响应按 Responses 事件输出
+// (`response.created`、`response.output_item.added`、`response.output_text.delta`、
+// `response.function_call_arguments.delta`、`response.completed` 等), 每个事件带 `sequence_number`。
+
+use std::pin::Pin;
+
+use bytes::{Bytes, BytesMut};
+use futures::{Stream, StreamExt};
+use serde_json::{json, Map, Value};
+
+fn new_id(prefix: &str) -> String {
+    format!("{}_{}", prefix, uuid::Uuid::new_v4().simple())
+}
+
+/// 内容块文本 (字符串或 `input_text` / `output_text` 块数组)
+fn text_of(content: &Value) -> String {
+    match content {
+        Value::String(s) => s.clone(),
+        Value::Array(parts) => parts.iter().filter_map(|p| p.get("text").and_then(Value::as_str)).collect::<Vec<_>>().join("\n"),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+/// Responses 消息内容转为 Chat 消息内容
+fn chat_content(content: &Value) -> Value {
+    let Some(parts) = content.as_array() else {
+        return Value::String(text_of(content));
+    };
+    let converted: Vec<Value> = parts
+        .iter()
+        .filter_map(|part| match part.get("type").and_then(Value::as_str)? {
+            "input_text" | "output_text" | "text" => Some(json!({ "type": "text", "text": part.get("text")? })),
+            "input_image" => {
+                let url = part.get("image_url").and_then(|u| u.as_str().or_else(|| u.get("url")?.as_str()))?;
+                let detail = part.get("detail").cloned().unwrap_or(json!("auto"));
+                Some(json!({ "type": "image_url", "image_url": { "url": url, "detail": detail } }))
+            }
+            "input_file" => {
+                let file = json!({ "filename": part.get("filename"), "file_data": part.get("file_data")? });
+                Some(json!({ "type": "file", "file": file }))
+            }
+            _ => None,
+        })
+        .collect();
+    if converted.iter().all(|p| p["type"] == "text") {
+        Value::String(text_of(&Value::Array(converted)))
+    } else {
+        Value::Array(converted)
+    }
+}
+
+/// `input` 转为 Chat 消息; 连续的 function_call 合并为一条带多个 tool_calls 的助手消息
+fn chat_messages(input: &Value) -> Vec<Value> {
+    let items = match input {
+        Value::Array(items) => items.clone(),
+        Value::Null => Vec::new(),
+        other => return vec![json!({ "role": "user", "content": text_of(other) })],
+    };
+    let mut messages: Vec<Value> = Vec::new();
+    for item in items {
+        let kind = item.get("type").and_then(Value::as_str).unwrap_or("message");
+        match kind {
+            "message" => {
+                let role = match item.get("role").and_then(Value::as_str).unwrap_or("user") {
+                    "developer" => "system",
+                    role => role,
+                };
+                let content = item.get("content").cloned().unwrap_or(Value::Null);
+                let content = if role == "user" { chat_content(&content) } else { Value::String(text_of(&content)) };
+                messages.push(json!({ "role": role, "content": content }));
+            }
+            "function_call" => {
+                let call = json!({
+                    "id": item.get("call_id").or(item.get("id")),
+                    "type": "function",
+                    "function": { "name": item.get("name"), "arguments": item.get("arguments").cloned().unwrap_or(json!("{}")) },
+                });
+                let merge = messages.last().is_some_and(|m| m["role"] == "assistant" && m.get("tool_calls").is_some());
+                match messages.last_mut().and_then(|m| m.get_mut("tool_calls")).and_then(Value::as_array_mut) {
+                    Some(calls) if merge => calls.push(call),
+                    _ => messages.push(json!({ "role": "assistant", "content": null, "tool_calls": [call] })),
+                }
+            }
+            "function_call_output" => {
+                let output = item.get("output").map(text_of).unwrap_or_default();
+                messages.push(json!({ "role": "tool", "tool_call_id": item.get("call_id"), "content": output }));
+            }
+            // reasoning / item_reference 等没有 Chat 等价物
+            _ => {}
+        }
+    }
+    messages
+}
+
+/// Responses 请求转为 Chat Completions 请求
+pub fn to_chat_request(body: &Value) -> Result<Value, String> {
+    if body.get("previous_response_id").is_some_and(|v| !v.is_null()) {
+        return Err("previous_response_id is not supported for this model; send the full conversation in 'input'".to_string());
+    }
+    let mut messages = Vec::new();
+    if let Some(instructions) = body.get("instructions").and_then(Value::as_str).filter(|i| !i.is_empty()) {
+        messages.push(json!({ "role": "system", "content": instructions }));
+    }
+    messages.extend(chat_messages(body.get("input").unwrap_or(&Value::Null)));
+
+    let mut chat = Map::new();
+    chat.insert("model".to_string(), body.get("model").cloned().unwrap_or(Value::Null));
+    chat.insert("messages".to_string(), Value::Array(messages));
+    for key in ["temperature", "top_p", "user", "parallel_tool_calls", "prompt_cache_key", "stream"] {
+        if let Some(value) = body.get(key).filter(|v| !v.is_null()) {
+            chat.insert(key.to_string(), value.clone());
+        }
+    }
+    if body.get("stream").and_then(Value::as_bool) == Some(true) {
+        chat.insert("stream_options".to_string(), json!({ "include_usage": true }));
+    }
+    if let Some(max) = body.get("max_output_tokens").filter(|v| !v.is_null()) {
+        chat.insert("max_tokens".to_string(), max.clone());
+    }
+    if let Some(effort) = body.pointer("/reasoning/effort").filter(|v| !v.is_null()) {
+        chat.insert("reasoning_effort".to_string(), effort.clone());
+    }
+
+    // Responses 的函数工具是扁平结构; 内置工具 (web_search 等) 没有 Chat 等价物
+    let tools: Vec<Value> = body
+        .get("tools")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter(|t| t["type"] == "function")
+        .map(|t| {
+            let mut function = t.as_object().cloned().unwrap_or_default();
+            function.remove("type");
+            json!({ "type": "function", "function": function })
+        })
+        .collect();
+    if !tools.is_empty() {
+        chat.insert("tools".to_string(), Value::Array(tools));
+        match body.get("tool_choice") {
+            Some(Value::String(choice)) => {
+                chat.insert("tool_choice".to_string(), json!(choice));
+            }
+            Some(choice) if choice["type"] == "function" => {
+                chat.insert("tool_choice".to_string(), json!({ "type": "function", "function": { "name": choice.get("name") } }));
+            }
+            _ => {}
+        }
+    }
+
+    match body.pointer("/text/format") {
+        Some(format) if format["type"] == "json_schema" => {
+            let mut schema = format.as_object().cloned().unwrap_or_default();
+            schema.remove("type");
+            chat.insert("response_format".to_string(), json!({ "type": "json_schema", "json_schema": schema }));
+        }
+        Some(format) if format["type"] == "json_object" => {
+            chat.insert("response_format".to_string(), json!({ "type": "json_object" }));
+        }
+        _ => {}
+    }
+    Ok(Value::Object(chat))
+}
+
+/// Chat usage 转为 Responses usage
+fn usage(chat: &Value) -> Value {
+    let number = |pointer: &str| chat.pointer(pointer).and_then(Value::as_u64).unwrap_or(0);
+    json!({
+        "input_tokens": number("/prompt_tokens"),
+        "input_tokens_details": { "cached_tokens": number("/prompt_tokens_details/cached_tokens") },
+        "output_tokens": number("/completion_tokens"),
+        "output_tokens_details": { "reasoning_tokens": number("/completion_tokens_details/reasoning_tokens") },
+        "total_tokens": number("/total_tokens"),
+    })
+}
+
+/// 输出项 (推理、消息或函数调用)
+#[derive(Debug, Clone)]
+enum Item {
+    Reasoning { id: String, text: String },
+    Message { id: String, text: String },
+    Call { id: String, call_id: String, name: String, arguments: String, index: u64 },
+}
+
+impl Item {
+    fn id(&self) -> &str {
+        match self {
+            Item::Reasoning { id, .. } | Item::Message { id, .. } | Item::Call { id, .. } => id,
+        }
+    }
+
+    fn to_value(&self, status: &str) -> Value {
+        match self {
+            Item::Reasoning { id, text } => {
+                let summary = if text.is_empty() { json!([]) } else { json!([{ "type": "summary_text", "text": text }]) };
+                json!({ "type": "reasoning", "id": id, "summary": summary })
+            }
+            Item::Message { id, text } => {
+                let content = if status == "completed" { json!([output_text(text)]) } else { json!([]) };
+                json!({ "type": "message", "id": id, "status": status, "role": "assistant", "content": content })
+            }
+            Item::Call { id, call_id, name, arguments, .. } => json!({
+                "type": "function_call", "id": id, "call_id": call_id, "name": name,
+                "arguments": if status == "completed" { arguments.as_str() } else { "" }, "status": status,
+            }),
+        }
+    }
+}
+
+fn output_text(text: &str) -> Value {
+    json!({ "type": "output_text", "text": text, "annotations": [] })
+}
+
+/// Responses 对象; 请求参数原样回显
+fn response_object(id: &str, created_at: i64, request: &Value, model: &Value, status: &str, output: Vec<Value>, usage: Value) -> Value {
+    let echo = |key: &str, default: Value| request.get(key).filter(|v| !v.is_null()).cloned().unwrap_or(default);
+    let incomplete = (status == "incomplete").then(|| json!({ "reason": "max_output_tokens" }));
+    json!({
+        "id": id,
+        "object": "response",
+        "created_at": created_at,
+        "status": status,
+        "error": null,
+        "incomplete_details": incomplete,
+        "instructions": echo("instructions", Value::Null),
+        "max_output_tokens": echo("max_output_tokens", Value::Null),
+        "model": model,
+        "output": output,
+        "parallel_tool_calls": echo("parallel_tool_calls", json!(true)),
+        "previous_response_id": null,
+        "reasoning": echo("reasoning", json!({ "effort": null, "summary": null })),
+        "store": false,
+        "temperature": echo("temperature", json!(1.0)),
+        "text": echo("text", json!({ "format": { "type": "text" } })),
+        "tool_choice": echo("tool_choice", json!("auto")),
+        "tools": echo("tools", json!([])),
+        "top_p": echo("top_p", json!(1.0)),
+        "truncation": "disabled",
+        "usage": usage,
+        "metadata": echo("metadata", json!({})),
+    })
+}
+
+fn status_for(finish_reason: Option<&str>) -> &'static str {
+    if finish_reason == Some("length") { "incomplete" } else { "completed" }
+}
+
+/// Chat Completions 响应转为 Responses 响应
+pub fn from_chat_response(chat: &Value, request: &Value) -> Value {
+    let message = chat.pointer("/choices/0/message").cloned().unwrap_or_else(|| json!({}));
+    let mut items = Vec::new();
+    if let Some(reasoning) = message.get("reasoning_content").and_then(Value::as_str).filter(|r| !r.is_empty()) {
+        items.push(Item::Reasoning { id: new_id("rs"), text: reasoning.to_string() });
+    }
+    if let Some(text) = message.get("content").and_then(Value::as_str).filter(|t| !t.is_empty()) {
+        items.push(Item::Message { id: new_id("msg"), text: text.to_string() });
+    }
+    for (index, call) in message.get("tool_calls").and_then(Value::as_array).into_iter().flatten().enumerate() {
+        items.push(Item::Call {
+            id: new_id("fc"),
+            call_id: call["id"].as_str().unwrap_or_default().to_string(),
+            name: call.pointer("/function/name").and_then(Value::as_str).unwrap_or_default().to_string(),
+            arguments: call.pointer("/function/arguments").and_then(Value::as_str).unwrap_or("{}").to_string(),
+            index: index as u64,
+        });
+    }
+    let status = status_for(chat.pointer("/choices/0/finish_reason").and_then(Value::as_str));
+    let output = items.iter().map(|i| i.to_value("completed")).collect();
+    let created_at = chat.get("created").and_then(Value::as_i64).unwrap_or_else(|| chrono::Utc::now().timestamp());
+    let usage = chat.get("usage").map(usage).unwrap_or(Value::Null);
+    let model = chat.get("model").cloned().unwrap_or_else(|| request.get("model").cloned().unwrap_or(Value::Null));
+    response_object(&new_id("resp"), created_at, request, &model, status, output, usage)
+}
+
+/// Chat SSE chunk → Responses 事件
+pub struct ResponsesStream {
+    request: Value,
+    id: String,
+    created_at: i64,
+    model: Value,
+    sequence: u64,
+    items: Vec<Item>,
+    /// 尚未结束的输出项
+    open: Option<usize>,
+    finish_reason: Option<String>,
+    usage: Value,
+    started: bool,
+    finished: bool,
+}
+
+impl ResponsesStream {
+    pub fn new(request: &Value) -> Self {
+        Self {
+            request: request.clone(),
+            id: new_id("resp"),
+            created_at: chrono::Utc::now().timestamp(),
+            model: request.get("model").cloned().unwrap_or(Value::Null),
+            sequence: 0,
+            items: Vec::new(),
+            open: None,
+            finish_reason: None,
+            usage: Value::Null,
+            started: false,
+            finished: false,
+        }
+    }
+
+    fn event(&mut self, kind: &str, mut data: Value) -> Bytes {
+        data["type"] = json!(kind);
+        data["sequence_number"] = json!(self.sequence);
+        self.sequence += 1;
+        Bytes::from(format!("event: {}\ndata: {}\n\n", kind, data))
+    }
+
+    fn snapshot(&self, status: &str) -> Value {
+        let output = self.items.iter().map(|i| i.to_value("completed")).collect();
+        response_object(&self.id, self.created_at, &self.request, &self.model, status, output, self.usage.clone())
+    }
+
+    fn start(&mut self, events: &mut Vec<Bytes>) {
+        if self.started {
+            return;
+        }
+        self.started = true;
+        let response = response_object(&self.id, self.created_at, &self.request, &self.model, "in_progress", Vec::new(), Value::Null);
+        events.push(self.event("response.created", json!({ "response": response })));
+        events.push(self.event("response.in_progress", json!({ "response": response })));
+    }
+
+    /// 结束当前输出项
+    fn close(&mut self, events: &mut Vec<Bytes>) {
+        let Some(index) = self.open.take() else {
+            return;
+        };
+        let item = self.items[index].clone();
+        let base = json!({ "item_id": item.id(), "output_index": index });
+        match &item {
+            Item::Reasoning { text, .. } => {
+                let mut data = base.clone();
+                data["summary_index"] = json!(0);
+                data["text"] = json!(text);
+                events.push(self.event("response.reasoning_summary_text.done", data));
+            }
+            Item::Message { text, .. } => {
+                let mut data = base.clone();
+                data["content_index"] = json!(0);
+                data["text"] = json!(text);
+                events.push(self.event("response.output_text.done", data));
+                let mut part = base.clone();
+                part["content_index"] = json!(0);
+                part["part"] = output_text(text);
+                events.push(self.event("response.content_part.done", part));
+            }
+            Item::Call { arguments, .. } => {
+                let mut data = base.clone();
+                data["arguments"] = json!(arguments);
+                events.push(self.event("response.function_call_arguments.done", data));
+            }
+        }
+        let done = json!({ "output_index": index, "item": item.to_value("completed") });
+        events.push(self.event("response.output_item.done", done));
+    }
+
+    /// 开始新的输出项 (先结束当前项)
+    fn open(&mut self, item: Item, events: &mut Vec<Bytes>) -> usize {
+        self.close(events);
+        let index = self.items.len();
+        let added = json!({ "output_index": index, "item": item.to_value("in_progress") });
+        let is_message = matches!(item, Item::Message { .. });
+        let id = item.id().to_string();
+        self.items.push(item);
+        self.open = Some(index);
+        events.push(self.event("response.output_item.added", added));
+        if is_message {
+            let part = json!({ "item_id": id, "output_index": index, "content_index": 0, "part": output_text("") });
+            events.push(self.event("response.content_part.added", part));
+        }
+        index
+    }
+
+    /// 当前打开的、与给定种类相同的输出项
+    fn current(&self, reasoning: bool) -> Option<usize> {
+        self.open.filter(|&i| match self.items[i] {
+            Item::Reasoning { .. } => reasoning,
+            Item::Message { .. } => !reasoning,
+            Item::Call { .. } => false,
+        })
+    }
+
+    /// 处理一个 Chat SSE chunk
+    pub fn push(&mut self, chunk: &Value) -> Vec<Bytes> {
+        let mut events = Vec::new();
+        if self.finished {
+            return events;
+        }
+        self.start(&mut events);
+        if let Some(model) = chunk.get("model").filter(|m| m.is_string()) {
+            self.model = model.clone();
+        }
+        if let Some(error) = chunk.get("error") {
+            self.finished = true;
+            let mut response = self.snapshot("failed");
+            let message = error.get("message").cloned().unwrap_or_else(|| error.clone());
+            response["error"] = json!({ "code": "server_error", "message": message });
+            events.push(self.event("response.failed", json!({ "response": response })));
+            return events;
+        }
+        if let Some(usage_value) = chunk.get("usage").filter(|u| u.is_object()) {
+            self.usage = usage(usage_value);
+        }
+        let Some(choice) = chunk.pointer("/choices/0") else {
+            return events;
+        };
+        let delta = choice.get("delta").cloned().unwrap_or_else(|| json!({}));
+        for (reasoning, key) in [(true, "reasoning_content"), (false, "content")] {
+            let Some(text) = delta.get(key).and_then(Value::as_str).filter(|t| !t.is_empty()) else {
+                continue;
+            };
+            let index = match self.current(reasoning) {
+                Some(index) => index,
+                None if reasoning => self.open(Item::Reasoning { id: new_id("rs"), text: String::new() }, &mut events),
+                None => self.open(Item::Message { id: new_id("msg"), text: String::new() }, &mut events),
+            };
+            let id = self.items[index].id().to_string();
+            let kind = match &mut self.items[index] {
+                Item::Reasoning { text: full, .. } | Item::Message { text: full, .. } => {
+                    full.push_str(text);
+                    if reasoning { "response.reasoning_summary_text.delta" } else { "response.output_text.delta" }
+                }
+                Item::Call { .. } => continue,
+            };
+            let position = if reasoning { "summary_index" } else { "content_index" };
+            let data = json!({ "item_id": id, "output_index": index, position: 0, "delta": text });
+            events.push(self.event(kind, data));
+        }
+        for call in delta.get("tool_calls").and_then(Value::as_array).into_iter().flatten() {
+            let chat_index = call.get("index").and_then(Value::as_u64).unwrap_or(0);
+            let existing = self.items.iter().position(|i| matches!(i, Item::Call { index, .. } if *index == chat_index));
+            let index = match existing {
+                Some(index) => index,
+                None => {
+                    let item = Item::Call {
+                        id: new_id("fc"),
+                        call_id: call["id"].as_str().map(String::from).unwrap_or_else(|| new_id("call")),
+                        name: call.pointer("/function/name").and_then(Value::as_str).unwrap_or_default().to_string(),
+                        arguments: String::new(),
+                        index: chat_index,
+                    };
+                    self.open(item, &mut events)
+                }
+            };
+            let Some(arguments) = call.pointer("/function/arguments").and_then(Value::as_str).filter(|a| !a.is_empty()) else {
+                continue;
+            };
+            let id = self.items[index].id().to_string();
+            if let Item::Call { arguments: full, .. } = &mut self.items[index] {
+                full.push_str(arguments);
+            }
+            let data = json!({ "item_id": id, "output_index": index, "delta": arguments });
+            events.push(self.event("response.function_call_arguments.delta", data));
+        }
+        if let Some(reason) = choice.get("finish_reason").and_then(Value::as_str) {
+            self.finish_reason = Some(reason.to_string());
+        }
+        events
+    }
+
+    /// 上游流结束: 结束所有输出项并发送 `response.completed` (或 `response.incomplete`)
+    pub fn finish(&mut self) -> Vec<Bytes> {
+        let mut events = Vec::new();
+        if self.finished {
+            return events;
+        }
+        self.start(&mut events);
+        self.close(&mut events);
+        self.finished = true;
+        let status = status_for(self.finish_reason.as_deref());
+        let response = self.snapshot(status);
+        let kind = if status == "incomplete" { "response.incomplete" } else { "response.completed" };
+        events.push(self.event(kind, json!({ "response": response })));
+        events
+    }
+}
+
+/// Chat SSE 流转为 Responses 事件流
+pub fn translate_stream<S, E>(upstream: S, request: &Value) -> Pin<Box<dyn Stream<Item = Result<Bytes, E>> + Send>>
+where
+    S: Stream<Item = Result<Bytes, E>> + Send + 'static,
+    E: Send + 'static,
+{
+    let mut state = ResponsesStream::new(request);
+    let stream = async_stream::stream! {
+        let mut upstream = Box::pin(upstream);
+        let mut buffer = BytesMut::new();
+        while let Some(chunk) = upstream.next().await {
+            let chunk = match chunk {
+                Ok(chunk) => chunk,
+                Err(e) => {
+                    yield Err(e);
+                    return;
+                }
+            };
+            buffer.extend_from_slice(&chunk);
+            while let Some(pos) = buffer.iter().position(|&b| b == b'\n') {
+                let line = buffer.split_to(pos + 1);
+                let line = String::from_utf8_lossy(&line);
+                let Some(data) = line.trim().strip_prefix("data:").map(str::trim) else {
+                    continue;
+                };
+                if data == "[DONE]" {
+                    continue;
+                }
+                if let Ok(json) = serde_json::from_str::<Value>(data) {
+                    for event in state.push(&json) {
+                        yield Ok(event);
+                    }
+                }
+            }
+        }
+        for event in state.finish() {
+            yield Ok(event);
+        }
+    };
+    Box::pin(stream)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_chat_request() {
+        let body = json!({
+            "model": "llama3",
+            "instructions": "Be terse.",
+            "input": [
+                {"role": "developer", "content": "Use metric units."},
+                {"type": "message", "role": "user", "content": [
+                    {"type": "input_text", "text": "Weather here?"},
+                    {"type": "input_image", "image_url": "data:image/png;base64,AAAA"}
+                ]},
+                {"type": "function_call", "call_id": "call_1", "name": "get_weather", "arguments": "{\"city\":\"Hanoi\"}"},
+                {"type": "function_call", "call_id": "call_2", "name": "get_time", "arguments": "{}"},
+                {"type": "function_call_output", "call_id": "call_1", "output": "31C"}
+            ],
+            "tools": [{"type": "function", "name": "get_weather", "parameters": {"type": "object"}}, {"type": "web_search"}],
+            "tool_choice": {"type": "function", "name": "get_weather"},
+            "text": {"format": {"type": "json_schema", "name": "w", "schema": {"type": "object"}, "strict": true}},
+            "max_output_tokens": 100,
+            "stream": true
+        });
+        let chat = to_chat_request(&body).unwrap();
+        let messages = chat["messages"].as_array().unwrap();
+        assert_eq!(messages[0], json!({"role": "system", "content": "Be terse."}));
+        assert_eq!(messages[1]["role"], "system");
+        assert_eq!(messages[2]["content"][1]["image_url"]["url"], "data:image/png;base64,AAAA");
+        assert_eq!(messages[3]["tool_calls"].as_array().unwrap().len(), 2);
+        assert_eq!(messages[4], json!({"role": "tool", "tool_call_id": "call_1", "content": "31C"}));
+        assert_eq!(chat["tools"], json!([{"type": "function", "function": {"name": "get_weather", "parameters": {"type": "object"}}}]));
+        assert_eq!(chat["tool_choice"]["function"]["name"], "get_weather");
+        assert_eq!(chat["response_format"]["json_schema"]["name"], "w");
+        assert_eq!((chat["max_tokens"].as_u64(), chat["stream_options"]["include_usage"].as_bool()), (Some(100), Some(true)));
+        assert!(to_chat_request(&json!({"model": "m", "input": "hi", "previous_response_id": "resp_1"})).is_err());
+
+        let reply = json!({
+            "model": "llama3", "created": 1,
+            "choices": [{"message": {"role": "assistant", "content": "Calling.", "tool_calls": [
+                {"id": "call_9", "type": "function", "function": {"name": "get_weather", "arguments": "{}"}}
+            ]}, "finish_reason": "tool_calls"}],
+            "usage": {"prompt_tokens": 5, "completion_tokens": 3, "total_tokens": 8}
+        });
+        let response = from_chat_response(&reply, &body);
+        assert_eq!(response["status"], "completed");
+        assert_eq!(response["output"][0]["content"][0]["text"], "Calling.");
+        assert_eq!(response["output"][1]["call_id"], "call_9");
+        assert_eq!(response["usage"]["input_tokens"], 5);
+    }
+
+    #[test]
+    fn test_stream_events() {
+        let mut stream = ResponsesStream::new(&json!({"model": "llama3"}));
+        let chunks = [
+            json!({"choices": [{"delta": {"role": "assistant", "content": "Hel"}}]}),
+            json!({"choices": [{"delta": {"content": "lo"}}]}),
+            json!({"choices": [{"delta": {"tool_calls": [{"index": 0, "id": "call_1", "function": {"name": "f", "arguments": "{\"a\""}}]}}]}),
+            json!({"choices": [{"delta": {"tool_calls": [{"index": 0, "function": {"arguments": ":1}"}}]}, "finish_reason": "tool_calls"}]}),
+            json!({"choices": [], "usage": {"prompt_tokens": 2, "completion_tokens": 4, "total_tokens": 6}}),
+        ];
+        let mut events: Vec<Value> = Vec::new();
+        for chunk in chunks.iter() {
+            events.extend(stream.push(chunk).iter().map(parse_event));
+        }
+        events.extend(stream.finish().iter().map(parse_event));
+        let kinds: Vec<&str> = events.iter().map(|e| e["type"].as_str().unwrap()).collect();
+        assert_eq!(kinds, vec![
+            "response.created", "response.in_progress",
+            "response.output_item.added", "response.content_part.added",
+            "response.output_text.delta", "response.output_text.delta",
+            "response.output_text.done", "response.content_part.done", "response.output_item.done",
+            "response.output_item.added", "response.function_call_arguments.delta", "response.function_call_arguments.delta",
+            "response.function_call_arguments.done", "response.output_item.done",
+            "response.completed",
+        ]);
+        let sequence: Vec<u64> = events.iter().map(|e| e["sequence_number"].as_u64().unwrap()).collect();
+        assert_eq!(sequence, (0..events.len() as u64).collect::<Vec<_>>());
+        let completed = &events[events.len() - 1]["response"];
+        assert_eq!(completed["output"][0]["content"][0]["text"], "Hello");
+        assert_eq!(completed["output"][1]["arguments"], "{\"a\":1}");
+        assert_eq!(completed["usage"]["total_tokens"], 6);
+    }
+
+    fn parse_event(bytes: &Bytes) -> Value {
+        let text = String::from_utf8_lossy(bytes);
+        let data = text.lines().find_map(|l| l.strip_prefix("data: ")).unwrap();
+        serde_json::from_str(data).unwrap()
+    }
+}
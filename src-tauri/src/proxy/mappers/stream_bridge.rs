@@ -0,0 +1,355 @@
+// 非流式响应 -> SSE 流
+// 客户端请求 stream: true 但上游只返回了完整 JSON 时, 按客户端协议合成等价的 SSE 事件:
+// 内容分片输出, 末尾附带 usage 和结束标记。与 collector (SSE -> JSON) 互为逆过程。
+
+use bytes::Bytes;
+use serde_json::{json, Value};
+
+/// 每个增量事件携带的字符数
+const PIECE_CHARS: usize = 64;
+
+/// 响应所属的协议
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResponseFormat {
+    OpenAIChat,
+    OpenAICompletion,
+    Anthropic,
+    Gemini,
+}
+
+impl ResponseFormat {
+    pub fn detect(json: &Value) -> Option<Self> {
+        if json.get("choices").is_some() {
+            return Some(match json.get("object").and_then(|o| o.as_str()) {
+                Some("text_completion") => Self::OpenAICompletion,
+                _ => Self::OpenAIChat,
+            });
+        }
+        if json.get("type").and_then(|t| t.as_str()) == Some("message") && json.get("content").is_some() {
+            return Some(Self::Anthropic);
+        }
+        if json.get("candidates").is_some() {
+            return Some(Self::Gemini);
+        }
+        None
+    }
+}
+
+/// 按字符边界切分文本
+fn pieces(text: &str) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    chars.chunks(PIECE_CHARS).map(|c| c.iter().collect()).collect()
+}
+
+fn data_event(data: &Value) -> Bytes {
+    Bytes::from(format!("data: {}\n\n", data))
+}
+
+fn named_event(event: &str, data: Value) -> Bytes {
+    Bytes::from(format!("event: {}\ndata: {}\n\n", event, data))
+}
+
+/// 将完整响应转换为 SSE 事件序列, 无法识别的响应返回 None
+pub fn json_to_sse(json: &Value) -> Option<Vec<Bytes>> {
+    Some(match ResponseFormat::detect(json)? {
+        ResponseFormat::OpenAIChat => openai_chat_to_sse(json),
+        ResponseFormat::OpenAICompletion => openai_completion_to_sse(json),
+        ResponseFormat::Anthropic => anthropic_to_sse(json),
+        // Gemini 的流式事件与完整响应结构相同
+        ResponseFormat::Gemini => vec![data_event(json)],
+    })
+}
+
+fn openai_chat_to_sse(json: &Value) -> Vec<Bytes> {
+    let envelope = |choices: Value| {
+        json!({
+            "id": json.get("id").cloned().unwrap_or(Value::Null),
+            "object": "chat.completion.chunk",
+            "created": json.get("created").cloned().unwrap_or_else(|| json!(chrono::Utc::now().timestamp())),
+            "model": json.get("model").cloned().unwrap_or(Value::Null),
+            "choices": choices
+        })
+    };
+    let delta_chunk = |index: &Value, delta: Value| {
+        data_event(&envelope(json!([{"index": index, "delta": delta, "finish_reason": null}])))
+    };
+
+    let mut out = Vec::new();
+    for choice in json.get("choices").and_then(|c| c.as_array()).into_iter().flatten() {
+        let index = choice.get("index").cloned().unwrap_or_else(|| json!(0));
+        let message = choice.get("message").cloned().unwrap_or_else(|| json!({}));
+
+        out.push(delta_chunk(&index, json!({"role": "assistant", "content": ""})));
+        if let Some(reasoning) = message.get("reasoning_content").and_then(|r| r.as_str()) {
+            for piece in pieces(reasoning) {
+                out.push(delta_chunk(&index, json!({"reasoning_content": piece})));
+            }
+        }
+        if let Some(content) = message.get("content").and_then(|c| c.as_str()) {
+            for piece in pieces(content) {
+                out.push(delta_chunk(&index, json!({"content": piece})));
+            }
+        }
+        for (i, call) in message
+            .get("tool_calls")
+            .and_then(|t| t.as_array())
+            .into_iter()
+            .flatten()
+            .enumerate()
+        {
+            let arguments = call.pointer("/function/arguments").and_then(|a| a.as_str()).unwrap_or_default();
+            out.push(delta_chunk(
+                &index,
+                json!({"tool_calls": [{
+                    "index": i,
+                    "id": call.get("id").cloned().unwrap_or(Value::Null),
+                    "type": "function",
+                    "function": {
+                        "name": call.pointer("/function/name").cloned().unwrap_or(Value::Null),
+                        "arguments": ""
+                    }
+                }]}),
+            ));
+            for piece in pieces(arguments) {
+                out.push(delta_chunk(&index, json!({"tool_calls": [{"index": i, "function": {"arguments": piece}}]})));
+            }
+        }
+        let finish_reason = choice.get("finish_reason").cloned().unwrap_or_else(|| json!("stop"));
+        out.push(data_event(&envelope(json!([{"index": index, "delta": {}, "finish_reason": finish_reason}]))));
+    }
+
+    if let Some(usage) = json.get("usage").filter(|u| !u.is_null()) {
+        let mut chunk = envelope(json!([]));
+        chunk["usage"] = usage.clone();
+        out.push(data_event(&chunk));
+    }
+    out.push(Bytes::from_static(b"data: [DONE]\n\n"));
+    out
+}
+
+fn openai_completion_to_sse(json: &Value) -> Vec<Bytes> {
+    let envelope = |choice: Value| {
+        json!({
+            "id": json.get("id").cloned().unwrap_or(Value::Null),
+            "object": "text_completion",
+            "created": json.get("created").cloned().unwrap_or_else(|| json!(chrono::Utc::now().timestamp())),
+            "model": json.get("model").cloned().unwrap_or(Value::Null),
+            "choices": [choice]
+        })
+    };
+
+    let mut out = Vec::new();
+    for choice in json.get("choices").and_then(|c| c.as_array()).into_iter().flatten() {
+        let index = choice.get("index").cloned().unwrap_or_else(|| json!(0));
+        let text = choice.get("text").and_then(|t| t.as_str()).unwrap_or_default();
+        for piece in pieces(text) {
+            out.push(data_event(&envelope(json!({"index": index, "text": piece, "finish_reason": null}))));
+        }
+        let finish_reason = choice.get("finish_reason").cloned().unwrap_or_else(|| json!("stop"));
+        out.push(data_event(&envelope(json!({"index": index, "text": "", "finish_reason": finish_reason}))));
+    }
+    if let Some(usage) = json.get("usage").filter(|u| !u.is_null()) {
+        let mut chunk = envelope(Value::Null);
+        chunk["choices"] = json!([]);
+        chunk["usage"] = usage.clone();
+        out.push(data_event(&chunk));
+    }
+    out.push(Bytes::from_static(b"data: [DONE]\n\n"));
+    out
+}
+
+fn anthropic_to_sse(json: &Value) -> Vec<Bytes> {
+    let usage = json.get("usage").cloned().unwrap_or_else(|| json!({}));
+    let mut start_usage = usage.clone();
+    start_usage["output_tokens"] = json!(0);
+
+    let mut out = vec![named_event(
+        "message_start",
+        json!({
+            "type": "message_start",
+            "message": {
+                "id": json.get("id").cloned().unwrap_or(Value::Null),
+                "type": "message",
+                "role": "assistant",
+                "model": json.get("model").cloned().unwrap_or(Value::Null),
+                "content": [],
+                "stop_reason": null,
+                "stop_sequence": null,
+                "usage": start_usage
+            }
+        }),
+    )];
+
+    let delta = |index: usize, delta: Value| {
+        named_event(
+            "content_block_delta",
+            json!({"type": "content_block_delta", "index": index, "delta": delta}),
+        )
+    };
+
+    for (index, block) in json.get("content").and_then(|c| c.as_array()).into_iter().flatten().enumerate() {
+        let block_type = block.get("type").and_then(|t| t.as_str()).unwrap_or_default();
+        let start_block = match block_type {
+            "text" => json!({"type": "text", "text": ""}),
+            "thinking" => json!({"type": "thinking", "thinking": ""}),
+            "tool_use" | "server_tool_use" => json!({
+                "type": block_type,
+                "id": block.get("id").cloned().unwrap_or(Value::Null),
+                "name": block.get("name").cloned().unwrap_or(Value::Null),
+                "input": {}
+            }),
+            // redacted_thinking / 工具结果等块整体放在 start 中
+            _ => block.clone(),
+        };
+        out.push(named_event(
+            "content_block_start",
+            json!({"type": "content_block_start", "index": index, "content_block": start_block}),
+        ));
+
+        match block_type {
+            "text" => {
+                for piece in pieces(block.get("text").and_then(|t| t.as_str()).unwrap_or_default()) {
+                    out.push(delta(index, json!({"type": "text_delta", "text": piece})));
+                }
+            }
+            "thinking" => {
+                for piece in pieces(block.get("thinking").and_then(|t| t.as_str()).unwrap_or_default()) {
+                    out.push(delta(index, json!({"type": "thinking_delta", "thinking": piece})));
+                }
+                if let Some(signature) = block.get("signature").and_then(|s| s.as_str()) {
+                    out.push(delta(index, json!({"type": "signature_delta", "signature": signature})));
+                }
+            }
+            "tool_use" | "server_tool_use" => {
+                let input = block.get("input").cloned().unwrap_or_else(|| json!({}));
+                for piece in pieces(&input.to_string()) {
+                    out.push(delta(index, json!({"type": "input_json_delta", "partial_json": piece})));
+                }
+            }
+            _ => {}
+        }
+        out.push(named_event(
+            "content_block_stop",
+            json!({"type": "content_block_stop", "index": index}),
+        ));
+    }
+
+    out.push(named_event(
+        "message_delta",
+        json!({
+            "type": "message_delta",
+            "delta": {
+                "stop_reason": json.get("stop_reason").cloned().unwrap_or_else(|| json!("end_turn")),
+                "stop_sequence": json.get("stop_sequence").cloned().unwrap_or(Value::Null)
+            },
+            // 累计 usage (与 Anthropic 当前行为一致, 包含 input_tokens)
+            "usage": usage
+        }),
+    ));
+    out.push(named_event("message_stop", json!({"type": "message_stop"})));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy::common::sse::SseParser;
+
+    fn parse(out: &[Bytes]) -> Vec<(String, String)> {
+        let mut parser = SseParser::new();
+        let mut events = Vec::new();
+        for chunk in out {
+            events.extend(parser.push(chunk));
+        }
+        events.extend(parser.finish());
+        events.into_iter().map(|e| (e.event, e.data)).collect()
+    }
+
+    #[test]
+    fn test_openai_chat_json_to_sse_round_trips_through_collector() {
+        let long_text = "x".repeat(150);
+        let resp = json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion",
+            "created": 1,
+            "model": "gpt-4o",
+            "choices": [{
+                "index": 0,
+                "message": {
+                    "role": "assistant",
+                    "content": long_text,
+                    "tool_calls": [{"id": "call_1", "type": "function", "function": {"name": "f", "arguments": "{\"a\":1}"}}]
+                },
+                "finish_reason": "tool_calls"
+            }],
+            "usage": {"prompt_tokens": 3, "completion_tokens": 5, "total_tokens": 8}
+        });
+
+        let events = parse(&json_to_sse(&resp).unwrap());
+        assert_eq!(events.last().unwrap().1, "[DONE]");
+        let chunks: Vec<Value> = events[..events.len() - 1]
+            .iter()
+            .map(|(_, data)| serde_json::from_str(data).unwrap())
+            .collect();
+        assert!(chunks.iter().all(|c| c["object"] == "chat.completion.chunk"));
+
+        let content: String = chunks
+            .iter()
+            .filter_map(|c| c.pointer("/choices/0/delta/content")?.as_str())
+            .collect();
+        assert_eq!(content, long_text);
+        assert_eq!(chunks[2]["choices"][0]["delta"]["content"].as_str().unwrap().len(), PIECE_CHARS);
+
+        let arguments: String = chunks
+            .iter()
+            .filter_map(|c| c.pointer("/choices/0/delta/tool_calls/0/function/arguments")?.as_str())
+            .collect();
+        assert_eq!(arguments, "{\"a\":1}");
+
+        let usage = chunks.last().unwrap();
+        assert_eq!(usage["choices"], json!([]));
+        assert_eq!(usage["usage"]["total_tokens"], 8);
+        assert_eq!(chunks[chunks.len() - 2]["choices"][0]["finish_reason"], "tool_calls");
+    }
+
+    #[tokio::test]
+    async fn test_anthropic_json_to_sse_matches_collector() {
+        let resp = json!({
+            "id": "msg_1",
+            "type": "message",
+            "role": "assistant",
+            "model": "claude-sonnet-4-5",
+            "content": [
+                {"type": "thinking", "thinking": "hmm", "signature": "sig"},
+                {"type": "text", "text": "Hello"},
+                {"type": "tool_use", "id": "toolu_1", "name": "lookup", "input": {"q": "rust"}}
+            ],
+            "stop_reason": "tool_use",
+            "stop_sequence": null,
+            "usage": {"input_tokens": 4, "output_tokens": 9}
+        });
+
+        let out = json_to_sse(&resp).unwrap();
+        let events = parse(&out);
+        assert_eq!(events[0].0, "message_start");
+        assert_eq!(events.last().unwrap().0, "message_stop");
+
+        // 用现有 collector 反向收集, 结果应与原响应一致
+        let stream = futures::stream::iter(out.into_iter().map(Ok::<Bytes, std::io::Error>));
+        let collected = crate::proxy::mappers::claude::collector::collect_stream_to_json(stream).await.unwrap();
+        let collected = serde_json::to_value(&collected).unwrap();
+        assert_eq!(collected["content"], resp["content"]);
+        assert_eq!(collected["stop_reason"], "tool_use");
+        assert_eq!(collected["usage"]["output_tokens"], 9);
+    }
+
+    #[test]
+    fn test_detect_format() {
+        assert_eq!(
+            ResponseFormat::detect(&json!({"object": "text_completion", "choices": []})),
+            Some(ResponseFormat::OpenAICompletion)
+        );
+        assert_eq!(ResponseFormat::detect(&json!({"candidates": []})), Some(ResponseFormat::Gemini));
+        assert_eq!(ResponseFormat::detect(&json!({"data": []})), None);
+    }
+}
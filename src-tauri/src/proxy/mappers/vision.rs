@@ -0,0 +1,363 @@
+// 多模态图片处理
+// 统一解析 OpenAI image_url / Anthropic image source, 目标上游只接受内联数据 (Gemini) 时
+// 先下载远程图片并转为 base64, 转换器只需处理内联数据。
+// 图片地址由客户端提供: 只下载解析到公网地址的 URL (每次跳转重新检查并固定解析结果),
+// 不读取本地文件。
+
+use crate::proxy::config::UpstreamProxyConfig;
+use crate::proxy::mappers::claude::models::{ClaudeRequest, ContentBlock, MessageContent};
+use crate::proxy::mappers::openai::models::{OpenAIContent, OpenAIContentBlock, OpenAIRequest};
+use base64::Engine as _;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+
+/// 单张远程图片的下载上限
+pub const MAX_IMAGE_BYTES: usize = 20 * 1024 * 1024;
+/// 单张远程图片最多跟随的跳转次数
+const MAX_IMAGE_REDIRECTS: usize = 3;
+const IMAGE_FETCH_TIMEOUT_SECS: u64 = 30;
+
+/// image_url 的来源
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImageRef<'a> {
+    /// data URL 或裸 base64
+    Inline { mime_type: String, data: String },
+    /// http(s) 远程图片
+    Remote(&'a str),
+    /// file:// 或本地路径 (出于安全考虑不读取)
+    Local(String),
+}
+
+pub fn parse_image_url(url: &str) -> ImageRef<'_> {
+    let trimmed = url.trim();
+    if let Some(rest) = trimmed.strip_prefix("data:") {
+        if let Some((meta, data)) = rest.split_once(',') {
+            let mime_type = meta.split(';').next().filter(|m| !m.is_empty()).unwrap_or("image/jpeg");
+            return ImageRef::Inline {
+                mime_type: mime_type.to_string(),
+                data: data.to_string(),
+            };
+        }
+    }
+    if trimmed.starts_with("http://") || trimmed.starts_with("https://") {
+        return ImageRef::Remote(trimmed);
+    }
+    // 部分客户端直接传 base64 而不带 data: 前缀
+    if let Some(mime_type) = sniff_base64_mime(trimmed) {
+        return ImageRef::Inline {
+            mime_type: mime_type.to_string(),
+            data: trimmed.to_string(),
+        };
+    }
+    let path = match trimmed.strip_prefix("file://") {
+        #[cfg(target_os = "windows")]
+        Some(rest) => rest.trim_start_matches('/').replace('/', "\\"),
+        #[cfg(not(target_os = "windows"))]
+        Some(rest) => rest.to_string(),
+        None => trimmed.to_string(),
+    };
+    ImageRef::Local(path)
+}
+
+/// 按文件头识别图片类型
+pub fn sniff_image_mime(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("image/jpeg")
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        Some("image/gif")
+    } else if bytes.len() >= 12 && &bytes[0..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        Some("image/webp")
+    } else {
+        None
+    }
+}
+
+fn sniff_base64_mime(data: &str) -> Option<&'static str> {
+    // 解码前 16 个字符 (12 字节) 足够识别文件头
+    let head = data.get(..16)?;
+    let bytes = base64::engine::general_purpose::STANDARD.decode(head).ok()?;
+    sniff_image_mime(&bytes)
+}
+
+/// 按扩展名推断 MIME 类型 (忽略 URL 查询参数)
+pub fn mime_from_path(path: &str) -> &'static str {
+    let path = path.split(['?', '#']).next().unwrap_or(path).to_lowercase();
+    if path.ends_with(".png") {
+        "image/png"
+    } else if path.ends_with(".gif") {
+        "image/gif"
+    } else if path.ends_with(".webp") {
+        "image/webp"
+    } else {
+        "image/jpeg"
+    }
+}
+
+/// 是否为公网地址 (排除回环、内网、链路本地、CGNAT、组播等)
+fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => {
+            let [a, b, ..] = v4.octets();
+            !(v4.is_loopback()
+                || v4.is_private()
+                || v4.is_link_local()
+                || v4.is_unspecified()
+                || v4.is_broadcast()
+                || v4.is_multicast()
+                || v4.is_documentation()
+                || a == 0
+                || (a == 100 && (64..128).contains(&b))
+                || a >= 240)
+        }
+        IpAddr::V6(v6) => {
+            if let Some(v4) = v6.to_ipv4_mapped() {
+                return is_public_ip(IpAddr::V4(v4));
+            }
+            let first = v6.segments()[0];
+            !(v6.is_loopback()
+                || v6.is_unspecified()
+                || v6.is_multicast()
+                || (first & 0xfe00) == 0xfc00
+                || (first & 0xffc0) == 0xfe80)
+        }
+    }
+}
+
+/// 解析 URL 的主机, 所有解析结果都必须是公网地址
+async fn resolve_public(url: &reqwest::Url) -> Result<(String, SocketAddr), String> {
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(format!("Unsupported image URL scheme: {}", url.scheme()));
+    }
+    let host = url.host_str().ok_or("Image URL has no host")?.to_string();
+    let port = url.port_or_known_default().unwrap_or(443);
+    let lookup = host.trim_start_matches('[').trim_end_matches(']').to_string();
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((lookup.as_str(), port))
+        .await
+        .map_err(|e| format!("Failed to resolve {}: {}", host, e))?
+        .collect();
+    match addrs.first() {
+        None => Err(format!("Failed to resolve {}", host)),
+        Some(_) if addrs.iter().any(|a| !is_public_ip(a.ip())) => {
+            Err(format!("Image host {} resolves to a non-public address", host))
+        }
+        Some(addr) => Ok((host, *addr)),
+    }
+}
+
+/// 固定解析结果、不自动跟随跳转的客户端 (避免 DNS 重绑定与跳转到内网)
+fn pinned_client(proxy: &UpstreamProxyConfig, host: &str, addr: SocketAddr) -> Result<reqwest::Client, String> {
+    let mut builder = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .timeout(Duration::from_secs(IMAGE_FETCH_TIMEOUT_SECS))
+        .resolve(host, addr);
+    if let Some(proxy) = crate::proxy::upstream::client_pool::build_proxy(proxy)? {
+        builder = builder.proxy(proxy);
+    }
+    builder.build().map_err(|e| format!("Failed to build image client: {}", e))
+}
+
+/// 下载远程图片, 返回 (MIME 类型, base64 数据)
+pub async fn fetch_image(proxy: &UpstreamProxyConfig, url: &str) -> Result<(String, String), String> {
+    let mut url = reqwest::Url::parse(url).map_err(|e| format!("Invalid image URL: {}", e))?;
+    let mut redirects = 0;
+    let mut resp = loop {
+        let (host, addr) = resolve_public(&url).await?;
+        let resp = pinned_client(proxy, &host, addr)?
+            .get(url.clone())
+            .send()
+            .await
+            .map_err(|e| format!("Failed to download image: {}", e))?;
+        if !resp.status().is_redirection() {
+            break resp;
+        }
+        redirects += 1;
+        if redirects > MAX_IMAGE_REDIRECTS {
+            return Err(format!("Image download exceeded {} redirects", MAX_IMAGE_REDIRECTS));
+        }
+        let location = resp
+            .headers()
+            .get(reqwest::header::LOCATION)
+            .and_then(|v| v.to_str().ok())
+            .ok_or_else(|| format!("Image download returned HTTP {} without Location", resp.status()))?;
+        url = url.join(location).map_err(|e| format!("Invalid redirect location: {}", e))?;
+    };
+    if !resp.status().is_success() {
+        return Err(format!("Image download returned HTTP {}", resp.status()));
+    }
+    if resp.content_length().is_some_and(|len| len as usize > MAX_IMAGE_BYTES) {
+        return Err(format!("Image exceeds {} bytes", MAX_IMAGE_BYTES));
+    }
+    let header_mime = resp
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.split(';').next().unwrap_or(v).trim().to_string())
+        .filter(|v| v.starts_with("image/"));
+
+    // 分块读取, 超出上限立即中止 (不依赖 Content-Length)
+    let mut bytes = Vec::new();
+    while let Some(chunk) = resp.chunk().await.map_err(|e| format!("Failed to read image body: {}", e))? {
+        if bytes.len() + chunk.len() > MAX_IMAGE_BYTES {
+            return Err(format!("Image exceeds {} bytes", MAX_IMAGE_BYTES));
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    let mime_type = match header_mime {
+        Some(mime) => mime,
+        None => sniff_image_mime(&bytes)
+            .ok_or_else(|| "Downloaded content is not a supported image".to_string())?
+            .to_string(),
+    };
+    Ok((mime_type, base64::engine::general_purpose::STANDARD.encode(&bytes)))
+}
+
+/// 并发下载一组 URL (去重), 失败的 URL 不出现在结果中
+async fn fetch_all(proxy: &UpstreamProxyConfig, mut urls: Vec<String>) -> HashMap<String, (String, String)> {
+    urls.sort();
+    urls.dedup();
+    let results = futures::future::join_all(urls.iter().map(|url| fetch_image(proxy, url))).await;
+    urls.into_iter()
+        .zip(results)
+        .filter_map(|(url, result)| match result {
+            Ok(image) => Some((url, image)),
+            Err(e) => {
+                tracing::warn!("[Vision] Keeping remote image URL {}: {}", url, e);
+                None
+            }
+        })
+        .collect()
+}
+
+/// OpenAI 请求中需要下载的远程图片
+pub fn remote_openai_image_urls(req: &OpenAIRequest) -> Vec<String> {
+    req.messages
+        .iter()
+        .filter_map(|msg| match msg.content.as_ref() {
+            Some(OpenAIContent::Array(parts)) => Some(parts),
+            _ => None,
+        })
+        .flatten()
+        .filter_map(|part| match part {
+            OpenAIContentBlock::ImageUrl { image_url } => match parse_image_url(&image_url.url) {
+                ImageRef::Remote(url) => Some(url.to_string()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+/// 将 OpenAI 请求中的远程 image_url 替换为 data URL, 返回替换的数量
+pub async fn inline_openai_images(req: &mut OpenAIRequest, proxy: &UpstreamProxyConfig) -> usize {
+    let remote = remote_openai_image_urls(req);
+    if remote.is_empty() {
+        return 0;
+    }
+
+    let fetched = fetch_all(proxy, remote).await;
+    let mut replaced = 0;
+    for msg in req.messages.iter_mut() {
+        let Some(OpenAIContent::Array(parts)) = msg.content.as_mut() else { continue };
+        for part in parts.iter_mut() {
+            let OpenAIContentBlock::ImageUrl { image_url } = part else { continue };
+            if let Some((mime_type, data)) = fetched.get(image_url.url.trim()) {
+                image_url.url = format!("data:{};base64,{}", mime_type, data);
+                replaced += 1;
+            }
+        }
+    }
+    replaced
+}
+
+/// Anthropic 请求中 `source.type = "url"` 的图片
+pub fn remote_claude_image_urls(req: &ClaudeRequest) -> Vec<String> {
+    req.messages
+        .iter()
+        .filter_map(|msg| match &msg.content {
+            MessageContent::Array(blocks) => Some(blocks),
+            _ => None,
+        })
+        .flatten()
+        .filter_map(|block| match block {
+            ContentBlock::Image { source, .. } if source.source_type == "url" => source.url.clone(),
+            _ => None,
+        })
+        .collect()
+}
+
+/// 将 Anthropic 请求中的 URL 图片转为 base64, 返回替换的数量
+pub async fn inline_claude_images(req: &mut ClaudeRequest, proxy: &UpstreamProxyConfig) -> usize {
+    let remote = remote_claude_image_urls(req);
+    if remote.is_empty() {
+        return 0;
+    }
+
+    let fetched = fetch_all(proxy, remote).await;
+    let mut replaced = 0;
+    for msg in req.messages.iter_mut() {
+        let MessageContent::Array(blocks) = &mut msg.content else { continue };
+        for block in blocks.iter_mut() {
+            let ContentBlock::Image { source, .. } = block else { continue };
+            let Some((mime_type, data)) = source.url.as_ref().and_then(|url| fetched.get(url)) else { continue };
+            source.source_type = "base64".to_string();
+            source.media_type = mime_type.clone();
+            source.data = data.clone();
+            source.url = None;
+            replaced += 1;
+        }
+    }
+    replaced
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_image_url() {
+        assert_eq!(
+            parse_image_url("data:image/png;base64,AAAA"),
+            ImageRef::Inline { mime_type: "image/png".to_string(), data: "AAAA".to_string() }
+        );
+        assert_eq!(parse_image_url("https://example.com/a.png"), ImageRef::Remote("https://example.com/a.png"));
+
+        // 裸 base64 PNG
+        let png = base64::engine::general_purpose::STANDARD.encode(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR");
+        assert!(matches!(parse_image_url(&png), ImageRef::Inline { mime_type, .. } if mime_type == "image/png"));
+
+        #[cfg(not(target_os = "windows"))]
+        assert_eq!(parse_image_url("file:///tmp/a.jpg"), ImageRef::Local("/tmp/a.jpg".to_string()));
+    }
+
+    #[test]
+    fn test_mime_detection() {
+        assert_eq!(sniff_image_mime(b"GIF89a...."), Some("image/gif"));
+        assert_eq!(sniff_image_mime(b"RIFF\0\0\0\0WEBPVP8 "), Some("image/webp"));
+        assert_eq!(sniff_image_mime(b"<html>"), None);
+        assert_eq!(mime_from_path("https://x.io/cat.WEBP?size=2"), "image/webp");
+        assert_eq!(mime_from_path("/tmp/photo"), "image/jpeg");
+    }
+
+    #[test]
+    fn test_public_ip() {
+        for ip in ["127.0.0.1", "10.1.2.3", "172.16.0.1", "192.168.1.1", "169.254.169.254", "100.64.0.1", "0.0.0.0", "::1", "fd00::1", "fe80::1", "::ffff:127.0.0.1"] {
+            assert!(!is_public_ip(ip.parse().unwrap()), "{}", ip);
+        }
+        for ip in ["8.8.8.8", "1.1.1.1", "2606:4700::1111"] {
+            assert!(is_public_ip(ip.parse().unwrap()), "{}", ip);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fetch_rejects_internal_hosts() {
+        let proxy = UpstreamProxyConfig::default();
+        for url in ["http://127.0.0.1:8045/api/config", "http://localhost/a.png", "http://[::1]/a.png", "http://169.254.169.254/latest/meta-data"] {
+            let err = fetch_image(&proxy, url).await.unwrap_err();
+            assert!(err.contains("non-public"), "{}: {}", url, err);
+        }
+        assert!(fetch_image(&proxy, "ftp://example.com/a.png").await.is_err());
+    }
+}
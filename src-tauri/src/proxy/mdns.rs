@@ -0,0 +1,339 @@
+// 局域网 mDNS 服务发布
+// 允许局域网访问时把反代发布为 _aiolauncher._tcp.local (DNS-SD), 同一网络的设备可以自动发现地址与端口。
+// 只实现发布方需要的部分: 启动时宣告两次, 之后定期续期, 回应匹配的查询, 停止时发送 TTL=0 的告别包。
+
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::net::UdpSocket;
+use tokio::task::JoinHandle;
+
+use crate::proxy::config::MdnsConfig;
+
+const MDNS_ADDR: Ipv4Addr = Ipv4Addr::new(224, 0, 0, 251);
+const MDNS_PORT: u16 = 5353;
+const SERVICE_TYPE: [&str; 3] = ["_aiolauncher", "_tcp", "local"];
+const SERVICES_META: [&str; 4] = ["_services", "_dns-sd", "_udp", "local"];
+
+const TYPE_A: u16 = 1;
+const TYPE_PTR: u16 = 12;
+const TYPE_TXT: u16 = 16;
+const TYPE_SRV: u16 = 33;
+const CLASS_IN: u16 = 1;
+const CACHE_FLUSH: u16 = 0x8000;
+
+/// 主机相关记录 (SRV / A) 的 TTL, 其余记录使用 4500 秒 (RFC 6762 推荐值)
+const HOST_TTL: u32 = 120;
+const OTHER_TTL: u32 = 4500;
+const ANNOUNCE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// 发布的服务信息
+#[derive(Debug, Clone)]
+struct Service {
+    instance: String,
+    host: String,
+    ip: Ipv4Addr,
+    port: u16,
+    txt: Vec<String>,
+}
+
+impl Service {
+    fn instance_name(&self) -> Vec<&str> {
+        let mut labels = vec![self.instance.as_str()];
+        labels.extend(SERVICE_TYPE);
+        labels
+    }
+
+    fn host_name(&self) -> Vec<&str> {
+        vec![self.host.as_str(), "local"]
+    }
+
+    /// 查询的名称是否属于本服务
+    fn answers(&self, name: &[String]) -> bool {
+        let same = |labels: &[&str]| {
+            labels.len() == name.len() && labels.iter().zip(name).all(|(a, b)| a.eq_ignore_ascii_case(b))
+        };
+        same(&SERVICE_TYPE) || same(&SERVICES_META) || same(&self.instance_name()) || same(&self.host_name())
+    }
+
+    /// 完整的应答包; goodbye 时所有记录 TTL 为 0
+    fn response(&self, id: u16, goodbye: bool) -> Vec<u8> {
+        let ttl = |t: u32| if goodbye { 0 } else { t };
+        let instance = encode_name(&self.instance_name());
+        let host = encode_name(&self.host_name());
+
+        let mut srv = Vec::new();
+        srv.extend_from_slice(&0u16.to_be_bytes()); // priority
+        srv.extend_from_slice(&0u16.to_be_bytes()); // weight
+        srv.extend_from_slice(&self.port.to_be_bytes());
+        srv.extend_from_slice(&host);
+
+        let mut txt = Vec::new();
+        for entry in &self.txt {
+            let bytes = &entry.as_bytes()[..entry.len().min(255)];
+            txt.push(bytes.len() as u8);
+            txt.extend_from_slice(bytes);
+        }
+
+        let mut packet = Vec::with_capacity(512);
+        packet.extend_from_slice(&id.to_be_bytes());
+        packet.extend_from_slice(&0x8400u16.to_be_bytes()); // QR + AA
+        packet.extend_from_slice(&0u16.to_be_bytes()); // QDCOUNT
+        packet.extend_from_slice(&5u16.to_be_bytes()); // ANCOUNT
+        packet.extend_from_slice(&0u16.to_be_bytes()); // NSCOUNT
+        packet.extend_from_slice(&0u16.to_be_bytes()); // ARCOUNT
+        write_record(&mut packet, &encode_name(&SERVICES_META), TYPE_PTR, CLASS_IN, ttl(OTHER_TTL), &encode_name(&SERVICE_TYPE));
+        write_record(&mut packet, &encode_name(&SERVICE_TYPE), TYPE_PTR, CLASS_IN, ttl(OTHER_TTL), &instance);
+        write_record(&mut packet, &instance, TYPE_SRV, CLASS_IN | CACHE_FLUSH, ttl(HOST_TTL), &srv);
+        write_record(&mut packet, &instance, TYPE_TXT, CLASS_IN | CACHE_FLUSH, ttl(OTHER_TTL), &txt);
+        write_record(&mut packet, &host, TYPE_A, CLASS_IN | CACHE_FLUSH, ttl(HOST_TTL), &self.ip.octets());
+        packet
+    }
+}
+
+/// 编码域名 (不压缩); 单个标签最长 63 字节, 在字符边界截断
+fn encode_name(labels: &[&str]) -> Vec<u8> {
+    let mut out = Vec::new();
+    for label in labels.iter().filter(|l| !l.is_empty()) {
+        let mut end = label.len().min(63);
+        while !label.is_char_boundary(end) {
+            end -= 1;
+        }
+        out.push(end as u8);
+        out.extend_from_slice(&label.as_bytes()[..end]);
+    }
+    out.push(0);
+    out
+}
+
+fn write_record(packet: &mut Vec<u8>, name: &[u8], rtype: u16, class: u16, ttl: u32, rdata: &[u8]) {
+    packet.extend_from_slice(name);
+    packet.extend_from_slice(&rtype.to_be_bytes());
+    packet.extend_from_slice(&class.to_be_bytes());
+    packet.extend_from_slice(&ttl.to_be_bytes());
+    packet.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+    packet.extend_from_slice(rdata);
+}
+
+/// 读取域名 (支持压缩指针), 返回标签与名称之后的偏移
+fn read_name(packet: &[u8], mut pos: usize) -> Option<(Vec<String>, usize)> {
+    let mut labels = Vec::new();
+    let mut end = None;
+    for _ in 0..32 {
+        let len = *packet.get(pos)? as usize;
+        if len == 0 {
+            return Some((labels, end.unwrap_or(pos + 1)));
+        }
+        if len & 0xC0 == 0xC0 {
+            let target = ((len & 0x3F) << 8) | *packet.get(pos + 1)? as usize;
+            end.get_or_insert(pos + 2);
+            pos = target;
+            continue;
+        }
+        let label = packet.get(pos + 1..pos + 1 + len)?;
+        labels.push(String::from_utf8_lossy(label).into_owned());
+        pos += 1 + len;
+    }
+    None
+}
+
+/// 解析查询包中的问题: (名称, 是否要求单播应答)
+fn parse_query(packet: &[u8]) -> Option<(u16, Vec<(Vec<String>, bool)>)> {
+    if packet.len() < 12 {
+        return None;
+    }
+    let id = u16::from_be_bytes([packet[0], packet[1]]);
+    let flags = u16::from_be_bytes([packet[2], packet[3]]);
+    if flags & 0x8000 != 0 {
+        return None; // 应答包
+    }
+    let count = u16::from_be_bytes([packet[4], packet[5]]);
+    let mut pos = 12;
+    let mut questions = Vec::new();
+    for _ in 0..count {
+        let (name, next) = read_name(packet, pos)?;
+        let class = u16::from_be_bytes([*packet.get(next + 2)?, *packet.get(next + 3)?]);
+        questions.push((name, class & 0x8000 != 0));
+        pos = next + 4;
+    }
+    Some((id, questions))
+}
+
+/// 主机名转为合法的 DNS 标签
+fn host_label(raw: &str) -> String {
+    let label: String = raw
+        .split('.')
+        .next()
+        .unwrap_or("")
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '-' })
+        .collect();
+    let label = label.trim_matches('-');
+    if label.is_empty() { "aiolauncher".to_string() } else { label.to_string() }
+}
+
+/// 默认路由所在网卡的 IPv4 地址 (UDP connect 不会发送数据)
+fn local_ipv4() -> Result<Ipv4Addr, String> {
+    let socket = std::net::UdpSocket::bind("0.0.0.0:0").map_err(|e| e.to_string())?;
+    socket.connect((MDNS_ADDR, MDNS_PORT)).map_err(|e| e.to_string())?;
+    match socket.local_addr().map_err(|e| e.to_string())? {
+        SocketAddr::V4(addr) if !addr.ip().is_loopback() && !addr.ip().is_unspecified() => Ok(*addr.ip()),
+        other => Err(format!("no usable LAN address ({})", other)),
+    }
+}
+
+fn bind_socket(interface: Ipv4Addr) -> std::io::Result<UdpSocket> {
+    use socket2::{Domain, Protocol, Socket, Type};
+
+    // 与系统自带的 mDNS 服务 (Bonjour / Avahi) 共用 5353 端口
+    let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))?;
+    socket.set_reuse_address(true)?;
+    socket.bind(&SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, MDNS_PORT).into())?;
+    socket.join_multicast_v4(&MDNS_ADDR, &interface)?;
+    socket.set_multicast_if_v4(&interface)?;
+    socket.set_multicast_ttl_v4(255)?;
+    socket.set_multicast_loop_v4(true)?;
+    socket.set_nonblocking(true)?;
+    UdpSocket::from_std(socket.into())
+}
+
+async fn serve(socket: Arc<UdpSocket>, service: Arc<Service>) {
+    let group = SocketAddr::from((MDNS_ADDR, MDNS_PORT));
+    let announcement = service.response(0, false);
+    // 启动时间隔 1 秒宣告两次, 之后定期续期
+    let mut next_announce = tokio::time::Instant::now();
+    let mut announced = 0u32;
+    let mut buf = vec![0u8; 9000];
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep_until(next_announce) => {
+                if let Err(e) = socket.send_to(&announcement, group).await {
+                    tracing::debug!("[mDNS] announcement failed: {}", e);
+                }
+                announced += 1;
+                next_announce += if announced < 2 { Duration::from_secs(1) } else { ANNOUNCE_INTERVAL };
+            }
+            received = socket.recv_from(&mut buf) => {
+                let Ok((len, from)) = received else { continue };
+                let Some((id, questions)) = parse_query(&buf[..len]) else { continue };
+                let Some((_, unicast)) = questions.iter().find(|(name, _)| service.answers(name)) else { continue };
+                // 非 5353 端口的查询方 (传统 DNS 客户端) 只接受单播应答, 且需要带回查询 ID
+                let legacy = from.port() != MDNS_PORT;
+                let target = if legacy || *unicast { from } else { group };
+                let packet = if legacy { service.response(id, false) } else { announcement.clone() };
+                if let Err(e) = socket.send_to(&packet, target).await {
+                    tracing::debug!("[mDNS] response to {} failed: {}", from, e);
+                }
+            }
+        }
+    }
+}
+
+pub struct MdnsAdvertiser {
+    socket: Arc<UdpSocket>,
+    service: Arc<Service>,
+    task: JoinHandle<()>,
+}
+
+impl MdnsAdvertiser {
+    /// 开始发布监听在 port 上的服务
+    pub fn start(config: &MdnsConfig, port: u16, tls: bool) -> Result<Self, String> {
+        let ip = local_ipv4()?;
+        let host = host_label(&sysinfo::System::host_name().unwrap_or_default());
+        let instance = match config.service_name.trim() {
+            "" => format!("AIO Launcher ({})", host),
+            name => name.to_string(),
+        };
+        let service = Arc::new(Service {
+            instance,
+            host,
+            ip,
+            port,
+            txt: vec![
+                format!("version={}", env!("CARGO_PKG_VERSION")),
+                format!("scheme={}", if tls { "https" } else { "http" }),
+                "path=/v1".to_string(),
+            ],
+        });
+        let socket = Arc::new(bind_socket(ip).map_err(|e| format!("mDNS socket: {}", e))?);
+        tracing::info!(
+            "[mDNS] advertising '{}' as {}.local ({}:{})",
+            service.instance,
+            service.host,
+            service.ip,
+            service.port
+        );
+        let task = tokio::spawn(serve(socket.clone(), service.clone()));
+        Ok(Self { socket, service, task })
+    }
+
+    /// 停止发布并通知其他设备移除缓存
+    pub async fn stop(self) {
+        self.task.abort();
+        let goodbye = self.service.response(0, true);
+        if let Err(e) = self.socket.send_to(&goodbye, (MDNS_ADDR, MDNS_PORT)).await {
+            tracing::debug!("[mDNS] goodbye failed: {}", e);
+        }
+        tracing::info!("[mDNS] stopped advertising '{}'", self.service.instance);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn service() -> Service {
+        Service {
+            instance: "Office Proxy".to_string(),
+            host: host_label("Dev-Box.lan"),
+            ip: Ipv4Addr::new(192, 168, 1, 20),
+            port: 8045,
+            txt: vec!["path=/v1".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_query_matching() {
+        let service = service();
+        assert_eq!(service.host, "dev-box");
+
+        // 查询 PTR _aiolauncher._tcp.local, 第二个问题用压缩指针指向第一个名称
+        let mut query = vec![0x12, 0x34, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0];
+        query.extend(encode_name(&SERVICE_TYPE));
+        query.extend_from_slice(&TYPE_PTR.to_be_bytes());
+        query.extend_from_slice(&(CLASS_IN | 0x8000).to_be_bytes());
+        query.extend_from_slice(&[0xC0, 12]);
+        query.extend_from_slice(&TYPE_PTR.to_be_bytes());
+        query.extend_from_slice(&CLASS_IN.to_be_bytes());
+
+        let (id, questions) = parse_query(&query).unwrap();
+        assert_eq!(id, 0x1234);
+        assert_eq!(questions.len(), 2);
+        assert!(questions[0].1 && !questions[1].1);
+        assert!(service.answers(&questions[1].0));
+        assert!(!service.answers(&["_http".to_string(), "_tcp".to_string(), "local".to_string()]));
+        assert!(service.answers(&["DEV-BOX".to_string(), "local".to_string()]));
+    }
+
+    #[test]
+    fn test_response_records() {
+        let service = service();
+        let packet = service.response(7, false);
+        assert_eq!(&packet[..2], &7u16.to_be_bytes());
+        assert_eq!(u16::from_be_bytes([packet[6], packet[7]]), 5);
+        // 应答包不会被当作查询处理
+        assert!(parse_query(&packet).is_none());
+        // 第一条记录: _services._dns-sd._udp.local PTR
+        let (name, pos) = read_name(&packet, 12).unwrap();
+        assert_eq!(name, SERVICES_META);
+        assert_eq!(u16::from_be_bytes([packet[pos], packet[pos + 1]]), TYPE_PTR);
+        assert_eq!(&packet[pos + 4..pos + 8], &OTHER_TTL.to_be_bytes());
+        assert!(packet.ends_with(&[192, 168, 1, 20]));
+
+        let goodbye = service.response(0, true);
+        let (_, pos) = read_name(&goodbye, 12).unwrap();
+        assert_eq!(&goodbye[pos + 4..pos + 8], &0u32.to_be_bytes());
+        assert_eq!(encode_name(&["é".repeat(40).as_str()])[0], 62);
+    }
+}
@@ -0,0 +1,248 @@
+// Prometheus 指标
+// 由 monitor 中间件记录, 通过 `/metrics` 以 Prometheus 文本格式导出。
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::proxy::upstream::concurrency::QueueStatus;
+use crate::proxy::upstream::routing::EndpointStatus;
+
+/// 直方图分桶上界 (秒)
+const LATENCY_BUCKETS: [f64; 11] = [0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 20.0, 30.0, 60.0, 120.0];
+
+#[derive(Debug, Clone, Default)]
+struct Histogram {
+    /// 每个分桶的计数 (非累计)
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, seconds: f64) {
+        if let Some(i) = LATENCY_BUCKETS.iter().position(|le| seconds <= *le) {
+            self.buckets[i] += 1;
+        }
+        self.sum += seconds;
+        self.count += 1;
+    }
+
+    fn render(&self, out: &mut String, name: &str, labels: &str) {
+        let mut cumulative = 0;
+        for (le, n) in LATENCY_BUCKETS.iter().zip(self.buckets.iter()) {
+            cumulative += n;
+            let _ = writeln!(out, "{}_bucket{{{},le=\"{}\"}} {}", name, labels, le, cumulative);
+        }
+        let _ = writeln!(out, "{}_bucket{{{},le=\"+Inf\"}} {}", name, labels, self.count);
+        let _ = writeln!(out, "{}_sum{{{}}} {}", name, labels, self.sum);
+        let _ = writeln!(out, "{}_count{{{}}} {}", name, labels, self.count);
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[derive(Default)]
+pub struct ProxyMetrics {
+    /// (model, status) -> 请求数
+    requests: Mutex<BTreeMap<(String, u16), u64>>,
+    latency: Mutex<BTreeMap<String, Histogram>>,
+    ttft: Mutex<BTreeMap<String, Histogram>>,
+    /// upstream -> 排队等待时间
+    queue_wait: Mutex<BTreeMap<String, Histogram>>,
+    active_streams: AtomicI64,
+}
+
+/// 活动流的登记, 释放时减少计数
+pub struct ActiveStreamGuard<'a>(&'a ProxyMetrics);
+
+impl Drop for ActiveStreamGuard<'_> {
+    fn drop(&mut self) {
+        self.0.active_streams.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl ProxyMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record_request(&self, model: &str, status: u16, latency: Duration) {
+        *self
+            .requests
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry((model.to_string(), status))
+            .or_default() += 1;
+        self.latency
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(model.to_string())
+            .or_default()
+            .observe(latency.as_secs_f64());
+    }
+
+    pub fn record_ttft(&self, model: &str, ttft: Duration) {
+        self.ttft
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(model.to_string())
+            .or_default()
+            .observe(ttft.as_secs_f64());
+    }
+
+    pub fn record_queue_wait(&self, upstream: &str, wait: Duration) {
+        self.queue_wait
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(upstream.to_string())
+            .or_default()
+            .observe(wait.as_secs_f64());
+    }
+
+    pub fn stream_started(&self) -> ActiveStreamGuard<'_> {
+        self.active_streams.fetch_add(1, Ordering::Relaxed);
+        ActiveStreamGuard(self)
+    }
+
+    /// 导出 Prometheus 文本格式
+    pub fn render(&self, upstreams: &[EndpointStatus], queues: &[QueueStatus]) -> String {
+        let mut out = String::new();
+
+        out.push_str("# HELP aiolauncher_requests_total Proxied requests by model and status.\n");
+        out.push_str("# TYPE aiolauncher_requests_total counter\n");
+        for ((model, status), n) in self.requests.lock().unwrap_or_else(|e| e.into_inner()).iter() {
+            let _ = writeln!(
+                out,
+                "aiolauncher_requests_total{{model=\"{}\",status=\"{}\"}} {}",
+                escape_label(model),
+                status,
+                n
+            );
+        }
+
+        out.push_str("# HELP aiolauncher_request_duration_seconds Request latency by model.\n");
+        out.push_str("# TYPE aiolauncher_request_duration_seconds histogram\n");
+        for (model, h) in self.latency.lock().unwrap_or_else(|e| e.into_inner()).iter() {
+            h.render(&mut out, "aiolauncher_request_duration_seconds", &format!("model=\"{}\"", escape_label(model)));
+        }
+
+        out.push_str("# HELP aiolauncher_time_to_first_token_seconds Time to the first streamed chunk by model.\n");
+        out.push_str("# TYPE aiolauncher_time_to_first_token_seconds histogram\n");
+        for (model, h) in self.ttft.lock().unwrap_or_else(|e| e.into_inner()).iter() {
+            h.render(&mut out, "aiolauncher_time_to_first_token_seconds", &format!("model=\"{}\"", escape_label(model)));
+        }
+
+        out.push_str("# HELP aiolauncher_queue_wait_seconds Time spent waiting for an upstream concurrency slot.\n");
+        out.push_str("# TYPE aiolauncher_queue_wait_seconds histogram\n");
+        for (upstream, h) in self.queue_wait.lock().unwrap_or_else(|e| e.into_inner()).iter() {
+            h.render(&mut out, "aiolauncher_queue_wait_seconds", &format!("upstream=\"{}\"", escape_label(upstream)));
+        }
+
+        out.push_str("# HELP aiolauncher_queue_depth Requests waiting for an upstream concurrency slot.\n");
+        out.push_str("# TYPE aiolauncher_queue_depth gauge\n");
+        for queue in queues {
+            let _ = writeln!(out, "aiolauncher_queue_depth{{upstream=\"{}\"}} {}", escape_label(&queue.upstream), queue.queued);
+        }
+        out.push_str("# HELP aiolauncher_upstream_in_flight Requests currently holding an upstream concurrency slot.\n");
+        out.push_str("# TYPE aiolauncher_upstream_in_flight gauge\n");
+        for queue in queues {
+            let _ = writeln!(
+                out,
+                "aiolauncher_upstream_in_flight{{upstream=\"{}\"}} {}",
+                escape_label(&queue.upstream),
+                queue.in_flight
+            );
+        }
+
+        out.push_str("# HELP aiolauncher_active_streams Streaming responses in flight.\n");
+        out.push_str("# TYPE aiolauncher_active_streams gauge\n");
+        let _ = writeln!(out, "aiolauncher_active_streams {}", self.active_streams.load(Ordering::Relaxed));
+
+        out.push_str("# HELP aiolauncher_upstream_healthy Whether the upstream endpoint is currently healthy (1) or cooling down (0).\n");
+        out.push_str("# TYPE aiolauncher_upstream_healthy gauge\n");
+        for upstream in upstreams {
+            let _ = writeln!(
+                out,
+                "aiolauncher_upstream_healthy{{endpoint=\"{}\"}} {}",
+                escape_label(&upstream.endpoint),
+                u8::from(upstream.healthy)
+            );
+        }
+        out.push_str("# HELP aiolauncher_upstream_circuit_state Circuit breaker state per upstream endpoint (0 closed, 1 half-open, 2 open).\n");
+        out.push_str("# TYPE aiolauncher_upstream_circuit_state gauge\n");
+        for upstream in upstreams {
+            let _ = writeln!(
+                out,
+                "aiolauncher_upstream_circuit_state{{endpoint=\"{}\"}} {}",
+                escape_label(&upstream.endpoint),
+                upstream.state as u8
+            );
+        }
+        out.push_str("# HELP aiolauncher_upstream_consecutive_failures Consecutive failures per upstream endpoint.\n");
+        out.push_str("# TYPE aiolauncher_upstream_consecutive_failures gauge\n");
+        for upstream in upstreams {
+            let _ = writeln!(
+                out,
+                "aiolauncher_upstream_consecutive_failures{{endpoint=\"{}\"}} {}",
+                escape_label(&upstream.endpoint),
+                upstream.consecutive_failures
+            );
+        }
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy::upstream::routing::CircuitState;
+
+    #[test]
+    fn test_render_prometheus_text() {
+        let metrics = ProxyMetrics::new();
+        metrics.record_request("gemini-2.5-flash", 200, Duration::from_millis(300));
+        metrics.record_request("gemini-2.5-flash", 200, Duration::from_secs(3));
+        metrics.record_request("a\"b", 429, Duration::from_millis(50));
+        metrics.record_ttft("gemini-2.5-flash", Duration::from_millis(200));
+        metrics.record_queue_wait("google", Duration::from_millis(800));
+        let guard = metrics.stream_started();
+
+        let text = metrics.render(
+            &[EndpointStatus {
+                endpoint: "https://example.com/v1internal".to_string(),
+                healthy: false,
+                consecutive_failures: 3,
+                state: CircuitState::Open,
+            }],
+            &[QueueStatus {
+                upstream: "google".to_string(),
+                max_concurrent: 4,
+                max_queue: 16,
+                in_flight: 4,
+                queued: 3,
+                avg_wait_ms: 800.0,
+                last_wait_ms: 800,
+            }],
+        );
+        assert!(text.contains("aiolauncher_requests_total{model=\"gemini-2.5-flash\",status=\"200\"} 2"));
+        assert!(text.contains("aiolauncher_requests_total{model=\"a\\\"b\",status=\"429\"} 1"));
+        assert!(text.contains("aiolauncher_request_duration_seconds_bucket{model=\"gemini-2.5-flash\",le=\"0.5\"} 1"));
+        assert!(text.contains("aiolauncher_request_duration_seconds_bucket{model=\"gemini-2.5-flash\",le=\"+Inf\"} 2"));
+        assert!(text.contains("aiolauncher_time_to_first_token_seconds_count{model=\"gemini-2.5-flash\"} 1"));
+        assert!(text.contains("aiolauncher_active_streams 1"));
+        assert!(text.contains("aiolauncher_upstream_healthy{endpoint=\"https://example.com/v1internal\"} 0"));
+        assert!(text.contains("aiolauncher_upstream_circuit_state{endpoint=\"https://example.com/v1internal\"} 2"));
+        assert!(text.contains("aiolauncher_queue_wait_seconds_bucket{upstream=\"google\",le=\"1\"} 1"));
+        assert!(text.contains("aiolauncher_queue_depth{upstream=\"google\"} 3"));
+        assert!(text.contains("aiolauncher_upstream_in_flight{upstream=\"google\"} 4"));
+
+        drop(guard);
+        assert!(metrics.render(&[], &[]).contains("aiolauncher_active_streams 0"));
+    }
+}
@@ -0,0 +1,202 @@
+// IP 访问控制
+// 每个监听按管理接口 / 推理接口分别匹配 CIDR 规则: deny 优先, allow 非空时只放行列表中的地址。
+// 本机地址不受 allow 限制 (避免把桌面端和本机隧道挡在外面), 但仍可被 deny 拒绝。
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, RwLock};
+
+use axum::{
+    extract::{Request, State},
+    http::{Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use ipnet::IpNet;
+use serde_json::json;
+
+use crate::proxy::config::{AccessControlConfig, AccessRules, ListenerConfig};
+use crate::proxy::middleware::listener::is_admin_path;
+use crate::proxy::middleware::rate_limit::client_ip;
+
+/// 主监听在规则表中的名称
+pub const MAIN_LISTENER: &str = "";
+
+/// 解析单个规则: CIDR (`10.0.0.0/8`) 或单个地址
+fn parse_entry(entry: &str) -> Result<IpNet, String> {
+    let entry = entry.trim();
+    entry
+        .parse::<IpNet>()
+        .or_else(|_| entry.parse::<IpAddr>().map(IpNet::from))
+        .map_err(|_| format!("Invalid IP or CIDR '{}'", entry))
+}
+
+/// 校验规则列表, 返回第一个无效项的错误
+pub fn validate_rules(rules: &AccessRules) -> Result<(), String> {
+    rules.allow.iter().chain(&rules.deny).try_for_each(|e| parse_entry(e).map(|_| ()))
+}
+
+#[derive(Debug, Default, Clone)]
+struct CompiledRules {
+    allow: Vec<IpNet>,
+    deny: Vec<IpNet>,
+}
+
+impl CompiledRules {
+    /// 无效项在保存时已被拒绝, 这里跳过 (例如手动编辑的配置文件)
+    fn compile(rules: &AccessRules) -> Self {
+        let parse = |entries: &[String]| entries.iter().filter_map(|e| parse_entry(e).ok()).collect();
+        Self { allow: parse(&rules.allow), deny: parse(&rules.deny) }
+    }
+
+    fn permits(&self, ip: IpAddr) -> bool {
+        let ip = ip.to_canonical();
+        if self.deny.iter().any(|net| net.contains(&ip)) {
+            return false;
+        }
+        self.allow.is_empty() || ip.is_loopback() || self.allow.iter().any(|net| net.contains(&ip))
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+struct ListenerRules {
+    admin: CompiledRules,
+    inference: CompiledRules,
+}
+
+impl ListenerRules {
+    fn compile(config: &AccessControlConfig) -> Self {
+        Self {
+            admin: CompiledRules::compile(&config.admin),
+            inference: CompiledRules::compile(&config.inference),
+        }
+    }
+}
+
+/// 各监听的访问规则 (支持热更新)
+#[derive(Default)]
+pub struct AccessControl {
+    listeners: RwLock<HashMap<String, ListenerRules>>,
+}
+
+impl AccessControl {
+    pub fn new(main: &AccessControlConfig, listeners: &[ListenerConfig]) -> Self {
+        let control = Self::default();
+        control.update_config(main, listeners);
+        control
+    }
+
+    /// 热更新规则; 额外监听未单独设置时使用主监听的规则
+    pub fn update_config(&self, main: &AccessControlConfig, extra: &[ListenerConfig]) {
+        let mut listeners = HashMap::new();
+        listeners.insert(MAIN_LISTENER.to_string(), ListenerRules::compile(main));
+        for listener in extra {
+            let rules = listener.access_control.as_ref().unwrap_or(main);
+            listeners.insert(listener.label(), ListenerRules::compile(rules));
+        }
+        *self.listeners.write().unwrap_or_else(|e| e.into_inner()) = listeners;
+    }
+
+    /// 未知的监听与无法识别的地址按主监听规则处理
+    pub fn permits(&self, listener: &str, ip: IpAddr, admin: bool) -> bool {
+        let listeners = self.listeners.read().unwrap_or_else(|e| e.into_inner());
+        let Some(rules) = listeners.get(listener).or_else(|| listeners.get(MAIN_LISTENER)) else {
+            return true;
+        };
+        if admin {
+            rules.admin.permits(ip)
+        } else {
+            rules.inference.permits(ip)
+        }
+    }
+}
+
+/// 挂在单个监听上的访问控制
+pub struct AccessGate {
+    pub listener: String,
+    pub control: Arc<AccessControl>,
+}
+
+pub async fn access_control_middleware(State(gate): State<Arc<AccessGate>>, request: Request, next: Next) -> Response {
+    if request.method() == Method::OPTIONS {
+        return next.run(request).await;
+    }
+    let Some(ip) = client_ip(&request) else {
+        return next.run(request).await;
+    };
+    let admin = is_admin_path(request.uri().path());
+    if gate.control.permits(&gate.listener, ip, admin) {
+        return next.run(request).await;
+    }
+    tracing::warn!(
+        "[AccessControl] Blocked {} from {} ({} API)",
+        request.uri().path(),
+        ip,
+        if admin { "admin" } else { "inference" }
+    );
+    let body = json!({
+        "error": {
+            "message": format!("Access from {} is not allowed", ip),
+            "type": "invalid_request_error",
+            "param": null,
+            "code": "access_denied"
+        }
+    });
+    (StatusCode::FORBIDDEN, axum::Json(body)).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy::config::ProxyConfig;
+
+    fn rules(allow: &[&str], deny: &[&str]) -> AccessRules {
+        AccessRules {
+            allow: allow.iter().map(|s| s.to_string()).collect(),
+            deny: deny.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_allow_and_deny_rules() {
+        let compiled = CompiledRules::compile(&rules(&["192.168.1.0/24", "2001:db8::/32"], &["192.168.1.13"]));
+        let ip = |s: &str| s.parse::<IpAddr>().unwrap();
+        assert!(compiled.permits(ip("192.168.1.20")));
+        assert!(!compiled.permits(ip("192.168.1.13")));
+        assert!(!compiled.permits(ip("10.0.0.5")));
+        assert!(compiled.permits(ip("2001:db8::1")));
+        assert!(compiled.permits(ip("::ffff:192.168.1.20")));
+        // 本机不受 allow 限制, 但 deny 仍然生效
+        assert!(compiled.permits(ip("127.0.0.1")));
+        assert!(!CompiledRules::compile(&rules(&[], &["127.0.0.0/8"])).permits(ip("127.0.0.1")));
+        assert!(CompiledRules::compile(&AccessRules::default()).permits(ip("203.0.113.9")));
+
+        assert!(validate_rules(&rules(&["10.0.0.0/8", "::1"], &[])).is_ok());
+        assert_eq!(validate_rules(&rules(&[], &["10.0.0.0/33"])).unwrap_err(), "Invalid IP or CIDR '10.0.0.0/33'");
+    }
+
+    #[test]
+    fn test_per_listener_scopes() {
+        let mut config = ProxyConfig::default();
+        config.access_control.admin = rules(&["10.0.0.0/8"], &[]);
+        config.listeners = vec![ListenerConfig {
+            enabled: true,
+            name: "lan".to_string(),
+            host: "0.0.0.0".to_string(),
+            port: 9000,
+            require_auth: true,
+            allow_admin: false,
+            tls: false,
+            access_control: Some(AccessControlConfig { admin: AccessRules::default(), inference: rules(&[], &["10.0.0.7"]) }),
+        }];
+        let control = AccessControl::new(&config.access_control, &config.listeners);
+        let ip = |s: &str| s.parse::<IpAddr>().unwrap();
+
+        assert!(!control.permits(MAIN_LISTENER, ip("192.168.1.5"), true));
+        assert!(control.permits(MAIN_LISTENER, ip("192.168.1.5"), false));
+        assert!(control.permits(MAIN_LISTENER, ip("10.1.2.3"), true));
+        assert!(control.permits("lan", ip("192.168.1.5"), true));
+        assert!(!control.permits("lan", ip("10.0.0.7"), false));
+        assert!(!control.permits("unknown", ip("192.168.1.5"), true));
+    }
+}
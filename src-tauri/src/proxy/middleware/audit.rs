@@ -0,0 +1,108 @@
+// 管理接口审计中间件
+// 位于 admin_auth 之内, 只记录通过鉴权的变更请求 (POST / PUT / PATCH / DELETE), 附带配置前后差异。
+
+use std::net::SocketAddr;
+
+use axum::{
+    extract::{ConnectInfo, MatchedPath, Request},
+    http::Method,
+    middleware::Next,
+    response::Response,
+};
+
+use crate::modules::audit::{self, AuditEvent};
+use crate::proxy::security::AdminIdentity;
+
+/// 不修改任何状态的 POST 接口
+const READ_ONLY_ROUTES: &[&str] = &[
+    "/api/config/validate",
+    "/api/proxy/cli/status",
+    "/api/proxy/cli/config",
+    "/api/zai/models/fetch",
+    "/api/accounts/device-preview",
+    "/api/accounts/oauth/prepare",
+    "/api/system/open-folder",
+    "/api/system/updates/check",
+];
+
+/// 管理接口路由对应的审计操作名, 未列出的路由记录为 `admin.<method>`
+fn action_for(method: &Method, route: &str) -> String {
+    let action = match (method, route) {
+        (_, "/api/config" | "/api/proxy/config" | "/api/proxy/config/:section" | "/api/proxy/mapping") => "config.update",
+        (_, "/api/system/http-api/settings" | "/api/system/updates/save") => "config.update",
+        (_, "/api/proxy/start") => "service.start",
+        (_, "/api/proxy/stop") => "service.stop",
+        (_, "/api/proxy/api-key/generate") => "api_key.regenerate",
+        (_, "/api/proxy/rate-limits" | "/api/proxy/rate-limits/:accountId") => "rate_limit.clear",
+        (_, "/api/proxy/preferred-account") => "account.set_preferred",
+        (&Method::POST, "/api/accounts") => "account.add",
+        (&Method::DELETE, "/api/accounts/:accountId") | (_, "/api/accounts/bulk-delete") => "account.delete",
+        (_, "/api/accounts/switch") => "account.switch",
+        (_, "/api/accounts/:accountId/toggle-proxy") => "account.toggle_proxy",
+        (_, "/api/logs/clear" | "/api/stats/token/clear") => "logs.clear",
+        _ => return format!("admin.{}", method.as_str().to_ascii_lowercase()),
+    };
+    action.to_string()
+}
+
+/// 读取配置快照 (同步读文件, 放到阻塞线程池执行)
+async fn config_snapshot() -> Option<serde_json::Value> {
+    tokio::task::spawn_blocking(audit::config_snapshot).await.ok().flatten()
+}
+
+pub async fn audit_middleware(request: Request, next: Next) -> Response {
+    let method = request.method().clone();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|p| p.as_str().to_string())
+        .unwrap_or_else(|| request.uri().path().to_string());
+    let mutating = matches!(method, Method::POST | Method::PUT | Method::PATCH | Method::DELETE);
+    if !mutating || READ_ONLY_ROUTES.contains(&route.as_str()) {
+        return next.run(request).await;
+    }
+
+    let ip = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    let name = request
+        .extensions()
+        .get::<AdminIdentity>()
+        .map(|identity| identity.name.clone())
+        .unwrap_or_else(|| "admin".to_string());
+    let target = request.uri().path().to_string();
+    let before = config_snapshot().await;
+
+    let response = next.run(request).await;
+
+    let changes = match (before, config_snapshot().await) {
+        (Some(before), Some(after)) => audit::diff(&before, &after),
+        _ => Vec::new(),
+    };
+    let event = AuditEvent {
+        actor: format!("{}@{}", name, ip),
+        source: "admin_api",
+        action: action_for(&method, &route),
+        target,
+        status: Some(response.status().as_u16()),
+        changes,
+        detail: None,
+    };
+    tokio::task::spawn_blocking(move || audit::record(event));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_action_names() {
+        assert_eq!(action_for(&Method::POST, "/api/config"), "config.update");
+        assert_eq!(action_for(&Method::PUT, "/api/proxy/config/:section"), "config.update");
+        assert_eq!(action_for(&Method::DELETE, "/api/accounts/:accountId"), "account.delete");
+        assert_eq!(action_for(&Method::POST, "/api/accounts/reorder"), "admin.post");
+    }
+}
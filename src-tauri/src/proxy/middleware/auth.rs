@@ -1,14 +1,17 @@
 // API Key 认证中间件
 use axum::{
     extract::State,
-    extract::Request,
-    http::{header, StatusCode},
+    extract::{MatchedPath, Request},
+    http::{header, HeaderMap, StatusCode},
     middleware::Next,
-    response::Response,
+    response::{IntoResponse, Response},
 };
+use serde_json::json;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
+use crate::proxy::middleware::monitor::RequestedModel;
+use crate::proxy::virtual_keys::{RequestModel, VirtualKeyError, VirtualKeyStore};
 use crate::proxy::{ProxyAuthMode, ProxySecurityConfig};
 
 /// API Key 认证中间件 (代理接口使用，遵循 auth_mode)
@@ -29,6 +32,90 @@ pub async fn admin_auth_middleware(
     auth_middleware_internal(state, request, next, true).await
 }
 
+/// 从请求头提取客户端 API Key (Authorization: Bearer / x-api-key / x-goog-api-key)
+pub(crate) fn extract_api_key(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|s| s.strip_prefix("Bearer ").or(Some(s)))
+        .or_else(|| headers.get("x-api-key").and_then(|h| h.to_str().ok()))
+        .or_else(|| headers.get("x-goog-api-key").and_then(|h| h.to_str().ok()))
+}
+
+/// API Key 指纹 (SHA-256 前 16 位), 用于在内存和统计中代替明文 Key; 已校验的 JWT 按 subject 计算
+pub(crate) fn api_key_fingerprint(key: &str) -> String {
+    use sha2::{Digest, Sha256};
+    let subject = crate::proxy::jwt_auth::verified_subject(key).map(|s| format!("jwt:{}", s));
+    let hash = format!("{:x}", Sha256::digest(subject.as_deref().unwrap_or(key).as_bytes()));
+    hash[..16].to_string()
+}
+
+/// 未提供 Key 的客户端共用的所有者
+pub(crate) const ANONYMOUS_OWNER: &str = "anonymous";
+
+/// 按客户端隔离的本地资源 (批次、文件) 的所有者: Key 指纹, 不同 Key 的资源互不可见
+pub(crate) fn key_owner(api_key: Option<&str>) -> String {
+    api_key
+        .filter(|k| !k.is_empty())
+        .map(api_key_fingerprint)
+        .unwrap_or_else(|| ANONYMOUS_OWNER.to_string())
+}
+
+/// 统计中展示的 Key 掩码, 已校验的 JWT 显示为 `jwt:<subject>`
+pub(crate) fn api_key_hint(key: &str) -> String {
+    match crate::proxy::jwt_auth::verified_subject(key) {
+        Some(subject) => format!("jwt:{}", subject),
+        None => crate::modules::usage::mask_api_key(key),
+    }
+}
+
+fn jwt_error_response(message: String) -> Response {
+    let body = json!({
+        "error": {
+            "message": message,
+            "type": "invalid_request_error",
+            "param": null,
+            "code": "invalid_api_key"
+        }
+    });
+    (StatusCode::UNAUTHORIZED, axum::Json(body)).into_response()
+}
+
+fn virtual_key_error_response(error: &VirtualKeyError) -> Response {
+    let (status, code) = match error {
+        VirtualKeyError::Revoked | VirtualKeyError::Expired => (StatusCode::UNAUTHORIZED, "invalid_api_key"),
+        VirtualKeyError::ModelNotAllowed(_) | VirtualKeyError::ModelUnknown => (StatusCode::FORBIDDEN, "model_not_allowed"),
+        VirtualKeyError::RequestQuotaExceeded(_) | VirtualKeyError::TokenQuotaExceeded(_) => {
+            (StatusCode::TOO_MANY_REQUESTS, "insufficient_quota")
+        }
+        VirtualKeyError::SpendLimitExceeded(_) => (StatusCode::PAYMENT_REQUIRED, "spend_limit_exceeded"),
+    };
+    let body = json!({
+        "error": {
+            "message": error.message(),
+            "type": "invalid_request_error",
+            "param": null,
+            "code": code
+        }
+    });
+    (status, axum::Json(body)).into_response()
+}
+
+fn notify_quota_exhausted(key: &str, error: &VirtualKeyError) {
+    let name = VirtualKeyStore::global().identify(key).map(|grant| grant.name).unwrap_or_default();
+    crate::modules::notifications::notify(
+        crate::modules::notifications::NotificationKind::QuotaExhausted,
+        &name,
+        "Virtual key quota exhausted",
+        &format!("Virtual key '{}': {}", name, error.message()),
+    );
+}
+
+/// 不调用模型的接口: 限制了可用模型的虚拟 Key 同样可以访问
+fn is_model_free(method: &axum::http::Method, path: &str) -> bool {
+    method == axum::http::Method::GET && matches!(path, "/v1/models" | "/v1beta/models" | "/healthz" | "/health")
+}
+
 /// 内部认证逻辑
 async fn auth_middleware_internal(
     State(security): State<Arc<RwLock<ProxySecurityConfig>>>,
@@ -52,6 +139,59 @@ async fn auth_middleware_internal(
         return Ok(next.run(request).await);
     }
 
+    // 虚拟 Key: 按其自身的过期时间、可用模型和额度校验, 并绑定上游账号
+    if !force_strict {
+        let virtual_key = extract_api_key(request.headers())
+            .filter(|k| !k.is_empty())
+            .map(str::to_string);
+        if let Some(key) = virtual_key {
+            let requested = request.extensions().get::<RequestedModel>().map(|m| m.0.clone());
+            let model = match &requested {
+                Some(model) => RequestModel::Named(model),
+                None if is_model_free(&method, &path) => RequestModel::NotApplicable,
+                None => RequestModel::Unknown,
+            };
+            // JWT: 校验签名与声明后按 subject 应用模型限制和额度
+            if crate::proxy::jwt_auth::looks_like_jwt(&key) {
+                let identity = match crate::proxy::jwt_auth::verify(&key).await {
+                    Ok(identity) => identity,
+                    Err(e) => {
+                        tracing::warn!("[JwtAuth] Request rejected: {}", e);
+                        return Ok(jwt_error_response(e));
+                    }
+                };
+                return match crate::proxy::jwt_auth::authorize(&identity, model) {
+                    Ok(grant) => {
+                        tracing::debug!("[JwtAuth] Request authorized for subject '{}'", identity.subject);
+                        Ok(crate::proxy::virtual_keys::with_upstream_account(grant.upstream_account, next.run(request)).await)
+                    }
+                    Err(e) => {
+                        tracing::warn!("[JwtAuth] Subject '{}' rejected: {}", identity.subject, e.message());
+                        Ok(virtual_key_error_response(&e))
+                    }
+                };
+            }
+            match VirtualKeyStore::global().authorize(&key, model) {
+                Some(Ok(grant)) => {
+                    tracing::debug!("[VirtualKeys] Request authorized by key '{}'", grant.name);
+                    return Ok(crate::proxy::virtual_keys::with_upstream_account(
+                        grant.upstream_account,
+                        next.run(request),
+                    )
+                    .await);
+                }
+                Some(Err(e)) => {
+                    tracing::warn!("[VirtualKeys] Request rejected: {}", e.message());
+                    if e.is_exhausted() {
+                        notify_quota_exhausted(&key, &e);
+                    }
+                    return Ok(virtual_key_error_response(&e));
+                }
+                None => {}
+            }
+        }
+    }
+
     let security = security.read().await.clone();
     let effective_mode = security.effective_auth_mode();
 
@@ -79,49 +219,38 @@ async fn auth_middleware_internal(
     }
     
     // 从 header 中提取 API key
-    let api_key = request
-        .headers()
-        .get(header::AUTHORIZATION)
-        .and_then(|h| h.to_str().ok())
-        .and_then(|s| s.strip_prefix("Bearer ").or(Some(s)))
-        .or_else(|| {
-            request
-                .headers()
-                .get("x-api-key")
-                .and_then(|h| h.to_str().ok())
-        })
-        .or_else(|| {
-            request
-                .headers()
-                .get("x-goog-api-key")
-                .and_then(|h| h.to_str().ok())
-        });
-
-    if security.api_key.is_empty() && (security.admin_password.is_none() || security.admin_password.as_ref().unwrap().is_empty()) {
-        if force_strict {
-             tracing::error!("Admin auth is required but both api_key and admin_password are empty; denying request");
-             return Err(StatusCode::UNAUTHORIZED);
+    let api_key = extract_api_key(request.headers());
+
+    if force_strict {
+        if !security.has_admin_credentials() {
+            tracing::error!("Admin auth is required but api_key, admin_password and admin_tokens are all empty; denying request");
+            return Err(StatusCode::UNAUTHORIZED);
+        }
+        // 管理接口：admin_password (未设置时回退 api_key) 为 admin, admin_tokens 按角色限制可用接口
+        let Some(identity) = api_key.and_then(|k| security.identify_admin(k)) else {
+            return Err(StatusCode::UNAUTHORIZED);
+        };
+        let route = request
+            .extensions()
+            .get::<MatchedPath>()
+            .map(|p| p.as_str().to_string())
+            .unwrap_or_else(|| path.clone());
+        if !identity.role.permits(&method, &route) {
+            tracing::warn!("[AdminAuth] {} ({:?}) is not allowed to {} {}", identity.name, identity.role, method, route);
+            return Err(StatusCode::FORBIDDEN);
         }
+        let mut request = request;
+        request.extensions_mut().insert(identity);
+        return Ok(next.run(request).await);
+    }
+
+    if security.api_key.is_empty() {
         tracing::error!("Proxy auth is enabled but api_key is empty; denying request");
         return Err(StatusCode::UNAUTHORIZED);
     }
 
-    // 认证逻辑
-    let authorized = if force_strict {
-        // 管理接口：优先使用独立的 admin_password，如果没有则回退使用 api_key
-        match &security.admin_password {
-            Some(pwd) if !pwd.is_empty() => {
-                api_key.map(|k| k == pwd).unwrap_or(false)
-            }
-            _ => {
-                // 回退使用 api_key
-                api_key.map(|k| k == security.api_key).unwrap_or(false)
-            }
-        }
-    } else {
-        // AI 代理接口：仅允许使用 api_key
-        api_key.map(|k| k == security.api_key).unwrap_or(false)
-    };
+    // AI 代理接口：仅允许使用 api_key
+    let authorized = api_key.map(|k| k == security.api_key).unwrap_or(false);
 
     if authorized {
         Ok(next.run(request).await)
@@ -141,6 +270,7 @@ mod tests {
             auth_mode: ProxyAuthMode::Strict,
             api_key: "sk-api".to_string(),
             admin_password: Some("admin123".to_string()),
+            admin_tokens: Vec::new(),
             allow_lan_access: true,
             port: 8045,
         }));
@@ -156,6 +286,15 @@ mod tests {
         // 我们在 auth_middleware_internal 基础上做了逻辑校验即可
     }
 
+    #[test]
+    fn test_model_free_routes() {
+        assert!(is_model_free(&axum::http::Method::GET, "/v1/models"));
+        assert!(is_model_free(&axum::http::Method::GET, "/v1beta/models"));
+        assert!(!is_model_free(&axum::http::Method::POST, "/v1/audio/transcriptions"));
+        assert!(!is_model_free(&axum::http::Method::GET, "/v1/realtime"));
+        assert!(!is_model_free(&axum::http::Method::GET, "/v1/files"));
+    }
+
     #[test]
     fn test_auth_placeholder() {
         assert!(true);
@@ -0,0 +1,146 @@
+// 请求体大小限制中间件
+// 按端点规则限制请求体大小: 声明了 Content-Length 的请求直接比较, 分块上传的请求边读边计数,
+// 超出时中断读取, 并把响应替换为 OpenAI 格式的 413 错误。
+// 注意: ABV_MAX_BODY_SIZE 仍然限制需要整体解析请求体的处理器, 更大的规则只对流式转发的上传生效。
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{header, HeaderMap, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use futures::StreamExt;
+use serde_json::json;
+
+use crate::proxy::common::model_mapping::wildcard_match;
+use crate::proxy::config::BodyLimitConfig;
+use crate::proxy::server::AppState;
+
+/// multipart 上传 (音频、文件) 不由中间件缓冲, 由处理器流式转发
+pub fn is_multipart(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.trim_start().to_ascii_lowercase().starts_with("multipart/"))
+}
+
+/// 该路径的上限 (字节)
+pub fn limit_for(config: &BodyLimitConfig, path: &str) -> u64 {
+    let mb = config
+        .rules
+        .iter()
+        .find(|r| wildcard_match(r.path.trim(), path))
+        .map_or(config.default_mb, |r| r.max_mb);
+    mb.max(1).saturating_mul(1024 * 1024)
+}
+
+fn too_large(path: &str, limit: u64) -> Response {
+    let body = json!({
+        "error": {
+            "message": format!("Request body exceeds the {} MB limit for {}", limit / 1024 / 1024, path),
+            "type": "invalid_request_error",
+            "param": null,
+            "code": "request_too_large"
+        }
+    });
+    (StatusCode::PAYLOAD_TOO_LARGE, axum::Json(body)).into_response()
+}
+
+/// 边读边计数, 超出上限时返回错误并设置标记
+fn limited(body: Body, limit: u64, exceeded: Arc<AtomicBool>) -> Body {
+    let mut seen = 0u64;
+    let stream = body.into_data_stream().map(move |chunk| {
+        let chunk = chunk?;
+        seen += chunk.len() as u64;
+        if seen > limit {
+            exceeded.store(true, Ordering::Relaxed);
+            return Err(axum::Error::new("request body too large"));
+        }
+        Ok(chunk)
+    });
+    Body::from_stream(stream)
+}
+
+pub async fn body_limit_middleware(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let config = state.body_limits.read().await.clone();
+    if !config.enabled {
+        return next.run(request).await;
+    }
+    let path = request.uri().path().to_string();
+    let limit = limit_for(&config, &path);
+    let declared = request
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok());
+    match declared {
+        Some(length) if length > limit => {
+            tracing::warn!("[BodyLimit] {} rejected: {} bytes exceeds {} bytes", path, length, limit);
+            too_large(&path, limit)
+        }
+        // 声明了长度时 hyper 保证实际长度一致, 无需计数
+        Some(_) => next.run(request).await,
+        None => {
+            let exceeded = Arc::new(AtomicBool::new(false));
+            let (parts, body) = request.into_parts();
+            let request = Request::from_parts(parts, limited(body, limit, exceeded.clone()));
+            let response = next.run(request).await;
+            if exceeded.load(Ordering::Relaxed) {
+                tracing::warn!("[BodyLimit] {} rejected: chunked body exceeds {} bytes", path, limit);
+                return too_large(&path, limit);
+            }
+            response
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy::config::BodyLimitRule;
+
+    #[test]
+    fn test_limit_for_path() {
+        let config = BodyLimitConfig {
+            enabled: true,
+            default_mb: 10,
+            rules: vec![BodyLimitRule { path: "/v1/audio/*".to_string(), max_mb: 25 }],
+        };
+        assert_eq!(limit_for(&config, "/v1/audio/transcriptions"), 25 * 1024 * 1024);
+        assert_eq!(limit_for(&config, "/v1/chat/completions"), 10 * 1024 * 1024);
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::CONTENT_TYPE, "multipart/form-data; boundary=x".parse().unwrap());
+        assert!(is_multipart(&headers));
+        headers.insert(header::CONTENT_TYPE, "application/json".parse().unwrap());
+        assert!(!is_multipart(&headers));
+    }
+
+    #[tokio::test]
+    async fn test_chunked_body_is_cut_off() {
+        let exceeded = Arc::new(AtomicBool::new(false));
+        let body = limited(Body::from(vec![0u8; 10]), 8, exceeded.clone());
+        assert!(axum::body::to_bytes(body, usize::MAX).await.is_err());
+        assert!(exceeded.load(Ordering::Relaxed));
+
+        let exceeded = Arc::new(AtomicBool::new(false));
+        let body = limited(Body::from(vec![0u8; 8]), 8, exceeded.clone());
+        assert_eq!(axum::body::to_bytes(body, usize::MAX).await.unwrap().len(), 8);
+        assert!(!exceeded.load(Ordering::Relaxed));
+
+        // 整体缓冲请求体的中间件读到被中断的 Body 时返回错误响应, 不会把空 Body 转发给上游
+        let exceeded = Arc::new(AtomicBool::new(false));
+        let body = limited(Body::from(vec![0u8; 10]), 8, exceeded.clone());
+        let response = crate::proxy::middleware::buffer::buffer_request(body).await.unwrap_err();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert!(exceeded.load(Ordering::Relaxed));
+    }
+}
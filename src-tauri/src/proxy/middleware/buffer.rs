@@ -0,0 +1,84 @@
+// 中间件缓冲请求体 / 响应体
+// 需要整体解析 Body 的中间件共用同一上限; 超出上限或读取失败时直接返回错误响应,
+// 不会把空 Body 转发给上游, 也不会带着原状态码把空 Body 返回给客户端。
+
+use axum::{
+    body::{Body, Bytes},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use futures::StreamExt;
+use serde_json::json;
+
+/// 中间件整体缓冲 Body 的上限 (与 monitor 记录请求的上限一致)
+pub const MAX_BODY_SIZE: usize = 100 * 1024 * 1024;
+
+#[derive(Debug, PartialEq)]
+enum BufferError {
+    TooLarge,
+    Read(String),
+}
+
+async fn collect(body: Body, limit: usize) -> Result<Bytes, BufferError> {
+    let mut stream = body.into_data_stream();
+    let mut buf = Vec::new();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| BufferError::Read(e.to_string()))?;
+        if buf.len() + chunk.len() > limit {
+            return Err(BufferError::TooLarge);
+        }
+        buf.extend_from_slice(&chunk);
+    }
+    Ok(Bytes::from(buf))
+}
+
+fn error_response(status: StatusCode, code: &str, message: String) -> Response {
+    let body = json!({ "error": { "message": message, "type": "invalid_request_error", "code": code } });
+    (status, axum::Json(body)).into_response()
+}
+
+/// 缓冲请求体; 超出上限返回 413, 读取失败 (例如客户端中断上传) 返回 400
+pub async fn buffer_request(body: Body) -> Result<Bytes, Response> {
+    collect(body, MAX_BODY_SIZE).await.map_err(|e| match e {
+        BufferError::TooLarge => error_response(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            "request_too_large",
+            format!("Request body exceeds {} MB", MAX_BODY_SIZE / 1024 / 1024),
+        ),
+        BufferError::Read(e) => {
+            error_response(StatusCode::BAD_REQUEST, "invalid_request_body", format!("Failed to read request body: {}", e))
+        }
+    })
+}
+
+/// 缓冲内层返回的响应体; 超出上限或读取失败返回 502
+pub async fn buffer_response(body: Body) -> Result<Bytes, Response> {
+    collect(body, MAX_BODY_SIZE).await.map_err(|e| {
+        let message = match e {
+            BufferError::TooLarge => format!("Upstream response exceeds {} MB", MAX_BODY_SIZE / 1024 / 1024),
+            BufferError::Read(e) => format!("Failed to read upstream response: {}", e),
+        };
+        error_response(StatusCode::BAD_GATEWAY, "upstream_error", message)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_collect_reports_errors() {
+        assert_eq!(collect(Body::from("abcd"), 4).await, Ok(Bytes::from("abcd")));
+        assert_eq!(collect(Body::from("abcde"), 4).await, Err(BufferError::TooLarge));
+
+        let broken = futures::stream::iter(vec![Ok(Bytes::from("a")), Err(std::io::Error::other("reset"))]);
+        assert!(matches!(collect(Body::from_stream(broken), 4).await, Err(BufferError::Read(_))));
+
+        let response = buffer_request(Body::from_stream(futures::stream::iter(vec![Err::<Bytes, _>(
+            std::io::Error::other("reset"),
+        )])))
+        .await
+        .unwrap_err();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
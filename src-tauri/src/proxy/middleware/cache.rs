@@ -0,0 +1,103 @@
+// 响应缓存中间件
+// 命中时直接返回缓存的响应 (X-Cache: HIT), 未命中时转发并缓存成功的非流式响应 (X-Cache: MISS)。
+// 客户端可通过 `Cache-Control: no-cache` / `no-store` 跳过缓存。
+
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{header, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use serde_json::Value;
+
+use crate::proxy::middleware::auth::{extract_api_key, key_owner};
+use crate::proxy::middleware::body_limit::is_multipart;
+use crate::proxy::middleware::buffer::{buffer_request, buffer_response};
+use crate::proxy::response_cache::{cache_key, CachedResponse};
+use crate::proxy::server::AppState;
+
+fn bypass_cache(request: &Request) -> bool {
+    request
+        .headers()
+        .get(header::CACHE_CONTROL)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| {
+            let v = v.to_ascii_lowercase();
+            v.contains("no-cache") || v.contains("no-store")
+        })
+        .unwrap_or(false)
+}
+
+fn cached_response(cached: CachedResponse) -> Response {
+    let mut response = Response::new(Body::from(cached.body));
+    *response.status_mut() = StatusCode::from_u16(cached.status).unwrap_or(StatusCode::OK);
+    let headers = response.headers_mut();
+    for (name, value) in &cached.headers {
+        if let (Ok(name), Ok(value)) = (
+            header::HeaderName::from_bytes(name.as_bytes()),
+            HeaderValue::from_str(value),
+        ) {
+            headers.insert(name, value);
+        }
+    }
+    headers.insert("X-Cache", HeaderValue::from_static("HIT"));
+    headers.insert("X-Upstream", HeaderValue::from_static("cache"));
+    response
+}
+
+pub async fn response_cache_middleware(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let cache = state.response_cache.clone();
+    if !cache.is_enabled() || request.method() != Method::POST || bypass_cache(&request) || is_multipart(request.headers()) {
+        return next.run(request).await;
+    }
+
+    let path = request.uri().path().to_string();
+    let owner = key_owner(extract_api_key(request.headers()));
+    let (parts, body) = request.into_parts();
+    let bytes = match buffer_request(body).await {
+        Ok(bytes) => bytes,
+        Err(response) => return response,
+    };
+    let key = serde_json::from_slice::<Value>(&bytes)
+        .ok()
+        .and_then(|v| cache_key(&path, &owner, &v));
+    let request = Request::from_parts(parts, Body::from(bytes));
+
+    let Some(key) = key else {
+        return next.run(request).await;
+    };
+    if let Some(cached) = cache.get(&key) {
+        tracing::debug!("[Response-Cache] HIT {}", path);
+        return cached_response(cached);
+    }
+
+    let response = next.run(request).await;
+    let is_stream = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|ct| ct.contains("text/event-stream"))
+        .unwrap_or(false);
+    if !response.status().is_success() || is_stream {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match buffer_response(body).await {
+        Ok(bytes) => bytes,
+        Err(response) => {
+            tracing::warn!("[Response-Cache] Failed to read response body");
+            return response;
+        }
+    };
+    if bytes.len() <= cache.max_body_bytes() {
+        cache.insert(key, CachedResponse::new(parts.status.as_u16(), &parts.headers, bytes.clone()));
+    }
+    parts.headers.insert("X-Cache", HeaderValue::from_static("MISS"));
+    Response::from_parts(parts, Body::from(bytes))
+}
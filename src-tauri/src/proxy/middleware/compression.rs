@@ -0,0 +1,86 @@
+// 压缩中间件
+// upstream_decoding_middleware 紧贴处理器: 透传的上游压缩响应先解压, 内层所有中间件看到的都是明文。
+// response_compression_middleware 位于外层: 按 Accept-Encoding 压缩完整的 JSON 响应, SSE 流始终原样返回。
+
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{header, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::proxy::common::compression::{compress, decode_body, negotiate, Encoding};
+use crate::proxy::middleware::buffer::buffer_response;
+use crate::proxy::server::AppState;
+
+pub async fn upstream_decoding_middleware(request: Request, next: Next) -> Response {
+    let response = next.run(request).await;
+    let encoding = match Encoding::from_headers(response.headers()) {
+        Ok(Some(encoding)) => encoding,
+        Ok(None) => return response,
+        Err(e) => {
+            tracing::warn!("[Compression] {}, passing response through", e);
+            return response;
+        }
+    };
+    let (mut parts, body) = response.into_parts();
+    parts.headers.remove(header::CONTENT_ENCODING);
+    parts.headers.remove(header::CONTENT_LENGTH);
+    Response::from_parts(parts, decode_body(body, encoding))
+}
+
+pub async fn response_compression_middleware(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let config = state.compression.read().await.clone();
+    if !config.enabled {
+        return next.run(request).await;
+    }
+    let accept = request
+        .headers()
+        .get(header::ACCEPT_ENCODING)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("");
+    let Some(encoding) = negotiate(accept, &config) else {
+        return next.run(request).await;
+    };
+
+    let response = next.run(request).await;
+    let content_type = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("");
+    // 流式响应逐块到达, 压缩会让客户端在缓冲区填满前收不到事件
+    if !content_type.contains("json")
+        || content_type.contains("text/event-stream")
+        || response.headers().contains_key(header::CONTENT_ENCODING)
+    {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match buffer_response(body).await {
+        Ok(bytes) => bytes,
+        Err(response) => return response,
+    };
+    parts.headers.append(header::VARY, HeaderValue::from_static("accept-encoding"));
+    if bytes.len() < config.min_bytes {
+        return Response::from_parts(parts, Body::from(bytes));
+    }
+    match compress(&bytes, encoding) {
+        Ok(compressed) => {
+            tracing::debug!("[Compression] {} {} -> {} bytes", encoding.as_str(), bytes.len(), compressed.len());
+            parts.headers.insert(header::CONTENT_ENCODING, HeaderValue::from_static(encoding.as_str()));
+            parts.headers.insert(header::CONTENT_LENGTH, HeaderValue::from(compressed.len()));
+            Response::from_parts(parts, Body::from(compressed))
+        }
+        Err(e) => {
+            tracing::warn!("[Compression] {} failed: {}", encoding.as_str(), e);
+            Response::from_parts(parts, Body::from(bytes))
+        }
+    }
+}
@@ -0,0 +1,109 @@
+// 上游并发限制中间件
+// 按请求将要访问的上游排队, 名额一直保留到响应体 (包括流式响应) 结束。
+// 排队时间和排队深度通过 `X-Queue-Wait-Ms` / `X-Queue-Depth` 返回, 由 monitor 记录到指标。
+
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use futures::StreamExt;
+use serde_json::json;
+
+use crate::proxy::upstream::concurrency::QueueRejected;
+use crate::proxy::server::AppState;
+use crate::proxy::ZaiDispatchMode;
+
+/// 请求将要访问的上游 (与 `concurrency.overrides` 的键一致), None 表示不限制
+async fn upstream_for(state: &AppState, method: &Method, path: &str) -> Option<&'static str> {
+    if method != Method::POST {
+        return None;
+    }
+    if path.starts_with("/mcp") || path.starts_with("/internal") || path.contains("event_logging") {
+        return None;
+    }
+    if path.starts_with("/v1/embeddings") {
+        return Some("embeddings");
+    }
+    if path.starts_with("/v1/images") {
+        return Some("images");
+    }
+    if path.starts_with("/v1/audio") {
+        return Some("audio");
+    }
+    if path.starts_with("/v1/messages") {
+        let zai = state.zai.read().await;
+        if zai.enabled && zai.dispatch_mode == ZaiDispatchMode::Exclusive {
+            return Some("zai");
+        }
+    }
+    Some("google")
+}
+
+fn queue_rejected_response(upstream: &str, rejected: QueueRejected) -> Response {
+    let (message, code) = match rejected {
+        QueueRejected::Full => (
+            format!("Too many queued requests for upstream '{}'. Please retry later.", upstream),
+            "upstream_queue_full",
+        ),
+        QueueRejected::Timeout => (
+            format!("Timed out waiting for a free slot on upstream '{}'.", upstream),
+            "upstream_queue_timeout",
+        ),
+    };
+    let body = json!({
+        "error": {
+            "message": message,
+            "type": "server_error",
+            "param": null,
+            "code": code
+        }
+    });
+    let mut response = (StatusCode::SERVICE_UNAVAILABLE, axum::Json(body)).into_response();
+    response.headers_mut().insert("Retry-After", HeaderValue::from_static("1"));
+    response
+}
+
+pub async fn concurrency_middleware(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    let Some(upstream) = upstream_for(&state, &method, &path).await else {
+        return next.run(request).await;
+    };
+    let admission = match state.concurrency.acquire(upstream).await {
+        Ok(admission) => admission,
+        Err(rejected) => {
+            tracing::warn!("[Concurrency] Rejected request for upstream {}: {:?}", upstream, rejected);
+            return queue_rejected_response(upstream, rejected);
+        }
+    };
+    if !admission.wait.is_zero() {
+        tracing::debug!(
+            "[Concurrency] {} waited {}ms in queue (depth {})",
+            upstream,
+            admission.wait.as_millis(),
+            admission.queue_depth
+        );
+    }
+    let wait_ms = admission.wait.as_millis() as u64;
+    let queue_depth = admission.queue_depth;
+
+    let response = next.run(request).await;
+    let (mut parts, body) = response.into_parts();
+    parts.headers.insert("X-Queue-Upstream", HeaderValue::from_static(upstream));
+    parts.headers.insert("X-Queue-Wait-Ms", HeaderValue::from(wait_ms));
+    parts.headers.insert("X-Queue-Depth", HeaderValue::from(queue_depth));
+
+    // 名额随响应体一起释放
+    let stream = body.into_data_stream().map(move |chunk| {
+        let _ = &admission;
+        chunk
+    });
+    Response::from_parts(parts, Body::from_stream(stream))
+}
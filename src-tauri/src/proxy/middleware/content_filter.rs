@@ -0,0 +1,269 @@
+// 内容过滤中间件
+// 请求: 在转发前检查消息文本, 命中 block 规则返回 400, 命中 redact 规则改写请求体。
+// 响应: JSON 响应整体检查; SSE 按事件检查, 命中 block 规则时发送错误事件并中断流。
+// 注意: 跨越多个 SSE 事件的匹配内容无法识别; 命中规则时只记录规则名, 不记录命中的文本。
+
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use bytes::Bytes;
+use serde_json::{json, Value};
+
+use crate::proxy::common::content_filter::{Direction, RouteFilter, ScanResult};
+use crate::proxy::common::sse::{SseEvent, SseParser};
+use crate::proxy::common::stream_relay::next_chunk;
+use crate::proxy::middleware::body_limit::is_multipart;
+use crate::proxy::middleware::buffer::{buffer_request, buffer_response};
+use crate::proxy::middleware::model_fallback::LOCAL_REJECTION_HEADER;
+use crate::proxy::server::AppState;
+
+fn report(path: &str, direction: Direction, result: &ScanResult) {
+    let side = match direction {
+        Direction::Request => "request",
+        Direction::Response => "response",
+    };
+    if let Some(rule) = &result.blocked_by {
+        tracing::warn!("[ContentFilter] {} {} blocked by rule '{}'", path, side, rule);
+    }
+    if !result.redacted_by.is_empty() {
+        tracing::info!("[ContentFilter] {} {} redacted by {:?}", path, side, result.redacted_by);
+    }
+    if !result.logged_by.is_empty() {
+        tracing::warn!("[ContentFilter] {} {} matched log-only rules {:?}", path, side, result.logged_by);
+    }
+}
+
+fn error_body(message: String) -> Value {
+    json!({
+        "type": "error",
+        "error": { "message": message, "type": "content_filter", "code": "content_filter" }
+    })
+}
+
+/// 规则拒绝的响应, 标记为本地拒绝, 备用模型链不会因此换模型重试
+fn rejection(status: StatusCode, message: String) -> Response {
+    let mut response = (status, axum::Json(error_body(message))).into_response();
+    response.headers_mut().insert(LOCAL_REJECTION_HEADER, HeaderValue::from_static("content_filter"));
+    response
+}
+
+pub async fn content_filter_middleware(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let path = request.uri().path().to_string();
+    let Some(filter) = state.content_filter.for_route(&path) else {
+        return next.run(request).await;
+    };
+
+    let request = if filter.applies(Direction::Request)
+        && request.method() == axum::http::Method::POST
+        && !is_multipart(request.headers())
+    {
+        match filter_request(&filter, &path, request).await {
+            Ok(request) => request,
+            Err(response) => return response,
+        }
+    } else {
+        request
+    };
+
+    let response = next.run(request).await;
+    if !filter.applies(Direction::Response) {
+        return response;
+    }
+    let content_type = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("")
+        .to_string();
+    if content_type.contains("text/event-stream") {
+        filter_stream(filter, path, response)
+    } else if content_type.contains("json") {
+        filter_json_response(&filter, &path, response).await
+    } else {
+        response
+    }
+}
+
+async fn filter_request(filter: &RouteFilter, path: &str, request: Request) -> Result<Request, Response> {
+    let (mut parts, body) = request.into_parts();
+    let bytes = buffer_request(body).await?;
+    let Ok(mut json) = serde_json::from_slice::<Value>(&bytes) else {
+        return Ok(Request::from_parts(parts, Body::from(bytes)));
+    };
+    let result = filter.scan(Direction::Request, &mut json);
+    report(path, Direction::Request, &result);
+    if let Some(rule) = result.blocked_by {
+        let message = format!("Request blocked by content filter rule '{}'", rule);
+        return Err(rejection(StatusCode::BAD_REQUEST, message));
+    }
+    if !result.modified() {
+        return Ok(Request::from_parts(parts, Body::from(bytes)));
+    }
+    parts.headers.remove(header::CONTENT_LENGTH);
+    Ok(Request::from_parts(parts, Body::from(json.to_string())))
+}
+
+async fn filter_json_response(filter: &RouteFilter, path: &str, response: Response) -> Response {
+    let (mut parts, body) = response.into_parts();
+    let bytes = match buffer_response(body).await {
+        Ok(bytes) => bytes,
+        Err(response) => return response,
+    };
+    let Ok(mut json) = serde_json::from_slice::<Value>(&bytes) else {
+        return Response::from_parts(parts, Body::from(bytes));
+    };
+    let result = filter.scan(Direction::Response, &mut json);
+    report(path, Direction::Response, &result);
+    if let Some(rule) = result.blocked_by {
+        let message = format!("Response blocked by content filter rule '{}'", rule);
+        return rejection(StatusCode::BAD_GATEWAY, message);
+    }
+    if !result.modified() {
+        return Response::from_parts(parts, Body::from(bytes));
+    }
+    parts.headers.remove(header::CONTENT_LENGTH);
+    Response::from_parts(parts, Body::from(json.to_string()))
+}
+
+/// 单个流的过滤状态
+pub(crate) struct StreamFilter {
+    filter: RouteFilter,
+    path: String,
+    parser: SseParser,
+    blocked: bool,
+}
+
+impl StreamFilter {
+    pub(crate) fn new(filter: RouteFilter, path: String) -> Self {
+        Self { filter, path, parser: SseParser::new(), blocked: false }
+    }
+
+    pub(crate) fn push(&mut self, chunk: &[u8]) -> Vec<u8> {
+        let events = self.parser.push(chunk);
+        self.render(events)
+    }
+
+    pub(crate) fn finish(&mut self) -> Vec<u8> {
+        let events = self.parser.finish();
+        self.render(events)
+    }
+
+    pub(crate) fn is_blocked(&self) -> bool {
+        self.blocked
+    }
+
+    fn render(&mut self, events: Vec<SseEvent>) -> Vec<u8> {
+        let mut out = Vec::new();
+        for mut event in events {
+            if self.blocked {
+                break;
+            }
+            if let Ok(mut json) = serde_json::from_str::<Value>(&event.data) {
+                let result = self.filter.scan(Direction::Response, &mut json);
+                report(&self.path, Direction::Response, &result);
+                if let Some(rule) = result.blocked_by {
+                    self.blocked = true;
+                    let error = SseEvent {
+                        event: "error".to_string(),
+                        data: error_body(format!("Response blocked by content filter rule '{}'", rule)).to_string(),
+                        id: None,
+                    };
+                    out.extend(super::stream_transform::encode_event(&error));
+                    break;
+                }
+                if result.modified() {
+                    event.data = json.to_string();
+                }
+            }
+            out.extend(super::stream_transform::encode_event(&event));
+        }
+        out
+    }
+}
+
+fn filter_stream(filter: RouteFilter, path: String, response: Response) -> Response {
+    let mut stream_filter = StreamFilter::new(filter, path);
+    let (mut parts, body) = response.into_parts();
+    parts.headers.remove(header::CONTENT_LENGTH);
+    let mut stream = body.into_data_stream();
+    let (tx, rx) = tokio::sync::mpsc::channel(64);
+
+    tokio::spawn(async move {
+        while let Ok(Some(chunk)) = next_chunk(&mut stream, &tx).await {
+            match chunk {
+                Ok(chunk) => {
+                    let out = stream_filter.push(&chunk);
+                    if !out.is_empty() && tx.send(Ok::<_, axum::Error>(Bytes::from(out))).await.is_err() {
+                        return; // 客户端已断开
+                    }
+                    if stream_filter.is_blocked() {
+                        return; // 丢弃上游剩余内容
+                    }
+                }
+                Err(e) => {
+                    let _ = tx.send(Err(e)).await;
+                    return;
+                }
+            }
+        }
+        let out = stream_filter.finish();
+        if !out.is_empty() {
+            let _ = tx.send(Ok(Bytes::from(out))).await;
+        }
+    });
+
+    Response::from_parts(parts, Body::from_stream(tokio_stream::wrappers::ReceiverStream::new(rx)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy::common::content_filter::ContentFilter;
+    use crate::proxy::config::{ContentFilterAction, ContentFilterConfig, ContentFilterMatch, ContentFilterRule, ContentFilterScope};
+
+    fn route_filter(action: ContentFilterAction) -> RouteFilter {
+        ContentFilter::new(&ContentFilterConfig {
+            enabled: true,
+            rules: vec![ContentFilterRule {
+                name: "token".to_string(),
+                pattern: r"sk-[a-z0-9]{8}".to_string(),
+                match_type: ContentFilterMatch::Regex,
+                action,
+                scope: ContentFilterScope::Response,
+                paths: Vec::new(),
+                enabled: true,
+            }],
+            ..ContentFilterConfig::default()
+        })
+        .for_route("/v1/chat/completions")
+        .unwrap()
+    }
+
+    fn chunk(content: &str) -> String {
+        format!("data: {}\n\n", json!({"object": "chat.completion.chunk", "choices": [{"index": 0, "delta": {"content": content}}]}))
+    }
+
+    #[test]
+    fn test_stream_redact_and_block() {
+        let input = format!("{}{}data: [DONE]\n\n", chunk("your key is sk-abcd1234"), chunk("done"));
+
+        let mut redact = StreamFilter::new(route_filter(ContentFilterAction::Redact), "/v1/chat/completions".to_string());
+        let out = String::from_utf8(redact.push(input.as_bytes())).unwrap();
+        assert!(out.contains("your key is [REDACTED]") && out.contains("done") && out.ends_with("data: [DONE]\n\n"));
+
+        let mut block = StreamFilter::new(route_filter(ContentFilterAction::Block), "/v1/chat/completions".to_string());
+        let out = String::from_utf8(block.push(input.as_bytes())).unwrap();
+        assert!(block.is_blocked());
+        assert!(out.starts_with("event: error\n"));
+        assert!(!out.contains("sk-abcd1234") && !out.contains("done"));
+        assert!(block.finish().is_empty());
+    }
+}
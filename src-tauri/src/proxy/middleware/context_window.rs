@@ -0,0 +1,132 @@
+// 上下文窗口保护与 max_tokens 调整中间件
+// 转发前按目标模型 (别名与自定义映射之后) 的上下文大小检查请求, 放不下时按策略截断或直接返回 400,
+// 避免把注定失败的请求发往上游。未知模型不检查。
+// max_tokens 先于上下文检查调整, 过大的输出预留不会导致历史消息被截断; 调整记录在响应头中。
+
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde_json::{json, Value};
+
+use crate::proxy::common::context_window::{context_limit, fit, FitOutcome};
+use crate::proxy::common::max_tokens::{self, MaxTokensAdjustment};
+use crate::proxy::middleware::body_limit::is_multipart;
+use crate::proxy::middleware::buffer::buffer_request;
+use crate::proxy::server::AppState;
+
+fn request_model(path: &str, body: &Value) -> Option<String> {
+    body.get("model")
+        .and_then(|m| m.as_str())
+        .map(String::from)
+        .or_else(|| path.split("/v1beta/models/").nth(1).and_then(|s| s.split(':').next()).map(String::from))
+}
+
+pub async fn context_window_middleware(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let config = state.context_window.read().await.clone();
+    let max_tokens_config = state.max_tokens.read().await.clone();
+    let is_json_post = request.method() == axum::http::Method::POST && !is_multipart(request.headers());
+    if !(config.enabled || max_tokens_config.enabled) || !is_json_post {
+        return next.run(request).await;
+    }
+    let path = request.uri().path().to_string();
+    let (mut parts, body) = request.into_parts();
+    let bytes = match buffer_request(body).await {
+        Ok(bytes) => bytes,
+        Err(response) => return response,
+    };
+    let Ok(mut json) = serde_json::from_slice::<Value>(&bytes) else {
+        return next.run(Request::from_parts(parts, Body::from(bytes))).await;
+    };
+    let Some(model) = request_model(&path, &json) else {
+        return next.run(Request::from_parts(parts, Body::from(bytes))).await;
+    };
+
+    // 先按客户端请求的模型查找, 找不到时按映射后的上游模型查找
+    let aliased = state.model_aliases.rewrite(&model);
+    let mapped = crate::proxy::common::model_mapping::resolve_model_route(&aliased, &*state.custom_mapping.read().await);
+    let candidates = [&aliased, &mapped];
+    let context = candidates
+        .into_iter()
+        .find_map(|m| context_limit(&config, m).map(|limit| (m.clone(), limit)));
+
+    let adjustment = if max_tokens_config.enabled {
+        let target = candidates
+            .into_iter()
+            .find(|m| max_tokens::output_limit(&max_tokens_config, m).is_some())
+            .or(context.as_ref().map(|(m, _)| m))
+            .unwrap_or(&mapped)
+            .clone();
+        let limit = context_limit(&config, &target);
+        let adjustment =
+            max_tokens::adjust(&max_tokens_config, &target, limit, max_tokens::requires_max_tokens(&path), &mut json);
+        if let Some(adjustment) = adjustment {
+            tracing::info!("[MaxTokens] {} model {}: {}", path, target, adjustment.header_value());
+        }
+        adjustment
+    } else {
+        None
+    };
+
+    let outcome = match &context {
+        Some((target, limit)) if config.enabled => fit(&config, target, *limit, &mut json),
+        _ => FitOutcome::Fits,
+    };
+    let request = match outcome {
+        FitOutcome::Fits if adjustment.is_none() => Request::from_parts(parts, Body::from(bytes)),
+        FitOutcome::Fits => {
+            parts.headers.remove(header::CONTENT_LENGTH);
+            Request::from_parts(parts, Body::from(json.to_string()))
+        }
+        FitOutcome::Truncated { dropped, tokens } => {
+            let (target, limit) = context.as_ref().map(|(m, l)| (m.as_str(), *l)).unwrap_or_default();
+            tracing::warn!(
+                "[ContextWindow] {} model {} exceeds {} tokens, dropped {} messages (~{} tokens left)",
+                path, target, limit, dropped, tokens
+            );
+            parts.headers.remove(header::CONTENT_LENGTH);
+            Request::from_parts(parts, Body::from(json.to_string()))
+        }
+        FitOutcome::Overflow { tokens, budget } => {
+            let (target, limit) = context.as_ref().map(|(m, l)| (m.as_str(), *l)).unwrap_or_default();
+            tracing::warn!("[ContextWindow] {} rejected: ~{} prompt tokens, budget {} for {}", path, tokens, budget, target);
+            let message = format!(
+                "This request has about {} prompt tokens, which exceeds the context window of model '{}' ({} tokens, {} available for the prompt after reserving output). Reduce the length of the messages.",
+                tokens, target, limit, budget
+            );
+            let body = json!({
+                "type": "error",
+                "error": { "message": message, "type": "invalid_request_error", "code": "context_length_exceeded" }
+            });
+            return (StatusCode::BAD_REQUEST, axum::Json(body)).into_response();
+        }
+    };
+
+    let mut response = next.run(request).await;
+    if let Some(value) = adjustment.as_ref().map(MaxTokensAdjustment::header_value).and_then(|v| HeaderValue::from_str(&v).ok()) {
+        response.headers_mut().insert("X-Max-Tokens-Adjusted", value);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_model() {
+        assert_eq!(request_model("/v1/chat/completions", &json!({"model": "gpt-4o"})).as_deref(), Some("gpt-4o"));
+        assert_eq!(
+            request_model("/v1beta/models/gemini-2.5-pro:generateContent", &json!({"contents": []})).as_deref(),
+            Some("gemini-2.5-pro")
+        );
+        assert_eq!(request_model("/v1/messages", &json!({})), None);
+    }
+}
@@ -0,0 +1,165 @@
+// 会话记忆中间件
+// 请求携带会话请求头时, 把保存的历史插入到请求消息中; 成功的响应 (JSON 或 SSE) 结束后把本轮消息和助手回复写回会话。
+// 位于脱敏与内容过滤之外: 保存的是客户端原文, 每次转发时完整历史都会重新经过脱敏和过滤。
+
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::header,
+    middleware::Next,
+    response::Response,
+};
+use bytes::Bytes;
+use serde_json::Value;
+use std::sync::Arc;
+
+use crate::proxy::common::stream_relay::next_chunk;
+use crate::proxy::conversation_store::{assistant_message, merge_history, session_key, stream_assistant_message, ConversationStore};
+use crate::proxy::middleware::body_limit::is_multipart;
+use crate::proxy::middleware::buffer::{buffer_request, buffer_response, MAX_BODY_SIZE};
+use crate::proxy::server::AppState;
+
+/// 响应结束后需要写回的会话
+struct PendingTurn {
+    store: Arc<ConversationStore>,
+    key: String,
+    session_id: String,
+    api_key_hint: Option<String>,
+    messages: Vec<Value>, // 历史 + 本轮新增的消息
+    gemini: bool,
+}
+
+impl PendingTurn {
+    fn commit(mut self, reply: Option<Value>) {
+        let Some(reply) = reply else {
+            return;
+        };
+        self.messages.push(reply);
+        tracing::debug!("[ConversationMemory] session {} now has {} messages", self.session_id, self.messages.len());
+        self.store.save(&self.key, &self.session_id, self.api_key_hint, self.messages);
+    }
+}
+
+pub async fn conversation_memory_middleware(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let store = state.conversation_store.clone();
+    if !store.is_enabled() || request.method() != axum::http::Method::POST || is_multipart(request.headers()) {
+        return next.run(request).await;
+    }
+    let config = store.config();
+    let Some(session_id) = request
+        .headers()
+        .get(config.header.as_str())
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+    else {
+        return next.run(request).await;
+    };
+
+    let (mut parts, body) = request.into_parts();
+    let bytes = match buffer_request(body).await {
+        Ok(bytes) => bytes,
+        Err(response) => return response,
+    };
+    let Ok(mut json) = serde_json::from_slice::<Value>(&bytes) else {
+        return next.run(Request::from_parts(parts, Body::from(bytes))).await;
+    };
+    let Some((field, messages)) = ["messages", "contents"]
+        .into_iter()
+        .find_map(|k| json.get(k).and_then(|v| v.as_array()).map(|m| (k, m.clone())))
+    else {
+        return next.run(Request::from_parts(parts, Body::from(bytes))).await;
+    };
+
+    let api_key = crate::proxy::middleware::auth::extract_api_key(&parts.headers).filter(|k| !k.is_empty());
+    let key = session_key(api_key, &session_id);
+    let history = store.history(&key);
+    let (full, turn) = merge_history(&history, messages);
+    let mut pending = PendingTurn {
+        store: store.clone(),
+        key,
+        session_id,
+        api_key_hint: api_key.map(crate::modules::usage::mask_api_key),
+        messages: history.clone(),
+        gemini: field == "contents",
+    };
+    pending.messages.extend(turn);
+
+    let request = if history.is_empty() {
+        Request::from_parts(parts, Body::from(bytes))
+    } else {
+        tracing::debug!("[ConversationMemory] session {} prepended {} messages", pending.session_id, history.len());
+        json[field] = Value::Array(full);
+        parts.headers.remove(header::CONTENT_LENGTH);
+        Request::from_parts(parts, Body::from(json.to_string()))
+    };
+
+    let response = next.run(request).await;
+    if !response.status().is_success() {
+        return response;
+    }
+    let content_type = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("")
+        .to_string();
+    if content_type.contains("text/event-stream") {
+        record_stream(pending, response)
+    } else if content_type.contains("json") {
+        record_json(pending, response).await
+    } else {
+        response
+    }
+}
+
+async fn record_json(pending: PendingTurn, response: Response) -> Response {
+    let (parts, body) = response.into_parts();
+    let bytes = match buffer_response(body).await {
+        Ok(bytes) => bytes,
+        Err(response) => return response,
+    };
+    let reply = serde_json::from_slice::<Value>(&bytes).ok().and_then(|json| assistant_message(&json));
+    pending.commit(reply);
+    Response::from_parts(parts, Body::from(bytes))
+}
+
+/// 边转发边收集, 流正常结束后写回会话; 客户端中途断开时不保存
+fn record_stream(pending: PendingTurn, response: Response) -> Response {
+    let (parts, body) = response.into_parts();
+    let mut stream = body.into_data_stream();
+    let (tx, rx) = tokio::sync::mpsc::channel(64);
+
+    tokio::spawn(async move {
+        let mut collected = Vec::new();
+        loop {
+            let chunk = match next_chunk(&mut stream, &tx).await {
+                Ok(Some(chunk)) => chunk,
+                Ok(None) => break,
+                Err(_) => return, // 客户端已断开
+            };
+            match chunk {
+                Ok(chunk) => {
+                    if collected.len() < MAX_BODY_SIZE {
+                        collected.extend_from_slice(&chunk);
+                    }
+                    if tx.send(Ok::<_, axum::Error>(Bytes::from(chunk))).await.is_err() {
+                        return; // 客户端已断开
+                    }
+                }
+                Err(e) => {
+                    let _ = tx.send(Err(e)).await;
+                    return;
+                }
+            }
+        }
+        let gemini = pending.gemini;
+        pending.commit(stream_assistant_message(&collected, gemini));
+    });
+
+    Response::from_parts(parts, Body::from_stream(tokio_stream::wrappers::ReceiverStream::new(rx)))
+}
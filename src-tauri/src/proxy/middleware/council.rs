@@ -0,0 +1,80 @@
+// 聚合路由中间件
+// 位于鉴权与限流之内、影子流量与智能路由之外: 客户端请求的模型是聚合名称时直接返回汇总后的响应,
+// 成员与评审请求经本地反代重新进入中间件链, 可以使用智能路由别名。客户端要求流式时合成等价的 SSE 流。
+
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{header, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde_json::{json, Value};
+
+use crate::proxy::common::client_info::CLIENT_NAME_HEADER;
+use crate::proxy::compare::CompareTarget;
+use crate::proxy::mappers::stream_bridge::json_to_sse;
+use crate::proxy::middleware::buffer::buffer_request;
+use crate::proxy::server::AppState;
+
+/// 响应头: 处理该请求的聚合名称
+pub const COUNCIL_HEADER: &str = "x-council";
+
+pub async fn council_middleware(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let from_council = request
+        .headers()
+        .get(CLIENT_NAME_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v == crate::proxy::council::CLIENT_NAME);
+    if !crate::proxy::council::is_enabled()
+        || request.method() != Method::POST
+        || request.uri().path() != "/v1/chat/completions"
+        || from_council
+    {
+        return next.run(request).await;
+    }
+    let (parts, body) = request.into_parts();
+    let bytes = match buffer_request(body).await {
+        Ok(bytes) => bytes,
+        Err(response) => return response,
+    };
+    let json = serde_json::from_slice::<Value>(&bytes).ok();
+    let council = json
+        .as_ref()
+        .and_then(|j| j.get("model"))
+        .and_then(Value::as_str)
+        .and_then(crate::proxy::council::find);
+    let (Some(council), Some(json)) = (council, json) else {
+        return next.run(Request::from_parts(parts, Body::from(bytes))).await;
+    };
+
+    let wants_stream = json.get("stream").and_then(Value::as_bool).unwrap_or(false);
+    let target = CompareTarget {
+        base_url: format!("http://127.0.0.1:{}", state.port),
+        api_key: state.security.read().await.api_key.clone(),
+        client_name: crate::proxy::council::CLIENT_NAME,
+    };
+    let prices = state.pricing.read().await.models.clone();
+    let result = crate::proxy::council::run(&council, json, target, &prices, state.request_timeout).await;
+    let mut response = match result {
+        Ok(completion) if wants_stream => {
+            let events = json_to_sse(&completion).unwrap_or_default();
+            let mut response =
+                Body::from_stream(futures::stream::iter(events.into_iter().map(Ok::<_, std::io::Error>))).into_response();
+            let headers = response.headers_mut();
+            headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("text/event-stream"));
+            headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+            response
+        }
+        Ok(completion) => axum::Json(completion).into_response(),
+        Err(e) => {
+            tracing::warn!("[Council] {} failed: {}", council.name, e);
+            let body = json!({ "error": { "message": e, "type": "council_error", "code": "council_error" } });
+            (StatusCode::BAD_GATEWAY, axum::Json(body)).into_response()
+        }
+    };
+    if let Ok(value) = HeaderValue::from_str(&council.name) {
+        response.headers_mut().insert(COUNCIL_HEADER, value);
+    }
+    response
+}
@@ -0,0 +1,267 @@
+// 优雅停机中间件
+// 停机时拒绝新请求, 进行中的请求 (包括流式响应) 最多等待 drain_timeout_secs 自然结束;
+// 超时后剩余的 SSE 流在事件边界处补发终止事件再结束, 客户端不会收到半截事件。
+
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::{
+    body::{Body, Bytes},
+    extract::{Request, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use futures::{Stream, StreamExt};
+use serde_json::json;
+use tokio::sync::{watch, Notify};
+use tokio::time::Instant;
+
+use crate::proxy::middleware::stream_watchdog::{error_event, ClientFormat};
+use crate::proxy::server::AppState;
+
+/// 终止信号发出后, 等待当前事件写完的最长时间
+const BOUNDARY_GRACE: Duration = Duration::from_secs(2);
+/// 终止事件写出所需的时间
+const FLUSH_GRACE: Duration = Duration::from_secs(3);
+
+pub struct RequestDrain {
+    timeout_secs: AtomicU64,
+    draining: AtomicBool,
+    active: AtomicUsize,
+    idle: Notify,
+    terminate: watch::Sender<bool>,
+}
+
+/// 进行中的请求, 释放时计数减一 (流式响应在流结束时释放)
+struct InFlight(Arc<RequestDrain>);
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        if self.0.active.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.0.idle.notify_waiters();
+        }
+    }
+}
+
+impl RequestDrain {
+    pub fn new(timeout_secs: u64) -> Self {
+        Self {
+            timeout_secs: AtomicU64::new(timeout_secs),
+            draining: AtomicBool::new(false),
+            active: AtomicUsize::new(0),
+            idle: Notify::new(),
+            terminate: watch::channel(false).0,
+        }
+    }
+
+    pub fn update_timeout(&self, timeout_secs: u64) {
+        self.timeout_secs.store(timeout_secs, Ordering::Relaxed);
+    }
+
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::SeqCst)
+    }
+
+    pub fn active(&self) -> usize {
+        self.active.load(Ordering::SeqCst)
+    }
+
+    fn enter(self: &Arc<Self>) -> InFlight {
+        self.active.fetch_add(1, Ordering::SeqCst);
+        InFlight(self.clone())
+    }
+
+    async fn wait_idle(&self, timeout: Duration) -> bool {
+        let idle = async {
+            loop {
+                // 先注册等待再检查计数, 避免错过通知
+                let notified = self.idle.notified();
+                if self.active() == 0 {
+                    return;
+                }
+                notified.await;
+            }
+        };
+        tokio::time::timeout(timeout, idle).await.is_ok()
+    }
+
+    /// 停止接收新请求并等待进行中的请求结束, 返回超时后被强制终止的请求数
+    pub async fn drain(&self) -> usize {
+        self.draining.store(true, Ordering::SeqCst);
+        let pending = self.active();
+        if pending == 0 {
+            return 0;
+        }
+        let timeout = Duration::from_secs(self.timeout_secs.load(Ordering::Relaxed));
+        tracing::info!("[Drain] waiting up to {}s for {} in-flight requests", timeout.as_secs(), pending);
+        if self.wait_idle(timeout).await {
+            tracing::info!("[Drain] all in-flight requests finished");
+            return 0;
+        }
+        let remaining = self.active();
+        tracing::warn!("[Drain] drain timeout reached, terminating {} in-flight requests", remaining);
+        self.terminate.send_replace(true);
+        self.wait_idle(FLUSH_GRACE).await;
+        remaining
+    }
+}
+
+fn ends_event(chunk: &[u8]) -> bool {
+    chunk.ends_with(b"\n\n") || chunk.ends_with(b"\r\n\r\n")
+}
+
+fn shutdown_event(format: ClientFormat) -> Bytes {
+    error_event(
+        format,
+        "Proxy is shutting down; the response was ended after the drain timeout",
+        "server_shutdown",
+        (503, "UNAVAILABLE"),
+    )
+}
+
+/// 转发 SSE 流直到结束; 收到终止信号后在事件边界处补发终止事件
+fn drain_stream<S, E>(
+    stream: S,
+    format: ClientFormat,
+    guard: InFlight,
+    mut terminate: watch::Receiver<bool>,
+) -> impl Stream<Item = Result<Bytes, E>> + Send
+where
+    S: Stream<Item = Result<Bytes, E>> + Send + Unpin + 'static,
+    E: Send + 'static,
+{
+    async_stream::stream! {
+        let _guard = guard;
+        let mut stream = stream;
+        let mut at_boundary = true;
+        loop {
+            let next = tokio::select! {
+                biased;
+                Ok(_) = terminate.wait_for(|t| *t) => None,
+                chunk = stream.next() => Some(chunk),
+            };
+            match next {
+                None => break,
+                Some(Some(Ok(chunk))) => {
+                    if !chunk.is_empty() {
+                        at_boundary = ends_event(&chunk);
+                    }
+                    yield Ok(chunk);
+                }
+                Some(Some(Err(e))) => {
+                    yield Err(e);
+                    return;
+                }
+                Some(None) => return,
+            }
+        }
+
+        // 当前事件尚未写完时再等一小段时间, 尽量在事件边界处结束
+        let deadline = Instant::now() + BOUNDARY_GRACE;
+        while !at_boundary {
+            match tokio::time::timeout_at(deadline, stream.next()).await {
+                Ok(Some(Ok(chunk))) => {
+                    if !chunk.is_empty() {
+                        at_boundary = ends_event(&chunk);
+                    }
+                    yield Ok(chunk);
+                }
+                Ok(Some(Err(e))) => {
+                    yield Err(e);
+                    return;
+                }
+                Ok(None) => return,
+                Err(_) => {
+                    yield Ok(Bytes::from_static(b"\n\n"));
+                    break;
+                }
+            }
+        }
+        yield Ok(shutdown_event(format));
+    }
+}
+
+pub async fn drain_middleware(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let drain = state.drain.clone();
+    if drain.is_draining() {
+        let mut response = (
+            StatusCode::SERVICE_UNAVAILABLE,
+            axum::Json(json!({
+                "error": {
+                    "message": "Proxy is shutting down",
+                    "type": "server_error",
+                    "code": "server_shutting_down"
+                }
+            })),
+        )
+            .into_response();
+        response.headers_mut().insert(header::CONNECTION, HeaderValue::from_static("close"));
+        return response;
+    }
+
+    let guard = drain.enter();
+    let format = ClientFormat::from_path(request.uri().path());
+    let response = next.run(request).await;
+    let is_sse = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map_or(false, |ct| ct.contains("text/event-stream"));
+    if !is_sse {
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let stream = drain_stream(body.into_data_stream(), format, guard, drain.terminate.subscribe());
+    Response::from_parts(parts, Body::from_stream(stream))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_drain_waits_for_streams() {
+        let drain = Arc::new(RequestDrain::new(5));
+        let guard = drain.enter();
+        let chunks = vec![Ok::<_, ()>(Bytes::from("data: {\"x\":1}\n\n")), Ok(Bytes::from("data: [DONE]\n\n"))];
+        let stream = drain_stream(futures::stream::iter(chunks), ClientFormat::OpenAI, guard, drain.terminate.subscribe());
+        assert_eq!(drain.active(), 1);
+
+        let waiter = tokio::spawn({
+            let drain = drain.clone();
+            async move { drain.drain().await }
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(drain.is_draining());
+        let out: Vec<Bytes> = stream.map(|c| c.unwrap()).collect().await;
+        assert_eq!(out.len(), 2);
+        assert_eq!(waiter.await.unwrap(), 0);
+        assert_eq!(drain.active(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_timeout_terminates_at_event_boundary() {
+        let drain = Arc::new(RequestDrain::new(0));
+        let guard = drain.enter();
+        // 第二块只写了半个事件, 之后上游不再有数据
+        let chunks = vec![Ok::<_, ()>(Bytes::from("event: content_block_delta\ndata: {}\n\n")), Ok(Bytes::from("event: ping\n"))];
+        let upstream = futures::stream::iter(chunks).chain(futures::stream::pending());
+        let stream = drain_stream(Box::pin(upstream), ClientFormat::Anthropic, guard, drain.terminate.subscribe());
+        let collected = tokio::spawn(stream.map(|c| c.unwrap()).collect::<Vec<Bytes>>());
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        assert_eq!(drain.drain().await, 1);
+        let out = collected.await.unwrap();
+        let text: String = out.iter().map(|c| String::from_utf8_lossy(c).into_owned()).collect();
+        assert!(text.contains("event: ping\n\n\nevent: error\n"));
+        assert!(text.contains("Proxy is shutting down"));
+        assert_eq!(drain.active(), 0);
+    }
+}
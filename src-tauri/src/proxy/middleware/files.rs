@@ -0,0 +1,52 @@
+// 文件引用中间件
+// Chat Completions / Responses 请求中引用 Files API `file_id` 的内容块在转发前展开为 data URL。
+// 位于会话记忆之内: 会话保存的是文件引用, 每次转发时重新展开。引用的文件不存在时返回 400。
+
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde_json::{json, Value};
+
+use crate::proxy::files::{may_reference_files, FileStore};
+use crate::proxy::middleware::body_limit::is_multipart;
+use crate::proxy::middleware::buffer::buffer_request;
+
+pub async fn file_reference_middleware(request: Request, next: Next) -> Response {
+    let path = request.uri().path();
+    if request.method() != Method::POST
+        || !matches!(path, "/v1/chat/completions" | "/v1/responses")
+        || is_multipart(request.headers())
+        || !FileStore::global().config().enabled
+    {
+        return next.run(request).await;
+    }
+    let (mut parts, body) = request.into_parts();
+    let bytes = match buffer_request(body).await {
+        Ok(bytes) => bytes,
+        Err(response) => return response,
+    };
+    if !may_reference_files(&bytes) {
+        return next.run(Request::from_parts(parts, Body::from(bytes))).await;
+    }
+    let Ok(mut json) = serde_json::from_slice::<Value>(&bytes) else {
+        return next.run(Request::from_parts(parts, Body::from(bytes))).await;
+    };
+    let api_key = crate::proxy::middleware::auth::extract_api_key(&parts.headers);
+    let owner = crate::proxy::middleware::auth::key_owner(api_key);
+    match FileStore::global().inline_references(&owner, &mut json) {
+        Ok(0) => next.run(Request::from_parts(parts, Body::from(bytes))).await,
+        Ok(count) => {
+            tracing::debug!("[Files] {} inlined {} file reference(s)", parts.uri.path(), count);
+            parts.headers.remove(header::CONTENT_LENGTH);
+            next.run(Request::from_parts(parts, Body::from(json.to_string()))).await
+        }
+        Err(e) => {
+            let body = json!({ "error": { "message": e, "type": "invalid_request_error", "code": "file_not_found" } });
+            (StatusCode::BAD_REQUEST, axum::Json(body)).into_response()
+        }
+    }
+}
@@ -0,0 +1,204 @@
+// 请求对冲中间件
+// 位于智能路由之外、影子流量之内: 备用请求与主请求一样经过智能路由、备用模型链、过滤、缓存与并发限制,
+// 排队时间同样计入首字节等待; 缓存命中的请求不会对冲。仅处理请求体中带 `model` 的 POST 请求。
+// 两个请求在同一任务中并发执行, 返回时丢弃落败的一方, 其上游连接随之关闭。
+
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header, HeaderValue, Method},
+    middleware::Next,
+    response::Response,
+};
+use futures::StreamExt;
+use serde_json::{json, Value};
+
+use crate::proxy::middleware::body_limit::is_multipart;
+use crate::proxy::middleware::buffer::buffer_request;
+use crate::proxy::middleware::stream_watchdog::is_heartbeat;
+
+/// 响应头: 发生对冲时胜出的一方 (`primary` / `backup`)
+pub const HEDGE_HEADER: &str = "x-hedge-winner";
+
+/// 等待响应体的首个数据块 (SSE 心跳不算), 再把读到的数据块放回响应体
+async fn first_byte(response: Response) -> Response {
+    let (parts, body) = response.into_parts();
+    let mut stream = body.into_data_stream();
+    let mut read = Vec::new();
+    while let Some(chunk) = stream.next().await {
+        let heartbeat = chunk.as_ref().is_ok_and(|c| is_heartbeat(c));
+        read.push(chunk);
+        if !heartbeat {
+            break;
+        }
+    }
+    let body = Body::from_stream(futures::stream::iter(read).chain(stream));
+    Response::from_parts(parts, body)
+}
+
+fn is_error(response: &Response) -> bool {
+    response.status().as_u16() >= 400
+}
+
+pub async fn hedging_middleware(request: Request, next: Next) -> Response {
+    if !crate::proxy::hedging::is_enabled() || request.method() != Method::POST || is_multipart(request.headers()) {
+        return next.run(request).await;
+    }
+    let (mut parts, body) = request.into_parts();
+    let bytes = match buffer_request(body).await {
+        Ok(bytes) => bytes,
+        Err(response) => return response,
+    };
+    let Ok(mut json) = serde_json::from_slice::<Value>(&bytes) else {
+        return next.run(Request::from_parts(parts, Body::from(bytes))).await;
+    };
+    let hedge = json
+        .get("model")
+        .and_then(Value::as_str)
+        .map(|m| (m.to_string(), crate::proxy::hedging::rule_for(m)));
+    let Some((model, Some(hedge))) = hedge else {
+        return next.run(Request::from_parts(parts, Body::from(bytes))).await;
+    };
+
+    parts.headers.remove(header::CONTENT_LENGTH);
+    let primary_request = Request::from_parts(parts.clone(), Body::from(bytes));
+    let primary_next = next.clone();
+    let primary = async move { first_byte(primary_next.run(primary_request).await).await };
+    tokio::pin!(primary);
+    tokio::select! {
+        response = &mut primary => return response,
+        _ = tokio::time::sleep(hedge.threshold) => {}
+    }
+
+    tracing::info!(
+        "[Hedging] {} has no first byte after {}ms, sending backup {} (rule '{}')",
+        model,
+        hedge.threshold.as_millis(),
+        hedge.backup_model,
+        hedge.rule
+    );
+    json["model"] = json!(hedge.backup_model);
+    let backup_request = Request::from_parts(parts, Body::from(json.to_string()));
+    let backup = async move { first_byte(next.run(backup_request).await).await };
+    tokio::pin!(backup);
+    let (backup_won, response) = tokio::select! {
+        response = &mut primary => (false, response),
+        response = &mut backup => (true, response),
+    };
+    // 先返回的一方出错时等待另一方, 两边都失败时返回主请求的错误
+    let (backup_won, response) = if !is_error(&response) {
+        (backup_won, response)
+    } else if backup_won {
+        (false, primary.await)
+    } else {
+        let other = backup.await;
+        if is_error(&other) { (false, response) } else { (true, other) }
+    };
+
+    let winner = if backup_won { "backup" } else { "primary" };
+    crate::proxy::monitor::note_param_adjustments(&[format!(
+        "hedged after {}ms: {} won ({})",
+        hedge.threshold.as_millis(),
+        winner,
+        if backup_won { &hedge.backup_model } else { &model }
+    )]);
+    let mut response = response;
+    response.headers_mut().insert(HEDGE_HEADER, HeaderValue::from_static(winner));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy::config::{HedgingConfig, HedgingRule};
+    use axum::{http::StatusCode, routing::post, Router};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tower::Service;
+
+    #[tokio::test]
+    async fn test_first_byte_keeps_body() {
+        let chunks = futures::stream::iter(vec![Ok::<_, std::io::Error>("data: a\n\n"), Ok("data: b\n\n")]);
+        let response = first_byte(Response::new(Body::from_stream(chunks))).await;
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], b"data: a\n\ndata: b\n\n");
+    }
+
+    /// 被丢弃时设置标记, 用于确认落败的一方已被取消
+    struct DropFlag(Arc<AtomicBool>);
+
+    impl Drop for DropFlag {
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    async fn send(app: &Router, model: &str) -> (Option<String>, String) {
+        let request = Request::post("/v1/chat/completions")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(json!({ "model": model }).to_string()))
+            .unwrap();
+        let response = app.clone().call(request).await.unwrap();
+        let winner = response.headers().get(HEDGE_HEADER).map(|v| v.to_str().unwrap().to_string());
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (winner, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_hedged_requests() {
+        crate::proxy::hedging::configure(&HedgingConfig {
+            enabled: true,
+            rules: vec![HedgingRule {
+                name: "test".to_string(),
+                models: Vec::new(),
+                backup_model: "backup".to_string(),
+                threshold_ms: 50,
+                enabled: true,
+            }],
+        });
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let flag = cancelled.clone();
+        let upstream = move |axum::Json(body): axum::Json<Value>| {
+            let flag = flag.clone();
+            async move {
+                let model = body["model"].as_str().unwrap_or_default().to_string();
+                match model.as_str() {
+                    // 立即返回心跳, 正文很久之后才到
+                    "slow" => {
+                        let guard = DropFlag(flag);
+                        let rest = futures::stream::once(async move {
+                            let _guard = guard;
+                            tokio::time::sleep(Duration::from_secs(5)).await;
+                            Ok::<_, std::io::Error>("data: slow\n\n")
+                        });
+                        let chunks = futures::stream::once(async { Ok(": ping\n\n") }).chain(rest);
+                        Response::new(Body::from_stream(chunks))
+                    }
+                    "failing" => {
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                        let mut response = Response::new(Body::from("failed"));
+                        *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+                        response
+                    }
+                    "backup" => {
+                        tokio::time::sleep(Duration::from_millis(200)).await;
+                        Response::new(Body::from("backup"))
+                    }
+                    _ => Response::new(Body::from(model)),
+                }
+            }
+        };
+        let app = Router::new()
+            .route("/v1/chat/completions", post(upstream))
+            .layer(axum::middleware::from_fn(hedging_middleware));
+
+        // 阈值内返回的请求不对冲
+        assert_eq!(send(&app, "quick").await, (None, "quick".to_string()));
+        // 只有心跳的主请求被备用请求超过, 主请求随即被取消
+        assert_eq!(send(&app, "slow").await, (Some("backup".to_string()), "backup".to_string()));
+        assert!(cancelled.load(Ordering::SeqCst));
+        // 主请求先出错时等待备用请求
+        assert_eq!(send(&app, "failing").await, (Some("backup".to_string()), "backup".to_string()));
+    }
+}
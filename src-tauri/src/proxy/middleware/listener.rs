@@ -0,0 +1,121 @@
+// 额外监听的访问策略
+// 每个额外监听在共享路由之外再套一层: 可以强制鉴权 (不受 auth_mode 影响), 也可以关闭管理接口,
+// 例如本机工具使用免鉴权的主监听, 局域网使用另一个端口并强制校验 Key。
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Request, State},
+    http::{Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde_json::json;
+use tokio::sync::RwLock;
+
+use crate::proxy::middleware::auth::extract_api_key;
+use crate::proxy::virtual_keys::VirtualKeyStore;
+use crate::proxy::ProxySecurityConfig;
+
+pub struct ListenerPolicy {
+    pub name: String,
+    pub require_auth: bool,
+    pub allow_admin: bool,
+    pub security: Arc<RwLock<ProxySecurityConfig>>,
+}
+
+fn is_health_check(path: &str) -> bool {
+    matches!(path, "/healthz" | "/health" | "/api/health")
+}
+
+/// 管理接口与 OAuth 回调
+pub(crate) fn is_admin_path(path: &str) -> bool {
+    path == "/api" || path.starts_with("/api/") || path.starts_with("/auth/")
+}
+
+/// 代理 Key、管理密码或有效的虚拟 Key; 管理 Token 只在管理接口上有效
+fn key_accepted(key: &str, path: &str, security: &ProxySecurityConfig) -> bool {
+    (!security.api_key.is_empty() && key == security.api_key)
+        || security.admin_password.as_deref().is_some_and(|p| !p.is_empty() && key == p)
+        || (is_admin_path(path) && security.admin_tokens.iter().any(|t| t.token == key))
+        || VirtualKeyStore::global().identify(key).is_some()
+}
+
+fn error_response(status: StatusCode, message: String, code: &str) -> Response {
+    let body = json!({
+        "error": {
+            "message": message,
+            "type": "invalid_request_error",
+            "param": null,
+            "code": code
+        }
+    });
+    (status, axum::Json(body)).into_response()
+}
+
+pub async fn listener_policy_middleware(
+    State(policy): State<Arc<ListenerPolicy>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let path = request.uri().path();
+    if request.method() == Method::OPTIONS || is_health_check(path) {
+        return next.run(request).await;
+    }
+    if !policy.allow_admin && is_admin_path(path) {
+        return error_response(
+            StatusCode::NOT_FOUND,
+            format!("Admin API is not available on listener '{}'", policy.name),
+            "not_found",
+        );
+    }
+    if policy.require_auth {
+        let accepted = match extract_api_key(request.headers()).filter(|k| !k.is_empty()) {
+            Some(key) if key_accepted(key, path, &*policy.security.read().await) => true,
+            Some(key) if crate::proxy::jwt_auth::looks_like_jwt(key) => crate::proxy::jwt_auth::verify(key).await.is_ok(),
+            Some(_) => false,
+            None => false,
+        };
+        if !accepted {
+            tracing::warn!("[Listener:{}] rejected unauthenticated request {}", policy.name, path);
+            return error_response(
+                StatusCode::UNAUTHORIZED,
+                format!("A valid API key is required on listener '{}'", policy.name),
+                "invalid_api_key",
+            );
+        }
+    }
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_listener_paths_and_keys() {
+        assert!(is_admin_path("/api/accounts"));
+        assert!(is_admin_path("/auth/callback"));
+        assert!(!is_admin_path("/v1/chat/completions"));
+        assert!(!is_admin_path("/apix"));
+        assert!(is_health_check("/api/health"));
+
+        let security = ProxySecurityConfig {
+            auth_mode: crate::proxy::ProxyAuthMode::Off,
+            api_key: "sk-local".to_string(),
+            admin_password: Some("admin-pass".to_string()),
+            admin_tokens: vec![crate::proxy::config::AdminToken {
+                name: "dashboard".to_string(),
+                token: "view-token".to_string(),
+                role: crate::proxy::config::AdminRole::Viewer,
+                enabled: true,
+            }],
+            allow_lan_access: true,
+            port: 8045,
+        };
+        assert!(key_accepted("sk-local", "/v1/models", &security));
+        assert!(key_accepted("admin-pass", "/v1/models", &security));
+        assert!(key_accepted("view-token", "/api/stats/summary", &security));
+        assert!(!key_accepted("view-token", "/v1/chat/completions", &security));
+    }
+}
@@ -1,5 +1,113 @@
 // 日志中间件
-// 直接使用 tower_http::trace::TraceLayer::new_for_http() 在路由中
+// 访问日志直接使用 tower_http::trace::TraceLayer::new_for_http() 在路由中,
+// 这里提供可选的请求/响应 Body 记录 (含流式响应), 写入前脱敏。
+
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+use std::time::Instant;
+
+use crate::proxy::body_logger::{body_to_text, BodyLogEntry};
+use crate::proxy::common::stream_relay::next_chunk;
+use crate::proxy::middleware::body_limit::is_multipart;
+use crate::proxy::middleware::buffer::buffer_request;
+use crate::proxy::server::AppState;
+
+pub async fn body_logging_middleware(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let store = state.body_logger.clone();
+    if !store.is_enabled() {
+        return next.run(request).await;
+    }
+
+    let cfg = store.config();
+    let redactor = store.redactor();
+    let limit = (cfg.max_body_kb.max(1) * 1024) as usize;
+    let start = Instant::now();
+
+    let method = request.method().to_string();
+    let url = redactor.redact_text(&request.uri().to_string());
+    let request_headers = redactor.redact_headers(request.headers());
+
+    // multipart 上传由处理器流式转发, 不缓冲也不记录请求体
+    let (request, request_body, request_truncated) = if is_multipart(request.headers()) {
+        (request, Some("[Multipart Upload]".to_string()), false)
+    } else {
+        let (parts, body) = request.into_parts();
+        let request_bytes = match buffer_request(body).await {
+            Ok(bytes) => bytes,
+            Err(response) => return response,
+        };
+        let (request_body, request_truncated) = body_to_text(&request_bytes, limit);
+        (Request::from_parts(parts, Body::from(request_bytes)), request_body, request_truncated)
+    };
+
+    let response = next.run(request).await;
+
+    let is_stream = response
+        .headers()
+        .get("content-type")
+        .and_then(|v| v.to_str().ok())
+        .map(|ct| ct.contains("text/event-stream"))
+        .unwrap_or(false);
+
+    let mut entry = BodyLogEntry {
+        id: uuid::Uuid::new_v4().to_string(),
+        timestamp: chrono::Utc::now().timestamp_millis(),
+        method,
+        url,
+        status: response.status().as_u16(),
+        duration_ms: 0,
+        request_headers,
+        request_body: request_body.map(|b| redactor.redact_text(&b)),
+        response_headers: redactor.redact_headers(response.headers()),
+        response_body: None,
+        stream: is_stream,
+        truncated: request_truncated,
+    };
+
+    // 流式响应边转发边收集, 结束后写入; 非流式响应同样以流方式透传, 不额外缓冲整个 Body
+    let (parts, body) = response.into_parts();
+    let mut stream = body.into_data_stream();
+    let (tx, rx) = tokio::sync::mpsc::channel(64);
+
+    tokio::spawn(async move {
+        let mut captured: Vec<u8> = Vec::new();
+        let mut total = 0usize;
+        while let Ok(Some(chunk)) = next_chunk(&mut stream, &tx).await {
+            match chunk {
+                Ok(chunk) => {
+                    total += chunk.len();
+                    if captured.len() < limit {
+                        let take = (limit - captured.len()).min(chunk.len());
+                        captured.extend_from_slice(&chunk[..take]);
+                    }
+                    if tx.send(Ok::<_, axum::Error>(chunk)).await.is_err() {
+                        break; // 客户端已断开
+                    }
+                }
+                Err(e) => {
+                    let _ = tx.send(Err(e)).await;
+                    break;
+                }
+            }
+        }
+
+        let (response_body, _) = body_to_text(&captured, limit);
+        entry.response_body = response_body.map(|b| redactor.redact_text(&b));
+        entry.truncated |= total > limit;
+        entry.duration_ms = start.elapsed().as_millis() as u64;
+        store.append(&entry).await;
+    });
+
+    Response::from_parts(parts, Body::from_stream(tokio_stream::wrappers::ReceiverStream::new(rx)))
+}
 
 #[cfg(test)]
 mod tests {
@@ -1,13 +1,69 @@
 // Middleware 模块 - Axum 中间件
 
+pub mod access_control;
+pub mod audit;
 pub mod auth;
+pub mod body_limit;
+pub mod buffer;
+pub mod cache;
+pub mod compression;
+pub mod concurrency;
+pub mod content_filter;
+pub mod context_window;
+pub mod conversation_memory;
+pub mod council;
 pub mod cors;
+pub mod drain;
+pub mod files;
+pub mod hedging;
+pub mod listener;
 pub mod logging;
+pub mod model_fallback;
 pub mod monitor;
+pub mod pii_redaction;
+pub mod prompt_template;
+pub mod rate_limit;
+pub mod shadow;
+pub mod request_id;
+pub mod smart_routing;
+pub mod stream_bridge;
+pub mod structured_output;
+pub mod tool_emulation;
+pub mod system_prompt;
+pub mod stream_transform;
+pub mod stream_watchdog;
 
 pub mod service_status;
 
+pub use access_control::access_control_middleware;
+pub use audit::audit_middleware;
+pub use body_limit::body_limit_middleware;
+pub use cache::response_cache_middleware;
+pub use compression::{response_compression_middleware, upstream_decoding_middleware};
+pub use concurrency::concurrency_middleware;
+pub use content_filter::content_filter_middleware;
+pub use context_window::context_window_middleware;
+pub use conversation_memory::conversation_memory_middleware;
+pub use council::council_middleware;
 pub use cors::cors_layer;
+pub use drain::drain_middleware;
+pub use files::file_reference_middleware;
+pub use hedging::hedging_middleware;
+pub use listener::listener_policy_middleware;
+pub use logging::body_logging_middleware;
+pub use model_fallback::model_fallback_middleware;
 pub use monitor::monitor_middleware;
+pub use pii_redaction::pii_redaction_middleware;
+pub use prompt_template::prompt_template_middleware;
+pub use rate_limit::rate_limit_middleware;
+pub use shadow::{shadow_middleware, shadow_scope_middleware};
+pub use request_id::request_id_middleware;
+pub use smart_routing::smart_routing_middleware;
+pub use stream_bridge::stream_bridge_middleware;
+pub use structured_output::structured_output_middleware;
+pub use tool_emulation::tool_emulation_middleware;
+pub use system_prompt::system_prompt_middleware;
+pub use stream_transform::stream_transform_middleware;
+pub use stream_watchdog::stream_watchdog_middleware;
 pub use service_status::service_status_middleware;
 pub use auth::{auth_middleware, admin_auth_middleware};
@@ -0,0 +1,165 @@
+// 备用模型链中间件
+// 位于文件引用之内、内容过滤之外: 每次尝试都重新经过过滤、脱敏、上下文检查与缓存,
+// 本地上下文检查拒绝的请求同样可以改用上下文更大的备用模型。
+// 仅处理请求体中带 `model` 的 POST 请求 (Gemini 原生路由的模型在路径中, 不参与);
+// 只根据上游返回的错误状态与错误码切换, 已经开始的流式响应中途出错时无法重试;
+// 带 `x-local-rejection` 头的本地拒绝 (例如内容过滤规则) 与模型无关, 直接返回。
+
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{header, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use serde_json::{json, Value};
+
+use crate::proxy::common::model_alias::without_aliases;
+use crate::proxy::config::FallbackTrigger;
+use crate::proxy::middleware::body_limit::is_multipart;
+use crate::proxy::middleware::buffer::{buffer_request, buffer_response};
+use crate::proxy::server::AppState;
+
+/// 响应头: 最终使用的备用模型
+pub const FALLBACK_HEADER: &str = "x-model-fallback";
+
+/// 响应头: 本地中间件生成的拒绝响应 (值为来源), 换用备用模型不会改变结果
+pub const LOCAL_REJECTION_HEADER: &str = "x-local-rejection";
+
+/// OpenAI / Azure 的内容审核错误码
+const CONTENT_FILTER_CODES: &[&str] = &["content_filter", "content_policy_violation", "ResponsibleAIPolicyViolation"];
+const CONTEXT_LENGTH_CODES: &[&str] = &["context_length_exceeded", "string_above_max_length"];
+/// Anthropic / Gemini 的上下文超长错误没有专门的错误码, 按错误信息判断
+const CONTEXT_LENGTH_HINTS: &[&str] = &[
+    "maximum context length",
+    "context window",
+    "prompt is too long",
+    "input token count",
+    "too many tokens",
+];
+
+fn label(trigger: FallbackTrigger) -> &'static str {
+    match trigger {
+        FallbackTrigger::ContentFilter => "content_filter",
+        FallbackTrigger::ContextLength => "context_length",
+        FallbackTrigger::RateLimit => "rate_limit",
+    }
+}
+
+/// 按状态码与上游错误码判断错误类型, 成功响应与无法识别的错误返回 None
+pub fn classify(status: StatusCode, body: &[u8]) -> Option<FallbackTrigger> {
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        return Some(FallbackTrigger::RateLimit);
+    }
+    if !status.is_client_error() && !status.is_server_error() {
+        return None;
+    }
+    let json = serde_json::from_slice::<Value>(body).ok()?;
+    let error = json.get("error")?;
+    // OpenAI / Azure: error.code (Azure 另有 innererror.code), Anthropic: error.type, Gemini: error.status
+    let codes: Vec<&str> = ["/code", "/innererror/code", "/type", "/status"]
+        .iter()
+        .filter_map(|p| error.pointer(p).and_then(Value::as_str))
+        .collect();
+    let message = error.get("message").and_then(Value::as_str).unwrap_or_default().to_lowercase();
+    if codes.iter().any(|c| CONTEXT_LENGTH_CODES.contains(c)) || CONTEXT_LENGTH_HINTS.iter().any(|h| message.contains(h)) {
+        Some(FallbackTrigger::ContextLength)
+    } else if codes.iter().any(|c| CONTENT_FILTER_CODES.contains(c)) {
+        Some(FallbackTrigger::ContentFilter)
+    } else {
+        None
+    }
+}
+
+pub async fn model_fallback_middleware(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    if !state.model_aliases.has_fallbacks() || request.method() != Method::POST || is_multipart(request.headers()) {
+        return next.run(request).await;
+    }
+    let (mut parts, body) = request.into_parts();
+    let bytes = match buffer_request(body).await {
+        Ok(bytes) => bytes,
+        Err(response) => return response,
+    };
+    let Ok(mut json) = serde_json::from_slice::<Value>(&bytes) else {
+        return next.run(Request::from_parts(parts, Body::from(bytes))).await;
+    };
+    let alias = json
+        .get("model")
+        .and_then(Value::as_str)
+        .and_then(|m| state.model_aliases.resolve(m))
+        .filter(|a| !a.fallbacks.is_empty());
+    let Some(alias) = alias else {
+        return next.run(Request::from_parts(parts, Body::from(bytes))).await;
+    };
+
+    parts.headers.remove(header::CONTENT_LENGTH);
+    let mut response = next.clone().run(Request::from_parts(parts.clone(), Body::from(bytes))).await;
+    let mut current = alias.target.clone();
+    let mut fell_back = false;
+    for fallback in &alias.fallbacks {
+        if response.status().as_u16() < 400 || response.headers().contains_key(LOCAL_REJECTION_HEADER) {
+            break;
+        }
+        let (response_parts, body) = response.into_parts();
+        let body = match buffer_response(body).await {
+            Ok(body) => body,
+            Err(response) => return response,
+        };
+        let trigger = classify(response_parts.status, &body).filter(|t| alias.fallback_on.contains(t));
+        let Some(trigger) = trigger else {
+            return Response::from_parts(response_parts, Body::from(body));
+        };
+
+        tracing::warn!(
+            "[ModelFallback] {} failed with {} ({}), retrying with {}",
+            current,
+            response_parts.status.as_u16(),
+            label(trigger),
+            fallback
+        );
+        crate::proxy::monitor::note_param_adjustments(&[format!(
+            "model fallback: {} -> {} ({})",
+            current,
+            fallback,
+            label(trigger)
+        )]);
+        json["model"] = json!(fallback);
+        let request = Request::from_parts(parts.clone(), Body::from(json.to_string()));
+        response = without_aliases(next.clone().run(request)).await;
+        current = fallback.clone();
+        fell_back = true;
+    }
+
+    if fell_back {
+        if let Ok(value) = HeaderValue::from_str(&current) {
+            response.headers_mut().insert(FALLBACK_HEADER, value);
+        }
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_upstream_errors() {
+        let openai = br#"{"error":{"message":"This model's maximum context length is 128000 tokens","code":"context_length_exceeded"}}"#;
+        assert_eq!(classify(StatusCode::BAD_REQUEST, openai), Some(FallbackTrigger::ContextLength));
+        let anthropic = br#"{"type":"error","error":{"type":"invalid_request_error","message":"prompt is too long: 210000 tokens > 200000 maximum"}}"#;
+        assert_eq!(classify(StatusCode::BAD_REQUEST, anthropic), Some(FallbackTrigger::ContextLength));
+        let azure = br#"{"error":{"code":"content_filter","message":"The response was filtered due to the prompt triggering content management policy"}}"#;
+        assert_eq!(classify(StatusCode::BAD_REQUEST, azure), Some(FallbackTrigger::ContentFilter));
+        assert_eq!(classify(StatusCode::TOO_MANY_REQUESTS, b""), Some(FallbackTrigger::RateLimit));
+
+        let gemini = br#"{"error":{"code":400,"message":"The input token count (1200000) exceeds the maximum number of tokens allowed","status":"INVALID_ARGUMENT"}}"#;
+        assert_eq!(classify(StatusCode::BAD_REQUEST, gemini), Some(FallbackTrigger::ContextLength));
+
+        assert_eq!(classify(StatusCode::BAD_REQUEST, br#"{"error":{"message":"invalid temperature"}}"#), None);
+        // 错误信息中出现 safety 等字样但没有内容审核错误码
+        let safety = br#"{"error":{"message":"safety_settings: invalid threshold","code":"invalid_value"}}"#;
+        assert_eq!(classify(StatusCode::BAD_REQUEST, safety), None);
+        assert_eq!(classify(StatusCode::BAD_GATEWAY, b"upstream failed"), None);
+        assert_eq!(classify(StatusCode::OK, openai), None);
+    }
+}
@@ -6,12 +6,17 @@ use axum::{
 };
 use std::time::Instant;
 use crate::proxy::server::AppState;
-use crate::proxy::monitor::ProxyRequestLog;
+use crate::proxy::monitor::{with_request_notes, InflightRequest, ProxyRequestLog, CLIENT_CLOSED_STATUS};
+use crate::proxy::middleware::body_limit::is_multipart;
+use crate::proxy::middleware::buffer::{buffer_request, buffer_response};
+use crate::proxy::middleware::rate_limit::RateLimitCharge;
+use crate::proxy::common::prompt_cache::{read_usage, TokenUsage};
+use crate::proxy::common::stream_relay::next_chunk;
 use serde_json::Value;
-use futures::StreamExt;
 
-const MAX_REQUEST_LOG_SIZE: usize = 100 * 1024 * 1024; // 100MB
-const MAX_RESPONSE_LOG_SIZE: usize = 100 * 1024 * 1024; // 100MB for image responses
+/// 请求体中解析出的模型名 (请求扩展)
+#[derive(Debug, Clone)]
+pub(crate) struct RequestedModel(pub String);
 
 pub async fn monitor_middleware(
     State(state): State<AppState>,
@@ -22,12 +27,39 @@ pub async fn monitor_middleware(
     
     let method = request.method().to_string();
     let uri = request.uri().to_string();
+
+    // Realtime 的模型在查询参数中, 同样交给鉴权中间件校验
+    let mut request = request;
+    if uri.starts_with("/v1/realtime") {
+        if let Some(m) = crate::proxy::handlers::realtime::query_param(request.uri().query(), "model") {
+            request.extensions_mut().insert(RequestedModel(m));
+        }
+    }
     
-    if uri.contains("event_logging") || uri.contains("/api/") {
+    // Realtime 会话在结束时由 handler 单独记录
+    if uri.contains("event_logging") || uri.contains("/api/") || uri.starts_with("/v1/realtime") || uri == "/metrics" {
         return next.run(request).await;
     }
     
     let start = Instant::now();
+
+    // 日志和用量记录使用同一个请求 ID (由 request_id_middleware 分配)
+    let request_id = request
+        .extensions()
+        .get::<crate::proxy::middleware::request_id::RequestId>()
+        .map(|id| id.0.clone())
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+    let api_key = crate::proxy::middleware::auth::extract_api_key(request.headers())
+        .filter(|k| !k.is_empty())
+        .map(str::to_string);
+    let client = crate::proxy::common::client_info::identify(
+        request.headers(),
+        request
+            .extensions()
+            .get::<axum::extract::ConnectInfo<std::net::SocketAddr>>()
+            .map(|c| c.0),
+    );
     
     let mut model = if uri.contains("/v1beta/models/") {
         uri.split("/v1beta/models/")
@@ -39,9 +71,13 @@ pub async fn monitor_middleware(
     };
 
     let request_body_str;
-    let request = if method == "POST" {
+    let mut request = if method == "POST" && is_multipart(request.headers()) {
+        // multipart 上传由处理器流式转发, 不缓冲
+        request_body_str = Some("[Multipart Upload]".to_string());
+        request
+    } else if method == "POST" {
         let (parts, body) = request.into_parts();
-        match axum::body::to_bytes(body, MAX_REQUEST_LOG_SIZE).await {
+        match buffer_request(body).await {
             Ok(bytes) => {
                 if model.is_none() {
                     model = serde_json::from_slice::<Value>(&bytes).ok().and_then(|v|
@@ -55,20 +91,65 @@ pub async fn monitor_middleware(
                 };
                 Request::from_parts(parts, Body::from(bytes))
             }
-            Err(_) => {
-                request_body_str = None;
-                Request::from_parts(parts, Body::empty())
-            }
+            Err(response) => return response,
         }
     } else {
         request_body_str = None;
         request
     };
+
+    // 供鉴权中间件校验虚拟 Key 的可用模型
+    if let Some(m) = &model {
+        request.extensions_mut().insert(RequestedModel(m.clone()));
+    }
     
-    let response = next.run(request).await;
+    // Determine protocol from URL path
+    let protocol = if uri.contains("/v1/messages") {
+        Some("anthropic".to_string())
+    } else if uri.contains("/v1beta/models") {
+        Some("gemini".to_string())
+    } else if uri.starts_with("/v1/") {
+        Some("openai".to_string())
+    } else {
+        None
+    };
+
+    // 实时检查器: 记录进行中的请求 (关闭日志时只计数)、协议转换后的上游请求体与参数调整
+    let mut param_adjustments = None;
+    let inflight = state.monitor.request_started(InflightRequest {
+        id: request_id.clone(),
+        timestamp: chrono::Utc::now().timestamp_millis(),
+        method: method.clone(),
+        url: uri.clone(),
+        model: model.clone(),
+        protocol: protocol.clone(),
+    });
+    let response = if state.monitor.is_enabled() {
+        let (response, notes) = with_request_notes(next.run(request)).await;
+        if let Some(capture) = notes.upstream_request {
+            state.monitor.store_upstream_capture(&request_id, capture);
+        }
+        if !notes.param_adjustments.is_empty() {
+            param_adjustments = Some(notes.param_adjustments.join("; "));
+        }
+        response
+    } else {
+        next.run(request).await
+    };
     
     let duration = start.elapsed().as_millis() as u64;
     let status = response.status().as_u16();
+
+    // 用量统计按客户端 API Key 聚合 (只保存指纹和掩码); 在鉴权之后计算, JWT 仅在签名校验通过后按 subject 归属。
+    // 鉴权失败的请求不计入用量
+    let (api_key_hash, api_key_hint) = api_key
+        .as_deref()
+        .map(|k| (
+            Some(crate::proxy::middleware::auth::api_key_fingerprint(k)),
+            Some(crate::proxy::middleware::auth::api_key_hint(k)),
+        ))
+        .unwrap_or((None, None));
+    let authorized = status != axum::http::StatusCode::UNAUTHORIZED.as_u16();
     
     let content_type = response.headers().get("content-type")
         .and_then(|v| v.to_str().ok())
@@ -82,27 +163,107 @@ pub async fn monitor_middleware(
         .and_then(|v| v.to_str().ok())
         .map(|s| s.to_string());
 
-    // Extract mapped model from X-Mapped-Model header if present
+    // Extract mapped model from X-Mapped-Model header if present (备用模型链切换后以最终模型为准)
     let mapped_model = response
         .headers()
         .get("X-Mapped-Model")
+        .or_else(|| response.headers().get(crate::proxy::middleware::model_fallback::FALLBACK_HEADER))
         .and_then(|v| v.to_str().ok())
         .map(|s| s.to_string());
 
-    // Determine protocol from URL path
-    let protocol = if uri.contains("/v1/messages") {
-        Some("anthropic".to_string())
-    } else if uri.contains("/v1beta/models") {
-        Some("gemini".to_string())
-    } else if uri.starts_with("/v1/") {
-        Some("openai".to_string())
-    } else {
-        None
+    // 上游排队时间 (由 concurrency_middleware 标记)
+    let queue_upstream = response.headers().get("X-Queue-Upstream").and_then(|v| v.to_str().ok());
+    let queue_wait_ms = response
+        .headers()
+        .get("X-Queue-Wait-Ms")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok());
+    if let (Some(upstream), Some(wait_ms)) = (queue_upstream, queue_wait_ms) {
+        state
+            .monitor
+            .metrics
+            .record_queue_wait(upstream, std::time::Duration::from_millis(wait_ms));
+    }
+
+    // 响应缓存统计 (由 response_cache_middleware 标记)
+    if let Some(cache) = response.headers().get("X-Cache").and_then(|v| v.to_str().ok()) {
+        state.monitor.record_cache_lookup(cache == "HIT");
+    }
+
+    let upstream = response
+        .headers()
+        .get("X-Upstream")
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.to_string())
+        .unwrap_or_else(|| if account_email.is_some() { "google" } else { "unknown" }.to_string());
+
+    // 客户端限流的预扣记录, 拿到实际用量后结算
+    let rate_limit_charge = response.extensions().get::<RateLimitCharge>().cloned();
+    let rate_limiter = state.client_rate_limiter.clone();
+    let metrics_monitor = state.monitor.clone();
+    let prices = state.pricing.read().await.models.clone();
+    // 请求完成: 结算限流额度, 更新指标与延迟统计并写入用量统计; `ttft` 仅流式响应有值
+    let finish_request = move |log: &mut ProxyRequestLog, ttft: Option<std::time::Duration>| {
+        fill_missing_usage(log);
+        let latency = std::time::Duration::from_millis(log.duration);
+        let metric_model = log.model.as_deref().unwrap_or("unknown");
+        metrics_monitor.metrics.record_request(metric_model, log.status, latency);
+        metrics_monitor.latency.record(metric_model, &upstream, latency, ttft);
+        if let Some(charge) = &rate_limit_charge {
+            if log.input_tokens.is_some() || log.output_tokens.is_some() {
+                let used = log.input_tokens.unwrap_or(0).saturating_add(log.output_tokens.unwrap_or(0));
+                rate_limiter.settle(charge, used);
+            }
+        }
+
+        let model = log.model.clone().unwrap_or_else(|| "unknown".to_string());
+        let prompt_tokens = log.input_tokens.unwrap_or(0);
+        let completion_tokens = log.output_tokens.unwrap_or(0);
+        let usage = TokenUsage {
+            input: log.input_tokens,
+            output: log.output_tokens,
+            cache_read: log.cache_read_tokens,
+            cache_write: log.cache_write_tokens,
+        };
+        let cost_usd = crate::proxy::common::pricing::usage_cost(&prices, &model, log.mapped_model.as_deref(), &usage);
+        if !authorized {
+            return;
+        }
+        if let Some(hash) = &api_key_hash {
+            let used = prompt_tokens as u64 + completion_tokens as u64;
+            crate::proxy::virtual_keys::VirtualKeyStore::global().record_usage(hash, used, cost_usd);
+        }
+
+        let record = crate::modules::usage::UsageRecord {
+            timestamp: log.timestamp / 1000,
+            model,
+            mapped_model: log.mapped_model.clone(),
+            upstream: upstream.clone(),
+            account_email: log.account_email.clone(),
+            api_key_hash: api_key_hash.clone(),
+            api_key_hint: api_key_hint.clone(),
+            client_name: log.client_name.clone(),
+            protocol: log.protocol.clone(),
+            prompt_tokens,
+            completion_tokens,
+            cache_read_tokens: log.cache_read_tokens.unwrap_or(0),
+            cache_write_tokens: log.cache_write_tokens.unwrap_or(0),
+            latency_ms: log.duration,
+            tokens_per_second: log.tokens_per_second,
+            status: log.status,
+            cost_usd,
+            request_id: Some(log.id.clone()),
+        };
+        tokio::task::spawn_blocking(move || {
+            if let Err(e) = crate::modules::usage::record(&record) {
+                tracing::debug!("Failed to record usage: {}", e);
+            }
+        });
     };
 
     let monitor = state.monitor.clone();
     let mut log = ProxyRequestLog {
-        id: uuid::Uuid::new_v4().to_string(),
+        id: request_id,
         timestamp: chrono::Utc::now().timestamp_millis(),
         method,
         url: uri,
@@ -117,6 +278,13 @@ pub async fn monitor_middleware(
         input_tokens: None,
         output_tokens: None,
         protocol,
+        param_adjustments,
+        client_name: client.name,
+        user_agent: client.user_agent,
+        client_port: client.source_port,
+        tokens_per_second: None,
+        cache_read_tokens: None,
+        cache_write_tokens: None,
     };
 
     if content_type.contains("text/event-stream") {
@@ -124,12 +292,34 @@ pub async fn monitor_middleware(
         let mut stream = body.into_data_stream();
         let (tx, rx) = tokio::sync::mpsc::channel(64);
         
+        let stream_monitor = state.monitor.clone();
         tokio::spawn(async move {
+            let _inflight = inflight;
             let mut all_stream_data = Vec::new();
             let mut last_few_bytes = Vec::new();
+            let active_stream = stream_monitor.metrics.stream_started();
+            let mut ttft = None;
+            let mut token_meter = crate::proxy::common::token_rate::StreamTokenMeter::new(
+                log.mapped_model.as_deref().or(log.model.as_deref()).unwrap_or("unknown"),
+            );
             
-            while let Some(chunk_res) = stream.next().await {
+            let mut cancelled = false;
+            loop {
+                let chunk_res = match next_chunk(&mut stream, &tx).await {
+                    Ok(Some(chunk_res)) => chunk_res,
+                    Ok(None) => break,
+                    Err(_) => {
+                        cancelled = true;
+                        break;
+                    }
+                };
                 if let Ok(chunk) = chunk_res {
+                    if ttft.is_none() {
+                        let elapsed = start.elapsed();
+                        ttft = Some(elapsed);
+                        stream_monitor.metrics.record_ttft(log.model.as_deref().unwrap_or("unknown"), elapsed);
+                    }
+                    token_meter.feed(&chunk, Instant::now());
                     all_stream_data.extend_from_slice(&chunk);
                     
                     if chunk.len() > 8192 {
@@ -140,111 +330,24 @@ pub async fn monitor_middleware(
                             last_few_bytes.drain(0..last_few_bytes.len()-8192);
                         }
                     }
-                    let _ = tx.send(Ok::<_, axum::Error>(chunk)).await;
+                    if tx.send(Ok::<_, axum::Error>(chunk)).await.is_err() {
+                        cancelled = true;
+                        break;
+                    }
                 } else if let Err(e) = chunk_res {
                     let _ = tx.send(Err(axum::Error::new(e))).await;
                 }
             }
+            // 客户端断开: 立即丢弃内层流, 关闭上游连接; 按已收到的部分记录用量
+            drop(stream);
             
+            drop(active_stream);
+            log.tokens_per_second = token_meter.tokens_per_second();
             // Parse and consolidate stream data into readable format
             if let Ok(full_response) = std::str::from_utf8(&all_stream_data) {
-                let mut thinking_content = String::new();
-                let mut response_content = String::new();
-                let mut thinking_signature = String::new();
-                
-                for line in full_response.lines() {
-                    if !line.starts_with("data: ") {
-                        continue;
-                    }
-                    let json_str = line.trim_start_matches("data: ").trim();
-                    if json_str == "[DONE]" {
-                        continue;
-                    }
-                    
-                    if let Ok(json) = serde_json::from_str::<Value>(json_str) {
-                        // OpenAI format: choices[0].delta.content / reasoning_content
-                        if let Some(choices) = json.get("choices").and_then(|c| c.as_array()) {
-                            for choice in choices {
-                                if let Some(delta) = choice.get("delta") {
-                                    // Thinking/reasoning content
-                                    if let Some(thinking) = delta.get("reasoning_content").and_then(|v| v.as_str()) {
-                                        thinking_content.push_str(thinking);
-                                    }
-                                    // Main response content
-                                    if let Some(content) = delta.get("content").and_then(|v| v.as_str()) {
-                                        response_content.push_str(content);
-                                    }
-                                }
-                            }
-                        }
-                        
-                        // Claude/Anthropic format: content_block_delta
-                        if let Some(delta) = json.get("delta") {
-                            // Thinking block
-                            if let Some(thinking) = delta.get("thinking").and_then(|v| v.as_str()) {
-                                thinking_content.push_str(thinking);
-                            }
-                            // Thinking signature
-                            if let Some(sig) = delta.get("signature").and_then(|v| v.as_str()) {
-                                thinking_signature = sig.to_string();
-                            }
-                            // Text content
-                            if let Some(text) = delta.get("text").and_then(|v| v.as_str()) {
-                                response_content.push_str(text);
-                            }
-                        }
-                        
-                        // Token usage extraction
-                        if let Some(usage) = json.get("usage")
-                            .or(json.get("usageMetadata"))
-                            .or(json.get("response").and_then(|r| r.get("usage")))
-                        {
-                            log.input_tokens = usage.get("prompt_tokens")
-                                .or(usage.get("input_tokens"))
-                                .or(usage.get("promptTokenCount"))
-                                .and_then(|v| v.as_u64())
-                                .map(|v| v as u32);
-                            log.output_tokens = usage.get("completion_tokens")
-                                .or(usage.get("output_tokens"))
-                                .or(usage.get("candidatesTokenCount"))
-                                .and_then(|v| v.as_u64())
-                                .map(|v| v as u32);
-                            
-                            if log.input_tokens.is_none() && log.output_tokens.is_none() {
-                                log.output_tokens = usage.get("total_tokens")
-                                    .or(usage.get("totalTokenCount"))
-                                    .and_then(|v| v.as_u64())
-                                    .map(|v| v as u32);
-                            }
-                        }
-                    }
-                }
-                
-                // Build consolidated response object
-                let mut consolidated = serde_json::Map::new();
-                
-                if !thinking_content.is_empty() {
-                    consolidated.insert("thinking".to_string(), Value::String(thinking_content));
-                }
-                if !thinking_signature.is_empty() {
-                    consolidated.insert("thinking_signature".to_string(), Value::String(thinking_signature));
-                }
-                if !response_content.is_empty() {
-                    consolidated.insert("content".to_string(), Value::String(response_content));
-                }
-                if let Some(input) = log.input_tokens {
-                    consolidated.insert("input_tokens".to_string(), Value::Number(input.into()));
-                }
-                if let Some(output) = log.output_tokens {
-                    consolidated.insert("output_tokens".to_string(), Value::Number(output.into()));
-                }
-                
-                if consolidated.is_empty() {
-                    // Fallback: store raw SSE data if parsing failed
-                    log.response_body = Some(full_response.to_string());
-                } else {
-                    log.response_body = Some(serde_json::to_string_pretty(&Value::Object(consolidated)).unwrap_or_else(|_| full_response.to_string()));
-                }
+                let (body, usage) = consolidate_stream(full_response);
+                log.response_body = Some(body);
+                apply_usage(&mut log, usage);
             } else {
                 log.response_body = Some(format!("[Binary Stream Data: {} bytes]", all_stream_data.len()));
             }
@@ -260,16 +363,7 @@ pub async fn monitor_middleware(
                                     .or(json.get("usageMetadata"))
                                     .or(json.get("response").and_then(|r| r.get("usage")))
                                 {
-                                    log.input_tokens = usage.get("prompt_tokens")
-                                        .or(usage.get("input_tokens"))
-                                        .or(usage.get("promptTokenCount"))
-                                        .and_then(|v| v.as_u64())
-                                        .map(|v| v as u32);
-                                    log.output_tokens = usage.get("completion_tokens")
-                                        .or(usage.get("output_tokens"))
-                                        .or(usage.get("candidatesTokenCount"))
-                                        .and_then(|v| v.as_u64())
-                                        .map(|v| v as u32);
+                                    apply_usage(&mut log, read_usage(usage));
                                     break;
                                 }
                             }
@@ -278,38 +372,29 @@ pub async fn monitor_middleware(
                 }
             }
             
-            if log.status >= 400 {
+            if cancelled {
+                tracing::info!("[Monitor] Client disconnected, cancelled upstream stream: {}", log.url);
+                log.status = CLIENT_CLOSED_STATUS;
+                log.error = Some("Client disconnected".to_string());
+            } else if log.status >= 400 {
                 log.error = Some("Stream Error or Failed".to_string());
             }
+            // 流式请求记录完整耗时 (而非首个响应头的耗时)
+            log.duration = start.elapsed().as_millis() as u64;
+            finish_request(&mut log, ttft);
             monitor.log_request(log).await;
         });
 
         Response::from_parts(parts, Body::from_stream(tokio_stream::wrappers::ReceiverStream::new(rx)))
     } else if content_type.contains("application/json") || content_type.contains("text/") {
         let (parts, body) = response.into_parts();
-        match axum::body::to_bytes(body, MAX_RESPONSE_LOG_SIZE).await {
+        match buffer_response(body).await {
             Ok(bytes) => {
                 if let Ok(s) = std::str::from_utf8(&bytes) {
                     if let Ok(json) = serde_json::from_str::<Value>(&s) {
                         // 支持 OpenAI "usage" 或 Gemini "usageMetadata"
                         if let Some(usage) = json.get("usage").or(json.get("usageMetadata")) {
-                            log.input_tokens = usage.get("prompt_tokens")
-                                .or(usage.get("input_tokens"))
-                                .or(usage.get("promptTokenCount"))
-                                .and_then(|v| v.as_u64())
-                                .map(|v| v as u32);
-                            log.output_tokens = usage.get("completion_tokens")
-                                .or(usage.get("output_tokens"))
-                                .or(usage.get("candidatesTokenCount"))
-                                .and_then(|v| v.as_u64())
-                                .map(|v| v as u32);
-                                
-                            if log.input_tokens.is_none() && log.output_tokens.is_none() {
-                                log.output_tokens = usage.get("total_tokens")
-                                    .or(usage.get("totalTokenCount"))
-                                    .and_then(|v| v.as_u64())
-                                    .map(|v| v as u32);
-                            }
+                            apply_usage(&mut log, read_usage(usage));
                         }
                     }
                     log.response_body = Some(s.to_string());
@@ -320,18 +405,152 @@ pub async fn monitor_middleware(
                 if log.status >= 400 {
                     log.error = log.response_body.clone();
                 }
+                finish_request(&mut log, None);
                 monitor.log_request(log).await;
                 Response::from_parts(parts, Body::from(bytes))
             }
-            Err(_) => {
-                log.response_body = Some("[Response too large (>100MB)]".to_string());
+            Err(response) => {
+                log.status = response.status().as_u16();
+                log.error = Some("Failed to read upstream response".to_string());
+                finish_request(&mut log, None);
                 monitor.log_request(log).await;
-                Response::from_parts(parts, Body::empty())
+                response
             }
         }
     } else {
         log.response_body = Some(format!("[{}]", content_type));
+        finish_request(&mut log, None);
         monitor.log_request(log).await;
         response
     }
 }
+
+fn apply_usage(log: &mut ProxyRequestLog, usage: TokenUsage) {
+    log.input_tokens = usage.input;
+    log.output_tokens = usage.output;
+    log.cache_read_tokens = usage.cache_read;
+    log.cache_write_tokens = usage.cache_write;
+}
+
+/// 上游没有返回 usage 时用本地 token 计数补全 (只处理成功与客户端中途断开的请求)
+fn fill_missing_usage(log: &mut ProxyRequestLog) {
+    if (log.status >= 400 && log.status != CLIENT_CLOSED_STATUS) || log.input_tokens.is_some() || log.output_tokens.is_some() {
+        return;
+    }
+    let Some(model) = log.mapped_model.as_deref().or(log.model.as_deref()) else {
+        return;
+    };
+    let (prompt, completion) = crate::proxy::common::token_counter::estimate_usage(
+        model,
+        log.request_body.as_deref(),
+        log.response_body.as_deref(),
+    );
+    // 只补全带 JSON 请求体的 AI 请求 (GET /v1/models 等不计)
+    if prompt.is_some() {
+        log.input_tokens = prompt;
+        log.output_tokens = completion;
+    }
+}
+
+/// 把 SSE 响应合并为可读的 JSON (思考内容、正文、token 数), 返回 (body, usage)
+pub(crate) fn consolidate_stream(full_response: &str) -> (String, TokenUsage) {
+    let mut usage = TokenUsage::default();
+    let mut thinking_content = String::new();
+    let mut response_content = String::new();
+    let mut thinking_signature = String::new();
+    
+    for line in full_response.lines() {
+        if !line.starts_with("data: ") {
+            continue;
+        }
+        let json_str = line.trim_start_matches("data: ").trim();
+        if json_str == "[DONE]" {
+            continue;
+        }
+        
+        if let Ok(json) = serde_json::from_str::<Value>(json_str) {
+            // OpenAI format: choices[0].delta.content / reasoning_content
+            if let Some(choices) = json.get("choices").and_then(|c| c.as_array()) {
+                for choice in choices {
+                    if let Some(delta) = choice.get("delta") {
+                        // Thinking/reasoning content
+                        if let Some(thinking) = delta.get("reasoning_content").and_then(|v| v.as_str()) {
+                            thinking_content.push_str(thinking);
+                        }
+                        // Main response content
+                        if let Some(content) = delta.get("content").and_then(|v| v.as_str()) {
+                            response_content.push_str(content);
+                        }
+                    }
+                }
+            }
+            
+            // Claude/Anthropic format: content_block_delta
+            if let Some(delta) = json.get("delta") {
+                // Thinking block
+                if let Some(thinking) = delta.get("thinking").and_then(|v| v.as_str()) {
+                    thinking_content.push_str(thinking);
+                }
+                // Thinking signature
+                if let Some(sig) = delta.get("signature").and_then(|v| v.as_str()) {
+                    thinking_signature = sig.to_string();
+                }
+                // Text content
+                if let Some(text) = delta.get("text").and_then(|v| v.as_str()) {
+                    response_content.push_str(text);
+                }
+            }
+
+            // Responses API format: response.output_text.delta / response.reasoning_summary_text.delta
+            if let (Some(kind), Some(delta)) = (json.get("type").and_then(|v| v.as_str()), json.get("delta").and_then(|v| v.as_str())) {
+                match kind {
+                    "response.output_text.delta" => response_content.push_str(delta),
+                    "response.reasoning_summary_text.delta" => thinking_content.push_str(delta),
+                    _ => {}
+                }
+            }
+            
+            // Token usage extraction (Anthropic 的 message_start 与 message_delta 各带一部分)
+            if let Some(event_usage) = json.get("usage")
+                .or(json.get("usageMetadata"))
+                .or(json.get("response").and_then(|r| r.get("usage")))
+                .or(json.pointer("/message/usage"))
+            {
+                usage = usage.merge(read_usage(event_usage));
+            }
+        }
+    }
+    
+    // Build consolidated response object
+    let mut consolidated = serde_json::Map::new();
+    
+    if !thinking_content.is_empty() {
+        consolidated.insert("thinking".to_string(), Value::String(thinking_content));
+    }
+    if !thinking_signature.is_empty() {
+        consolidated.insert("thinking_signature".to_string(), Value::String(thinking_signature));
+    }
+    if !response_content.is_empty() {
+        consolidated.insert("content".to_string(), Value::String(response_content));
+    }
+    if let Some(input) = usage.input {
+        consolidated.insert("input_tokens".to_string(), Value::Number(input.into()));
+    }
+    if let Some(output) = usage.output {
+        consolidated.insert("output_tokens".to_string(), Value::Number(output.into()));
+    }
+    if let Some(cached) = usage.cache_read {
+        consolidated.insert("cache_read_tokens".to_string(), Value::Number(cached.into()));
+    }
+    if let Some(written) = usage.cache_write {
+        consolidated.insert("cache_write_tokens".to_string(), Value::Number(written.into()));
+    }
+    
+    let body = if consolidated.is_empty() {
+        // Fallback: store raw SSE data if parsing failed
+        full_response.to_string()
+    } else {
+        serde_json::to_string_pretty(&Value::Object(consolidated)).unwrap_or_else(|_| full_response.to_string())
+    };
+    (body, usage)
+}
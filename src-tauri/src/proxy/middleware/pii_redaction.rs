@@ -0,0 +1,257 @@
+// 个人信息脱敏中间件
+// 请求: 脱敏消息文本后转发; 路由到本地 Ollama 或 skip_models 中的模型不处理。
+// 响应: 还原占位符。SSE 中被拆到两个事件的占位符会暂存, 与同一字段的下一段文本合并后再还原。
+
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::header,
+    middleware::Next,
+    response::Response,
+};
+use bytes::Bytes;
+use serde_json::Value;
+
+use crate::proxy::common::content_filter::visit_text_fields;
+use crate::proxy::common::model_mapping::wildcard_match;
+use crate::proxy::common::pii::{partial_placeholder_start, PiiMasker, PiiRestorer};
+use crate::proxy::common::sse::{SseEvent, SseParser};
+use crate::proxy::common::stream_relay::next_chunk;
+use crate::proxy::config::PiiRedactionConfig;
+use crate::proxy::middleware::body_limit::is_multipart;
+use crate::proxy::middleware::stream_transform::encode_event;
+use crate::proxy::middleware::buffer::{buffer_request, buffer_response};
+use crate::proxy::server::AppState;
+
+/// 本地上游不是第三方, 不需要脱敏
+async fn is_skipped(state: &AppState, config: &PiiRedactionConfig, body: &Value) -> bool {
+    let Some(model) = body.get("model").and_then(|m| m.as_str()) else {
+        return false;
+    };
+    let model = state.model_aliases.rewrite(model);
+    config.skip_models.iter().any(|p| wildcard_match(p.trim(), &model))
+        || crate::proxy::mappers::ollama::resolve_model(&*state.ollama.read().await, &model).is_some()
+}
+
+pub async fn pii_redaction_middleware(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let config = state.pii_redaction.read().await.clone();
+    if !config.enabled || request.method() != axum::http::Method::POST || is_multipart(request.headers()) {
+        return next.run(request).await;
+    }
+    let path = request.uri().path().to_string();
+    let (mut parts, body) = request.into_parts();
+    let bytes = match buffer_request(body).await {
+        Ok(bytes) => bytes,
+        Err(response) => return response,
+    };
+    let Ok(mut json) = serde_json::from_slice::<Value>(&bytes) else {
+        return next.run(Request::from_parts(parts, Body::from(bytes))).await;
+    };
+    if is_skipped(&state, &config, &json).await {
+        return next.run(Request::from_parts(parts, Body::from(bytes))).await;
+    }
+    let mut masker = PiiMasker::new(&config);
+    if !masker.mask_json(&mut json) {
+        return next.run(Request::from_parts(parts, Body::from(bytes))).await;
+    }
+    tracing::info!("[PII] {} masked {}", path, masker.summary());
+    parts.headers.remove(header::CONTENT_LENGTH);
+    let response = next.run(Request::from_parts(parts, Body::from(json.to_string()))).await;
+    if !config.restore_response {
+        return response;
+    }
+
+    let restorer = masker.into_restorer();
+    let content_type = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("")
+        .to_string();
+    if content_type.contains("text/event-stream") {
+        restore_stream(restorer, response)
+    } else if content_type.contains("json") {
+        restore_json_response(&restorer, response).await
+    } else {
+        response
+    }
+}
+
+async fn restore_json_response(restorer: &PiiRestorer, response: Response) -> Response {
+    let (mut parts, body) = response.into_parts();
+    let bytes = match buffer_response(body).await {
+        Ok(bytes) => bytes,
+        Err(response) => return response,
+    };
+    let Ok(mut json) = serde_json::from_slice::<Value>(&bytes) else {
+        return Response::from_parts(parts, Body::from(bytes));
+    };
+    if !restorer.restore_json(&mut json) {
+        return Response::from_parts(parts, Body::from(bytes));
+    }
+    parts.headers.remove(header::CONTENT_LENGTH);
+    Response::from_parts(parts, Body::from(json.to_string()))
+}
+
+/// 暂存的文本: 字段的 JSON Pointer、文本、用于补发的事件
+struct Pending {
+    path: String,
+    text: String,
+    event: String,
+    template: Value,
+}
+
+/// 单个流的还原状态
+pub(crate) struct StreamRestorer {
+    restorer: PiiRestorer,
+    parser: SseParser,
+    pending: Vec<Pending>,
+}
+
+impl StreamRestorer {
+    pub(crate) fn new(restorer: PiiRestorer) -> Self {
+        Self { restorer, parser: SseParser::new(), pending: Vec::new() }
+    }
+
+    pub(crate) fn push(&mut self, chunk: &[u8]) -> Vec<u8> {
+        let events = self.parser.push(chunk);
+        self.render(events)
+    }
+
+    pub(crate) fn finish(&mut self) -> Vec<u8> {
+        let events = self.parser.finish();
+        let mut out = self.render(events);
+        out.extend(self.flush(|_| true));
+        out
+    }
+
+    fn render(&mut self, events: Vec<SseEvent>) -> Vec<u8> {
+        let mut out = Vec::new();
+        for mut event in events {
+            let Ok(mut json) = serde_json::from_str::<Value>(&event.data) else {
+                out.extend(self.flush(|_| true));
+                out.extend(encode_event(&event));
+                continue;
+            };
+            let mut seen = Vec::new();
+            let mut held = Vec::new();
+            let mut modified = false;
+            let (pending, restorer) = (&mut self.pending, &self.restorer);
+            visit_text_fields(&mut json, &mut |path, text| {
+                seen.push(path.to_string());
+                let mut full = match pending.iter().position(|p| p.path == path) {
+                    Some(i) => pending.remove(i).text,
+                    None => String::new(),
+                };
+                full.push_str(text);
+                let full = restorer.restore_text(&full).unwrap_or(full);
+                let emitted = match partial_placeholder_start(&full) {
+                    Some(start) => {
+                        held.push((path.to_string(), full[start..].to_string()));
+                        full[..start].to_string()
+                    }
+                    None => full,
+                };
+                if emitted != *text {
+                    *text = emitted;
+                    modified = true;
+                }
+            });
+            // 暂存文本所在的字段没有后续内容时, 先补发暂存文本
+            out.extend(self.flush(|path| !seen.iter().any(|s| s == path)));
+            for (path, text) in held {
+                self.pending.push(Pending { path, text, event: event.event.clone(), template: json.clone() });
+            }
+            if modified {
+                event.data = json.to_string();
+            }
+            out.extend(encode_event(&event));
+        }
+        out
+    }
+
+    /// 用模板事件补发暂存文本, 模板中的其他文本字段置空
+    fn flush(&mut self, select: impl Fn(&str) -> bool) -> Vec<u8> {
+        let mut out = Vec::new();
+        let (flushed, kept): (Vec<Pending>, Vec<Pending>) =
+            std::mem::take(&mut self.pending).into_iter().partition(|p| select(&p.path));
+        self.pending = kept;
+        for Pending { path, text, event, mut template } in flushed {
+            visit_text_fields(&mut template, &mut |_, t| t.clear());
+            if let Some(field) = template.pointer_mut(&path) {
+                *field = Value::String(text);
+            }
+            out.extend(encode_event(&SseEvent { event, data: template.to_string(), id: None }));
+        }
+        out
+    }
+}
+
+fn restore_stream(restorer: PiiRestorer, response: Response) -> Response {
+    let mut stream_restorer = StreamRestorer::new(restorer);
+    let (mut parts, body) = response.into_parts();
+    parts.headers.remove(header::CONTENT_LENGTH);
+    let mut stream = body.into_data_stream();
+    let (tx, rx) = tokio::sync::mpsc::channel(64);
+
+    tokio::spawn(async move {
+        while let Ok(Some(chunk)) = next_chunk(&mut stream, &tx).await {
+            match chunk {
+                Ok(chunk) => {
+                    let out = stream_restorer.push(&chunk);
+                    if !out.is_empty() && tx.send(Ok::<_, axum::Error>(Bytes::from(out))).await.is_err() {
+                        return; // 客户端已断开
+                    }
+                }
+                Err(e) => {
+                    let _ = tx.send(Err(e)).await;
+                    return;
+                }
+            }
+        }
+        let out = stream_restorer.finish();
+        if !out.is_empty() {
+            let _ = tx.send(Ok(Bytes::from(out))).await;
+        }
+    });
+
+    Response::from_parts(parts, Body::from_stream(tokio_stream::wrappers::ReceiverStream::new(rx)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn chunk(content: &str) -> String {
+        format!("data: {}\n\n", json!({"choices": [{"index": 0, "delta": {"content": content}}]}))
+    }
+
+    #[test]
+    fn test_stream_restores_split_placeholders() {
+        let mut masker = PiiMasker::new(&PiiRedactionConfig::default());
+        masker.mask_text("alice@example.com or 4111-1111-1111-1111").unwrap();
+        let mut restorer = StreamRestorer::new(masker.into_restorer());
+
+        let finish = format!("data: {}\n\n", json!({"choices": [{"index": 0, "delta": {}, "finish_reason": "stop"}]}));
+        let input = format!("{}{}{}{}data: [DONE]\n\n", chunk("Mail [EMA"), chunk("IL_1] now, card [CARD_"), chunk("1"), finish);
+        let mut out = String::from_utf8(restorer.push(input.as_bytes())).unwrap();
+        out.push_str(&String::from_utf8(restorer.finish()).unwrap());
+
+        let contents: Vec<String> = SseParser::new()
+            .push(out.as_bytes())
+            .iter()
+            .filter_map(|e| serde_json::from_str::<Value>(&e.data).ok())
+            .filter_map(|v| v["choices"][0]["delta"]["content"].as_str().map(String::from))
+            .collect();
+        // 流结束前未闭合的占位符原样补发
+        assert_eq!(contents.concat(), "Mail alice@example.com now, card [CARD_1");
+        assert!(out.ends_with("data: [DONE]\n\n"));
+        let finish_at = out.find("finish_reason").unwrap();
+        assert!(out.find("[CARD_1").unwrap() < finish_at);
+    }
+}
@@ -0,0 +1,74 @@
+// 提示词模板中间件
+// 位于 monitor 之外: 请求日志、虚拟 Key 的模型白名单和后续处理看到的都是展开后的请求与去掉前缀的模型名。
+// 引用的模板不存在或缺少变量时返回 400。
+
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde_json::{json, Value};
+
+use crate::proxy::middleware::body_limit::is_multipart;
+use crate::proxy::prompt_templates::{PromptTemplateStore, TEMPLATE_HEADER, VARIABLES_HEADER};
+use crate::proxy::middleware::buffer::buffer_request;
+
+fn bad_request(message: String) -> Response {
+    let body = json!({ "error": { "message": message, "type": "invalid_request_error", "code": "prompt_template" } });
+    (StatusCode::BAD_REQUEST, axum::Json(body)).into_response()
+}
+
+pub async fn prompt_template_middleware(request: Request, next: Next) -> Response {
+    if request.method() != Method::POST
+        || is_multipart(request.headers())
+        || PromptTemplateStore::global().is_empty()
+    {
+        return next.run(request).await;
+    }
+    let header_name = request
+        .headers()
+        .get(TEMPLATE_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty());
+    let (mut parts, body) = request.into_parts();
+    let bytes = match buffer_request(body).await {
+        Ok(bytes) => bytes,
+        Err(response) => return response,
+    };
+    let Ok(mut json) = serde_json::from_slice::<Value>(&bytes) else {
+        return next.run(Request::from_parts(parts, Body::from(bytes))).await;
+    };
+    // 模型名前缀优先于请求头
+    let prefixed = json
+        .get("model")
+        .and_then(Value::as_str)
+        .and_then(crate::proxy::prompt_templates::split_model)
+        .map(|(name, model)| (name.to_string(), model.to_string()));
+    let name = match (&prefixed, header_name) {
+        (Some((name, model)), _) => {
+            json["model"] = Value::String(model.clone());
+            name.clone()
+        }
+        (None, Some(name)) => name,
+        (None, None) => return next.run(Request::from_parts(parts, Body::from(bytes))).await,
+    };
+    let Some(template) = PromptTemplateStore::global().get(&name) else {
+        return bad_request(format!("Prompt template not found: {}", name));
+    };
+
+    let variables_header = parts.headers.get(VARIABLES_HEADER).and_then(|v| v.to_str().ok());
+    let path = parts.uri.path().to_string();
+    let applied = crate::proxy::prompt_templates::variables(variables_header, &mut json)
+        .and_then(|values| crate::proxy::prompt_templates::apply(&path, &mut json, &template, &values));
+    if let Err(e) = applied {
+        return bad_request(e);
+    }
+    tracing::debug!("[PromptTemplate] {} expanded template '{}'", path, template.name);
+    parts.headers.remove(TEMPLATE_HEADER);
+    parts.headers.remove(VARIABLES_HEADER);
+    parts.headers.remove(header::CONTENT_LENGTH);
+    next.run(Request::from_parts(parts, Body::from(json.to_string()))).await
+}
@@ -0,0 +1,561 @@
+// 客户端限流中间件
+// 按 API Key / 客户端 IP 维护令牌桶, 同时限制每分钟请求数和 Token 数。
+// Token 维度在请求时按请求体大小预估扣除, 响应完成后由 monitor 按实际用量结算。
+
+use axum::{
+    body::Body,
+    extract::{ConnectInfo, Request, State},
+    http::{HeaderMap, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use dashmap::DashMap;
+use serde_json::json;
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, Instant};
+
+use crate::proxy::config::ClientRateLimitConfig;
+use crate::proxy::middleware::auth::{api_key_fingerprint, extract_api_key};
+use crate::proxy::server::AppState;
+
+/// 超过该时长未活动的客户端桶会被清理
+const IDLE_BUCKET_TTL: Duration = Duration::from_secs(600);
+/// 跟踪的客户端数量超过该值时触发清理
+const CLEANUP_THRESHOLD: usize = 4096;
+
+/// 令牌桶
+#[derive(Debug, Clone)]
+struct TokenBucket {
+    capacity: f64,
+    available: f64,
+    refill_per_sec: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    fn new(capacity: u32, per_minute: u32, now: Instant) -> Self {
+        Self {
+            capacity: capacity as f64,
+            available: capacity as f64,
+            refill_per_sec: per_minute as f64 / 60.0,
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.available = (self.available + elapsed * self.refill_per_sec).min(self.capacity);
+        self.updated = now;
+    }
+
+    /// 余额达到 `amount` 还需等待的时间
+    fn wait_time(&self, amount: f64) -> Duration {
+        if self.available >= amount || self.refill_per_sec <= 0.0 {
+            return Duration::ZERO;
+        }
+        Duration::from_secs_f64((amount - self.available) / self.refill_per_sec)
+    }
+
+    /// 扣除 (Token 维度允许出现负余额, 由后续补充抵消)
+    fn take(&mut self, amount: f64) {
+        self.available = (self.available - amount).min(self.capacity);
+    }
+
+    fn remaining(&self) -> u32 {
+        self.available.max(0.0) as u32
+    }
+
+    /// 按新的限额调整, 保留当前余额 (不超过新容量)
+    fn resize(&mut self, capacity: u32, per_minute: u32, now: Instant) {
+        self.refill(now);
+        self.capacity = capacity as f64;
+        self.refill_per_sec = per_minute as f64 / 60.0;
+        self.available = self.available.min(self.capacity);
+    }
+}
+
+/// 请求维度的 (容量, 每分钟补充量), 未限制时为 None
+fn request_limit(config: &ClientRateLimitConfig) -> Option<(u32, u32)> {
+    let rpm = config.requests_per_minute;
+    let burst = if config.burst > 0 { config.burst } else { rpm };
+    (rpm > 0).then_some((burst, rpm))
+}
+
+/// Token 维度的 (容量, 每分钟补充量), 未限制时为 None
+fn token_limit(config: &ClientRateLimitConfig) -> Option<(u32, u32)> {
+    let tpm = config.tokens_per_minute;
+    (tpm > 0).then_some((tpm, tpm))
+}
+
+/// 限额变化后调整桶: 取消限制时删除, 新增限制时从满额开始
+fn resized(bucket: Option<TokenBucket>, limit: Option<(u32, u32)>, now: Instant) -> Option<TokenBucket> {
+    let (capacity, per_minute) = limit?;
+    Some(match bucket {
+        Some(mut bucket) => {
+            bucket.resize(capacity, per_minute, now);
+            bucket
+        }
+        None => TokenBucket::new(capacity, per_minute, now),
+    })
+}
+
+#[derive(Debug)]
+struct ClientBuckets {
+    requests: Option<TokenBucket>,
+    tokens: Option<TokenBucket>,
+    last_seen: Instant,
+}
+
+impl ClientBuckets {
+    fn new(config: &ClientRateLimitConfig, now: Instant) -> Self {
+        Self {
+            requests: request_limit(config).map(|(capacity, rpm)| TokenBucket::new(capacity, rpm, now)),
+            tokens: token_limit(config).map(|(capacity, tpm)| TokenBucket::new(capacity, tpm, now)),
+            last_seen: now,
+        }
+    }
+}
+
+/// 触发限流的维度
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitKind {
+    Requests,
+    Tokens,
+}
+
+/// 限流拒绝信息
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RateLimited {
+    pub kind: LimitKind,
+    pub limit: u32,
+    pub retry_after: Duration,
+}
+
+/// 通过限流后的剩余额度 (多个维度取最小值)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RateLimitStatus {
+    pub requests: Option<(u32, u32)>,
+    pub tokens: Option<(u32, u32)>,
+}
+
+/// 请求预扣记录, 通过响应 extensions 传递给 monitor 用于按实际用量结算
+#[derive(Debug, Clone)]
+pub struct RateLimitCharge {
+    clients: Vec<String>,
+    estimated_tokens: u32,
+}
+
+/// 客户端限流器
+pub struct ClientRateLimiter {
+    config: std::sync::RwLock<ClientRateLimitConfig>,
+    buckets: DashMap<String, ClientBuckets>,
+}
+
+impl ClientRateLimiter {
+    pub fn new(config: ClientRateLimitConfig) -> Self {
+        Self {
+            config: std::sync::RwLock::new(config),
+            buckets: DashMap::new(),
+        }
+    }
+
+    pub fn config(&self) -> ClientRateLimitConfig {
+        self.config.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// 热更新配置; 只调整限额发生变化的维度, 其余维度的余额保持不变
+    pub fn update_config(&self, config: ClientRateLimitConfig) {
+        self.reconfigure(config, Instant::now());
+    }
+
+    fn reconfigure(&self, config: ClientRateLimitConfig, now: Instant) {
+        let (requests, tokens) = (request_limit(&config), token_limit(&config));
+        let previous = std::mem::replace(&mut *self.config.write().unwrap_or_else(|e| e.into_inner()), config);
+        let requests_changed = request_limit(&previous) != requests;
+        let tokens_changed = token_limit(&previous) != tokens;
+        if !requests_changed && !tokens_changed {
+            return;
+        }
+        for mut entry in self.buckets.iter_mut() {
+            let buckets = entry.value_mut();
+            if requests_changed {
+                buckets.requests = resized(buckets.requests.take(), requests, now);
+            }
+            if tokens_changed {
+                buckets.tokens = resized(buckets.tokens.take(), tokens, now);
+            }
+        }
+    }
+
+    /// 检查所有客户端维度, 全部通过时才扣除额度
+    pub fn check(
+        &self,
+        clients: &[String],
+        estimated_tokens: u32,
+        now: Instant,
+    ) -> Result<RateLimitStatus, RateLimited> {
+        let config = self.config();
+        if self.buckets.len() > CLEANUP_THRESHOLD {
+            self.buckets
+                .retain(|_, b| now.saturating_duration_since(b.last_seen) < IDLE_BUCKET_TTL);
+        }
+
+        // 单次请求超过整个桶容量时, 只要求桶是满的, 否则该请求永远无法通过
+        let token_cost = estimated_tokens as f64;
+        let mut rejection: Option<RateLimited> = None;
+        for client in clients {
+            let mut entry = self
+                .buckets
+                .entry(client.clone())
+                .or_insert_with(|| ClientBuckets::new(&config, now));
+            entry.last_seen = now;
+
+            if let Some(bucket) = entry.requests.as_mut() {
+                bucket.refill(now);
+                let wait = bucket.wait_time(1.0);
+                if !wait.is_zero() {
+                    merge_rejection(&mut rejection, LimitKind::Requests, config.requests_per_minute, wait);
+                }
+            }
+            if let Some(bucket) = entry.tokens.as_mut() {
+                bucket.refill(now);
+                let wait = bucket.wait_time(token_cost.min(bucket.capacity));
+                if !wait.is_zero() {
+                    merge_rejection(&mut rejection, LimitKind::Tokens, config.tokens_per_minute, wait);
+                }
+            }
+        }
+        if let Some(rejection) = rejection {
+            return Err(rejection);
+        }
+
+        let mut status = RateLimitStatus::default();
+        for client in clients {
+            if let Some(mut entry) = self.buckets.get_mut(client) {
+                if let Some(bucket) = entry.requests.as_mut() {
+                    bucket.take(1.0);
+                    min_remaining(&mut status.requests, config.requests_per_minute, bucket.remaining());
+                }
+                if let Some(bucket) = entry.tokens.as_mut() {
+                    bucket.take(token_cost);
+                    min_remaining(&mut status.tokens, config.tokens_per_minute, bucket.remaining());
+                }
+            }
+        }
+        Ok(status)
+    }
+
+    /// 按实际 Token 用量结算预扣额度 (多退少补)
+    pub fn settle(&self, charge: &RateLimitCharge, actual_tokens: u32) {
+        let diff = actual_tokens as f64 - charge.estimated_tokens as f64;
+        if diff == 0.0 {
+            return;
+        }
+        for client in &charge.clients {
+            if let Some(mut entry) = self.buckets.get_mut(client) {
+                if let Some(bucket) = entry.tokens.as_mut() {
+                    bucket.take(diff);
+                }
+            }
+        }
+    }
+}
+
+fn merge_rejection(current: &mut Option<RateLimited>, kind: LimitKind, limit: u32, wait: Duration) {
+    if current.as_ref().map_or(true, |r| wait > r.retry_after) {
+        *current = Some(RateLimited {
+            kind,
+            limit,
+            retry_after: wait,
+        });
+    }
+}
+
+fn min_remaining(slot: &mut Option<(u32, u32)>, limit: u32, remaining: u32) {
+    match slot {
+        Some((_, r)) if *r <= remaining => {}
+        _ => *slot = Some((limit, remaining)),
+    }
+}
+
+/// 客户端 IP: 优先使用连接地址, 仅当连接来自本机 (如 cloudflared 隧道) 时信任转发头
+pub(crate) fn client_ip(request: &Request) -> Option<IpAddr> {
+    let peer = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip());
+
+    if let Some(ip) = peer.filter(|ip| !ip.is_loopback()) {
+        return Some(ip);
+    }
+
+    forwarded_ip(request.headers()).or(peer)
+}
+
+fn forwarded_ip(headers: &HeaderMap) -> Option<IpAddr> {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+    header("cf-connecting-ip")
+        .or_else(|| header("x-forwarded-for").and_then(|v| v.split(',').next()))
+        .or_else(|| header("x-real-ip"))
+        .and_then(|v| v.trim().parse().ok())
+}
+
+/// 计算请求所属的限流维度 (未能识别任何维度时归入全局桶)
+fn client_identities(request: &Request, config: &ClientRateLimitConfig) -> Vec<String> {
+    let mut clients = Vec::new();
+    if config.per_api_key {
+        if let Some(key) = extract_api_key(request.headers()).filter(|k| !k.is_empty()) {
+            // 不在内存中保存明文 Key
+            clients.push(format!("key:{}", api_key_fingerprint(key)));
+        }
+    }
+    if config.per_ip {
+        if let Some(ip) = client_ip(request) {
+            clients.push(format!("ip:{}", ip));
+        }
+    }
+    if clients.is_empty() {
+        clients.push("global".to_string());
+    }
+    clients
+}
+
+/// 按请求体大小粗略估算输入 Token (约 4 字节 / Token)
+fn estimate_request_tokens(headers: &HeaderMap) -> u32 {
+    headers
+        .get(axum::http::header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok())
+        .map(|len| (len / 4).min(u32::MAX as u64) as u32)
+        .unwrap_or(0)
+}
+
+fn rate_limited_response(rejected: &RateLimited) -> Response {
+    let retry_secs = rejected.retry_after.as_secs_f64().ceil().max(1.0) as u64;
+    let (what, error_type) = match rejected.kind {
+        LimitKind::Requests => ("requests per minute (RPM)", "requests"),
+        LimitKind::Tokens => ("tokens per minute (TPM)", "tokens"),
+    };
+    let body = json!({
+        "error": {
+            "message": format!(
+                "Rate limit reached for {}: Limit {}. Please try again in {}s.",
+                what, rejected.limit, retry_secs
+            ),
+            "type": error_type,
+            "param": null,
+            "code": "rate_limit_exceeded"
+        }
+    });
+
+    let mut response = (StatusCode::TOO_MANY_REQUESTS, axum::Json(body)).into_response();
+    let headers = response.headers_mut();
+    headers.insert("Retry-After", HeaderValue::from(retry_secs));
+    let reset = HeaderValue::from_str(&format!("{}s", retry_secs)).unwrap_or(HeaderValue::from_static("1s"));
+    match rejected.kind {
+        LimitKind::Requests => {
+            headers.insert("x-ratelimit-limit-requests", HeaderValue::from(rejected.limit));
+            headers.insert("x-ratelimit-remaining-requests", HeaderValue::from(0u32));
+            headers.insert("x-ratelimit-reset-requests", reset);
+        }
+        LimitKind::Tokens => {
+            headers.insert("x-ratelimit-limit-tokens", HeaderValue::from(rejected.limit));
+            headers.insert("x-ratelimit-remaining-tokens", HeaderValue::from(0u32));
+            headers.insert("x-ratelimit-reset-tokens", reset);
+        }
+    }
+    response
+}
+
+fn notify_rate_limited(clients: &[String], rejected: &RateLimited) {
+    let what = match rejected.kind {
+        LimitKind::Requests => "RPM",
+        LimitKind::Tokens => "TPM",
+    };
+    let subject = clients.join(",");
+    crate::proxy::webhooks::notify(crate::proxy::webhooks::WebhookEvent::new(
+        crate::proxy::config::WebhookEventKind::RateLimited,
+        subject.clone(),
+        format!("Client {} hit the {} limit ({})", subject, what, rejected.limit),
+        json!({
+            "clients": clients,
+            "limit_type": what,
+            "limit": rejected.limit,
+            "retry_after_secs": rejected.retry_after.as_secs(),
+        }),
+    ));
+}
+
+/// 客户端限流中间件
+pub async fn rate_limit_middleware(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let limiter = state.client_rate_limiter.clone();
+    let config = limiter.config();
+    if !config.enabled || request.method() == axum::http::Method::OPTIONS {
+        return next.run(request).await;
+    }
+
+    let clients = client_identities(&request, &config);
+    let estimated_tokens = estimate_request_tokens(request.headers());
+
+    let status = match limiter.check(&clients, estimated_tokens, Instant::now()) {
+        Ok(status) => status,
+        Err(rejected) => {
+            tracing::warn!(
+                "[RateLimit] {:?} limit exceeded for {:?}, retry after {:?}",
+                rejected.kind,
+                clients,
+                rejected.retry_after
+            );
+            notify_rate_limited(&clients, &rejected);
+            return rate_limited_response(&rejected);
+        }
+    };
+
+    let mut response: Response<Body> = next.run(request).await;
+    let headers = response.headers_mut();
+    if let Some((limit, remaining)) = status.requests {
+        headers.insert("x-ratelimit-limit-requests", HeaderValue::from(limit));
+        headers.insert("x-ratelimit-remaining-requests", HeaderValue::from(remaining));
+    }
+    if let Some((limit, remaining)) = status.tokens {
+        headers.insert("x-ratelimit-limit-tokens", HeaderValue::from(limit));
+        headers.insert("x-ratelimit-remaining-tokens", HeaderValue::from(remaining));
+    }
+    response.extensions_mut().insert(RateLimitCharge {
+        clients,
+        estimated_tokens,
+    });
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(rpm: u32, tpm: u32, burst: u32) -> ClientRateLimitConfig {
+        ClientRateLimitConfig {
+            enabled: true,
+            requests_per_minute: rpm,
+            tokens_per_minute: tpm,
+            burst,
+            per_api_key: true,
+            per_ip: true,
+        }
+    }
+
+    #[test]
+    fn test_request_burst_then_refill() {
+        let limiter = ClientRateLimiter::new(config(60, 0, 2));
+        let clients = vec!["key:a".to_string()];
+        let now = Instant::now();
+
+        assert_eq!(limiter.check(&clients, 0, now).unwrap().requests, Some((60, 1)));
+        assert!(limiter.check(&clients, 0, now).is_ok());
+        let rejected = limiter.check(&clients, 0, now).unwrap_err();
+        assert_eq!(rejected.kind, LimitKind::Requests);
+        assert_eq!(rejected.retry_after, Duration::from_secs(1));
+
+        // 60 RPM => 每秒补充 1 个
+        assert!(limiter.check(&clients, 0, now + Duration::from_secs(1)).is_ok());
+        // 其他客户端互不影响
+        assert!(limiter.check(&["key:b".to_string()], 0, now).is_ok());
+    }
+
+    #[test]
+    fn test_reconfigure_keeps_unchanged_buckets() {
+        let limiter = ClientRateLimiter::new(config(60, 600, 2));
+        let clients = vec!["key:a".to_string()];
+        let now = Instant::now();
+        limiter.check(&clients, 100, now).unwrap();
+        limiter.check(&clients, 100, now).unwrap();
+
+        // 保存与原来相同的限额 (例如修改了其他设置) 不会重置额度
+        let mut same = config(60, 600, 2);
+        same.per_ip = false;
+        limiter.reconfigure(same, now);
+        assert_eq!(limiter.check(&clients, 0, now).unwrap_err().kind, LimitKind::Requests);
+
+        // 只调整 Token 维度: 请求维度的余额保持不变, Token 余额不超过新容量
+        limiter.reconfigure(config(60, 300, 2), now);
+        assert_eq!(limiter.check(&clients, 0, now).unwrap_err().kind, LimitKind::Requests);
+        let later = now + Duration::from_secs(1);
+        assert_eq!(limiter.check(&clients, 0, later).unwrap().tokens, Some((300, 300)));
+
+        // 放宽请求维度后按新的速率补充 (原速率 0.5 秒只能补充半个请求)
+        limiter.reconfigure(config(120, 300, 0), later);
+        assert!(limiter.check(&clients, 0, later + Duration::from_millis(500)).is_ok());
+    }
+
+    #[test]
+    fn test_token_limit_and_settle() {
+        let limiter = ClientRateLimiter::new(config(0, 600, 0));
+        let clients = vec!["ip:1.2.3.4".to_string()];
+        let now = Instant::now();
+
+        assert!(limiter.check(&clients, 100, now).is_ok());
+        // 实际用量远超预估, 结算后额度透支
+        let charge = RateLimitCharge {
+            clients: clients.clone(),
+            estimated_tokens: 100,
+        };
+        limiter.settle(&charge, 700);
+        let rejected = limiter.check(&clients, 10, now).unwrap_err();
+        assert_eq!(rejected.kind, LimitKind::Tokens);
+        // 余额 -100, 需要补足 110 个 Token (10 Token/秒)
+        assert_eq!(rejected.retry_after, Duration::from_secs(11));
+    }
+
+    #[test]
+    fn test_oversized_request_passes_with_full_bucket() {
+        let limiter = ClientRateLimiter::new(config(0, 100, 0));
+        let clients = vec!["global".to_string()];
+        let now = Instant::now();
+        assert!(limiter.check(&clients, 1000, now).is_ok());
+        assert!(limiter.check(&clients, 1, now).is_err());
+    }
+
+    #[test]
+    fn test_rejection_without_partial_charge() {
+        let limiter = ClientRateLimiter::new(config(60, 0, 1));
+        let now = Instant::now();
+        let ip_only = vec!["ip:10.0.0.1".to_string()];
+        let both = vec!["key:a".to_string(), "ip:10.0.0.1".to_string()];
+
+        assert!(limiter.check(&ip_only, 0, now).is_ok());
+        // IP 维度已耗尽, key 维度不应被扣除
+        assert!(limiter.check(&both, 0, now).is_err());
+        assert!(limiter.check(&["key:a".to_string()], 0, now).is_ok());
+    }
+
+    #[test]
+    fn test_client_identities_from_headers() {
+        let request = Request::builder()
+            .header("Authorization", "Bearer sk-test")
+            .header("x-forwarded-for", "203.0.113.7, 10.0.0.1")
+            .body(Body::empty())
+            .unwrap();
+        let clients = client_identities(&request, &config(60, 0, 0));
+        assert_eq!(clients.len(), 2);
+        assert!(clients[0].starts_with("key:") && !clients[0].contains("sk-test"));
+        assert_eq!(clients[1], "ip:203.0.113.7");
+
+        let anonymous = Request::builder().body(Body::empty()).unwrap();
+        assert_eq!(client_identities(&anonymous, &config(60, 0, 0)), vec!["global"]);
+    }
+
+    #[test]
+    fn test_rate_limited_response_headers() {
+        let response = rate_limited_response(&RateLimited {
+            kind: LimitKind::Requests,
+            limit: 60,
+            retry_after: Duration::from_millis(1500),
+        });
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()["Retry-After"], "2");
+        assert_eq!(response.headers()["x-ratelimit-reset-requests"], "2s");
+    }
+}
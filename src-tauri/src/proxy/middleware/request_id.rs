@@ -0,0 +1,155 @@
+// 请求 ID 中间件
+// 为每个代理请求分配唯一 ID, 写入 tracing span、请求扩展和响应头, 转发上游时附带同一 ID,
+// 错误响应体中也会带上, 便于端到端排查。客户端自带的 `X-Request-Id` 只记录在 span 中 (可能重复, 不作为日志主键)。
+
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header, HeaderMap, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use serde_json::Value;
+use tracing::Instrument;
+
+use crate::proxy::middleware::buffer::buffer_response;
+
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+tokio::task_local! {
+    static CURRENT_REQUEST_ID: String;
+}
+
+/// 当前请求的 ID (请求扩展)
+#[derive(Debug, Clone)]
+pub struct RequestId(pub String);
+
+/// 当前任务所处理请求的 ID, 用于给上游请求附加 `X-Request-Id`
+pub fn current_request_id() -> Option<String> {
+    CURRENT_REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+/// 共享的上游客户端不能按请求设置默认 Header, 发送前附加当前请求 ID
+pub trait WithRequestId {
+    fn with_request_id(self) -> Self;
+}
+
+impl WithRequestId for reqwest::RequestBuilder {
+    fn with_request_id(self) -> Self {
+        match current_request_id() {
+            Some(id) => self.header(REQUEST_ID_HEADER, id),
+            None => self,
+        }
+    }
+}
+
+/// 客户端传入的 ID 只接受常见字符, 避免日志注入
+fn incoming_request_id(headers: &HeaderMap) -> Option<String> {
+    let id = headers.get(REQUEST_ID_HEADER)?.to_str().ok()?.trim();
+    let valid = !id.is_empty()
+        && id.len() <= 128
+        && id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'));
+    valid.then(|| id.to_string())
+}
+
+/// 在错误响应体中加入请求 ID: JSON 的 `error` 对象加 `request_id` 字段, 纯文本追加后缀
+fn annotate_error_body(body: &[u8], is_json: bool, request_id: &str) -> Option<Vec<u8>> {
+    if is_json {
+        let mut json: Value = serde_json::from_slice(body).ok()?;
+        match json.get_mut("error") {
+            Some(Value::Object(error)) => {
+                error.insert("request_id".to_string(), Value::String(request_id.to_string()));
+            }
+            _ => {
+                json.as_object_mut()?
+                    .insert("request_id".to_string(), Value::String(request_id.to_string()));
+            }
+        }
+        return serde_json::to_vec(&json).ok();
+    }
+    let text = std::str::from_utf8(body).ok()?;
+    Some(format!("{} (request id: {})", text.trim_end(), request_id).into_bytes())
+}
+
+pub async fn request_id_middleware(mut request: Request, next: Next) -> Response {
+    let request_id = uuid::Uuid::new_v4().to_string();
+    request.extensions_mut().insert(RequestId(request_id.clone()));
+
+    let span = match incoming_request_id(request.headers()) {
+        Some(client_id) => tracing::info_span!("request", request_id = %request_id, client_request_id = %client_id),
+        None => tracing::info_span!("request", request_id = %request_id),
+    };
+    let mut response = CURRENT_REQUEST_ID
+        .scope(request_id.clone(), next.run(request))
+        .instrument(span)
+        .await;
+
+    if let Ok(v) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, v);
+    }
+
+    if !(response.status().is_client_error() || response.status().is_server_error()) {
+        return response;
+    }
+    let content_type = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("")
+        .to_string();
+    if content_type.contains("text/event-stream") {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match buffer_response(body).await {
+        Ok(bytes) => bytes,
+        Err(mut response) => {
+            if let Some(v) = parts.headers.get(REQUEST_ID_HEADER) {
+                response.headers_mut().insert(REQUEST_ID_HEADER, v.clone());
+            }
+            return response;
+        }
+    };
+    match annotate_error_body(&bytes, content_type.contains("json"), &request_id) {
+        Some(annotated) => {
+            parts.headers.remove(header::CONTENT_LENGTH);
+            Response::from_parts(parts, Body::from(annotated))
+        }
+        None => Response::from_parts(parts, Body::from(bytes)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_incoming_request_id() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(incoming_request_id(&headers), None);
+        headers.insert(REQUEST_ID_HEADER, HeaderValue::from_static("req_abc-123"));
+        assert_eq!(incoming_request_id(&headers).as_deref(), Some("req_abc-123"));
+        headers.insert(REQUEST_ID_HEADER, HeaderValue::from_static("bad id\twith spaces"));
+        assert_eq!(incoming_request_id(&headers), None);
+    }
+
+    #[test]
+    fn test_annotate_error_body() {
+        let body = serde_json::to_vec(&json!({"error": {"message": "boom", "type": "x"}})).unwrap();
+        let annotated: Value = serde_json::from_slice(&annotate_error_body(&body, true, "rid").unwrap()).unwrap();
+        assert_eq!(annotated["error"]["request_id"], "rid");
+        assert_eq!(annotated["error"]["message"], "boom");
+
+        let annotated: Value =
+            serde_json::from_slice(&annotate_error_body(br#"{"detail":"x"}"#, true, "rid").unwrap()).unwrap();
+        assert_eq!(annotated["request_id"], "rid");
+
+        assert_eq!(
+            annotate_error_body(b"upstream failed\n", false, "rid").unwrap(),
+            b"upstream failed (request id: rid)".to_vec()
+        );
+        assert!(annotate_error_body(b"not json", true, "rid").is_none());
+    }
+}
@@ -2,9 +2,10 @@ use axum::{
     extract::{Request, State},
     middleware::Next,
     response::{IntoResponse, Response},
-    http::StatusCode,
+    http::{HeaderValue, StatusCode},
 };
 use crate::proxy::server::AppState;
+use crate::proxy::upstream::health_check::OverallHealth;
 
 pub async fn service_status_middleware(
     State(state): State<AppState>,
@@ -31,5 +32,48 @@ pub async fn service_status_middleware(
             .into_response();
     }
 
-    next.run(request).await
+    // 健康检查判定所有上游不可用时快速失败
+    let health = state.health_checker.overall();
+    if health == Some(OverallHealth::Down) {
+        let config = state.health_checker.config();
+        if config.fail_fast {
+            let mut response = (
+                StatusCode::SERVICE_UNAVAILABLE,
+                axum::Json(serde_json::json!({
+                    "error": {
+                        "message": "All upstreams are failing health checks. Please retry later.",
+                        "type": "server_error",
+                        "code": "upstream_unavailable"
+                    }
+                })),
+            )
+                .into_response();
+            if let Ok(v) = HeaderValue::from_str(&config.interval_secs.to_string()) {
+                response.headers_mut().insert("Retry-After", v);
+            }
+            return response;
+        }
+    }
+
+    let mut response = next.run(request).await;
+    // 服务曾被监督任务重启时上报重启次数与最近一次原因
+    let supervisor = crate::proxy::supervisor::Supervisor::global().status();
+    if supervisor.restarts > 0 {
+        response.headers_mut().insert("X-Proxy-Restarts", HeaderValue::from(supervisor.restarts));
+        if let Some(reason) = supervisor.last_reason.and_then(|r| HeaderValue::from_str(&r).ok()) {
+            response.headers_mut().insert("X-Proxy-Last-Restart-Reason", reason);
+        }
+    }
+    if let Some(health) = health {
+        response
+            .headers_mut()
+            .insert("X-Upstream-Health", HeaderValue::from_static(health.as_str()));
+    }
+    // 启用熔断时标记上游熔断状态 (closed / half_open / open)
+    if let Some(circuit) = state.upstream.circuit_summary() {
+        response
+            .headers_mut()
+            .insert("X-Circuit-State", HeaderValue::from_static(circuit.as_str()));
+    }
+    response
 }
@@ -0,0 +1,205 @@
+// 影子流量中间件
+// 位于内容过滤与脱敏之内: 复制的是过滤、脱敏后的请求体, 发往第三方影子上游的请求同样不含原文。
+// 规则按客户端请求的模型 (monitor 记录的 RequestedModel) 匹配, 不受智能路由与备用模型链改写的影响;
+// 外层的 shadow_scope_middleware 保证备用模型链重试与对冲的备用请求不会被重复复制。
+// 影子请求与主请求同时发出, 不延迟主请求; 主请求返回响应头后在后台等待影子请求结束并记录结果。
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+use serde_json::Value;
+
+use crate::proxy::config::{ShadowRule, UpstreamProxyConfig};
+use crate::proxy::middleware::body_limit::is_multipart;
+use crate::proxy::middleware::buffer::buffer_request;
+use crate::proxy::middleware::monitor::RequestedModel;
+use crate::proxy::middleware::request_id::RequestId;
+use crate::proxy::server::AppState;
+use crate::proxy::shadow::{ShadowResult, ShadowTarget, SHADOW_HEADER};
+
+/// 同一客户端请求的各次尝试共享的标记, 只复制第一次到达的请求
+#[derive(Clone, Default)]
+struct ShadowOnce(Arc<AtomicBool>);
+
+/// 位于备用模型链与对冲之外, 为每个客户端请求插入复制标记
+pub async fn shadow_scope_middleware(mut request: Request, next: Next) -> Response {
+    if crate::proxy::shadow::is_enabled() {
+        request.extensions_mut().insert(ShadowOnce::default());
+    }
+    next.run(request).await
+}
+
+/// 解析影子目标所需的服务状态
+#[derive(Clone)]
+struct ShadowEnv {
+    port: u16,
+    api_key: String,
+    upstream_proxy: UpstreamProxyConfig,
+}
+
+fn target_for(env: &ShadowEnv, rule: &ShadowRule) -> ShadowTarget {
+    match rule.base_url.as_deref().map(str::trim).filter(|u| !u.is_empty()) {
+        Some(base_url) => ShadowTarget {
+            base_url: base_url.to_string(),
+            api_key: rule.api_key.clone().unwrap_or_default(),
+            upstream_proxy: env.upstream_proxy.clone(),
+        },
+        None => ShadowTarget {
+            base_url: format!("http://127.0.0.1:{}", env.port),
+            api_key: rule.api_key.clone().unwrap_or_else(|| env.api_key.clone()),
+            upstream_proxy: Default::default(),
+        },
+    }
+}
+
+pub async fn shadow_middleware(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    if !crate::proxy::shadow::is_enabled() {
+        return next.run(request).await;
+    }
+    let env = ShadowEnv {
+        port: state.port,
+        api_key: state.security.read().await.api_key.clone(),
+        upstream_proxy: state.upstream_proxy.read().await.clone(),
+    };
+    mirror(env, request, next).await
+}
+
+async fn mirror(env: ShadowEnv, request: Request, next: Next) -> Response {
+    let claimed = request.extensions().get::<ShadowOnce>().is_some_and(|once| once.0.swap(true, Ordering::SeqCst));
+    if claimed
+        || request.method() != axum::http::Method::POST
+        || is_multipart(request.headers())
+        || request.headers().contains_key(SHADOW_HEADER)
+    {
+        return next.run(request).await;
+    }
+    let (parts, body) = request.into_parts();
+    let bytes = match buffer_request(body).await {
+        Ok(bytes) => bytes,
+        Err(response) => return response,
+    };
+    let path = parts.uri.path_and_query().map(|p| p.as_str()).unwrap_or(parts.uri.path()).to_string();
+    let requested = parts.extensions.get::<RequestedModel>().map(|m| m.0.clone());
+    let picked = serde_json::from_slice::<Value>(&bytes).ok().and_then(|json| {
+        let model = requested.or_else(|| crate::proxy::shadow::request_model(&path, &json))?;
+        let rule = crate::proxy::shadow::pick(&model)?;
+        Some((json, model, rule))
+    });
+    let Some((json, model, rule)) = picked else {
+        return next.run(Request::from_parts(parts, Body::from(bytes))).await;
+    };
+
+    let target = target_for(&env, &rule);
+    let request_id = parts.extensions.get::<RequestId>().map(|r| r.0.clone());
+    let shadow_task = {
+        let (rule, path, request_id) = (rule.clone(), path.clone(), request_id.clone());
+        let timeout = crate::proxy::shadow::timeout_secs();
+        tokio::spawn(async move {
+            crate::proxy::shadow::send(&rule, target, &path, json, request_id.as_deref(), timeout).await
+        })
+    };
+
+    let start = std::time::Instant::now();
+    let response = next.run(Request::from_parts(parts, Body::from(bytes))).await;
+    let primary_status = response.status().as_u16();
+    let primary_duration = start.elapsed().as_millis() as u64;
+    tokio::spawn(async move {
+        let Ok(outcome) = shadow_task.await else {
+            return;
+        };
+        crate::proxy::shadow::record(ShadowResult {
+            id: uuid::Uuid::new_v4().to_string(),
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            rule: rule.name,
+            request_id,
+            path,
+            model,
+            primary_status,
+            primary_duration,
+            shadow_url: outcome.url,
+            shadow_model: outcome.model,
+            shadow_status: outcome.status,
+            shadow_duration: outcome.duration,
+            shadow_response: outcome.response,
+            error: outcome.error,
+        });
+    });
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy::common::pii::PiiMasker;
+    use crate::proxy::config::{PiiRedactionConfig, ShadowConfig};
+    use axum::{http::header, routing::post, Router};
+    use std::time::Duration;
+    use tower::Service;
+
+    /// 与 pii_redaction_middleware 相同的请求脱敏, 位于影子流量之外
+    async fn redact(request: Request, next: Next) -> Response {
+        let (mut parts, body) = request.into_parts();
+        let bytes = buffer_request(body).await.unwrap();
+        let mut json: Value = serde_json::from_slice(&bytes).unwrap();
+        PiiMasker::new(&PiiRedactionConfig { enabled: true, ..Default::default() }).mask_json(&mut json);
+        parts.headers.remove(header::CONTENT_LENGTH);
+        next.run(Request::from_parts(parts, Body::from(json.to_string()))).await
+    }
+
+    #[tokio::test]
+    async fn test_external_shadow_receives_redacted_body() {
+        // 第三方影子上游: 记录收到的请求体
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<String>();
+        let external = Router::new().route(
+            "/v1/chat/completions",
+            post(move |body: String| {
+                let tx = tx.clone();
+                async move {
+                    let _ = tx.send(body);
+                    "{}"
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, external).await });
+
+        crate::proxy::shadow::configure(&ShadowConfig {
+            enabled: true,
+            rules: vec![ShadowRule {
+                name: "external".to_string(),
+                percent: 100.0,
+                base_url: Some(format!("http://{}", addr)),
+                enabled: true,
+                ..Default::default()
+            }],
+            ..Default::default()
+        });
+        let env = ShadowEnv { port: 0, api_key: String::new(), upstream_proxy: Default::default() };
+        let mut app = Router::new()
+            .route("/v1/chat/completions", post(|| async { "primary" }))
+            .layer(axum::middleware::from_fn(move |req, next| mirror(env.clone(), req, next)))
+            .layer(axum::middleware::from_fn(redact));
+
+        let body = serde_json::json!({
+            "model": "gpt-4o",
+            "messages": [{ "role": "user", "content": "Reply to alice@example.com" }]
+        });
+        let request = Request::post("/v1/chat/completions")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let response = app.call(request).await.unwrap();
+        assert!(response.status().is_success());
+
+        let mirrored = tokio::time::timeout(Duration::from_secs(5), rx.recv()).await.unwrap().unwrap();
+        assert!(mirrored.contains("[EMAIL_1]"));
+        assert!(!mirrored.contains("alice@example.com"));
+    }
+}
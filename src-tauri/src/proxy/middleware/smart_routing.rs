@@ -0,0 +1,53 @@
+// 智能路由中间件
+// 位于鉴权与限流之内: 虚拟 Key 的模型白名单与限流按客户端请求的别名判断,
+// 之后把请求体中的 `model` 改写为选中候选的模型, 由后续的分发逻辑路由到对应上游。
+
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{header, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use serde_json::{json, Value};
+
+use crate::proxy::middleware::body_limit::is_multipart;
+use crate::proxy::middleware::buffer::buffer_request;
+use crate::proxy::server::AppState;
+
+/// 响应头: 智能路由选中的 `上游/模型`
+pub const SMART_ROUTE_HEADER: &str = "x-smart-route";
+
+pub async fn smart_routing_middleware(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    if !crate::proxy::smart_routing::is_enabled()
+        || request.method() != axum::http::Method::POST
+        || is_multipart(request.headers())
+    {
+        return next.run(request).await;
+    }
+    let (mut parts, body) = request.into_parts();
+    let bytes = match buffer_request(body).await {
+        Ok(bytes) => bytes,
+        Err(response) => return response,
+    };
+    let Ok(mut json) = serde_json::from_slice::<Value>(&bytes) else {
+        return next.run(Request::from_parts(parts, Body::from(bytes))).await;
+    };
+    let Some(model) = json.get("model").and_then(Value::as_str).map(String::from) else {
+        return next.run(Request::from_parts(parts, Body::from(bytes))).await;
+    };
+    let health = state.health_checker.snapshot();
+    let prices = state.pricing.read().await.models.clone();
+    let Some(chosen) = crate::proxy::smart_routing::select(&model, &health, &prices).await else {
+        return next.run(Request::from_parts(parts, Body::from(bytes))).await;
+    };
+
+    tracing::debug!("[SmartRouting] {} -> {} on {}", model, chosen.model, chosen.upstream);
+    json["model"] = json!(chosen.model);
+    parts.headers.remove(header::CONTENT_LENGTH);
+    let mut response = next.run(Request::from_parts(parts, Body::from(json.to_string()))).await;
+    if let Ok(value) = HeaderValue::from_str(&format!("{}/{}", chosen.upstream, chosen.model)) {
+        response.headers_mut().insert(SMART_ROUTE_HEADER, value);
+    }
+    response
+}
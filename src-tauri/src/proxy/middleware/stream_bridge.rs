@@ -0,0 +1,91 @@
+// 非流式 -> 流式桥接中间件
+// 客户端要求流式输出而上游 (或处理器) 返回了完整 JSON 时, 合成等价的 SSE 流,
+// 客户端无需关心上游是否支持流式。
+
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header, HeaderValue, Method},
+    middleware::Next,
+    response::Response,
+};
+use serde_json::Value;
+
+use crate::proxy::mappers::stream_bridge::json_to_sse;
+use crate::proxy::middleware::buffer::{buffer_request, buffer_response};
+
+/// 通过请求体 `stream` 字段选择流式的路径
+const STREAM_FLAG_PATHS: [&str; 3] = ["/v1/chat/completions", "/v1/completions", "/v1/messages"];
+
+/// 客户端期望的流式格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Expected {
+    Sse,
+    /// Gemini streamGenerateContent 未指定 alt=sse 时返回 JSON 数组
+    JsonArray,
+}
+
+pub async fn stream_bridge_middleware(request: Request, next: Next) -> Response {
+    if request.method() != Method::POST {
+        return next.run(request).await;
+    }
+    let path = request.uri().path().to_string();
+
+    let (request, expected) = if path.contains(":streamGenerateContent") {
+        let sse = request.uri().query().map_or(false, |q| q.contains("alt=sse"));
+        (request, if sse { Expected::Sse } else { Expected::JsonArray })
+    } else if STREAM_FLAG_PATHS.iter().any(|p| path == *p || path.starts_with(&format!("{}/", p))) {
+        let (parts, body) = request.into_parts();
+        let bytes = match buffer_request(body).await {
+            Ok(bytes) => bytes,
+            Err(response) => return response,
+        };
+        let wants_stream = serde_json::from_slice::<Value>(&bytes)
+            .ok()
+            .and_then(|v| v.get("stream").and_then(|s| s.as_bool()))
+            .unwrap_or(false);
+        let request = Request::from_parts(parts, Body::from(bytes));
+        if !wants_stream {
+            return next.run(request).await;
+        }
+        (request, Expected::Sse)
+    } else {
+        return next.run(request).await;
+    };
+
+    let response = next.run(request).await;
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map_or(false, |ct| ct.contains("application/json"));
+    if !response.status().is_success() || !is_json {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match buffer_response(body).await {
+        Ok(bytes) => bytes,
+        Err(response) => return response,
+    };
+    let Ok(json) = serde_json::from_slice::<Value>(&bytes) else {
+        return Response::from_parts(parts, Body::from(bytes));
+    };
+
+    let body = match expected {
+        Expected::JsonArray if json.is_array() => return Response::from_parts(parts, Body::from(bytes)),
+        Expected::JsonArray => Body::from(Value::Array(vec![json]).to_string()),
+        Expected::Sse => match json_to_sse(&json) {
+            Some(events) => {
+                parts.headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("text/event-stream"));
+                parts.headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+                Body::from_stream(futures::stream::iter(events.into_iter().map(Ok::<_, std::io::Error>)))
+            }
+            None => return Response::from_parts(parts, Body::from(bytes)),
+        },
+    };
+    tracing::debug!("[StreamBridge] Synthesized stream response for {}", path);
+    parts.headers.remove(header::CONTENT_LENGTH);
+    parts.headers.insert("X-Stream-Synthesized", HeaderValue::from_static("true"));
+    Response::from_parts(parts, body)
+}
@@ -0,0 +1,732 @@
+// 流式响应改写中间件
+// 按路由配置在转发途中改写 SSE 事件: 删除非标准字段、改写 model 字段、归一化推理内容、
+// 上游未返回 usage 时补发估算的 usage chunk, 让不兼容扩展字段的客户端也能正常工作。
+// 推理内容归一化与输出后处理 (停止序列、代码块、前后缀、正则替换) 同样作用于非流式 JSON 响应。
+// 注意: 命中规则的流会按事件重新编码, SSE 注释行 (心跳) 不会被转发。
+
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::header,
+    middleware::Next,
+    response::Response,
+};
+use bytes::Bytes;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+use crate::proxy::common::output_rules::{CompiledOutputRules, OutputProcessor};
+use crate::proxy::common::reasoning::{normalize_response, StreamReasoningNormalizer};
+use crate::proxy::common::sse::{SseEvent, SseParser};
+use crate::proxy::common::stream_relay::next_chunk;
+use crate::proxy::config::{ReasoningMode, StreamTransformRule};
+use crate::proxy::mappers::context_manager::estimate_tokens_from_str;
+use crate::proxy::middleware::body_limit::is_multipart;
+use crate::proxy::middleware::buffer::{buffer_request, buffer_response};
+use crate::proxy::server::AppState;
+
+/// `model` 改写值中代表客户端请求模型名的占位符
+const REQUEST_MODEL_PLACEHOLDER: &str = "{request}";
+
+/// 请求体中计入 prompt 估算的字段
+const PROMPT_FIELDS: [&str; 6] = ["messages", "input", "instructions", "prompt", "system", "contents"];
+
+fn find_rule<'a>(rules: &'a [StreamTransformRule], path: &str) -> Option<&'a StreamTransformRule> {
+    rules
+        .iter()
+        .find(|r| crate::proxy::common::model_mapping::wildcard_match(r.path.trim(), path))
+}
+
+pub async fn stream_transform_middleware(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let rule = {
+        let cfg = state.stream_transform.read().await;
+        if !cfg.enabled {
+            return next.run(request).await;
+        }
+        match find_rule(&cfg.rules, request.uri().path()) {
+            Some(rule) => rule.clone(),
+            None => return next.run(request).await,
+        }
+    };
+
+    // 只有需要请求模型名或 prompt 估算时才缓冲请求体
+    let needs_body = rule.inject_usage
+        || rule
+            .model
+            .as_deref()
+            .map_or(false, |m| m.contains(REQUEST_MODEL_PLACEHOLDER));
+    let (request, request_json) = if needs_body && !is_multipart(request.headers()) {
+        let (parts, body) = request.into_parts();
+        let bytes = match buffer_request(body).await {
+            Ok(bytes) => bytes,
+            Err(response) => return response,
+        };
+        let json = serde_json::from_slice::<Value>(&bytes).ok();
+        (Request::from_parts(parts, Body::from(bytes)), json)
+    } else {
+        (request, None)
+    };
+
+    let response = next.run(request).await;
+    let content_type = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("")
+        .to_string();
+    if !content_type.contains("text/event-stream") {
+        let output = compile_output(&rule);
+        if (rule.reasoning != ReasoningMode::Passthrough || output.is_some()) && content_type.contains("json") {
+            return transform_json_response(rule.reasoning, output.as_ref(), response).await;
+        }
+        return response;
+    }
+
+    let mut transformer = StreamTransformer::new(rule, request_json.as_ref());
+    let (mut parts, body) = response.into_parts();
+    parts.headers.remove(header::CONTENT_LENGTH);
+    let mut stream = body.into_data_stream();
+    let (tx, rx) = tokio::sync::mpsc::channel(64);
+
+    tokio::spawn(async move {
+        while let Ok(Some(chunk)) = next_chunk(&mut stream, &tx).await {
+            match chunk {
+                Ok(chunk) => {
+                    let out = transformer.push(&chunk);
+                    if !out.is_empty() && tx.send(Ok::<_, axum::Error>(Bytes::from(out))).await.is_err() {
+                        return; // 客户端已断开
+                    }
+                    // 命中停止序列后不再读取上游
+                    if transformer.is_done() {
+                        return;
+                    }
+                }
+                Err(e) => {
+                    let _ = tx.send(Err(e)).await;
+                    return;
+                }
+            }
+        }
+        let out = transformer.finish();
+        if !out.is_empty() {
+            let _ = tx.send(Ok(Bytes::from(out))).await;
+        }
+    });
+
+    Response::from_parts(parts, Body::from_stream(tokio_stream::wrappers::ReceiverStream::new(rx)))
+}
+
+/// 规则中的输出后处理, 没有配置或正则无效时为 None (配置校验会报告无效正则)
+fn compile_output(rule: &StreamTransformRule) -> Option<CompiledOutputRules> {
+    match CompiledOutputRules::new(&rule.output) {
+        Ok(rules) => (!rules.is_empty()).then_some(rules),
+        Err(e) => {
+            tracing::warn!("[StreamTransform] output rules of {} ignored: {}", rule.path, e);
+            None
+        }
+    }
+}
+
+/// 非流式响应 (包括 collector 收集后的结果) 的推理内容归一化与输出后处理
+async fn transform_json_response(
+    mode: ReasoningMode,
+    output: Option<&CompiledOutputRules>,
+    response: Response,
+) -> Response {
+    let (mut parts, body) = response.into_parts();
+    let bytes = match buffer_response(body).await {
+        Ok(bytes) => bytes,
+        Err(response) => return response,
+    };
+    let Ok(mut json) = serde_json::from_slice::<Value>(&bytes) else {
+        return Response::from_parts(parts, Body::from(bytes));
+    };
+    let mut changed = normalize_response(mode, &mut json);
+    changed |= output.is_some_and(|rules| crate::proxy::common::output_rules::apply_response(rules, &mut json));
+    if !changed {
+        return Response::from_parts(parts, Body::from(bytes));
+    }
+    parts.headers.remove(header::CONTENT_LENGTH);
+    Response::from_parts(parts, Body::from(json.to_string()))
+}
+
+/// 单个流的改写状态
+pub(crate) struct StreamTransformer {
+    rule: StreamTransformRule,
+    parser: SseParser,
+    reasoning: StreamReasoningNormalizer,
+    output_rules: Option<CompiledOutputRules>,
+    /// 按 OpenAI choice index / Anthropic 块 index 区分的输出后处理状态
+    outputs: HashMap<u64, OutputProcessor>,
+    /// 命中停止序列, 流已结束
+    done: bool,
+    /// 解析后的 model 改写值
+    model: Option<String>,
+    prompt_tokens: u32,
+    completion_text: String,
+    /// 是否为 OpenAI chunk 格式 (只对该格式补发 usage)
+    openai_format: bool,
+    saw_usage: bool,
+    usage_sent: bool,
+    last_id: Option<Value>,
+    last_created: Option<Value>,
+    last_model: Option<Value>,
+}
+
+impl StreamTransformer {
+    pub(crate) fn new(rule: StreamTransformRule, request: Option<&Value>) -> Self {
+        let request_model = request
+            .and_then(|r| r.get("model"))
+            .and_then(|m| m.as_str())
+            .unwrap_or_default();
+        let model = rule.model.as_deref().and_then(|m| {
+            if m.contains(REQUEST_MODEL_PLACEHOLDER) && request_model.is_empty() {
+                return None;
+            }
+            Some(m.replace(REQUEST_MODEL_PLACEHOLDER, request_model))
+        });
+
+        let prompt_tokens = request.map_or(0, |r| {
+            let mut text = String::new();
+            for field in PROMPT_FIELDS {
+                if let Some(v) = r.get(field) {
+                    collect_strings(v, &mut text);
+                }
+            }
+            estimate_tokens_from_str(&text)
+        });
+
+        Self {
+            reasoning: StreamReasoningNormalizer::new(rule.reasoning),
+            output_rules: compile_output(&rule),
+            outputs: HashMap::new(),
+            done: false,
+            rule,
+            parser: SseParser::new(),
+            model,
+            prompt_tokens,
+            completion_text: String::new(),
+            openai_format: false,
+            saw_usage: false,
+            usage_sent: false,
+            last_id: None,
+            last_created: None,
+            last_model: None,
+        }
+    }
+
+    pub(crate) fn push(&mut self, chunk: &[u8]) -> Vec<u8> {
+        if self.done {
+            return Vec::new();
+        }
+        let events = self.parser.push(chunk);
+        self.render(events)
+    }
+
+    pub(crate) fn finish(&mut self) -> Vec<u8> {
+        if self.done {
+            return Vec::new();
+        }
+        let events = self.parser.finish();
+        let mut out = self.render(events);
+        // 上游未发送 [DONE] 时在流末尾补发
+        if !self.done {
+            if let Some(flushed) = self.flush_output() {
+                out.extend(encode_event(&flushed));
+            }
+            if let Some(usage) = self.usage_event() {
+                out.extend(encode_event(&usage));
+            }
+        }
+        out
+    }
+
+    /// 命中停止序列后流已结束, 不必继续读取上游
+    pub(crate) fn is_done(&self) -> bool {
+        self.done
+    }
+
+    fn render(&mut self, events: Vec<SseEvent>) -> Vec<u8> {
+        let mut out = Vec::new();
+        for event in events {
+            if event.is_done() {
+                if let Some(flushed) = self.flush_output() {
+                    out.extend(encode_event(&flushed));
+                }
+                if let Some(usage) = self.usage_event() {
+                    out.extend(encode_event(&usage));
+                }
+                out.extend(encode_event(&event));
+                continue;
+            }
+            for event in self.transform(event) {
+                out.extend(encode_event(&event));
+            }
+            if self.done {
+                // OpenAI 流由这里结束; Anthropic 流的结束事件已由 transform 补发
+                if self.openai_format {
+                    if let Some(usage) = self.usage_event() {
+                        out.extend(encode_event(&usage));
+                    }
+                    out.extend(encode_event(&SseEvent { data: "[DONE]".to_string(), ..Default::default() }));
+                }
+                break;
+            }
+        }
+        out
+    }
+
+    /// 改写单个事件, 返回要发送的事件 (可能为空或包含补发的事件)
+    fn transform(&mut self, mut event: SseEvent) -> Vec<SseEvent> {
+        let Ok(mut json) = serde_json::from_str::<Value>(&event.data) else {
+            return vec![event];
+        };
+
+        self.observe(&json);
+
+        let mut stripped = strip_fields(&mut json, &self.rule.strip_fields);
+        let (normalized, drop) = self.reasoning.apply(&mut json);
+        if drop {
+            return Vec::new();
+        }
+        stripped |= normalized;
+        let (output_changed, output_drop, before, after) = self.apply_output(&mut json);
+        stripped |= output_changed;
+        // 删除字段后只剩空 delta 的 chunk (例如纯 reasoning chunk) 直接丢弃
+        if output_drop || (stripped && is_empty_chunk(&json)) {
+            return before.into_iter().chain(after).collect();
+        }
+
+        if let Some(model) = &self.model {
+            if json.get("model").is_some() {
+                json["model"] = Value::String(model.clone());
+            }
+            // Anthropic message_start
+            if let Some(message) = json.get_mut("message").filter(|m| m.get("model").is_some()) {
+                message["model"] = Value::String(model.clone());
+            }
+        }
+
+        if stripped || self.model.is_some() {
+            event.data = json.to_string();
+        }
+        before.into_iter().chain(std::iter::once(event)).chain(after).collect()
+    }
+
+    /// 输出后处理, 返回 (是否有改动, 是否丢弃该事件, 之前补发的事件, 之后补发的事件)
+    fn apply_output(&mut self, json: &mut Value) -> (bool, bool, Vec<SseEvent>, Vec<SseEvent>) {
+        let Some(rules) = &self.output_rules else {
+            return (false, false, Vec::new(), Vec::new());
+        };
+        let (mut changed, mut before, mut after) = (false, Vec::new(), Vec::new());
+
+        if let Some(choices) = json.get_mut("choices").and_then(|c| c.as_array_mut()) {
+            for choice in choices {
+                let index = choice.get("index").and_then(|i| i.as_u64()).unwrap_or(0);
+                let finished = choice.get("finish_reason").is_some_and(|f| !f.is_null());
+                let pointer = if choice.get("delta").is_some() { "/delta/content" } else { "/text" };
+                let text = choice.pointer(pointer).and_then(|t| t.as_str()).map(String::from);
+                if text.is_none() && !finished {
+                    continue;
+                }
+                let text = text.unwrap_or_default();
+                let processor = self.outputs.entry(index).or_insert_with(|| OutputProcessor::new(rules.clone()));
+                let mut out = processor.push(&text);
+                if finished {
+                    out.push_str(&processor.finish());
+                }
+                if processor.stop_sequence().is_some() {
+                    choice["finish_reason"] = json!("stop");
+                    self.done = true;
+                    changed = true;
+                }
+                if out != text {
+                    match choice.get_mut("delta") {
+                        Some(delta) => delta["content"] = Value::String(out),
+                        None => choice["text"] = Value::String(out),
+                    }
+                    changed = true;
+                }
+            }
+            return (changed, false, before, after);
+        }
+
+        // Anthropic: 每个文本块单独处理
+        let Some(index) = json.get("index").and_then(|i| i.as_u64()) else {
+            return (false, false, before, after);
+        };
+        match json.get("type").and_then(|t| t.as_str()).unwrap_or_default() {
+            "content_block_start" if json.pointer("/content_block/type").and_then(|t| t.as_str()) == Some("text") => {
+                self.outputs.insert(index, OutputProcessor::new(rules.clone()));
+            }
+            "content_block_delta" if json.pointer("/delta/type").and_then(|t| t.as_str()) == Some("text_delta") => {
+                let Some(processor) = self.outputs.get_mut(&index) else {
+                    return (false, false, before, after);
+                };
+                let text = json.pointer("/delta/text").and_then(|t| t.as_str()).unwrap_or_default().to_string();
+                let out = processor.push(&text);
+                if let Some(stop) = processor.stop_sequence() {
+                    after = vec![
+                        named_event("content_block_stop", json!({"type": "content_block_stop", "index": index})),
+                        named_event(
+                            "message_delta",
+                            json!({
+                                "type": "message_delta",
+                                "delta": {"stop_reason": "stop_sequence", "stop_sequence": stop},
+                                "usage": {"output_tokens": estimate_tokens_from_str(&self.completion_text)}
+                            }),
+                        ),
+                        named_event("message_stop", json!({"type": "message_stop"})),
+                    ];
+                    self.done = true;
+                }
+                self.completion_text.push_str(&out);
+                let drop = out.is_empty();
+                changed = out != text;
+                json["delta"]["text"] = Value::String(out);
+                return (changed, drop, before, after);
+            }
+            "content_block_stop" => {
+                let rest = self.outputs.get_mut(&index).map(|p| p.finish()).unwrap_or_default();
+                if !rest.is_empty() {
+                    self.completion_text.push_str(&rest);
+                    let delta = json!({"type": "text_delta", "text": rest});
+                    before.push(named_event(
+                        "content_block_delta",
+                        json!({"type": "content_block_delta", "index": index, "delta": delta}),
+                    ));
+                }
+            }
+            _ => {}
+        }
+        (changed, false, before, after)
+    }
+
+    /// 流在 finish_reason 之前结束时补发暂存的 OpenAI 文本
+    fn flush_output(&mut self) -> Option<SseEvent> {
+        if !self.openai_format {
+            return None;
+        }
+        let mut indexes: Vec<u64> = self.outputs.keys().copied().collect();
+        indexes.sort_unstable();
+        let choices: Vec<Value> = indexes
+            .into_iter()
+            .filter_map(|index| {
+                let rest = self.outputs.get_mut(&index)?.finish();
+                (!rest.is_empty()).then(|| json!({"index": index, "delta": {"content": rest}, "finish_reason": null}))
+            })
+            .collect();
+        if choices.is_empty() {
+            return None;
+        }
+        let chunk = json!({
+            "id": self.last_id.clone().unwrap_or(Value::Null),
+            "object": "chat.completion.chunk",
+            "created": self.last_created.clone().unwrap_or_else(|| json!(chrono::Utc::now().timestamp())),
+            "model": self.model.clone().map(Value::String).or_else(|| self.last_model.clone()).unwrap_or(Value::Null),
+            "choices": choices,
+        });
+        Some(SseEvent { data: chunk.to_string(), ..Default::default() })
+    }
+
+    fn observe(&mut self, json: &Value) {
+        if let Some(object) = json.get("object").and_then(|o| o.as_str()) {
+            if object.starts_with("chat.completion") || object == "text_completion" {
+                self.openai_format = true;
+            }
+        }
+        if json.get("usage").map_or(false, |u| !u.is_null()) || json.get("usageMetadata").is_some() {
+            self.saw_usage = true;
+        }
+        for (slot, key) in [
+            (&mut self.last_id, "id"),
+            (&mut self.last_created, "created"),
+            (&mut self.last_model, "model"),
+        ] {
+            if let Some(v) = json.get(key) {
+                *slot = Some(v.clone());
+            }
+        }
+        // 删除字段之前统计输出, reasoning 同样计入 completion tokens
+        if let Some(choices) = json.get("choices").and_then(|c| c.as_array()) {
+            for choice in choices {
+                if let Some(delta) = choice.get("delta") {
+                    collect_strings(delta, &mut self.completion_text);
+                }
+                if let Some(text) = choice.get("text") {
+                    collect_strings(text, &mut self.completion_text);
+                }
+            }
+        }
+    }
+
+    fn usage_event(&mut self) -> Option<SseEvent> {
+        if !self.rule.inject_usage || !self.openai_format || self.saw_usage || self.usage_sent {
+            return None;
+        }
+        self.usage_sent = true;
+        let completion_tokens = estimate_tokens_from_str(&self.completion_text);
+        let model = self
+            .model
+            .clone()
+            .map(Value::String)
+            .or_else(|| self.last_model.clone())
+            .unwrap_or(Value::Null);
+        let chunk = json!({
+            "id": self.last_id.clone().unwrap_or(Value::Null),
+            "object": "chat.completion.chunk",
+            "created": self.last_created.clone().unwrap_or_else(|| json!(chrono::Utc::now().timestamp())),
+            "model": model,
+            "choices": [],
+            "usage": {
+                "prompt_tokens": self.prompt_tokens,
+                "completion_tokens": completion_tokens,
+                "total_tokens": self.prompt_tokens + completion_tokens,
+            }
+        });
+        Some(SseEvent {
+            data: chunk.to_string(),
+            ..Default::default()
+        })
+    }
+}
+
+fn named_event(event: &str, data: Value) -> SseEvent {
+    SseEvent { event: event.to_string(), data: data.to_string(), id: None }
+}
+
+pub(crate) fn encode_event(event: &SseEvent) -> Vec<u8> {
+    let mut out = String::new();
+    if !event.event.is_empty() {
+        out.push_str(&format!("event: {}\n", event.event));
+    }
+    if let Some(id) = &event.id {
+        out.push_str(&format!("id: {}\n", id));
+    }
+    for line in event.data.split('\n') {
+        out.push_str(&format!("data: {}\n", line));
+    }
+    out.push('\n');
+    out.into_bytes()
+}
+
+/// 递归删除指定字段, 返回是否有改动
+fn strip_fields(value: &mut Value, fields: &[String]) -> bool {
+    if fields.is_empty() {
+        return false;
+    }
+    match value {
+        Value::Object(map) => {
+            let before = map.len();
+            map.retain(|k, _| !fields.iter().any(|f| f == k));
+            let mut changed = map.len() != before;
+            for v in map.values_mut() {
+                changed |= strip_fields(v, fields);
+            }
+            changed
+        }
+        Value::Array(items) => items
+            .iter_mut()
+            .fold(false, |changed, v| strip_fields(v, fields) | changed),
+        _ => false,
+    }
+}
+
+/// OpenAI chunk 是否已没有任何有效内容
+fn is_empty_chunk(json: &Value) -> bool {
+    let Some(choices) = json.get("choices").and_then(|c| c.as_array()) else {
+        return false;
+    };
+    if choices.is_empty() || json.get("usage").map_or(false, |u| !u.is_null()) {
+        return false;
+    }
+    choices.iter().all(|choice| {
+        let delta_empty = choice
+            .get("delta")
+            .and_then(|d| d.as_object())
+            .map_or(true, |d| d.iter().all(|(k, v)| k == "role" || v.is_null() || v.as_str() == Some("")));
+        let no_text = choice
+            .get("text")
+            .and_then(|t| t.as_str())
+            .map_or(true, str::is_empty);
+        let no_finish = choice.get("finish_reason").map_or(true, Value::is_null);
+        delta_empty && no_text && no_finish
+    })
+}
+
+fn collect_strings(value: &Value, out: &mut String) {
+    match value {
+        Value::String(s) => {
+            out.push_str(s);
+            out.push(' ');
+        }
+        Value::Array(items) => items.iter().for_each(|v| collect_strings(v, out)),
+        Value::Object(map) => map.values().for_each(|v| collect_strings(v, out)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule() -> StreamTransformRule {
+        StreamTransformRule {
+            path: "/v1/chat/*".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn chunk(delta: Value, finish: Value) -> String {
+        format!(
+            "data: {}\n\n",
+            json!({
+                "id": "chatcmpl-1",
+                "object": "chat.completion.chunk",
+                "created": 1,
+                "model": "gemini-2.5-flash",
+                "choices": [{"index": 0, "delta": delta, "finish_reason": finish}]
+            })
+        )
+    }
+
+    fn data_events(out: &[u8]) -> Vec<String> {
+        let mut parser = SseParser::new();
+        let mut events = parser.push(out);
+        events.extend(parser.finish());
+        events.into_iter().map(|e| e.data).collect()
+    }
+
+    #[test]
+    fn test_find_rule_by_path() {
+        let rules = vec![rule()];
+        assert!(find_rule(&rules, "/v1/chat/completions").is_some());
+        assert!(find_rule(&rules, "/v1/messages").is_none());
+    }
+
+    #[test]
+    fn test_strip_reasoning_and_rename_model() {
+        let mut rule = rule();
+        rule.strip_fields = vec!["reasoning_content".to_string()];
+        rule.model = Some("{request}".to_string());
+        let request = json!({"model": "gpt-4o", "messages": []});
+        let mut t = StreamTransformer::new(rule, Some(&request));
+
+        let input = format!(
+            "{}{}data: [DONE]\n\n",
+            chunk(json!({"role": "assistant", "reasoning_content": "hmm"}), Value::Null),
+            chunk(json!({"content": "Hi"}), json!("stop"))
+        );
+        // 按任意位置切分, 验证跨 chunk 解析
+        let (a, b) = input.as_bytes().split_at(37);
+        let mut out = t.push(a);
+        out.extend(t.push(b));
+        out.extend(t.finish());
+
+        let events = data_events(&out);
+        assert_eq!(events.len(), 2, "reasoning-only chunk should be dropped");
+        let first: Value = serde_json::from_str(&events[0]).unwrap();
+        assert_eq!(first["model"], "gpt-4o");
+        assert_eq!(first["choices"][0]["delta"]["content"], "Hi");
+        assert_eq!(events[1], "[DONE]");
+    }
+
+    #[test]
+    fn test_inject_usage_before_done() {
+        let mut rule = rule();
+        rule.inject_usage = true;
+        let request = json!({"model": "gpt-4o", "messages": [{"role": "user", "content": "hello there"}]});
+        let mut t = StreamTransformer::new(rule, Some(&request));
+
+        let input = format!("{}data: [DONE]\n\n", chunk(json!({"content": "Hello world"}), json!("stop")));
+        let out = t.push(input.as_bytes());
+        let events = data_events(&out);
+        assert_eq!(events.len(), 3);
+        let usage: Value = serde_json::from_str(&events[1]).unwrap();
+        assert_eq!(usage["choices"], json!([]));
+        assert_eq!(usage["id"], "chatcmpl-1");
+        assert!(usage["usage"]["prompt_tokens"].as_u64().unwrap() > 0);
+        assert!(usage["usage"]["completion_tokens"].as_u64().unwrap() > 0);
+        assert_eq!(events[2], "[DONE]");
+        assert!(t.finish().is_empty());
+    }
+
+    #[test]
+    fn test_no_injection_when_upstream_sent_usage() {
+        let mut rule = rule();
+        rule.inject_usage = true;
+        let mut t = StreamTransformer::new(rule, None);
+        let with_usage = json!({
+            "object": "chat.completion.chunk",
+            "choices": [],
+            "usage": {"prompt_tokens": 1, "completion_tokens": 1, "total_tokens": 2}
+        });
+        let input = format!("data: {}\n\ndata: [DONE]\n\n", with_usage);
+        let out = t.push(input.as_bytes());
+        assert_eq!(data_events(&out).len(), 2);
+
+        // 非 OpenAI 格式 (Anthropic) 不补发
+        let mut t = StreamTransformer::new(StreamTransformRule { inject_usage: true, ..rule_any() }, None);
+        let out = t.push(b"event: message_stop\ndata: {\"type\":\"message_stop\"}\n\n");
+        assert!(t.finish().is_empty());
+        assert_eq!(data_events(&out).len(), 1);
+    }
+
+    #[test]
+    fn test_strip_reasoning_mode() {
+        let mut rule = rule();
+        rule.reasoning = ReasoningMode::Strip;
+        let mut t = StreamTransformer::new(rule, None);
+
+        let input = format!(
+            "{}{}{}data: [DONE]\n\n",
+            chunk(json!({"role": "assistant", "reasoning_content": "hmm"}), Value::Null),
+            chunk(json!({"content": "<think>plan</think>"}), Value::Null),
+            chunk(json!({"content": "Hi"}), json!("stop"))
+        );
+        let mut out = t.push(input.as_bytes());
+        out.extend(t.finish());
+
+        let events = data_events(&out);
+        assert_eq!(events.len(), 2, "reasoning-only chunks should be dropped");
+        let first: Value = serde_json::from_str(&events[0]).unwrap();
+        assert_eq!(first["choices"][0]["delta"], json!({"content": "Hi"}));
+    }
+
+    #[test]
+    fn test_output_stop_sequence_ends_stream() {
+        let mut rule = rule();
+        rule.output.stop = vec!["STOP".to_string()];
+        let mut t = StreamTransformer::new(rule, None);
+
+        let input = format!(
+            "{}{}{}",
+            chunk(json!({"content": "Hello ST"}), Value::Null),
+            chunk(json!({"content": "OP ignored"}), Value::Null),
+            chunk(json!({"content": "more"}), Value::Null)
+        );
+        let out = t.push(input.as_bytes());
+        assert!(t.is_done());
+        assert!(t.finish().is_empty());
+
+        let events = data_events(&out);
+        assert_eq!(events.len(), 3);
+        let first: Value = serde_json::from_str(&events[0]).unwrap();
+        assert_eq!(first["choices"][0]["delta"]["content"], "Hello ");
+        let last: Value = serde_json::from_str(&events[1]).unwrap();
+        assert_eq!(last["choices"][0]["delta"]["content"], "");
+        assert_eq!(last["choices"][0]["finish_reason"], "stop");
+        assert_eq!(events[2], "[DONE]");
+    }
+
+    fn rule_any() -> StreamTransformRule {
+        StreamTransformRule {
+            path: "*".to_string(),
+            ..Default::default()
+        }
+    }
+}
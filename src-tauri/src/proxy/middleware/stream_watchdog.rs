@@ -0,0 +1,204 @@
+// 流式响应看门狗中间件
+// 上游在流中途停滞时 (超过 `stream_idle_timeout_secs` 没有新数据, 心跳注释不计入),
+// 中断响应流、按客户端协议发送 SSE 错误事件, 并把提供该流的 v1internal 端点记为一次超时失败。
+
+use axum::{
+    body::{Body, Bytes},
+    extract::{Request, State},
+    http::header,
+    middleware::Next,
+    response::Response,
+};
+use futures::{Stream, StreamExt};
+use serde_json::json;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+
+use crate::proxy::server::AppState;
+
+tokio::task_local! {
+    static SERVED_ENDPOINT: Arc<Mutex<Option<String>>>;
+}
+
+/// 记录当前请求最终使用的上游端点 (由 UpstreamClient 在请求成功时调用)
+pub fn record_served_endpoint(endpoint: &str) {
+    let _ = SERVED_ENDPOINT.try_with(|slot| {
+        *slot.lock().unwrap_or_else(|e| e.into_inner()) = Some(endpoint.to_string());
+    });
+}
+
+/// 错误事件使用的协议格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ClientFormat {
+    OpenAI,
+    Anthropic,
+    Gemini,
+}
+
+impl ClientFormat {
+    pub(crate) fn from_path(path: &str) -> Self {
+        if path.starts_with("/v1/messages") {
+            ClientFormat::Anthropic
+        } else if path.starts_with("/v1beta") || path.contains(":streamGenerateContent") {
+            ClientFormat::Gemini
+        } else {
+            ClientFormat::OpenAI
+        }
+    }
+}
+
+/// 按客户端协议构造结束流的错误事件 (OpenAI 格式附带 [DONE])
+pub(crate) fn error_event(format: ClientFormat, message: &str, code: &str, gemini_status: (u16, &str)) -> Bytes {
+    let event = match format {
+        ClientFormat::Anthropic => format!(
+            "event: error\ndata: {}\n\n",
+            json!({"type": "error", "error": {"type": "api_error", "message": message}})
+        ),
+        ClientFormat::OpenAI => format!(
+            "data: {}\n\ndata: [DONE]\n\n",
+            json!({"error": {"message": message, "type": "server_error", "code": code}})
+        ),
+        ClientFormat::Gemini => format!(
+            "data: {}\n\n",
+            json!({"error": {"code": gemini_status.0, "message": message, "status": gemini_status.1}})
+        ),
+    };
+    Bytes::from(event)
+}
+
+fn stall_event(format: ClientFormat, idle: Duration) -> Bytes {
+    let message = format!("Upstream stream stalled: no data received for {}s", idle.as_secs());
+    error_event(format, &message, "stream_idle_timeout", (504, "DEADLINE_EXCEEDED"))
+}
+
+/// 只包含 SSE 注释 (`: ping`) 的数据块视为心跳, 不重置空闲计时
+pub(crate) fn is_heartbeat(chunk: &[u8]) -> bool {
+    let Ok(text) = std::str::from_utf8(chunk) else {
+        return false;
+    };
+    let mut lines = text.lines().map(str::trim).filter(|l| !l.is_empty()).peekable();
+    lines.peek().is_some() && lines.all(|l| l.starts_with(':'))
+}
+
+/// 为响应流加上首包与空闲超时, 超时后输出错误事件并结束流
+fn watch_stream<S, E>(
+    stream: S,
+    format: ClientFormat,
+    first_byte: Duration,
+    idle: Duration,
+    on_stall: impl FnOnce() + Send + 'static,
+) -> impl Stream<Item = Result<Bytes, E>> + Send
+where
+    S: Stream<Item = Result<Bytes, E>> + Send + Unpin + 'static,
+    E: Send + 'static,
+{
+    let deadline = Instant::now() + first_byte;
+    futures::stream::unfold(
+        (stream, deadline, Some(on_stall), false),
+        move |(mut stream, deadline, mut on_stall, done)| async move {
+            if done {
+                return None;
+            }
+            match tokio::time::timeout_at(deadline, stream.next()).await {
+                Ok(Some(Ok(chunk))) => {
+                    let deadline = if is_heartbeat(&chunk) { deadline } else { Instant::now() + idle };
+                    Some((Ok(chunk), (stream, deadline, on_stall, false)))
+                }
+                Ok(Some(Err(e))) => Some((Err(e), (stream, deadline, on_stall, false))),
+                Ok(None) => None,
+                Err(_) => {
+                    if let Some(on_stall) = on_stall.take() {
+                        on_stall();
+                    }
+                    Some((Ok(stall_event(format, idle)), (stream, deadline, on_stall, true)))
+                }
+            }
+        },
+    )
+}
+
+pub async fn stream_watchdog_middleware(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(idle) = state.upstream.stream_idle_timeout() else {
+        return next.run(request).await;
+    };
+    let format = ClientFormat::from_path(request.uri().path());
+    let served = Arc::new(Mutex::new(None::<String>));
+    let response = SERVED_ENDPOINT.scope(served.clone(), next.run(request)).await;
+
+    let is_sse = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map_or(false, |ct| ct.contains("text/event-stream"));
+    if !is_sse {
+        return response;
+    }
+
+    let upstream = state.upstream.clone();
+    let on_stall = move || {
+        let endpoint = served.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        match endpoint {
+            Some(endpoint) => {
+                tracing::warn!("[Stream-Watchdog] Stream from {} stalled for {}s, terminating", endpoint, idle.as_secs());
+                upstream.record_stream_stall(&endpoint);
+            }
+            None => tracing::warn!("[Stream-Watchdog] Stream stalled for {}s, terminating", idle.as_secs()),
+        }
+    };
+
+    let (parts, body) = response.into_parts();
+    let stream = watch_stream(body.into_data_stream(), format, state.upstream.first_byte_timeout(), idle, on_stall);
+    Response::from_parts(parts, Body::from_stream(stream))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    #[test]
+    fn test_is_heartbeat() {
+        assert!(is_heartbeat(b": ping\n\n"));
+        assert!(!is_heartbeat(b"data: {}\n\n"));
+        assert!(!is_heartbeat(b": ping\n\ndata: {}\n\n"));
+        assert!(!is_heartbeat(b"\n\n"));
+    }
+
+    #[tokio::test]
+    async fn test_stalled_stream_is_terminated() {
+        let chunks = vec![Ok::<_, ()>(Bytes::from("data: {\"x\":1}\n\n")), Ok(Bytes::from(": ping\n\n"))];
+        let upstream = futures::stream::iter(chunks).chain(futures::stream::pending());
+        let stalled = Arc::new(AtomicBool::new(false));
+        let flag = stalled.clone();
+        let watched = watch_stream(
+            Box::pin(upstream),
+            ClientFormat::Anthropic,
+            Duration::from_millis(500),
+            Duration::from_millis(50),
+            move || flag.store(true, Ordering::SeqCst),
+        );
+
+        let out: Vec<Bytes> = watched.map(|c| c.unwrap()).collect().await;
+        assert_eq!(out.len(), 3);
+        assert!(stalled.load(Ordering::SeqCst));
+        let text = String::from_utf8_lossy(&out[2]);
+        assert!(text.starts_with("event: error\n"));
+        assert!(text.contains("\"api_error\""));
+    }
+
+    #[test]
+    fn test_stall_event_formats() {
+        let openai = stall_event(ClientFormat::from_path("/v1/chat/completions"), Duration::from_secs(5));
+        assert!(String::from_utf8_lossy(&openai).ends_with("data: [DONE]\n\n"));
+        let gemini = stall_event(
+            ClientFormat::from_path("/v1beta/models/gemini-2.5-pro:streamGenerateContent"),
+            Duration::from_secs(5),
+        );
+        assert!(String::from_utf8_lossy(&gemini).contains("DEADLINE_EXCEEDED"));
+    }
+}
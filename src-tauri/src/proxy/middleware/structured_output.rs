@@ -0,0 +1,193 @@
+// 结构化输出兼容层中间件
+// 位于响应缓存之内、并发限制之外: 缓存的是校验通过后的最终响应, 每次重试都重新占用上游并发名额。
+// 请求按非流式转发 (需要完整回复才能校验), 客户端要求流式时合成等价的 SSE 流。
+// 响应的 usage 为所有尝试的合计, 请求日志与用量统计按实际消耗记录。
+// json_object 请求 (开启修复时) 原样转发, 只修复回复中的 JSON; 无法修复时返回 502 与结构化错误。
+
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde_json::{json, Value};
+
+use crate::proxy::config::SystemPromptMode;
+use crate::proxy::mappers::stream_bridge::json_to_sse;
+use crate::proxy::middleware::buffer::{buffer_request, buffer_response};
+
+/// 响应头: 校验失败后的重试次数
+pub const RETRIES_HEADER: &str = "x-structured-output-retries";
+/// 响应头: 最终回复是否符合 schema (`valid` / `invalid`), json_object 修复后为 `repaired`
+pub const STATUS_HEADER: &str = "x-structured-output";
+
+fn add_usage(total: &mut Value, usage: Option<&Value>) {
+    for key in ["prompt_tokens", "completion_tokens", "total_tokens"] {
+        let current = total.get(key).and_then(Value::as_u64).unwrap_or(0);
+        let added = usage.and_then(|u| u.get(key)).and_then(Value::as_u64).unwrap_or(0);
+        total[key] = json!(current + added);
+    }
+}
+
+pub async fn structured_output_middleware(request: Request, next: Next) -> Response {
+    if request.method() != Method::POST || request.uri().path() != "/v1/chat/completions" {
+        return next.run(request).await;
+    }
+    let (parts, body) = request.into_parts();
+    let bytes = match buffer_request(body).await {
+        Ok(bytes) => bytes,
+        Err(response) => return response,
+    };
+    let json = serde_json::from_slice::<Value>(&bytes).ok();
+    let model = json.as_ref().and_then(|j| j.get("model")).and_then(Value::as_str).unwrap_or_default();
+    let retries = crate::proxy::structured_output::retries_for(model);
+    let repair = crate::proxy::structured_output::repairs_json_object(model);
+    let schema = json.as_ref().and_then(crate::proxy::structured_output::requested_schema);
+    let (max_retries, name, schema, mut json) = match (retries, schema, json) {
+        (Some(max_retries), Some((name, schema)), Some(json)) => (max_retries, name, schema, json),
+        (_, None, Some(json)) if repair && crate::proxy::structured_output::requests_json_object(&json) => {
+            return repair_json_object(parts, json, next).await;
+        }
+        _ => return next.run(Request::from_parts(parts, Body::from(bytes))).await,
+    };
+
+    let wants_stream = force_non_stream(&mut json);
+    if let Some(obj) = json.as_object_mut() {
+        obj.remove("response_format");
+    }
+    let prompt = crate::proxy::structured_output::instructions(&name, &schema);
+    crate::proxy::common::system_prompt::inject("/v1/chat/completions", &mut json, &prompt, SystemPromptMode::Suffix);
+
+    let mut base = parts;
+    base.headers.remove(header::CONTENT_LENGTH);
+    let mut usage = json!({});
+    let mut attempt = 0;
+    let (mut response_parts, mut completion, valid) = loop {
+        let request = Request::from_parts(base.clone(), Body::from(json.to_string()));
+        let response = next.clone().run(request).await;
+        if !response.status().is_success() {
+            return response;
+        }
+        let (response_parts, body) = response.into_parts();
+        let bytes = match buffer_response(body).await {
+            Ok(bytes) => bytes,
+            Err(response) => return response,
+        };
+        let Ok(mut completion) = serde_json::from_slice::<Value>(&bytes) else {
+            return Response::from_parts(response_parts, Body::from(bytes));
+        };
+        add_usage(&mut usage, completion.get("usage"));
+        // 工具调用等没有文本内容的回复不做校验
+        let Some(content) = completion.pointer("/choices/0/message/content").and_then(Value::as_str).map(String::from)
+        else {
+            break (response_parts, completion, true);
+        };
+        match crate::proxy::structured_output::check(&content, &schema) {
+            Ok(value) => {
+                // 去掉代码块与说明文字, 客户端可以直接解析
+                completion["choices"][0]["message"]["content"] = Value::String(value.to_string());
+                break (response_parts, completion, true);
+            }
+            Err(errors) if attempt >= max_retries => {
+                tracing::warn!(
+                    "[StructuredOutput] {} still invalid after {} retries: {}",
+                    model_of(&json),
+                    attempt,
+                    errors.join("; ")
+                );
+                break (response_parts, completion, false);
+            }
+            Err(errors) => {
+                tracing::debug!(
+                    "[StructuredOutput] {} attempt {} invalid: {}",
+                    model_of(&json),
+                    attempt + 1,
+                    errors.join("; ")
+                );
+                if let Some(messages) = json.get_mut("messages").and_then(Value::as_array_mut) {
+                    messages.push(json!({"role": "assistant", "content": content}));
+                    let feedback = crate::proxy::structured_output::feedback(&errors);
+                    messages.push(json!({"role": "user", "content": feedback}));
+                }
+                attempt += 1;
+            }
+        }
+    };
+    if attempt > 0 {
+        completion["usage"] = usage;
+    }
+
+    response_parts.headers.insert(RETRIES_HEADER, HeaderValue::from(attempt));
+    response_parts.headers.insert(STATUS_HEADER, HeaderValue::from_static(if valid { "valid" } else { "invalid" }));
+    finish(response_parts, completion, wants_stream)
+}
+
+/// 改为非流式请求, 返回客户端是否要求流式
+fn force_non_stream(json: &mut Value) -> bool {
+    let wants_stream = json.get("stream").and_then(Value::as_bool).unwrap_or(false);
+    if let Some(obj) = json.as_object_mut() {
+        obj.remove("stream_options");
+        obj.insert("stream".to_string(), Value::Bool(false));
+    }
+    wants_stream
+}
+
+/// 按客户端期望的格式返回 (非流式 JSON 或合成的 SSE)
+fn finish(mut response_parts: axum::http::response::Parts, completion: Value, wants_stream: bool) -> Response {
+    response_parts.headers.remove(header::CONTENT_LENGTH);
+    if !wants_stream {
+        return Response::from_parts(response_parts, axum::Json(completion).into_response().into_body());
+    }
+    let events = json_to_sse(&completion).unwrap_or_default();
+    response_parts.headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("text/event-stream"));
+    response_parts.headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+    let body = Body::from_stream(futures::stream::iter(events.into_iter().map(Ok::<_, std::io::Error>)));
+    Response::from_parts(response_parts, body)
+}
+
+async fn repair_json_object(mut parts: axum::http::request::Parts, mut json: Value, next: Next) -> Response {
+    let wants_stream = force_non_stream(&mut json);
+    parts.headers.remove(header::CONTENT_LENGTH);
+    let response = next.run(Request::from_parts(parts, Body::from(json.to_string()))).await;
+    if !response.status().is_success() {
+        return response;
+    }
+    let (mut response_parts, body) = response.into_parts();
+    let bytes = match buffer_response(body).await {
+        Ok(bytes) => bytes,
+        Err(response) => return response,
+    };
+    let Ok(mut completion) = serde_json::from_slice::<Value>(&bytes) else {
+        return Response::from_parts(response_parts, Body::from(bytes));
+    };
+    let Some(content) = completion.pointer("/choices/0/message/content").and_then(Value::as_str) else {
+        return finish(response_parts, completion, wants_stream);
+    };
+    let status = match crate::proxy::structured_output::repair_json(content) {
+        Some(value) => {
+            let repaired = serde_json::from_str::<Value>(content.trim()).ok().as_ref() != Some(&value);
+            completion["choices"][0]["message"]["content"] = Value::String(value.to_string());
+            if repaired { "repaired" } else { "valid" }
+        }
+        None => {
+            tracing::warn!("[StructuredOutput] {} returned JSON that could not be repaired", model_of(&json));
+            let body = json!({ "error": {
+                "message": "Model returned malformed JSON that could not be repaired",
+                "type": "invalid_response_error",
+                "code": "malformed_json",
+                "content": content,
+                "finish_reason": completion.pointer("/choices/0/finish_reason"),
+            }});
+            let mut response = (StatusCode::BAD_GATEWAY, axum::Json(body)).into_response();
+            response.headers_mut().insert(STATUS_HEADER, HeaderValue::from_static("invalid"));
+            return response;
+        }
+    };
+    response_parts.headers.insert(STATUS_HEADER, HeaderValue::from_static(status));
+    finish(response_parts, completion, wants_stream)
+}
+
+fn model_of(json: &Value) -> &str {
+    json.get("model").and_then(Value::as_str).unwrap_or_default()
+}
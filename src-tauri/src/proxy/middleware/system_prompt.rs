@@ -0,0 +1,58 @@
+// 系统提示词注入中间件
+// 按顺序应用所有命中的规则; 只有虚拟 Key 规则需要识别客户端 Key。
+
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::header,
+    middleware::Next,
+    response::Response,
+};
+use serde_json::Value;
+
+use crate::proxy::common::system_prompt::{inject, rule_matches};
+use crate::proxy::middleware::body_limit::is_multipart;
+use crate::proxy::server::AppState;
+use crate::proxy::virtual_keys::VirtualKeyStore;
+use crate::proxy::middleware::buffer::buffer_request;
+
+pub async fn system_prompt_middleware(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let config = state.system_prompt.read().await.clone();
+    if !config.enabled || request.method() != axum::http::Method::POST || is_multipart(request.headers()) {
+        return next.run(request).await;
+    }
+    let path = request.uri().path().to_string();
+    let virtual_key = crate::proxy::middleware::auth::extract_api_key(request.headers())
+        .filter(|k| !k.is_empty())
+        .and_then(|k| VirtualKeyStore::global().identify(k));
+    let key = virtual_key.as_ref().map(|k| (k.id.as_str(), k.name.as_str()));
+    let rules: Vec<_> = config.rules.iter().filter(|r| rule_matches(r, &path, key)).collect();
+    if rules.is_empty() {
+        return next.run(request).await;
+    }
+
+    let (mut parts, body) = request.into_parts();
+    let bytes = match buffer_request(body).await {
+        Ok(bytes) => bytes,
+        Err(response) => return response,
+    };
+    let Ok(mut json) = serde_json::from_slice::<Value>(&bytes) else {
+        return next.run(Request::from_parts(parts, Body::from(bytes))).await;
+    };
+    let mut applied = Vec::new();
+    for rule in rules {
+        if inject(&path, &mut json, &rule.prompt, rule.mode) {
+            applied.push(rule.name.as_str());
+        }
+    }
+    if applied.is_empty() {
+        return next.run(Request::from_parts(parts, Body::from(bytes))).await;
+    }
+    tracing::debug!("[SystemPrompt] {} applied rules {:?}", path, applied);
+    parts.headers.remove(header::CONTENT_LENGTH);
+    next.run(Request::from_parts(parts, Body::from(json.to_string()))).await
+}
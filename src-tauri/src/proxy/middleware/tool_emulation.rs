@@ -0,0 +1,73 @@
+// 函数调用模拟中间件
+// 与结构化输出相同, 位于响应缓存之内: 请求按非流式转发 (需要完整回复才能解析工具调用),
+// 客户端要求流式时合成等价的 SSE 流 (含 tool_calls 增量)。
+
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header, HeaderValue, Method},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde_json::Value;
+
+use crate::proxy::mappers::stream_bridge::json_to_sse;
+use crate::proxy::middleware::buffer::{buffer_request, buffer_response};
+
+/// 响应头: 从回复中还原的工具调用数
+pub const TOOL_CALLS_HEADER: &str = "x-tool-emulation";
+
+pub async fn tool_emulation_middleware(request: Request, next: Next) -> Response {
+    if request.method() != Method::POST || request.uri().path() != "/v1/chat/completions" {
+        return next.run(request).await;
+    }
+    let (mut parts, body) = request.into_parts();
+    let bytes = match buffer_request(body).await {
+        Ok(bytes) => bytes,
+        Err(response) => return response,
+    };
+    let emulated = |json: &Value| {
+        json.get("model").and_then(Value::as_str).is_some_and(crate::proxy::tool_emulation::applies_to)
+    };
+    let mut json = match serde_json::from_slice::<Value>(&bytes) {
+        Ok(json) if emulated(&json) => json,
+        _ => return next.run(Request::from_parts(parts, Body::from(bytes))).await,
+    };
+    let Some(names) = crate::proxy::tool_emulation::rewrite_request(&mut json) else {
+        return next.run(Request::from_parts(parts, Body::from(bytes))).await;
+    };
+
+    let wants_stream = json.get("stream").and_then(Value::as_bool).unwrap_or(false);
+    if let Some(obj) = json.as_object_mut() {
+        obj.remove("stream_options");
+        obj.insert("stream".to_string(), Value::Bool(false));
+    }
+    parts.headers.remove(header::CONTENT_LENGTH);
+    let response = next.run(Request::from_parts(parts, Body::from(json.to_string()))).await;
+    if !response.status().is_success() {
+        return response;
+    }
+    let (mut response_parts, body) = response.into_parts();
+    let bytes = match buffer_response(body).await {
+        Ok(bytes) => bytes,
+        Err(response) => return response,
+    };
+    let Ok(mut completion) = serde_json::from_slice::<Value>(&bytes) else {
+        return Response::from_parts(response_parts, Body::from(bytes));
+    };
+    let count = crate::proxy::tool_emulation::rewrite_response(&mut completion, &names);
+    if count > 0 {
+        tracing::debug!("[ToolEmulation] parsed {} tool call(s) from the reply", count);
+    }
+
+    response_parts.headers.remove(header::CONTENT_LENGTH);
+    response_parts.headers.insert(TOOL_CALLS_HEADER, HeaderValue::from(count));
+    if !wants_stream {
+        return Response::from_parts(response_parts, axum::Json(completion).into_response().into_body());
+    }
+    let events = json_to_sse(&completion).unwrap_or_default();
+    response_parts.headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("text/event-stream"));
+    response_parts.headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+    let body = Body::from_stream(futures::stream::iter(events.into_iter().map(Ok::<_, std::io::Error>)));
+    Response::from_parts(response_parts, body)
+}
@@ -24,6 +24,32 @@ pub mod audio;             // 音频处理模块
 pub mod signature_cache;   // Signature Cache (v3.3.16)
 pub mod cli_sync;          // CLI 配置同步 (v3.3.35)
 pub mod debug_logger;      // 调试日志
+pub mod body_logger;       // 请求/响应 Body 日志 (脱敏)
+pub mod response_cache;    // 相同请求的响应缓存
+pub mod conversation_store; // 服务端会话记忆
+pub mod model_catalog;     // /v1/models 聚合
+pub mod realtime;          // Realtime API WebSocket 会话
+pub mod metrics;           // Prometheus 指标
+pub mod latency;           // 延迟分位统计 (滑动窗口)
+pub mod virtual_keys;      // 虚拟 API Key (额度/模型/过期)
+pub mod jwt_auth;          // JWT / OIDC 鉴权
+pub mod webhooks;          // Webhook 事件通知
+pub mod smart_routing;     // 按延迟 / 错误率 / 费用选择上游
+pub mod shadow;            // 影子流量 (复制部分请求到次要上游)
+pub mod hedging;           // 请求对冲 (主请求慢时并发备用模型)
+pub mod compare;           // 多模型并排对比
+pub mod council;           // 聚合路由 (多模型回答 + 评审)
+pub mod prompt_templates;  // 提示词模板库
+pub mod structured_output; // json_schema 结构化输出兼容层
+pub mod tool_emulation;    // 函数调用模拟 (工具定义转提示词)
+pub mod mdns;              // 局域网 mDNS 服务发布
+pub mod supervisor;        // 服务崩溃自动重启
+pub mod replay;            // 请求重放与响应对比
+pub mod batches;           // Batch API (/v1/batches)
+pub mod files;             // Files API (/v1/files)
+pub mod assistants;        // Assistants API 兼容层
+pub mod moderation;        // 本地审核分类器
+pub mod llama_server;      // 本地 llama-server 托管
 
 
 pub use config::ProxyConfig;
@@ -0,0 +1,464 @@
+// 模型目录 (`/v1/models` 聚合)
+// 汇总内置与自定义映射模型、z.ai、本地 Ollama、Azure / Bedrock、OpenAI 兼容对话上游以及 Embeddings / 图像 / 音频上游的模型,
+// 应用精确匹配的别名规则后按模型 ID 去重, 并标注提供该模型的上游; 结果按 TTL 缓存, 配置热更新时失效。
+
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::proxy::common::model_mapping::wildcard_match;
+use crate::proxy::config::{AliasUpstream, ModelAliasMatch, ModelAliasRule, ModelCatalogConfig};
+use crate::proxy::server::AppState;
+use crate::proxy::upstream::health_check::{openai_models_target, zai_models_target, ProbeTarget};
+
+/// 兼容旧版本列表中的固定创建时间
+const MODEL_CREATED: i64 = 1706745600;
+
+/// 拉取上游模型列表的超时 (秒)
+const FETCH_TIMEOUT_SECS: u64 = 10;
+
+const DEFAULT_UPSTREAM: &str = "antigravity";
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct CatalogModel {
+    pub id: String,
+    pub object: &'static str,
+    pub created: i64,
+    pub owned_by: String,
+    /// 提供该模型的上游
+    pub upstream: String,
+    /// 别名规则的目标模型
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alias_of: Option<String>,
+}
+
+/// (模型 ID, 上游返回的 owned_by)
+type UpstreamModel = (String, Option<String>);
+
+/// 单个上游提供的模型
+struct Source {
+    upstream: String,
+    models: Vec<UpstreamModel>,
+}
+
+/// 待拉取的上游
+struct PlannedSource {
+    upstream: String,
+    /// 客户端可请求的模型规则, None 表示上游列表中的模型全部可用
+    patterns: Option<Vec<String>>,
+    target: Option<ProbeTarget>,
+    /// 上游列表不可用时使用的模型
+    fallback: Vec<String>,
+    /// 客户端请求时需要加在上游模型名前的前缀
+    id_prefix: String,
+}
+
+/// 不含通配符的规则本身即为模型 ID, 通配规则从上游模型列表中筛选
+fn pattern_models(patterns: &[String], remote: &[UpstreamModel]) -> Vec<UpstreamModel> {
+    let mut models: Vec<UpstreamModel> = patterns
+        .iter()
+        .filter(|p| !p.is_empty() && !p.contains('*'))
+        .map(|p| (p.clone(), None))
+        .collect();
+    let wildcards: Vec<&String> = patterns.iter().filter(|p| p.contains('*')).collect();
+    models.extend(
+        remote
+            .iter()
+            .filter(|(id, _)| wildcards.iter().any(|p| wildcard_match(p, id)))
+            .cloned(),
+    );
+    models
+}
+
+/// 合并各上游的模型: 相同 ID 以先出现的上游为准, 精确匹配的别名覆盖同名模型
+fn merge(sources: Vec<Source>, aliases: &[ModelAliasRule]) -> Vec<CatalogModel> {
+    let mut merged: BTreeMap<String, CatalogModel> = BTreeMap::new();
+    for source in sources {
+        for (id, owned_by) in source.models {
+            merged.entry(id.clone()).or_insert_with(|| CatalogModel {
+                id,
+                object: "model",
+                created: MODEL_CREATED,
+                owned_by: owned_by.unwrap_or_else(|| source.upstream.clone()),
+                upstream: source.upstream.clone(),
+                alias_of: None,
+            });
+        }
+    }
+
+    for rule in aliases.iter().filter(|r| r.enabled) {
+        let pattern = rule.pattern.trim();
+        let target = rule.target.trim();
+        // 通配与正则规则无法枚举出具体模型名
+        let is_literal = match rule.match_type {
+            ModelAliasMatch::Exact => true,
+            ModelAliasMatch::Glob => !pattern.contains('*'),
+            ModelAliasMatch::Regex => false,
+        };
+        if !is_literal || pattern.is_empty() || target.is_empty() || pattern == target {
+            continue;
+        }
+        let upstream = match rule.upstream {
+            Some(AliasUpstream::Zai) => "zai".to_string(),
+            Some(AliasUpstream::Google) => DEFAULT_UPSTREAM.to_string(),
+            None => merged
+                .get(target)
+                .map(|m| m.upstream.clone())
+                .unwrap_or_else(|| DEFAULT_UPSTREAM.to_string()),
+        };
+        merged.insert(
+            pattern.to_string(),
+            CatalogModel {
+                id: pattern.to_string(),
+                object: "model",
+                created: MODEL_CREATED,
+                owned_by: upstream.clone(),
+                upstream,
+                alias_of: Some(target.to_string()),
+            },
+        );
+    }
+
+    merged.into_values().collect()
+}
+
+async fn fetch_models(client: &reqwest::Client, target: &ProbeTarget) -> Result<Vec<UpstreamModel>, String> {
+    let mut request = client.get(&target.url);
+    if let Some((name, value)) = &target.auth {
+        request = request.header(*name, value);
+    }
+    let resp = request.send().await.map_err(|e| e.to_string())?;
+    if !resp.status().is_success() {
+        return Err(format!("HTTP {}", resp.status()));
+    }
+    let body: Value = resp.json().await.map_err(|e| e.to_string())?;
+    Ok(body
+        .get("data")
+        .and_then(|d| d.as_array())
+        .map(|models| {
+            models
+                .iter()
+                .filter_map(|m| {
+                    let id = m.get("id")?.as_str()?.to_string();
+                    let owned_by = m.get("owned_by").and_then(|v| v.as_str()).map(String::from);
+                    Some((id, owned_by))
+                })
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+async fn plan_sources(state: &AppState) -> Vec<PlannedSource> {
+    let mut planned = Vec::new();
+
+    let zai = state.zai.read().await.clone();
+    if let Some(target) = zai_models_target(&zai, &state.zai_keys) {
+        let mut fallback = vec![zai.models.opus.clone(), zai.models.sonnet.clone(), zai.models.haiku.clone()];
+        fallback.extend(zai.model_mapping.values().cloned());
+        let mut seen = std::collections::HashSet::new();
+        fallback.retain(|m| !m.is_empty() && seen.insert(m.clone()));
+        planned.push(PlannedSource {
+            upstream: target.name.clone(),
+            patterns: None,
+            target: Some(target),
+            fallback,
+            id_prefix: String::new(),
+        });
+    }
+
+    let embeddings = state.embeddings.read().await.clone();
+    if embeddings.enabled {
+        for provider in embeddings.providers.iter().filter(|p| p.enabled) {
+            let name = format!("embeddings:{}", provider.pattern);
+            planned.push(PlannedSource {
+                upstream: name.clone(),
+                patterns: Some(vec![provider.pattern.clone()]),
+                target: (!provider.base_url.is_empty())
+                    .then(|| openai_models_target(name, "embeddings", &provider.base_url, &provider.api_key)),
+                fallback: Vec::new(),
+                id_prefix: String::new(),
+            });
+        }
+    }
+
+    let images = state.images.read().await.clone();
+    for backend in images.backends.iter().filter(|b| b.enabled) {
+        let name = format!("images:{}", backend.pattern);
+        // Stability 没有 OpenAI 风格的模型列表
+        let listable = !backend.base_url.is_empty()
+            && backend.provider == crate::proxy::config::ImageBackendKind::Openai;
+        planned.push(PlannedSource {
+            upstream: name.clone(),
+            patterns: Some(vec![backend.pattern.clone()]),
+            target: listable.then(|| openai_models_target(name, "images", &backend.base_url, &backend.api_key)),
+            fallback: Vec::new(),
+            id_prefix: String::new(),
+        });
+    }
+
+    let audio = state.audio.read().await.clone();
+    if audio.enabled {
+        planned.push(PlannedSource {
+            upstream: "audio".to_string(),
+            patterns: Some(audio.transcription_models.clone()),
+            target: Some(openai_models_target("audio".to_string(), "openai", &audio.base_url, &audio.api_key)),
+            fallback: Vec::new(),
+            id_prefix: String::new(),
+        });
+    }
+
+    let azure = state.azure_openai.read().await.clone();
+    if azure.enabled {
+        planned.push(PlannedSource {
+            upstream: "azure".to_string(),
+            patterns: Some(azure.deployments.iter().map(|d| d.model.clone()).collect()),
+            target: None,
+            fallback: Vec::new(),
+            id_prefix: String::new(),
+        });
+    }
+
+    let bedrock = state.bedrock.read().await.clone();
+    if bedrock.enabled {
+        planned.push(PlannedSource {
+            upstream: "bedrock".to_string(),
+            patterns: Some(bedrock.models.iter().map(|m| m.model.clone()).collect()),
+            target: None,
+            fallback: Vec::new(),
+            id_prefix: String::new(),
+        });
+    }
+
+    for upstream in state.compatible_upstreams.read().await.upstreams.iter().filter(|u| u.enabled) {
+        planned.push(PlannedSource {
+            upstream: crate::proxy::providers::openai_compat::upstream_name(upstream),
+            patterns: Some(crate::proxy::providers::openai_compat::model_patterns(upstream)),
+            target: None,
+            fallback: Vec::new(),
+            id_prefix: String::new(),
+        });
+    }
+
+    let ollama = state.ollama.read().await.clone();
+    if ollama.enabled {
+        // 前缀为空时只有 `models` 规则匹配的模型可用
+        let patterns = ollama.model_prefix.is_empty().then(|| ollama.models.clone());
+        planned.push(PlannedSource {
+            upstream: "ollama".to_string(),
+            patterns,
+            target: Some(openai_models_target("ollama".to_string(), "ollama", &ollama.base_url, "")),
+            fallback: Vec::new(),
+            id_prefix: ollama.model_prefix.clone(),
+        });
+    }
+
+    planned
+}
+
+async fn collect_sources(state: &AppState, fetch_remote: bool) -> Vec<Source> {
+    let builtin = crate::proxy::common::model_mapping::get_all_dynamic_models(&state.custom_mapping).await;
+    let mut sources = vec![Source {
+        upstream: DEFAULT_UPSTREAM.to_string(),
+        models: builtin.into_iter().map(|id| (id, None)).collect(),
+    }];
+
+    let planned = plan_sources(state).await;
+    let client = if fetch_remote {
+        crate::proxy::handlers::common::build_upstream_client(state.upstream_proxy.read().await.clone(), FETCH_TIMEOUT_SECS)
+            .map_err(|e| tracing::warn!("[Model-Catalog] {}", e))
+            .ok()
+    } else {
+        None
+    };
+
+    let fetched = futures::future::join_all(planned.iter().map(|source| {
+        let client = client.clone();
+        async move {
+            // 规则全部为具体模型名时无需请求上游
+            let needs_remote = source
+                .patterns
+                .as_ref()
+                .map_or(true, |p| p.iter().any(|p| p.contains('*')));
+            match (client, &source.target) {
+                (Some(client), Some(target)) if needs_remote => match fetch_models(&client, target).await {
+                    Ok(models) => models,
+                    Err(e) => {
+                        tracing::warn!("[Model-Catalog] Failed to list models from {}: {}", source.upstream, e);
+                        Vec::new()
+                    }
+                },
+                _ => Vec::new(),
+            }
+        }
+    }))
+    .await;
+
+    for (source, mut remote) in planned.into_iter().zip(fetched) {
+        if !source.id_prefix.is_empty() {
+            for (id, _) in remote.iter_mut() {
+                id.insert_str(0, &source.id_prefix);
+            }
+        }
+        let models = match &source.patterns {
+            Some(patterns) => pattern_models(patterns, &remote),
+            None if remote.is_empty() => source.fallback.into_iter().map(|id| (id, None)).collect(),
+            None => remote,
+        };
+        sources.push(Source {
+            upstream: source.upstream,
+            models,
+        });
+    }
+    sources
+}
+
+struct CachedList {
+    built_at: Instant,
+    generation: u64,
+    models: Arc<Vec<CatalogModel>>,
+}
+
+pub struct ModelCatalog {
+    config: std::sync::RwLock<ModelCatalogConfig>,
+    cache: std::sync::Mutex<Option<CachedList>>,
+    /// 配置变更计数, 避免重建期间的配置更新被旧结果覆盖
+    generation: AtomicU64,
+    /// 串行化重建, 缓存过期时只向上游请求一次
+    refresh: tokio::sync::Mutex<()>,
+}
+
+impl ModelCatalog {
+    pub fn new(config: ModelCatalogConfig) -> Self {
+        Self {
+            config: std::sync::RwLock::new(config),
+            cache: std::sync::Mutex::new(None),
+            generation: AtomicU64::new(0),
+            refresh: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    pub fn update_config(&self, config: ModelCatalogConfig) {
+        *self.config.write().unwrap_or_else(|e| e.into_inner()) = config;
+        self.invalidate();
+    }
+
+    /// 丢弃缓存, 下次请求时重新聚合
+    pub fn invalidate(&self) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        *self.cache.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+
+    fn cached(&self, config: &ModelCatalogConfig) -> Option<Arc<Vec<CatalogModel>>> {
+        let cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+        cache
+            .as_ref()
+            .filter(|c| c.generation == self.generation.load(Ordering::SeqCst))
+            .filter(|c| c.built_at.elapsed().as_secs() < config.cache_ttl_secs)
+            .map(|c| c.models.clone())
+    }
+
+    /// 当前可用的全部模型 (按 ID 排序)
+    pub async fn list(&self, state: &AppState) -> Arc<Vec<CatalogModel>> {
+        let config = self.config.read().unwrap_or_else(|e| e.into_inner()).clone();
+        if let Some(models) = self.cached(&config) {
+            return models;
+        }
+
+        let _guard = self.refresh.lock().await;
+        if let Some(models) = self.cached(&config) {
+            return models;
+        }
+        let generation = self.generation.load(Ordering::SeqCst);
+        let sources = collect_sources(state, config.include_upstream_models).await;
+        let models = Arc::new(merge(sources, &state.model_aliases.rules()));
+
+        if config.cache_ttl_secs > 0 && generation == self.generation.load(Ordering::SeqCst) {
+            *self.cache.lock().unwrap_or_else(|e| e.into_inner()) = Some(CachedList {
+                built_at: Instant::now(),
+                generation,
+                models: models.clone(),
+            });
+        }
+        models
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source(upstream: &str, ids: &[&str]) -> Source {
+        Source {
+            upstream: upstream.to_string(),
+            models: ids.iter().map(|id| (id.to_string(), None)).collect(),
+        }
+    }
+
+    fn alias(pattern: &str, target: &str, match_type: ModelAliasMatch, upstream: Option<AliasUpstream>) -> ModelAliasRule {
+        ModelAliasRule {
+            pattern: pattern.to_string(),
+            target: target.to_string(),
+            match_type,
+            upstream,
+            enabled: true,
+            fallbacks: Vec::new(),
+            fallback_on: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_merge_dedupes_and_applies_aliases() {
+        let sources = vec![
+            source("antigravity", &["gemini-2.5-flash", "claude-sonnet-4-5"]),
+            source("zai", &["glm-4.7", "gemini-2.5-flash"]),
+        ];
+        let aliases = vec![
+            alias("gpt-4o", "glm-4.7", ModelAliasMatch::Exact, None),
+            alias("fast", "gemini-2.5-flash", ModelAliasMatch::Glob, None),
+            alias("gpt-*", "gemini-2.5-flash", ModelAliasMatch::Glob, None),
+            alias("claude-sonnet-4-5", "glm-4.7", ModelAliasMatch::Exact, Some(AliasUpstream::Zai)),
+        ];
+        let models = merge(sources, &aliases);
+        let find = |id: &str| models.iter().find(|m| m.id == id).cloned();
+
+        assert_eq!(models.iter().filter(|m| m.id == "gemini-2.5-flash").count(), 1);
+        assert_eq!(find("gemini-2.5-flash").unwrap().upstream, "antigravity");
+        assert_eq!(find("glm-4.7").unwrap().upstream, "zai");
+
+        let gpt = find("gpt-4o").unwrap();
+        assert_eq!(gpt.upstream, "zai");
+        assert_eq!(gpt.alias_of.as_deref(), Some("glm-4.7"));
+        assert_eq!(find("fast").unwrap().upstream, "antigravity");
+        assert!(find("gpt-*").is_none());
+
+        // 别名覆盖同名模型
+        let sonnet = find("claude-sonnet-4-5").unwrap();
+        assert_eq!(sonnet.upstream, "zai");
+        assert_eq!(sonnet.alias_of.as_deref(), Some("glm-4.7"));
+
+        let ids: Vec<&str> = models.iter().map(|m| m.id.as_str()).collect();
+        let mut sorted = ids.clone();
+        sorted.sort();
+        assert_eq!(ids, sorted);
+    }
+
+    #[test]
+    fn test_pattern_models_filters_remote_list() {
+        let remote = vec![
+            ("text-embedding-3-small".to_string(), Some("openai".to_string())),
+            ("gpt-4o".to_string(), None),
+        ];
+        let patterns = vec!["text-embedding-*".to_string(), "bge-m3".to_string()];
+        let models = pattern_models(&patterns, &remote);
+        assert_eq!(
+            models,
+            vec![
+                ("bge-m3".to_string(), None),
+                ("text-embedding-3-small".to_string(), Some("openai".to_string())),
+            ]
+        );
+    }
+}
@@ -0,0 +1,225 @@
+// 本地审核分类器
+// 没有可用的审核上游时, `/v1/moderations` 按关键字 / 正则规则给出 OpenAI 审核格式的结果。
+// 同一类别命中多条规则时分数按 1 - Π(1 - score) 累加; 子类别 (例如 `violence/graphic`) 的分数同时计入父类别。
+
+use std::sync::{OnceLock, RwLock};
+
+use regex::Regex;
+use serde_json::{json, Map, Value};
+
+use crate::proxy::config::{ContentFilterMatch, ModerationConfig, ModerationRule};
+
+/// OpenAI 审核类别
+pub const CATEGORIES: &[&str] = &[
+    "harassment",
+    "harassment/threatening",
+    "hate",
+    "hate/threatening",
+    "illicit",
+    "illicit/violent",
+    "self-harm",
+    "self-harm/intent",
+    "self-harm/instructions",
+    "sexual",
+    "sexual/minors",
+    "violence",
+    "violence/graphic",
+];
+
+pub const DEFAULT_MODEL: &str = "omni-moderation-latest";
+
+/// 内置规则 (类别, 正则, 分数): 只覆盖明显的表述, 误报与漏报都在所难免
+const BUILTIN_RULES: &[(&str, &str, f64)] = &[
+    ("harassment/threatening", r"(?i)\b(i('| a)?m going to|i will|i'll|gonna)\s+(kill|hurt|beat|find)\s+you\b", 0.9),
+    ("harassment", r"(?i)\byou('re| are)\s+(an?\s+)?(worthless|pathetic|idiot|moron|loser)\b", 0.7),
+    ("self-harm/intent", r"(?i)\b(i\s+want\s+to|i('m| am)\s+going\s+to)\s+(kill\s+myself|end\s+my\s+life|die)\b", 0.9),
+    ("self-harm", r"(?i)\b(suicide|self[- ]harm|cut(ting)?\s+myself)\b", 0.6),
+    ("self-harm/instructions", r"(?i)\bhow\s+(to|do\s+i)\s+(kill\s+myself|commit\s+suicide)\b", 0.9),
+    ("violence", r"(?i)\b(murder|kill(ing)?|stab(bing)?|shoot(ing)?)\s+(him|her|them|people|everyone)\b", 0.7),
+    ("violence/graphic", r"(?i)\b(dismember(ed|ing)?|disembowel(ed|ing)?|gore)\b", 0.7),
+    ("illicit/violent", r"(?i)\bhow\s+(to|do\s+i)\s+(make|build)\s+(a\s+)?(bomb|pipe\s*bomb|explosive)s?\b", 0.9),
+    ("illicit", r"(?i)\bhow\s+(to|do\s+i)\s+(make|cook|synthesi[sz]e)\s+(meth|cocaine|heroin)\b", 0.8),
+];
+
+#[derive(Debug, Clone)]
+struct CompiledRule {
+    category: String,
+    regex: Regex,
+    score: f64,
+}
+
+fn compile(rule: &ModerationRule) -> Result<CompiledRule, String> {
+    if !CATEGORIES.contains(&rule.category.as_str()) {
+        return Err(format!("Unknown moderation category '{}'", rule.category));
+    }
+    if rule.pattern.is_empty() {
+        return Err("Moderation rule has an empty pattern".to_string());
+    }
+    if !(0.0..=1.0).contains(&rule.score) {
+        return Err(format!("Moderation rule score must be between 0 and 1, got {}", rule.score));
+    }
+    let source = match rule.match_type {
+        ContentFilterMatch::Keyword => format!("(?i){}", regex::escape(&rule.pattern)),
+        ContentFilterMatch::Regex => rule.pattern.clone(),
+    };
+    let regex = Regex::new(&source).map_err(|e| format!("Invalid moderation regex '{}': {}", rule.pattern, e))?;
+    Ok(CompiledRule { category: rule.category.clone(), regex, score: rule.score })
+}
+
+/// 校验规则 (类别已知、正则可编译、分数在 0-1 之间)
+pub fn validate_rules(rules: &[ModerationRule]) -> Result<(), String> {
+    rules.iter().filter(|r| r.enabled).try_for_each(|r| compile(r).map(|_| ()))
+}
+
+pub struct Moderator {
+    state: RwLock<(ModerationConfig, Vec<CompiledRule>)>,
+}
+
+impl Moderator {
+    fn new(config: &ModerationConfig) -> Self {
+        let moderator = Self { state: RwLock::new((config.clone(), Vec::new())) };
+        moderator.update(config);
+        moderator
+    }
+
+    pub fn global() -> &'static Moderator {
+        static INSTANCE: OnceLock<Moderator> = OnceLock::new();
+        INSTANCE.get_or_init(|| Moderator::new(&ModerationConfig::default()))
+    }
+
+    pub fn config(&self) -> ModerationConfig {
+        self.state.read().unwrap_or_else(|e| e.into_inner()).0.clone()
+    }
+
+    /// 无效规则会被跳过并记录警告 (保存时已经通过 `validate_rules` 校验)
+    fn update(&self, config: &ModerationConfig) {
+        let builtin: &[(&str, &str, f64)] = if config.builtin_rules { BUILTIN_RULES } else { &[] };
+        let builtin = builtin.iter().map(|(category, pattern, score)| {
+            let regex = Regex::new(pattern).map_err(|e| e.to_string())?;
+            Ok(CompiledRule { category: category.to_string(), regex, score: *score })
+        });
+        let custom = config.rules.iter().filter(|r| r.enabled).map(compile);
+        let compiled = builtin
+            .chain(custom)
+            .filter_map(|r: Result<CompiledRule, String>| {
+                r.map_err(|e| tracing::warn!("[Moderation] Skipping rule: {}", e)).ok()
+            })
+            .collect();
+        *self.state.write().unwrap_or_else(|e| e.into_inner()) = (config.clone(), compiled);
+    }
+
+    /// 单条输入的审核结果
+    pub fn classify(&self, text: &str) -> Value {
+        let state = self.state.read().unwrap_or_else(|e| e.into_inner());
+        let (config, rules) = &*state;
+        // 各类别未命中的概率
+        let mut clean: Vec<f64> = vec![1.0; CATEGORIES.len()];
+        for rule in rules.iter().filter(|r| r.regex.is_match(text)) {
+            let parent = rule.category.split('/').next().unwrap_or_default();
+            for (i, category) in CATEGORIES.iter().enumerate() {
+                if *category == rule.category || (*category == parent && parent != rule.category) {
+                    clean[i] *= 1.0 - rule.score;
+                }
+            }
+        }
+        let mut categories = Map::new();
+        let mut scores = Map::new();
+        let mut applied = Map::new();
+        for (category, clean) in CATEGORIES.iter().zip(clean) {
+            let score = ((1.0 - clean) * 1e6).round() / 1e6;
+            categories.insert(category.to_string(), json!(score >= config.threshold));
+            scores.insert(category.to_string(), json!(score));
+            applied.insert(category.to_string(), json!(["text"]));
+        }
+        let flagged = categories.values().any(|v| v == &json!(true));
+        json!({
+            "flagged": flagged,
+            "categories": categories,
+            "category_scores": scores,
+            "category_applied_input_types": applied,
+        })
+    }
+
+    /// 处理 `/v1/moderations` 请求体: 字符串数组逐条审核, 多模态内容数组中的文本合并为一条 (图片不审核)
+    pub fn moderate(&self, body: &Value) -> Result<Value, String> {
+        let inputs: Vec<String> = match body.get("input") {
+            Some(Value::String(text)) => vec![text.clone()],
+            Some(Value::Array(items)) if items.iter().all(Value::is_string) => {
+                items.iter().filter_map(Value::as_str).map(String::from).collect()
+            }
+            Some(Value::Array(items)) => {
+                let text: Vec<&str> = items
+                    .iter()
+                    .filter(|i| i["type"] == "text")
+                    .filter_map(|i| i.get("text").and_then(Value::as_str))
+                    .collect();
+                vec![text.join("\n")]
+            }
+            _ => return Err("'input' must be a string, an array of strings or an array of content parts".to_string()),
+        };
+        let model = body.get("model").and_then(Value::as_str).unwrap_or(DEFAULT_MODEL);
+        Ok(json!({
+            "id": format!("modr-{}", uuid::Uuid::new_v4().simple()),
+            "model": model,
+            "results": inputs.iter().map(|text| self.classify(text)).collect::<Vec<_>>(),
+        }))
+    }
+}
+
+pub fn configure(config: &ModerationConfig) {
+    Moderator::global().update(config);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_and_custom_rules() {
+        let config = ModerationConfig {
+            enabled: true,
+            rules: vec![ModerationRule {
+                category: "hate".to_string(),
+                pattern: "Forbidden Phrase".to_string(),
+                match_type: ContentFilterMatch::Keyword,
+                score: 0.4,
+                enabled: true,
+            }],
+            ..ModerationConfig::default()
+        };
+        let moderator = Moderator::new(&config);
+        let response = moderator.moderate(&json!({"input": ["I'm going to kill you", "a forbidden phrase", "hello"]})).unwrap();
+        let results = response["results"].as_array().unwrap();
+        assert_eq!(response["model"], DEFAULT_MODEL);
+        assert_eq!(results[0]["flagged"], true);
+        assert_eq!(results[0]["categories"]["harassment/threatening"], true);
+        // 子类别计入父类别
+        assert_eq!(results[0]["category_scores"]["harassment"], 0.9);
+        assert_eq!((results[1]["flagged"].clone(), results[1]["category_scores"]["hate"].clone()), (json!(false), json!(0.4)));
+        assert_eq!(results[2]["flagged"], false);
+        assert_eq!(results[2]["categories"].as_object().unwrap().len(), CATEGORIES.len());
+
+        let parts = json!({"input": [{"type": "text", "text": "how to make a pipe bomb"}, {"type": "image_url", "image_url": {"url": "x"}}]});
+        let response = moderator.moderate(&parts).unwrap();
+        assert_eq!(response["results"].as_array().unwrap().len(), 1);
+        assert_eq!(response["results"][0]["categories"]["illicit/violent"], true);
+        assert!(moderator.moderate(&json!({})).is_err());
+    }
+
+    #[test]
+    fn test_validate_rules() {
+        let rule = |category: &str, pattern: &str| ModerationRule {
+            category: category.to_string(),
+            pattern: pattern.to_string(),
+            match_type: ContentFilterMatch::Regex,
+            score: 1.0,
+            enabled: true,
+        };
+        assert!(validate_rules(&[rule("violence", r"\bstab\b")]).is_ok());
+        assert!(validate_rules(&[rule("spam", "x")]).is_err());
+        assert!(validate_rules(&[rule("violence", "(")]).is_err());
+        for (_, pattern, _) in BUILTIN_RULES {
+            assert!(Regex::new(pattern).is_ok(), "{}", pattern);
+        }
+    }
+}
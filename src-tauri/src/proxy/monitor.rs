@@ -2,7 +2,20 @@ use serde::{Serialize, Deserialize};
 use std::collections::VecDeque;
 use tokio::sync::RwLock;
 use tauri::Emitter;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use dashmap::DashMap;
+
+/// 保留映射后上游请求体的最近请求数 (仅内存, 供实时检查器查看)
+const MAX_UPSTREAM_CAPTURES: usize = 50;
+/// 单个上游请求体的保留上限, 超出部分截断
+const MAX_CAPTURE_BODY_SIZE: usize = 512 * 1024;
+/// 流式响应中途客户端断开时记录的状态码 (沿用 nginx 的 499 Client Closed Request)
+pub const CLIENT_CLOSED_STATUS: u16 = 499;
+
+tokio::task_local! {
+    static REQUEST_NOTES: Arc<Mutex<RequestNotes>>;
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProxyRequestLog {
@@ -21,6 +34,107 @@ pub struct ProxyRequestLog {
     pub input_tokens: Option<u32>,
     pub output_tokens: Option<u32>,
     pub protocol: Option<String>,     // 协议类型: "openai", "anthropic", "gemini"
+    #[serde(default)]
+    pub param_adjustments: Option<String>, // 参数策略删除 / 截断的参数, 以 "; " 分隔
+    #[serde(default)]
+    pub client_name: Option<String>,  // X-Client-Name 或从 User-Agent 推断的工具名
+    #[serde(default)]
+    pub user_agent: Option<String>,
+    #[serde(default)]
+    pub client_port: Option<u16>,     // 客户端源端口, 用于区分同一主机上的多个进程
+    #[serde(default)]
+    pub tokens_per_second: Option<f64>, // 流式响应的输出速率
+    #[serde(default)]
+    pub cache_read_tokens: Option<u32>, // 命中提示词缓存的输入 token (已包含在 input_tokens 中)
+    #[serde(default)]
+    pub cache_write_tokens: Option<u32>, // 写入提示词缓存的输入 token (已包含在 input_tokens 中)
+}
+
+/// 正在处理中的请求 (实时检查器)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InflightRequest {
+    pub id: String,
+    pub timestamp: i64,
+    pub method: String,
+    pub url: String,
+    pub model: Option<String>,
+    pub protocol: Option<String>,
+}
+
+/// 进行中请求的登记; 请求被取消 (处理中的 Future 被丢弃) 而没有写入日志时, 释放时移出列表并通知前端
+pub struct InflightGuard {
+    monitor: Arc<ProxyMonitor>,
+    id: String,
+}
+
+impl Drop for InflightGuard {
+    fn drop(&mut self) {
+        self.monitor.active.fetch_sub(1, Ordering::Relaxed);
+        if self.monitor.inflight.remove(&self.id).is_some() {
+            if let Some(app) = &self.monitor.app_handle {
+                let _ = app.emit("proxy://request-cancelled", &self.id);
+            }
+        }
+    }
+}
+
+/// 协议转换后实际发往上游的请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpstreamCapture {
+    /// v1internal 方法, 例如 `streamGenerateContent`
+    pub method: String,
+    pub body: String,
+}
+
+/// 单个请求的完整检查信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequestInspection {
+    pub log: ProxyRequestLog,
+    pub upstream_request: Option<UpstreamCapture>,
+}
+
+/// 请求处理过程中收集的附加信息
+#[derive(Debug, Default)]
+pub struct RequestNotes {
+    pub upstream_request: Option<UpstreamCapture>,
+    pub param_adjustments: Vec<String>,
+}
+
+/// 记录当前请求映射后的上游请求体 (由 UpstreamClient 调用, 仅在检查器开启时生效)
+pub fn capture_upstream_request(method: &str, body: &serde_json::Value) {
+    let _ = REQUEST_NOTES.try_with(|slot| {
+        let mut body = serde_json::to_string_pretty(body).unwrap_or_default();
+        if body.len() > MAX_CAPTURE_BODY_SIZE {
+            let mut end = MAX_CAPTURE_BODY_SIZE;
+            while !body.is_char_boundary(end) {
+                end -= 1;
+            }
+            body.truncate(end);
+            body.push_str("\n... [truncated]");
+        }
+        slot.lock().unwrap_or_else(|e| e.into_inner()).upstream_request = Some(UpstreamCapture {
+            method: method.to_string(),
+            body,
+        });
+    });
+}
+
+/// 记录参数策略对当前请求做出的调整 (写入请求日志)
+pub fn note_param_adjustments(adjustments: &[String]) {
+    let _ = REQUEST_NOTES.try_with(|slot| {
+        slot.lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .param_adjustments
+            .extend(adjustments.iter().cloned());
+    });
+}
+
+/// 在捕获作用域内执行请求, 返回结果和收集到的附加信息 (最后一次发往上游的请求、参数调整)
+pub async fn with_request_notes<F: std::future::Future>(fut: F) -> (F::Output, RequestNotes) {
+    let slot = Arc::new(Mutex::new(RequestNotes::default()));
+    let output = REQUEST_NOTES.scope(slot.clone(), fut).await;
+    let notes = std::mem::take(&mut *slot.lock().unwrap_or_else(|e| e.into_inner()));
+    (output, notes)
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -28,6 +142,11 @@ pub struct ProxyStats {
     pub total_requests: u64,
     pub success_count: u64,
     pub error_count: u64,
+    /// 响应缓存命中/未命中次数 (进程内统计)
+    #[serde(default)]
+    pub cache_hits: u64,
+    #[serde(default)]
+    pub cache_misses: u64,
 }
 
 pub struct ProxyMonitor {
@@ -35,6 +154,14 @@ pub struct ProxyMonitor {
     pub stats: RwLock<ProxyStats>,
     pub max_logs: usize,
     pub enabled: AtomicBool,
+    cache_hits: AtomicU64,
+    cache_misses: AtomicU64,
+    pub metrics: crate::proxy::metrics::ProxyMetrics, // Prometheus 指标
+    pub latency: crate::proxy::latency::LatencyTracker, // 按模型 / 上游的延迟分位
+    inflight: DashMap<String, InflightRequest>,
+    /// 进行中的请求数 (不受日志开关影响)
+    active: AtomicUsize,
+    upstream_captures: Mutex<VecDeque<(String, UpstreamCapture)>>,
     app_handle: Option<tauri::AppHandle>,
 }
 
@@ -64,6 +191,13 @@ impl ProxyMonitor {
             stats: RwLock::new(ProxyStats::default()),
             max_logs,
             enabled: AtomicBool::new(false), // Default to disabled
+            cache_hits: AtomicU64::new(0),
+            cache_misses: AtomicU64::new(0),
+            metrics: crate::proxy::metrics::ProxyMetrics::new(),
+            latency: crate::proxy::latency::LatencyTracker::new(),
+            inflight: DashMap::new(),
+            active: AtomicUsize::new(0),
+            upstream_captures: Mutex::new(VecDeque::with_capacity(MAX_UPSTREAM_CAPTURES)),
             app_handle,
         }
     }
@@ -76,7 +210,59 @@ impl ProxyMonitor {
         self.enabled.load(Ordering::Relaxed)
     }
 
+    /// 记录一次响应缓存查询结果
+    pub fn record_cache_lookup(&self, hit: bool) {
+        let counter = if hit { &self.cache_hits } else { &self.cache_misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn with_cache_stats(&self, mut stats: ProxyStats) -> ProxyStats {
+        stats.cache_hits = self.cache_hits.load(Ordering::Relaxed);
+        stats.cache_misses = self.cache_misses.load(Ordering::Relaxed);
+        stats
+    }
+
+    /// 请求开始处理: 加入进行中列表并通知前端; 返回的登记需保留到请求结束
+    pub fn request_started(self: &Arc<Self>, request: InflightRequest) -> InflightGuard {
+        self.active.fetch_add(1, Ordering::Relaxed);
+        let guard = InflightGuard { monitor: self.clone(), id: request.id.clone() };
+        if !self.is_enabled() {
+            return guard;
+        }
+        if let Some(app) = &self.app_handle {
+            let _ = app.emit("proxy://request-started", &request);
+        }
+        self.inflight.insert(request.id.clone(), request);
+        guard
+    }
+
+    /// 进行中的请求数
+    pub fn active_requests(&self) -> usize {
+        self.active.load(Ordering::Relaxed)
+    }
+
+    /// 进行中的请求 (按开始时间排序)
+    pub fn inflight_requests(&self) -> Vec<InflightRequest> {
+        let mut requests: Vec<InflightRequest> = self.inflight.iter().map(|r| r.value().clone()).collect();
+        requests.sort_by_key(|r| r.timestamp);
+        requests
+    }
+
+    pub fn store_upstream_capture(&self, id: &str, capture: UpstreamCapture) {
+        let mut captures = self.upstream_captures.lock().unwrap_or_else(|e| e.into_inner());
+        if captures.len() >= MAX_UPSTREAM_CAPTURES {
+            captures.pop_back();
+        }
+        captures.push_front((id.to_string(), capture));
+    }
+
+    pub fn upstream_capture(&self, id: &str) -> Option<UpstreamCapture> {
+        let captures = self.upstream_captures.lock().unwrap_or_else(|e| e.into_inner());
+        captures.iter().find(|(cid, _)| cid == id).map(|(_, c)| c.clone())
+    }
+
     pub async fn log_request(&self, log: ProxyRequestLog) {
+        self.inflight.remove(&log.id);
         if let (Some(account), Some(input), Some(output)) = (
             &log.account_email,
             log.input_tokens,
@@ -153,6 +339,13 @@ impl ProxyMonitor {
                 input_tokens: log.input_tokens,
                 output_tokens: log.output_tokens,
                 protocol: log.protocol.clone(),
+                param_adjustments: log.param_adjustments.clone(),
+                client_name: log.client_name.clone(),
+                user_agent: log.user_agent.clone(),
+                client_port: log.client_port,
+                tokens_per_second: log.tokens_per_second,
+                cache_read_tokens: log.cache_read_tokens,
+                cache_write_tokens: log.cache_write_tokens,
             };
             let _ = app.emit("proxy://request", &log_summary);
         }
@@ -185,7 +378,7 @@ impl ProxyMonitor {
             crate::modules::proxy_db::get_stats()
         }).await;
 
-        match db_result {
+        let stats = match db_result {
             Ok(Ok(stats)) => stats,
             Ok(Err(e)) => {
                 tracing::error!("Failed to get stats from DB: {}", e);
@@ -195,7 +388,8 @@ impl ProxyMonitor {
                 tracing::error!("Spawn blocking failed for get_stats: {}", e);
                 self.stats.read().await.clone()
             }
-        }
+        };
+        self.with_cache_stats(stats)
     }
     
     pub async fn get_logs_filtered(
@@ -222,8 +416,11 @@ impl ProxyMonitor {
     pub async fn clear(&self) {
         let mut logs = self.logs.write().await;
         logs.clear();
+        self.upstream_captures.lock().unwrap_or_else(|e| e.into_inner()).clear();
         let mut stats = self.stats.write().await;
         *stats = ProxyStats::default();
+        self.cache_hits.store(0, Ordering::Relaxed);
+        self.cache_misses.store(0, Ordering::Relaxed);
 
         let _ = tokio::task::spawn_blocking(|| {
             if let Err(e) = crate::modules::proxy_db::clear_logs() {
@@ -231,4 +428,66 @@ impl ProxyMonitor {
             }
         }).await;
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_upstream_capture_scope() {
+        // 作用域外调用不会 panic, 也不会记录
+        capture_upstream_request("generateContent", &json!({"a": 1}));
+
+        let (value, notes) = with_request_notes(async {
+            capture_upstream_request("generateContent", &json!({"first": true}));
+            note_param_adjustments(&["dropped logit_bias".to_string()]);
+            capture_upstream_request("streamGenerateContent", &json!({"text": "x".repeat(MAX_CAPTURE_BODY_SIZE)}));
+            42
+        })
+        .await;
+        assert_eq!(value, 42);
+        assert_eq!(notes.param_adjustments, vec!["dropped logit_bias"]);
+        let capture = notes.upstream_request.unwrap();
+        assert_eq!(capture.method, "streamGenerateContent");
+        assert!(capture.body.ends_with("[truncated]"));
+        assert!(capture.body.len() <= MAX_CAPTURE_BODY_SIZE + 20);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_request_leaves_inflight() {
+        let monitor = Arc::new(ProxyMonitor::new(10, None));
+        monitor.set_enabled(true);
+        let request = |id: &str| InflightRequest {
+            id: id.to_string(),
+            timestamp: 0,
+            method: "POST".to_string(),
+            url: "/v1/chat/completions".to_string(),
+            model: None,
+            protocol: None,
+        };
+        let handler = {
+            let monitor = monitor.clone();
+            tokio::spawn(async move {
+                let _inflight = monitor.request_started(request("a"));
+                std::future::pending::<()>().await;
+            })
+        };
+        tokio::task::yield_now().await;
+        assert_eq!(monitor.inflight_requests().len(), 1);
+        assert_eq!(monitor.active_requests(), 1);
+        // 客户端断开: 处理任务被丢弃
+        handler.abort();
+        let _ = handler.await;
+        assert!(monitor.inflight_requests().is_empty());
+        assert_eq!(monitor.active_requests(), 0);
+
+        // 关闭日志时不记录请求详情, 但仍然计数
+        monitor.set_enabled(false);
+        let guard = monitor.request_started(request("b"));
+        assert!(monitor.inflight_requests().is_empty());
+        assert_eq!(monitor.active_requests(), 1);
+        drop(guard);
+        assert_eq!(monitor.active_requests(), 0);
+    }
+}
@@ -0,0 +1,339 @@
+// 提示词模板库
+// 模板保存在 `<data_dir>/prompt_templates.json`, 使用 Handlebars 风格的 `{{变量}}` 占位符。
+// 请求通过 `X-Prompt-Template` 头或 `tpl:<模板名>/<模型>` 形式的模型名引用模板, 由反代在服务端展开:
+// `system` 写入系统提示词, `prompt` 替换最后一条用户消息 (原文可用 `{{input}}` 引用)。
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{OnceLock, RwLock};
+
+use crate::proxy::config::SystemPromptMode;
+
+const STORE_FILE: &str = "prompt_templates.json";
+/// 引用模板的请求头
+pub const TEMPLATE_HEADER: &str = "x-prompt-template";
+/// 模板变量 (JSON 对象) 的请求头
+pub const VARIABLES_HEADER: &str = "x-prompt-variables";
+/// 请求体中的模板变量字段, 转发前移除
+pub const VARIABLES_FIELD: &str = "template_variables";
+/// 模型名前缀: `tpl:<模板名>/<模型>`
+const MODEL_PREFIX: &str = "tpl:";
+/// 最后一条用户消息的原文
+const INPUT_VARIABLE: &str = "input";
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct TemplateVariable {
+    pub name: String,
+    /// 请求未提供时使用; 为空且未提供时展开失败
+    #[serde(default)]
+    pub default: Option<String>,
+    #[serde(default)]
+    pub description: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct PromptTemplate {
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// 系统提示词模板
+    #[serde(default)]
+    pub system: Option<String>,
+    #[serde(default)]
+    pub system_mode: SystemPromptMode,
+    /// 用户消息模板
+    #[serde(default)]
+    pub prompt: Option<String>,
+    #[serde(default)]
+    pub variables: Vec<TemplateVariable>,
+    #[serde(default)]
+    pub updated_at: i64,
+}
+
+/// 替换 `{{name}}` 占位符; 变量依次取请求提供的值、模板默认值、内置变量 (`input`, `date`)
+pub fn render(text: &str, template: &PromptTemplate, values: &HashMap<String, String>) -> Result<String, String> {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        out.push_str(&rest[..start]);
+        let name = rest[start + 2..start + 2 + len].trim();
+        let value = values
+            .get(name)
+            .cloned()
+            .or_else(|| template.variables.iter().find(|v| v.name == name).and_then(|v| v.default.clone()))
+            .or_else(|| (name == "date").then(|| chrono::Local::now().format("%Y-%m-%d").to_string()))
+            .ok_or_else(|| format!("Missing value for template variable '{}' in template '{}'", name, template.name))?;
+        out.push_str(&value);
+        rest = &rest[start + 2 + len + 2..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// `tpl:<模板名>/<模型>` 拆分为 (模板名, 模型)
+pub fn split_model(model: &str) -> Option<(&str, &str)> {
+    let (name, model) = model.strip_prefix(MODEL_PREFIX)?.split_once('/')?;
+    (!name.is_empty() && !model.is_empty()).then_some((name, model))
+}
+
+/// 最后一条用户消息 (OpenAI / Anthropic `messages`, Gemini `contents`) 及其文本所在的字段
+fn last_user_message(body: &mut Value) -> Option<(&mut Map<String, Value>, &'static str)> {
+    let (list, key) = match body.get("messages").is_some() {
+        true => ("messages", "content"),
+        false => ("contents", "parts"),
+    };
+    let message = body
+        .get_mut(list)?
+        .as_array_mut()?
+        .iter_mut()
+        .rev()
+        .find(|m| m.get("role").and_then(Value::as_str).is_none_or(|r| r == "user"))?;
+    Some((message.as_object_mut()?, key))
+}
+
+/// 用渲染结果替换最后一条用户消息的文本 (保留图片等非文本部分), 没有用户消息时追加一条
+fn replace_user_text(body: &mut Value, render_with: impl FnOnce(&str) -> Result<String, String>) -> Result<(), String> {
+    let Some((message, key)) = last_user_message(body) else {
+        let text = render_with("")?;
+        match body.get_mut("contents").and_then(Value::as_array_mut) {
+            Some(contents) => contents.push(json!({ "role": "user", "parts": [{ "text": text }] })),
+            None => match body.get_mut("messages").and_then(Value::as_array_mut) {
+                Some(messages) => messages.push(json!({ "role": "user", "content": text })),
+                None => return Err("Request has no messages to apply the template to".to_string()),
+            },
+        }
+        return Ok(());
+    };
+    match message.get_mut(key) {
+        Some(Value::String(text)) => *text = render_with(text)?,
+        Some(Value::Array(parts)) => {
+            let is_text = |p: &Value| p.get("text").is_some_and(Value::is_string);
+            let input: Vec<&str> = parts.iter().filter_map(|p| p.get("text").and_then(Value::as_str)).collect();
+            let rendered = render_with(&input.join("\n"))?;
+            // 多个文本部分合并到第一个的位置
+            let mut seen_text = false;
+            let mut rebuilt = Vec::with_capacity(parts.len());
+            for mut part in parts.drain(..) {
+                if is_text(&part) {
+                    if seen_text {
+                        continue;
+                    }
+                    seen_text = true;
+                    part["text"] = Value::String(rendered.clone());
+                }
+                rebuilt.push(part);
+            }
+            if !seen_text {
+                let part = match key {
+                    "parts" => json!({ "text": rendered }),
+                    _ => json!({ "type": "text", "text": rendered }),
+                };
+                rebuilt.insert(0, part);
+            }
+            *parts = rebuilt;
+        }
+        _ => {
+            message.insert(key.to_string(), Value::String(render_with("")?));
+        }
+    }
+    Ok(())
+}
+
+/// 展开模板: 写入系统提示词并替换最后一条用户消息
+pub fn apply(path: &str, body: &mut Value, template: &PromptTemplate, values: &HashMap<String, String>) -> Result<(), String> {
+    if let Some(prompt) = template.prompt.as_deref().filter(|p| !p.is_empty()) {
+        replace_user_text(body, |input| {
+            let mut values = values.clone();
+            values.entry(INPUT_VARIABLE.to_string()).or_insert_with(|| input.to_string());
+            render(prompt, template, &values)
+        })?;
+    }
+    if let Some(system) = template.system.as_deref().filter(|s| !s.trim().is_empty()) {
+        let system = render(system, template, values)?;
+        crate::proxy::common::system_prompt::inject(path, body, &system, template.system_mode);
+    }
+    Ok(())
+}
+
+/// 请求中的变量值: 请求头的 JSON 对象, 请求体字段优先
+pub fn variables(header: Option<&str>, body: &mut Value) -> Result<HashMap<String, String>, String> {
+    let mut values = HashMap::new();
+    let as_text = |v: Value| match v {
+        Value::String(s) => s,
+        other => other.to_string(),
+    };
+    if let Some(header) = header.filter(|h| !h.trim().is_empty()) {
+        let parsed: Map<String, Value> =
+            serde_json::from_str(header).map_err(|e| format!("{} must be a JSON object: {}", VARIABLES_HEADER, e))?;
+        values.extend(parsed.into_iter().map(|(k, v)| (k, as_text(v))));
+    }
+    match body.as_object_mut().and_then(|o| o.remove(VARIABLES_FIELD)) {
+        Some(Value::Object(map)) => values.extend(map.into_iter().map(|(k, v)| (k, as_text(v)))),
+        Some(Value::Null) | None => {}
+        Some(_) => return Err(format!("{} must be an object", VARIABLES_FIELD)),
+    }
+    Ok(values)
+}
+
+fn read_templates(path: &Path) -> Option<Vec<PromptTemplate>> {
+    let content = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+pub struct PromptTemplateStore {
+    path: Option<PathBuf>,
+    templates: RwLock<Vec<PromptTemplate>>,
+}
+
+impl PromptTemplateStore {
+    fn new(path: Option<PathBuf>) -> Self {
+        let templates = path.as_deref().and_then(read_templates).unwrap_or_default();
+        Self { path, templates: RwLock::new(templates) }
+    }
+
+    /// Global singleton instance, persisted to `<data_dir>/prompt_templates.json`
+    pub fn global() -> &'static PromptTemplateStore {
+        static INSTANCE: OnceLock<PromptTemplateStore> = OnceLock::new();
+        INSTANCE.get_or_init(|| {
+            let path = crate::modules::account::get_data_dir().ok().map(|d| d.join(STORE_FILE));
+            PromptTemplateStore::new(path)
+        })
+    }
+
+    fn persist(&self, templates: &[PromptTemplate]) -> Result<(), String> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let content = serde_json::to_string_pretty(templates).map_err(|e| e.to_string())?;
+        std::fs::write(path, content).map_err(|e| format!("failed_to_save_prompt_templates: {}", e))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.templates.read().unwrap_or_else(|e| e.into_inner()).is_empty()
+    }
+
+    pub fn list(&self) -> Vec<PromptTemplate> {
+        self.templates.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    pub fn get(&self, name: &str) -> Option<PromptTemplate> {
+        self.templates.read().unwrap_or_else(|e| e.into_inner()).iter().find(|t| t.name == name).cloned()
+    }
+
+    /// 新建 (id 为空) 或更新模板; 名称不能与其他模板重复
+    pub fn save(&self, mut template: PromptTemplate) -> Result<PromptTemplate, String> {
+        template.name = template.name.trim().to_string();
+        if template.name.is_empty() || template.name.contains('/') {
+            return Err("Template name is required and cannot contain '/'".to_string());
+        }
+        if template.system.as_deref().is_none_or(|s| s.trim().is_empty())
+            && template.prompt.as_deref().is_none_or(|p| p.trim().is_empty())
+        {
+            return Err("Template needs a system prompt or a user prompt".to_string());
+        }
+        template.variables.retain(|v| !v.name.trim().is_empty());
+        template.updated_at = chrono::Utc::now().timestamp();
+
+        let mut templates = self.templates.write().unwrap_or_else(|e| e.into_inner());
+        if templates.iter().any(|t| t.name == template.name && t.id != template.id) {
+            return Err(format!("A template named '{}' already exists", template.name));
+        }
+        let previous = templates.clone();
+        match templates.iter_mut().find(|t| !template.id.is_empty() && t.id == template.id) {
+            Some(existing) => *existing = template.clone(),
+            None => {
+                template.id = uuid::Uuid::new_v4().to_string();
+                templates.push(template.clone());
+            }
+        }
+        if let Err(e) = self.persist(&templates) {
+            *templates = previous;
+            return Err(e);
+        }
+        Ok(template)
+    }
+
+    pub fn delete(&self, id: &str) -> Result<(), String> {
+        let mut templates = self.templates.write().unwrap_or_else(|e| e.into_inner());
+        let index = templates
+            .iter()
+            .position(|t| t.id == id)
+            .ok_or_else(|| format!("Prompt template not found: {}", id))?;
+        let removed = templates.remove(index);
+        if let Err(e) = self.persist(&templates) {
+            templates.insert(index, removed);
+            return Err(e);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn template() -> PromptTemplate {
+        PromptTemplate {
+            name: "translate".to_string(),
+            system: Some("You translate into {{lang}}.".to_string()),
+            prompt: Some("Translate to {{ lang }} ({{tone}}):\n\n{{input}}".to_string()),
+            variables: vec![TemplateVariable { name: "tone".to_string(), default: Some("formal".to_string()), ..Default::default() }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_render_and_apply() {
+        let template = template();
+        let values = HashMap::from([("lang".to_string(), "French".to_string())]);
+        let mut body = json!({
+            "model": "tpl:translate/gpt-4o",
+            "messages": [{"role": "user", "content": "old"}, {"role": "assistant", "content": "ok"},
+                         {"role": "user", "content": [{"type": "text", "text": "Hello"}, {"type": "image_url"}, {"type": "text", "text": "world"}]}]
+        });
+        apply("/v1/chat/completions", &mut body, &template, &values).unwrap();
+        assert_eq!(body["messages"][0], json!({"role": "system", "content": "You translate into French."}));
+        assert_eq!(body["messages"][1]["content"], "old");
+        assert_eq!(
+            body["messages"][3]["content"],
+            json!([{"type": "text", "text": "Translate to French (formal):\n\nHello\nworld"}, {"type": "image_url"}])
+        );
+
+        let mut gemini = json!({"contents": [{"role": "user", "parts": [{"text": "Hi"}]}]});
+        apply("/v1beta/models/gemini-2.5-pro:generateContent", &mut gemini, &template, &values).unwrap();
+        assert_eq!(gemini["contents"][0]["parts"][0]["text"], "Translate to French (formal):\n\nHi");
+        assert_eq!(gemini["systemInstruction"]["parts"][0]["text"], "You translate into French.");
+
+        let err = render("{{lang}}", &template, &HashMap::new()).unwrap_err();
+        assert!(err.contains("'lang'"));
+        assert_eq!(render("a {{ unclosed", &template, &HashMap::new()).unwrap(), "a {{ unclosed");
+        assert_eq!(split_model("tpl:translate/gpt-4o"), Some(("translate", "gpt-4o")));
+        assert_eq!(split_model("gpt-4o"), None);
+    }
+
+    #[test]
+    fn test_variables_and_store() {
+        let mut body = json!({"messages": [], "template_variables": {"lang": "German", "n": 3}});
+        let values = variables(Some(r#"{"lang": "French", "tone": "casual"}"#), &mut body).unwrap();
+        assert_eq!(values["lang"], "German");
+        assert_eq!((values["tone"].as_str(), values["n"].as_str()), ("casual", "3"));
+        assert!(body.get(VARIABLES_FIELD).is_none());
+        assert!(variables(Some("not json"), &mut json!({})).is_err());
+
+        let store = PromptTemplateStore::new(None);
+        let saved = store.save(template()).unwrap();
+        assert!(!saved.id.is_empty());
+        assert!(store.save(PromptTemplate { id: String::new(), ..template() }).is_err());
+        store.save(PromptTemplate { description: "updated".to_string(), ..saved.clone() }).unwrap();
+        assert_eq!(store.get("translate").unwrap().description, "updated");
+        store.delete(&saved.id).unwrap();
+        assert!(store.list().is_empty());
+    }
+}
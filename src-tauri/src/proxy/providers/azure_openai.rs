@@ -0,0 +1,203 @@
+// Azure OpenAI 上游
+// 模型由 URL 中的部署名决定 (`/openai/deployments/{name}/...`), 使用 `api-key` Header 鉴权,
+// 并自动补齐 `api-version` 查询参数; 请求与响应均为 OpenAI 格式, 直接透传。
+
+use axum::{
+    body::Body,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde_json::{json, Value};
+
+use crate::proxy::common::model_mapping::wildcard_match;
+use crate::proxy::common::stream_relay::{is_event_stream, relay_upstream};
+use crate::proxy::config::AzureOpenAIConfig;
+use crate::proxy::middleware::request_id::WithRequestId;
+use crate::proxy::server::AppState;
+
+/// 模型解析结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AzureRoute {
+    pub deployment: String,
+    pub api_version: String,
+    /// api-version 来自部署配置, 优先于资源地址中的参数
+    pub pinned: bool,
+    pub chat_only: bool,
+}
+
+/// 第一条匹配的部署生效, 未启用或未命中时返回 None
+pub fn resolve_deployment(config: &AzureOpenAIConfig, model: &str) -> Option<AzureRoute> {
+    if !config.enabled || config.endpoint.is_empty() {
+        return None;
+    }
+    let deployment = config.deployments.iter().find(|d| wildcard_match(&d.model, model))?;
+    let pinned_version = deployment.api_version.clone().filter(|v| !v.is_empty());
+    Some(AzureRoute {
+        deployment: if deployment.deployment.is_empty() {
+            model.to_string()
+        } else {
+            deployment.deployment.clone()
+        },
+        pinned: pinned_version.is_some(),
+        chat_only: deployment.chat_only,
+        api_version: pinned_version.unwrap_or_else(|| config.api_version.clone()),
+    })
+}
+
+/// 拼接部署地址: 资源地址中 `/openai` 之后的路径会被替换, 已有的查询参数保留;
+/// `api-version` 优先级为 部署配置 > 地址中的参数 > 全局配置
+pub fn build_url(endpoint: &str, route: &AzureRoute, operation: &str) -> Result<String, String> {
+    let mut url = url::Url::parse(endpoint.trim()).map_err(|e| format!("Invalid Azure endpoint '{}': {}", endpoint, e))?;
+
+    let base_path = {
+        let path = url.path().trim_end_matches('/');
+        match path.find("/openai") {
+            Some(pos) => path[..pos].to_string(),
+            None => path.to_string(),
+        }
+    };
+    let existing: Vec<(String, String)> = url.query_pairs().map(|(k, v)| (k.into_owned(), v.into_owned())).collect();
+    let endpoint_version = existing.iter().find(|(k, _)| k == "api-version").map(|(_, v)| v.clone());
+    let api_version = match endpoint_version {
+        Some(v) if !route.pinned => v,
+        _ => route.api_version.clone(),
+    };
+
+    url.set_path(&base_path);
+    url.path_segments_mut()
+        .map_err(|_| format!("Invalid Azure endpoint '{}'", endpoint))?
+        .pop_if_empty()
+        .extend(["openai", "deployments", route.deployment.as_str()])
+        .extend(operation.split('/'));
+    {
+        let mut query = url.query_pairs_mut();
+        query.clear();
+        for (k, v) in existing.iter().filter(|(k, _)| k != "api-version") {
+            query.append_pair(k, v);
+        }
+        query.append_pair("api-version", &api_version);
+    }
+    Ok(url.to_string())
+}
+
+fn error_response(status: StatusCode, message: String) -> Response {
+    (
+        status,
+        axum::Json(json!({ "error": { "message": message, "type": "upstream_error", "code": "azure_error" } })),
+    )
+        .into_response()
+}
+
+/// 转发到 Azure 部署, `operation` 为部署下的接口路径 (例如 `chat/completions`, `embeddings`)
+pub async fn forward(state: &AppState, operation: &str, model: &str, mut body: Value) -> Option<Response> {
+    let config = state.azure_openai.read().await.clone();
+    let route = resolve_deployment(&config, model)?;
+    let url = match build_url(&config.endpoint, &route, operation) {
+        Ok(url) => url,
+        Err(e) => return Some(error_response(StatusCode::BAD_REQUEST, e)),
+    };
+    if config.api_key.is_empty() {
+        return Some(error_response(StatusCode::BAD_REQUEST, "Azure OpenAI api_key is not set".to_string()));
+    }
+    crate::proxy::common::param_policy::sanitize(&*state.param_policy.read().await, "azure", None, &mut body);
+    tracing::info!("[Azure] {} -> deployment {} ({})", model, route.deployment, operation);
+    crate::proxy::monitor::capture_upstream_request(&format!("POST {}", operation), &body);
+
+    let client = match crate::proxy::handlers::common::provider_client(state, "azure_openai", state.request_timeout).await {
+        Ok(client) => client,
+        Err(e) => return Some(error_response(StatusCode::INTERNAL_SERVER_ERROR, e)),
+    };
+
+    let resp = match client.post(&url).with_request_id().header("api-key", &config.api_key).json(&body).send().await {
+        Ok(resp) => resp,
+        Err(e) => {
+            return Some(error_response(StatusCode::BAD_GATEWAY, format!("Azure OpenAI request failed: {}", e)));
+        }
+    };
+
+    let status = StatusCode::from_u16(resp.status().as_u16()).unwrap_or(StatusCode::BAD_GATEWAY);
+    let mut out = Response::builder()
+        .status(status)
+        .header("X-Upstream", "azure")
+        .header("X-Mapped-Model", route.deployment.as_str());
+    if let Some(ct) = resp.headers().get(header::CONTENT_TYPE) {
+        out = out.header(header::CONTENT_TYPE, ct.clone());
+    }
+
+    // SSE 与 JSON 均原样透传
+    let sse = is_event_stream(resp.headers());
+    let stream = relay_upstream(resp.bytes_stream(), sse, "Azure");
+    Some(out.body(Body::from_stream(stream)).unwrap_or_else(|_| {
+        (StatusCode::INTERNAL_SERVER_ERROR, "Failed to build response").into_response()
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy::config::AzureDeployment;
+
+    fn config() -> AzureOpenAIConfig {
+        AzureOpenAIConfig {
+            enabled: true,
+            endpoint: "https://res.openai.azure.com".to_string(),
+            api_key: "key".to_string(),
+            deployments: vec![
+                AzureDeployment {
+                    model: "gpt-4o".to_string(),
+                    deployment: "prod-gpt4o".to_string(),
+                    api_version: Some("2025-01-01-preview".to_string()),
+                    chat_only: false,
+                },
+                AzureDeployment {
+                    model: "text-embedding-*".to_string(),
+                    deployment: String::new(),
+                    api_version: None,
+                    chat_only: false,
+                },
+            ],
+            ..AzureOpenAIConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_resolve_deployment() {
+        let cfg = config();
+        assert_eq!(
+            resolve_deployment(&cfg, "gpt-4o"),
+            Some(AzureRoute {
+                deployment: "prod-gpt4o".to_string(),
+                api_version: "2025-01-01-preview".to_string(),
+                pinned: true,
+                chat_only: false,
+            })
+        );
+        let embed = resolve_deployment(&cfg, "text-embedding-3-large").unwrap();
+        assert_eq!(embed.deployment, "text-embedding-3-large");
+        assert_eq!(embed.api_version, "2024-10-21");
+        assert_eq!(resolve_deployment(&cfg, "gpt-4o-mini"), None);
+    }
+
+    #[test]
+    fn test_build_url_handles_api_version() {
+        let mut route = AzureRoute {
+            deployment: "prod-gpt4o".to_string(),
+            api_version: "2024-10-21".to_string(),
+            pinned: false,
+            chat_only: false,
+        };
+        assert_eq!(
+            build_url("https://res.openai.azure.com/", &route, "chat/completions").unwrap(),
+            "https://res.openai.azure.com/openai/deployments/prod-gpt4o/chat/completions?api-version=2024-10-21"
+        );
+        // 粘贴的完整部署地址: 路径被替换, 地址中的 api-version 优先于全局配置
+        let pasted = "https://res.openai.azure.com/openai/deployments/old/chat/completions?api-version=2024-06-01";
+        assert_eq!(
+            build_url(pasted, &route, "embeddings").unwrap(),
+            "https://res.openai.azure.com/openai/deployments/prod-gpt4o/embeddings?api-version=2024-06-01"
+        );
+        // 部署级配置优先于地址
+        route.pinned = true;
+        assert!(build_url(pasted, &route, "embeddings").unwrap().ends_with("?api-version=2024-10-21"));
+    }
+}
@@ -0,0 +1,175 @@
+// AWS Bedrock 上游 (Converse / ConverseStream API)
+// OpenAI Chat Completions 请求转换为 Converse 格式, 使用 SigV4 签名;
+// 流式响应为 AWS event-stream 二进制帧, 转换为 OpenAI SSE。
+
+use axum::{
+    body::Body,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde_json::{json, Value};
+
+use crate::proxy::common::model_mapping::wildcard_match;
+use crate::proxy::common::sigv4::{uri_encode, AwsCredentials, AwsSigner};
+use crate::proxy::config::BedrockConfig;
+use crate::proxy::mappers::bedrock::{build_converse_request, converse_response_to_openai, converse_stream_to_sse};
+use crate::proxy::middleware::request_id::WithRequestId;
+use crate::proxy::server::AppState;
+
+/// 第一条匹配的规则生效, 返回 Bedrock 模型 ID; 未启用或未命中时返回 None
+pub fn resolve_model_id(config: &BedrockConfig, model: &str) -> Option<String> {
+    if !config.enabled {
+        return None;
+    }
+    let rule = config.models.iter().find(|m| wildcard_match(&m.model, model))?;
+    Some(if rule.model_id.is_empty() {
+        model.to_string()
+    } else {
+        rule.model_id.clone()
+    })
+}
+
+/// 配置中的凭证优先, 为空时读取 AWS 标准环境变量
+fn credentials(config: &BedrockConfig) -> Option<AwsCredentials> {
+    let pick = |value: &str, env: &str| {
+        if value.is_empty() {
+            std::env::var(env).ok().filter(|v| !v.is_empty())
+        } else {
+            Some(value.to_string())
+        }
+    };
+    Some(AwsCredentials {
+        access_key_id: pick(&config.access_key_id, "AWS_ACCESS_KEY_ID")?,
+        secret_access_key: pick(&config.secret_access_key, "AWS_SECRET_ACCESS_KEY")?,
+        session_token: pick(&config.session_token, "AWS_SESSION_TOKEN"),
+    })
+}
+
+pub fn build_url(region: &str, model_id: &str, stream: bool) -> String {
+    format!(
+        "https://bedrock-runtime.{}.amazonaws.com/model/{}/{}",
+        region,
+        uri_encode(model_id, true),
+        if stream { "converse-stream" } else { "converse" }
+    )
+}
+
+fn error_response(status: StatusCode, message: String) -> Response {
+    (
+        status,
+        axum::Json(json!({ "error": { "message": message, "type": "upstream_error", "code": "bedrock_error" } })),
+    )
+        .into_response()
+}
+
+/// 转发到 Bedrock, 模型未命中任何规则时返回 None
+pub async fn forward(state: &AppState, model: &str, body: &Value) -> Option<Response> {
+    let config = state.bedrock.read().await.clone();
+    let model_id = resolve_model_id(&config, model)?;
+    let Some(credentials) = credentials(&config) else {
+        return Some(error_response(StatusCode::BAD_REQUEST, "Bedrock AWS credentials are not set".to_string()));
+    };
+    let stream = body.get("stream").and_then(|s| s.as_bool()).unwrap_or(false);
+    let mut body = body.clone();
+    crate::proxy::common::param_policy::sanitize(&*state.param_policy.read().await, "bedrock", None, &mut body);
+    let request = match build_converse_request(&body) {
+        Ok(request) => request,
+        Err(e) => return Some(error_response(StatusCode::BAD_REQUEST, e)),
+    };
+    tracing::info!("[Bedrock] {} -> {} (stream: {})", model, model_id, stream);
+    crate::proxy::monitor::capture_upstream_request("POST bedrock/converse", &request);
+
+    let url = build_url(&config.region, &model_id, stream);
+    let parsed = match url::Url::parse(&url) {
+        Ok(parsed) => parsed,
+        Err(e) => return Some(error_response(StatusCode::BAD_REQUEST, format!("Invalid Bedrock URL '{}': {}", url, e))),
+    };
+    let payload = request.to_string().into_bytes();
+    let accept = if stream { "application/vnd.amazon.eventstream" } else { "application/json" };
+    let signer = AwsSigner { credentials: &credentials, region: &config.region, service: "bedrock" };
+    let signed = signer.sign(
+        "POST",
+        &parsed,
+        &[("content-type", "application/json"), ("accept", accept)],
+        &payload,
+        chrono::Utc::now(),
+    );
+
+    let client = match crate::proxy::handlers::common::provider_client(state, "bedrock", state.request_timeout).await {
+        Ok(client) => client,
+        Err(e) => return Some(error_response(StatusCode::INTERNAL_SERVER_ERROR, e)),
+    };
+    let mut req = client
+        .post(parsed)
+        .with_request_id()
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(header::ACCEPT, accept);
+    for (name, value) in &signed {
+        req = req.header(name.as_str(), value.as_str());
+    }
+    let resp = match req.body(payload).send().await {
+        Ok(resp) => resp,
+        Err(e) => return Some(error_response(StatusCode::BAD_GATEWAY, format!("Bedrock request failed: {}", e))),
+    };
+
+    let status = StatusCode::from_u16(resp.status().as_u16()).unwrap_or(StatusCode::BAD_GATEWAY);
+    if !status.is_success() {
+        let text = resp.text().await.unwrap_or_default();
+        let message = serde_json::from_str::<Value>(&text)
+            .ok()
+            .and_then(|v| v.get("message").and_then(|m| m.as_str()).map(String::from))
+            .unwrap_or(text);
+        tracing::warn!("[Bedrock] {} returned {}: {}", model_id, status, message);
+        return Some(error_response(status, message));
+    }
+
+    let out = Response::builder()
+        .status(StatusCode::OK)
+        .header("X-Upstream", "bedrock")
+        .header("X-Mapped-Model", model_id.as_str());
+    let built = if stream {
+        out.header(header::CONTENT_TYPE, "text/event-stream")
+            .header(header::CACHE_CONTROL, "no-cache")
+            .body(Body::from_stream(converse_stream_to_sse(resp.bytes_stream(), model)))
+    } else {
+        match resp.json::<Value>().await {
+            Ok(v) => out
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(converse_response_to_openai(&v, model).to_string())),
+            Err(e) => {
+                return Some(error_response(StatusCode::BAD_GATEWAY, format!("Invalid Bedrock response: {}", e)));
+            }
+        }
+    };
+    Some(built.unwrap_or_else(|_| {
+        (StatusCode::INTERNAL_SERVER_ERROR, "Failed to build response").into_response()
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy::config::BedrockModel;
+
+    #[test]
+    fn test_resolve_model_id_and_url() {
+        let cfg = BedrockConfig {
+            enabled: true,
+            models: vec![
+                BedrockModel {
+                    model: "claude-3-5-sonnet".to_string(),
+                    model_id: "us.anthropic.claude-3-5-sonnet-20241022-v2:0".to_string(),
+                },
+                BedrockModel { model: "amazon.titan-*".to_string(), model_id: String::new() },
+            ],
+            ..BedrockConfig::default()
+        };
+        let id = resolve_model_id(&cfg, "claude-3-5-sonnet").unwrap();
+        assert_eq!(
+            build_url(&cfg.region, &id, true),
+            "https://bedrock-runtime.us-east-1.amazonaws.com/model/us.anthropic.claude-3-5-sonnet-20241022-v2%3A0/converse-stream"
+        );
+        assert_eq!(resolve_model_id(&cfg, "amazon.titan-text-express-v1").as_deref(), Some("amazon.titan-text-express-v1"));
+        assert_eq!(resolve_model_id(&cfg, "gpt-4o"), None);
+    }
+}
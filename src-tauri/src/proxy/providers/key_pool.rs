@@ -0,0 +1,431 @@
+// 上游 API Key 池
+// 同一 provider 配置多个 Key 时按加权轮询或最少并发分发请求,
+// 并根据响应中的限流头临时跳过已耗尽的 Key; 连续被拒绝 (401 / 403) 或额度耗尽的 Key 停用到手动恢复或重启。
+
+use reqwest::header::HeaderMap;
+use reqwest::StatusCode;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::proxy::config::{KeyBalanceStrategy, WeightedApiKey};
+
+/// 429 未携带任何重置提示时的默认冷却时间
+const DEFAULT_EXHAUSTED_COOLDOWN: Duration = Duration::from_secs(60);
+/// 连续失败达到该次数后停用 Key
+const DISABLE_AFTER_FAILURES: u32 = 3;
+/// 429 响应体中表示额度 / 余额耗尽 (而非短时限流) 的标记
+const QUOTA_HINTS: &[&str] = &["insufficient_quota", "insufficient balance", "insufficient_balance", "billing", "credit", "余额不足", "\"1113\""];
+
+/// 上游拒绝 Key 的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyFailure {
+    Unauthorized,
+    QuotaExhausted,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DisabledKey {
+    /// 稳定标识, 恢复 Key 时使用 (脱敏后的 Key 可能重复)
+    pub key_id: String,
+    pub key_hint: String,
+    pub reason: KeyFailure,
+    pub disabled_at: i64,
+}
+
+/// Key 的稳定标识 (SHA-256 前 16 位), 在接口中代替明文 Key
+pub fn key_id(key: &str) -> String {
+    use sha2::{Digest, Sha256};
+    format!("{:x}", Sha256::digest(key.as_bytes()))[..16].to_string()
+}
+
+#[derive(Debug, Default)]
+struct KeyState {
+    in_flight: Arc<AtomicUsize>,
+    /// 平滑加权轮询的当前权重
+    current_weight: i64,
+    exhausted_until: Option<Instant>,
+    /// 连续的鉴权 / 额度失败次数
+    failures: u32,
+    /// (原因, 停用时间)
+    disabled: Option<(KeyFailure, i64)>,
+}
+
+/// 一次 Key 租用, 释放时自动减少并发计数
+#[derive(Debug)]
+pub struct KeyLease {
+    pub key: String,
+    in_flight: Arc<AtomicUsize>,
+}
+
+impl Drop for KeyLease {
+    fn drop(&mut self) {
+        self.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct KeyPool {
+    states: Mutex<HashMap<String, KeyState>>,
+}
+
+impl KeyPool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 选择一个 Key; 全部耗尽时选择最早恢复的那个, 交由上游决定; 全部停用时返回 None
+    pub fn acquire(&self, keys: &[WeightedApiKey], strategy: KeyBalanceStrategy) -> Option<KeyLease> {
+        self.acquire_at(keys, strategy, Instant::now())
+    }
+
+    /// 请求被拒绝后换用另一个可用的 Key (跳过已尝试、冷却中与已停用的 Key)
+    pub fn rotate(&self, keys: &[WeightedApiKey], strategy: KeyBalanceStrategy, tried: &[String]) -> Option<KeyLease> {
+        self.pick(keys, strategy, Instant::now(), tried, false)
+    }
+
+    fn acquire_at(&self, keys: &[WeightedApiKey], strategy: KeyBalanceStrategy, now: Instant) -> Option<KeyLease> {
+        self.pick(keys, strategy, now, &[], true)
+    }
+
+    fn pick(
+        &self,
+        keys: &[WeightedApiKey],
+        strategy: KeyBalanceStrategy,
+        now: Instant,
+        tried: &[String],
+        fallback: bool,
+    ) -> Option<KeyLease> {
+        if keys.is_empty() {
+            return None;
+        }
+        let mut states = self.states.lock().unwrap_or_else(|e| e.into_inner());
+        // 配置变更后移除已删除的 Key
+        states.retain(|k, _| keys.iter().any(|w| &w.key == k));
+        for w in keys {
+            states.entry(w.key.clone()).or_default();
+        }
+
+        let usable: Vec<&WeightedApiKey> = keys.iter().filter(|w| states[&w.key].disabled.is_none()).collect();
+        let available: Vec<&WeightedApiKey> = usable
+            .iter()
+            .copied()
+            .filter(|w| {
+                !tried.contains(&w.key)
+                    && states[&w.key]
+                        .exhausted_until
+                        .map_or(true, |until| until <= now)
+            })
+            .collect();
+
+        let chosen: &WeightedApiKey = if available.is_empty() {
+            if !fallback {
+                return None;
+            }
+            usable.iter().copied().min_by_key(|w| states[&w.key].exhausted_until)?
+        } else {
+            match strategy {
+                KeyBalanceStrategy::WeightedRoundRobin => {
+                    let total: i64 = available.iter().map(|w| w.weight as i64).sum();
+                    let mut best: Option<(&WeightedApiKey, i64)> = None;
+                    for w in &available {
+                        let state = states.get_mut(&w.key).expect("state exists");
+                        state.current_weight += w.weight as i64;
+                        if best.map_or(true, |(_, weight)| state.current_weight > weight) {
+                            best = Some((w, state.current_weight));
+                        }
+                    }
+                    let (best, _) = best.expect("available is not empty");
+                    states.get_mut(&best.key).expect("state exists").current_weight -= total;
+                    best
+                }
+                KeyBalanceStrategy::LeastInFlight => available
+                    .iter()
+                    .copied()
+                    .min_by(|a, b| {
+                        let load = |w: &WeightedApiKey| {
+                            states[&w.key].in_flight.load(Ordering::Relaxed) as f64 / w.weight.max(1) as f64
+                        };
+                        load(a).partial_cmp(&load(b)).unwrap_or(std::cmp::Ordering::Equal)
+                    })
+                    .expect("available is not empty"),
+            }
+        };
+
+        let in_flight = states[&chosen.key].in_flight.clone();
+        in_flight.fetch_add(1, Ordering::Relaxed);
+        Some(KeyLease {
+            key: chosen.key.clone(),
+            in_flight,
+        })
+    }
+
+    /// 根据上游响应更新 Key 的限流状态
+    pub fn observe(&self, key: &str, status: StatusCode, headers: &HeaderMap) {
+        self.observe_at(key, status, headers, Instant::now());
+    }
+
+    fn observe_at(&self, key: &str, status: StatusCode, headers: &HeaderMap, now: Instant) {
+        let cooldown = exhausted_cooldown(status, headers);
+        let mut states = self.states.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(state) = states.get_mut(key) else {
+            return;
+        };
+        match cooldown {
+            Some(cooldown) => {
+                state.exhausted_until = Some(now + cooldown);
+                tracing::warn!(
+                    "[Key-Pool] Key {} exhausted, skipping for {:?}",
+                    crate::modules::usage::mask_api_key(key),
+                    cooldown
+                );
+            }
+            None if status.is_success() => {
+                state.exhausted_until = None;
+                state.failures = 0;
+            }
+            None => {}
+        }
+    }
+
+    /// 记录一次鉴权 / 额度失败; 连续失败达到上限时停用该 Key 并返回 true
+    pub fn report_failure(&self, key: &str, failure: KeyFailure) -> bool {
+        let mut states = self.states.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(state) = states.get_mut(key) else {
+            return false;
+        };
+        if state.disabled.is_some() {
+            return false;
+        }
+        state.failures += 1;
+        if state.failures < DISABLE_AFTER_FAILURES {
+            return false;
+        }
+        state.disabled = Some((failure, chrono::Utc::now().timestamp()));
+        tracing::warn!(
+            "[Key-Pool] Key {} disabled after {} consecutive {:?} errors",
+            crate::modules::usage::mask_api_key(key),
+            state.failures,
+            failure
+        );
+        true
+    }
+
+    /// 已停用的 Key
+    pub fn disabled(&self) -> Vec<DisabledKey> {
+        let states = self.states.lock().unwrap_or_else(|e| e.into_inner());
+        let mut disabled: Vec<DisabledKey> = states
+            .iter()
+            .filter_map(|(key, state)| {
+                let (reason, disabled_at) = state.disabled?;
+                Some(DisabledKey {
+                    key_id: key_id(key),
+                    key_hint: crate::modules::usage::mask_api_key(key),
+                    reason,
+                    disabled_at,
+                })
+            })
+            .collect();
+        disabled.sort_by_key(|d| d.disabled_at);
+        disabled
+    }
+
+    /// 按 `key_id` 恢复停用的 Key, 返回是否找到
+    pub fn enable(&self, id: &str) -> bool {
+        let mut states = self.states.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(state) = states.iter_mut().find(|(key, s)| s.disabled.is_some() && key_id(key) == id).map(|(_, s)| s)
+        else {
+            return false;
+        };
+        state.disabled = None;
+        state.failures = 0;
+        state.exhausted_until = None;
+        true
+    }
+}
+
+/// 响应是否表示 Key 本身不可用 (而非请求或短时限流问题)
+pub fn key_failure(status: StatusCode, body: &[u8]) -> Option<KeyFailure> {
+    match status.as_u16() {
+        401 | 403 => Some(KeyFailure::Unauthorized),
+        402 => Some(KeyFailure::QuotaExhausted),
+        429 => {
+            let body = String::from_utf8_lossy(body).to_lowercase();
+            QUOTA_HINTS.iter().any(|h| body.contains(h)).then_some(KeyFailure::QuotaExhausted)
+        }
+        _ => None,
+    }
+}
+
+fn header_str<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|v| v.to_str().ok()).map(str::trim)
+}
+
+/// 重置时间: 秒数、Go 风格时长 ("6m0s") 或 RFC 3339 时间戳
+fn parse_reset(value: &str) -> Option<Duration> {
+    if let Ok(secs) = value.parse::<f64>() {
+        return Some(Duration::from_secs_f64(secs.max(0.0)));
+    }
+    if let Ok(at) = chrono::DateTime::parse_from_rfc3339(value) {
+        let ms = (at.with_timezone(&chrono::Utc) - chrono::Utc::now()).num_milliseconds();
+        return Some(Duration::from_millis(ms.max(0) as u64));
+    }
+    crate::proxy::upstream::retry::parse_duration_ms(value).map(Duration::from_millis)
+}
+
+/// Key 是否已耗尽以及需要等待多久
+///
+/// 支持 OpenAI (`x-ratelimit-*`) 和 Anthropic (`anthropic-ratelimit-*`) 风格的限流头。
+fn exhausted_cooldown(status: StatusCode, headers: &HeaderMap) -> Option<Duration> {
+    const DIMENSIONS: [(&str, &str); 4] = [
+        ("x-ratelimit-remaining-requests", "x-ratelimit-reset-requests"),
+        ("x-ratelimit-remaining-tokens", "x-ratelimit-reset-tokens"),
+        ("anthropic-ratelimit-requests-remaining", "anthropic-ratelimit-requests-reset"),
+        ("anthropic-ratelimit-tokens-remaining", "anthropic-ratelimit-tokens-reset"),
+    ];
+
+    let retry_after = header_str(headers, "retry-after").and_then(parse_reset);
+
+    // 剩余额度为 0 的维度中取最长的重置时间
+    let depleted = DIMENSIONS
+        .iter()
+        .filter(|(remaining, _)| {
+            header_str(headers, remaining)
+                .and_then(|v| v.parse::<u64>().ok())
+                .map_or(false, |v| v == 0)
+        })
+        .map(|(_, reset)| {
+            header_str(headers, reset)
+                .and_then(parse_reset)
+                .or(retry_after)
+                .unwrap_or(DEFAULT_EXHAUSTED_COOLDOWN)
+        })
+        .max();
+
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        return Some(depleted.or(retry_after).unwrap_or(DEFAULT_EXHAUSTED_COOLDOWN));
+    }
+    depleted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(weights: &[(&str, u32)]) -> Vec<WeightedApiKey> {
+        weights
+            .iter()
+            .map(|(k, w)| WeightedApiKey {
+                key: k.to_string(),
+                weight: *w,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_weighted_round_robin_distribution() {
+        let pool = KeyPool::new();
+        let keys = keys(&[("a", 3), ("b", 1)]);
+        let picks: Vec<String> = (0..8)
+            .map(|_| pool.acquire(&keys, KeyBalanceStrategy::WeightedRoundRobin).unwrap().key.clone())
+            .collect();
+        assert_eq!(picks.iter().filter(|k| *k == "a").count(), 6);
+        // 平滑轮询: b 不会被连续选中
+        assert_eq!(picks[..4].iter().filter(|k| *k == "b").count(), 1);
+    }
+
+    #[test]
+    fn test_least_in_flight_prefers_idle_key() {
+        let pool = KeyPool::new();
+        let keys = keys(&[("a", 1), ("b", 1)]);
+        let first = pool.acquire(&keys, KeyBalanceStrategy::LeastInFlight).unwrap();
+        let second = pool.acquire(&keys, KeyBalanceStrategy::LeastInFlight).unwrap();
+        assert_ne!(first.key, second.key);
+
+        drop(first);
+        let third = pool.acquire(&keys, KeyBalanceStrategy::LeastInFlight).unwrap();
+        assert_ne!(third.key, second.key);
+    }
+
+    #[test]
+    fn test_exhausted_key_is_skipped_until_reset() {
+        let pool = KeyPool::new();
+        let keys = keys(&[("a", 1), ("b", 1)]);
+        let now = Instant::now();
+        let _ = pool.acquire_at(&keys, KeyBalanceStrategy::WeightedRoundRobin, now);
+
+        let mut headers = HeaderMap::new();
+        headers.insert("anthropic-ratelimit-requests-remaining", "0".parse().unwrap());
+        headers.insert("anthropic-ratelimit-requests-reset", "30".parse().unwrap());
+        pool.observe_at("a", StatusCode::OK, &headers, now);
+
+        for _ in 0..3 {
+            let lease = pool.acquire_at(&keys, KeyBalanceStrategy::WeightedRoundRobin, now).unwrap();
+            assert_eq!(lease.key, "b");
+        }
+        let later = now + Duration::from_secs(31);
+        let picks: Vec<String> = (0..2)
+            .map(|_| pool.acquire_at(&keys, KeyBalanceStrategy::WeightedRoundRobin, later).unwrap().key.clone())
+            .collect();
+        assert!(picks.contains(&"a".to_string()));
+    }
+
+    #[test]
+    fn test_failing_key_is_disabled_and_rotated() {
+        let pool = KeyPool::new();
+        let keys = keys(&[("sk-dead-key-0001", 1), ("sk-good-key-0002", 1)]);
+        let lease = pool.acquire(&keys, KeyBalanceStrategy::WeightedRoundRobin).unwrap();
+        let tried = vec![lease.key.clone()];
+        let next = pool.rotate(&keys, KeyBalanceStrategy::WeightedRoundRobin, &tried).unwrap();
+        assert_ne!(next.key, lease.key);
+        assert!(pool.rotate(&keys, KeyBalanceStrategy::WeightedRoundRobin, &[lease.key.clone(), next.key.clone()]).is_none());
+
+        let failure = key_failure(StatusCode::UNAUTHORIZED, b"").unwrap();
+        assert!(!pool.report_failure("sk-dead-key-0001", failure));
+        assert!(!pool.report_failure("sk-dead-key-0001", failure));
+        assert!(pool.report_failure("sk-dead-key-0001", failure));
+        for _ in 0..3 {
+            assert_eq!(pool.acquire(&keys, KeyBalanceStrategy::WeightedRoundRobin).unwrap().key, "sk-good-key-0002");
+        }
+        let disabled = pool.disabled();
+        assert_eq!((disabled.len(), disabled[0].reason), (1, KeyFailure::Unauthorized));
+
+        assert!(!pool.enable(&disabled[0].key_hint));
+        assert!(pool.enable(&disabled[0].key_id));
+        assert!(pool.disabled().is_empty());
+        let single = &keys[..1];
+        for _ in 0..3 {
+            pool.report_failure("sk-dead-key-0001", KeyFailure::QuotaExhausted);
+        }
+        assert!(pool.acquire(single, KeyBalanceStrategy::WeightedRoundRobin).is_none());
+    }
+
+    #[test]
+    fn test_key_failure_classification() {
+        assert_eq!(key_failure(StatusCode::PAYMENT_REQUIRED, b""), Some(KeyFailure::QuotaExhausted));
+        let zai = "{\"error\":{\"code\":\"1113\",\"message\":\"余额不足或无可用资源包,请充值。\"}}";
+        assert_eq!(key_failure(StatusCode::TOO_MANY_REQUESTS, zai.as_bytes()), Some(KeyFailure::QuotaExhausted));
+        assert_eq!(key_failure(StatusCode::TOO_MANY_REQUESTS, b"{\"error\":\"rate limited\"}"), None);
+        assert_eq!(key_failure(StatusCode::BAD_REQUEST, b""), None);
+    }
+
+    #[test]
+    fn test_exhausted_cooldown_parsing() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(exhausted_cooldown(StatusCode::OK, &headers), None);
+        assert_eq!(
+            exhausted_cooldown(StatusCode::TOO_MANY_REQUESTS, &headers),
+            Some(DEFAULT_EXHAUSTED_COOLDOWN)
+        );
+
+        headers.insert("retry-after", "5".parse().unwrap());
+        assert_eq!(exhausted_cooldown(StatusCode::TOO_MANY_REQUESTS, &headers), Some(Duration::from_secs(5)));
+
+        headers.insert("x-ratelimit-remaining-tokens", "0".parse().unwrap());
+        headers.insert("x-ratelimit-reset-tokens", "1m30s".parse().unwrap());
+        assert_eq!(exhausted_cooldown(StatusCode::OK, &headers), Some(Duration::from_secs(90)));
+    }
+}
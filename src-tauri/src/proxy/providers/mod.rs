@@ -1,2 +1,8 @@
+pub mod azure_openai;
+pub mod bedrock;
+pub mod key_pool;
+pub mod ollama;
+pub mod openai_compat;
+pub mod presets;
 pub mod zai_anthropic;
 
@@ -0,0 +1,97 @@
+// 本地 Ollama 上游
+// 请求转换为 Ollama 原生接口后转发, 响应 (JSON 或 NDJSON 流) 还原为 OpenAI 格式。
+
+use axum::{
+    body::Body,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::Value;
+
+use crate::proxy::mappers::ollama::{
+    build_chat_request, build_generate_request, chat_response_to_openai, error_to_openai,
+    generate_response_to_openai, is_stream, ndjson_to_sse, OllamaEndpoint,
+};
+use crate::proxy::middleware::request_id::WithRequestId;
+use crate::proxy::server::AppState;
+
+fn error_response(status: StatusCode, message: String) -> Response {
+    (status, Json(error_to_openai(&message))).into_response()
+}
+
+/// 转发到 Ollama, `model` 为客户端请求的模型名, `upstream_model` 为 Ollama 侧的模型名
+pub async fn forward(
+    state: &AppState,
+    endpoint: OllamaEndpoint,
+    mut body: Value,
+    model: &str,
+    upstream_model: &str,
+) -> Response {
+    crate::proxy::common::param_policy::sanitize(&*state.param_policy.read().await, "ollama", None, &mut body);
+    let config = state.ollama.read().await.clone();
+    let request = match endpoint {
+        OllamaEndpoint::Chat => build_chat_request(&body, upstream_model, &config),
+        OllamaEndpoint::Generate => build_generate_request(&body, upstream_model, &config),
+    };
+    let stream = is_stream(&body);
+    let url = format!("{}{}", config.base_url.trim_end_matches('/'), endpoint.path());
+    tracing::info!("[Ollama] {} -> {} ({}), stream: {}", model, upstream_model, endpoint.path(), stream);
+    crate::proxy::monitor::capture_upstream_request(&format!("POST {}", endpoint.path()), &request);
+
+    // Ollama 通常在本机运行, 不走上游代理
+    let client = match crate::proxy::handlers::common::build_upstream_client(
+        crate::proxy::config::UpstreamProxyConfig::default(),
+        state.request_timeout,
+    ) {
+        Ok(client) => client,
+        Err(e) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, e),
+    };
+
+    let resp = match client.post(&url).with_request_id().json(&request).send().await {
+        Ok(resp) => resp,
+        Err(e) => {
+            return error_response(StatusCode::BAD_GATEWAY, format!("Ollama request failed: {}", e));
+        }
+    };
+
+    let status = StatusCode::from_u16(resp.status().as_u16()).unwrap_or(StatusCode::BAD_GATEWAY);
+    if !status.is_success() {
+        let text = resp.text().await.unwrap_or_default();
+        return (
+            status,
+            [("X-Upstream", "ollama"), ("X-Mapped-Model", upstream_model)],
+            Json(error_to_openai(&text)),
+        )
+            .into_response();
+    }
+
+    if stream {
+        let sse = ndjson_to_sse(resp.bytes_stream(), endpoint, model);
+        return Response::builder()
+            .status(StatusCode::OK)
+            .header(header::CONTENT_TYPE, "text/event-stream")
+            .header(header::CACHE_CONTROL, "no-cache")
+            .header("X-Upstream", "ollama")
+            .header("X-Mapped-Model", upstream_model)
+            .body(Body::from_stream(sse))
+            .unwrap_or_else(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Failed to build response").into_response());
+    }
+
+    let json: Value = match resp.json().await {
+        Ok(json) => json,
+        Err(e) => {
+            return error_response(StatusCode::BAD_GATEWAY, format!("Invalid Ollama response: {}", e));
+        }
+    };
+    let converted = match endpoint {
+        OllamaEndpoint::Chat => chat_response_to_openai(&json, model),
+        OllamaEndpoint::Generate => generate_response_to_openai(&json, model),
+    };
+    (
+        StatusCode::OK,
+        [("X-Upstream", "ollama"), ("X-Mapped-Model", upstream_model)],
+        Json(converted),
+    )
+        .into_response()
+}
@@ -0,0 +1,188 @@
+// 通用 OpenAI 兼容对话上游 (Mistral / Groq / Together / OpenRouter 等)
+// 请求经参数策略清理并按预设改名参数后转发, 响应 (JSON 或 SSE) 原样透传。
+
+use axum::{
+    body::Body,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde_json::{json, Value};
+
+use super::presets::{self, ProviderPreset};
+use crate::proxy::common::model_mapping::wildcard_match;
+use crate::proxy::common::stream_relay::{is_event_stream, relay_upstream};
+use crate::proxy::config::{CompatibleUpstream, CompatibleUpstreamsConfig};
+use crate::proxy::middleware::request_id::WithRequestId;
+use crate::proxy::server::AppState;
+
+/// 合并预设后的上游
+#[derive(Debug, Clone)]
+pub struct CompatRoute {
+    pub name: String,
+    pub base_url: String,
+    pub api_key: String,
+    pub preset: Option<&'static ProviderPreset>,
+    /// 传统 Completions 请求需要转换为 Chat Completions
+    pub chat_only: bool,
+}
+
+/// 路由到该上游的模型: 显式配置优先, 否则使用预设的已知模型
+pub fn model_patterns(upstream: &CompatibleUpstream) -> Vec<String> {
+    if !upstream.models.is_empty() {
+        return upstream.models.clone();
+    }
+    upstream
+        .preset
+        .as_deref()
+        .and_then(presets::find)
+        .map(|p| p.models.iter().map(|m| m.to_string()).collect())
+        .unwrap_or_default()
+}
+
+/// 显示名称, 为空时使用预设 id
+pub fn upstream_name(upstream: &CompatibleUpstream) -> String {
+    if !upstream.name.is_empty() {
+        return upstream.name.clone();
+    }
+    match upstream.preset.as_deref().filter(|p| !p.is_empty()) {
+        Some(preset) => preset.to_ascii_lowercase(),
+        None => "openai_compatible".to_string(),
+    }
+}
+
+/// 合并预设的默认地址与鉴权方式, 缺少地址时返回 None
+pub fn route_for(upstream: &CompatibleUpstream) -> Option<CompatRoute> {
+    let preset = upstream.preset.as_deref().filter(|p| !p.is_empty()).and_then(presets::find);
+    let base_url = if upstream.base_url.trim().is_empty() {
+        preset?.base_url.to_string()
+    } else {
+        upstream.base_url.trim().trim_end_matches('/').to_string()
+    };
+    Some(CompatRoute {
+        name: upstream_name(upstream),
+        base_url,
+        api_key: upstream.api_key.clone(),
+        chat_only: upstream.chat_only || preset.is_some_and(|p| !p.legacy_completions),
+        preset,
+    })
+}
+
+/// 第一条启用且模型匹配的上游生效 (缺少地址的条目跳过)
+pub fn resolve(config: &CompatibleUpstreamsConfig, model: &str) -> Option<CompatRoute> {
+    config
+        .upstreams
+        .iter()
+        .filter(|u| u.enabled && model_patterns(u).iter().any(|p| wildcard_match(p, model)))
+        .find_map(route_for)
+}
+
+fn error_response(status: StatusCode, message: String) -> Response {
+    (
+        status,
+        axum::Json(json!({ "error": { "message": message, "type": "upstream_error", "code": "upstream_error" } })),
+    )
+        .into_response()
+}
+
+/// 转发到匹配的兼容上游, `operation` 为接口路径 (例如 `chat/completions`)
+pub async fn forward(state: &AppState, operation: &str, model: &str, mut body: Value) -> Option<Response> {
+    // 托管的本地 llama-server 优先
+    let route = match crate::proxy::llama_server::LlamaServer::global().route(model) {
+        Some(route) => route,
+        None => resolve(&*state.compatible_upstreams.read().await, model)?,
+    };
+    if route.api_key.is_empty() {
+        return Some(error_response(
+            StatusCode::BAD_REQUEST,
+            format!("API key for upstream '{}' is not set", route.name),
+        ));
+    }
+    body["model"] = json!(model);
+    crate::proxy::common::param_policy::sanitize(&*state.param_policy.read().await, &route.name, route.preset, &mut body);
+    if let Some(preset) = route.preset {
+        preset.rename_params(&mut body);
+        if !preset.cache_control {
+            let removed = crate::proxy::common::prompt_cache::strip_cache_hints(&mut body);
+            if removed > 0 {
+                tracing::debug!("[Compat] {}: removed {} cache_control hint(s)", route.name, removed);
+            }
+        }
+    }
+    tracing::info!("[Compat] {} -> {} ({})", model, route.name, operation);
+    crate::proxy::monitor::capture_upstream_request(&format!("POST {}", operation), &body);
+
+    let client = match crate::proxy::handlers::common::provider_client(state, &format!("compat:{}", route.name), state.request_timeout).await {
+        Ok(client) => client,
+        Err(e) => return Some(error_response(StatusCode::INTERNAL_SERVER_ERROR, e)),
+    };
+
+    let mut req = client.post(format!("{}/{}", route.base_url, operation)).with_request_id().json(&body);
+    req = match route.preset {
+        Some(preset) => req.header(preset.auth_header, preset.auth_value(&route.api_key)),
+        None => req.bearer_auth(&route.api_key),
+    };
+    for (name, value) in route.preset.map(|p| p.extra_headers).unwrap_or_default() {
+        req = req.header(*name, *value);
+    }
+    let resp = match req.send().await {
+        Ok(resp) => resp,
+        Err(e) => {
+            return Some(error_response(
+                StatusCode::BAD_GATEWAY,
+                format!("Upstream '{}' request failed: {}", route.name, e),
+            ));
+        }
+    };
+
+    let status = StatusCode::from_u16(resp.status().as_u16()).unwrap_or(StatusCode::BAD_GATEWAY);
+    let mut out = Response::builder()
+        .status(status)
+        .header("X-Upstream", route.name.as_str())
+        .header("X-Mapped-Model", model);
+    if let Some(ct) = resp.headers().get(header::CONTENT_TYPE) {
+        out = out.header(header::CONTENT_TYPE, ct.clone());
+    }
+
+    let sse = is_event_stream(resp.headers());
+    let stream = relay_upstream(resp.bytes_stream(), sse, "OpenAICompat");
+    Some(out.body(Body::from_stream(stream)).unwrap_or_else(|_| {
+        (StatusCode::INTERNAL_SERVER_ERROR, "Failed to build response").into_response()
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn upstream(name: &str, preset: Option<&str>, base_url: &str, models: &[&str]) -> CompatibleUpstream {
+        CompatibleUpstream {
+            name: name.to_string(),
+            preset: preset.map(String::from),
+            base_url: base_url.to_string(),
+            api_key: "key".to_string(),
+            models: models.iter().map(|m| m.to_string()).collect(),
+            chat_only: false,
+            enabled: true,
+        }
+    }
+
+    #[test]
+    fn test_resolve_uses_preset_defaults() {
+        let config = CompatibleUpstreamsConfig {
+            upstreams: vec![
+                upstream("", Some("groq"), "", &[]),
+                upstream("my-together", Some("together"), "https://proxy.local/v1/", &["meta-llama/*"]),
+                upstream("broken", Some("unknown"), "", &["*"]),
+            ],
+        };
+        let groq = resolve(&config, "llama-3.3-70b-versatile").unwrap();
+        assert_eq!((groq.name.as_str(), groq.base_url.as_str()), ("groq", "https://api.groq.com/openai/v1"));
+
+        let together = resolve(&config, "meta-llama/Llama-3.3-70B-Instruct-Turbo").unwrap();
+        assert_eq!(together.base_url, "https://proxy.local/v1");
+        assert_eq!(together.preset.map(|p| p.id), Some("together"));
+
+        // 未知预设且没有地址的上游被忽略
+        assert!(resolve(&config, "gpt-4o").is_none());
+    }
+}
@@ -0,0 +1,183 @@
+// 内置的 OpenAI 兼容上游预设
+// 选择预设后只需填写 API Key; 预设提供默认地址、鉴权方式、已知模型与参数限制。
+
+use serde::Serialize;
+use serde_json::Value;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderPreset {
+    pub id: &'static str,
+    pub name: &'static str,
+    /// 包含版本前缀, 接口路径直接拼接在后面 (例如 `{base_url}/chat/completions`)
+    pub base_url: &'static str,
+    /// API Key 所在的 Header
+    pub auth_header: &'static str,
+    /// Key 前的认证方案 (例如 `Bearer`), 为空时直接发送原始 Key
+    pub auth_scheme: &'static str,
+    /// 未配置模型列表时路由到该上游的模型
+    pub models: &'static [&'static str],
+    /// 上游不支持的请求参数, 由参数策略在转发前删除
+    pub unsupported_params: &'static [&'static str],
+    /// 数值参数的取值范围 (参数名, 最小值, 最大值)
+    pub clamped_params: &'static [(&'static str, f64, f64)],
+    /// 参数改名 (OpenAI 参数名 → 上游参数名)
+    pub renamed_params: &'static [(&'static str, &'static str)],
+    pub extra_headers: &'static [(&'static str, &'static str)],
+    /// 接受消息与工具上的 Anthropic 风格 `cache_control` 断点; 不接受时转发前删除
+    pub cache_control: bool,
+    /// 提供传统 `/completions` 接口; 不提供时传统 Completions 请求转换为 Chat Completions
+    pub legacy_completions: bool,
+}
+
+pub static PRESETS: &[ProviderPreset] = &[
+    ProviderPreset {
+        id: "mistral",
+        name: "Mistral",
+        base_url: "https://api.mistral.ai/v1",
+        auth_header: "Authorization",
+        auth_scheme: "Bearer",
+        models: &[
+            "mistral-large-latest",
+            "mistral-medium-latest",
+            "mistral-small-latest",
+            "codestral-latest",
+            "pixtral-large-latest",
+            "ministral-8b-latest",
+            "open-mistral-nemo",
+        ],
+        // Mistral 拒绝未知字段
+        unsupported_params: &[
+            "logit_bias",
+            "logprobs",
+            "top_logprobs",
+            "user",
+            "store",
+            "metadata",
+            "service_tier",
+            "prompt_cache_key",
+        ],
+        clamped_params: &[("temperature", 0.0, 1.5), ("top_p", 0.0, 1.0)],
+        renamed_params: &[("max_completion_tokens", "max_tokens"), ("seed", "random_seed")],
+        extra_headers: &[],
+        cache_control: false,
+        legacy_completions: false,
+    },
+    ProviderPreset {
+        id: "groq",
+        name: "Groq",
+        base_url: "https://api.groq.com/openai/v1",
+        auth_header: "Authorization",
+        auth_scheme: "Bearer",
+        models: &[
+            "llama-3.3-70b-versatile",
+            "llama-3.1-8b-instant",
+            "gemma2-9b-it",
+            "deepseek-r1-distill-llama-70b",
+            "qwen-qwq-32b",
+        ],
+        unsupported_params: &["logit_bias", "logprobs", "top_logprobs", "store", "metadata"],
+        clamped_params: &[("temperature", 0.0, 2.0), ("n", 1.0, 1.0)],
+        renamed_params: &[],
+        extra_headers: &[],
+        cache_control: false,
+        legacy_completions: false,
+    },
+    ProviderPreset {
+        id: "together",
+        name: "Together AI",
+        base_url: "https://api.together.xyz/v1",
+        auth_header: "Authorization",
+        auth_scheme: "Bearer",
+        models: &[
+            "meta-llama/Llama-3.3-70B-Instruct-Turbo",
+            "meta-llama/Meta-Llama-3.1-8B-Instruct-Turbo",
+            "Qwen/Qwen2.5-72B-Instruct-Turbo",
+            "Qwen/Qwen2.5-Coder-32B-Instruct",
+            "deepseek-ai/DeepSeek-V3",
+            "mistralai/Mixtral-8x7B-Instruct-v0.1",
+        ],
+        unsupported_params: &["store", "metadata", "service_tier", "parallel_tool_calls"],
+        clamped_params: &[],
+        renamed_params: &[("max_completion_tokens", "max_tokens")],
+        extra_headers: &[],
+        cache_control: false,
+        legacy_completions: true,
+    },
+    ProviderPreset {
+        id: "openrouter",
+        name: "OpenRouter",
+        base_url: "https://openrouter.ai/api/v1",
+        auth_header: "Authorization",
+        auth_scheme: "Bearer",
+        models: &[
+            "openai/gpt-4o",
+            "openai/gpt-4o-mini",
+            "anthropic/claude-3.5-sonnet",
+            "google/gemini-2.0-flash-001",
+            "meta-llama/llama-3.3-70b-instruct",
+            "deepseek/deepseek-chat",
+        ],
+        unsupported_params: &[],
+        clamped_params: &[],
+        renamed_params: &[],
+        // OpenRouter 用于排行榜统计的应用标识
+        extra_headers: &[("X-Title", "AIOLauncher Server Trans")],
+        // 转发给 Anthropic / Gemini 时用于提示词缓存
+        cache_control: true,
+        legacy_completions: true,
+    },
+];
+
+pub fn find(id: &str) -> Option<&'static ProviderPreset> {
+    PRESETS.iter().find(|p| p.id.eq_ignore_ascii_case(id))
+}
+
+impl ProviderPreset {
+    pub fn auth_value(&self, api_key: &str) -> String {
+        if self.auth_scheme.is_empty() {
+            api_key.to_string()
+        } else {
+            format!("{} {}", self.auth_scheme, api_key)
+        }
+    }
+
+    /// 按预设改名参数; 目标参数已存在时保留客户端的值
+    pub fn rename_params(&self, body: &mut Value) {
+        let Some(obj) = body.as_object_mut() else {
+            return;
+        };
+        for (from, to) in self.renamed_params {
+            if let Some(value) = obj.remove(*from) {
+                obj.entry(*to).or_insert(value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_presets_are_unique_and_well_formed() {
+        for (i, preset) in PRESETS.iter().enumerate() {
+            assert!(PRESETS[i + 1..].iter().all(|p| p.id != preset.id), "duplicate preset {}", preset.id);
+            assert!(preset.base_url.starts_with("https://") && !preset.base_url.ends_with('/'));
+            assert!(!preset.models.is_empty());
+        }
+        assert_eq!(find("Groq").map(|p| p.name), Some("Groq"));
+    }
+
+    #[test]
+    fn test_rename_params() {
+        let mut body = json!({
+            "model": "mistral-small-latest",
+            "max_completion_tokens": 100,
+            "seed": 7,
+            "random_seed": 9
+        });
+        find("mistral").unwrap().rename_params(&mut body);
+        assert_eq!(body, json!({ "model": "mistral-small-latest", "max_tokens": 100, "random_seed": 9 }));
+    }
+}
@@ -6,7 +6,6 @@ use axum::{
 use bytes::Bytes;
 use futures::StreamExt;
 use serde_json::Value;
-use tokio::time::Duration;
 
 use crate::proxy::server::AppState;
 
@@ -46,26 +45,6 @@ fn join_base_url(base: &str, path: &str) -> Result<String, String> {
     Ok(format!("{}{}", base, path))
 }
 
-fn build_client(
-    upstream_proxy: Option<crate::proxy::config::UpstreamProxyConfig>,
-    timeout_secs: u64,
-) -> Result<reqwest::Client, String> {
-    let mut builder = reqwest::Client::builder()
-        .timeout(Duration::from_secs(timeout_secs.max(5)));
-
-    if let Some(config) = upstream_proxy {
-        if config.enabled && !config.url.is_empty() {
-            let proxy = reqwest::Proxy::all(&config.url)
-                .map_err(|e| format!("Invalid upstream proxy url: {}", e))?;
-            builder = builder.proxy(proxy);
-        }
-    }
-
-    builder
-        .tcp_nodelay(true) // [FIX #307] Disable Nagle's algorithm to improve latency for small requests
-        .build()
-        .map_err(|e| format!("Failed to build HTTP client: {}", e))
-}
 
 fn copy_passthrough_headers(incoming: &HeaderMap) -> HeaderMap {
     // Only forward a conservative set of headers to avoid leaking the local proxy key or cookies.
@@ -78,9 +57,16 @@ fn copy_passthrough_headers(incoming: &HeaderMap) -> HeaderMap {
                 out.insert(k.clone(), v.clone());
             }
             // Some clients use these for streaming; safe to pass through.
-            "accept-encoding" | "cache-control" => {
+            "cache-control" => {
                 out.insert(k.clone(), v.clone());
             }
+            // 只请求能够解压的编码, 压缩的响应由 upstream_decoding_middleware 解压
+            "accept-encoding" => {
+                out.insert(
+                    k.clone(),
+                    HeaderValue::from_static(crate::proxy::common::compression::SUPPORTED_ENCODINGS),
+                );
+            }
             _ => {}
         }
     }
@@ -130,6 +116,22 @@ pub fn deep_remove_cache_control(value: &mut Value) {
     }
 }
 
+/// 记录 z.ai Key 被拒绝 (鉴权失败或额度耗尽); Key 因此停用时发送通知
+pub(crate) fn report_key_rejection(state: &AppState, key: &str, status: StatusCode, body: &[u8]) {
+    let Some(failure) = crate::proxy::providers::key_pool::key_failure(status, body) else {
+        return;
+    };
+    if state.zai_keys.report_failure(key, failure) {
+        let hint = crate::modules::usage::mask_api_key(key);
+        crate::modules::notifications::notify(
+            crate::modules::notifications::NotificationKind::UpstreamKey,
+            &format!("zai:{}", hint),
+            "z.ai key disabled",
+            &format!("The z.ai key {} was disabled after repeated {} errors; requests now use the remaining keys", hint, status),
+        );
+    }
+}
+
 pub async fn forward_anthropic_json(
     state: &AppState,
     method: Method,
@@ -143,9 +145,14 @@ pub async fn forward_anthropic_json(
         return (StatusCode::BAD_REQUEST, "z.ai is disabled").into_response();
     }
 
-    if zai.api_key.trim().is_empty() {
-        return (StatusCode::BAD_REQUEST, "z.ai api_key is not set").into_response();
-    }
+    let key_pool = zai.key_pool();
+    let Some(mut key_lease) = state.zai_keys.acquire(&key_pool, zai.key_strategy) else {
+        if key_pool.is_empty() {
+            return (StatusCode::BAD_REQUEST, "z.ai api_key is not set").into_response();
+        }
+        return (StatusCode::SERVICE_UNAVAILABLE, "All z.ai API keys are disabled after repeated authentication or quota errors")
+            .into_response();
+    };
 
     if let Some(model) = body.get("model").and_then(|v| v.as_str()) {
         let mapped = map_model_for_zai(model, &zai);
@@ -168,14 +175,18 @@ pub async fn forward_anthropic_json(
     };
 
     let timeout_secs = state.request_timeout.max(5);
-    let upstream_proxy = state.upstream_proxy.read().await.clone();
-    let client = match build_client(Some(upstream_proxy), timeout_secs) {
+    // 共享客户端复用到 z.ai 的连接 (tcp_nodelay 等由连接池配置控制, 见 [FIX #307])
+    let client = match crate::proxy::handlers::common::provider_client(state, "zai", timeout_secs).await {
         Ok(c) => c,
         Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
     };
 
     let mut headers = copy_passthrough_headers(incoming_headers);
-    set_zai_auth(&mut headers, incoming_headers, &zai.api_key);
+    if let Some(request_id) = crate::proxy::middleware::request_id::current_request_id() {
+        if let Ok(v) = HeaderValue::from_str(&request_id) {
+            headers.insert(crate::proxy::middleware::request_id::REQUEST_ID_HEADER, v);
+        }
+    }
 
     // Ensure JSON content type.
     headers
@@ -191,37 +202,76 @@ pub async fn forward_anthropic_json(
 
     // [FIX #307] Explicitly serialize body to Vec<u8> to ensure Content-Length is set correctly.
     // This avoids "Transfer-Encoding: chunked" for small bodies which caused connection errors.
-    let body_bytes = serde_json::to_vec(&body).unwrap_or_default();
+    let body_bytes = Bytes::from(serde_json::to_vec(&body).unwrap_or_default());
     let body_len = body_bytes.len();
     
     tracing::debug!("Forwarding request to z.ai (len: {} bytes): {}", body_len, url);
 
-    let req = client.request(method, &url)
-        .headers(headers)
-        .body(body_bytes); // Use .body(Vec<u8>) instead of .json()
-
-    let resp = match req.send().await {
-        Ok(r) => r,
-        Err(e) => {
-            return (
-                StatusCode::BAD_GATEWAY,
-                format!("Upstream request failed: {}", e),
-            )
-                .into_response();
+    let mut tried: Vec<String> = Vec::new();
+    let (resp, status) = loop {
+        let mut key_headers = headers.clone();
+        set_zai_auth(&mut key_headers, incoming_headers, &key_lease.key);
+        let req = client.request(method.clone(), &url)
+            .headers(key_headers)
+            .body(body_bytes.clone()); // Use .body(Bytes) instead of .json()
+
+        let resp = match req.send().await {
+            Ok(r) => r,
+            Err(e) => {
+                return (
+                    StatusCode::BAD_GATEWAY,
+                    format!("Upstream request failed: {}", e),
+                )
+                    .into_response();
+            }
+        };
+
+        let status = StatusCode::from_u16(resp.status().as_u16()).unwrap_or(StatusCode::BAD_GATEWAY);
+        state.zai_keys.observe(&key_lease.key, status, resp.headers());
+        if !matches!(status.as_u16(), 401 | 402 | 403 | 429) {
+            break (resp, status);
         }
-    };
 
-    let status = StatusCode::from_u16(resp.status().as_u16()).unwrap_or(StatusCode::BAD_GATEWAY);
+        // Key 被拒绝或额度耗尽: 记录失败并换用下一个 Key 重试, 没有可用的 Key 时把上游错误返回给客户端
+        let content_type = resp.headers().get(header::CONTENT_TYPE).cloned();
+        let content_encoding = resp.headers().get(header::CONTENT_ENCODING).cloned();
+        let error_body = resp.bytes().await.unwrap_or_default();
+        report_key_rejection(state, &key_lease.key, status, &error_body);
+        tried.push(key_lease.key.clone());
+        match state.zai_keys.rotate(&key_pool, zai.key_strategy, &tried) {
+            Some(next) => {
+                tracing::info!("[z.ai] Upstream returned {}, retrying with another key", status);
+                key_lease = next;
+            }
+            None => {
+                let mut out = Response::builder().status(status).header("X-Upstream", "zai");
+                if let Some(ct) = content_type {
+                    out = out.header(header::CONTENT_TYPE, ct);
+                }
+                if let Some(ce) = content_encoding {
+                    out = out.header(header::CONTENT_ENCODING, ce);
+                }
+                return out.body(Body::from(error_body)).unwrap_or_else(|_| status.into_response());
+            }
+        }
+    };
 
-    let mut out = Response::builder().status(status);
+    let mut out = Response::builder().status(status).header("X-Upstream", "zai");
     if let Some(ct) = resp.headers().get(header::CONTENT_TYPE) {
         out = out.header(header::CONTENT_TYPE, ct.clone());
     }
+    if let Some(ce) = resp.headers().get(header::CONTENT_ENCODING) {
+        out = out.header(header::CONTENT_ENCODING, ce.clone());
+    }
 
     // Stream response body to the client (covers SSE and non-SSE).
-    let stream = resp.bytes_stream().map(|chunk| match chunk {
-        Ok(b) => Ok::<Bytes, std::io::Error>(b),
-        Err(e) => Ok(Bytes::from(format!("Upstream stream error: {}", e))),
+    // The key lease is held until the stream ends so least-in-flight sees the full request duration.
+    let stream = resp.bytes_stream().map(move |chunk| {
+        let _lease = &key_lease;
+        match chunk {
+            Ok(b) => Ok::<Bytes, std::io::Error>(b),
+            Err(e) => Ok(Bytes::from(format!("Upstream stream error: {}", e))),
+        }
     });
 
     out.body(Body::from_stream(stream)).unwrap_or_else(|_| {
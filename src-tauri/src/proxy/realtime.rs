@@ -0,0 +1,91 @@
+// Realtime API 会话状态
+// 保存透传配置并跟踪活动的 WebSocket 会话, 服务停止时通知所有会话正常关闭。
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::sync::watch;
+
+use crate::proxy::config::RealtimeConfig;
+
+pub struct RealtimeHub {
+    config: std::sync::RwLock<RealtimeConfig>,
+    /// 每次 `close_all` 递增, 会话监听变化后关闭
+    shutdown: watch::Sender<u64>,
+    active: AtomicUsize,
+}
+
+/// 活动会话的登记, 释放时自动减少计数
+pub struct RealtimeSessionGuard<'a> {
+    hub: &'a RealtimeHub,
+    pub shutdown: watch::Receiver<u64>,
+}
+
+impl Drop for RealtimeSessionGuard<'_> {
+    fn drop(&mut self) {
+        self.hub.active.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl RealtimeHub {
+    pub fn new(config: RealtimeConfig) -> Self {
+        Self {
+            config: std::sync::RwLock::new(config),
+            shutdown: watch::channel(0).0,
+            active: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn config(&self) -> RealtimeConfig {
+        self.config.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    pub fn update_config(&self, config: RealtimeConfig) {
+        let enabled = config.enabled;
+        *self.config.write().unwrap_or_else(|e| e.into_inner()) = config;
+        if !enabled {
+            self.close_all();
+        }
+    }
+
+    pub fn register(&self) -> RealtimeSessionGuard<'_> {
+        self.active.fetch_add(1, Ordering::Relaxed);
+        let mut shutdown = self.shutdown.subscribe();
+        shutdown.mark_unchanged();
+        RealtimeSessionGuard { hub: self, shutdown }
+    }
+
+    pub fn active_sessions(&self) -> usize {
+        self.active.load(Ordering::Relaxed)
+    }
+
+    /// 通知所有活动会话关闭
+    pub fn close_all(&self) {
+        let active = self.active_sessions();
+        self.shutdown.send_modify(|generation| *generation += 1);
+        if active > 0 {
+            tracing::info!("[Realtime] Closing {} active session(s)", active);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_close_all_notifies_registered_sessions() {
+        let hub = RealtimeHub::new(RealtimeConfig::default());
+        {
+            let mut session = hub.register();
+            assert_eq!(hub.active_sessions(), 1);
+            assert!(!session.shutdown.has_changed().unwrap());
+
+            hub.close_all();
+            session.shutdown.changed().await.unwrap();
+        }
+        assert_eq!(hub.active_sessions(), 0);
+
+        // 之前的关闭信号不影响新会话
+        let session = hub.register();
+        assert!(!session.shutdown.has_changed().unwrap());
+    }
+}
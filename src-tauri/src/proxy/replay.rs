@@ -0,0 +1,237 @@
+// 请求重放
+// 把请求日志中的请求重新发送 (可替换模型, 或发往其他 OpenAI 兼容上游), 比较两次响应的差异,
+// 用于排查协议转换回归和对比不同上游。比较前去掉 id、created 等每次都不同的字段。
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::proxy::monitor::ProxyRequestLog;
+
+/// 比较响应时忽略的字段
+const VOLATILE_KEYS: [&str; 6] = ["id", "created", "created_at", "system_fingerprint", "responseId", "createTime"];
+
+/// 超过该规模 (行数乘积) 的响应不逐行比较, 整体标记为替换
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReplayOptions {
+    /// 替换请求中的模型名 (Gemini 原生接口替换 URL 中的模型)
+    #[serde(default)]
+    pub model: Option<String>,
+    /// 发往其他上游 (例如 `https://api.groq.com/openai/v1`), 为空时发往本地反代
+    #[serde(default)]
+    pub base_url: Option<String>,
+    /// 其他上游的 API Key
+    #[serde(default)]
+    pub api_key: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffTag {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// 一行差异: delete 为原响应独有, insert 为重放响应独有
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DiffLine {
+    pub tag: DiffTag,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplayResult {
+    pub log_id: String,
+    pub url: String,
+    pub model: Option<String>,
+    pub status: u16,
+    pub original_status: u16,
+    pub duration: u64, // ms
+    pub original: String,
+    pub replayed: String,
+    pub identical: bool,
+    pub diff: Vec<DiffLine>,
+}
+
+/// 重放目标: 本地反代或其他上游
+pub struct ReplayTarget {
+    pub base_url: String,
+    pub api_key: String,
+    pub upstream_proxy: crate::proxy::config::UpstreamProxyConfig,
+}
+
+/// 替换请求体和 Gemini 原生路径中的模型
+pub(crate) fn apply_model(path: &str, body: &mut Value, model: &str) -> String {
+    if let Some(obj) = body.as_object_mut() {
+        if obj.contains_key("model") {
+            obj.insert("model".to_string(), Value::String(model.to_string()));
+        }
+    }
+    match path.split_once("/models/").and_then(|(prefix, rest)| Some((prefix, rest.split_once(':')?.1))) {
+        Some((prefix, action)) => format!("{}/models/{}:{}", prefix, model, action),
+        None => path.to_string(),
+    }
+}
+
+/// `base_url` 已包含 `/v1` 时不重复拼接
+pub(crate) fn join_url(base_url: &str, path: &str) -> String {
+    let base = base_url.trim_end_matches('/');
+    match path.strip_prefix("/v1/") {
+        Some(rest) if base.ends_with("/v1") => format!("{}/{}", base, rest),
+        _ => format!("{}{}", base, path),
+    }
+}
+
+fn strip_volatile(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            map.retain(|k, _| !VOLATILE_KEYS.contains(&k.as_str()));
+            map.values_mut().for_each(strip_volatile);
+        }
+        Value::Array(items) => items.iter_mut().for_each(strip_volatile),
+        _ => {}
+    }
+}
+
+/// JSON 去掉易变字段后格式化; 非 JSON 原样返回
+pub fn normalize(body: &str) -> String {
+    match serde_json::from_str::<Value>(body) {
+        Ok(mut json) => {
+            strip_volatile(&mut json);
+            serde_json::to_string_pretty(&json).unwrap_or_else(|_| body.to_string())
+        }
+        Err(_) => body.to_string(),
+    }
+}
+
+/// 按行比较 (LCS)
+pub fn diff_lines(original: &str, replayed: &str) -> Vec<DiffLine> {
+    let a: Vec<&str> = original.lines().collect();
+    let b: Vec<&str> = replayed.lines().collect();
+    let line = |tag, text: &str| DiffLine { tag, text: text.to_string() };
+    if a.len().saturating_mul(b.len()) > MAX_DIFF_CELLS {
+        return a.iter().map(|t| line(DiffTag::Delete, t)).chain(b.iter().map(|t| line(DiffTag::Insert, t))).collect();
+    }
+
+    // lcs[i][j] = a[i..] 与 b[j..] 的最长公共子序列长度
+    let mut lcs = vec![vec![0u32; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] { lcs[i + 1][j + 1] + 1 } else { lcs[i + 1][j].max(lcs[i][j + 1]) };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    let mut out = Vec::new();
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            out.push(line(DiffTag::Equal, a[i]));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            out.push(line(DiffTag::Delete, a[i]));
+            i += 1;
+        } else {
+            out.push(line(DiffTag::Insert, b[j]));
+            j += 1;
+        }
+    }
+    out.extend(a[i..].iter().map(|t| line(DiffTag::Delete, t)));
+    out.extend(b[j..].iter().map(|t| line(DiffTag::Insert, t)));
+    out
+}
+
+/// 重新发送日志中的请求并与原响应比较
+pub async fn replay(log: &ProxyRequestLog, target: ReplayTarget, options: &ReplayOptions) -> Result<ReplayResult, String> {
+    let raw = log.request_body.as_deref().ok_or("This request has no logged body to replay")?;
+    let mut body: Value =
+        serde_json::from_str(raw).map_err(|e| format!("Logged request body is not JSON and cannot be replayed: {}", e))?;
+    let mut path = log.url.clone();
+    if let Some(model) = options.model.as_deref().filter(|m| !m.is_empty()) {
+        path = apply_model(&path, &mut body, model);
+    }
+    let url = join_url(&target.base_url, &path);
+    let model = body.get("model").and_then(|m| m.as_str()).map(String::from).or_else(|| options.model.clone()).or_else(|| log.model.clone());
+
+    let client = crate::proxy::handlers::common::build_upstream_client(target.upstream_proxy, 300)?;
+    let mut req = client.request(
+        reqwest::Method::from_bytes(log.method.as_bytes()).unwrap_or(reqwest::Method::POST),
+        &url,
+    );
+    req = req.header("Content-Type", "application/json").header("X-Replay-Of", log.id.as_str());
+    if !target.api_key.is_empty() {
+        req = req
+            .header("Authorization", format!("Bearer {}", target.api_key))
+            .header("x-api-key", target.api_key.as_str());
+    }
+
+    tracing::info!("[Replay] {} -> {} (model: {:?})", log.id, url, model);
+    let start = std::time::Instant::now();
+    let resp = req.body(body.to_string()).send().await.map_err(|e| format!("Replay request failed: {}", e))?;
+    let status = resp.status().as_u16();
+    let is_stream = resp
+        .headers()
+        .get("content-type")
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.contains("text/event-stream"));
+    let text = resp.text().await.map_err(|e| format!("Failed to read replay response: {}", e))?;
+    let duration = start.elapsed().as_millis() as u64;
+
+    // 流式响应按 monitor 的方式合并, 与日志中保存的格式一致
+    let replayed = if is_stream {
+        crate::proxy::middleware::monitor::consolidate_stream(&text).0
+    } else {
+        text
+    };
+    let original = normalize(log.response_body.as_deref().unwrap_or(""));
+    let replayed = normalize(&replayed);
+    let diff = diff_lines(&original, &replayed);
+    Ok(ReplayResult {
+        log_id: log.id.clone(),
+        url,
+        model,
+        status,
+        original_status: log.status,
+        duration,
+        identical: diff.iter().all(|d| d.tag == DiffTag::Equal),
+        original,
+        replayed,
+        diff,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_model_override_and_url_join() {
+        let mut body = json!({"model": "gpt-4o", "messages": []});
+        assert_eq!(apply_model("/v1/chat/completions", &mut body, "llama-3.3-70b-versatile"), "/v1/chat/completions");
+        assert_eq!(body["model"], "llama-3.3-70b-versatile");
+
+        let mut gemini = json!({"contents": []});
+        assert_eq!(
+            apply_model("/v1beta/models/gemini-2.5-flash:streamGenerateContent?alt=sse", &mut gemini, "gemini-2.5-pro"),
+            "/v1beta/models/gemini-2.5-pro:streamGenerateContent?alt=sse"
+        );
+        assert!(gemini.get("model").is_none());
+
+        assert_eq!(join_url("https://api.groq.com/openai/v1/", "/v1/chat/completions"), "https://api.groq.com/openai/v1/chat/completions");
+        assert_eq!(join_url("http://127.0.0.1:8045", "/v1/messages"), "http://127.0.0.1:8045/v1/messages");
+    }
+
+    #[test]
+    fn test_normalize_and_diff() {
+        let original = normalize(r#"{"id":"chatcmpl-1","created":1,"choices":[{"message":{"content":"hi"}}],"model":"a"}"#);
+        let same = normalize(r#"{"id":"chatcmpl-2","created":2,"choices":[{"message":{"content":"hi"}}],"model":"a"}"#);
+        assert!(diff_lines(&original, &same).iter().all(|d| d.tag == DiffTag::Equal));
+
+        let diff = diff_lines("a\nb\nc", "a\nx\nc");
+        let tags: Vec<DiffTag> = diff.iter().map(|d| d.tag).collect();
+        assert_eq!(tags, vec![DiffTag::Equal, DiffTag::Delete, DiffTag::Insert, DiffTag::Equal]);
+        assert_eq!(diff[2].text, "x");
+    }
+}
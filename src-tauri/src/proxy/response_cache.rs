@@ -0,0 +1,237 @@
+// 相同请求的响应缓存
+// 以 (路径, 客户端 Key, 去掉 `stream` 的完整请求体) 的哈希为键, 在 TTL 内直接返回缓存的非流式响应。
+
+use bytes::Bytes;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::proxy::config::ResponseCacheConfig;
+
+/// 缓存命中时需要还原的响应头
+const CACHED_HEADERS: [&str; 2] = ["content-type", "x-mapped-model"];
+
+#[derive(Debug, Clone)]
+pub struct CachedResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Bytes,
+}
+
+impl CachedResponse {
+    pub fn new(status: u16, headers: &axum::http::HeaderMap, body: Bytes) -> Self {
+        let headers = CACHED_HEADERS
+            .iter()
+            .filter_map(|name| {
+                headers
+                    .get(*name)
+                    .and_then(|v| v.to_str().ok())
+                    .map(|v| (name.to_string(), v.to_string()))
+            })
+            .collect();
+        Self { status, headers, body }
+    }
+}
+
+struct CacheEntry {
+    response: CachedResponse,
+    inserted_at: Instant,
+}
+
+/// 计算缓存键, 流式请求或无法识别的请求返回 None
+/// `owner` 为客户端 Key 的所有者 (见 `auth::key_owner`), 不同 Key 的缓存互不可见
+pub fn cache_key(path: &str, owner: &str, body: &Value) -> Option<String> {
+    if path.contains("streamGenerateContent") || body.get("stream").and_then(|v| v.as_bool()).unwrap_or(false) {
+        return None;
+    }
+    // OpenAI / Claude: messages, Gemini: contents, Responses / Completions: input / prompt
+    if !["messages", "contents", "input", "prompt"].iter().any(|k| body.get(*k).is_some()) {
+        return None;
+    }
+    let mut normalized = body.clone();
+    if let Some(obj) = normalized.as_object_mut() {
+        obj.remove("stream");
+    }
+
+    // Gemini 的 model 在路径中, 路径本身参与哈希; serde_json 的对象按键排序序列化, 字段顺序不影响结果
+    let material = json!({
+        "path": path,
+        "owner": owner,
+        "body": normalized,
+    });
+    Some(format!("{:x}", Sha256::digest(material.to_string().as_bytes())))
+}
+
+pub struct ResponseCache {
+    config: std::sync::RwLock<ResponseCacheConfig>,
+    entries: Mutex<HashMap<String, CacheEntry>>,
+}
+
+impl ResponseCache {
+    pub fn new(config: ResponseCacheConfig) -> Self {
+        Self {
+            config: std::sync::RwLock::new(config),
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn config(&self) -> ResponseCacheConfig {
+        self.config.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.read().unwrap_or_else(|e| e.into_inner()).enabled
+    }
+
+    pub fn update_config(&self, config: ResponseCacheConfig) {
+        let enabled = config.enabled;
+        *self.config.write().unwrap_or_else(|e| e.into_inner()) = config;
+        if !enabled {
+            self.clear();
+        }
+    }
+
+    pub fn max_body_bytes(&self) -> usize {
+        (self.config().max_body_kb.max(1) * 1024) as usize
+    }
+
+    pub fn get(&self, key: &str) -> Option<CachedResponse> {
+        self.get_at(key, Instant::now())
+    }
+
+    fn get_at(&self, key: &str, now: Instant) -> Option<CachedResponse> {
+        let ttl = Duration::from_secs(self.config().ttl_secs);
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        match entries.get(key) {
+            Some(entry) if now.duration_since(entry.inserted_at) < ttl => Some(entry.response.clone()),
+            Some(_) => {
+                entries.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub fn insert(&self, key: String, response: CachedResponse) {
+        self.insert_at(key, response, Instant::now());
+    }
+
+    fn insert_at(&self, key: String, response: CachedResponse, now: Instant) {
+        let config = self.config();
+        if config.max_entries == 0 {
+            return;
+        }
+        let ttl = Duration::from_secs(config.ttl_secs);
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if entries.len() >= config.max_entries && !entries.contains_key(&key) {
+            entries.retain(|_, e| now.duration_since(e.inserted_at) < ttl);
+            while entries.len() >= config.max_entries {
+                let oldest = entries
+                    .iter()
+                    .min_by_key(|(_, e)| e.inserted_at)
+                    .map(|(k, _)| k.clone());
+                match oldest {
+                    Some(k) => entries.remove(&k),
+                    None => break,
+                };
+            }
+        }
+        entries.insert(key, CacheEntry { response, inserted_at: now });
+    }
+
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    /// 清空缓存, 返回清除的条目数
+    pub fn clear(&self) -> usize {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let count = entries.len();
+        entries.clear();
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache(max_entries: usize) -> ResponseCache {
+        ResponseCache::new(ResponseCacheConfig {
+            enabled: true,
+            ttl_secs: 60,
+            max_entries,
+            ..Default::default()
+        })
+    }
+
+    fn response(body: &'static str) -> CachedResponse {
+        CachedResponse {
+            status: 200,
+            headers: Vec::new(),
+            body: Bytes::from_static(body.as_bytes()),
+        }
+    }
+
+    #[test]
+    fn test_cache_key() {
+        let body = json!({"model": "gpt-4o", "messages": [{"role": "user", "content": "hi"}], "temperature": 0});
+        let path = "/v1/chat/completions";
+        let key = cache_key(path, "a", &body).unwrap();
+        assert_eq!(cache_key(path, "a", &body).as_deref(), Some(key.as_str()));
+        // 不同客户端 Key 互不命中
+        assert_ne!(cache_key(path, "b", &body).unwrap(), key);
+
+        // 请求体中的任意字段都参与缓存键
+        for (field, value) in [
+            ("temperature", json!(0.7)),
+            ("max_tokens", json!(16)),
+            ("top_p", json!(0.5)),
+            ("response_format", json!({"type": "json_object"})),
+            ("n", json!(2)),
+            ("stop", json!(["\n"])),
+            ("seed", json!(1)),
+            ("tool_choice", json!("auto")),
+        ] {
+            let mut other = body.clone();
+            other[field] = value;
+            assert_ne!(cache_key(path, "a", &other).unwrap(), key, "{}", field);
+        }
+        // stream: false 与省略等价
+        let mut other = body.clone();
+        other["stream"] = json!(false);
+        assert_eq!(cache_key(path, "a", &other).unwrap(), key);
+
+        let mut stream = body.clone();
+        stream["stream"] = json!(true);
+        assert_eq!(cache_key(path, "a", &stream), None);
+        assert_eq!(cache_key("/v1beta/models/gemini-2.5-flash:streamGenerateContent", "a", &json!({"contents": []})), None);
+        assert_eq!(cache_key(path, "a", &json!({"model": "gpt-4o"})), None);
+    }
+
+    #[test]
+    fn test_ttl_expiry() {
+        let cache = cache(10);
+        let now = Instant::now();
+        cache.insert_at("a".to_string(), response("x"), now);
+        assert!(cache.get_at("a", now + Duration::from_secs(59)).is_some());
+        assert!(cache.get_at("a", now + Duration::from_secs(60)).is_none());
+        assert_eq!(cache.len(), 0);
+    }
+
+    #[test]
+    fn test_evicts_oldest_when_full() {
+        let cache = cache(2);
+        let now = Instant::now();
+        cache.insert_at("a".to_string(), response("1"), now);
+        cache.insert_at("b".to_string(), response("2"), now + Duration::from_secs(1));
+        cache.insert_at("c".to_string(), response("3"), now + Duration::from_secs(2));
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get_at("a", now).is_none());
+        assert!(cache.get_at("c", now + Duration::from_secs(2)).is_some());
+        assert_eq!(cache.clear(), 2);
+    }
+}
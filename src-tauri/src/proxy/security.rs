@@ -1,20 +1,124 @@
-use crate::proxy::config::{ProxyAuthMode, ProxyConfig};
+use axum::http::Method;
+
+use crate::proxy::config::{AdminRole, AdminToken, ProxyAuthMode, ProxyConfig};
 
 #[derive(Debug, Clone)]
 pub struct ProxySecurityConfig {
     pub auth_mode: ProxyAuthMode,
     pub api_key: String,
     pub admin_password: Option<String>,
+    pub admin_tokens: Vec<AdminToken>,
     pub allow_lan_access: bool,
     pub port: u16,
 }
 
+/// 通过管理接口鉴权的身份, 由 admin_auth 中间件写入请求扩展
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdminIdentity {
+    pub name: String,
+    pub role: AdminRole,
+}
+
+/// viewer 可读取的接口 (统计、日志、状态); 未列出的接口 (包括新增接口) 默认仅 admin 可用
+const VIEWER_READ_ROUTES: &[&str] = &[
+    "/api/health",
+    "/api/accounts",
+    "/api/accounts/current",
+    "/api/accounts/:accountId/quota",
+    "/api/accounts/:accountId/device-profiles",
+    "/api/accounts/:accountId/device-versions",
+    "/api/stats/summary",
+    "/api/stats/hourly",
+    "/api/stats/daily",
+    "/api/stats/weekly",
+    "/api/stats/accounts",
+    "/api/stats/models",
+    "/api/stats/token/summary",
+    "/api/stats/token/hourly",
+    "/api/stats/token/daily",
+    "/api/stats/token/weekly",
+    "/api/stats/token/by-account",
+    "/api/stats/token/by-model",
+    "/api/stats/token/model-trend/hourly",
+    "/api/stats/token/model-trend/daily",
+    "/api/stats/token/account-trend/hourly",
+    "/api/stats/token/account-trend/daily",
+    "/api/proxy/status",
+    "/api/proxy/stats",
+    "/api/proxy/latency",
+    "/api/proxy/shadow",
+    "/api/proxy/upstream-health",
+    "/api/proxy/key-check",
+    "/api/proxy/disabled-keys",
+    "/api/proxy/provider-presets",
+    "/api/proxy/preferred-account",
+    "/api/proxy/cloudflared/status",
+    "/api/logs",
+    "/api/logs/count",
+    "/api/logs/:logId",
+    "/api/audit",
+    "/api/system/data-dir",
+    "/api/system/updates/settings",
+    "/api/system/updates/check-status",
+    "/api/system/autostart/status",
+    "/api/system/http-api/settings",
+    "/api/system/antigravity/path",
+    "/api/system/antigravity/args",
+];
+
+/// operator 额外可读取的接口; 返回前清空其中的密钥 (管理密码、admin_tokens、上游 Key 等)
+const OPERATOR_READ_ROUTES: &[&str] = &["/api/config", "/api/proxy/config", "/api/proxy/config/:section"];
+
+/// 不修改状态的 POST 接口, viewer 也可调用
+const VIEWER_POST_ROUTES: &[&str] = &["/api/config/validate", "/api/proxy/cli/status"];
+
+/// operator 允许的运维操作; 其他变更 (配置、上游、密钥、账号增删) 仅 admin 可用
+const OPERATOR_ROUTES: &[&str] = &[
+    "/api/proxy/start",
+    "/api/proxy/stop",
+    "/api/proxy/session-bindings/clear",
+    "/api/proxy/rate-limits",
+    "/api/proxy/rate-limits/:accountId",
+    "/api/proxy/preferred-account",
+    "/api/proxy/monitor/toggle",
+    "/api/accounts/switch",
+    "/api/accounts/refresh",
+    "/api/accounts/warmup",
+    "/api/accounts/:accountId/warmup",
+    "/api/accounts/:accountId/toggle-proxy",
+    "/api/accounts/reorder",
+    "/api/logs/clear",
+    "/api/zai/models/fetch",
+];
+
+impl AdminRole {
+    /// 角色是否允许调用给定路由 (`route` 为匹配到的路由模板)
+    pub fn permits(self, method: &Method, route: &str) -> bool {
+        if self == AdminRole::Admin || *method == Method::OPTIONS {
+            return true;
+        }
+        let allowed: &[&[&str]] = match (self, matches!(*method, Method::GET | Method::HEAD)) {
+            (AdminRole::Operator, true) => &[VIEWER_READ_ROUTES, OPERATOR_READ_ROUTES],
+            (AdminRole::Operator, false) => &[VIEWER_POST_ROUTES, OPERATOR_ROUTES],
+            (_, true) => &[VIEWER_READ_ROUTES],
+            (_, false) => &[VIEWER_POST_ROUTES],
+        };
+        allowed.iter().any(|routes| routes.contains(&route))
+    }
+
+    /// 读取配置时是否需要清空密钥
+    pub fn redacts_secrets(self) -> bool {
+        self != AdminRole::Admin
+    }
+}
+
 impl ProxySecurityConfig {
     pub fn from_proxy_config(config: &ProxyConfig) -> Self {
         Self {
             auth_mode: config.auth_mode.clone(),
             api_key: config.api_key.clone(),
             admin_password: config.admin_password.clone(),
+            admin_tokens: config.admin_tokens.iter().filter(|t| t.enabled && !t.token.is_empty()).cloned().collect(),
             allow_lan_access: config.allow_lan_access,
             port: config.port,
         }
@@ -32,6 +136,28 @@ impl ProxySecurityConfig {
             ref other => other.clone(),
         }
     }
+
+    /// 是否配置了任何管理凭证
+    pub fn has_admin_credentials(&self) -> bool {
+        !self.api_key.is_empty()
+            || self.admin_password.as_deref().is_some_and(|p| !p.is_empty())
+            || !self.admin_tokens.is_empty()
+    }
+
+    /// 识别管理接口凭证: admin_password (未设置时回退 api_key) 为 admin, 其次按 admin_tokens 匹配角色
+    pub fn identify_admin(&self, key: &str) -> Option<AdminIdentity> {
+        let master = match self.admin_password.as_deref() {
+            Some(pwd) if !pwd.is_empty() => pwd,
+            _ => self.api_key.as_str(),
+        };
+        if !master.is_empty() && key == master {
+            return Some(AdminIdentity { name: "admin".to_string(), role: AdminRole::Admin });
+        }
+        self.admin_tokens
+            .iter()
+            .find(|t| t.enabled && !t.token.is_empty() && t.token == key)
+            .map(|t| AdminIdentity { name: t.name.clone(), role: t.role })
+    }
 }
 
 #[cfg(test)]
@@ -44,6 +170,7 @@ mod tests {
             auth_mode: ProxyAuthMode::Auto,
             api_key: "sk-test".to_string(),
             admin_password: None,
+            admin_tokens: Vec::new(),
             allow_lan_access: false,
             port: 8080,
         };
@@ -56,6 +183,7 @@ mod tests {
             auth_mode: ProxyAuthMode::Auto,
             api_key: "sk-test".to_string(),
             admin_password: None,
+            admin_tokens: Vec::new(),
             allow_lan_access: true,
             port: 8080,
         };
@@ -64,5 +192,43 @@ mod tests {
             ProxyAuthMode::AllExceptHealth
         ));
     }
+
+    #[test]
+    fn admin_tokens_resolve_roles() {
+        let token = |name: &str, role| AdminToken { name: name.to_string(), token: format!("tok-{}", name), role, enabled: true };
+        let s = ProxySecurityConfig {
+            auth_mode: ProxyAuthMode::Strict,
+            api_key: "sk-test".to_string(),
+            admin_password: Some("admin-pass".to_string()),
+            admin_tokens: vec![token("dash", AdminRole::Viewer), token("ops", AdminRole::Operator)],
+            allow_lan_access: true,
+            port: 8080,
+        };
+        assert_eq!(s.identify_admin("admin-pass").unwrap().role, AdminRole::Admin);
+        assert_eq!(s.identify_admin("sk-test"), None);
+        let viewer = s.identify_admin("tok-dash").unwrap();
+        assert_eq!((viewer.name.as_str(), viewer.role), ("dash", AdminRole::Viewer));
+        assert_eq!(s.identify_admin("tok-ops").unwrap().role, AdminRole::Operator);
+
+        assert!(AdminRole::Viewer.permits(&Method::GET, "/api/stats/summary"));
+        assert!(AdminRole::Viewer.permits(&Method::POST, "/api/config/validate"));
+        assert!(!AdminRole::Viewer.permits(&Method::GET, "/api/config"));
+        assert!(!AdminRole::Viewer.permits(&Method::POST, "/api/proxy/api-key/generate"));
+        assert!(!AdminRole::Viewer.permits(&Method::POST, "/api/proxy/stop"));
+        assert!(AdminRole::Operator.permits(&Method::POST, "/api/proxy/stop"));
+        // 未列入白名单的读取接口默认拒绝
+        assert!(!AdminRole::Viewer.permits(&Method::GET, "/api/auth/url"));
+        assert!(!AdminRole::Operator.permits(&Method::GET, "/api/auth/url"));
+        assert!(!AdminRole::Viewer.permits(&Method::GET, "/api/proxy/config/:section"));
+        assert!(!AdminRole::Operator.permits(&Method::POST, "/api/proxy/cli/config"));
+        // operator 读取的配置会清空密钥
+        assert!(AdminRole::Operator.permits(&Method::GET, "/api/config"));
+        assert!(AdminRole::Operator.redacts_secrets() && AdminRole::Viewer.redacts_secrets());
+        assert!(!AdminRole::Admin.redacts_secrets());
+        assert!(!AdminRole::Operator.permits(&Method::POST, "/api/config"));
+        assert!(!AdminRole::Operator.permits(&Method::POST, "/api/proxy/api-key/generate"));
+        assert!(!AdminRole::Operator.permits(&Method::PATCH, "/api/proxy/config"));
+        assert!(AdminRole::Admin.permits(&Method::POST, "/api/proxy/api-key/generate"));
+    }
 }
 
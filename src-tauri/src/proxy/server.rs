@@ -1,6 +1,6 @@
 use crate::proxy::TokenManager;
 use axum::{
-    extract::{DefaultBodyLimit, Path, State, Query},
+    extract::{DefaultBodyLimit, Extension, Path, State, Query},
     http::{StatusCode, HeaderMap},
     response::{IntoResponse, Json, Response, Html},
     routing::{any, get, post, delete},
@@ -21,23 +21,55 @@ use crate::models::{Account, AppConfig, QuotaData, DeviceProfile};
 pub struct AppState {
     pub token_manager: Arc<TokenManager>,
     pub custom_mapping: Arc<tokio::sync::RwLock<std::collections::HashMap<String, String>>>,
+    pub model_aliases: Arc<crate::proxy::common::model_alias::ModelAliasTable>, // 模型别名规则
     #[allow(dead_code)]
     pub request_timeout: u64, // API 请求超时(秒)
     #[allow(dead_code)]
     pub thought_signature_map: Arc<tokio::sync::Mutex<std::collections::HashMap<String, String>>>, // 思维链签名映射 (ID -> Signature)
     #[allow(dead_code)]
     pub upstream_proxy: Arc<tokio::sync::RwLock<crate::proxy::config::UpstreamProxyConfig>>,
+    pub provider_proxies: Arc<RwLock<Vec<crate::proxy::config::ProviderProxyRule>>>, // 按上游覆盖出站代理
+    pub provider_tls: Arc<RwLock<Vec<crate::proxy::config::ProviderTlsRule>>>, // 按上游的 TLS 选项
+    pub listener_certs: Arc<crate::proxy::common::tls_cert::ListenerCerts>, // 本地监听的 HTTPS 证书 (可热替换)
     pub upstream: Arc<crate::proxy::upstream::client::UpstreamClient>,
     pub zai: Arc<RwLock<crate::proxy::ZaiConfig>>,
     pub provider_rr: Arc<AtomicUsize>,
+    pub zai_keys: Arc<crate::proxy::providers::key_pool::KeyPool>, // z.ai 多 Key 负载均衡
     pub zai_vision_mcp: Arc<crate::proxy::zai_vision_mcp::ZaiVisionMcpState>,
     pub monitor: Arc<crate::proxy::monitor::ProxyMonitor>,
     pub experimental: Arc<RwLock<crate::proxy::config::ExperimentalConfig>>,
+    pub stream_transform: Arc<RwLock<crate::proxy::config::StreamTransformConfig>>, // 流式响应改写
+    pub embeddings: Arc<RwLock<crate::proxy::config::EmbeddingsConfig>>, // Embeddings 上游映射
+    pub images: Arc<RwLock<crate::proxy::config::ImagesConfig>>, // 图像生成上游与本地保存
+    pub audio: Arc<RwLock<crate::proxy::config::AudioConfig>>, // 音频透传
+    pub ollama: Arc<RwLock<crate::proxy::config::OllamaConfig>>, // 本地 Ollama
+    pub azure_openai: Arc<RwLock<crate::proxy::config::AzureOpenAIConfig>>, // Azure OpenAI 部署
+    pub bedrock: Arc<RwLock<crate::proxy::config::BedrockConfig>>, // AWS Bedrock
+    pub compatible_upstreams: Arc<RwLock<crate::proxy::config::CompatibleUpstreamsConfig>>, // OpenAI 兼容上游
+    pub param_policy: Arc<RwLock<crate::proxy::config::ParamPolicyConfig>>, // 请求参数清理
+    pub content_filter: Arc<crate::proxy::common::content_filter::ContentFilter>, // 内容过滤规则
+    pub pii_redaction: Arc<RwLock<crate::proxy::config::PiiRedactionConfig>>, // 个人信息脱敏
+    pub system_prompt: Arc<RwLock<crate::proxy::config::SystemPromptConfig>>, // 系统提示词注入
+    pub context_window: Arc<RwLock<crate::proxy::config::ContextWindowConfig>>, // 上下文窗口保护
+    pub max_tokens: Arc<RwLock<crate::proxy::config::MaxTokensConfig>>, // max_tokens 自动调整
+    pub body_limits: Arc<RwLock<crate::proxy::config::BodyLimitConfig>>, // 请求体大小限制
+    pub compression: Arc<RwLock<crate::proxy::config::CompressionConfig>>, // 响应压缩
+    pub pricing: Arc<RwLock<crate::proxy::config::PricingConfig>>, // 模型价格表
     pub debug_logging: Arc<RwLock<crate::proxy::config::DebugLoggingConfig>>,
     pub switching: Arc<RwLock<bool>>, // [NEW] 账号切换状态，用于防止并发切换
     pub integration: crate::modules::integration::SystemManager, // [NEW] 系统集成层实现
     pub account_service: Arc<crate::modules::account_service::AccountService>, // [NEW] 账号管理服务层
     pub security: Arc<RwLock<crate::proxy::ProxySecurityConfig>>, // [NEW] 安全配置状态
+    pub client_rate_limiter: Arc<crate::proxy::middleware::rate_limit::ClientRateLimiter>, // 客户端限流
+    pub access_control: Arc<crate::proxy::middleware::access_control::AccessControl>, // IP 访问控制
+    pub concurrency: Arc<crate::proxy::upstream::concurrency::ConcurrencyLimiter>, // 上游并发限制
+    pub health_checker: Arc<crate::proxy::upstream::health_check::HealthChecker>, // 上游健康检查
+    pub model_catalog: Arc<crate::proxy::model_catalog::ModelCatalog>, // /v1/models 聚合
+    pub body_logger: Arc<crate::proxy::body_logger::BodyLogStore>, // 请求/响应 Body 日志
+    pub response_cache: Arc<crate::proxy::response_cache::ResponseCache>, // 响应缓存
+    pub conversation_store: Arc<crate::proxy::conversation_store::ConversationStore>, // 服务端会话记忆
+    pub realtime: Arc<crate::proxy::realtime::RealtimeHub>, // Realtime WebSocket 会话
+    pub drain: Arc<crate::proxy::middleware::drain::RequestDrain>, // 优雅停机 (进行中的请求)
     pub cloudflared_state: Arc<crate::commands::cloudflared::CloudflaredState>, // [NEW] Cloudflared 插件状态
     pub is_running: Arc<RwLock<bool>>, // [NEW] 运行状态标识
     pub port: u16, // [NEW] 本地监听端口 (v4.0.8 修复)
@@ -122,16 +154,115 @@ fn to_account_response(account: &crate::models::account::Account, current_id: &O
     }
 }
 
+/// 监听证书剩余有效期不足该天数时提醒
+const CERT_EXPIRY_WARN_DAYS: i64 = 14;
+const CERT_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(12 * 3600);
+
+/// 定期检查监听证书的到期时间, 即将过期或已过期时发送桌面通知
+async fn watch_cert_expiry(cert_path: String) {
+    loop {
+        let not_after = std::fs::read_to_string(&cert_path)
+            .map_err(|e| e.to_string())
+            .and_then(|pem| crate::proxy::common::tls_cert::not_after(&pem));
+        match not_after {
+            Ok(not_after) => {
+                let days_left = (not_after - chrono::Utc::now().timestamp()).div_euclid(86_400);
+                if days_left < CERT_EXPIRY_WARN_DAYS {
+                    let body = if days_left < 0 {
+                        format!("The TLS certificate {} has expired", cert_path)
+                    } else {
+                        format!("The TLS certificate {} expires in {} day(s)", cert_path, days_left)
+                    };
+                    tracing::warn!("[TLS] {}", body);
+                    crate::modules::notifications::notify(
+                        crate::modules::notifications::NotificationKind::CertExpiry,
+                        &cert_path,
+                        "TLS certificate expiring",
+                        &body,
+                    );
+                }
+            }
+            Err(e) => tracing::warn!("[TLS] 无法读取证书 {} 的有效期: {}", cert_path, e),
+        }
+        tokio::time::sleep(CERT_CHECK_INTERVAL).await;
+    }
+}
+
+/// 连续接收连接失败达到该次数时视为监听失效
+const MAX_ACCEPT_FAILURES: u32 = 50;
+
+/// 接收连接并交给路由处理 (直到任务被取消); 监听失效时返回失败原因, 由监督任务重启服务
+async fn serve_listener(
+    listener: tokio::net::TcpListener,
+    app: Router,
+    tls: Option<Arc<crate::proxy::common::tls_cert::ListenerCerts>>,
+) -> String {
+    use hyper::server::conn::http1;
+    use hyper_util::rt::TokioIo;
+
+    let mut failures = 0u32;
+    loop {
+        let (stream, remote_addr) = match listener.accept().await {
+            Ok(conn) => {
+                failures = 0;
+                conn
+            }
+            Err(e) => {
+                error!("接收连接失败: {:?}", e);
+                failures += 1;
+                if failures >= MAX_ACCEPT_FAILURES {
+                    return format!("listener failed after {} consecutive accept errors: {}", failures, e);
+                }
+                // 文件描述符耗尽等错误会立即重复出现, 稍作等待
+                tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+                continue;
+            }
+        };
+        // 注入连接地址, 供客户端限流识别 IP
+        let app = app.clone();
+        let service = hyper::service::service_fn(move |mut req: axum::http::Request<hyper::body::Incoming>| {
+            req.extensions_mut().insert(axum::extract::ConnectInfo(remote_addr));
+            tower::Service::call(&mut app.clone(), req)
+        });
+
+        // 每个连接使用当前证书, 热替换后的新连接立即生效
+        let tls_acceptor = tls.as_ref().and_then(|certs| certs.current());
+        tokio::task::spawn(async move {
+            let result = match tls_acceptor {
+                Some(acceptor) => match acceptor.accept(stream).await {
+                    Ok(tls_stream) => {
+                        http1::Builder::new()
+                            .serve_connection(TokioIo::new(tls_stream), service)
+                            .with_upgrades()
+                            .await
+                    }
+                    Err(e) => {
+                        debug!("TLS 握手失败 ({}): {}", remote_addr, e);
+                        return;
+                    }
+                },
+                None => {
+                    http1::Builder::new()
+                        .serve_connection(TokioIo::new(stream), service)
+                        .with_upgrades() // 支持 WebSocket (如果以后需要)
+                        .await
+                }
+            };
+            if let Err(err) = result {
+                debug!("连接处理结束或出错: {:?}", err);
+            }
+        });
+    }
+}
+
 /// Axum 服务器实例
 #[derive(Clone)]
 pub struct AxumServer {
     shutdown_tx: Arc<tokio::sync::Mutex<Option<oneshot::Sender<()>>>>,
-    custom_mapping: Arc<tokio::sync::RwLock<std::collections::HashMap<String, String>>>,
-    proxy_state: Arc<tokio::sync::RwLock<crate::proxy::config::UpstreamProxyConfig>>,
-    security_state: Arc<RwLock<crate::proxy::ProxySecurityConfig>>,
-    zai_state: Arc<RwLock<crate::proxy::ZaiConfig>>,
-    experimental: Arc<RwLock<crate::proxy::config::ExperimentalConfig>>,
-    debug_logging: Arc<RwLock<crate::proxy::config::DebugLoggingConfig>>,
+    /// 实际绑定的主监听端口 (端口回退后可能与配置不同)
+    bound_port: u16,
+    /// 与请求处理共用的应用状态, 热更新直接作用于此
+    state: AppState,
     pub cloudflared_state: Arc<crate::commands::cloudflared::CloudflaredState>,
     pub is_running: Arc<RwLock<bool>>,
 }
@@ -139,107 +270,250 @@ pub struct AxumServer {
 impl AxumServer {
     pub async fn update_mapping(&self, config: &crate::proxy::config::ProxyConfig) {
         {
-            let mut m = self.custom_mapping.write().await;
+            let mut m = self.state.custom_mapping.write().await;
             *m = config.custom_mapping.clone();
         }
         tracing::debug!("模型映射 (Custom) 已全量热更新");
     }
 
-    /// 更新代理配置
-    pub async fn update_proxy(&self, new_config: crate::proxy::config::UpstreamProxyConfig) {
-        let mut proxy = self.proxy_state.write().await;
-        *proxy = new_config;
-        tracing::info!("上游代理配置已热更新");
+    pub fn update_model_aliases(&self, config: &crate::proxy::config::ProxyConfig) {
+        self.state.model_aliases.update_rules(config.model_aliases.clone());
+        tracing::debug!("模型别名规则已热更新");
     }
 
-    pub async fn update_security(&self, config: &crate::proxy::config::ProxyConfig) {
-        let mut sec = self.security_state.write().await;
-        *sec = crate::proxy::ProxySecurityConfig::from_proxy_config(config);
-        tracing::info!("反代服务安全配置已热更新");
+    /// 正在处理的客户端请求数
+    pub fn active_requests(&self) -> usize {
+        self.state.monitor.active_requests()
     }
 
-    pub async fn update_zai(&self, config: &crate::proxy::config::ProxyConfig) {
-        let mut zai = self.zai_state.write().await;
-        *zai = config.zai.clone();
-        tracing::info!("z.ai 配置已热更新");
+    /// 各上游的排队状态
+    pub fn queue_status(&self) -> Vec<crate::proxy::upstream::concurrency::QueueStatus> {
+        self.state.concurrency.snapshot()
     }
 
-    pub async fn update_experimental(&self, config: &crate::proxy::config::ProxyConfig) {
-        let mut exp = self.experimental.write().await;
-        *exp = config.experimental.clone();
-        tracing::info!("实验性配置已热更新");
+    pub fn update_client_rate_limit(&self, config: &crate::proxy::config::ProxyConfig) {
+        self.state.client_rate_limiter.update_config(config.client_rate_limit.clone());
+        tracing::info!("客户端限流配置已热更新");
+    }
+
+    pub fn update_access_control(&self, config: &crate::proxy::config::ProxyConfig) {
+        self.state.access_control.update_config(&config.access_control, &config.listeners);
+        tracing::info!("IP 访问控制规则已热更新");
+    }
+
+    /// 各上游的健康检查结果
+    pub fn upstream_health(&self) -> Vec<crate::proxy::upstream::health_check::UpstreamHealth> {
+        self.state.health_checker.snapshot()
+    }
+
+    /// 因连续鉴权 / 额度错误停用的 z.ai Key
+    pub fn disabled_keys(&self) -> Vec<crate::proxy::providers::key_pool::DisabledKey> {
+        self.state.zai_keys.disabled()
+    }
+
+    /// 恢复停用的 z.ai Key
+    pub fn enable_key(&self, key_id: &str) -> bool {
+        self.state.zai_keys.enable(key_id)
+    }
+
+    /// 一次性热更新全部代理配置 (保存配置与配置文件热加载共用); 证书无法加载时返回错误, 配置不会生效
+    pub async fn apply_config(&self, config: &crate::proxy::config::ProxyConfig) -> Result<(), String> {
+        apply_proxy_config(&self.state, config).await
     }
 
-    pub async fn update_debug_logging(&self, config: &crate::proxy::config::ProxyConfig) {
-        let mut dbg_cfg = self.debug_logging.write().await;
-        *dbg_cfg = config.debug_logging.clone();
-        tracing::info!("调试日志配置已热更新");
+    /// 清空响应缓存, 返回清除的条目数
+    pub fn clear_response_cache(&self) -> usize {
+        self.state.response_cache.clear()
+    }
+
+    pub fn list_conversation_sessions(&self) -> Vec<crate::proxy::conversation_store::ConversationSessionInfo> {
+        self.state.conversation_store.list()
+    }
+
+    pub fn get_conversation_session(&self, id: &str) -> Option<crate::proxy::conversation_store::ConversationSessionDetail> {
+        self.state.conversation_store.get(id)
+    }
+
+    /// 删除指定会话 (None 时删除全部), 返回删除的会话数
+    pub fn clear_conversation_sessions(&self, id: Option<&str>) -> usize {
+        self.state.conversation_store.clear(id)
+    }
+
+    pub fn client_rate_limit_config(&self) -> crate::proxy::config::ClientRateLimitConfig {
+        self.state.client_rate_limiter.config()
+    }
+
+    pub fn model_alias_rules(&self) -> Vec<crate::proxy::config::ModelAliasRule> {
+        self.state.model_aliases.rules()
+    }
+
+    pub async fn is_running(&self) -> bool {
+        *self.is_running.read().await
+    }
+
+    pub fn bound_port(&self) -> u16 {
+        self.bound_port
     }
 
     pub async fn set_running(&self, running: bool) {
         let mut r = self.is_running.write().await;
         *r = running;
+        if !running {
+            // 服务停止时关闭所有 Realtime 会话
+            self.state.realtime.close_all();
+        }
         tracing::info!("反代服务运行状态更新为: {}", running);
     }
 
-    /// 启动 Axum 服务器
+    /// 启动 Axum 服务器; `config` 为生效方案覆盖后的代理配置, 各组件的初始状态都从中读取
     pub async fn start(
-        host: String,
-        port: u16,
+        config: &crate::proxy::config::ProxyConfig,
         token_manager: Arc<TokenManager>,
-        custom_mapping: std::collections::HashMap<String, String>,
-        _request_timeout: u64,
-        upstream_proxy: crate::proxy::config::UpstreamProxyConfig,
-        security_config: crate::proxy::ProxySecurityConfig,
-        zai_config: crate::proxy::ZaiConfig,
         monitor: Arc<crate::proxy::monitor::ProxyMonitor>,
-        experimental_config: crate::proxy::config::ExperimentalConfig,
-        debug_logging: crate::proxy::config::DebugLoggingConfig,
         integration: crate::modules::integration::SystemManager,
         cloudflared_state: Arc<crate::commands::cloudflared::CloudflaredState>,
-    ) -> Result<(Self, tokio::task::JoinHandle<()>), String> {
-        let custom_mapping_state = Arc::new(tokio::sync::RwLock::new(custom_mapping));
-	        let proxy_state = Arc::new(tokio::sync::RwLock::new(upstream_proxy.clone()));
-	        let security_state = Arc::new(RwLock::new(security_config));
-	        let zai_state = Arc::new(RwLock::new(zai_config));
+    ) -> Result<(Self, tokio::task::JoinHandle<Result<(), String>>), String> {
+        // 先绑定主监听: 端口回退后状态与响应中的端口以实际绑定的为准
+        let host = config.get_bind_address().to_string();
+        let listener = crate::utils::ports::bind_with_fallback(&host, config.port, config.port_fallback, "proxy")
+            .await
+            .map_err(|e| format!("地址 {}:{} 绑定失败: {}", host, config.port, e))?;
+        let port = listener.local_addr().map(|a| a.port()).unwrap_or(config.port);
+        let custom_mapping_state = Arc::new(tokio::sync::RwLock::new(config.custom_mapping.clone()));
+        let model_aliases_state = Arc::new(crate::proxy::common::model_alias::ModelAliasTable::new(config.model_aliases.clone()));
+	        let proxy_state = Arc::new(tokio::sync::RwLock::new(config.upstream_proxy.clone()));
+	        let provider_proxies_state = Arc::new(RwLock::new(config.provider_proxies.clone()));
+	        let provider_tls_state = Arc::new(RwLock::new(config.provider_tls.clone()));
+	        let security_state = Arc::new(RwLock::new(crate::proxy::ProxySecurityConfig::from_proxy_config(config)));
+	        crate::proxy::jwt_auth::configure(&config.jwt_auth);
+        crate::proxy::webhooks::configure(&config.webhooks);
+        crate::proxy::smart_routing::configure(&config.smart_routing);
+        crate::proxy::shadow::configure(&config.shadow);
+        crate::proxy::hedging::configure(&config.hedging);
+        crate::proxy::council::configure(&config.council);
+        crate::proxy::structured_output::configure(&config.structured_output);
+        crate::proxy::tool_emulation::configure(&config.tool_emulation);
+        crate::proxy::batches::configure(&config.batches);
+        crate::proxy::files::configure(&config.files);
+        crate::proxy::assistants::configure(&config.assistants);
+        crate::proxy::moderation::configure(&config.moderation);
+        crate::proxy::handlers::rerank::configure(&config.rerank);
+        crate::proxy::llama_server::configure(&config.llama_server);
+	        crate::modules::notifications::set_integration(integration.clone());
+	        let zai_state = Arc::new(RwLock::new(config.zai.clone()));
 	        let provider_rr = Arc::new(AtomicUsize::new(0));
 	        let zai_vision_mcp_state =
 	            Arc::new(crate::proxy::zai_vision_mcp::ZaiVisionMcpState::new());
-	        let experimental_state = Arc::new(RwLock::new(experimental_config));
-            let debug_logging_state = Arc::new(RwLock::new(debug_logging));
+	        let experimental_state = Arc::new(RwLock::new(config.experimental.clone()));
+	        let stream_transform_state = Arc::new(RwLock::new(config.stream_transform.clone()));
+	        let embeddings_state = Arc::new(RwLock::new(config.embeddings.clone()));
+	        let images_state = Arc::new(RwLock::new(config.images.clone()));
+	        let audio_state = Arc::new(RwLock::new(config.audio.clone()));
+	        let ollama_state = Arc::new(RwLock::new(config.ollama.clone()));
+	        let azure_openai_state = Arc::new(RwLock::new(config.azure_openai.clone()));
+	        let bedrock_state = Arc::new(RwLock::new(config.bedrock.clone()));
+	        let compatible_upstreams_state = Arc::new(RwLock::new(config.compatible_upstreams.clone()));
+	        let zai_keys = Arc::new(crate::proxy::providers::key_pool::KeyPool::new());
+	        let param_policy_state = Arc::new(RwLock::new(config.param_policy.clone()));
+	        let content_filter_state = Arc::new(crate::proxy::common::content_filter::ContentFilter::new(&config.content_filter));
+	        let pii_redaction_state = Arc::new(RwLock::new(config.pii_redaction.clone()));
+	        let system_prompt_state = Arc::new(RwLock::new(config.system_prompt.clone()));
+	        let context_window_state = Arc::new(RwLock::new(config.context_window.clone()));
+	        let max_tokens_state = Arc::new(RwLock::new(config.max_tokens.clone()));
+	        let body_limits_state = Arc::new(RwLock::new(config.body_limits.clone()));
+	        let compression_state = Arc::new(RwLock::new(config.compression.clone()));
+	        let pricing_state = Arc::new(RwLock::new(config.pricing.clone()));
+            let debug_logging_state = Arc::new(RwLock::new(config.debug_logging.clone()));
             let is_running_state = Arc::new(RwLock::new(true));
+            let client_rate_limiter = Arc::new(
+                crate::proxy::middleware::rate_limit::ClientRateLimiter::new(config.client_rate_limit.clone()),
+            );
+            let access_control = Arc::new(
+                crate::proxy::middleware::access_control::AccessControl::new(&config.access_control, &config.listeners),
+            );
+            let concurrency_limiter = Arc::new(
+                crate::proxy::upstream::concurrency::ConcurrencyLimiter::new(config.concurrency.clone()),
+            );
+            let body_logger = Arc::new(crate::proxy::body_logger::BodyLogStore::new(config.body_logging.clone()));
+            let response_cache = Arc::new(crate::proxy::response_cache::ResponseCache::new(config.response_cache.clone()));
+            let conversation_store = Arc::new(crate::proxy::conversation_store::ConversationStore::new(config.conversation_memory.clone()));
+            let realtime_hub = Arc::new(crate::proxy::realtime::RealtimeHub::new(config.realtime.clone()));
+            let request_drain = Arc::new(crate::proxy::middleware::drain::RequestDrain::new(config.drain_timeout_secs));
+            // v1internal 客户端的代理与 TLS 在启动时确定 (上游标识 google)
+            let upstream_client = Arc::new(crate::proxy::upstream::client::UpstreamClient::new(
+                Some(crate::proxy::upstream::client_pool::resolve_proxy(&config.provider_proxies, "google", &config.upstream_proxy)),
+                crate::proxy::upstream::client_pool::resolve_tls(&config.provider_tls, "google"),
+            ));
+            upstream_client.update_routing(config.upstream_routing.clone());
+            upstream_client.update_connection_pool(config.connection_pool.clone());
+            let health_checker = Arc::new(crate::proxy::upstream::health_check::HealthChecker::new(config.health_check.clone()));
+            let model_catalog = Arc::new(crate::proxy::model_catalog::ModelCatalog::new(config.model_catalog.clone()));
 
 	        let state = AppState {
 	            token_manager: token_manager.clone(),
 	            custom_mapping: custom_mapping_state.clone(),
+	            model_aliases: model_aliases_state.clone(),
 	            request_timeout: 300, // 5分钟超时
             thought_signature_map: Arc::new(tokio::sync::Mutex::new(
                 std::collections::HashMap::new(),
             )),
             upstream_proxy: proxy_state.clone(),
-            upstream: Arc::new(crate::proxy::upstream::client::UpstreamClient::new(Some(
-                upstream_proxy.clone(),
-            ))),
+            provider_proxies: provider_proxies_state.clone(),
+            provider_tls: provider_tls_state.clone(),
+            listener_certs: Arc::new(crate::proxy::common::tls_cert::ListenerCerts::default()),
+            upstream: upstream_client.clone(),
             zai: zai_state.clone(),
             provider_rr: provider_rr.clone(),
+            zai_keys: zai_keys.clone(),
             zai_vision_mcp: zai_vision_mcp_state,
             monitor: monitor.clone(),
             experimental: experimental_state.clone(),
+            stream_transform: stream_transform_state.clone(),
+            embeddings: embeddings_state.clone(),
+            images: images_state.clone(),
+            audio: audio_state.clone(),
+            ollama: ollama_state.clone(),
+            azure_openai: azure_openai_state.clone(),
+            bedrock: bedrock_state.clone(),
+            compatible_upstreams: compatible_upstreams_state.clone(),
+            param_policy: param_policy_state.clone(),
+            content_filter: content_filter_state.clone(),
+            pii_redaction: pii_redaction_state.clone(),
+            system_prompt: system_prompt_state.clone(),
+            context_window: context_window_state.clone(),
+            max_tokens: max_tokens_state.clone(),
+            body_limits: body_limits_state.clone(),
+            compression: compression_state.clone(),
+            pricing: pricing_state.clone(),
             debug_logging: debug_logging_state.clone(),
             switching: Arc::new(RwLock::new(false)),
             integration: integration.clone(),
             account_service: Arc::new(crate::modules::account_service::AccountService::new(integration.clone())),
             security: security_state.clone(),
+            client_rate_limiter: client_rate_limiter.clone(),
+            access_control: access_control.clone(),
+            concurrency: concurrency_limiter.clone(),
+            health_checker: health_checker.clone(),
+            model_catalog: model_catalog.clone(),
+            body_logger: body_logger.clone(),
+            response_cache: response_cache.clone(),
+            conversation_store: conversation_store.clone(),
+            realtime: realtime_hub.clone(),
+            drain: request_drain.clone(),
             cloudflared_state: cloudflared_state.clone(),
             is_running: is_running_state.clone(),
             port,
         };
+        health_checker.start(state.clone());
+        // 继续执行重启前未完成的批次
+        crate::proxy::batches::start(state.port, state.request_timeout);
+        crate::proxy::llama_server::start();
 
 
         // 构建路由 - 使用新架构的 handlers！
         use crate::proxy::handlers;
         use crate::proxy::middleware::{
-            auth_middleware, admin_auth_middleware, monitor_middleware, 
+            auth_middleware, admin_auth_middleware, monitor_middleware, rate_limit_middleware, request_id_middleware, response_cache_middleware, concurrency_middleware, content_filter_middleware, pii_redaction_middleware, system_prompt_middleware, context_window_middleware, conversation_memory_middleware, body_limit_middleware, response_compression_middleware, upstream_decoding_middleware, stream_bridge_middleware, stream_watchdog_middleware, stream_transform_middleware, body_logging_middleware, listener_policy_middleware, drain_middleware, audit_middleware, access_control_middleware, smart_routing_middleware, shadow_middleware, shadow_scope_middleware, council_middleware, prompt_template_middleware, structured_output_middleware, tool_emulation_middleware, file_reference_middleware, model_fallback_middleware, hedging_middleware,
             service_status_middleware, cors_layer
         };
 
@@ -255,7 +529,7 @@ impl AxumServer {
                 "/v1/completions",
                 post(handlers::openai::handle_completions),
             )
-            .route("/v1/responses", post(handlers::openai::handle_completions)) // 兼容 Codex CLI
+            .route("/v1/responses", post(handlers::openai::handle_responses)) // 兼容 Codex CLI
             .route(
                 "/v1/images/generations",
                 post(handlers::openai::handle_images_generations),
@@ -268,6 +542,62 @@ impl AxumServer {
                 "/v1/audio/transcriptions",
                 post(handlers::audio::handle_audio_transcription),
             ) // 音频转录 API
+            .route("/v1/audio/speech", post(handlers::audio::handle_audio_speech)) // 语音合成 API (透传)
+            .route("/v1/embeddings", post(handlers::embeddings::handle_embeddings)) // Embeddings API
+            .route("/v1/moderations", post(handlers::moderations::handle_moderations)) // Moderations API
+            .route("/v1/rerank", post(handlers::rerank::handle_rerank)) // Rerank API
+            .route(
+                "/v1/batches",
+                post(handlers::batches::handle_create_batch).get(handlers::batches::handle_list_batches),
+            )
+            .route("/v1/batches/:batch_id", get(handlers::batches::handle_get_batch))
+            .route("/v1/batches/:batch_id/cancel", post(handlers::batches::handle_cancel_batch))
+            .route("/v1/batches/:batch_id/output", get(handlers::batches::handle_batch_output))
+            .route("/v1/batches/:batch_id/errors", get(handlers::batches::handle_batch_errors))
+            .route(
+                "/v1/files",
+                post(handlers::files::handle_upload_file).get(handlers::files::handle_list_files),
+            )
+            .route(
+                "/v1/files/:file_id",
+                get(handlers::files::handle_get_file).delete(handlers::files::handle_delete_file),
+            )
+            .route("/v1/files/:file_id/content", get(handlers::files::handle_file_content))
+            // Assistants API
+            .route(
+                "/v1/assistants",
+                post(handlers::assistants::handle_create_assistant).get(handlers::assistants::handle_list_assistants),
+            )
+            .route(
+                "/v1/assistants/:assistant_id",
+                get(handlers::assistants::handle_get_assistant)
+                    .post(handlers::assistants::handle_update_assistant)
+                    .delete(handlers::assistants::handle_delete_assistant),
+            )
+            .route("/v1/threads", post(handlers::assistants::handle_create_thread))
+            .route("/v1/threads/runs", post(handlers::assistants::handle_create_thread_and_run))
+            .route(
+                "/v1/threads/:thread_id",
+                get(handlers::assistants::handle_get_thread)
+                    .post(handlers::assistants::handle_update_thread)
+                    .delete(handlers::assistants::handle_delete_thread),
+            )
+            .route(
+                "/v1/threads/:thread_id/messages",
+                post(handlers::assistants::handle_create_message).get(handlers::assistants::handle_list_messages),
+            )
+            .route("/v1/threads/:thread_id/messages/:message_id", get(handlers::assistants::handle_get_message))
+            .route(
+                "/v1/threads/:thread_id/runs",
+                post(handlers::assistants::handle_create_run).get(handlers::assistants::handle_list_runs),
+            )
+            .route("/v1/threads/:thread_id/runs/:run_id", get(handlers::assistants::handle_get_run))
+            .route("/v1/threads/:thread_id/runs/:run_id/cancel", post(handlers::assistants::handle_cancel_run))
+            .route(
+                "/v1/threads/:thread_id/runs/:run_id/submit_tool_outputs",
+                post(handlers::assistants::handle_submit_tool_outputs),
+            )
+            .route("/v1/realtime", get(handlers::realtime::handle_realtime)) // Realtime API (WebSocket)
             // Claude Protocol
             .route("/v1/messages", post(handlers::claude::handle_messages))
             .route(
@@ -303,12 +633,61 @@ impl AxumServer {
                 post(handlers::gemini::handle_count_tokens),
             ) // Specific route priority
             .route("/v1/models/detect", post(handlers::common::handle_detect_model))
+            .route("/v1/token_count", post(handlers::common::handle_token_count)) // 本地 token 计数
             .route("/internal/warmup", post(handlers::warmup::handle_warmup)) // 内部预热端点
             .route("/v1/api/event_logging/batch", post(silent_ok_handler))
             .route("/v1/api/event_logging", post(silent_ok_handler))
-            // 应用 AI 服务特定的层
+            .route("/metrics", get(metrics_handler)) // Prometheus 指标
+            // 应用 AI 服务特定的层 (限流位于鉴权之后, 缓存命中同样计入限流; 缓存命中不占用上游并发名额)
+            // 上游压缩响应在最内层解压, 其余中间件只处理明文
+            .layer(axum::middleware::from_fn(upstream_decoding_middleware))
+            // 桥接紧贴处理器: 外层中间件看到的都是客户端期望的流式格式
+            .layer(axum::middleware::from_fn(stream_bridge_middleware))
+            .layer(axum::middleware::from_fn_with_state(state.clone(), stream_watchdog_middleware))
+            .layer(axum::middleware::from_fn_with_state(state.clone(), concurrency_middleware))
+            // 结构化输出位于缓存之内: 缓存校验通过后的响应, 每次重试重新占用并发名额
+            .layer(axum::middleware::from_fn(structured_output_middleware))
+            // 函数调用模拟位于结构化输出之外: 两者同时生效时, 校验的是带工具说明的请求的文本回复
+            .layer(axum::middleware::from_fn(tool_emulation_middleware))
+            .layer(axum::middleware::from_fn_with_state(state.clone(), response_cache_middleware))
+            // 上下文检查 (含 max_tokens 调整) 位于缓存之外: 调整后的请求才参与缓存, 被拒绝的请求不占用缓存与并发
+            .layer(axum::middleware::from_fn_with_state(state.clone(), context_window_middleware))
+            // 注入位于脱敏与过滤之内 (配置的提示词不经过它们), 位于上下文检查之外 (提示词计入 token)
+            .layer(axum::middleware::from_fn_with_state(state.clone(), system_prompt_middleware))
+            // 影子流量位于脱敏与过滤之内: 复制过滤、脱敏后的请求体, 第三方影子上游收不到原文
+            .layer(axum::middleware::from_fn_with_state(state.clone(), shadow_middleware))
+            // 脱敏位于内容过滤之内: 过滤规则检查原文, 响应先还原占位符再过滤
+            .layer(axum::middleware::from_fn_with_state(state.clone(), pii_redaction_middleware))
+            // 内容过滤位于缓存之外: 缓存键基于脱敏后的请求, 缓存命中的响应同样经过过滤
+            .layer(axum::middleware::from_fn_with_state(state.clone(), content_filter_middleware))
+            // 备用模型链位于内容过滤之外: 每次尝试重新经过过滤、上下文检查与缓存
+            .layer(axum::middleware::from_fn_with_state(state.clone(), model_fallback_middleware))
+            // 文件引用位于会话记忆之内: 会话保存文件 id, 展开后的内容同样经过过滤与上下文检查
+            .layer(axum::middleware::from_fn(file_reference_middleware))
+            // 会话记忆位于内容过滤与脱敏之外: 保存客户端原文, 补上的历史同样经过过滤、脱敏和上下文检查
+            .layer(axum::middleware::from_fn_with_state(state.clone(), conversation_memory_middleware))
+            // 智能路由位于鉴权与限流之内: 模型白名单和限流按客户端请求的别名判断
+            .layer(axum::middleware::from_fn_with_state(state.clone(), smart_routing_middleware))
+            // 对冲位于智能路由之外: 备用请求同样经过智能路由、备用模型链与并发排队, 缓存命中的请求不对冲
+            .layer(axum::middleware::from_fn(hedging_middleware))
+            // 影子复制标记位于对冲之外: 对冲的备用请求与备用模型链的重试只复制一次
+            .layer(axum::middleware::from_fn(shadow_scope_middleware))
+            // 聚合路由位于影子流量之外: 聚合请求本身不复制, 成员请求经本地反代时按各自的模型处理
+            .layer(axum::middleware::from_fn_with_state(state.clone(), council_middleware))
+            .layer(axum::middleware::from_fn_with_state(state.clone(), rate_limit_middleware))
             .layer(axum::middleware::from_fn_with_state(state.clone(), auth_middleware))
-            .layer(axum::middleware::from_fn_with_state(state.clone(), monitor_middleware));
+            .layer(axum::middleware::from_fn_with_state(state.clone(), monitor_middleware))
+            // 模板展开位于 monitor 之外: 日志与模型白名单看到的是展开后的请求和去掉前缀的模型名
+            .layer(axum::middleware::from_fn(prompt_template_middleware))
+            // 改写位于 monitor 之外: 监控记录的是上游原始 usage, Body 日志记录的是客户端实际收到的内容
+            .layer(axum::middleware::from_fn_with_state(state.clone(), stream_transform_middleware))
+            .layer(axum::middleware::from_fn_with_state(state.clone(), body_logging_middleware))
+            // 压缩位于 Body 日志之外: 日志记录明文
+            .layer(axum::middleware::from_fn_with_state(state.clone(), response_compression_middleware))
+            // 大小限制位于所有缓冲请求体的中间件之外, 超限请求尽早拒绝
+            .layer(axum::middleware::from_fn_with_state(state.clone(), body_limit_middleware))
+            // 请求 ID 位于最外层, 所有内层日志都处于同一 span
+            .layer(axum::middleware::from_fn(request_id_middleware));
 
         // 2. 构建管理 API (强制鉴权)
         let admin_routes = Router::new()
@@ -346,6 +725,12 @@ impl AxumServer {
             .route("/stats/accounts", get(admin_get_token_stats_by_account))
             .route("/stats/models", get(admin_get_token_stats_by_model))
             .route("/config", get(admin_get_config).post(admin_save_config))
+            .route("/config/validate", post(admin_validate_config))
+            .route("/proxy/config", get(admin_get_proxy_config).patch(admin_patch_proxy_config))
+            .route(
+                "/proxy/config/:section",
+                get(admin_get_proxy_config_section).put(admin_put_proxy_config_section),
+            )
             .route("/proxy/cli/status", post(admin_get_cli_sync_status))
             .route("/proxy/cli/sync", post(admin_execute_cli_sync))
             .route("/proxy/cli/restore", post(admin_execute_cli_restore))
@@ -375,6 +760,14 @@ impl AxumServer {
             .route("/proxy/cloudflared/stop", post(admin_cloudflared_stop))
             .route("/system/open-folder", post(admin_open_folder))
             .route("/proxy/stats", get(admin_get_proxy_stats))
+            .route("/proxy/latency", get(admin_get_latency_stats))
+            .route("/proxy/shadow", get(admin_get_shadow_results).delete(admin_clear_shadow_results))
+            .route("/proxy/compare", post(admin_compare_models))
+            .route("/proxy/upstream-health", get(admin_get_upstream_health))
+            .route("/proxy/key-check", get(admin_get_key_check_results).post(admin_check_upstream_keys))
+            .route("/proxy/disabled-keys", get(admin_get_disabled_keys))
+            .route("/proxy/disabled-keys/enable", post(admin_enable_disabled_key))
+            .route("/proxy/provider-presets", get(admin_get_provider_presets))
             .route("/logs", get(admin_get_proxy_logs_filtered))
             .route("/logs/count", get(admin_get_proxy_logs_count_filtered))
             .route("/logs/clear", post(admin_clear_proxy_logs))
@@ -425,6 +818,9 @@ impl AxumServer {
             .route("/system/antigravity/args", get(admin_get_antigravity_args))
             // OAuth (Web) - Admin 接口
             .route("/auth/url", get(admin_prepare_oauth_url_web))
+            .route("/audit", get(admin_get_audit_log))
+            // 审计位于鉴权之内, 只记录通过鉴权的变更请求
+            .layer(axum::middleware::from_fn(audit_middleware))
             // 应用管理特定鉴权层 (强制校验)
             .layer(axum::middleware::from_fn_with_state(state.clone(), admin_auth_middleware));
 
@@ -441,6 +837,8 @@ impl AxumServer {
             .merge(proxy_routes)
             // 公开路由 (无需鉴权)
             .route("/auth/callback", get(handle_oauth_callback))
+            // 停机排空位于所有中间件之外: 终止事件追加在客户端实际收到的流之后
+            .layer(axum::middleware::from_fn_with_state(state.clone(), drain_middleware))
             // 应用全局监控与状态层 (外层)
             .layer(axum::middleware::from_fn_with_state(state.clone(), service_status_middleware))
             .layer(cors_layer())
@@ -461,77 +859,132 @@ impl AxumServer {
 
         // 绑定地址
         let addr = format!("{}:{}", host, port);
-        let listener = tokio::net::TcpListener::bind(&addr)
-            .await
-            .map_err(|e| format!("地址 {} 绑定失败: {}", addr, e))?;
+        // 主监听与额外监听共用同一份证书
+        let listener_tls = &config.listener_tls;
+        let tls_used = listener_tls.enabled || config.listeners.iter().any(|l| l.enabled && l.tls);
+        if tls_used {
+            state.listener_certs.load(listener_tls)?;
+        }
+        let tls_for = |enabled: bool| enabled.then(|| state.listener_certs.clone());
+
+        let scheme = if listener_tls.enabled { "https" } else { "http" };
+        tracing::info!("反代服务器启动在 {}://{}", scheme, addr);
+
+        // 额外监听: 共享路由, 外层套上各自的访问策略
+        let mut extra_listeners = Vec::new();
+        for extra_config in config.listeners.iter().filter(|l| l.enabled) {
+            let name = extra_config.label();
+            let addr = format!("{}:{}", extra_config.host, extra_config.port);
+            let tls = tls_for(extra_config.tls);
+            let listener = tokio::net::TcpListener::bind(&addr).await.map_err(|e| match e.kind() {
+                std::io::ErrorKind::AddrInUse => {
+                    format!("监听 {} 的地址 {} 绑定失败: {}", name, addr, crate::utils::ports::describe_conflict(extra_config.port))
+                }
+                _ => format!("监听 {} 的地址 {} 绑定失败: {}", name, addr, e),
+            })?;
+            let policy = Arc::new(crate::proxy::middleware::listener::ListenerPolicy {
+                name: name.clone(),
+                require_auth: extra_config.require_auth,
+                allow_admin: extra_config.allow_admin,
+                security: state.security.clone(),
+            });
+            let gate = Arc::new(crate::proxy::middleware::access_control::AccessGate {
+                listener: name.clone(),
+                control: access_control.clone(),
+            });
+            let app = app
+                .clone()
+                .layer(axum::middleware::from_fn_with_state(policy, listener_policy_middleware))
+                .layer(axum::middleware::from_fn_with_state(gate, access_control_middleware));
+            tracing::info!(
+                "额外监听 {} 启动在 {}://{} (强制鉴权: {}, 管理接口: {})",
+                name,
+                if tls.is_some() { "https" } else { "http" },
+                addr,
+                extra_config.require_auth,
+                extra_config.allow_admin
+            );
+            extra_listeners.push((name, listener, app, tls));
+        }
+
+        // 主监听的 IP 访问控制 (额外监听已各自套上)
+        let app = app.layer(axum::middleware::from_fn_with_state(
+            Arc::new(crate::proxy::middleware::access_control::AccessGate {
+                listener: crate::proxy::middleware::access_control::MAIN_LISTENER.to_string(),
+                control: access_control.clone(),
+            }),
+            access_control_middleware,
+        ));
+
+        // 局域网服务发布: 仅在主监听对局域网开放时启用, 发布失败不影响服务启动
+        let mdns_advertiser = if config.mdns.enabled && host == "0.0.0.0" {
+            match crate::proxy::mdns::MdnsAdvertiser::start(&config.mdns, port, listener_tls.enabled) {
+                Ok(advertiser) => Some(advertiser),
+                Err(e) => {
+                    tracing::warn!("mDNS 服务发布失败: {}", e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
 
-        tracing::info!("反代服务器启动在 http://{}", addr);
+        // 监听证书到期提醒
+        let cert_watch = tls_used.then(|| tokio::spawn(watch_cert_expiry(listener_tls.cert_path.trim().to_string())));
 
         // 创建关闭通道
         let (shutdown_tx, mut shutdown_rx) = oneshot::channel::<()>();
 
         let server_instance = Self {
             shutdown_tx: Arc::new(tokio::sync::Mutex::new(Some(shutdown_tx))),
-            custom_mapping: custom_mapping_state.clone(),
-            proxy_state,
-            security_state,
-            zai_state,
-            experimental: experimental_state.clone(),
-            debug_logging: debug_logging_state.clone(),
+            bound_port: port,
+            state: state.clone(),
             cloudflared_state,
             is_running: is_running_state,
         };
 
-        // 在新任务中启动服务器
+        // 在新任务中启动服务器; 任一监听失效时返回失败原因, 停止时同时关闭所有额外监听
+        let main_tls = tls_for(listener_tls.enabled);
         let handle = tokio::spawn(async move {
-            use hyper::server::conn::http1;
-            use hyper_util::rt::TokioIo;
-            use hyper_util::service::TowerToHyperService;
-
-            loop {
-                tokio::select! {
-                    res = listener.accept() => {
-                        match res {
-                            Ok((stream, _)) => {
-                                let io = TokioIo::new(stream);
-                                let service = TowerToHyperService::new(app.clone());
-
-                                tokio::task::spawn(async move {
-                                    if let Err(err) = http1::Builder::new()
-                                        .serve_connection(io, service)
-                                        .with_upgrades() // 支持 WebSocket (如果以后需要)
-                                        .await
-                                    {
-                                        debug!("连接处理结束或出错: {:?}", err);
-                                    }
-                                });
-                            }
-                            Err(e) => {
-                                error!("接收连接失败: {:?}", e);
-                            }
-                        }
-                    }
-                    _ = &mut shutdown_rx => {
-                        tracing::info!("反代服务器停止监听");
-                        break;
-                    }
+            let mut extra: futures::stream::FuturesUnordered<_> = extra_listeners
+                .into_iter()
+                .map(|(name, listener, app, tls)| async move { (name, serve_listener(listener, app, tls).await) })
+                .collect();
+            let result = tokio::select! {
+                reason = serve_listener(listener, app, main_tls) => Err(reason),
+                Some((name, reason)) = futures::StreamExt::next(&mut extra) => Err(format!("{}: {}", name, reason)),
+                _ = &mut shutdown_rx => {
+                    tracing::info!("反代服务器停止监听");
+                    Ok(())
                 }
+            };
+            drop(extra);
+            if let Some(task) = cert_watch {
+                task.abort();
+            }
+            if let Some(advertiser) = mdns_advertiser {
+                advertiser.stop().await;
             }
+            result
         });
 
         Ok((server_instance, handle))
     }
 
-    /// 停止服务器
-    pub fn stop(&self) {
-        let tx_mutex = self.shutdown_tx.clone();
-        tokio::spawn(async move {
-            let mut lock = tx_mutex.lock().await;
-            if let Some(tx) = lock.take() {
-                let _ = tx.send(());
-                tracing::info!("Axum server 停止信号已发送");
-            }
-        });
+    /// 停止服务器: 不再接收新连接与新请求, 等待进行中的请求结束 (最多 drain_timeout_secs) 后返回
+    pub async fn stop(&self) {
+        self.state.realtime.close_all();
+        crate::proxy::batches::pause();
+        crate::proxy::llama_server::stop();
+        self.state.health_checker.stop();
+        if let Some(tx) = self.shutdown_tx.lock().await.take() {
+            let _ = tx.send(());
+            tracing::info!("Axum server 停止信号已发送");
+        }
+        let terminated = self.state.drain.drain().await;
+        if terminated > 0 {
+            tracing::warn!("停机排空超时, {} 个请求被提前结束", terminated);
+        }
     }
 }
 
@@ -545,6 +998,19 @@ async fn health_check_handler() -> Response {
     .into_response()
 }
 
+/// Prometheus 文本格式指标
+async fn metrics_handler(State(state): State<AppState>) -> Response {
+    let body = state
+        .monitor
+        .metrics
+        .render(&state.upstream.endpoint_health(), &state.concurrency.snapshot());
+    (
+        [(axum::http::header::CONTENT_TYPE, "text/plain; version=0.0.4; charset=utf-8")],
+        body,
+    )
+        .into_response()
+}
+
 /// 静默成功处理器 (用于拦截遥测日志等)
 async fn silent_ok_handler() -> Response {
     StatusCode::OK.into_response()
@@ -891,11 +1357,21 @@ async fn admin_get_logs(
 
 
 
-async fn admin_get_config() -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+/// 非 admin 角色读取配置时清空其中的密钥; `config` 为完整配置对应的 JSON
+fn redact_config(identity: Option<Extension<crate::proxy::security::AdminIdentity>>, mut config: serde_json::Value) -> serde_json::Value {
+    if identity.is_some_and(|Extension(i)| i.role.redacts_secrets()) {
+        crate::modules::config_transfer::strip_secrets(&mut config);
+    }
+    config
+}
+
+async fn admin_get_config(
+    identity: Option<Extension<crate::proxy::security::AdminIdentity>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
     let cfg = config::load_app_config().map_err(|e| {
         (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e }))
     })?;
-    Ok(Json(cfg))
+    Ok(Json(redact_config(identity, serde_json::to_value(cfg).unwrap_or_default())))
 }
 
 #[derive(Deserialize)]
@@ -909,50 +1385,344 @@ async fn admin_save_config(
     Json(payload): Json<SaveConfigWrapper>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
     let new_config = payload.config;
+    crate::proxy::common::config_validation::ensure_valid(&new_config.proxy)
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })))?;
     // 1. 持久化
     config::save_app_config(&new_config).map_err(|e| {
         (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e }))
     })?;
 
     // 2. 热更新内存状态
-    // 这里我们直接复用内部组件的 update 方法
-    // 注意：AppState 本身持有各个组件的 Arc<RwLock> 或直接持有引用
-    
-    // 我们需要一个方式获取到当前的 AxumServer 实例来进行热更新，
-    // 或者直接操作 AppState 里的各状态。
-    // 在本重构中，各个状态已经在 AppState 中了。
-    
+    apply_proxy_config(&state, &new_config.proxy)
+        .await
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })))?;
+    state
+        .token_manager
+        .update_circuit_breaker_config(new_config.circuit_breaker.clone())
+        .await;
+    crate::modules::notifications::configure(&new_config.desktop_notifications);
+
+    Ok(StatusCode::OK)
+}
+
+async fn admin_get_audit_log(
+    Query(query): Query<crate::modules::audit::AuditQuery>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    crate::modules::audit::query(&query)
+        .map(Json)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e })))
+}
+
+async fn admin_validate_config(Json(payload): Json<SaveConfigWrapper>) -> impl IntoResponse {
+    Json(crate::proxy::common::config_validation::check(&payload.config.proxy))
+}
+
+/// 将代理配置热更新到运行中的各组件 (管理 API、设置页保存与配置文件热加载共用)
+/// 先加载证书: 监听证书或上游 TLS 选项无效时返回错误, 其余配置也不会生效
+pub(crate) async fn apply_proxy_config(state: &AppState, config: &crate::proxy::config::ProxyConfig) -> Result<(), String> {
+    // 保存的配置不含启动方案的覆盖项
+    let config = &crate::modules::profiles::effective(config);
+    crate::proxy::upstream::client_pool::check_tls(&config.provider_tls)?;
+    state.listener_certs.reload(&config.listener_tls)?;
+
     // 更新模型映射
     {
         let mut mapping = state.custom_mapping.write().await;
-        *mapping = new_config.clone().proxy.custom_mapping;
+        *mapping = config.custom_mapping.clone();
     }
     
     // 更新上游代理
     {
         let mut proxy = state.upstream_proxy.write().await;
-        *proxy = new_config.clone().proxy.upstream_proxy;
+        *proxy = config.upstream_proxy.clone();
+    }
+
+    // 更新上游出站代理覆盖规则
+    {
+        let mut rules = state.provider_proxies.write().await;
+        *rules = config.provider_proxies.clone();
+    }
+
+    // 更新上游 TLS 配置
+    {
+        let mut rules = state.provider_tls.write().await;
+        *rules = config.provider_tls.clone();
     }
     
     // 更新安全策略
     {
         let mut security = state.security.write().await;
-        *security = crate::proxy::ProxySecurityConfig::from_proxy_config(&new_config.proxy);
+        *security = crate::proxy::ProxySecurityConfig::from_proxy_config(config);
+        crate::proxy::jwt_auth::configure(&config.jwt_auth);
     }
+
+    // 更新 Webhook 通知与智能路由配置
+    crate::proxy::webhooks::configure(&config.webhooks);
+    crate::proxy::smart_routing::configure(&config.smart_routing);
+    crate::proxy::shadow::configure(&config.shadow);
+    crate::proxy::hedging::configure(&config.hedging);
+    crate::proxy::council::configure(&config.council);
+    crate::proxy::structured_output::configure(&config.structured_output);
+    crate::proxy::tool_emulation::configure(&config.tool_emulation);
+    crate::proxy::batches::configure(&config.batches);
+    crate::proxy::files::configure(&config.files);
+    crate::proxy::assistants::configure(&config.assistants);
+    crate::proxy::moderation::configure(&config.moderation);
+    crate::proxy::handlers::rerank::configure(&config.rerank);
+    crate::proxy::llama_server::configure(&config.llama_server);
     
     // 更新 z.ai 配置
     {
         let mut zai = state.zai.write().await;
-        *zai = new_config.clone().proxy.zai;
+        *zai = config.zai.clone();
     }
     
     // 更新实验性配置
     {
         let mut exp = state.experimental.write().await;
-        *exp = new_config.clone().proxy.experimental;
+        *exp = config.experimental.clone();
     }
 
-    Ok(StatusCode::OK)
+    // 更新流式响应改写
+    {
+        let mut cfg = state.stream_transform.write().await;
+        *cfg = config.stream_transform.clone();
+    }
+
+    // 更新 Embeddings 上游映射
+    {
+        let mut cfg = state.embeddings.write().await;
+        *cfg = config.embeddings.clone();
+    }
+
+    // 更新图像生成配置
+    {
+        let mut cfg = state.images.write().await;
+        *cfg = config.images.clone();
+    }
+
+    // 更新音频透传配置
+    {
+        let mut cfg = state.audio.write().await;
+        *cfg = config.audio.clone();
+    }
+
+    // 更新 Ollama 配置
+    {
+        let mut cfg = state.ollama.write().await;
+        *cfg = config.ollama.clone();
+    }
+
+    // 更新 Azure OpenAI 部署
+    {
+        let mut cfg = state.azure_openai.write().await;
+        *cfg = config.azure_openai.clone();
+    }
+
+    // 更新 Bedrock 配置
+    {
+        let mut cfg = state.bedrock.write().await;
+        *cfg = config.bedrock.clone();
+    }
+
+    // 更新 OpenAI 兼容上游
+    {
+        let mut cfg = state.compatible_upstreams.write().await;
+        *cfg = config.compatible_upstreams.clone();
+    }
+
+    // 更新参数策略
+    {
+        let mut cfg = state.param_policy.write().await;
+        *cfg = config.param_policy.clone();
+    }
+
+    // 更新模型价格表
+    {
+        let mut cfg = state.pricing.write().await;
+        *cfg = config.pricing.clone();
+    }
+
+    // 更新调试日志配置
+    {
+        let mut cfg = state.debug_logging.write().await;
+        *cfg = config.debug_logging.clone();
+    }
+
+    // 更新模型别名
+    state
+        .model_aliases
+        .update_rules(config.model_aliases.clone());
+
+    // 更新内容过滤规则
+    state.content_filter.update(&config.content_filter);
+
+    // 更新个人信息脱敏配置
+    {
+        let mut cfg = state.pii_redaction.write().await;
+        *cfg = config.pii_redaction.clone();
+    }
+
+    // 更新系统提示词注入配置
+    {
+        let mut cfg = state.system_prompt.write().await;
+        *cfg = config.system_prompt.clone();
+    }
+
+    // 更新请求体大小限制配置
+    {
+        let mut cfg = state.body_limits.write().await;
+        *cfg = config.body_limits.clone();
+    }
+
+    // 更新响应压缩配置
+    {
+        let mut cfg = state.compression.write().await;
+        *cfg = config.compression.clone();
+    }
+
+    // 更新上下文窗口保护配置
+    {
+        let mut cfg = state.context_window.write().await;
+        *cfg = config.context_window.clone();
+    }
+
+    // 更新 max_tokens 调整配置
+    {
+        let mut cfg = state.max_tokens.write().await;
+        *cfg = config.max_tokens.clone();
+    }
+
+    // 更新客户端限流
+    state
+        .client_rate_limiter
+        .update_config(config.client_rate_limit.clone());
+
+    // 更新 IP 访问控制
+    state.access_control.update_config(&config.access_control, &config.listeners);
+
+    // 更新上游并发限制
+    state
+        .concurrency
+        .update_config(config.concurrency.clone());
+
+    // 更新上游路由
+    state
+        .upstream
+        .update_routing(config.upstream_routing.clone());
+
+    // 更新上游连接池
+    state
+        .upstream
+        .update_connection_pool(config.connection_pool.clone());
+
+    // 更新上游健康检查
+    state
+        .health_checker
+        .update_config(config.health_check.clone());
+
+    // 更新模型目录 (同时使聚合缓存失效)
+    state
+        .model_catalog
+        .update_config(config.model_catalog.clone());
+
+    // 更新 Body 日志
+    state
+        .body_logger
+        .update_config(config.body_logging.clone());
+
+    // 更新响应缓存
+    state
+        .response_cache
+        .update_config(config.response_cache.clone());
+
+    // 更新会话记忆
+    state
+        .conversation_store
+        .update_config(config.conversation_memory.clone());
+
+    // 更新 Realtime 透传
+    state.realtime.update_config(config.realtime.clone());
+
+    // 更新优雅停机等待时间
+    state.drain.update_timeout(config.drain_timeout_secs);
+
+    tracing::info!("反代服务配置已全量热更新");
+    Ok(())
+}
+
+/// 读取代理配置
+async fn admin_get_proxy_config(
+    identity: Option<Extension<crate::proxy::security::AdminIdentity>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let cfg = config::load_app_config().map_err(|e| {
+        (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e }))
+    })?;
+    let config = redact_config(identity, serde_json::json!({ "proxy": cfg.proxy }));
+    Ok(Json(config["proxy"].clone()))
+}
+
+/// 持久化并热更新代理配置, 返回更新后的配置
+async fn persist_proxy_config(
+    state: &AppState,
+    mut app_config: AppConfig,
+    proxy: crate::proxy::config::ProxyConfig,
+) -> Result<Json<crate::proxy::config::ProxyConfig>, (StatusCode, Json<ErrorResponse>)> {
+    app_config.proxy = proxy;
+    config::save_app_config(&app_config).map_err(|e| {
+        (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e }))
+    })?;
+    apply_proxy_config(state, &app_config.proxy)
+        .await
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })))?;
+    logger::log_info("[API] 代理配置已通过管理 API 更新");
+    Ok(Json(app_config.proxy))
+}
+
+/// 以 JSON Merge Patch 局部更新代理配置 (无需重启服务)
+async fn admin_patch_proxy_config(
+    State(state): State<AppState>,
+    Json(patch): Json<serde_json::Value>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let app_config = config::load_app_config().map_err(|e| {
+        (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e }))
+    })?;
+    let proxy = crate::proxy::common::config_patch::apply_patch(&app_config.proxy, &patch)
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })))?;
+    persist_proxy_config(&state, app_config, proxy).await
+}
+
+/// 读取单个代理配置字段 (例如 zai / model_aliases / client_rate_limit / upstream_routing)
+async fn admin_get_proxy_config_section(
+    identity: Option<Extension<crate::proxy::security::AdminIdentity>>,
+    Path(section): Path<String>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let cfg = config::load_app_config().map_err(|e| {
+        (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e }))
+    })?;
+    crate::proxy::common::config_patch::get_section(&cfg.proxy, &section)
+        .map(|value| {
+            let config = redact_config(identity, serde_json::json!({ "proxy": { section.as_str(): value } }));
+            Json(config["proxy"][section.as_str()].clone())
+        })
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse { error: format!("Unknown config section: {}", section) }),
+        ))
+}
+
+/// 整体替换单个代理配置字段
+async fn admin_put_proxy_config_section(
+    State(state): State<AppState>,
+    Path(section): Path<String>,
+    Json(value): Json<serde_json::Value>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let app_config = config::load_app_config().map_err(|e| {
+        (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e }))
+    })?;
+    let proxy = crate::proxy::common::config_patch::replace_section(&app_config.proxy, &section, value)
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })))?;
+    persist_proxy_config(&state, app_config, proxy).await
 }
 
 async fn admin_get_proxy_status(
@@ -1222,6 +1992,82 @@ async fn admin_get_proxy_stats(
     Ok(Json(stats))
 }
 
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+struct LatencyQuery {
+    window_mins: Option<u64>,
+}
+
+async fn admin_get_latency_stats(State(state): State<AppState>, Query(q): Query<LatencyQuery>) -> impl IntoResponse {
+    let window = std::time::Duration::from_secs(q.window_mins.unwrap_or(15) * 60);
+    Json(state.monitor.latency.snapshot(window))
+}
+
+async fn admin_get_shadow_results() -> impl IntoResponse {
+    Json(crate::proxy::shadow::results())
+}
+
+async fn admin_clear_shadow_results() -> impl IntoResponse {
+    crate::proxy::shadow::clear();
+    StatusCode::OK
+}
+
+async fn admin_compare_models(
+    State(state): State<AppState>,
+    Json(request): Json<crate::proxy::compare::CompareRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let target = crate::proxy::compare::CompareTarget {
+        base_url: format!("http://127.0.0.1:{}", state.port),
+        api_key: state.security.read().await.api_key.clone(),
+        client_name: crate::proxy::compare::CLIENT_NAME,
+    };
+    let prices = state.pricing.read().await.models.clone();
+    crate::proxy::compare::compare(request, target, &prices, state.request_timeout)
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })))
+}
+
+async fn admin_get_upstream_health(State(state): State<AppState>) -> impl IntoResponse {
+    Json(state.health_checker.snapshot())
+}
+
+async fn admin_get_key_check_results() -> impl IntoResponse {
+    Json(crate::modules::key_check::results())
+}
+
+async fn admin_check_upstream_keys() -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let cfg = config::load_app_config().map_err(|e| {
+        (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e }))
+    })?;
+    let proxy = crate::modules::profiles::effective(&cfg.proxy);
+    Ok(Json(crate::modules::key_check::check_all(&proxy).await))
+}
+
+async fn admin_get_disabled_keys(State(state): State<AppState>) -> impl IntoResponse {
+    Json(state.zai_keys.disabled())
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EnableKeyRequest {
+    key_id: String,
+}
+
+async fn admin_enable_disabled_key(
+    State(state): State<AppState>,
+    Json(payload): Json<EnableKeyRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    if !state.zai_keys.enable(&payload.key_id) {
+        return Err((StatusCode::NOT_FOUND, Json(ErrorResponse { error: format!("No disabled key {}", payload.key_id) })));
+    }
+    Ok(StatusCode::OK)
+}
+
+async fn admin_get_provider_presets() -> impl IntoResponse {
+    Json(crate::proxy::providers::presets::PRESETS)
+}
+
 async fn admin_get_data_dir_path() -> impl IntoResponse {
     match crate::modules::account::get_data_dir() {
         Ok(p) => Json(p.to_string_lossy().to_string()),
@@ -1888,8 +2734,8 @@ async fn handle_oauth_callback(
 ) ->  Result<Html<String>, StatusCode> {
     let code = params.code;
 
-    // Exchange token
-    let port = state.security.read().await.port;
+    // Exchange token (端口回退后以实际绑定的端口为准, 与授权时的回调地址一致)
+    let port = state.port;
     let host = headers.get("host").and_then(|h| h.to_str().ok());
     let proto = headers.get("x-forwarded-proto").and_then(|h| h.to_str().ok());
     let redirect_uri = get_oauth_redirect_uri(port, host, proto);
@@ -1988,7 +2834,8 @@ async fn admin_prepare_oauth_url_web(
     headers: HeaderMap,
     State(state): State<AppState>,
 ) ->  Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResponse>)> {
-    let port = state.security.read().await.port; 
+    // 回调地址使用实际绑定的端口, 配置端口被占用回退后仍能收到回调
+    let port = state.port;
     let host = headers.get("host").and_then(|h| h.to_str().ok());
     let proto = headers.get("x-forwarded-proto").and_then(|h| h.to_str().ok());
     let redirect_uri = get_oauth_redirect_uri(port, host, proto);
@@ -0,0 +1,248 @@
+// 影子流量
+// 按规则把一定比例的请求复制一份发往次要目标 (其他上游, 或本地反代的其他模型), 客户端只收到主请求的响应。
+// 两边的状态与耗时记录在一起, 影子响应保存在内存中 (服务重启后清空), 用于在真实流量上评估新的上游或模型。
+
+use std::collections::VecDeque;
+use std::sync::{Mutex, OnceLock, RwLock};
+use std::time::Instant;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::proxy::common::model_mapping::wildcard_match;
+use crate::proxy::config::{ShadowConfig, ShadowRule, UpstreamProxyConfig};
+
+/// 影子请求携带该 Header (值为主请求 ID), 再次经过本地反代时不会被复制
+pub const SHADOW_HEADER: &str = "X-Shadow-Of";
+/// 保存的影子响应长度上限 (字符)
+const MAX_RESPONSE_CHARS: usize = 64 * 1024;
+
+/// 一次影子请求与对应主请求的结果
+#[derive(Debug, Clone, Serialize)]
+pub struct ShadowResult {
+    pub id: String,
+    pub timestamp: i64,
+    pub rule: String,
+    /// 主请求 ID, 与请求日志对应
+    pub request_id: Option<String>,
+    pub path: String,
+    pub model: String,
+    pub primary_status: u16,
+    pub primary_duration: u64, // ms, 到响应头
+    pub shadow_url: String,
+    pub shadow_model: String,
+    /// 请求失败 (网络错误、超时) 时为 null
+    pub shadow_status: Option<u16>,
+    pub shadow_duration: u64, // ms, 到响应结束
+    pub shadow_response: Option<String>,
+    pub error: Option<String>,
+}
+
+/// 影子请求的发送目标
+pub struct ShadowTarget {
+    pub base_url: String,
+    pub api_key: String,
+    pub upstream_proxy: UpstreamProxyConfig,
+}
+
+/// 影子请求本身的结果 (不含主请求)
+pub struct ShadowOutcome {
+    pub url: String,
+    pub model: String,
+    pub status: Option<u16>,
+    pub duration: u64,
+    pub response: Option<String>,
+    pub error: Option<String>,
+}
+
+struct ShadowState {
+    config: RwLock<ShadowConfig>,
+    results: Mutex<VecDeque<ShadowResult>>,
+}
+
+fn state() -> &'static ShadowState {
+    static STATE: OnceLock<ShadowState> = OnceLock::new();
+    STATE.get_or_init(|| ShadowState { config: RwLock::new(ShadowConfig::default()), results: Mutex::new(VecDeque::new()) })
+}
+
+/// 应用影子流量配置 (服务启动与配置热更新时调用)
+pub fn configure(config: &ShadowConfig) {
+    *state().config.write().unwrap_or_else(|e| e.into_inner()) = config.clone();
+    let mut results = state().results.lock().unwrap_or_else(|e| e.into_inner());
+    results.truncate(config.max_results);
+}
+
+/// 是否启用 (中间件据此决定是否读取请求体)
+pub fn is_enabled() -> bool {
+    let config = state().config.read().unwrap_or_else(|e| e.into_inner());
+    config.enabled && config.rules.iter().any(|r| r.enabled && r.percent > 0.0)
+}
+
+pub fn timeout_secs() -> u64 {
+    state().config.read().unwrap_or_else(|e| e.into_inner()).timeout_secs
+}
+
+/// 请求的模型: 请求体中的 `model`, 或 Gemini 原生路径中的模型
+pub fn request_model(path: &str, body: &Value) -> Option<String> {
+    if let Some(model) = body.get("model").and_then(Value::as_str) {
+        return Some(model.to_string());
+    }
+    let (_, rest) = path.split_once("/models/")?;
+    rest.split(':').next().filter(|m| !m.is_empty()).map(String::from)
+}
+
+/// 第一条匹配模型的规则; `roll` 为 [0, 100) 的随机数, 小于规则比例时复制
+fn select_rule<'a>(config: &'a ShadowConfig, model: &str, roll: f64) -> Option<&'a ShadowRule> {
+    if !config.enabled {
+        return None;
+    }
+    config
+        .rules
+        .iter()
+        .find(|r| r.enabled && (r.models.is_empty() || r.models.iter().any(|p| wildcard_match(p, model))))
+        .filter(|r| roll < r.percent)
+}
+
+/// 按规则与比例决定是否复制该请求
+pub fn pick(model: &str) -> Option<ShadowRule> {
+    let config = state().config.read().unwrap_or_else(|e| e.into_inner());
+    select_rule(&config, model, rand::random::<f64>() * 100.0).cloned()
+}
+
+fn truncate(text: String) -> String {
+    match text.char_indices().nth(MAX_RESPONSE_CHARS) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text,
+    }
+}
+
+/// 发送影子请求并读取完整响应 (流式响应按 monitor 的方式合并)
+pub async fn send(
+    rule: &ShadowRule,
+    target: ShadowTarget,
+    path: &str,
+    mut body: Value,
+    request_id: Option<&str>,
+    timeout_secs: u64,
+) -> ShadowOutcome {
+    let path = match rule.target_model.as_deref().filter(|m| !m.is_empty()) {
+        Some(model) => crate::proxy::replay::apply_model(path, &mut body, model),
+        None => path.to_string(),
+    };
+    let url = crate::proxy::replay::join_url(&target.base_url, &path);
+    let model = request_model(&path, &body).unwrap_or_default();
+    let mut outcome = ShadowOutcome { url, model, status: None, duration: 0, response: None, error: None };
+
+    let client = match crate::proxy::handlers::common::build_upstream_client(target.upstream_proxy, timeout_secs) {
+        Ok(client) => client,
+        Err(e) => {
+            outcome.error = Some(e);
+            return outcome;
+        }
+    };
+    let mut req = client
+        .post(&outcome.url)
+        .header("Content-Type", "application/json")
+        .header(SHADOW_HEADER, request_id.unwrap_or("-"));
+    if !target.api_key.is_empty() {
+        req = req
+            .header("Authorization", format!("Bearer {}", target.api_key))
+            .header("x-api-key", target.api_key.as_str());
+    }
+
+    let start = Instant::now();
+    let result = async {
+        let resp = req.body(body.to_string()).send().await?;
+        let status = resp.status().as_u16();
+        let is_stream = resp
+            .headers()
+            .get("content-type")
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.contains("text/event-stream"));
+        let text = resp.text().await?;
+        Ok::<_, reqwest::Error>((status, is_stream, text))
+    }
+    .await;
+    outcome.duration = start.elapsed().as_millis() as u64;
+    match result {
+        Ok((status, is_stream, text)) => {
+            let text = if is_stream {
+                crate::proxy::middleware::monitor::consolidate_stream(&text).0
+            } else {
+                text
+            };
+            outcome.status = Some(status);
+            outcome.response = Some(truncate(text));
+        }
+        Err(e) => outcome.error = Some(format!("Shadow request failed: {}", e)),
+    }
+    outcome
+}
+
+/// 保存一条结果 (超出上限时丢弃最早的)
+pub fn record(result: ShadowResult) {
+    tracing::info!(
+        "[Shadow] {} {} -> {}: primary {} in {}ms, shadow {:?} in {}ms",
+        result.rule,
+        result.model,
+        result.shadow_url,
+        result.primary_status,
+        result.primary_duration,
+        result.shadow_status,
+        result.shadow_duration
+    );
+    let max_results = state().config.read().unwrap_or_else(|e| e.into_inner()).max_results;
+    let mut results = state().results.lock().unwrap_or_else(|e| e.into_inner());
+    results.push_front(result);
+    results.truncate(max_results);
+}
+
+/// 最近的结果, 新的在前
+pub fn results() -> Vec<ShadowResult> {
+    state().results.lock().unwrap_or_else(|e| e.into_inner()).iter().cloned().collect()
+}
+
+pub fn clear() {
+    state().results.lock().unwrap_or_else(|e| e.into_inner()).clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn rule(name: &str, models: &[&str], percent: f64) -> ShadowRule {
+        ShadowRule {
+            name: name.to_string(),
+            models: models.iter().map(|m| m.to_string()).collect(),
+            percent,
+            enabled: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_select_rule_by_model_and_percent() {
+        let config = ShadowConfig {
+            enabled: true,
+            rules: vec![rule("claude", &["claude-*"], 10.0), rule("all", &[], 100.0)],
+            ..Default::default()
+        };
+        let name = |model: &str, roll: f64| select_rule(&config, model, roll).map(|r| r.name.as_str());
+        assert_eq!(name("claude-sonnet-4-5", 5.0), Some("claude"));
+        // 匹配的第一条规则未抽中时不再尝试后面的规则
+        assert_eq!(name("claude-sonnet-4-5", 50.0), None);
+        assert_eq!(name("gpt-4o", 99.9), Some("all"));
+        assert_eq!(select_rule(&ShadowConfig { enabled: false, ..config.clone() }, "gpt-4o", 0.0), None);
+    }
+
+    #[test]
+    fn test_request_model() {
+        assert_eq!(request_model("/v1/chat/completions", &json!({"model": "gpt-4o"})).as_deref(), Some("gpt-4o"));
+        assert_eq!(
+            request_model("/v1beta/models/gemini-2.5-pro:generateContent", &json!({"contents": []})).as_deref(),
+            Some("gemini-2.5-pro")
+        );
+        assert_eq!(request_model("/v1/embeddings", &json!({"input": "hi"})), None);
+    }
+}
@@ -0,0 +1,231 @@
+// 智能路由
+// 客户端请求某个模型别名时, 在配置的候选 (上游 + 模型) 中选择当前评分最好的一个:
+// 延迟与错误率优先取用量库中最近窗口的数据, 样本不足时使用健康检查结果; 费用取价格表。
+// 健康检查判定不可用的候选不参与选择 (全部不可用时退回第一个候选)。
+
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock, RwLock};
+use std::time::{Duration, Instant};
+
+use crate::modules::usage::UpstreamModelStats;
+use crate::proxy::common::pricing::find_price;
+use crate::proxy::config::{ModelPrice, SmartRoute, SmartRouteCandidate, SmartRoutingConfig, SmartRoutingWeights};
+use crate::proxy::upstream::health_check::UpstreamHealth;
+
+/// 用量统计的缓存时间, 避免每个请求都查询数据库
+const STATS_TTL: Duration = Duration::from_secs(30);
+/// 缺少数据的指标按中间值计分
+const UNKNOWN_SCORE: f64 = 0.5;
+
+/// 单个候选的原始指标
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Metrics {
+    latency_ms: Option<f64>,
+    error_rate: Option<f64>,
+    /// 输入 + 输出的百万 token 价格
+    cost: Option<f64>,
+}
+
+/// (上游, 模型) -> 用量统计
+type StatsMap = HashMap<(String, String), UpstreamModelStats>;
+
+struct RouterState {
+    config: RwLock<SmartRoutingConfig>,
+    stats: Mutex<Option<(Instant, StatsMap)>>,
+}
+
+fn state() -> &'static RouterState {
+    static STATE: OnceLock<RouterState> = OnceLock::new();
+    STATE.get_or_init(|| RouterState { config: RwLock::new(SmartRoutingConfig::default()), stats: Mutex::new(None) })
+}
+
+/// 应用智能路由配置 (服务启动与配置热更新时调用)
+pub fn configure(config: &SmartRoutingConfig) {
+    *state().config.write().unwrap_or_else(|e| e.into_inner()) = config.clone();
+    *state().stats.lock().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// 是否启用 (中间件据此决定是否读取请求体)
+pub fn is_enabled() -> bool {
+    let config = state().config.read().unwrap_or_else(|e| e.into_inner());
+    config.enabled && !config.routes.is_empty()
+}
+
+fn metrics_for(
+    candidate: &SmartRouteCandidate,
+    stats: &StatsMap,
+    health: Option<&UpstreamHealth>,
+    prices: &[ModelPrice],
+    min_samples: u64,
+) -> Metrics {
+    let usage = stats
+        .get(&(candidate.upstream.clone(), candidate.model.clone()))
+        .filter(|s| s.requests > 0 && s.requests >= min_samples);
+    Metrics {
+        latency_ms: usage
+            .map(|s| s.avg_latency_ms)
+            .or_else(|| health.and_then(|h| h.last_latency_ms).map(|l| l as f64)),
+        error_rate: usage
+            .map(|s| s.errors as f64 / s.requests as f64)
+            .or_else(|| health.filter(|h| !h.history.is_empty()).map(|h| 1.0 - h.availability)),
+        cost: find_price(prices, &candidate.model).map(|p| p.input_per_million + p.output_per_million),
+    }
+}
+
+/// 在候选之间归一化到 0-1 (越小越好)
+fn normalize(values: &[Option<f64>]) -> Vec<f64> {
+    let known = values.iter().flatten();
+    let min = known.clone().cloned().fold(f64::INFINITY, f64::min);
+    let max = known.cloned().fold(f64::NEG_INFINITY, f64::max);
+    values
+        .iter()
+        .map(|v| match v {
+            Some(v) if max > min => (v - min) / (max - min),
+            Some(_) => 0.0,
+            None => UNKNOWN_SCORE,
+        })
+        .collect()
+}
+
+/// 返回得分最低的可用候选的下标; 同分时取列表中靠前的
+fn pick(metrics: &[Metrics], available: &[bool], weights: &SmartRoutingWeights) -> Option<usize> {
+    let latency = normalize(&metrics.iter().map(|m| m.latency_ms).collect::<Vec<_>>());
+    let errors = normalize(&metrics.iter().map(|m| m.error_rate).collect::<Vec<_>>());
+    let cost = normalize(&metrics.iter().map(|m| m.cost).collect::<Vec<_>>());
+    let any_available = available.iter().any(|a| *a);
+    (0..metrics.len())
+        .filter(|&i| !any_available || available[i])
+        .map(|i| (i, weights.latency * latency[i] + weights.error_rate * errors[i] + weights.cost * cost[i]))
+        .fold(None, |best: Option<(usize, f64)>, (i, score)| match best {
+            Some((_, best_score)) if best_score <= score => best,
+            _ => Some((i, score)),
+        })
+        .map(|(i, _)| i)
+}
+
+fn choose<'a>(
+    route: &'a SmartRoute,
+    config: &SmartRoutingConfig,
+    stats: &StatsMap,
+    health: &[UpstreamHealth],
+    prices: &[ModelPrice],
+) -> Option<&'a SmartRouteCandidate> {
+    let health_of = |c: &SmartRouteCandidate| health.iter().find(|h| h.upstream == c.upstream);
+    let metrics: Vec<Metrics> = route
+        .candidates
+        .iter()
+        .map(|c| metrics_for(c, stats, health_of(c), prices, config.min_samples))
+        .collect();
+    let available: Vec<bool> = route.candidates.iter().map(|c| health_of(c).is_none_or(|h| h.available)).collect();
+    pick(&metrics, &available, &config.weights).map(|i| &route.candidates[i])
+}
+
+/// 最近窗口的用量统计 (带缓存)
+async fn usage_stats(window_mins: u64) -> StatsMap {
+    if let Some((at, stats)) = state().stats.lock().unwrap_or_else(|e| e.into_inner()).as_ref() {
+        if at.elapsed() < STATS_TTL {
+            return stats.clone();
+        }
+    }
+    let since = chrono::Utc::now().timestamp() - window_mins as i64 * 60;
+    let rows = tokio::task::spawn_blocking(move || crate::modules::usage::get_upstream_model_stats(since))
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|r| r);
+    let stats: StatsMap = match rows {
+        Ok(rows) => rows.into_iter().map(|s| ((s.upstream.clone(), s.model.clone()), s)).collect(),
+        Err(e) => {
+            tracing::debug!("[SmartRouting] Failed to load usage stats: {}", e);
+            HashMap::new()
+        }
+    };
+    *state().stats.lock().unwrap_or_else(|e| e.into_inner()) = Some((Instant::now(), stats.clone()));
+    stats
+}
+
+/// 请求的模型是已配置的别名时返回选中的候选; 未启用或没有匹配的别名时返回 None
+pub async fn select(model: &str, health: &[UpstreamHealth], prices: &[ModelPrice]) -> Option<SmartRouteCandidate> {
+    let config = state().config.read().unwrap_or_else(|e| e.into_inner()).clone();
+    if !config.enabled {
+        return None;
+    }
+    let route = config.routes.iter().find(|r| r.enabled && r.alias == model && !r.candidates.is_empty())?;
+    let stats = usage_stats(config.window_mins).await;
+    choose(route, &config, &stats, health, prices).cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(upstream: &str, model: &str) -> SmartRouteCandidate {
+        SmartRouteCandidate { upstream: upstream.to_string(), model: model.to_string() }
+    }
+
+    fn usage(upstream: &str, model: &str, requests: u64, errors: u64, latency: f64) -> UpstreamModelStats {
+        UpstreamModelStats {
+            upstream: upstream.to_string(),
+            model: model.to_string(),
+            requests,
+            errors,
+            avg_latency_ms: latency,
+        }
+    }
+
+    fn price(pattern: &str, input: f64, output: f64) -> ModelPrice {
+        ModelPrice { pattern: pattern.to_string(), input_per_million: input, output_per_million: output, ..Default::default() }
+    }
+
+    #[test]
+    fn test_choose_by_weighted_score() {
+        let route = SmartRoute {
+            alias: "fast-chat".to_string(),
+            candidates: vec![candidate("google", "gemini-2.5-flash"), candidate("groq", "llama-3.3-70b"), candidate("zai", "glm-4.6")],
+            enabled: true,
+        };
+        let mut config = SmartRoutingConfig { enabled: true, routes: vec![route.clone()], ..Default::default() };
+        let stats: HashMap<_, _> = [
+            usage("google", "gemini-2.5-flash", 50, 5, 2_000.0),
+            usage("groq", "llama-3.3-70b", 50, 0, 400.0),
+            usage("zai", "glm-4.6", 2, 0, 100.0), // 样本不足, 不计入
+        ]
+        .into_iter()
+        .map(|s| ((s.upstream.clone(), s.model.clone()), s))
+        .collect();
+        let prices = vec![price("gemini-2.5-flash", 0.3, 2.5), price("llama-3.3-70b", 0.6, 0.8), price("glm-4.6", 0.6, 2.2)];
+
+        let chosen = |config: &SmartRoutingConfig, health: &[UpstreamHealth]| {
+            choose(&route, config, &stats, health, &prices).map(|c| c.upstream.clone())
+        };
+        assert_eq!(chosen(&config, &[]).as_deref(), Some("groq"));
+
+        // 权重全为 0 时同分, 取列表中靠前的候选
+        config.weights = SmartRoutingWeights { latency: 0.0, error_rate: 0.0, cost: 0.0 };
+        assert_eq!(chosen(&config, &[]).as_deref(), Some("google"));
+
+        // 健康检查判定不可用的候选被排除
+        config.weights = SmartRoutingWeights::default();
+        let down = UpstreamHealth {
+            upstream: "groq".to_string(),
+            kind: "openai".to_string(),
+            available: false,
+            last_latency_ms: None,
+            availability: 0.0,
+            history: Vec::new(),
+        };
+        assert_eq!(chosen(&config, std::slice::from_ref(&down)).as_deref(), Some("zai"));
+    }
+
+    #[test]
+    fn test_normalize_and_pick() {
+        assert_eq!(normalize(&[Some(100.0), Some(300.0), None]), vec![0.0, 1.0, UNKNOWN_SCORE]);
+        assert_eq!(normalize(&[Some(5.0), Some(5.0)]), vec![0.0, 0.0]);
+
+        let weights = SmartRoutingWeights::default();
+        let metrics = [Metrics { latency_ms: Some(10.0), ..Default::default() }, Metrics::default()];
+        // 全部不可用时仍返回一个候选
+        assert_eq!(pick(&metrics, &[false, false], &weights), Some(0));
+        assert_eq!(pick(&metrics, &[false, true], &weights), Some(1));
+        assert_eq!(pick(&[], &[], &weights), None);
+    }
+}
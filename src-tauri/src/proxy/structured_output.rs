@@ -0,0 +1,416 @@
+// 结构化输出兼容层
+// 部分上游不支持 `response_format: {"type": "json_schema"}`: 请求转发前去掉该参数, 把 schema 写入系统提示词,
+// 收到回复后按 schema 校验, 不符合时把错误反馈给模型重试, 超过次数后返回最后一次回复。
+// `json_object` 请求可选修复被截断或格式错误的回复 (补全括号与字符串、删除多余逗号)。
+// 校验器只实现常用关键字 (type / properties / required / additionalProperties / items / enum / const /
+// anyOf / oneOf / allOf / 长度与数值范围 / pattern / 本地 $ref), 其余关键字忽略。
+
+use std::sync::{OnceLock, RwLock};
+
+use serde_json::Value;
+
+use crate::proxy::common::model_mapping::wildcard_match;
+use crate::proxy::config::StructuredOutputConfig;
+
+/// 允许配置的最大重试次数
+pub const MAX_RETRIES: u32 = 5;
+/// 单次反馈给模型的错误条数上限
+const MAX_FEEDBACK_ERRORS: usize = 10;
+/// `$ref` 与嵌套的递归深度上限
+const MAX_DEPTH: usize = 64;
+
+fn config() -> &'static RwLock<StructuredOutputConfig> {
+    static CONFIG: OnceLock<RwLock<StructuredOutputConfig>> = OnceLock::new();
+    CONFIG.get_or_init(|| RwLock::new(StructuredOutputConfig::default()))
+}
+
+/// 应用结构化输出配置 (服务启动与配置热更新时调用)
+pub fn configure(structured_output: &StructuredOutputConfig) {
+    *config().write().unwrap_or_else(|e| e.into_inner()) = structured_output.clone();
+}
+
+fn matches(config: &StructuredOutputConfig, model: &str) -> bool {
+    config.enabled && (config.models.is_empty() || config.models.iter().any(|p| wildcard_match(p, model)))
+}
+
+/// 模型需要兼容处理时返回最大重试次数
+pub fn retries_for(model: &str) -> Option<u32> {
+    let config = config().read().unwrap_or_else(|e| e.into_inner());
+    matches(&config, model).then_some(config.max_retries.min(MAX_RETRIES))
+}
+
+/// 是否修复该模型 json_object 请求的回复
+pub fn repairs_json_object(model: &str) -> bool {
+    let config = config().read().unwrap_or_else(|e| e.into_inner());
+    matches(&config, model) && config.repair_json_object
+}
+
+pub fn requests_json_object(body: &Value) -> bool {
+    body.pointer("/response_format/type").and_then(Value::as_str) == Some("json_object")
+}
+
+/// 请求中的 json_schema 格式: (名称, schema)
+pub fn requested_schema(body: &Value) -> Option<(String, Value)> {
+    let format = body.get("response_format")?;
+    if format.get("type").and_then(Value::as_str) != Some("json_schema") {
+        return None;
+    }
+    let spec = format.get("json_schema")?;
+    let name = spec.get("name").and_then(Value::as_str).unwrap_or("response").to_string();
+    Some((name, spec.get("schema").cloned().unwrap_or(Value::Bool(true))))
+}
+
+/// 写入系统提示词的格式要求
+pub fn instructions(name: &str, schema: &Value) -> String {
+    format!(
+        "Respond with a single JSON value that conforms to the JSON Schema \"{}\" below. \
+Output only the JSON, without markdown code fences or any explanation.\n\n{}",
+        name,
+        serde_json::to_string_pretty(schema).unwrap_or_default()
+    )
+}
+
+/// 校验失败时追加的用户消息
+pub fn feedback(errors: &[String]) -> String {
+    let listed: Vec<String> = errors.iter().take(MAX_FEEDBACK_ERRORS).map(|e| format!("- {}", e)).collect();
+    format!(
+        "Your previous reply does not match the required JSON Schema:\n{}\n\nReply again with only the corrected JSON.",
+        listed.join("\n")
+    )
+}
+
+/// 从模型回复中取出 JSON: 原文、```json 代码块, 或说明文字中的第一个对象 / 数组
+pub fn extract_json(text: &str) -> Option<Value> {
+    let trimmed = text.trim();
+    if let Ok(value) = serde_json::from_str(trimmed) {
+        return Some(value);
+    }
+    let unfenced = trimmed
+        .strip_prefix("```")
+        .map(|rest| rest.trim_start_matches(|c: char| c.is_ascii_alphanumeric()))
+        .and_then(|rest| rest.trim_end().strip_suffix("```"));
+    if let Some(value) = unfenced.and_then(|s| serde_json::from_str(s.trim()).ok()) {
+        return Some(value);
+    }
+    let start = trimmed.find(['{', '['])?;
+    let end = trimmed.rfind(['}', ']'])?;
+    (start < end).then(|| serde_json::from_str(&trimmed[start..=end]).ok()).flatten()
+}
+
+/// 去掉末尾的空白与逗号
+fn trim_trailing_comma(out: &mut String) {
+    let trimmed = out.trim_end().trim_end_matches(',').len();
+    out.truncate(trimmed);
+}
+
+/// 补全被截断的 JSON: 关闭未结束的字符串, 删除末尾的逗号, 冒号后补 null, 按顺序补上括号
+fn close_truncated(mut out: String, in_string: bool, escaped: bool, stack: &[char]) -> String {
+    if in_string {
+        if escaped {
+            out.pop();
+        }
+        out.push('"');
+    }
+    trim_trailing_comma(&mut out);
+    if out.ends_with(':') {
+        out.push_str("null");
+    }
+    out.extend(stack.iter().rev());
+    out
+}
+
+/// 提取并修复回复中的 JSON; 无法修复时返回 None
+/// 完整但不合法的 JSON 只删除多余逗号; 被截断的 JSON 依次尝试补全与回退到上一个逗号处补全。
+pub fn repair_json(text: &str) -> Option<Value> {
+    if let Some(value) = extract_json(text) {
+        return Some(value);
+    }
+    let trimmed = text.trim();
+    let start = trimmed.find(['{', '['])?;
+    let mut out = String::new();
+    let mut stack: Vec<char> = Vec::new(); // 待补的结束括号
+    let mut commas: Vec<(usize, Vec<char>)> = Vec::new(); // 字符串之外的逗号位置与当时的括号
+    let (mut in_string, mut escaped) = (false, false);
+    for c in trimmed[start..].chars() {
+        if in_string {
+            out.push(c);
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+            continue;
+        }
+        match c {
+            '"' => {
+                in_string = true;
+                out.push(c);
+            }
+            '{' => {
+                stack.push('}');
+                out.push(c);
+            }
+            '[' => {
+                stack.push(']');
+                out.push(c);
+            }
+            '}' | ']' if stack.contains(&c) => {
+                // 结束括号与最近的开括号不匹配时补齐中间缺少的括号
+                trim_trailing_comma(&mut out);
+                while let Some(closer) = stack.pop() {
+                    out.push(closer);
+                    if closer == c {
+                        break;
+                    }
+                }
+                if stack.is_empty() {
+                    break; // 忽略顶层值之后的文字
+                }
+            }
+            '}' | ']' => {}
+            ',' => {
+                commas.push((out.len(), stack.clone()));
+                out.push(c);
+            }
+            _ => out.push(c),
+        }
+    }
+    if stack.is_empty() && !in_string {
+        return serde_json::from_str(&out).ok();
+    }
+    if let Ok(value) = serde_json::from_str(&close_truncated(out.clone(), in_string, escaped, &stack)) {
+        return Some(value);
+    }
+    commas.into_iter().rev().find_map(|(pos, stack)| {
+        serde_json::from_str(&close_truncated(out[..pos].to_string(), false, false, &stack)).ok()
+    })
+}
+
+/// 校验回复文本, 返回解析出的 JSON 或错误列表
+pub fn check(text: &str, schema: &Value) -> Result<Value, Vec<String>> {
+    let value = repair_json(text).ok_or_else(|| vec!["$: reply is not valid JSON".to_string()])?;
+    let errors = validate(schema, &value);
+    if errors.is_empty() {
+        Ok(value)
+    } else {
+        Err(errors)
+    }
+}
+
+/// 按 schema 校验, 错误格式为 `<路径>: <说明>`, 路径以 `$` 表示根
+pub fn validate(schema: &Value, value: &Value) -> Vec<String> {
+    let mut errors = Vec::new();
+    validate_at(schema, schema, value, "$", 0, &mut errors);
+    errors
+}
+
+fn type_matches(name: &str, value: &Value) -> bool {
+    match name {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|f| f.fract() == 0.0),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn validate_at(schema: &Value, root: &Value, value: &Value, path: &str, depth: usize, errors: &mut Vec<String>) {
+    let schema = match schema {
+        Value::Bool(true) => return,
+        Value::Bool(false) => return errors.push(format!("{}: no value is allowed here", path)),
+        Value::Object(schema) => schema,
+        _ => return,
+    };
+    if depth > MAX_DEPTH {
+        return errors.push(format!("{}: schema nesting is too deep", path));
+    }
+    if let Some(target) = schema.get("$ref").and_then(Value::as_str) {
+        match target.strip_prefix('#').and_then(|p| if p.is_empty() { Some(root) } else { root.pointer(p) }) {
+            Some(resolved) => validate_at(resolved, root, value, path, depth + 1, errors),
+            None => errors.push(format!("{}: unresolved $ref {}", path, target)),
+        }
+    }
+
+    if let Some(expected) = schema.get("type") {
+        let names: Vec<&str> = match expected {
+            Value::String(s) => vec![s.as_str()],
+            Value::Array(items) => items.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !names.is_empty() && !names.iter().any(|n| type_matches(n, value)) {
+            // 类型不符时其余关键字没有意义
+            return errors.push(format!("{}: expected {}, got {}", path, names.join(" or "), type_name(value)));
+        }
+    }
+    if let Some(options) = schema.get("enum").and_then(Value::as_array) {
+        if !options.contains(value) {
+            errors.push(format!("{}: must be one of {}", path, Value::Array(options.clone())));
+        }
+    }
+    if let Some(expected) = schema.get("const") {
+        if expected != value {
+            errors.push(format!("{}: must equal {}", path, expected));
+        }
+    }
+    for key in ["anyOf", "oneOf"] {
+        if let Some(options) = schema.get(key).and_then(Value::as_array) {
+            let matched = options.iter().any(|option| {
+                let mut nested = Vec::new();
+                validate_at(option, root, value, path, depth + 1, &mut nested);
+                nested.is_empty()
+            });
+            if !matched {
+                errors.push(format!("{}: does not match any of the allowed schemas", path));
+            }
+        }
+    }
+    if let Some(all) = schema.get("allOf").and_then(Value::as_array) {
+        for option in all {
+            validate_at(option, root, value, path, depth + 1, errors);
+        }
+    }
+
+    match value {
+        Value::Object(object) => {
+            for name in schema.get("required").and_then(Value::as_array).into_iter().flatten().filter_map(Value::as_str) {
+                if !object.contains_key(name) {
+                    errors.push(format!("{}: missing required property \"{}\"", path, name));
+                }
+            }
+            let properties = schema.get("properties").and_then(Value::as_object);
+            for (key, item) in object {
+                let item_path = format!("{}.{}", path, key);
+                match (properties.and_then(|p| p.get(key)), schema.get("additionalProperties")) {
+                    (Some(property), _) => validate_at(property, root, item, &item_path, depth + 1, errors),
+                    (None, Some(Value::Bool(false))) => {
+                        errors.push(format!("{}: property \"{}\" is not allowed", path, key))
+                    }
+                    (None, Some(additional)) => validate_at(additional, root, item, &item_path, depth + 1, errors),
+                    (None, None) => {}
+                }
+            }
+        }
+        Value::Array(items) => {
+            let count = items.len() as u64;
+            if let Some(min) = schema.get("minItems").and_then(Value::as_u64).filter(|min| count < *min) {
+                errors.push(format!("{}: expected at least {} items, got {}", path, min, count));
+            }
+            if let Some(max) = schema.get("maxItems").and_then(Value::as_u64).filter(|max| count > *max) {
+                errors.push(format!("{}: expected at most {} items, got {}", path, max, count));
+            }
+            if let Some(item_schema) = schema.get("items") {
+                for (i, item) in items.iter().enumerate() {
+                    validate_at(item_schema, root, item, &format!("{}[{}]", path, i), depth + 1, errors);
+                }
+            }
+        }
+        Value::String(s) => {
+            let length = s.chars().count() as u64;
+            if let Some(min) = schema.get("minLength").and_then(Value::as_u64).filter(|min| length < *min) {
+                errors.push(format!("{}: expected at least {} characters", path, min));
+            }
+            if let Some(max) = schema.get("maxLength").and_then(Value::as_u64).filter(|max| length > *max) {
+                errors.push(format!("{}: expected at most {} characters", path, max));
+            }
+            if let Some(pattern) = schema.get("pattern").and_then(Value::as_str) {
+                if regex::Regex::new(pattern).is_ok_and(|re| !re.is_match(s)) {
+                    errors.push(format!("{}: does not match pattern {}", path, pattern));
+                }
+            }
+        }
+        Value::Number(n) => {
+            let n = n.as_f64().unwrap_or_default();
+            let bound = |key: &str| schema.get(key).and_then(Value::as_f64);
+            if bound("minimum").is_some_and(|min| n < min) || bound("exclusiveMinimum").is_some_and(|min| n <= min) {
+                errors.push(format!("{}: {} is below the minimum", path, n));
+            }
+            if bound("maximum").is_some_and(|max| n > max) || bound("exclusiveMaximum").is_some_and(|max| n >= max) {
+                errors.push(format!("{}: {} is above the maximum", path, n));
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_validate_schema() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "name": { "type": "string", "minLength": 1 },
+                "age": { "type": "integer", "minimum": 0 },
+                "tags": { "type": "array", "items": { "$ref": "#/$defs/tag" }, "maxItems": 2 },
+                "role": { "enum": ["admin", "user"] },
+                "note": { "anyOf": [{ "type": "string" }, { "type": "null" }] }
+            },
+            "required": ["name", "age"],
+            "additionalProperties": false,
+            "$defs": { "tag": { "type": "string", "pattern": "^[a-z]+$" } }
+        });
+        let valid = json!({"name": "Ann", "age": 30, "tags": ["a", "b"], "role": "user", "note": null});
+        assert!(validate(&schema, &valid).is_empty());
+
+        let invalid = json!({"name": "", "age": -1.5, "tags": ["a", "B", "c"], "role": "root", "note": 1, "extra": true});
+        assert_eq!(
+            validate(&schema, &invalid),
+            vec![
+                "$.age: expected integer, got number",
+                "$: property \"extra\" is not allowed",
+                "$.name: expected at least 1 characters",
+                "$.note: does not match any of the allowed schemas",
+                "$.role: must be one of [\"admin\",\"user\"]",
+                "$.tags: expected at most 2 items, got 3",
+                "$.tags[1]: does not match pattern ^[a-z]+$",
+            ]
+        );
+        assert_eq!(validate(&schema, &json!([])), vec!["$: expected object, got array"]);
+    }
+
+    #[test]
+    fn test_repair_json() {
+        let cases = [
+            ("{\"a\": [1, 2,], \"b\": {\"c\": true,},}", json!({"a": [1, 2], "b": {"c": true}})),
+            ("```json\n{\"items\": [{\"id\": 1}, {\"id\": 2", json!({"items": [{"id": 1}, {"id": 2}]})),
+            ("{\"text\": \"unfinished \\", json!({"text": "unfinished "})),
+            ("{\"a\": 1, \"b\":", json!({"a": 1, "b": null})),
+            ("{\"a\": 1, \"b\": tr", json!({"a": 1})),
+            ("{\"a\": [1, 2}", json!({"a": [1, 2]})),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(repair_json(input), Some(expected), "{}", input);
+        }
+        assert_eq!(repair_json("no json here"), None);
+        assert_eq!(repair_json("{\"a\" 1}"), None);
+    }
+
+    #[test]
+    fn test_extract_and_check() {
+        let schema = json!({"type": "object", "required": ["ok"]});
+        assert_eq!(check("{\"ok\": true}", &schema), Ok(json!({"ok": true})));
+        assert_eq!(check("```json\n{\"ok\": 1}\n```", &schema), Ok(json!({"ok": 1})));
+        assert_eq!(check("Here you go: {\"ok\": [1]} hope it helps", &schema), Ok(json!({"ok": [1]})));
+        assert_eq!(check("no json", &schema), Err(vec!["$: reply is not valid JSON".to_string()]));
+        assert_eq!(check("{}", &schema), Err(vec!["$: missing required property \"ok\"".to_string()]));
+    }
+}
@@ -0,0 +1,119 @@
+// 反代服务监督
+// 服务器任务 panic 或监听失败退出时, 由 commands::proxy 中的监督任务按指数退避重新启动;
+// 重启次数与最近一次原因通过 service_status 中间件的响应头和前端事件上报。
+
+use std::any::Any;
+use std::sync::{OnceLock, RwLock};
+use std::time::Duration;
+
+use serde::Serialize;
+use tokio::task::JoinError;
+
+/// 稳定运行超过该时长后再失败, 退避从头开始
+const STABLE_UPTIME: Duration = Duration::from_secs(300);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SupervisorStatus {
+    /// 成功重启的次数
+    pub restarts: u32,
+    /// 连续失败次数 (决定退避时长)
+    pub consecutive_failures: u32,
+    pub last_reason: Option<String>,
+    pub last_failure_at: Option<i64>,
+    /// 正在等待重启
+    pub restarting: bool,
+}
+
+pub struct Supervisor {
+    status: RwLock<SupervisorStatus>,
+}
+
+impl Supervisor {
+    pub fn new() -> Self {
+        Self { status: RwLock::new(SupervisorStatus::default()) }
+    }
+
+    pub fn global() -> &'static Supervisor {
+        static INSTANCE: OnceLock<Supervisor> = OnceLock::new();
+        INSTANCE.get_or_init(Supervisor::new)
+    }
+
+    pub fn status(&self) -> SupervisorStatus {
+        self.status.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// 记录一次异常退出 (或重启失败), 返回下次重启前的等待时间
+    pub fn record_failure(&self, reason: &str, uptime: Option<Duration>) -> Duration {
+        let mut status = self.status.write().unwrap_or_else(|e| e.into_inner());
+        if uptime.is_some_and(|u| u >= STABLE_UPTIME) {
+            status.consecutive_failures = 0;
+        }
+        status.consecutive_failures += 1;
+        status.last_reason = Some(reason.to_string());
+        status.last_failure_at = Some(chrono::Utc::now().timestamp());
+        status.restarting = true;
+        backoff(status.consecutive_failures)
+    }
+
+    pub fn record_restart(&self) {
+        let mut status = self.status.write().unwrap_or_else(|e| e.into_inner());
+        status.restarts += 1;
+        status.restarting = false;
+    }
+}
+
+/// 第 n 次连续失败后的等待时间: 1s, 2s, 4s ... 最长 60s
+pub fn backoff(failures: u32) -> Duration {
+    Duration::from_secs(1u64 << failures.saturating_sub(1).min(6)).min(MAX_BACKOFF)
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string())
+}
+
+/// 服务器任务的退出原因; 正常停止或被取消时返回 None
+pub fn exit_reason(result: Result<Result<(), String>, JoinError>) -> Option<String> {
+    match result {
+        Ok(Ok(())) => None,
+        Ok(Err(reason)) => Some(reason),
+        Err(e) if e.is_panic() => Some(format!("server task panicked: {}", panic_message(e.into_panic()))),
+        Err(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_and_reset() {
+        assert_eq!(backoff(1), Duration::from_secs(1));
+        assert_eq!(backoff(3), Duration::from_secs(4));
+        assert_eq!(backoff(20), MAX_BACKOFF);
+
+        let supervisor = Supervisor::new();
+        assert_eq!(supervisor.record_failure("listener failed", Some(Duration::from_secs(10))), Duration::from_secs(1));
+        assert_eq!(supervisor.record_failure("bind failed", None), Duration::from_secs(2));
+        supervisor.record_restart();
+        // 稳定运行一段时间后再失败, 重新从 1s 开始
+        assert_eq!(supervisor.record_failure("listener failed", Some(STABLE_UPTIME)), Duration::from_secs(1));
+        let status = supervisor.status();
+        assert_eq!(status.restarts, 1);
+        assert!(status.restarting);
+        assert_eq!(status.last_reason.as_deref(), Some("listener failed"));
+    }
+
+    #[tokio::test]
+    async fn test_exit_reason() {
+        let panicked = tokio::spawn(async { panic!("boom") }).await.map(|()| Ok(()));
+        assert_eq!(exit_reason(panicked).as_deref(), Some("server task panicked: boom"));
+        let failed = tokio::spawn(async { Err::<(), _>("listener failed".to_string()) }).await;
+        assert_eq!(exit_reason(failed).as_deref(), Some("listener failed"));
+        assert_eq!(exit_reason(tokio::spawn(async { Ok(()) }).await), None);
+    }
+}
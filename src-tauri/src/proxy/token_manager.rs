@@ -492,6 +492,11 @@ impl TokenManager {
         session_id: Option<&str>,
         target_model: &str,
     ) -> Result<(String, String, String, u64), String> {
+        // 虚拟 Key 绑定了上游账号时直接使用该账号
+        if let Some(email) = crate::proxy::virtual_keys::current_upstream_account() {
+            return self.get_token_by_email(&email).await;
+        }
+
         // 【优化 Issue #284】添加 5 秒超时，防止死锁
         let timeout_duration = std::time::Duration::from_secs(5);
         match tokio::time::timeout(timeout_duration, self.get_token_internal(quota_group, force_rotate, session_id, target_model)).await {
@@ -0,0 +1,233 @@
+// 函数调用模拟
+// 对不支持原生 function calling 的模型 (例如小型本地模型): 请求中的 `tools` 转为系统提示词中的调用说明,
+// 历史中的 tool_calls 与工具结果转为普通文本; 回复中的 `<tool_call>{...}</tool_call>` 块再还原为
+// OpenAI 格式的 `tool_calls`, Agent 客户端无需改动即可使用这些模型。
+
+use std::sync::{OnceLock, RwLock};
+
+use serde_json::{json, Value};
+
+use crate::proxy::common::model_mapping::wildcard_match;
+use crate::proxy::config::ToolEmulationConfig;
+
+const OPEN_TAG: &str = "<tool_call>";
+const CLOSE_TAG: &str = "</tool_call>";
+
+const INSTRUCTIONS: &str = "You can call the tools listed below. To call tools, reply with one or more blocks in exactly this format:\n\
+<tool_call>{\"name\": \"tool_name\", \"arguments\": {\"param\": \"value\"}}</tool_call>\n\
+The arguments must be a JSON object matching the tool's parameters schema. Do not write anything after the tool calls. \
+Tool results are sent back to you in a following message. If no tool is needed, answer normally without any tool_call block.";
+
+fn config() -> &'static RwLock<ToolEmulationConfig> {
+    static CONFIG: OnceLock<RwLock<ToolEmulationConfig>> = OnceLock::new();
+    CONFIG.get_or_init(|| RwLock::new(ToolEmulationConfig::default()))
+}
+
+/// 应用函数调用模拟配置 (服务启动与配置热更新时调用)
+pub fn configure(tool_emulation: &ToolEmulationConfig) {
+    *config().write().unwrap_or_else(|e| e.into_inner()) = tool_emulation.clone();
+}
+
+pub fn applies_to(model: &str) -> bool {
+    let config = config().read().unwrap_or_else(|e| e.into_inner());
+    config.enabled && config.models.iter().any(|p| wildcard_match(p, model))
+}
+
+fn text_of(content: Option<&Value>) -> String {
+    match content {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Array(parts)) => parts
+            .iter()
+            .filter_map(|p| p.get("text").and_then(Value::as_str))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+/// 写入系统提示词的工具说明
+fn instructions(tools: &[Value], tool_choice: Option<&Value>) -> String {
+    let listed: Vec<String> = tools
+        .iter()
+        .filter_map(|t| t.get("function"))
+        .map(|f| {
+            format!(
+                "- {}: {}\n  parameters: {}",
+                f.get("name").and_then(Value::as_str).unwrap_or_default(),
+                f.get("description").and_then(Value::as_str).unwrap_or_default(),
+                f.get("parameters").cloned().unwrap_or_else(|| json!({"type": "object"}))
+            )
+        })
+        .collect();
+    let requirement = match tool_choice {
+        Some(Value::String(s)) if s == "required" => "\n\nYou must call at least one tool in this reply.".to_string(),
+        Some(choice) => match choice.pointer("/function/name").and_then(Value::as_str) {
+            Some(name) => format!("\n\nYou must call the tool \"{}\" in this reply.", name),
+            None => String::new(),
+        },
+        None => String::new(),
+    };
+    format!("{}\n\nTools:\n{}{}", INSTRUCTIONS, listed.join("\n"), requirement)
+}
+
+fn tool_call_block(name: &str, arguments: &str) -> String {
+    let arguments: Value = serde_json::from_str(arguments).unwrap_or_else(|_| Value::String(arguments.to_string()));
+    format!("{}{}{}", OPEN_TAG, json!({"name": name, "arguments": arguments}), CLOSE_TAG)
+}
+
+/// 历史消息转为纯文本: assistant 的 tool_calls 写回 `<tool_call>` 块, tool 结果改为 user 消息
+fn convert_history(messages: &mut [Value]) {
+    let mut names: Vec<(String, String)> = Vec::new(); // (call id, 工具名)
+    for message in messages.iter_mut() {
+        let role = message.get("role").and_then(Value::as_str).unwrap_or_default().to_string();
+        if role == "assistant" {
+            let Some(calls) = message.as_object_mut().and_then(|m| m.remove("tool_calls")) else {
+                continue;
+            };
+            let mut text = text_of(message.get("content"));
+            for call in calls.as_array().into_iter().flatten() {
+                let name = call.pointer("/function/name").and_then(Value::as_str).unwrap_or_default();
+                let arguments = call.pointer("/function/arguments").and_then(Value::as_str).unwrap_or("{}");
+                let id = call.get("id").and_then(Value::as_str).unwrap_or_default();
+                names.push((id.to_string(), name.to_string()));
+                if !text.is_empty() {
+                    text.push('\n');
+                }
+                text.push_str(&tool_call_block(name, arguments));
+            }
+            message["content"] = Value::String(text);
+        } else if role == "tool" {
+            let id = message.get("tool_call_id").and_then(Value::as_str).unwrap_or_default();
+            let name = names.iter().find(|(call_id, _)| call_id == id).map(|(_, n)| n.as_str()).unwrap_or("tool");
+            let content = format!("Result of tool call {} ({}):\n{}", name, id, text_of(message.get("content")));
+            *message = json!({"role": "user", "content": content});
+        }
+    }
+}
+
+/// 改写请求, 返回可以还原的工具名; 请求没有工具时返回 None
+pub fn rewrite_request(body: &mut Value) -> Option<Vec<String>> {
+    let obj = body.as_object_mut()?;
+    let tools = obj.get("tools").and_then(Value::as_array).filter(|t| !t.is_empty())?.clone();
+    obj.remove("tools");
+    obj.remove("parallel_tool_calls");
+    let tool_choice = obj.remove("tool_choice");
+    if let Some(messages) = obj.get_mut("messages").and_then(Value::as_array_mut) {
+        convert_history(messages);
+    }
+    if tool_choice.as_ref().and_then(Value::as_str) == Some("none") {
+        return Some(Vec::new());
+    }
+    let prompt = instructions(&tools, tool_choice.as_ref());
+    crate::proxy::common::system_prompt::inject(
+        "/v1/chat/completions",
+        body,
+        &prompt,
+        crate::proxy::config::SystemPromptMode::Suffix,
+    );
+    Some(tools.iter().filter_map(|t| t.pointer("/function/name").and_then(Value::as_str)).map(String::from).collect())
+}
+
+/// 从回复中取出工具调用, 返回 (剩余文本, tool_calls); 未声明的工具名保留为文本
+pub fn parse_tool_calls(text: &str, names: &[String]) -> (String, Vec<Value>) {
+    let mut remaining = String::new();
+    let mut calls = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find(OPEN_TAG) {
+        let after = &rest[start + OPEN_TAG.len()..];
+        // 模型在结束标签前停止时取到结尾
+        let (inner, next) = match after.find(CLOSE_TAG) {
+            Some(end) => (&after[..end], &after[end + CLOSE_TAG.len()..]),
+            None => (after, ""),
+        };
+        let parsed = crate::proxy::structured_output::extract_json(inner).and_then(|call| {
+            let name = call.get("name").and_then(Value::as_str).filter(|n| names.iter().any(|known| known == n))?;
+            let arguments = match call.get("arguments").or_else(|| call.get("parameters")) {
+                Some(Value::String(s)) => s.clone(),
+                Some(value) => value.to_string(),
+                None => "{}".to_string(),
+            };
+            Some(json!({
+                "id": format!("call_{}", &uuid::Uuid::new_v4().simple().to_string()[..24]),
+                "type": "function",
+                "function": { "name": name, "arguments": arguments }
+            }))
+        });
+        match parsed {
+            Some(call) => {
+                remaining.push_str(&rest[..start]);
+                calls.push(call);
+            }
+            None => remaining.push_str(&rest[..rest.len() - next.len()]),
+        }
+        rest = next;
+    }
+    remaining.push_str(rest);
+    (remaining.trim().to_string(), calls)
+}
+
+/// 还原 Chat Completions 响应中的 tool_calls, 返回调用数
+pub fn rewrite_response(completion: &mut Value, names: &[String]) -> usize {
+    let Some(choice) = completion.pointer_mut("/choices/0") else {
+        return 0;
+    };
+    let Some(text) = choice.pointer("/message/content").and_then(Value::as_str) else {
+        return 0;
+    };
+    let (remaining, calls) = parse_tool_calls(text, names);
+    if calls.is_empty() {
+        return 0;
+    }
+    let count = calls.len();
+    choice["message"]["content"] = if remaining.is_empty() { Value::Null } else { Value::String(remaining) };
+    choice["message"]["tool_calls"] = Value::Array(calls);
+    choice["finish_reason"] = json!("tool_calls");
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rewrite_request_flattens_tools_and_history() {
+        let mut body = json!({
+            "model": "llama3",
+            "messages": [
+                {"role": "user", "content": "Weather in Paris?"},
+                {"role": "assistant", "content": null, "tool_calls": [
+                    {"id": "call_1", "type": "function", "function": {"name": "weather", "arguments": "{\"city\":\"Paris\"}"}}
+                ]},
+                {"role": "tool", "tool_call_id": "call_1", "content": "18C"}
+            ],
+            "tools": [{"type": "function", "function": {"name": "weather", "description": "Get weather", "parameters": {"type": "object"}}}],
+            "tool_choice": "required"
+        });
+        assert_eq!(rewrite_request(&mut body), Some(vec!["weather".to_string()]));
+        assert!(body.get("tools").is_none() && body.get("tool_choice").is_none());
+        let messages = body["messages"].as_array().unwrap();
+        let system = messages[0]["content"].as_str().unwrap();
+        assert!(system.contains("- weather: Get weather") && system.ends_with("You must call at least one tool in this reply."));
+        assert_eq!(messages[2]["content"], "<tool_call>{\"arguments\":{\"city\":\"Paris\"},\"name\":\"weather\"}</tool_call>");
+        assert_eq!(messages[3], json!({"role": "user", "content": "Result of tool call weather (call_1):\n18C"}));
+
+        let mut plain = json!({"messages": [{"role": "user", "content": "hi"}]});
+        assert_eq!(rewrite_request(&mut plain), None);
+    }
+
+    #[test]
+    fn test_parse_tool_calls() {
+        let names = vec!["weather".to_string()];
+        let text = "Let me check.\n<tool_call>{\"name\": \"weather\", \"arguments\": {\"city\": \"Paris\"}}</tool_call>\n\
+<tool_call>{\"name\": \"unknown\", \"arguments\": {}}</tool_call>\n<tool_call>{\"name\": \"weather\", \"arguments\": {\"city\": \"Rome\"}}";
+        let (remaining, calls) = parse_tool_calls(text, &names);
+        assert_eq!(remaining, "Let me check.\n\n<tool_call>{\"name\": \"unknown\", \"arguments\": {}}</tool_call>");
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0]["function"]["arguments"], "{\"city\":\"Paris\"}");
+        assert_eq!(calls[1]["function"]["arguments"], "{\"city\":\"Rome\"}");
+
+        let mut completion = json!({"choices": [{"message": {"role": "assistant", "content": text}, "finish_reason": "stop"}]});
+        assert_eq!(rewrite_response(&mut completion, &names), 2);
+        assert_eq!(completion["choices"][0]["finish_reason"], "tool_calls");
+    }
+}
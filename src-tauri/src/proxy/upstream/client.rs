@@ -1,49 +1,145 @@
 // 上游客户端实现
 // 基于高性能通讯接口封装
 
-use reqwest::{header, Client, Response, StatusCode};
+use reqwest::{header, Client, Response};
 use serde_json::Value;
 use tokio::time::Duration;
 
+use super::routing::{is_retryable, AttemptFailure, UpstreamRouter};
+
 // Cloud Code v1internal endpoints (fallback order: Sandbox → Daily → Prod)
 // 优先使用 Sandbox/Daily 环境以避免 Prod环境的 429 错误 (Ref: Issue #1176)
 const V1_INTERNAL_BASE_URL_PROD: &str = "https://cloudcode-pa.googleapis.com/v1internal";
 const V1_INTERNAL_BASE_URL_DAILY: &str = "https://daily-cloudcode-pa.googleapis.com/v1internal";
 const V1_INTERNAL_BASE_URL_SANDBOX: &str = "https://daily-cloudcode-pa.sandbox.googleapis.com/v1internal";
 
-const V1_INTERNAL_BASE_URL_FALLBACKS: [&str; 3] = [
+pub(crate) const V1_INTERNAL_BASE_URL_FALLBACKS: [&str; 3] = [
     V1_INTERNAL_BASE_URL_SANDBOX, // 优先级 1: Sandbox (已知有效且稳定)
     V1_INTERNAL_BASE_URL_DAILY,   // 优先级 2: Daily (备用)
     V1_INTERNAL_BASE_URL_PROD,    // 优先级 3: Prod (仅作为兜底)
 ];
 
 pub struct UpstreamClient {
-    http_client: Client,
+    /// 连接超时或连接池配置变化时重建 (reqwest 只能在构建时设置)
+    http_client: std::sync::RwLock<Client>,
+    proxy_config: Option<crate::proxy::config::UpstreamProxyConfig>,
+    tls: crate::proxy::config::UpstreamTlsConfig,
+    pool_config: std::sync::RwLock<crate::proxy::config::ConnectionPoolConfig>,
+    router: UpstreamRouter,
 }
 
 impl UpstreamClient {
-    pub fn new(proxy_config: Option<crate::proxy::config::UpstreamProxyConfig>) -> Self {
-        let mut builder = Client::builder()
-            // Connection settings (优化连接复用，减少建立开销)
-            .connect_timeout(Duration::from_secs(20))
-            .pool_max_idle_per_host(16)                  // 每主机最多 16 个空闲连接
-            .pool_idle_timeout(Duration::from_secs(90))  // 空闲连接保持 90 秒
-            .tcp_keepalive(Duration::from_secs(60))      // TCP 保活探测 60 秒
-            .timeout(Duration::from_secs(600))
-            .user_agent(crate::constants::USER_AGENT.as_str());
+    pub fn new(
+        proxy_config: Option<crate::proxy::config::UpstreamProxyConfig>,
+        tls: crate::proxy::config::UpstreamTlsConfig,
+    ) -> Self {
+        let routing = crate::proxy::config::UpstreamRoutingConfig::default();
+        let pool = crate::proxy::config::ConnectionPoolConfig::default();
+        let http_client = Self::build_http_client(proxy_config.as_ref(), &tls, routing.connect_timeout_secs, &pool);
+
+        Self {
+            http_client: std::sync::RwLock::new(http_client),
+            proxy_config,
+            tls,
+            pool_config: std::sync::RwLock::new(pool),
+            router: UpstreamRouter::new(routing),
+        }
+    }
+
+    fn build_http_client(
+        proxy_config: Option<&crate::proxy::config::UpstreamProxyConfig>,
+        tls: &crate::proxy::config::UpstreamTlsConfig,
+        connect_timeout_secs: u64,
+        pool: &crate::proxy::config::ConnectionPoolConfig,
+    ) -> Client {
+        // Connection settings (优化连接复用，减少建立开销)
+        let base = || {
+            let builder = Client::builder()
+                .connect_timeout(Duration::from_secs(connect_timeout_secs.max(1)))
+                .timeout(Duration::from_secs(600))
+                .user_agent(crate::constants::USER_AGENT.as_str());
+            super::client_pool::tune(builder, pool)
+        };
+        let mut builder = super::client_pool::apply_tls(base(), tls).unwrap_or_else(|e| {
+            tracing::warn!("UpstreamClient ignored TLS options: {}", e);
+            base()
+        });
 
         if let Some(config) = proxy_config {
-            if config.enabled && !config.url.is_empty() {
-                if let Ok(proxy) = reqwest::Proxy::all(&config.url) {
+            match super::client_pool::build_proxy(config) {
+                Ok(Some(proxy)) => {
                     builder = builder.proxy(proxy);
                     tracing::info!("UpstreamClient enabled proxy: {}", config.url);
                 }
+                Ok(None) => {}
+                Err(e) => tracing::warn!("UpstreamClient ignored proxy: {}", e),
             }
         }
 
-        let http_client = builder.build().expect("Failed to create HTTP client");
+        builder.build().expect("Failed to create HTTP client")
+    }
+
+    fn http(&self) -> Client {
+        self.http_client.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    fn pool_config(&self) -> crate::proxy::config::ConnectionPoolConfig {
+        self.pool_config.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// 热更新上游路由与重试策略
+    pub fn update_routing(&self, config: crate::proxy::config::UpstreamRoutingConfig) {
+        if config.connect_timeout_secs != self.router.config().connect_timeout_secs {
+            let client = Self::build_http_client(self.proxy_config.as_ref(), &self.tls, config.connect_timeout_secs, &self.pool_config());
+            *self.http_client.write().unwrap_or_else(|e| e.into_inner()) = client;
+        }
+        self.router.update_config(config);
+    }
+
+    /// 热更新连接池配置, 同时更新直连第三方上游的共享客户端
+    pub fn update_connection_pool(&self, config: crate::proxy::config::ConnectionPoolConfig) {
+        super::client_pool::ClientPool::global().update_config(config.clone());
+        if config == self.pool_config() {
+            return;
+        }
+        let client = Self::build_http_client(self.proxy_config.as_ref(), &self.tls, self.router.config().connect_timeout_secs, &config);
+        *self.http_client.write().unwrap_or_else(|e| e.into_inner()) = client;
+        *self.pool_config.write().unwrap_or_else(|e| e.into_inner()) = config;
+    }
+
+    /// 流式响应等待首个有效数据块的超时
+    pub fn first_byte_timeout(&self) -> Duration {
+        self.router.first_byte_timeout()
+    }
+
+    /// 流式响应数据块间隔超时 (None = 不限制)
+    pub fn stream_idle_timeout(&self) -> Option<Duration> {
+        self.router.stream_idle_timeout()
+    }
+
+    /// 健康检查探测失败: 计入端点失败
+    pub fn record_probe_failure(&self, endpoint: &str, failure: AttemptFailure) {
+        self.router.record_failure(endpoint, failure);
+    }
+
+    /// 健康检查探测成功
+    pub fn record_probe_success(&self, endpoint: &str) {
+        self.router.record_probe_success(endpoint);
+    }
 
-        Self { http_client }
+    /// 流式响应中途停滞: 计入端点超时失败
+    pub fn record_stream_stall(&self, endpoint: &str) {
+        self.router.record_failure(endpoint, AttemptFailure::Timeout);
+    }
+
+    /// 各 v1internal 端点的健康状态
+    pub fn endpoint_health(&self) -> Vec<super::routing::EndpointStatus> {
+        self.router.health_snapshot()
+    }
+
+    /// 熔断器汇总状态 (未启用熔断时为 None)
+    pub fn circuit_summary(&self) -> Option<super::routing::CircuitState> {
+        self.router.circuit_summary()
     }
 
     /// 构建 v1internal URL
@@ -57,18 +153,115 @@ impl UpstreamClient {
         }
     }
 
-    /// 判断是否应尝试下一个端点
-    /// 
-    /// 当遇到以下错误时，尝试切换到备用端点：
-    /// - 429 Too Many Requests（限流）
-    /// - 408 Request Timeout（超时）
-    /// - 404 Not Found（端点不存在）
-    /// - 5xx Server Error（服务器错误）
-    fn should_try_next_endpoint(status: StatusCode) -> bool {
-        status == StatusCode::TOO_MANY_REQUESTS
-            || status == StatusCode::REQUEST_TIMEOUT
-            || status == StatusCode::NOT_FOUND
-            || status.is_server_error()
+    /// 按路由策略发送请求: 失败时切换端点并退避重试
+    ///
+    /// `streaming` 为 true 时启用幂等保护 (见 `routing::is_retryable`)。
+    /// 所有尝试都返回错误状态码时, 返回最后一个上游响应交给调用方处理。
+    async fn send_with_failover<F>(
+        &self,
+        label: &str,
+        model: Option<&str>,
+        streaming: bool,
+        build_request: F,
+    ) -> Result<Response, String>
+    where
+        F: Fn(&str) -> reqwest::RequestBuilder,
+    {
+        let endpoints = self.router.endpoints_for(model);
+        if endpoints.is_empty() {
+            let retry_in = self.router.circuit_retry_after().map(|d| d.as_secs() + 1).unwrap_or(0);
+            tracing::warn!("Circuit breaker open for all upstream endpoints ({}), failing fast", label);
+            return Err(format!(
+                "Circuit breaker open: all upstream endpoints are failing, next probe in {}s",
+                retry_in
+            ));
+        }
+        let max_attempts = self.router.max_attempts(endpoints.len());
+        let attempt_timeout = self.router.attempt_timeout();
+        let mut last_err: Option<String> = None;
+
+        for attempt in 0..max_attempts {
+            let Some(base_url) = UpstreamRouter::endpoint_for_attempt(&endpoints, attempt) else {
+                break;
+            };
+            let has_next = attempt + 1 < max_attempts;
+            // 半开端点的探测名额在实际发送时才占用, 已被其他请求占用时换下一个端点
+            if !self.router.claim(base_url) {
+                tracing::debug!("Endpoint {} is being probed by another request, skipping ({})", base_url, label);
+                last_err = Some(format!("Circuit breaker open for {}", base_url));
+                continue;
+            }
+
+            let delay = self.router.backoff(attempt, endpoints.len());
+            if !delay.is_zero() {
+                tokio::time::sleep(delay).await;
+            }
+
+            let send = build_request(base_url).send();
+            let result = match attempt_timeout {
+                Some(limit) => match tokio::time::timeout(limit, send).await {
+                    Ok(r) => r.map_err(|e| (AttemptFailure::from_reqwest(&e), e.to_string())),
+                    Err(_) => Err((AttemptFailure::Timeout, format!("no response within {}s", limit.as_secs()))),
+                },
+                None => send.await.map_err(|e| (AttemptFailure::from_reqwest(&e), e.to_string())),
+            };
+
+            match result {
+                Ok(resp) if resp.status().is_success() => {
+                    self.router.record_success(base_url);
+                    crate::proxy::middleware::stream_watchdog::record_served_endpoint(base_url);
+                    if attempt > 0 {
+                        tracing::info!(
+                            "✓ Upstream fallback succeeded | {} | Endpoint: {} | Status: {} | Attempt: {}/{}",
+                            label,
+                            base_url,
+                            resp.status(),
+                            attempt + 1,
+                            max_attempts
+                        );
+                    } else {
+                        tracing::debug!("✓ Upstream request succeeded | {} | Endpoint: {} | Status: {}", label, base_url, resp.status());
+                    }
+                    return Ok(resp);
+                }
+                Ok(resp) => {
+                    let status = resp.status();
+                    let failure = AttemptFailure::Status(status);
+                    self.router.record_failure(base_url, failure);
+
+                    // 如果还有剩余尝试且当前错误可重试，则切换
+                    if has_next && is_retryable(failure, streaming) {
+                        tracing::warn!(
+                            "Upstream endpoint returned {} at {} ({}), trying next endpoint",
+                            status,
+                            base_url,
+                            label
+                        );
+                        last_err = Some(format!("Upstream {} returned {}", base_url, status));
+                        continue;
+                    }
+
+                    // 不可重试的错误或已是最后一次尝试，直接返回
+                    return Ok(resp);
+                }
+                Err((failure, e)) => {
+                    let msg = format!("HTTP request failed at {}: {}", base_url, e);
+                    tracing::debug!("{}", msg);
+                    self.router.record_failure(base_url, failure);
+                    last_err = Some(msg);
+
+                    if has_next && is_retryable(failure, streaming) {
+                        continue;
+                    }
+                    if streaming && has_next {
+                        tracing::warn!("Not retrying streaming request after {:?} at {} ({})", failure, base_url, label);
+                    }
+                    break;
+                }
+            }
+        }
+
+        Err(last_err.unwrap_or_else(|| "All endpoints failed".to_string()))
     }
 
     /// 调用 v1internal API（基础方法）
@@ -113,6 +306,12 @@ impl UpstreamClient {
                 }),
         );
 
+        if let Some(request_id) = crate::proxy::middleware::request_id::current_request_id() {
+            if let Ok(hv) = header::HeaderValue::from_str(&request_id) {
+                headers.insert(crate::proxy::middleware::request_id::REQUEST_ID_HEADER, hv);
+            }
+        }
+
         // 注入额外的 Headers (如 anthropic-beta)
         for (k, v) in extra_headers {
             if let Ok(hk) = header::HeaderName::from_bytes(k.as_bytes()) {
@@ -122,69 +321,18 @@ impl UpstreamClient {
             }
         }
 
-        let mut last_err: Option<String> = None;
-
-        // 遍历所有端点，失败时自动切换
-        for (idx, base_url) in V1_INTERNAL_BASE_URL_FALLBACKS.iter().enumerate() {
-            let url = Self::build_url(base_url, method, query_string);
-            let has_next = idx + 1 < V1_INTERNAL_BASE_URL_FALLBACKS.len();
+        let model = body.get("model").and_then(|v| v.as_str());
+        let streaming = query_string.map_or(false, |qs| qs.contains("alt=sse"));
+        crate::proxy::monitor::capture_upstream_request(method, &body);
 
-            let response = self
-                .http_client
-                .post(&url)
+        // 按路由策略遍历端点，失败时自动切换
+        let http = self.http();
+        self.send_with_failover(method, model, streaming, |base_url| {
+            http.post(Self::build_url(base_url, method, query_string))
                 .headers(headers.clone())
                 .json(&body)
-                .send()
-                .await;
-
-            match response {
-                Ok(resp) => {
-                    let status = resp.status();
-                    if status.is_success() {
-                        if idx > 0 {
-                            tracing::info!(
-                                "✓ Upstream fallback succeeded | Endpoint: {} | Status: {} | Attempt: {}/{}",
-                                base_url,
-                                status,
-                                idx + 1,
-                                V1_INTERNAL_BASE_URL_FALLBACKS.len()
-                            );
-                        } else {
-                            tracing::debug!("✓ Upstream request succeeded | Endpoint: {} | Status: {}", base_url, status);
-                        }
-                        return Ok(resp);
-                    }
-
-                    // 如果有下一个端点且当前错误可重试，则切换
-                    if has_next && Self::should_try_next_endpoint(status) {
-                        tracing::warn!(
-                            "Upstream endpoint returned {} at {} (method={}), trying next endpoint",
-                            status,
-                            base_url,
-                            method
-                        );
-                        last_err = Some(format!("Upstream {} returned {}", base_url, status));
-                        continue;
-                    }
-
-                    // 不可重试的错误或已是最后一个端点，直接返回
-                    return Ok(resp);
-                }
-                Err(e) => {
-                    let msg = format!("HTTP request failed at {}: {}", base_url, e);
-                    tracing::debug!("{}", msg);
-                    last_err = Some(msg);
-
-                    // 如果是最后一个端点，退出循环
-                    if !has_next {
-                        break;
-                    }
-                    continue;
-                }
-            }
-        }
-
-        Err(last_err.unwrap_or_else(|| "All endpoints failed".to_string()))
+        })
+        .await
     }
 
     /// 调用 v1internal API（带 429 重试,支持闭包）
@@ -230,70 +378,22 @@ impl UpstreamClient {
                 }),
         );
 
-        let mut last_err: Option<String> = None;
-
-        // 遍历所有端点，失败时自动切换
-        for (idx, base_url) in V1_INTERNAL_BASE_URL_FALLBACKS.iter().enumerate() {
-            let url = Self::build_url(base_url, "fetchAvailableModels", None);
-
-            let response = self
-                .http_client
-                .post(&url)
-                .headers(headers.clone())
-                .json(&serde_json::json!({}))
-                .send()
-                .await;
-
-            match response {
-                Ok(resp) => {
-                    let status = resp.status();
-                    if status.is_success() {
-                        if idx > 0 {
-                            tracing::info!(
-                                "✓ Upstream fallback succeeded for fetchAvailableModels | Endpoint: {} | Status: {}",
-                                base_url,
-                                status
-                            );
-                        } else {
-                            tracing::debug!("✓ fetchAvailableModels succeeded | Endpoint: {}", base_url);
-                        }
-                        let json: Value = resp
-                            .json()
-                            .await
-                            .map_err(|e| format!("Parse json failed: {}", e))?;
-                        return Ok(json);
-                    }
-
-                    // 如果有下一个端点且当前错误可重试，则切换
-                    let has_next = idx + 1 < V1_INTERNAL_BASE_URL_FALLBACKS.len();
-                    if has_next && Self::should_try_next_endpoint(status) {
-                        tracing::warn!(
-                            "fetchAvailableModels returned {} at {}, trying next endpoint",
-                            status,
-                            base_url
-                        );
-                        last_err = Some(format!("Upstream error: {}", status));
-                        continue;
-                    }
-
-                    // 不可重试的错误或已是最后一个端点
-                    return Err(format!("Upstream error: {}", status));
-                }
-                Err(e) => {
-                    let msg = format!("Request failed at {}: {}", base_url, e);
-                    tracing::debug!("{}", msg);
-                    last_err = Some(msg);
-
-                    // 如果是最后一个端点，退出循环
-                    if idx + 1 >= V1_INTERNAL_BASE_URL_FALLBACKS.len() {
-                        break;
-                    }
-                    continue;
-                }
-            }
+        let http = self.http();
+        let resp = self
+            .send_with_failover("fetchAvailableModels", None, false, |base_url| {
+                http.post(Self::build_url(base_url, "fetchAvailableModels", None))
+                    .headers(headers.clone())
+                    .json(&serde_json::json!({}))
+            })
+            .await?;
+
+        let status = resp.status();
+        if !status.is_success() {
+            return Err(format!("Upstream error: {}", status));
         }
-
-        Err(last_err.unwrap_or_else(|| "All endpoints failed".to_string()))
+        resp.json()
+            .await
+            .map_err(|e| format!("Parse json failed: {}", e))
     }
 }
 
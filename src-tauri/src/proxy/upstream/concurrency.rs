@@ -0,0 +1,243 @@
+// 上游并发限制
+// 每个上游一个信号量, 超出并发的请求按 FIFO 排队 (tokio Semaphore 公平调度),
+// 队列已满或排队超时时拒绝, 避免突发流量压垮上游。
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::proxy::config::ConcurrencyConfig;
+
+#[derive(Debug)]
+struct Lane {
+    semaphore: Arc<Semaphore>,
+    max_concurrent: usize,
+    max_queue: usize,
+    queued: AtomicUsize,
+    waited: AtomicU64,
+    total_wait_ms: AtomicU64,
+    last_wait_ms: AtomicU64,
+}
+
+impl Lane {
+    fn new(max_concurrent: usize, max_queue: usize) -> Self {
+        Self {
+            semaphore: Arc::new(Semaphore::new(max_concurrent)),
+            max_concurrent,
+            max_queue,
+            queued: AtomicUsize::new(0),
+            waited: AtomicU64::new(0),
+            total_wait_ms: AtomicU64::new(0),
+            last_wait_ms: AtomicU64::new(0),
+        }
+    }
+
+    fn record_wait(&self, wait: Duration) {
+        let ms = wait.as_millis() as u64;
+        self.waited.fetch_add(1, Ordering::Relaxed);
+        self.total_wait_ms.fetch_add(ms, Ordering::Relaxed);
+        self.last_wait_ms.store(ms, Ordering::Relaxed);
+    }
+}
+
+/// 离开队列时 (包括请求被取消) 减少排队计数
+struct QueuedGuard<'a>(&'a AtomicUsize);
+
+impl Drop for QueuedGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// 获得的执行许可, 释放时归还并发名额
+#[derive(Debug)]
+pub struct Admission {
+    _permit: Option<OwnedSemaphorePermit>,
+    pub wait: Duration,
+    /// 进入时前面排队的请求数
+    pub queue_depth: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueueRejected {
+    Full,
+    Timeout,
+}
+
+/// 单个上游的队列状态
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct QueueStatus {
+    pub upstream: String,
+    pub max_concurrent: usize,
+    pub max_queue: usize,
+    pub in_flight: usize,
+    pub queued: usize,
+    pub avg_wait_ms: f64,
+    pub last_wait_ms: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct ConcurrencyLimiter {
+    config: RwLock<ConcurrencyConfig>,
+    lanes: Mutex<HashMap<String, Arc<Lane>>>,
+}
+
+impl ConcurrencyLimiter {
+    pub fn new(config: ConcurrencyConfig) -> Self {
+        Self {
+            config: RwLock::new(config),
+            lanes: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn update_config(&self, config: ConcurrencyConfig) {
+        *self.config.write().unwrap_or_else(|e| e.into_inner()) = config;
+    }
+
+    pub fn config(&self) -> ConcurrencyConfig {
+        self.config.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// 获取上游的队列; 限制变更后新建队列, 旧队列中的请求照常完成
+    fn lane(&self, upstream: &str, max_concurrent: usize, max_queue: usize) -> Arc<Lane> {
+        let mut lanes = self.lanes.lock().unwrap_or_else(|e| e.into_inner());
+        match lanes.get(upstream) {
+            Some(lane) if lane.max_concurrent == max_concurrent && lane.max_queue == max_queue => lane.clone(),
+            _ => {
+                let lane = Arc::new(Lane::new(max_concurrent, max_queue));
+                lanes.insert(upstream.to_string(), lane.clone());
+                lane
+            }
+        }
+    }
+
+    /// 等待执行名额; 未启用时立即放行
+    pub async fn acquire(&self, upstream: &str) -> Result<Admission, QueueRejected> {
+        let config = self.config();
+        if !config.enabled {
+            return Ok(Admission {
+                _permit: None,
+                wait: Duration::ZERO,
+                queue_depth: 0,
+            });
+        }
+        let (max_concurrent, max_queue) = config.limits_for(upstream);
+        let lane = self.lane(upstream, max_concurrent, max_queue);
+
+        if let Ok(permit) = lane.semaphore.clone().try_acquire_owned() {
+            lane.record_wait(Duration::ZERO);
+            return Ok(Admission {
+                _permit: Some(permit),
+                wait: Duration::ZERO,
+                queue_depth: 0,
+            });
+        }
+
+        let queue_depth = lane.queued.fetch_add(1, Ordering::Relaxed);
+        let _queued = QueuedGuard(&lane.queued);
+        if queue_depth >= lane.max_queue {
+            return Err(QueueRejected::Full);
+        }
+
+        let start = Instant::now();
+        let timeout = Duration::from_secs(config.queue_timeout_secs.max(1));
+        match tokio::time::timeout(timeout, lane.semaphore.clone().acquire_owned()).await {
+            Ok(Ok(permit)) => {
+                let wait = start.elapsed();
+                lane.record_wait(wait);
+                Ok(Admission {
+                    _permit: Some(permit),
+                    wait,
+                    queue_depth,
+                })
+            }
+            _ => Err(QueueRejected::Timeout),
+        }
+    }
+
+    pub fn snapshot(&self) -> Vec<QueueStatus> {
+        let lanes = self.lanes.lock().unwrap_or_else(|e| e.into_inner());
+        let mut status: Vec<QueueStatus> = lanes
+            .iter()
+            .map(|(upstream, lane)| {
+                let waited = lane.waited.load(Ordering::Relaxed);
+                QueueStatus {
+                    upstream: upstream.clone(),
+                    max_concurrent: lane.max_concurrent,
+                    max_queue: lane.max_queue,
+                    in_flight: lane.max_concurrent - lane.semaphore.available_permits(),
+                    queued: lane.queued.load(Ordering::Relaxed),
+                    avg_wait_ms: if waited == 0 {
+                        0.0
+                    } else {
+                        lane.total_wait_ms.load(Ordering::Relaxed) as f64 / waited as f64
+                    },
+                    last_wait_ms: lane.last_wait_ms.load(Ordering::Relaxed),
+                }
+            })
+            .collect();
+        status.sort_by(|a, b| a.upstream.cmp(&b.upstream));
+        status
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(max_concurrent: usize, max_queue: usize) -> Arc<ConcurrencyLimiter> {
+        Arc::new(ConcurrencyLimiter::new(ConcurrencyConfig {
+            enabled: true,
+            max_concurrent,
+            max_queue,
+            queue_timeout_secs: 5,
+            ..Default::default()
+        }))
+    }
+
+    #[tokio::test]
+    async fn test_queue_fifo_and_full() {
+        let limiter = limiter(1, 1);
+        let first = limiter.acquire("google").await.unwrap();
+        assert_eq!(first.queue_depth, 0);
+
+        // 第二个请求排队, 第三个因队列已满被拒绝
+        let queued = {
+            let limiter = limiter.clone();
+            tokio::spawn(async move { limiter.acquire("google").await.map(|a| a.queue_depth) })
+        };
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(limiter.snapshot()[0].queued, 1);
+        assert_eq!(limiter.acquire("google").await.unwrap_err(), QueueRejected::Full);
+
+        // 其他上游互不影响
+        assert!(limiter.acquire("zai").await.is_ok());
+
+        drop(first);
+        assert_eq!(queued.await.unwrap(), Ok(0));
+        let google = limiter.snapshot().into_iter().find(|s| s.upstream == "google").unwrap();
+        assert_eq!(google.queued, 0);
+        assert_eq!(google.in_flight, 0);
+        assert!(google.last_wait_ms >= 10);
+    }
+
+    #[tokio::test]
+    async fn test_queue_timeout_and_disabled() {
+        let limiter = ConcurrencyLimiter::new(ConcurrencyConfig {
+            enabled: true,
+            max_concurrent: 1,
+            max_queue: 4,
+            queue_timeout_secs: 1,
+            ..Default::default()
+        });
+        let _held = limiter.acquire("google").await.unwrap();
+        assert_eq!(limiter.acquire("google").await.unwrap_err(), QueueRejected::Timeout);
+
+        limiter.update_config(ConcurrencyConfig::default());
+        assert!(limiter.acquire("google").await.is_ok());
+        assert!(limiter.acquire("google").await.is_ok());
+    }
+}
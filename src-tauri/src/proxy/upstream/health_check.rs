@@ -0,0 +1,449 @@
+// 上游健康检查调度
+// 后台定时探测每个已配置的上游 (v1internal 端点、z.ai、OpenAI 兼容上游), 记录延迟与可用性历史。
+// 探测携带真实凭证 (v1internal 使用账号池中的 Token, z.ai 使用 Key 池中的 Key), 凭证被拒或被限流同样视为不可用。
+// v1internal 端点的结果会回写路由器: 探测失败计入端点失败, 冷却结束后探测成功即关闭熔断,
+// 无需等待真实请求充当探测; service_status 中间件据此标记整体健康状态。
+
+use dashmap::DashMap;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use super::routing::AttemptFailure;
+use crate::proxy::providers::key_pool::KeyPool;
+use crate::proxy::config::{HealthCheckConfig, WebhookEventKind};
+use crate::proxy::webhooks::{self, WebhookEvent};
+use crate::proxy::server::AppState;
+
+/// 探测目标
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProbeTarget {
+    /// 展示名称, 同时作为历史记录的键
+    pub name: String,
+    pub kind: &'static str,
+    pub url: String,
+    /// (Header 名, 值)
+    pub auth: Option<(&'static str, String)>,
+}
+
+/// 单次探测结果
+#[derive(Debug, Clone, Serialize)]
+pub struct ProbeResult {
+    pub timestamp: i64,
+    pub ok: bool,
+    pub latency_ms: Option<u64>,
+    pub status: Option<u16>,
+    pub error: Option<String>,
+}
+
+/// 上游健康状态 (用于状态展示)
+#[derive(Debug, Clone, Serialize)]
+pub struct UpstreamHealth {
+    pub upstream: String,
+    pub kind: String,
+    pub available: bool,
+    pub last_latency_ms: Option<u64>,
+    /// 历史窗口内的探测成功率 (0.0 - 1.0)
+    pub availability: f64,
+    pub history: Vec<ProbeResult>,
+}
+
+/// 所有上游的汇总状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverallHealth {
+    Ok,
+    Degraded,
+    Down,
+}
+
+impl OverallHealth {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            OverallHealth::Ok => "ok",
+            OverallHealth::Degraded => "degraded",
+            OverallHealth::Down => "down",
+        }
+    }
+}
+
+struct TargetHistory {
+    kind: &'static str,
+    results: VecDeque<ProbeResult>,
+}
+
+pub struct HealthChecker {
+    config: std::sync::RwLock<HealthCheckConfig>,
+    history: DashMap<String, TargetHistory>,
+    task: std::sync::Mutex<Option<tokio::task::JoinHandle<()>>>,
+}
+
+/// 探测结果判定: 5xx、凭证被拒 (401/403) 与限流 (429) 视为不可用
+fn classify_status(status: u16) -> bool {
+    status < 500 && !matches!(status, 401 | 403 | 429)
+}
+
+pub(crate) fn openai_models_target(name: String, kind: &'static str, base_url: &str, api_key: &str) -> ProbeTarget {
+    let base = base_url.trim_end_matches('/');
+    let url = if base.ends_with("/v1") { format!("{}/models", base) } else { format!("{}/v1/models", base) };
+    ProbeTarget {
+        name,
+        kind,
+        url,
+        auth: (!api_key.is_empty()).then(|| ("Authorization", format!("Bearer {}", api_key))),
+    }
+}
+
+/// z.ai 的模型列表地址, 使用 Key 池按负载均衡策略选出的 Key (全部停用时不带凭证); 未启用时返回 None
+pub(crate) fn zai_models_target(zai: &crate::proxy::ZaiConfig, keys: &KeyPool) -> Option<ProbeTarget> {
+    if !zai.enabled || zai.dispatch_mode == crate::proxy::ZaiDispatchMode::Off {
+        return None;
+    }
+    let key = keys.acquire(&zai.key_pool(), zai.key_strategy).map(|lease| lease.key.clone());
+    Some(ProbeTarget {
+        name: "zai".to_string(),
+        kind: "zai",
+        url: format!("{}/v1/models", zai.base_url.trim_end_matches('/')),
+        auth: key.map(|key| ("x-api-key", key)),
+    })
+}
+
+/// 根据当前配置生成探测目标列表; 没有可用账号 Token 时跳过 v1internal 端点
+pub fn probe_targets(
+    endpoints: &[String],
+    access_token: Option<&str>,
+    zai: &crate::proxy::ZaiConfig,
+    zai_keys: &KeyPool,
+    audio: &crate::proxy::config::AudioConfig,
+    embeddings: &crate::proxy::config::EmbeddingsConfig,
+    images: &crate::proxy::config::ImagesConfig,
+) -> Vec<ProbeTarget> {
+    let mut targets: Vec<ProbeTarget> = access_token
+        .map(|token| {
+            endpoints
+                .iter()
+                .map(|endpoint| ProbeTarget {
+                    name: endpoint.clone(),
+                    kind: "v1internal",
+                    url: format!("{}:fetchAvailableModels", endpoint),
+                    auth: Some(("Authorization", format!("Bearer {}", token))),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    if let Some(target) = zai_models_target(zai, zai_keys) {
+        targets.push(target);
+    }
+    if audio.enabled {
+        targets.push(openai_models_target("audio".to_string(), "openai", &audio.base_url, &audio.api_key));
+    }
+    if embeddings.enabled {
+        for provider in embeddings.providers.iter().filter(|p| p.enabled && !p.base_url.is_empty()) {
+            targets.push(openai_models_target(
+                format!("embeddings:{}", provider.pattern),
+                "embeddings",
+                &provider.base_url,
+                &provider.api_key,
+            ));
+        }
+    }
+    for backend in images.backends.iter().filter(|b| b.enabled && !b.base_url.is_empty()) {
+        targets.push(openai_models_target(
+            format!("images:{}", backend.pattern),
+            "images",
+            &backend.base_url,
+            &backend.api_key,
+        ));
+    }
+
+    // 相同名称只保留第一个
+    let mut seen = std::collections::HashSet::new();
+    targets.retain(|t| seen.insert(t.name.clone()));
+    targets
+}
+
+/// 可用性变化对应的通知事件; 首次探测即失败也视为故障
+fn availability_change(previous: Option<bool>, ok: bool) -> Option<WebhookEventKind> {
+    match (previous, ok) {
+        (Some(true) | None, false) => Some(WebhookEventKind::UpstreamDown),
+        (Some(false), true) => Some(WebhookEventKind::UpstreamRecovered),
+        _ => None,
+    }
+}
+
+fn notify_availability(kind: WebhookEventKind, target: &ProbeTarget, result: &ProbeResult) {
+    let message = match kind {
+        WebhookEventKind::UpstreamDown => {
+            format!("Upstream {} is unavailable: {}", target.name, result.error.as_deref().unwrap_or("-"))
+        }
+        _ => format!("Upstream {} is available again", target.name),
+    };
+    if kind == WebhookEventKind::UpstreamDown {
+        crate::modules::notifications::notify(
+            crate::modules::notifications::NotificationKind::UpstreamOutage,
+            &target.name,
+            "Upstream unavailable",
+            &message,
+        );
+    }
+    let data = serde_json::json!({
+        "upstream": target.name,
+        "kind": target.kind,
+        "status": result.status,
+        "latency_ms": result.latency_ms,
+        "error": result.error,
+    });
+    webhooks::notify(WebhookEvent::new(kind, target.name.clone(), message, data));
+}
+
+async fn probe(client: &reqwest::Client, target: &ProbeTarget, timeout: Duration) -> (ProbeResult, Option<AttemptFailure>) {
+    // fetchAvailableModels 只接受 POST
+    let request = if target.kind == "v1internal" {
+        client.post(&target.url).json(&serde_json::json!({}))
+    } else {
+        client.get(&target.url)
+    };
+    let mut request = request.timeout(timeout);
+    if let Some((name, value)) = &target.auth {
+        request = request.header(*name, value);
+    }
+    let start = Instant::now();
+    let result = request.send().await;
+    let latency_ms = start.elapsed().as_millis() as u64;
+    let timestamp = chrono::Utc::now().timestamp_millis();
+    match result {
+        Ok(resp) => {
+            let status = resp.status();
+            let ok = classify_status(status.as_u16());
+            (
+                ProbeResult {
+                    timestamp,
+                    ok,
+                    latency_ms: Some(latency_ms),
+                    status: Some(status.as_u16()),
+                    error: (!ok).then(|| format!("HTTP {}", status)),
+                },
+                (!ok).then_some(AttemptFailure::Status(status)),
+            )
+        }
+        Err(e) => (
+            ProbeResult {
+                timestamp,
+                ok: false,
+                latency_ms: None,
+                status: None,
+                error: Some(e.to_string()),
+            },
+            Some(AttemptFailure::from_reqwest(&e)),
+        ),
+    }
+}
+
+impl HealthChecker {
+    pub fn new(config: HealthCheckConfig) -> Self {
+        Self {
+            config: std::sync::RwLock::new(config),
+            history: DashMap::new(),
+            task: std::sync::Mutex::new(None),
+        }
+    }
+
+    pub fn config(&self) -> HealthCheckConfig {
+        self.config.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    pub fn update_config(&self, config: HealthCheckConfig) {
+        if !config.enabled {
+            self.history.clear();
+        }
+        *self.config.write().unwrap_or_else(|e| e.into_inner()) = config;
+    }
+
+    fn record(&self, target: &ProbeTarget, result: ProbeResult) {
+        let history_size = self.config().history_size.max(1);
+        let mut entry = self.history.entry(target.name.clone()).or_insert_with(|| TargetHistory {
+            kind: target.kind,
+            results: VecDeque::with_capacity(history_size),
+        });
+        entry.kind = target.kind;
+        entry.results.push_back(result);
+        while entry.results.len() > history_size {
+            entry.results.pop_front();
+        }
+    }
+
+    /// 探测一轮所有目标
+    async fn run_once(&self, state: &AppState) {
+        let config = self.config();
+        let endpoints: Vec<String> = state.upstream.endpoint_health().into_iter().map(|s| s.endpoint).collect();
+        let access_token = match state.token_manager.get_token("gemini", false, None, "").await {
+            Ok((token, ..)) => Some(token),
+            Err(e) => {
+                tracing::debug!("[Health-Check] Skipping v1internal endpoints: {}", e);
+                None
+            }
+        };
+        let targets = probe_targets(
+            &endpoints,
+            access_token.as_deref(),
+            &*state.zai.read().await,
+            &state.zai_keys,
+            &*state.audio.read().await,
+            &*state.embeddings.read().await,
+            &*state.images.read().await,
+        );
+        // 已移除的上游不再展示
+        self.history.retain(|name, _| targets.iter().any(|t| &t.name == name));
+
+        let timeout = Duration::from_secs(config.timeout_secs.max(1));
+        let client = match crate::proxy::handlers::common::build_upstream_client(
+            state.upstream_proxy.read().await.clone(),
+            config.timeout_secs,
+        ) {
+            Ok(client) => client,
+            Err(e) => {
+                tracing::warn!("[Health-Check] {}", e);
+                return;
+            }
+        };
+
+        let results = futures::future::join_all(targets.iter().map(|t| probe(&client, t, timeout))).await;
+        for (target, (result, failure)) in targets.iter().zip(results) {
+            if target.kind == "v1internal" {
+                match failure {
+                    Some(failure) => state.upstream.record_probe_failure(&target.name, failure),
+                    None => state.upstream.record_probe_success(&target.name),
+                }
+            }
+            if !result.ok {
+                tracing::warn!("[Health-Check] {} unavailable: {}", target.name, result.error.as_deref().unwrap_or("-"));
+            }
+            let previous = self.history.get(&target.name).and_then(|h| h.results.back().map(|r| r.ok));
+            if let Some(kind) = availability_change(previous, result.ok) {
+                notify_availability(kind, target, &result);
+            }
+            self.record(target, result);
+        }
+    }
+
+    /// 启动后台探测任务 (重复调用会替换旧任务)
+    pub fn start(self: &Arc<Self>, state: AppState) {
+        let checker = self.clone();
+        let handle = tokio::spawn(async move {
+            loop {
+                let config = checker.config();
+                if config.enabled {
+                    checker.run_once(&state).await;
+                }
+                tokio::time::sleep(Duration::from_secs(config.interval_secs.max(5))).await;
+            }
+        });
+        if let Some(old) = self.task.lock().unwrap_or_else(|e| e.into_inner()).replace(handle) {
+            old.abort();
+        }
+    }
+
+    pub fn stop(&self) {
+        if let Some(handle) = self.task.lock().unwrap_or_else(|e| e.into_inner()).take() {
+            handle.abort();
+        }
+    }
+
+    pub fn snapshot(&self) -> Vec<UpstreamHealth> {
+        let mut list: Vec<UpstreamHealth> = self
+            .history
+            .iter()
+            .map(|entry| {
+                let results = &entry.results;
+                let last = results.back();
+                let ok_count = results.iter().filter(|r| r.ok).count();
+                UpstreamHealth {
+                    upstream: entry.key().clone(),
+                    kind: entry.kind.to_string(),
+                    available: last.map_or(true, |r| r.ok),
+                    last_latency_ms: last.and_then(|r| r.latency_ms),
+                    availability: if results.is_empty() { 1.0 } else { ok_count as f64 / results.len() as f64 },
+                    history: results.iter().cloned().collect(),
+                }
+            })
+            .collect();
+        list.sort_by(|a, b| a.upstream.cmp(&b.upstream));
+        list
+    }
+
+    /// 汇总状态 (未启用或尚无探测结果时为 None)
+    pub fn overall(&self) -> Option<OverallHealth> {
+        if !self.config().enabled || self.history.is_empty() {
+            return None;
+        }
+        let total = self.history.len();
+        let available = self
+            .history
+            .iter()
+            .filter(|h| h.results.back().map_or(true, |r| r.ok))
+            .count();
+        Some(match available {
+            0 => OverallHealth::Down,
+            n if n == total => OverallHealth::Ok,
+            _ => OverallHealth::Degraded,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_probe_targets() {
+        let endpoints = vec!["https://a.example/v1internal".to_string()];
+        let mut zai = crate::proxy::ZaiConfig::default();
+        let audio = crate::proxy::config::AudioConfig { enabled: true, api_key: "sk".to_string(), ..Default::default() };
+        let keys = KeyPool::new();
+        let targets = probe_targets(&endpoints, Some("ya29"), &zai, &keys, &audio, &Default::default(), &Default::default());
+        assert_eq!(targets.len(), 2);
+        assert_eq!(targets[0].url, "https://a.example/v1internal:fetchAvailableModels");
+        assert_eq!(targets[0].auth, Some(("Authorization", "Bearer ya29".to_string())));
+        assert_eq!(targets[1].url, "https://api.openai.com/v1/models");
+        assert_eq!(targets[1].auth, Some(("Authorization", "Bearer sk".to_string())));
+        // 没有账号 Token 时不探测 v1internal 端点
+        let targets = probe_targets(&endpoints, None, &zai, &keys, &audio, &Default::default(), &Default::default());
+        assert!(targets.iter().all(|t| t.kind != "v1internal"));
+
+        zai.enabled = true;
+        zai.dispatch_mode = crate::proxy::ZaiDispatchMode::Exclusive;
+        zai.api_key = "zk".to_string();
+        let targets = probe_targets(&endpoints, None, &zai, &keys, &Default::default(), &Default::default(), &Default::default());
+        let target = targets.iter().find(|t| t.kind == "zai").unwrap();
+        assert!(target.url.ends_with("/v1/models"));
+        assert_eq!(target.auth, Some(("x-api-key", "zk".to_string())));
+    }
+
+    #[test]
+    fn test_history_and_overall() {
+        let checker = HealthChecker::new(HealthCheckConfig { enabled: true, history_size: 3, ..Default::default() });
+        assert_eq!(checker.overall(), None);
+
+        let target = |name: &str| ProbeTarget { name: name.to_string(), kind: "openai", url: String::new(), auth: None };
+        let result = |ok| ProbeResult { timestamp: 0, ok, latency_ms: Some(10), status: Some(200), error: None };
+        for ok in [false, true, true, true] {
+            checker.record(&target("a"), result(ok));
+        }
+        checker.record(&target("b"), result(false));
+
+        let snapshot = checker.snapshot();
+        assert_eq!(snapshot[0].history.len(), 3);
+        assert_eq!(snapshot[0].availability, 1.0);
+        assert!(!snapshot[1].available);
+        assert_eq!(checker.overall(), Some(OverallHealth::Degraded));
+        assert!(classify_status(200) && classify_status(404));
+        assert!(!classify_status(401) && !classify_status(403) && !classify_status(429) && !classify_status(503));
+
+        assert_eq!(availability_change(None, false), Some(WebhookEventKind::UpstreamDown));
+        assert_eq!(availability_change(Some(true), false), Some(WebhookEventKind::UpstreamDown));
+        assert_eq!(availability_change(Some(false), true), Some(WebhookEventKind::UpstreamRecovered));
+        assert_eq!(availability_change(None, true), None);
+        assert_eq!(availability_change(Some(false), false), None);
+    }
+}
@@ -2,5 +2,9 @@
 // 对应上游通讯接口
 
 pub mod client;
+pub mod client_pool;
+pub mod concurrency;
+pub mod health_check;
 pub mod retry;
+pub mod routing;
 pub mod models;
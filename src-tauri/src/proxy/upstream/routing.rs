@@ -0,0 +1,481 @@
+// 上游路由与故障转移策略
+// 每个模型可配置多个 v1internal 端点, 按健康状态排序;
+// 遇到 5xx / 超时 / 连接错误时按退避策略切换到下一个端点重试。
+// 启用熔断后, 打开 (open) 的端点不再作为兜底; 冷却结束进入半开 (half-open), 放行一个探测请求。
+
+use dashmap::DashMap;
+use reqwest::StatusCode;
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+use crate::proxy::config::UpstreamRoutingConfig;
+
+/// 一次上游尝试的失败原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttemptFailure {
+    /// 上游返回了错误状态码 (请求已被明确拒绝, 未产生输出)
+    Status(StatusCode),
+    /// 未能建立连接 (请求未到达上游)
+    Connect,
+    /// 在收到响应头之前超时
+    Timeout,
+    /// 其他网络错误 (请求可能已部分发送)
+    Network,
+}
+
+impl AttemptFailure {
+    pub fn from_reqwest(e: &reqwest::Error) -> Self {
+        if e.is_connect() {
+            AttemptFailure::Connect
+        } else if e.is_timeout() {
+            AttemptFailure::Timeout
+        } else {
+            AttemptFailure::Network
+        }
+    }
+
+    /// 是否计入端点健康度 (429 / 404 属于账号或模型问题, 不代表端点故障)
+    fn is_endpoint_fault(self) -> bool {
+        match self {
+            AttemptFailure::Status(status) => status.is_server_error() || status == StatusCode::REQUEST_TIMEOUT,
+            _ => true,
+        }
+    }
+}
+
+/// 判断失败后是否可以重试
+///
+/// 流式请求的幂等保护: 超时或中途断开时上游可能已经开始生成 (并计入配额),
+/// 只有在请求确定未被处理 (连接失败 / 明确的错误状态码) 时才重试。
+pub fn is_retryable(failure: AttemptFailure, streaming: bool) -> bool {
+    match failure {
+        AttemptFailure::Status(status) => {
+            status == StatusCode::TOO_MANY_REQUESTS
+                || status == StatusCode::REQUEST_TIMEOUT
+                || status == StatusCode::NOT_FOUND
+                || status.is_server_error()
+        }
+        AttemptFailure::Connect => true,
+        AttemptFailure::Timeout | AttemptFailure::Network => !streaming,
+    }
+}
+
+/// 半开状态下探测请求的占用时长, 超时未回报结果则允许新的探测
+const PROBE_CLAIM_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Default)]
+struct EndpointHealth {
+    consecutive_failures: u32,
+    unhealthy_until: Option<Instant>,
+    /// 半开状态下正在进行的探测请求
+    probe_started: Option<Instant>,
+}
+
+impl EndpointHealth {
+    fn is_probing(&self, now: Instant) -> bool {
+        self.probe_started
+            .is_some_and(|started| now.duration_since(started) < PROBE_CLAIM_TIMEOUT)
+    }
+
+    fn circuit_state(&self, now: Instant) -> CircuitState {
+        match self.unhealthy_until {
+            Some(until) if now < until => CircuitState::Open,
+            Some(_) => CircuitState::HalfOpen,
+            None => CircuitState::Closed,
+        }
+    }
+}
+
+/// 熔断器状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    Closed,
+    HalfOpen,
+    Open,
+}
+
+impl CircuitState {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CircuitState::Closed => "closed",
+            CircuitState::HalfOpen => "half_open",
+            CircuitState::Open => "open",
+        }
+    }
+}
+
+/// 端点健康状态快照 (用于指标与状态展示)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EndpointStatus {
+    pub endpoint: String,
+    pub healthy: bool,
+    pub consecutive_failures: u32,
+    pub state: CircuitState,
+}
+
+/// 上游路由器: 端点选择、健康跟踪与退避计算
+pub struct UpstreamRouter {
+    config: std::sync::RwLock<UpstreamRoutingConfig>,
+    health: DashMap<String, EndpointHealth>,
+}
+
+impl UpstreamRouter {
+    pub fn new(config: UpstreamRoutingConfig) -> Self {
+        Self {
+            config: std::sync::RwLock::new(config),
+            health: DashMap::new(),
+        }
+    }
+
+    pub fn config(&self) -> UpstreamRoutingConfig {
+        self.config.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    pub fn update_config(&self, config: UpstreamRoutingConfig) {
+        *self.config.write().unwrap_or_else(|e| e.into_inner()) = config;
+        self.health.clear();
+    }
+
+    /// 模型对应的端点列表 (精确匹配优先, 其次 `prefix*` 通配, 最后默认列表)
+    fn configured_endpoints(config: &UpstreamRoutingConfig, model: Option<&str>) -> Vec<String> {
+        if let Some(model) = model {
+            if let Some(list) = config.model_endpoints.get(model).filter(|l| !l.is_empty()) {
+                return list.clone();
+            }
+            let wildcard = config
+                .model_endpoints
+                .iter()
+                .filter_map(|(pattern, list)| {
+                    let prefix = pattern.strip_suffix('*')?;
+                    (model.starts_with(prefix) && !list.is_empty()).then_some((prefix.len(), list))
+                })
+                .max_by_key(|(len, _)| *len);
+            if let Some((_, list)) = wildcard {
+                return list.clone();
+            }
+        }
+        if config.endpoints.is_empty() {
+            return UpstreamRoutingConfig::default_endpoints();
+        }
+        config.endpoints.clone()
+    }
+
+    /// 按健康状态排序后的候选端点 (不健康的端点排在最后, 仍可作为兜底)
+    ///
+    /// 启用熔断时跳过打开的端点与正在探测的半开端点; 全部熔断时返回空列表。
+    /// 不占用探测名额: 实际尝试某个端点前调用 `claim`。
+    pub fn endpoints_for(&self, model: Option<&str>) -> Vec<String> {
+        let config = self.config();
+        let now = Instant::now();
+        if config.circuit_breaker_enabled {
+            return Self::configured_endpoints(&config, model)
+                .into_iter()
+                .filter(|endpoint| self.available(endpoint, now))
+                .collect();
+        }
+        let (mut healthy, unhealthy): (Vec<String>, Vec<String>) = Self::configured_endpoints(&config, model)
+            .into_iter()
+            .partition(|endpoint| {
+                self.health
+                    .get(endpoint)
+                    .and_then(|h| h.unhealthy_until)
+                    .map_or(true, |until| until <= now)
+            });
+        healthy.extend(unhealthy);
+        healthy
+    }
+
+    /// 熔断检查 (不占用名额): 关闭状态可用, 半开状态在没有进行中的探测时可用
+    fn available(&self, endpoint: &str, now: Instant) -> bool {
+        self.health.get(endpoint).is_none_or(|health| match health.circuit_state(now) {
+            CircuitState::Closed => true,
+            CircuitState::Open => false,
+            CircuitState::HalfOpen => !health.is_probing(now),
+        })
+    }
+
+    /// 即将向端点发送请求: 关闭状态 (或未启用熔断) 直接放行, 半开状态占用探测名额后放行
+    pub fn claim(&self, endpoint: &str) -> bool {
+        if !self.config().circuit_breaker_enabled {
+            return true;
+        }
+        let now = Instant::now();
+        let Some(mut health) = self.health.get_mut(endpoint) else {
+            return true;
+        };
+        match health.circuit_state(now) {
+            CircuitState::Closed => true,
+            CircuitState::Open => false,
+            CircuitState::HalfOpen => {
+                if health.is_probing(now) {
+                    return false;
+                }
+                health.probe_started = Some(now);
+                tracing::info!("[Upstream-Router] Circuit half-open, probing endpoint {}", endpoint);
+                true
+            }
+        }
+    }
+
+    /// 启用熔断时返回所有端点中最好的状态 (全部打开时为 Open)
+    pub fn circuit_summary(&self) -> Option<CircuitState> {
+        if !self.config().circuit_breaker_enabled {
+            return None;
+        }
+        self.health_snapshot().iter().map(|s| s.state).min()
+    }
+
+    /// 最近一个打开的端点进入半开状态的剩余时间
+    pub fn circuit_retry_after(&self) -> Option<Duration> {
+        let now = Instant::now();
+        self.health
+            .iter()
+            .filter_map(|h| h.unhealthy_until)
+            .filter(|until| *until > now)
+            .min()
+            .map(|until| until - now)
+    }
+
+    /// 所有已配置端点 (默认列表 + 按模型配置) 的健康状态
+    pub fn health_snapshot(&self) -> Vec<EndpointStatus> {
+        let config = self.config();
+        let now = Instant::now();
+        let mut endpoints = Self::configured_endpoints(&config, None);
+        let mut model_endpoints: Vec<&String> = config.model_endpoints.values().flatten().collect();
+        model_endpoints.sort();
+        for endpoint in model_endpoints {
+            if !endpoints.contains(endpoint) {
+                endpoints.push(endpoint.clone());
+            }
+        }
+        endpoints
+            .into_iter()
+            .map(|endpoint| {
+                let (healthy, consecutive_failures, state) = self
+                    .health
+                    .get(&endpoint)
+                    .map(|h| {
+                        let state = h.circuit_state(now);
+                        (state != CircuitState::Open, h.consecutive_failures, state)
+                    })
+                    .unwrap_or((true, 0, CircuitState::Closed));
+                EndpointStatus { endpoint, healthy, consecutive_failures, state }
+            })
+            .collect()
+    }
+
+    /// 第 `attempt` 次尝试 (从 0 开始) 使用的端点
+    pub fn endpoint_for_attempt(endpoints: &[String], attempt: u32) -> Option<&String> {
+        if endpoints.is_empty() {
+            return None;
+        }
+        endpoints.get(attempt as usize % endpoints.len())
+    }
+
+    /// 最大尝试次数 (0 表示每个端点各尝试一次)
+    pub fn max_attempts(&self, endpoint_count: usize) -> u32 {
+        match self.config().max_attempts {
+            0 => endpoint_count.max(1) as u32,
+            n => n,
+        }
+    }
+
+    /// 单次尝试等待响应头的超时时间
+    pub fn attempt_timeout(&self) -> Option<Duration> {
+        match self.config().attempt_timeout_secs {
+            0 => None,
+            secs => Some(Duration::from_secs(secs)),
+        }
+    }
+
+    /// 流式响应等待首个有效数据块的超时
+    pub fn first_byte_timeout(&self) -> Duration {
+        Duration::from_secs(self.config().first_byte_timeout_secs.max(1))
+    }
+
+    /// 流式响应数据块间隔超时 (None = 不限制)
+    pub fn stream_idle_timeout(&self) -> Option<Duration> {
+        match self.config().stream_idle_timeout_secs {
+            0 => None,
+            secs => Some(Duration::from_secs(secs)),
+        }
+    }
+
+    /// 第 `attempt` 次重试前的退避时间 (指数退避, 有上限)
+    ///
+    /// 切换到新端点时不等待, 同一端点再次尝试时才退避。
+    pub fn backoff(&self, attempt: u32, endpoint_count: usize) -> Duration {
+        let config = self.config();
+        if attempt == 0 || (attempt as usize) < endpoint_count {
+            return Duration::ZERO;
+        }
+        let round = (attempt as usize / endpoint_count.max(1)) as u32;
+        let delay = config
+            .backoff_base_ms
+            .saturating_mul(1u64 << round.saturating_sub(1).min(16));
+        Duration::from_millis(delay.min(config.backoff_max_ms))
+    }
+
+    pub fn record_success(&self, endpoint: &str) {
+        if let Some(mut health) = self.health.get_mut(endpoint) {
+            if health.unhealthy_until.is_some() {
+                tracing::info!("[Upstream-Router] Endpoint {} recovered, circuit closed", endpoint);
+            }
+            health.consecutive_failures = 0;
+            health.unhealthy_until = None;
+            health.probe_started = None;
+        }
+    }
+
+    /// 健康检查探测成功: 只在冷却结束 (半开) 时关闭熔断, 冷却期内不会提前恢复
+    pub fn record_probe_success(&self, endpoint: &str) {
+        let half_open = self
+            .health
+            .get(endpoint)
+            .is_some_and(|h| h.circuit_state(Instant::now()) == CircuitState::HalfOpen);
+        if half_open {
+            self.record_success(endpoint);
+        }
+    }
+
+    pub fn record_failure(&self, endpoint: &str, failure: AttemptFailure) {
+        if !failure.is_endpoint_fault() {
+            // 端点可达 (例如 429), 释放探测名额但不改变熔断状态
+            if let Some(mut health) = self.health.get_mut(endpoint) {
+                health.probe_started = None;
+            }
+            return;
+        }
+        let config = self.config();
+        let mut health = self.health.entry(endpoint.to_string()).or_default();
+        health.consecutive_failures += 1;
+        health.probe_started = None;
+        // 半开探测失败时连续失败数仍不低于阈值, 立即重新打开
+        if health.consecutive_failures >= config.failure_threshold.max(1) {
+            health.unhealthy_until = Some(Instant::now() + Duration::from_secs(config.unhealthy_cooldown_secs));
+            tracing::warn!(
+                "[Upstream-Router] Endpoint {} marked unhealthy for {}s after {} consecutive failures",
+                endpoint,
+                config.unhealthy_cooldown_secs,
+                health.consecutive_failures
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> UpstreamRoutingConfig {
+        let mut config = UpstreamRoutingConfig {
+            endpoints: vec!["a".to_string(), "b".to_string()],
+            failure_threshold: 1,
+            backoff_base_ms: 100,
+            backoff_max_ms: 250,
+            ..Default::default()
+        };
+        config.model_endpoints.insert("gemini-3-pro".to_string(), vec!["exact".to_string()]);
+        config.model_endpoints.insert("gemini-*".to_string(), vec!["wild".to_string()]);
+        config.model_endpoints.insert("gemini-2.5-*".to_string(), vec!["longer".to_string()]);
+        config
+    }
+
+    #[test]
+    fn test_model_endpoint_resolution() {
+        let router = UpstreamRouter::new(config());
+        assert_eq!(router.endpoints_for(Some("gemini-3-pro")), vec!["exact"]);
+        assert_eq!(router.endpoints_for(Some("gemini-2.5-flash")), vec!["longer"]);
+        assert_eq!(router.endpoints_for(Some("gemini-3-flash")), vec!["wild"]);
+        assert_eq!(router.endpoints_for(Some("claude-sonnet-4-5")), vec!["a", "b"]);
+        assert_eq!(router.endpoints_for(None), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_unhealthy_endpoint_moves_to_back() {
+        let router = UpstreamRouter::new(config());
+        router.record_failure("a", AttemptFailure::Status(StatusCode::TOO_MANY_REQUESTS));
+        assert_eq!(router.endpoints_for(None), vec!["a", "b"]);
+
+        router.record_failure("a", AttemptFailure::Status(StatusCode::BAD_GATEWAY));
+        assert_eq!(router.endpoints_for(None), vec!["b", "a"]);
+        let snapshot = router.health_snapshot();
+        let names: Vec<&str> = snapshot.iter().map(|s| s.endpoint.as_str()).collect();
+        assert_eq!(names, vec!["a", "b", "exact", "longer", "wild"]);
+        assert!(!snapshot[0].healthy);
+        assert_eq!(snapshot[0].consecutive_failures, 1);
+        assert!(snapshot[1].healthy);
+
+        router.record_success("a");
+        assert_eq!(router.endpoints_for(None), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_circuit_breaker_states() {
+        let mut cfg = config();
+        cfg.circuit_breaker_enabled = true;
+        cfg.unhealthy_cooldown_secs = 60;
+        let router = UpstreamRouter::new(cfg.clone());
+        assert_eq!(router.circuit_summary(), Some(CircuitState::Closed));
+
+        router.record_failure("a", AttemptFailure::Connect);
+        assert_eq!(router.endpoints_for(None), vec!["b"]);
+        router.record_failure("b", AttemptFailure::Timeout);
+        assert!(router.endpoints_for(None).is_empty());
+        assert!(router.circuit_retry_after().is_some());
+
+        // 冷却为 0: 立即进入半开, 只放行一个探测请求
+        cfg.unhealthy_cooldown_secs = 0;
+        let router = UpstreamRouter::new(cfg);
+        router.record_failure("a", AttemptFailure::Connect);
+        assert_eq!(router.health_snapshot()[0].state, CircuitState::HalfOpen);
+        // 列出候选端点不占用探测名额, 实际发送时才占用
+        assert_eq!(router.endpoints_for(None), vec!["a", "b"]);
+        assert_eq!(router.endpoints_for(None), vec!["a", "b"]);
+        assert!(router.claim("a"));
+        assert!(!router.claim("a"));
+        assert!(router.claim("b"));
+        assert_eq!(router.endpoints_for(None), vec!["b"]);
+
+        router.record_success("a");
+        assert_eq!(router.health_snapshot()[0].state, CircuitState::Closed);
+        assert_eq!(router.endpoints_for(None), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_probe_success_only_closes_half_open_circuit() {
+        let mut cfg = config();
+        cfg.unhealthy_cooldown_secs = 60;
+        let router = UpstreamRouter::new(cfg.clone());
+        router.record_failure("a", AttemptFailure::Connect);
+        router.record_probe_success("a");
+        assert_eq!(router.health_snapshot()[0].state, CircuitState::Open);
+
+        cfg.unhealthy_cooldown_secs = 0;
+        let router = UpstreamRouter::new(cfg);
+        router.record_failure("a", AttemptFailure::Connect);
+        router.record_probe_success("a");
+        assert_eq!(router.health_snapshot()[0].state, CircuitState::Closed);
+    }
+
+    #[test]
+    fn test_streaming_idempotency_guard() {
+        assert!(is_retryable(AttemptFailure::Connect, true));
+        assert!(is_retryable(AttemptFailure::Status(StatusCode::SERVICE_UNAVAILABLE), true));
+        assert!(!is_retryable(AttemptFailure::Timeout, true));
+        assert!(!is_retryable(AttemptFailure::Network, true));
+        assert!(is_retryable(AttemptFailure::Timeout, false));
+        assert!(!is_retryable(AttemptFailure::Status(StatusCode::BAD_REQUEST), false));
+    }
+
+    #[test]
+    fn test_backoff_only_when_revisiting_endpoint() {
+        let router = UpstreamRouter::new(config());
+        assert_eq!(router.backoff(1, 2), Duration::ZERO);
+        assert_eq!(router.backoff(2, 2), Duration::from_millis(100));
+        assert_eq!(router.backoff(4, 2), Duration::from_millis(200));
+        assert_eq!(router.backoff(6, 2), Duration::from_millis(250));
+        assert_eq!(UpstreamRouter::endpoint_for_attempt(&["a".to_string(), "b".to_string()], 3).map(String::as_str), Some("b"));
+    }
+}
@@ -0,0 +1,635 @@
+// 虚拟 API Key
+// 为本地客户端签发独立的 Key, 各自带有额度、月度预算、可用模型和过期时间, 并可绑定到指定的上游账号。
+// 明文 Key 只在创建时返回一次, 存储中仅保存 SHA-256。
+
+use chrono::{Datelike, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock, RwLock};
+use std::time::SystemTime;
+
+use crate::modules::usage::KeyTotals;
+use crate::proxy::common::model_mapping::wildcard_match;
+
+const STORE_FILE: &str = "virtual_keys.json";
+
+tokio::task_local! {
+    /// 当前请求所用虚拟 Key 绑定的上游账号 (由鉴权中间件设置, TokenManager 读取)
+    static UPSTREAM_ACCOUNT: Option<String>;
+}
+
+/// 额度统计周期
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum QuotaPeriod {
+    Daily,
+    #[default]
+    Monthly,
+    /// 不重置, 从创建起累计
+    Total,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VirtualKey {
+    pub id: String,
+    pub name: String,
+    /// SHA-256 of the plaintext key
+    pub key_hash: String,
+    /// Masked key for display
+    pub key_hint: String,
+    /// Allowed model patterns (wildcards supported); empty allows all models
+    #[serde(default)]
+    pub allowed_models: Vec<String>,
+    #[serde(default)]
+    pub request_quota: Option<u64>,
+    /// Prompt + completion tokens per period
+    #[serde(default)]
+    pub token_quota: Option<u64>,
+    #[serde(default)]
+    pub quota_period: QuotaPeriod,
+    /// Monthly spend cap in USD (calendar month, UTC)
+    #[serde(default)]
+    pub monthly_spend_cap: Option<f64>,
+    /// Unix seconds
+    #[serde(default)]
+    pub expires_at: Option<i64>,
+    /// Upstream account email used for every request made with this key
+    #[serde(default)]
+    pub upstream_account: Option<String>,
+    pub created_at: i64,
+    #[serde(default)]
+    pub revoked: bool,
+}
+
+impl VirtualKey {
+    /// 与用量统计中 `api_key_hash` 相同的指纹
+    pub fn fingerprint(&self) -> &str {
+        &self.key_hash[..16]
+    }
+
+    /// 审计日志中记录的 Key 设置 (不含哈希)
+    pub fn audit_detail(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": self.name,
+            "key_hint": self.key_hint,
+            "allowed_models": self.allowed_models,
+            "request_quota": self.request_quota,
+            "token_quota": self.token_quota,
+            "quota_period": self.quota_period,
+            "monthly_spend_cap": self.monthly_spend_cap,
+            "expires_at": self.expires_at,
+            "upstream_account": self.upstream_account,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct CreateVirtualKeyRequest {
+    pub name: String,
+    pub allowed_models: Vec<String>,
+    pub request_quota: Option<u64>,
+    pub token_quota: Option<u64>,
+    pub quota_period: QuotaPeriod,
+    pub monthly_spend_cap: Option<f64>,
+    pub expires_at: Option<i64>,
+    pub upstream_account: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CreatedVirtualKey {
+    /// Plaintext key, only returned once
+    pub key: String,
+    #[serde(flatten)]
+    pub info: VirtualKey,
+}
+
+/// 列表展示用, 附带当前周期的用量
+#[derive(Debug, Clone, Serialize)]
+pub struct VirtualKeySummary {
+    #[serde(flatten)]
+    pub info: VirtualKey,
+    pub used_requests: u64,
+    pub used_tokens: u64,
+    /// Spend in the current calendar month (USD)
+    pub spent_this_month: f64,
+}
+
+/// 校验通过的虚拟 Key
+#[derive(Debug, Clone, PartialEq)]
+pub struct VirtualKeyGrant {
+    pub id: String,
+    pub name: String,
+    pub upstream_account: Option<String>,
+}
+
+/// 请求使用的模型 (用于可用模型校验)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RequestModel<'a> {
+    Named(&'a str),
+    /// 不调用模型的接口 (例如模型列表)
+    NotApplicable,
+    /// 未能解析出模型 (GET / multipart / WebSocket 等), 限制了可用模型的 Key 一律拒绝
+    Unknown,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum VirtualKeyError {
+    Revoked,
+    Expired,
+    ModelNotAllowed(String),
+    ModelUnknown,
+    RequestQuotaExceeded(u64),
+    TokenQuotaExceeded(u64),
+    SpendLimitExceeded(f64),
+}
+
+impl VirtualKeyError {
+    pub fn message(&self) -> String {
+        match self {
+            VirtualKeyError::Revoked => "This API key has been revoked".to_string(),
+            VirtualKeyError::Expired => "This API key has expired".to_string(),
+            VirtualKeyError::ModelNotAllowed(model) => format!("Model '{}' is not allowed for this API key", model),
+            VirtualKeyError::ModelUnknown => {
+                "This API key is restricted to specific models and the request model could not be determined".to_string()
+            }
+            VirtualKeyError::RequestQuotaExceeded(limit) => {
+                format!("Request quota exceeded for this API key: Limit {}", limit)
+            }
+            VirtualKeyError::TokenQuotaExceeded(limit) => {
+                format!("Token quota exceeded for this API key: Limit {}", limit)
+            }
+            VirtualKeyError::SpendLimitExceeded(cap) => {
+                format!("Monthly spend limit reached for this API key: Limit ${:.2}", cap)
+            }
+        }
+    }
+
+    /// 额度或月度预算已用尽
+    pub fn is_exhausted(&self) -> bool {
+        matches!(
+            self,
+            VirtualKeyError::RequestQuotaExceeded(_)
+                | VirtualKeyError::TokenQuotaExceeded(_)
+                | VirtualKeyError::SpendLimitExceeded(_)
+        )
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct UsageCounter {
+    period_start: i64,
+    requests: u64,
+    tokens: u64,
+    month_start: i64,
+    cost_usd: f64,
+}
+
+/// 统计周期起点 (UTC)
+fn period_start(period: QuotaPeriod, created_at: i64, now: i64) -> i64 {
+    let Some(now) = Utc.timestamp_opt(now, 0).single() else {
+        return created_at;
+    };
+    let start = match period {
+        QuotaPeriod::Daily => now.date_naive().and_hms_opt(0, 0, 0),
+        QuotaPeriod::Monthly => now
+            .date_naive()
+            .with_day(1)
+            .and_then(|d| d.and_hms_opt(0, 0, 0)),
+        QuotaPeriod::Total => None,
+    };
+    start.map(|s| s.and_utc().timestamp()).unwrap_or(created_at)
+}
+
+/// 外部身份 (JWT subject) 的计数器 id
+fn subject_id(fingerprint: &str) -> String {
+    format!("subject:{}", fingerprint)
+}
+
+fn hash_key(key: &str) -> String {
+    format!("{:x}", Sha256::digest(key.as_bytes()))
+}
+
+/// 从用量记录中读取指定时间以来的用量, 用于重启后恢复当前周期的计数
+type UsageSeed = fn(&str, i64) -> KeyTotals;
+
+fn seed_from_usage_db(fingerprint: &str, since: i64) -> KeyTotals {
+    crate::modules::usage::get_key_totals_since(fingerprint, since).unwrap_or_else(|e| {
+        tracing::warn!("[VirtualKeys] Failed to load usage for key {}: {}", fingerprint, e);
+        KeyTotals::default()
+    })
+}
+
+fn modified_at(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn read_keys(path: &Path) -> Option<Vec<VirtualKey>> {
+    let content = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+pub struct VirtualKeyStore {
+    path: Option<PathBuf>,
+    /// 最近一次读取或写入时存储文件的修改时间
+    modified: Mutex<Option<SystemTime>>,
+    keys: RwLock<Vec<VirtualKey>>,
+    usage: Mutex<HashMap<String, UsageCounter>>,
+    seed: UsageSeed,
+}
+
+impl VirtualKeyStore {
+    fn new(path: Option<PathBuf>, seed: UsageSeed) -> Self {
+        let keys = path.as_deref().and_then(read_keys).unwrap_or_default();
+        Self {
+            modified: Mutex::new(path.as_deref().and_then(modified_at)),
+            path,
+            keys: RwLock::new(keys),
+            usage: Mutex::new(HashMap::new()),
+            seed,
+        }
+    }
+
+    /// Global singleton instance, persisted to `<data_dir>/virtual_keys.json`
+    pub fn global() -> &'static VirtualKeyStore {
+        static INSTANCE: OnceLock<VirtualKeyStore> = OnceLock::new();
+        INSTANCE.get_or_init(|| {
+            let path = crate::modules::account::get_data_dir().ok().map(|d| d.join(STORE_FILE));
+            VirtualKeyStore::new(path, seed_from_usage_db)
+        })
+    }
+
+    fn persist(&self, keys: &[VirtualKey]) -> Result<(), String> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let content = serde_json::to_string_pretty(keys).map_err(|e| e.to_string())?;
+        std::fs::write(path, content).map_err(|e| format!("failed_to_save_virtual_keys: {}", e))?;
+        *self.modified.lock().unwrap_or_else(|e| e.into_inner()) = modified_at(path);
+        Ok(())
+    }
+
+    /// 存储文件被其他进程修改 (例如命令行创建 Key) 时重新加载, 返回是否重新加载
+    pub fn reload_if_changed(&self) -> bool {
+        let Some(path) = &self.path else {
+            return false;
+        };
+        let modified = modified_at(path);
+        {
+            let mut seen = self.modified.lock().unwrap_or_else(|e| e.into_inner());
+            if modified.is_none() || *seen == modified {
+                return false;
+            }
+            *seen = modified;
+        }
+        // 解析失败时保留当前的 Key
+        let Some(keys) = read_keys(path) else {
+            return false;
+        };
+        tracing::info!("[VirtualKeys] Store file changed, reloaded {} keys", keys.len());
+        *self.keys.write().unwrap_or_else(|e| e.into_inner()) = keys;
+        true
+    }
+
+    pub fn create(&self, request: CreateVirtualKeyRequest) -> Result<CreatedVirtualKey, String> {
+        let name = request.name.trim().to_string();
+        if name.is_empty() {
+            return Err("Key name is required".to_string());
+        }
+        let key = format!("sk-vk-{}", uuid::Uuid::new_v4().simple());
+        let info = VirtualKey {
+            id: uuid::Uuid::new_v4().to_string(),
+            name,
+            key_hash: hash_key(&key),
+            key_hint: crate::modules::usage::mask_api_key(&key),
+            allowed_models: request
+                .allowed_models
+                .into_iter()
+                .map(|m| m.trim().to_string())
+                .filter(|m| !m.is_empty())
+                .collect(),
+            request_quota: request.request_quota,
+            token_quota: request.token_quota,
+            quota_period: request.quota_period,
+            monthly_spend_cap: request.monthly_spend_cap.filter(|cap| *cap > 0.0),
+            expires_at: request.expires_at,
+            upstream_account: request.upstream_account.filter(|a| !a.trim().is_empty()),
+            created_at: Utc::now().timestamp(),
+            revoked: false,
+        };
+
+        let mut keys = self.keys.write().unwrap_or_else(|e| e.into_inner());
+        keys.push(info.clone());
+        if let Err(e) = self.persist(&keys) {
+            keys.pop();
+            return Err(e);
+        }
+        tracing::info!("[VirtualKeys] Created key '{}' ({})", info.name, info.key_hint);
+        Ok(CreatedVirtualKey { key, info })
+    }
+
+    pub fn revoke(&self, id: &str) -> Result<(), String> {
+        let mut keys = self.keys.write().unwrap_or_else(|e| e.into_inner());
+        let key = keys
+            .iter_mut()
+            .find(|k| k.id == id)
+            .ok_or_else(|| format!("Virtual key not found: {}", id))?;
+        key.revoked = true;
+        tracing::info!("[VirtualKeys] Revoked key '{}' ({})", key.name, key.key_hint);
+        self.persist(&keys)
+    }
+
+    /// 修改月度预算, None 表示不限
+    pub fn set_spend_cap(&self, id: &str, cap: Option<f64>) -> Result<(), String> {
+        let mut keys = self.keys.write().unwrap_or_else(|e| e.into_inner());
+        let key = keys
+            .iter_mut()
+            .find(|k| k.id == id)
+            .ok_or_else(|| format!("Virtual key not found: {}", id))?;
+        key.monthly_spend_cap = cap.filter(|c| *c > 0.0);
+        self.persist(&keys)
+    }
+
+    pub fn list(&self) -> Vec<VirtualKeySummary> {
+        let now = Utc::now().timestamp();
+        let keys = self.keys.read().unwrap_or_else(|e| e.into_inner()).clone();
+        keys.into_iter()
+            .map(|info| {
+                let counter = self.counter(&info, now);
+                VirtualKeySummary {
+                    info,
+                    used_requests: counter.requests,
+                    used_tokens: counter.tokens,
+                    spent_this_month: counter.cost_usd,
+                }
+            })
+            .collect()
+    }
+
+    /// 当前周期的用量, 周期切换或首次访问时从用量记录恢复
+    fn counter(&self, key: &VirtualKey, now: i64) -> UsageCounter {
+        let start = period_start(key.quota_period, key.created_at, now);
+        let month_start = period_start(QuotaPeriod::Monthly, key.created_at, now);
+        let mut usage = self.usage.lock().unwrap_or_else(|e| e.into_inner());
+        let counter = usage.entry(key.id.clone()).or_insert(UsageCounter {
+            period_start: i64::MIN,
+            month_start: i64::MIN,
+            ..Default::default()
+        });
+        if counter.period_start != start {
+            let totals = (self.seed)(key.fingerprint(), start);
+            counter.period_start = start;
+            counter.requests = totals.requests;
+            counter.tokens = totals.tokens;
+        }
+        if counter.month_start != month_start {
+            counter.month_start = month_start;
+            counter.cost_usd = (self.seed)(key.fingerprint(), month_start).cost_usd;
+        }
+        *counter
+    }
+
+    /// 识别客户端 Key 对应的虚拟 Key, 不做校验 (鉴权中间件已校验过)
+    pub fn identify(&self, key: &str) -> Option<VirtualKeyGrant> {
+        let hash = hash_key(key);
+        let keys = self.keys.read().unwrap_or_else(|e| e.into_inner());
+        keys.iter().find(|k| k.key_hash == hash).map(|k| VirtualKeyGrant {
+            id: k.id.clone(),
+            name: k.name.clone(),
+            upstream_account: k.upstream_account.clone(),
+        })
+    }
+
+    /// 校验客户端 Key; 返回 None 表示不是虚拟 Key
+    pub fn authorize(&self, key: &str, model: RequestModel) -> Option<Result<VirtualKeyGrant, VirtualKeyError>> {
+        self.authorize_at(key, model, Utc::now().timestamp())
+    }
+
+    fn authorize_at(
+        &self,
+        key: &str,
+        model: RequestModel,
+        now: i64,
+    ) -> Option<Result<VirtualKeyGrant, VirtualKeyError>> {
+        let hash = hash_key(key);
+        let found = self
+            .keys
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .find(|k| k.key_hash == hash)
+            .cloned()?;
+        Some(self.check(found, model, now))
+    }
+
+    /// JWT 等外部身份按 subject 校验, `key` 为临时构造的虚拟 Key (计数按指纹保存)
+    pub fn authorize_subject(&self, mut key: VirtualKey, model: RequestModel) -> Result<VirtualKeyGrant, VirtualKeyError> {
+        key.id = subject_id(key.fingerprint());
+        self.check(key, model, Utc::now().timestamp())
+    }
+
+    fn check(&self, found: VirtualKey, model: RequestModel, now: i64) -> Result<VirtualKeyGrant, VirtualKeyError> {
+        if found.revoked {
+            return Err(VirtualKeyError::Revoked);
+        }
+        if found.expires_at.is_some_and(|at| at <= now) {
+            return Err(VirtualKeyError::Expired);
+        }
+        if !found.allowed_models.is_empty() {
+            match model {
+                RequestModel::Named(model) if !found.allowed_models.iter().any(|p| wildcard_match(p, model)) => {
+                    return Err(VirtualKeyError::ModelNotAllowed(model.to_string()));
+                }
+                RequestModel::Unknown => return Err(VirtualKeyError::ModelUnknown),
+                _ => {}
+            }
+        }
+        if found.request_quota.is_some() || found.token_quota.is_some() || found.monthly_spend_cap.is_some() {
+            let counter = self.counter(&found, now);
+            if let Some(cap) = found.monthly_spend_cap.filter(|cap| counter.cost_usd >= *cap) {
+                return Err(VirtualKeyError::SpendLimitExceeded(cap));
+            }
+            if let Some(limit) = found.request_quota.filter(|limit| counter.requests >= *limit) {
+                return Err(VirtualKeyError::RequestQuotaExceeded(limit));
+            }
+            if let Some(limit) = found.token_quota.filter(|limit| counter.tokens >= *limit) {
+                return Err(VirtualKeyError::TokenQuotaExceeded(limit));
+            }
+        }
+        Ok(VirtualKeyGrant {
+            id: found.id,
+            name: found.name,
+            upstream_account: found.upstream_account,
+        })
+    }
+
+    /// 请求完成后累计用量 (按用量统计中的 Key 指纹匹配)
+    pub fn record_usage(&self, fingerprint: &str, tokens: u64, cost_usd: f64) {
+        let (id, name, cap) = {
+            let keys = self.keys.read().unwrap_or_else(|e| e.into_inner());
+            match keys.iter().find(|k| k.fingerprint() == fingerprint) {
+                Some(k) => (k.id.clone(), k.name.clone(), k.monthly_spend_cap),
+                None => (subject_id(fingerprint), String::new(), None),
+            }
+        };
+        // 尚未加载的计数器会在下次访问时从用量记录恢复, 这里无需处理
+        let spent = {
+            let mut usage = self.usage.lock().unwrap_or_else(|e| e.into_inner());
+            let Some(counter) = usage.get_mut(&id) else { return };
+            counter.requests += 1;
+            counter.tokens = counter.tokens.saturating_add(tokens);
+            let before = counter.cost_usd;
+            counter.cost_usd += cost_usd;
+            (before, counter.cost_usd)
+        };
+        if let Some(cap) = cap {
+            notify_spend_threshold(&id, &name, cap, spent);
+        }
+    }
+}
+
+/// 本次用量跨过的花费阈值 (预警比例或上限), 返回 (阈值, 是否为上限)
+fn crossed_threshold(cap: f64, warning_ratio: f64, (before, after): (f64, f64)) -> Option<(f64, bool)> {
+    if before < cap && after >= cap {
+        return Some((cap, true));
+    }
+    let warning = cap * warning_ratio;
+    (warning > 0.0 && before < warning && after >= warning).then_some((warning, false))
+}
+
+fn notify_spend_threshold(id: &str, name: &str, cap: f64, spent: (f64, f64)) {
+    use crate::proxy::webhooks::{self, WebhookEvent};
+    let ratio = webhooks::current_config().spend_warning_ratio;
+    let Some((threshold, reached_cap)) = crossed_threshold(cap, ratio, spent) else { return };
+    let message = if reached_cap {
+        format!("Virtual key '{}' reached its monthly spend cap of ${:.2}", name, cap)
+    } else {
+        format!("Virtual key '{}' has spent ${:.2} of its ${:.2} monthly cap", name, spent.1, cap)
+    };
+    let data = serde_json::json!({
+        "key_id": id,
+        "key_name": name,
+        "spent_usd": spent.1,
+        "threshold_usd": threshold,
+        "monthly_spend_cap": cap,
+        "cap_reached": reached_cap,
+    });
+    // 预警与达到上限分开去重
+    let subject = format!("{}:{}", id, if reached_cap { "cap" } else { "warning" });
+    webhooks::notify(WebhookEvent::new(crate::proxy::config::WebhookEventKind::SpendThreshold, subject, message, data));
+}
+
+/// 在绑定上游账号的上下文中执行请求
+pub async fn with_upstream_account<F: std::future::Future>(account: Option<String>, fut: F) -> F::Output {
+    UPSTREAM_ACCOUNT.scope(account, fut).await
+}
+
+/// 当前请求绑定的上游账号
+pub fn current_upstream_account() -> Option<String> {
+    UPSTREAM_ACCOUNT.try_with(|a| a.clone()).ok().flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store() -> VirtualKeyStore {
+        VirtualKeyStore::new(None, |_, _| KeyTotals::default())
+    }
+
+    #[test]
+    fn test_authorize_virtual_key() {
+        let store = store();
+        let created = store
+            .create(CreateVirtualKeyRequest {
+                name: "ci".to_string(),
+                allowed_models: vec!["gemini-*".to_string()],
+                request_quota: Some(2),
+                upstream_account: Some("a@example.com".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+        let now = Utc::now().timestamp();
+
+        assert!(store.authorize_at("sk-unknown", RequestModel::Unknown, now).is_none());
+        let grant = store.authorize_at(&created.key, RequestModel::Named("gemini-2.5-pro"), now).unwrap().unwrap();
+        assert_eq!(grant.upstream_account.as_deref(), Some("a@example.com"));
+        assert_eq!(
+            store.authorize_at(&created.key, RequestModel::Named("claude-sonnet-4-5"), now),
+            Some(Err(VirtualKeyError::ModelNotAllowed("claude-sonnet-4-5".to_string())))
+        );
+        // 限制了可用模型时, 无法确定模型的请求被拒绝, 不调用模型的接口放行
+        assert_eq!(store.authorize_at(&created.key, RequestModel::Unknown, now), Some(Err(VirtualKeyError::ModelUnknown)));
+        assert!(store.authorize_at(&created.key, RequestModel::NotApplicable, now).unwrap().is_ok());
+
+        store.record_usage(created.info.fingerprint(), 100, 0.0);
+        store.record_usage(created.info.fingerprint(), 100, 0.0);
+        assert_eq!(
+            store.authorize_at(&created.key, RequestModel::NotApplicable, now),
+            Some(Err(VirtualKeyError::RequestQuotaExceeded(2)))
+        );
+        assert_eq!(store.list()[0].used_tokens, 200);
+
+        store.revoke(&created.info.id).unwrap();
+        assert_eq!(store.authorize_at(&created.key, RequestModel::NotApplicable, now), Some(Err(VirtualKeyError::Revoked)));
+        assert!(store.revoke("missing").is_err());
+    }
+
+    #[test]
+    fn test_monthly_spend_cap() {
+        let store = VirtualKeyStore::new(None, |_, _| KeyTotals {
+            requests: 3,
+            tokens: 0,
+            cost_usd: 4.0,
+        });
+        let created = store
+            .create(CreateVirtualKeyRequest {
+                name: "budget".to_string(),
+                monthly_spend_cap: Some(5.0),
+                ..Default::default()
+            })
+            .unwrap();
+        let now = Utc::now().timestamp();
+
+        // 从用量记录恢复本月已花费 $4
+        assert!(store.authorize_at(&created.key, RequestModel::NotApplicable, now).unwrap().is_ok());
+        store.record_usage(created.info.fingerprint(), 1000, 1.5);
+        assert_eq!(
+            store.authorize_at(&created.key, RequestModel::NotApplicable, now),
+            Some(Err(VirtualKeyError::SpendLimitExceeded(5.0)))
+        );
+
+        store.set_spend_cap(&created.info.id, Some(10.0)).unwrap();
+        assert!(store.authorize_at(&created.key, RequestModel::NotApplicable, now).unwrap().is_ok());
+        store.set_spend_cap(&created.info.id, None).unwrap();
+        assert_eq!(store.list()[0].info.monthly_spend_cap, None);
+        assert!((store.list()[0].spent_this_month - 5.5).abs() < 1e-9);
+
+        assert_eq!(crossed_threshold(5.0, 0.8, (3.5, 4.2)), Some((4.0, false)));
+        assert_eq!(crossed_threshold(5.0, 0.8, (3.5, 5.5)), Some((5.0, true)));
+        assert_eq!(crossed_threshold(5.0, 0.8, (4.2, 4.5)), None);
+        assert_eq!(crossed_threshold(5.0, 0.0, (3.5, 4.2)), None);
+    }
+
+    #[test]
+    fn test_expiry_and_period_start() {
+        let store = store();
+        let created = store
+            .create(CreateVirtualKeyRequest {
+                name: "temp".to_string(),
+                expires_at: Some(1_700_000_000),
+                ..Default::default()
+            })
+            .unwrap();
+        assert!(store.authorize_at(&created.key, RequestModel::NotApplicable, 1_699_999_999).unwrap().is_ok());
+        assert_eq!(store.authorize_at(&created.key, RequestModel::NotApplicable, 1_700_000_000), Some(Err(VirtualKeyError::Expired)));
+        assert!(store.create(CreateVirtualKeyRequest::default()).is_err());
+
+        // 2023-11-14 22:13:20 UTC
+        assert_eq!(period_start(QuotaPeriod::Daily, 0, 1_700_000_000), 1_699_920_000);
+        assert_eq!(period_start(QuotaPeriod::Monthly, 0, 1_700_000_000), 1_698_796_800);
+        assert_eq!(period_start(QuotaPeriod::Total, 42, 1_700_000_000), 42);
+    }
+}
@@ -0,0 +1,205 @@
+// Webhook 事件通知
+// 上游故障 / 恢复、虚拟 Key 花费预警、客户端限流、服务自动重启等事件以 JSON POST 到用户配置的地址,
+// 也可按 Slack / Discord 的消息格式发送。发送在后台进行, 网络错误、429 与 5xx 按指数退避重试;
+// 同一事件 (类型 + 对象) 在冷却时间内只通知一次。
+
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock, RwLock};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::proxy::config::{WebhookConfig, WebhookEventKind, WebhookFormat, WebhookTarget};
+
+/// JSON 格式的签名头, 值为 `sha256=<hex>` (HMAC-SHA256, 密钥为目标的 secret)
+pub const SIGNATURE_HEADER: &str = "X-Webhook-Signature";
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WebhookEvent {
+    pub event: WebhookEventKind,
+    /// 事件对象 (上游名称、Key 名称等), 与事件类型一起用于去重
+    pub subject: String,
+    pub message: String,
+    /// Unix 秒
+    pub timestamp: i64,
+    pub data: Value,
+}
+
+impl WebhookEvent {
+    pub fn new(event: WebhookEventKind, subject: impl Into<String>, message: impl Into<String>, data: Value) -> Self {
+        Self {
+            event,
+            subject: subject.into(),
+            message: message.into(),
+            timestamp: chrono::Utc::now().timestamp(),
+            data,
+        }
+    }
+}
+
+struct WebhookState {
+    config: RwLock<WebhookConfig>,
+    last_sent: Mutex<HashMap<(WebhookEventKind, String), Instant>>,
+}
+
+impl WebhookState {
+    fn new() -> Self {
+        Self { config: RwLock::new(WebhookConfig::default()), last_sent: Mutex::new(HashMap::new()) }
+    }
+
+    /// 冷却时间内的重复事件返回 false
+    fn should_send(&self, event: &WebhookEvent, cooldown: Duration, now: Instant) -> bool {
+        let mut last_sent = self.last_sent.lock().unwrap_or_else(|e| e.into_inner());
+        last_sent.retain(|_, at| now.duration_since(*at) < cooldown);
+        let key = (event.event, event.subject.clone());
+        if last_sent.contains_key(&key) {
+            return false;
+        }
+        last_sent.insert(key, now);
+        true
+    }
+}
+
+fn state() -> &'static WebhookState {
+    static STATE: OnceLock<WebhookState> = OnceLock::new();
+    STATE.get_or_init(WebhookState::new)
+}
+
+/// 应用 Webhook 配置 (服务启动与配置热更新时调用)
+pub fn configure(config: &WebhookConfig) {
+    *state().config.write().unwrap_or_else(|e| e.into_inner()) = config.clone();
+}
+
+pub fn current_config() -> WebhookConfig {
+    state().config.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// 按目标格式生成请求体
+fn payload(format: WebhookFormat, event: &WebhookEvent) -> Value {
+    match format {
+        WebhookFormat::Json => serde_json::to_value(event).unwrap_or(Value::Null),
+        WebhookFormat::Slack => json!({ "text": format!("*[AIO Launcher]* {}", event.message) }),
+        WebhookFormat::Discord => json!({ "content": format!("**[AIO Launcher]** {}", event.message) }),
+    }
+}
+
+fn sign(secret: &str, body: &[u8]) -> String {
+    let key = ring::hmac::Key::new(ring::hmac::HMAC_SHA256, secret.as_bytes());
+    let tag = ring::hmac::sign(&key, body);
+    let hex: String = tag.as_ref().iter().map(|b| format!("{:02x}", b)).collect();
+    format!("sha256={}", hex)
+}
+
+fn retryable(status: u16) -> bool {
+    status == 429 || status >= 500
+}
+
+/// 发送到单个目标, 失败时最多重试 `max_retries` 次
+async fn deliver(target: &WebhookTarget, event: &WebhookEvent, max_retries: u32) -> Result<(), String> {
+    let body = serde_json::to_vec(&payload(target.format, event)).map_err(|e| e.to_string())?;
+    let client = crate::utils::http::get_client();
+    let mut attempt = 0;
+    loop {
+        let mut request = client
+            .post(&target.url)
+            .timeout(DELIVERY_TIMEOUT)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body.clone());
+        if target.format == WebhookFormat::Json && !target.secret.is_empty() {
+            request = request.header(SIGNATURE_HEADER, sign(&target.secret, &body));
+        }
+        let error = match request.send().await {
+            Ok(resp) if resp.status().is_success() => return Ok(()),
+            Ok(resp) if !retryable(resp.status().as_u16()) => return Err(format!("HTTP {}", resp.status())),
+            Ok(resp) => format!("HTTP {}", resp.status()),
+            Err(e) => e.to_string(),
+        };
+        if attempt >= max_retries {
+            return Err(error);
+        }
+        attempt += 1;
+        tokio::time::sleep(crate::proxy::supervisor::backoff(attempt)).await;
+    }
+}
+
+/// 后台发送事件到所有订阅的目标; 未启用、无订阅目标或处于冷却时间内时忽略
+pub fn notify(event: WebhookEvent) {
+    let config = current_config();
+    if !config.enabled {
+        return;
+    }
+    let targets: Vec<WebhookTarget> = config
+        .targets
+        .into_iter()
+        .filter(|t| t.enabled && !t.url.is_empty() && t.subscribes(event.event))
+        .collect();
+    if targets.is_empty() || !state().should_send(&event, Duration::from_secs(config.cooldown_secs), Instant::now()) {
+        return;
+    }
+    let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+        tracing::warn!("[Webhook] No async runtime, dropped {} event", event.event.as_str());
+        return;
+    };
+    for target in targets {
+        let event = event.clone();
+        let max_retries = config.max_retries;
+        runtime.spawn(async move {
+            match deliver(&target, &event, max_retries).await {
+                Ok(()) => tracing::debug!("[Webhook] Sent {} to {}", event.event.as_str(), target.name),
+                Err(e) => tracing::warn!("[Webhook] Failed to send {} to {}: {}", event.event.as_str(), target.name, e),
+            }
+        });
+    }
+}
+
+/// 发送测试事件 (不重试, 不受订阅与冷却限制)
+pub async fn send_test(target: &WebhookTarget) -> Result<(), String> {
+    if target.url.is_empty() {
+        return Err("Webhook URL is empty".to_string());
+    }
+    let event = WebhookEvent::new(
+        WebhookEventKind::ServiceRestarted,
+        "test",
+        format!("Test notification for webhook '{}'", target.name),
+        json!({ "test": true }),
+    );
+    deliver(target, &event, 0).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payload_formats_and_signature() {
+        let event = WebhookEvent::new(WebhookEventKind::UpstreamDown, "zai", "Upstream zai is down: HTTP 503", json!({}));
+        let raw = payload(WebhookFormat::Json, &event);
+        assert_eq!(raw["event"], "upstream_down");
+        assert_eq!(raw["subject"], "zai");
+        assert_eq!(payload(WebhookFormat::Slack, &event)["text"], "*[AIO Launcher]* Upstream zai is down: HTTP 503");
+        assert_eq!(payload(WebhookFormat::Discord, &event)["content"], "**[AIO Launcher]** Upstream zai is down: HTTP 503");
+
+        // RFC 4231 test case 2
+        assert_eq!(
+            sign("Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        assert!(retryable(429) && retryable(502) && !retryable(404));
+    }
+
+    #[test]
+    fn test_cooldown_per_event_and_subject() {
+        let state = WebhookState::new();
+        let cooldown = Duration::from_secs(300);
+        let now = Instant::now();
+        let event = |kind, subject: &str| WebhookEvent::new(kind, subject, "", Value::Null);
+
+        assert!(state.should_send(&event(WebhookEventKind::RateLimited, "ip:10.0.0.1"), cooldown, now));
+        assert!(!state.should_send(&event(WebhookEventKind::RateLimited, "ip:10.0.0.1"), cooldown, now));
+        assert!(state.should_send(&event(WebhookEventKind::RateLimited, "ip:10.0.0.2"), cooldown, now));
+        assert!(state.should_send(&event(WebhookEventKind::UpstreamDown, "ip:10.0.0.1"), cooldown, now));
+        assert!(state.should_send(&event(WebhookEventKind::RateLimited, "ip:10.0.0.1"), cooldown, now + cooldown));
+    }
+}
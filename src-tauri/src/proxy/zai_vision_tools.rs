@@ -11,9 +11,7 @@ fn build_client(upstream_proxy: UpstreamProxyConfig, timeout_secs: u64) -> Resul
     let mut builder = reqwest::Client::builder()
         .timeout(Duration::from_secs(timeout_secs.max(5)));
 
-    if upstream_proxy.enabled && !upstream_proxy.url.is_empty() {
-        let proxy = reqwest::Proxy::all(&upstream_proxy.url)
-            .map_err(|e| format!("Invalid upstream proxy url: {}", e))?;
+    if let Some(proxy) = crate::proxy::upstream::client_pool::build_proxy(&upstream_proxy)? {
         builder = builder.proxy(proxy);
     }
 
@@ -277,10 +275,10 @@ pub async fn call_tool(
     tool_name: &str,
     arguments: &Value,
 ) -> Result<Value, String> {
-    let api_key = zai.api_key.trim();
-    if api_key.is_empty() {
+    let key_pool = zai.key_pool();
+    let Some(api_key) = key_pool.first().map(|k| k.key.as_str()) else {
         return Err("z.ai api_key is missing".to_string());
-    }
+    };
 
     let client = build_client(upstream_proxy, timeout_secs)?;
 
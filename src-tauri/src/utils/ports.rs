@@ -0,0 +1,189 @@
+// 端口冲突检测
+// 启动反代或托管进程前检查端口是否被占用, 尽量找出占用端口的进程 (Linux 读取 /proc, macOS 使用 lsof,
+// Windows 使用 netstat); 允许回退时改用后续的空闲端口, 并通过事件把实际绑定的地址通知前端。
+
+use serde::Serialize;
+
+/// 前端监听该事件提示端口已自动更换
+pub const PORT_FALLBACK_EVENT: &str = "app://port-fallback";
+/// 自动回退时向后尝试的端口数
+pub const FALLBACK_RANGE: u16 = 20;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PortOwner {
+    pub pid: u32,
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PortFallback {
+    pub service: String,
+    pub requested: u16,
+    pub actual: u16,
+    pub conflict: String,
+}
+
+pub fn is_available(host: &str, port: u16) -> bool {
+    std::net::TcpListener::bind((host, port)).is_ok()
+}
+
+/// `start` 之后 (不含) 第一个空闲端口
+pub fn find_available(host: &str, start: u16) -> Option<u16> {
+    (1..=FALLBACK_RANGE).filter_map(|i| start.checked_add(i)).find(|p| is_available(host, *p))
+}
+
+/// `/proc/net/tcp{,6}` 中监听该端口的 socket inode
+pub fn parse_proc_net_tcp(content: &str, port: u16) -> Vec<u64> {
+    content
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let local_port = u16::from_str_radix(fields.get(1)?.rsplit(':').next()?, 16).ok()?;
+            // 0A = LISTEN
+            (local_port == port && *fields.get(3)? == "0A").then(|| fields.get(9)?.parse().ok())?
+        })
+        .collect()
+}
+
+/// `netstat -ano -p TCP` 中监听该端口的进程
+#[cfg(any(target_os = "windows", test))]
+pub fn parse_netstat(output: &str, port: u16) -> Option<u32> {
+    let suffix = format!(":{}", port);
+    output.lines().find_map(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        match fields.as_slice() {
+            [proto, local, _, state, pid] if proto.eq_ignore_ascii_case("tcp") && local.ends_with(&suffix) && *state == "LISTENING" => {
+                pid.parse().ok()
+            }
+            _ => None,
+        }
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn owner_pid(port: u16) -> Option<u32> {
+    let inodes: Vec<u64> = ["/proc/net/tcp", "/proc/net/tcp6"]
+        .iter()
+        .filter_map(|path| std::fs::read_to_string(path).ok())
+        .flat_map(|content| parse_proc_net_tcp(&content, port))
+        .filter(|inode| *inode != 0)
+        .collect();
+    if inodes.is_empty() {
+        return None;
+    }
+    let targets: Vec<String> = inodes.iter().map(|i| format!("socket:[{}]", i)).collect();
+    // 其他用户的进程没有权限读取 fd, 这时只能报告端口被占用
+    std::fs::read_dir("/proc").ok()?.flatten().find_map(|entry| {
+        let pid: u32 = entry.file_name().to_str()?.parse().ok()?;
+        let fds = std::fs::read_dir(entry.path().join("fd")).ok()?;
+        fds.flatten()
+            .any(|fd| std::fs::read_link(fd.path()).is_ok_and(|link| targets.iter().any(|t| link.as_os_str() == t.as_str())))
+            .then_some(pid)
+    })
+}
+
+#[cfg(target_os = "macos")]
+fn owner_pid(port: u16) -> Option<u32> {
+    let output = std::process::Command::new("lsof")
+        .args(["-nP", &format!("-iTCP:{}", port), "-sTCP:LISTEN", "-Fp"])
+        .output()
+        .ok()?;
+    String::from_utf8_lossy(&output.stdout).lines().find_map(|l| l.strip_prefix('p')?.parse().ok())
+}
+
+#[cfg(target_os = "windows")]
+fn owner_pid(port: u16) -> Option<u32> {
+    use std::os::windows::process::CommandExt;
+    let output = std::process::Command::new("netstat")
+        .args(["-ano", "-p", "TCP"])
+        .creation_flags(0x08000000)
+        .output()
+        .ok()?;
+    parse_netstat(&String::from_utf8_lossy(&output.stdout), port)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn owner_pid(_port: u16) -> Option<u32> {
+    None
+}
+
+/// 占用端口的进程 (无法识别时返回 None)
+pub fn port_owner(port: u16) -> Option<PortOwner> {
+    let pid = owner_pid(port)?;
+    let mut system = sysinfo::System::new();
+    let sys_pid = sysinfo::Pid::from_u32(pid);
+    system.refresh_processes(sysinfo::ProcessesToUpdate::Some(&[sys_pid]));
+    let name = system.process(sys_pid).map(|p| p.name().to_string_lossy().to_string());
+    Some(PortOwner { pid, name })
+}
+
+pub fn describe_conflict(port: u16) -> String {
+    match port_owner(port) {
+        Some(PortOwner { pid, name: Some(name) }) => format!("Port {} is already in use by {} (pid {})", port, name, pid),
+        Some(PortOwner { pid, name: None }) => format!("Port {} is already in use by pid {}", port, pid),
+        None => format!("Port {} is already in use by another process", port),
+    }
+}
+
+/// 记录端口回退并通知前端
+pub fn report_fallback(service: &str, requested: u16, actual: u16, conflict: String) {
+    tracing::warn!("[Ports] {}: {}, using port {} instead", service, conflict, actual);
+    let event = PortFallback { service: service.to_string(), requested, actual, conflict };
+    if let Some(integration) = crate::modules::notifications::integration() {
+        integration.emit(PORT_FALLBACK_EVENT, &event);
+    }
+}
+
+/// 绑定端口; 被占用且允许回退时改用后续的空闲端口
+pub async fn bind_with_fallback(host: &str, port: u16, fallback: bool, service: &str) -> Result<tokio::net::TcpListener, String> {
+    let error = match tokio::net::TcpListener::bind((host, port)).await {
+        Ok(listener) => return Ok(listener),
+        Err(e) => e,
+    };
+    if error.kind() != std::io::ErrorKind::AddrInUse || port == 0 {
+        return Err(format!("Failed to bind {}:{}: {}", host, port, error));
+    }
+    let conflict = describe_conflict(port);
+    if !fallback {
+        return Err(format!("{}; enable port fallback or choose another port", conflict));
+    }
+    for candidate in (1..=FALLBACK_RANGE).filter_map(|i| port.checked_add(i)) {
+        if let Ok(listener) = tokio::net::TcpListener::bind((host, candidate)).await {
+            report_fallback(service, port, candidate, conflict);
+            return Ok(listener);
+        }
+    }
+    Err(format!("{}; no free port found in {}-{}", conflict, port.saturating_add(1), port.saturating_add(FALLBACK_RANGE)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_listeners() {
+        let tcp = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode\n   \
+                   0: 0100007F:1F6D 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 41234 1 0000000000000000 100 0 0 10 0\n   \
+                   1: 0100007F:1F6D 0100007F:D2A4 01 00000000:00000000 00:00000000 00000000  1000        0 51234 1 0000000000000000 20 4 30 10 -1\n";
+        assert_eq!(parse_proc_net_tcp(tcp, 8045), vec![41234]);
+        assert!(parse_proc_net_tcp(tcp, 8046).is_empty());
+
+        let netstat = "\r\nActive Connections\r\n\r\n  Proto  Local Address          Foreign Address        State           PID\r\n  \
+                       TCP    0.0.0.0:8045           0.0.0.0:0              LISTENING       4321\r\n  \
+                       TCP    127.0.0.1:18045        127.0.0.1:50000        ESTABLISHED     99\r\n";
+        assert_eq!(parse_netstat(netstat, 8045), Some(4321));
+        assert_eq!(parse_netstat(netstat, 18045), None);
+    }
+
+    #[tokio::test]
+    async fn test_bind_with_fallback() {
+        let taken = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = taken.local_addr().unwrap().port();
+        assert!(!is_available("127.0.0.1", port));
+        let err = bind_with_fallback("127.0.0.1", port, false, "test").await.unwrap_err();
+        assert!(err.contains(&format!("Port {} is already in use", port)), "{}", err);
+        let listener = bind_with_fallback("127.0.0.1", port, true, "test").await.unwrap();
+        assert_ne!(listener.local_addr().unwrap().port(), port);
+    }
+}
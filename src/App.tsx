@@ -7,6 +7,7 @@ import Settings from './pages/Settings';
 import ApiProxy from './pages/ApiProxy';
 import Monitor from './pages/Monitor';
 import TokenStats from './pages/TokenStats';
+import Inspector from './pages/Inspector';
 import ThemeManager from './components/common/ThemeManager';
 import { UpdateNotification } from './components/UpdateNotification';
 import { useEffect, useState } from 'react';
@@ -17,6 +18,7 @@ import { listen } from '@tauri-apps/api/event';
 import { isTauri } from './utils/env';
 import { request as invoke } from './utils/request';
 import { AdminAuthGuard } from './components/common/AdminAuthGuard';
+import { showToast } from './components/common/ToastContainer';
 
 const router = createBrowserRouter([
   {
@@ -49,6 +51,11 @@ const router = createBrowserRouter([
       },
     ],
   },
+  {
+    // 独立的实时请求检查器窗口
+    path: '/inspector',
+    element: <Inspector />,
+  },
 ]);
 
 function App() {
@@ -96,13 +103,51 @@ function App() {
       })
     );
 
+    // 监听托盘打开仪表盘事件
+    unlistenPromises.push(
+      listen('tray://open-dashboard', () => {
+        router.navigate('/');
+      })
+    );
+
+    // 监听配置文件热加载事件
+    unlistenPromises.push(
+      listen('config://reloaded', () => {
+        console.log('[App] Config file reloaded, refreshing config...');
+        loadConfig();
+      })
+    );
+
+    unlistenPromises.push(
+      listen<string>('config://reload-failed', (event) => {
+        console.warn('[App] Config file reload failed:', event.payload);
+      })
+    );
+
+    // 反代关键事件的系统通知 (未授权时退回应用内提示)
+    unlistenPromises.push(
+      listen<{ title: string; body: string }>('app://notification', async (event) => {
+        const { title, body } = event.payload;
+        if ('Notification' in window) {
+          if (Notification.permission === 'default') {
+            await Notification.requestPermission();
+          }
+          if (Notification.permission === 'granted') {
+            new Notification(title, { body });
+            return;
+          }
+        }
+        showToast(`${title}: ${body}`, 'warning', 8000);
+      })
+    );
+
     // Cleanup
     return () => {
       Promise.all(unlistenPromises).then(unlisteners => {
         unlisteners.forEach(unlisten => unlisten());
       });
     };
-  }, [fetchCurrentAccount, fetchAccounts]);
+  }, [fetchCurrentAccount, fetchAccounts, loadConfig]);
 
   // Update notification state
   const [showUpdateNotification, setShowUpdateNotification] = useState(false);
@@ -0,0 +1,136 @@
+import { useCallback, useEffect, useState } from 'react';
+import { useTranslation } from 'react-i18next';
+import { KeyRound, RefreshCw } from 'lucide-react';
+import { listen } from '@tauri-apps/api/event';
+import { request as invoke } from '../../utils/request';
+import { isTauri } from '../../utils/env';
+import { DisabledUpstreamKey, KeyCheckResult, KeyStatus } from '../../types/config';
+
+const STATUS_STYLE: Record<KeyStatus, string> = {
+    valid: 'badge-success',
+    invalid: 'badge-error',
+    low_balance: 'badge-warning',
+    expiring: 'badge-warning',
+    unreachable: 'badge-ghost',
+};
+
+function KeyHealthCard() {
+    const { t } = useTranslation();
+    const [results, setResults] = useState<KeyCheckResult[]>([]);
+    const [disabledKeys, setDisabledKeys] = useState<DisabledUpstreamKey[]>([]);
+    const [checking, setChecking] = useState(false);
+
+    const loadDisabled = useCallback(() => {
+        // 反代未启动时为空
+        invoke<DisabledUpstreamKey[]>('get_disabled_upstream_keys')
+            .then(setDisabledKeys)
+            .catch((e) => console.debug('Failed to load disabled keys:', e));
+    }, []);
+
+    useEffect(() => {
+        invoke<KeyCheckResult[]>('get_key_check_results')
+            .then(setResults)
+            .catch((e) => console.debug('Failed to load key check results:', e));
+        loadDisabled();
+        if (!isTauri()) return;
+        // 定时巡检完成后推送
+        const unlisten = listen<KeyCheckResult[]>('key-check://updated', (event) => setResults(event.payload));
+        return () => {
+            unlisten.then((fn) => fn());
+        };
+    }, [loadDisabled]);
+
+    const enableKey = async (keyId: string) => {
+        try {
+            await invoke('enable_upstream_key', { keyId });
+        } catch (e) {
+            console.error('Failed to enable key:', e);
+        }
+        loadDisabled();
+    };
+
+    const checkNow = useCallback(async () => {
+        setChecking(true);
+        try {
+            setResults(await invoke<KeyCheckResult[]>('check_upstream_keys'));
+        } catch (e) {
+            console.error('Failed to check upstream keys:', e);
+        } finally {
+            setChecking(false);
+        }
+        loadDisabled();
+    }, [loadDisabled]);
+
+    // 有效的 Key 只计数, 不逐条列出
+    const attention = results.filter((r) => r.status !== 'valid');
+    const validCount = results.length - attention.length;
+
+    return (
+        <div className="bg-white dark:bg-base-100 rounded-xl p-4 shadow-sm border border-gray-100 dark:border-base-200">
+            <div className="flex items-center justify-between mb-3">
+                <div className="flex items-center gap-2">
+                    <KeyRound className="w-4 h-4 text-amber-500" />
+                    <h2 className="text-sm font-semibold text-gray-900 dark:text-base-content">{t('dashboard.key_health.title')}</h2>
+                    {results.length > 0 && (
+                        <span className="text-xs text-gray-500 dark:text-gray-400">
+                            {t('dashboard.key_health.valid_count', { count: validCount, total: results.length })}
+                        </span>
+                    )}
+                </div>
+                <button className="btn btn-ghost btn-xs" onClick={checkNow} disabled={checking}>
+                    <RefreshCw className={`w-3 h-3 ${checking ? 'animate-spin' : ''}`} />
+                    {t('dashboard.key_health.check_now')}
+                </button>
+            </div>
+            {disabledKeys.length > 0 && (
+                <ul className="space-y-2 mb-3">
+                    {disabledKeys.map((k) => (
+                        <li key={k.key_id} className="flex items-center justify-between text-xs">
+                            <div className="min-w-0">
+                                <span className="font-medium text-gray-900 dark:text-base-content">zai</span>
+                                <span className="ml-2 font-mono text-gray-500">{k.key_hint}</span>
+                                <p className="text-gray-500 truncate">{t(`dashboard.key_health.disabled_reason.${k.reason}`)}</p>
+                            </div>
+                            <div className="flex items-center gap-2 shrink-0">
+                                <span className="badge badge-sm badge-error">{t('dashboard.key_health.disabled')}</span>
+                                <button className="btn btn-ghost btn-xs" onClick={() => enableKey(k.key_id)}>
+                                    {t('dashboard.key_health.enable')}
+                                </button>
+                            </div>
+                        </li>
+                    ))}
+                </ul>
+            )}
+            {results.length === 0 ? (
+                <p className="text-xs text-gray-500 dark:text-gray-400">{t('dashboard.key_health.empty')}</p>
+            ) : attention.length === 0 ? (
+                <p className="text-xs text-green-600 dark:text-green-400">{t('dashboard.key_health.all_valid')}</p>
+            ) : (
+                <ul className="space-y-2">
+                    {attention.map((r) => (
+                        <li key={`${r.upstream}:${r.key_hint}`} className="flex items-center justify-between text-xs">
+                            <div className="min-w-0">
+                                <span className="font-medium text-gray-900 dark:text-base-content">{r.upstream}</span>
+                                <span className="ml-2 font-mono text-gray-500">{r.key_hint}</span>
+                                {r.error && <p className="text-gray-500 truncate">{r.error}</p>}
+                            </div>
+                            <div className="flex items-center gap-2 shrink-0">
+                                {r.balance?.remaining != null && (
+                                    <span className="text-gray-500">
+                                        {r.balance.remaining.toFixed(2)} {r.balance.currency}
+                                    </span>
+                                )}
+                                {r.balance?.expires_at != null && (
+                                    <span className="text-gray-500">{new Date(r.balance.expires_at * 1000).toLocaleDateString()}</span>
+                                )}
+                                <span className={`badge badge-sm ${STATUS_STYLE[r.status]}`}>{t(`dashboard.key_health.status.${r.status}`)}</span>
+                            </div>
+                        </li>
+                    ))}
+                </ul>
+            )}
+        </div>
+    );
+}
+
+export default KeyHealthCard;
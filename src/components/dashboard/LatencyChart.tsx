@@ -0,0 +1,132 @@
+import { useCallback, useEffect, useState } from 'react';
+import { useTranslation } from 'react-i18next';
+import { BarChart, Bar, XAxis, YAxis, CartesianGrid, Tooltip, ResponsiveContainer, Legend } from 'recharts';
+import { Timer, RefreshCw } from 'lucide-react';
+import { request as invoke } from '../../utils/request';
+
+interface LatencySummary {
+    count: number;
+    avg_ms: number;
+    p50_ms: number;
+    p95_ms: number;
+    p99_ms: number;
+    max_ms: number;
+    histogram: { le_ms: number | null; count: number }[];
+}
+
+interface LatencyStats {
+    model: string;
+    upstream: string;
+    total: LatencySummary;
+    ttft: LatencySummary | null; // 窗口内没有流式请求时为 null
+}
+
+type Metric = 'total' | 'ttft';
+
+const WINDOWS = [15, 60];
+const REFRESH_INTERVAL = 30_000;
+const MAX_ROWS = 8;
+
+const formatMs = (ms: number) => (ms >= 1000 ? `${(ms / 1000).toFixed(1)}s` : `${ms}ms`);
+
+function LatencyChart() {
+    const { t } = useTranslation();
+    const [stats, setStats] = useState<LatencyStats[]>([]);
+    const [windowMins, setWindowMins] = useState(15);
+    const [metric, setMetric] = useState<Metric>('total');
+    const [loading, setLoading] = useState(false);
+
+    const load = useCallback(async () => {
+        setLoading(true);
+        try {
+            setStats(await invoke<LatencyStats[]>('get_latency_stats', { windowMins }));
+        } catch (e) {
+            // 反代未启动时没有数据
+            console.debug('Failed to load latency stats:', e);
+            setStats([]);
+        } finally {
+            setLoading(false);
+        }
+    }, [windowMins]);
+
+    useEffect(() => {
+        load();
+        const timer = setInterval(load, REFRESH_INTERVAL);
+        return () => clearInterval(timer);
+    }, [load]);
+
+    const data = stats
+        .map((s) => ({ name: `${s.model} · ${s.upstream}`, summary: metric === 'total' ? s.total : s.ttft }))
+        .filter((row): row is { name: string; summary: LatencySummary } => row.summary !== null)
+        .slice(0, MAX_ROWS)
+        .map(({ name, summary }) => ({
+            name,
+            count: summary.count,
+            p50: summary.p50_ms,
+            p95: summary.p95_ms,
+            p99: summary.p99_ms,
+        }));
+
+    return (
+        <div className="bg-white dark:bg-base-100 rounded-xl p-4 shadow-sm border border-gray-100 dark:border-base-200">
+            <div className="flex items-center justify-between mb-3">
+                <div className="flex items-center gap-2">
+                    <Timer className="w-4 h-4 text-blue-500" />
+                    <h2 className="text-sm font-semibold text-gray-900 dark:text-base-content">{t('dashboard.latency.title')}</h2>
+                </div>
+                <div className="flex items-center gap-2">
+                    <div className="join">
+                        {(['total', 'ttft'] as Metric[]).map((m) => (
+                            <button
+                                key={m}
+                                className={`join-item btn btn-xs ${metric === m ? 'btn-primary' : 'btn-ghost'}`}
+                                onClick={() => setMetric(m)}
+                            >
+                                {t(`dashboard.latency.${m}`)}
+                            </button>
+                        ))}
+                    </div>
+                    <select
+                        className="select select-bordered select-xs"
+                        value={windowMins}
+                        onChange={(e) => setWindowMins(Number(e.target.value))}
+                    >
+                        {WINDOWS.map((w) => (
+                            <option key={w} value={w}>
+                                {t('dashboard.latency.window', { minutes: w })}
+                            </option>
+                        ))}
+                    </select>
+                    <button className="btn btn-ghost btn-xs" onClick={load} disabled={loading}>
+                        <RefreshCw className={`w-3 h-3 ${loading ? 'animate-spin' : ''}`} />
+                    </button>
+                </div>
+            </div>
+            <div className="h-64">
+                {data.length > 0 ? (
+                    <ResponsiveContainer width="100%" height="100%">
+                        <BarChart data={data} layout="vertical" margin={{ left: 16 }}>
+                            <CartesianGrid strokeDasharray="3 3" horizontal={false} stroke="#374151" strokeOpacity={0.15} />
+                            <XAxis type="number" tick={{ fontSize: 11, fill: '#6b7280' }} tickFormatter={formatMs} axisLine={false} tickLine={false} />
+                            <YAxis type="category" dataKey="name" width={160} tick={{ fontSize: 11, fill: '#6b7280' }} axisLine={false} tickLine={false} />
+                            <Tooltip
+                                formatter={(value) => formatMs(Number(value))}
+                                labelFormatter={(label, payload) =>
+                                    `${label} (${t('dashboard.latency.requests', { count: payload?.[0]?.payload?.count ?? 0 })})`
+                                }
+                            />
+                            <Legend wrapperStyle={{ fontSize: 11 }} />
+                            <Bar dataKey="p50" name="p50" fill="#3b82f6" radius={[0, 4, 4, 0]} maxBarSize={12} />
+                            <Bar dataKey="p95" name="p95" fill="#f59e0b" radius={[0, 4, 4, 0]} maxBarSize={12} />
+                            <Bar dataKey="p99" name="p99" fill="#ef4444" radius={[0, 4, 4, 0]} maxBarSize={12} />
+                        </BarChart>
+                    </ResponsiveContainer>
+                ) : (
+                    <div className="h-full flex items-center justify-center text-xs text-gray-400">{t('dashboard.latency.empty')}</div>
+                )}
+            </div>
+        </div>
+    );
+}
+
+export default LatencyChart;
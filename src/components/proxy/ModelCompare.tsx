@@ -0,0 +1,137 @@
+import { useState } from 'react';
+import { useTranslation } from 'react-i18next';
+import { Columns, Loader2, Play } from 'lucide-react';
+import { request as invoke } from '../../utils/request';
+import { useProxyModels } from '../../hooks/useProxyModels';
+import { cn } from '../../utils/cn';
+
+interface CompareResult {
+    model: string;
+    mapped_model?: string;
+    status?: number; // 请求失败时为空
+    duration: number;
+    content?: string;
+    input_tokens?: number;
+    output_tokens?: number;
+    cost?: number;
+    error?: string;
+}
+
+const MAX_MODELS = 6;
+
+const formatCost = (cost?: number) => (cost == null ? '-' : `$${cost < 0.01 ? cost.toFixed(5) : cost.toFixed(3)}`);
+
+export const ModelCompare = ({ running }: { running: boolean }) => {
+    const { t } = useTranslation();
+    const { models: knownModels } = useProxyModels();
+    const [models, setModels] = useState<string[]>(['', '']);
+    const [system, setSystem] = useState('');
+    const [prompt, setPrompt] = useState('');
+    const [results, setResults] = useState<CompareResult[]>([]);
+    const [error, setError] = useState<string | null>(null);
+    const [loading, setLoading] = useState(false);
+
+    const selected = models.map(m => m.trim()).filter(Boolean);
+
+    const updateModel = (index: number, value: string) => {
+        setModels(prev => prev.map((m, i) => (i === index ? value : m)));
+    };
+
+    const run = async () => {
+        setLoading(true);
+        setError(null);
+        try {
+            const messages = [
+                ...(system.trim() ? [{ role: 'system', content: system }] : []),
+                { role: 'user', content: prompt },
+            ];
+            setResults(await invoke<CompareResult[]>('compare_models', { models: selected, request: { messages } }));
+        } catch (e) {
+            setError(String(e));
+        } finally {
+            setLoading(false);
+        }
+    };
+
+    return (
+        <div className="bg-white dark:bg-base-100 rounded-xl p-4 shadow-sm border border-gray-100 dark:border-base-200 space-y-3">
+            <div className="flex items-center gap-2">
+                <Columns size={18} className="text-indigo-500" />
+                <h3 className="text-sm font-semibold text-gray-900 dark:text-base-content">{t('proxy.compare.title')}</h3>
+                <span className="text-[11px] text-gray-400">{t('proxy.compare.desc')}</span>
+            </div>
+            <datalist id="compare-models">
+                {knownModels.map(m => (
+                    <option key={m.id} value={m.id}>{m.name}</option>
+                ))}
+            </datalist>
+            <div className="flex flex-wrap gap-2 items-center">
+                {models.map((model, i) => (
+                    <input
+                        key={i}
+                        list="compare-models"
+                        className="input input-xs input-bordered w-48 font-mono"
+                        placeholder={t('proxy.compare.model_placeholder', { index: i + 1 })}
+                        value={model}
+                        onChange={e => updateModel(i, e.target.value)}
+                    />
+                ))}
+                {models.length < MAX_MODELS && (
+                    <button className="btn btn-xs btn-ghost" onClick={() => setModels(prev => [...prev, ''])}>
+                        + {t('proxy.compare.add_model')}
+                    </button>
+                )}
+            </div>
+            <input
+                className="input input-sm input-bordered w-full"
+                placeholder={t('proxy.compare.system_placeholder')}
+                value={system}
+                onChange={e => setSystem(e.target.value)}
+            />
+            <textarea
+                className="textarea textarea-bordered w-full text-sm min-h-[80px]"
+                placeholder={t('proxy.compare.prompt_placeholder')}
+                value={prompt}
+                onChange={e => setPrompt(e.target.value)}
+            />
+            <div className="flex items-center gap-2">
+                <button
+                    className="btn btn-sm btn-primary"
+                    disabled={loading || !running || selected.length === 0 || !prompt.trim()}
+                    onClick={run}
+                >
+                    {loading ? <Loader2 size={14} className="animate-spin" /> : <Play size={14} />}
+                    {t('proxy.compare.run')}
+                </button>
+                {!running && <span className="text-xs text-gray-400">{t('proxy.compare.not_running')}</span>}
+                {error && <span className="text-xs text-red-500 font-mono">{error}</span>}
+            </div>
+            {results.length > 0 && (
+                <div className={cn('grid gap-3', results.length > 1 ? 'md:grid-cols-2 xl:grid-cols-3' : 'grid-cols-1')}>
+                    {results.map(r => (
+                        <div key={r.model} className="rounded-lg border border-gray-100 dark:border-base-200 p-3 flex flex-col gap-2 min-w-0">
+                            <div className="flex items-center justify-between gap-2 text-xs">
+                                <span className="font-mono font-semibold truncate" title={r.mapped_model || r.model}>{r.model}</span>
+                                <span className={cn('badge badge-xs text-white border-none', r.status && r.status < 400 ? 'badge-success' : 'badge-error')}>
+                                    {r.status ?? 'ERR'}
+                                </span>
+                            </div>
+                            <div className="flex flex-wrap gap-x-3 text-[11px] text-gray-500 font-mono">
+                                <span>{r.duration}ms</span>
+                                {r.input_tokens != null && <span>I: {r.input_tokens}</span>}
+                                {r.output_tokens != null && <span>O: {r.output_tokens}</span>}
+                                <span>{formatCost(r.cost)}</span>
+                            </div>
+                            <pre className={cn(
+                                'text-xs whitespace-pre-wrap break-words max-h-80 overflow-auto rounded bg-gray-50 dark:bg-base-200 p-2',
+                                r.error && 'text-red-500'
+                            )}>
+                                {r.error || r.content || t('proxy.compare.empty')}
+                            </pre>
+                        </div>
+                    ))}
+                </div>
+            )}
+        </div>
+    );
+};
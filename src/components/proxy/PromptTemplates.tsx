@@ -0,0 +1,203 @@
+import { useCallback, useEffect, useState } from 'react';
+import { useTranslation } from 'react-i18next';
+import { FileText, Plus, Save, Trash2, Eye } from 'lucide-react';
+import { request as invoke } from '../../utils/request';
+import { showToast } from '../common/ToastContainer';
+
+interface TemplateVariable {
+    name: string;
+    default?: string | null;
+    description: string;
+}
+
+interface PromptTemplate {
+    id: string;
+    name: string;
+    description: string;
+    system?: string | null;
+    system_mode: 'prefix' | 'suffix' | 'replace';
+    prompt?: string | null;
+    variables: TemplateVariable[];
+    updated_at: number;
+}
+
+const emptyTemplate = (): PromptTemplate => ({
+    id: '',
+    name: '',
+    description: '',
+    system: '',
+    system_mode: 'prefix',
+    prompt: '',
+    variables: [],
+    updated_at: 0,
+});
+
+// 变量以每行 `name=默认值` 编辑, 没有 `=` 的变量没有默认值
+const variablesToText = (variables: TemplateVariable[]) =>
+    variables.map(v => (v.default != null ? `${v.name}=${v.default}` : v.name)).join('\n');
+
+const textToVariables = (text: string): TemplateVariable[] =>
+    text
+        .split('\n')
+        .map(line => line.trim())
+        .filter(Boolean)
+        .map(line => {
+            const index = line.indexOf('=');
+            return index < 0
+                ? { name: line, default: null, description: '' }
+                : { name: line.slice(0, index).trim(), default: line.slice(index + 1), description: '' };
+        });
+
+export const PromptTemplates = () => {
+    const { t } = useTranslation();
+    const [templates, setTemplates] = useState<PromptTemplate[]>([]);
+    const [editing, setEditing] = useState<PromptTemplate | null>(null);
+    const [variablesText, setVariablesText] = useState('');
+    const [previewInput, setPreviewInput] = useState('');
+    const [preview, setPreview] = useState<string | null>(null);
+
+    const load = useCallback(async () => {
+        try {
+            setTemplates(await invoke<PromptTemplate[]>('list_prompt_templates'));
+        } catch (e) {
+            console.error('Failed to load prompt templates', e);
+        }
+    }, []);
+
+    useEffect(() => {
+        load();
+    }, [load]);
+
+    const edit = (template: PromptTemplate) => {
+        setEditing(template);
+        setVariablesText(variablesToText(template.variables));
+        setPreview(null);
+    };
+
+    const current = () => (editing ? { ...editing, variables: textToVariables(variablesText) } : null);
+
+    const save = async () => {
+        const template = current();
+        if (!template) return;
+        try {
+            edit(await invoke<PromptTemplate>('save_prompt_template', { template }));
+            showToast(t('proxy.templates.saved'), 'success');
+            load();
+        } catch (e) {
+            showToast(String(e), 'error');
+        }
+    };
+
+    const remove = async (id: string) => {
+        try {
+            await invoke('delete_prompt_template', { id });
+            if (editing?.id === id) setEditing(null);
+            load();
+        } catch (e) {
+            showToast(String(e), 'error');
+        }
+    };
+
+    const runPreview = async () => {
+        const template = current();
+        if (!template) return;
+        try {
+            const body = await invoke<unknown>('preview_prompt_template', { template, variables: {}, input: previewInput });
+            setPreview(JSON.stringify(body, null, 2));
+        } catch (e) {
+            setPreview(String(e));
+        }
+    };
+
+    return (
+        <div className="bg-white dark:bg-base-100 rounded-xl p-4 shadow-sm border border-gray-100 dark:border-base-200 space-y-3">
+            <div className="flex items-center gap-2">
+                <FileText size={18} className="text-emerald-500" />
+                <h3 className="text-sm font-semibold text-gray-900 dark:text-base-content">{t('proxy.templates.title')}</h3>
+                <span className="text-[11px] text-gray-400 flex-1">{t('proxy.templates.desc')}</span>
+                <button className="btn btn-xs btn-ghost" onClick={() => edit(emptyTemplate())}>
+                    <Plus size={14} /> {t('proxy.templates.new')}
+                </button>
+            </div>
+            {templates.length > 0 && (
+                <div className="flex flex-wrap gap-2">
+                    {templates.map(tpl => (
+                        <div key={tpl.id} className={`badge badge-lg gap-1 cursor-pointer ${editing?.id === tpl.id ? 'badge-primary' : 'badge-ghost'}`}>
+                            <span className="font-mono text-xs" onClick={() => edit(tpl)} title={tpl.description}>{tpl.name}</span>
+                            <Trash2 size={12} className="opacity-60 hover:opacity-100" onClick={() => remove(tpl.id)} />
+                        </div>
+                    ))}
+                </div>
+            )}
+            {editing && (
+                <div className="space-y-2">
+                    <div className="grid grid-cols-1 md:grid-cols-2 gap-2">
+                        <input
+                            className="input input-sm input-bordered font-mono"
+                            placeholder={t('proxy.templates.name')}
+                            value={editing.name}
+                            onChange={e => setEditing({ ...editing, name: e.target.value })}
+                        />
+                        <input
+                            className="input input-sm input-bordered"
+                            placeholder={t('proxy.templates.description')}
+                            value={editing.description}
+                            onChange={e => setEditing({ ...editing, description: e.target.value })}
+                        />
+                    </div>
+                    <div className="flex gap-2">
+                        <textarea
+                            className="textarea textarea-bordered flex-1 text-sm font-mono"
+                            placeholder={t('proxy.templates.system')}
+                            value={editing.system ?? ''}
+                            onChange={e => setEditing({ ...editing, system: e.target.value })}
+                        />
+                        <select
+                            className="select select-sm select-bordered"
+                            value={editing.system_mode}
+                            onChange={e => setEditing({ ...editing, system_mode: e.target.value as PromptTemplate['system_mode'] })}
+                        >
+                            {(['prefix', 'suffix', 'replace'] as const).map(mode => (
+                                <option key={mode} value={mode}>{mode}</option>
+                            ))}
+                        </select>
+                    </div>
+                    <textarea
+                        className="textarea textarea-bordered w-full text-sm font-mono min-h-[80px]"
+                        placeholder={t('proxy.templates.prompt')}
+                        value={editing.prompt ?? ''}
+                        onChange={e => setEditing({ ...editing, prompt: e.target.value })}
+                    />
+                    <textarea
+                        className="textarea textarea-bordered w-full text-xs font-mono"
+                        placeholder={t('proxy.templates.variables')}
+                        value={variablesText}
+                        onChange={e => setVariablesText(e.target.value)}
+                    />
+                    <div className="text-[11px] text-gray-400">
+                        {t('proxy.templates.usage', { name: editing.name || '<name>' })}
+                    </div>
+                    <div className="flex gap-2 items-center">
+                        <input
+                            className="input input-sm input-bordered flex-1"
+                            placeholder={t('proxy.templates.preview_input')}
+                            value={previewInput}
+                            onChange={e => setPreviewInput(e.target.value)}
+                        />
+                        <button className="btn btn-sm btn-ghost" onClick={runPreview}>
+                            <Eye size={14} /> {t('proxy.templates.preview')}
+                        </button>
+                        <button className="btn btn-sm btn-primary" onClick={save}>
+                            <Save size={14} /> {t('proxy.templates.save')}
+                        </button>
+                    </div>
+                    {preview && (
+                        <pre className="text-xs font-mono whitespace-pre-wrap break-all bg-gray-50 dark:bg-base-200 rounded p-2 max-h-64 overflow-auto">
+                            {preview}
+                        </pre>
+                    )}
+                </div>
+            )}
+        </div>
+    );
+};
@@ -3,7 +3,7 @@ import { listen } from '@tauri-apps/api/event';
 import ModalDialog from '../common/ModalDialog';
 import { useTranslation } from 'react-i18next';
 import { request as invoke } from '../../utils/request';
-import { Trash2, Search, X, Copy, CheckCircle, ChevronLeft, ChevronRight, RefreshCw, User } from 'lucide-react';
+import { Trash2, Search, X, Copy, CheckCircle, ChevronLeft, ChevronRight, RefreshCw, User, Activity } from 'lucide-react';
 
 import { AppConfig } from '../../types/config';
 import { formatCompactNumber } from '../../utils/format';
@@ -28,12 +28,21 @@ interface ProxyRequestLog {
     output_tokens?: number;
     account_email?: string;
     protocol?: string;  // "openai" | "anthropic" | "gemini"
+    param_adjustments?: string;  // 参数策略删除 / 截断的参数
+    client_name?: string;  // X-Client-Name 或从 User-Agent 推断的工具
+    user_agent?: string;
+    client_port?: number;
+    tokens_per_second?: number;  // 流式响应的输出速率
+    cache_read_tokens?: number;  // 命中提示词缓存的输入 token (已包含在 input_tokens 中)
+    cache_write_tokens?: number; // 写入提示词缓存的输入 token
 }
 
 interface ProxyStats {
     total_requests: number;
     success_count: number;
     error_count: number;
+    cache_hits?: number;
+    cache_misses?: number;
 }
 
 interface ProxyMonitorProps {
@@ -485,6 +494,11 @@ export const ProxyMonitor: React.FC<ProxyMonitorProps> = ({ className }) => {
                         <span className="text-red-500">{formatCompactNumber(stats.error_count)} ERR</span>
                     </div>
 
+                    {isTauri() && (
+                        <button onClick={() => invoke('open_request_inspector')} className="btn btn-sm btn-ghost text-gray-400" title={t('monitor.inspector.open')}>
+                            <Activity size={16} />
+                        </button>
+                    )}
                     <button onClick={() => loadData(currentPage, filter)} className="btn btn-sm btn-ghost text-gray-400" title={t('common.refresh') || 'Refresh'}>
                         <RefreshCw size={16} className={loading ? 'animate-spin' : ''} />
                     </button>
@@ -594,6 +608,12 @@ export const ProxyMonitor: React.FC<ProxyMonitorProps> = ({ className }) => {
                                         <div className="font-mono text-[11px] flex gap-2">
                                             <span className="text-blue-700 dark:text-blue-300 bg-blue-100 dark:bg-blue-900/40 px-2.5 py-1 rounded-md border border-blue-200 dark:border-blue-800/50 font-bold">In: {formatCompactNumber(selectedLog.input_tokens ?? 0)}</span>
                                             <span className="text-green-700 dark:text-green-300 bg-green-100 dark:bg-green-900/40 px-2.5 py-1 rounded-md border border-green-200 dark:border-green-800/50 font-bold">Out: {formatCompactNumber(selectedLog.output_tokens ?? 0)}</span>
+                                            {(selectedLog.cache_read_tokens ?? 0) + (selectedLog.cache_write_tokens ?? 0) > 0 && (
+                                                <span className="text-purple-700 dark:text-purple-300 bg-purple-100 dark:bg-purple-900/40 px-2.5 py-1 rounded-md border border-purple-200 dark:border-purple-800/50 font-bold">Cache: {formatCompactNumber(selectedLog.cache_read_tokens ?? 0)} / +{formatCompactNumber(selectedLog.cache_write_tokens ?? 0)}</span>
+                                            )}
+                                            {selectedLog.tokens_per_second != null && (
+                                                <span className="text-amber-700 dark:text-amber-300 bg-amber-100 dark:bg-amber-900/40 px-2.5 py-1 rounded-md border border-amber-200 dark:border-amber-800/50 font-bold">{selectedLog.tokens_per_second.toFixed(1)} tok/s</span>
+                                            )}
                                         </div>
                                     </div>
                                 </div>
@@ -623,6 +643,12 @@ export const ProxyMonitor: React.FC<ProxyMonitorProps> = ({ className }) => {
                                         )}
                                     </div>
                                 </div>
+                                {selectedLog.param_adjustments && (
+                                    <div className="mt-5 pt-5 border-t border-gray-200 dark:border-base-300">
+                                        <span className="block text-gray-500 dark:text-gray-400 uppercase font-black text-[10px] tracking-widest mb-2">{t('monitor.details.param_adjustments')}</span>
+                                        <span className="font-mono font-semibold text-amber-600 dark:text-amber-400 text-xs break-all">{selectedLog.param_adjustments}</span>
+                                    </div>
+                                )}
                                 {selectedLog.account_email && (
                                     <div className="mt-5 pt-5 border-t border-gray-200 dark:border-base-300">
                                         <span className="block text-gray-500 dark:text-gray-400 uppercase font-black text-[10px] tracking-widest mb-2">{t('monitor.details.account_used')}</span>
@@ -32,6 +32,8 @@ import { cn } from '../utils/cn';
 import { useProxyModels } from '../hooks/useProxyModels';
 import GroupedSelect, { SelectOption } from '../components/common/GroupedSelect';
 import { CliSyncCard } from '../components/proxy/CliSyncCard';
+import { ModelCompare } from '../components/proxy/ModelCompare';
+import { PromptTemplates } from '../components/proxy/PromptTemplates';
 import DebouncedSlider from '../components/common/DebouncedSlider';
 import { listAccounts } from '../services/accountService';
 import CircuitBreaker from '../components/settings/CircuitBreaker';
@@ -1277,6 +1279,12 @@ print(response.text)`;
                                 apiKey={appConfig.proxy.api_key}
                             />
 
+                            {/* 多模型并排对比 */}
+                            <ModelCompare running={status.running} />
+
+                            {/* 提示词模板 (仅桌面端) */}
+                            {isTauri() && <PromptTemplates />}
+
                             {/* z.ai (GLM) Dispatcher */}
                             <CollapsibleCard
                                 title={t('proxy.config.zai.title')}
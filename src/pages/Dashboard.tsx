@@ -6,6 +6,8 @@ import { isTauri } from '../utils/env';
 import { useAccountStore } from '../stores/useAccountStore';
 import CurrentAccount from '../components/dashboard/CurrentAccount';
 import BestAccounts from '../components/dashboard/BestAccounts';
+import LatencyChart from '../components/dashboard/LatencyChart';
+import KeyHealthCard from '../components/dashboard/KeyHealthCard';
 import AddAccountDialog from '../components/accounts/AddAccountDialog';
 import { save } from '@tauri-apps/plugin-dialog';
 import { request as invoke } from '../utils/request';
@@ -275,6 +277,12 @@ function Dashboard() {
                     />
                 </div>
 
+                {/* 反代延迟分位 */}
+                <LatencyChart />
+
+                {/* 上游 Key 有效性与余额 */}
+                <KeyHealthCard />
+
                 {/* 快速链接 */}
                 <div className="grid grid-cols-2 gap-3">
                     <button
@@ -0,0 +1,312 @@
+import React, { useEffect, useState } from 'react';
+import { listen } from '@tauri-apps/api/event';
+import { useTranslation } from 'react-i18next';
+import { request as invoke } from '../utils/request';
+import { isTauri } from '../utils/env';
+import { formatCompactNumber } from '../utils/format';
+
+interface InflightRequest {
+    id: string;
+    timestamp: number;
+    method: string;
+    url: string;
+    model?: string;
+    protocol?: string;
+}
+
+interface ProxyRequestLog {
+    id: string;
+    timestamp: number;
+    method: string;
+    url: string;
+    status: number;
+    duration: number;
+    model?: string;
+    mapped_model?: string;
+    error?: string;
+    request_body?: string;
+    response_body?: string;
+    input_tokens?: number;
+    output_tokens?: number;
+    account_email?: string;
+    protocol?: string;
+    param_adjustments?: string;
+}
+
+interface UpstreamCapture {
+    method: string;
+    body: string;
+}
+
+interface RequestInspection {
+    log: ProxyRequestLog;
+    upstream_request?: UpstreamCapture;
+}
+
+interface ShadowResult {
+    request_id?: string;
+    rule: string;
+    shadow_url: string;
+    shadow_model: string;
+    shadow_status?: number; // 请求失败时为空
+    shadow_duration: number;
+    shadow_response?: string;
+    error?: string;
+}
+
+interface DiffLine {
+    tag: 'equal' | 'delete' | 'insert';
+    text: string;
+}
+
+interface ReplayResult {
+    log_id: string;
+    url: string;
+    model?: string;
+    status: number;
+    original_status: number;
+    duration: number;
+    original: string;
+    replayed: string;
+    identical: boolean;
+    diff: DiffLine[];
+}
+
+const MAX_RECENT = 200;
+
+const formatBody = (body?: string) => {
+    if (!body) return '';
+    try {
+        return JSON.stringify(JSON.parse(body), null, 2);
+    } catch {
+        return body;
+    }
+};
+
+const BodyPanel: React.FC<{ title: string; body?: string; empty: string }> = ({ title, body, empty }) => (
+    <div className="flex flex-col min-h-0 flex-1">
+        <div className="text-[10px] font-bold uppercase text-gray-400 mb-1">{title}</div>
+        <pre className="flex-1 overflow-auto text-[11px] font-mono bg-gray-50 dark:bg-base-200 rounded p-2 whitespace-pre-wrap break-all">
+            {body ? formatBody(body) : <span className="text-gray-400">{empty}</span>}
+        </pre>
+    </div>
+);
+
+const DIFF_STYLE: Record<DiffLine['tag'], string> = {
+    equal: 'text-gray-500',
+    delete: 'bg-red-50 dark:bg-red-900/20 text-red-600',
+    insert: 'bg-green-50 dark:bg-green-900/20 text-green-600',
+};
+
+const DIFF_PREFIX: Record<DiffLine['tag'], string> = { equal: ' ', delete: '-', insert: '+' };
+
+const Inspector: React.FC = () => {
+    const { t } = useTranslation();
+    const [inflight, setInflight] = useState<InflightRequest[]>([]);
+    const [recent, setRecent] = useState<ProxyRequestLog[]>([]);
+    const [selected, setSelected] = useState<RequestInspection | null>(null);
+    const [loggingEnabled, setLoggingEnabled] = useState(true);
+    const [now, setNow] = useState(Date.now());
+    const [replayModel, setReplayModel] = useState('');
+    const [replayBaseUrl, setReplayBaseUrl] = useState('');
+    const [replayApiKey, setReplayApiKey] = useState('');
+    const [replaying, setReplaying] = useState(false);
+    const [replay, setReplay] = useState<ReplayResult | null>(null);
+    const [replayError, setReplayError] = useState<string | null>(null);
+    const [shadow, setShadow] = useState<ShadowResult | null>(null);
+
+    useEffect(() => {
+        if (!isTauri()) return;
+
+        invoke<InflightRequest[]>('get_proxy_inflight_requests').then(setInflight).catch(console.error);
+        invoke<ProxyRequestLog[]>('get_proxy_logs_paginated', { limit: 50, offset: 0 }).then(setRecent).catch(console.error);
+        invoke<any>('load_config')
+            .then(config => setLoggingEnabled(!!config?.proxy?.enable_logging))
+            .catch(console.error);
+
+        const unlistenPromises = [
+            listen<InflightRequest>('proxy://request-started', (event) => {
+                setInflight(prev => [...prev.filter(r => r.id !== event.payload.id), event.payload]);
+            }),
+            listen<ProxyRequestLog>('proxy://request', (event) => {
+                const log = event.payload;
+                setInflight(prev => prev.filter(r => r.id !== log.id));
+                setRecent(prev => [log, ...prev.filter(r => r.id !== log.id)].slice(0, MAX_RECENT));
+            }),
+            // 客户端取消且没有写入日志的请求
+            listen<string>('proxy://request-cancelled', (event) => {
+                setInflight(prev => prev.filter(r => r.id !== event.payload));
+            }),
+        ];
+
+        // 进行中请求的耗时按秒刷新
+        const timer = setInterval(() => setNow(Date.now()), 1000);
+
+        return () => {
+            clearInterval(timer);
+            Promise.all(unlistenPromises).then(unlisteners => unlisteners.forEach(unlisten => unlisten()));
+        };
+    }, []);
+
+    const openDetail = async (id: string) => {
+        try {
+            setSelected(await invoke<RequestInspection>('get_proxy_request_inspection', { logId: id }));
+            setReplay(null);
+            setReplayError(null);
+            // 影子流量结果只保存在内存中, 没有对应结果时不显示
+            const shadows = await invoke<ShadowResult[]>('get_shadow_results').catch(() => []);
+            setShadow(shadows.find(s => s.request_id === id) ?? null);
+        } catch (e) {
+            console.error('Failed to load request inspection', e);
+        }
+    };
+
+    const runReplay = async () => {
+        if (!selected) return;
+        setReplaying(true);
+        setReplayError(null);
+        try {
+            setReplay(await invoke<ReplayResult>('replay_proxy_request', {
+                logId: selected.log.id,
+                options: {
+                    model: replayModel.trim() || null,
+                    base_url: replayBaseUrl.trim() || null,
+                    api_key: replayApiKey.trim() || null,
+                },
+            }));
+        } catch (e) {
+            setReplayError(String(e));
+        } finally {
+            setReplaying(false);
+        }
+    };
+
+    return (
+        <div className="h-screen flex flex-col bg-white dark:bg-base-100 text-gray-700 dark:text-gray-300">
+            <div className="px-4 py-2 border-b border-gray-200 dark:border-base-300 font-bold text-sm">
+                {t('monitor.inspector.title')}
+            </div>
+            {!loggingEnabled && (
+                <div className="px-4 py-1 text-xs bg-yellow-50 dark:bg-yellow-900/20 text-yellow-700">
+                    {t('monitor.inspector.logging_paused')}
+                </div>
+            )}
+            <div className="flex flex-1 min-h-0">
+                <div className="w-[45%] flex flex-col border-r border-gray-200 dark:border-base-300 min-h-0">
+                    <div className="px-3 py-1 text-[10px] font-bold uppercase text-gray-400">
+                        {t('monitor.inspector.inflight')} ({inflight.length})
+                    </div>
+                    <div className="max-h-[35%] overflow-y-auto">
+                        <table className="table table-xs w-full font-mono">
+                            <tbody>
+                                {inflight.map(r => (
+                                    <tr key={r.id}>
+                                        <td><span className="loading loading-spinner loading-xs text-blue-500" /></td>
+                                        <td className="font-bold">{r.method}</td>
+                                        <td className="text-blue-600 truncate max-w-[180px]">{r.model || r.url}</td>
+                                        <td className="text-right">{Math.max(0, Math.round((now - r.timestamp) / 1000))}s</td>
+                                    </tr>
+                                ))}
+                                {inflight.length === 0 && (
+                                    <tr><td className="text-gray-400 text-center">{t('monitor.inspector.empty_inflight')}</td></tr>
+                                )}
+                            </tbody>
+                        </table>
+                    </div>
+                    <div className="px-3 py-1 text-[10px] font-bold uppercase text-gray-400 border-t border-gray-200 dark:border-base-300">
+                        {t('monitor.inspector.recent')}
+                    </div>
+                    <div className="flex-1 overflow-y-auto">
+                        <table className="table table-xs w-full font-mono">
+                            <tbody>
+                                {recent.map(log => (
+                                    <tr
+                                        key={log.id}
+                                        className={`cursor-pointer hover:bg-blue-50 dark:hover:bg-blue-900/20 ${selected?.log.id === log.id ? 'bg-blue-50 dark:bg-blue-900/20' : ''}`}
+                                        onClick={() => openDetail(log.id)}
+                                    >
+                                        <td>
+                                            <span className={`badge badge-xs text-white border-none ${log.status >= 200 && log.status < 400 ? 'badge-success' : 'badge-error'}`}>
+                                                {log.status}
+                                            </span>
+                                        </td>
+                                        <td className="font-bold">{log.method}</td>
+                                        <td className="text-blue-600 truncate max-w-[160px]">{log.model || log.url}</td>
+                                        <td className="text-right text-[9px]">
+                                            {log.input_tokens != null && <div>I: {formatCompactNumber(log.input_tokens)}</div>}
+                                            {log.output_tokens != null && <div>O: {formatCompactNumber(log.output_tokens)}</div>}
+                                        </td>
+                                        <td className="text-right">{log.duration}ms</td>
+                                    </tr>
+                                ))}
+                                {recent.length === 0 && (
+                                    <tr><td className="text-gray-400 text-center">{t('monitor.inspector.empty_recent')}</td></tr>
+                                )}
+                            </tbody>
+                        </table>
+                    </div>
+                </div>
+                <div className="flex-1 flex flex-col gap-2 p-3 min-h-0">
+                    {selected ? (
+                        <>
+                            <div className="text-xs font-mono flex flex-wrap gap-x-4">
+                                <span>{t('monitor.details.id')}: {selected.log.id}</span>
+                                <span>{t('monitor.details.model')}: {selected.log.model || '-'}</span>
+                                {selected.log.mapped_model && <span>{t('monitor.details.mapped_model')}: {selected.log.mapped_model}</span>}
+                                {selected.upstream_request && <span>{selected.upstream_request.method}</span>}
+                                {selected.log.param_adjustments && <span>{t('monitor.details.param_adjustments')}: {selected.log.param_adjustments}</span>}
+                            </div>
+                            <BodyPanel title={t('monitor.inspector.client_request')} body={selected.log.request_body} empty={t('monitor.details.payload_empty')} />
+                            <BodyPanel title={t('monitor.inspector.upstream_request')} body={selected.upstream_request?.body} empty={t('monitor.inspector.upstream_unavailable')} />
+                            <BodyPanel title={t('monitor.inspector.response')} body={selected.log.response_body || selected.log.error} empty={t('monitor.details.payload_empty')} />
+                            {shadow && (
+                                <BodyPanel
+                                    title={t('monitor.inspector.shadow', {
+                                        rule: shadow.rule,
+                                        model: shadow.shadow_model,
+                                        status: shadow.shadow_status ?? '-',
+                                        duration: shadow.shadow_duration,
+                                    })}
+                                    body={shadow.shadow_response || shadow.error}
+                                    empty={t('monitor.details.payload_empty')}
+                                />
+                            )}
+                            <div className="flex gap-2 items-center">
+                                <input className="input input-xs input-bordered flex-1 font-mono" placeholder={t('monitor.inspector.replay_model')} value={replayModel} onChange={e => setReplayModel(e.target.value)} />
+                                <input className="input input-xs input-bordered flex-1 font-mono" placeholder={t('monitor.inspector.replay_base_url')} value={replayBaseUrl} onChange={e => setReplayBaseUrl(e.target.value)} />
+                                {replayBaseUrl.trim() && (
+                                    <input type="password" className="input input-xs input-bordered w-40 font-mono" placeholder={t('monitor.inspector.replay_api_key')} value={replayApiKey} onChange={e => setReplayApiKey(e.target.value)} />
+                                )}
+                                <button className="btn btn-xs btn-primary" disabled={replaying || !selected.log.request_body} onClick={runReplay}>
+                                    {replaying && <span className="loading loading-spinner loading-xs" />}
+                                    {t('monitor.inspector.replay')}
+                                </button>
+                            </div>
+                            {replayError && <div className="text-xs text-red-500 font-mono">{replayError}</div>}
+                            {replay && (
+                                <div className="flex flex-col min-h-0 flex-1">
+                                    <div className="text-[10px] font-bold uppercase text-gray-400 mb-1">
+                                        {t('monitor.inspector.replay_diff', { original: replay.original_status, status: replay.status, duration: replay.duration })}
+                                    </div>
+                                    <pre className="flex-1 overflow-auto text-[11px] font-mono bg-gray-50 dark:bg-base-200 rounded p-2 whitespace-pre-wrap break-all">
+                                        {replay.identical
+                                            ? <span className="text-gray-400">{t('monitor.inspector.replay_identical')}</span>
+                                            : replay.diff.map((line, i) => (
+                                                <div key={i} className={DIFF_STYLE[line.tag]}>{DIFF_PREFIX[line.tag]} {line.text}</div>
+                                            ))}
+                                    </pre>
+                                </div>
+                            )}
+                        </>
+                    ) : (
+                        <div className="flex-1 flex items-center justify-center text-sm text-gray-400">
+                            {t('monitor.inspector.select')}
+                        </div>
+                    )}
+                </div>
+            </div>
+        </div>
+    );
+};
+
+export default Inspector;
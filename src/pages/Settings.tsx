@@ -1,9 +1,10 @@
 import { useState, useEffect } from 'react';
 import { Save, ExternalLink, Sparkles, RefreshCw, User } from 'lucide-react';
 import { request as invoke } from '../utils/request';
+import { validateConfig } from '../services/configService';
 import { open } from '@tauri-apps/plugin-dialog';
 import { useConfigStore } from '../stores/useConfigStore';
-import { AppConfig } from '../types/config';
+import { AppConfig, DesktopNotificationConfig } from '../types/config';
 import ModalDialog from '../components/common/ModalDialog';
 import { showToast } from '../components/common/ToastContainer';
 import QuotaProtection from '../components/settings/QuotaProtection';
@@ -59,6 +60,17 @@ function Settings() {
         }
     });
 
+    // 桌面通知设置 (旧配置中没有该字段时全部开启)
+    const notifications: DesktopNotificationConfig = formData.desktop_notifications ?? {
+        enabled: true,
+        upstream_outage: true,
+        quota_exhausted: true,
+        cert_expiry: true,
+        service_restarted: true,
+    };
+    const setNotifications = (patch: Partial<DesktopNotificationConfig>) =>
+        setFormData({ ...formData, desktop_notifications: { ...notifications, ...patch } });
+
     // Dialog state
     // Dialog state
     const [isClearLogsOpen, setIsClearLogsOpen] = useState(false);
@@ -110,9 +122,23 @@ function Settings() {
                 return;
             }
 
+            // 保存前校验整份配置，错误带字段路径
+            const issues = await validateConfig(formData);
+            const errors = issues.filter(issue => issue.severity === 'error');
+            const warnings = issues.filter(issue => issue.severity === 'warning');
+            if (errors.length > 0) {
+                const details = errors.slice(0, 3).map(issue => `${issue.path}: ${issue.message}`).join('\n');
+                showToast(`${t('settings.validation.errors', { count: errors.length })}\n${details}`, 'error');
+                return;
+            }
+
             // 强制开启后台自动刷新，确保联动逻辑生效
             await saveConfig({ ...formData, auto_refresh: true });
             showToast(t('common.saved'), 'success');
+            if (warnings.length > 0) {
+                const details = warnings.slice(0, 3).map(issue => `${issue.path}: ${issue.message}`).join('\n');
+                showToast(`${t('settings.validation.warnings', { count: warnings.length })}\n${details}`, 'warning');
+            }
 
             // 如果修改了代理配置，提示用户需要重启
             if (proxyEnabled && proxyUrl) {
@@ -408,6 +434,33 @@ function Settings() {
                                     </div>
                                 )}
                             </>
+
+                            {/* 桌面通知 */}
+                            <div className="p-4 bg-gray-50 dark:bg-base-200 rounded-lg border border-gray-100 dark:border-base-300 space-y-3">
+                                <div className="flex items-center justify-between">
+                                    <div>
+                                        <div className="font-medium text-gray-900 dark:text-base-content">{t('settings.general.notifications')}</div>
+                                        <p className="text-sm text-gray-600 dark:text-gray-400 mt-1">{t('settings.general.notifications_desc')}</p>
+                                    </div>
+                                    <input
+                                        type="checkbox"
+                                        className="toggle toggle-primary"
+                                        checked={notifications.enabled}
+                                        onChange={(e) => setNotifications({ enabled: e.target.checked })}
+                                    />
+                                </div>
+                                {notifications.enabled && (['upstream_outage', 'quota_exhausted', 'cert_expiry', 'service_restarted'] as const).map((kind) => (
+                                    <label key={kind} className="flex items-center gap-3 text-sm text-gray-700 dark:text-gray-300 cursor-pointer">
+                                        <input
+                                            type="checkbox"
+                                            className="checkbox checkbox-sm"
+                                            checked={notifications[kind]}
+                                            onChange={(e) => setNotifications({ [kind]: e.target.checked })}
+                                        />
+                                        {t(`settings.general.notify_${kind}`)}
+                                    </label>
+                                ))}
+                            </div>
                         </div>
                     )}
 
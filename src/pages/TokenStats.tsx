@@ -38,6 +38,14 @@ interface AccountTrendPoint {
     account_data: Record<string, number>;
 }
 
+interface UpstreamUsage {
+    key: string;
+    request_count: number;
+    completion_tokens: number;
+    avg_latency_ms: number;
+    avg_tokens_per_second: number | null; // 没有流式请求时为 null
+}
+
 interface TokenStatsSummary {
     total_input_tokens: number;
     total_output_tokens: number;
@@ -83,6 +91,7 @@ const TokenStats: React.FC = () => {
     const [allModels, setAllModels] = useState<string[]>([]);
     const [allAccounts, setAllAccounts] = useState<string[]>([]);
     const [summary, setSummary] = useState<TokenStatsSummary | null>(null);
+    const [upstreamData, setUpstreamData] = useState<UpstreamUsage[]>([]);
     const [loading, setLoading] = useState(true);
 
     const fetchData = async () => {
@@ -158,6 +167,11 @@ const TokenStats: React.FC = () => {
             setAccountData(accounts);
             setModelData(models_stats);
             setSummary(summaryData);
+
+            // 按上游对比流式输出速率 (用量库, 与 Token 统计分开失败)
+            invoke<UpstreamUsage[]>('get_usage_by_upstream', { days: Math.ceil(hours / 24) })
+                .then(setUpstreamData)
+                .catch(() => setUpstreamData([]));
         } catch (error) {
             console.error('Failed to fetch token stats:', error);
         } finally {
@@ -770,6 +784,62 @@ const TokenStats: React.FC = () => {
                         </div>
                     )
                 }
+
+                {
+                    upstreamData.length > 0 && (
+                        <div className="bg-white dark:bg-gray-800 rounded-xl p-6 shadow-sm border border-gray-200 dark:border-gray-700">
+                            <h2 className="text-lg font-semibold text-gray-800 dark:text-white mb-4 flex items-center gap-2">
+                                <Zap className="w-5 h-5 text-amber-500" />
+                                {t('token_stats.upstream_throughput', '上游吞吐对比')}
+                            </h2>
+                            <div className="overflow-x-auto">
+                                <table className="w-full text-sm">
+                                    <thead>
+                                        <tr className="border-b border-gray-200 dark:border-gray-700">
+                                            <th className="text-left py-3 px-4 font-medium text-gray-500 dark:text-gray-400">
+                                                {t('token_stats.upstream', '上游')}
+                                            </th>
+                                            <th className="text-right py-3 px-4 font-medium text-gray-500 dark:text-gray-400">
+                                                {t('token_stats.requests', '请求数')}
+                                            </th>
+                                            <th className="text-right py-3 px-4 font-medium text-gray-500 dark:text-gray-400">
+                                                {t('token_stats.output', '输出')}
+                                            </th>
+                                            <th className="text-right py-3 px-4 font-medium text-gray-500 dark:text-gray-400">
+                                                {t('token_stats.avg_latency', '平均耗时')}
+                                            </th>
+                                            <th className="text-right py-3 px-4 font-medium text-gray-500 dark:text-gray-400">
+                                                {t('token_stats.tokens_per_second', '流式速率')}
+                                            </th>
+                                        </tr>
+                                    </thead>
+                                    <tbody>
+                                        {upstreamData.map((upstream) => (
+                                            <tr
+                                                key={upstream.key}
+                                                className="border-b border-gray-100 dark:border-gray-700/50 hover:bg-gray-50 dark:hover:bg-gray-700/30"
+                                            >
+                                                <td className="py-3 px-4 text-gray-800 dark:text-white font-medium">{upstream.key}</td>
+                                                <td className="py-3 px-4 text-right text-gray-600 dark:text-gray-300">
+                                                    {upstream.request_count.toLocaleString()}
+                                                </td>
+                                                <td className="py-3 px-4 text-right text-purple-600">
+                                                    {formatNumber(upstream.completion_tokens)}
+                                                </td>
+                                                <td className="py-3 px-4 text-right text-gray-600 dark:text-gray-300">
+                                                    {(upstream.avg_latency_ms / 1000).toFixed(1)}s
+                                                </td>
+                                                <td className="py-3 px-4 text-right font-semibold text-gray-800 dark:text-white">
+                                                    {upstream.avg_tokens_per_second != null ? `${upstream.avg_tokens_per_second.toFixed(1)} tok/s` : '-'}
+                                                </td>
+                                            </tr>
+                                        ))}
+                                    </tbody>
+                                </table>
+                            </div>
+                        </div>
+                    )
+                }
             </div>
         </div>
     );
@@ -1,5 +1,5 @@
 import { request as invoke } from '../utils/request';
-import { AppConfig } from '../types/config';
+import { AccessControlConfig, AccessControlOverview, AppConfig, AuditEntry, AuditQuery, ConfigIssue, SecretStorageStatus, WebhookTarget } from '../types/config';
 
 export async function loadConfig(): Promise<AppConfig> {
     return await invoke('load_config');
@@ -8,3 +8,36 @@ export async function loadConfig(): Promise<AppConfig> {
 export async function saveConfig(config: AppConfig): Promise<void> {
     return await invoke('save_config', { config });
 }
+
+export async function validateConfig(config: AppConfig): Promise<ConfigIssue[]> {
+    return await invoke('validate_config', { config });
+}
+
+export async function getSecretStorageStatus(): Promise<SecretStorageStatus> {
+    return await invoke('get_secret_storage_status');
+}
+
+export async function setSecret(name: string, value: string): Promise<void> {
+    return await invoke('set_secret', { name, value });
+}
+
+export async function rotateSecretKey(): Promise<number> {
+    return await invoke('rotate_secret_key');
+}
+
+export async function getAuditLog(query: AuditQuery = {}): Promise<AuditEntry[]> {
+    return await invoke('get_audit_log', { ...query });
+}
+
+export async function getAccessControl(): Promise<AccessControlOverview> {
+    return await invoke('get_access_control');
+}
+
+// listener 为空时修改主监听; rules 为 null 时额外监听沿用主监听的规则
+export async function updateAccessControl(listener: string | null, rules: AccessControlConfig | null): Promise<void> {
+    return await invoke('update_access_control', { listener, rules });
+}
+
+export async function testWebhook(target: WebhookTarget): Promise<void> {
+    return await invoke('test_webhook', { target });
+}
@@ -1,6 +1,303 @@
 export interface UpstreamProxyConfig {
     enabled: boolean;
+    url: string;                // http:// / https:// / socks5://
+    username?: string;
+    password?: string;
+}
+
+export interface UpstreamTlsConfig {
+    ca_cert_path?: string;      // 额外信任的根证书 (PEM)
+    client_cert_path?: string;  // mTLS 客户端证书 (PEM)
+    client_key_path?: string;   // mTLS 客户端私钥 (PKCS#8 PEM)
+    accept_invalid_certs: boolean; // 跳过证书校验, 仅用于实验环境
+}
+
+export interface ProviderTlsRule {
+    provider: string;           // 与 ProviderProxyRule 相同, 支持 * 通配
+    tls: UpstreamTlsConfig;
+}
+
+export interface ListenerConfig {
+    enabled: boolean;
+    name: string;
+    host: string;               // 127.0.0.1 / 0.0.0.0 / 局域网 IP
+    port: number;
+    require_auth: boolean;      // 强制校验 API Key, 不受 auth_mode 影响
+    allow_admin: boolean;       // 开放管理接口 (/api)
+    tls: boolean;               // 使用 listener_tls 中的证书
+    access_control?: AccessControlConfig | null; // 未设置时使用 proxy.access_control
+}
+
+// CIDR 或单个地址; deny 优先, allow 非空时只放行其中的地址 (本机不受 allow 限制)
+export interface AccessRules {
+    allow: string[];
+    deny: string[];
+}
+
+export interface AccessControlConfig {
+    admin: AccessRules;         // 管理接口 (/api、OAuth 回调)
+    inference: AccessRules;     // 推理接口
+}
+
+export interface AccessControlOverview {
+    main: AccessControlConfig;
+    listeners: { listener: string; access_control?: AccessControlConfig | null }[];
+}
+
+export type WebhookEventKind =
+    | 'upstream_down'
+    | 'upstream_recovered'
+    | 'spend_threshold'
+    | 'rate_limited'
+    | 'service_restarted';
+
+export interface WebhookTarget {
+    name: string;
     url: string;
+    enabled: boolean;
+    format: 'json' | 'slack' | 'discord';
+    events: WebhookEventKind[]; // 为空表示订阅全部事件
+    secret: string;             // JSON 格式时的 HMAC-SHA256 签名密钥 (X-Webhook-Signature)
+}
+
+export interface WebhookConfig {
+    enabled: boolean;
+    targets: WebhookTarget[];
+    max_retries: number;
+    cooldown_secs: number;      // 同一事件的最小通知间隔
+    spend_warning_ratio: number; // 月度花费达到上限的该比例时预警, 0 表示只在达到上限时通知
+}
+
+export interface SmartRouteCandidate {
+    upstream: string;  // 与健康检查、用量统计中的上游名称一致
+    model: string;     // 发往该上游的模型名
+}
+
+export interface SmartRoute {
+    alias: string;
+    candidates: SmartRouteCandidate[]; // 同分时按列表顺序
+    enabled: boolean;
+}
+
+export interface SmartRoutingConfig {
+    enabled: boolean;
+    routes: SmartRoute[];
+    weights: { latency: number; error_rate: number; cost: number };
+    window_mins: number;  // 用量数据的统计窗口
+    min_samples: number;  // 样本不足时改用健康检查结果
+}
+
+export interface ShadowRule {
+    name: string;
+    models: string[];         // 通配符, 为空时匹配全部
+    percent: number;          // 0-100
+    target_model?: string;
+    base_url?: string;        // 为空时发往本地反代
+    api_key?: string;
+    enabled: boolean;
+}
+
+export interface ShadowConfig {
+    enabled: boolean;
+    rules: ShadowRule[];
+    max_results: number;
+    timeout_secs: number;
+}
+
+export interface HedgingRule {
+    name: string;
+    models: string[];         // 通配符, 为空时匹配全部
+    backup_model: string;     // 按本地反代的正常路由分发
+    threshold_ms: number;     // 主请求首字节等待时间
+    enabled: boolean;
+}
+
+export interface HedgingConfig {
+    enabled: boolean;
+    rules: HedgingRule[];
+}
+
+export interface Council {
+    name: string;            // 客户端请求的模型名
+    members: string[];       // 最多 6 个
+    judge: string;
+    strategy: 'merge' | 'select';
+    judge_prompt?: string;
+    enabled: boolean;
+}
+
+export interface CouncilConfig {
+    enabled: boolean;
+    councils: Council[];
+}
+
+export interface StructuredOutputConfig {
+    enabled: boolean;
+    models: string[];        // 支持 * 通配, 为空时处理所有 json_schema 请求
+    max_retries: number;     // 0-5
+    repair_json_object: boolean; // 修复 json_object 请求中被截断或格式错误的回复
+}
+
+export interface ToolEmulationConfig {
+    enabled: boolean;
+    models: string[];        // 需要模拟的模型, 支持 * 通配
+}
+
+export interface BatchConfig {
+    enabled: boolean;
+    concurrency: number;          // 同时执行的请求数 (1-64)
+    requests_per_minute: number;  // 0 表示不限制
+    max_requests: number;         // 单个批次的最大请求数
+}
+
+export interface FilesConfig {
+    enabled: boolean;
+    max_file_mb: number;          // 单个文件的大小上限
+}
+
+export interface AssistantsConfig {
+    enabled: boolean;
+}
+
+export interface ModerationRule {
+    category: string;          // OpenAI 审核类别, 例如 violence、self-harm/intent
+    pattern: string;
+    match_type?: 'keyword' | 'regex';
+    score?: number;            // 0-1, 默认 1
+    enabled?: boolean;
+}
+
+export interface ModerationConfig {
+    enabled: boolean;          // 没有审核上游时使用本地分类器
+    builtin_rules?: boolean;
+    threshold?: number;        // 默认 0.5
+    rules?: ModerationRule[];
+}
+
+// 事件 `app://port-fallback`
+export interface PortFallback {
+    service: string;            // proxy / llama-server / 外部服务 id
+    requested: number;
+    actual: number;
+    conflict: string;           // 占用端口的进程
+}
+
+// 配置云同步: 本地加密后经 WebDAV / S3 / Gist 同步
+export interface ConfigSyncConfig {
+    enabled: boolean;
+    backend: 'web_dav' | 's3' | 'gist';
+    url: string;                 // WebDAV 文件地址或 S3 服务地址
+    username: string;
+    password: string;
+    bucket: string;
+    object_key: string;
+    region: string;
+    access_key_id: string;
+    secret_access_key: string;
+    token: string;               // GitHub Token
+    gist_id: string;
+    passphrase: string;          // 只保存在本机
+    interval_minutes: number;    // 0: 只手动同步
+}
+
+export type ConfigSyncAction = 'up_to_date' | 'push' | 'pull' | 'conflict';
+
+export interface ConfigSyncState {
+    remote_updated_at: number | null;
+    local_hash: string | null;
+    last_sync_at: number | null;
+    last_device: string | null;
+    last_error: string | null;
+    gist_id: string | null;
+}
+
+// sync_config_now 的结果与 config-sync://synced 事件
+export interface ConfigSyncReport {
+    action: ConfigSyncAction;
+    remote_updated_at: number | null;
+    remote_device: string | null;
+    missing_secrets: string[];
+}
+
+export interface KeyCheckConfig {
+    enabled: boolean;
+    interval_minutes: number;       // 默认 360
+    low_balance_threshold: number;  // 上游货币, 默认 1
+    expiry_warning_days: number;    // 默认 7
+}
+
+export type KeyStatus = 'valid' | 'invalid' | 'low_balance' | 'expiring' | 'unreachable';
+
+export interface KeyCheckResult {
+    upstream: string;
+    key_hint: string;
+    status: KeyStatus;
+    http_status: number | null;
+    balance: { remaining: number | null; currency: string; expires_at: number | null } | null; // 仅 OpenRouter / DeepSeek
+    error: string | null;
+    checked_at: number;
+}
+
+// 连续 3 次鉴权 / 额度错误后停用的 z.ai Key, 恢复前不再分配请求
+export interface DisabledUpstreamKey {
+    key_id: string;
+    key_hint: string;
+    reason: 'unauthorized' | 'quota_exhausted';
+    disabled_at: number;
+}
+
+export interface LlamaServerConfig {
+    enabled: boolean;
+    binary_path: string;        // llama-server 可执行文件
+    model_path: string;         // GGUF 模型文件
+    alias?: string;             // 为空时使用模型文件名
+    port?: number;              // 默认 8091, 只监听 127.0.0.1
+    ctx_size?: number;          // 默认 4096
+    gpu_layers?: number | null;
+    threads?: number | null;
+    extra_args?: string[];
+    max_restarts?: number;      // 默认 5
+    restart_delay_secs?: number; // 默认 3, 每次加倍
+}
+
+export interface LlamaServerStatus {
+    running: boolean;
+    ready: boolean;
+    pid: number | null;
+    base_url: string | null;
+    model: string | null;
+    restarts: number;
+    last_error: string | null;
+}
+
+export interface MdnsConfig {
+    enabled: boolean;           // 仅在允许局域网访问时生效, 重启服务后生效
+    service_name: string;       // 留空时使用 "AIO Launcher (<主机名>)"
+}
+
+export interface ListenerTlsConfig {
+    enabled: boolean;           // 重启服务后生效
+    cert_path: string;
+    key_path: string;
+}
+
+export interface ProviderProxyRule {
+    provider: string;           // google / zai / azure_openai / bedrock / compat:<名称> / embeddings / images / audio, 支持 * 通配
+    proxy: UpstreamProxyConfig; // enabled = false 表示该上游直连
+}
+
+export interface UpstreamProxyTestResult {
+    url: string;
+    status: number;
+    latency_ms: number;
+}
+
+export interface SupervisorStatus {
+    restarts: number;               // 自动重启次数
+    consecutive_failures: number;
+    last_reason?: string;
+    last_failure_at?: number;       // Unix 秒
+    restarting: boolean;
 }
 
 export interface ProxyConfig {
@@ -8,17 +305,441 @@ export interface ProxyConfig {
     allow_lan_access?: boolean;
     auth_mode?: 'off' | 'strict' | 'all_except_health' | 'auto';
     port: number;
+    port_fallback?: boolean;    // 端口被占用时改用后续空闲端口 (事件 app://port-fallback)
     api_key: string;
     admin_password?: string;
+    admin_tokens?: AdminToken[]; // 额外的管理接口 Token, 按角色限制权限
     auto_start: boolean;
     custom_mapping?: Record<string, string>;
     request_timeout: number;
+    drain_timeout_secs?: number; // 停止服务时等待进行中请求的最长时间 (秒)
     enable_logging: boolean;
     debug_logging?: DebugLoggingConfig;
     upstream_proxy: UpstreamProxyConfig;
+    provider_proxies?: ProviderProxyRule[];
+    provider_tls?: ProviderTlsRule[];
+    listener_tls?: ListenerTlsConfig;
+    listeners?: ListenerConfig[];
+    mdns?: MdnsConfig;
+    secret_storage?: SecretStorageMode;
+    jwt_auth?: JwtAuthConfig;
+    access_control?: AccessControlConfig;
+    webhooks?: WebhookConfig;
+    smart_routing?: SmartRoutingConfig;
+    shadow?: ShadowConfig;
+    hedging?: HedgingConfig;
+    council?: CouncilConfig;
+    structured_output?: StructuredOutputConfig;
+    tool_emulation?: ToolEmulationConfig;
+    batches?: BatchConfig;
+    files?: FilesConfig;
+    assistants?: AssistantsConfig;
+    moderation?: ModerationConfig;
+    rerank?: RerankConfig;
+    llama_server?: LlamaServerConfig;
+    config_sync?: ConfigSyncConfig;
+    key_check?: KeyCheckConfig;
     zai?: ZaiConfig;
     scheduling?: StickySessionConfig;
     experimental?: ExperimentalConfig;
+    client_rate_limit?: ClientRateLimitConfig;
+    body_logging?: BodyLoggingConfig;
+    body_limits?: BodyLimitConfig;
+    compression?: CompressionConfig;
+    upstream_routing?: UpstreamRoutingConfig;
+    connection_pool?: ConnectionPoolConfig;
+    health_check?: HealthCheckConfig;
+    ollama?: OllamaConfig;
+    azure_openai?: AzureOpenAIConfig;
+    bedrock?: BedrockConfig;
+    compatible_upstreams?: CompatibleUpstreamsConfig;
+    param_policy?: ParamPolicyConfig;
+    content_filter?: ContentFilterConfig;
+    pii_redaction?: PiiRedactionConfig;
+    system_prompt?: SystemPromptConfig;
+    context_window?: ContextWindowConfig;
+    max_tokens?: MaxTokensConfig;
+    model_catalog?: ModelCatalogConfig;
+    model_aliases?: ModelAliasRule[];
+    stream_transform?: StreamTransformConfig;
+    response_cache?: ResponseCacheConfig;
+    conversation_memory?: ConversationMemoryConfig;
+    realtime?: RealtimeConfig;
+    embeddings?: EmbeddingsConfig;
+    images?: ImagesConfig;
+    audio?: AudioConfig;
+    pricing?: PricingConfig;
+    concurrency?: ConcurrencyConfig;
+}
+
+export interface UpstreamConcurrencyLimit {
+    max_concurrent: number;
+    max_queue: number;
+}
+
+export interface ConcurrencyConfig {
+    enabled: boolean;
+    max_concurrent: number;         // 每个上游的默认最大并发, 默认 16
+    max_queue: number;              // 每个上游的默认最大排队数, 默认 64 (队列满返回 503)
+    queue_timeout_secs: number;     // 排队超时, 默认 60 秒
+    overrides?: Record<string, UpstreamConcurrencyLimit>; // google / zai / embeddings / images / audio
+}
+
+export interface QueueStatus {
+    upstream: string;
+    max_concurrent: number;
+    max_queue: number;
+    in_flight: number;
+    queued: number;
+    avg_wait_ms: number;
+    last_wait_ms: number;
+}
+
+export interface ModelPrice {
+    pattern: string;                // 模型名, 支持 * 通配, 按顺序匹配
+    input_per_million: number;      // 美元 / 百万输入 token
+    output_per_million: number;     // 美元 / 百万输出 token
+    cache_read_per_million?: number;  // 命中提示词缓存的输入价格, 未设置时按输入价格
+    cache_write_per_million?: number; // 写入提示词缓存的输入价格, 未设置时按输入价格
+}
+
+export interface PricingConfig {
+    models: ModelPrice[];
+}
+
+export interface AudioConfig {
+    enabled: boolean;
+    base_url: string;               // 默认 https://api.openai.com
+    api_key: string;
+    transcription_models: string[]; // 透传的转录模型, 支持 * 通配
+}
+
+export type ImageBackendKind = 'openai' | 'stability';
+
+export interface ImageBackendConfig {
+    pattern: string;                // 模型名, 支持 * 通配
+    provider: ImageBackendKind;
+    base_url?: string;              // 为空时使用官方地址
+    api_key: string;
+    model?: string | null;          // 上游模型名 / Stability engine id
+    enabled?: boolean;
+}
+
+export interface ImagesConfig {
+    backends: ImageBackendConfig[];
+    save_enabled: boolean;
+    save_dir: string;               // 为空时使用数据目录下的 generated_images
+}
+
+export type EmbeddingProviderKind = 'openai' | 'gemini' | 'voyage' | 'cohere';
+
+export interface EmbeddingProviderConfig {
+    pattern: string;                // 模型名, 支持 * 通配
+    provider: EmbeddingProviderKind;
+    base_url?: string;              // 为空时使用官方地址
+    api_key: string;
+    model?: string | null;          // 上游模型名
+    enabled?: boolean;
+}
+
+export interface EmbeddingsConfig {
+    enabled: boolean;
+    max_batch_size: number;         // 默认 96
+    providers: EmbeddingProviderConfig[];
+}
+
+export type RerankProviderKind = 'cohere' | 'jina' | 'voyage';
+
+export interface RerankProviderConfig {
+    pattern: string;                // 模型名, 支持 * 通配
+    provider: RerankProviderKind;
+    base_url?: string;              // 为空时使用官方地址
+    api_key: string;
+    model?: string | null;          // 上游模型名
+    enabled?: boolean;
+}
+
+export interface RerankConfig {
+    enabled: boolean;
+    providers: RerankProviderConfig[];
+    embedding_model?: string;       // 没有匹配的上游时用于余弦相似度排序的向量模型
+}
+
+export interface RealtimeConfig {
+    enabled: boolean;
+    upstream_url: string;           // 默认 wss://api.openai.com/v1/realtime
+    api_key: string;
+    headers?: Record<string, string>;
+}
+
+export interface ResponseCacheConfig {
+    enabled: boolean;
+    ttl_secs: number;
+    max_entries: number;
+    max_body_kb: number;
+}
+
+export interface ConversationMemoryConfig {
+    enabled: boolean;
+    header: string;                 // 会话 ID 请求头, 默认 "X-Session-Id"
+    ttl_secs: number;               // 最后一次更新后的保留时间
+    max_sessions: number;
+    max_messages: number;           // 每个会话最多保存的消息数
+}
+
+export interface StreamTransformRule {
+    path: string;                   // 支持 "*" 通配, 例如 "/v1/chat/completions"
+    strip_fields?: string[];        // 例如 ["reasoning_content"]
+    model?: string | null;          // "{request}" 表示客户端请求的模型名
+    inject_usage?: boolean;
+    reasoning?: 'passthrough' | 'normalize' | 'strip';  // 流式与非流式响应均生效
+    output?: OutputRules;           // 流式与非流式响应均生效
+}
+
+export interface OutputRules {
+    stop?: string[];                // 额外的停止序列, 命中后流式响应随即结束
+    strip_code_fences?: boolean;    // 去掉包裹整个回复的 ``` 代码块
+    trim_prefix?: string | null;
+    trim_suffix?: string | null;
+    replacements?: { pattern: string; replacement: string }[];  // 正则, 逐行替换
+}
+
+export interface StreamTransformConfig {
+    enabled: boolean;
+    rules: StreamTransformRule[];
+}
+
+export interface ModelAliasRule {
+    pattern: string;
+    target: string;                 // regex 规则可引用捕获组 $1 / ${name}
+    match_type?: 'exact' | 'glob' | 'regex';
+    upstream?: 'google' | 'zai' | null;
+    enabled?: boolean;
+    fallbacks?: string[];           // 备用模型链, 按原样转发
+    fallback_on?: FallbackTrigger[];
+}
+
+export type FallbackTrigger = 'content_filter' | 'context_length' | 'rate_limit';
+
+export interface UpstreamRoutingConfig {
+    endpoints: string[];
+    model_endpoints?: Record<string, string[]>; // 支持 "gemini-*" 前缀通配
+    max_attempts: number;         // 0 = 每个端点各尝试一次
+    backoff_base_ms: number;
+    backoff_max_ms: number;
+    attempt_timeout_secs: number; // 0 = 不单独限制
+    connect_timeout_secs?: number;
+    first_byte_timeout_secs?: number;   // 流式响应首个数据块超时
+    stream_idle_timeout_secs?: number;  // 流式数据块间隔超时, 0 = 不限制
+    failure_threshold: number;
+    unhealthy_cooldown_secs: number;
+    circuit_breaker_enabled?: boolean;  // 熔断: 冷却期内跳过故障端点, 全部熔断时快速失败
+}
+
+export interface ConnectionPoolConfig {
+    max_idle_per_host: number;    // 0 = 不复用连接
+    idle_timeout_secs: number;
+    tcp_keepalive_secs: number;   // 0 = 关闭
+    http2: boolean;               // 关闭时只使用 HTTP/1.1
+    tcp_nodelay: boolean;
+}
+
+export interface HealthCheckConfig {
+    enabled: boolean;
+    interval_secs: number;
+    timeout_secs: number;
+    history_size: number;
+    fail_fast: boolean;  // 所有上游均不可用时直接返回 503
+}
+
+export interface OllamaConfig {
+    enabled: boolean;
+    base_url: string;
+    model_prefix: string;  // 例如 "ollama/llama3.1" 转发为 "llama3.1"
+    models: string[];      // 额外原样转发的模型 (支持 * 通配)
+    num_ctx: number;       // 0 = 使用模型默认值
+    keep_alive: string;
+}
+
+export interface AzureDeployment {
+    model: string;         // 支持 * 通配
+    deployment: string;    // 为空时与模型名相同
+    api_version?: string;
+    chat_only?: boolean;   // 只提供 Chat Completions, 传统 Completions 请求转换后转发
+}
+
+export interface AzureOpenAIConfig {
+    enabled: boolean;
+    endpoint: string;      // https://{resource}.openai.azure.com
+    api_key: string;
+    api_version: string;
+    deployments: AzureDeployment[];
+}
+
+export interface BedrockModel {
+    model: string;      // 支持 * 通配
+    model_id: string;   // 为空时与模型名相同
+}
+
+export interface BedrockConfig {
+    enabled: boolean;
+    region: string;
+    access_key_id: string;      // 为空时读取 AWS_* 环境变量
+    secret_access_key: string;
+    session_token: string;
+    models: BedrockModel[];
+}
+
+export interface CompatibleUpstream {
+    name: string;
+    preset?: string | null;   // mistral / groq / together / openrouter
+    base_url: string;         // 为空时使用预设地址
+    api_key: string;
+    models: string[];         // 为空时使用预设的已知模型
+    chat_only?: boolean;      // 只提供 Chat Completions; 预设上游自动判断
+    enabled: boolean;
+}
+
+export interface CompatibleUpstreamsConfig {
+    upstreams: CompatibleUpstream[];
+}
+
+export interface ProviderPreset {
+    id: string;
+    name: string;
+    base_url: string;
+    auth_header: string;
+    auth_scheme: string;      // 为空时直接发送原始 Key
+    models: string[];
+    unsupported_params: string[];
+    clamped_params: [string, number, number][];
+    renamed_params: [string, string][];
+    extra_headers: [string, string][];
+}
+
+export interface ParamClamp {
+    param: string;
+    min?: number | null;
+    max?: number | null;
+}
+
+export interface ParamPolicyRule {
+    upstream: string;   // 支持 * 通配, 例如 google / bedrock / groq
+    drop: string[];
+    clamp: ParamClamp[];
+}
+
+export interface ParamPolicyConfig {
+    enabled: boolean;
+    rules: ParamPolicyRule[];
+}
+
+export interface ContentFilterRule {
+    name: string;
+    pattern: string;
+    match_type: 'keyword' | 'regex';        // keyword 不区分大小写
+    action: 'block' | 'redact' | 'log';
+    scope: 'request' | 'response' | 'both';
+    paths: string[];                         // 为空时作用于所有路由, 支持 * 通配
+    enabled: boolean;
+}
+
+export interface ContentFilterConfig {
+    enabled: boolean;
+    redaction_text: string;
+    rules: ContentFilterRule[];
+}
+
+export interface PiiRedactionConfig {
+    enabled: boolean;
+    emails: boolean;
+    phone_numbers: boolean;
+    api_keys: boolean;
+    credit_cards: boolean;                   // 仅匹配通过 Luhn 校验的号码
+    restore_response: boolean;               // 在响应中还原占位符
+    skip_models: string[];                   // 支持 * 通配, 本地 Ollama 模型始终跳过
+}
+
+export interface SystemPromptRule {
+    name: string;
+    prompt: string;
+    mode: 'prefix' | 'suffix' | 'replace';
+    paths: string[];                         // 支持 * 通配, 为空时作用于所有路由
+    virtual_keys: string[];                  // 虚拟 Key 名称或 id, 为空时不限制
+    enabled: boolean;
+}
+
+export interface SystemPromptConfig {
+    enabled: boolean;
+    rules: SystemPromptRule[];               // 按顺序应用所有命中的规则
+}
+
+export interface ContextWindowRule {
+    model: string;                           // 支持 * 通配
+    context_tokens: number;
+}
+
+export interface ContextWindowConfig {
+    enabled: boolean;
+    strategy: 'error' | 'drop_oldest' | 'summarize';
+    reserve_output_tokens: number;           // 请求未设置 max_tokens 时为输出预留
+    models: ContextWindowRule[];             // 优先于内置的上下文大小表
+}
+
+export interface MaxTokensRule {
+    model: string;                           // 支持 * 通配
+    max_output_tokens: number;
+}
+
+export interface MaxTokensConfig {
+    enabled: boolean;
+    inject_missing: boolean;                 // Anthropic Messages 缺少 max_tokens 时补上
+    default_max_tokens: number;
+    models: MaxTokensRule[];                 // 优先于内置的最大输出表
+}
+
+export interface ModelCatalogConfig {
+    cache_ttl_secs: number;            // 0 = 不缓存
+    include_upstream_models: boolean;  // 向 z.ai / OpenAI 兼容上游请求模型列表
+}
+
+export interface BodyLimitRule {
+    path: string;                      // 支持 * 通配, 例如 /v1/audio/*
+    max_mb: number;
+}
+
+export interface BodyLimitConfig {
+    enabled: boolean;
+    default_mb: number;
+    rules: BodyLimitRule[];            // 按顺序匹配, 未命中时使用 default_mb
+}
+
+export interface CompressionConfig {
+    enabled: boolean;
+    min_bytes: number;                 // 小于该大小的响应不压缩
+    gzip: boolean;
+    brotli: boolean;
+}
+
+export interface BodyLoggingConfig {
+    enabled: boolean;
+    output_dir?: string;
+    max_file_size_mb: number;
+    max_files: number;
+    max_body_kb: number;
+    redact_secrets: boolean;
+    redact_pii: boolean;
+    redact_headers: string[];
+    redact_patterns: string[];
+}
+
+export interface ClientRateLimitConfig {
+    enabled: boolean;
+    requests_per_minute: number; // 0 = 不限制
+    tokens_per_minute: number;   // 0 = 不限制
+    burst: number;                // 0 = 等于 requests_per_minute
+    per_api_key: boolean;
+    per_ip: boolean;
 }
 
 export interface DebugLoggingConfig {
@@ -48,10 +769,19 @@ export interface ZaiModelDefaults {
     haiku: string;
 }
 
+export type KeyBalanceStrategy = 'weighted_round_robin' | 'least_in_flight';
+
+export interface WeightedApiKey {
+    key: string;
+    weight: number;
+}
+
 export interface ZaiConfig {
     enabled: boolean;
     base_url: string;
     api_key: string;
+    api_keys?: WeightedApiKey[];
+    key_strategy?: KeyBalanceStrategy;
     dispatch_mode: ZaiDispatchMode;
     model_mapping?: Record<string, string>;
     models: ZaiModelDefaults;
@@ -85,6 +815,65 @@ export interface CircuitBreakerConfig {
     backoff_steps: number[];
 }
 
+// 上游密钥的加密主密钥保存位置, plaintext 表示明文写入配置文件
+// viewer: 只读; operator: 只读 + 启停服务、切换账号等运维操作; admin: 全部权限
+export type AdminRole = 'viewer' | 'operator' | 'admin';
+
+export interface AdminToken {
+    name: string;
+    token: string;
+    role: AdminRole;
+    enabled: boolean;
+}
+
+export type SecretStorageMode = 'auto' | 'keychain' | 'file' | 'plaintext';
+
+export interface SecretInfo {
+    name: string; // 配置字段路径, 例如 proxy.zai.api_key
+    hint: string;
+    updated_at: number;
+}
+
+export interface SecretStorageStatus {
+    mode: SecretStorageMode;
+    backend: 'keychain' | 'file' | null;
+    keychain_available: boolean;
+    secrets: SecretInfo[];
+}
+
+export interface AuditChange {
+    path: string;
+    before: unknown;
+    after: unknown;
+}
+
+export interface AuditEntry {
+    id: number;
+    timestamp: number;
+    actor: string; // admin@<ip> / desktop / cli / supervisor
+    source: 'admin_api' | 'desktop' | 'cli' | 'system';
+    action: string; // 例如 config.update / virtual_key.create / service.restart
+    target: string;
+    status: number | null;
+    changes: AuditChange[];
+    detail: unknown | null;
+}
+
+export interface AuditQuery {
+    since?: number;
+    until?: number;
+    actor?: string;
+    action?: string; // 前缀匹配
+    limit?: number;
+    offset?: number;
+}
+
+export interface ConfigIssue {
+    path: string; // 例如 proxy.listeners[1].port
+    message: string;
+    severity: 'error' | 'warning';
+}
+
 export interface AppConfig {
     language: string;
     theme: string;
@@ -103,9 +892,92 @@ export interface AppConfig {
     quota_protection: QuotaProtectionConfig; // [NEW] 配额保护配置
     pinned_quota_models: PinnedQuotaModelsConfig; // [NEW] 配额关注列表
     circuit_breaker: CircuitBreakerConfig; // [NEW] 熔断器配置
+    desktop_notifications?: DesktopNotificationConfig;
+    external_services?: ExternalServiceConfig[];
+    profiles?: StartupProfile[];
+    active_profile?: string | null;
     proxy: ProxyConfig;
 }
 
+// 启动方案: 叠加在 proxy 上的 JSON Merge Patch 与需要运行的外部服务
+export interface StartupProfile {
+    name: string;
+    description?: string;
+    proxy?: Record<string, unknown>;
+    services?: string[] | null;  // null: 不改变外部服务
+}
+
+export interface ProfileSummary {
+    name: string;
+    description: string;
+    active: boolean;
+    sections: string[];          // 方案覆盖的配置字段
+    services: string[] | null;
+}
+
+// import_config 的结果
+export interface ConfigImportReport {
+    from_version: number;        // 1: 直接复制的 gui_config.json
+    app_version: string | null;
+    exported_at: number | null;
+    migrated: boolean;
+    missing_secrets: string[];   // 需要重新填写的密钥字段路径
+}
+
+// profile://switched 事件
+export interface ProfileSwitched {
+    profile: string | null;
+    restarted: boolean;
+    service_error: string | null;
+}
+
+// 启动器托管的外部服务 (Ollama / ComfyUI / vLLM 等)
+export interface ExternalServiceConfig {
+    id: string;                 // 唯一, 供 depends_on 引用
+    name?: string;
+    command: string;
+    args?: string[];
+    cwd?: string | null;
+    env?: Record<string, string>;
+    port?: number | null;       // args / env / health_url 中的 {port} 替换为实际端口
+    health_url?: string | null; // 返回 2xx 视为就绪
+    depends_on?: string[];
+    autostart?: boolean;
+    startup_timeout_secs?: number; // 默认 60
+}
+
+export type ServiceState = 'stopped' | 'starting' | 'running' | 'failed';
+
+// 事件 `service://status`
+export interface ServiceStatus {
+    id: string;
+    name: string;
+    state: ServiceState;
+    pid: number | null;
+    port: number | null;
+    started_at: number | null;
+    exit_code: number | null;
+    error: string | null;
+}
+
+// 事件 `service://log`
+export interface ServiceLogLine {
+    id: string;
+    stream: 'stdout' | 'stderr';
+    line: string;
+    timestamp: number;
+}
+
+// 反代关键事件的桌面通知, 可按类型关闭
+export interface DesktopNotificationConfig {
+    enabled: boolean;
+    upstream_outage: boolean;   // 需开启上游健康检查
+    quota_exhausted: boolean;   // 虚拟 Key 额度或月度预算用尽
+    cert_expiry: boolean;       // 监听证书 14 天内到期
+    service_restarted: boolean;
+    upstream_keys: boolean;     // 上游 Key 失效、余额不足或即将过期
+}
+
 // ============================================================================
 // Cloudflared (CF隧道) 类型定义
 // ============================================================================
@@ -127,3 +999,167 @@ export interface CloudflaredStatus {
     url?: string;
     error?: string;
 }
+
+// GGUF 模型下载 (Hugging Face), 进度事件 `model-download://progress`
+export interface ModelDownloadRequest {
+    repo: string;               // 例如 Qwen/Qwen2.5-7B-Instruct-GGUF
+    file: string;               // 仓库内的 .gguf 文件路径
+    revision?: string;          // 默认 main
+    sha256?: string;            // 为空时使用 Hugging Face 记录的哈希
+    token?: string;             // gated 仓库; 为空时读取 HF_TOKEN
+    activate?: boolean;         // 完成后设为 llama-server 的模型
+}
+
+export type ModelDownloadState = 'downloading' | 'verifying' | 'completed' | 'failed' | 'cancelled';
+
+export interface ModelDownload {
+    id: string;
+    repo: string;
+    file: string;
+    revision: string;
+    path: string;
+    total_bytes: number | null;
+    downloaded_bytes: number;
+    bytes_per_sec: number;
+    state: ModelDownloadState;
+    error: string | null;
+}
+
+export interface LocalModel {
+    path: string;
+    name: string;
+    size: number;
+}
+
+// 本地资源监控, 事件 `system://resources` (字节 / 百分比)
+export interface GpuInfo {
+    index: number;
+    name: string;
+    vendor: 'nvidia' | 'amd' | string;
+    memory_total: number;
+    memory_used: number;
+    utilization: number | null;
+}
+
+export interface ResourceSnapshot {
+    timestamp: number;
+    cpu_percent: number;
+    cpu_cores: number;
+    memory_total: number;
+    memory_used: number;
+    memory_available: number;
+    gpus: GpuInfo[];
+    llama_server: { pid: number; cpu_percent: number; memory: number } | null;
+}
+
+export interface ModelFit {
+    model_bytes: number;
+    required_bytes: number;
+    gpu_free: number | null;
+    memory_available: number;
+    fits_gpu: boolean;
+    fits_memory: boolean;
+    warning: string | null;
+}
+
+// ============================================================================
+// 虚拟 API Key 类型定义
+// ============================================================================
+
+export type QuotaPeriod = 'daily' | 'monthly' | 'total';
+
+// JWT 鉴权 (HS256 共享密钥或 RS256 + JWKS), 额度按 subject 统计
+export interface JwtAuthConfig {
+    enabled: boolean;
+    issuer: string;
+    audience: string;
+    hmac_secret: string;
+    jwks_url: string;
+    jwks_cache_secs: number;
+    leeway_secs: number;
+    subject_claim: string;       // 默认 sub
+    models_claim: string;        // 默认 allowed_models
+    account_claim: string;       // 绑定上游账号的 claim, 留空不绑定
+    request_quota?: number | null;
+    token_quota?: number | null;
+    quota_period: QuotaPeriod;
+    monthly_spend_cap?: number | null;
+}
+
+export interface VirtualKey {
+    id: string;
+    name: string;
+    key_hash: string;
+    key_hint: string;
+    allowed_models: string[]; // 支持通配符, 空数组表示不限
+    request_quota?: number;
+    token_quota?: number;
+    quota_period: QuotaPeriod;
+    monthly_spend_cap?: number; // 月度预算 (美元)
+    expires_at?: number; // Unix 秒
+    upstream_account?: string; // 绑定的上游账号邮箱
+    created_at: number;
+    revoked: boolean;
+}
+
+export interface VirtualKeySummary extends VirtualKey {
+    used_requests: number;
+    used_tokens: number;
+    spent_this_month: number; // 本月花费 (美元)
+}
+
+export interface CreateVirtualKeyRequest {
+    name: string;
+    allowed_models?: string[];
+    request_quota?: number;
+    token_quota?: number;
+    quota_period?: QuotaPeriod;
+    monthly_spend_cap?: number;
+    expires_at?: number;
+    upstream_account?: string;
+}
+
+export interface CreatedVirtualKey extends VirtualKey {
+    key: string; // 明文 Key, 仅在创建时返回
+}
+
+export interface SetupProviderOption {
+    id: string;
+    name: string;
+    base_url: string;
+}
+
+export interface SetupKeyTestRequest {
+    provider: string; // 预设 id、openai、deepseek、zai 或 custom
+    api_key: string;
+    base_url?: string; // custom 必填
+}
+
+export interface SetupKeyTestResult {
+    provider: string;
+    ok: boolean;
+    status?: number | null;
+    latency_ms?: number | null;
+    models: string[];
+    error?: string | null;
+}
+
+export interface SetupLocalBackend {
+    kind: 'ollama' | 'lm_studio';
+    base_url: string;
+    models: string[];
+}
+
+export interface SetupChoices {
+    keys: SetupKeyTestRequest[];
+    local_backends: SetupLocalBackend[];
+    port?: number;
+    allow_lan_access?: boolean;
+    auto_start?: boolean;
+}
+
+export interface SetupStep {
+    step: 'validate' | 'save' | 'start_proxy';
+    status: 'running' | 'ok' | 'failed' | 'skipped';
+    message?: string | null;
+}
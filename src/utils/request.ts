@@ -44,7 +44,19 @@ const COMMAND_MAPPING: Record<string, { url: string; method: 'GET' | 'POST' | 'D
   'fetch_zai_models': { url: '/api/zai/models/fetch', method: 'POST' },
   'load_config': { url: '/api/config', method: 'GET' },
   'save_config': { url: '/api/config', method: 'POST' },
+  'validate_config': { url: '/api/config/validate', method: 'POST' },
+  'get_audit_log': { url: '/api/audit', method: 'GET' },
   'get_proxy_stats': { url: '/api/proxy/stats', method: 'GET' },
+  'get_latency_stats': { url: '/api/proxy/latency', method: 'GET' },
+  'get_shadow_results': { url: '/api/proxy/shadow', method: 'GET' },
+  'compare_models': { url: '/api/proxy/compare', method: 'POST' },
+  'clear_shadow_results': { url: '/api/proxy/shadow', method: 'DELETE' },
+  'get_upstream_health': { url: '/api/proxy/upstream-health', method: 'GET' },
+  'get_key_check_results': { url: '/api/proxy/key-check', method: 'GET' },
+  'check_upstream_keys': { url: '/api/proxy/key-check', method: 'POST' },
+  'get_disabled_upstream_keys': { url: '/api/proxy/disabled-keys', method: 'GET' },
+  'enable_upstream_key': { url: '/api/proxy/disabled-keys/enable', method: 'POST' },
+  'get_provider_presets': { url: '/api/proxy/provider-presets', method: 'GET' },
   'set_proxy_monitor_enabled': { url: '/api/proxy/monitor/toggle', method: 'POST' },
 
   // Logs & Monitoring